@@ -8,12 +8,26 @@ use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 use tauri::Manager;
+use tauri::tray::TrayIcon;
 use tauri_plugin_shell::ShellExt;
 use tauri_plugin_shell::process::CommandEvent;
 use tokio::sync::Mutex;
 
-use crate::AppHandle;
-use crate::config::{self, DesktopConfig};
+use crate::config::{self, BackendConfig, DesktopConfig};
+use crate::logs;
+use crate::{AppHandle, AppRuntime};
+
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(5);
+const WATCHDOG_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Failed probes in a row before a running backend counts as hung.
+const WATCHDOG_UNHEALTHY_PROBES: u32 = 3;
+const RESTART_BACKOFF_BASE: Duration = Duration::from_secs(1);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(60);
+const TRAY_TOOLTIP: &str = "OpenCode Studio";
+/// Passed as `--shutdown-grace-secs` so the drain window is known here.
+const BACKEND_SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+/// Extra time after the drain for the backend to persist state and exit.
+const BACKEND_EXIT_MARGIN: Duration = Duration::from_secs(5);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackendStatus {
@@ -21,6 +35,11 @@ pub struct BackendStatus {
     pub url: Option<String>,
     pub last_error: Option<String>,
     pub last_error_info: Option<BackendErrorInfo>,
+    /// Crashes or hangs the watchdog has recovered from (or is recovering
+    /// from) since the app started.
+    pub crash_count: u32,
+    /// The backend should be running but is down or unresponsive.
+    pub degraded: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -110,9 +129,25 @@ struct BackendInner {
     url: Option<String>,
     last_error: Option<String>,
     last_error_info: Option<BackendErrorInfo>,
+    /// Set by start requests and cleared by an explicit stop; the watchdog
+    /// only restarts backends that are wanted.
+    wanted: bool,
+    crash_count: u32,
+    degraded: bool,
 }
 
 impl BackendInner {
+    fn status(&self) -> BackendStatus {
+        BackendStatus {
+            running: self.child.is_some(),
+            url: self.url.clone(),
+            last_error: self.last_error.clone(),
+            last_error_info: self.last_error_info.clone(),
+            crash_count: self.crash_count,
+            degraded: self.degraded,
+        }
+    }
+
     fn clear_last_error(&mut self) {
         self.last_error = None;
         self.last_error_info = None;
@@ -132,31 +167,22 @@ impl BackendManager {
 
     pub async fn status(&self) -> BackendStatus {
         let guard = self.inner.lock().await;
-        BackendStatus {
-            running: guard.child.is_some(),
-            url: guard.url.clone(),
-            last_error: guard.last_error.clone(),
-            last_error_info: guard.last_error_info.clone(),
-        }
+        guard.status()
     }
 
     pub async fn ensure_started(&self, app: &AppHandle) -> Result<BackendStatus, String> {
         let _start_guard = self.start_lock.lock().await;
 
         {
-            let guard = self.inner.lock().await;
+            let mut guard = self.inner.lock().await;
+            guard.wanted = true;
             if guard.child.is_some() {
-                return Ok(BackendStatus {
-                    running: true,
-                    url: guard.url.clone(),
-                    last_error: guard.last_error.clone(),
-                    last_error_info: guard.last_error_info.clone(),
-                });
+                return Ok(guard.status());
             }
         }
 
         let cfg = config::load_or_create(app).unwrap_or_default();
-        let runtime_config_path = match config::runtime_config_path(app) {
+        let runtime_config_path = match config::active_runtime_config_path(app, &cfg) {
             Some(path) => path,
             None => {
                 let info = BackendErrorInfo::new(
@@ -177,7 +203,8 @@ impl BackendManager {
                 return Err(message);
             }
         };
-        let (child, url) = match spawn_backend_service(app, &cfg, &runtime_config_path).await {
+        let (child, url) = match spawn_backend_service(app, &cfg, &runtime_config_path, None).await
+        {
             Ok(started) => started,
             Err(err) => {
                 let info = backend_start_error_info(err);
@@ -207,7 +234,7 @@ impl BackendManager {
         if let Err(err) = wait_for_health(&url).await {
             let info = backend_start_error_info(err);
             let message = info.legacy_message();
-            let _ = self.stop(app).await;
+            self.stop_child(app).await;
             let mut guard = self.inner.lock().await;
             guard.set_last_error_info(info);
             append_backend_log_line(app, &format!("[desktop] backend start failed: {message}"));
@@ -218,6 +245,16 @@ impl BackendManager {
     }
 
     pub async fn stop(&self, app: &AppHandle) -> Result<(), String> {
+        {
+            let mut guard = self.inner.lock().await;
+            guard.wanted = false;
+        }
+        self.stop_child(app).await;
+        set_degraded(app, self, false).await;
+        Ok(())
+    }
+
+    async fn stop_child(&self, app: &AppHandle) {
         let (mut child, pid) = {
             let mut guard = self.inner.lock().await;
             guard.url = None;
@@ -226,34 +263,273 @@ impl BackendManager {
 
         if let Some(c) = child.take() {
             let pid = c.pid();
-            kill_process_tree(pid);
+            terminate_process_tree(pid).await;
             // Try to kill directly as a fallback in case taskkill misses.
             let _ = c.kill();
             #[cfg(target_os = "windows")]
             force_cleanup_port_windows(app);
             wait_for_port_release(app).await;
-            return Ok(());
+            return;
         }
 
         if let Some(pid) = pid {
-            kill_process_tree(pid);
+            terminate_process_tree(pid).await;
             #[cfg(target_os = "windows")]
             force_cleanup_port_windows(app);
             wait_for_port_release(app).await;
         }
 
         let _ = app; // reserved for future graceful shutdown.
-        Ok(())
     }
 
     pub async fn restart(&self, app: &AppHandle) -> Result<BackendStatus, String> {
-        let _ = self.stop(app).await;
+        #[cfg(not(target_os = "windows"))]
+        if let Some(status) = self.hand_over(app).await {
+            return Ok(status);
+        }
+        self.cold_restart(app).await
+    }
+
+    async fn cold_restart(&self, app: &AppHandle) -> Result<BackendStatus, String> {
+        self.stop_child(app).await;
         self.ensure_started(app).await
     }
+
+    /// Start a replacement on the running backend's port (both bind with
+    /// SO_REUSEPORT) and only SIGTERM the old one once the new one is listening
+    /// and healthy, so a restart never leaves the port unserved.
+    ///
+    /// Returns `None` when there is no running backend to hand over from, the
+    /// configured address changed, or the replacement failed to come up; the
+    /// caller then falls back to stop + start.
+    #[cfg(not(target_os = "windows"))]
+    async fn hand_over(&self, app: &AppHandle) -> Option<BackendStatus> {
+        let _start_guard = self.start_lock.lock().await;
+
+        let cfg = config::load_or_create(app).unwrap_or_default();
+        let runtime_config_path = config::active_runtime_config_path(app, &cfg)?;
+        let backend = cfg.active_backend();
+        let port = if backend.port == 0 {
+            3210
+        } else {
+            backend.port
+        };
+        let expected_url = format!("http://{}:{}", normalize_connect_host(&backend.host), port);
+
+        let old_pid = {
+            let guard = self.inner.lock().await;
+            if guard.url.as_deref() != Some(expected_url.as_str()) {
+                return None;
+            }
+            guard.pid?
+        };
+
+        let (child, url) =
+            match spawn_backend_service(app, &cfg, &runtime_config_path, Some(port)).await {
+                Ok(started) => started,
+                Err(err) => {
+                    append_backend_log_line(
+                        app,
+                        &format!("[desktop] backend handover failed to start: {err}"),
+                    );
+                    return None;
+                }
+            };
+        let pid = child.pid();
+
+        // While both processes listen either may answer /health, so first wait
+        // until the replacement owns a listening socket of its own.
+        let mut ready = match wait_for_listener(pid, port).await {
+            Ok(()) => wait_for_health(&url).await,
+            Err(err) => Err(err),
+        };
+        if ready.is_ok() && wait_for_process_exit(pid, 1, 0) {
+            ready = Err("replacement backend exited".to_string());
+        }
+        if let Err(err) = ready {
+            append_backend_log_line(app, &format!("[desktop] backend handover failed: {err}"));
+            terminate_process_tree(pid).await;
+            let _ = child.kill();
+            return None;
+        }
+
+        let old_child = {
+            let mut guard = self.inner.lock().await;
+            let old_child = guard.child.take();
+            guard.pid = Some(pid);
+            guard.child = Some(child);
+            guard.url = Some(url);
+            guard.clear_last_error();
+            old_child
+        };
+        append_backend_log_line(
+            app,
+            &format!("[desktop] backend handed over from pid {old_pid} to pid {pid}"),
+        );
+
+        // The old backend drains its in-flight requests in the background.
+        tauri::async_runtime::spawn(async move {
+            terminate_process_tree(old_pid).await;
+            if let Some(c) = old_child {
+                let _ = c.kill();
+            }
+        });
+
+        Some(self.status().await)
+    }
+}
+
+async fn probe_health(client: &reqwest::Client, base_url: &str) -> bool {
+    let health_url = format!("{}/health", base_url.trim_end_matches('/'));
+    matches!(client.get(&health_url).send().await, Ok(resp) if resp.status().is_success())
+}
+
+fn restart_backoff(attempt: u32) -> Duration {
+    RESTART_BACKOFF_BASE
+        .saturating_mul(1u32 << attempt.min(16))
+        .min(RESTART_BACKOFF_MAX)
+}
+
+/// Record the degraded flag and reflect it in the tray tooltip.
+async fn set_degraded(app: &AppHandle, manager: &BackendManager, degraded: bool) {
+    let crash_count = {
+        let mut guard = manager.inner.lock().await;
+        if guard.degraded == degraded {
+            return;
+        }
+        guard.degraded = degraded;
+        guard.crash_count
+    };
+    let tooltip = if degraded {
+        format!("{TRAY_TOOLTIP} - backend down, restarting (crashes: {crash_count})")
+    } else {
+        TRAY_TOOLTIP.to_string()
+    };
+    if let Some(tray) = app.try_state::<TrayIcon<AppRuntime>>() {
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+/// Supervise the backend: poll `/health` while it is wanted and restart it
+/// with exponential backoff when it exits or stops answering.
+pub fn spawn_watchdog(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let Ok(client) = reqwest::Client::builder()
+            .timeout(WATCHDOG_PROBE_TIMEOUT)
+            .build()
+        else {
+            return;
+        };
+        let manager = app.state::<BackendManager>().inner().clone();
+        let mut failed_probes = 0u32;
+        let mut restart_attempt = 0u32;
+
+        loop {
+            tokio::time::sleep(WATCHDOG_INTERVAL).await;
+
+            // Leave starts and stops in progress alone.
+            let Ok(start_guard) = manager.start_lock.try_lock() else {
+                continue;
+            };
+            drop(start_guard);
+
+            let (wanted, running, url) = {
+                let guard = manager.inner.lock().await;
+                (guard.wanted, guard.child.is_some(), guard.url.clone())
+            };
+            if !wanted {
+                failed_probes = 0;
+                restart_attempt = 0;
+                continue;
+            }
+
+            let healthy = match url.as_deref() {
+                Some(url) if running => probe_health(&client, url).await,
+                _ => false,
+            };
+            if healthy {
+                failed_probes = 0;
+                restart_attempt = 0;
+                set_degraded(&app, &manager, false).await;
+                continue;
+            }
+            failed_probes += 1;
+            if running && failed_probes < WATCHDOG_UNHEALTHY_PROBES {
+                continue;
+            }
+
+            // Failed restarts are part of the same incident.
+            if restart_attempt == 0 {
+                let mut guard = manager.inner.lock().await;
+                guard.crash_count = guard.crash_count.saturating_add(1);
+            }
+            set_degraded(&app, &manager, true).await;
+            let delay = restart_backoff(restart_attempt);
+            restart_attempt = restart_attempt.saturating_add(1);
+            append_backend_log_line(
+                &app,
+                &format!(
+                    "[desktop] backend {}; restarting in {}s",
+                    if running { "unresponsive" } else { "exited" },
+                    delay.as_secs()
+                ),
+            );
+            tokio::time::sleep(delay).await;
+
+            if !manager.inner.lock().await.wanted {
+                continue;
+            }
+            failed_probes = 0;
+            if manager.cold_restart(&app).await.is_ok() {
+                append_backend_log_line(&app, "[desktop] backend restarted by watchdog");
+            }
+        }
+    });
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthSessionBody {
+    token: Option<String>,
+}
+
+/// Log in with the configured UI password so desktop-side API calls pass UI
+/// auth. `None` when no password is set or login fails.
+pub async fn api_auth_token(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    base: &str,
+) -> Option<String> {
+    let cfg = config::load_or_create(app).ok()?;
+    let password = cfg
+        .active_backend()
+        .ui_password
+        .as_deref()
+        .map(str::trim)
+        .filter(|pw| !pw.is_empty())?
+        .to_string();
+    let resp = client
+        .post(format!("{base}/auth/session"))
+        .json(&serde_json::json!({ "password": password }))
+        .send()
+        .await
+        .ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    resp.json::<AuthSessionBody>().await.ok()?.token
+}
+
+/// End a session opened by [`api_auth_token`] instead of leaving it to expire.
+pub async fn api_auth_logout(client: &reqwest::Client, base: &str, token: &str) {
+    let _ = client
+        .delete(format!("{base}/auth/session"))
+        .bearer_auth(token)
+        .send()
+        .await;
 }
 
 pub fn open_logs_dir(app: &AppHandle) -> Result<(), String> {
-    let dir = logs_dir(app).ok_or_else(|| "unable to resolve log dir".to_string())?;
+    let dir = logs::logs_dir(app).ok_or_else(|| "unable to resolve log dir".to_string())?;
     fs::create_dir_all(&dir).map_err(|e| format!("mkdir {dir:?}: {e}"))?;
     use tauri_plugin_opener::OpenerExt;
     let _ = app
@@ -262,19 +538,13 @@ pub fn open_logs_dir(app: &AppHandle) -> Result<(), String> {
     Ok(())
 }
 
-fn logs_dir(app: &AppHandle) -> Option<PathBuf> {
-    app.path().app_log_dir().ok()
-}
-
-fn backend_log_path(app: &AppHandle) -> Option<PathBuf> {
-    let dir = logs_dir(app)?;
-    Some(dir.join("backend.log"))
-}
-
+/// `reuse_port` is the port of a running backend to share during a handover;
+/// otherwise the configured port must be free.
 async fn spawn_backend_service(
     app: &AppHandle,
     cfg: &DesktopConfig,
     runtime_config_path: &Path,
+    reuse_port: Option<u16>,
 ) -> Result<(tauri_plugin_shell::process::CommandChild, String), String> {
     // If the bundled backend service is unavailable, startup fails and desktop commands can
     // still report status/manage retries.
@@ -285,30 +555,34 @@ async fn spawn_backend_service(
         }
     };
 
-    let ui_dir = match cfg.backend.ui_dir.as_deref().map(str::trim) {
+    let backend = cfg.active_backend();
+    let ui_dir = match backend.ui_dir.as_deref().map(str::trim) {
         Some(path) if !path.is_empty() => PathBuf::from(path),
         _ => resolve_ui_dir(app)?,
     };
-    let port = pick_port(cfg.backend.port)?;
-    let connect_host = normalize_connect_host(&cfg.backend.host);
+    let port = match reuse_port {
+        Some(port) => port,
+        None => pick_port(backend.port)?,
+    };
+    let connect_host = normalize_connect_host(&backend.host);
     let url = format!("http://{}:{}", connect_host, port);
 
-    let data_dir = app
-        .path()
-        .app_config_dir()
-        .map_err(|e| format!("resolve app config dir: {e}"))?;
+    let data_dir = config::active_data_dir(app, cfg)?;
+    fs::create_dir_all(&data_dir).map_err(|e| format!("mkdir {data_dir:?}: {e}"))?;
     let home_env = resolve_home_env();
 
     let mut cmd = backend_cmd
         .args([
             "--host",
-            cfg.backend.host.as_str(),
+            backend.host.as_str(),
             "--port",
             &port.to_string(),
             "--config",
             runtime_config_path.to_string_lossy().as_ref(),
             "--ui-dir",
             ui_dir.to_string_lossy().as_ref(),
+            "--shutdown-grace-secs",
+            &BACKEND_SHUTDOWN_GRACE.as_secs().to_string(),
         ])
         .env(
             "OPENCODE_STUDIO_DATA_DIR",
@@ -317,30 +591,35 @@ async fn spawn_backend_service(
 
     cmd = cmd.env(
         "RUST_LOG",
-        backend_rust_log_filter(cfg.backend.backend_log_level.as_deref()),
+        backend_rust_log_filter(backend.backend_log_level.as_deref()),
     );
 
     if let Some(home) = home_env.as_deref() {
         cmd = cmd.env("HOME", home);
     }
 
-    if let Some(pw) = cfg.backend.ui_password.as_deref() {
+    if let Some(pw) = backend.ui_password.as_deref() {
         if !pw.trim().is_empty() {
             cmd = cmd.args(["--ui-password", pw]);
         }
     }
 
-    if cfg.backend.skip_opencode_start {
+    // Passed via env so the key never shows up in the process list.
+    if let Some(key) = crate::keychain::secrets_key() {
+        cmd = cmd.env("OPENCODE_STUDIO_SECRETS_KEY", key);
+    }
+
+    if backend.skip_opencode_start {
         cmd = cmd.args(["--skip-opencode-start"]);
     }
 
-    if let Some(opencode_port) = cfg.backend.opencode_port {
+    if let Some(opencode_port) = backend.opencode_port {
         cmd = cmd
-            .args(["--opencode-host", cfg.backend.opencode_host.as_str()])
+            .args(["--opencode-host", backend.opencode_host.as_str()])
             .args(["--opencode-port", &opencode_port.to_string()]);
     }
 
-    if let Some(level) = normalize_log_level(cfg.backend.opencode_log_level.as_deref()) {
+    if let Some(level) = normalize_log_level(backend.opencode_log_level.as_deref()) {
         cmd = cmd
             .env("OPENCODE_STUDIO_OPENCODE_LOG_LEVEL", level)
             .env("OPENCODE_LOG_LEVEL", level)
@@ -350,23 +629,30 @@ async fn spawn_backend_service(
         cmd = cmd.env("OPENCODE_STUDIO_OPENCODE_LOGS", "false");
     }
 
-    for origin in merge_cors_origins(cfg) {
+    for origin in merge_cors_origins(backend) {
         cmd = cmd.args(["--cors-origin", &origin]);
     }
 
-    if cfg.backend.cors_allow_all {
+    if backend.cors_allow_all {
         cmd = cmd.args(["--cors-allow-all"]);
     }
 
+    // Lets a later restart bind the replacement before this one exits.
+    #[cfg(not(target_os = "windows"))]
+    {
+        cmd = cmd.args(["--reuse-port"]);
+    }
+
     let (mut rx, child) = cmd.spawn().map_err(|e| format!("spawn backend: {e}"))?;
     let child_pid = child.pid();
 
     // Stream backend output to a log file (best-effort).
-    let log_path = backend_log_path(app);
+    let log_path = logs::backend_log_path(app);
     let app_handle = app.clone();
     tauri::async_runtime::spawn(async move {
         let mut file = log_path.and_then(|p| {
             let _ = fs::create_dir_all(p.parent().unwrap_or_else(|| std::path::Path::new(".")));
+            logs::rotate_if_needed(&p);
             fs::OpenOptions::new()
                 .create(true)
                 .append(true)
@@ -422,6 +708,12 @@ async fn spawn_backend_service(
     Ok((child, url))
 }
 
+/// Run [`kill_process_tree`] off the async runtime; it blocks for up to the
+/// backend's shutdown grace.
+async fn terminate_process_tree(pid: u32) {
+    let _ = tauri::async_runtime::spawn_blocking(move || kill_process_tree(pid)).await;
+}
+
 fn kill_process_tree(pid: u32) {
     #[cfg(target_os = "windows")]
     {
@@ -439,7 +731,11 @@ fn kill_process_tree(pid: u32) {
         let _ = StdCommand::new("kill")
             .args(["-TERM", pid_str.as_str()])
             .status();
-        if !wait_for_process_exit(pid, 20, 100) {
+        // The backend drains in-flight requests on SIGTERM and then persists its
+        // replay buffer, so only SIGKILL once the whole grace window has passed.
+        let wait_ms = 100;
+        let rounds = (BACKEND_SHUTDOWN_GRACE + BACKEND_EXIT_MARGIN).as_millis() / wait_ms;
+        if !wait_for_process_exit(pid, rounds as usize, wait_ms as u64) {
             let _ = StdCommand::new("pkill")
                 .args(["-KILL", "-P", pid_str.as_str()])
                 .status();
@@ -469,8 +765,47 @@ fn wait_for_process_exit(pid: u32, rounds: usize, wait_ms: u64) -> bool {
     false
 }
 
+/// Wait until `pid` has a listening TCP socket on `port`.
+///
+/// Best effort: if `lsof` is unavailable this returns immediately and the
+/// caller relies on `/health` alone.
+#[cfg(not(target_os = "windows"))]
+async fn wait_for_listener(pid: u32, port: u16) -> Result<(), String> {
+    let started = std::time::Instant::now();
+    let deadline = Duration::from_secs(15);
+    let pid_str = pid.to_string();
+    let port_arg = format!("-iTCP:{port}");
+
+    loop {
+        if started.elapsed() > deadline {
+            return Err(format!("backend pid {pid} did not listen on port {port}"));
+        }
+
+        let args = [
+            "-a".to_string(),
+            "-p".to_string(),
+            pid_str.clone(),
+            port_arg.clone(),
+            "-sTCP:LISTEN".to_string(),
+        ];
+        let status = tauri::async_runtime::spawn_blocking(move || {
+            StdCommand::new("lsof")
+                .args(args)
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status()
+        })
+        .await;
+        match status {
+            Ok(Ok(status)) if status.success() => return Ok(()),
+            Ok(Ok(_)) => tokio::time::sleep(Duration::from_millis(250)).await,
+            _ => return Ok(()),
+        }
+    }
+}
+
 fn append_backend_log_line(app: &AppHandle, line: &str) {
-    let Some(path) = backend_log_path(app) else {
+    let Some(path) = logs::backend_log_path(app) else {
         return;
     };
     let _ = fs::create_dir_all(path.parent().unwrap_or_else(|| Path::new(".")));
@@ -540,10 +875,11 @@ fn can_bind_port(port: u16) -> bool {
 
 async fn wait_for_port_release(app: &AppHandle) {
     let cfg = config::load_or_create(app).unwrap_or_default();
-    let port = if cfg.backend.port == 0 {
+    let backend = cfg.active_backend();
+    let port = if backend.port == 0 {
         3210
     } else {
-        cfg.backend.port
+        backend.port
     };
 
     for _ in 0..30 {
@@ -662,10 +998,11 @@ fn restart_windows_service(service: &str) -> bool {
 #[cfg(target_os = "windows")]
 fn force_cleanup_port_windows(app: &AppHandle) {
     let cfg = config::load_or_create(app).unwrap_or_default();
-    let port = if cfg.backend.port == 0 {
+    let backend = cfg.active_backend();
+    let port = if backend.port == 0 {
         3210
     } else {
-        cfg.backend.port
+        backend.port
     };
     for pid in listening_pids_on_port_windows(port) {
         if pid == std::process::id() {
@@ -938,7 +1275,7 @@ fn resolve_home_env() -> Option<String> {
     }
 }
 
-fn merge_cors_origins(cfg: &DesktopConfig) -> Vec<String> {
+fn merge_cors_origins(backend: &BackendConfig) -> Vec<String> {
     let mut origins = Vec::<String>::new();
     let mut push_unique = |origin: &str| {
         if !origins.iter().any(|v| v == origin) {
@@ -946,7 +1283,7 @@ fn merge_cors_origins(cfg: &DesktopConfig) -> Vec<String> {
         }
     };
 
-    for raw in &cfg.backend.cors_origins {
+    for raw in &backend.cors_origins {
         let trimmed = raw.trim();
         if trimmed.is_empty() {
             continue;
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -7,6 +8,10 @@ use tauri::Manager;
 use crate::AppHandle;
 
 const RUNTIME_CONFIG_FILE_NAME: &str = "opencode-studio.toml";
+const PROFILES_DIR_NAME: &str = "profiles";
+/// Profile name for the top-level `[backend]` table.
+pub const DEFAULT_PROFILE: &str = "default";
+const MAX_PROFILE_NAME_LEN: usize = 32;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -14,6 +19,95 @@ pub struct DesktopConfig {
     #[serde(default = "default_autostart_on_boot")]
     pub autostart_on_boot: bool,
     pub backend: BackendConfig,
+    /// Server `[[routes]]` rules, kept as-is so saving here does not drop them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<toml::Value>,
+    /// Entry of `profiles` the backend runs with; unset means `backend`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    /// Named alternatives to `backend` (e.g. "work", "personal"), switchable
+    /// from the tray.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+    pub shortcuts: ShortcutConfig,
+}
+
+/// Global hotkeys in the global-shortcut plugin's syntax (e.g.
+/// `CmdOrCtrl+Shift+O`). An empty string disables that shortcut.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShortcutConfig {
+    /// Show or hide the main window.
+    pub toggle_window: String,
+    /// Open the quick-prompt window, which sends to the most recent session.
+    pub quick_prompt: String,
+}
+
+impl Default for ShortcutConfig {
+    fn default() -> Self {
+        Self {
+            toggle_window: "CmdOrCtrl+Shift+O".to_string(),
+            quick_prompt: "CmdOrCtrl+Shift+Space".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileConfig {
+    /// Backend data directory; defaults to `profiles/<name>` under the app
+    /// config dir so profiles never share sessions or settings.
+    pub data_dir: Option<String>,
+    pub backend: BackendConfig,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSummary {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub data_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileList {
+    pub active: String,
+    pub profiles: Vec<ProfileSummary>,
+}
+
+impl DesktopConfig {
+    pub fn active_profile_name(&self) -> &str {
+        self.active_profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    /// Backend settings of the active profile.
+    pub fn active_backend(&self) -> &BackendConfig {
+        self.active_profile
+            .as_deref()
+            .and_then(|name| self.profiles.get(name))
+            .map_or(&self.backend, |profile| &profile.backend)
+    }
+
+    pub fn profile_list(&self) -> ProfileList {
+        let mut profiles = vec![ProfileSummary {
+            name: DEFAULT_PROFILE.to_string(),
+            host: self.backend.host.clone(),
+            port: self.backend.port,
+            data_dir: None,
+        }];
+        profiles.extend(self.profiles.iter().map(|(name, profile)| ProfileSummary {
+            name: name.clone(),
+            host: profile.backend.host.clone(),
+            port: profile.backend.port,
+            data_dir: profile.data_dir.clone(),
+        }));
+        ProfileList {
+            active: self.active_profile_name().to_string(),
+            profiles,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +118,8 @@ pub struct BackendConfig {
     pub ui_dir: Option<String>,
     pub cors_origins: Vec<String>,
     pub cors_allow_all: bool,
+    /// Extra root CAs (PEM files or directories); also used by the updater.
+    pub ca_certs: Vec<String>,
     pub backend_log_level: Option<String>,
     pub ui_password: Option<String>,
     pub ui_cookie_samesite: Option<String>,
@@ -40,6 +136,10 @@ impl Default for DesktopConfig {
         Self {
             autostart_on_boot: default_autostart_on_boot(),
             backend: BackendConfig::default(),
+            routes: Vec::new(),
+            active_profile: None,
+            profiles: BTreeMap::new(),
+            shortcuts: ShortcutConfig::default(),
         }
     }
 }
@@ -56,6 +156,7 @@ impl Default for BackendConfig {
             ui_dir: None,
             cors_origins: Vec::new(),
             cors_allow_all: false,
+            ca_certs: Vec::new(),
             backend_log_level: None,
             ui_password: Some(String::new()),
             ui_cookie_samesite: None,
@@ -72,6 +173,64 @@ pub fn runtime_config_path(app: &AppHandle) -> Option<PathBuf> {
     Some(dir.join(RUNTIME_CONFIG_FILE_NAME))
 }
 
+fn profile_dir(app: &AppHandle, name: &str) -> Option<PathBuf> {
+    let dir = app.path().app_config_dir().ok()?;
+    Some(dir.join(PROFILES_DIR_NAME).join(name))
+}
+
+/// Data directory for the active profile's backend.
+pub fn active_data_dir(app: &AppHandle, cfg: &DesktopConfig) -> Result<PathBuf, String> {
+    let Some(name) = cfg.active_profile.as_deref() else {
+        return app
+            .path()
+            .app_config_dir()
+            .map_err(|e| format!("resolve app config dir: {e}"));
+    };
+    if let Some(dir) = cfg.profiles.get(name).and_then(|p| p.data_dir.as_deref()) {
+        return Ok(PathBuf::from(dir));
+    }
+    profile_dir(app, name).ok_or_else(|| "unable to resolve app config dir".to_string())
+}
+
+/// Runtime config file to hand the backend. Named profiles get a generated
+/// copy whose `[backend]` is the profile's, so the server reads the same
+/// settings the desktop launched it with.
+pub fn active_runtime_config_path(app: &AppHandle, cfg: &DesktopConfig) -> Option<PathBuf> {
+    let Some(name) = cfg.active_profile.as_deref() else {
+        return runtime_config_path(app);
+    };
+    let profile = cfg.profiles.get(name)?;
+    let path = profile_dir(app, name)?.join(RUNTIME_CONFIG_FILE_NAME);
+    ensure_parent_dir(&path).ok()?;
+    let generated = DesktopConfig {
+        autostart_on_boot: cfg.autostart_on_boot,
+        backend: profile.backend.clone(),
+        routes: cfg.routes.clone(),
+        active_profile: None,
+        profiles: BTreeMap::new(),
+    };
+    let txt = toml::to_string_pretty(&generated).ok()?;
+    let header = format!(
+        "# Generated from the \"{name}\" profile; edit {RUNTIME_CONFIG_FILE_NAME} instead."
+    );
+    fs::write(&path, format!("{header}\n{txt}\n")).ok()?;
+    Some(path)
+}
+
+/// Make `name` the active profile and persist it.
+pub fn set_active_profile(app: &AppHandle, name: &str) -> Result<DesktopConfig, String> {
+    let mut cfg = load_or_create(app)?;
+    let name = name.trim();
+    if name == DEFAULT_PROFILE {
+        cfg.active_profile = None;
+    } else if cfg.profiles.contains_key(name) {
+        cfg.active_profile = Some(name.to_string());
+    } else {
+        return Err(format!("unknown profile: {name}"));
+    }
+    save(app, cfg)
+}
+
 pub fn load_or_create(app: &AppHandle) -> Result<DesktopConfig, String> {
     let path =
         runtime_config_path(app).ok_or_else(|| "unable to resolve app config dir".to_string())?;
@@ -128,27 +287,56 @@ fn ensure_parent_dir(path: &Path) -> Result<(), String> {
 }
 
 fn normalize_config(mut cfg: DesktopConfig) -> DesktopConfig {
-    cfg.backend.host = normalize_host(&cfg.backend.host);
-    cfg.backend.ui_dir = normalize_optional_path(cfg.backend.ui_dir.take());
-    if cfg.backend.ui_password.is_none() {
-        cfg.backend.ui_password = Some(String::new());
-    }
-    cfg.backend.opencode_host = normalize_host(&cfg.backend.opencode_host);
-    cfg.backend.cors_origins = normalize_cors_origins(cfg.backend.cors_origins);
-    cfg.backend.backend_log_level = normalize_log_level(cfg.backend.backend_log_level.take());
-    cfg.backend.ui_cookie_samesite =
-        normalize_ui_cookie_samesite(cfg.backend.ui_cookie_samesite.take());
-    cfg.backend.opencode_log_level = normalize_log_level(cfg.backend.opencode_log_level.take());
+    cfg.backend = normalize_backend(cfg.backend);
+    cfg.profiles = std::mem::take(&mut cfg.profiles)
+        .into_iter()
+        .filter_map(|(name, mut profile)| {
+            let name = normalize_profile_name(&name)?;
+            profile.data_dir = normalize_optional_path(profile.data_dir.take());
+            profile.backend = normalize_backend(profile.backend);
+            Some((name, profile))
+        })
+        .collect();
+    cfg.active_profile = cfg
+        .active_profile
+        .take()
+        .and_then(|name| normalize_profile_name(&name))
+        .filter(|name| cfg.profiles.contains_key(name));
+    cfg.shortcuts.toggle_window = cfg.shortcuts.toggle_window.trim().to_string();
+    cfg.shortcuts.quick_prompt = cfg.shortcuts.quick_prompt.trim().to_string();
     cfg
 }
 
+fn normalize_backend(mut backend: BackendConfig) -> BackendConfig {
+    backend.host = normalize_host(&backend.host);
+    backend.ui_dir = normalize_optional_path(backend.ui_dir.take());
+    if backend.ui_password.is_none() {
+        backend.ui_password = Some(String::new());
+    }
+    backend.opencode_host = normalize_host(&backend.opencode_host);
+    backend.cors_origins = normalize_string_list(backend.cors_origins);
+    backend.ca_certs = normalize_string_list(backend.ca_certs);
+    backend.backend_log_level = normalize_log_level(backend.backend_log_level.take());
+    backend.ui_cookie_samesite = normalize_ui_cookie_samesite(backend.ui_cookie_samesite.take());
+    backend.opencode_log_level = normalize_log_level(backend.opencode_log_level.take());
+    backend
+}
+
+/// Profile names double as directory names: letters, digits, `-` and `_`.
+fn normalize_profile_name(raw: &str) -> Option<String> {
+    let name = raw.trim();
+    let valid = !name.is_empty()
+        && name.len() <= MAX_PROFILE_NAME_LEN
+        && name != DEFAULT_PROFILE
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| name.to_string())
+}
+
 fn normalize_optional_path(raw: Option<String>) -> Option<String> {
     let value = raw?.trim().to_string();
-    if value.is_empty() {
-        None
-    } else {
-        Some(value)
-    }
+    if value.is_empty() { None } else { Some(value) }
 }
 
 fn normalize_host(raw: &str) -> String {
@@ -160,7 +348,7 @@ fn normalize_host(raw: &str) -> String {
     }
 }
 
-fn normalize_cors_origins(values: Vec<String>) -> Vec<String> {
+fn normalize_string_list(values: Vec<String>) -> Vec<String> {
     let mut out = Vec::<String>::new();
     for raw in values {
         let trimmed = raw.trim();
@@ -0,0 +1,45 @@
+use keyring::Entry;
+
+const SERVICE: &str = "opencode-studio";
+const SECRETS_KEY_ACCOUNT: &str = "secrets-key";
+
+/// Master key for the backend's encrypted secrets store, kept in the OS
+/// keychain (Keychain, Credential Manager or Secret Service) and created on
+/// first use. `None` when no keychain is available; the backend then falls
+/// back to a key file in its data directory.
+pub fn secrets_key() -> Option<String> {
+    let entry = match Entry::new(SERVICE, SECRETS_KEY_ACCOUNT) {
+        Ok(entry) => entry,
+        Err(err) => {
+            eprintln!("desktop keychain unavailable: {err}");
+            return None;
+        }
+    };
+    match entry.get_password() {
+        Ok(key) if is_hex_key(&key) => return Some(key),
+        Ok(_) => {
+            eprintln!("desktop keychain secrets key is malformed; ignoring it");
+            return None;
+        }
+        Err(keyring::Error::NoEntry) => {}
+        Err(err) => {
+            eprintln!("desktop keychain read failed: {err}");
+            return None;
+        }
+    }
+
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).ok()?;
+    let key: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    match entry.set_password(&key) {
+        Ok(()) => Some(key),
+        Err(err) => {
+            eprintln!("desktop keychain write failed: {err}");
+            None
+        }
+    }
+}
+
+fn is_hex_key(key: &str) -> bool {
+    key.len() == 64 && key.bytes().all(|b| b.is_ascii_hexdigit())
+}
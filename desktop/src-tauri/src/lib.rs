@@ -1,5 +1,10 @@
 mod backend;
 mod config;
+mod keychain;
+mod logs;
+mod notify;
+mod quit;
+mod shortcuts;
 mod updater;
 
 #[cfg(not(feature = "cef"))]
@@ -12,13 +17,15 @@ type AppHandle = tauri::AppHandle<AppRuntime>;
 
 use tauri::{
     Manager,
-    menu::{Menu, MenuItem},
-    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, Submenu},
+    tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent},
 };
 use tauri_plugin_autostart::ManagerExt;
 
 use backend::BackendManager;
 
+const PROFILE_MENU_PREFIX: &str = "profile:";
+
 pub fn run() {
     let app = tauri::Builder::<AppRuntime>::new()
         .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
@@ -26,10 +33,13 @@ pub fn run() {
         }))
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_autostart::init(
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             None,
         ))
+        .plugin(shortcuts::plugin())
         .invoke_handler(tauri::generate_handler![
             desktop_backend_status,
             desktop_backend_start,
@@ -38,73 +48,44 @@ pub fn run() {
             desktop_config_get,
             desktop_config_save,
             desktop_open_logs_dir,
+            desktop_logs_list,
+            desktop_logs_tail,
+            desktop_logs_unfollow,
             desktop_open_config,
             desktop_runtime_info,
             desktop_open_external,
             desktop_service_update,
             desktop_installer_update,
             desktop_update_progress_get,
+            desktop_profile_list,
+            desktop_profile_switch,
+            desktop_quick_prompt_send,
+            desktop_quick_prompt_close,
         ])
         .setup(|app| {
             let app_handle = app.handle().clone();
+            app.manage(shortcuts::ShortcutBindings::default());
 
             // Ensure a user-editable runtime config file exists.
             if let Ok(cfg) = config::load_or_create(&app_handle) {
                 if let Err(err) = apply_autostart_on_boot(&app_handle, cfg.autostart_on_boot) {
                     eprintln!("desktop autostart apply failed: {err}");
                 }
+                shortcuts::apply(&app_handle, &cfg.shortcuts);
             }
 
             // Backend manager is always present so tray actions and UI commands share
             // one code path even when backend startup fails.
             app.manage(BackendManager::new());
             app.manage(updater::UpdateProgressState::default());
+            app.manage(logs::LogFollowers::default());
 
             // Create tray.
-            let open_i = MenuItem::with_id(app, "open", "Open", true, None::<&str>)?;
-            let start_i =
-                MenuItem::with_id(app, "backend_start", "Start backend", true, None::<&str>)?;
-            let stop_i =
-                MenuItem::with_id(app, "backend_stop", "Stop backend", true, None::<&str>)?;
-            let restart_i = MenuItem::with_id(
-                app,
-                "backend_restart",
-                "Restart backend",
-                true,
-                None::<&str>,
-            )?;
-            let logs_i = MenuItem::with_id(app, "open_logs", "Open logs", true, None::<&str>)?;
-            let cfg_i = MenuItem::with_id(
-                app,
-                "open_config",
-                "Open runtime config",
-                true,
-                None::<&str>,
-            )?;
-            let autostart_i = MenuItem::with_id(
-                app,
-                "toggle_autostart_on_boot",
-                "Toggle launch at login",
-                true,
-                None::<&str>,
-            )?;
-            let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let menu = Menu::with_items(
-                app,
-                &[
-                    &open_i,
-                    &start_i,
-                    &stop_i,
-                    &restart_i,
-                    &logs_i,
-                    &cfg_i,
-                    &autostart_i,
-                    &quit_i,
-                ],
-            )?;
+            let menu = build_tray_menu(app.handle())?;
 
             let tray = TrayIconBuilder::<AppRuntime>::new()
                 .icon(app.default_window_icon().unwrap().clone())
+                .tooltip("OpenCode Studio")
                 .menu(&menu)
                 .show_menu_on_left_click(false)
                 .on_menu_event(|app, event| {
@@ -143,9 +124,7 @@ pub fn run() {
                         {
                             let app = app_handle.clone();
                             tauri::async_runtime::spawn(async move {
-                                let manager = app.state::<BackendManager>().inner().clone();
-                                let _ = manager.stop(&app).await;
-                                app.exit(0);
+                                quit::request_quit(&app).await;
                             });
                         }
                         #[cfg(not(target_os = "macos"))]
@@ -156,6 +135,9 @@ pub fn run() {
                 });
             }
 
+            notify::spawn_notification_poller(app_handle.clone());
+            backend::spawn_watchdog(app_handle.clone());
+
             // Attempt autostart backend.
             let manager = app_handle.state::<BackendManager>().inner().clone();
             tauri::async_runtime::spawn(async move {
@@ -213,11 +195,60 @@ fn desktop_config_save(
     save_desktop_config(&app, config)
 }
 
+#[tauri::command]
+fn desktop_profile_list(app: AppHandle) -> Result<config::ProfileList, String> {
+    Ok(config::load_or_create(&app)?.profile_list())
+}
+
+#[tauri::command]
+async fn desktop_profile_switch(
+    app: AppHandle,
+    name: String,
+) -> Result<config::ProfileList, String> {
+    switch_profile(&app, &name).await
+}
+
+#[tauri::command]
+async fn desktop_quick_prompt_send(
+    app: AppHandle,
+    text: String,
+) -> Result<shortcuts::QuickPromptSent, String> {
+    let sent = shortcuts::send_quick_prompt(&app, &text).await?;
+    shortcuts::hide_quick_prompt(&app);
+    Ok(sent)
+}
+
+#[tauri::command]
+fn desktop_quick_prompt_close(app: AppHandle) {
+    shortcuts::hide_quick_prompt(&app);
+}
+
 #[tauri::command]
 fn desktop_open_logs_dir(app: AppHandle) -> Result<(), String> {
     backend::open_logs_dir(&app)
 }
 
+#[tauri::command]
+fn desktop_logs_list(app: AppHandle) -> Result<Vec<logs::LogFileInfo>, String> {
+    logs::list_files(&app)
+}
+
+#[tauri::command]
+fn desktop_logs_tail(
+    app: AppHandle,
+    file: Option<String>,
+    lines: Option<usize>,
+    level: Option<logs::LogLevel>,
+    on_line: Option<tauri::ipc::Channel<logs::LogFollowEvent>>,
+) -> Result<logs::LogTail, String> {
+    logs::tail(&app, file.as_deref(), lines, level, on_line)
+}
+
+#[tauri::command]
+fn desktop_logs_unfollow(app: AppHandle, follow_id: u32) {
+    app.state::<logs::LogFollowers>().stop(follow_id);
+}
+
 #[tauri::command]
 fn desktop_open_config(app: AppHandle) -> Result<(), String> {
     config::open_runtime_config_file(&app)
@@ -458,12 +489,117 @@ async fn handle_tray_menu(app: &AppHandle, id: &str) {
             let _ = toggle_autostart_on_boot(app);
         }
         "quit" => {
-            let manager = app.state::<BackendManager>().inner().clone();
-            let _ = manager.stop(app).await;
-            app.exit(0);
+            quit::request_quit(app).await;
+        }
+        other => {
+            if let Some(name) = other.strip_prefix(PROFILE_MENU_PREFIX) {
+                let _ = switch_profile(app, name).await;
+            }
+        }
+    }
+}
+
+fn build_tray_menu<M: Manager<AppRuntime>>(app: &M) -> tauri::Result<Menu<AppRuntime>> {
+    let open_i = MenuItem::with_id(app, "open", "Open", true, None::<&str>)?;
+    let start_i = MenuItem::with_id(app, "backend_start", "Start backend", true, None::<&str>)?;
+    let stop_i = MenuItem::with_id(app, "backend_stop", "Stop backend", true, None::<&str>)?;
+    let restart_i = MenuItem::with_id(
+        app,
+        "backend_restart",
+        "Restart backend",
+        true,
+        None::<&str>,
+    )?;
+    let logs_i = MenuItem::with_id(app, "open_logs", "Open logs", true, None::<&str>)?;
+    let cfg_i = MenuItem::with_id(
+        app,
+        "open_config",
+        "Open runtime config",
+        true,
+        None::<&str>,
+    )?;
+    let autostart_i = MenuItem::with_id(
+        app,
+        "toggle_autostart_on_boot",
+        "Toggle launch at login",
+        true,
+        None::<&str>,
+    )?;
+    let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    let profiles = config::load_or_create(app.app_handle())
+        .unwrap_or_default()
+        .profile_list();
+    let profile_items = profiles
+        .profiles
+        .iter()
+        .map(|profile| {
+            CheckMenuItem::with_id(
+                app,
+                format!("{PROFILE_MENU_PREFIX}{}", profile.name),
+                &profile.name,
+                true,
+                profile.name == profiles.active,
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let profile_refs: Vec<&dyn IsMenuItem<AppRuntime>> = profile_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<AppRuntime>)
+        .collect();
+    let profile_menu = Submenu::with_items(app, "Profile", true, &profile_refs)?;
+
+    Menu::with_items(
+        app,
+        &[
+            &open_i,
+            &start_i,
+            &stop_i,
+            &restart_i,
+            &profile_menu,
+            &logs_i,
+            &cfg_i,
+            &autostart_i,
+            &quit_i,
+        ],
+    )
+}
+
+/// Rebuild the tray menu so the profile submenu reflects the saved config.
+fn refresh_tray_menu(app: &AppHandle) {
+    let Some(tray) = app.try_state::<TrayIcon<AppRuntime>>() else {
+        return;
+    };
+    match build_tray_menu(app) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
         }
-        _ => {}
+        Err(err) => eprintln!("desktop tray menu rebuild failed: {err}"),
+    }
+}
+
+/// Restart the backend under another profile. `default` is the top-level
+/// `[backend]` table.
+async fn switch_profile(app: &AppHandle, name: &str) -> Result<config::ProfileList, String> {
+    let name = name.trim();
+    let current = config::load_or_create(app)?;
+    if current.active_profile_name() == name {
+        return Ok(current.profile_list());
+    }
+    if name != config::DEFAULT_PROFILE && !current.profiles.contains_key(name) {
+        return Err(format!("unknown profile: {name}"));
+    }
+
+    // Stop while the old profile is still active so port cleanup targets it.
+    let manager = app.state::<BackendManager>().inner().clone();
+    let _ = manager.stop(app).await;
+    let saved = config::set_active_profile(app, name)?;
+    refresh_tray_menu(app);
+    if let Err(err) = manager.ensure_started(app).await {
+        eprintln!("desktop backend start after profile switch failed: {err}");
     }
+    Ok(saved.profile_list())
 }
 
 fn reveal_main_window(app: &AppHandle) {
@@ -486,6 +622,8 @@ fn save_desktop_config(
 ) -> Result<config::DesktopConfig, String> {
     let saved = config::save(app, cfg)?;
     apply_autostart_on_boot(app, saved.autostart_on_boot)?;
+    shortcuts::apply(app, &saved.shortcuts);
+    refresh_tray_menu(app);
     Ok(saved)
 }
 
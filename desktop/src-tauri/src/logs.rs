@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tauri::ipc::Channel;
+
+use crate::AppHandle;
+
+pub const BACKEND_LOG_FILE: &str = "backend.log";
+/// `backend.log` is rotated to `backend.log.1` once it grows past this.
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+const ROTATED_LOGS_KEPT: u32 = 5;
+const DEFAULT_TAIL_LINES: usize = 500;
+const MAX_TAIL_LINES: usize = 10_000;
+/// Tails only look this far back from the end of the file.
+const MAX_TAIL_READ_BYTES: u64 = 4 * 1024 * 1024;
+const MAX_FOLLOW_READ_BYTES: u64 = 1024 * 1024;
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn parse(token: &str) -> Option<Self> {
+        match token {
+            "TRACE" => Some(Self::Trace),
+            "DEBUG" => Some(Self::Debug),
+            "INFO" => Some(Self::Info),
+            "WARN" => Some(Self::Warn),
+            "ERROR" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LogLine {
+    pub level: LogLevel,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFileInfo {
+    pub name: String,
+    pub size: u64,
+    pub modified_ms: Option<i64>,
+    /// The file the backend is writing to; the rest are rotated copies.
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogTail {
+    pub file: String,
+    pub lines: Vec<LogLine>,
+    /// Pass to `desktop_logs_unfollow` to stop a follow started with this tail.
+    pub follow_id: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum LogFollowEvent {
+    Lines {
+        lines: Vec<LogLine>,
+    },
+    /// The file was rotated or truncated; following restarts at its top.
+    Rotated,
+}
+
+/// Stop flags of the active follow tasks.
+#[derive(Debug, Default)]
+pub struct LogFollowers {
+    next_id: AtomicU32,
+    active: Mutex<HashMap<u32, Arc<AtomicBool>>>,
+}
+
+impl LogFollowers {
+    fn start(&self) -> (u32, Arc<AtomicBool>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let stop = Arc::new(AtomicBool::new(false));
+        self.active
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id, stop.clone());
+        (id, stop)
+    }
+
+    pub fn stop(&self, id: u32) {
+        if let Some(stop) = self
+            .active
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&id)
+        {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+pub fn logs_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_log_dir().ok()
+}
+
+pub fn backend_log_path(app: &AppHandle) -> Option<PathBuf> {
+    Some(logs_dir(app)?.join(BACKEND_LOG_FILE))
+}
+
+/// Shift `backend.log` to `backend.log.1` (and older copies up by one) once it
+/// is larger than [`MAX_LOG_BYTES`]. Called before the backend starts writing.
+pub fn rotate_if_needed(path: &Path) {
+    let Ok(meta) = fs::metadata(path) else {
+        return;
+    };
+    if meta.len() <= MAX_LOG_BYTES {
+        return;
+    }
+    let rotated = |n: u32| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    };
+    let _ = fs::remove_file(rotated(ROTATED_LOGS_KEPT));
+    for n in (1..ROTATED_LOGS_KEPT).rev() {
+        let _ = fs::rename(rotated(n), rotated(n + 1));
+    }
+    let _ = fs::rename(path, rotated(1));
+}
+
+/// `backend.log` first, then rotated copies from newest to oldest.
+fn rotation_index(name: &str) -> Option<u32> {
+    let rest = name.strip_prefix(BACKEND_LOG_FILE)?;
+    if rest.is_empty() {
+        return Some(0);
+    }
+    rest.strip_prefix('.')?.parse().ok()
+}
+
+pub fn list_files(app: &AppHandle) -> Result<Vec<LogFileInfo>, String> {
+    let dir = logs_dir(app).ok_or_else(|| "unable to resolve log dir".to_string())?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("read log dir: {err}")),
+    };
+    let mut files: Vec<(u32, LogFileInfo)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let index = rotation_index(&name)?;
+            let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+            let modified_ms = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64);
+            Some((
+                index,
+                LogFileInfo {
+                    name,
+                    size: meta.len(),
+                    modified_ms,
+                    current: index == 0,
+                },
+            ))
+        })
+        .collect();
+    files.sort_by_key(|(index, _)| *index);
+    Ok(files.into_iter().map(|(_, info)| info).collect())
+}
+
+fn resolve_file(app: &AppHandle, file: Option<&str>) -> Result<(String, PathBuf), String> {
+    let name = file
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or(BACKEND_LOG_FILE);
+    // Only backend logs in the log dir; never arbitrary paths.
+    if rotation_index(name).is_none() {
+        return Err(format!("unknown log file: {name}"));
+    }
+    let dir = logs_dir(app).ok_or_else(|| "unable to resolve log dir".to_string())?;
+    Ok((name.to_string(), dir.join(name)))
+}
+
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            out.push(c);
+            continue;
+        }
+        // CSI sequences end at the first byte in `@`..=`~`.
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Level of a backend log line. Lines without one (wrapped output, panics)
+/// belong to the entry above them.
+fn line_level(text: &str, previous: LogLevel) -> LogLevel {
+    if text.starts_with("[backend error]") {
+        return LogLevel::Error;
+    }
+    text.split_whitespace()
+        .take(3)
+        .find_map(LogLevel::parse)
+        .unwrap_or(previous)
+}
+
+/// Parse complete lines, carrying the level across continuation lines, and
+/// keep those at or above `min_level`.
+fn parse_lines(raw: &str, previous: &mut LogLevel, min_level: Option<LogLevel>) -> Vec<LogLine> {
+    raw.lines()
+        .filter_map(|line| {
+            let text = strip_ansi(line.trim_end_matches('\r'));
+            let level = line_level(&text, *previous);
+            *previous = level;
+            if min_level.is_some_and(|min| level < min) {
+                return None;
+            }
+            Some(LogLine { level, text })
+        })
+        .collect()
+}
+
+fn read_range(path: &Path, start: u64, max: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = fs::File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut buf = Vec::new();
+    file.take(max).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Last `lines` lines of a backend log at or above `level`. With a `follow`
+/// channel, new lines are streamed to it until [`LogFollowers::stop`].
+pub fn tail(
+    app: &AppHandle,
+    file: Option<&str>,
+    lines: Option<usize>,
+    level: Option<LogLevel>,
+    follow: Option<Channel<LogFollowEvent>>,
+) -> Result<LogTail, String> {
+    let (name, path) = resolve_file(app, file)?;
+    let limit = lines.unwrap_or(DEFAULT_TAIL_LINES).clamp(1, MAX_TAIL_LINES);
+
+    let len = match fs::metadata(&path) {
+        Ok(meta) => meta.len(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0,
+        Err(err) => return Err(format!("read {name}: {err}")),
+    };
+    let start = len.saturating_sub(MAX_TAIL_READ_BYTES);
+    let mut buf = if len == 0 {
+        Vec::new()
+    } else {
+        read_range(&path, start, len - start).map_err(|e| format!("read {name}: {e}"))?
+    };
+    // Drop the line cut in half by the read window, and any unterminated
+    // last line; the follower picks that one up once it is complete.
+    if start > 0
+        && let Some(pos) = buf.iter().position(|b| *b == b'\n')
+    {
+        buf.drain(..=pos);
+    }
+    let end = match buf.iter().rposition(|b| *b == b'\n') {
+        Some(pos) => {
+            let cut = buf.len() - pos - 1;
+            buf.truncate(pos + 1);
+            len - cut as u64
+        }
+        None => {
+            let cut = buf.len();
+            buf.clear();
+            len - cut as u64
+        }
+    };
+
+    let mut previous = LogLevel::Info;
+    let mut parsed = parse_lines(&String::from_utf8_lossy(&buf), &mut previous, level);
+    if parsed.len() > limit {
+        parsed.drain(..parsed.len() - limit);
+    }
+
+    let follow_id = follow.map(|channel| {
+        let followers = app.state::<LogFollowers>();
+        let (id, stop) = followers.start();
+        spawn_follow(app.clone(), id, path, end, previous, level, channel, stop);
+        id
+    });
+
+    Ok(LogTail {
+        file: name,
+        lines: parsed,
+        follow_id,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_follow(
+    app: AppHandle,
+    id: u32,
+    path: PathBuf,
+    mut offset: u64,
+    mut previous: LogLevel,
+    level: Option<LogLevel>,
+    channel: Channel<LogFollowEvent>,
+    stop: Arc<AtomicBool>,
+) {
+    tauri::async_runtime::spawn(async move {
+        let mut pending: Vec<u8> = Vec::new();
+        while !stop.load(Ordering::Relaxed) {
+            tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+
+            let len = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            if len < offset {
+                // Rotated away or truncated: the path now names a new file.
+                offset = 0;
+                pending.clear();
+                previous = LogLevel::Info;
+                if channel.send(LogFollowEvent::Rotated).is_err() {
+                    break;
+                }
+            }
+            if len == offset {
+                continue;
+            }
+            let Ok(chunk) = read_range(&path, offset, (len - offset).min(MAX_FOLLOW_READ_BYTES))
+            else {
+                continue;
+            };
+            offset += chunk.len() as u64;
+            pending.extend_from_slice(&chunk);
+            let Some(pos) = pending.iter().rposition(|b| *b == b'\n') else {
+                continue;
+            };
+            let complete: Vec<u8> = pending.drain(..=pos).collect();
+            let lines = parse_lines(&String::from_utf8_lossy(&complete), &mut previous, level);
+            if !lines.is_empty() && channel.send(LogFollowEvent::Lines { lines }).is_err() {
+                break;
+            }
+        }
+        app.state::<LogFollowers>().stop(id);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_are_detected_and_inherited() {
+        let raw = "\u{1b}[2m2026-01-01T00:00:00Z\u{1b}[0m \u{1b}[32m INFO\u{1b}[0m listening\n\
+                   2026-01-01T00:00:01Z ERROR request failed\n\
+                   caused by: timeout\n\
+                   2026-01-01T00:00:02Z DEBUG polled\n\
+                   [backend error] spawn failed\n";
+        let mut previous = LogLevel::Info;
+        let all = parse_lines(raw, &mut previous, None);
+        assert_eq!(
+            all.iter().map(|l| l.level).collect::<Vec<_>>(),
+            vec![
+                LogLevel::Info,
+                LogLevel::Error,
+                LogLevel::Error,
+                LogLevel::Debug,
+                LogLevel::Error,
+            ]
+        );
+        assert_eq!(all[0].text, "2026-01-01T00:00:00Z  INFO listening");
+
+        let mut previous = LogLevel::Info;
+        let warn_up = parse_lines(raw, &mut previous, Some(LogLevel::Warn));
+        assert_eq!(warn_up.len(), 3);
+        assert_eq!(warn_up[1].text, "caused by: timeout");
+    }
+
+    #[test]
+    fn only_backend_logs_are_selectable() {
+        assert_eq!(rotation_index("backend.log"), Some(0));
+        assert_eq!(rotation_index("backend.log.3"), Some(3));
+        assert_eq!(rotation_index("backend.logx"), None);
+        assert_eq!(rotation_index("../backend.log"), None);
+        assert_eq!(rotation_index("backend.log.1/../../etc"), None);
+    }
+}
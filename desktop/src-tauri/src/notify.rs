@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use tauri::Manager;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::AppHandle;
+use crate::backend::BackendManager;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct DesktopNotificationsResponse {
+    latest_seq: u64,
+    notifications: Vec<DesktopNotification>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct DesktopNotification {
+    seq: u64,
+    title: String,
+    body: String,
+}
+
+/// Poll the backend's desktop notification queue and surface entries as
+/// native notifications while the main window is not focused.
+pub fn spawn_notification_poller(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let Ok(client) = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() else {
+            return;
+        };
+        let mut token: Option<String> = None;
+        // `None` until the first successful poll, which only records the
+        // current sequence so older entries are not replayed.
+        let mut after: Option<u64> = None;
+        let mut last_base: Option<String> = None;
+
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let Some(url) = app.state::<BackendManager>().inner().status().await.url else {
+                continue;
+            };
+            let base = url.trim_end_matches('/').to_string();
+            if last_base.as_deref() != Some(base.as_str()) {
+                // Backend restarted on a new address; its queue starts over.
+                after = None;
+                token = None;
+                last_base = Some(base.clone());
+            }
+
+            let mut request_url = format!("{base}/api/notifications/desktop");
+            if let Some(seq) = after {
+                request_url.push_str(&format!("?after={seq}"));
+            }
+            let mut req = client.get(&request_url);
+            if let Some(token) = token.as_deref() {
+                req = req.bearer_auth(token);
+            }
+            let resp = match req.send().await {
+                Ok(resp) => resp,
+                Err(_) => continue,
+            };
+            if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+                token = crate::backend::api_auth_token(&app, &client, &base).await;
+                continue;
+            }
+            if !resp.status().is_success() {
+                continue;
+            }
+            let Ok(batch) = resp.json::<DesktopNotificationsResponse>().await else {
+                continue;
+            };
+
+            // The server restarted behind the same address.
+            if after.is_some_and(|seq| batch.latest_seq < seq) {
+                after = Some(batch.latest_seq);
+                continue;
+            }
+            if after.is_some() && !main_window_focused(&app) {
+                for n in &batch.notifications {
+                    let _ = app
+                        .notification()
+                        .builder()
+                        .title(&n.title)
+                        .body(&n.body)
+                        .show();
+                }
+            }
+            let newest = batch.notifications.iter().map(|n| n.seq).max().unwrap_or(0);
+            after = Some(batch.latest_seq.max(newest));
+        }
+    });
+}
+
+fn main_window_focused(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .is_some_and(|win| win.is_visible().unwrap_or(false) && win.is_focused().unwrap_or(false))
+}
@@ -0,0 +1,303 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use tauri::Manager;
+use tauri_plugin_dialog::{
+    DialogExt, MessageDialogButtons, MessageDialogKind, MessageDialogResult,
+};
+use tokio::sync::oneshot;
+
+use crate::AppHandle;
+use crate::backend::BackendManager;
+
+const BUSY_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const MAX_LISTED_ITEMS: usize = 8;
+
+const QUIT_NOW_LABEL: &str = "Quit now";
+const FINISH_THEN_QUIT_LABEL: &str = "Finish tasks, then quit";
+const CANCEL_LABEL: &str = "Cancel";
+const UNKNOWN_MESSAGE: &str = "OpenCode Studio could not check whether sessions or jobs are still running. Quitting now may interrupt them. Quit anyway?";
+
+static QUIT_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct BusyReport {
+    busy: bool,
+    sessions: Vec<BusySession>,
+    jobs: Vec<BusyJob>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct BusySession {
+    #[serde(rename = "sessionID")]
+    session_id: String,
+    title: Option<String>,
+    directory: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct BusyJob {
+    kind: String,
+    directory: String,
+}
+
+/// What the backend said about outstanding work.
+#[derive(Debug)]
+enum BusyState {
+    /// Nothing running, or no backend to ask.
+    Idle,
+    Busy(BusyReport),
+    /// The backend answered but refused or failed the check (e.g. 401), so
+    /// the user has to decide.
+    Unknown,
+}
+
+/// One authenticated client for the whole quit flow, so polling reuses a
+/// single UI session and logs it out at the end.
+struct BusyProbe {
+    client: reqwest::Client,
+    base: String,
+    token: Option<String>,
+}
+
+impl BusyProbe {
+    async fn connect(app: &AppHandle) -> Option<Self> {
+        let url = app.state::<BackendManager>().inner().status().await.url?;
+        let base = url.trim_end_matches('/').to_string();
+        let client = reqwest::Client::builder()
+            .timeout(BUSY_REQUEST_TIMEOUT)
+            .build()
+            .ok()?;
+        let token = crate::backend::api_auth_token(app, &client, &base).await;
+        Some(Self {
+            client,
+            base,
+            token,
+        })
+    }
+
+    async fn query(&self) -> BusyState {
+        let mut req = self
+            .client
+            .get(format!("{}/api/opencode-studio/busy", self.base));
+        if let Some(token) = self.token.as_deref() {
+            req = req.bearer_auth(token);
+        }
+        // A backend that stops answering has nothing left to interrupt.
+        let Ok(resp) = req.send().await else {
+            return BusyState::Idle;
+        };
+        if !resp.status().is_success() {
+            return BusyState::Unknown;
+        }
+        match resp.json::<BusyReport>().await {
+            Ok(report) if report.busy => BusyState::Busy(report),
+            Ok(_) => BusyState::Idle,
+            Err(_) => BusyState::Unknown,
+        }
+    }
+
+    async fn close(self) {
+        if let Some(token) = self.token.as_deref() {
+            crate::backend::api_auth_logout(&self.client, &self.base, token).await;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QuitChoice {
+    QuitNow,
+    FinishThenQuit,
+    Cancel,
+}
+
+/// Quit the app, first asking for confirmation when the backend still has
+/// sessions generating or jobs running, or cannot tell.
+pub async fn request_quit(app: &AppHandle) {
+    if QUIT_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let probe = BusyProbe::connect(app).await;
+    let quit = confirm_quit(app, probe.as_ref()).await;
+    if let Some(probe) = probe {
+        probe.close().await;
+    }
+    if !quit {
+        QUIT_IN_PROGRESS.store(false, Ordering::SeqCst);
+        return;
+    }
+
+    let manager = app.state::<BackendManager>().inner().clone();
+    let _ = manager.stop(app).await;
+    app.exit(0);
+}
+
+async fn confirm_quit(app: &AppHandle, probe: Option<&BusyProbe>) -> bool {
+    let Some(probe) = probe else {
+        return true;
+    };
+
+    let choice = match probe.query().await {
+        BusyState::Idle => QuitChoice::QuitNow,
+        BusyState::Busy(report) => ask_quit_choice(app, &report).await,
+        BusyState::Unknown => return ask_quit_anyway(app, UNKNOWN_MESSAGE).await,
+    };
+    match choice {
+        QuitChoice::Cancel => false,
+        QuitChoice::QuitNow => true,
+        QuitChoice::FinishThenQuit => {
+            if let Some(win) = app.get_webview_window("main") {
+                let _ = win.hide();
+            }
+            match wait_until_idle(probe).await {
+                BusyState::Idle => true,
+                BusyState::Busy(_) => ask_quit_anyway(app, &timeout_message()).await,
+                BusyState::Unknown => ask_quit_anyway(app, UNKNOWN_MESSAGE).await,
+            }
+        }
+    }
+}
+
+fn describe_busy(report: &BusyReport) -> String {
+    let mut lines = Vec::new();
+    for session in &report.sessions {
+        let label = session
+            .title
+            .as_deref()
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or(session.session_id.as_str());
+        match session
+            .directory
+            .as_deref()
+            .filter(|d| !d.trim().is_empty())
+        {
+            Some(dir) => lines.push(format!("• Session \"{label}\" ({dir})")),
+            None => lines.push(format!("• Session \"{label}\"")),
+        }
+    }
+    for job in &report.jobs {
+        lines.push(format!("• {} operation in {}", job.kind, job.directory));
+    }
+
+    let total = lines.len();
+    let mut out = String::from("OpenCode Studio is still working:\n\n");
+    for line in lines.iter().take(MAX_LISTED_ITEMS) {
+        out.push_str(line);
+        out.push('\n');
+    }
+    if total > MAX_LISTED_ITEMS {
+        out.push_str(&format!("• …and {} more\n", total - MAX_LISTED_ITEMS));
+    }
+    out.push_str("\nQuitting now stops the backend and interrupts this work.");
+    out
+}
+
+async fn ask_quit_choice(app: &AppHandle, report: &BusyReport) -> QuitChoice {
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .message(describe_busy(report))
+        .title("Quit OpenCode Studio?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::YesNoCancelCustom(
+            QUIT_NOW_LABEL.to_string(),
+            FINISH_THEN_QUIT_LABEL.to_string(),
+            CANCEL_LABEL.to_string(),
+        ))
+        .show_with_result(move |result| {
+            let _ = tx.send(result);
+        });
+
+    match rx.await {
+        Ok(MessageDialogResult::Yes) => QuitChoice::QuitNow,
+        Ok(MessageDialogResult::No) => QuitChoice::FinishThenQuit,
+        Ok(MessageDialogResult::Custom(label)) if label == QUIT_NOW_LABEL => QuitChoice::QuitNow,
+        Ok(MessageDialogResult::Custom(label)) if label == FINISH_THEN_QUIT_LABEL => {
+            QuitChoice::FinishThenQuit
+        }
+        _ => QuitChoice::Cancel,
+    }
+}
+
+/// Poll the backend until nothing is busy or the check stops working.
+/// Returns the last `Busy` state on timeout.
+async fn wait_until_idle(probe: &BusyProbe) -> BusyState {
+    let deadline = Instant::now() + DRAIN_TIMEOUT;
+    loop {
+        let state = probe.query().await;
+        if !matches!(state, BusyState::Busy(_)) || Instant::now() >= deadline {
+            return state;
+        }
+        tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+    }
+}
+
+fn timeout_message() -> String {
+    format!(
+        "Tasks are still running after {} minutes. Quit anyway?",
+        DRAIN_TIMEOUT.as_secs() / 60
+    )
+}
+
+async fn ask_quit_anyway(app: &AppHandle, message: &str) -> bool {
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .message(message)
+        .title("Quit OpenCode Studio?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            QUIT_NOW_LABEL.to_string(),
+            CANCEL_LABEL.to_string(),
+        ))
+        .show_with_result(move |result| {
+            let _ = tx.send(result);
+        });
+
+    match rx.await {
+        Ok(MessageDialogResult::Ok) => true,
+        Ok(MessageDialogResult::Custom(label)) => label == QUIT_NOW_LABEL,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_busy_lists_sessions_and_jobs_with_overflow() {
+        let mut report = BusyReport {
+            busy: true,
+            sessions: Vec::new(),
+            jobs: vec![BusyJob {
+                kind: "git".to_string(),
+                directory: "/repo".to_string(),
+            }],
+        };
+        report.sessions.push(BusySession {
+            session_id: "ses_1".to_string(),
+            title: Some("Fix parser".to_string()),
+            directory: Some("/repo".to_string()),
+        });
+        let text = describe_busy(&report);
+        assert!(text.contains("Session \"Fix parser\" (/repo)"));
+        assert!(text.contains("git operation in /repo"));
+
+        for i in 0..10 {
+            report.sessions.push(BusySession {
+                session_id: format!("ses_x{i}"),
+                title: None,
+                directory: None,
+            });
+        }
+        let text = describe_busy(&report);
+        assert!(text.contains("…and 4 more"));
+        assert!(text.contains("Session \"ses_x0\""));
+    }
+}
@@ -0,0 +1,219 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::backend::BackendManager;
+use crate::config::ShortcutConfig;
+use crate::{AppHandle, AppRuntime};
+
+pub const QUICK_PROMPT_WINDOW: &str = "quick-prompt";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShortcutAction {
+    ToggleWindow,
+    QuickPrompt,
+}
+
+/// Shortcut ids currently registered, so the plugin handler can tell which
+/// action fired after the config changes.
+#[derive(Debug, Default)]
+pub struct ShortcutBindings(Mutex<Vec<(u32, ShortcutAction)>>);
+
+#[derive(Debug, Deserialize)]
+struct RecentSessionsResponse {
+    items: Vec<RecentSession>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecentSession {
+    session_id: String,
+    directory_path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickPromptSent {
+    pub session_id: String,
+    pub directory: String,
+}
+
+pub fn plugin() -> tauri::plugin::TauriPlugin<AppRuntime> {
+    tauri_plugin_global_shortcut::Builder::<AppRuntime>::new()
+        .with_handler(|app, shortcut, event| {
+            if event.state != ShortcutState::Pressed {
+                return;
+            }
+            let action = app.try_state::<ShortcutBindings>().and_then(|bindings| {
+                let bindings = bindings.0.lock().unwrap_or_else(|e| e.into_inner());
+                bindings
+                    .iter()
+                    .find(|(id, _)| *id == shortcut.id())
+                    .map(|(_, action)| *action)
+            });
+            match action {
+                Some(ShortcutAction::ToggleWindow) => toggle_main_window(app),
+                Some(ShortcutAction::QuickPrompt) => open_quick_prompt(app),
+                None => {}
+            }
+        })
+        .build()
+}
+
+/// Replace the registered global shortcuts with the configured ones. Invalid
+/// or already-taken hotkeys are logged and skipped.
+pub fn apply(app: &AppHandle, cfg: &ShortcutConfig) {
+    let manager = app.global_shortcut();
+    if let Err(err) = manager.unregister_all() {
+        eprintln!("desktop shortcut unregister failed: {err}");
+    }
+
+    let mut registered = Vec::new();
+    for (raw, action) in [
+        (cfg.toggle_window.as_str(), ShortcutAction::ToggleWindow),
+        (cfg.quick_prompt.as_str(), ShortcutAction::QuickPrompt),
+    ] {
+        if raw.is_empty() {
+            continue;
+        }
+        let shortcut = match raw.parse::<Shortcut>() {
+            Ok(shortcut) => shortcut,
+            Err(err) => {
+                eprintln!("desktop shortcut {raw:?} is invalid: {err}");
+                continue;
+            }
+        };
+        if registered.iter().any(|(id, _)| *id == shortcut.id()) {
+            eprintln!("desktop shortcut {raw:?} is assigned twice; keeping the first");
+            continue;
+        }
+        match manager.register(shortcut) {
+            Ok(()) => registered.push((shortcut.id(), action)),
+            Err(err) => eprintln!("desktop shortcut {raw:?} register failed: {err}"),
+        }
+    }
+
+    let bindings = app.state::<ShortcutBindings>();
+    *bindings.0.lock().unwrap_or_else(|e| e.into_inner()) = registered;
+}
+
+fn toggle_main_window(app: &AppHandle) {
+    let Some(win) = app.get_webview_window("main") else {
+        return;
+    };
+    let visible = win.is_visible().unwrap_or(false);
+    let focused = win.is_focused().unwrap_or(false);
+    if visible && focused {
+        let _ = win.hide();
+    } else {
+        crate::reveal_main_window(app);
+    }
+}
+
+/// Show the small always-on-top prompt window, creating it on first use.
+fn open_quick_prompt(app: &AppHandle) {
+    if let Some(win) = app.get_webview_window(QUICK_PROMPT_WINDOW) {
+        let _ = win.show();
+        let _ = win.set_focus();
+        return;
+    }
+
+    let url = WebviewUrl::App("index.html?ocQuickPrompt=1".into());
+    let built = WebviewWindowBuilder::new(app, QUICK_PROMPT_WINDOW, url)
+        .title("OpenCode Studio Quick Prompt")
+        .inner_size(560.0, 160.0)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .focused(true)
+        .build();
+    match built {
+        Ok(win) => {
+            let win2 = win.clone();
+            win.on_window_event(move |event| match event {
+                tauri::WindowEvent::CloseRequested { api, .. } => {
+                    api.prevent_close();
+                    let _ = win2.hide();
+                }
+                // Behave like a palette: dismiss when focus moves elsewhere.
+                tauri::WindowEvent::Focused(false) => {
+                    let _ = win2.hide();
+                }
+                _ => {}
+            });
+        }
+        Err(err) => eprintln!("desktop quick prompt window failed: {err}"),
+    }
+}
+
+pub fn hide_quick_prompt(app: &AppHandle) {
+    if let Some(win) = app.get_webview_window(QUICK_PROMPT_WINDOW) {
+        let _ = win.hide();
+    }
+}
+
+/// Send `text` to the most recently updated session through the backend.
+pub async fn send_quick_prompt(app: &AppHandle, text: &str) -> Result<QuickPromptSent, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("prompt is empty".to_string());
+    }
+    let url = app
+        .state::<BackendManager>()
+        .inner()
+        .status()
+        .await
+        .url
+        .ok_or_else(|| "backend is not running".to_string())?;
+    let base = url.trim_end_matches('/');
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("reqwest client: {e}"))?;
+    let token = crate::backend::api_auth_token(app, &client, base).await;
+    let with_auth = |req: reqwest::RequestBuilder| match token.as_deref() {
+        Some(token) => req.bearer_auth(token),
+        None => req,
+    };
+
+    let resp = with_auth(client.get(format!("{base}/api/sessions/recent?limit=1")))
+        .send()
+        .await
+        .map_err(|e| format!("list recent sessions: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("list recent sessions: HTTP {}", resp.status()));
+    }
+    let recent = resp
+        .json::<RecentSessionsResponse>()
+        .await
+        .map_err(|e| format!("list recent sessions: {e}"))?;
+    let session = recent
+        .items
+        .into_iter()
+        .next()
+        .ok_or_else(|| "no recent session to send to".to_string())?;
+
+    let resp = with_auth(client.post(format!(
+        "{base}/api/session/{}/prompt_async",
+        session.session_id
+    )))
+    .query(&[("directory", session.directory_path.as_str())])
+    .json(&serde_json::json!({ "parts": [{ "type": "text", "text": text }] }))
+    .send()
+    .await
+    .map_err(|e| format!("send prompt: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("send prompt: HTTP {}", resp.status()));
+    }
+
+    Ok(QuickPromptSent {
+        session_id: session.session_id,
+        directory: session.directory_path,
+    })
+}
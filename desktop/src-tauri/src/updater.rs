@@ -126,7 +126,7 @@ pub(crate) async fn apply_service_update(
         });
 
         let progress_clone = progress.clone();
-        download_asset_to_path(&asset_url, &archive_path, move |downloaded, total| {
+        download_asset_to_path(app, &asset_url, &archive_path, move |downloaded, total| {
             progress_clone.set_download(
                 "downloading",
                 "Downloading service package...",
@@ -202,14 +202,19 @@ pub(crate) async fn apply_installer_update(
         let installer_path = downloads_dir.join(format!("desktop-{suffix}-{installer_name}"));
 
         let progress_clone = progress.clone();
-        download_asset_to_path(&asset_url, &installer_path, move |downloaded, total| {
-            progress_clone.set_download(
-                "downloading",
-                "Downloading desktop installer package...",
-                downloaded,
-                total,
-            );
-        })
+        download_asset_to_path(
+            app,
+            &asset_url,
+            &installer_path,
+            move |downloaded, total| {
+                progress_clone.set_download(
+                    "downloading",
+                    "Downloading desktop installer package...",
+                    downloaded,
+                    total,
+                );
+            },
+        )
         .await?;
 
         progress.set_phase("stopping", "Stopping runtime processes...");
@@ -247,7 +252,56 @@ fn normalize_http_url(raw: &str) -> Result<String, String> {
     Ok(url.to_string())
 }
 
+fn load_ca_certs(path: &Path) -> Result<Vec<reqwest::Certificate>, String> {
+    let files = if path.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(path)
+            .map_err(|err| format!("read CA dir {}: {err}", path.display()))?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|p| {
+                p.extension().and_then(OsStr::to_str).is_some_and(|ext| {
+                    matches!(ext.to_ascii_lowercase().as_str(), "pem" | "crt" | "cer")
+                })
+            })
+            .collect();
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut certs = Vec::new();
+    for file in files {
+        let pem = fs::read(&file).map_err(|err| format!("read CA {}: {err}", file.display()))?;
+        certs.extend(
+            reqwest::Certificate::from_pem_bundle(&pem)
+                .map_err(|err| format!("parse CA {}: {err}", file.display()))?,
+        );
+    }
+    Ok(certs)
+}
+
+/// Same extra roots the backend trusts (`backend.ca_certs`), so downloads work
+/// behind TLS-intercepting proxies.
+fn http_client_builder(app: &AppHandle) -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder();
+    let cfg = crate::config::load_or_create(app).unwrap_or_default();
+    let mut certs = Vec::new();
+    for raw in &cfg.active_backend().ca_certs {
+        match load_ca_certs(Path::new(raw)) {
+            Ok(found) => certs.extend(found),
+            Err(err) => eprintln!("updater: skipping CA certificate: {err}"),
+        }
+    }
+    if certs.is_empty() {
+        builder
+    } else {
+        builder.tls_certs_merge(certs)
+    }
+}
+
 async fn download_asset_to_path<F>(
+    app: &AppHandle,
     url: &str,
     destination: &Path,
     mut on_progress: F,
@@ -260,7 +314,7 @@ where
             .map_err(|err| format!("create update download dir {}: {err}", parent.display()))?;
     }
 
-    let client = reqwest::Client::builder()
+    let client = http_client_builder(app)
         .timeout(Duration::from_secs(15 * 60))
         .build()
         .map_err(|err| format!("create http client for updater: {err}"))?;
@@ -0,0 +1,443 @@
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::{Method, StatusCode},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+
+use crate::{ApiResult, AppError};
+
+/// Distinguishes API tokens from UI session tokens in `Authorization: Bearer`.
+pub(crate) const API_TOKEN_PREFIX: &str = "ocs_";
+const MAX_NAME_CHARS: usize = 80;
+const MAX_TOKENS: usize = 200;
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+/// `lastUsedAt` is written back at most this often per token.
+const LAST_USED_PERSIST_INTERVAL_MILLIS: u64 = 10 * 60 * 1000;
+
+/// In-memory copy of the persisted tokens, consulted on every API request.
+static TOKENS: LazyLock<RwLock<Vec<ApiTokenRecord>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub(crate) enum ApiScope {
+    #[serde(rename = "git:read")]
+    GitRead,
+    #[serde(rename = "git:write")]
+    GitWrite,
+    #[serde(rename = "sessions:read")]
+    SessionsRead,
+    #[serde(rename = "sessions:write")]
+    SessionsWrite,
+    #[serde(rename = "settings:read")]
+    SettingsRead,
+    #[serde(rename = "settings:write")]
+    SettingsWrite,
+    #[serde(rename = "terminal")]
+    Terminal,
+    /// Every API route except token, user, secret and admin management.
+    #[serde(rename = "*")]
+    All,
+}
+
+fn is_read(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+fn under(path: &str, prefix: &str) -> bool {
+    path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// The scope a request needs. `None` means API tokens may never call it.
+fn required_scope(method: &Method, path: &str) -> Option<ApiScope> {
+    let read = is_read(method);
+    if ["/api/auth", "/api/secrets", "/api/admin"]
+        .iter()
+        .any(|prefix| under(path, prefix))
+    {
+        return None;
+    }
+    if under(path, "/api/git") || under(path, "/api/forge") {
+        return Some(if read {
+            ApiScope::GitRead
+        } else {
+            ApiScope::GitWrite
+        });
+    }
+    if under(path, "/api/terminal") {
+        return Some(ApiScope::Terminal);
+    }
+    if ["/api/session", "/api/sessions", "/api/session-activity"]
+        .iter()
+        .any(|prefix| under(path, prefix))
+    {
+        return Some(if read {
+            ApiScope::SessionsRead
+        } else {
+            ApiScope::SessionsWrite
+        });
+    }
+    if under(path, "/api/config") {
+        return Some(if read {
+            ApiScope::SettingsRead
+        } else {
+            ApiScope::SettingsWrite
+        });
+    }
+    Some(ApiScope::All)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ApiTokenRecord {
+    pub id: String,
+    pub name: String,
+    /// SHA-256 of the token; tokens are random, so a slow hash adds nothing.
+    pub token_hash: String,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: u64,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub last_used_at: Option<u64>,
+}
+
+impl ApiTokenRecord {
+    fn allows(&self, scope: ApiScope) -> bool {
+        self.scopes.contains(&ApiScope::All) || self.scopes.contains(&scope)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ApiTokenStore {
+    #[serde(default)]
+    tokens: Vec<ApiTokenRecord>,
+}
+
+fn hash_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Load persisted tokens into memory; called once at startup.
+pub(crate) async fn load(db: &crate::studio_db::StudioDb) {
+    let tokens = match db
+        .get_json::<ApiTokenStore>(crate::studio_db::KV_KEY_API_TOKENS)
+        .await
+    {
+        Ok(store) => store.unwrap_or_default().tokens,
+        Err(err) => {
+            tracing::warn!(error = %err, "Failed to load API tokens");
+            Vec::new()
+        }
+    };
+    if let Ok(mut guard) = TOKENS.write() {
+        *guard = tokens;
+    }
+}
+
+fn snapshot() -> Vec<ApiTokenRecord> {
+    TOKENS.read().map(|guard| guard.clone()).unwrap_or_default()
+}
+
+async fn update_tokens<R>(
+    db: &crate::studio_db::StudioDb,
+    update: impl FnOnce(&mut Vec<ApiTokenRecord>) -> ApiResult<R>,
+) -> ApiResult<R> {
+    db.update_json(
+        crate::studio_db::KV_KEY_API_TOKENS,
+        |store: &mut ApiTokenStore| {
+            // `lastUsedAt` is bumped in memory first and only written lazily.
+            let cached = snapshot();
+            for record in &mut store.tokens {
+                if let Some(used) = cached.iter().find(|c| c.id == record.id) {
+                    record.last_used_at = record.last_used_at.max(used.last_used_at);
+                }
+            }
+            let result = update(&mut store.tokens)?;
+            // Still under the update lock, so the cache sees updates in order.
+            if let Ok(mut guard) = TOKENS.write() {
+                *guard = store.tokens.clone();
+            }
+            Ok(result)
+        },
+    )
+    .await
+    .map_err(AppError::internal)?
+}
+
+pub(crate) fn is_api_token(token: &str) -> bool {
+    token.starts_with(API_TOKEN_PREFIX)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ApiTokenDenied {
+    /// Unknown, revoked or expired.
+    Invalid,
+    MissingScope,
+}
+
+/// Identity attached to requests authenticated with an API token.
+#[derive(Debug, Clone)]
+pub(crate) struct ApiTokenPrincipal {
+    pub id: String,
+    pub name: String,
+}
+
+/// Check `token` against the store and the scope the request needs.
+pub(crate) fn authorize(
+    state: &Arc<crate::AppState>,
+    token: &str,
+    method: &Method,
+    path: &str,
+) -> Result<ApiTokenPrincipal, ApiTokenDenied> {
+    let hash = hash_token(token);
+    let now = now_millis();
+    let (principal, persist_last_used) = {
+        let mut guard = TOKENS.write().map_err(|_| ApiTokenDenied::Invalid)?;
+        let record = guard
+            .iter_mut()
+            .find(|r| r.token_hash == hash)
+            .filter(|r| r.expires_at.is_none_or(|at| at > now))
+            .ok_or(ApiTokenDenied::Invalid)?;
+        let scope = required_scope(method, path).ok_or(ApiTokenDenied::MissingScope)?;
+        if !record.allows(scope) {
+            return Err(ApiTokenDenied::MissingScope);
+        }
+        let stale = record
+            .last_used_at
+            .is_none_or(|at| now.saturating_sub(at) >= LAST_USED_PERSIST_INTERVAL_MILLIS);
+        if stale {
+            record.last_used_at = Some(now);
+        }
+        (
+            ApiTokenPrincipal {
+                id: record.id.clone(),
+                name: record.name.clone(),
+            },
+            stale,
+        )
+    };
+
+    if persist_last_used {
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(err) = update_tokens(&state.studio_db, |_| Ok(())).await {
+                tracing::warn!(error = %err, "Failed to persist API token usage");
+            }
+        });
+    }
+    Ok(principal)
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTokenView {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+    pub last_used_at: Option<u64>,
+}
+
+impl From<&ApiTokenRecord> for ApiTokenView {
+    fn from(record: &ApiTokenRecord) -> Self {
+        Self {
+            id: record.id.clone(),
+            name: record.name.clone(),
+            scopes: record.scopes.clone(),
+            created_at: record.created_at,
+            expires_at: record.expires_at,
+            last_used_at: record.last_used_at,
+        }
+    }
+}
+
+pub(crate) async fn api_tokens_list() -> Json<Vec<ApiTokenView>> {
+    Json(snapshot().iter().map(ApiTokenView::from).collect())
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTokenCreateBody {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    /// Omit for a token that never expires.
+    pub expires_in_days: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTokenCreated {
+    /// Only returned here; the server keeps a hash.
+    pub token: String,
+    #[serde(flatten)]
+    pub info: ApiTokenView,
+}
+
+pub(crate) async fn api_tokens_create(
+    State(state): State<Arc<crate::AppState>>,
+    Json(body): Json<ApiTokenCreateBody>,
+) -> ApiResult<(StatusCode, Json<ApiTokenCreated>)> {
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(AppError::bad_request("name is required"));
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(AppError::bad_request("name is too long"));
+    }
+    let mut scopes: Vec<ApiScope> = Vec::new();
+    for scope in body.scopes {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+    if scopes.is_empty() {
+        return Err(AppError::bad_request("at least one scope is required"));
+    }
+    if body.expires_in_days == Some(0) {
+        return Err(AppError::bad_request("expiresInDays must be at least 1"));
+    }
+
+    let token = format!("{API_TOKEN_PREFIX}{}", crate::issue_token());
+    let info = update_tokens(&state.studio_db, |tokens| {
+        if tokens.len() >= MAX_TOKENS {
+            return Err(AppError::conflict(
+                "Too many API tokens; revoke unused ones",
+            ));
+        }
+
+        let now = now_millis();
+        let record = ApiTokenRecord {
+            id: format!("tok_{}", uuid::Uuid::new_v4().simple()),
+            name: name.to_string(),
+            token_hash: hash_token(&token),
+            scopes,
+            created_at: now,
+            expires_at: body
+                .expires_in_days
+                .map(|days| now.saturating_add(days.saturating_mul(DAY_MILLIS))),
+            last_used_at: None,
+        };
+        let info = ApiTokenView::from(&record);
+        tokens.push(record);
+        Ok(info)
+    })
+    .await?;
+    tracing::info!(id = %info.id, name = %info.name, "API token created");
+
+    Ok((StatusCode::CREATED, Json(ApiTokenCreated { token, info })))
+}
+
+pub(crate) async fn api_tokens_delete(
+    State(state): State<Arc<crate::AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<StatusCode> {
+    update_tokens(&state.studio_db, |tokens| {
+        let before = tokens.len();
+        tokens.retain(|t| t.id != id);
+        if tokens.len() == before {
+            return Err(AppError::not_found(format!("API token '{id}' not found")));
+        }
+        Ok(())
+    })
+    .await?;
+    tracing::info!(id = %id, "API token revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_follow_route_groups() {
+        assert_eq!(
+            required_scope(&Method::GET, "/api/git/status"),
+            Some(ApiScope::GitRead)
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/api/git/commit"),
+            Some(ApiScope::GitWrite)
+        );
+        assert_eq!(
+            required_scope(&Method::GET, "/api/session/abc/message"),
+            Some(ApiScope::SessionsRead)
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/api/terminal/create"),
+            Some(ApiScope::Terminal)
+        );
+        assert_eq!(
+            required_scope(&Method::PUT, "/api/config/settings"),
+            Some(ApiScope::SettingsWrite)
+        );
+        assert_eq!(
+            required_scope(&Method::GET, "/api/fs/read"),
+            Some(ApiScope::All)
+        );
+        assert_eq!(required_scope(&Method::POST, "/api/auth/tokens"), None);
+        assert_eq!(
+            required_scope(&Method::POST, "/api/admin/plugins/install"),
+            None
+        );
+        assert_eq!(required_scope(&Method::PUT, "/api/admin/log-level"), None);
+        assert_eq!(
+            required_scope(&Method::GET, "/api/adminx"),
+            Some(ApiScope::All)
+        );
+        assert_eq!(
+            required_scope(&Method::GET, "/api/gitx"),
+            Some(ApiScope::All)
+        );
+    }
+
+    #[test]
+    fn wildcard_scope_allows_everything_it_can() {
+        let record = ApiTokenRecord {
+            id: "t".to_string(),
+            name: "ci".to_string(),
+            token_hash: hash_token("ocs_x"),
+            scopes: vec![ApiScope::All],
+            created_at: 0,
+            expires_at: None,
+            last_used_at: None,
+        };
+        assert!(record.allows(ApiScope::Terminal));
+        assert!(record.allows(ApiScope::GitWrite));
+
+        let read_only = ApiTokenRecord {
+            scopes: vec![ApiScope::GitRead],
+            ..record
+        };
+        assert!(read_only.allows(ApiScope::GitRead));
+        assert!(!read_only.allows(ApiScope::GitWrite));
+    }
+
+    #[test]
+    fn scope_names_round_trip() {
+        let scopes: Vec<ApiScope> =
+            serde_json::from_value(serde_json::json!(["git:read", "terminal", "*"])).unwrap();
+        assert_eq!(
+            scopes,
+            vec![ApiScope::GitRead, ApiScope::Terminal, ApiScope::All]
+        );
+        assert!(serde_json::from_value::<ApiScope>(serde_json::json!("root")).is_err());
+    }
+}
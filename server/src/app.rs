@@ -1,19 +1,20 @@
 use std::collections::HashSet;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use axum::{
     Json, Router,
     body::to_bytes,
-    extract::Query,
+    extract::{DefaultBodyLimit, Query},
     http::{HeaderValue, Method, header},
     middleware,
     response::{Html, IntoResponse},
-    routing::{any, get, post},
+    routing::{MethodRouter, any, get, post, put},
 };
 use axum_extra::extract::cookie::SameSite;
 use futures_util::stream::{self as futures_stream, StreamExt as _};
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use tokio::sync::RwLock;
@@ -21,7 +22,6 @@ use tokio::time::{Duration, timeout};
 use tower_http::{
     cors::{AllowOrigin, Any, CorsLayer},
     limit::RequestBodyLimitLayer,
-    services::{ServeDir, ServeFile},
     trace::TraceLayer,
 };
 use url::Url;
@@ -47,9 +47,9 @@ pub(crate) struct AppState {
     pub(crate) settings: Arc<RwLock<crate::settings::Settings>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-struct HealthResponse {
+pub(crate) struct HealthResponse {
     status: &'static str,
     timestamp: String,
     open_code_port: Option<u16>,
@@ -77,9 +77,9 @@ async fn health(
     Json(resp)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-struct DiagnosticsQuery {
+pub(crate) struct DiagnosticsQuery {
     directory: Option<String>,
 }
 
@@ -464,6 +464,9 @@ async fn hydrate_runtime_session_directory_mappings(
                 include_children: None,
                 ids: Some(ids_csv),
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -558,6 +561,7 @@ async fn reconcile_runtime_attention_from_opencode(
     let tasks = futures_stream::iter(directory_list.into_iter().map(|directory| {
         let bridge = bridge.clone();
         async move {
+            let bridge = directory_bridge(state, &directory, bridge).await;
             let (permissions, questions) = tokio::join!(
                 fetch_attention_session_ids(&bridge, "/permission", Some(&directory)),
                 fetch_attention_session_ids(&bridge, "/question", Some(&directory)),
@@ -596,7 +600,21 @@ async fn reconcile_runtime_attention_from_opencode(
     all_attention
 }
 
-async fn reconcile_runtime_status_from_opencode(state: &Arc<AppState>) {
+/// The bridge of the pooled instance already serving `directory`, or
+/// `primary`. Reconciliation never starts pooled instances.
+async fn directory_bridge(
+    state: &AppState,
+    directory: &str,
+    primary: crate::opencode::OpenCodeBridge,
+) -> crate::opencode::OpenCodeBridge {
+    let upstream = state.opencode.running_for_directory(Some(directory));
+    if Arc::ptr_eq(&upstream, &state.opencode) {
+        return primary;
+    }
+    upstream.bridge().await.unwrap_or(primary)
+}
+
+pub(crate) async fn reconcile_runtime_status_from_opencode(state: &Arc<AppState>) {
     let oc = state.opencode.status().await;
     if oc.restarting || !oc.ready {
         return;
@@ -632,6 +650,7 @@ async fn reconcile_runtime_status_from_opencode(state: &Arc<AppState>) {
         let tasks = futures_stream::iter(status_directories.into_iter().map(|directory| {
             let bridge = bridge.clone();
             async move {
+                let bridge = directory_bridge(state, &directory, bridge).await;
                 let payload = fetch_session_status_map(&bridge, Some(&directory)).await;
                 (directory, payload)
             }
@@ -762,8 +781,943 @@ async fn session_activity(
     Json(state.session_activity.snapshot_json())
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BusySessionEntry {
+    #[serde(rename = "sessionID")]
+    session_id: String,
+    title: Option<String>,
+    directory: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BusyJobEntry {
+    kind: &'static str,
+    directory: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BusyResponse {
+    busy: bool,
+    sessions: Vec<BusySessionEntry>,
+    jobs: Vec<BusyJobEntry>,
+}
+
+/// Work that would be interrupted by stopping the server; the desktop shell
+/// polls this before quitting.
+async fn opencode_studio_busy(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+) -> impl IntoResponse {
+    reconcile_runtime_status_from_opencode(&state).await;
+
+    let index = &state.directory_session_index;
+    let sessions: Vec<BusySessionEntry> = index
+        .busy_session_ids()
+        .into_iter()
+        .map(|session_id| {
+            let summary = index.summary(&session_id);
+            BusySessionEntry {
+                title: summary
+                    .as_ref()
+                    .map(|s| s.title.trim().to_string())
+                    .filter(|t| !t.is_empty()),
+                directory: summary
+                    .map(|s| s.directory_path)
+                    .or_else(|| index.directory_for_session(&session_id)),
+                session_id,
+            }
+        })
+        .collect();
+    let jobs: Vec<BusyJobEntry> = crate::git::busy_repo_directories()
+        .into_iter()
+        .map(|directory| BusyJobEntry {
+            kind: "git",
+            directory,
+        })
+        .collect();
+
+    Json(BusyResponse {
+        busy: !sessions.is_empty() || !jobs.is_empty(),
+        sessions,
+        jobs,
+    })
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OpenCodeInstancesResponse {
+    pool_max: usize,
+    instances: Vec<crate::opencode::OpenCodeInstanceStatus>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct OpenCodeInstanceQuery {
+    directory: Option<String>,
+}
+
+async fn opencode_instances_response(state: &AppState) -> Json<OpenCodeInstancesResponse> {
+    Json(OpenCodeInstancesResponse {
+        pool_max: state.opencode.pool_max(),
+        instances: state.opencode.instances().await,
+    })
+}
+
+/// The primary OpenCode instance and any per-project pooled instances.
+async fn opencode_instances_list(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+) -> impl IntoResponse {
+    opencode_instances_response(&state).await
+}
+
+/// Restart the pooled instance for `directory`, or the primary one when no
+/// directory is given.
+async fn opencode_instance_restart(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Query(query): Query<OpenCodeInstanceQuery>,
+) -> crate::ApiResult<Json<OpenCodeInstancesResponse>> {
+    let directory = query
+        .directory
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty());
+    let instance = match directory {
+        Some(directory) => state.opencode.pool_instance(directory).ok_or_else(|| {
+            crate::AppError::not_found("No OpenCode instance is running for this directory")
+        })?,
+        None => state.opencode.clone(),
+    };
+    instance
+        .restart_instance("manual instance restart")
+        .await
+        .map_err(crate::AppError::bad_gateway)?;
+    Ok(opencode_instances_response(&state).await)
+}
+
+/// Stop a pooled instance; later requests for its directory start a fresh one.
+async fn opencode_instance_stop(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    Query(query): Query<OpenCodeInstanceQuery>,
+) -> crate::ApiResult<Json<OpenCodeInstancesResponse>> {
+    let directory = query
+        .directory
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .ok_or_else(|| crate::AppError::bad_request("directory is required"))?;
+    if !state.opencode.stop_pool_instance(directory).await {
+        return Err(crate::AppError::not_found(
+            "No OpenCode instance is running for this directory",
+        ));
+    }
+    Ok(opencode_instances_response(&state).await)
+}
+
 // Note: update/install is intentionally not exposed via the UI.
 
+/// Run the server until it exits on its own or the shutdown grace period
+/// after a signal runs out.
+async fn serve_until_drained(
+    server: impl std::future::Future<Output = std::io::Result<()>>,
+    grace_secs: u64,
+) {
+    tokio::select! {
+        res = server => res.expect("server run"),
+        _ = crate::graceful_shutdown::drain_deadline(Duration::from_secs(grace_secs)) => {
+            tracing::warn!("Shutdown grace period elapsed; closing remaining connections");
+        }
+    }
+}
+
+/// The `/api` router plus the path of every route added to it, which the
+/// OpenAPI tests compare with the document.
+pub(crate) struct ApiRoutes {
+    pub(crate) router: Router<Arc<AppState>>,
+    pub(crate) paths: Vec<&'static str>,
+}
+
+impl ApiRoutes {
+    fn route(mut self, path: &'static str, method_router: MethodRouter<Arc<AppState>>) -> Self {
+        self.router = self.router.route(path, method_router);
+        self.paths.push(path);
+        self
+    }
+}
+
+/// Every route under `/api`, without the middleware `run` wraps them in.
+pub(crate) fn api_routes() -> ApiRoutes {
+    ApiRoutes {
+        router: Router::new(),
+        paths: Vec::new(),
+    }
+    // Providers
+    .route(
+        "/provider/{provider_id}/source",
+        get(crate::providers::provider_source_get),
+    )
+    .route(
+        "/provider/{provider_id}/override/ping",
+        post(crate::providers::provider_override_ping_post),
+    )
+    .route(
+        "/provider/{provider_id}/validate",
+        post(crate::providers::provider_validate_post),
+    )
+    .route(
+        "/provider/models/refresh",
+        post(crate::model_catalog::model_catalog_refresh_post),
+    )
+    .route(
+        "/provider/env/check",
+        post(crate::providers::env_check_post),
+    )
+    .route("/discovery/peers", get(crate::discovery::discovery_peers))
+    .route("/openapi.json", get(crate::openapi::openapi_json))
+    .route("/docs", get(crate::openapi::openapi_docs))
+    // Config / Skills
+    .route(
+        "/config/settings",
+        get(crate::config::config_settings_get).put(crate::config::config_settings_put),
+    )
+    .route(
+        "/config/settings/meta",
+        get(crate::config::config_settings_meta_get),
+    )
+    .route(
+        "/config/settings/events",
+        get(crate::settings_events::config_settings_events),
+    )
+    .route(
+        "/config/providers",
+        get(crate::model_catalog::config_providers_get),
+    )
+    .route(
+        "/config/opencode",
+        get(crate::config::config_opencode_get)
+            .put(crate::config::config_opencode_put)
+            .patch(crate::config::config_opencode_patch),
+    )
+    .route("/plugins", get(crate::plugin_runtime::plugins_list_get))
+    .route(
+        "/plugins/{plugin_id}/manifest",
+        get(crate::plugin_runtime::plugin_manifest_get),
+    )
+    .route(
+        "/plugins/{plugin_id}/action",
+        post(crate::plugin_runtime::plugin_action_post),
+    )
+    .route(
+        "/plugins/{plugin_id}/events",
+        get(crate::plugin_runtime::plugin_events_get),
+    )
+    .route(
+        "/plugins/{plugin_id}/assets/{*asset_path}",
+        get(crate::plugin_runtime::plugin_asset_get),
+    )
+    .route(
+        "/plugins/{plugin_id}/routes/{*route_path}",
+        any(crate::plugin_runtime::plugin_route_handler),
+    )
+    .route("/config/reload", post(crate::config::config_reload_post))
+    .route(
+        "/admin/self-update",
+        get(crate::self_update::self_update_get).post(crate::self_update::self_update_post),
+    )
+    .route(
+        "/admin/plugins",
+        get(crate::plugin_runtime::admin_plugins_get),
+    )
+    .route(
+        "/admin/plugins/installed",
+        get(crate::plugin_install::installed_plugins_get),
+    )
+    .route(
+        "/admin/plugins/install",
+        post(crate::plugin_install::plugin_install_post),
+    )
+    .route(
+        "/admin/plugins/installed/{plugin_id}",
+        axum::routing::delete(crate::plugin_install::plugin_uninstall_delete),
+    )
+    .route(
+        "/admin/plugins/installed/{plugin_id}/update",
+        post(crate::plugin_install::plugin_update_post),
+    )
+    .route(
+        "/admin/diagnostics/tls",
+        get(crate::tls_roots::tls_probe_get),
+    )
+    .route(
+        "/admin/log-level",
+        get(crate::log_level::log_level_get)
+            .put(crate::log_level::log_level_put)
+            .delete(crate::log_level::log_level_delete),
+    )
+    .route(
+        "/ui/terminal/state",
+        get(crate::terminal_ui_state::terminal_ui_state_get)
+            .put(crate::terminal_ui_state::terminal_ui_state_put),
+    )
+    .route(
+        "/ui/terminal/state/events",
+        get(crate::terminal_ui_state::terminal_ui_state_events),
+    )
+    .route(
+        "/memory",
+        get(crate::memory_snippets::memory_list).post(crate::memory_snippets::memory_create),
+    )
+    .route("/memory/export", get(crate::memory_snippets::memory_export))
+    .route(
+        "/memory/{id}",
+        get(crate::memory_snippets::memory_get)
+            .put(crate::memory_snippets::memory_update)
+            .delete(crate::memory_snippets::memory_delete),
+    )
+    .route(
+        "/quick-captures",
+        get(crate::quick_captures::quick_capture_list)
+            .post(crate::quick_captures::quick_capture_create),
+    )
+    .route(
+        "/quick-captures/{id}",
+        put(crate::quick_captures::quick_capture_update)
+            .delete(crate::quick_captures::quick_capture_delete),
+    )
+    .route(
+        "/quick-captures/{id}/convert",
+        post(crate::quick_captures::quick_capture_convert),
+    )
+    .route(
+        "/prompts",
+        get(crate::prompt_templates::prompt_list).post(crate::prompt_templates::prompt_create),
+    )
+    .route(
+        "/prompts/{id}",
+        get(crate::prompt_templates::prompt_get)
+            .put(crate::prompt_templates::prompt_update)
+            .delete(crate::prompt_templates::prompt_delete),
+    )
+    .route(
+        "/prompts/{id}/render",
+        post(crate::prompt_templates::prompt_render),
+    )
+    .route(
+        "/slash-commands",
+        get(crate::slash_commands::slash_commands_list),
+    )
+    // SSE bridge
+    .route(
+        "/event",
+        get(crate::opencode_proxy::proxy_opencode_sse_event),
+    )
+    .route(
+        "/global/event",
+        get(crate::global_sse_hub::global_event_sse),
+    )
+    .route(
+        "/ws/events",
+        get(crate::opencode_proxy::proxy_opencode_ws_events),
+    )
+    .route("/global/ws", get(crate::global_sse_hub::global_event_ws))
+    .route(
+        "/chat-sidebar/state",
+        get(crate::chat_sidebar::chat_sidebar_state),
+    )
+    .route(
+        "/chat-sidebar/commands",
+        post(crate::chat_sidebar::chat_sidebar_commands_post),
+    )
+    .route(
+        "/chat-sidebar/devices",
+        get(crate::chat_sidebar_devices::chat_sidebar_devices_list)
+            .delete(crate::chat_sidebar_devices::chat_sidebar_devices_clear),
+    )
+    .route(
+        "/chat-sidebar/devices/{device_id}",
+        axum::routing::delete(crate::chat_sidebar_devices::chat_sidebar_device_delete),
+    )
+    .route(
+        "/chat-sidebar/search",
+        get(crate::chat_sidebar::chat_sidebar_session_search),
+    )
+    .route(
+        "/chat-sidebar/footer",
+        get(crate::chat_sidebar::chat_sidebar_footer_get),
+    )
+    .route(
+        "/sessions/summaries",
+        get(crate::chat_sidebar::sessions_summaries_get),
+    )
+    .route(
+        "/sessions/recent",
+        get(crate::chat_sidebar::sessions_recent_get),
+    )
+    .route("/directories", get(crate::chat_sidebar::directories_get))
+    .route(
+        "/directories/{directory_id}/sessions",
+        get(crate::chat_sidebar::directory_sessions_by_id_get),
+    )
+    .route(
+        "/workspace/preview",
+        get(crate::workspace_preview::workspace_preview_get),
+    )
+    .route(
+        "/workspace/preview-url",
+        get(crate::workspace_preview::workspace_preview_url_get),
+    )
+    .route(
+        "/workspace/preview/proxy",
+        get(crate::workspace_preview::workspace_preview_proxy_get),
+    )
+    .route(
+        "/workspace/preview/sessions",
+        get(crate::workspace_preview::workspace_preview_sessions_get)
+            .post(crate::workspace_preview::workspace_preview_sessions_post),
+    )
+    .route(
+        "/workspace/preview/sessions/{id}",
+        get(crate::workspace_preview::workspace_preview_sessions_by_id_get)
+            .delete(crate::workspace_preview::workspace_preview_sessions_delete)
+            .put(crate::workspace_preview::workspace_preview_sessions_put),
+    )
+    .route(
+        "/workspace/preview/sessions/{id}/rename",
+        post(crate::workspace_preview::workspace_preview_sessions_rename_post),
+    )
+    .route(
+        "/workspace/preview/sessions/{id}/start",
+        post(crate::workspace_preview::workspace_preview_sessions_start_post),
+    )
+    .route(
+        "/workspace/preview/sessions/{id}/stop",
+        post(crate::workspace_preview::workspace_preview_sessions_stop_post),
+    )
+    .route(
+        "/workspace/preview/sessions/discover",
+        post(crate::workspace_preview::workspace_preview_sessions_discover_post),
+    )
+    .route(
+        "/workspace/preview/s/{id}",
+        any(crate::workspace_preview::workspace_preview_session_proxy_root),
+    )
+    .route(
+        "/workspace/preview/s/{id}/",
+        any(crate::workspace_preview::workspace_preview_session_proxy_root),
+    )
+    .route(
+        "/workspace/preview/s/{id}/{*path}",
+        any(crate::workspace_preview::workspace_preview_session_proxy_path),
+    )
+    // OpenCode Studio session list + filtered message history
+    .route(
+        "/session",
+        get(crate::opencode_session::session_list).post(crate::opencode_session::session_post),
+    )
+    .route(
+        "/session/status",
+        get(crate::opencode_proxy::session_status_get),
+    )
+    .route("/session/tags", get(crate::session_tags::session_tags_list))
+    .route(
+        "/session/archive",
+        post(crate::session_cleanup::session_archive_post),
+    )
+    .route(
+        "/session/cleanup",
+        post(crate::session_cleanup::session_cleanup_post),
+    )
+    .route(
+        "/session/import",
+        post(crate::session_import::session_import_post).layer(DefaultBodyLimit::max(
+            crate::session_import::MAX_IMPORT_BYTES,
+        )),
+    )
+    .route(
+        "/session/{session_id}/export",
+        get(crate::session_export::session_export_get),
+    )
+    .route(
+        "/session/{session_id}/tags",
+        get(crate::session_tags::session_tags_get).patch(crate::session_tags::session_tags_patch),
+    )
+    .route(
+        "/session/{session_id}/checkpoints",
+        get(crate::session_checkpoints::session_checkpoints_list),
+    )
+    .route(
+        "/session/{session_id}/checkpoints/{message_id}/diff",
+        get(crate::session_checkpoints::session_checkpoint_diff),
+    )
+    .route(
+        "/session/{session_id}/checkpoints/{message_id}/restore",
+        post(crate::session_checkpoints::session_checkpoint_restore),
+    )
+    .route(
+        "/session/{session_id}/fork",
+        post(crate::session_fork::session_fork_post),
+    )
+    .route(
+        "/session/{session_id}/summary",
+        get(crate::session_summary::session_summary_get)
+            .post(crate::session_summary::session_summary_post),
+    )
+    .route(
+        "/session/{session_id}/message",
+        get(crate::opencode_session::session_message_get)
+            .post(crate::opencode_proxy::session_message_post),
+    )
+    .route(
+        "/session/{session_id}/message/{message_id}/part/{part_id}",
+        get(crate::opencode_session::session_message_part_get),
+    )
+    .route(
+        "/session/{session_id}/message/{message_id}/part/{part_id}/detail",
+        get(crate::opencode_session::session_message_part_detail_get),
+    )
+    .route(
+        "/session/{session_id}/message/{message_id}/part/{part_id}/apply",
+        post(crate::session_part_apply::session_message_part_apply_post),
+    )
+    .route("/lsp", get(crate::opencode_proxy::lsp_list))
+    .route("/mcp", get(crate::opencode_proxy::mcp_status))
+    .route("/permission", get(crate::opencode_proxy::permission_list))
+    .route(
+        "/permission/grants",
+        get(crate::permission_grants::permission_grants_list)
+            .delete(crate::permission_grants::permission_grants_clear),
+    )
+    .route(
+        "/permission/grants/{id}",
+        axum::routing::delete(crate::permission_grants::permission_grant_delete),
+    )
+    .route("/question", get(crate::opencode_proxy::question_list))
+    .route(
+        "/opencode/capabilities",
+        get(crate::opencode_capabilities::opencode_capabilities_get),
+    )
+    // OpenCode Studio activity tracking
+    .route("/usage/summary", get(crate::usage::usage_summary))
+    .route("/usage/budgets", get(crate::budgets::budget_status_get))
+    .route(
+        "/usage/budgets/acknowledge",
+        post(crate::budgets::budget_acknowledge_post),
+    )
+    .route("/audit", get(crate::audit::audit_list))
+    .route("/session-activity", get(session_activity))
+    .route("/opencode-studio/busy", get(opencode_studio_busy))
+    .route(
+        "/opencode-studio/opencode-instances",
+        get(opencode_instances_list).delete(opencode_instance_stop),
+    )
+    .route(
+        "/opencode-studio/opencode-instances/restart",
+        post(opencode_instance_restart),
+    )
+    .route(
+        "/sessions/index/status",
+        get(crate::opencode_session::session_index_status),
+    )
+    .route(
+        "/opencode-studio/session-index",
+        get(crate::session_index_export::session_index_export),
+    )
+    // OpenCode Studio meta endpoints
+    .route(
+        "/opencode-studio/update-check",
+        get(crate::updates::update_check),
+    )
+    .route(
+        "/opencode-studio/session-locate",
+        get(crate::opencode_proxy::opencode_studio_session_locate),
+    )
+    .route(
+        "/opencode-studio/diagnostics",
+        get(opencode_studio_diagnostics),
+    )
+    .route(
+        "/markdown/render",
+        post(crate::markdown_render::markdown_render_post),
+    )
+    .route(
+        "/notifications/desktop",
+        get(crate::notifications::notifications_desktop_get),
+    )
+    .route(
+        "/notifications/test",
+        post(crate::notifications::notifications_test_post),
+    )
+    .route(
+        "/auth/tokens",
+        get(crate::api_tokens::api_tokens_list).post(crate::api_tokens::api_tokens_create),
+    )
+    .route(
+        "/auth/tokens/{id}",
+        axum::routing::delete(crate::api_tokens::api_tokens_delete),
+    )
+    .route(
+        "/auth/users",
+        get(crate::ui_users::ui_users_list).post(crate::ui_users::ui_users_create),
+    )
+    .route(
+        "/auth/users/{username}",
+        put(crate::ui_users::ui_users_update).delete(crate::ui_users::ui_users_delete),
+    )
+    // Filesystem
+    .route(
+        "/attachments/cache/stats",
+        get(crate::attachment_cache::attachment_cache_stats),
+    )
+    .route(
+        "/attachments/cache/clear",
+        post(crate::attachment_cache::attachment_cache_clear),
+    )
+    .route("/fs/home", get(crate::fs::fs_home))
+    .route("/fs/mkdir", post(crate::fs::fs_mkdir))
+    .route("/fs/read", get(crate::fs::fs_read))
+    .route("/fs/read-chunk", get(crate::fs::fs_read_chunk))
+    .route("/fs/raw", get(crate::fs::fs_raw))
+    .route("/fs/preview", get(crate::fs_preview::fs_preview))
+    .route("/fs/download", get(crate::fs::fs_download))
+    .route("/fs/write", post(crate::fs::fs_write))
+    .route(
+        "/fs/upload",
+        post(crate::fs::fs_upload).layer(RequestBodyLimitLayer::new(crate::fs::MAX_UPLOAD_BYTES)),
+    )
+    .route("/fs/delete", post(crate::fs::fs_delete))
+    .route("/fs/rename", post(crate::fs::fs_rename))
+    .route("/fs/list", get(crate::fs::fs_list))
+    .route("/fs/tree", get(crate::fs_tree::fs_tree))
+    .route(
+        "/fs/trash",
+        get(crate::fs_trash::trash_list).delete(crate::fs_trash::trash_purge_delete),
+    )
+    .route(
+        "/fs/trash/{id}",
+        axum::routing::delete(crate::fs_trash::trash_entry_delete),
+    )
+    .route(
+        "/fs/trash/{id}/restore",
+        post(crate::fs_trash::trash_restore_post),
+    )
+    .route("/fs/usage", get(crate::fs_usage::fs_usage))
+    .route("/fs/watch", get(crate::fs_watch::fs_watch_sse))
+    .route("/fs/search", get(crate::fs::fs_search))
+    .route("/fs/search-content", post(crate::fs::fs_content_search))
+    .route(
+        "/fs/search-content/stream",
+        get(crate::fs::fs_content_search_stream),
+    )
+    .route("/fs/replace-content", post(crate::fs::fs_content_replace))
+    .route(
+        "/tool-output/{archive_id}",
+        get(crate::tool_output_retention::tool_output_archive_get),
+    )
+    // Terminal
+    .route("/jobs", get(crate::jobs::jobs_get))
+    .route("/jobs/{job_id}", get(crate::jobs::job_get))
+    .route("/jobs/{job_id}/events", get(crate::jobs::job_events_get))
+    .route(
+        "/jobs/{job_id}/artifact",
+        get(crate::jobs::job_artifact_get),
+    )
+    .route("/jobs/{job_id}/cancel", post(crate::jobs::job_cancel_post))
+    .route("/tasks", get(crate::tasks::tasks_get))
+    .route("/tasks/run", post(crate::tasks::tasks_run_post))
+    .route("/tasks/runs", get(crate::tasks::task_runs_get))
+    .route("/tasks/runs/{run_id}", get(crate::tasks::task_run_get))
+    .route(
+        "/tasks/runs/{run_id}/events",
+        get(crate::tasks::task_run_events_get),
+    )
+    .route(
+        "/tasks/runs/{run_id}/cancel",
+        post(crate::tasks::task_run_cancel_post),
+    )
+    .route("/terminal/create", post(crate::terminal::terminal_create))
+    .route("/terminal/sessions", get(crate::terminal::terminal_list))
+    .route(
+        "/terminal/{session_id}/rename",
+        post(crate::terminal::terminal_rename),
+    )
+    .route(
+        "/terminal/{session_id}/stream",
+        get(crate::terminal::terminal_stream),
+    )
+    .route(
+        "/terminal/{session_id}/input",
+        post(crate::terminal::terminal_input),
+    )
+    .route(
+        "/terminal/{session_id}/resize",
+        post(crate::terminal::terminal_resize),
+    )
+    .route(
+        "/terminal/{session_id}",
+        get(crate::terminal::terminal_get).delete(crate::terminal::terminal_delete),
+    )
+    .route(
+        "/terminal/{session_id}/start",
+        post(crate::terminal::terminal_start),
+    )
+    .route(
+        "/terminal/{session_id}/stop",
+        post(crate::terminal::terminal_stop),
+    )
+    .route(
+        "/terminal/{session_id}/restart",
+        post(crate::terminal::terminal_restart),
+    )
+    .route(
+        "/terminal/{session_id}/upload",
+        post(crate::terminal_transfer::terminal_upload).layer(RequestBodyLimitLayer::new(
+            crate::terminal_transfer::MAX_TERMINAL_TRANSFER_BYTES,
+        )),
+    )
+    .route(
+        "/terminal/{session_id}/download",
+        get(crate::terminal_transfer::terminal_download),
+    )
+    // Git
+    .route(
+        "/secrets",
+        get(crate::secrets::secrets_list).post(crate::secrets::secrets_create),
+    )
+    .route(
+        "/secrets/{id}",
+        put(crate::secrets::secrets_update).delete(crate::secrets::secrets_delete),
+    )
+    .route("/forge", get(crate::forge::forge_info))
+    .route(
+        "/forge/token",
+        put(crate::forge::forge_token_put).delete(crate::forge::forge_token_delete),
+    )
+    .route(
+        "/forge/pulls",
+        get(crate::forge::forge_pulls_list).post(crate::forge::forge_pulls_create),
+    )
+    .route(
+        "/forge/pulls/{number}/comments",
+        get(crate::forge::forge_pull_comments),
+    )
+    .route("/git/check", get(crate::git::git_check))
+    .route("/git/repos", get(crate::git::git_repos))
+    .route("/git/safe-directory", post(crate::git::git_safe_directory))
+    .route("/git/init", post(crate::git::git_init))
+    .route("/git/clone", post(crate::git::git_clone))
+    .route(
+        "/git/gpg/enable-preset-passphrase",
+        post(crate::git::git_gpg_enable_preset_passphrase),
+    )
+    .route(
+        "/git/gpg/disable-signing",
+        post(crate::git::git_gpg_disable_signing),
+    )
+    .route(
+        "/git/gpg/set-signing-key",
+        post(crate::git::git_gpg_set_signing_key),
+    )
+    .route("/git/signatures", post(crate::git::git_signatures))
+    .route("/git/remote-info", get(crate::git::git_remote_info))
+    .route(
+        "/git/remotes",
+        post(crate::git::git_remote_add)
+            .put(crate::git::git_remote_rename)
+            .delete(crate::git::git_remote_remove),
+    )
+    .route("/git/remotes/set-url", post(crate::git::git_remote_set_url))
+    .route("/git/signing-info", get(crate::git::git_signing_info))
+    .route("/git/state", get(crate::git::git_state))
+    .route("/git/merge/abort", post(crate::git::git_merge_abort))
+    .route("/git/rebase/abort", post(crate::git::git_rebase_abort))
+    .route("/git/stash", get(crate::git::git_stash_list))
+    .route("/git/stash/show", get(crate::git::git_stash_show))
+    .route("/git/stash/push", post(crate::git::git_stash_push))
+    .route("/git/stash/apply", post(crate::git::git_stash_apply))
+    .route("/git/stash/pop", post(crate::git::git_stash_pop))
+    .route("/git/stash/drop", post(crate::git::git_stash_drop))
+    .route("/git/stash/drop-all", post(crate::git::git_stash_drop_all))
+    .route("/git/stash/branch", post(crate::git::git_stash_branch))
+    .route(
+        "/git/rebase/continue",
+        post(crate::git::git_rebase_continue),
+    )
+    .route("/git/rebase/skip", post(crate::git::git_rebase_skip))
+    .route(
+        "/git/cherry-pick/abort",
+        post(crate::git::git_cherry_pick_abort),
+    )
+    .route(
+        "/git/cherry-pick/continue",
+        post(crate::git::git_cherry_pick_continue),
+    )
+    .route(
+        "/git/cherry-pick/skip",
+        post(crate::git::git_cherry_pick_skip),
+    )
+    .route("/git/cherry-pick", post(crate::git::git_cherry_pick))
+    .route("/git/revert/abort", post(crate::git::git_revert_abort))
+    .route(
+        "/git/revert/continue",
+        post(crate::git::git_revert_continue),
+    )
+    .route("/git/revert/skip", post(crate::git::git_revert_skip))
+    .route("/git/revert-commit", post(crate::git::git_revert_commit))
+    .route("/git/merge", post(crate::git::git_merge))
+    .route("/git/rebase", post(crate::git::git_rebase))
+    .route(
+        "/git/rebase/interactive",
+        post(crate::git::git_rebase_interactive),
+    )
+    .route("/git/rebase/plan", get(crate::git::git_rebase_plan))
+    .route("/git/rebase/status", get(crate::git::git_rebase_status))
+    .route(
+        "/git/remote-branches",
+        get(crate::git::git_remote_branches_list),
+    )
+    // Git data
+    .route("/git/status", get(crate::git::git_status))
+    .route("/git/watch", get(crate::git::git_watch))
+    .route("/git/diff", get(crate::git::git_diff))
+    .route("/git/file-diff", get(crate::git::git_file_diff))
+    .route("/git/compare", get(crate::git::git_compare))
+    .route("/git/compare-any", post(crate::git::git_compare_any))
+    .route("/git/patch", post(crate::git::git_apply_patch))
+    .route("/git/hunks", get(crate::git::git_hunks))
+    .route("/git/hunks/stage", post(crate::git::git_stage_hunks))
+    .route("/git/hunks/unstage", post(crate::git::git_unstage_hunks))
+    .route("/git/lfs", get(crate::git::git_lfs_status))
+    .route("/git/lfs/install", post(crate::git::git_lfs_install))
+    .route("/git/lfs/track", post(crate::git::git_lfs_track))
+    .route("/git/lfs/locks", get(crate::git::git_lfs_locks))
+    .route("/git/lfs/lock", post(crate::git::git_lfs_lock))
+    .route("/git/lfs/unlock", post(crate::git::git_lfs_unlock))
+    .route("/git/lfs/migrate", post(crate::git::git_lfs_migrate))
+    .route("/git/size-advisor", get(crate::git::git_size_advisor))
+    .route("/git/submodules", get(crate::git::git_submodules))
+    .route("/git/submodules/add", post(crate::git::git_submodule_add))
+    .route("/git/submodules/init", post(crate::git::git_submodule_init))
+    .route(
+        "/git/submodules/update",
+        post(crate::git::git_submodule_update),
+    )
+    .route(
+        "/git/submodules/status",
+        get(crate::git::git_submodules_status),
+    )
+    .route(
+        "/git/submodules/batch",
+        post(crate::git::git_submodules_batch),
+    )
+    .route("/git/log", get(crate::git::git_log))
+    .route("/git/graph", get(crate::git::git_graph))
+    .route("/git/commit-diff", get(crate::git::git_commit_diff))
+    .route("/git/commit-files", get(crate::git::git_commit_files))
+    .route(
+        "/git/commit-file-diff",
+        get(crate::git::git_commit_file_diff),
+    )
+    .route(
+        "/git/commit-file-content",
+        get(crate::git::git_commit_file_content),
+    )
+    .route("/git/blame", get(crate::git::git_blame))
+    .route("/git/bisect", get(crate::git::git_bisect_status))
+    .route("/git/bisect/start", post(crate::git::git_bisect_start))
+    .route("/git/bisect/good", post(crate::git::git_bisect_good))
+    .route("/git/bisect/bad", post(crate::git::git_bisect_bad))
+    .route("/git/bisect/skip", post(crate::git::git_bisect_skip))
+    .route("/git/bisect/reset", post(crate::git::git_bisect_reset))
+    .route("/git/bisect/run", post(crate::git::git_bisect_run))
+    .route("/git/stage", post(crate::git::git_stage))
+    .route("/git/clean", post(crate::git::git_clean))
+    .route("/git/ignore", post(crate::git::git_ignore))
+    .route("/git/rename", post(crate::git::git_rename))
+    .route("/git/delete", post(crate::git::git_delete))
+    .route("/git/unstage", post(crate::git::git_unstage))
+    .route("/git/revert", post(crate::git::git_revert))
+    .route("/git/pull", post(crate::git::git_pull))
+    .route("/git/push", post(crate::git::git_push))
+    .route(
+        "/git/create-github-repo-and-push",
+        post(crate::git::git_create_github_repo_and_push),
+    )
+    .route("/git/fetch", post(crate::git::git_fetch))
+    .route("/git/commit", post(crate::git::git_commit))
+    .route("/git/commit-message", post(crate::git::git_commit_message))
+    .route("/git/locks", get(crate::git::git_locks_list))
+    .route("/git/locks/release", post(crate::git::git_locks_release))
+    .route(
+        "/git/lint",
+        get(crate::git::git_lint_get).post(crate::git::git_lint_run),
+    )
+    .route(
+        "/git/identity",
+        get(crate::git::git_identity_get).put(crate::git::git_identity_set),
+    )
+    .route("/git/undo-commit", post(crate::git::git_undo_commit))
+    .route("/git/reset", post(crate::git::git_reset_commit))
+    .route("/git/commit-template", get(crate::git::git_commit_template))
+    .route("/git/conflicts", get(crate::git::git_conflicts_list))
+    .route("/git/conflicts/file", get(crate::git::git_conflict_file))
+    .route(
+        "/git/conflicts/details",
+        get(crate::git::git_conflicts_details),
+    )
+    .route(
+        "/git/conflicts/resolve-batch",
+        post(crate::git::git_conflicts_resolve_batch),
+    )
+    .route(
+        "/git/conflicts/resolve",
+        post(crate::git::git_conflict_resolve),
+    )
+    .route(
+        "/git/branches",
+        get(crate::git::git_branches)
+            .post(crate::git::git_create_branch)
+            .delete(crate::git::git_delete_branch),
+    )
+    .route("/git/branches/compare", get(crate::git::git_branch_compare))
+    .route("/git/branches/rename", post(crate::git::git_rename_branch))
+    .route(
+        "/git/branches/delete-remote",
+        post(crate::git::git_delete_remote_branch),
+    )
+    .route("/git/tags", get(crate::git::git_tags_list))
+    .route("/git/tags", post(crate::git::git_tags_create))
+    .route(
+        "/git/tags",
+        axum::routing::delete(crate::git::git_tags_delete),
+    )
+    .route(
+        "/git/tags/delete-remote",
+        post(crate::git::git_tags_delete_remote),
+    )
+    .route("/git/checkout", post(crate::git::git_checkout))
+    .route(
+        "/git/checkout-detached",
+        post(crate::git::git_checkout_detached),
+    )
+    .route(
+        "/git/branches/create-from",
+        post(crate::git::git_create_branch_from),
+    )
+    .route(
+        "/git/worktrees",
+        get(crate::git::git_worktrees)
+            .post(crate::git::git_worktree_add)
+            .delete(crate::git::git_worktree_remove),
+    )
+    .route("/git/worktrees/prune", post(crate::git::git_worktree_prune))
+    .route(
+        "/git/worktrees/migrate",
+        post(crate::git::git_worktree_migrate),
+    )
+    // OpenCode REST reverse proxy fallback
+    .route("/{*path}", any(crate::opencode_proxy::proxy_opencode_rest))
+}
+
 pub(crate) async fn run(args: crate::Args) {
     fn normalize_origin_str(raw: &str) -> Option<String> {
         let trimmed = raw.trim();
@@ -894,10 +1848,19 @@ pub(crate) async fn run(args: crate::Args) {
             })
         });
 
-    let ui_auth = crate::ui_auth::init_ui_auth(args.ui_password.clone());
-    if crate::ui_auth::spawn_cleanup_sessions_task_if_enabled(&ui_auth) {
-        tracing::info!("UI password protection enabled");
-    }
+    crate::tls_roots::init(&args.ca_certs);
+    let tls_config = match (args.tls_cert.as_deref(), args.tls_key.as_deref()) {
+        (Some(cert), Some(key)) => {
+            match crate::tls_server::load_server_config(Path::new(cert), Path::new(key)) {
+                Ok(config) => Some(config),
+                Err(err) => {
+                    eprintln!("{err}");
+                    std::process::exit(2);
+                }
+            }
+        }
+        _ => None,
+    };
 
     let studio_db = Arc::new(match crate::studio_db::StudioDb::open().await {
         Ok(db) => db,
@@ -907,8 +1870,32 @@ pub(crate) async fn run(args: crate::Args) {
         }
     });
 
+    crate::api_tokens::load(studio_db.as_ref()).await;
+    crate::session_tags::load(studio_db.as_ref()).await;
+    let ui_users = crate::ui_users::load_users(studio_db.as_ref()).await;
+    let ui_user_count = ui_users.len();
+    let ui_auth = crate::ui_auth::init_ui_auth(args.ui_password.clone(), ui_users);
+    if crate::ui_auth::spawn_cleanup_sessions_task_if_enabled(&ui_auth) {
+        tracing::info!(users = ui_user_count, "UI authentication enabled");
+    }
+
+    crate::secrets::init(
+        studio_db.as_ref(),
+        args.secrets_key.as_deref(),
+        args.secrets_passphrase.as_deref(),
+    )
+    .await;
     let settings_value = crate::settings::init_settings(studio_db.as_ref()).await;
 
+    let replay_snapshot_path = crate::persistence_paths::sse_replay_snapshot_path();
+    let restored_frames = crate::global_sse_hub::restore_replay_buffer(&replay_snapshot_path);
+    if restored_frames > 0 {
+        tracing::info!(
+            frames = restored_frames,
+            "Restored SSE replay buffer from previous process"
+        );
+    }
+
     let configured_opencode_port = args.opencode_port;
     let should_bootstrap_opencode = configured_opencode_port.is_some() || !args.skip_opencode_start;
 
@@ -921,6 +1908,16 @@ pub(crate) async fn run(args: crate::Args) {
         Some(studio_base_url),
         ui_auth.clone(),
     ));
+    opencode.set_pool_max(args.opencode_pool_max);
+    opencode.set_pool_idle_timeout(Duration::from_secs(args.opencode_pool_idle_secs));
+    if args.opencode_pool_max > 0 {
+        opencode.spawn_pool_reaper();
+    }
+    opencode
+        .set_config_content(crate::opencode_config::managed_config_content(
+            &settings_value,
+        ))
+        .await;
 
     let terminal = Arc::new(crate::terminal::TerminalManager::new(studio_db.clone()).await);
     terminal.clone().spawn_cleanup_task();
@@ -928,6 +1925,9 @@ pub(crate) async fn run(args: crate::Args) {
     let attachment_cache = Arc::new(crate::attachment_cache::AttachmentCacheManager::new(
         studio_db.clone(),
     ));
+    attachment_cache.set_max_bytes(crate::attachment_cache::max_bytes_from_settings(
+        &settings_value,
+    ));
 
     let plugin_runtime = Arc::new(crate::plugin_runtime::PluginRuntime::new());
 
@@ -960,6 +1960,15 @@ pub(crate) async fn run(args: crate::Args) {
         settings: Arc::new(RwLock::new(settings_value)),
     });
 
+    crate::git::spawn_auto_fetch_task(state.clone());
+    crate::sidebar_sync::spawn_sidebar_sync_task(state.clone());
+    crate::opencode_session::configure_storage_scan(
+        args.session_scan_concurrency,
+        args.session_dir_cache_limit,
+        args.session_file_cache_limit,
+    );
+    crate::opencode_session::spawn_session_index_warmup(state.clone());
+
     if should_bootstrap_opencode {
         spawn_opencode_bootstrap_task(state.clone());
     } else {
@@ -985,431 +1994,70 @@ pub(crate) async fn run(args: crate::Args) {
         });
     }
 
-    let api_router = Router::new()
-        // Providers
-        .route(
-            "/provider/{provider_id}/source",
-            get(crate::providers::provider_source_get),
-        )
-        .route(
-            "/provider/env/check",
-            post(crate::providers::env_check_post),
-        )
-        // Config / Skills
-        .route(
-            "/config/settings",
-            get(crate::config::config_settings_get).put(crate::config::config_settings_put),
-        )
-        .route(
-            "/config/settings/events",
-            get(crate::settings_events::config_settings_events),
-        )
-        .route(
-            "/config/opencode",
-            get(crate::config::config_opencode_get).put(crate::config::config_opencode_put),
-        )
-        .route("/plugins", get(crate::plugin_runtime::plugins_list_get))
-        .route(
-            "/plugins/{plugin_id}/manifest",
-            get(crate::plugin_runtime::plugin_manifest_get),
-        )
-        .route(
-            "/plugins/{plugin_id}/action",
-            post(crate::plugin_runtime::plugin_action_post),
-        )
-        .route(
-            "/plugins/{plugin_id}/events",
-            get(crate::plugin_runtime::plugin_events_get),
-        )
-        .route(
-            "/plugins/{plugin_id}/assets/{*asset_path}",
-            get(crate::plugin_runtime::plugin_asset_get),
-        )
-        .route("/config/reload", post(crate::config::config_reload_post))
-        .route(
-            "/ui/terminal/state",
-            get(crate::terminal_ui_state::terminal_ui_state_get)
-                .put(crate::terminal_ui_state::terminal_ui_state_put),
-        )
-        .route(
-            "/ui/terminal/state/events",
-            get(crate::terminal_ui_state::terminal_ui_state_events),
-        )
-        // SSE bridge
-        .route(
-            "/event",
-            get(crate::opencode_proxy::proxy_opencode_sse_event),
-        )
-        .route(
-            "/global/event",
-            get(crate::global_sse_hub::global_event_sse),
-        )
-        .route("/global/ws", get(crate::global_sse_hub::global_event_ws))
-        .route(
-            "/chat-sidebar/state",
-            get(crate::chat_sidebar::chat_sidebar_state),
-        )
-        .route(
-            "/chat-sidebar/commands",
-            post(crate::chat_sidebar::chat_sidebar_commands_post),
-        )
-        .route(
-            "/chat-sidebar/search",
-            get(crate::chat_sidebar::chat_sidebar_session_search),
-        )
-        .route(
-            "/chat-sidebar/footer",
-            get(crate::chat_sidebar::chat_sidebar_footer_get),
-        )
-        .route(
-            "/sessions/summaries",
-            get(crate::chat_sidebar::sessions_summaries_get),
-        )
-        .route("/directories", get(crate::chat_sidebar::directories_get))
-        .route(
-            "/directories/{directory_id}/sessions",
-            get(crate::chat_sidebar::directory_sessions_by_id_get),
-        )
-        .route(
-            "/workspace/preview",
-            get(crate::workspace_preview::workspace_preview_get),
-        )
-        .route(
-            "/workspace/preview-url",
-            get(crate::workspace_preview::workspace_preview_url_get),
-        )
-        .route(
-            "/workspace/preview/proxy",
-            get(crate::workspace_preview::workspace_preview_proxy_get),
-        )
-        .route(
-            "/workspace/preview/sessions",
-            get(crate::workspace_preview::workspace_preview_sessions_get)
-                .post(crate::workspace_preview::workspace_preview_sessions_post),
-        )
-        .route(
-            "/workspace/preview/sessions/{id}",
-            get(crate::workspace_preview::workspace_preview_sessions_by_id_get)
-                .delete(crate::workspace_preview::workspace_preview_sessions_delete)
-                .put(crate::workspace_preview::workspace_preview_sessions_put),
-        )
-        .route(
-            "/workspace/preview/sessions/{id}/rename",
-            post(crate::workspace_preview::workspace_preview_sessions_rename_post),
-        )
-        .route(
-            "/workspace/preview/sessions/{id}/start",
-            post(crate::workspace_preview::workspace_preview_sessions_start_post),
-        )
-        .route(
-            "/workspace/preview/sessions/{id}/stop",
-            post(crate::workspace_preview::workspace_preview_sessions_stop_post),
-        )
-        .route(
-            "/workspace/preview/sessions/discover",
-            post(crate::workspace_preview::workspace_preview_sessions_discover_post),
-        )
-        .route(
-            "/workspace/preview/s/{id}",
-            any(crate::workspace_preview::workspace_preview_session_proxy_root),
-        )
-        .route(
-            "/workspace/preview/s/{id}/",
-            any(crate::workspace_preview::workspace_preview_session_proxy_root),
-        )
-        .route(
-            "/workspace/preview/s/{id}/{*path}",
-            any(crate::workspace_preview::workspace_preview_session_proxy_path),
-        )
-        // OpenCode Studio session list + filtered message history
-        .route(
-            "/session",
-            get(crate::opencode_session::session_list).post(crate::opencode_session::session_post),
-        )
-        .route(
-            "/session/status",
-            get(crate::opencode_proxy::session_status_get),
-        )
-        .route(
-            "/session/{session_id}/message",
-            get(crate::opencode_session::session_message_get)
-                .post(crate::opencode_proxy::session_message_post),
-        )
-        .route(
-            "/session/{session_id}/message/{message_id}/part/{part_id}",
-            get(crate::opencode_session::session_message_part_get),
-        )
-        .route("/lsp", get(crate::opencode_proxy::lsp_list))
-        .route("/mcp", get(crate::opencode_proxy::mcp_status))
-        .route("/permission", get(crate::opencode_proxy::permission_list))
-        .route("/question", get(crate::opencode_proxy::question_list))
-        // OpenCode Studio activity tracking
-        .route("/session-activity", get(session_activity))
-        // OpenCode Studio meta endpoints
-        .route(
-            "/opencode-studio/update-check",
-            get(crate::updates::update_check),
-        )
-        .route(
-            "/opencode-studio/session-locate",
-            get(crate::opencode_proxy::opencode_studio_session_locate),
-        )
-        .route(
-            "/opencode-studio/diagnostics",
-            get(opencode_studio_diagnostics),
-        )
-        // Filesystem
-        .route("/fs/home", get(crate::fs::fs_home))
-        .route("/fs/mkdir", post(crate::fs::fs_mkdir))
-        .route("/fs/read", get(crate::fs::fs_read))
-        .route("/fs/read-chunk", get(crate::fs::fs_read_chunk))
-        .route("/fs/raw", get(crate::fs::fs_raw))
-        .route("/fs/download", get(crate::fs::fs_download))
-        .route("/fs/write", post(crate::fs::fs_write))
-        .route(
-            "/fs/upload",
-            post(crate::fs::fs_upload)
-                .layer(RequestBodyLimitLayer::new(crate::fs::MAX_UPLOAD_BYTES)),
-        )
-        .route("/fs/delete", post(crate::fs::fs_delete))
-        .route("/fs/rename", post(crate::fs::fs_rename))
-        .route("/fs/list", get(crate::fs::fs_list))
-        .route("/fs/search", get(crate::fs::fs_search))
-        .route("/fs/search-content", post(crate::fs::fs_content_search))
-        .route("/fs/replace-content", post(crate::fs::fs_content_replace))
-        // Terminal
-        .route("/terminal/create", post(crate::terminal::terminal_create))
-        .route(
-            "/terminal/{session_id}/stream",
-            get(crate::terminal::terminal_stream),
-        )
-        .route(
-            "/terminal/{session_id}/input",
-            post(crate::terminal::terminal_input),
-        )
-        .route(
-            "/terminal/{session_id}/resize",
-            post(crate::terminal::terminal_resize),
-        )
-        .route(
-            "/terminal/{session_id}",
-            get(crate::terminal::terminal_get).delete(crate::terminal::terminal_delete),
-        )
-        .route(
-            "/terminal/{session_id}/start",
-            post(crate::terminal::terminal_start),
-        )
-        .route(
-            "/terminal/{session_id}/stop",
-            post(crate::terminal::terminal_stop),
-        )
-        .route(
-            "/terminal/{session_id}/restart",
-            post(crate::terminal::terminal_restart),
-        )
-        // Git
-        .route("/git/check", get(crate::git::git_check))
-        .route("/git/repos", get(crate::git::git_repos))
-        .route("/git/safe-directory", post(crate::git::git_safe_directory))
-        .route("/git/init", post(crate::git::git_init))
-        .route("/git/clone", post(crate::git::git_clone))
-        .route(
-            "/git/gpg/enable-preset-passphrase",
-            post(crate::git::git_gpg_enable_preset_passphrase),
-        )
-        .route(
-            "/git/gpg/disable-signing",
-            post(crate::git::git_gpg_disable_signing),
-        )
-        .route(
-            "/git/gpg/set-signing-key",
-            post(crate::git::git_gpg_set_signing_key),
-        )
-        .route("/git/remote-info", get(crate::git::git_remote_info))
-        .route(
-            "/git/remotes",
-            post(crate::git::git_remote_add)
-                .put(crate::git::git_remote_rename)
-                .delete(crate::git::git_remote_remove),
-        )
-        .route("/git/remotes/set-url", post(crate::git::git_remote_set_url))
-        .route("/git/signing-info", get(crate::git::git_signing_info))
-        .route("/git/state", get(crate::git::git_state))
-        .route("/git/merge/abort", post(crate::git::git_merge_abort))
-        .route("/git/rebase/abort", post(crate::git::git_rebase_abort))
-        .route("/git/stash", get(crate::git::git_stash_list))
-        .route("/git/stash/show", get(crate::git::git_stash_show))
-        .route("/git/stash/push", post(crate::git::git_stash_push))
-        .route("/git/stash/apply", post(crate::git::git_stash_apply))
-        .route("/git/stash/pop", post(crate::git::git_stash_pop))
-        .route("/git/stash/drop", post(crate::git::git_stash_drop))
-        .route("/git/stash/drop-all", post(crate::git::git_stash_drop_all))
-        .route("/git/stash/branch", post(crate::git::git_stash_branch))
-        .route(
-            "/git/rebase/continue",
-            post(crate::git::git_rebase_continue),
-        )
-        .route("/git/rebase/skip", post(crate::git::git_rebase_skip))
-        .route(
-            "/git/cherry-pick/abort",
-            post(crate::git::git_cherry_pick_abort),
-        )
-        .route(
-            "/git/cherry-pick/continue",
-            post(crate::git::git_cherry_pick_continue),
-        )
-        .route(
-            "/git/cherry-pick/skip",
-            post(crate::git::git_cherry_pick_skip),
-        )
-        .route("/git/cherry-pick", post(crate::git::git_cherry_pick))
-        .route("/git/revert/abort", post(crate::git::git_revert_abort))
-        .route(
-            "/git/revert/continue",
-            post(crate::git::git_revert_continue),
-        )
-        .route("/git/revert/skip", post(crate::git::git_revert_skip))
-        .route("/git/revert-commit", post(crate::git::git_revert_commit))
-        .route("/git/merge", post(crate::git::git_merge))
-        .route("/git/rebase", post(crate::git::git_rebase))
-        .route(
-            "/git/remote-branches",
-            get(crate::git::git_remote_branches_list),
-        )
-        // Git data
-        .route("/git/status", get(crate::git::git_status))
-        .route("/git/watch", get(crate::git::git_watch))
-        .route("/git/diff", get(crate::git::git_diff))
-        .route("/git/file-diff", get(crate::git::git_file_diff))
-        .route("/git/compare", get(crate::git::git_compare))
-        .route("/git/patch", post(crate::git::git_apply_patch))
-        .route("/git/lfs", get(crate::git::git_lfs_status))
-        .route("/git/lfs/install", post(crate::git::git_lfs_install))
-        .route("/git/lfs/track", post(crate::git::git_lfs_track))
-        .route("/git/lfs/locks", get(crate::git::git_lfs_locks))
-        .route("/git/lfs/lock", post(crate::git::git_lfs_lock))
-        .route("/git/lfs/unlock", post(crate::git::git_lfs_unlock))
-        .route("/git/submodules", get(crate::git::git_submodules))
-        .route("/git/submodules/add", post(crate::git::git_submodule_add))
-        .route("/git/submodules/init", post(crate::git::git_submodule_init))
-        .route(
-            "/git/submodules/update",
-            post(crate::git::git_submodule_update),
-        )
-        .route("/git/log", get(crate::git::git_log))
-        .route("/git/commit-diff", get(crate::git::git_commit_diff))
-        .route("/git/commit-files", get(crate::git::git_commit_files))
-        .route(
-            "/git/commit-file-diff",
-            get(crate::git::git_commit_file_diff),
-        )
-        .route(
-            "/git/commit-file-content",
-            get(crate::git::git_commit_file_content),
-        )
-        .route("/git/blame", get(crate::git::git_blame))
-        .route("/git/stage", post(crate::git::git_stage))
-        .route("/git/clean", post(crate::git::git_clean))
-        .route("/git/ignore", post(crate::git::git_ignore))
-        .route("/git/rename", post(crate::git::git_rename))
-        .route("/git/delete", post(crate::git::git_delete))
-        .route("/git/unstage", post(crate::git::git_unstage))
-        .route("/git/revert", post(crate::git::git_revert))
-        .route("/git/pull", post(crate::git::git_pull))
-        .route("/git/push", post(crate::git::git_push))
-        .route(
-            "/git/create-github-repo-and-push",
-            post(crate::git::git_create_github_repo_and_push),
-        )
-        .route("/git/fetch", post(crate::git::git_fetch))
-        .route("/git/commit", post(crate::git::git_commit))
-        .route("/git/undo-commit", post(crate::git::git_undo_commit))
-        .route("/git/reset", post(crate::git::git_reset_commit))
-        .route("/git/commit-template", get(crate::git::git_commit_template))
-        .route("/git/conflicts", get(crate::git::git_conflicts_list))
-        .route("/git/conflicts/file", get(crate::git::git_conflict_file))
-        .route(
-            "/git/conflicts/resolve",
-            post(crate::git::git_conflict_resolve),
-        )
-        .route(
-            "/git/branches",
-            get(crate::git::git_branches)
-                .post(crate::git::git_create_branch)
-                .delete(crate::git::git_delete_branch),
-        )
-        .route("/git/branches/rename", post(crate::git::git_rename_branch))
-        .route(
-            "/git/branches/delete-remote",
-            post(crate::git::git_delete_remote_branch),
-        )
-        .route("/git/tags", get(crate::git::git_tags_list))
-        .route("/git/tags", post(crate::git::git_tags_create))
-        .route(
-            "/git/tags",
-            axum::routing::delete(crate::git::git_tags_delete),
-        )
-        .route(
-            "/git/tags/delete-remote",
-            post(crate::git::git_tags_delete_remote),
-        )
-        .route("/git/checkout", post(crate::git::git_checkout))
-        .route(
-            "/git/checkout-detached",
-            post(crate::git::git_checkout_detached),
-        )
-        .route(
-            "/git/branches/create-from",
-            post(crate::git::git_create_branch_from),
-        )
-        .route(
-            "/git/worktrees",
-            get(crate::git::git_worktrees)
-                .post(crate::git::git_worktree_add)
-                .delete(crate::git::git_worktree_remove),
-        )
-        .route("/git/worktrees/prune", post(crate::git::git_worktree_prune))
-        .route(
-            "/git/worktrees/migrate",
-            post(crate::git::git_worktree_migrate),
-        )
-        // OpenCode REST reverse proxy fallback
-        .route("/{*path}", any(crate::opencode_proxy::proxy_opencode_rest))
+    if args.rate_limit.as_ref().is_some_and(|cfg| cfg.enabled) {
+        tracing::info!(target: "opencode_studio.rate_limit", "Rate limiting enabled");
+    }
+    if args
+        .rate_limit
+        .as_ref()
+        .is_some_and(|cfg| cfg.trust_proxy_headers)
+    {
+        tracing::warn!(
+            target: "opencode_studio.rate_limit",
+            "rate_limit.trust_proxy_headers is ignored; use --trusted-proxy instead"
+        );
+    }
+    let rate_limiter = Arc::new(crate::rate_limit::RateLimiter::from_config(
+        args.rate_limit.clone(),
+    ));
+
+    let api_router = api_routes()
+        .router
+        .layer(middleware::from_fn(crate::git::announce_status_change))
+        .layer(middleware::from_fn(crate::audit::record_destructive))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::budgets::enforce_prompt_budget,
+        ))
+        .layer(middleware::from_fn_with_state(
+            rate_limiter,
+            crate::rate_limit::enforce_rate_limit,
+        ))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             crate::ui_auth::require_ui_auth,
         ));
 
-    let (has_ui, asset_files, static_files) = match &ui_dir_path {
+    let ui_files = match &ui_dir_path {
         None => {
             tracing::info!("UI disabled (API-only mode)");
-            (false, None, None)
+            None
         }
         Some(dir) => {
-            let index_file = dir.join("index.html");
-            let has_ui = index_file.is_file();
+            let has_ui = dir.join("index.html").is_file();
             tracing::info!(
                 "UI dir resolved to {} (index.html exists: {})",
                 dir.to_string_lossy(),
                 has_ui
             );
-
-            // Serve Vite hashed assets with a real 404 when missing. The SPA fallback
-            // (index.html) is only appropriate for client routes, not JS/CSS chunks.
-            let asset_files = ServeDir::new(dir.join("assets"));
-            let static_files = ServeDir::new(dir).fallback(ServeFile::new(index_file));
-            (has_ui, Some(asset_files), Some(static_files))
+            has_ui.then(|| crate::ui_static::router(dir))
         }
     };
 
+    let opencode_for_exit = state.opencode.clone();
     let mut app = Router::new()
         .route("/health", get(health))
         .route(
             "/auth/session",
-            get(crate::ui_auth::auth_session_status).post(crate::ui_auth::auth_session_create),
+            get(crate::ui_auth::auth_session_status)
+                .post(crate::ui_auth::auth_session_create)
+                .delete(crate::ui_auth::auth_session_delete),
         )
         .nest("/api", api_router)
         .with_state(state)
+        .layer(middleware::from_fn(
+            crate::graceful_shutdown::reject_while_draining,
+        ))
         .layer(TraceLayer::new_for_http());
 
     if let Some(cors) = build_cors_layer(&normalized_cors_origins, args.cors_allow_all) {
@@ -1425,9 +2073,18 @@ pub(crate) async fn run(args: crate::Args) {
         app = app.layer(cors);
     }
 
-    app = if has_ui {
-        app.nest_service("/assets", asset_files.expect("assets service"))
-            .fallback_service(static_files.expect("static service"))
+    if let Some(compression) =
+        crate::compression::layer(!args.no_compression, args.compression_min_bytes)
+    {
+        app = app
+            .layer(middleware::from_fn(
+                crate::compression::mark_uncompressed_routes,
+            ))
+            .layer(compression);
+    }
+
+    app = if let Some(ui_files) = ui_files {
+        app.fallback_service(ui_files)
     } else {
         app.fallback(|| async {
             Html(
@@ -1436,15 +2093,93 @@ pub(crate) async fn run(args: crate::Args) {
         })
     };
 
+    if !args.route_rules.is_empty() {
+        tracing::info!(rules = args.route_rules.len(), "Route rules enabled");
+        // Router layers run after route selection, so rewrites must happen in an
+        // outer router that only forwards to the app.
+        app = Router::new()
+            .fallback_service(app)
+            .layer(middleware::from_fn_with_state(
+                Arc::new(args.route_rules.clone()),
+                crate::route_rules::apply_route_rules,
+            ));
+    }
+
+    if let Some(filter) = args.ip_filter.clone().filter(|f| f.is_active()) {
+        tracing::info!(
+            target: "opencode_studio.ip_filter",
+            localhost_bypass = filter.localhost_bypass,
+            "IP filter enabled"
+        );
+        // Outermost, so it runs before route rules and authentication.
+        app = app.layer(middleware::from_fn_with_state(
+            Arc::new(crate::ip_filter::IpFilter::new(
+                filter,
+                args.route_rules.clone(),
+            )),
+            crate::ip_filter::enforce_ip_filter,
+        ));
+    }
+
     let addr: SocketAddr = format!("{}:{}", args.host, args.port)
         .parse()
         .expect("valid bind address");
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .expect("bind listener");
+    let listener =
+        crate::graceful_shutdown::bind_listener(addr, args.reuse_port).expect("bind listener");
+
+    crate::self_update::configure(&args);
+    crate::fs_trash::configure(&args);
+    crate::client_addr::configure(&args);
+    if args.discovery {
+        crate::discovery::start(addr, tls_config.is_some(), args.instance_name.as_deref());
+    }
 
-    tracing::info!("OpenCode Studio listening on http://{}", addr);
-    axum::serve(listener, app).await.expect("server run");
+    if let Some(tls_config) = tls_config {
+        if let Some(redirect_port) = args.tls_redirect_port {
+            let redirect_addr = SocketAddr::new(addr.ip(), redirect_port);
+            crate::tls_server::spawn_https_redirect(redirect_addr, addr.port())
+                .await
+                .expect("bind HTTPS redirect listener");
+        }
+        tracing::info!("OpenCode Studio listening on https://{}", addr);
+        let listener = crate::tls_server::TlsListener::new(listener, tls_config);
+        let app = app.layer(middleware::map_request(crate::tls_server::expose_peer_addr));
+        serve_until_drained(
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<crate::tls_server::TlsPeer>(),
+            )
+            .with_graceful_shutdown(crate::graceful_shutdown::wait_for_shutdown_signal())
+            .into_future(),
+            args.shutdown_grace_secs,
+        )
+        .await;
+    } else {
+        tracing::info!("OpenCode Studio listening on http://{}", addr);
+        serve_until_drained(
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(crate::graceful_shutdown::wait_for_shutdown_signal())
+            .into_future(),
+            args.shutdown_grace_secs,
+        )
+        .await;
+    }
+
+    if let Err(err) = crate::global_sse_hub::persist_replay_buffer(&replay_snapshot_path) {
+        tracing::warn!(
+            path = %replay_snapshot_path.display(),
+            error = %err,
+            "Failed to persist SSE replay buffer"
+        );
+    }
+
+    if crate::self_update::restart_pending() {
+        opencode_for_exit.shutdown().await;
+        crate::self_update::exec_restart();
+    }
 }
 
 #[cfg(test)]
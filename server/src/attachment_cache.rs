@@ -1,15 +1,86 @@
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use axum::{Json, extract::State};
 use base64::Engine as _;
+use schemars::JsonSchema;
+use serde::Serialize;
 use sha2::{Digest as _, Sha256};
 
-use crate::studio_db;
+use crate::{ApiResult, AppError, studio_db};
+
+/// Settings key holding the cache size limit in bytes; `0` disables it.
+pub(crate) const ATTACHMENT_CACHE_MAX_BYTES_KEY: &str = "attachmentCacheMaxBytes";
+pub(crate) const DEFAULT_ATTACHMENT_CACHE_MAX_BYTES: u64 = 512 * 1024 * 1024;
+/// Upper bound accepted from settings.
+pub(crate) const MAX_ATTACHMENT_CACHE_LIMIT_BYTES: i64 = 64 * 1024 * 1024 * 1024;
+/// Eviction frees space down to this share of the limit so that every new
+/// entry does not trigger another eviction pass.
+const EVICTION_TARGET_PERCENT: u64 = 90;
+const PRESSURE_WINDOW_MS: i64 = 10 * 60 * 1000;
+/// Evictions within one window that count as high pressure.
+const PRESSURE_EVICTIONS: u64 = 50;
 
 #[derive(Clone)]
 pub(crate) struct AttachmentCacheManager {
     db: Arc<studio_db::StudioDb>,
+    max_bytes: Arc<AtomicU64>,
+    evictions: Arc<AtomicU64>,
+    pressure: Arc<Mutex<PressureWindow>>,
+}
+
+#[derive(Debug, Default)]
+struct PressureWindow {
+    started_at: i64,
+    evictions: u64,
+    warned: bool,
+    last_eviction_at: Option<i64>,
+}
+
+impl PressureWindow {
+    /// Count `evicted` entries; true the first time a window crosses the
+    /// pressure threshold.
+    fn record(&mut self, evicted: u64, now: i64) -> bool {
+        if now - self.started_at >= PRESSURE_WINDOW_MS {
+            self.started_at = now;
+            self.evictions = 0;
+            self.warned = false;
+        }
+        self.evictions += evicted;
+        self.last_eviction_at = Some(now);
+        if self.evictions >= PRESSURE_EVICTIONS && !self.warned {
+            self.warned = true;
+            return true;
+        }
+        false
+    }
+}
+
+pub(crate) fn max_bytes_from_settings(settings: &crate::settings::Settings) -> u64 {
+    settings
+        .extra
+        .get(ATTACHMENT_CACHE_MAX_BYTES_KEY)
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_ATTACHMENT_CACHE_MAX_BYTES)
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AttachmentCacheStats {
+    /// `0` means unlimited.
+    pub max_bytes: u64,
+    /// Size of the cached files (stored base64, so on disk ~4/3 of this).
+    pub total_bytes: u64,
+    pub blob_count: u64,
+    pub source_count: u64,
+    pub hit_count: u64,
+    /// Entries evicted since the server started.
+    pub evictions: u64,
+    pub last_eviction_at: Option<i64>,
+    /// Evictions in the current pressure window.
+    pub recent_evictions: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -21,7 +92,16 @@ struct SourceSnapshot {
 
 impl AttachmentCacheManager {
     pub(crate) fn new(db: Arc<studio_db::StudioDb>) -> Self {
-        Self { db }
+        Self {
+            db,
+            max_bytes: Arc::new(AtomicU64::new(DEFAULT_ATTACHMENT_CACHE_MAX_BYTES)),
+            evictions: Arc::new(AtomicU64::new(0)),
+            pressure: Arc::new(Mutex::new(PressureWindow::default())),
+        }
+    }
+
+    pub(crate) fn set_max_bytes(&self, max_bytes: u64) {
+        self.max_bytes.store(max_bytes, Ordering::Relaxed);
     }
 
     pub(crate) async fn data_url_for_file(
@@ -200,8 +280,197 @@ impl AttachmentCacheManager {
 
         tx.commit().await.map_err(|err| err.to_string())?;
 
+        if let Err(err) = self.enforce_limit(&digest).await {
+            tracing::warn!(
+                target: "opencode_studio.attachment_cache",
+                error = %err,
+                "Attachment cache eviction failed"
+            );
+        }
+        Ok(())
+    }
+
+    /// Evict least recently used blobs (never `keep`) once the cache is
+    /// over its limit.
+    async fn enforce_limit(&self, keep: &str) -> Result<(), String> {
+        let max_bytes = self.max_bytes.load(Ordering::Relaxed);
+        if max_bytes == 0 {
+            return Ok(());
+        }
+        let pool = self.db.pool();
+        let total = sqlx::query_scalar::<_, i64>(
+            "SELECT COALESCE(SUM(bytes_size), 0) FROM attachment_cache_blob_store",
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|err| err.to_string())?;
+        let total = u64::try_from(total).unwrap_or(0);
+        if total <= max_bytes {
+            return Ok(());
+        }
+
+        let target = max_bytes.saturating_mul(EVICTION_TARGET_PERCENT) / 100;
+        let candidates = sqlx::query_as::<_, (String, i64)>(
+            "SELECT digest_sha256, bytes_size FROM attachment_cache_blob_store\n             WHERE digest_sha256 != ?\n             ORDER BY last_accessed_at ASC",
+        )
+        .bind(keep)
+        .fetch_all(pool)
+        .await
+        .map_err(|err| err.to_string())?;
+
+        let mut remaining = total;
+        let mut evict = Vec::new();
+        for (digest, size) in candidates {
+            if remaining <= target {
+                break;
+            }
+            remaining = remaining.saturating_sub(u64::try_from(size).unwrap_or(0));
+            evict.push(digest);
+        }
+        if evict.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = pool.begin().await.map_err(|err| err.to_string())?;
+        for digest in &evict {
+            sqlx::query("DELETE FROM attachment_cache_source_index WHERE digest_sha256 = ?")
+                .bind(digest)
+                .execute(&mut *tx)
+                .await
+                .map_err(|err| err.to_string())?;
+            sqlx::query("DELETE FROM attachment_cache_blob_store WHERE digest_sha256 = ?")
+                .bind(digest)
+                .execute(&mut *tx)
+                .await
+                .map_err(|err| err.to_string())?;
+        }
+        tx.commit().await.map_err(|err| err.to_string())?;
+
+        let evicted = evict.len() as u64;
+        self.evictions.fetch_add(evicted, Ordering::Relaxed);
+        tracing::debug!(
+            target: "opencode_studio.attachment_cache",
+            evicted,
+            freed_bytes = total.saturating_sub(remaining),
+            "Evicted attachment cache entries"
+        );
+        let high_pressure = self
+            .pressure
+            .lock()
+            .map(|mut window| window.record(evicted, now_unix_ms()))
+            .unwrap_or(false);
+        if high_pressure {
+            tracing::warn!(
+                target: "opencode_studio.attachment_cache",
+                max_bytes,
+                "Attachment cache is evicting heavily; consider raising {ATTACHMENT_CACHE_MAX_BYTES_KEY}"
+            );
+            let payload = serde_json::json!({
+                "type": "attachment-cache.pressure",
+                "properties": {
+                    "maxBytes": max_bytes,
+                    "evictions": PRESSURE_EVICTIONS,
+                    "windowMs": PRESSURE_WINDOW_MS,
+                },
+            });
+            crate::global_sse_hub::publish_downstream_json(&payload.to_string());
+        }
         Ok(())
     }
+
+    pub(crate) async fn stats(&self) -> Result<AttachmentCacheStats, String> {
+        let pool = self.db.pool();
+        let (blob_count, total_bytes) = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT COUNT(*), COALESCE(SUM(bytes_size), 0) FROM attachment_cache_blob_store",
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|err| err.to_string())?;
+        let (source_count, hit_count) = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT COUNT(*), COALESCE(SUM(hit_count), 0) FROM attachment_cache_source_index",
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|err| err.to_string())?;
+        let (last_eviction_at, recent_evictions) = self
+            .pressure
+            .lock()
+            .map(|window| (window.last_eviction_at, window.evictions))
+            .unwrap_or_default();
+        Ok(AttachmentCacheStats {
+            max_bytes: self.max_bytes.load(Ordering::Relaxed),
+            total_bytes: u64::try_from(total_bytes).unwrap_or(0),
+            blob_count: u64::try_from(blob_count).unwrap_or(0),
+            source_count: u64::try_from(source_count).unwrap_or(0),
+            hit_count: u64::try_from(hit_count).unwrap_or(0),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            last_eviction_at,
+            recent_evictions,
+        })
+    }
+
+    /// Drop every cached attachment; returns `(blobs, bytes)` removed.
+    pub(crate) async fn clear(&self) -> Result<(u64, u64), String> {
+        let pool = self.db.pool();
+        let mut tx = pool.begin().await.map_err(|err| err.to_string())?;
+        let (blobs, bytes) = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT COUNT(*), COALESCE(SUM(bytes_size), 0) FROM attachment_cache_blob_store",
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|err| err.to_string())?;
+        sqlx::query("DELETE FROM attachment_cache_source_index")
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| err.to_string())?;
+        sqlx::query("DELETE FROM attachment_cache_blob_store")
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| err.to_string())?;
+        tx.commit().await.map_err(|err| err.to_string())?;
+        Ok((
+            u64::try_from(blobs).unwrap_or(0),
+            u64::try_from(bytes).unwrap_or(0),
+        ))
+    }
+}
+
+pub(crate) async fn attachment_cache_stats(
+    State(state): State<Arc<crate::AppState>>,
+) -> ApiResult<Json<AttachmentCacheStats>> {
+    state
+        .attachment_cache
+        .stats()
+        .await
+        .map(Json)
+        .map_err(AppError::internal)
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AttachmentCacheClearResponse {
+    pub removed_blobs: u64,
+    pub freed_bytes: u64,
+}
+
+pub(crate) async fn attachment_cache_clear(
+    State(state): State<Arc<crate::AppState>>,
+) -> ApiResult<Json<AttachmentCacheClearResponse>> {
+    let (removed_blobs, freed_bytes) = state
+        .attachment_cache
+        .clear()
+        .await
+        .map_err(AppError::internal)?;
+    tracing::info!(
+        target: "opencode_studio.attachment_cache",
+        removed_blobs,
+        freed_bytes,
+        "Attachment cache cleared"
+    );
+    Ok(Json(AttachmentCacheClearResponse {
+        removed_blobs,
+        freed_bytes,
+    }))
 }
 
 fn normalize_source_path(source: &Path) -> Result<PathBuf, String> {
@@ -340,4 +609,39 @@ mod tests {
             .expect("lookup");
         assert!(data_url.contains("Y2FjaGVkIHVwbG9hZCBieXRlcw=="));
     }
+
+    #[tokio::test]
+    async fn attachment_cache_evicts_least_recently_used_over_limit() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let studio_db = Arc::new(
+            crate::studio_db::StudioDb::open_at_path(tmp.path().join("studio.db"))
+                .await
+                .expect("open studio db"),
+        );
+        let cache = AttachmentCacheManager::new(studio_db);
+        cache.set_max_bytes(60);
+
+        let mut sources = Vec::new();
+        for name in ["a.bin", "b.bin", "c.bin"] {
+            let source = tmp.path().join(name);
+            tokio::fs::write(&source, name.repeat(5)).await.unwrap();
+            cache
+                .data_url_for_file(&source, "application/octet-stream")
+                .await
+                .expect("cache encode");
+            sources.push(source);
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let stats = cache.stats().await.expect("stats");
+        assert_eq!(stats.max_bytes, 60);
+        assert_eq!(stats.total_bytes, 50, "{stats:?}");
+        assert_eq!(stats.blob_count, 2);
+        assert_eq!(stats.evictions, 1);
+        assert!(stats.last_eviction_at.is_some());
+
+        let (blobs, bytes) = cache.clear().await.expect("clear");
+        assert_eq!((blobs, bytes), (2, 50));
+        assert_eq!(cache.stats().await.expect("stats").blob_count, 0);
+    }
 }
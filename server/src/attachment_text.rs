@@ -0,0 +1,353 @@
+use std::io::Read;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use base64::Engine as _;
+use regex::Regex;
+use serde_json::Value;
+
+/// Settings flag; off by default.
+pub(crate) const ATTACHMENT_TEXT_EXTRACTION_KEY: &str = "attachmentTextExtraction";
+pub(crate) const ATTACHMENT_TEXT_MAX_CHARS_KEY: &str = "attachmentTextExtractionMaxChars";
+pub(crate) const DEFAULT_ATTACHMENT_TEXT_MAX_CHARS: usize = 100_000;
+/// Upper bound accepted from settings.
+pub(crate) const MAX_ATTACHMENT_TEXT_LIMIT_CHARS: i64 = 2_000_000;
+
+/// Decompressed size cap for a single XML entry of an office document.
+const MAX_OFFICE_ENTRY_BYTES: u64 = 64 * 1024 * 1024;
+
+static XML_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+static BLANK_LINES: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\n{3,}").unwrap());
+
+/// Character budget for extracted text, or `None` when extraction is off.
+pub(crate) fn max_chars_from_settings(settings: &crate::settings::Settings) -> Option<usize> {
+    let enabled = settings
+        .extra
+        .get(ATTACHMENT_TEXT_EXTRACTION_KEY)
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if !enabled {
+        return None;
+    }
+    let max_chars = settings
+        .extra
+        .get(ATTACHMENT_TEXT_MAX_CHARS_KEY)
+        .and_then(|v| v.as_u64())
+        .map(|v| v as usize)
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_ATTACHMENT_TEXT_MAX_CHARS);
+    Some(max_chars)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DocumentKind {
+    Pdf,
+    Docx,
+    Pptx,
+    OpenDocument,
+}
+
+fn document_kind(mime: &str, filename: Option<&str>) -> Option<DocumentKind> {
+    let by_mime = match mime.trim().to_ascii_lowercase().as_str() {
+        "application/pdf" => Some(DocumentKind::Pdf),
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
+            Some(DocumentKind::Docx)
+        }
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation" => {
+            Some(DocumentKind::Pptx)
+        }
+        "application/vnd.oasis.opendocument.text"
+        | "application/vnd.oasis.opendocument.presentation" => Some(DocumentKind::OpenDocument),
+        _ => None,
+    };
+    by_mime.or_else(|| {
+        let ext = Path::new(filename?)
+            .extension()?
+            .to_str()?
+            .to_ascii_lowercase();
+        match ext.as_str() {
+            "pdf" => Some(DocumentKind::Pdf),
+            "docx" => Some(DocumentKind::Docx),
+            "pptx" => Some(DocumentKind::Pptx),
+            "odt" | "odp" => Some(DocumentKind::OpenDocument),
+            _ => None,
+        }
+    })
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Flatten document XML to text: paragraph ends become newlines, tabs stay
+/// tabs and every other tag is dropped.
+fn xml_to_text(xml: &str, paragraph_ends: &[&str], tabs: &[&str]) -> String {
+    let mut xml = xml.to_string();
+    for tag in paragraph_ends {
+        xml = xml.replace(tag, "\n");
+    }
+    for tag in tabs {
+        xml = xml.replace(tag, "\t");
+    }
+    unescape_xml(&XML_TAG.replace_all(&xml, ""))
+}
+
+fn read_zip_entry<R: Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<String, String> {
+    let entry = archive.by_name(name).map_err(|err| err.to_string())?;
+    let mut out = String::new();
+    entry
+        .take(MAX_OFFICE_ENTRY_BYTES)
+        .read_to_string(&mut out)
+        .map_err(|err| err.to_string())?;
+    Ok(out)
+}
+
+fn slide_number(name: &str) -> Option<u32> {
+    name.strip_prefix("ppt/slides/slide")?
+        .strip_suffix(".xml")?
+        .parse()
+        .ok()
+}
+
+fn extract_office_text(kind: DocumentKind, bytes: &[u8]) -> Result<String, String> {
+    let mut archive =
+        zip::ZipArchive::new(std::io::Cursor::new(bytes)).map_err(|err| err.to_string())?;
+    match kind {
+        DocumentKind::Docx => {
+            let xml = read_zip_entry(&mut archive, "word/document.xml")?;
+            Ok(xml_to_text(&xml, &["</w:p>", "<w:br/>"], &["<w:tab/>"]))
+        }
+        DocumentKind::Pptx => {
+            let mut slides: Vec<(u32, String)> = archive
+                .file_names()
+                .filter_map(|name| slide_number(name).map(|n| (n, name.to_string())))
+                .collect();
+            slides.sort();
+            let mut out = String::new();
+            for (number, name) in slides {
+                let xml = read_zip_entry(&mut archive, &name)?;
+                out.push_str(&format!("--- Slide {number} ---\n"));
+                out.push_str(&xml_to_text(&xml, &["</a:p>", "<a:br/>"], &[]));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+        DocumentKind::OpenDocument => {
+            let xml = read_zip_entry(&mut archive, "content.xml")?;
+            Ok(xml_to_text(
+                &xml,
+                &["</text:p>", "</text:h>", "<text:line-break/>"],
+                &["<text:tab/>"],
+            ))
+        }
+        DocumentKind::Pdf => Err("not an office document".to_string()),
+    }
+}
+
+fn extract_document_text(kind: DocumentKind, bytes: &[u8]) -> Result<String, String> {
+    let text = match kind {
+        // The PDF parser panics on some malformed inputs.
+        DocumentKind::Pdf => std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(bytes))
+            .map_err(|_| "PDF parser panicked".to_string())?
+            .map_err(|err| err.to_string())?,
+        _ => extract_office_text(kind, bytes)?,
+    };
+    let text = text.replace("\r\n", "\n");
+    Ok(BLANK_LINES.replace_all(text.trim(), "\n\n").into_owned())
+}
+
+/// Synthetic text part carrying the extracted text, cut at `max_chars`.
+fn text_part(filename: &str, text: &str, max_chars: usize) -> Value {
+    let total = text.chars().count();
+    let mut body: String = text.chars().take(max_chars).collect();
+    let header = if total > max_chars {
+        body.push_str("\n[…]");
+        format!(
+            "Text extracted from attachment \"{filename}\" (first {max_chars} of {total} characters):"
+        )
+    } else {
+        format!("Text extracted from attachment \"{filename}\":")
+    };
+    serde_json::json!({
+        "type": "text",
+        "synthetic": true,
+        "text": format!("{header}\n\n{body}"),
+    })
+}
+
+async fn text_part_from_bytes(
+    bytes: Vec<u8>,
+    kind: DocumentKind,
+    filename: String,
+    max_chars: usize,
+) -> Option<Value> {
+    let result = tokio::task::spawn_blocking(move || extract_document_text(kind, &bytes)).await;
+    match result {
+        Ok(Ok(text)) if !text.is_empty() => Some(text_part(&filename, &text, max_chars)),
+        Ok(Ok(_)) => None,
+        Ok(Err(err)) => {
+            tracing::warn!(filename = %filename, error = %err, "Attachment text extraction failed");
+            None
+        }
+        Err(err) => {
+            tracing::warn!(filename = %filename, error = %err, "Attachment text extraction aborted");
+            None
+        }
+    }
+}
+
+/// Text part for a document attachment on disk; `None` for other file types
+/// or when nothing could be extracted.
+pub(crate) async fn text_part_for_file(
+    abs: &Path,
+    mime: &str,
+    filename: Option<&str>,
+    max_chars: usize,
+) -> Option<Value> {
+    let name = filename
+        .map(str::to_string)
+        .or_else(|| abs.file_name()?.to_str().map(str::to_string))
+        .unwrap_or_default();
+    let kind = document_kind(mime, Some(&name))?;
+    let bytes = tokio::fs::read(abs).await.ok()?;
+    text_part_from_bytes(bytes, kind, name, max_chars).await
+}
+
+/// Same as [`text_part_for_file`] for a part that already carries a base64
+/// `data:` URL.
+pub(crate) async fn text_part_for_data_url(
+    url: &str,
+    mime: Option<&str>,
+    filename: Option<&str>,
+    max_chars: usize,
+) -> Option<Value> {
+    let (meta, payload) = url.strip_prefix("data:")?.split_once(',')?;
+    let encoded_mime = meta.strip_suffix(";base64")?;
+    let kind = document_kind(mime.unwrap_or(encoded_mime), filename)?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(payload.trim())
+        .ok()?;
+    let name = filename.unwrap_or("attachment").to_string();
+    text_part_from_bytes(bytes, kind, name, max_chars).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn zip_with(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buf);
+            for (name, body) in entries {
+                zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                    .unwrap();
+                zip.write_all(body.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        buf.into_inner()
+    }
+
+    #[test]
+    fn office_documents_flatten_to_paragraphs() {
+        let docx = zip_with(&[(
+            "word/document.xml",
+            r#"<w:document><w:body><w:p><w:r><w:t>Q3 &amp; Q4</w:t></w:r></w:p><w:p><w:r><w:t>a</w:t><w:tab/><w:t>b</w:t></w:r></w:p></w:body></w:document>"#,
+        )]);
+        assert_eq!(
+            extract_document_text(DocumentKind::Docx, &docx).unwrap(),
+            "Q3 & Q4\na\tb"
+        );
+
+        let pptx = zip_with(&[
+            (
+                "ppt/slides/slide10.xml",
+                "<p:sld><a:p><a:t>Last</a:t></a:p></p:sld>",
+            ),
+            (
+                "ppt/slides/slide2.xml",
+                "<p:sld><a:p><a:t>Second</a:t></a:p></p:sld>",
+            ),
+        ]);
+        let text = extract_document_text(DocumentKind::Pptx, &pptx).unwrap();
+        assert!(text.find("Second").unwrap() < text.find("Last").unwrap());
+        assert!(text.starts_with("--- Slide 2 ---"));
+
+        assert!(extract_document_text(DocumentKind::Pdf, b"not a pdf").is_err());
+    }
+
+    #[test]
+    fn document_kind_uses_mime_then_extension() {
+        assert_eq!(
+            document_kind("application/pdf", None),
+            Some(DocumentKind::Pdf)
+        );
+        assert_eq!(
+            document_kind("application/octet-stream", Some("Notes.ODT")),
+            Some(DocumentKind::OpenDocument)
+        );
+        assert_eq!(document_kind("image/png", Some("a.png")), None);
+    }
+
+    #[test]
+    fn long_text_is_truncated_with_a_note() {
+        let part = text_part("r.pdf", "abcdef", 4);
+        let text = part["text"].as_str().unwrap();
+        assert!(text.contains("first 4 of 6 characters"));
+        assert!(text.ends_with("abcd\n[…]"));
+        assert_eq!(part["synthetic"], true);
+    }
+
+    fn minimal_pdf(text: &str) -> Vec<u8> {
+        let stream = format!("BT /F1 18 Tf 20 100 Td ({text}) Tj ET");
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 300 144] /Contents 4 0 R \
+             /Resources << /Font << /F1 5 0 R >> >> >>"
+                .to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{stream}\nendstream",
+                stream.len()
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        ];
+        let mut out = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.push_str(&format!("{} 0 obj\n{object}\nendobj\n", i + 1));
+        }
+        let xref = out.len();
+        out.push_str(&format!(
+            "xref\n0 {}\n0000000000 65535 f \n",
+            objects.len() + 1
+        ));
+        for offset in offsets {
+            out.push_str(&format!("{offset:010} 00000 n \n"));
+        }
+        out.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        ));
+        out.into_bytes()
+    }
+
+    #[test]
+    fn pdf_text_layer_is_extracted() {
+        let pdf = minimal_pdf("Quarterly report");
+        assert_eq!(
+            extract_document_text(DocumentKind::Pdf, &pdf).unwrap(),
+            "Quarterly report"
+        );
+    }
+}
@@ -0,0 +1,462 @@
+use std::path::Path;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    body::Body,
+    extract::{OriginalUri, Query},
+    http::{Method, Request},
+    middleware::Next,
+    response::Response,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::io::AsyncWriteExt as _;
+use tokio::sync::Mutex as AsyncMutex;
+
+use crate::{ApiResult, AppError};
+
+const AUDIT_RETENTION_DAYS_ENV: &str = "OPENCODE_STUDIO_AUDIT_RETENTION_DAYS";
+const DEFAULT_RETENTION_DAYS: u64 = 90;
+const MAX_RECORDS: usize = 20_000;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Bodies above this size are not inspected for the record summary.
+const MAX_CAPTURED_BODY_BYTES: u64 = 64 * 1024;
+const DEFAULT_LIST_LIMIT: usize = 100;
+const MAX_LIST_LIMIT: usize = 1000;
+/// Body fields copied into the summary. Anything else (notably `auth`) is
+/// left out of the log.
+const SUMMARY_FIELDS: &[&str] = &[
+    "remote", "branch", "ref", "name", "force", "tags", "path", "paths", "files", "mode", "commit",
+    "hash", "target",
+];
+
+/// Bulk session cleanup criteria.
+const CLEANUP_SUMMARY_FIELDS: &[&str] = &[
+    "olderThanDays",
+    "directory",
+    "archivedOnly",
+    "includeFavorites",
+    "dryRun",
+];
+
+// Appends and prunes must not interleave.
+static AUDIT_FILE_LOCK: LazyLock<AsyncMutex<()>> = LazyLock::new(|| AsyncMutex::new(()));
+static LAST_PRUNE_AT: AtomicU64 = AtomicU64::new(0);
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn retention() -> Duration {
+    let days = std::env::var(AUDIT_RETENTION_DAYS_ENV)
+        .ok()
+        .and_then(|raw| raw.trim().parse::<u64>().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+    Duration::from_secs(days * 24 * 60 * 60)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AuditActor {
+    /// `user`, `token`, or `local` when UI auth is disabled.
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AuditRecord {
+    pub ts: u64,
+    pub actor: AuditActor,
+    pub action: String,
+    pub method: String,
+    pub route: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    /// Client address, through trusted proxies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    pub status: u16,
+    #[serde(default)]
+    pub summary: Map<String, Value>,
+}
+
+/// Name of the destructive action a request performs, if any. `path` is the
+/// full request path including `/api`.
+fn classify(method: &Method, path: &str) -> Option<&'static str> {
+    let action = match (method.as_str(), path) {
+        ("POST", "/api/git/push") => "git.push",
+        ("DELETE", "/api/git/branches") => "git.branch.delete",
+        ("POST", "/api/git/branches/delete-remote") => "git.branch.deleteRemote",
+        ("POST", "/api/git/reset") => "git.reset",
+        ("POST", "/api/git/clean") => "git.clean",
+        ("POST", "/api/fs/delete") => "fs.delete",
+        ("PUT", "/api/config/settings") => "settings.update",
+        ("POST", "/api/session/cleanup") => "session.cleanup",
+        ("POST", "/api/git/locks/release") => "git.lock.release",
+        ("DELETE", "/api/fs/trash") => "fs.trash.purge",
+        ("DELETE", p) if p.starts_with("/api/fs/trash/") => {
+            let id = &p["/api/fs/trash/".len()..];
+            if id.is_empty() || id.contains('/') {
+                return None;
+            }
+            "fs.trash.delete"
+        }
+        ("DELETE", p) => {
+            let id = p.strip_prefix("/api/session/")?;
+            if id.is_empty() || id.contains('/') {
+                return None;
+            }
+            "session.delete"
+        }
+        _ => return None,
+    };
+    Some(action)
+}
+
+fn summarize_body(action: &str, path: &str, body: Option<&Value>) -> Map<String, Value> {
+    let mut summary = Map::new();
+    if action == "session.delete"
+        && let Some(id) = path.strip_prefix("/api/session/")
+    {
+        summary.insert("sessionID".to_string(), Value::String(id.to_string()));
+    }
+    if action == "fs.trash.delete"
+        && let Some(id) = path.strip_prefix("/api/fs/trash/")
+    {
+        summary.insert("entry".to_string(), Value::String(id.to_string()));
+    }
+    let Some(Value::Object(body)) = body else {
+        return summary;
+    };
+    if action == "settings.update" {
+        let mut keys: Vec<Value> = body.keys().cloned().map(Value::String).collect();
+        keys.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+        summary.insert("keys".to_string(), Value::Array(keys));
+        return summary;
+    }
+    let fields = match action {
+        "session.cleanup" => CLEANUP_SUMMARY_FIELDS,
+        // The repository is in the body, not the query.
+        "git.lock.release" => &["directory"],
+        _ => SUMMARY_FIELDS,
+    };
+    for field in fields {
+        if let Some(value) = body.get(*field).filter(|v| !v.is_null()) {
+            summary.insert((*field).to_string(), value.clone());
+        }
+    }
+    summary
+}
+
+fn actor_for(req: &Request<Body>) -> AuditActor {
+    let extensions = req.extensions();
+    if let Some(token) = extensions.get::<crate::api_tokens::ApiTokenPrincipal>() {
+        return AuditActor {
+            kind: "token".to_string(),
+            name: Some(token.name.clone()),
+        };
+    }
+    if let Some(user) = extensions.get::<crate::ui_auth::UiPrincipal>() {
+        return AuditActor {
+            kind: "user".to_string(),
+            name: user.username.clone(),
+        };
+    }
+    AuditActor {
+        kind: "local".to_string(),
+        name: None,
+    }
+}
+
+pub(crate) fn directory_for(req: &Request<Body>) -> Option<String> {
+    let from_query = req.uri().query().and_then(|query| {
+        serde_urlencoded::from_str::<Vec<(String, String)>>(query)
+            .ok()?
+            .into_iter()
+            .find(|(key, _)| key == "directory")
+            .map(|(_, value)| value)
+    });
+    from_query
+        .or_else(|| {
+            req.headers()
+                .get("x-opencode-directory")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        })
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty())
+}
+
+/// Record destructive requests once they have been handled. Runs inside the
+/// auth layer so the acting principal is known.
+pub(crate) async fn record_destructive(req: Request<Body>, next: Next) -> Response {
+    let path = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.path().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let Some(action) = classify(req.method(), &path) else {
+        return next.run(req).await;
+    };
+
+    let actor = actor_for(&req);
+    let directory = directory_for(&req);
+    let method = req.method().to_string();
+    let client = crate::client_addr::ClientAddr::from_request(req.extensions(), req.headers());
+
+    let small_body = req
+        .headers()
+        .get(axum::http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .is_some_and(|len| len > 0 && len <= MAX_CAPTURED_BODY_BYTES);
+    let (req, body) = if small_body {
+        let (parts, body) = req.into_parts();
+        match axum::body::to_bytes(body, MAX_CAPTURED_BODY_BYTES as usize).await {
+            Ok(bytes) => {
+                let parsed = serde_json::from_slice::<Value>(&bytes).ok();
+                (Request::from_parts(parts, Body::from(bytes)), parsed)
+            }
+            Err(_) => (Request::from_parts(parts, Body::empty()), None),
+        }
+    } else {
+        (req, None)
+    };
+
+    // Previews change nothing.
+    if body
+        .as_ref()
+        .and_then(|b| b.get("dryRun"))
+        .and_then(|v| v.as_bool())
+        == Some(true)
+    {
+        return next.run(req).await;
+    }
+
+    let response = next.run(req).await;
+    let record = AuditRecord {
+        ts: now_millis(),
+        actor,
+        action: action.to_string(),
+        method,
+        summary: summarize_body(action, &path, body.as_ref()),
+        route: path,
+        directory,
+        ip: client.ip.map(|ip| ip.to_string()),
+        status: response.status().as_u16(),
+    };
+    tokio::spawn(async move {
+        if let Err(err) = append_record(&record).await {
+            tracing::warn!(target: "opencode_studio.audit", error = %err, "failed to write audit record");
+        }
+    });
+    response
+}
+
+async fn append_record(record: &AuditRecord) -> std::io::Result<()> {
+    let path = crate::persistence_paths::audit_log_path();
+    let mut line = serde_json::to_string(record).map_err(std::io::Error::other)?;
+    line.push('\n');
+
+    let _guard = AUDIT_FILE_LOCK.lock().await;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await?;
+    file.write_all(line.as_bytes()).await?;
+    file.flush().await?;
+    drop(file);
+
+    let now = now_millis();
+    let last = LAST_PRUNE_AT.load(Ordering::Relaxed);
+    if now.saturating_sub(last) >= PRUNE_INTERVAL.as_millis() as u64 {
+        LAST_PRUNE_AT.store(now, Ordering::Relaxed);
+        prune_file(&path, now).await?;
+    }
+    Ok(())
+}
+
+/// Lines to keep: within the retention window, at most `MAX_RECORDS`.
+fn retained_lines(content: &str, now: u64, retention: Duration) -> Vec<&str> {
+    let cutoff = now.saturating_sub(retention.as_millis() as u64);
+    let kept: Vec<&str> = content
+        .lines()
+        .filter(|line| {
+            serde_json::from_str::<AuditRecord>(line).is_ok_and(|record| record.ts >= cutoff)
+        })
+        .collect();
+    let skip = kept.len().saturating_sub(MAX_RECORDS);
+    kept[skip..].to_vec()
+}
+
+async fn prune_file(path: &Path, now: u64) -> std::io::Result<()> {
+    let content = tokio::fs::read_to_string(path).await?;
+    let kept = retained_lines(&content, now, retention());
+    if kept.len() == content.lines().count() {
+        return Ok(());
+    }
+    let mut out = kept.join("\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    let tmp = path.with_extension("jsonl.tmp");
+    tokio::fs::write(&tmp, out).await?;
+    tokio::fs::rename(&tmp, path).await
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AuditListQuery {
+    pub limit: Option<usize>,
+    /// Exact action name or a prefix ending in `.`, e.g. `git.`.
+    pub action: Option<String>,
+    pub directory: Option<String>,
+}
+
+/// Most recent audit records first.
+pub(crate) async fn audit_list(
+    Query(q): Query<AuditListQuery>,
+) -> ApiResult<Json<Vec<AuditRecord>>> {
+    let limit = q
+        .limit
+        .unwrap_or(DEFAULT_LIST_LIMIT)
+        .clamp(1, MAX_LIST_LIMIT);
+    let action = q.action.as_deref().map(str::trim).filter(|a| !a.is_empty());
+    let directory = q
+        .directory
+        .as_deref()
+        .and_then(crate::path_utils::normalize_directory_for_match);
+
+    let content = {
+        let _guard = AUDIT_FILE_LOCK.lock().await;
+        match tokio::fs::read_to_string(crate::persistence_paths::audit_log_path()).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(AppError::internal(err.to_string())),
+        }
+    };
+
+    let records: Vec<AuditRecord> = content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<AuditRecord>(line).ok())
+        .filter(|record| {
+            action.is_none_or(|a| {
+                if a.ends_with('.') {
+                    record.action.starts_with(a)
+                } else {
+                    record.action == a
+                }
+            })
+        })
+        .filter(|record| {
+            directory.as_deref().is_none_or(|dir| {
+                record
+                    .directory
+                    .as_deref()
+                    .and_then(crate::path_utils::normalize_directory_for_match)
+                    .is_some_and(|d| d == dir)
+            })
+        })
+        .take(limit)
+        .collect();
+    Ok(Json(records))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn classify_and_summarize_destructive_requests() {
+        assert_eq!(classify(&Method::POST, "/api/git/push"), Some("git.push"));
+        assert_eq!(
+            classify(&Method::DELETE, "/api/session/ses_1"),
+            Some("session.delete")
+        );
+        assert_eq!(classify(&Method::DELETE, "/api/session/ses_1/share"), None);
+        assert_eq!(classify(&Method::GET, "/api/git/push"), None);
+        assert_eq!(
+            classify(&Method::DELETE, "/api/fs/trash"),
+            Some("fs.trash.purge")
+        );
+        assert_eq!(
+            classify(&Method::DELETE, "/api/fs/trash/tr_1"),
+            Some("fs.trash.delete")
+        );
+        assert_eq!(classify(&Method::POST, "/api/fs/trash/tr_1/restore"), None);
+        assert_eq!(classify(&Method::GET, "/api/fs/trash"), None);
+        let summary = summarize_body("fs.trash.delete", "/api/fs/trash/tr_1", None);
+        assert_eq!(summary.get("entry"), Some(&json!("tr_1")));
+
+        let push = json!({ "remote": "origin", "force": "force", "auth": { "password": "x" } });
+        let summary = summarize_body("git.push", "/api/git/push", Some(&push));
+        assert_eq!(summary.get("force"), Some(&json!("force")));
+        assert!(summary.get("auth").is_none());
+
+        let settings = json!({ "theme": "dark", "gitLinters": [] });
+        let summary = summarize_body("settings.update", "/api/config/settings", Some(&settings));
+        assert_eq!(summary.get("keys"), Some(&json!(["gitLinters", "theme"])));
+
+        assert_eq!(
+            classify(&Method::POST, "/api/git/locks/release"),
+            Some("git.lock.release")
+        );
+        let release = json!({ "directory": "/repo" });
+        let summary = summarize_body("git.lock.release", "/api/git/locks/release", Some(&release));
+        assert_eq!(summary.get("directory"), Some(&json!("/repo")));
+
+        assert_eq!(
+            classify(&Method::POST, "/api/session/cleanup"),
+            Some("session.cleanup")
+        );
+        let cleanup = json!({ "olderThanDays": 30, "archivedOnly": true, "name": "x" });
+        let summary = summarize_body("session.cleanup", "/api/session/cleanup", Some(&cleanup));
+        assert_eq!(
+            summary,
+            json!({ "olderThanDays": 30, "archivedOnly": true })
+                .as_object()
+                .unwrap()
+                .clone()
+        );
+    }
+
+    #[test]
+    fn retained_lines_drop_expired_and_malformed_records() {
+        let record = |ts: u64| {
+            serde_json::to_string(&AuditRecord {
+                ts,
+                actor: AuditActor {
+                    kind: "local".to_string(),
+                    name: None,
+                },
+                action: "fs.delete".to_string(),
+                method: "POST".to_string(),
+                route: "/api/fs/delete".to_string(),
+                directory: None,
+                ip: None,
+                status: 200,
+                summary: Map::new(),
+            })
+            .unwrap()
+        };
+        let content = format!("{}\nnot json\n{}\n", record(1_000), record(9_000));
+        let kept = retained_lines(&content, 10_000, Duration::from_millis(5_000));
+        assert_eq!(kept.len(), 1);
+        assert!(kept[0].contains("9000"));
+    }
+}
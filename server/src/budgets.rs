@@ -0,0 +1,542 @@
+//! Spending budgets over the cost recorded by `usage.rs`.
+//!
+//! `settings.budgets` caps spend per session and per UTC day. Crossing a cap
+//! emits `opencode-studio:budget-exceeded` once; with `blockPrompts` on,
+//! prompt posts are refused with 402 until the user acknowledges the breach.
+//! [`enforce_prompt_budget`] is the one place that check runs: it sits in
+//! front of every `/api` route that posts a prompt to OpenCode.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    body::Body,
+    extract::{OriginalUri, Query, Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashSet;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::studio_db::{KV_KEY_BUDGET_ACKNOWLEDGEMENTS, StudioDb};
+use crate::{ApiResult, AppError};
+
+const SETTINGS_KEY: &str = "budgets";
+pub(crate) const BUDGET_EXCEEDED_EVENT: &str = "opencode-studio:budget-exceeded";
+const MAX_ACKNOWLEDGEMENTS: usize = 512;
+const ALERTED_CAP: usize = 4096;
+
+// Breaches already announced by this process.
+static ALERTED: LazyLock<DashSet<String>> = LazyLock::new(DashSet::new);
+// Serializes read-modify-write of the acknowledgement map.
+static ACK_LOCK: LazyLock<tokio::sync::Mutex<()>> = LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+/// Parsed `settings.budgets`; limits are in USD, as OpenCode reports cost.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BudgetSettings {
+    pub session_limit_usd: Option<f64>,
+    pub daily_limit_usd: Option<f64>,
+    pub block_prompts: bool,
+}
+
+fn parse_budget_settings(value: Option<&Value>) -> BudgetSettings {
+    let Some(Value::Object(obj)) = value else {
+        return BudgetSettings::default();
+    };
+    let limit = |key: &str| {
+        obj.get(key)
+            .and_then(Value::as_f64)
+            .filter(|n| n.is_finite() && *n > 0.0)
+    };
+    BudgetSettings {
+        session_limit_usd: limit("sessionLimitUsd"),
+        daily_limit_usd: limit("dailyLimitUsd"),
+        block_prompts: obj
+            .get("blockPrompts")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+    }
+}
+
+async fn budget_settings(state: &crate::AppState) -> BudgetSettings {
+    let guard = state.settings.read().await;
+    parse_budget_settings(guard.extra.get(SETTINGS_KEY))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum BudgetScope {
+    Session,
+    Day,
+}
+
+impl BudgetScope {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Session => "session",
+            Self::Day => "day",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BudgetBreach {
+    pub scope: BudgetScope,
+    /// Session id or UTC `YYYY-MM-DD`, depending on `scope`.
+    pub key: String,
+    pub limit_usd: f64,
+    pub spent_usd: f64,
+    pub acknowledged: bool,
+}
+
+impl BudgetBreach {
+    fn id(&self) -> String {
+        breach_id(self.scope, &self.key)
+    }
+}
+
+fn breach_id(scope: BudgetScope, key: &str) -> String {
+    format!("{}:{key}", scope.as_str())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn today() -> String {
+    crate::usage::day_for_millis(now_millis() as i64)
+}
+
+async fn sum_cost(db: &StudioDb, column: &str, value: &str) -> f64 {
+    let sql = format!("SELECT COALESCE(SUM(cost), 0.0) FROM usage_messages WHERE {column} = ?");
+    sqlx::query_scalar::<_, f64>(&sql)
+        .bind(value)
+        .fetch_one(db.pool())
+        .await
+        .unwrap_or(0.0)
+}
+
+/// Breach ids the user acknowledged, with when.
+async fn acknowledgements(db: &StudioDb) -> BTreeMap<String, u64> {
+    db.get_json::<BTreeMap<String, u64>>(KV_KEY_BUDGET_ACKNOWLEDGEMENTS)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+fn over_limit(
+    scope: BudgetScope,
+    key: &str,
+    limit: Option<f64>,
+    spent: f64,
+    acks: &BTreeMap<String, u64>,
+) -> Option<BudgetBreach> {
+    let limit = limit?;
+    (spent >= limit).then(|| BudgetBreach {
+        scope,
+        key: key.to_string(),
+        limit_usd: limit,
+        spent_usd: spent,
+        acknowledged: acks.contains_key(&breach_id(scope, key)),
+    })
+}
+
+async fn breaches(
+    db: &StudioDb,
+    settings: &BudgetSettings,
+    session_id: Option<&str>,
+    day: &str,
+) -> Vec<BudgetBreach> {
+    let acks = acknowledgements(db).await;
+    let mut out = Vec::new();
+    if let Some(session_id) = session_id
+        && settings.session_limit_usd.is_some()
+    {
+        let spent = sum_cost(db, "session_id", session_id).await;
+        out.extend(over_limit(
+            BudgetScope::Session,
+            session_id,
+            settings.session_limit_usd,
+            spent,
+            &acks,
+        ));
+    }
+    if settings.daily_limit_usd.is_some() {
+        let spent = sum_cost(db, "day", day).await;
+        out.extend(over_limit(
+            BudgetScope::Day,
+            day,
+            settings.daily_limit_usd,
+            spent,
+            &acks,
+        ));
+    }
+    out
+}
+
+/// Called after usage for `session_id` was recorded on `day`; announces any
+/// budget the new cost pushed over its limit.
+pub(crate) async fn check_after_usage(state: &crate::AppState, session_id: &str, day: &str) {
+    let settings = budget_settings(state).await;
+    if settings == BudgetSettings::default() {
+        return;
+    }
+    for breach in breaches(&state.studio_db, &settings, Some(session_id), day).await {
+        if breach.acknowledged || !ALERTED.insert(breach.id()) {
+            continue;
+        }
+        if ALERTED.len() > ALERTED_CAP {
+            ALERTED.clear();
+        }
+        let payload = serde_json::json!({
+            "type": BUDGET_EXCEEDED_EVENT,
+            "properties": {
+                "sessionID": session_id,
+                "scope": breach.scope,
+                "key": breach.key,
+                "limitUsd": breach.limit_usd,
+                "spentUsd": breach.spent_usd,
+                "blocking": settings.block_prompts,
+            }
+        });
+        crate::global_sse_hub::publish_downstream_json(&payload.to_string());
+    }
+}
+
+/// 402 response for a prompt while an unacknowledged budget breach blocks
+/// prompts; `None` when the prompt may go through. Without `session_id`
+/// (a prompt into a new session) only the daily budget applies.
+async fn prompt_block(state: &crate::AppState, session_id: Option<&str>) -> Option<Response> {
+    let settings = budget_settings(state).await;
+    if !settings.block_prompts {
+        return None;
+    }
+    let open: Vec<BudgetBreach> = breaches(&state.studio_db, &settings, session_id, &today())
+        .await
+        .into_iter()
+        .filter(|b| !b.acknowledged)
+        .collect();
+    if open.is_empty() {
+        return None;
+    }
+    Some(
+        (
+            StatusCode::PAYMENT_REQUIRED,
+            Json(serde_json::json!({
+                "error": "Spending budget exceeded; acknowledge it to keep prompting",
+                "code": "budget_exceeded",
+                "breaches": open,
+            })),
+        )
+            .into_response(),
+    )
+}
+
+/// How a prompt-posting route names the session it prompts.
+#[derive(Debug, PartialEq, Eq)]
+enum PromptRoute {
+    /// `/api/session/{id}/…`.
+    Session(String),
+    /// `sessionId` in the JSON body; without one the route either creates a
+    /// session (`prompts_when_absent`) or posts no prompt at all.
+    BodySession { prompts_when_absent: bool },
+    /// Prompts a throwaway session of its own.
+    NewSession,
+}
+
+/// Every `/api` route that posts a prompt to OpenCode. `path` is the full
+/// request path including `/api`.
+fn prompt_route(method: &Method, path: &str) -> Option<PromptRoute> {
+    if method != Method::POST {
+        return None;
+    }
+    if let Some(rest) = path.strip_prefix("/api/session/")
+        && let Some((id, action)) = rest.split_once('/')
+        && !id.is_empty()
+        && matches!(
+            action,
+            "message" | "prompt_async" | "command" | "shell" | "summary"
+        )
+    {
+        let id = urlencoding::decode(id)
+            .map(|id| id.into_owned())
+            .unwrap_or_else(|_| id.to_string());
+        return Some(PromptRoute::Session(id));
+    }
+    if path == "/api/git/commit-message" {
+        return Some(PromptRoute::NewSession);
+    }
+    let segments: Vec<&str> = path.split('/').collect();
+    match segments.as_slice() {
+        ["", "api", "quick-captures", id, "convert"] if !id.is_empty() => {
+            Some(PromptRoute::BodySession {
+                prompts_when_absent: true,
+            })
+        }
+        ["", "api", "prompts", id, "render"] if !id.is_empty() => Some(PromptRoute::BodySession {
+            prompts_when_absent: false,
+        }),
+        _ => None,
+    }
+}
+
+// Bodies of the routes that carry `sessionId` are small JSON objects.
+const MAX_PROMPT_ROUTE_BODY_BYTES: usize = 1024 * 1024;
+
+/// Refuse prompt posts with 402 while a budget breach blocks prompts.
+pub(crate) async fn enforce_prompt_budget(
+    State(state): State<Arc<crate::AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let path = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.path().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let Some(route) = prompt_route(req.method(), &path) else {
+        return next.run(req).await;
+    };
+
+    let (req, session_id) = match route {
+        PromptRoute::Session(id) => (req, Some(id)),
+        PromptRoute::NewSession => (req, None),
+        PromptRoute::BodySession {
+            prompts_when_absent,
+        } => {
+            let (parts, body) = req.into_parts();
+            let Ok(bytes) = axum::body::to_bytes(body, MAX_PROMPT_ROUTE_BODY_BYTES).await else {
+                return AppError::payload_too_large("Request body too large").into_response();
+            };
+            let session_id = serde_json::from_slice::<Value>(&bytes)
+                .ok()
+                .and_then(|body| {
+                    body.get("sessionId")
+                        .and_then(|v| v.as_str())
+                        .map(str::trim)
+                        .filter(|id| !id.is_empty())
+                        .map(str::to_string)
+                });
+            let req = Request::from_parts(parts, Body::from(bytes));
+            if session_id.is_none() && !prompts_when_absent {
+                return next.run(req).await;
+            }
+            (req, session_id)
+        }
+    };
+
+    if let Some(blocked) = prompt_block(&state, session_id.as_deref()).await {
+        return blocked;
+    }
+    next.run(req).await
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BudgetStatusQuery {
+    session_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BudgetStatusResponse {
+    settings: BudgetSettings,
+    day: String,
+    day_spent_usd: f64,
+    session_spent_usd: Option<f64>,
+    breaches: Vec<BudgetBreach>,
+}
+
+async fn status(state: &crate::AppState, session_id: Option<&str>) -> BudgetStatusResponse {
+    let db = &state.studio_db;
+    let settings = budget_settings(state).await;
+    let day = today();
+    let session_spent_usd = match session_id {
+        Some(id) => Some(sum_cost(db, "session_id", id).await),
+        None => None,
+    };
+    BudgetStatusResponse {
+        breaches: breaches(db, &settings, session_id, &day).await,
+        day_spent_usd: sum_cost(db, "day", &day).await,
+        session_spent_usd,
+        settings,
+        day,
+    }
+}
+
+fn trimmed_session_id(raw: Option<&str>) -> Option<&str> {
+    raw.map(str::trim).filter(|id| !id.is_empty())
+}
+
+/// Configured budgets, today's spend and current breaches.
+pub(crate) async fn budget_status_get(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<BudgetStatusQuery>,
+) -> ApiResult<Json<BudgetStatusResponse>> {
+    Ok(Json(
+        status(&state, trimmed_session_id(q.session_id.as_deref())).await,
+    ))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BudgetAcknowledgeBody {
+    session_id: Option<String>,
+    /// Only acknowledge this scope; both by default.
+    scope: Option<BudgetScope>,
+}
+
+/// Acknowledge current breaches so prompts are accepted again. A breach stays
+/// acknowledged for the rest of its session or day.
+pub(crate) async fn budget_acknowledge_post(
+    State(state): State<Arc<crate::AppState>>,
+    Json(body): Json<BudgetAcknowledgeBody>,
+) -> ApiResult<Json<BudgetStatusResponse>> {
+    let session_id = trimmed_session_id(body.session_id.as_deref());
+    let current = status(&state, session_id).await;
+    let selected: Vec<String> = current
+        .breaches
+        .iter()
+        .filter(|b| !b.acknowledged && body.scope.is_none_or(|scope| scope == b.scope))
+        .map(BudgetBreach::id)
+        .collect();
+    if !selected.is_empty() {
+        let _guard = ACK_LOCK.lock().await;
+        let mut acks = acknowledgements(&state.studio_db).await;
+        let now = now_millis();
+        for id in selected {
+            acks.insert(id, now);
+        }
+        while acks.len() > MAX_ACKNOWLEDGEMENTS {
+            let Some(oldest) = acks
+                .iter()
+                .min_by_key(|(_, at)| **at)
+                .map(|(k, _)| k.clone())
+            else {
+                break;
+            };
+            acks.remove(&oldest);
+        }
+        state
+            .studio_db
+            .set_json(KV_KEY_BUDGET_ACKNOWLEDGEMENTS, &acks)
+            .await
+            .map_err(AppError::internal)?;
+    }
+    Ok(Json(status(&state, session_id).await))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn prompt_route_covers_every_prompt_post() {
+        let post = Method::POST;
+        for action in ["message", "prompt_async", "command", "shell", "summary"] {
+            assert_eq!(
+                prompt_route(&post, &format!("/api/session/ses%5F1/{action}")),
+                Some(PromptRoute::Session("ses_1".to_string()))
+            );
+        }
+        assert_eq!(
+            prompt_route(&post, "/api/git/commit-message"),
+            Some(PromptRoute::NewSession)
+        );
+        assert_eq!(
+            prompt_route(&post, "/api/quick-captures/cap_1/convert"),
+            Some(PromptRoute::BodySession {
+                prompts_when_absent: true
+            })
+        );
+        assert_eq!(
+            prompt_route(&post, "/api/prompts/tpl_1/render"),
+            Some(PromptRoute::BodySession {
+                prompts_when_absent: false
+            })
+        );
+        assert_eq!(
+            prompt_route(&Method::GET, "/api/session/ses_1/message"),
+            None
+        );
+        assert_eq!(prompt_route(&post, "/api/session/ses_1/abort"), None);
+        assert_eq!(prompt_route(&post, "/api/quick-captures"), None);
+    }
+
+    #[test]
+    fn parse_budget_settings_ignores_unusable_limits() {
+        assert_eq!(parse_budget_settings(None), BudgetSettings::default());
+        let parsed = parse_budget_settings(Some(&json!({
+            "sessionLimitUsd": 2.5,
+            "dailyLimitUsd": -1,
+            "blockPrompts": true,
+        })));
+        assert_eq!(parsed.session_limit_usd, Some(2.5));
+        assert_eq!(parsed.daily_limit_usd, None);
+        assert!(parsed.block_prompts);
+    }
+
+    #[tokio::test]
+    async fn breaches_compare_recorded_cost_with_limits() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let db = StudioDb::open_at_path(tmp.path().join("studio.db"))
+            .await
+            .unwrap();
+        for (id, session, cost) in [
+            ("m1", "ses_a", 1.0),
+            ("m2", "ses_a", 0.75),
+            ("m3", "ses_b", 0.5),
+        ] {
+            sqlx::query("INSERT INTO usage_messages (message_id, session_id, directory, provider_id, model_id, day, created_at, input_tokens, output_tokens, reasoning_tokens, cache_read_tokens, cache_write_tokens, cost) VALUES (?, ?, '/a', 'p', 'm', '2024-05-01', 0, 0, 0, 0, 0, 0, ?)")
+                .bind(id)
+                .bind(session)
+                .bind(cost)
+                .execute(db.pool())
+                .await
+                .unwrap();
+        }
+        let settings = BudgetSettings {
+            session_limit_usd: Some(1.5),
+            daily_limit_usd: Some(3.0),
+            block_prompts: true,
+        };
+
+        let found = breaches(&db, &settings, Some("ses_a"), "2024-05-01").await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].scope, BudgetScope::Session);
+        assert!((found[0].spent_usd - 1.75).abs() < 1e-9);
+        assert!(!found[0].acknowledged);
+        assert!(
+            breaches(&db, &settings, Some("ses_b"), "2024-05-01")
+                .await
+                .is_empty()
+        );
+
+        let mut acks = BTreeMap::new();
+        acks.insert(breach_id(BudgetScope::Session, "ses_a"), 1);
+        db.set_json(KV_KEY_BUDGET_ACKNOWLEDGEMENTS, &acks)
+            .await
+            .unwrap();
+        let tighter = BudgetSettings {
+            daily_limit_usd: Some(2.0),
+            ..settings
+        };
+        let found = breaches(&db, &tighter, Some("ses_a"), "2024-05-01").await;
+        assert_eq!(found.len(), 2);
+        assert!(found[0].acknowledged);
+        assert_eq!(found[1].scope, BudgetScope::Day);
+        assert!(!found[1].acknowledged);
+    }
+}
@@ -12,6 +12,7 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use futures_util::stream::{self, StreamExt};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use tokio::sync::{Mutex as AsyncMutex, RwLock};
@@ -388,6 +389,20 @@ pub(crate) async fn chat_sidebar_preferences_snapshot(
     read_sidebar_preferences_cached(db).await
 }
 
+/// Global preferences with `device_id`'s overrides applied.
+pub(crate) async fn chat_sidebar_preferences_for_device(
+    db: &studio_db::StudioDb,
+    device_id: Option<&str>,
+) -> SessionsSidebarPreferences {
+    let global = read_sidebar_preferences_cached(db).await;
+    match device_id {
+        Some(device_id) => crate::chat_sidebar_devices::device_overrides(db, device_id)
+            .await
+            .apply_to(&global),
+        None => global,
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct DirectoryWire {
@@ -404,7 +419,7 @@ pub(crate) struct ChatSidebarIndexQuery {
     pub limit: Option<usize>,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ChatSidebarStateQuery {
     pub directories_page: Option<usize>,
@@ -419,7 +434,7 @@ pub(crate) struct ChatSidebarStateQuery {
     pub running_page_size: Option<usize>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub(crate) enum ChatSidebarCommandRequest {
     #[serde(rename = "setDirectoriesPage")]
@@ -454,7 +469,7 @@ pub(crate) enum ChatSidebarCommandRequest {
     FooterPage { kind: String, page: usize },
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub(crate) enum ChatSidebarCommandsRequest {
     Single(ChatSidebarCommandRequest),
@@ -476,14 +491,14 @@ impl ChatSidebarCommandsRequest {
     }
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ChatSidebarSessionSearchQuery {
     pub query: Option<String>,
     pub limit: Option<usize>,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ChatSidebarFooterQuery {
     pub kind: Option<String>,
@@ -491,7 +506,7 @@ pub(crate) struct ChatSidebarFooterQuery {
     pub page_size: Option<usize>,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct DirectoriesQuery {
     pub offset: Option<usize>,
@@ -499,12 +514,17 @@ pub(crate) struct DirectoriesQuery {
     pub query: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct SessionSummariesByIdsQuery {
     pub ids: Option<String>,
 }
 
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub(crate) struct RecentSessionsQuery {
+    pub limit: Option<usize>,
+}
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct DirectorySessionsPath {
     pub directory_id: String,
@@ -617,6 +637,11 @@ struct SessionSummariesByIdsResponse {
     missing_ids: Vec<String>,
 }
 
+#[derive(Debug, Serialize)]
+struct RecentSessionsResponse {
+    items: Vec<RecentIndexItem>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ChatSidebarStateResponse {
@@ -786,8 +811,9 @@ fn cache_key_part(raw: Option<&str>) -> String {
     raw.map(|v| v.trim()).unwrap_or("").to_string()
 }
 
-fn sidebar_state_cache_key(query: &ChatSidebarStateQuery) -> String {
+fn sidebar_state_cache_key(query: &ChatSidebarStateQuery, device_id: Option<&str>) -> String {
     [
+        format!("device={}", device_id.unwrap_or_default()),
         format!(
             "directoriesPage={}",
             query
@@ -1164,13 +1190,13 @@ async fn fetch_sidebar_directory_session_pages(
         let state = state.clone();
         async move {
             let directory_id = request.directory_id;
-            let directory_response = directory_sessions_by_id_get(
-                State(state),
+            let directory_response = directory_sessions_page(
+                state,
                 HeaderMap::new(),
-                AxumPath(DirectorySessionsPath {
+                DirectorySessionsPath {
                     directory_id: directory_id.clone(),
-                }),
-                Query(crate::opencode_session::SessionListQuery {
+                },
+                crate::opencode_session::SessionListQuery {
                     directory: None,
                     scope: Some("directory".to_string()),
                     roots: Some("true".to_string()),
@@ -1189,7 +1215,10 @@ async fn fetch_sidebar_directory_session_pages(
                     include_children: Some("true".to_string()),
                     ids: None,
                     focus_session_id: request.focus_session_id,
-                }),
+                    tag: None,
+                    favorite: None,
+                    archived: None,
+                },
             )
             .await
             .map_err(|error| error.into_response())?;
@@ -2051,6 +2080,9 @@ fn build_recent_index_items(state: &Arc<crate::AppState>) -> Vec<RecentIndexItem
         let Some(root_id) = root_session_id_for_session(state, &recent.session_id) else {
             continue;
         };
+        if crate::session_tags::is_archived(&root_id) {
+            continue;
+        }
 
         let updated_at = if recent.updated_at.is_finite() {
             recent.updated_at
@@ -2534,11 +2566,37 @@ pub(crate) async fn directories_get(
     Json(page(items, offset, limit)).into_response()
 }
 
+/// With `since`, returns only the sessions changed after that cursor (see
+/// `sidebar_sync`); otherwise the full page. Full pages carry the cursor in
+/// `x-sidebar-cursor`.
 pub(crate) async fn directory_sessions_by_id_get(
     State(state): State<Arc<crate::AppState>>,
     headers: HeaderMap,
     AxumPath(path): AxumPath<DirectorySessionsPath>,
-    Query(mut query): Query<crate::opencode_session::SessionListQuery>,
+    Query(sync): Query<crate::sidebar_sync::SidebarSyncQuery>,
+    Query(query): Query<crate::opencode_session::SessionListQuery>,
+) -> crate::ApiResult<Response> {
+    let directory_id = path.directory_id.trim();
+    if let Some(since) = sync.since.as_deref().filter(|v| !v.trim().is_empty()) {
+        let known = directory_path_by_id(&*state.settings.read().await, directory_id).is_some();
+        if known && let Some(delta) = crate::sidebar_sync::sync_since(directory_id, since) {
+            return Ok(Json(delta).into_response());
+        }
+    }
+
+    let cursor = crate::sidebar_sync::current_cursor();
+    let mut response = directory_sessions_page(state, headers, path, query).await?;
+    if let Ok(value) = axum::http::HeaderValue::from_str(&cursor) {
+        response.headers_mut().insert("x-sidebar-cursor", value);
+    }
+    Ok(response)
+}
+
+async fn directory_sessions_page(
+    state: Arc<crate::AppState>,
+    headers: HeaderMap,
+    path: DirectorySessionsPath,
+    mut query: crate::opencode_session::SessionListQuery,
 ) -> crate::ApiResult<Response> {
     let directory_id = path.directory_id.trim().to_string();
     if directory_id.is_empty() {
@@ -2571,7 +2629,9 @@ pub(crate) async fn directory_sessions_by_id_get(
         .as_deref()
         .and_then(|value| value.trim().parse::<usize>().ok())
         .unwrap_or(SIDEBAR_STATE_DIRECTORY_SESSIONS_PAGE_SIZE_DEFAULT);
-    let preferences = chat_sidebar_preferences_snapshot(state.studio_db.as_ref()).await;
+    let device_id = crate::chat_sidebar_devices::device_id_from_headers(&headers);
+    let preferences =
+        chat_sidebar_preferences_for_device(state.studio_db.as_ref(), device_id.as_deref()).await;
 
     let cacheable = is_cacheable_directory_sessions_query(&query);
     let delta_seq = chat_sidebar_delta_latest_seq();
@@ -2696,6 +2756,7 @@ async fn chat_sidebar_recent_index_page(
 
 pub(crate) async fn chat_sidebar_footer_get(
     State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
     Query(query): Query<ChatSidebarFooterQuery>,
 ) -> Response {
     let kind_raw = query.kind.as_deref().unwrap_or("");
@@ -2714,7 +2775,9 @@ pub(crate) async fn chat_sidebar_footer_get(
         let settings = state.settings.read().await;
         configured_directories(&settings)
     };
-    let mut preferences = chat_sidebar_preferences_snapshot(state.studio_db.as_ref()).await;
+    let device_id = crate::chat_sidebar_devices::device_id_from_headers(&headers);
+    let mut preferences =
+        chat_sidebar_preferences_for_device(state.studio_db.as_ref(), device_id.as_deref()).await;
     let page = query.page.unwrap_or(match kind {
         ChatSidebarFooterKind::Pinned => preferences.pinned_sessions_page,
         ChatSidebarFooterKind::Recent => preferences.recent_sessions_page,
@@ -2777,6 +2840,9 @@ pub(crate) async fn chat_sidebar_session_search(
             include_children: Some("false".to_string()),
             ids: None,
             focus_session_id: None,
+            tag: None,
+            favorite: None,
+            archived: None,
         }),
     )
     .await
@@ -2829,11 +2895,13 @@ pub(crate) async fn chat_sidebar_session_search(
 
 pub(crate) async fn chat_sidebar_state(
     State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
     Query(query): Query<ChatSidebarStateQuery>,
 ) -> crate::ApiResult<Response> {
     crate::directory_sessions::ensure_directory_sessions_poller_started(state.clone());
 
-    let response_cache_key = sidebar_state_cache_key(&query);
+    let device_id = crate::chat_sidebar_devices::device_id_from_headers(&headers);
+    let response_cache_key = sidebar_state_cache_key(&query, device_id.as_deref());
     let delta_seq_at_start = chat_sidebar_delta_latest_seq();
     if let Ok(mut cache) = SIDEBAR_STATE_RESPONSE_CACHE.lock()
         && let Some(payload) = cache.get_fresh(&response_cache_key, delta_seq_at_start)
@@ -2841,7 +2909,8 @@ pub(crate) async fn chat_sidebar_state(
         return Ok(Json(payload).into_response());
     }
 
-    let preferences = chat_sidebar_preferences_snapshot(state.studio_db.as_ref()).await;
+    let preferences =
+        chat_sidebar_preferences_for_device(state.studio_db.as_ref(), device_id.as_deref()).await;
     let seq = crate::directory_sessions::directory_sessions_latest_seq();
     let runtime_by_session_id = state.directory_session_index.runtime_snapshot_json();
 
@@ -3019,13 +3088,13 @@ pub(crate) async fn chat_sidebar_state(
             .iter()
             .filter(|directory| !expanded_id_set.contains(&directory.id))
         {
-            let response = match directory_sessions_by_id_get(
-                State(state.clone()),
+            let response = match directory_sessions_page(
+                state.clone(),
                 HeaderMap::new(),
-                AxumPath(DirectorySessionsPath {
+                DirectorySessionsPath {
                     directory_id: directory.id.clone(),
-                }),
-                Query(crate::opencode_session::SessionListQuery {
+                },
+                crate::opencode_session::SessionListQuery {
                     directory: None,
                     scope: Some("directory".to_string()),
                     roots: Some("true".to_string()),
@@ -3037,7 +3106,10 @@ pub(crate) async fn chat_sidebar_state(
                     include_children: Some("true".to_string()),
                     ids: None,
                     focus_session_id: None,
-                }),
+                    tag: None,
+                    favorite: None,
+                    archived: None,
+                },
             )
             .await
             {
@@ -3510,6 +3582,9 @@ fn build_patch_directory_view_op_from_cache(
         include_children: Some("true".to_string()),
         ids: None,
         focus_session_id: None,
+        tag: None,
+        favorite: None,
+        archived: None,
     };
     let cache_key = directory_sessions_page_cache_key(did, &query, preferences);
     let delta_seq = chat_sidebar_delta_latest_seq();
@@ -3647,6 +3722,15 @@ fn sidebar_patch_data_ops_for_command(
 }
 
 pub(crate) fn publish_chat_sidebar_delta_event(ops: Vec<ChatSidebarPatchOp>) -> Option<u64> {
+    publish_chat_sidebar_delta(ops, None)
+}
+
+/// Deltas carrying a `deviceId` reflect that device's effective preferences;
+/// clients on other devices resync instead of applying them.
+fn publish_chat_sidebar_delta(
+    ops: Vec<ChatSidebarPatchOp>,
+    device_id: Option<&str>,
+) -> Option<u64> {
     if ops.is_empty() {
         return None;
     }
@@ -3657,14 +3741,18 @@ pub(crate) fn publish_chat_sidebar_delta_event(ops: Vec<ChatSidebarPatchOp>) ->
         return Some(seq);
     }
 
+    let mut properties = json!({
+        "seq": seq,
+        "delta": {
+            "ops": ops,
+        }
+    });
+    if let Some(device_id) = device_id {
+        properties["deviceId"] = json!(device_id);
+    }
     let payload = serde_json::to_string(&json!({
         "type": "chat-sidebar.delta",
-        "properties": {
-            "seq": seq,
-            "delta": {
-                "ops": ops,
-            }
-        }
+        "properties": properties,
     }))
     .unwrap_or_else(|_| "{}".to_string());
 
@@ -3672,8 +3760,83 @@ pub(crate) fn publish_chat_sidebar_delta_event(ops: Vec<ChatSidebarPatchOp>) ->
     Some(seq)
 }
 
+fn apply_sidebar_command(
+    preferences: &mut SessionsSidebarPreferences,
+    command: &ChatSidebarCommandRequest,
+    normalized: Option<&str>,
+) {
+    match command {
+        ChatSidebarCommandRequest::DirectoriesPage { page } => {
+            preferences.directories_page = *page;
+        }
+        ChatSidebarCommandRequest::DirectoryCollapsed {
+            directory_id,
+            collapsed,
+        } => {
+            let Some(directory_id) = trim_non_empty(directory_id) else {
+                return;
+            };
+            set_toggle_membership(
+                &mut preferences.collapsed_directory_ids,
+                &directory_id,
+                *collapsed,
+            );
+        }
+        ChatSidebarCommandRequest::DirectoryRootPage { directory_id, page } => {
+            let Some(directory_id) = trim_non_empty(directory_id) else {
+                return;
+            };
+            preferences
+                .session_root_page_by_directory_id
+                .insert(directory_id, *page);
+        }
+        ChatSidebarCommandRequest::SessionPinned { session_id, pinned } => {
+            let Some(session_id) = trim_non_empty(session_id) else {
+                return;
+            };
+            set_toggle_membership(&mut preferences.pinned_session_ids, &session_id, *pinned);
+        }
+        ChatSidebarCommandRequest::SessionExpanded {
+            session_id,
+            expanded,
+        } => {
+            let Some(session_id) = trim_non_empty(session_id) else {
+                return;
+            };
+            set_toggle_membership(
+                &mut preferences.expanded_parent_session_ids,
+                &session_id,
+                *expanded,
+            );
+        }
+        ChatSidebarCommandRequest::FooterOpen { kind, open } => {
+            let kind = normalized
+                .or_else(|| normalize_footer_kind(kind))
+                .unwrap_or("pinned");
+            match kind {
+                "pinned" => preferences.pinned_sessions_open = *open,
+                "recent" => preferences.recent_sessions_open = *open,
+                "running" => preferences.running_sessions_open = *open,
+                _ => {}
+            }
+        }
+        ChatSidebarCommandRequest::FooterPage { kind, page } => {
+            let kind = normalized
+                .or_else(|| normalize_footer_kind(kind))
+                .unwrap_or("pinned");
+            match kind {
+                "pinned" => preferences.pinned_sessions_page = *page,
+                "recent" => preferences.recent_sessions_page = *page,
+                "running" => preferences.running_sessions_page = *page,
+                _ => {}
+            }
+        }
+    }
+}
+
 pub(crate) async fn chat_sidebar_commands_post(
     State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
     Json(request): Json<ChatSidebarCommandsRequest>,
 ) -> crate::ApiResult<Response> {
     let (commands, compact) = request.into_parts();
@@ -3731,86 +3894,58 @@ pub(crate) async fn chat_sidebar_commands_post(
         normalized_kinds.push(normalized);
     }
 
-    let preferences = match mutate_sidebar_preferences(state.studio_db.clone(), |preferences| {
-        for (idx, command) in commands.iter().enumerate() {
-            let normalized = normalized_kinds.get(idx).and_then(|value| value.as_deref());
-            match command {
-                ChatSidebarCommandRequest::DirectoriesPage { page } => {
-                    preferences.directories_page = *page;
-                }
-                ChatSidebarCommandRequest::DirectoryCollapsed {
-                    directory_id,
-                    collapsed,
-                } => {
-                    let Some(directory_id) = trim_non_empty(directory_id) else {
-                        continue;
-                    };
-                    set_toggle_membership(
-                        &mut preferences.collapsed_directory_ids,
-                        &directory_id,
-                        *collapsed,
-                    );
-                }
-                ChatSidebarCommandRequest::DirectoryRootPage { directory_id, page } => {
-                    let Some(directory_id) = trim_non_empty(directory_id) else {
-                        continue;
-                    };
-                    preferences
-                        .session_root_page_by_directory_id
-                        .insert(directory_id, *page);
-                }
-                ChatSidebarCommandRequest::SessionPinned { session_id, pinned } => {
-                    let Some(session_id) = trim_non_empty(session_id) else {
-                        continue;
-                    };
-                    set_toggle_membership(
-                        &mut preferences.pinned_session_ids,
-                        &session_id,
-                        *pinned,
-                    );
-                }
-                ChatSidebarCommandRequest::SessionExpanded {
-                    session_id,
-                    expanded,
-                } => {
-                    let Some(session_id) = trim_non_empty(session_id) else {
-                        continue;
-                    };
-                    set_toggle_membership(
-                        &mut preferences.expanded_parent_session_ids,
-                        &session_id,
-                        *expanded,
-                    );
-                }
-                ChatSidebarCommandRequest::FooterOpen { kind, open } => {
-                    let kind = normalized
-                        .or_else(|| normalize_footer_kind(kind))
-                        .unwrap_or("pinned");
-                    match kind {
-                        "pinned" => preferences.pinned_sessions_open = *open,
-                        "recent" => preferences.recent_sessions_open = *open,
-                        "running" => preferences.running_sessions_open = *open,
-                        _ => {}
-                    }
-                }
-                ChatSidebarCommandRequest::FooterPage { kind, page } => {
-                    let kind = normalized
-                        .or_else(|| normalize_footer_kind(kind))
-                        .unwrap_or("pinned");
-                    match kind {
-                        "pinned" => preferences.pinned_sessions_page = *page,
-                        "recent" => preferences.recent_sessions_page = *page,
-                        "running" => preferences.running_sessions_page = *page,
-                        _ => {}
-                    }
+    let device_id = crate::chat_sidebar_devices::device_id_from_headers(&headers);
+    // With a device id, layout commands go to that device's overrides and
+    // only pins change the global preferences.
+    let is_global = |command: &ChatSidebarCommandRequest| {
+        device_id.is_none() || matches!(command, ChatSidebarCommandRequest::SessionPinned { .. })
+    };
+    let global_changes = commands.iter().any(is_global);
+    let preferences = if global_changes {
+        match mutate_sidebar_preferences(state.studio_db.clone(), |preferences| {
+            for (idx, command) in commands.iter().enumerate() {
+                if is_global(command) {
+                    let normalized = normalized_kinds.get(idx).and_then(|value| value.as_deref());
+                    apply_sidebar_command(preferences, command, normalized);
                 }
             }
+        })
+        .await
+        {
+            Ok(preferences) => preferences,
+            Err(response) => return Ok(response),
         }
-    })
-    .await
-    {
-        Ok(preferences) => preferences,
-        Err(response) => return Ok(response),
+    } else {
+        chat_sidebar_preferences_snapshot(state.studio_db.as_ref()).await
+    };
+    let preferences = match device_id.as_deref() {
+        Some(device_id) => {
+            let overrides = if commands.iter().all(is_global) {
+                crate::chat_sidebar_devices::device_overrides(state.studio_db.as_ref(), device_id)
+                    .await
+            } else {
+                crate::chat_sidebar_devices::mutate_device_overrides(
+                    state.studio_db.as_ref(),
+                    device_id,
+                    |overrides| {
+                        let before = overrides.apply_to(&preferences);
+                        let mut after = before.clone();
+                        for (idx, command) in commands.iter().enumerate() {
+                            if !is_global(command) {
+                                let normalized =
+                                    normalized_kinds.get(idx).and_then(|value| value.as_deref());
+                                apply_sidebar_command(&mut after, command, normalized);
+                            }
+                        }
+                        overrides.record_changes(&before, &sanitize_sidebar_preferences(after));
+                    },
+                )
+                .await
+                .map_err(crate::AppError::internal)?
+            };
+            overrides.apply_to(&preferences)
+        }
+        None => preferences,
     };
 
     let configured = {
@@ -3857,7 +3992,7 @@ pub(crate) async fn chat_sidebar_commands_post(
             affected_directory_id,
         ));
     }
-    let delta_seq = publish_chat_sidebar_delta_event(ops.clone());
+    let delta_seq = publish_chat_sidebar_delta(ops.clone(), device_id.as_deref());
 
     let seq = crate::directory_sessions::directory_sessions_latest_seq();
     Ok(Json(ChatSidebarCommandsResponse {
@@ -3920,6 +4055,9 @@ pub(crate) async fn sessions_summaries_get(
                 include_children: None,
                 ids: Some(ids_csv),
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             };
 
             let response = match crate::opencode_session::session_list(
@@ -3960,6 +4098,21 @@ pub(crate) async fn sessions_summaries_get(
     .into_response()
 }
 
+/// Most recently updated root sessions across all directories, newest first.
+/// Used by clients that prompt "the last session" without loading the sidebar.
+pub(crate) async fn sessions_recent_get(
+    State(state): State<Arc<crate::AppState>>,
+    Query(query): Query<RecentSessionsQuery>,
+) -> Response {
+    let limit = query
+        .limit
+        .unwrap_or(SIDEBAR_RECENT_INDEX_SEED_LIMIT)
+        .clamp(1, SIDEBAR_RECENT_INDEX_SEED_LIMIT);
+    let mut items = build_recent_index_items(&state);
+    items.truncate(limit);
+    Json(RecentSessionsResponse { items }).into_response()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4090,6 +4243,9 @@ mod tests {
             include_children: Some("true".to_string()),
             ids: None,
             focus_session_id: Some("ses_focus".to_string()),
+            tag: None,
+            favorite: None,
+            archived: None,
         };
         let preferences = SessionsSidebarPreferences {
             version: 7,
@@ -4309,4 +4465,71 @@ mod tests {
             "invalidateState"
         );
     }
+
+    #[tokio::test]
+    async fn sessions_recent_get_orders_roots_by_update_and_clamps_limit() {
+        let db_dir = tempfile::tempdir().expect("tempdir");
+        let state = crate::test_support::app_state(db_dir.path(), Default::default()).await;
+        let index = &state.directory_session_index;
+        index.replace_directory_mappings(vec![
+            ("dir_a".to_string(), "/work/a".to_string()),
+            ("dir_b".to_string(), "/work/b".to_string()),
+        ]);
+        for session in [
+            json!({ "id": "ses_recent_old", "directory": "/work/a", "time": { "updated": 100 } }),
+            json!({ "id": "ses_recent_new", "directory": "/work/b", "time": { "updated": 300 } }),
+            json!({ "id": "ses_recent_mid", "directory": "/work/a", "time": { "updated": 200 } }),
+            // A fresh child lifts its root to the top instead of being listed.
+            json!({
+                "id": "ses_recent_child",
+                "parentID": "ses_recent_old",
+                "directory": "/work/a",
+                "time": { "updated": 400 },
+            }),
+        ] {
+            index.upsert_summary_from_value(&session);
+        }
+
+        let recent = |limit: Option<usize>| {
+            let state = state.clone();
+            async move {
+                let response =
+                    sessions_recent_get(State(state), Query(RecentSessionsQuery { limit })).await;
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("body");
+                let payload: Value = serde_json::from_slice(&body).expect("json");
+                payload["items"]
+                    .as_array()
+                    .expect("items")
+                    .iter()
+                    .map(|item| {
+                        (
+                            item["sessionId"].as_str().unwrap_or_default().to_string(),
+                            item["directoryId"].as_str().unwrap_or_default().to_string(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let pair = |session: &str, directory: &str| (session.to_string(), directory.to_string());
+        assert_eq!(
+            recent(None).await,
+            vec![
+                pair("ses_recent_old", "dir_a"),
+                pair("ses_recent_new", "dir_b"),
+                pair("ses_recent_mid", "dir_a"),
+            ]
+        );
+        assert_eq!(
+            recent(Some(2)).await,
+            vec![
+                pair("ses_recent_old", "dir_a"),
+                pair("ses_recent_new", "dir_b")
+            ]
+        );
+        assert_eq!(recent(Some(0)).await, vec![pair("ses_recent_old", "dir_a")]);
+        assert_eq!(recent(Some(10_000)).await.len(), 3);
+    }
 }
@@ -0,0 +1,420 @@
+//! Per-device overrides for the chat sidebar preferences.
+//!
+//! Clients identify themselves with an `x-opencode-device-id` header. Layout
+//! state (collapsed directories, expanded parents, page positions and footer
+//! sections) changed by such a client is stored as that device's override;
+//! pinned sessions stay global. Reads layer the device's overrides over the
+//! global preferences, so a phone and a desktop keep their own layouts while
+//! sharing pins. Requests without a device id use the global preferences.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock};
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::{Mutex as AsyncMutex, RwLock};
+
+use crate::chat_sidebar::SessionsSidebarPreferences;
+use crate::studio_db::{self, StudioDb};
+use crate::{ApiResult, AppError};
+
+pub(crate) const DEVICE_ID_HEADER: &str = "x-opencode-device-id";
+const MAX_DEVICE_ID_LEN: usize = 128;
+/// Least recently updated devices are forgotten beyond this.
+const MAX_DEVICES: usize = 64;
+
+/// Fields a device has diverged on; `None` follows the global preferences.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SidebarDeviceOverrides {
+    #[serde(default)]
+    pub version: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collapsed_directory_ids: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expanded_parent_session_ids: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directories_page: Option<usize>,
+    /// Merged per directory over the global map.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub session_root_page_by_directory_id: BTreeMap<String, usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_sessions_open: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_sessions_page: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recent_sessions_open: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recent_sessions_page: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub running_sessions_open: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub running_sessions_page: Option<usize>,
+}
+
+fn record<T: Clone + PartialEq>(slot: &mut Option<T>, before: &T, after: &T) {
+    if before != after {
+        *slot = Some(after.clone());
+    }
+}
+
+impl SidebarDeviceOverrides {
+    /// `global` with this device's overrides applied. The version is the sum
+    /// of both, so it grows whenever either side changes.
+    pub(crate) fn apply_to(
+        &self,
+        global: &SessionsSidebarPreferences,
+    ) -> SessionsSidebarPreferences {
+        let mut out = global.clone();
+        out.version = global.version.saturating_add(self.version);
+        out.updated_at = global.updated_at.max(self.updated_at);
+        if let Some(ids) = &self.collapsed_directory_ids {
+            out.collapsed_directory_ids = ids.clone();
+        }
+        if let Some(ids) = &self.expanded_parent_session_ids {
+            out.expanded_parent_session_ids = ids.clone();
+        }
+        if let Some(page) = self.directories_page {
+            out.directories_page = page;
+        }
+        for (directory_id, page) in &self.session_root_page_by_directory_id {
+            out.session_root_page_by_directory_id
+                .insert(directory_id.clone(), *page);
+        }
+        let footer = [
+            (self.pinned_sessions_open, &mut out.pinned_sessions_open),
+            (self.recent_sessions_open, &mut out.recent_sessions_open),
+            (self.running_sessions_open, &mut out.running_sessions_open),
+        ];
+        for (value, slot) in footer {
+            if let Some(value) = value {
+                *slot = value;
+            }
+        }
+        let pages = [
+            (self.pinned_sessions_page, &mut out.pinned_sessions_page),
+            (self.recent_sessions_page, &mut out.recent_sessions_page),
+            (self.running_sessions_page, &mut out.running_sessions_page),
+        ];
+        for (value, slot) in pages {
+            if let Some(value) = value {
+                *slot = value;
+            }
+        }
+        out
+    }
+
+    /// Keep every layout field that differs between the effective
+    /// preferences before and after a change. Pins are never recorded.
+    pub(crate) fn record_changes(
+        &mut self,
+        before: &SessionsSidebarPreferences,
+        after: &SessionsSidebarPreferences,
+    ) {
+        record(
+            &mut self.collapsed_directory_ids,
+            &before.collapsed_directory_ids,
+            &after.collapsed_directory_ids,
+        );
+        record(
+            &mut self.expanded_parent_session_ids,
+            &before.expanded_parent_session_ids,
+            &after.expanded_parent_session_ids,
+        );
+        record(
+            &mut self.directories_page,
+            &before.directories_page,
+            &after.directories_page,
+        );
+        for (directory_id, page) in &after.session_root_page_by_directory_id {
+            if before.session_root_page_by_directory_id.get(directory_id) != Some(page) {
+                self.session_root_page_by_directory_id
+                    .insert(directory_id.clone(), *page);
+            }
+        }
+        record(
+            &mut self.pinned_sessions_open,
+            &before.pinned_sessions_open,
+            &after.pinned_sessions_open,
+        );
+        record(
+            &mut self.pinned_sessions_page,
+            &before.pinned_sessions_page,
+            &after.pinned_sessions_page,
+        );
+        record(
+            &mut self.recent_sessions_open,
+            &before.recent_sessions_open,
+            &after.recent_sessions_open,
+        );
+        record(
+            &mut self.recent_sessions_page,
+            &before.recent_sessions_page,
+            &after.recent_sessions_page,
+        );
+        record(
+            &mut self.running_sessions_open,
+            &before.running_sessions_open,
+            &after.running_sessions_open,
+        );
+        record(
+            &mut self.running_sessions_page,
+            &before.running_sessions_page,
+            &after.running_sessions_page,
+        );
+    }
+}
+
+type DeviceOverridesMap = BTreeMap<String, SidebarDeviceOverrides>;
+
+static DEVICE_PREFS_CACHE: LazyLock<RwLock<Option<DeviceOverridesMap>>> =
+    LazyLock::new(|| RwLock::new(None));
+static DEVICE_PREFS_PUT_LOCK: LazyLock<AsyncMutex<()>> = LazyLock::new(|| AsyncMutex::new(()));
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn normalize_device_id(raw: &str) -> Option<String> {
+    let id = raw.trim();
+    let valid = !id.is_empty()
+        && id.len() <= MAX_DEVICE_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+    valid.then(|| id.to_string())
+}
+
+/// The device id a client sent, if any. Malformed ids are ignored, so such
+/// clients fall back to the global preferences.
+pub(crate) fn device_id_from_headers(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(DEVICE_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(normalize_device_id)
+}
+
+async fn read_all(db: &StudioDb) -> DeviceOverridesMap {
+    {
+        let guard = DEVICE_PREFS_CACHE.read().await;
+        if let Some(map) = guard.as_ref() {
+            return map.clone();
+        }
+    }
+    let loaded = db
+        .get_json::<DeviceOverridesMap>(studio_db::KV_KEY_CHAT_SIDEBAR_DEVICE_PREFERENCES)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    let mut guard = DEVICE_PREFS_CACHE.write().await;
+    if let Some(existing) = guard.as_ref() {
+        return existing.clone();
+    }
+    *guard = Some(loaded.clone());
+    loaded
+}
+
+async fn write_all(db: &StudioDb, map: DeviceOverridesMap) -> Result<(), String> {
+    db.set_json(studio_db::KV_KEY_CHAT_SIDEBAR_DEVICE_PREFERENCES, &map)
+        .await?;
+    *DEVICE_PREFS_CACHE.write().await = Some(map);
+    Ok(())
+}
+
+pub(crate) async fn device_overrides(db: &StudioDb, device_id: &str) -> SidebarDeviceOverrides {
+    read_all(db).await.remove(device_id).unwrap_or_default()
+}
+
+/// Update one device's overrides and persist the set, evicting the least
+/// recently updated devices beyond `MAX_DEVICES`.
+pub(crate) async fn mutate_device_overrides(
+    db: &StudioDb,
+    device_id: &str,
+    mutator: impl FnOnce(&mut SidebarDeviceOverrides),
+) -> Result<SidebarDeviceOverrides, String> {
+    let _put_guard = DEVICE_PREFS_PUT_LOCK.lock().await;
+    let mut map = read_all(db).await;
+    let mut overrides = map.remove(device_id).unwrap_or_default();
+    mutator(&mut overrides);
+    overrides.version = overrides.version.saturating_add(1);
+    overrides.updated_at = now_millis();
+    map.insert(device_id.to_string(), overrides.clone());
+    while map.len() > MAX_DEVICES {
+        let Some(oldest) = map
+            .iter()
+            .min_by_key(|(_, entry)| entry.updated_at)
+            .map(|(id, _)| id.clone())
+        else {
+            break;
+        };
+        map.remove(&oldest);
+    }
+    write_all(db, map).await?;
+    Ok(overrides)
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SidebarDeviceEntry {
+    pub device_id: String,
+    pub overrides: SidebarDeviceOverrides,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SidebarDevicesResponse {
+    /// Most recently updated first.
+    pub devices: Vec<SidebarDeviceEntry>,
+    /// The calling client's device id, when it sent one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_device_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SidebarDevicesClearResponse {
+    pub cleared: usize,
+}
+
+/// Tell clients their effective preferences changed without a delta to
+/// apply, so they refetch the sidebar state.
+fn publish_cleared(device_id: Option<&str>) {
+    let payload = json!({
+        "type": "chat-sidebar.state",
+        "properties": {
+            "reason": "device-preferences-cleared",
+            "deviceId": device_id,
+        }
+    });
+    crate::global_sse_hub::publish_downstream_json(&payload.to_string());
+}
+
+/// Devices with their own sidebar layout.
+pub(crate) async fn chat_sidebar_devices_list(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+) -> Json<SidebarDevicesResponse> {
+    let mut devices: Vec<SidebarDeviceEntry> = read_all(state.studio_db.as_ref())
+        .await
+        .into_iter()
+        .map(|(device_id, overrides)| SidebarDeviceEntry {
+            device_id,
+            overrides,
+        })
+        .collect();
+    devices.sort_by_key(|entry| std::cmp::Reverse(entry.overrides.updated_at));
+    Json(SidebarDevicesResponse {
+        devices,
+        current_device_id: device_id_from_headers(&headers),
+    })
+}
+
+/// Drop every device's overrides; all clients go back to the global layout.
+pub(crate) async fn chat_sidebar_devices_clear(
+    State(state): State<Arc<crate::AppState>>,
+) -> ApiResult<Json<SidebarDevicesClearResponse>> {
+    let _put_guard = DEVICE_PREFS_PUT_LOCK.lock().await;
+    let cleared = read_all(state.studio_db.as_ref()).await.len();
+    write_all(state.studio_db.as_ref(), DeviceOverridesMap::new())
+        .await
+        .map_err(AppError::internal)?;
+    if cleared > 0 {
+        publish_cleared(None);
+    }
+    Ok(Json(SidebarDevicesClearResponse { cleared }))
+}
+
+/// Drop one device's overrides.
+pub(crate) async fn chat_sidebar_device_delete(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(device_id): AxumPath<String>,
+) -> ApiResult<Json<SidebarDevicesClearResponse>> {
+    let _put_guard = DEVICE_PREFS_PUT_LOCK.lock().await;
+    let mut map = read_all(state.studio_db.as_ref()).await;
+    if map.remove(device_id.trim()).is_none() {
+        return Err(AppError::not_found("Device has no sidebar preferences"));
+    }
+    write_all(state.studio_db.as_ref(), map)
+        .await
+        .map_err(AppError::internal)?;
+    publish_cleared(Some(device_id.trim()));
+    Ok(Json(SidebarDevicesClearResponse { cleared: 1 }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_layout_overrides_global_but_pins_stay_shared() {
+        let global = SessionsSidebarPreferences {
+            version: 4,
+            collapsed_directory_ids: vec!["d1".to_string()],
+            pinned_session_ids: vec!["s1".to_string()],
+            session_root_page_by_directory_id: BTreeMap::from([
+                ("d1".to_string(), 2),
+                ("d2".to_string(), 1),
+            ]),
+            recent_sessions_open: true,
+            ..SessionsSidebarPreferences::default()
+        };
+
+        let mut overrides = SidebarDeviceOverrides::default();
+        let before = overrides.apply_to(&global);
+        let mut after = before.clone();
+        after.collapsed_directory_ids.clear();
+        after
+            .session_root_page_by_directory_id
+            .insert("d2".to_string(), 3);
+        after.recent_sessions_open = false;
+        after.pinned_session_ids.push("s2".to_string());
+        overrides.record_changes(&before, &after);
+        overrides.version = 1;
+
+        assert_eq!(overrides.collapsed_directory_ids, Some(Vec::new()));
+        assert_eq!(overrides.recent_sessions_open, Some(false));
+        assert_eq!(overrides.directories_page, None);
+        assert_eq!(
+            overrides.session_root_page_by_directory_id,
+            BTreeMap::from([("d2".to_string(), 3)])
+        );
+
+        let mut newer_global = global.clone();
+        newer_global.version = 5;
+        newer_global.pinned_session_ids.push("s3".to_string());
+        newer_global.directories_page = 2;
+        newer_global
+            .session_root_page_by_directory_id
+            .insert("d1".to_string(), 5);
+
+        let effective = overrides.apply_to(&newer_global);
+        assert_eq!(effective.version, 6);
+        assert!(effective.collapsed_directory_ids.is_empty());
+        assert!(!effective.recent_sessions_open);
+        assert_eq!(effective.directories_page, 2);
+        assert_eq!(effective.pinned_session_ids, vec!["s1", "s3"]);
+        assert_eq!(effective.session_root_page_by_directory_id["d1"], 5);
+        assert_eq!(effective.session_root_page_by_directory_id["d2"], 3);
+    }
+
+    #[test]
+    fn device_ids_are_validated() {
+        let mut headers = HeaderMap::new();
+        headers.insert(DEVICE_ID_HEADER, " phone-1 ".parse().unwrap());
+        assert_eq!(device_id_from_headers(&headers).as_deref(), Some("phone-1"));
+        headers.insert(DEVICE_ID_HEADER, "bad id/..".parse().unwrap());
+        assert_eq!(device_id_from_headers(&headers), None);
+        assert_eq!(device_id_from_headers(&HeaderMap::new()), None);
+    }
+}
@@ -0,0 +1,276 @@
+//! Client address and scheme as seen through trusted reverse proxies.
+//!
+//! `X-Forwarded-For`, `X-Real-IP` and `X-Forwarded-Proto` are only believed
+//! when the socket peer is in `--trusted-proxy` (loopback by default).
+//! Forwarded-for chains are walked from the right, skipping trusted hops, so a
+//! client cannot prepend its own address.
+
+use std::convert::Infallible;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{LazyLock, RwLock};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{Extensions, HeaderMap, request::Parts},
+};
+use serde::Deserialize;
+
+/// An address range such as `10.0.0.0/8`; a bare address is a single host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub(crate) fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, canonical(ip)) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                prefix_matches(u32::from(net).into(), u32::from(ip).into(), self.prefix, 32)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                prefix_matches(u128::from(net), u128::from(ip), self.prefix, 128)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_matches(net: u128, ip: u128, prefix: u8, bits: u8) -> bool {
+    if prefix == 0 {
+        return true;
+    }
+    let shift = u32::from(bits - prefix);
+    (net >> shift) == (ip >> shift)
+}
+
+/// IPv4-mapped IPv6 addresses (dual-stack sockets) compare as IPv4.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        v4 => v4,
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, String> {
+        let raw = raw.trim();
+        let (addr, prefix) = match raw.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (raw, None),
+        };
+        let addr = canonical(
+            addr.parse::<IpAddr>()
+                .map_err(|_| format!("invalid address in {raw:?}"))?,
+        );
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("invalid prefix length in {raw:?}"))?,
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl TryFrom<String> for Cidr {
+    type Error = String;
+
+    fn try_from(raw: String) -> Result<Self, String> {
+        raw.parse()
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+pub(crate) fn any_contains(list: &[Cidr], ip: IpAddr) -> bool {
+    list.iter().any(|cidr| cidr.contains(ip))
+}
+
+static TRUSTED_PROXIES: LazyLock<RwLock<Vec<Cidr>>> =
+    LazyLock::new(|| RwLock::new(default_trusted_proxies()));
+
+fn default_trusted_proxies() -> Vec<Cidr> {
+    vec![
+        Cidr {
+            addr: IpAddr::from([127, 0, 0, 0]),
+            prefix: 8,
+        },
+        Cidr {
+            addr: IpAddr::from([0u16, 0, 0, 0, 0, 0, 0, 1]),
+            prefix: 128,
+        },
+    ]
+}
+
+pub(crate) fn configure(args: &crate::Args) {
+    *TRUSTED_PROXIES.write().unwrap_or_else(|e| e.into_inner()) = args.trusted_proxies.clone();
+}
+
+/// Where a request came from, after applying trusted proxy headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ClientAddr {
+    /// `None` when the connection has no socket address (in-process tests).
+    pub ip: Option<IpAddr>,
+    /// The client reached us over HTTPS, directly or via a trusted proxy.
+    pub secure: bool,
+    /// The peer is a trusted proxy, so forwarded headers were honoured.
+    pub via_trusted_proxy: bool,
+}
+
+impl ClientAddr {
+    pub(crate) fn from_request(extensions: &Extensions, headers: &HeaderMap) -> Self {
+        let peer = extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip());
+        let trusted = TRUSTED_PROXIES.read().unwrap_or_else(|e| e.into_inner());
+        resolve(&trusted, peer, headers, crate::tls_server::serving_https())
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn parse_ip(raw: &str) -> Option<IpAddr> {
+    let raw = raw.trim().trim_matches('"');
+    let raw = raw
+        .strip_prefix('[')
+        .and_then(|r| r.split_once(']'))
+        .map_or(raw, |(ip, _)| ip);
+    raw.parse::<IpAddr>()
+        .ok()
+        .or_else(|| raw.parse::<SocketAddr>().ok().map(|s| s.ip()))
+        .map(canonical)
+}
+
+fn resolve(
+    trusted: &[Cidr],
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    serving_https: bool,
+) -> ClientAddr {
+    let peer = peer.map(canonical);
+    let via_trusted_proxy = peer.is_some_and(|ip| any_contains(trusted, ip));
+    if !via_trusted_proxy {
+        return ClientAddr {
+            ip: peer,
+            secure: serving_https,
+            via_trusted_proxy,
+        };
+    }
+
+    let mut ip = peer;
+    if let Some(chain) = header(headers, "x-forwarded-for") {
+        let hops: Vec<IpAddr> = chain.split(',').filter_map(parse_ip).collect();
+        // Rightmost hop that is not one of our proxies; the leftmost when
+        // every hop is trusted.
+        ip = hops
+            .iter()
+            .rev()
+            .find(|hop| !any_contains(trusted, **hop))
+            .or(hops.first())
+            .copied()
+            .or(ip);
+    } else if let Some(real) = header(headers, "x-real-ip").and_then(parse_ip) {
+        ip = Some(real);
+    }
+    let forwarded_https = header(headers, "x-forwarded-proto")
+        .and_then(|v| v.split(',').next())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("https"));
+    ClientAddr {
+        ip,
+        secure: serving_https || forwarded_https,
+        via_trusted_proxy,
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientAddr {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_request(&parts.extensions, &parts.headers))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cidr(raw: &str) -> Cidr {
+        raw.parse().expect("cidr")
+    }
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().expect("ip")
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, value.parse().unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn cidrs_parse_and_match() {
+        assert!(cidr("10.0.0.0/8").contains(ip("10.1.2.3")));
+        assert!(!cidr("10.0.0.0/8").contains(ip("11.0.0.1")));
+        assert!(cidr("192.168.1.7").contains(ip("::ffff:192.168.1.7")));
+        assert!(cidr("fd00::/8").contains(ip("fd12::1")));
+        assert!(cidr("0.0.0.0/0").contains(ip("8.8.8.8")));
+        assert!(!cidr("0.0.0.0/0").contains(ip("::2")));
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("example.com".parse::<Cidr>().is_err());
+        assert_eq!(cidr("::1").to_string(), "::1/128");
+    }
+
+    #[test]
+    fn forwarded_headers_only_count_from_trusted_peers() {
+        let trusted = vec![cidr("127.0.0.0/8"), cidr("10.0.0.0/8")];
+        let forwarded = headers(&[
+            ("x-forwarded-for", "6.6.6.6, 203.0.113.9, 10.0.0.2"),
+            ("x-forwarded-proto", "https"),
+        ]);
+
+        let direct = resolve(&trusted, Some(ip("198.51.100.1")), &forwarded, false);
+        assert_eq!(direct.ip, Some(ip("198.51.100.1")));
+        assert!(!direct.secure);
+        assert!(!direct.via_trusted_proxy);
+
+        let proxied = resolve(&trusted, Some(ip("127.0.0.1")), &forwarded, false);
+        assert_eq!(proxied.ip, Some(ip("203.0.113.9")));
+        assert!(proxied.secure);
+
+        let real_ip = resolve(
+            &trusted,
+            Some(ip("::ffff:10.0.0.5")),
+            &headers(&[("x-real-ip", "[2001:db8::1]:443")]),
+            false,
+        );
+        assert_eq!(real_ip.ip, Some(ip("2001:db8::1")));
+        assert!(!real_ip.secure);
+
+        let all_trusted = resolve(
+            &trusted,
+            Some(ip("127.0.0.1")),
+            &headers(&[("x-forwarded-for", "10.0.0.9, 10.0.0.2")]),
+            true,
+        );
+        assert_eq!(all_trusted.ip, Some(ip("10.0.0.9")));
+        assert!(all_trusted.secure);
+    }
+}
@@ -0,0 +1,127 @@
+//! gzip/brotli compression for API responses.
+//!
+//! Event streams, websocket upgrades, file downloads and bodies that are
+//! already compressed (images, archives, upstream `Content-Encoding`) pass
+//! through untouched.
+
+use axum::{
+    extract::Request,
+    http::{Extensions, HeaderMap, StatusCode, Version, header},
+    middleware::Next,
+    response::Response,
+};
+use tower_http::compression::{
+    CompressionLayer, Predicate,
+    predicate::{NotForContentType, SizeAbove},
+};
+
+pub(crate) const DEFAULT_MIN_BYTES: u16 = 1024;
+
+/// Response extension marking a route whose body must be streamed as-is.
+#[derive(Debug, Clone, Copy)]
+struct SkipCompression;
+
+// Media and archive formats gain nothing from another compression pass.
+const ALREADY_COMPRESSED_TYPES: [&str; 9] = [
+    "video/",
+    "audio/",
+    "font/woff",
+    "application/zip",
+    "application/gzip",
+    "application/x-gzip",
+    "application/zstd",
+    "application/x-xz",
+    "application/x-7z-compressed",
+];
+
+fn is_uncompressed_path(path: &str) -> bool {
+    let Some(api_path) = path.strip_prefix("/api") else {
+        return false;
+    };
+    matches!(
+        api_path,
+        "/event" | "/global/event" | "/ws/events" | "/global/ws" | "/fs/download"
+    ) || (api_path.starts_with("/terminal/")
+        && (api_path.ends_with("/stream") || api_path.ends_with("/download")))
+}
+
+/// Tag responses of streaming/download routes so the compression layer skips them.
+pub(crate) async fn mark_uncompressed_routes(req: Request, next: Next) -> Response {
+    let skip = is_uncompressed_path(req.uri().path());
+    let mut resp = next.run(req).await;
+    if skip {
+        resp.extensions_mut().insert(SkipCompression);
+    }
+    resp
+}
+
+fn compressible(
+    _status: StatusCode,
+    _version: Version,
+    headers: &HeaderMap,
+    extensions: &Extensions,
+) -> bool {
+    if extensions.get::<SkipCompression>().is_some() || headers.contains_key(header::UPGRADE) {
+        return false;
+    }
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    !ALREADY_COMPRESSED_TYPES
+        .iter()
+        .any(|prefix| content_type.starts_with(prefix))
+}
+
+fn predicate(min_bytes: u16) -> impl Predicate {
+    SizeAbove::new(min_bytes)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE)
+        .and(compressible as fn(StatusCode, Version, &HeaderMap, &Extensions) -> bool)
+}
+
+/// Compression layer for the app, or `None` when disabled.
+pub(crate) fn layer(enabled: bool, min_bytes: u16) -> Option<CompressionLayer<impl Predicate>> {
+    enabled.then(|| {
+        CompressionLayer::new()
+            .no_deflate()
+            .no_zstd()
+            .compress_when(predicate(min_bytes))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(content_type: &str, len: usize) -> axum::http::Response<String> {
+        axum::http::Response::builder()
+            .header(header::CONTENT_TYPE, content_type)
+            .body("x".repeat(len))
+            .unwrap()
+    }
+
+    #[test]
+    fn excludes_streaming_and_download_routes() {
+        assert!(is_uncompressed_path("/api/global/event"));
+        assert!(is_uncompressed_path("/api/terminal/abc/stream"));
+        assert!(is_uncompressed_path("/api/fs/download"));
+        assert!(!is_uncompressed_path("/api/session/ses_1/message"));
+        assert!(!is_uncompressed_path("/event"));
+    }
+
+    #[test]
+    fn compresses_large_json_only() {
+        let predicate = predicate(DEFAULT_MIN_BYTES);
+        assert!(predicate.should_compress(&response("application/json", 4096)));
+        assert!(!predicate.should_compress(&response("application/json", 100)));
+        assert!(!predicate.should_compress(&response("text/event-stream", 4096)));
+        assert!(!predicate.should_compress(&response("image/png", 4096)));
+        assert!(!predicate.should_compress(&response("application/zip", 4096)));
+
+        let mut marked = response("application/json", 4096);
+        marked.extensions_mut().insert(SkipCompression);
+        assert!(!predicate.should_compress(&marked));
+    }
+}
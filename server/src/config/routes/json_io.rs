@@ -19,12 +19,6 @@ pub(super) async fn read_jsonc_value(path: &Path) -> Result<Value, String> {
 }
 
 pub(super) async fn write_json_value(path: &Path, value: &Value) -> Result<(), String> {
-    if let Some(parent) = path.parent() {
-        tokio::fs::create_dir_all(parent)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
-
     // Saving JSONC via this endpoint rewrites as JSON and strips comments.
     // Create a timestamped backup when overwriting an existing .jsonc file.
     if path.exists() && path.extension().and_then(|s| s.to_str()) == Some("jsonc") {
@@ -41,8 +35,18 @@ pub(super) async fn write_json_value(path: &Path, value: &Value) -> Result<(), S
     }
 
     let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    write_text(path, &json).await
+}
+
+/// Replace `path` with `contents` via a temp file and rename.
+pub(super) async fn write_text(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
     let tmp = path.with_extension("json.tmp");
-    tokio::fs::write(&tmp, json)
+    tokio::fs::write(&tmp, contents)
         .await
         .map_err(|e| e.to_string())?;
     tokio::fs::rename(&tmp, path)
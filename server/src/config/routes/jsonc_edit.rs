@@ -0,0 +1,392 @@
+//! In-place edits of JSONC config text.
+//!
+//! Values are spliced into the original text so comments, key order and
+//! indentation elsewhere in the file survive. Anything the scanner does not
+//! understand returns `None` and the caller falls back to a full rewrite.
+
+use serde_json::Value;
+
+struct Member {
+    key: String,
+    key_start: usize,
+    value_start: usize,
+    value_end: usize,
+}
+
+/// Set (`Some`) or remove (`None`) the value at `path` in `root`, creating
+/// intermediate objects as needed.
+pub(super) fn apply_to_value(
+    root: &mut Value,
+    path: &[String],
+    value: Option<&Value>,
+) -> Result<(), String> {
+    let Some((last, parents)) = path.split_last() else {
+        return Err("edit path must not be empty".to_string());
+    };
+    let mut node = root;
+    for (depth, key) in parents.iter().enumerate() {
+        let Value::Object(map) = node else {
+            return Err(format!("{} is not an object", path[..depth].join(".")));
+        };
+        if value.is_none() && !map.contains_key(key) {
+            return Ok(());
+        }
+        node = map
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()));
+    }
+    let Value::Object(map) = node else {
+        return Err(format!("{} is not an object", parents.join(".")));
+    };
+    match value {
+        Some(value) => {
+            map.insert(last.clone(), value.clone());
+        }
+        None => {
+            map.remove(last);
+        }
+    }
+    Ok(())
+}
+
+/// Text counterpart of [`apply_to_value`].
+pub(super) fn edit_text(text: &str, path: &[String], value: Option<&Value>) -> Option<String> {
+    let src = text.as_bytes();
+    let mut open = skip_trivia(src, 0);
+    if src.get(open) != Some(&b'{') {
+        return None;
+    }
+    for (depth, key) in path.iter().enumerate() {
+        let (members, close) = object_members(text, open)?;
+        let Some(idx) = members.iter().position(|m| &m.key == key) else {
+            let Some(value) = value else {
+                return Some(text.to_string());
+            };
+            let nested = path[depth + 1..]
+                .iter()
+                .rev()
+                .fold(value.clone(), |acc, key| {
+                    let mut map = serde_json::Map::new();
+                    map.insert(key.clone(), acc);
+                    Value::Object(map)
+                });
+            return insert_member(text, open, close, &members, key, &nested);
+        };
+        let member = &members[idx];
+        if depth + 1 < path.len() {
+            if src[member.value_start] != b'{' {
+                return None;
+            }
+            open = member.value_start;
+            continue;
+        }
+        return match value {
+            Some(value) => {
+                let rendered = render(value, line_indent(text, member.key_start));
+                Some(splice(
+                    text,
+                    member.value_start,
+                    member.value_end,
+                    &rendered,
+                ))
+            }
+            None => Some(remove_member(text, &members, idx)),
+        };
+    }
+    None
+}
+
+fn insert_member(
+    text: &str,
+    open: usize,
+    close: usize,
+    members: &[Member],
+    key: &str,
+    value: &Value,
+) -> Option<String> {
+    let src = text.as_bytes();
+    let key = serde_json::to_string(key).ok()?;
+    let Some(last) = members.last() else {
+        if !text[open + 1..close].trim().is_empty() {
+            // Only comments inside; keep it simple and rewrite instead.
+            return None;
+        }
+        let outer = line_indent(text, open);
+        let indent = format!("{outer}  ");
+        let entry = format!("\n{indent}{key}: {}\n{outer}", render(value, &indent));
+        return Some(splice(text, open + 1, close, &entry));
+    };
+
+    let indent = line_indent(text, members[0].key_start);
+    let entry = format!("{key}: {}", render(value, indent));
+    let after_last = skip_trivia(src, last.value_end);
+    let has_comma = src.get(after_last) == Some(&b',');
+    let close_line = line_start(text, close);
+    if text[close_line..close].trim().is_empty() && close_line > last.value_end {
+        // Closing brace on its own line: add a line above it.
+        let out = splice(
+            text,
+            close_line,
+            close_line,
+            &format!("{indent}{entry}{}\n", if has_comma { "," } else { "" }),
+        );
+        return Some(if has_comma {
+            out
+        } else {
+            splice(&out, last.value_end, last.value_end, ",")
+        });
+    }
+    Some(if has_comma {
+        splice(text, after_last + 1, after_last + 1, &format!(" {entry},"))
+    } else {
+        splice(text, last.value_end, last.value_end, &format!(", {entry}"))
+    })
+}
+
+fn remove_member(text: &str, members: &[Member], idx: usize) -> String {
+    let src = text.as_bytes();
+    let member = &members[idx];
+    let after = skip_trivia(src, member.value_end);
+    if src.get(after) == Some(&b',') {
+        let mut start = member.key_start;
+        let ls = line_start(text, start);
+        if text[ls..start].trim().is_empty() {
+            start = ls;
+        }
+        let mut end = after + 1;
+        let le = text[end..].find('\n').map_or(text.len(), |n| end + n);
+        if text[end..le].trim().is_empty() {
+            end = (le + 1).min(text.len());
+        }
+        return splice(text, start, end, "");
+    }
+    match idx.checked_sub(1) {
+        // Last member without a trailing comma: drop the separator before it.
+        Some(prev) => splice(text, members[prev].value_end, member.value_end, ""),
+        None => splice(text, member.key_start, member.value_end, ""),
+    }
+}
+
+fn object_members(text: &str, open: usize) -> Option<(Vec<Member>, usize)> {
+    let src = text.as_bytes();
+    let mut members = Vec::new();
+    let mut i = skip_trivia(src, open + 1);
+    loop {
+        match src.get(i)? {
+            b'}' => return Some((members, i)),
+            b'"' | b'\'' => {
+                let end = string_end(src, i)?;
+                let key = json5::from_str::<String>(&text[i..end]).ok()?;
+                members.push(Member {
+                    key,
+                    key_start: i,
+                    value_start: 0,
+                    value_end: end,
+                });
+            }
+            c if c.is_ascii_alphabetic() || *c == b'_' || *c == b'$' => {
+                let end = i + src[i..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || **c == b'_' || **c == b'$')
+                    .count();
+                members.push(Member {
+                    key: text[i..end].to_string(),
+                    key_start: i,
+                    value_start: 0,
+                    value_end: end,
+                });
+            }
+            _ => return None,
+        }
+        let member = members.last_mut()?;
+        i = skip_trivia(src, member.value_end);
+        if src.get(i) != Some(&b':') {
+            return None;
+        }
+        member.value_start = skip_trivia(src, i + 1);
+        member.value_end = value_end(src, member.value_start)?;
+        i = skip_trivia(src, member.value_end);
+        match src.get(i)? {
+            b',' => i = skip_trivia(src, i + 1),
+            b'}' => {}
+            _ => return None,
+        }
+    }
+}
+
+fn skip_trivia(src: &[u8], mut i: usize) -> usize {
+    loop {
+        while i < src.len() && src[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if src[i..].starts_with(b"//") {
+            while i < src.len() && src[i] != b'\n' {
+                i += 1;
+            }
+        } else if src[i..].starts_with(b"/*") {
+            match src[i + 2..].windows(2).position(|w| w == b"*/") {
+                Some(n) => i += n + 4,
+                None => return src.len(),
+            }
+        } else {
+            return i;
+        }
+    }
+}
+
+fn string_end(src: &[u8], start: usize) -> Option<usize> {
+    let quote = src[start];
+    let mut i = start + 1;
+    while i < src.len() {
+        match src[i] {
+            b'\\' => i += 2,
+            c if c == quote => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn value_end(src: &[u8], start: usize) -> Option<usize> {
+    match src.get(start)? {
+        b'"' | b'\'' => string_end(src, start),
+        b'{' | b'[' => {
+            let mut depth = 0usize;
+            let mut i = start;
+            while i < src.len() {
+                match src[i] {
+                    b'"' | b'\'' => {
+                        i = string_end(src, i)?;
+                        continue;
+                    }
+                    b'/' if matches!(src.get(i + 1), Some(b'/' | b'*')) => {
+                        i = skip_trivia(src, i);
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(i + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            None
+        }
+        _ => {
+            let mut i = start;
+            while i < src.len()
+                && !matches!(src[i], b',' | b'}' | b']')
+                && !src[i].is_ascii_whitespace()
+                && !src[i..].starts_with(b"//")
+                && !src[i..].starts_with(b"/*")
+            {
+                i += 1;
+            }
+            (i > start).then_some(i)
+        }
+    }
+}
+
+fn line_start(text: &str, pos: usize) -> usize {
+    text[..pos].rfind('\n').map_or(0, |n| n + 1)
+}
+
+fn line_indent(text: &str, pos: usize) -> &str {
+    let start = line_start(text, pos);
+    let line = &text[start..pos];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+fn render(value: &Value, indent: &str) -> String {
+    let pretty = serde_json::to_string_pretty(value).unwrap_or_else(|_| "null".to_string());
+    pretty.replace('\n', &format!("\n{indent}"))
+}
+
+fn splice(text: &str, start: usize, end: usize, insert: &str) -> String {
+    let mut out = String::with_capacity(text.len() + insert.len());
+    out.push_str(&text[..start]);
+    out.push_str(insert);
+    out.push_str(&text[end..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SAMPLE: &str = r#"{
+  // Default model for new sessions.
+  "$schema": "https://opencode.ai/config.json",
+  model: 'anthropic/claude-sonnet-4-5', /* keep */
+  "agent": {
+    "build": { "model": "a/b" },
+  },
+  "mcp": {}
+}
+"#;
+
+    fn path(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn edited(text: &str, parts: &[&str], value: Option<Value>) -> (String, Value) {
+        let out = edit_text(text, &path(parts), value.as_ref()).expect("edit");
+        let mut expected: Value = json5::from_str(text).expect("parse input");
+        apply_to_value(&mut expected, &path(parts), value.as_ref()).expect("apply");
+        let parsed: Value = json5::from_str(&out).expect("parse output");
+        assert_eq!(parsed, expected, "{out}");
+        (out, parsed)
+    }
+
+    #[test]
+    fn replaces_existing_values_and_keeps_comments() {
+        let (out, _) = edited(SAMPLE, &["model"], Some(json!("openai/gpt-5")));
+        assert!(out.contains("// Default model for new sessions."));
+        assert!(out.contains("\"openai/gpt-5\", /* keep */"));
+
+        let (out, _) = edited(
+            SAMPLE,
+            &["agent", "build"],
+            Some(json!({ "model": "x/y", "temperature": 0.2 })),
+        );
+        assert!(out.contains("    \"build\": {\n      \"model\": \"x/y\""));
+    }
+
+    #[test]
+    fn inserts_missing_members_and_parents() {
+        let (out, _) = edited(SAMPLE, &["agent", "plan"], Some(json!({ "model": "c/d" })));
+        assert!(out.contains("    \"plan\": {"), "{out}");
+
+        edited(
+            SAMPLE,
+            &["mcp", "docs"],
+            Some(json!({ "type": "remote", "url": "https://x" })),
+        );
+        edited(SAMPLE, &["provider", "openai", "models"], Some(json!({})));
+        edited("{}", &["model"], Some(json!("a/b")));
+        edited("{ \"a\": 1 }", &["b"], Some(json!(2)));
+    }
+
+    #[test]
+    fn removes_members_with_their_separator() {
+        let (out, _) = edited(SAMPLE, &["model"], None);
+        assert!(!out.contains("anthropic"));
+        edited(SAMPLE, &["mcp"], None);
+        edited(SAMPLE, &["agent", "build"], None);
+        edited(SAMPLE, &["agent", "missing"], None);
+        edited("{ \"a\": 1 }", &["a"], None);
+    }
+
+    #[test]
+    fn bails_out_on_unsupported_shapes() {
+        assert!(edit_text("[1, 2]", &path(&["a"]), Some(&json!(1))).is_none());
+        assert!(edit_text("{ \"a\": 1 }", &path(&["a", "b"]), Some(&json!(1))).is_none());
+        let mut value = json!({ "a": 1 });
+        assert!(apply_to_value(&mut value, &path(&["a", "b"]), Some(&json!(1))).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock};
 
 use axum::{
     Json,
@@ -7,20 +7,26 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tokio::sync::Mutex as AsyncMutex;
 
 use crate::{opencode_config, opencode_config_model::OpenCodeConfig};
 
-use super::json_io::{read_jsonc_value, write_json_value};
+use super::json_io::{read_jsonc_value, write_json_value, write_text};
+use super::jsonc_edit::{apply_to_value, edit_text};
 use super::utils::resolve_directory_path_no_fs;
 
 const CLIENT_RELOAD_DELAY_MS: i64 = 800;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct OpencodeConfigQuery {
     pub directory: Option<String>,
     pub scope: Option<String>,
+    /// Validate and return the resulting config without writing it.
+    #[serde(default, rename = "dryRun")]
+    pub dry_run: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -41,6 +47,88 @@ pub struct OpencodeConfigResponse {
     pub paths: OpencodeConfigPaths,
 }
 
+/// One change to the config file. `path` names object keys from the root,
+/// e.g. `["agent", "build"]`, `["mcp", "docs"]` or `["model"]`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OpencodeConfigEdit {
+    pub path: Vec<String>,
+    /// New value; omit together with `remove: true` to delete the key.
+    #[serde(default)]
+    pub value: Option<Value>,
+    #[serde(default)]
+    pub remove: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OpencodeConfigPatchBody {
+    pub edits: Vec<OpencodeConfigEdit>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpencodeConfigPatchResponse {
+    #[serde(flatten)]
+    pub config: OpencodeConfigResponse,
+    pub dry_run: bool,
+    /// The file had to be re-serialized, dropping comments and layout. A
+    /// `.bak` copy is kept for `.jsonc` files.
+    pub rewritten: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfigIssue {
+    pub path: Vec<String>,
+    pub message: String,
+}
+
+const MAX_CONFIG_EDITS: usize = 64;
+const MAX_EDIT_PATH_DEPTH: usize = 8;
+
+// Sections keyed by a user-chosen name; validation errors name the entry.
+const KEYED_SECTIONS: [&str; 4] = ["agent", "command", "mcp", "provider"];
+
+// Serializes read-modify-write cycles on config files.
+static CONFIG_WRITE_LOCK: LazyLock<AsyncMutex<()>> = LazyLock::new(|| AsyncMutex::new(()));
+
+struct ConfigTarget {
+    scope: &'static str,
+    directory: Option<PathBuf>,
+    paths: opencode_config::ConfigPaths,
+    path: PathBuf,
+}
+
+impl ConfigTarget {
+    fn response(&self, exists: bool, config: Value) -> OpencodeConfigResponse {
+        OpencodeConfigResponse {
+            scope: self.scope.to_string(),
+            path: Some(self.path.to_string_lossy().into_owned()),
+            exists,
+            config,
+            paths: OpencodeConfigPaths {
+                user: self.paths.user_path.to_string_lossy().into_owned(),
+                project: self
+                    .paths
+                    .project_path
+                    .as_ref()
+                    .map(|p| p.to_string_lossy().into_owned()),
+                custom: self
+                    .paths
+                    .custom_path
+                    .as_ref()
+                    .map(|p| p.to_string_lossy().into_owned()),
+            },
+        }
+    }
+}
+
+fn error_response(status: StatusCode, error: impl Into<String>) -> Response {
+    (status, Json(serde_json::json!({"error": error.into()}))).into_response()
+}
+
 fn resolve_config_scope(raw: Option<&str>) -> Result<&'static str, String> {
     match raw.unwrap_or("user").trim() {
         "user" => Ok("user"),
@@ -50,20 +138,9 @@ fn resolve_config_scope(raw: Option<&str>) -> Result<&'static str, String> {
     }
 }
 
-pub async fn config_opencode_get(
-    State(_state): State<Arc<crate::AppState>>,
-    Query(query): Query<OpencodeConfigQuery>,
-) -> Response {
-    let scope = match resolve_config_scope(query.scope.as_deref()) {
-        Ok(v) => v,
-        Err(err) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": err})),
-            )
-                .into_response();
-        }
-    };
+fn resolve_config_target(query: &OpencodeConfigQuery) -> Result<ConfigTarget, Box<Response>> {
+    let scope = resolve_config_scope(query.scope.as_deref())
+        .map_err(|err| Box::new(error_response(StatusCode::BAD_REQUEST, err)))?;
 
     let directory = query
         .directory
@@ -72,110 +149,140 @@ pub async fn config_opencode_get(
         .map(PathBuf::from);
 
     if scope == "project" && directory.is_none() {
-        return (
+        return Err(Box::new(error_response(
             StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({"error": "project scope requires directory"})),
-        )
-            .into_response();
+            "project scope requires directory",
+        )));
     }
 
     let store = opencode_config::OpenCodeConfigStore::from_env();
     let paths = store.get_config_paths(directory.as_deref());
-    let target_path = match scope {
+    let path = match scope {
         "user" => Some(paths.user_path.clone()),
         "project" => paths.project_path.clone(),
         "custom" => paths.custom_path.clone(),
         _ => None,
     };
-
-    let Some(target_path) = target_path else {
-        return (
+    let Some(path) = path else {
+        return Err(Box::new(error_response(
             StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({"error": "config scope not available"})),
-        )
-            .into_response();
+            "config scope not available",
+        )));
     };
 
-    let exists = target_path.exists();
-    let config = match read_jsonc_value(&target_path).await {
-        Ok(v) => v,
-        Err(err) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": err})),
-            )
-                .into_response();
+    Ok(ConfigTarget {
+        scope,
+        directory,
+        paths,
+        path,
+    })
+}
+
+fn config_issue(path: Vec<String>, err: serde_json::Error) -> ConfigIssue {
+    ConfigIssue {
+        path,
+        message: err.to_string(),
+    }
+}
+
+/// Check `config` against the typed model. serde loses the location of
+/// errors below flattened structs, so failing sections are re-checked on
+/// their own to name the offending key.
+fn validate_config(config: &Value) -> Result<(), ConfigIssue> {
+    let err = match serde_json::from_value::<OpenCodeConfig>(config.clone()) {
+        Ok(parsed) => {
+            return parsed.validate().map_err(|message| ConfigIssue {
+                path: vec!["lsp".to_string()],
+                message,
+            });
         }
+        Err(err) => err,
+    };
+    let Value::Object(map) = config else {
+        return Err(config_issue(Vec::new(), err));
     };
+    for (key, value) in map {
+        let section = serde_json::json!({ key: value });
+        let Err(section_err) = serde_json::from_value::<OpenCodeConfig>(section) else {
+            continue;
+        };
+        if let Value::Object(entries) = value
+            && KEYED_SECTIONS.contains(&key.as_str())
+        {
+            for (name, entry) in entries {
+                let single = serde_json::json!({ key: { name: entry } });
+                if let Err(entry_err) = serde_json::from_value::<OpenCodeConfig>(single) {
+                    return Err(config_issue(vec![key.clone(), name.clone()], entry_err));
+                }
+            }
+        }
+        return Err(config_issue(vec![key.clone()], section_err));
+    }
+    Err(config_issue(Vec::new(), err))
+}
 
-    let response = OpencodeConfigResponse {
-        scope: scope.to_string(),
-        path: Some(target_path.to_string_lossy().into_owned()),
-        exists,
-        config,
-        paths: OpencodeConfigPaths {
-            user: paths.user_path.to_string_lossy().into_owned(),
-            project: paths.project_path.map(|p| p.to_string_lossy().into_owned()),
-            custom: paths.custom_path.map(|p| p.to_string_lossy().into_owned()),
-        },
+fn invalid_config_response(issue: ConfigIssue) -> Response {
+    let location = if issue.path.is_empty() {
+        String::new()
+    } else {
+        format!(" at {}", issue.path.join("."))
     };
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "error": format!("invalid opencode config{location}: {}", issue.message),
+            "code": "invalid_opencode_config",
+            "errors": [issue],
+        })),
+    )
+        .into_response()
+}
 
-    Json(response).into_response()
+async fn refresh_plugin_runtime(state: &crate::AppState, target: &ConfigTarget) {
+    if let Err(err) = state
+        .plugin_runtime
+        .refresh_from_opencode_config_layers(target.directory.as_deref())
+        .await
+    {
+        tracing::warn!(
+            target: "opencode_studio.plugin_runtime",
+            scope = target.scope,
+            error = %err,
+            "failed to refresh plugin runtime after opencode config update"
+        );
+    }
 }
 
-pub async fn config_opencode_put(
-    State(state): State<Arc<crate::AppState>>,
+pub async fn config_opencode_get(
+    State(_state): State<Arc<crate::AppState>>,
     Query(query): Query<OpencodeConfigQuery>,
-    Json(body): Json<Value>,
 ) -> Response {
-    let scope = match resolve_config_scope(query.scope.as_deref()) {
-        Ok(v) => v,
-        Err(err) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": err})),
-            )
-                .into_response();
-        }
+    let target = match resolve_config_target(&query) {
+        Ok(target) => target,
+        Err(resp) => return *resp,
     };
 
-    let directory = query
-        .directory
-        .as_deref()
-        .and_then(resolve_directory_path_no_fs)
-        .map(PathBuf::from);
-
-    if scope == "project" && directory.is_none() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({"error": "project scope requires directory"})),
-        )
-            .into_response();
-    }
-
-    let store = opencode_config::OpenCodeConfigStore::from_env();
-    let paths = store.get_config_paths(directory.as_deref());
-    let target_path = match scope {
-        "user" => Some(paths.user_path.clone()),
-        "project" => paths.project_path.clone(),
-        "custom" => paths.custom_path.clone(),
-        _ => None,
+    let exists = target.path.exists();
+    let config = match read_jsonc_value(&target.path).await {
+        Ok(v) => v,
+        Err(err) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, err),
     };
 
-    let Some(target_path) = target_path else {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({"error": "config scope not available"})),
-        )
-            .into_response();
+    Json(target.response(exists, config)).into_response()
+}
+
+pub async fn config_opencode_put(
+    State(state): State<Arc<crate::AppState>>,
+    Query(query): Query<OpencodeConfigQuery>,
+    Json(body): Json<Value>,
+) -> Response {
+    let target = match resolve_config_target(&query) {
+        Ok(target) => target,
+        Err(resp) => return *resp,
     };
 
     if !body.is_object() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({"error": "config must be a JSON object"})),
-        )
-            .into_response();
+        return error_response(StatusCode::BAD_REQUEST, "config must be a JSON object");
     }
 
     let mut config = body;
@@ -184,62 +291,135 @@ pub async fn config_opencode_put(
             .or_insert_with(|| Value::String("https://opencode.ai/config.json".to_string()));
     }
 
-    let parsed = match serde_json::from_value::<OpenCodeConfig>(config.clone()) {
-        Ok(v) => v,
-        Err(err) => {
-            return (
+    if let Err(issue) = validate_config(&config) {
+        return invalid_config_response(issue);
+    }
+    if query.dry_run {
+        return Json(target.response(target.path.exists(), config)).into_response();
+    }
+
+    let _write_guard = CONFIG_WRITE_LOCK.lock().await;
+    if let Err(err) = write_json_value(&target.path, &config).await {
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, err);
+    }
+
+    refresh_plugin_runtime(&state, &target).await;
+    crate::model_catalog::invalidate();
+
+    Json(target.response(true, config)).into_response()
+}
+
+/// Apply targeted edits (agents, MCP servers, models, ...) to one config
+/// file. Edits are written into the existing text so comments and layout
+/// survive; the whole file is only re-serialized when that is not possible.
+pub async fn config_opencode_patch(
+    State(state): State<Arc<crate::AppState>>,
+    Query(query): Query<OpencodeConfigQuery>,
+    Json(body): Json<OpencodeConfigPatchBody>,
+) -> Response {
+    let target = match resolve_config_target(&query) {
+        Ok(target) => target,
+        Err(resp) => return *resp,
+    };
+    let dry_run = body.dry_run || query.dry_run;
+
+    if body.edits.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "no edits");
+    }
+    if body.edits.len() > MAX_CONFIG_EDITS {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            format!("at most {MAX_CONFIG_EDITS} edits per request"),
+        );
+    }
+    for edit in &body.edits {
+        if edit.path.is_empty() || edit.path.len() > MAX_EDIT_PATH_DEPTH {
+            return error_response(
                 StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": format!("invalid opencode config: {err}")})),
-            )
-                .into_response();
+                format!("edit path must have 1 to {MAX_EDIT_PATH_DEPTH} keys"),
+            );
         }
+        if edit.remove == edit.value.is_some() {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "edit {} must set either value or remove",
+                    edit.path.join(".")
+                ),
+            );
+        }
+    }
+
+    let _write_guard = CONFIG_WRITE_LOCK.lock().await;
+    let exists = target.path.exists();
+    let original = if exists {
+        match tokio::fs::read_to_string(&target.path).await {
+            Ok(raw) => raw,
+            Err(err) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+        }
+    } else {
+        String::new()
     };
-    if let Err(err) = parsed.validate() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({"error": format!("invalid opencode config: {err}")})),
-        )
-            .into_response();
+    let mut config = match read_jsonc_value(&target.path).await {
+        Ok(v) => v,
+        Err(err) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, err),
+    };
+    if !config.is_object() {
+        return error_response(StatusCode::CONFLICT, "existing config is not a JSON object");
+    }
+    if !exists && let Value::Object(obj) = &mut config {
+        obj.insert(
+            "$schema".to_string(),
+            Value::String("https://opencode.ai/config.json".to_string()),
+        );
     }
 
-    if let Err(err) = write_json_value(&target_path, &config).await {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"error": err})),
-        )
-            .into_response();
+    // Keep a text copy in step with the value; drop it once an edit cannot
+    // be expressed in place.
+    let mut text = (!original.trim().is_empty()).then(|| original.clone());
+    for edit in &body.edits {
+        let value = if edit.remove {
+            None
+        } else {
+            edit.value.as_ref()
+        };
+        if let Err(err) = apply_to_value(&mut config, &edit.path, value) {
+            return error_response(StatusCode::BAD_REQUEST, err);
+        }
+        text = text.and_then(|text| edit_text(&text, &edit.path, value));
     }
 
-    if let Err(err) = state
-        .plugin_runtime
-        .refresh_from_opencode_config_layers(directory.as_deref())
-        .await
-    {
-        tracing::warn!(
-            target: "opencode_studio.plugin_runtime",
-            scope,
-            error = %err,
-            "failed to refresh plugin runtime after opencode config update"
-        );
+    if let Err(issue) = validate_config(&config) {
+        return invalid_config_response(issue);
     }
 
-    let response = OpencodeConfigResponse {
-        scope: scope.to_string(),
-        path: Some(target_path.to_string_lossy().into_owned()),
-        exists: true,
-        config,
-        paths: OpencodeConfigPaths {
-            user: paths.user_path.to_string_lossy().into_owned(),
-            project: paths.project_path.map(|p| p.to_string_lossy().into_owned()),
-            custom: paths.custom_path.map(|p| p.to_string_lossy().into_owned()),
-        },
-    };
+    // Trust the in-place result only if it reads back as the intended config.
+    let text = text.filter(|text| json5::from_str::<Value>(text).is_ok_and(|v| v == config));
+    let rewritten = exists && text.is_none();
+
+    if !dry_run {
+        let written = match &text {
+            Some(text) => write_text(&target.path, text).await,
+            None => write_json_value(&target.path, &config).await,
+        };
+        if let Err(err) = written {
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, err);
+        }
+        refresh_plugin_runtime(&state, &target).await;
+        crate::model_catalog::invalidate();
+    }
 
-    Json(response).into_response()
+    Json(OpencodeConfigPatchResponse {
+        config: target.response(exists || !dry_run, config),
+        dry_run,
+        rewritten,
+    })
+    .into_response()
 }
 
 pub async fn config_reload_post(State(state): State<Arc<crate::AppState>>) -> Response {
     // This endpoint acts as an explicit "apply" step.
+    crate::model_catalog::invalidate();
     // Kick OpenCode refresh in the background so the UI can reload quickly.
     tokio::spawn(async move {
         let _ = state.opencode.restart("manual configuration reload").await;
@@ -253,3 +433,28 @@ pub async fn config_reload_post(State(state): State<Arc<crate::AppState>>) -> Re
     }))
     .into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::validate_config;
+    use serde_json::json;
+
+    #[test]
+    fn validate_config_names_the_failing_entry() {
+        assert!(validate_config(&json!({ "agent": { "build": { "model": "a/b" } } })).is_ok());
+
+        let issue = validate_config(&json!({
+            "model": "a/b",
+            "agent": { "build": { "model": "a/b" }, "plan": { "mode": "sideways" } },
+        }))
+        .expect_err("invalid agent mode");
+        assert_eq!(issue.path, vec!["agent".to_string(), "plan".to_string()]);
+
+        let issue = validate_config(&json!({ "snapshot": "yes" })).expect_err("invalid snapshot");
+        assert_eq!(issue.path, vec!["snapshot".to_string()]);
+
+        let issue = validate_config(&json!({ "lsp": { "custom": { "command": ["x"] } } }))
+            .expect_err("custom lsp without extensions");
+        assert_eq!(issue.path, vec!["lsp".to_string()]);
+    }
+}
@@ -21,6 +21,16 @@ pub(super) fn merge_persisted_settings(current: &Value, changes: &Value) -> Valu
         }
     }
 
+    // The UI only ever sees masked header values; map those back.
+    if let Some(overrides) = next.get_mut("providerOverrides")
+        && changes_obj.contains_key("providerOverrides")
+    {
+        crate::opencode_config::restore_masked_headers(
+            overrides,
+            current_obj.get("providerOverrides"),
+        );
+    }
+
     let base_approved = if changes_obj.get("approvedDirectories").is_some() {
         normalize_string_array(changes_obj.get("approvedDirectories"))
     } else {
@@ -3,9 +3,11 @@ use std::sync::Arc;
 use axum::{
     Json,
     extract::State,
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
+use serde::Serialize;
 use serde_json::Value;
 
 use crate::settings;
@@ -41,17 +43,88 @@ async fn validate_project_entries(projects: &[settings::Project]) -> Vec<setting
     out
 }
 
+fn with_revision_header(revision: u64, formatted: Value) -> Response {
+    (
+        [(
+            crate::settings_events::SETTINGS_REVISION_HEADER,
+            revision.to_string(),
+        )],
+        Json(formatted),
+    )
+        .into_response()
+}
+
+/// Revision the client based its update on: `If-Match` (quotes and a weak
+/// prefix are ignored) or a `revision` field in the body.
+/// `Ok(None)` means the client asked to overwrite regardless (`If-Match: *`
+/// or `"force": true`); `Err(())` means it sent no usable revision.
+fn expected_revision(headers: &HeaderMap, body: &Value) -> Result<Option<u64>, ()> {
+    if body.get("force").and_then(Value::as_bool) == Some(true) {
+        return Ok(None);
+    }
+    if let Some(raw) = headers.get("if-match").and_then(|v| v.to_str().ok()) {
+        let raw = raw.trim();
+        if raw == "*" {
+            return Ok(None);
+        }
+        let raw = raw.strip_prefix("W/").unwrap_or(raw).trim_matches('"');
+        return raw.parse::<u64>().map(Some).map_err(|_| ());
+    }
+    body.get("revision")
+        .and_then(Value::as_u64)
+        .map(Some)
+        .ok_or(())
+}
+
+fn revision_rejection(status: StatusCode, message: &str, current: &settings::Settings) -> Response {
+    let value = serde_json::to_value(current).unwrap_or(serde_json::json!({}));
+    (
+        status,
+        [(
+            crate::settings_events::SETTINGS_REVISION_HEADER,
+            current.revision.to_string(),
+        )],
+        Json(serde_json::json!({
+            "error": message,
+            "code": if status == StatusCode::CONFLICT { "settings_conflict" } else { "settings_revision_required" },
+            "revision": current.revision,
+            "settings": format_settings_response(&value),
+        })),
+    )
+        .into_response()
+}
+
 pub async fn config_settings_get(State(state): State<Arc<crate::AppState>>) -> Response {
     let current = state.settings.read().await.clone();
     let value = serde_json::to_value(&current).unwrap_or(serde_json::json!({}));
-    Json(format_settings_response(&value)).into_response()
+    with_revision_header(current.revision, format_settings_response(&value))
 }
 
+/// Merge a partial update into the settings. The client must name the
+/// revision it read; a stale one gets 409 with the current copy.
 pub async fn config_settings_put(
     State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
     Json(body): Json<Value>,
 ) -> Response {
     let mut guard = state.settings.write().await;
+    match expected_revision(&headers, &body) {
+        Ok(Some(expected)) if expected != guard.revision => {
+            return revision_rejection(
+                StatusCode::CONFLICT,
+                "Settings were changed by another client",
+                &guard,
+            );
+        }
+        Ok(_) => {}
+        Err(()) => {
+            return revision_rejection(
+                StatusCode::PRECONDITION_REQUIRED,
+                "Send the settings revision in If-Match, or force to overwrite",
+                &guard,
+            );
+        }
+    }
     let current_value = serde_json::to_value(&*guard).unwrap_or(serde_json::json!({}));
     let sanitized = sanitize_settings_update(&body);
     let merged = merge_persisted_settings(&current_value, &sanitized);
@@ -62,6 +135,9 @@ pub async fn config_settings_put(
     if !next_settings.projects.is_empty() {
         next_settings.projects = validate_project_entries(&next_settings.projects).await;
     }
+    // Clients cannot change the layout version.
+    next_settings.schema_version = guard.schema_version;
+    next_settings.revision = guard.revision.saturating_add(1);
 
     *guard = next_settings.clone();
     if let Err(err) = settings::persist_settings(state.studio_db.as_ref(), &next_settings).await {
@@ -72,10 +148,56 @@ pub async fn config_settings_put(
             .into_response();
     }
 
+    state
+        .attachment_cache
+        .set_max_bytes(crate::attachment_cache::max_bytes_from_settings(
+            &next_settings,
+        ));
+
+    // Applied by the next OpenCode (re)start, e.g. via `/api/config/reload`.
+    state
+        .opencode
+        .set_config_content(crate::opencode_config::managed_config_content(
+            &next_settings,
+        ))
+        .await;
+
     let out = serde_json::to_value(&next_settings).unwrap_or(serde_json::json!({}));
     let formatted = format_settings_response(&out);
-    crate::settings_events::publish_settings_replace(formatted.clone()).await;
-    Json(formatted).into_response()
+    crate::settings_events::publish_settings_patch(
+        &format_settings_response(&current_value),
+        formatted.clone(),
+    )
+    .await;
+    with_revision_header(next_settings.revision, formatted)
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsMetaResponse {
+    /// Version of the settings currently in use.
+    schema_version: u32,
+    /// Version this build writes.
+    current_version: u32,
+    /// Version found in storage at startup, before migrating.
+    loaded_version: u32,
+    applied_migrations: Vec<crate::settings_migrations::AppliedMigration>,
+    newer_than_supported: bool,
+}
+
+/// Schema version of the stored settings and the migrations applied at load.
+pub async fn config_settings_meta_get(
+    State(state): State<Arc<crate::AppState>>,
+) -> Json<SettingsMetaResponse> {
+    let schema_version = state.settings.read().await.schema_version;
+    let report = crate::settings_migrations::last_load();
+    Json(SettingsMetaResponse {
+        schema_version,
+        current_version: crate::settings_migrations::SETTINGS_SCHEMA_VERSION,
+        loaded_version: report.loaded_version,
+        applied_migrations: report.applied,
+        newer_than_supported: report.newer_than_supported,
+    })
 }
 
 #[cfg(test)]
@@ -100,6 +222,11 @@ mod tests {
             obj.get("showReasoningTraces").and_then(|v| v.as_bool()),
             Some(false)
         );
+        assert_eq!(
+            obj.get("chatToolOutputTablePreviewRows")
+                .and_then(|v| v.as_u64()),
+            Some(crate::tool_output_table::DEFAULT_TABLE_PREVIEW_ROWS as u64)
+        );
         assert_eq!(
             obj.get("showTextJustificationActivity")
                 .and_then(|v| v.as_bool()),
@@ -138,6 +265,11 @@ mod tests {
             obj.get("gitPostCommitCommand").and_then(|v| v.as_str()),
             Some("none")
         );
+        assert_eq!(obj.get("disabledPlugins"), Some(&serde_json::json!([])));
+        assert_eq!(
+            obj.get("pluginPermissionGrants"),
+            Some(&serde_json::json!([]))
+        );
         assert!(obj.get("chatActivityItemsDefaultExpanded").is_none());
         assert_eq!(
             obj.get("chatActivityAutoCollapseOnIdle")
@@ -488,6 +620,176 @@ mod tests {
         assert_eq!(rules, vec!["main".to_string(), "release/*".to_string()]);
     }
 
+    #[test]
+    fn sanitize_settings_update_normalizes_git_identities() {
+        let input = serde_json::json!({
+            "gitIdentities": [
+                {"id": " work ", "label": "Work", "name": "Ada", "email": "ada@corp.example"},
+                {"id": "work", "name": "Dup", "email": "dup@example.com"},
+                {"id": "bot", "name": "Bot", "email": ""},
+                "junk",
+            ],
+            "gitRepositoryIdentities": {
+                "/srv/repos/app/": "work",
+                "/srv/repos/other": "",
+            },
+        });
+
+        let out = sanitize_settings_update(&input);
+        let obj = out.as_object().expect("sanitized object");
+
+        assert_eq!(
+            obj.get("gitIdentities"),
+            Some(&serde_json::json!([
+                {"id": "work", "label": "Work", "name": "Ada", "email": "ada@corp.example"}
+            ]))
+        );
+        assert_eq!(
+            obj.get("gitRepositoryIdentities"),
+            Some(&serde_json::json!({"/srv/repos/app": "work"}))
+        );
+    }
+
+    #[test]
+    fn sanitize_settings_update_filters_notification_settings() {
+        let input = serde_json::json!({
+            "notifications": {
+                "enabled": true,
+                "events": {"idle": ["desktop", "pager", "desktop"], "bogus": ["webhook"]},
+                "webhook": {"url": "file:///etc/passwd"},
+                "smtp": {"host": " smtp.example.com ", "port": 70000, "security": "tls", "password": "x", "to": ["a@example.com", ""]},
+            },
+        });
+
+        let out = sanitize_settings_update(&input);
+        assert_eq!(
+            out.get("notifications"),
+            Some(&serde_json::json!({
+                "enabled": true,
+                "events": {"idle": ["desktop"]},
+                "smtp": {"host": "smtp.example.com", "security": "tls", "to": ["a@example.com"]},
+            }))
+        );
+    }
+
+    #[test]
+    fn sanitize_settings_update_filters_budgets() {
+        let input = serde_json::json!({
+            "budgets": {"sessionLimitUsd": 5, "dailyLimitUsd": "10", "blockPrompts": true, "extra": 1},
+        });
+
+        let out = sanitize_settings_update(&input);
+        assert_eq!(
+            out.get("budgets"),
+            Some(&serde_json::json!({
+                "sessionLimitUsd": 5.0,
+                "dailyLimitUsd": null,
+                "blockPrompts": true,
+            }))
+        );
+    }
+
+    #[test]
+    fn sanitize_settings_update_filters_slash_commands() {
+        let input = serde_json::json!({
+            "slashCommands": [
+                {"name": "/Test", "template": "Run {{args}}", "files": ["Cargo.toml", " ", 3]},
+                {"name": "test", "template": "duplicate"},
+                {"name": "test", "template": "Run cargo test", "directory": "/repo", "description": " Project tests "},
+                {"name": "bad name", "template": "x"},
+                {"name": "empty", "template": "  "},
+            ],
+        });
+
+        let out = sanitize_settings_update(&input);
+        assert_eq!(
+            out.get("slashCommands"),
+            Some(&serde_json::json!([
+                {"name": "test", "template": "Run {{args}}", "files": ["Cargo.toml"]},
+                {"name": "test", "description": "Project tests", "template": "Run cargo test", "files": [], "directory": "/repo"},
+            ]))
+        );
+    }
+
+    #[test]
+    fn sanitize_settings_update_filters_git_linters() {
+        let input = serde_json::json!({
+            "gitLinters": [
+                {"id": "ruff", "command": " ruff ", "args": ["check", "--output-format=json", 1], "format": "Ruff", "timeoutSeconds": 99999},
+                {"id": "ruff", "command": "ruff", "format": "ruff"},
+                {"id": "pylint", "command": "pylint", "format": "pylint"},
+            ],
+        });
+
+        let out = sanitize_settings_update(&input);
+        assert_eq!(
+            out.get("gitLinters"),
+            Some(&serde_json::json!([{
+                "id": "ruff",
+                "command": "ruff",
+                "args": ["check", "--output-format=json"],
+                "format": "ruff",
+                "extensions": [],
+                "timeoutSeconds": 1800,
+            }]))
+        );
+    }
+
+    #[test]
+    fn sanitize_settings_update_clamps_git_auto_fetch_interval() {
+        let out = sanitize_settings_update(&serde_json::json!({
+            "gitAutoFetch": {"intervalMinutes": 99999, "extra": true},
+        }));
+        assert_eq!(
+            out.get("gitAutoFetch"),
+            Some(&serde_json::json!({"intervalMinutes": 1440}))
+        );
+
+        let out = sanitize_settings_update(&serde_json::json!({"gitAutoFetch": {}}));
+        assert_eq!(
+            out.get("gitAutoFetch"),
+            Some(&serde_json::json!({"intervalMinutes": 0}))
+        );
+    }
+
+    #[test]
+    fn sanitize_settings_update_filters_git_commit_message() {
+        let out = sanitize_settings_update(&serde_json::json!({
+            "gitCommitMessage": {"template": "{{diff}}", "model": "no-slash", "maxDiffBytes": 4096},
+        }));
+        assert_eq!(
+            out.get("gitCommitMessage"),
+            Some(&serde_json::json!({"template": "{{diff}}", "maxDiffBytes": 4096}))
+        );
+    }
+
+    #[test]
+    fn sanitize_settings_update_filters_provider_overrides() {
+        let input = serde_json::json!({
+            "providerOverrides": {
+                "anthropic": {
+                    "baseUrl": " https://eu.example.com/v1 ",
+                    "headers": {"X-Region": "eu", "bad header": "x", "X-Split": "a\r\nb"},
+                    "timeoutMs": 0,
+                },
+                "openai": {"baseUrl": "ftp://example.com"},
+                "bad id": {"timeoutMs": 1000},
+            },
+        });
+
+        let out = sanitize_settings_update(&input);
+        assert_eq!(
+            out.get("providerOverrides"),
+            Some(&serde_json::json!({
+                "anthropic": {
+                    "baseUrl": "https://eu.example.com/v1",
+                    "headers": {"X-Region": "eu"},
+                    "timeoutMs": 1,
+                },
+            }))
+        );
+    }
+
     #[test]
     fn format_settings_response_includes_non_empty_directories_alias() {
         let input = serde_json::json!({
@@ -575,4 +877,32 @@ mod tests {
             Some(false)
         );
     }
+
+    #[test]
+    fn expected_revision_reads_if_match_body_and_force() {
+        let mut headers = HeaderMap::new();
+        let body = serde_json::json!({ "themeId": "x" });
+        assert_eq!(expected_revision(&headers, &body), Err(()));
+        assert_eq!(
+            expected_revision(&headers, &serde_json::json!({ "revision": 4 })),
+            Ok(Some(4))
+        );
+        assert_eq!(
+            expected_revision(&headers, &serde_json::json!({ "force": true })),
+            Ok(None)
+        );
+
+        headers.insert("if-match", "W/\"7\"".parse().unwrap());
+        assert_eq!(expected_revision(&headers, &body), Ok(Some(7)));
+        headers.insert("if-match", "*".parse().unwrap());
+        assert_eq!(expected_revision(&headers, &body), Ok(None));
+        headers.insert("if-match", "abc".parse().unwrap());
+        assert_eq!(expected_revision(&headers, &body), Err(()));
+    }
+
+    #[test]
+    fn format_settings_response_exposes_revision() {
+        let out = format_settings_response(&serde_json::json!({ "revision": 12, "projects": [] }));
+        assert_eq!(out.get("revision").and_then(|v| v.as_u64()), Some(12));
+    }
 }
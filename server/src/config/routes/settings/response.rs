@@ -47,8 +47,54 @@ impl<'a> SettingsResponseBuilder<'a> {
         self.set_bool_with_default("updateAutoInstallerInstallEnabled", false);
         self.set_nullable_trimmed_string_with_default_null("updateIgnoredReleaseTag");
         self.set_nonnegative_i64_with_default("updateReminderSnoozeUntil", 0);
+        self.set_nonnegative_i64_with_default("revision", 0);
         self.set_bool_with_default("showChatTimestamps", true);
         self.set_bool_with_default("chatActivityAutoCollapseOnIdle", true);
+        self.set_bool_with_default("chatToolOutputTablePreview", false);
+        self.set_nonnegative_i64_with_default(
+            "chatToolOutputTablePreviewRows",
+            crate::tool_output_table::DEFAULT_TABLE_PREVIEW_ROWS as i64,
+        );
+        self.set_bool_with_default("chatToolOutputRetention", false);
+        self.set_nonnegative_i64_with_default(
+            "chatToolOutputRetentionMaxBytes",
+            crate::tool_output_retention::DEFAULT_TOOL_OUTPUT_MAX_BYTES as i64,
+        );
+        self.set_bool_with_default(
+            crate::attachment_text::ATTACHMENT_TEXT_EXTRACTION_KEY,
+            false,
+        );
+        self.set_nonnegative_i64_with_default(
+            crate::attachment_text::ATTACHMENT_TEXT_MAX_CHARS_KEY,
+            crate::attachment_text::DEFAULT_ATTACHMENT_TEXT_MAX_CHARS as i64,
+        );
+        self.set_nonnegative_i64_with_default(
+            crate::attachment_cache::ATTACHMENT_CACHE_MAX_BYTES_KEY,
+            crate::attachment_cache::DEFAULT_ATTACHMENT_CACHE_MAX_BYTES as i64,
+        );
+        self.output
+            .entry("chatToolOutputRetentionToolLimits")
+            .or_insert_with(|| Value::Object(serde_json::Map::new()));
+        self.output
+            .entry("gitIdentities")
+            .or_insert_with(|| Value::Array(Vec::new()));
+        self.output
+            .entry("gitRepositoryIdentities")
+            .or_insert_with(|| Value::Object(serde_json::Map::new()));
+        self.output
+            .entry("gitLinters")
+            .or_insert_with(|| Value::Array(Vec::new()));
+        crate::opencode_config::mask_provider_overrides(
+            self.output
+                .entry("providerOverrides")
+                .or_insert_with(|| Value::Object(serde_json::Map::new())),
+        );
+
+        for key in ["disabledPlugins", "pluginPermissionGrants"] {
+            self.output
+                .entry(key)
+                .or_insert_with(|| Value::Array(Vec::new()));
+        }
 
         self.set_git_branch_protection_prompt();
         self.set_git_branch_protection();
@@ -50,6 +50,15 @@ impl<'a> SettingsUpdateSanitizer<'a> {
         self.sanitize_typography_sizes();
         self.sanitize_projects_alias();
         self.sanitize_skill_catalogs();
+        self.sanitize_git_identities();
+        self.sanitize_git_linters();
+        self.sanitize_git_auto_fetch();
+        self.sanitize_git_commit_message();
+        self.sanitize_provider_overrides();
+        self.sanitize_notifications();
+        self.sanitize_budgets();
+        self.sanitize_slash_commands();
+        self.sanitize_tool_output_retention_limits();
         Value::Object(self.output)
     }
 
@@ -132,6 +141,9 @@ impl<'a> SettingsUpdateSanitizer<'a> {
             "showTextJustificationActivity",
             "showChatTimestamps",
             "chatActivityAutoCollapseOnIdle",
+            "chatToolOutputTablePreview",
+            "chatToolOutputRetention",
+            "attachmentTextExtraction",
             "autoDeleteEnabled",
             "queueModeEnabled",
             "autoCreateWorktree",
@@ -158,6 +170,22 @@ impl<'a> SettingsUpdateSanitizer<'a> {
         self.insert_bounded_number("memoryLimitHistorical", 10, 500);
         self.insert_bounded_number("memoryLimitViewport", 20, 500);
         self.insert_bounded_number("memoryLimitActiveSession", 30, 1000);
+        self.insert_bounded_number("chatToolOutputTablePreviewRows", 1, 500);
+        self.insert_bounded_number(
+            "chatToolOutputRetentionMaxBytes",
+            0,
+            crate::tool_output_retention::MAX_TOOL_OUTPUT_LIMIT_BYTES,
+        );
+        self.insert_bounded_number(
+            crate::attachment_text::ATTACHMENT_TEXT_MAX_CHARS_KEY,
+            1,
+            crate::attachment_text::MAX_ATTACHMENT_TEXT_LIMIT_CHARS,
+        );
+        self.insert_bounded_number(
+            crate::attachment_cache::ATTACHMENT_CACHE_MAX_BYTES_KEY,
+            0,
+            crate::attachment_cache::MAX_ATTACHMENT_CACHE_LIMIT_BYTES,
+        );
         self.insert_bounded_number("updateReminderSnoozeUntil", 0, 4_102_444_800_000);
     }
 
@@ -176,6 +204,8 @@ impl<'a> SettingsUpdateSanitizer<'a> {
         self.insert_normalized_string_array("securityScopedBookmarks");
         self.insert_normalized_string_array("pinnedDirectories");
         self.insert_normalized_string_array("gitBranchProtection");
+        self.insert_normalized_string_array("disabledPlugins");
+        self.insert_normalized_string_array("pluginPermissionGrants");
 
         if self.input.get("chatActivityFilters").is_some() {
             self.output.insert(
@@ -262,6 +292,90 @@ impl<'a> SettingsUpdateSanitizer<'a> {
             self.output.insert("skillCatalogs".to_string(), v);
         }
     }
+
+    fn sanitize_git_identities(&mut self) {
+        if let Some(v) = sanitize_git_identities(self.input.get("gitIdentities")) {
+            self.output.insert("gitIdentities".to_string(), v);
+        }
+        if let Some(v) =
+            sanitize_git_repository_identities(self.input.get("gitRepositoryIdentities"))
+        {
+            self.output.insert("gitRepositoryIdentities".to_string(), v);
+        }
+    }
+
+    fn sanitize_git_linters(&mut self) {
+        if let Some(v) = sanitize_git_linters(self.input.get("gitLinters")) {
+            self.output.insert("gitLinters".to_string(), v);
+        }
+    }
+
+    fn sanitize_git_auto_fetch(&mut self) {
+        if let Some(v) = sanitize_git_auto_fetch(self.input.get("gitAutoFetch")) {
+            self.output.insert("gitAutoFetch".to_string(), v);
+        }
+    }
+
+    fn sanitize_git_commit_message(&mut self) {
+        if let Some(v) = sanitize_git_commit_message(self.input.get("gitCommitMessage")) {
+            self.output.insert("gitCommitMessage".to_string(), v);
+        }
+    }
+
+    fn sanitize_provider_overrides(&mut self) {
+        if let Some(v) = sanitize_provider_overrides(self.input.get("providerOverrides")) {
+            self.output.insert("providerOverrides".to_string(), v);
+        }
+    }
+
+    fn sanitize_notifications(&mut self) {
+        if let Some(v) = sanitize_notifications(self.input.get("notifications")) {
+            self.output.insert("notifications".to_string(), v);
+        }
+    }
+
+    fn sanitize_budgets(&mut self) {
+        if let Some(v) = sanitize_budgets(self.input.get("budgets")) {
+            self.output.insert("budgets".to_string(), v);
+        }
+    }
+
+    fn sanitize_slash_commands(&mut self) {
+        if let Some(v) = sanitize_slash_commands(self.input.get("slashCommands")) {
+            self.output.insert("slashCommands".to_string(), v);
+        }
+    }
+
+    fn sanitize_tool_output_retention_limits(&mut self) {
+        if let Some(v) =
+            sanitize_tool_output_limits(self.input.get("chatToolOutputRetentionToolLimits"))
+        {
+            self.output
+                .insert("chatToolOutputRetentionToolLimits".to_string(), v);
+        }
+    }
+}
+
+/// Tool id -> max stored bytes (0 = unlimited). Tool ids are lowercased to
+/// match how parts are looked up.
+fn sanitize_tool_output_limits(input: Option<&Value>) -> Option<Value> {
+    let Some(Value::Object(obj)) = input else {
+        return None;
+    };
+
+    let mut out = serde_json::Map::new();
+    for (tool, limit) in obj {
+        let tool = tool.trim().to_ascii_lowercase();
+        let Some(n) = limit.as_i64() else {
+            continue;
+        };
+        if tool.is_empty() {
+            continue;
+        }
+        let clamped = n.clamp(0, crate::tool_output_retention::MAX_TOOL_OUTPUT_LIMIT_BYTES);
+        out.insert(tool, Value::Number(clamped.into()));
+    }
+    Some(Value::Object(out))
 }
 
 fn normalize_chat_activity_default_expanded(v: Option<&Value>) -> Vec<String> {
@@ -381,6 +495,412 @@ fn sanitize_skill_catalogs(input: Option<&Value>) -> Option<Value> {
     Some(Value::Array(out))
 }
 
+/// Identity profiles need an id, name and email; the label is optional.
+fn sanitize_git_identities(input: Option<&Value>) -> Option<Value> {
+    let Some(Value::Array(arr)) = input else {
+        return None;
+    };
+
+    let mut out: Vec<Value> = Vec::new();
+    let mut seen = HashSet::<String>::new();
+    for entry in arr {
+        let Value::Object(obj) = entry else {
+            continue;
+        };
+        let field = |key: &str| obj.get(key).and_then(|v| v.as_str()).unwrap_or("").trim();
+        let (id, label, name, email) = (field("id"), field("label"), field("name"), field("email"));
+        if id.is_empty() || name.is_empty() || email.is_empty() || !seen.insert(id.to_string()) {
+            continue;
+        }
+
+        let mut next = serde_json::Map::new();
+        next.insert("id".to_string(), Value::String(id.to_string()));
+        if !label.is_empty() {
+            next.insert("label".to_string(), Value::String(label.to_string()));
+        }
+        next.insert("name".to_string(), Value::String(name.to_string()));
+        next.insert("email".to_string(), Value::String(email.to_string()));
+        out.push(Value::Object(next));
+    }
+    Some(Value::Array(out))
+}
+
+/// Repository root -> identity id, keyed by the normalized root path.
+fn sanitize_git_repository_identities(input: Option<&Value>) -> Option<Value> {
+    let Some(Value::Object(obj)) = input else {
+        return None;
+    };
+
+    let mut out = serde_json::Map::new();
+    for (path, id) in obj {
+        let Some(id) = id.as_str().map(str::trim).filter(|id| !id.is_empty()) else {
+            continue;
+        };
+        let Some(key) = crate::path_utils::normalize_directory_for_match(path) else {
+            continue;
+        };
+        out.insert(key, Value::String(id.to_string()));
+    }
+    Some(Value::Object(out))
+}
+
+const GIT_LINTER_FORMATS: [&str; 3] = ["eslint", "clippy", "ruff"];
+const GIT_LINTER_MAX_TIMEOUT_SECONDS: u64 = 30 * 60;
+
+/// Linter commands for `/api/git/lint`. `command` is a program name, never a
+/// shell line; arguments go in `args`.
+fn sanitize_git_linters(input: Option<&Value>) -> Option<Value> {
+    let Some(Value::Array(arr)) = input else {
+        return None;
+    };
+
+    let mut out: Vec<Value> = Vec::new();
+    let mut seen = HashSet::<String>::new();
+    for entry in arr {
+        let Value::Object(obj) = entry else {
+            continue;
+        };
+        let field = |key: &str| obj.get(key).and_then(|v| v.as_str()).unwrap_or("").trim();
+        let (id, label, command) = (field("id"), field("label"), field("command"));
+        let format = field("format").to_ascii_lowercase();
+        if id.is_empty()
+            || command.is_empty()
+            || !GIT_LINTER_FORMATS.contains(&format.as_str())
+            || !seen.insert(id.to_string())
+        {
+            continue;
+        }
+        let strings = |key: &str| -> Vec<Value> {
+            let Some(Value::Array(items)) = obj.get(key) else {
+                return Vec::new();
+            };
+            items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| Value::String(s.to_string()))
+                .collect()
+        };
+
+        let mut next = serde_json::Map::new();
+        next.insert("id".to_string(), Value::String(id.to_string()));
+        if !label.is_empty() {
+            next.insert("label".to_string(), Value::String(label.to_string()));
+        }
+        next.insert("command".to_string(), Value::String(command.to_string()));
+        next.insert("args".to_string(), Value::Array(strings("args")));
+        next.insert("format".to_string(), Value::String(format));
+        next.insert(
+            "extensions".to_string(),
+            Value::Array(strings("extensions")),
+        );
+        if let Some(Value::Bool(b)) = obj.get("passFiles") {
+            next.insert("passFiles".to_string(), Value::Bool(*b));
+        }
+        if let Some(n) = obj.get("timeoutSeconds").and_then(|v| v.as_u64()) {
+            let clamped = n.clamp(1, GIT_LINTER_MAX_TIMEOUT_SECONDS);
+            next.insert("timeoutSeconds".to_string(), Value::Number(clamped.into()));
+        }
+        out.push(Value::Object(next));
+    }
+    Some(Value::Array(out))
+}
+
+const GIT_AUTO_FETCH_MAX_INTERVAL_MINUTES: u64 = 24 * 60;
+
+/// Background `git fetch` for configured projects; `intervalMinutes` 0 turns
+/// it off.
+fn sanitize_git_auto_fetch(input: Option<&Value>) -> Option<Value> {
+    let Some(Value::Object(obj)) = input else {
+        return None;
+    };
+    let minutes = obj
+        .get("intervalMinutes")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0)
+        .min(GIT_AUTO_FETCH_MAX_INTERVAL_MINUTES);
+    Some(serde_json::json!({ "intervalMinutes": minutes }))
+}
+
+const GIT_COMMIT_MESSAGE_MAX_TEMPLATE_CHARS: usize = 16 * 1024;
+
+/// Prompt template, `provider/model`, and diff budget for
+/// `/api/git/commit-message`. Empty strings fall back to the defaults.
+fn sanitize_git_commit_message(input: Option<&Value>) -> Option<Value> {
+    let Some(Value::Object(obj)) = input else {
+        return None;
+    };
+    let mut out = serde_json::Map::new();
+    if let Some(template) = obj
+        .get("template")
+        .and_then(|v| v.as_str())
+        .filter(|t| !t.trim().is_empty())
+    {
+        out.insert(
+            "template".to_string(),
+            Value::String(
+                template
+                    .chars()
+                    .take(GIT_COMMIT_MESSAGE_MAX_TEMPLATE_CHARS)
+                    .collect(),
+            ),
+        );
+    }
+    if let Some(model) = obj
+        .get("model")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|m| {
+            m.split_once('/')
+                .is_some_and(|(p, m)| !p.is_empty() && !m.is_empty())
+        })
+    {
+        out.insert("model".to_string(), Value::String(model.to_string()));
+    }
+    if let Some(n) = obj.get("maxDiffBytes").and_then(|v| v.as_u64()) {
+        out.insert("maxDiffBytes".to_string(), Value::Number(n.into()));
+    }
+    Some(Value::Object(out))
+}
+
+const PROVIDER_OVERRIDE_MAX_TIMEOUT_MS: u64 = 60 * 60 * 1000;
+
+/// Per-provider base URL/header/timeout overrides merged into the managed
+/// OpenCode config. Invalid header names/values and non-http(s) URLs are
+/// dropped rather than forwarded to OpenCode.
+fn sanitize_provider_overrides(input: Option<&Value>) -> Option<Value> {
+    let Some(Value::Object(map)) = input else {
+        return None;
+    };
+
+    let mut out = serde_json::Map::new();
+    for (provider_id, entry) in map {
+        let provider_id = provider_id.trim();
+        if provider_id.is_empty()
+            || provider_id.len() > 64
+            || !provider_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            continue;
+        }
+        let Value::Object(obj) = entry else {
+            continue;
+        };
+
+        let mut next = serde_json::Map::new();
+        if let Some(raw) = obj.get("baseUrl").and_then(|v| v.as_str()).map(str::trim)
+            && let Ok(url) = url::Url::parse(raw)
+            && matches!(url.scheme(), "http" | "https")
+        {
+            next.insert("baseUrl".to_string(), Value::String(raw.to_string()));
+        }
+        if let Some(Value::Object(headers)) = obj.get("headers") {
+            let mut clean = serde_json::Map::new();
+            for (name, value) in headers {
+                let name = name.trim();
+                let Some(value) = value.as_str() else {
+                    continue;
+                };
+                if axum::http::HeaderName::from_bytes(name.as_bytes()).is_ok()
+                    && axum::http::HeaderValue::from_str(value).is_ok()
+                {
+                    clean.insert(name.to_string(), Value::String(value.to_string()));
+                }
+            }
+            if !clean.is_empty() {
+                next.insert("headers".to_string(), Value::Object(clean));
+            }
+        }
+        if let Some(ms) = obj.get("timeoutMs").and_then(|v| v.as_u64()) {
+            let clamped = ms.clamp(1, PROVIDER_OVERRIDE_MAX_TIMEOUT_MS);
+            next.insert("timeoutMs".to_string(), Value::Number(clamped.into()));
+        }
+        if !next.is_empty() {
+            out.insert(provider_id.to_string(), Value::Object(next));
+        }
+    }
+    Some(Value::Object(out))
+}
+
+/// Spend limits in USD; anything but a positive number clears a limit.
+fn sanitize_budgets(input: Option<&Value>) -> Option<Value> {
+    let Some(Value::Object(obj)) = input else {
+        return None;
+    };
+    let limit = |key: &str| {
+        obj.get(key)
+            .and_then(Value::as_f64)
+            .filter(|n| n.is_finite() && *n > 0.0)
+            .and_then(serde_json::Number::from_f64)
+            .map_or(Value::Null, Value::Number)
+    };
+    Some(serde_json::json!({
+        "sessionLimitUsd": limit("sessionLimitUsd"),
+        "dailyLimitUsd": limit("dailyLimitUsd"),
+        "blockPrompts": obj.get("blockPrompts").and_then(Value::as_bool).unwrap_or(false),
+    }))
+}
+
+const SLASH_COMMAND_MAX_NAME_CHARS: usize = 40;
+const SLASH_COMMAND_MAX_TEMPLATE_CHARS: usize = 16 * 1024;
+const SLASH_COMMAND_MAX_FILES: usize = 16;
+
+/// Server-expanded slash commands. Names are lowercase `[a-z0-9_-]`, unique
+/// per project; `directory` limits a command to one project.
+fn sanitize_slash_commands(input: Option<&Value>) -> Option<Value> {
+    let Some(Value::Array(arr)) = input else {
+        return None;
+    };
+
+    let mut out: Vec<Value> = Vec::new();
+    let mut seen = HashSet::<(String, String)>::new();
+    for entry in arr {
+        let Value::Object(obj) = entry else {
+            continue;
+        };
+        let field = |key: &str| obj.get(key).and_then(|v| v.as_str()).unwrap_or("").trim();
+        let name = field("name").trim_start_matches('/').to_ascii_lowercase();
+        let template = field("template");
+        let directory = field("directory");
+        if name.is_empty()
+            || name.len() > SLASH_COMMAND_MAX_NAME_CHARS
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+            || template.is_empty()
+            || !seen.insert((name.clone(), directory.to_string()))
+        {
+            continue;
+        }
+
+        let mut next = serde_json::Map::new();
+        next.insert("name".to_string(), Value::String(name));
+        let description = field("description");
+        if !description.is_empty() {
+            next.insert(
+                "description".to_string(),
+                Value::String(description.to_string()),
+            );
+        }
+        next.insert(
+            "template".to_string(),
+            Value::String(
+                template
+                    .chars()
+                    .take(SLASH_COMMAND_MAX_TEMPLATE_CHARS)
+                    .collect(),
+            ),
+        );
+        let files: Vec<Value> = match obj.get("files") {
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(str::trim)
+                .filter(|f| !f.is_empty())
+                .take(SLASH_COMMAND_MAX_FILES)
+                .map(|f| Value::String(f.to_string()))
+                .collect(),
+            _ => Vec::new(),
+        };
+        next.insert("files".to_string(), Value::Array(files));
+        if !directory.is_empty() {
+            next.insert(
+                "directory".to_string(),
+                Value::String(directory.to_string()),
+            );
+        }
+        out.push(Value::Object(next));
+    }
+    Some(Value::Array(out))
+}
+
+const NOTIFICATION_EVENTS: [&str; 4] = ["idle", "error", "permission", "question"];
+const NOTIFICATION_SINKS: [&str; 3] = ["webhook", "desktop", "email"];
+
+/// Event -> sink lists plus webhook/SMTP targets. The SMTP password is never
+/// stored here; it comes from `OPENCODE_STUDIO_SMTP_PASSWORD`.
+fn sanitize_notifications(input: Option<&Value>) -> Option<Value> {
+    let Some(Value::Object(obj)) = input else {
+        return None;
+    };
+
+    let mut out = serde_json::Map::new();
+    if let Some(Value::Bool(b)) = obj.get("enabled") {
+        out.insert("enabled".to_string(), Value::Bool(*b));
+    }
+
+    let mut events = serde_json::Map::new();
+    if let Some(Value::Object(raw_events)) = obj.get("events") {
+        for event in NOTIFICATION_EVENTS {
+            let Some(Value::Array(arr)) = raw_events.get(event) else {
+                continue;
+            };
+            let mut sinks: Vec<Value> = Vec::new();
+            for sink in arr.iter().filter_map(|v| v.as_str()).map(str::trim) {
+                if NOTIFICATION_SINKS.contains(&sink) && !sinks.iter().any(|s| s == sink) {
+                    sinks.push(Value::String(sink.to_string()));
+                }
+            }
+            events.insert(event.to_string(), Value::Array(sinks));
+        }
+    }
+    out.insert("events".to_string(), Value::Object(events));
+
+    let trimmed = |obj: &serde_json::Map<String, Value>, key: &str| {
+        obj.get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| Value::String(v.to_string()))
+    };
+
+    if let Some(Value::Object(webhook)) = obj.get("webhook")
+        && let Some(url) = trimmed(webhook, "url").filter(|url| {
+            url.as_str()
+                .and_then(|u| url::Url::parse(u).ok())
+                .is_some_and(|u| matches!(u.scheme(), "http" | "https"))
+        })
+    {
+        out.insert("webhook".to_string(), serde_json::json!({ "url": url }));
+    }
+
+    if let Some(Value::Object(smtp)) = obj.get("smtp") {
+        let mut next = serde_json::Map::new();
+        for key in ["host", "username", "from"] {
+            if let Some(v) = trimmed(smtp, key) {
+                next.insert(key.to_string(), v);
+            }
+        }
+        if let Some(port) = smtp
+            .get("port")
+            .and_then(|v| v.as_u64())
+            .filter(|p| (1..=65535).contains(p))
+        {
+            next.insert("port".to_string(), Value::Number(port.into()));
+        }
+        if let Some(Value::String(security)) = smtp.get("security")
+            && ["starttls", "tls", "none"].contains(&security.trim())
+        {
+            next.insert(
+                "security".to_string(),
+                Value::String(security.trim().to_string()),
+            );
+        }
+        next.insert(
+            "to".to_string(),
+            Value::Array(
+                normalize_string_array(smtp.get("to"))
+                    .into_iter()
+                    .map(Value::String)
+                    .collect(),
+            ),
+        );
+        out.insert("smtp".to_string(), Value::Object(next));
+    }
+
+    Some(Value::Object(out))
+}
+
 fn sanitize_projects(input: Option<&Value>) -> Option<Value> {
     let Some(Value::Array(arr)) = input else {
         return None;
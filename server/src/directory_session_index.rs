@@ -43,6 +43,19 @@ pub(crate) enum RuntimeDisplayState {
     NeedsReply,
 }
 
+/// Upstream tracking state of a directory's checkout, refreshed by the
+/// background auto-fetch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RemoteTrackingRecord {
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub fetched_at: i64,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct RecentSessionRecord {
     pub session_id: String,
@@ -120,6 +133,7 @@ pub struct DirectorySessionIndexManager {
     runtime_by_session: Arc<DashMap<String, RuntimeRecord>>,
     deleted_sessions: Arc<DashMap<String, i64>>,
     recent_sessions: Arc<Mutex<RecentSessionsCache>>,
+    remote_by_directory: Arc<DashMap<String, RemoteTrackingRecord>>,
 }
 
 fn normalize_directory_for_index(path: &str) -> Option<String> {
@@ -205,6 +219,7 @@ impl DirectorySessionIndexManager {
             runtime_by_session: Arc::new(DashMap::new()),
             deleted_sessions: Arc::new(DashMap::new()),
             recent_sessions: Arc::new(Mutex::new(RecentSessionsCache::default())),
+            remote_by_directory: Arc::new(DashMap::new()),
         }
     }
 
@@ -422,6 +437,26 @@ impl DirectorySessionIndexManager {
             .collect()
     }
 
+    pub fn summaries_snapshot(&self) -> Vec<SessionSummaryRecord> {
+        self.summaries_by_session
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    /// Visit every summary without cloning the whole map.
+    pub(crate) fn for_each_summary(&self, mut f: impl FnMut(&SessionSummaryRecord)) {
+        for entry in self.summaries_by_session.iter() {
+            f(entry.value());
+        }
+    }
+
+    pub fn runtime(&self, session_id: &str) -> Option<RuntimeRecord> {
+        self.runtime_by_session
+            .get(session_id.trim())
+            .map(|v| v.clone())
+    }
+
     pub fn recent_sessions_snapshot(&self) -> Vec<RecentSessionRecord> {
         self.recent_sessions.lock().unwrap().snapshot()
     }
@@ -445,6 +480,37 @@ impl DirectorySessionIndexManager {
             .filter(|v| !v.is_empty())
     }
 
+    /// Record the latest tracking state; returns whether branch, upstream,
+    /// counts, or error changed since the previous record.
+    pub(crate) fn upsert_remote_tracking(
+        &self,
+        directory_path: &str,
+        record: RemoteTrackingRecord,
+    ) -> bool {
+        let Some(directory_key) = normalize_directory_for_index(directory_path) else {
+            return false;
+        };
+        let changed = self
+            .remote_by_directory
+            .get(&directory_key)
+            .is_none_or(|prev| {
+                prev.branch != record.branch
+                    || prev.upstream != record.upstream
+                    || prev.ahead != record.ahead
+                    || prev.behind != record.behind
+                    || prev.error != record.error
+            });
+        self.remote_by_directory.insert(directory_key, record);
+        changed
+    }
+
+    pub(crate) fn remote_tracking(&self, directory_path: &str) -> Option<RemoteTrackingRecord> {
+        let directory_key = normalize_directory_for_index(directory_path)?;
+        self.remote_by_directory
+            .get(&directory_key)
+            .map(|v| v.value().clone())
+    }
+
     pub fn session_ids_for_directory(&self, directory: &str) -> HashSet<String> {
         let Some(directory_key) = normalize_directory_for_index(directory) else {
             return HashSet::new();
@@ -702,6 +768,19 @@ impl DirectorySessionIndexManager {
         }
     }
 
+    /// Sessions currently generating or retrying (attention and cooldown
+    /// states are not counted).
+    pub fn busy_session_ids(&self) -> Vec<String> {
+        let mut out: Vec<String> = self
+            .runtime_by_session
+            .iter()
+            .filter(|entry| entry.value().effective_type == "busy")
+            .map(|entry| entry.key().to_string())
+            .collect();
+        out.sort();
+        out
+    }
+
     pub fn runtime_snapshot_json(&self) -> Value {
         let mut out = Map::new();
         for entry in self.runtime_by_session.iter() {
@@ -1095,6 +1174,23 @@ mod tests {
         assert_eq!(first, second);
     }
 
+    #[test]
+    fn busy_session_ids_skips_attention_and_cooldown() {
+        let idx = DirectorySessionIndexManager::new();
+
+        idx.upsert_runtime_status("s_busy", "busy");
+        idx.upsert_runtime_status("s_retry", "retry");
+        idx.upsert_runtime_status("s_waiting", "busy");
+        idx.upsert_runtime_attention("s_waiting", Some("permission"));
+        idx.upsert_runtime_phase("s_cooldown", "cooldown");
+        idx.upsert_runtime_status("s_idle", "idle");
+
+        assert_eq!(
+            idx.busy_session_ids(),
+            vec!["s_busy".to_string(), "s_retry".to_string()]
+        );
+    }
+
     #[test]
     fn recent_sessions_snapshot_keeps_latest_40() {
         let idx = DirectorySessionIndexManager::new();
@@ -269,6 +269,9 @@ async fn fetch_directory_session_page(
         include_children: Some("true".to_string()),
         ids: None,
         focus_session_id: None,
+        tag: None,
+        favorite: None,
+        archived: None,
     };
 
     let response =
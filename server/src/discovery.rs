@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Mutex, OnceLock};
+
+use axum::Json;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+const SERVICE_TYPE: &str = "_opencode-studio._tcp.local.";
+const MAX_INSTANCE_NAME_CHARS: usize = 63;
+const MAX_PEERS: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiscoveryPeer {
+    pub name: String,
+    pub fullname: String,
+    pub hostname: String,
+    pub addresses: Vec<String>,
+    pub port: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub tls: bool,
+    /// Base URL built from the first advertised address; IPv4 preferred.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub last_seen: i64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiscoverySelf {
+    pub name: String,
+    pub port: u16,
+    pub tls: bool,
+    /// False when bound to a loopback address, where advertising is pointless.
+    pub advertised: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiscoveryPeersResponse {
+    pub enabled: bool,
+    #[serde(rename = "self", skip_serializing_if = "Option::is_none")]
+    pub this: Option<DiscoverySelf>,
+    pub peers: Vec<DiscoveryPeer>,
+}
+
+struct Discovery {
+    own_fullname: String,
+    this: DiscoverySelf,
+    peers: Mutex<HashMap<String, DiscoveryPeer>>,
+    // Dropping the daemon would stop advertising and browsing.
+    _daemon: ServiceDaemon,
+}
+
+static DISCOVERY: OnceLock<Discovery> = OnceLock::new();
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn local_hostname() -> String {
+    #[cfg(unix)]
+    {
+        let mut buf = [0u8; 256];
+        // SAFETY: the buffer is valid for `buf.len()` bytes and gethostname
+        // NUL-terminates on success.
+        let rc = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+        if rc == 0 {
+            let end = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+            let name = String::from_utf8_lossy(&buf[..end]).trim().to_string();
+            if !name.is_empty() {
+                return name;
+            }
+        }
+    }
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "opencode-studio".to_string())
+}
+
+/// DNS label for the `.local.` host record: letters, digits and dashes.
+fn mdns_host_label(hostname: &str) -> String {
+    let first = hostname.split('.').next().unwrap_or("");
+    let label: String = first
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string();
+    if label.is_empty() {
+        "opencode-studio".to_string()
+    } else {
+        label
+    }
+}
+
+/// Instance names are shown to users, so keep them readable but bounded.
+fn normalize_instance_name(raw: Option<&str>, hostname: &str) -> String {
+    let name = raw
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("OpenCode Studio ({hostname})"));
+    name.replace('.', " ")
+        .chars()
+        .take(MAX_INSTANCE_NAME_CHARS)
+        .collect()
+}
+
+fn peer_url(addresses: &[IpAddr], port: u16, tls: bool) -> Option<String> {
+    let addr = addresses
+        .iter()
+        .find(|a| a.is_ipv4())
+        .or_else(|| addresses.first())?;
+    let scheme = if tls { "https" } else { "http" };
+    Some(format!("{scheme}://{}", SocketAddr::new(*addr, port)))
+}
+
+fn peer_from_info(info: &ServiceInfo) -> DiscoveryPeer {
+    let mut addresses: Vec<IpAddr> = info.get_addresses().iter().copied().collect();
+    addresses.sort();
+    let tls = info.get_property_val_str("tls") == Some("1");
+    let fullname = info.get_fullname().to_string();
+    let name = info
+        .get_property_val_str("name")
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            fullname
+                .strip_suffix(SERVICE_TYPE)
+                .map(|n| n.trim_end_matches('.').to_string())
+                .unwrap_or_else(|| fullname.clone())
+        });
+    DiscoveryPeer {
+        name,
+        hostname: info.get_hostname().trim_end_matches('.').to_string(),
+        url: peer_url(&addresses, info.get_port(), tls),
+        addresses: addresses.iter().map(ToString::to_string).collect(),
+        port: info.get_port(),
+        version: info.get_property_val_str("version").map(str::to_string),
+        tls,
+        last_seen: now_millis(),
+        fullname,
+    }
+}
+
+/// Advertise this server as `_opencode-studio._tcp` and browse for other
+/// instances. Failures are logged; discovery never blocks startup.
+pub(crate) fn start(bind: SocketAddr, tls: bool, instance_name: Option<&str>) {
+    let daemon = match ServiceDaemon::new() {
+        Ok(daemon) => daemon,
+        Err(err) => {
+            tracing::warn!(target: "opencode_studio.discovery", error = %err, "mDNS unavailable; discovery disabled");
+            return;
+        }
+    };
+
+    let hostname = local_hostname();
+    let name = normalize_instance_name(instance_name, &hostname);
+    let advertised = !bind.ip().is_loopback();
+    let version = env!("CARGO_PKG_VERSION");
+    let tls_flag = if tls { "1" } else { "0" };
+    let properties = [
+        ("name", name.as_str()),
+        ("version", version),
+        ("tls", tls_flag),
+        ("path", "/"),
+    ];
+    let host = format!("{}.local.", mdns_host_label(&hostname));
+    let ip = if bind.ip().is_unspecified() {
+        String::new()
+    } else {
+        bind.ip().to_string()
+    };
+    let info = ServiceInfo::new(SERVICE_TYPE, &name, &host, ip, bind.port(), &properties[..]).map(
+        |info| {
+            if bind.ip().is_unspecified() {
+                info.enable_addr_auto()
+            } else {
+                info
+            }
+        },
+    );
+    let own_fullname = match info {
+        Ok(info) => {
+            let fullname = info.get_fullname().to_string();
+            if advertised {
+                match daemon.register(info) {
+                    Ok(()) => tracing::info!(
+                        target: "opencode_studio.discovery",
+                        instance = %name,
+                        "Advertising via mDNS"
+                    ),
+                    Err(err) => tracing::warn!(
+                        target: "opencode_studio.discovery",
+                        error = %err,
+                        "mDNS registration failed"
+                    ),
+                }
+            } else {
+                tracing::info!(
+                    target: "opencode_studio.discovery",
+                    "Bound to loopback; browsing for peers without advertising"
+                );
+            }
+            fullname
+        }
+        Err(err) => {
+            tracing::warn!(target: "opencode_studio.discovery", error = %err, "invalid mDNS service info");
+            String::new()
+        }
+    };
+
+    let events = match daemon.browse(SERVICE_TYPE) {
+        Ok(events) => events,
+        Err(err) => {
+            tracing::warn!(target: "opencode_studio.discovery", error = %err, "mDNS browse failed");
+            return;
+        }
+    };
+
+    let discovery = Discovery {
+        own_fullname,
+        this: DiscoverySelf {
+            name,
+            port: bind.port(),
+            tls,
+            advertised,
+        },
+        peers: Mutex::new(HashMap::new()),
+        _daemon: daemon,
+    };
+    if DISCOVERY.set(discovery).is_err() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        while let Ok(event) = events.recv_async().await {
+            let Some(discovery) = DISCOVERY.get() else {
+                break;
+            };
+            match event {
+                ServiceEvent::ServiceResolved(info) => {
+                    if info.get_fullname() == discovery.own_fullname {
+                        continue;
+                    }
+                    let peer = peer_from_info(&info);
+                    let mut peers = discovery.peers.lock().unwrap_or_else(|e| e.into_inner());
+                    if peers.len() < MAX_PEERS || peers.contains_key(&peer.fullname) {
+                        peers.insert(peer.fullname.clone(), peer);
+                    }
+                }
+                ServiceEvent::ServiceRemoved(_, fullname) => {
+                    let mut peers = discovery.peers.lock().unwrap_or_else(|e| e.into_inner());
+                    peers.remove(&fullname);
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+/// Other OpenCode Studio instances seen on the LAN.
+pub(crate) async fn discovery_peers() -> Json<DiscoveryPeersResponse> {
+    let Some(discovery) = DISCOVERY.get() else {
+        return Json(DiscoveryPeersResponse {
+            enabled: false,
+            this: None,
+            peers: Vec::new(),
+        });
+    };
+    let mut peers: Vec<DiscoveryPeer> = discovery
+        .peers
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect();
+    peers.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| a.fullname.cmp(&b.fullname))
+    });
+    Json(DiscoveryPeersResponse {
+        enabled: true,
+        this: Some(discovery.this.clone()),
+        peers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_urls_are_normalized() {
+        assert_eq!(mdns_host_label("my_box.example.com"), "my-box");
+        assert_eq!(mdns_host_label("__"), "opencode-studio");
+        assert_eq!(
+            normalize_instance_name(None, "devbox"),
+            "OpenCode Studio (devbox)"
+        );
+        assert_eq!(
+            normalize_instance_name(Some(" team.studio "), "devbox"),
+            "team studio"
+        );
+
+        let addrs: Vec<IpAddr> = vec!["fe80::1".parse().unwrap(), "192.168.1.20".parse().unwrap()];
+        assert_eq!(
+            peer_url(&addrs, 3000, false).as_deref(),
+            Some("http://192.168.1.20:3000")
+        );
+        let v6: Vec<IpAddr> = vec!["fd00::2".parse().unwrap()];
+        assert_eq!(
+            peer_url(&v6, 443, true).as_deref(),
+            Some("https://[fd00::2]:443")
+        );
+        assert_eq!(peer_url(&[], 443, true), None);
+    }
+}
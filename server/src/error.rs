@@ -3,13 +3,14 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::Serialize;
 use thiserror::Error;
 
 pub type ApiResult<T> = Result<T, AppError>;
 
-#[derive(Debug, Serialize)]
-struct ErrorBody {
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct ErrorBody {
     error: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     locked: Option<bool>,
@@ -28,6 +29,9 @@ pub enum AppError {
     #[error("{message}")]
     NotFound { message: String },
 
+    #[error("{message}")]
+    Conflict { message: String },
+
     #[error("{message}")]
     PayloadTooLarge { message: String },
 
@@ -60,6 +64,12 @@ impl AppError {
         }
     }
 
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict {
+            message: message.into(),
+        }
+    }
+
     pub fn payload_too_large(message: impl Into<String>) -> Self {
         Self::PayloadTooLarge {
             message: message.into(),
@@ -89,6 +99,7 @@ impl AppError {
             Self::BadRequest { .. } => StatusCode::BAD_REQUEST,
             Self::Forbidden { .. } => StatusCode::FORBIDDEN,
             Self::NotFound { .. } => StatusCode::NOT_FOUND,
+            Self::Conflict { .. } => StatusCode::CONFLICT,
             Self::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
             Self::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
             Self::BadGateway { .. } => StatusCode::BAD_GATEWAY,
@@ -101,6 +112,7 @@ impl AppError {
             Self::BadRequest { message }
             | Self::Forbidden { message }
             | Self::NotFound { message }
+            | Self::Conflict { message }
             | Self::PayloadTooLarge { message }
             | Self::TooManyRequests { message }
             | Self::BadGateway { message }
@@ -0,0 +1,766 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, Query, State},
+    http::StatusCode,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{ApiResult, AppError};
+
+const DEFAULT_REMOTE: &str = "origin";
+const FORGE_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_TOKEN_CHARS: usize = 512;
+const PAGE_SIZE: &str = "50";
+const COMMENTS_PAGE_SIZE: &str = "100";
+const USER_AGENT: &str = "opencode-studio";
+
+static TOKEN_STORE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ForgeKind {
+    Github,
+    Gitlab,
+}
+
+impl ForgeKind {
+    fn label(self) -> &'static str {
+        match self {
+            ForgeKind::Github => "GitHub",
+            ForgeKind::Gitlab => "GitLab",
+        }
+    }
+
+    fn detect(host: &str) -> Option<Self> {
+        let host = host.to_ascii_lowercase();
+        if host == "github.com" || host.split(['.', '-']).any(|part| part == "github") {
+            Some(ForgeKind::Github)
+        } else if host == "gitlab.com" || host.split(['.', '-']).any(|part| part == "gitlab") {
+            Some(ForgeKind::Gitlab)
+        } else {
+            None
+        }
+    }
+
+    fn api_base(self, host: &str) -> String {
+        match self {
+            ForgeKind::Github if host.eq_ignore_ascii_case("github.com") => {
+                "https://api.github.com".to_string()
+            }
+            ForgeKind::Github => format!("https://{host}/api/v3"),
+            ForgeKind::Gitlab => format!("https://{host}/api/v4"),
+        }
+    }
+}
+
+/// Host and `owner/repo` path of a remote URL (https, ssh or scp-like).
+fn parse_remote(url: &str) -> Option<(String, String)> {
+    let url = url.trim();
+    let (host, path) = if let Some((_, rest)) = url.split_once("://") {
+        let (authority, path) = rest.split_once('/')?;
+        let host = authority.rsplit('@').next().unwrap_or(authority);
+        // Drop the port: forges serve the API on the default HTTPS port.
+        let host = host.split(':').next().unwrap_or(host);
+        (host, path)
+    } else {
+        // scp-like: git@host:owner/repo.git
+        let (authority, path) = url.split_once(':')?;
+        (authority.rsplit('@').next().unwrap_or(authority), path)
+    };
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    if host.is_empty() || !path.contains('/') {
+        return None;
+    }
+    Some((host.to_ascii_lowercase(), path.to_string()))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct ForgeQuery {
+    pub directory: Option<String>,
+    pub remote: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForgeTokenRecord {
+    /// Repository root the token belongs to.
+    project: String,
+    host: String,
+    /// Used when the host name does not identify the forge.
+    #[serde(default)]
+    kind: Option<ForgeKind>,
+    /// Sealed with [`crate::secrets::seal`], bound to project and host.
+    ciphertext: String,
+    updated_at: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ForgeTokenStore {
+    #[serde(default)]
+    tokens: Vec<ForgeTokenRecord>,
+}
+
+/// Everything needed to talk to the forge for one repository.
+struct ForgeContext {
+    project: String,
+    kind: Option<ForgeKind>,
+    host: String,
+    repo_path: String,
+    token: Option<String>,
+}
+
+impl ForgeContext {
+    fn require(&self) -> ApiResult<(ForgeKind, &str)> {
+        let kind = self.kind.ok_or_else(|| {
+            AppError::bad_request(format!(
+                "Unsupported forge host '{}'; set the forge kind when storing a token",
+                self.host
+            ))
+        })?;
+        let token = self.token.as_deref().ok_or_else(|| {
+            AppError::bad_request(format!("No {} token stored for this project", kind.label()))
+        })?;
+        Ok((kind, token))
+    }
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn token_aad(project: &str, host: &str) -> Vec<u8> {
+    format!("{project}\n{host}").into_bytes()
+}
+
+async fn load_store(db: &crate::studio_db::StudioDb) -> ApiResult<ForgeTokenStore> {
+    db.get_json::<ForgeTokenStore>(crate::studio_db::KV_KEY_FORGE_TOKENS)
+        .await
+        .map(Option::unwrap_or_default)
+        .map_err(AppError::internal)
+}
+
+async fn git_output(dir: &Path, args: &[&str]) -> Option<String> {
+    match crate::git::run_git(dir, args).await {
+        Ok((0, out, _)) => Some(out.trim().to_string()).filter(|s| !s.is_empty()),
+        _ => None,
+    }
+}
+
+async fn repo_root(q: &ForgeQuery) -> ApiResult<PathBuf> {
+    let dir = q
+        .directory
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| AppError::bad_request("directory parameter is required"))?;
+    let dir = crate::git::abs_path(dir);
+    git_output(&dir, &["rev-parse", "--show-toplevel"])
+        .await
+        .map(PathBuf::from)
+        .ok_or_else(|| AppError::bad_request("directory is not inside a git repository"))
+}
+
+async fn resolve_context(
+    db: &crate::studio_db::StudioDb,
+    q: &ForgeQuery,
+) -> ApiResult<(PathBuf, ForgeContext)> {
+    let root = repo_root(q).await?;
+    let remote = q
+        .remote
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .unwrap_or(DEFAULT_REMOTE);
+    let url = git_output(&root, &["remote", "get-url", remote])
+        .await
+        .ok_or_else(|| AppError::bad_request(format!("remote '{remote}' is not configured")))?;
+    let (host, repo_path) = parse_remote(&url).ok_or_else(|| {
+        AppError::bad_request(format!(
+            "remote '{remote}' does not point at a hosted repository"
+        ))
+    })?;
+
+    let project = root.to_string_lossy().into_owned();
+    let store = load_store(db).await?;
+    let record = store
+        .tokens
+        .iter()
+        .find(|t| t.project == project && t.host == host);
+    let token = match record {
+        Some(record) => {
+            let token = crate::secrets::open(&token_aad(&project, &host), &record.ciphertext)
+                .map_err(|err| {
+                    AppError::internal(format!("stored forge token is unreadable: {err}"))
+                })?;
+            Some(token)
+        }
+        None => None,
+    };
+    let kind = ForgeKind::detect(&host).or(record.and_then(|r| r.kind));
+    Ok((
+        root,
+        ForgeContext {
+            project,
+            kind,
+            host,
+            repo_path,
+            token,
+        },
+    ))
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ForgeInfoResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<ForgeKind>,
+    host: String,
+    repo: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_base: Option<String>,
+    has_token: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
+}
+
+/// Which forge hosts the repository, and whether a token is stored for it.
+pub(crate) async fn forge_info(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<ForgeQuery>,
+) -> ApiResult<Json<ForgeInfoResponse>> {
+    let (root, ctx) = resolve_context(&state.studio_db, &q).await?;
+    Ok(Json(ForgeInfoResponse {
+        api_base: ctx.kind.map(|kind| kind.api_base(&ctx.host)),
+        kind: ctx.kind,
+        has_token: ctx.token.is_some(),
+        branch: crate::git::git_current_branch(&root).await,
+        host: ctx.host,
+        repo: ctx.repo_path,
+    }))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ForgeTokenBody {
+    pub token: String,
+    #[serde(default)]
+    pub kind: Option<ForgeKind>,
+}
+
+pub(crate) async fn forge_token_put(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<ForgeQuery>,
+    Json(body): Json<ForgeTokenBody>,
+) -> ApiResult<StatusCode> {
+    let token = body.token.trim();
+    if token.is_empty() {
+        return Err(AppError::bad_request("token is required"));
+    }
+    if token.chars().count() > MAX_TOKEN_CHARS {
+        return Err(AppError::bad_request("token is too long"));
+    }
+    let (_, ctx) = resolve_context(&state.studio_db, &q).await?;
+    if ctx.kind.is_none() && body.kind.is_none() {
+        return Err(AppError::bad_request(format!(
+            "Cannot tell which forge '{}' is; pass kind \"github\" or \"gitlab\"",
+            ctx.host
+        )));
+    }
+    let ciphertext = crate::secrets::seal(&token_aad(&ctx.project, &ctx.host), token)
+        .map_err(AppError::internal)?;
+
+    let _guard = TOKEN_STORE_LOCK.lock().await;
+    let mut store = load_store(&state.studio_db).await?;
+    store
+        .tokens
+        .retain(|t| !(t.project == ctx.project && t.host == ctx.host));
+    store.tokens.push(ForgeTokenRecord {
+        project: ctx.project.clone(),
+        host: ctx.host.clone(),
+        kind: body.kind,
+        ciphertext,
+        updated_at: now_millis(),
+    });
+    state
+        .studio_db
+        .set_json(crate::studio_db::KV_KEY_FORGE_TOKENS, &store)
+        .await
+        .map_err(AppError::internal)?;
+    tracing::info!(project = %ctx.project, host = %ctx.host, "Forge token stored");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub(crate) async fn forge_token_delete(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<ForgeQuery>,
+) -> ApiResult<StatusCode> {
+    let root = repo_root(&q).await?;
+    let project = root.to_string_lossy().into_owned();
+
+    let _guard = TOKEN_STORE_LOCK.lock().await;
+    let mut store = load_store(&state.studio_db).await?;
+    let before = store.tokens.len();
+    store.tokens.retain(|t| t.project != project);
+    if store.tokens.len() == before {
+        return Err(AppError::not_found(
+            "No forge token stored for this project",
+        ));
+    }
+    state
+        .studio_db
+        .set_json(crate::studio_db::KV_KEY_FORGE_TOKENS, &store)
+        .await
+        .map_err(AppError::internal)?;
+    tracing::info!(project = %project, "Forge token removed");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ForgePullRequest {
+    number: u64,
+    title: String,
+    url: String,
+    state: String,
+    draft: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    source_branch: String,
+    target_branch: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ForgeReviewComment {
+    id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_at: Option<String>,
+}
+
+fn str_field(value: &Value, pointer: &str) -> Option<String> {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+fn pull_from_json(kind: ForgeKind, v: &Value) -> Option<ForgePullRequest> {
+    Some(match kind {
+        ForgeKind::Github => ForgePullRequest {
+            number: v.get("number")?.as_u64()?,
+            title: str_field(v, "/title").unwrap_or_default(),
+            url: str_field(v, "/html_url").unwrap_or_default(),
+            state: str_field(v, "/state").unwrap_or_default(),
+            draft: v.get("draft").and_then(Value::as_bool).unwrap_or(false),
+            author: str_field(v, "/user/login"),
+            source_branch: str_field(v, "/head/ref").unwrap_or_default(),
+            target_branch: str_field(v, "/base/ref").unwrap_or_default(),
+            created_at: str_field(v, "/created_at"),
+            updated_at: str_field(v, "/updated_at"),
+        },
+        ForgeKind::Gitlab => ForgePullRequest {
+            number: v.get("iid")?.as_u64()?,
+            title: str_field(v, "/title").unwrap_or_default(),
+            url: str_field(v, "/web_url").unwrap_or_default(),
+            state: str_field(v, "/state").unwrap_or_default(),
+            draft: v
+                .get("draft")
+                .or_else(|| v.get("work_in_progress"))
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            author: str_field(v, "/author/username"),
+            source_branch: str_field(v, "/source_branch").unwrap_or_default(),
+            target_branch: str_field(v, "/target_branch").unwrap_or_default(),
+            created_at: str_field(v, "/created_at"),
+            updated_at: str_field(v, "/updated_at"),
+        },
+    })
+}
+
+fn comment_from_json(kind: ForgeKind, v: &Value) -> Option<ForgeReviewComment> {
+    Some(match kind {
+        ForgeKind::Github => ForgeReviewComment {
+            id: v.get("id")?.as_u64()?,
+            author: str_field(v, "/user/login"),
+            body: str_field(v, "/body").unwrap_or_default(),
+            path: str_field(v, "/path"),
+            line: v
+                .get("line")
+                .or_else(|| v.get("original_line"))
+                .and_then(Value::as_u64),
+            url: str_field(v, "/html_url"),
+            created_at: str_field(v, "/created_at"),
+        },
+        ForgeKind::Gitlab => {
+            // System notes record pushes and label changes, not review feedback.
+            if v.get("system").and_then(Value::as_bool).unwrap_or(false) {
+                return None;
+            }
+            ForgeReviewComment {
+                id: v.get("id")?.as_u64()?,
+                author: str_field(v, "/author/username"),
+                body: str_field(v, "/body").unwrap_or_default(),
+                path: str_field(v, "/position/new_path"),
+                line: v
+                    .pointer("/position/new_line")
+                    .or_else(|| v.pointer("/position/old_line"))
+                    .and_then(Value::as_u64),
+                url: None,
+                created_at: str_field(v, "/created_at"),
+            }
+        }
+    })
+}
+
+struct ForgeClient<'a> {
+    kind: ForgeKind,
+    base: String,
+    token: &'a str,
+    /// `repos/{owner}/{repo}` or `projects/{url-encoded path}`.
+    repo: String,
+    http: reqwest::Client,
+}
+
+impl<'a> ForgeClient<'a> {
+    fn new(ctx: &'a ForgeContext) -> ApiResult<Self> {
+        let (kind, token) = ctx.require()?;
+        let repo = match kind {
+            ForgeKind::Github => format!("repos/{}", ctx.repo_path),
+            ForgeKind::Gitlab => format!("projects/{}", urlencoding::encode(&ctx.repo_path)),
+        };
+        let http = crate::tls_roots::client_builder()
+            .timeout(FORGE_TIMEOUT)
+            .build()
+            .map_err(|err| AppError::internal(format!("build http client: {err}")))?;
+        Ok(Self {
+            kind,
+            base: kind.api_base(&ctx.host),
+            token,
+            repo,
+            http,
+        })
+    }
+
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<Value>,
+    ) -> ApiResult<Value> {
+        let url =
+            url::Url::parse_with_params(&format!("{}/{}{}", self.base, self.repo, path), query)
+                .map_err(|err| AppError::bad_request(format!("invalid forge URL: {err}")))?;
+        let mut req = self
+            .http
+            .request(method, url)
+            .header(reqwest::header::USER_AGENT, USER_AGENT);
+        req = match self.kind {
+            ForgeKind::Github => req
+                .bearer_auth(self.token)
+                .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+                .header("X-GitHub-Api-Version", "2022-11-28"),
+            ForgeKind::Gitlab => req.header("PRIVATE-TOKEN", self.token),
+        };
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+        let label = self.kind.label();
+        let resp = req
+            .send()
+            .await
+            .map_err(|err| AppError::bad_gateway(format!("{label} request failed: {err}")))?;
+        let status = resp.status();
+        let payload = resp.json::<Value>().await.unwrap_or(Value::Null);
+        if status.is_success() {
+            return Ok(payload);
+        }
+        Err(forge_error(label, status, &payload))
+    }
+}
+
+fn forge_error(label: &str, status: reqwest::StatusCode, payload: &Value) -> AppError {
+    let detail = payload
+        .get("message")
+        .and_then(|m| match m {
+            Value::String(s) => Some(s.clone()),
+            Value::Null => None,
+            other => Some(other.to_string()),
+        })
+        .or_else(|| str_field(payload, "/error"));
+    // GitHub puts the useful part of validation failures in `errors`.
+    let extra = payload
+        .get("errors")
+        .and_then(Value::as_array)
+        .and_then(|errs| errs.first())
+        .and_then(|e| str_field(e, "/message"));
+    let message = match (detail, extra) {
+        (Some(d), Some(e)) => format!("{label}: {d} ({e})"),
+        (Some(d), None) => format!("{label}: {d}"),
+        (None, _) => format!("{label} returned HTTP {status}"),
+    };
+    match status.as_u16() {
+        401 => AppError::forbidden(format!("{message}; check the stored token")),
+        403 => AppError::forbidden(message),
+        404 => AppError::not_found(message),
+        409 | 422 => AppError::conflict(message),
+        429 => AppError::too_many_requests(message),
+        _ => AppError::bad_gateway(message),
+    }
+}
+
+/// Open pull/merge requests of the repository.
+pub(crate) async fn forge_pulls_list(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<ForgeQuery>,
+) -> ApiResult<Json<Vec<ForgePullRequest>>> {
+    let (_, ctx) = resolve_context(&state.studio_db, &q).await?;
+    let client = ForgeClient::new(&ctx)?;
+    let (path, query) = match client.kind {
+        ForgeKind::Github => ("/pulls", [("state", "open"), ("per_page", PAGE_SIZE)]),
+        ForgeKind::Gitlab => (
+            "/merge_requests",
+            [("state", "opened"), ("per_page", PAGE_SIZE)],
+        ),
+    };
+    let payload = client
+        .send(reqwest::Method::GET, path, &query, None)
+        .await?;
+    let pulls = payload
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|v| pull_from_json(client.kind, v))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(Json(pulls))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ForgePullCreateBody {
+    pub title: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Target branch; the repository default branch when omitted.
+    #[serde(default)]
+    pub base: Option<String>,
+    #[serde(default)]
+    pub draft: bool,
+}
+
+/// Open a pull/merge request from the current branch. The branch must
+/// already be pushed; its upstream name is used as the source branch.
+pub(crate) async fn forge_pulls_create(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<ForgeQuery>,
+    Json(body): Json<ForgePullCreateBody>,
+) -> ApiResult<(StatusCode, Json<ForgePullRequest>)> {
+    let title = body.title.trim();
+    if title.is_empty() {
+        return Err(AppError::bad_request("title is required"));
+    }
+    let (root, ctx) = resolve_context(&state.studio_db, &q).await?;
+    let client = ForgeClient::new(&ctx)?;
+
+    let branch = crate::git::git_current_branch(&root)
+        .await
+        .ok_or_else(|| AppError::bad_request("Cannot open a pull request from detached HEAD"))?;
+    let head = crate::git::git_upstream_ref(&root)
+        .await
+        .and_then(|upstream| upstream.split_once('/').map(|(_, b)| b.to_string()))
+        .ok_or_else(|| {
+            AppError::bad_request(format!(
+                "Branch '{branch}' has no upstream; push it before opening a pull request"
+            ))
+        })?;
+
+    let base = match body
+        .base
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        Some(base) => base.to_string(),
+        None => {
+            let repo = client.send(reqwest::Method::GET, "", &[], None).await?;
+            str_field(&repo, "/default_branch").ok_or_else(|| {
+                AppError::bad_gateway(format!("{} did not report a default branch", ctx.host))
+            })?
+        }
+    };
+    if base == head {
+        return Err(AppError::bad_request(format!(
+            "Source and target branch are both '{base}'"
+        )));
+    }
+
+    let description = body.body.unwrap_or_default();
+    let (path, payload) = match client.kind {
+        ForgeKind::Github => (
+            "/pulls",
+            serde_json::json!({
+                "title": title,
+                "head": head,
+                "base": base,
+                "body": description,
+                "draft": body.draft,
+            }),
+        ),
+        ForgeKind::Gitlab => (
+            "/merge_requests",
+            serde_json::json!({
+                "title": if body.draft { format!("Draft: {title}") } else { title.to_string() },
+                "source_branch": head,
+                "target_branch": base,
+                "description": description,
+            }),
+        ),
+    };
+    let created = client
+        .send(reqwest::Method::POST, path, &[], Some(payload))
+        .await?;
+    let pull = pull_from_json(client.kind, &created).ok_or_else(|| {
+        AppError::bad_gateway(format!("{} returned an unexpected response", ctx.host))
+    })?;
+    tracing::info!(
+        host = %ctx.host,
+        repo = %ctx.repo_path,
+        number = pull.number,
+        "Opened pull request"
+    );
+    Ok((StatusCode::CREATED, Json(pull)))
+}
+
+/// Review comments of one pull/merge request, oldest first.
+pub(crate) async fn forge_pull_comments(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(number): AxumPath<u64>,
+    Query(q): Query<ForgeQuery>,
+) -> ApiResult<Json<Vec<ForgeReviewComment>>> {
+    let (_, ctx) = resolve_context(&state.studio_db, &q).await?;
+    let client = ForgeClient::new(&ctx)?;
+    let (path, query) = match client.kind {
+        ForgeKind::Github => (
+            format!("/pulls/{number}/comments"),
+            vec![("per_page", COMMENTS_PAGE_SIZE)],
+        ),
+        ForgeKind::Gitlab => (
+            format!("/merge_requests/{number}/notes"),
+            vec![
+                ("per_page", COMMENTS_PAGE_SIZE),
+                ("sort", "asc"),
+                ("order_by", "created_at"),
+            ],
+        ),
+    };
+    let payload = client
+        .send(reqwest::Method::GET, &path, &query, None)
+        .await?;
+    let comments = payload
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|v| comment_from_json(client.kind, v))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(Json(comments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remotes_resolve_to_forge_and_repo() {
+        assert_eq!(
+            parse_remote("git@github.com:acme/widgets.git"),
+            Some(("github.com".to_string(), "acme/widgets".to_string()))
+        );
+        assert_eq!(
+            parse_remote("https://user:pw@GitLab.example.com:8443/group/sub/proj.git/"),
+            Some((
+                "gitlab.example.com".to_string(),
+                "group/sub/proj".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_remote("ssh://git@github.acme.io/acme/widgets"),
+            Some(("github.acme.io".to_string(), "acme/widgets".to_string()))
+        );
+        assert_eq!(parse_remote("/srv/repos/widgets.git"), None);
+
+        assert_eq!(ForgeKind::detect("github.com"), Some(ForgeKind::Github));
+        assert_eq!(
+            ForgeKind::detect("gitlab.example.com"),
+            Some(ForgeKind::Gitlab)
+        );
+        assert_eq!(ForgeKind::detect("git.example.com"), None);
+        assert_eq!(
+            ForgeKind::Github.api_base("github.com"),
+            "https://api.github.com"
+        );
+        assert_eq!(
+            ForgeKind::Github.api_base("github.acme.io"),
+            "https://github.acme.io/api/v3"
+        );
+    }
+
+    #[test]
+    fn tokens_round_trip_and_are_bound_to_project() {
+        use crate::secrets::{open_with, seal_with};
+        let key = [7u8; 32];
+        let aad = token_aad("/work/widgets", "github.com");
+        let sealed = seal_with(&key, &aad, "ghp_secret").unwrap();
+        assert_eq!(open_with(&key, &aad, &sealed).unwrap(), "ghp_secret");
+
+        let other = token_aad("/work/other", "github.com");
+        assert!(open_with(&key, &other, &sealed).is_err());
+    }
+
+    #[test]
+    fn gitlab_system_notes_are_skipped() {
+        let note = serde_json::json!({
+            "id": 3, "body": "added 1 commit", "system": true,
+        });
+        assert!(comment_from_json(ForgeKind::Gitlab, &note).is_none());
+        let review = serde_json::json!({
+            "id": 4,
+            "body": "nit",
+            "system": false,
+            "author": {"username": "sam"},
+            "position": {"new_path": "src/lib.rs", "new_line": 12},
+        });
+        let comment = comment_from_json(ForgeKind::Gitlab, &review).unwrap();
+        assert_eq!(comment.path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(comment.line, Some(12));
+        assert_eq!(comment.author.as_deref(), Some("sam"));
+    }
+}
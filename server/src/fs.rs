@@ -1,11 +1,12 @@
 use std::{
     collections::HashSet,
+    convert::Infallible,
     ffi::OsStr,
     path::{Component, Path, PathBuf},
-    time::Instant,
+    time::{Duration, Instant},
 };
 
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock};
 
 use crate::{ApiResult, AppError};
 use axum::{
@@ -13,13 +14,19 @@ use axum::{
     body::{Body, Bytes},
     extract::{Query, State},
     http::{HeaderMap, StatusCode},
-    response::Response,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
 };
 use ignore::WalkBuilder;
 use regex::{Regex, RegexBuilder};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
 use tokio::io::{AsyncReadExt, AsyncSeekExt, SeekFrom};
 use tokio::process::Command;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
 
 use crate::path_utils::{home_dir_env, normalize_directory_path};
 
@@ -34,6 +41,9 @@ const MAX_CONTENT_SEARCH_MAX_MATCHES_PER_FILE: usize = 300;
 const DEFAULT_CONTENT_SEARCH_CONTEXT_CHARS: usize = 48;
 const MAX_CONTENT_SEARCH_CONTEXT_CHARS: usize = 160;
 const MAX_CONTENT_SEARCH_FILE_BYTES: u64 = 2 * 1024 * 1024;
+const MAX_CONTENT_SEARCH_GLOBS: usize = 32;
+const MAX_CONTENT_SEARCH_STREAM_FILES: usize = 50_000;
+const CONTENT_SEARCH_STREAM_TIMEOUT: Duration = Duration::from_secs(60);
 const MAX_CONTENT_REPLACE_PATHS: usize = 4000;
 const MAX_FS_CHANGE_EVENT_PATHS: usize = 160;
 
@@ -137,11 +147,11 @@ pub(crate) fn publish_fs_changed_event<I>(
     }
 }
 
-fn has_parent_dir_component(p: &Path) -> bool {
+pub(crate) fn has_parent_dir_component(p: &Path) -> bool {
     p.components().any(|c| matches!(c, Component::ParentDir))
 }
 
-fn resolve_path(input: &str) -> PathBuf {
+pub(crate) fn resolve_path(input: &str) -> PathBuf {
     let normalized = normalize_directory_path(input);
     PathBuf::from(normalized)
 }
@@ -182,10 +192,18 @@ fn is_path_within_base(base: &str, target: &str) -> bool {
         .is_some_and(|suffix| suffix.starts_with('/'))
 }
 
-fn to_api_path(path: &Path) -> String {
+pub(crate) fn to_api_path(path: &Path) -> String {
     path.to_string_lossy().replace('\\', "/")
 }
 
+/// A path relative to a workspace root, joined with `/` on every platform.
+pub(crate) fn rel_api_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 fn ensure_within_base(base: &Path, target: &Path) -> ApiResult<()> {
     let base = normalize_for_workspace_compare(base);
     let target = normalize_for_workspace_compare(target);
@@ -225,7 +243,7 @@ pub(crate) async fn validate_directory(candidate: &str) -> ApiResult<PathBuf> {
     Ok(abs)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ProjectDirQuery {
     pub directory: Option<String>,
 }
@@ -252,7 +270,7 @@ pub async fn resolve_project_directory(
     Err(AppError::bad_request("Directory parameter is required"))
 }
 
-async fn resolve_workspace_path_from_context(
+pub(crate) async fn resolve_workspace_path_from_context(
     state: &crate::AppState,
     headers: &HeaderMap,
     query_directory: Option<&str>,
@@ -283,12 +301,12 @@ async fn resolve_workspace_path_from_context(
     Ok((base, candidate))
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct FsHomeResponse {
     pub home: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct SuccessPathResponse {
     pub success: bool,
     pub path: String,
@@ -302,7 +320,7 @@ pub async fn fs_home() -> ApiResult<Json<FsHomeResponse>> {
     Ok(Json(FsHomeResponse { home }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct MkdirBody {
     pub path: Option<String>,
 }
@@ -344,7 +362,7 @@ pub async fn fs_mkdir(
     }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ReadQuery {
     pub path: Option<String>,
 }
@@ -401,21 +419,24 @@ pub async fn fs_read(Query(q): Query<ReadQuery>) -> ApiResult<Response> {
 
     crate::fs_watch::hint_watch_path(&abs);
 
+    let version = FileVersion::new(&meta, content.as_bytes());
     Ok(Response::builder()
         .status(StatusCode::OK)
         .header("content-type", "text/plain")
+        .header("etag", format!("\"{}\"", version.hash))
+        .header(FILE_MTIME_HEADER, version.mtime)
         .body(Body::from(content))
         .unwrap())
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ReadChunkQuery {
     pub path: Option<String>,
     pub offset: Option<usize>,
     pub limit: Option<usize>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ReadChunkResponse {
     pub path: String,
@@ -427,6 +448,12 @@ pub struct ReadChunkResponse {
     pub has_more: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub next_offset: Option<usize>,
+    /// Modification time (ms since epoch); echo it as `expectedMtime` on save.
+    pub mtime: u64,
+    /// Content hash, set only when this chunk is the whole file; echo it as
+    /// `expectedHash` on save.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
 }
 
 fn decode_utf8_chunk(bytes: &[u8]) -> ApiResult<(String, usize)> {
@@ -510,6 +537,8 @@ pub async fn fs_read_chunk(Query(q): Query<ReadChunkQuery>) -> ApiResult<Json<Re
             total_bytes,
             has_more,
             next_offset: if has_more { Some(offset) } else { None },
+            mtime: file_mtime_millis(&meta),
+            hash: None,
         }));
     }
 
@@ -537,6 +566,8 @@ pub async fn fs_read_chunk(Query(q): Query<ReadChunkQuery>) -> ApiResult<Json<Re
 
     crate::fs_watch::hint_watch_path(&abs);
 
+    let whole_file = offset == 0 && consumed_bytes as u64 == total_bytes_u64;
+
     Ok(Json(ReadChunkResponse {
         path: to_api_path(&abs),
         content,
@@ -546,10 +577,12 @@ pub async fn fs_read_chunk(Query(q): Query<ReadChunkQuery>) -> ApiResult<Json<Re
         total_bytes,
         has_more,
         next_offset: if has_more { Some(loaded_bytes) } else { None },
+        mtime: file_mtime_millis(&meta),
+        hash: whole_file.then(|| FileVersion::new(&meta, &buffer).hash),
     }))
 }
 
-fn mime_for_ext(path: &Path) -> &'static str {
+pub(crate) fn mime_for_ext(path: &Path) -> &'static str {
     match path
         .extension()
         .and_then(OsStr::to_str)
@@ -641,7 +674,7 @@ pub async fn fs_raw(Query(q): Query<ReadQuery>) -> ApiResult<Response> {
         .unwrap())
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct FsPathQuery {
     pub directory: Option<String>,
     pub path: Option<String>,
@@ -670,7 +703,7 @@ fn content_disposition_for(path: &Path, disposition_type: &str) -> String {
     )
 }
 
-fn content_disposition_attachment(path: &Path) -> String {
+pub(crate) fn content_disposition_attachment(path: &Path) -> String {
     content_disposition_for(path, "attachment")
 }
 
@@ -731,14 +764,14 @@ pub async fn fs_download(
         .unwrap())
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct UploadQuery {
     pub directory: Option<String>,
     pub path: Option<String>,
     pub overwrite: Option<bool>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct UploadResponse {
     pub success: bool,
     pub path: String,
@@ -830,10 +863,156 @@ pub async fn fs_upload(
     }))
 }
 
-#[derive(Debug, Deserialize)]
+/// Identity of a file's contents as last seen by a client. Reads return it in
+/// the `ETag` and `X-File-Mtime` headers; saves echo it back to detect edits
+/// made on disk in between.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct FileVersion {
+    /// Modification time in milliseconds since the Unix epoch.
+    pub mtime: u64,
+    /// Hex SHA-256 of the contents.
+    pub hash: String,
+}
+
+const FILE_MTIME_HEADER: &str = "x-file-mtime";
+
+fn file_mtime_millis(meta: &std::fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl FileVersion {
+    fn new(meta: &std::fs::Metadata, content: &[u8]) -> Self {
+        let mtime = file_mtime_millis(meta);
+        let hash = Sha256::digest(content)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        Self { mtime, hash }
+    }
+
+    /// `None` when nothing exists at `path`.
+    async fn current(path: &Path) -> ApiResult<Option<Self>> {
+        let meta = match tokio::fs::metadata(path).await {
+            Ok(meta) => meta,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(map_write_error(err)),
+        };
+        if !meta.is_file() {
+            return Err(AppError::bad_request("Specified path is not a file"));
+        }
+        let content = tokio::fs::read(path).await.map_err(map_write_error)?;
+        Ok(Some(Self::new(&meta, &content)))
+    }
+}
+
+static PATH_WRITE_LOCKS: LazyLock<dashmap::DashMap<PathBuf, Arc<AsyncMutex<()>>>> =
+    LazyLock::new(dashmap::DashMap::new);
+
+/// Serializes version-checked writes to one path, so two saves carrying the
+/// same expected version cannot both pass the check before either renames.
+struct PathWriteLock {
+    path: PathBuf,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl PathWriteLock {
+    async fn acquire(path: &Path) -> Self {
+        let lock = PATH_WRITE_LOCKS
+            .entry(path.to_path_buf())
+            .or_insert_with(|| Arc::new(AsyncMutex::new(())))
+            .clone();
+        Self {
+            path: path.to_path_buf(),
+            guard: Some(lock.lock_owned().await),
+        }
+    }
+}
+
+impl Drop for PathWriteLock {
+    fn drop(&mut self) {
+        drop(self.guard.take());
+        // Only the map still holds the mutex once nobody is waiting on it.
+        PATH_WRITE_LOCKS.remove_if(&self.path, |_, lock| Arc::strong_count(lock) == 1);
+    }
+}
+
+/// Optimistic-concurrency check. A matching hash wins over a differing mtime,
+/// so a `touch` alone does not block a save.
+fn check_expected_version(
+    current: Option<&FileVersion>,
+    expected_mtime: Option<u64>,
+    expected_hash: Option<&str>,
+) -> ApiResult<()> {
+    if expected_mtime.is_none() && expected_hash.is_none() {
+        return Ok(());
+    }
+    let Some(current) = current else {
+        return Err(AppError::conflict("File was deleted since it was loaded"));
+    };
+    let unchanged = match expected_hash {
+        Some(hash) => hash.trim_matches('"').eq_ignore_ascii_case(&current.hash),
+        None => expected_mtime == Some(current.mtime),
+    };
+    if unchanged {
+        Ok(())
+    } else {
+        Err(AppError::conflict(
+            "File changed on disk since it was loaded",
+        ))
+    }
+}
+
+fn map_write_error(err: std::io::Error) -> AppError {
+    if err.kind() == std::io::ErrorKind::PermissionDenied {
+        AppError::forbidden("Access denied")
+    } else {
+        AppError::internal(err.to_string())
+    }
+}
+
+/// Write through a temp file in the same directory and rename it over `path`,
+/// so readers never see a partial file. Keeps the existing file's permissions
+/// and writes through symlinks to their target.
+fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let target = match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_symlink() => std::fs::canonicalize(path)?,
+        _ => path.to_path_buf(),
+    };
+    let dir = target.parent().unwrap_or_else(|| Path::new("."));
+    let mut tmp = tempfile::Builder::new()
+        .prefix(".opencode-studio-")
+        .suffix(".tmp")
+        .tempfile_in(dir)?;
+    std::io::Write::write_all(&mut tmp, content)?;
+    if let Ok(meta) = std::fs::metadata(&target) {
+        tmp.as_file().set_permissions(meta.permissions())?;
+    }
+    tmp.as_file().sync_all()?;
+    tmp.persist(&target).map_err(|err| err.error)?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct WriteBody {
     pub path: Option<String>,
     pub content: Option<String>,
+    /// Reject the save with 409 unless the file still has this mtime.
+    pub expected_mtime: Option<u64>,
+    /// Reject the save with 409 unless the file still has this hash (or ETag).
+    pub expected_hash: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WriteResponse {
+    pub success: bool,
+    pub path: String,
+    #[serde(flatten)]
+    pub version: FileVersion,
 }
 
 pub async fn fs_write(
@@ -841,7 +1020,7 @@ pub async fn fs_write(
     headers: HeaderMap,
     Query(q): Query<ProjectDirQuery>,
     Json(body): Json<WriteBody>,
-) -> ApiResult<Json<SuccessPathResponse>> {
+) -> ApiResult<Json<WriteResponse>> {
     let file_path = body
         .path
         .as_deref()
@@ -864,35 +1043,54 @@ pub async fn fs_write(
     )
     .await?;
 
+    let _write_lock = PathWriteLock::acquire(&resolved).await;
+    if body.expected_mtime.is_some() || body.expected_hash.is_some() {
+        let current = FileVersion::current(&resolved).await?;
+        check_expected_version(
+            current.as_ref(),
+            body.expected_mtime,
+            body.expected_hash.as_deref(),
+        )?;
+    }
+
     if let Some(parent) = resolved.parent() {
-        tokio::fs::create_dir_all(parent).await.map_err(|err| {
-            if err.kind() == std::io::ErrorKind::PermissionDenied {
-                AppError::forbidden("Access denied")
-            } else {
-                AppError::internal(err.to_string())
-            }
-        })?;
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(map_write_error)?;
     }
 
-    tokio::fs::write(&resolved, content).await.map_err(|err| {
-        if err.kind() == std::io::ErrorKind::PermissionDenied {
-            AppError::forbidden("Access denied")
-        } else {
-            AppError::internal(err.to_string())
-        }
-    })?;
+    crate::fs_trash::trash_before_overwrite(&base, &resolved).await;
+
+    let target = resolved.clone();
+    let content = Bytes::from(content);
+    let written = content.clone();
+    tokio::task::spawn_blocking(move || write_atomic(&target, &written))
+        .await
+        .map_err(|err| AppError::internal(err.to_string()))?
+        .map_err(map_write_error)?;
+
+    let meta = tokio::fs::metadata(&resolved)
+        .await
+        .map_err(map_write_error)?;
+    let version = FileVersion::new(&meta, &content);
 
     publish_fs_changed_event(&base, "write", [resolved.as_path()], None, None);
 
-    Ok(Json(SuccessPathResponse {
+    Ok(Json(WriteResponse {
         success: true,
         path: to_api_path(&resolved),
+        version,
     }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct DeleteBody {
     pub path: Option<String>,
+    /// For files: reject with 409 unless the file still has this mtime.
+    pub expected_mtime: Option<u64>,
+    /// For files: reject with 409 unless the file still has this hash.
+    pub expected_hash: Option<String>,
 }
 
 pub async fn fs_delete(
@@ -934,18 +1132,29 @@ pub async fn fs_delete(
     }
 
     let meta = meta.unwrap();
-    if meta.is_dir() {
-        tokio::fs::remove_dir_all(&resolved).await
-    } else {
-        tokio::fs::remove_file(&resolved).await
-    }
-    .map_err(|err| {
-        if err.kind() == std::io::ErrorKind::PermissionDenied {
-            AppError::forbidden("Access denied")
+    let _write_lock = PathWriteLock::acquire(&resolved).await;
+    if meta.is_file() && (body.expected_mtime.is_some() || body.expected_hash.is_some()) {
+        let current = FileVersion::current(&resolved).await?;
+        check_expected_version(
+            current.as_ref(),
+            body.expected_mtime,
+            body.expected_hash.as_deref(),
+        )?;
+    }
+    if !crate::fs_trash::trash_before_delete(&base, &resolved).await {
+        if meta.is_dir() {
+            tokio::fs::remove_dir_all(&resolved).await
         } else {
-            AppError::internal(err.to_string())
+            tokio::fs::remove_file(&resolved).await
         }
-    })?;
+        .map_err(|err| {
+            if err.kind() == std::io::ErrorKind::PermissionDenied {
+                AppError::forbidden("Access denied")
+            } else {
+                AppError::internal(err.to_string())
+            }
+        })?;
+    }
 
     publish_fs_changed_event(&base, "delete", [resolved.as_path()], None, None);
 
@@ -955,12 +1164,15 @@ pub async fn fs_delete(
     }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct RenameBody {
     #[serde(rename = "oldPath")]
     pub old_path: Option<String>,
     #[serde(rename = "newPath")]
     pub new_path: Option<String>,
+    /// Replace an existing destination instead of failing with 409.
+    #[serde(default)]
+    pub overwrite: bool,
 }
 
 pub async fn fs_rename(
@@ -1003,6 +1215,16 @@ pub async fn fs_rename(
         ));
     }
 
+    if !body.overwrite
+        && tokio::fs::symlink_metadata(&resolved_new).await.is_ok()
+        && !is_same_file(&resolved_old, &resolved_new).await
+    {
+        return Err(AppError::conflict("Destination already exists"));
+    }
+    if body.overwrite && !is_same_file(&resolved_old, &resolved_new).await {
+        crate::fs_trash::trash_before_overwrite(&base_new, &resolved_new).await;
+    }
+
     tokio::fs::rename(&resolved_old, &resolved_new)
         .await
         .map_err(|err| match err.kind() {
@@ -1025,7 +1247,19 @@ pub async fn fs_rename(
     }))
 }
 
-#[derive(Debug, Deserialize)]
+/// Case-only renames on case-insensitive filesystems see the destination as
+/// already existing.
+async fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (
+        tokio::fs::canonicalize(a).await,
+        tokio::fs::canonicalize(b).await,
+    ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ListQuery {
     pub path: Option<String>,
     #[serde(rename = "respectGitignore")]
@@ -1034,7 +1268,7 @@ pub struct ListQuery {
     pub limit: Option<usize>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ListEntry {
     pub name: String,
@@ -1044,7 +1278,7 @@ pub struct ListEntry {
     pub is_symbolic_link: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ListResponse {
     pub path: String,
@@ -1202,7 +1436,7 @@ pub async fn fs_list(Query(q): Query<ListQuery>) -> ApiResult<Json<ListResponse>
     }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct SearchQuery {
     pub root: Option<String>,
     pub directory: Option<String>,
@@ -1214,7 +1448,7 @@ pub struct SearchQuery {
     pub limit: Option<usize>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchFile {
     pub name: String,
@@ -1224,7 +1458,7 @@ pub struct SearchFile {
     pub extension: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct SearchResponse {
     pub root: String,
     pub count: usize,
@@ -1474,7 +1708,7 @@ pub async fn fs_search(Query(q): Query<SearchQuery>) -> ApiResult<Json<SearchRes
     }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ContentSearchBody {
     pub query: Option<String>,
@@ -1489,7 +1723,7 @@ pub struct ContentSearchBody {
     pub context_chars: Option<usize>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ContentSearchMatch {
     pub line: usize,
@@ -1502,7 +1736,7 @@ pub struct ContentSearchMatch {
     pub after: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ContentSearchFileResult {
     pub path: String,
@@ -1511,7 +1745,7 @@ pub struct ContentSearchFileResult {
     pub matches: Vec<ContentSearchMatch>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ContentSearchResponse {
     pub root: String,
@@ -1522,7 +1756,7 @@ pub struct ContentSearchResponse {
     pub truncated: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ContentReplaceMatchRef {
     pub path: Option<String>,
@@ -1531,7 +1765,7 @@ pub struct ContentReplaceMatchRef {
     pub expected: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ContentReplaceBody {
     pub query: Option<String>,
@@ -1545,7 +1779,7 @@ pub struct ContentReplaceBody {
     pub r#match: Option<ContentReplaceMatchRef>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ContentReplaceFileResult {
     pub path: String,
@@ -1553,7 +1787,7 @@ pub struct ContentReplaceFileResult {
     pub replacements: usize,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ContentReplaceResponse {
     pub root: String,
@@ -1563,7 +1797,7 @@ pub struct ContentReplaceResponse {
     pub files: Vec<ContentReplaceFileResult>,
 }
 
-fn resolve_path_within_workspace(base: &Path, target: &str) -> ApiResult<PathBuf> {
+pub(crate) fn resolve_path_within_workspace(base: &Path, target: &str) -> ApiResult<PathBuf> {
     let target_trimmed = target.trim();
     if target_trimmed.is_empty() {
         return Err(AppError::bad_request("Path is required"));
@@ -1627,6 +1861,25 @@ fn walk_workspace_files(
     respect_gitignore: bool,
     max_files: usize,
 ) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if max_files == 0 {
+        return files;
+    }
+    walk_workspace_entries(root, include_hidden, respect_gitignore, None, |path| {
+        files.push(path);
+        files.len() < max_files
+    });
+    files
+}
+
+/// Visit workspace files in walk order until `visit` returns false.
+fn walk_workspace_entries(
+    root: &Path,
+    include_hidden: bool,
+    respect_gitignore: bool,
+    overrides: Option<ignore::overrides::Override>,
+    mut visit: impl FnMut(PathBuf) -> bool,
+) {
     let excluded: HashSet<&'static str> = FILE_SEARCH_EXCLUDED_DIRS.iter().copied().collect();
     let root_for_filter = root.to_path_buf();
 
@@ -1640,8 +1893,6 @@ fn walk_workspace_files(
     }
     builder.follow_links(false);
 
-    let mut files = Vec::new();
-
     for result in builder
         .filter_entry(move |entry| {
             let path = entry.path();
@@ -1673,13 +1924,18 @@ fn walk_workspace_files(
             continue;
         }
 
-        files.push(entry.path().to_path_buf());
-        if files.len() >= max_files {
+        // Applied after the walk so .gitignore still wins over a glob match.
+        if overrides
+            .as_ref()
+            .is_some_and(|o| o.matched(entry.path(), false).is_ignore())
+        {
+            continue;
+        }
+
+        if !visit(entry.path().to_path_buf()) {
             break;
         }
     }
-
-    files
 }
 
 async fn normalize_content_scope_paths(
@@ -1742,7 +1998,19 @@ async fn read_searchable_text(path: &Path) -> Option<String> {
         return None;
     }
 
-    let bytes = tokio::fs::read(path).await.ok()?;
+    searchable_text(tokio::fs::read(path).await.ok()?)
+}
+
+fn read_searchable_text_blocking(path: &Path) -> Option<String> {
+    let meta = std::fs::metadata(path).ok()?;
+    if !meta.is_file() || meta.len() > MAX_CONTENT_SEARCH_FILE_BYTES {
+        return None;
+    }
+
+    searchable_text(std::fs::read(path).ok()?)
+}
+
+fn searchable_text(bytes: Vec<u8>) -> Option<String> {
     if bytes.contains(&0) {
         return None;
     }
@@ -1983,6 +2251,237 @@ pub async fn fs_content_search(
     }))
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentSearchStreamQuery {
+    pub directory: Option<String>,
+    pub q: Option<String>,
+    pub regex: Option<bool>,
+    pub case_sensitive: Option<bool>,
+    pub whole_word: Option<bool>,
+    /// Comma-separated globs relative to the directory; `!glob` excludes.
+    pub glob: Option<String>,
+    pub include_hidden: Option<bool>,
+    pub respect_gitignore: Option<bool>,
+    pub max_results: Option<usize>,
+    pub max_matches_per_file: Option<usize>,
+    pub context_chars: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ContentSearchStreamSummary {
+    file_count: usize,
+    match_count: usize,
+    files_scanned: usize,
+    truncated: bool,
+    timed_out: bool,
+}
+
+struct ContentSearchStreamLimits {
+    max_results: usize,
+    max_matches_per_file: usize,
+    context_chars: usize,
+    deadline: Instant,
+}
+
+/// Split a comma-separated glob list, keeping commas inside `{a,b}` groups.
+fn split_glob_list(raw: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    for ch in raw.chars() {
+        match ch {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                out.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(ch);
+    }
+    out.push(current);
+    out.into_iter()
+        .map(|g| g.trim().to_string())
+        .filter(|g| !g.is_empty())
+        .collect()
+}
+
+fn build_glob_overrides(
+    root: &Path,
+    raw: Option<&str>,
+) -> ApiResult<Option<ignore::overrides::Override>> {
+    let globs = raw.map(split_glob_list).unwrap_or_default();
+    if globs.is_empty() {
+        return Ok(None);
+    }
+    if globs.len() > MAX_CONTENT_SEARCH_GLOBS {
+        return Err(AppError::bad_request("Too many glob patterns"));
+    }
+    let mut builder = ignore::overrides::OverrideBuilder::new(root);
+    for glob in &globs {
+        builder
+            .add(glob)
+            .map_err(|err| AppError::bad_request(format!("Invalid glob '{glob}': {err}")))?;
+    }
+    builder
+        .build()
+        .map(Some)
+        .map_err(|err| AppError::bad_request(format!("Invalid glob: {err}")))
+}
+
+/// Search the workspace, handing each file with matches to `emit` as soon as
+/// it is found. Stops early when `emit` returns false (receiver gone).
+fn search_workspace_streaming(
+    root: &Path,
+    regex: &Regex,
+    include_hidden: bool,
+    respect_gitignore: bool,
+    overrides: Option<ignore::overrides::Override>,
+    limits: &ContentSearchStreamLimits,
+    mut emit: impl FnMut(ContentSearchFileResult) -> bool,
+) -> ContentSearchStreamSummary {
+    let mut summary = ContentSearchStreamSummary::default();
+    walk_workspace_entries(root, include_hidden, respect_gitignore, overrides, |path| {
+        if Instant::now() >= limits.deadline {
+            summary.timed_out = true;
+            return false;
+        }
+        if summary.files_scanned >= MAX_CONTENT_SEARCH_STREAM_FILES {
+            summary.truncated = true;
+            return false;
+        }
+        summary.files_scanned += 1;
+
+        let Some(content) = read_searchable_text_blocking(&path) else {
+            return true;
+        };
+        let remaining = limits.max_results.saturating_sub(summary.match_count);
+        let (matches, file_truncated) = collect_content_matches(
+            &content,
+            regex,
+            limits.max_matches_per_file.min(remaining),
+            limits.context_chars,
+        );
+        if matches.is_empty() {
+            return true;
+        }
+
+        summary.file_count += 1;
+        summary.match_count += matches.len();
+        summary.truncated |= file_truncated;
+        let file = ContentSearchFileResult {
+            path: to_api_path(&path),
+            relative_path: normalize_relative_search_path(root, &path),
+            match_count: matches.len(),
+            matches,
+        };
+        if !emit(file) {
+            return false;
+        }
+        if summary.match_count >= limits.max_results {
+            summary.truncated = true;
+            return false;
+        }
+        true
+    });
+    summary
+}
+
+fn search_stream_event(name: &str, payload: &impl Serialize) -> Event {
+    Event::default()
+        .event(name)
+        .data(serde_json::to_string(payload).unwrap_or_else(|_| "{}".to_string()))
+}
+
+/// Project-wide content search streamed as SSE: one `match` event per file,
+/// then `done` with totals. Closing the connection cancels the walk.
+pub async fn fs_content_search_stream(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Query(q): Query<ContentSearchStreamQuery>,
+) -> ApiResult<Response> {
+    let root = resolve_project_directory(state.as_ref(), &headers, q.directory.as_deref()).await?;
+
+    let query =
+        q.q.as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .ok_or_else(|| AppError::bad_request("Search query is required"))?
+            .to_string();
+    let regex = build_content_regex(
+        &query,
+        q.regex.unwrap_or(false),
+        q.case_sensitive.unwrap_or(false),
+        q.whole_word.unwrap_or(false),
+    )?;
+    let overrides = build_glob_overrides(&root, q.glob.as_deref())?;
+    let include_hidden = q.include_hidden.unwrap_or(false);
+    let respect_gitignore = q.respect_gitignore.unwrap_or(true);
+    let started = Instant::now();
+    let limits = ContentSearchStreamLimits {
+        max_results: q
+            .max_results
+            .unwrap_or(DEFAULT_CONTENT_SEARCH_MAX_RESULTS)
+            .clamp(1, MAX_CONTENT_SEARCH_MAX_RESULTS),
+        max_matches_per_file: q
+            .max_matches_per_file
+            .unwrap_or(DEFAULT_CONTENT_SEARCH_MAX_MATCHES_PER_FILE)
+            .clamp(1, MAX_CONTENT_SEARCH_MAX_MATCHES_PER_FILE),
+        context_chars: q
+            .context_chars
+            .unwrap_or(DEFAULT_CONTENT_SEARCH_CONTEXT_CHARS)
+            .clamp(0, MAX_CONTENT_SEARCH_CONTEXT_CHARS),
+        deadline: started + CONTENT_SEARCH_STREAM_TIMEOUT,
+    };
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<ContentSearchFileResult>(32);
+    let walk_root = root.clone();
+    let search = tokio::task::spawn_blocking(move || {
+        search_workspace_streaming(
+            &walk_root,
+            &regex,
+            include_hidden,
+            respect_gitignore,
+            overrides,
+            &limits,
+            |file| tx.blocking_send(file).is_ok(),
+        )
+    });
+
+    let stream = async_stream::stream! {
+        while let Some(file) = rx.recv().await {
+            yield Ok::<Event, Infallible>(search_stream_event("match", &file));
+        }
+        match search.await {
+            Ok(summary) => {
+                tracing::debug!(
+                    "fs_content_search_stream root={} q='{}' files={} matches={} scanned={} elapsed_ms={}",
+                    root.to_string_lossy(),
+                    query,
+                    summary.file_count,
+                    summary.match_count,
+                    summary.files_scanned,
+                    started.elapsed().as_millis()
+                );
+                yield Ok(search_stream_event("done", &summary));
+            }
+            Err(err) => {
+                yield Ok(search_stream_event(
+                    "error",
+                    &serde_json::json!({ "message": err.to_string() }),
+                ));
+            }
+        }
+    };
+
+    Ok(Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
 pub async fn fs_content_replace(
     State(state): State<Arc<crate::AppState>>,
     headers: HeaderMap,
@@ -2042,6 +2541,7 @@ pub async fn fs_content_replace(
         updated.push_str(&replacement);
         updated.push_str(&content[end_offset..]);
 
+        crate::fs_trash::trash_before_overwrite(&root, &resolved).await;
         tokio::fs::write(&resolved, updated)
             .await
             .map_err(|err| match err.kind() {
@@ -2115,6 +2615,7 @@ pub async fn fs_content_replace(
             continue;
         }
 
+        crate::fs_trash::trash_before_overwrite(&root, &path).await;
         if let Err(err) = tokio::fs::write(&path, updated).await {
             skipped += 1;
             tracing::warn!(
@@ -2179,6 +2680,61 @@ mod tests {
         assert!(ensure_within_base(base, target).is_err());
     }
 
+    #[test]
+    fn expected_version_prefers_hash_over_mtime() {
+        let current = FileVersion {
+            mtime: 10,
+            hash: "abc".to_string(),
+        };
+        assert!(check_expected_version(Some(&current), None, None).is_ok());
+        assert!(check_expected_version(Some(&current), Some(10), None).is_ok());
+        assert!(check_expected_version(Some(&current), Some(9), None).is_err());
+        assert!(check_expected_version(Some(&current), Some(9), Some("\"ABC\"")).is_ok());
+        assert!(check_expected_version(Some(&current), Some(10), Some("def")).is_err());
+        assert!(check_expected_version(None, Some(10), None).is_err());
+        assert!(check_expected_version(None, None, None).is_ok());
+    }
+
+    #[tokio::test]
+    async fn path_write_lock_serializes_and_cleans_up() {
+        let path = PathBuf::from("/tmp/opencode-studio-path-write-lock-test");
+        let first = PathWriteLock::acquire(&path).await;
+        let waiting = tokio::spawn({
+            let path = path.clone();
+            async move { PathWriteLock::acquire(&path).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+        drop(first);
+        let second = waiting.await.expect("join");
+        assert!(PATH_WRITE_LOCKS.contains_key(&path));
+        drop(second);
+        assert!(!PATH_WRITE_LOCKS.contains_key(&path));
+    }
+
+    #[test]
+    fn write_atomic_replaces_contents_and_keeps_permissions() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let path = tmp.path().join("script.sh");
+        std::fs::write(&path, "old").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o750)).unwrap();
+        }
+
+        write_atomic(&path, b"new").expect("write");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o750);
+        }
+        let leftovers = std::fs::read_dir(tmp.path()).unwrap().count();
+        assert_eq!(leftovers, 1);
+    }
+
     #[test]
     fn ensure_within_base_rejects_unix_prefix_collision() {
         let base = Path::new("/home/alice/work");
@@ -2186,6 +2742,62 @@ mod tests {
         assert!(ensure_within_base(base, target).is_err());
     }
 
+    #[test]
+    fn content_search_stream_honors_globs_gitignore_and_cancellation() {
+        assert_eq!(
+            split_glob_list("src/**/*.{rs,ts}, !src/gen/**,"),
+            vec!["src/**/*.{rs,ts}".to_string(), "!src/gen/**".to_string()]
+        );
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("src/gen")).unwrap();
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join(".gitignore"), "ignored.rs\n").unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n// needle here\n").unwrap();
+        std::fs::write(root.join("src/lib.ts"), "needle\n").unwrap();
+        std::fs::write(root.join("src/ignored.rs"), "needle\n").unwrap();
+        std::fs::write(root.join("src/gen/out.rs"), "needle\n").unwrap();
+        std::fs::write(root.join("docs/notes.md"), "needle\n").unwrap();
+        // The walker only reads .gitignore inside a repository.
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+
+        let regex = build_content_regex("needle", false, false, false).unwrap();
+        let limits = ContentSearchStreamLimits {
+            max_results: 100,
+            max_matches_per_file: 10,
+            context_chars: 8,
+            deadline: Instant::now() + Duration::from_secs(30),
+        };
+        let overrides = build_glob_overrides(root, Some("src/**/*.{rs,ts},!src/gen/**")).unwrap();
+        let mut found = Vec::new();
+        let summary =
+            search_workspace_streaming(root, &regex, false, true, overrides, &limits, |file| {
+                found.push((file.relative_path, file.matches[0].line));
+                true
+            });
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                ("src/lib.ts".to_string(), 1),
+                ("src/main.rs".to_string(), 2)
+            ]
+        );
+        assert_eq!(summary.match_count, 2);
+        assert!(!summary.truncated);
+
+        let mut emitted = 0;
+        let summary = search_workspace_streaming(root, &regex, false, true, None, &limits, |_| {
+            emitted += 1;
+            false
+        });
+        assert_eq!(emitted, 1, "a closed receiver stops the walk");
+        assert_eq!(summary.file_count, 1);
+
+        assert!(build_glob_overrides(root, Some("src/[")).is_err());
+    }
+
     #[test]
     fn to_api_path_uses_forward_slashes() {
         let path = Path::new("C:\\Users\\Alice\\workspace\\file.txt");
@@ -0,0 +1,309 @@
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use axum::{
+    body::Body,
+    extract::Query,
+    http::{HeaderMap, StatusCode, header},
+    response::Response,
+};
+use image::{DynamicImage, ImageFormat, codecs::jpeg::JpegEncoder};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::fs::{has_parent_dir_component, resolve_path};
+use crate::{ApiResult, AppError};
+
+const DEFAULT_PREVIEW_WIDTH: u32 = 320;
+const MIN_PREVIEW_EDGE: u32 = 16;
+const MAX_PREVIEW_EDGE: u32 = 2048;
+/// Requested edges are rounded up to this step so fractional device pixel
+/// ratios don't each get their own cache entry.
+const PREVIEW_EDGE_STEP: u32 = 32;
+const MAX_SOURCE_BYTES: u64 = 50 * 1024 * 1024;
+const MAX_SOURCE_EDGE: u32 = 16_384;
+const MAX_DECODE_ALLOC_BYTES: u64 = 512 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 82;
+const THUMBNAIL_CACHE_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FsPreviewQuery {
+    pub path: Option<String>,
+    /// Max width in pixels (default 320).
+    pub w: Option<u32>,
+    /// Max height in pixels; unbounded (up to the max edge) when omitted.
+    pub h: Option<u32>,
+}
+
+fn snap_edge(edge: u32) -> u32 {
+    let edge = edge.clamp(MIN_PREVIEW_EDGE, MAX_PREVIEW_EDGE);
+    edge.div_ceil(PREVIEW_EDGE_STEP) * PREVIEW_EDGE_STEP
+}
+
+fn is_previewable(path: &Path) -> bool {
+    matches!(
+        crate::fs::mime_for_ext(path),
+        "image/png" | "image/jpeg" | "image/gif" | "image/webp" | "image/bmp"
+    )
+}
+
+/// Cache key: source path, size and mtime plus the requested box, so an
+/// edited file never serves a stale preview.
+fn cache_key(abs: &Path, meta: &std::fs::Metadata, width: u32, height: u32) -> String {
+    let mtime = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let mut hasher = Sha256::new();
+    hasher.update(abs.to_string_lossy().as_bytes());
+    hasher.update(format!("\0{mtime}\0{}\0{width}x{height}", meta.len()).as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+struct Preview {
+    bytes: Vec<u8>,
+    mime: &'static str,
+}
+
+fn preview_mime(path: &Path) -> Option<&'static str> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("png") => Some("image/png"),
+        Some("jpg") => Some("image/jpeg"),
+        _ => None,
+    }
+}
+
+fn cached_preview(dir: &Path, key: &str) -> Option<Preview> {
+    ["jpg", "png"].iter().find_map(|ext| {
+        let path = dir.join(format!("{key}.{ext}"));
+        let bytes = std::fs::read(&path).ok()?;
+        Some(Preview {
+            bytes,
+            mime: preview_mime(&path)?,
+        })
+    })
+}
+
+/// Decode, shrink to fit `width`x`height` and re-encode. Images with alpha
+/// become PNG, everything else JPEG; animated GIFs keep their first frame.
+fn render_preview(source: &[u8], width: u32, height: u32) -> Result<Preview, String> {
+    let mut reader = image::ImageReader::new(Cursor::new(source))
+        .with_guessed_format()
+        .map_err(|err| err.to_string())?;
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_EDGE);
+    limits.max_image_height = Some(MAX_SOURCE_EDGE);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC_BYTES);
+    reader.limits(limits);
+    let img = reader.decode().map_err(|err| err.to_string())?;
+    let img = if img.width() > width || img.height() > height {
+        img.thumbnail(width, height)
+    } else {
+        img
+    };
+
+    let mut bytes = Vec::new();
+    if img.color().has_alpha() {
+        img.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .map_err(|err| err.to_string())?;
+        return Ok(Preview {
+            bytes,
+            mime: "image/png",
+        });
+    }
+    DynamicImage::ImageRgb8(img.to_rgb8())
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY))
+        .map_err(|err| err.to_string())?;
+    Ok(Preview {
+        bytes,
+        mime: "image/jpeg",
+    })
+}
+
+fn store_preview(dir: &Path, key: &str, preview: &Preview) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let ext = if preview.mime == "image/png" {
+        "png"
+    } else {
+        "jpg"
+    };
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    std::io::Write::write_all(&mut tmp, &preview.bytes)?;
+    tmp.persist(dir.join(format!("{key}.{ext}")))
+        .map_err(|err| err.error)?;
+    prune_cache(dir, THUMBNAIL_CACHE_MAX_BYTES);
+    Ok(())
+}
+
+/// Drop the oldest previews once the cache grows past `max_bytes`, down to
+/// 90% of it.
+fn prune_cache(dir: &Path, max_bytes: u64) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(std::time::SystemTime, u64, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            meta.is_file().then(|| {
+                (
+                    meta.modified().unwrap_or(UNIX_EPOCH),
+                    meta.len(),
+                    entry.path(),
+                )
+            })
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    if total <= max_bytes {
+        return;
+    }
+    let target = max_bytes.saturating_mul(90) / 100;
+    files.sort_by_key(|(modified, _, _)| *modified);
+    for (_, len, path) in files {
+        if total <= target {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total = total.saturating_sub(len);
+        }
+    }
+}
+
+fn preview_response(preview: Preview, etag: &str) -> Response {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, preview.mime)
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::ETAG, etag)
+        .body(Body::from(preview.bytes))
+        .unwrap()
+}
+
+/// Resized preview of an image file, cached on disk by path and mtime.
+/// Responses carry an ETag so unchanged previews revalidate with a 304.
+pub async fn fs_preview(
+    headers: HeaderMap,
+    Query(q): Query<FsPreviewQuery>,
+) -> ApiResult<Response> {
+    let file_path = q.path.unwrap_or_default();
+    let file_path = file_path.trim();
+    if file_path.is_empty() {
+        return Err(AppError::bad_request("Path is required"));
+    }
+    let resolved = resolve_path(file_path);
+    if has_parent_dir_component(&resolved) {
+        return Err(AppError::bad_request(
+            "Invalid path: path traversal not allowed",
+        ));
+    }
+    let abs = if resolved.is_absolute() {
+        resolved
+    } else {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join(resolved)
+    };
+    if !is_previewable(&abs) {
+        return Err(AppError::bad_request("Unsupported image type"));
+    }
+
+    let meta = tokio::fs::metadata(&abs)
+        .await
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => AppError::not_found("File not found"),
+            std::io::ErrorKind::PermissionDenied => AppError::forbidden("Access to file denied"),
+            _ => AppError::internal("Failed to read file"),
+        })?;
+    if !meta.is_file() {
+        return Err(AppError::bad_request("Specified path is not a file"));
+    }
+    if meta.len() > MAX_SOURCE_BYTES {
+        return Err(AppError::payload_too_large("File too large"));
+    }
+
+    let width = snap_edge(q.w.unwrap_or(DEFAULT_PREVIEW_WIDTH));
+    let height = q.h.map(snap_edge).unwrap_or(MAX_PREVIEW_EDGE);
+    let key = cache_key(&abs, &meta, width, height);
+    let etag = format!("\"{key}\"");
+    crate::fs_watch::hint_watch_path(&abs);
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == etag));
+    if not_modified {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, &etag)
+            .body(Body::empty())
+            .unwrap());
+    }
+
+    let cache_dir = crate::persistence_paths::thumbnail_cache_dir();
+    let preview = tokio::task::spawn_blocking(move || -> ApiResult<Preview> {
+        if let Some(cached) = cached_preview(&cache_dir, &key) {
+            return Ok(cached);
+        }
+        let source = std::fs::read(&abs).map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => AppError::not_found("File not found"),
+            std::io::ErrorKind::PermissionDenied => AppError::forbidden("Access to file denied"),
+            _ => AppError::internal(err.to_string()),
+        })?;
+        let preview = render_preview(&source, width, height)
+            .map_err(|err| AppError::bad_request(format!("Failed to decode image: {err}")))?;
+        if let Err(err) = store_preview(&cache_dir, &key, &preview) {
+            tracing::warn!(error = %err, "Failed to cache image preview");
+        }
+        Ok(preview)
+    })
+    .await
+    .map_err(|err| AppError::internal(err.to_string()))??;
+
+    Ok(preview_response(preview, &etag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(img: DynamicImage) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        img.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn previews_fit_the_box_and_pick_format_by_alpha() {
+        let opaque = encode(DynamicImage::new_rgb8(1000, 500));
+        let preview = render_preview(&opaque, 320, MAX_PREVIEW_EDGE).unwrap();
+        assert_eq!(preview.mime, "image/jpeg");
+        let decoded = image::load_from_memory(&preview.bytes).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (320, 160));
+
+        let small = encode(DynamicImage::new_rgba8(40, 20));
+        let preview = render_preview(&small, 320, 320).unwrap();
+        assert_eq!(preview.mime, "image/png");
+        let decoded = image::load_from_memory(&preview.bytes).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (40, 20));
+
+        assert!(render_preview(b"not an image", 320, 320).is_err());
+    }
+
+    #[test]
+    fn preview_edges_snap_to_steps() {
+        assert_eq!(snap_edge(0), MIN_PREVIEW_EDGE.div_ceil(32) * 32);
+        assert_eq!(snap_edge(300), 320);
+        assert_eq!(snap_edge(320), 320);
+        assert_eq!(snap_edge(100_000), MAX_PREVIEW_EDGE);
+    }
+}
@@ -0,0 +1,493 @@
+//! Per-project trash for workspace files deleted or overwritten through the
+//! API, so a stray delete or save can be undone.
+//!
+//! Each entry is a directory under `<data dir>/trash/<project key>/` holding
+//! the pre-image as `payload` next to a `meta.json`. Entries older than the
+//! retention window are purged hourly; a retention of 0 disables the trash.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, Query, State},
+    http::HeaderMap,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+
+use crate::fs::{
+    ProjectDirQuery, SuccessPathResponse, has_parent_dir_component, publish_fs_changed_event,
+    rel_api_path, resolve_project_directory, to_api_path,
+};
+use crate::{ApiResult, AppError};
+
+pub(crate) const DEFAULT_RETENTION_DAYS: u32 = 7;
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Overwritten files larger than this are not copied into the trash.
+const MAX_PREIMAGE_BYTES: u64 = 64 * 1024 * 1024;
+/// Oldest entries of a project are dropped beyond this count.
+const MAX_ENTRIES_PER_PROJECT: usize = 500;
+const META_FILE: &str = "meta.json";
+const PAYLOAD: &str = "payload";
+
+static RETENTION_DAYS: AtomicU32 = AtomicU32::new(DEFAULT_RETENTION_DAYS);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TrashReason {
+    Delete,
+    Overwrite,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TrashEntry {
+    pub id: String,
+    /// Original location, relative to the workspace root.
+    pub path: String,
+    pub reason: TrashReason,
+    pub is_directory: bool,
+    pub bytes: u64,
+    pub trashed_at: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TrashListResponse {
+    pub entries: Vec<TrashEntry>,
+    /// 0 when the trash is disabled.
+    pub retention_days: u32,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub(crate) struct TrashRestoreBody {
+    /// Replace whatever now exists at the original path; the replaced file
+    /// goes to the trash in turn.
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct TrashPurgeResponse {
+    pub success: bool,
+    pub purged: usize,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Last `trashedAt` handed out, so entries stashed within the same
+// millisecond still list newest first.
+static LAST_TRASHED_AT: AtomicU64 = AtomicU64::new(0);
+
+fn next_trashed_at() -> u64 {
+    let now = now_millis();
+    let previous = LAST_TRASHED_AT
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+            Some(now.max(last.saturating_add(1)))
+        })
+        .unwrap_or(0);
+    now.max(previous.saturating_add(1))
+}
+
+fn retention_days() -> u32 {
+    RETENTION_DAYS.load(Ordering::Relaxed)
+}
+
+/// Apply `--trash-retention-days` and start the hourly purge.
+pub(crate) fn configure(args: &crate::Args) {
+    RETENTION_DAYS.store(args.trash_retention_days, Ordering::Relaxed);
+    if args.trash_retention_days == 0 {
+        return;
+    }
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let cutoff = expiry_cutoff(now_millis(), retention_days());
+            let trash_root = crate::persistence_paths::trash_dir();
+            let purged = tokio::task::spawn_blocking(move || purge_expired(&trash_root, cutoff))
+                .await
+                .unwrap_or(0);
+            if purged > 0 {
+                tracing::info!(purged, "Purged expired trash entries");
+            }
+        }
+    });
+}
+
+fn expiry_cutoff(now: u64, days: u32) -> u64 {
+    now.saturating_sub(u64::from(days) * 24 * 60 * 60 * 1000)
+}
+
+fn project_dir(trash_root: &Path, workspace: &Path) -> PathBuf {
+    let digest = Sha256::digest(to_api_path(workspace).as_bytes());
+    let key: String = digest[..8].iter().map(|b| format!("{b:02x}")).collect();
+    trash_root.join(key)
+}
+
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn dir_bytes(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+fn read_entry(entry_dir: &Path) -> Option<TrashEntry> {
+    let raw = std::fs::read(entry_dir.join(META_FILE)).ok()?;
+    serde_json::from_slice(&raw).ok()
+}
+
+fn list_entries(project: &Path) -> Vec<TrashEntry> {
+    let Ok(dirs) = std::fs::read_dir(project) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<TrashEntry> = dirs
+        .filter_map(Result::ok)
+        .filter_map(|dir| read_entry(&dir.path()))
+        .collect();
+    entries.sort_by(|a, b| {
+        b.trashed_at
+            .cmp(&a.trashed_at)
+            .then_with(|| b.id.cmp(&a.id))
+    });
+    entries
+}
+
+/// Move (or, for an overwrite, copy) `target` into a new trash entry.
+fn stash(
+    trash_root: &Path,
+    workspace: &Path,
+    target: &Path,
+    reason: TrashReason,
+) -> std::io::Result<Option<TrashEntry>> {
+    let meta = std::fs::symlink_metadata(target)?;
+    let is_directory = meta.is_dir();
+    if reason == TrashReason::Overwrite && (!meta.is_file() || meta.len() > MAX_PREIMAGE_BYTES) {
+        return Ok(None);
+    }
+    let Ok(rel) = target.strip_prefix(workspace) else {
+        return Ok(None);
+    };
+
+    let project = project_dir(trash_root, workspace);
+    let trashed_at = next_trashed_at();
+    let id = format!(
+        "{trashed_at}-{}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    let entry_dir = project.join(&id);
+    std::fs::create_dir_all(&entry_dir)?;
+    let payload = entry_dir.join(PAYLOAD);
+
+    let stored = match reason {
+        TrashReason::Overwrite => std::fs::copy(target, &payload).map(|_| ()),
+        // Renaming fails across filesystems; the caller then deletes as usual.
+        TrashReason::Delete => std::fs::rename(target, &payload),
+    };
+    if let Err(err) = stored {
+        let _ = std::fs::remove_dir_all(&entry_dir);
+        return Err(err);
+    }
+
+    let entry = TrashEntry {
+        id,
+        path: rel_api_path(rel),
+        reason,
+        is_directory,
+        bytes: if is_directory {
+            dir_bytes(&payload)
+        } else {
+            meta.len()
+        },
+        trashed_at,
+    };
+    std::fs::write(
+        entry_dir.join(META_FILE),
+        serde_json::to_vec(&entry).unwrap_or_default(),
+    )?;
+
+    for old in list_entries(&project)
+        .into_iter()
+        .skip(MAX_ENTRIES_PER_PROJECT)
+    {
+        let _ = std::fs::remove_dir_all(project.join(old.id));
+    }
+    Ok(Some(entry))
+}
+
+fn purge_expired(trash_root: &Path, cutoff: u64) -> usize {
+    let Ok(projects) = std::fs::read_dir(trash_root) else {
+        return 0;
+    };
+    let mut purged = 0;
+    for project in projects.filter_map(Result::ok) {
+        let project = project.path();
+        for entry in list_entries(&project) {
+            if entry.trashed_at < cutoff && std::fs::remove_dir_all(project.join(&entry.id)).is_ok()
+            {
+                purged += 1;
+            }
+        }
+        // Drops the project directory once it is empty.
+        let _ = std::fs::remove_dir(&project);
+    }
+    purged
+}
+
+async fn stash_async(workspace: &Path, target: &Path, reason: TrashReason) -> bool {
+    if retention_days() == 0 {
+        return false;
+    }
+    let trash_root = crate::persistence_paths::trash_dir();
+    let workspace = workspace.to_path_buf();
+    let target = target.to_path_buf();
+    let result = tokio::task::spawn_blocking({
+        let target = target.clone();
+        move || stash(&trash_root, &workspace, &target, reason)
+    })
+    .await;
+    match result {
+        Ok(Ok(entry)) => entry.is_some(),
+        Ok(Err(err)) => {
+            tracing::warn!(
+                path = %target.to_string_lossy(),
+                error = %err,
+                "Failed to move file to trash"
+            );
+            false
+        }
+        Err(_) => false,
+    }
+}
+
+/// Move `target` into the trash before a delete. Returns `true` when it is
+/// gone from the workspace; otherwise the caller deletes it as usual.
+pub(crate) async fn trash_before_delete(workspace: &Path, target: &Path) -> bool {
+    stash_async(workspace, target, TrashReason::Delete).await
+}
+
+/// Keep a copy of the file at `target` before it is overwritten.
+pub(crate) async fn trash_before_overwrite(workspace: &Path, target: &Path) {
+    if tokio::fs::metadata(target)
+        .await
+        .is_ok_and(|meta| meta.is_file())
+    {
+        stash_async(workspace, target, TrashReason::Overwrite).await;
+    }
+}
+
+/// Trash entries for a workspace, newest first.
+pub(crate) async fn trash_list(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Query(q): Query<ProjectDirQuery>,
+) -> ApiResult<Json<TrashListResponse>> {
+    let root = resolve_project_directory(state.as_ref(), &headers, q.directory.as_deref()).await?;
+    let project = project_dir(&crate::persistence_paths::trash_dir(), &root);
+    let entries = tokio::task::spawn_blocking(move || list_entries(&project))
+        .await
+        .map_err(|err| AppError::internal(err.to_string()))?;
+    Ok(Json(TrashListResponse {
+        entries,
+        retention_days: retention_days(),
+    }))
+}
+
+fn restore(trash_root: &Path, workspace: &Path, id: &str, overwrite: bool) -> ApiResult<PathBuf> {
+    if !valid_id(id) {
+        return Err(AppError::bad_request("Invalid trash entry id"));
+    }
+    let entry_dir = project_dir(trash_root, workspace).join(id);
+    let entry =
+        read_entry(&entry_dir).ok_or_else(|| AppError::not_found("Trash entry not found"))?;
+    let rel = PathBuf::from(&entry.path);
+    if entry.path.is_empty() || rel.is_absolute() || has_parent_dir_component(&rel) {
+        return Err(AppError::bad_request("Trash entry has an invalid path"));
+    }
+    let dest = workspace.join(rel);
+
+    if std::fs::symlink_metadata(&dest).is_ok() {
+        if !overwrite {
+            return Err(AppError::conflict(
+                "A file already exists at the original path",
+            ));
+        }
+        let replaced = stash(trash_root, workspace, &dest, TrashReason::Delete)
+            .map_err(|err| AppError::internal(err.to_string()))?;
+        if replaced.is_none() {
+            return Err(AppError::internal("Failed to move the existing file aside"));
+        }
+    }
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent).map_err(|err| AppError::internal(err.to_string()))?;
+    }
+    std::fs::rename(entry_dir.join(PAYLOAD), &dest)
+        .map_err(|err| AppError::internal(err.to_string()))?;
+    let _ = std::fs::remove_dir_all(&entry_dir);
+    Ok(dest)
+}
+
+/// Put a trash entry back at its original path.
+pub(crate) async fn trash_restore_post(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Query(q): Query<ProjectDirQuery>,
+    AxumPath(id): AxumPath<String>,
+    body: Option<Json<TrashRestoreBody>>,
+) -> ApiResult<Json<SuccessPathResponse>> {
+    let root = resolve_project_directory(state.as_ref(), &headers, q.directory.as_deref()).await?;
+    let overwrite = body.map(|Json(body)| body.overwrite).unwrap_or(false);
+    let workspace = root.clone();
+    let dest = tokio::task::spawn_blocking(move || {
+        restore(
+            &crate::persistence_paths::trash_dir(),
+            &workspace,
+            &id,
+            overwrite,
+        )
+    })
+    .await
+    .map_err(|err| AppError::internal(err.to_string()))??;
+
+    publish_fs_changed_event(&root, "restore", [dest.as_path()], None, None);
+    Ok(Json(SuccessPathResponse {
+        success: true,
+        path: to_api_path(&dest),
+    }))
+}
+
+/// Permanently remove one trash entry.
+pub(crate) async fn trash_entry_delete(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Query(q): Query<ProjectDirQuery>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<TrashPurgeResponse>> {
+    if !valid_id(&id) {
+        return Err(AppError::bad_request("Invalid trash entry id"));
+    }
+    let root = resolve_project_directory(state.as_ref(), &headers, q.directory.as_deref()).await?;
+    let entry_dir = project_dir(&crate::persistence_paths::trash_dir(), &root).join(id);
+    if tokio::fs::metadata(&entry_dir).await.is_err() {
+        return Err(AppError::not_found("Trash entry not found"));
+    }
+    tokio::fs::remove_dir_all(&entry_dir)
+        .await
+        .map_err(|err| AppError::internal(err.to_string()))?;
+    Ok(Json(TrashPurgeResponse {
+        success: true,
+        purged: 1,
+    }))
+}
+
+/// Empty a workspace's trash.
+pub(crate) async fn trash_purge_delete(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Query(q): Query<ProjectDirQuery>,
+) -> ApiResult<Json<TrashPurgeResponse>> {
+    let root = resolve_project_directory(state.as_ref(), &headers, q.directory.as_deref()).await?;
+    let project = project_dir(&crate::persistence_paths::trash_dir(), &root);
+    let purged = tokio::task::spawn_blocking(move || {
+        let count = list_entries(&project).len();
+        match std::fs::remove_dir_all(&project) {
+            Ok(()) => Ok(count),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(err) => Err(err),
+        }
+    })
+    .await
+    .map_err(|err| AppError::internal(err.to_string()))?
+    .map_err(|err| AppError::internal(err.to_string()))?;
+    Ok(Json(TrashPurgeResponse {
+        success: true,
+        purged,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deleted_and_overwritten_files_restore_to_their_path() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let trash_root = tmp.path().join("trash");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(workspace.join("src")).unwrap();
+        let file = workspace.join("src/main.rs");
+        std::fs::write(&file, "v1").unwrap();
+
+        let saved = stash(&trash_root, &workspace, &file, TrashReason::Overwrite)
+            .unwrap()
+            .expect("pre-image");
+        assert_eq!(saved.path, "src/main.rs");
+        assert!(file.exists());
+        std::fs::write(&file, "v2").unwrap();
+
+        let deleted = stash(&trash_root, &workspace, &file, TrashReason::Delete)
+            .unwrap()
+            .expect("deleted");
+        assert!(!file.exists());
+
+        let project = project_dir(&trash_root, &workspace);
+        let entries = list_entries(&project);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].reason, TrashReason::Delete);
+
+        restore(&trash_root, &workspace, &deleted.id, false).expect("restore");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v2");
+
+        assert!(restore(&trash_root, &workspace, &saved.id, false).is_err());
+        restore(&trash_root, &workspace, &saved.id, true).expect("restore over");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v1");
+
+        // The v2 file replaced by the second restore is now in the trash.
+        let entries = list_entries(&project);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].bytes, 2);
+
+        assert!(restore(&trash_root, &workspace, "../escape", false).is_err());
+    }
+
+    #[test]
+    fn purge_drops_entries_past_retention() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let trash_root = tmp.path().join("trash");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(workspace.join("dir")).unwrap();
+        std::fs::write(workspace.join("dir/a.txt"), "abc").unwrap();
+
+        let entry = stash(
+            &trash_root,
+            &workspace,
+            &workspace.join("dir"),
+            TrashReason::Delete,
+        )
+        .unwrap()
+        .expect("entry");
+        assert!(entry.is_directory);
+        assert_eq!(entry.bytes, 3);
+
+        assert_eq!(purge_expired(&trash_root, entry.trashed_at), 0);
+        assert_eq!(purge_expired(&trash_root, entry.trashed_at + 1), 1);
+        assert!(!project_dir(&trash_root, &workspace).exists());
+        assert_eq!(expiry_cutoff(10, 0), 10);
+    }
+}
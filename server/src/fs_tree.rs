@@ -0,0 +1,481 @@
+//! Lazily expandable workspace tree for the file explorer: one request lists a
+//! directory page plus a few levels below it, with sizes, mtimes and a git
+//! status overlay.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::HeaderMap,
+};
+use ignore::WalkBuilder;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::fs::{
+    rel_api_path, resolve_project_directory, resolve_workspace_path_from_context, to_api_path,
+};
+use crate::git2_utils;
+use crate::{ApiResult, AppError};
+
+const DEFAULT_DEPTH: usize = 1;
+const MAX_DEPTH: usize = 4;
+const DEFAULT_PAGE_LIMIT: usize = 500;
+const MAX_PAGE_LIMIT: usize = 5000;
+/// Nested directories stop expanding once a response holds this many nodes;
+/// the client expands them on demand.
+const MAX_TREE_NODES: usize = 20_000;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FsTreeQuery {
+    pub directory: Option<String>,
+    /// Directory to expand, relative to the workspace root (default: the root).
+    pub path: Option<String>,
+    /// Levels to list below `path` (default 1, max 4).
+    pub depth: Option<usize>,
+    /// Hide gitignored entries (default `true`).
+    pub respect_gitignore: Option<bool>,
+    /// List dot-files and dot-directories (default `true`); `.git` is always
+    /// hidden.
+    pub include_hidden: Option<bool>,
+    /// Attach git status codes (default `true`).
+    pub git_status: Option<bool>,
+    /// Page through `path`'s own children; nested directories return their
+    /// first page.
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FsTreeNodeType {
+    File,
+    Directory,
+    Symlink,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FsTreeNode {
+    pub name: String,
+    /// Relative to the workspace root, slash-separated.
+    pub path: String,
+    #[serde(rename = "type")]
+    pub node_type: FsTreeNodeType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Milliseconds since the Unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<u64>,
+    /// Porcelain-style code (`M`, `A`, `D`, `R`, `T`, `U`, `?`). Directories
+    /// get `U` for a conflict below them, `M` for any other change, or `?`
+    /// when everything below is untracked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_status: Option<&'static str>,
+    /// Present for directories expanded within `depth`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<FsTreePage>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FsTreePage {
+    pub entries: Vec<FsTreeNode>,
+    pub offset: usize,
+    pub total: usize,
+    pub has_more: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FsTreeResponse {
+    pub root: String,
+    /// The listed directory, relative to `root`; empty for the root itself.
+    pub path: String,
+    #[serde(flatten)]
+    pub page: FsTreePage,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TreeOptions {
+    respect_gitignore: bool,
+    include_hidden: bool,
+    limit: usize,
+}
+
+/// Git status codes keyed by workspace-relative path, for files and for every
+/// directory above a changed file.
+#[derive(Debug, Default)]
+struct GitOverlay {
+    codes: HashMap<String, &'static str>,
+}
+
+impl GitOverlay {
+    fn insert_file(&mut self, path: &str, code: &'static str) {
+        self.codes.insert(path.to_string(), code);
+        let mut dir = path;
+        while let Some((parent, _)) = dir.rsplit_once('/') {
+            let merged = match self.codes.get(parent) {
+                Some(existing) => merge_dir_code(existing, code),
+                None => dir_code(code),
+            };
+            self.codes.insert(parent.to_string(), merged);
+            dir = parent;
+        }
+    }
+
+    fn get(&self, path: &str) -> Option<&'static str> {
+        self.codes.get(path).copied()
+    }
+}
+
+fn dir_code(file_code: &'static str) -> &'static str {
+    match file_code {
+        "U" | "?" => file_code,
+        _ => "M",
+    }
+}
+
+fn merge_dir_code(existing: &'static str, file_code: &'static str) -> &'static str {
+    let incoming = dir_code(file_code);
+    let rank = |code: &str| match code {
+        "U" => 2,
+        "M" => 1,
+        _ => 0,
+    };
+    if rank(incoming) > rank(existing) {
+        incoming
+    } else {
+        existing
+    }
+}
+
+fn status_code(st: git2::Status) -> Option<&'static str> {
+    use git2::Status;
+    if st.is_conflicted() {
+        Some("U")
+    } else if st.contains(Status::WT_NEW) {
+        Some("?")
+    } else if st.intersects(Status::INDEX_NEW) {
+        Some("A")
+    } else if st.intersects(Status::INDEX_DELETED | Status::WT_DELETED) {
+        Some("D")
+    } else if st.intersects(Status::INDEX_RENAMED | Status::WT_RENAMED) {
+        Some("R")
+    } else if st.intersects(Status::INDEX_TYPECHANGE | Status::WT_TYPECHANGE) {
+        Some("T")
+    } else if st.intersects(Status::INDEX_MODIFIED | Status::WT_MODIFIED) {
+        Some("M")
+    } else {
+        None
+    }
+}
+
+/// Status of everything under `root/subdir`, or an empty overlay outside a
+/// repository.
+fn load_git_overlay(root: &Path, subdir: &Path) -> GitOverlay {
+    let mut overlay = GitOverlay::default();
+    let Ok(repo) = git2_utils::open_repo_discover(root) else {
+        return overlay;
+    };
+    let Some(workdir) = repo.workdir() else {
+        return overlay;
+    };
+    let prefix = match root.strip_prefix(workdir) {
+        Ok(prefix) => prefix.to_path_buf(),
+        Err(_) => {
+            let (Ok(root), Ok(workdir)) = (root.canonicalize(), workdir.canonicalize()) else {
+                return overlay;
+            };
+            match root.strip_prefix(&workdir) {
+                Ok(prefix) => prefix.to_path_buf(),
+                Err(_) => return overlay,
+            }
+        }
+    };
+    let prefix = rel_api_path(&prefix);
+    let scope = rel_api_path(&Path::new(&prefix).join(subdir));
+
+    let mut opts = git2::StatusOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .include_unmodified(false);
+    if !scope.is_empty() {
+        opts.pathspec(&scope);
+    }
+    let Ok(statuses) = repo.statuses(Some(&mut opts)) else {
+        return overlay;
+    };
+    for entry in statuses.iter() {
+        let (Some(path), Some(code)) = (entry.path(), status_code(entry.status())) else {
+            continue;
+        };
+        let workspace_path = if prefix.is_empty() {
+            Some(path)
+        } else {
+            path.strip_prefix(&prefix)
+                .and_then(|rest| rest.strip_prefix('/'))
+        };
+        if let Some(workspace_path) = workspace_path {
+            overlay.insert_file(workspace_path.trim_end_matches('/'), code);
+        }
+    }
+    overlay
+}
+
+fn modified_millis(meta: &std::fs::Metadata) -> Option<u64> {
+    meta.modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
+}
+
+/// List one page of `root/rel_dir`, expanding subdirectories until `depth`
+/// runs out or the response reaches `MAX_TREE_NODES`.
+fn list_dir(
+    root: &Path,
+    rel_dir: &Path,
+    offset: usize,
+    depth: usize,
+    opts: &TreeOptions,
+    git: &GitOverlay,
+    budget: &mut usize,
+) -> FsTreePage {
+    let mut builder = WalkBuilder::new(root.join(rel_dir));
+    builder.max_depth(Some(1));
+    builder.hidden(!opts.include_hidden);
+    builder.follow_links(false);
+    builder.require_git(false);
+    if !opts.respect_gitignore {
+        builder.git_ignore(false);
+        builder.git_global(false);
+        builder.git_exclude(false);
+        builder.ignore(false);
+        builder.parents(false);
+    }
+    builder.filter_entry(|entry| entry.depth() == 0 || entry.file_name() != ".git");
+
+    let mut items: Vec<(String, FsTreeNodeType, Option<std::fs::Metadata>)> = builder
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.depth() == 1)
+        .filter_map(|entry| {
+            let file_type = entry.file_type()?;
+            let node_type = if file_type.is_symlink() {
+                FsTreeNodeType::Symlink
+            } else if file_type.is_dir() {
+                FsTreeNodeType::Directory
+            } else {
+                FsTreeNodeType::File
+            };
+            let name = entry.file_name().to_string_lossy().into_owned();
+            Some((name, node_type, entry.metadata().ok()))
+        })
+        .collect();
+    items.sort_by(|a, b| {
+        let a_dir = a.1 == FsTreeNodeType::Directory;
+        let b_dir = b.1 == FsTreeNodeType::Directory;
+        b_dir
+            .cmp(&a_dir)
+            .then_with(|| a.0.to_lowercase().cmp(&b.0.to_lowercase()))
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    let total = items.len();
+    let offset = offset.min(total);
+    let end = offset.saturating_add(opts.limit).min(total);
+    *budget = budget.saturating_sub(end - offset);
+
+    let entries = items
+        .drain(offset..end)
+        .map(|(name, node_type, meta)| {
+            let rel = rel_dir.join(&name);
+            let path = rel_api_path(&rel);
+            let children = (node_type == FsTreeNodeType::Directory && depth > 1 && *budget > 0)
+                .then(|| list_dir(root, &rel, 0, depth - 1, opts, git, budget));
+            FsTreeNode {
+                size: meta
+                    .as_ref()
+                    .filter(|_| node_type == FsTreeNodeType::File)
+                    .map(|m| m.len()),
+                mtime: meta.as_ref().and_then(modified_millis),
+                git_status: git.get(&path),
+                name,
+                path,
+                node_type,
+                children,
+            }
+        })
+        .collect();
+
+    let has_more = end < total;
+    FsTreePage {
+        entries,
+        offset,
+        total,
+        has_more,
+        next_offset: has_more.then_some(end),
+    }
+}
+
+/// A page of a workspace directory, optionally expanded a few levels deep.
+pub(crate) async fn fs_tree(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Query(q): Query<FsTreeQuery>,
+) -> ApiResult<Json<FsTreeResponse>> {
+    let requested = q.path.as_deref().map(str::trim).unwrap_or_default();
+    let (root, rel_dir) = if requested.is_empty() || requested == "." || requested == "/" {
+        let root =
+            resolve_project_directory(state.as_ref(), &headers, q.directory.as_deref()).await?;
+        (root, PathBuf::new())
+    } else {
+        let (root, target) = resolve_workspace_path_from_context(
+            state.as_ref(),
+            &headers,
+            q.directory.as_deref(),
+            requested,
+        )
+        .await?;
+        let rel = target.strip_prefix(&root).unwrap_or(&target).to_path_buf();
+        (root, rel)
+    };
+
+    let abs = root.join(&rel_dir);
+    let meta = tokio::fs::metadata(&abs)
+        .await
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => AppError::not_found("Directory not found"),
+            std::io::ErrorKind::PermissionDenied => {
+                AppError::forbidden("Access to directory denied")
+            }
+            _ => AppError::internal("Failed to list directory"),
+        })?;
+    if !meta.is_dir() {
+        return Err(AppError::bad_request("Specified path is not a directory"));
+    }
+
+    let opts = TreeOptions {
+        respect_gitignore: q.respect_gitignore.unwrap_or(true),
+        include_hidden: q.include_hidden.unwrap_or(true),
+        limit: q
+            .limit
+            .unwrap_or(DEFAULT_PAGE_LIMIT)
+            .clamp(1, MAX_PAGE_LIMIT),
+    };
+    let depth = q.depth.unwrap_or(DEFAULT_DEPTH).clamp(1, MAX_DEPTH);
+    let offset = q.offset.unwrap_or(0);
+    let with_git = q.git_status.unwrap_or(true);
+
+    let walk_root = root.clone();
+    let walk_rel = rel_dir.clone();
+    let page = tokio::task::spawn_blocking(move || {
+        let git = if with_git {
+            load_git_overlay(&walk_root, &walk_rel)
+        } else {
+            GitOverlay::default()
+        };
+        let mut budget = MAX_TREE_NODES;
+        list_dir(
+            &walk_root,
+            &walk_rel,
+            offset,
+            depth,
+            &opts,
+            &git,
+            &mut budget,
+        )
+    })
+    .await
+    .map_err(|err| AppError::internal(format!("Directory listing failed: {err}")))?;
+
+    Ok(Json(FsTreeResponse {
+        root: to_api_path(&root),
+        path: rel_api_path(&rel_dir),
+        page,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(limit: usize) -> TreeOptions {
+        TreeOptions {
+            respect_gitignore: true,
+            include_hidden: true,
+            limit,
+        }
+    }
+
+    #[test]
+    fn lists_directories_first_and_pages_children() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(root.join("README.md"), "hello").unwrap();
+        std::fs::write(root.join("b.txt"), "").unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(root.join("src/nested/deep.rs"), "").unwrap();
+
+        let mut budget = MAX_TREE_NODES;
+        let page = list_dir(
+            root,
+            Path::new(""),
+            0,
+            2,
+            &opts(10),
+            &GitOverlay::default(),
+            &mut budget,
+        );
+        let names: Vec<&str> = page.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["src", ".gitignore", "b.txt", "README.md"]);
+        assert_eq!(page.entries[3].size, Some(5));
+
+        let src = page.entries[0].children.as_ref().expect("expanded src");
+        assert_eq!(src.entries[0].path, "src/nested");
+        assert!(src.entries[0].children.is_none());
+        assert_eq!(src.entries[1].path, "src/main.rs");
+
+        let mut budget = MAX_TREE_NODES;
+        let second = list_dir(
+            root,
+            Path::new(""),
+            1,
+            1,
+            &opts(2),
+            &GitOverlay::default(),
+            &mut budget,
+        );
+        assert_eq!(second.total, 4);
+        assert_eq!(second.entries.len(), 2);
+        assert!(second.has_more);
+        assert_eq!(second.next_offset, Some(3));
+    }
+
+    #[test]
+    fn git_overlay_rolls_codes_up_to_directories() {
+        let mut overlay = GitOverlay::default();
+        overlay.insert_file("src/new.rs", "?");
+        assert_eq!(overlay.get("src"), Some("?"));
+        overlay.insert_file("src/lib/mod.rs", "A");
+        assert_eq!(overlay.get("src"), Some("M"));
+        assert_eq!(overlay.get("src/lib"), Some("M"));
+        overlay.insert_file("src/conflict.rs", "U");
+        overlay.insert_file("src/other.rs", "M");
+        assert_eq!(overlay.get("src"), Some("U"));
+        assert_eq!(overlay.get("src/new.rs"), Some("?"));
+        assert_eq!(overlay.get("docs"), None);
+    }
+}
@@ -0,0 +1,336 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::HeaderMap,
+};
+use ignore::WalkBuilder;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::fs::{rel_api_path, resolve_project_directory, to_api_path};
+use crate::{ApiResult, AppError};
+
+const DEFAULT_TREE_DEPTH: usize = 3;
+const MAX_TREE_DEPTH: usize = 8;
+const DEFAULT_TOP_FILES: usize = 20;
+const MAX_TOP_FILES: usize = 200;
+const DEFAULT_MAX_CHILDREN: usize = 50;
+const MAX_MAX_CHILDREN: usize = 500;
+const USAGE_WALK_TIMEOUT: Duration = Duration::from_secs(30);
+const USAGE_MAX_ENTRIES: usize = 2_000_000;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FsUsageQuery {
+    pub directory: Option<String>,
+    /// Skip gitignored files (default `true`).
+    pub respect_gitignore: Option<bool>,
+    /// Count dot-files and dot-directories (default `true`); `.git` is
+    /// always skipped.
+    pub include_hidden: Option<bool>,
+    /// Levels of the directory tree to return; deeper sizes roll up.
+    pub depth: Option<usize>,
+    /// Number of largest files to report.
+    pub top: Option<usize>,
+    /// Children kept per tree node; the rest are summed into `otherBytes`.
+    pub max_children: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FsUsageNode {
+    /// Relative to the workspace root, slash-separated; empty for the root.
+    pub path: String,
+    pub name: String,
+    pub bytes: u64,
+    pub files: u64,
+    pub children: Vec<FsUsageNode>,
+    /// Bytes in children omitted by `maxChildren`.
+    pub other_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FsUsageFile {
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FsUsageResponse {
+    pub root: String,
+    pub total_bytes: u64,
+    pub file_count: u64,
+    pub tree: FsUsageNode,
+    pub largest_files: Vec<FsUsageFile>,
+    /// The walk stopped early (timeout, entry cap, or cancellation); sizes
+    /// are a lower bound.
+    pub truncated: bool,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct UsageOptions {
+    respect_gitignore: bool,
+    include_hidden: bool,
+    depth: usize,
+    top: usize,
+    max_children: usize,
+    deadline: Instant,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct DirTotals {
+    bytes: u64,
+    files: u64,
+}
+
+struct UsageScan {
+    dirs: HashMap<PathBuf, DirTotals>,
+    largest: Vec<FsUsageFile>,
+    truncated: bool,
+}
+
+/// Sets the flag when the request future is dropped, i.e. the client went away.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Walk `root` summing file sizes into every ancestor up to `depth` levels
+/// deep, and keep the `top` largest files.
+fn scan_usage(root: &Path, opts: &UsageOptions, cancel: &AtomicBool) -> UsageScan {
+    let mut builder = WalkBuilder::new(root);
+    builder.hidden(!opts.include_hidden);
+    builder.follow_links(false);
+    builder.require_git(false);
+    if !opts.respect_gitignore {
+        builder.git_ignore(false);
+        builder.git_global(false);
+        builder.git_exclude(false);
+        builder.ignore(false);
+        builder.parents(false);
+    }
+    let root_for_filter = root.to_path_buf();
+    builder
+        .filter_entry(move |entry| entry.path() == root_for_filter || entry.file_name() != ".git");
+
+    let mut dirs: HashMap<PathBuf, DirTotals> = HashMap::new();
+    dirs.insert(PathBuf::new(), DirTotals::default());
+    let mut heap: BinaryHeap<Reverse<(u64, String)>> = BinaryHeap::new();
+    let mut truncated = false;
+
+    for (seen, result) in builder.build().enumerate() {
+        if cancel.load(Ordering::Relaxed)
+            || seen >= USAGE_MAX_ENTRIES
+            || (seen % 1024 == 0 && Instant::now() >= opts.deadline)
+        {
+            truncated = true;
+            break;
+        }
+        let Ok(entry) = result else {
+            continue;
+        };
+        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+            continue;
+        }
+        let Ok(rel) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        let bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+
+        let mut dir = PathBuf::new();
+        let parent_components: Vec<_> = rel
+            .parent()
+            .map(|p| p.components().collect())
+            .unwrap_or_default();
+        for level in 0..=parent_components.len().min(opts.depth) {
+            if level > 0 {
+                dir.push(parent_components[level - 1]);
+            }
+            let totals = dirs.entry(dir.clone()).or_default();
+            totals.bytes += bytes;
+            totals.files += 1;
+        }
+
+        if opts.top > 0 {
+            heap.push(Reverse((bytes, rel_api_path(rel))));
+            if heap.len() > opts.top {
+                heap.pop();
+            }
+        }
+    }
+
+    let mut largest: Vec<FsUsageFile> = heap
+        .into_iter()
+        .map(|Reverse((bytes, path))| FsUsageFile { path, bytes })
+        .collect();
+    largest.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+
+    UsageScan {
+        dirs,
+        largest,
+        truncated,
+    }
+}
+
+fn build_tree(dirs: &HashMap<PathBuf, DirTotals>, max_children: usize) -> FsUsageNode {
+    let mut children_of: HashMap<&Path, Vec<&PathBuf>> = HashMap::new();
+    for path in dirs.keys() {
+        if let Some(parent) = path.parent() {
+            children_of.entry(parent).or_default().push(path);
+        }
+    }
+
+    fn node(
+        path: &Path,
+        dirs: &HashMap<PathBuf, DirTotals>,
+        children_of: &HashMap<&Path, Vec<&PathBuf>>,
+        max_children: usize,
+    ) -> FsUsageNode {
+        let totals = dirs.get(path).copied().unwrap_or_default();
+        let mut children: Vec<&PathBuf> = children_of.get(path).cloned().unwrap_or_default();
+        children.sort_by(|a, b| {
+            let size = |p: &PathBuf| dirs.get(p).map_or(0, |t| t.bytes);
+            size(b).cmp(&size(a)).then_with(|| a.cmp(b))
+        });
+        let other_bytes = children
+            .iter()
+            .skip(max_children)
+            .map(|p| dirs.get(*p).map_or(0, |t| t.bytes))
+            .sum();
+        FsUsageNode {
+            path: rel_api_path(path),
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            bytes: totals.bytes,
+            files: totals.files,
+            children: children
+                .into_iter()
+                .take(max_children)
+                .map(|child| node(child, dirs, children_of, max_children))
+                .collect(),
+            other_bytes,
+        }
+    }
+
+    node(Path::new(""), dirs, &children_of, max_children)
+}
+
+/// Disk usage of a workspace: a size tree of its directories plus the
+/// largest files. The walk is abandoned when the client disconnects.
+pub(crate) async fn fs_usage(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Query(q): Query<FsUsageQuery>,
+) -> ApiResult<Json<FsUsageResponse>> {
+    let root = resolve_project_directory(state.as_ref(), &headers, q.directory.as_deref()).await?;
+    let started = Instant::now();
+    let opts = UsageOptions {
+        respect_gitignore: q.respect_gitignore.unwrap_or(true),
+        include_hidden: q.include_hidden.unwrap_or(true),
+        depth: q.depth.unwrap_or(DEFAULT_TREE_DEPTH).min(MAX_TREE_DEPTH),
+        top: q.top.unwrap_or(DEFAULT_TOP_FILES).min(MAX_TOP_FILES),
+        max_children: q
+            .max_children
+            .unwrap_or(DEFAULT_MAX_CHILDREN)
+            .clamp(1, MAX_MAX_CHILDREN),
+        deadline: started + USAGE_WALK_TIMEOUT,
+    };
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let _cancel_guard = CancelOnDrop(cancel.clone());
+    let walk_root = root.clone();
+    let scan = tokio::task::spawn_blocking(move || scan_usage(&walk_root, &opts, &cancel))
+        .await
+        .map_err(|err| AppError::internal(format!("Disk usage scan failed: {err}")))?;
+
+    let tree = build_tree(&scan.dirs, opts.max_children);
+    Ok(Json(FsUsageResponse {
+        root: to_api_path(&root),
+        total_bytes: tree.bytes,
+        file_count: tree.files,
+        tree,
+        largest_files: scan.largest,
+        truncated: scan.truncated,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(depth: usize, top: usize) -> UsageOptions {
+        UsageOptions {
+            respect_gitignore: true,
+            include_hidden: true,
+            depth,
+            top,
+            max_children: 1,
+            deadline: Instant::now() + USAGE_WALK_TIMEOUT,
+        }
+    }
+
+    #[test]
+    fn usage_scan_rolls_up_sizes_and_ranks_largest_files() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("assets/img/deep")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("target/big.bin"), vec![0u8; 5000]).unwrap();
+        std::fs::write(root.join(".git/objects"), vec![0u8; 9000]).unwrap();
+        std::fs::write(root.join("assets/img/deep/logo.png"), vec![0u8; 3000]).unwrap();
+        std::fs::write(root.join("assets/readme.txt"), vec![0u8; 100]).unwrap();
+        std::fs::write(root.join("src/main.rs"), vec![0u8; 500]).unwrap();
+
+        let cancel = AtomicBool::new(false);
+        let scan = scan_usage(root, &opts(1, 2), &cancel);
+        assert!(!scan.truncated);
+        assert_eq!(
+            scan.largest,
+            vec![
+                FsUsageFile {
+                    path: "assets/img/deep/logo.png".into(),
+                    bytes: 3000
+                },
+                FsUsageFile {
+                    path: "src/main.rs".into(),
+                    bytes: 500
+                },
+            ]
+        );
+
+        let tree = build_tree(&scan.dirs, 1);
+        // .gitignore (8 bytes) + assets + src; target/ and .git are skipped.
+        assert_eq!(tree.bytes, 3000 + 100 + 500 + 8);
+        assert_eq!(tree.files, 4);
+        assert_eq!(tree.children.len(), 1);
+        assert_eq!(tree.children[0].path, "assets");
+        assert_eq!(tree.children[0].bytes, 3100);
+        // Depth 1: nested directories roll up into `assets`.
+        assert!(tree.children[0].children.is_empty());
+        assert_eq!(tree.other_bytes, 500);
+
+        cancel.store(true, Ordering::Relaxed);
+        let cancelled = scan_usage(root, &opts(1, 2), &cancel);
+        assert!(cancelled.truncated);
+        assert!(cancelled.largest.is_empty());
+    }
+}
@@ -1,10 +1,19 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::Infallible;
 use std::ffi::OsStr;
 use std::path::{Component, Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, LazyLock, Mutex};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::{
+        IntoResponse, Response,
+        sse::{Event as SseEvent, KeepAlive, Sse},
+    },
+};
 use notify::{
     Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
     event::{CreateKind, ModifyKind, RemoveKind},
@@ -18,6 +27,9 @@ const WATCH_ROOT_HINT_MAX_ENTRIES: usize = 320;
 const WATCH_ROOT_HINT_ACTIVE_LIMIT: usize = 64;
 const WATCH_FAILURE_BACKOFF_BASE: Duration = Duration::from_secs(3);
 const WATCH_FAILURE_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+const WATCH_STREAM_DEBOUNCE: Duration = Duration::from_millis(300);
+const WATCH_STREAM_REHINT_INTERVAL: Duration = Duration::from_secs(60);
+const WATCH_STREAM_MAX_PATHS: usize = 500;
 
 struct FsWatchHub {
     started: AtomicBool,
@@ -538,6 +550,158 @@ async fn run_fs_watch_loop(state: Arc<crate::AppState>) {
     }
 }
 
+/// Coalesced change kinds reported by `GET /api/fs/watch`.
+fn watch_stream_kind(change_type: &str) -> &'static str {
+    match change_type {
+        "watch-create" | "mkdir" | "upload" => "create",
+        "watch-remove" | "delete" => "delete",
+        "watch-rename" | "rename" => "rename",
+        "watch-rescan" => "rescan",
+        _ => "modify",
+    }
+}
+
+/// Fold a change into the pending batch: a create followed by edits stays a
+/// create, and anything followed by a delete is a delete.
+fn merge_watch_change(
+    pending: &mut BTreeMap<String, &'static str>,
+    path: String,
+    kind: &'static str,
+) {
+    let merged = match (pending.get(&path).copied(), kind) {
+        (Some("create"), "modify") => "create",
+        (Some("create"), "delete") => {
+            pending.remove(&path);
+            return;
+        }
+        (_, kind) => kind,
+    };
+    pending.insert(path, merged);
+}
+
+/// Changes under `root` (normalized) carried by one hub payload.
+fn scoped_watch_changes(payload: &str, root: &str) -> Vec<(String, &'static str)> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(payload) else {
+        return Vec::new();
+    };
+    if value.get("type").and_then(|v| v.as_str()) != Some("opencode-studio:fs-changed") {
+        return Vec::new();
+    }
+    let Some(props) = value.get("properties") else {
+        return Vec::new();
+    };
+    let kind = watch_stream_kind(
+        props
+            .get("changeType")
+            .and_then(|v| v.as_str())
+            .unwrap_or(""),
+    );
+    props
+        .get("paths")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .filter_map(|path| {
+            let normalized = crate::path_utils::normalize_directory_for_match(path)?;
+            if kind == "rescan" && path_is_within_root(root, &normalized) {
+                // A rescan of an enclosing root covers this directory too.
+                return Some((root.to_string(), kind));
+            }
+            path_is_within_root(&normalized, root).then_some((normalized, kind))
+        })
+        .collect()
+}
+
+fn watch_stream_event(root: &str, pending: &mut BTreeMap<String, &'static str>) -> SseEvent {
+    let truncated = pending.len() > WATCH_STREAM_MAX_PATHS;
+    let changes = std::mem::take(pending)
+        .into_iter()
+        .take(WATCH_STREAM_MAX_PATHS)
+        .map(|(path, kind)| serde_json::json!({ "path": path, "kind": kind }))
+        .collect::<Vec<_>>();
+    let payload = serde_json::json!({
+        "directory": root,
+        "changes": changes,
+        "truncated": truncated,
+    });
+    SseEvent::default()
+        .event("changes")
+        .data(serde_json::to_string(&payload).unwrap_or_else(|_| "{}".to_string()))
+}
+
+/// SSE stream of debounced create/modify/delete/rename changes under one
+/// project directory, fed by the shared watcher through the global hub.
+pub async fn fs_watch_sse(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Query(q): Query<crate::fs::ProjectDirQuery>,
+) -> crate::ApiResult<Response> {
+    let directory =
+        crate::fs::resolve_project_directory(state.as_ref(), &headers, q.directory.as_deref())
+            .await?;
+    let root = normalized_path_for_match(&directory)
+        .ok_or_else(|| crate::AppError::bad_request("Invalid directory"))?;
+
+    start_fs_watch_hub_if_needed(state.clone());
+    hint_watch_root(&directory);
+    let mut subscriber = crate::global_sse_hub::subscribe_downstream();
+
+    let stream = async_stream::stream! {
+        let ready = serde_json::json!({ "directory": root });
+        yield Ok::<SseEvent, Infallible>(SseEvent::default().event("ready").data(ready.to_string()));
+
+        let mut pending: BTreeMap<String, &'static str> = BTreeMap::new();
+        let mut flush_at: Option<tokio::time::Instant> = None;
+        let mut rehint = tokio::time::interval(WATCH_STREAM_REHINT_INTERVAL);
+        rehint.tick().await;
+        loop {
+            let flush_sleep = async {
+                match flush_at {
+                    Some(at) => tokio::time::sleep_until(at).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                recv = subscriber.recv() => {
+                    let changes = match recv {
+                        crate::global_sse_hub::DownstreamRecv::Json(payload) => {
+                            scoped_watch_changes(&payload, &root)
+                        }
+                        crate::global_sse_hub::DownstreamRecv::Lagged => vec![(root.clone(), "rescan")],
+                        crate::global_sse_hub::DownstreamRecv::Closed => break,
+                    };
+                    if changes.is_empty() {
+                        continue;
+                    }
+                    for (path, kind) in changes {
+                        merge_watch_change(&mut pending, path, kind);
+                    }
+                    flush_at.get_or_insert_with(|| tokio::time::Instant::now() + WATCH_STREAM_DEBOUNCE);
+                }
+                _ = flush_sleep => {
+                    flush_at = None;
+                    if !pending.is_empty() {
+                        yield Ok(watch_stream_event(&root, &mut pending));
+                    }
+                }
+                _ = rehint.tick() => {
+                    // Keep the root alive past the hint TTL while someone is listening.
+                    hint_watch_root(&directory);
+                }
+            }
+        }
+    };
+
+    Ok(Sse::new(stream)
+        .keep_alive(
+            KeepAlive::new()
+                .interval(Duration::from_secs(15))
+                .text("ping"),
+        )
+        .into_response())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -565,52 +729,14 @@ mod tests {
 
         let db_dir = unique_tmp_dir("fs-watch-db");
         std::fs::create_dir_all(&db_dir).expect("mkdir db dir");
-        let studio_db = Arc::new(
-            crate::studio_db::StudioDb::open_at_path(db_dir.join("opencode.db"))
-                .await
-                .expect("open studio db"),
-        );
-
-        let workspace_preview_registry = Arc::new(
-            crate::workspace_preview_registry::WorkspacePreviewRegistry::new(studio_db.clone()),
-        );
-        let workspace_preview_runtime = Arc::new(
-            crate::workspace_preview_runtime::WorkspacePreviewRuntime::new(
-                workspace_preview_registry.clone(),
-            ),
-        );
-
-        let terminal = Arc::new(crate::terminal::TerminalManager::new(studio_db.clone()).await);
-
-        Arc::new(crate::AppState {
-            ui_auth: crate::ui_auth::UiAuth::Disabled,
-            ui_cookie_same_site: axum_extra::extract::cookie::SameSite::Strict,
-            cors_allowed_origins: Vec::new(),
-            cors_allow_all: false,
-            opencode: Arc::new(crate::opencode::OpenCodeManager::new(
-                "127.0.0.1".to_string(),
-                Some(1),
-                true,
-                None,
-                None,
-                crate::ui_auth::UiAuth::Disabled,
-            )),
-            plugin_runtime: Arc::new(crate::plugin_runtime::PluginRuntime::new()),
-            terminal,
-            attachment_cache: Arc::new(crate::attachment_cache::AttachmentCacheManager::new(
-                studio_db.clone(),
-            )),
-            session_activity: crate::session_activity::SessionActivityManager::new(),
-            directory_session_index:
-                crate::directory_session_index::DirectorySessionIndexManager::new(),
-            workspace_preview_registry,
-            workspace_preview_runtime,
-            studio_db,
-            settings: Arc::new(tokio::sync::RwLock::new(crate::settings::Settings {
+        crate::test_support::app_state(
+            &db_dir,
+            crate::settings::Settings {
                 projects: vec![project],
                 ..Default::default()
-            })),
-        })
+            },
+        )
+        .await
     }
 
     fn skip_if_watch_limit_reached(path: &Path) -> bool {
@@ -659,6 +785,50 @@ mod tests {
         assert!(!should_ignore_watch_path(Path::new("/repo/src/main.ts")));
     }
 
+    #[test]
+    fn watch_stream_scopes_and_coalesces_hub_changes() {
+        let payload = |change_type: &str, paths: &[&str]| {
+            serde_json::json!({
+                "type": "opencode-studio:fs-changed",
+                "properties": {"directory": "/work", "changeType": change_type, "paths": paths},
+            })
+            .to_string()
+        };
+        assert_eq!(
+            scoped_watch_changes(
+                &payload(
+                    "watch-create",
+                    &["/work/app/a.rs", "/work/other/b.rs", "/work/app"]
+                ),
+                "/work/app"
+            ),
+            vec![
+                ("/work/app/a.rs".to_string(), "create"),
+                ("/work/app".to_string(), "create")
+            ]
+        );
+        assert_eq!(
+            scoped_watch_changes(&payload("watch-rescan", &["/work"]), "/work/app"),
+            vec![("/work/app".to_string(), "rescan")]
+        );
+        assert!(scoped_watch_changes(r#"{"type":"session.idle"}"#, "/work/app").is_empty());
+
+        let mut pending = BTreeMap::new();
+        merge_watch_change(&mut pending, "/w/new.rs".into(), "create");
+        merge_watch_change(&mut pending, "/w/new.rs".into(), "modify");
+        merge_watch_change(&mut pending, "/w/old.rs".into(), "modify");
+        merge_watch_change(&mut pending, "/w/old.rs".into(), "delete");
+        merge_watch_change(&mut pending, "/w/tmp.rs".into(), "create");
+        merge_watch_change(&mut pending, "/w/tmp.rs".into(), "delete");
+        assert_eq!(
+            pending.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("/w/new.rs".to_string(), "create"),
+                ("/w/old.rs".to_string(), "delete")
+            ]
+        );
+    }
+
     #[test]
     fn classify_change_type_maps_notify_kinds() {
         assert_eq!(
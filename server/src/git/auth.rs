@@ -1,15 +1,16 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitAuthInput {
     pub username: Option<String>,
     pub password: Option<String>,
 }
 
-pub(crate) fn normalize_http_auth(auth: &GitAuthInput) -> Option<(String, String)> {
+fn normalize_http_auth(auth: &GitAuthInput) -> Option<(String, String)> {
     let username = auth.username.as_deref().unwrap_or("").trim().to_string();
     let password = auth.password.as_deref().unwrap_or("").trim().to_string();
     if username.is_empty() || password.is_empty() {
@@ -18,6 +19,47 @@ pub(crate) fn normalize_http_auth(auth: &GitAuthInput) -> Option<(String, String
     Some((username, password))
 }
 
+/// Host of an `http(s)://` remote URL; askpass is never used for ssh.
+fn http_remote_host(url: &str) -> Option<String> {
+    let rest = url
+        .trim()
+        .strip_prefix("https://")
+        .or_else(|| url.trim().strip_prefix("http://"))?;
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host);
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// Credentials for a network operation on `remote`: the ones sent with the
+/// request, else a `gitHttp` secret stored for the remote's host.
+pub(crate) async fn resolve_http_auth(
+    dir: &Path,
+    remote: Option<&str>,
+    auth: Option<&GitAuthInput>,
+) -> Option<(String, String)> {
+    if let Some(auth) = auth.and_then(normalize_http_auth) {
+        return Some(auth);
+    }
+    let remote = remote.unwrap_or("origin");
+    let Ok((0, url, _)) = super::run_git(dir, &["remote", "get-url", remote]).await else {
+        return None;
+    };
+    crate::secrets::git_http_credentials(&http_remote_host(&url)?)
+}
+
+/// Credentials for a URL that has no remote yet (clone): the ones sent with
+/// the request, else a `gitHttp` secret stored for the URL's host.
+pub(crate) fn http_auth_for_url(
+    url: &str,
+    auth: Option<&GitAuthInput>,
+) -> Option<(String, String)> {
+    if let Some(auth) = auth.and_then(normalize_http_auth) {
+        return Some(auth);
+    }
+    crate::secrets::git_http_credentials(&http_remote_host(url)?)
+}
+
 fn git_http_auth_options(username: &str, password: &str) -> Vec<String> {
     // Avoid putting secrets directly into argv.
     // We still disable credential helpers so the operation is predictable.
@@ -88,3 +130,22 @@ pub(crate) async fn git_http_auth_env(
     ];
     Ok((args, env, askpass))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn http_remote_hosts_are_extracted() {
+        assert_eq!(
+            http_remote_host("https://user@GitHub.com:443/acme/widgets.git\n").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(
+            http_remote_host("http://git.internal/repo").as_deref(),
+            Some("git.internal")
+        );
+        assert_eq!(http_remote_host("git@github.com:acme/widgets.git"), None);
+        assert_eq!(http_remote_host("ssh://git@github.com/acme/widgets"), None);
+    }
+}
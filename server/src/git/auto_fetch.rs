@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::directory_session_index::RemoteTrackingRecord;
+
+use super::{abs_path, busy_repo_directories, run_git};
+
+const SETTINGS_KEY: &str = "gitAutoFetch";
+const SCHEDULER_TICK: Duration = Duration::from_secs(30);
+const MIN_INTERVAL_MINUTES: u64 = 1;
+const MAX_INTERVAL_MINUTES: u64 = 24 * 60;
+const MAX_ERROR_CHARS: usize = 500;
+
+/// Auto-fetch interval from settings (`gitAutoFetch.intervalMinutes`);
+/// `None` when disabled, which is the default.
+pub(crate) fn auto_fetch_interval(value: Option<&Value>) -> Option<Duration> {
+    let minutes = value?.get("intervalMinutes")?.as_u64()?;
+    if minutes == 0 {
+        return None;
+    }
+    let minutes = minutes.clamp(MIN_INTERVAL_MINUTES, MAX_INTERVAL_MINUTES);
+    Some(Duration::from_secs(minutes * 60))
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// `git rev-list --left-right --count HEAD...@{u}` output: ahead, behind.
+fn parse_ahead_behind(out: &str) -> Option<(u32, u32)> {
+    let mut fields = out.split_whitespace();
+    let ahead = fields.next()?.parse().ok()?;
+    let behind = fields.next()?.parse().ok()?;
+    Some((ahead, behind))
+}
+
+async fn git_line(dir: &Path, args: &[&str]) -> Option<String> {
+    match run_git(dir, args).await {
+        Ok((0, out, _)) => Some(out.trim().to_string()).filter(|s| !s.is_empty()),
+        _ => None,
+    }
+}
+
+async fn fetch_and_track(dir: &Path) -> RemoteTrackingRecord {
+    let error = match run_git(dir, &["fetch", "--prune", "--quiet"]).await {
+        Ok((0, _, _)) => None,
+        Ok((_, out, err)) => Some(
+            Some(err.trim())
+                .filter(|e| !e.is_empty())
+                .unwrap_or(out.trim())
+                .chars()
+                .take(MAX_ERROR_CHARS)
+                .collect(),
+        ),
+        Err(err) => Some(err),
+    };
+    let branch = git_line(dir, &["symbolic-ref", "--short", "-q", "HEAD"]).await;
+    let upstream = git_line(
+        dir,
+        &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+    )
+    .await;
+    let (ahead, behind) = if upstream.is_some() {
+        git_line(dir, &["rev-list", "--left-right", "--count", "HEAD...@{u}"])
+            .await
+            .and_then(|out| parse_ahead_behind(&out))
+            .unwrap_or((0, 0))
+    } else {
+        (0, 0)
+    };
+    RemoteTrackingRecord {
+        branch,
+        upstream,
+        ahead,
+        behind,
+        fetched_at: now_millis(),
+        error,
+    }
+}
+
+fn publish_remote_updated(directory: &str, record: &RemoteTrackingRecord) {
+    let mut properties = serde_json::to_value(record).unwrap_or_default();
+    properties["directory"] = Value::String(directory.to_string());
+    let payload = serde_json::json!({
+        "type": "git.remote-updated",
+        "properties": properties,
+    });
+    crate::global_sse_hub::publish_downstream_json(&payload.to_string());
+}
+
+/// Periodically `git fetch --prune` every configured project, record
+/// ahead/behind in the directory index, and announce changes on the global
+/// event stream. Projects with a git operation in flight are skipped until
+/// the next tick.
+pub(crate) fn spawn_auto_fetch_task(state: Arc<crate::AppState>) {
+    tokio::spawn(async move {
+        let mut last_fetch: HashMap<String, Instant> = HashMap::new();
+        loop {
+            tokio::time::sleep(SCHEDULER_TICK).await;
+
+            let (interval, projects) = {
+                let settings = state.settings.read().await;
+                (
+                    auto_fetch_interval(settings.extra.get(SETTINGS_KEY)),
+                    settings
+                        .projects
+                        .iter()
+                        .map(|p| p.path.clone())
+                        .collect::<Vec<_>>(),
+                )
+            };
+            let Some(interval) = interval else {
+                last_fetch.clear();
+                continue;
+            };
+            last_fetch.retain(|path, _| projects.contains(path));
+
+            for project in projects {
+                if last_fetch
+                    .get(&project)
+                    .is_some_and(|at| at.elapsed() < interval)
+                {
+                    continue;
+                }
+                let dir = abs_path(&project);
+                if !dir.join(".git").exists() {
+                    continue;
+                }
+                let busy = busy_repo_directories();
+                if busy.iter().any(|b| Path::new(b) == dir) {
+                    continue;
+                }
+                last_fetch.insert(project.clone(), Instant::now());
+
+                let record = fetch_and_track(&dir).await;
+                if let Some(error) = record.error.as_deref() {
+                    tracing::debug!(
+                        target: "opencode_studio.git.auto_fetch",
+                        directory = %project,
+                        error = %error,
+                        "auto-fetch failed"
+                    );
+                }
+                if state
+                    .directory_session_index
+                    .upsert_remote_tracking(&project, record.clone())
+                {
+                    publish_remote_updated(&project, &record);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interval_and_ahead_behind_parsing() {
+        assert_eq!(auto_fetch_interval(None), None);
+        assert_eq!(
+            auto_fetch_interval(Some(&serde_json::json!({"intervalMinutes": 0}))),
+            None
+        );
+        assert_eq!(
+            auto_fetch_interval(Some(&serde_json::json!({"intervalMinutes": 15}))),
+            Some(Duration::from_secs(15 * 60))
+        );
+        assert_eq!(
+            auto_fetch_interval(Some(&serde_json::json!({"intervalMinutes": 100000}))),
+            Some(Duration::from_secs(MAX_INTERVAL_MINUTES * 60))
+        );
+
+        assert_eq!(parse_ahead_behind("2\t5\n"), Some((2, 5)));
+        assert_eq!(parse_ahead_behind("garbage"), None);
+    }
+}
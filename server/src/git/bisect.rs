@@ -0,0 +1,644 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use axum::{
+    Json,
+    extract::Query,
+    http::StatusCode,
+    response::{
+        IntoResponse, Response,
+        sse::{KeepAlive, Sse},
+    },
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+use super::{
+    DirectoryQuery, git_stdout, is_safe_repo_rel_path, lock_repo, map_git_failure,
+    require_directory, run_git, sse_json,
+};
+
+const DEFAULT_RUN_TIMEOUT_SECONDS: u64 = 10 * 60;
+const MAX_RUN_TIMEOUT_SECONDS: u64 = 60 * 60;
+/// Safety net for `run`; a bisect over a million commits needs ~20 steps.
+const MAX_RUN_STEPS: usize = 64;
+/// Output lines streamed per step; the rest are counted but not sent.
+const MAX_STREAMED_LINES_PER_STEP: usize = 2000;
+/// `git bisect run` convention: the commit can't be tested.
+const SKIP_EXIT_CODE: i32 = 125;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBisectCommit {
+    pub hash: String,
+    pub subject: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBisectStatus {
+    pub active: bool,
+    /// Commit checked out for testing.
+    pub current: Option<GitBisectCommit>,
+    pub bad: Option<String>,
+    pub good: Vec<String>,
+    pub skipped: Vec<String>,
+    /// Commits still in range, as reported by `rev-list --bisect-vars`.
+    pub remaining: Option<u64>,
+    /// Estimated steps left after the current one.
+    pub steps_left: Option<u64>,
+    pub first_bad: Option<GitBisectCommit>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitBisectStartBody {
+    pub bad: Option<String>,
+    #[serde(default)]
+    pub good: Vec<String>,
+    /// Limit the bisection to commits touching these paths.
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitBisectMarkBody {
+    /// Defaults to the checked-out candidate.
+    pub rev: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBisectRunBody {
+    /// Program to run at each step; never a shell line, arguments go in
+    /// `args`. Exit 0 marks good, 125 skip, 1-127 bad, anything else aborts.
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub timeout_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum BisectVerdict {
+    Good,
+    Bad,
+    Skip,
+}
+
+impl BisectVerdict {
+    fn as_str(self) -> &'static str {
+        match self {
+            BisectVerdict::Good => "good",
+            BisectVerdict::Bad => "bad",
+            BisectVerdict::Skip => "skip",
+        }
+    }
+
+    /// Map a test command's exit code the way `git bisect run` does.
+    fn from_exit_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(BisectVerdict::Good),
+            SKIP_EXIT_CODE => Some(BisectVerdict::Skip),
+            1..=127 => Some(BisectVerdict::Bad),
+            _ => None,
+        }
+    }
+}
+
+fn bisect_error(status: StatusCode, code: &str, error: impl Into<String>) -> Response {
+    (
+        status,
+        Json(serde_json::json!({"error": error.into(), "code": code})),
+    )
+        .into_response()
+}
+
+fn is_valid_rev(rev: &str) -> bool {
+    !rev.is_empty() && !rev.starts_with('-') && !rev.chars().any(char::is_whitespace)
+}
+
+async fn git_dir(dir: &Path) -> Option<PathBuf> {
+    let out = git_stdout(dir, &["rev-parse", "--absolute-git-dir"]).await?;
+    Some(PathBuf::from(out.trim()))
+}
+
+async fn commit_summary(dir: &Path, rev: &str) -> Option<GitBisectCommit> {
+    let out = git_stdout(dir, &["log", "-1", "--format=%H%x1f%s", rev, "--"]).await?;
+    let (hash, subject) = out.trim_end().split_once('\x1f')?;
+    Some(GitBisectCommit {
+        hash: hash.to_string(),
+        subject: subject.to_string(),
+    })
+}
+
+/// Parse `rev-list --bisect-vars` output into (remaining, steps).
+fn parse_bisect_vars(out: &str) -> (Option<u64>, Option<u64>) {
+    let var = |name: &str| {
+        out.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+            .and_then(|v| v.trim().trim_matches('\'').parse::<u64>().ok())
+    };
+    (var("bisect_all"), var("bisect_steps"))
+}
+
+/// Hash printed by `git bisect good|bad|skip` once the culprit is known.
+fn parse_first_bad(out: &str) -> Option<String> {
+    out.lines().find_map(|line| {
+        let hash = line.strip_suffix(" is the first bad commit")?.trim();
+        hash.chars()
+            .all(|c| c.is_ascii_hexdigit())
+            .then(|| hash.to_string())
+    })
+}
+
+async fn bisect_status(dir: &Path) -> GitBisectStatus {
+    let Some(git_dir) = git_dir(dir).await else {
+        return GitBisectStatus::default();
+    };
+    if !git_dir.join("BISECT_START").exists() {
+        return GitBisectStatus::default();
+    }
+
+    let mut status = GitBisectStatus {
+        active: true,
+        ..GitBisectStatus::default()
+    };
+    let refs = git_stdout(
+        dir,
+        &[
+            "for-each-ref",
+            "--format=%(refname:strip=2) %(objectname)",
+            "refs/bisect/",
+        ],
+    )
+    .await
+    .unwrap_or_default();
+    for line in refs.lines() {
+        let Some((name, hash)) = line.split_once(' ') else {
+            continue;
+        };
+        if name == "bad" {
+            status.bad = Some(hash.to_string());
+        } else if name.starts_with("good-") {
+            status.good.push(hash.to_string());
+        } else if name.starts_with("skip-") {
+            status.skipped.push(hash.to_string());
+        }
+    }
+    status.current = commit_summary(dir, "HEAD").await;
+
+    if let Some(bad) = status.bad.clone()
+        && !status.good.is_empty()
+    {
+        let mut args = vec!["rev-list", "--bisect-vars", bad.as_str()];
+        let excluded: Vec<String> = status.good.iter().map(|h| format!("^{h}")).collect();
+        args.extend(excluded.iter().map(String::as_str));
+        if let Some(out) = git_stdout(dir, &args).await {
+            let (remaining, steps) = parse_bisect_vars(&out);
+            status.remaining = remaining;
+            status.steps_left = steps;
+            // Only the bad commit is left in range: it is the culprit.
+            if remaining == Some(1) {
+                status.first_bad = commit_summary(dir, &bad).await;
+            }
+        }
+    }
+    status
+}
+
+fn status_response(status: GitBisectStatus, output: Option<String>) -> Response {
+    let mut value = serde_json::to_value(status).unwrap_or_default();
+    if let Some(output) = output {
+        value["output"] = serde_json::Value::String(output);
+    }
+    Json(value).into_response()
+}
+
+async fn run_bisect_command(dir: &Path, args: &[&str]) -> Result<String, Box<Response>> {
+    let (code, out, err) = run_git(dir, args)
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        if let Some(resp) = map_git_failure(code, &out, &err) {
+            return Err(Box::new(resp));
+        }
+        return Err(Box::new(bisect_error(
+            StatusCode::CONFLICT,
+            "git_bisect_failed",
+            Some(err.trim())
+                .filter(|e| !e.is_empty())
+                .unwrap_or(out.trim()),
+        )));
+    }
+    Ok(out)
+}
+
+async fn status_with_output(dir: &Path, out: String) -> Response {
+    let mut status = bisect_status(dir).await;
+    if let Some(hash) = parse_first_bad(&out)
+        && status.first_bad.is_none()
+    {
+        status.first_bad = commit_summary(dir, &hash).await;
+    }
+    status_response(status, Some(out))
+}
+
+/// Current bisect session: candidate, marked commits and steps left.
+pub async fn git_bisect_status(Query(q): Query<DirectoryQuery>) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    status_response(bisect_status(&dir).await, None)
+}
+
+pub async fn git_bisect_start(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitBisectStartBody>,
+) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let bad = body.bad.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    let good: Vec<&str> = body
+        .good
+        .iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if bad.is_some_and(|r| !is_valid_rev(r)) || good.iter().any(|r| !is_valid_rev(r)) {
+        return bisect_error(StatusCode::BAD_REQUEST, "invalid_rev", "Invalid revision");
+    }
+    if !good.is_empty() && bad.is_none() {
+        return bisect_error(
+            StatusCode::BAD_REQUEST,
+            "missing_bad",
+            "A bad revision is required when good revisions are given",
+        );
+    }
+    let paths: Vec<&str> = body
+        .paths
+        .iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if let Some(bad_path) = paths.iter().find(|p| !is_safe_repo_rel_path(p)) {
+        return bisect_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_path",
+            format!("Invalid path: {bad_path}"),
+        );
+    }
+
+    let _guard = match lock_repo(&dir, "bisect").await {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+    if bisect_status(&dir).await.active {
+        return bisect_error(
+            StatusCode::CONFLICT,
+            "bisect_in_progress",
+            "A bisect session is already running; reset it first",
+        );
+    }
+
+    let mut args = vec!["bisect", "start"];
+    args.extend(bad);
+    args.extend(good.iter().copied());
+    args.push("--");
+    args.extend(paths.iter().copied());
+    match run_bisect_command(&dir, &args).await {
+        Ok(out) => status_with_output(&dir, out).await,
+        Err(resp) => *resp,
+    }
+}
+
+async fn mark(q: DirectoryQuery, body: GitBisectMarkBody, verdict: BisectVerdict) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let rev = body.rev.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    if rev.is_some_and(|r| !is_valid_rev(r)) {
+        return bisect_error(StatusCode::BAD_REQUEST, "invalid_rev", "Invalid revision");
+    }
+    let _guard = match lock_repo(&dir, "bisect").await {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+    if !bisect_status(&dir).await.active {
+        return bisect_error(
+            StatusCode::CONFLICT,
+            "no_bisect",
+            "No bisect session is running",
+        );
+    }
+
+    let mut args = vec!["bisect", verdict.as_str()];
+    args.extend(rev);
+    match run_bisect_command(&dir, &args).await {
+        Ok(out) => status_with_output(&dir, out).await,
+        Err(resp) => *resp,
+    }
+}
+
+pub async fn git_bisect_good(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitBisectMarkBody>,
+) -> Response {
+    mark(q, body, BisectVerdict::Good).await
+}
+
+pub async fn git_bisect_bad(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitBisectMarkBody>,
+) -> Response {
+    mark(q, body, BisectVerdict::Bad).await
+}
+
+pub async fn git_bisect_skip(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitBisectMarkBody>,
+) -> Response {
+    mark(q, body, BisectVerdict::Skip).await
+}
+
+/// End the session and check out the branch bisect started from.
+pub async fn git_bisect_reset(Query(q): Query<DirectoryQuery>) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let _guard = match lock_repo(&dir, "bisect").await {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+    match run_bisect_command(&dir, &["bisect", "reset"]).await {
+        Ok(out) => status_response(bisect_status(&dir).await, Some(out)),
+        Err(resp) => *resp,
+    }
+}
+
+enum StepOutput {
+    Line(&'static str, String),
+    Exited(Result<std::process::ExitStatus, String>),
+    TimedOut,
+}
+
+/// Spawn the test command and forward its output line by line, followed by
+/// the exit status. The child is killed if the receiver goes away.
+fn spawn_step(
+    dir: &Path,
+    command: &str,
+    args: &[String],
+    timeout: Duration,
+) -> Result<tokio::sync::mpsc::Receiver<StepOutput>, String> {
+    let mut child = Command::new(command)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| format!("failed to start '{command}': {err}"))?;
+    let (tx, rx) = tokio::sync::mpsc::channel(256);
+
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        let tx = tx.clone();
+        readers.push(tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if tx.send(StepOutput::Line("stdout", line)).await.is_err() {
+                    break;
+                }
+            }
+        }));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let tx = tx.clone();
+        readers.push(tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if tx.send(StepOutput::Line("stderr", line)).await.is_err() {
+                    break;
+                }
+            }
+        }));
+    }
+    tokio::spawn(async move {
+        let status = tokio::select! {
+            status = child.wait() => {
+                for reader in readers {
+                    let _ = reader.await;
+                }
+                StepOutput::Exited(status.map_err(|err| err.to_string()))
+            }
+            _ = tokio::time::sleep(timeout) => {
+                let _ = child.kill().await;
+                StepOutput::TimedOut
+            }
+            _ = tx.closed() => return,
+        };
+        let _ = tx.send(status).await;
+    });
+    Ok(rx)
+}
+
+/// Drive the bisect session with a test command: run it at each candidate,
+/// mark the commit from its exit code and stream output and verdicts until
+/// the first bad commit is found. The repository stays locked meanwhile.
+pub async fn git_bisect_run(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitBisectRunBody>,
+) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let Some(command) = body
+        .command
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+    else {
+        return bisect_error(
+            StatusCode::BAD_REQUEST,
+            "missing_command",
+            "command is required",
+        );
+    };
+    let args = body.args;
+    let timeout = Duration::from_secs(
+        body.timeout_seconds
+            .unwrap_or(DEFAULT_RUN_TIMEOUT_SECONDS)
+            .clamp(1, MAX_RUN_TIMEOUT_SECONDS),
+    );
+
+    let guard = match lock_repo(&dir, "bisect").await {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+    let status = bisect_status(&dir).await;
+    if !status.active || status.bad.is_none() || status.good.is_empty() {
+        return bisect_error(
+            StatusCode::CONFLICT,
+            "bisect_not_ready",
+            "Start a bisect session with a good and a bad commit first",
+        );
+    }
+
+    let stream = async_stream::stream! {
+        let _guard = guard;
+        yield sse_json("start", serde_json::json!({
+            "command": command,
+            "args": args,
+            "stepsLeft": status.steps_left,
+        }));
+
+        let mut first_bad: Option<GitBisectCommit> = status.first_bad;
+        let mut error: Option<String> = None;
+        let mut step = 0usize;
+        while first_bad.is_none() && error.is_none() {
+            if step >= MAX_RUN_STEPS {
+                error = Some(format!("Stopped after {MAX_RUN_STEPS} steps"));
+                break;
+            }
+            let current = commit_summary(&dir, "HEAD").await;
+            yield sse_json("step", serde_json::json!({
+                "step": step,
+                "commit": current,
+            }));
+
+            let mut rx = match spawn_step(&dir, &command, &args, timeout) {
+                Ok(rx) => rx,
+                Err(err) => {
+                    error = Some(err);
+                    break;
+                }
+            };
+            let mut lines = 0usize;
+            let mut exit: Option<Result<std::process::ExitStatus, String>> = None;
+            while let Some(message) = rx.recv().await {
+                match message {
+                    StepOutput::Line(stream, line) => {
+                        lines += 1;
+                        if lines <= MAX_STREAMED_LINES_PER_STEP {
+                            yield sse_json("output", serde_json::json!({
+                                "step": step,
+                                "stream": stream,
+                                "line": line,
+                            }));
+                        }
+                    }
+                    StepOutput::Exited(result) => exit = Some(result),
+                    StepOutput::TimedOut => {
+                        exit = Some(Err(format!(
+                            "Test command timed out after {}s",
+                            timeout.as_secs()
+                        )));
+                    }
+                }
+            }
+            let verdict = match exit {
+                Some(Ok(exit)) => match exit.code().and_then(BisectVerdict::from_exit_code) {
+                    Some(verdict) => verdict,
+                    None => {
+                        error = Some(format!("Test command aborted the bisect ({exit})"));
+                        break;
+                    }
+                },
+                Some(Err(err)) => {
+                    error = Some(err);
+                    break;
+                }
+                None => {
+                    error = Some("Test command ended without an exit status".to_string());
+                    break;
+                }
+            };
+
+            let marked = run_git(&dir, &["bisect", verdict.as_str()]).await;
+            let out = match marked {
+                Ok((0, out, _)) => out,
+                Ok((_, out, err)) => {
+                    error = Some(
+                        Some(err.trim())
+                            .filter(|e| !e.is_empty())
+                            .unwrap_or(out.trim())
+                            .to_string(),
+                    );
+                    break;
+                }
+                Err(err) => {
+                    error = Some(err);
+                    break;
+                }
+            };
+            let after = bisect_status(&dir).await;
+            yield sse_json("result", serde_json::json!({
+                "step": step,
+                "commit": current,
+                "verdict": verdict,
+                "truncatedLines": lines.saturating_sub(MAX_STREAMED_LINES_PER_STEP),
+                "stepsLeft": after.steps_left,
+            }));
+            first_bad = match parse_first_bad(&out) {
+                Some(hash) => commit_summary(&dir, &hash).await,
+                None => after.first_bad,
+            };
+            // Only skippable commits left: git can't narrow it down further.
+            if first_bad.is_none() && out.contains("only skipped commits left") {
+                error = Some(out.trim().to_string());
+            }
+            step += 1;
+        }
+
+        yield sse_json("done", serde_json::json!({
+            "firstBad": first_bad,
+            "steps": step,
+            "error": error,
+        }));
+    };
+
+    let keep = KeepAlive::new()
+        .interval(Duration::from_secs(15))
+        .text("ping");
+    Sse::new(stream).keep_alive(keep).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes_map_like_git_bisect_run() {
+        assert_eq!(BisectVerdict::from_exit_code(0), Some(BisectVerdict::Good));
+        assert_eq!(BisectVerdict::from_exit_code(1), Some(BisectVerdict::Bad));
+        assert_eq!(
+            BisectVerdict::from_exit_code(125),
+            Some(BisectVerdict::Skip)
+        );
+        assert_eq!(BisectVerdict::from_exit_code(127), Some(BisectVerdict::Bad));
+        assert_eq!(BisectVerdict::from_exit_code(128), None);
+        assert_eq!(BisectVerdict::from_exit_code(-1), None);
+    }
+
+    #[test]
+    fn parses_bisect_vars_and_first_bad() {
+        let vars = "bisect_rev=abc\nbisect_nr=3\nbisect_good=2\nbisect_bad=3\nbisect_all=7\nbisect_steps=2\n";
+        assert_eq!(parse_bisect_vars(vars), (Some(7), Some(2)));
+        let out = "0123abcd0123abcd0123abcd0123abcd0123abcd is the first bad commit\ncommit 0123\n";
+        assert_eq!(
+            parse_first_bad(out).as_deref(),
+            Some("0123abcd0123abcd0123abcd0123abcd0123abcd")
+        );
+        assert_eq!(parse_first_bad("Bisecting: 3 revisions left"), None);
+    }
+}
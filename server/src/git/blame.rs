@@ -4,21 +4,31 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Instant, SystemTime};
 
 use super::{
     DirectoryQuery, abs_path, is_safe_repo_rel_path, map_git_failure, require_directory, run_git,
 };
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitBlameQuery {
     pub directory: Option<String>,
     pub path: Option<String>,
+    /// First line to blame (1-based); defaults to 1 when only `end` is set.
+    pub start: Option<usize>,
+    /// Last line to blame, inclusive; defaults to the end of the file.
+    pub end: Option<usize>,
+    /// Repo-relative file of revisions to skip, as `--ignore-revs-file`.
+    #[serde(rename = "ignoreRevsFile")]
+    pub ignore_revs_file: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GitBlameLine {
     pub line: usize,
@@ -29,7 +39,7 @@ pub struct GitBlameLine {
     pub summary: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct GitBlameResponse {
     pub lines: Vec<GitBlameLine>,
 }
@@ -37,6 +47,98 @@ pub struct GitBlameResponse {
 const NOT_COMMITTED_HASH: &str = "0000000000000000000000000000000000000000";
 const NOT_COMMITTED_AUTHOR: &str = "Not Committed Yet";
 const NOT_COMMITTED_SUMMARY: &str = "Uncommitted changes";
+const BLAME_CACHE_MAX_ENTRIES: usize = 64;
+
+/// Everything a blame result depends on: the file and its on-disk state,
+/// HEAD, the line range and the ignore-revs file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct BlameCacheKey {
+    repo_root: PathBuf,
+    path: String,
+    file_stamp: Option<(SystemTime, u64)>,
+    head: String,
+    range: Option<(usize, Option<usize>)>,
+    ignore_revs: Option<(String, Option<SystemTime>)>,
+}
+
+struct BlameCacheEntry {
+    used: Instant,
+    lines: Arc<Vec<GitBlameLine>>,
+}
+
+static BLAME_CACHE: LazyLock<Mutex<HashMap<BlameCacheKey, BlameCacheEntry>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn cached_blame(key: &BlameCacheKey) -> Option<Arc<Vec<GitBlameLine>>> {
+    let mut cache = BLAME_CACHE.lock().ok()?;
+    let entry = cache.get_mut(key)?;
+    entry.used = Instant::now();
+    Some(entry.lines.clone())
+}
+
+fn store_blame(key: BlameCacheKey, lines: Arc<Vec<GitBlameLine>>) {
+    let Ok(mut cache) = BLAME_CACHE.lock() else {
+        return;
+    };
+    if cache.len() >= BLAME_CACHE_MAX_ENTRIES
+        && !cache.contains_key(&key)
+        && let Some(oldest) = cache
+            .iter()
+            .min_by_key(|(_, entry)| entry.used)
+            .map(|(key, _)| key.clone())
+    {
+        cache.remove(&oldest);
+    }
+    cache.insert(
+        key,
+        BlameCacheEntry {
+            used: Instant::now(),
+            lines,
+        },
+    );
+}
+
+/// Drop cached blame for the repository containing `dir` (or any repository
+/// below it). Called when a git operation changes the repository status.
+pub(crate) fn invalidate_blame_cache(dir: &Path) {
+    if let Ok(mut cache) = BLAME_CACHE.lock() {
+        cache.retain(|key, _| !dir.starts_with(&key.repo_root) && !key.repo_root.starts_with(dir));
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Validate `start`/`end` into a `-L` range; `Ok(None)` blames the whole file.
+fn blame_range(
+    start: Option<usize>,
+    end: Option<usize>,
+) -> Result<Option<(usize, Option<usize>)>, ()> {
+    match (start, end) {
+        (None, None) => Ok(None),
+        (start, end) => {
+            let start = start.unwrap_or(1);
+            if start == 0 || end.is_some_and(|end| end < start) {
+                return Err(());
+            }
+            Ok(Some((start, end)))
+        }
+    }
+}
+
+fn range_arg((start, end): (usize, Option<usize>)) -> String {
+    match end {
+        Some(end) => format!("{start},{end}"),
+        None => format!("{start},"),
+    }
+}
+
+fn is_range_failure(stderr: &str) -> bool {
+    let lower = stderr.to_ascii_lowercase();
+    lower.contains("has only") && lower.contains("line")
+}
 
 fn is_hash(s: &str) -> bool {
     if s.len() != 40 {
@@ -179,10 +281,17 @@ fn build_uncommitted_blame_lines_from_content(content: &str) -> Vec<GitBlameLine
         .collect()
 }
 
-fn build_uncommitted_blame_lines(path: &Path) -> Option<Vec<GitBlameLine>> {
+fn build_uncommitted_blame_lines(
+    path: &Path,
+    range: Option<(usize, Option<usize>)>,
+) -> Option<Vec<GitBlameLine>> {
     let bytes = std::fs::read(path).ok()?;
     let content = std::str::from_utf8(&bytes).ok()?;
-    Some(build_uncommitted_blame_lines_from_content(content))
+    let mut lines = build_uncommitted_blame_lines_from_content(content);
+    if let Some((start, end)) = range {
+        lines.retain(|line| line.line >= start && end.is_none_or(|end| line.line <= end));
+    }
+    Some(lines)
 }
 
 fn file_dir(path: &Path) -> PathBuf {
@@ -220,6 +329,26 @@ pub async fn git_blame(Query(q): Query<GitBlameQuery>) -> Response {
             .into_response();
     }
 
+    let Ok(range) = blame_range(q.start, q.end) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid line range", "code": "invalid_range"})),
+        )
+            .into_response();
+    };
+    let ignore_revs_file = q
+        .ignore_revs_file
+        .as_deref()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty());
+    if ignore_revs_file.is_some_and(|f| !is_safe_repo_rel_path(f)) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid ignore-revs file", "code": "invalid_ignore_revs_file"})),
+        )
+            .into_response();
+    }
+
     let abs = dir.join(path);
     if !abs.starts_with(&dir) {
         return (
@@ -259,15 +388,68 @@ pub async fn git_blame(Query(q): Query<GitBlameQuery>) -> Response {
         .trim_start_matches('/')
         .replace('\\', "/");
 
-    let (code, out, err) = run_git(&repo_root, &["blame", "--line-porcelain", "--", &rel])
-        .await
-        .unwrap_or((1, "".to_string(), "".to_string()));
+    let ignore_revs = match ignore_revs_file {
+        Some(file) => {
+            let Ok(meta) = std::fs::metadata(repo_root.join(file)) else {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "Ignore-revs file not found", "code": "invalid_ignore_revs_file"})),
+                )
+                    .into_response();
+            };
+            Some((file.to_string(), meta.modified().ok()))
+        }
+        None => None,
+    };
+    let head = match run_git(&repo_root, &["rev-parse", "--verify", "--quiet", "HEAD"]).await {
+        Ok((0, out, _)) => out.trim().to_string(),
+        _ => String::new(),
+    };
+    let key = BlameCacheKey {
+        repo_root: repo_root.clone(),
+        path: rel.clone(),
+        file_stamp: file_stamp(&abs),
+        head,
+        range,
+        ignore_revs,
+    };
+    if let Some(lines) = cached_blame(&key) {
+        return Json(GitBlameResponse {
+            lines: lines.as_ref().clone(),
+        })
+        .into_response();
+    }
+
+    let mut args: Vec<String> = vec!["blame".into(), "--line-porcelain".into()];
+    if let Some(range) = range {
+        args.push("-L".into());
+        args.push(range_arg(range));
+    }
+    if let Some((file, _)) = &key.ignore_revs {
+        args.push("--ignore-revs-file".into());
+        args.push(file.clone());
+    }
+    args.push("--".into());
+    args.push(rel.clone());
+    let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+
+    let (code, out, err) =
+        run_git(&repo_root, &args_ref)
+            .await
+            .unwrap_or((1, "".to_string(), "".to_string()));
     if code != 0 {
         if should_fallback_to_uncommitted_blame(&out, &err)
-            && let Some(lines) = build_uncommitted_blame_lines(&abs)
+            && let Some(lines) = build_uncommitted_blame_lines(&abs, range)
         {
             return Json(GitBlameResponse { lines }).into_response();
         }
+        if range.is_some() && is_range_failure(&err) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": err.trim(), "code": "invalid_range"})),
+            )
+                .into_response();
+        }
 
         if let Some(resp) = map_git_failure(code, &out, &err) {
             return resp;
@@ -280,6 +462,7 @@ pub async fn git_blame(Query(q): Query<GitBlameQuery>) -> Response {
     }
 
     let lines = parse_blame_porcelain(&out);
+    store_blame(key, Arc::new(lines.clone()));
     Json(GitBlameResponse { lines }).into_response()
 }
 
@@ -368,6 +551,17 @@ filename src/main.rs\n\
         assert_eq!(lines[0].hash, NOT_COMMITTED_HASH);
     }
 
+    #[test]
+    fn line_ranges_are_validated() {
+        assert_eq!(blame_range(None, None), Ok(None));
+        assert_eq!(blame_range(Some(5), None), Ok(Some((5, None))));
+        assert_eq!(blame_range(None, Some(3)), Ok(Some((1, Some(3)))));
+        assert_eq!(blame_range(Some(0), Some(3)), Err(()));
+        assert_eq!(blame_range(Some(4), Some(3)), Err(()));
+        assert_eq!(range_arg((5, None)), "5,");
+        assert_eq!(range_arg((2, Some(9))), "2,9");
+    }
+
     #[test]
     fn detects_missing_path_failure_for_fallback() {
         assert!(should_fallback_to_uncommitted_blame(
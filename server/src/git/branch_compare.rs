@@ -0,0 +1,228 @@
+use std::path::Path;
+
+use axum::{
+    Json,
+    extract::Query,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use futures_util::StreamExt;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::branches::parse_track_counts;
+use super::{map_git_failure, require_directory_raw, run_git};
+
+const BASE_COMPARE_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBranchCompareQuery {
+    pub directory: Option<String>,
+    /// Also count each branch against this ref, e.g. `main` or `origin/main`.
+    pub base: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBranchComparison {
+    pub name: String,
+    pub current: bool,
+    pub commit: String,
+    pub subject: String,
+    pub author_name: String,
+    pub committer_date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    /// The upstream is configured but its remote branch no longer exists.
+    pub upstream_gone: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ahead: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub behind: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_ahead: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_behind: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBranchCompareResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_commit: Option<String>,
+    pub branches: Vec<GitBranchComparison>,
+}
+
+const BRANCH_FORMAT: &str = "%(refname:short)%00%(objectname)%00%(HEAD)%00%(upstream:short)%00%(upstream:track)%00%(authorname)%00%(committerdate:iso-strict)%00%(subject)";
+
+fn parse_branch_rows(out: &str) -> Vec<GitBranchComparison> {
+    out.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\0').collect();
+            if fields.len() < 8 || fields[0].is_empty() {
+                return None;
+            }
+            let upstream = Some(fields[3].trim())
+                .filter(|s| !s.is_empty())
+                .map(str::to_string);
+            let track = fields[4].trim();
+            let upstream_gone = track == "[gone]";
+            let (ahead, behind) = match (&upstream, track) {
+                (None, _) => (None, None),
+                (Some(_), "[gone]") => (None, None),
+                // In sync with the upstream: git prints nothing.
+                (Some(_), "") => (Some(0), Some(0)),
+                (Some(_), track) => {
+                    let (a, b) = parse_track_counts(track);
+                    (a.or(Some(0)), b.or(Some(0)))
+                }
+            };
+            Some(GitBranchComparison {
+                name: fields[0].to_string(),
+                current: fields[2] == "*",
+                commit: fields[1].to_string(),
+                subject: fields[7].to_string(),
+                author_name: fields[5].to_string(),
+                committer_date: fields[6].to_string(),
+                upstream,
+                upstream_gone,
+                ahead,
+                behind,
+                base_ahead: None,
+                base_behind: None,
+            })
+        })
+        .collect()
+}
+
+async fn count_against_base(dir: &Path, commit: &str, base: &str) -> Option<(u32, u32)> {
+    let range = format!("{commit}...{base}");
+    let (code, out, _) = run_git(dir, &["rev-list", "--left-right", "--count", &range, "--"])
+        .await
+        .ok()?;
+    if code != 0 {
+        return None;
+    }
+    let mut fields = out.split_whitespace();
+    let ahead = fields.next()?.parse().ok()?;
+    let behind = fields.next()?.parse().ok()?;
+    Some((ahead, behind))
+}
+
+/// Ahead/behind of every local branch against its upstream and, when `base`
+/// is given, against that ref, with last-commit metadata, in one call.
+pub async fn git_branch_compare(Query(q): Query<GitBranchCompareQuery>) -> Response {
+    let dir = match require_directory_raw(q.directory.as_deref()) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let base = q.base.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    if base.is_some_and(|b| b.starts_with('-')) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid base", "code": "invalid_ref"})),
+        )
+            .into_response();
+    }
+
+    let base_commit = match base {
+        Some(base) => {
+            let spec = format!("{base}^{{commit}}");
+            let (code, out, err) = run_git(&dir, &["rev-parse", "--verify", "--quiet", &spec])
+                .await
+                .unwrap_or((1, "".to_string(), "".to_string()));
+            if code != 0 {
+                if let Some(resp) = map_git_failure(code, &out, &err)
+                    && !err.trim().is_empty()
+                {
+                    return resp;
+                }
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(serde_json::json!({
+                        "error": format!("Base ref not found: {base}"),
+                        "code": "base_not_found",
+                    })),
+                )
+                    .into_response();
+            }
+            Some(out.trim().to_string())
+        }
+        None => None,
+    };
+
+    let (code, out, err) = run_git(
+        &dir,
+        &[
+            "for-each-ref",
+            "--sort=-committerdate",
+            &format!("--format={BRANCH_FORMAT}"),
+            "refs/heads",
+        ],
+    )
+    .await
+    .unwrap_or((1, "".to_string(), "".to_string()));
+    if let Some(resp) = map_git_failure(code, &out, &err) {
+        return resp;
+    }
+    let mut branches = parse_branch_rows(&out);
+
+    if let Some(base_commit) = base_commit.as_deref() {
+        let jobs: Vec<(usize, String)> = branches
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (i, b.commit.clone()))
+            .collect();
+        let base_commit = base_commit.to_string();
+        let counts = futures_util::stream::iter(jobs.into_iter().map(move |(i, commit)| {
+            let dir = dir.clone();
+            let base_commit = base_commit.clone();
+            async move { (i, count_against_base(&dir, &commit, &base_commit).await) }
+        }))
+        .buffer_unordered(BASE_COMPARE_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+        for (i, counts) in counts {
+            if let Some((ahead, behind)) = counts {
+                branches[i].base_ahead = Some(ahead);
+                branches[i].base_behind = Some(behind);
+            }
+        }
+    }
+
+    Json(GitBranchCompareResponse {
+        base: base.map(str::to_string),
+        base_commit,
+        branches,
+    })
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_upstream_tracking_states() {
+        let out = [
+            "main|aaa|*|origin/main||Ada|2024-01-02|tip",
+            "feat|bbb| |origin/feat|[ahead 2, behind 1]|Ada|2024-01-01|wip",
+            "old|ccc| |origin/old|[gone]|Ada|2023-01-01|stale",
+            "local|ddd| |||Ada|2023-01-01|solo",
+        ]
+        .join("\n")
+        .replace('|', "\0");
+        let rows = parse_branch_rows(&out);
+        assert_eq!(rows.len(), 4);
+        assert!(rows[0].current);
+        assert_eq!((rows[0].ahead, rows[0].behind), (Some(0), Some(0)));
+        assert_eq!((rows[1].ahead, rows[1].behind), (Some(2), Some(1)));
+        assert!(rows[2].upstream_gone);
+        assert_eq!(rows[2].ahead, None);
+        assert_eq!(rows[3].upstream, None);
+        assert_eq!(rows[3].ahead, None);
+    }
+}
@@ -7,11 +7,14 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use super::remote::git_current_branch;
 use super::{
-    DirectoryQuery, GitAuthInput, TempGitAskpass, git_http_auth_env, lock_repo, map_git_failure,
-    normalize_http_auth, require_directory, require_directory_raw, run_git, run_git_env,
+    DirectoryQuery, GitAuthInput, GitDryRunPreview, TempGitAskpass, git_http_auth_env,
+    list_commits, lock_repo, map_git_failure, require_directory, require_directory_raw,
+    resolve_http_auth, rev_parse_commit, run_git, run_git_env,
 };
 
 #[derive(Debug, Clone, Serialize)]
@@ -43,7 +46,7 @@ pub struct GitBranchesResponse {
     pub search: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitBranchesQuery {
     pub directory: Option<String>,
@@ -53,7 +56,7 @@ pub struct GitBranchesQuery {
     pub local_only: Option<bool>,
 }
 
-fn parse_track_counts(track: &str) -> (Option<i32>, Option<i32>) {
+pub(super) fn parse_track_counts(track: &str) -> (Option<i32>, Option<i32>) {
     // trackshort examples: "ahead 1" or "behind 2" or "ahead 1, behind 2" or "="
     if track.trim() == "=" {
         return (Some(0), Some(0));
@@ -305,7 +308,7 @@ pub async fn git_branches(Query(q): Query<GitBranchesQuery>) -> Response {
     .into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct CreateBranchBody {
     pub name: Option<String>,
     #[serde(rename = "startPoint")]
@@ -321,7 +324,7 @@ pub async fn git_create_branch(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "create-branch").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -359,10 +362,12 @@ pub async fn git_create_branch(
     Json(serde_json::json!({"success": true, "branch": name})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct DeleteBranchBody {
     pub branch: Option<String>,
     pub force: Option<bool>,
+    #[serde(default, rename = "dryRun")]
+    pub dry_run: Option<bool>,
 }
 
 pub async fn git_delete_branch(
@@ -374,7 +379,7 @@ pub async fn git_delete_branch(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "delete-branch").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -391,6 +396,9 @@ pub async fn git_delete_branch(
             .into_response();
     };
     let branch = branch.strip_prefix("refs/heads/").unwrap_or(branch);
+    if body.dry_run.unwrap_or(false) {
+        return git_delete_branch_dry_run(&dir, branch, body.force.unwrap_or(false)).await;
+    }
     let flag = if body.force.unwrap_or(false) {
         "-D"
     } else {
@@ -414,7 +422,47 @@ pub async fn git_delete_branch(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+async fn git_delete_branch_dry_run(dir: &Path, branch: &str, force: bool) -> Response {
+    let full_ref = format!("refs/heads/{branch}");
+    let Some(tip) = rev_parse_commit(dir, &full_ref).await else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Branch not found", "code": "branch_not_found"})),
+        )
+            .into_response();
+    };
+
+    let mut preview = GitDryRunPreview::new("delete-branch");
+    // Commits only reachable from this branch become unreferenced once it is gone.
+    let exclude = format!("--exclude={branch}");
+    let (dropped, truncated) = list_commits(
+        dir,
+        &full_ref,
+        &[exclude.as_str(), "--branches", "--remotes", "--tags"],
+    )
+    .await;
+    preview.commits_dropped = dropped;
+    preview.truncated = truncated;
+    preview.move_ref(full_ref.clone(), Some(tip), None);
+
+    if git_current_branch(dir).await.as_deref() == Some(branch) {
+        preview
+            .warnings
+            .push("Cannot delete the currently checked out branch.".to_string());
+    }
+    if !force {
+        let (unmerged, _) = list_commits(dir, &full_ref, &["HEAD"]).await;
+        if !unmerged.is_empty() {
+            preview
+                .warnings
+                .push("Branch is not fully merged into HEAD; deletion requires force.".to_string());
+        }
+    }
+
+    preview.into_response()
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct RenameBranchBody {
     pub from: Option<String>,
     pub to: Option<String>,
@@ -430,7 +478,7 @@ pub async fn git_rename_branch(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "rename-branch").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -501,7 +549,7 @@ pub async fn git_rename_branch(
     Json(serde_json::json!({"success": true, "from": from, "to": to})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DeleteRemoteBranchBody {
     pub name: Option<String>,
@@ -518,7 +566,7 @@ pub async fn git_delete_remote_branch(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "delete-remote-branch").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -575,7 +623,7 @@ pub async fn git_delete_remote_branch(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct CheckoutBody {
     pub branch: Option<String>,
 }
@@ -589,7 +637,7 @@ pub async fn git_checkout(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "checkout").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -749,7 +797,7 @@ pub async fn git_tags_list(Query(q): Query<DirectoryQuery>) -> Response {
     Json(GitTagsListResponse { tags }).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitTagCreateBody {
     pub name: Option<String>,
@@ -766,7 +814,7 @@ pub async fn git_tags_create(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "tags-create").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -833,7 +881,7 @@ pub async fn git_tags_create(
     Json(serde_json::json!({"success": true, "name": name})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitTagDeleteBody {
     pub name: Option<String>,
@@ -848,7 +896,7 @@ pub async fn git_tags_delete(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "tags-delete").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -881,7 +929,7 @@ pub async fn git_tags_delete(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitTagDeleteRemoteBody {
     pub remote: Option<String>,
@@ -899,7 +947,7 @@ pub async fn git_tags_delete_remote(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "tags-delete-remote").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -925,7 +973,7 @@ pub async fn git_tags_delete_remote(
     let mut args: Vec<String> = Vec::new();
     let mut extra_env: Vec<(String, String)> = Vec::new();
     let mut _askpass: Option<TempGitAskpass> = None;
-    if let Some((u, p)) = body.auth.as_ref().and_then(normalize_http_auth) {
+    if let Some((u, p)) = resolve_http_auth(&dir, Some(remote), body.auth.as_ref()).await {
         match git_http_auth_env(&u, &p).await {
             Ok((prefix, env, guard)) => {
                 args.extend(prefix);
@@ -967,7 +1015,7 @@ pub async fn git_tags_delete_remote(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitCheckoutDetachedBody {
     pub r#ref: Option<String>,
@@ -982,7 +1030,7 @@ pub async fn git_checkout_detached(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "checkout-detached").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -1014,7 +1062,7 @@ pub async fn git_checkout_detached(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitCreateBranchFromBody {
     pub name: Option<String>,
@@ -1031,7 +1079,7 @@ pub async fn git_create_branch_from(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "create-branch-from").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
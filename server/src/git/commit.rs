@@ -7,15 +7,19 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use super::remote::git_current_branch;
 use super::{
-    DirectoryQuery, GitBranchProtectionPrompt, GitCommitSummary, git_allow_no_verify_commit,
-    git_branch_protection_for_branch, git_config_get, git_enforce_branch_protection, lock_repo,
-    map_git_failure, redact_git_output, require_directory, run_git, truncate_for_payload,
+    DirectoryQuery, GitBranchProtectionPrompt, GitCommitSummary, GitDryRunPreview,
+    git_allow_no_verify_commit, git_branch_protection_for_branch, git_config_get,
+    git_enforce_branch_protection, list_commits, list_uncommitted_tracked_paths, lock_repo,
+    map_git_failure, redact_git_output, require_directory, resolve_git_identity, rev_parse_commit,
+    run_git, run_git_env, truncate_for_payload,
 };
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitCommitBody {
     pub message: Option<String>,
     #[serde(rename = "addAll")]
@@ -35,6 +39,10 @@ pub struct GitCommitBody {
     pub allow_empty: Option<bool>,
     #[serde(default, rename = "noGpgSign")]
     pub no_gpg_sign: Option<bool>,
+    /// Identity profile to attribute the commit to; defaults to the
+    /// repository's mapped profile, then `defaultGitIdentityId`.
+    #[serde(default, rename = "identityId")]
+    pub identity_id: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -46,17 +54,19 @@ pub struct GitCommitResult {
     pub summary: GitCommitSummary,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitUndoCommitBody {
     // "soft" (default) | "mixed"
     pub mode: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitResetCommitBody {
     pub commit: Option<String>,
     pub mode: Option<String>,
+    #[serde(default, rename = "dryRun")]
+    pub dry_run: Option<bool>,
 }
 
 async fn git_path_exists(dir: &Path, name: &str) -> bool {
@@ -84,7 +94,7 @@ pub async fn git_undo_commit(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "undo-commit").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -170,7 +180,7 @@ pub async fn git_reset_commit(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "reset-commit").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -227,6 +237,10 @@ pub async fn git_reset_commit(
             .into_response();
     };
 
+    if body.dry_run.unwrap_or(false) {
+        return git_reset_dry_run(&dir, commit, &mode).await;
+    }
+
     let (code, out, err) = run_git(&dir, &["reset", flag, commit]).await.unwrap_or((
         1,
         "".to_string(),
@@ -246,6 +260,42 @@ pub async fn git_reset_commit(
     Json(serde_json::json!({"success": true, "mode": mode, "commit": commit})).into_response()
 }
 
+async fn git_reset_dry_run(dir: &Path, commit: &str, mode: &str) -> Response {
+    let Some(target) = rev_parse_commit(dir, commit).await else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Unknown commit", "code": "invalid_commit"})),
+        )
+            .into_response();
+    };
+    let head = rev_parse_commit(dir, "HEAD").await;
+
+    let mut preview = GitDryRunPreview::new("reset");
+    let (dropped, truncated) = list_commits(dir, "HEAD", &[target.as_str()]).await;
+    preview.commits_dropped = dropped;
+    preview.truncated |= truncated;
+
+    let head_ref = git_current_branch(dir)
+        .await
+        .map(|b| format!("refs/heads/{b}"))
+        .unwrap_or_else(|| "HEAD".to_string());
+    preview.move_ref(head_ref, head, Some(target));
+
+    match mode {
+        "hard" => {
+            let (discarded, truncated) = list_uncommitted_tracked_paths(dir).await;
+            preview.files_discarded = discarded;
+            preview.truncated |= truncated;
+        }
+        "mixed" => preview
+            .warnings
+            .push("Staged changes will be unstaged; working tree files are kept.".to_string()),
+        _ => {}
+    }
+
+    preview.into_response()
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GitCommitTemplateResponse {
@@ -342,7 +392,7 @@ pub async fn git_commit(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "commit").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -374,6 +424,11 @@ pub async fn git_commit(
             .into_response();
     }
 
+    let identity = match resolve_git_identity(&state, &dir, body.identity_id.as_deref()).await {
+        Ok(identity) => identity,
+        Err(resp) => return *resp,
+    };
+
     if git_enforce_branch_protection(&state).await
         && let Some(branch) = super::remote::git_current_branch(&dir).await
         && let Some(prompt_mode) = git_branch_protection_for_branch(&state, &branch).await
@@ -514,10 +569,14 @@ pub async fn git_commit(
             commit_args.push(f);
         }
     }
-    let (c, o, e) =
-        run_git(&dir, &commit_args)
-            .await
-            .unwrap_or((1, "".to_string(), "".to_string()));
+    let identity_env = identity.as_ref().map(|i| i.env());
+    let (c, o, e) = run_git_env(
+        &dir,
+        &commit_args,
+        identity_env.as_ref().map_or(&[], |env| env),
+    )
+    .await
+    .unwrap_or((1, "".to_string(), "".to_string()));
     if c != 0 {
         if let Some(resp) = map_git_failure(c, &o, &e) {
             return resp;
@@ -0,0 +1,331 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::identity::repository_root;
+use super::{DirectoryQuery, StagedChanges, require_directory, staged_changes};
+
+const SETTINGS_KEY: &str = "gitCommitMessage";
+const DEFAULT_MAX_DIFF_BYTES: usize = 48 * 1024;
+const MAX_DIFF_BYTES_LIMIT: usize = 256 * 1024;
+const MAX_HINT_CHARS: usize = 2000;
+
+/// Placeholders: `{{files}}`, `{{stat}}`, `{{diff}}`, `{{hint}}`.
+const DEFAULT_TEMPLATE: &str = "Write a git commit message for the staged changes below.
+Use the Conventional Commits format: a first line `type(scope): summary` of at most 72 characters, then, only if the change needs explaining, a blank line and a short body.
+Reply with the commit message only, without code fences or commentary.
+{{hint}}
+Changed files:
+{{files}}
+
+Diff stat:
+{{stat}}
+
+Diff:
+{{diff}}";
+
+/// Commit message generation settings (`gitCommitMessage`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CommitMessageConfig {
+    pub template: String,
+    /// `provider/model`; `None` uses OpenCode's default model.
+    pub model: Option<String>,
+    pub max_diff_bytes: usize,
+}
+
+pub(crate) fn parse_commit_message_config(value: Option<&Value>) -> CommitMessageConfig {
+    let obj = value.and_then(|v| v.as_object());
+    let field = |key: &str| {
+        obj.and_then(|o| o.get(key))
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(ToString::to_string)
+    };
+    let max_diff_bytes = obj
+        .and_then(|o| o.get("maxDiffBytes"))
+        .and_then(|v| v.as_u64())
+        .map_or(DEFAULT_MAX_DIFF_BYTES, |n| {
+            (n as usize).clamp(1024, MAX_DIFF_BYTES_LIMIT)
+        });
+    CommitMessageConfig {
+        template: field("template").unwrap_or_else(|| DEFAULT_TEMPLATE.to_string()),
+        model: field("model"),
+        max_diff_bytes,
+    }
+}
+
+fn render_prompt(template: &str, changes: &StagedChanges, hint: Option<&str>) -> String {
+    let mut diff = changes.patch.trim_end().to_string();
+    if changes.truncated {
+        diff.push_str("\n...(diff truncated)");
+    }
+    let hint = hint
+        .map(|h| format!("\nAdditional context from the author: {h}\n"))
+        .unwrap_or_default();
+    template
+        .replace("{{files}}", &changes.files.join("\n"))
+        .replace("{{stat}}", &changes.stat)
+        .replace("{{hint}}", &hint)
+        .replace("{{diff}}", &diff)
+}
+
+/// Strip wrappers models tend to add despite being asked not to.
+fn clean_reply(raw: &str) -> String {
+    let mut text = raw.trim();
+    if let Some(rest) = text.strip_prefix("```") {
+        let rest = rest.split_once('\n').map_or("", |(_, body)| body);
+        text = rest.trim_end().strip_suffix("```").unwrap_or(rest).trim();
+    }
+    text.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+fn model_payload(model: &str) -> Option<Value> {
+    let (provider, model) = model.split_once('/')?;
+    let (provider, model) = (provider.trim(), model.trim());
+    if provider.is_empty() || model.is_empty() {
+        return None;
+    }
+    Some(json!({ "providerID": provider, "modelID": model }))
+}
+
+fn message_error(status: StatusCode, code: &str, error: impl Into<String>) -> Response {
+    (status, Json(json!({"error": error.into(), "code": code}))).into_response()
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommitMessageBody {
+    /// Overrides the configured `provider/model`.
+    pub model: Option<String>,
+    /// Free-form guidance appended to the prompt (e.g. the ticket).
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommitMessageResponse {
+    pub message: String,
+    pub subject: String,
+    pub body: Option<String>,
+    pub files: usize,
+    pub diff_truncated: bool,
+    pub model: Option<String>,
+}
+
+/// Ask the connected model for a commit message describing the staged diff.
+/// The prompt runs in a throwaway OpenCode session that is deleted afterwards.
+pub async fn git_commit_message(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<DirectoryQuery>,
+    body: Option<Json<GitCommitMessageBody>>,
+) -> Response {
+    let body = body.map(|Json(b)| b).unwrap_or_default();
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let root = match repository_root(&dir).await {
+        Ok(root) => root,
+        Err(resp) => return *resp,
+    };
+
+    let config = {
+        let guard = state.settings.read().await;
+        parse_commit_message_config(guard.extra.get(SETTINGS_KEY))
+    };
+    let model = body
+        .model
+        .as_deref()
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .map(ToString::to_string)
+        .or(config.model.clone());
+    let model_value = match model.as_deref() {
+        Some(m) => match model_payload(m) {
+            Some(v) => Some(v),
+            None => {
+                return message_error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_model",
+                    "model must be in provider/model form",
+                );
+            }
+        },
+        None => None,
+    };
+
+    let changes = match staged_changes(&root, config.max_diff_bytes).await {
+        Ok(changes) => changes,
+        Err(err) => return message_error(StatusCode::CONFLICT, "git_diff_failed", err),
+    };
+    if changes.files.is_empty() {
+        return message_error(
+            StatusCode::BAD_REQUEST,
+            "nothing_staged",
+            "There are no staged changes",
+        );
+    }
+
+    let hint = body
+        .hint
+        .as_deref()
+        .map(str::trim)
+        .filter(|h| !h.is_empty())
+        .map(|h| h.chars().take(MAX_HINT_CHARS).collect::<String>());
+    let prompt = render_prompt(&config.template, &changes, hint.as_deref());
+
+    let directory = root.to_string_lossy().into_owned();
+    let upstream = state.opencode.for_directory(Some(&directory)).await;
+    let Some(bridge) = upstream.bridge().await else {
+        return message_error(
+            StatusCode::BAD_GATEWAY,
+            "opencode_unavailable",
+            "OpenCode is not running",
+        );
+    };
+    let base = bridge.base_url.trim_end_matches('/').to_string();
+    let query = format!("?directory={}", urlencoding::encode(&directory));
+
+    let session_id = match bridge
+        .client
+        .post(format!("{base}/session{query}"))
+        .json(&json!({ "title": "Commit message" }))
+        .send()
+        .await
+    {
+        Ok(resp) if resp.status().is_success() => resp
+            .json::<Value>()
+            .await
+            .ok()
+            .and_then(|v| v.get("id").and_then(|id| id.as_str()).map(str::to_string)),
+        _ => None,
+    };
+    let Some(session_id) = session_id else {
+        return message_error(
+            StatusCode::BAD_GATEWAY,
+            "opencode_session_failed",
+            "Failed to create an OpenCode session",
+        );
+    };
+    let session_url = format!("{base}/session/{}", urlencoding::encode(&session_id));
+
+    let mut payload = json!({ "parts": [{ "type": "text", "text": prompt }] });
+    if let Some(model_value) = model_value {
+        payload["model"] = model_value;
+    }
+    let reply = bridge
+        .client
+        .post(format!("{session_url}/message{query}"))
+        .json(&payload)
+        .send()
+        .await;
+
+    // The session only exists to run this prompt.
+    let _ = bridge
+        .client
+        .delete(format!("{session_url}{query}"))
+        .send()
+        .await;
+
+    let reply = match reply {
+        Ok(resp) if resp.status().is_success() => resp.json::<Value>().await.ok(),
+        Ok(resp) => {
+            let status = resp.status();
+            let detail = resp.text().await.unwrap_or_default();
+            return message_error(
+                StatusCode::BAD_GATEWAY,
+                "opencode_prompt_failed",
+                format!("OpenCode rejected the prompt ({status}): {}", detail.trim()),
+            );
+        }
+        Err(_) => {
+            return message_error(
+                StatusCode::BAD_GATEWAY,
+                "opencode_unavailable",
+                "OpenCode did not answer the prompt",
+            );
+        }
+    };
+    let text = reply
+        .as_ref()
+        .and_then(|v| v.get("parts"))
+        .and_then(|v| v.as_array())
+        .map(|parts| {
+            parts
+                .iter()
+                .filter(|p| p.get("type").and_then(|t| t.as_str()) == Some("text"))
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .map(|raw| clean_reply(&raw))
+        .unwrap_or_default();
+    if text.is_empty() {
+        return message_error(
+            StatusCode::BAD_GATEWAY,
+            "empty_reply",
+            "The model returned no commit message",
+        );
+    }
+
+    let (subject, rest) = text.split_once('\n').unwrap_or((text.as_str(), ""));
+    let rest = rest.trim();
+    Json(GitCommitMessageResponse {
+        subject: subject.trim().to_string(),
+        body: (!rest.is_empty()).then(|| rest.to_string()),
+        message: text.clone(),
+        files: changes.files.len(),
+        diff_truncated: changes.truncated,
+        model,
+    })
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_rendering_and_reply_cleanup() {
+        let changes = StagedChanges {
+            files: vec!["src/a.rs".into(), "src/b.rs".into()],
+            stat: " 2 files changed".into(),
+            patch: "diff --git a/src/a.rs b/src/a.rs\n".into(),
+            truncated: true,
+        };
+        let prompt = render_prompt("{{files}}|{{stat}}|{{hint}}|{{diff}}", &changes, None);
+        assert_eq!(
+            prompt,
+            "src/a.rs\nsrc/b.rs| 2 files changed||diff --git a/src/a.rs b/src/a.rs\n...(diff truncated)"
+        );
+
+        assert_eq!(
+            clean_reply("```text\nfeat(git): add thing  \n\nBody line\n```\n"),
+            "feat(git): add thing\n\nBody line"
+        );
+        assert_eq!(clean_reply("  fix: typo \n"), "fix: typo");
+
+        let config = parse_commit_message_config(Some(&json!({
+            "model": "anthropic/some-model",
+            "maxDiffBytes": 10,
+        })));
+        assert_eq!(config.max_diff_bytes, 1024);
+        assert_eq!(config.template, DEFAULT_TEMPLATE);
+        assert!(model_payload(config.model.as_deref().unwrap()).is_some());
+        assert!(model_payload("no-slash").is_none());
+    }
+}
@@ -0,0 +1,438 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use axum::{
+    Json,
+    extract::Query,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ConflictBlock, DirectoryQuery, apply_conflict_choices, is_safe_repo_rel_path, lock_repo,
+    map_git_failure, parse_conflict_markers, parse_unmerged_paths, require_directory, run_git,
+};
+
+const DETAILS_MAX_FILES: usize = 100;
+const DETAILS_MAX_STAGE_BYTES: usize = 512 * 1024;
+const RESOLVE_MAX_FILES: usize = 500;
+
+/// One index stage of an unmerged path: 1 = base, 2 = ours, 3 = theirs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UnmergedStage {
+    stage: u8,
+    mode: String,
+    oid: String,
+}
+
+/// `git ls-files -u` lines grouped by path.
+fn parse_unmerged_stages(out: &str) -> BTreeMap<String, Vec<UnmergedStage>> {
+    let mut by_path: BTreeMap<String, Vec<UnmergedStage>> = BTreeMap::new();
+    for line in out.lines() {
+        let Some((meta, path)) = line.split_once('\t') else {
+            continue;
+        };
+        let mut fields = meta.split_whitespace();
+        let (Some(mode), Some(oid), Some(stage)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let Ok(stage) = stage.parse::<u8>() else {
+            continue;
+        };
+        by_path
+            .entry(path.to_string())
+            .or_default()
+            .push(UnmergedStage {
+                stage,
+                mode: mode.to_string(),
+                oid: oid.to_string(),
+            });
+    }
+    for stages in by_path.values_mut() {
+        stages.sort_by_key(|s| s.stage);
+    }
+    by_path
+}
+
+/// Porcelain names for the stage combinations `git status` reports.
+fn conflict_kind(stages: &[UnmergedStage]) -> &'static str {
+    let has = |n: u8| stages.iter().any(|s| s.stage == n);
+    match (has(1), has(2), has(3)) {
+        (true, true, true) => "both_modified",
+        (false, true, true) => "both_added",
+        (true, true, false) => "deleted_by_them",
+        (true, false, true) => "deleted_by_us",
+        (false, true, false) => "added_by_us",
+        (false, false, true) => "added_by_them",
+        _ => "both_deleted",
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictVersion {
+    pub exists: bool,
+    pub oid: Option<String>,
+    pub mode: Option<String>,
+    /// Omitted for binary, oversized, or submodule entries.
+    pub content: Option<String>,
+    pub binary: bool,
+    pub too_large: bool,
+}
+
+impl GitConflictVersion {
+    fn missing() -> Self {
+        Self {
+            exists: false,
+            oid: None,
+            mode: None,
+            content: None,
+            binary: false,
+            too_large: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictDetails {
+    pub path: String,
+    /// e.g. `both_modified`, `deleted_by_them`.
+    pub kind: &'static str,
+    pub base: GitConflictVersion,
+    pub ours: GitConflictVersion,
+    pub theirs: GitConflictVersion,
+    /// Marker hunks in the worktree file, addressable by `id` in `hunks`
+    /// resolutions.
+    pub blocks: Vec<ConflictBlock>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictDetailsResponse {
+    pub files: Vec<GitConflictDetails>,
+    pub truncated: bool,
+}
+
+async fn read_stage(dir: &Path, stage: Option<&UnmergedStage>) -> GitConflictVersion {
+    let Some(stage) = stage else {
+        return GitConflictVersion::missing();
+    };
+    let mut version = GitConflictVersion {
+        exists: true,
+        oid: Some(stage.oid.clone()),
+        mode: Some(stage.mode.clone()),
+        content: None,
+        binary: false,
+        too_large: false,
+    };
+    // Gitlinks have no blob to show.
+    if stage.mode == "160000" {
+        return version;
+    }
+    let size = run_git(dir, &["cat-file", "-s", &stage.oid])
+        .await
+        .ok()
+        .filter(|(code, _, _)| *code == 0)
+        .and_then(|(_, out, _)| out.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    if size > DETAILS_MAX_STAGE_BYTES {
+        version.too_large = true;
+        return version;
+    }
+    if let Ok((0, out, _)) = run_git(dir, &["cat-file", "blob", &stage.oid]).await {
+        if out.contains('\0') {
+            version.binary = true;
+        } else {
+            version.content = Some(out);
+        }
+    }
+    version
+}
+
+/// Unmerged files with their base, ours, and theirs contents from the index.
+pub async fn git_conflicts_details(Query(q): Query<DirectoryQuery>) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let (code, out, err) =
+        run_git(&dir, &["ls-files", "-u"])
+            .await
+            .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        if let Some(resp) = map_git_failure(code, &out, &err) {
+            return resp;
+        }
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": err.trim()})),
+        )
+            .into_response();
+    }
+
+    let by_path = parse_unmerged_stages(&out);
+    let truncated = by_path.len() > DETAILS_MAX_FILES;
+    let mut files = Vec::new();
+    for (path, stages) in by_path.into_iter().take(DETAILS_MAX_FILES) {
+        let find = |n: u8| stages.iter().find(|s| s.stage == n);
+        let full = dir.join(&path);
+        let blocks = match tokio::fs::metadata(&full).await {
+            Ok(meta) if meta.is_file() && meta.len() <= DETAILS_MAX_STAGE_BYTES as u64 => {
+                tokio::fs::read_to_string(&full)
+                    .await
+                    .map(|text| parse_conflict_markers(&text))
+                    .unwrap_or_default()
+            }
+            _ => Vec::new(),
+        };
+        files.push(GitConflictDetails {
+            kind: conflict_kind(&stages),
+            base: read_stage(&dir, find(1)).await,
+            ours: read_stage(&dir, find(2)).await,
+            theirs: read_stage(&dir, find(3)).await,
+            blocks,
+            path,
+        });
+    }
+    Json(GitConflictDetailsResponse { files, truncated }).into_response()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GitConflictResolution {
+    Ours,
+    Theirs,
+    /// Write `content` as the merged result.
+    Content,
+    /// Pick a side per marker hunk via `choices`.
+    Hunks,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictHunkChoice {
+    pub id: usize,
+    /// `ours`, `theirs`, `base`, or `both`.
+    pub choice: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictFileResolution {
+    pub path: String,
+    pub resolution: GitConflictResolution,
+    pub content: Option<String>,
+    #[serde(default)]
+    pub choices: Vec<GitConflictHunkChoice>,
+    /// Choice for hunks missing from `choices` (default `ours`).
+    pub default_choice: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictsResolveBody {
+    pub files: Vec<GitConflictFileResolution>,
+    /// Stage each resolved file (default `true`).
+    pub stage: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictResolveFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictsResolveResponse {
+    pub resolved: Vec<String>,
+    pub failed: Vec<GitConflictResolveFailure>,
+    /// Paths still unmerged after this request.
+    pub remaining: Vec<String>,
+}
+
+fn normalize_choice(choice: &str) -> Option<String> {
+    let c = choice.trim().to_ascii_lowercase();
+    matches!(c.as_str(), "ours" | "theirs" | "base" | "both").then_some(c)
+}
+
+async fn git_ok(dir: &Path, args: &[&str]) -> Result<(), String> {
+    match run_git(dir, args).await {
+        Ok((0, _, _)) => Ok(()),
+        Ok((_, out, err)) => Err(Some(err.trim())
+            .filter(|e| !e.is_empty())
+            .unwrap_or(out.trim())
+            .to_string()),
+        Err(err) => Err(err),
+    }
+}
+
+async fn resolve_one(
+    dir: &Path,
+    path: &str,
+    file: &GitConflictFileResolution,
+    stages: &[UnmergedStage],
+    stage: bool,
+) -> Result<(), String> {
+    let full = dir.join(path);
+    match file.resolution {
+        GitConflictResolution::Ours | GitConflictResolution::Theirs => {
+            let (number, flag) = if file.resolution == GitConflictResolution::Ours {
+                (2, "--ours")
+            } else {
+                (3, "--theirs")
+            };
+            // The chosen side deleted the file: resolving to it means removing it.
+            if !stages.iter().any(|s| s.stage == number) {
+                return git_ok(dir, &["rm", "--quiet", "--force", "--", path]).await;
+            }
+            git_ok(dir, &["checkout", flag, "--", path]).await?;
+        }
+        GitConflictResolution::Content => {
+            let Some(content) = file.content.as_deref() else {
+                return Err("content is required".to_string());
+            };
+            tokio::fs::write(&full, content)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        GitConflictResolution::Hunks => {
+            let text = tokio::fs::read_to_string(&full)
+                .await
+                .map_err(|e| e.to_string())?;
+            if !text.contains("<<<<<<<") {
+                return Err("No conflict markers found".to_string());
+            }
+            let choices: HashMap<usize, String> = file
+                .choices
+                .iter()
+                .filter_map(|c| normalize_choice(&c.choice).map(|choice| (c.id, choice)))
+                .collect();
+            let default_choice = file
+                .default_choice
+                .as_deref()
+                .and_then(normalize_choice)
+                .unwrap_or_else(|| "ours".to_string());
+            let merged = apply_conflict_choices(&text, &choices, &default_choice);
+            tokio::fs::write(&full, merged)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    if stage {
+        git_ok(dir, &["add", "--", path]).await?;
+    }
+    Ok(())
+}
+
+/// Resolve several conflicted files at once (ours, theirs, merged content, or
+/// per-hunk choices), stage them, and report what is still unmerged.
+pub async fn git_conflicts_resolve_batch(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitConflictsResolveBody>,
+) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    if body.files.is_empty() || body.files.len() > RESOLVE_MAX_FILES {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!("files must contain 1 to {RESOLVE_MAX_FILES} entries"),
+                "code": "invalid_files",
+            })),
+        )
+            .into_response();
+    }
+    if let Some(bad) = body
+        .files
+        .iter()
+        .find(|f| f.path.trim().is_empty() || !is_safe_repo_rel_path(f.path.trim()))
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!("Invalid path: {}", bad.path),
+                "code": "invalid_path",
+            })),
+        )
+            .into_response();
+    }
+
+    let _guard = match lock_repo(&dir, "conflict-resolve").await {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+    let (code, out, err) =
+        run_git(&dir, &["ls-files", "-u"])
+            .await
+            .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        if let Some(resp) = map_git_failure(code, &out, &err) {
+            return resp;
+        }
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": err.trim()})),
+        )
+            .into_response();
+    }
+    let unmerged = parse_unmerged_stages(&out);
+    let stage = body.stage.unwrap_or(true);
+
+    let mut resolved = Vec::new();
+    let mut failed = Vec::new();
+    for file in &body.files {
+        let path = file.path.trim().to_string();
+        let Some(stages) = unmerged.get(&path) else {
+            failed.push(GitConflictResolveFailure {
+                path,
+                error: "Path is not in conflict".to_string(),
+            });
+            continue;
+        };
+        match resolve_one(&dir, &path, file, stages, stage).await {
+            Ok(()) => resolved.push(path),
+            Err(error) => failed.push(GitConflictResolveFailure { path, error }),
+        }
+    }
+
+    let remaining = match run_git(&dir, &["ls-files", "-u"]).await {
+        Ok((0, out, _)) => parse_unmerged_paths(&out),
+        _ => Vec::new(),
+    };
+    Json(GitConflictsResolveResponse {
+        resolved,
+        failed,
+        remaining,
+    })
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unmerged_stages_group_by_path_and_classify() {
+        let out = "100644 aaa 1\tsrc/lib.rs\n\
+100644 bbb 2\tsrc/lib.rs\n\
+100644 ccc 3\tsrc/lib.rs\n\
+100644 ddd 1\tREADME.md\n\
+100644 eee 3\tREADME.md\n";
+        let by_path = parse_unmerged_stages(out);
+        assert_eq!(by_path.len(), 2);
+        assert_eq!(conflict_kind(&by_path["src/lib.rs"]), "both_modified");
+        assert_eq!(conflict_kind(&by_path["README.md"]), "deleted_by_us");
+        assert_eq!(by_path["README.md"][1].oid, "eee");
+
+        assert_eq!(normalize_choice(" Theirs "), Some("theirs".to_string()));
+        assert_eq!(normalize_choice("mine"), None);
+    }
+}
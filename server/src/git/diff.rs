@@ -1,17 +1,28 @@
 #![allow(unused_imports)]
 
+mod compare_any;
 mod conflicts;
 mod file_diff;
+mod hunks;
 mod patch;
 mod stage;
 mod unified;
 
+pub use compare_any::{GitCompareAnyBody, GitDiffSide, git_compare_any};
 pub use conflicts::{
-    ConflictBlock, GitConflictFileResponse, GitConflictResolveBody, GitConflictsListResponse,
-    git_conflict_file, git_conflict_resolve, git_conflicts_list,
+    ConflictBlock, GitConflictFileResponse, GitConflictReportFile, GitConflictResolveBody,
+    GitConflictsListResponse, git_conflict_file, git_conflict_resolve, git_conflicts_list,
+};
+pub(crate) use conflicts::{
+    apply_conflict_choices, conflict_report_files, parse_conflict_markers, parse_unmerged_paths,
 };
 pub use file_diff::{GitCompareQuery, GitFileDiffQuery, git_compare, git_file_diff};
+pub use hunks::{
+    GitHunkLineSelection, GitHunksApplyBody, GitHunksQuery, git_hunks, git_stage_hunks,
+    git_unstage_hunks,
+};
 pub use patch::{GitApplyPatchBody, GitDiffQuery, git_apply_patch, git_diff};
+pub(crate) use patch::{StagedChanges, staged_changes};
 pub use stage::{
     GitCleanBody, GitDeleteBody, GitRenameBody, GitRevertBody, GitStageBody, GitUnstageBody,
     git_clean, git_delete, git_rename, git_revert, git_stage, git_unstage,
@@ -0,0 +1,459 @@
+use std::path::{Path, PathBuf};
+
+use axum::{
+    Json,
+    extract::Query,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::super::{DirectoryQuery, abs_path, is_safe_repo_rel_path, map_git_failure, run_git};
+use super::unified::{UnifiedDiffMeta, parse_unified_diff_meta};
+
+/// Per side; compare-anything is for files a person reads in an editor.
+const MAX_COMPARE_BYTES: usize = 4 * 1024 * 1024;
+const MAX_LABEL_CHARS: usize = 200;
+
+/// One side of a comparison.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum GitDiffSide {
+    /// `rev:path` from the object database.
+    Ref {
+        #[serde(rename = "ref")]
+        rev: String,
+        path: String,
+    },
+    /// The staged blob (`:path`).
+    Index { path: String },
+    /// The file on disk.
+    Worktree { path: String },
+    /// Text supplied by the client, e.g. an unsaved editor buffer.
+    Content {
+        content: String,
+        label: Option<String>,
+    },
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCompareAnyBody {
+    pub left: GitDiffSide,
+    pub right: GitDiffSide,
+    pub context_lines: Option<u32>,
+    pub ignore_whitespace: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCompareSideInfo {
+    pub label: String,
+    /// `false` when the path is absent at that ref, index, or worktree; the
+    /// side then compares as empty.
+    pub exists: bool,
+    pub binary: bool,
+    pub bytes: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCompareAnyResponse {
+    pub diff: String,
+    pub meta: UnifiedDiffMeta,
+    pub identical: bool,
+    pub left: GitCompareSideInfo,
+    pub right: GitCompareSideInfo,
+}
+
+struct ResolvedSide {
+    label: String,
+    exists: bool,
+    text: String,
+}
+
+impl ResolvedSide {
+    fn binary(&self) -> bool {
+        self.text.contains('\0')
+    }
+
+    fn info(&self) -> GitCompareSideInfo {
+        GitCompareSideInfo {
+            label: self.label.clone(),
+            exists: self.exists,
+            binary: self.binary(),
+            bytes: self.text.len(),
+        }
+    }
+}
+
+fn compare_error(status: StatusCode, code: &str, error: impl Into<String>) -> Box<Response> {
+    Box::new(
+        (
+            status,
+            Json(serde_json::json!({"error": error.into(), "code": code})),
+        )
+            .into_response(),
+    )
+}
+
+fn too_large(label: &str) -> Box<Response> {
+    compare_error(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        "compare_too_large",
+        format!("{label} is larger than {MAX_COMPARE_BYTES} bytes"),
+    )
+}
+
+fn checked_path(path: &str) -> Result<&str, Box<Response>> {
+    let path = path.trim();
+    if path.is_empty() || !is_safe_repo_rel_path(path) {
+        return Err(compare_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_path",
+            "Invalid path",
+        ));
+    }
+    Ok(path)
+}
+
+fn sanitize_label(label: &str) -> String {
+    label
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_LABEL_CHARS)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Read `spec` (`rev:path` or `:path`) from the object database; a missing
+/// path yields `None`.
+async fn read_object(root: &Path, spec: &str) -> Result<Option<String>, Box<Response>> {
+    let (code, _, _) =
+        run_git(root, &["cat-file", "-e", spec])
+            .await
+            .unwrap_or((1, String::new(), String::new()));
+    if code != 0 {
+        return Ok(None);
+    }
+    let (code, size, err) =
+        run_git(root, &["cat-file", "-s", spec])
+            .await
+            .unwrap_or((1, String::new(), String::new()));
+    if code != 0 {
+        return Err(compare_error(
+            StatusCode::CONFLICT,
+            "git_compare_failed",
+            err.trim(),
+        ));
+    }
+    if size.trim().parse::<usize>().unwrap_or(0) > MAX_COMPARE_BYTES {
+        return Err(too_large(spec));
+    }
+    let (code, out, err) =
+        run_git(root, &["cat-file", "-p", spec])
+            .await
+            .unwrap_or((1, String::new(), String::new()));
+    if code != 0 {
+        if let Some(resp) = map_git_failure(code, &out, &err) {
+            return Err(Box::new(resp));
+        }
+        return Err(compare_error(
+            StatusCode::CONFLICT,
+            "git_compare_failed",
+            err.trim(),
+        ));
+    }
+    Ok(Some(out))
+}
+
+async fn resolve_side(
+    root: Option<&Path>,
+    side: &GitDiffSide,
+) -> Result<ResolvedSide, Box<Response>> {
+    let need_root = || {
+        root.ok_or_else(|| {
+            compare_error(
+                StatusCode::BAD_REQUEST,
+                "missing_directory",
+                "directory parameter is required",
+            )
+        })
+    };
+    match side {
+        GitDiffSide::Ref { rev, path } => {
+            let rev = rev.trim();
+            if rev.is_empty() || rev.starts_with('-') || rev.contains(':') {
+                return Err(compare_error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_ref",
+                    "Invalid ref",
+                ));
+            }
+            let path = checked_path(path)?;
+            let spec = format!("{rev}:{path}");
+            let text = read_object(need_root()?, &spec).await?;
+            Ok(ResolvedSide {
+                label: spec,
+                exists: text.is_some(),
+                text: text.unwrap_or_default(),
+            })
+        }
+        GitDiffSide::Index { path } => {
+            let path = checked_path(path)?;
+            let spec = format!(":{path}");
+            let text = read_object(need_root()?, &spec).await?;
+            Ok(ResolvedSide {
+                label: spec,
+                exists: text.is_some(),
+                text: text.unwrap_or_default(),
+            })
+        }
+        GitDiffSide::Worktree { path } => {
+            let path = checked_path(path)?;
+            let full = need_root()?.join(path);
+            let text = match tokio::fs::metadata(&full).await {
+                Ok(meta) if meta.is_file() => {
+                    if meta.len() as usize > MAX_COMPARE_BYTES {
+                        return Err(too_large(path));
+                    }
+                    let bytes = tokio::fs::read(&full).await.map_err(|err| {
+                        compare_error(StatusCode::CONFLICT, "read_failed", err.to_string())
+                    })?;
+                    Some(String::from_utf8_lossy(&bytes).into_owned())
+                }
+                Ok(_) => {
+                    return Err(compare_error(
+                        StatusCode::BAD_REQUEST,
+                        "not_a_file",
+                        format!("{path} is not a file"),
+                    ));
+                }
+                Err(_) => None,
+            };
+            Ok(ResolvedSide {
+                label: path.to_string(),
+                exists: text.is_some(),
+                text: text.unwrap_or_default(),
+            })
+        }
+        GitDiffSide::Content { content, label } => {
+            if content.len() > MAX_COMPARE_BYTES {
+                return Err(too_large("content"));
+            }
+            let label = label
+                .as_deref()
+                .map(sanitize_label)
+                .filter(|l| !l.is_empty())
+                .unwrap_or_else(|| "content".to_string());
+            Ok(ResolvedSide {
+                label,
+                exists: true,
+                text: content.clone(),
+            })
+        }
+    }
+}
+
+/// `git diff --no-index` names the sides after the temporary files; put the
+/// requested labels in the header instead.
+fn relabel_diff(diff: &str, left: &str, right: &str) -> String {
+    let mut out = String::with_capacity(diff.len());
+    let mut in_header = true;
+    for line in diff.split_inclusive('\n') {
+        if line.starts_with("@@") {
+            in_header = false;
+        }
+        if in_header {
+            let ending = if line.ends_with('\n') { "\n" } else { "" };
+            if line.starts_with("diff --git ") {
+                out.push_str(&format!("diff --git a/{left} b/{right}{ending}"));
+                continue;
+            }
+            if line.starts_with("--- a/") {
+                out.push_str(&format!("--- a/{left}{ending}"));
+                continue;
+            }
+            if line.starts_with("+++ b/") {
+                out.push_str(&format!("+++ b/{right}{ending}"));
+                continue;
+            }
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+async fn diff_texts(
+    left: &ResolvedSide,
+    right: &ResolvedSide,
+    context: u32,
+    ignore_whitespace: bool,
+) -> Result<String, Box<Response>> {
+    let tmp = tempfile::TempDir::new().map_err(|err| {
+        compare_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "git_compare_failed",
+            err.to_string(),
+        )
+    })?;
+    let work: PathBuf = tmp.path().to_path_buf();
+    for (name, side) in [("left", left), ("right", right)] {
+        tokio::fs::write(work.join(name), side.text.as_bytes())
+            .await
+            .map_err(|err| {
+                compare_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "git_compare_failed",
+                    err.to_string(),
+                )
+            })?;
+    }
+
+    let context_arg = format!("-U{context}");
+    let mut args = vec![
+        "diff",
+        "--no-index",
+        "--no-color",
+        "--no-ext-diff",
+        context_arg.as_str(),
+    ];
+    if ignore_whitespace {
+        args.push("--ignore-all-space");
+    }
+    args.extend(["--", "left", "right"]);
+    let (code, out, err) = run_git(&work, &args)
+        .await
+        .unwrap_or((2, String::new(), String::new()));
+    // `--no-index` exits 1 when the files differ.
+    if code > 1 {
+        return Err(compare_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "git_compare_failed",
+            err.trim(),
+        ));
+    }
+    Ok(relabel_diff(&out, &left.label, &right.label))
+}
+
+/// Unified diff between any two of: a blob at a ref, the staged blob, a
+/// worktree file, or client-supplied text.
+pub async fn git_compare_any(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitCompareAnyBody>,
+) -> Response {
+    let root = q
+        .directory
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(abs_path);
+
+    let left = match resolve_side(root.as_deref(), &body.left).await {
+        Ok(side) => side,
+        Err(resp) => return *resp,
+    };
+    let right = match resolve_side(root.as_deref(), &body.right).await {
+        Ok(side) => side,
+        Err(resp) => return *resp,
+    };
+
+    let identical = left.text == right.text;
+    let diff = if identical || left.binary() || right.binary() {
+        String::new()
+    } else {
+        let context = body.context_lines.unwrap_or(3).min(500);
+        match diff_texts(
+            &left,
+            &right,
+            context,
+            body.ignore_whitespace.unwrap_or(false),
+        )
+        .await
+        {
+            Ok(diff) => diff,
+            Err(resp) => return *resp,
+        }
+    };
+
+    Json(GitCompareAnyResponse {
+        meta: parse_unified_diff_meta(&diff),
+        diff,
+        identical,
+        left: left.info(),
+        right: right.info(),
+    })
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn compare_any_diffs_content_against_worktree_with_labels() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        std::fs::write(tmp.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+
+        let left = resolve_side(
+            Some(tmp.path()),
+            &GitDiffSide::Worktree {
+                path: "a.txt".into(),
+            },
+        )
+        .await
+        .ok()
+        .unwrap();
+        let right = resolve_side(
+            None,
+            &GitDiffSide::Content {
+                content: "one\n2\nthree\n".into(),
+                label: Some("buffer\n".into()),
+            },
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert_eq!(right.label, "buffer");
+
+        let diff = diff_texts(&left, &right, 3, false).await.ok().unwrap();
+        assert!(diff.starts_with("diff --git a/a.txt b/buffer\n"));
+        assert!(diff.contains("--- a/a.txt\n+++ b/buffer\n"));
+        let meta = parse_unified_diff_meta(&diff);
+        assert_eq!(meta.hunks.len(), 1);
+        assert_eq!((meta.hunks[0].additions, meta.hunks[0].deletions), (1, 1));
+
+        let missing = resolve_side(
+            Some(tmp.path()),
+            &GitDiffSide::Worktree {
+                path: "nope.txt".into(),
+            },
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert!(!missing.exists);
+        assert!(
+            resolve_side(
+                Some(tmp.path()),
+                &GitDiffSide::Worktree {
+                    path: "../etc/passwd".into()
+                }
+            )
+            .await
+            .is_err()
+        );
+        assert!(
+            resolve_side(
+                None,
+                &GitDiffSide::Index {
+                    path: "a.txt".into()
+                }
+            )
+            .await
+            .is_err()
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::Path;
 
 use axum::{
     Json,
@@ -6,6 +7,7 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::super::{
@@ -42,6 +44,11 @@ pub async fn git_conflicts_list(Query(q): Query<DirectoryQuery>) -> Response {
         )
             .into_response();
     }
+    let files = parse_unmerged_paths(&out);
+    Json(GitConflictsListResponse { files }).into_response()
+}
+
+pub(crate) fn parse_unmerged_paths(out: &str) -> Vec<String> {
     let mut files: Vec<String> = Vec::new();
     for line in out.lines() {
         let t = line.trim();
@@ -59,7 +66,7 @@ pub async fn git_conflicts_list(Query(q): Query<DirectoryQuery>) -> Response {
     }
     files.sort();
     files.dedup();
-    Json(GitConflictsListResponse { files }).into_response()
+    files
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -91,7 +98,7 @@ async fn git_path_is_unmerged(dir: &std::path::Path, path: &str) -> bool {
     code == 0 && out.lines().any(|line| !line.trim().is_empty())
 }
 
-fn parse_conflict_markers(text: &str) -> Vec<ConflictBlock> {
+pub(crate) fn parse_conflict_markers(text: &str) -> Vec<ConflictBlock> {
     let mut blocks: Vec<ConflictBlock> = Vec::new();
     let mut state = 0;
     let mut ours: Vec<String> = Vec::new();
@@ -160,6 +167,49 @@ fn parse_conflict_markers(text: &str) -> Vec<ConflictBlock> {
     blocks
 }
 
+const REPORT_MAX_FILES: usize = 50;
+const REPORT_MAX_FILE_BYTES: u64 = 512 * 1024;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictReportFile {
+    pub path: String,
+    /// Empty for binary/oversized files or conflicts without text markers
+    /// (e.g. modify/delete).
+    pub blocks: Vec<ConflictBlock>,
+}
+
+/// Unmerged files with their parsed conflict hunks, for operations that stop
+/// on conflicts. Returns the files and whether the list was truncated.
+pub(crate) async fn conflict_report_files(dir: &Path) -> (Vec<GitConflictReportFile>, bool) {
+    let (code, out, _) =
+        run_git(dir, &["ls-files", "-u"])
+            .await
+            .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        return (Vec::new(), false);
+    }
+    let paths = parse_unmerged_paths(&out);
+    let truncated = paths.len() > REPORT_MAX_FILES;
+    let mut files = Vec::new();
+    for path in paths.into_iter().take(REPORT_MAX_FILES) {
+        let full = dir.join(&path);
+        let small = tokio::fs::metadata(&full)
+            .await
+            .is_ok_and(|m| m.is_file() && m.len() <= REPORT_MAX_FILE_BYTES);
+        let blocks = if small {
+            tokio::fs::read_to_string(&full)
+                .await
+                .map(|text| parse_conflict_markers(&text))
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        files.push(GitConflictReportFile { path, blocks });
+    }
+    (files, truncated)
+}
+
 pub async fn git_conflict_file(Query(q): Query<GitFileDiffQuery>) -> Response {
     // Reuse `GitFileDiffQuery` for directory+path query params.
     let dir = match require_directory_raw(q.directory.as_deref()) {
@@ -219,7 +269,7 @@ pub async fn git_conflict_file(Query(q): Query<GitFileDiffQuery>) -> Response {
     .into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitConflictResolveBody {
     pub path: Option<String>,
@@ -231,7 +281,7 @@ pub struct GitConflictResolveBody {
     pub choices: Option<Vec<serde_json::Value>>,
 }
 
-fn apply_conflict_choices(
+pub(crate) fn apply_conflict_choices(
     text: &str,
     choices: &HashMap<usize, String>,
     default_choice: &str,
@@ -303,7 +353,7 @@ pub async fn git_conflict_resolve(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "conflict-resolve").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -7,6 +7,7 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use base64::Engine as _;
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::git2_utils;
@@ -48,7 +49,7 @@ fn image_mime(path: &str) -> &'static str {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitFileDiffQuery {
     pub directory: Option<String>,
     pub path: Option<String>,
@@ -234,7 +235,7 @@ pub async fn git_file_diff(Query(q): Query<GitFileDiffQuery>) -> Response {
         .into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitCompareQuery {
     pub directory: Option<String>,
@@ -0,0 +1,485 @@
+use axum::{
+    Json,
+    extract::Query,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::super::{
+    DirectoryQuery, is_safe_repo_rel_path, lock_repo, map_git_failure, require_directory, run_git,
+    run_git_with_input,
+};
+use super::unified::{
+    UnifiedDiffHunkMeta, UnifiedDiffMeta, parse_unified_diff_meta, patch_paths_are_safe,
+    validate_unified_patch_hunks,
+};
+
+const DEFAULT_CONTEXT_LINES: u32 = 3;
+const MAX_PATCH_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitHunksQuery {
+    pub directory: Option<String>,
+    pub path: Option<String>,
+    #[serde(rename = "contextLines")]
+    pub context_lines: Option<u32>,
+}
+
+/// Lines picked inside one hunk, as indices into that hunk's `lines`.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitHunkLineSelection {
+    pub hunk: usize,
+    pub lines: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitHunksApplyBody {
+    pub path: Option<String>,
+    /// Whole hunks to apply, as 0-based indices into the current breakdown.
+    pub hunks: Option<Vec<usize>>,
+    /// Individual lines of a hunk; combined with `hunks`.
+    pub lines: Option<Vec<GitHunkLineSelection>>,
+    /// Prebuilt single-file patch, used instead of `hunks`/`lines`.
+    pub patch: Option<String>,
+    /// Context the client's hunk indices were computed with (default 3).
+    #[serde(rename = "contextLines")]
+    pub context_lines: Option<u32>,
+}
+
+/// Staged and unstaged hunks of one file, for interactive staging.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GitHunkBreakdown {
+    path: String,
+    staged: Vec<UnifiedDiffHunkMeta>,
+    unstaged: Vec<UnifiedDiffHunkMeta>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HunkAction {
+    Stage,
+    Unstage,
+}
+
+fn error_response(status: StatusCode, code: &str, error: &str) -> Response {
+    (
+        status,
+        Json(serde_json::json!({"error": error, "code": code})),
+    )
+        .into_response()
+}
+
+fn validated_path(path: Option<&str>) -> Result<String, Box<Response>> {
+    let Some(path) = path.map(|s| s.trim()).filter(|s| !s.is_empty()) else {
+        return Err(Box::new(error_response(
+            StatusCode::BAD_REQUEST,
+            "missing_path",
+            "path is required",
+        )));
+    };
+    if !is_safe_repo_rel_path(path) {
+        return Err(Box::new(error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_path",
+            "Invalid path",
+        )));
+    }
+    Ok(path.to_string())
+}
+
+async fn file_diff_meta(
+    dir: &Path,
+    path: &str,
+    cached: bool,
+    context: u32,
+) -> Result<UnifiedDiffMeta, Box<Response>> {
+    let context_arg = format!("-U{context}");
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff", &context_arg];
+    if cached {
+        args.push("--cached");
+    }
+    args.extend(["--", path]);
+    let (code, out, err) = run_git(dir, &args)
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        if let Some(resp) = map_git_failure(code, &out, &err) {
+            return Err(Box::new(resp));
+        }
+        return Err(Box::new(error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "git_diff_failed",
+            err.trim(),
+        )));
+    }
+    Ok(parse_unified_diff_meta(&out))
+}
+
+async fn hunk_breakdown(
+    dir: &Path,
+    path: &str,
+    context: u32,
+) -> Result<GitHunkBreakdown, Box<Response>> {
+    let staged = file_diff_meta(dir, path, true, context).await?;
+    let unstaged = file_diff_meta(dir, path, false, context).await?;
+    Ok(GitHunkBreakdown {
+        path: path.to_string(),
+        staged: staged.hunks,
+        unstaged: unstaged.hunks,
+    })
+}
+
+/// Rebuild a hunk keeping only `selected` change lines. Unselected lines
+/// that exist on the side the patch applies to become context, the others
+/// are dropped: for staging that side is the old one (`-` lines stay), for
+/// a reversed unstage it is the new one (`+` lines stay).
+/// Returns the body lines with their old/new line counts, or `None` when
+/// nothing in the hunk is selected.
+fn select_hunk_lines(
+    lines: &[String],
+    selected: &dyn Fn(usize) -> bool,
+    action: HunkAction,
+) -> Option<(Vec<String>, usize, usize)> {
+    let mut out = Vec::with_capacity(lines.len());
+    let (mut old_count, mut new_count) = (0usize, 0usize);
+    let mut changed = false;
+    let mut kept_previous = false;
+    for (i, line) in lines.iter().enumerate() {
+        if line.starts_with('\\') {
+            if kept_previous {
+                out.push(line.clone());
+            }
+            continue;
+        }
+        let (prefix, rest) = line.split_at(line.len().min(1));
+        let keep = match prefix {
+            "+" | "-" if selected(i) => {
+                changed = true;
+                Some(prefix)
+            }
+            "-" if action == HunkAction::Stage => Some(" "),
+            "+" if action == HunkAction::Unstage => Some(" "),
+            "+" | "-" => None,
+            _ => Some(" "),
+        };
+        kept_previous = keep.is_some();
+        let Some(prefix) = keep else {
+            continue;
+        };
+        match prefix {
+            "+" => new_count += 1,
+            "-" => old_count += 1,
+            _ => {
+                old_count += 1;
+                new_count += 1;
+            }
+        }
+        out.push(format!("{prefix}{rest}"));
+    }
+    changed.then_some((out, old_count, new_count))
+}
+
+/// Start of the side the patch produces. An empty side names the line
+/// before the hunk, so moving between empty and non-empty shifts it by one.
+fn derived_start(base_start: usize, base_count: usize, count: usize, shift: isize) -> usize {
+    let start = base_start.saturating_add_signed(shift);
+    match (base_count, count) {
+        (0, n) if n > 0 => start + 1,
+        (b, 0) if b > 0 => start.saturating_sub(1),
+        _ => start,
+    }
+}
+
+fn format_side(start: usize, count: usize) -> String {
+    if count == 1 {
+        start.to_string()
+    } else {
+        format!("{start},{count}")
+    }
+}
+
+/// Build a patch from whole hunks and line selections of `meta`. Hunk
+/// start lines are shifted by what the earlier selected hunks add or drop,
+/// on the side opposite to the one the patch applies to.
+fn build_selection_patch(
+    meta: &UnifiedDiffMeta,
+    hunks: &[usize],
+    lines: &[GitHunkLineSelection],
+    action: HunkAction,
+) -> Result<String, &'static str> {
+    if hunks
+        .iter()
+        .chain(lines.iter().map(|sel| &sel.hunk))
+        .any(|&i| i >= meta.hunks.len())
+    {
+        return Err("stale_selection");
+    }
+
+    let mut patch = String::new();
+    for line in &meta.file_header {
+        patch.push_str(line);
+        patch.push('\n');
+    }
+    let mut shift: isize = 0;
+    let mut any = false;
+    for (index, hunk) in meta.hunks.iter().enumerate() {
+        let whole = hunks.contains(&index);
+        let picked: Vec<usize> = lines
+            .iter()
+            .filter(|sel| sel.hunk == index)
+            .flat_map(|sel| sel.lines.iter().copied())
+            .collect();
+        if !whole && picked.is_empty() {
+            continue;
+        }
+        let selected = |i: usize| whole || picked.contains(&i);
+        let Some((body, old_count, new_count)) = select_hunk_lines(&hunk.lines, &selected, action)
+        else {
+            continue;
+        };
+        any = true;
+
+        let (old_start, new_start) = match action {
+            HunkAction::Stage => (
+                hunk.old_start,
+                derived_start(hunk.old_start, old_count, new_count, shift),
+            ),
+            HunkAction::Unstage => (
+                derived_start(hunk.new_start, new_count, old_count, shift),
+                hunk.new_start,
+            ),
+        };
+        shift += match action {
+            HunkAction::Stage => new_count as isize - old_count as isize,
+            HunkAction::Unstage => old_count as isize - new_count as isize,
+        };
+
+        patch.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            format_side(old_start, old_count),
+            format_side(new_start, new_count)
+        ));
+        for line in body {
+            patch.push_str(&line);
+            patch.push('\n');
+        }
+    }
+    if !any {
+        return Err("empty_selection");
+    }
+    Ok(patch)
+}
+
+fn context_lines(value: Option<u32>) -> u32 {
+    value.unwrap_or(DEFAULT_CONTEXT_LINES).min(500)
+}
+
+/// Staged and unstaged hunks of a file, each with its own patch.
+pub async fn git_hunks(Query(q): Query<GitHunksQuery>) -> Response {
+    let dir = match require_directory(&DirectoryQuery {
+        directory: q.directory.clone(),
+    }) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let path = match validated_path(q.path.as_deref()) {
+        Ok(p) => p,
+        Err(resp) => return *resp,
+    };
+    match hunk_breakdown(&dir, &path, context_lines(q.context_lines)).await {
+        Ok(breakdown) => Json(breakdown).into_response(),
+        Err(resp) => *resp,
+    }
+}
+
+async fn apply_hunks(q: DirectoryQuery, body: GitHunksApplyBody, action: HunkAction) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let _guard = match lock_repo(
+        &dir,
+        match action {
+            HunkAction::Stage => "stage-hunks",
+            HunkAction::Unstage => "unstage-hunks",
+        },
+    )
+    .await
+    {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+    let path = match validated_path(body.path.as_deref()) {
+        Ok(p) => p,
+        Err(resp) => return *resp,
+    };
+    let context = context_lines(body.context_lines);
+
+    let patch = if let Some(raw) = body.patch.as_deref().filter(|p| !p.trim().is_empty()) {
+        if raw.len() > MAX_PATCH_BYTES {
+            return error_response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "patch_too_large",
+                "Patch too large",
+            );
+        }
+        let single_file = validate_unified_patch_hunks(raw).is_ok_and(|s| s.files == 1);
+        let meta = parse_unified_diff_meta(raw);
+        let targets_path = meta.file_header.iter().any(|line| {
+            line.strip_prefix("+++ b/")
+                .or_else(|| line.strip_prefix("--- a/"))
+                .is_some_and(|p| p == path)
+        });
+        if !single_file || !targets_path || !patch_paths_are_safe(raw) {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_patch",
+                "Patch must be a valid unified diff of the requested file",
+            );
+        }
+        if raw.ends_with('\n') {
+            raw.to_string()
+        } else {
+            format!("{raw}\n")
+        }
+    } else {
+        let hunks = body.hunks.unwrap_or_default();
+        let lines = body.lines.unwrap_or_default();
+        if hunks.is_empty() && lines.is_empty() {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                "empty_selection",
+                "Select at least one hunk or line",
+            );
+        }
+        let meta = match file_diff_meta(&dir, &path, action == HunkAction::Unstage, context).await {
+            Ok(m) => m,
+            Err(resp) => return *resp,
+        };
+        match build_selection_patch(&meta, &hunks, &lines, action) {
+            Ok(p) => p,
+            Err("stale_selection") => {
+                return error_response(
+                    StatusCode::CONFLICT,
+                    "stale_selection",
+                    "The selected hunks no longer match the file; refresh and retry",
+                );
+            }
+            Err(code) => {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    code,
+                    "Selection contains no changed lines",
+                );
+            }
+        }
+    };
+
+    let mut args = vec!["apply", "--cached", "--whitespace=nowarn"];
+    if action == HunkAction::Unstage {
+        args.push("--reverse");
+    }
+    let (code, out, err) = run_git_with_input(&dir, &args, &patch).await.unwrap_or((
+        1,
+        "".to_string(),
+        "".to_string(),
+    ));
+    if code != 0 {
+        if let Some(resp) = map_git_failure(code, &out, &err) {
+            return resp;
+        }
+        return error_response(StatusCode::CONFLICT, "git_apply_failed", err.trim());
+    }
+
+    match hunk_breakdown(&dir, &path, context).await {
+        Ok(breakdown) => Json(breakdown).into_response(),
+        Err(resp) => *resp,
+    }
+}
+
+/// Stage selected hunks or lines of a file's worktree changes.
+pub async fn git_stage_hunks(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitHunksApplyBody>,
+) -> Response {
+    apply_hunks(q, body, HunkAction::Stage).await
+}
+
+/// Unstage selected hunks or lines of a file's staged changes.
+pub async fn git_unstage_hunks(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitHunksApplyBody>,
+) -> Response {
+    apply_hunks(q, body, HunkAction::Unstage).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str =
+        "diff --git a/a.txt b/a.txt\nindex 1111111..2222222 100644\n--- a/a.txt\n+++ b/a.txt\n";
+
+    fn meta(hunks: &str) -> UnifiedDiffMeta {
+        parse_unified_diff_meta(&format!("{HEADER}{hunks}"))
+    }
+
+    #[test]
+    fn staging_selected_lines_keeps_unselected_removals_as_context() {
+        let meta = meta("@@ -1,3 +1,3 @@\n keep\n-old1\n-old2\n+new1\n+new2\n");
+        let selection = [GitHunkLineSelection {
+            hunk: 0,
+            lines: vec![1, 3],
+        }];
+        let patch = build_selection_patch(&meta, &[], &selection, HunkAction::Stage).unwrap();
+        assert_eq!(
+            patch,
+            format!("{HEADER}@@ -1,3 +1,3 @@\n keep\n-old1\n old2\n+new1\n")
+        );
+        assert!(validate_unified_patch_hunks(&patch).is_ok());
+    }
+
+    #[test]
+    fn unstaging_selected_lines_keeps_unselected_additions_as_context() {
+        let meta = meta("@@ -1,2 +1,3 @@\n keep\n-old\n+new1\n+new2\n");
+        let selection = [GitHunkLineSelection {
+            hunk: 0,
+            lines: vec![2],
+        }];
+        let patch = build_selection_patch(&meta, &[], &selection, HunkAction::Unstage).unwrap();
+        assert_eq!(
+            patch,
+            format!("{HEADER}@@ -1,2 +1,3 @@\n keep\n+new1\n new2\n")
+        );
+        assert!(validate_unified_patch_hunks(&patch).is_ok());
+    }
+
+    #[test]
+    fn later_hunks_shift_by_earlier_selected_changes() {
+        let meta = meta("@@ -1,2 +1,3 @@\n a\n+b\n c\n@@ -10,2 +11,2 @@\n x\n-y\n+z\n");
+        let patch = build_selection_patch(&meta, &[1], &[], HunkAction::Stage).unwrap();
+        assert!(patch.contains("@@ -10,2 +10,2 @@\n"));
+        let patch = build_selection_patch(&meta, &[0, 1], &[], HunkAction::Stage).unwrap();
+        assert!(patch.contains("@@ -10,2 +11,2 @@\n"));
+    }
+
+    #[test]
+    fn rejects_stale_or_empty_selections() {
+        let meta = meta("@@ -1,2 +1,2 @@\n a\n-b\n+c\n");
+        assert_eq!(
+            build_selection_patch(&meta, &[3], &[], HunkAction::Stage),
+            Err("stale_selection")
+        );
+        let context_only = [GitHunkLineSelection {
+            hunk: 0,
+            lines: vec![0],
+        }];
+        assert_eq!(
+            build_selection_patch(&meta, &[], &context_only, HunkAction::Stage),
+            Err("empty_selection")
+        );
+    }
+}
@@ -4,7 +4,9 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::Deserialize;
+use std::path::Path;
 use std::sync::Arc;
 
 use super::super::{
@@ -15,7 +17,7 @@ use super::unified::{
     PatchSummary, parse_unified_diff_meta, patch_paths_are_safe, validate_unified_patch_hunks,
 };
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitDiffQuery {
     pub directory: Option<String>,
     pub path: Option<String>,
@@ -26,6 +28,57 @@ pub struct GitDiffQuery {
     pub include_meta: Option<String>,
 }
 
+/// Staged changes of a repository, for consumers that need the whole index
+/// diff at once (e.g. commit message generation).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StagedChanges {
+    pub files: Vec<String>,
+    pub stat: String,
+    pub patch: String,
+    /// `patch` was cut to the requested size.
+    pub truncated: bool,
+}
+
+pub(crate) async fn staged_changes(
+    dir: &Path,
+    max_patch_bytes: usize,
+) -> Result<StagedChanges, String> {
+    let run = |args: &'static [&'static str]| async move {
+        let (code, out, err) = run_git(dir, args).await?;
+        if code != 0 {
+            return Err(err.trim().to_string());
+        }
+        Ok(out)
+    };
+
+    let files: Vec<String> = run(&["diff", "--cached", "--name-only", "-z"])
+        .await?
+        .split('\0')
+        .filter(|p| !p.is_empty())
+        .map(ToString::to_string)
+        .collect();
+    if files.is_empty() {
+        return Ok(StagedChanges::default());
+    }
+    let stat = run(&["diff", "--cached", "--no-color", "--stat=120"]).await?;
+    let mut patch = run(&["diff", "--cached", "--no-color", "--no-ext-diff"]).await?;
+
+    let truncated = patch.len() > max_patch_bytes;
+    if truncated {
+        let mut end = max_patch_bytes;
+        while end > 0 && !patch.is_char_boundary(end) {
+            end -= 1;
+        }
+        patch.truncate(end);
+    }
+    Ok(StagedChanges {
+        files,
+        stat: stat.trim_end().to_string(),
+        patch,
+        truncated,
+    })
+}
+
 pub async fn git_diff(Query(q): Query<GitDiffQuery>) -> Response {
     let Some(dir_raw) = q.directory.as_deref() else {
         return (
@@ -142,7 +195,7 @@ pub async fn git_diff(Query(q): Query<GitDiffQuery>) -> Response {
     Json(serde_json::json!({"diff": out})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitApplyPatchBody {
     pub patch: Option<String>,
     pub mode: Option<String>,
@@ -291,7 +344,7 @@ pub async fn git_apply_patch(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "apply-patch").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -4,14 +4,16 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use super::super::{
-    DirectoryQuery, abs_path, is_safe_repo_rel_path, lock_repo, map_git_failure, require_directory,
-    run_git,
+    DirectoryQuery, GitDryRunPreview, abs_path, is_safe_repo_rel_path,
+    list_uncommitted_tracked_paths, lock_repo, map_git_failure, parse_clean_dry_run_output,
+    require_directory, run_git,
 };
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitRevertBody {
     pub path: Option<String>,
 }
@@ -25,7 +27,7 @@ pub async fn git_revert(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "revert").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -80,7 +82,7 @@ pub async fn git_revert(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitStageBody {
     pub path: Option<String>,
     pub paths: Option<Vec<String>>,
@@ -98,7 +100,7 @@ pub async fn git_stage(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "stage").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -324,7 +326,7 @@ pub async fn git_stage(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitUnstageBody {
     pub path: Option<String>,
     pub paths: Option<Vec<String>>,
@@ -340,7 +342,7 @@ pub async fn git_unstage(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "unstage").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -434,12 +436,13 @@ pub async fn git_unstage(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitCleanBody {
     // "untracked" (default) | "all" | "tracked"
     pub scope: Option<String>,
     pub paths: Option<Vec<String>>,
+    pub dry_run: Option<bool>,
 }
 
 pub async fn git_clean(
@@ -451,7 +454,7 @@ pub async fn git_clean(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "clean").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -479,7 +482,16 @@ pub async fn git_clean(
             .into_response();
     }
 
+    let dry_run = body.dry_run.unwrap_or(false);
+
     if scope == "tracked" {
+        if dry_run {
+            let mut preview = GitDryRunPreview::new("clean");
+            let (discarded, truncated) = list_uncommitted_tracked_paths(&dir).await;
+            preview.files_discarded = discarded;
+            preview.truncated = truncated;
+            return preview.into_response();
+        }
         // Discard all tracked changes (index + worktree) without touching untracked.
         // Prefer `git restore` and fall back to older git commands.
         let (c1, _o1, _e1) = run_git(&dir, &["restore", "--staged", "--worktree", "--", "."])
@@ -506,7 +518,7 @@ pub async fn git_clean(
         return Json(serde_json::json!({"success": true})).into_response();
     }
 
-    let mut args: Vec<&str> = vec!["clean", "-f", "-d"];
+    let mut args: Vec<&str> = vec!["clean", if dry_run { "-n" } else { "-f" }, "-d"];
     if scope == "all" {
         args.push("-x");
     } else if scope != "untracked" {
@@ -538,10 +550,16 @@ pub async fn git_clean(
             .into_response();
     }
 
+    if dry_run {
+        let mut preview = GitDryRunPreview::new("clean");
+        preview.files_removed = parse_clean_dry_run_output(&out);
+        return preview.into_response();
+    }
+
     Json(serde_json::json!({"success": true, "output": out.trim()})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitRenameBody {
     pub from: String,
@@ -557,7 +575,7 @@ pub async fn git_rename(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "rename").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -601,7 +619,7 @@ pub async fn git_rename(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitDeleteBody {
     pub path: String,
@@ -617,7 +635,7 @@ pub async fn git_delete(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "delete").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
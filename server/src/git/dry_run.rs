@@ -0,0 +1,292 @@
+use std::path::Path;
+
+use axum::{
+    Json,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use super::run_git;
+
+// Keep previews bounded for long-lived branches.
+const DRY_RUN_MAX_COMMITS: usize = 200;
+const DRY_RUN_MAX_FILES: usize = 1000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDryRunCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub subject: String,
+    pub author: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDryRunRefMove {
+    #[serde(rename = "ref")]
+    pub r#ref: String,
+    pub from: Option<String>,
+    // None when the ref is deleted, or when the new target is only known after
+    // commits are replayed (rebase).
+    pub to: Option<String>,
+}
+
+/// Shared response payload for `dryRun: true` on destructive git endpoints.
+///
+/// Handlers run their usual validation, then describe the effect instead of
+/// executing it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDryRunPreview {
+    pub dry_run: bool,
+    pub operation: &'static str,
+    pub summary: String,
+    pub commits_dropped: Vec<GitDryRunCommit>,
+    pub commits_rewritten: Vec<GitDryRunCommit>,
+    pub files_removed: Vec<String>,
+    pub files_discarded: Vec<String>,
+    pub refs_moved: Vec<GitDryRunRefMove>,
+    pub warnings: Vec<String>,
+    pub truncated: bool,
+}
+
+impl GitDryRunPreview {
+    pub(crate) fn new(operation: &'static str) -> Self {
+        Self {
+            dry_run: true,
+            operation,
+            summary: String::new(),
+            commits_dropped: Vec::new(),
+            commits_rewritten: Vec::new(),
+            files_removed: Vec::new(),
+            files_discarded: Vec::new(),
+            refs_moved: Vec::new(),
+            warnings: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    pub(crate) fn move_ref(
+        &mut self,
+        r#ref: impl Into<String>,
+        from: Option<String>,
+        to: Option<String>,
+    ) {
+        if from.is_some() && from == to {
+            return;
+        }
+        self.refs_moved.push(GitDryRunRefMove {
+            r#ref: r#ref.into(),
+            from,
+            to,
+        });
+    }
+
+    fn build_summary(&self) -> String {
+        fn plural(n: usize, one: &str, many: &str) -> String {
+            format!("{n} {}", if n == 1 { one } else { many })
+        }
+
+        let mut parts = Vec::<String>::new();
+        if !self.commits_dropped.is_empty() {
+            parts.push(format!(
+                "drop {}",
+                plural(self.commits_dropped.len(), "commit", "commits")
+            ));
+        }
+        if !self.commits_rewritten.is_empty() {
+            parts.push(format!(
+                "rewrite {}",
+                plural(self.commits_rewritten.len(), "commit", "commits")
+            ));
+        }
+        if !self.files_removed.is_empty() {
+            parts.push(format!(
+                "remove {}",
+                plural(self.files_removed.len(), "file", "files")
+            ));
+        }
+        if !self.files_discarded.is_empty() {
+            parts.push(format!(
+                "discard changes in {}",
+                plural(self.files_discarded.len(), "file", "files")
+            ));
+        }
+        if !self.refs_moved.is_empty() {
+            parts.push(format!(
+                "move {}",
+                plural(self.refs_moved.len(), "ref", "refs")
+            ));
+        }
+
+        if parts.is_empty() {
+            return format!("{} would make no changes", self.operation);
+        }
+        format!("{} would {}", self.operation, parts.join(", "))
+    }
+
+    pub(crate) fn into_response(mut self) -> Response {
+        self.summary = self.build_summary();
+        Json(self).into_response()
+    }
+}
+
+pub(crate) async fn rev_parse_commit(dir: &Path, rev: &str) -> Option<String> {
+    let spec = format!("{rev}^{{commit}}");
+    let (code, out, _) = run_git(dir, &["rev-parse", "--verify", "--quiet", &spec])
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        return None;
+    }
+    let sha = out.trim();
+    if sha.is_empty() {
+        None
+    } else {
+        Some(sha.to_string())
+    }
+}
+
+/// Commits reachable from `include` but not from any of `exclude` (newest first).
+pub(crate) async fn list_commits(
+    dir: &Path,
+    include: &str,
+    exclude: &[&str],
+) -> (Vec<GitDryRunCommit>, bool) {
+    let max_count = format!("--max-count={}", DRY_RUN_MAX_COMMITS + 1);
+    let mut args: Vec<&str> = vec![
+        "log",
+        "--format=%H%x1f%h%x1f%s%x1f%an%x1e",
+        &max_count,
+        include,
+    ];
+    if !exclude.is_empty() {
+        args.push("--not");
+        args.extend_from_slice(exclude);
+    }
+    args.push("--");
+
+    let (code, out, _) = run_git(dir, &args)
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        return (Vec::new(), false);
+    }
+
+    let mut commits = parse_commit_records(&out);
+    let truncated = commits.len() > DRY_RUN_MAX_COMMITS;
+    commits.truncate(DRY_RUN_MAX_COMMITS);
+    (commits, truncated)
+}
+
+pub(super) fn parse_commit_records(out: &str) -> Vec<GitDryRunCommit> {
+    out.split('\x1e')
+        .filter_map(|record| {
+            let record = record.trim_matches(|c| c == '\n' || c == '\r');
+            if record.is_empty() {
+                return None;
+            }
+            let mut fields = record.split('\x1f');
+            let hash = fields.next()?.trim().to_string();
+            if hash.is_empty() {
+                return None;
+            }
+            Some(GitDryRunCommit {
+                hash,
+                short_hash: fields.next().unwrap_or("").trim().to_string(),
+                subject: fields.next().unwrap_or("").to_string(),
+                author: fields.next().unwrap_or("").to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Run a path-listing git command (`diff --name-only`, ...) and collect the
+/// NUL-separated output.
+async fn list_paths(dir: &Path, args: &[&str]) -> (Vec<String>, bool) {
+    let (code, out, _) = run_git(dir, args)
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        return (Vec::new(), false);
+    }
+    let mut paths = out
+        .split(['\0', '\n'])
+        .map(|p| p.trim_end_matches('\r'))
+        .filter(|p| !p.is_empty())
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+    let truncated = paths.len() > DRY_RUN_MAX_FILES;
+    paths.truncate(DRY_RUN_MAX_FILES);
+    (paths, truncated)
+}
+
+/// Tracked files whose uncommitted (staged or unstaged) changes would be lost.
+pub(crate) async fn list_uncommitted_tracked_paths(dir: &Path) -> (Vec<String>, bool) {
+    list_paths(dir, &["diff", "--name-only", "-z", "HEAD", "--"]).await
+}
+
+/// Parse `git clean -n` output ("Would remove <path>").
+pub(crate) fn parse_clean_dry_run_output(out: &str) -> Vec<String> {
+    let mut paths = out
+        .lines()
+        .filter_map(|line| line.trim_end().strip_prefix("Would remove "))
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_commit_records_reads_unit_separated_fields() {
+        let out = "aaa\x1fa\x1ffirst subject\x1fAlice\x1e\nbbb\x1fb\x1fsecond\x1fBob\x1e\n";
+        let commits = parse_commit_records(out);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].hash, "aaa");
+        assert_eq!(commits[0].subject, "first subject");
+        assert_eq!(commits[1].author, "Bob");
+    }
+
+    #[test]
+    fn parse_clean_dry_run_output_extracts_paths() {
+        let out = "Would remove build/\nWould remove notes.txt\n";
+        assert_eq!(
+            parse_clean_dry_run_output(out),
+            vec!["build/".to_string(), "notes.txt".to_string()]
+        );
+    }
+
+    #[test]
+    fn summary_lists_effects_in_stable_order() {
+        let mut preview = GitDryRunPreview::new("reset");
+        preview.commits_dropped.push(GitDryRunCommit {
+            hash: "a".to_string(),
+            short_hash: "a".to_string(),
+            subject: "s".to_string(),
+            author: "x".to_string(),
+        });
+        preview.move_ref(
+            "refs/heads/main",
+            Some("a".to_string()),
+            Some("b".to_string()),
+        );
+        preview.move_ref("HEAD", Some("b".to_string()), Some("b".to_string()));
+        assert_eq!(preview.refs_moved.len(), 1);
+        assert_eq!(
+            preview.build_summary(),
+            "reset would drop 1 commit, move 1 ref"
+        );
+        assert_eq!(
+            GitDryRunPreview::new("clean").build_summary(),
+            "clean would make no changes"
+        );
+    }
+}
@@ -1,16 +1,8 @@
 use std::path::Path;
 use std::process::Stdio;
-use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
 
-use axum::{
-    Json,
-    http::StatusCode,
-    response::{IntoResponse, Response},
-};
-use dashmap::DashMap;
 use tokio::process::Command;
-use tokio::sync::Mutex;
 
 const DEFAULT_GIT_TIMEOUT: Duration = Duration::from_secs(60);
 
@@ -118,39 +110,6 @@ fn git_timeout() -> Duration {
     DEFAULT_GIT_TIMEOUT
 }
 
-// VS Code queues git operations per repository. Do the same server-side so we don't
-// race on the index/worktree (and to reduce index.lock errors under rapid UI clicks).
-static REPO_LOCKS: OnceLock<DashMap<String, Arc<Mutex<()>>>> = OnceLock::new();
-
-fn repo_lock_key(dir: &Path) -> String {
-    dir.to_string_lossy().to_string()
-}
-
-pub(crate) async fn lock_repo(dir: &Path) -> Result<tokio::sync::OwnedMutexGuard<()>, Response> {
-    let key = repo_lock_key(dir);
-    let locks = REPO_LOCKS.get_or_init(DashMap::new);
-    let m = if let Some(v) = locks.get(&key) {
-        v.value().clone()
-    } else {
-        let v = Arc::new(Mutex::new(()));
-        locks.insert(key.clone(), v.clone());
-        v
-    };
-
-    match tokio::time::timeout(Duration::from_secs(10), m.clone().lock_owned()).await {
-        Ok(g) => Ok(g),
-        Err(_) => Err((
-            StatusCode::CONFLICT,
-            Json(serde_json::json!({
-                "error": "Repository is busy running another git operation",
-                "code": "git_busy",
-                "hint": "Wait for the current operation to finish, then retry.",
-            })),
-        )
-            .into_response()),
-    }
-}
-
 pub(crate) async fn run_git_env(
     directory: &Path,
     args: &[&str],
@@ -344,6 +303,60 @@ pub(crate) async fn run_git_input(
     Ok((code, stdout_text, stderr_text))
 }
 
+/// Spawn git with piped stdout/stderr for callers that consume output as it
+/// arrives (e.g. clone progress). No timeout applies; the child is killed
+/// when dropped.
+pub(crate) fn spawn_git_piped(
+    directory: &Path,
+    args: &[&str],
+    extra_env: &[(&str, &str)],
+) -> Result<tokio::process::Child, String> {
+    spawn_git_piped_stdin(directory, args, extra_env, Stdio::null())
+}
+
+/// [`spawn_git_piped`] reading `stdin`, e.g. another git process's stdout.
+pub(crate) fn spawn_git_piped_stdin(
+    directory: &Path,
+    args: &[&str],
+    extra_env: &[(&str, &str)],
+    stdin: Stdio,
+) -> Result<tokio::process::Child, String> {
+    let mut cmd = Command::new("git");
+    cmd.args(args)
+        .current_dir(directory)
+        // Prevent hanging on interactive credential prompts.
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GCM_INTERACTIVE", "Never")
+        // Prevent spawning an interactive editor in server mode.
+        .env("GIT_EDITOR", "true")
+        .env("EDITOR", "true")
+        .env("GPG_TTY", "")
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    #[cfg(unix)]
+    {
+        use std::io;
+
+        unsafe {
+            cmd.pre_exec(|| {
+                let rc = libc::setsid();
+                if rc == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    for (k, v) in extra_env {
+        cmd.env(k, v);
+    }
+
+    cmd.spawn().map_err(|e| e.to_string())
+}
+
 pub(crate) async fn run_git_with_input(
     directory: &Path,
     args: &[&str],
@@ -358,3 +371,11 @@ pub(crate) async fn run_git(
 ) -> Result<(i32, String, String), String> {
     run_git_env(directory, args, &[]).await
 }
+
+/// Stdout of a git command that exited 0; `None` on any failure.
+pub(crate) async fn git_stdout(directory: &Path, args: &[&str]) -> Option<String> {
+    match run_git(directory, args).await {
+        Ok((0, out, _)) => Some(out),
+        _ => None,
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::process::Stdio;
 
@@ -7,7 +8,8 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use tokio::process::Command;
 
 use super::{DirectoryQuery, map_git_failure, require_directory, run_git};
@@ -224,7 +226,7 @@ pub async fn git_gpg_disable_signing(Query(q): Query<DirectoryQuery>) -> Respons
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitGpgSetSigningKeyBody {
     pub signing_key: Option<String>,
@@ -271,6 +273,191 @@ pub async fn git_gpg_set_signing_key(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
+const MAX_VERIFY_COMMITS: usize = 200;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSignaturesBody {
+    /// Commits to verify, typically the visible history page. HEAD is
+    /// always reported separately.
+    #[serde(default)]
+    pub commits: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSignatureInfo {
+    /// `good`, `bad`, `untrusted`, `expired`, `expiredKey`, `revoked`,
+    /// `unknownKey` or `unsigned`.
+    pub status: &'static str,
+    /// Good signature, regardless of key trust or expiry.
+    pub verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trust: Option<String>,
+}
+
+/// Map git's `%G?` letter to a status name.
+fn signature_status(code: &str) -> &'static str {
+    match code {
+        "G" => "good",
+        "B" => "bad",
+        "U" => "untrusted",
+        "X" => "expired",
+        "Y" => "expiredKey",
+        "R" => "revoked",
+        "E" => "unknownKey",
+        _ => "unsigned",
+    }
+}
+
+fn parse_signature_records(out: &str) -> Vec<(String, GitSignatureInfo)> {
+    let field = |s: &str| Some(s.trim()).filter(|s| !s.is_empty()).map(str::to_string);
+    out.split('\x1e')
+        .map(|r| r.trim_matches(['\n', '\r']))
+        .filter(|r| !r.is_empty())
+        .filter_map(|record| {
+            let fields: Vec<&str> = record.split('\x1f').collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            let status = signature_status(fields[1].trim());
+            let unsigned = status == "unsigned";
+            Some((
+                fields[0].to_string(),
+                GitSignatureInfo {
+                    status,
+                    verified: matches!(status, "good" | "untrusted" | "expired" | "expiredKey"),
+                    signer: field(fields[2]),
+                    key: field(fields[3]),
+                    fingerprint: field(fields[4]),
+                    trust: field(fields[5]).filter(|_| !unsigned),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Signature status of the requested commits and of HEAD, using the
+/// repository's configured gpg/ssh verification.
+pub async fn git_signatures(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitSignaturesBody>,
+) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let commits: Vec<&str> = body
+        .commits
+        .iter()
+        .map(|c| c.trim())
+        .filter(|c| !c.is_empty())
+        .collect();
+    if commits.len() > MAX_VERIFY_COMMITS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!("At most {MAX_VERIFY_COMMITS} commits can be verified at once"),
+                "code": "too_many_commits",
+            })),
+        )
+            .into_response();
+    }
+    if commits.iter().any(|c| c.starts_with('-')) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid commit", "code": "invalid_ref"})),
+        )
+            .into_response();
+    }
+
+    // Resolve everything up front so abbreviated hashes and refs map back
+    // to the spelling the caller used.
+    let mut resolved: Vec<String> = Vec::with_capacity(commits.len());
+    if !commits.is_empty() {
+        let specs: Vec<String> = commits.iter().map(|c| format!("{c}^{{commit}}")).collect();
+        let mut args = vec!["rev-parse"];
+        args.extend(specs.iter().map(String::as_str));
+        args.push("--");
+        let (code, out, err) =
+            run_git(&dir, &args)
+                .await
+                .unwrap_or((1, "".to_string(), "".to_string()));
+        if code != 0 {
+            if let Some(resp) = map_git_failure(code, &out, &err)
+                && !err.contains("bad revision")
+            {
+                return resp;
+            }
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": err.trim(), "code": "unknown_commit"})),
+            )
+                .into_response();
+        }
+        resolved = out
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && *l != "--")
+            .map(str::to_string)
+            .collect();
+    }
+
+    let (code, head, _) = run_git(&dir, &["rev-parse", "--verify", "--quiet", "HEAD"])
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    let head = Some(head.trim().to_string()).filter(|h| code == 0 && !h.is_empty());
+
+    let mut hashes: Vec<&str> = Vec::new();
+    for hash in head.iter().chain(resolved.iter()) {
+        if !hashes.contains(&hash.as_str()) {
+            hashes.push(hash);
+        }
+    }
+    if hashes.is_empty() {
+        // Unborn HEAD and nothing requested.
+        return Json(serde_json::json!({"head": null, "commits": {}})).into_response();
+    }
+    let mut args: Vec<&str> = vec![
+        "log",
+        "--no-walk=unsorted",
+        "--format=%H%x1f%G?%x1f%GS%x1f%GK%x1f%GF%x1f%GT%x1e",
+    ];
+    args.extend(hashes);
+    args.push("--");
+    let (code, out, err) =
+        run_git(&dir, &args)
+            .await
+            .unwrap_or((1, "".to_string(), "".to_string()));
+    if let Some(resp) = map_git_failure(code, &out, &err) {
+        return resp;
+    }
+
+    let records: HashMap<String, GitSignatureInfo> =
+        parse_signature_records(&out).into_iter().collect();
+    let mut by_commit = serde_json::Map::new();
+    for (commit, hash) in commits.iter().zip(&resolved) {
+        if let Some(sig) = records.get(hash) {
+            let mut value = serde_json::to_value(sig).unwrap_or_default();
+            value["hash"] = serde_json::Value::String(hash.clone());
+            by_commit.insert(commit.to_string(), value);
+        }
+    }
+    let head = head.and_then(|hash| {
+        let mut value = serde_json::to_value(records.get(&hash)?).unwrap_or_default();
+        value["hash"] = serde_json::Value::String(hash);
+        Some(value)
+    });
+
+    Json(serde_json::json!({"head": head, "commits": by_commit})).into_response()
+}
+
 // Internal helpers used by commit signing.
 pub(crate) async fn gpg_list_keys_for_signing()
 -> Result<Vec<(Option<String>, Option<String>, Option<String>)>, String> {
@@ -288,3 +475,28 @@ pub(crate) async fn gpg_preset_for_signing(
     };
     gpg_preset_passphrase(&grip, passphrase).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_signature_records() {
+        let out = "aaa\x1fG\x1fAda <ada@example.com>\x1fKEY1\x1fFPR1\x1fultimate\x1e\n\
+                   bbb\x1fN\x1f\x1f\x1f\x1fundefined\x1e\n\
+                   ccc\x1fE\x1f\x1fKEY2\x1f\x1fundefined\x1e\n";
+        let records = parse_signature_records(out);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].1.status, "good");
+        assert!(records[0].1.verified);
+        assert_eq!(
+            records[0].1.signer.as_deref(),
+            Some("Ada <ada@example.com>")
+        );
+        assert_eq!(records[1].1.status, "unsigned");
+        assert_eq!(records[1].1.trust, None);
+        assert_eq!(records[2].1.status, "unknownKey");
+        assert!(!records[2].1.verified);
+        assert_eq!(records[2].1.key.as_deref(), Some("KEY2"));
+    }
+}
@@ -0,0 +1,314 @@
+use std::path::Path;
+
+use axum::{
+    Json,
+    extract::Query,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{map_git_failure, require_directory_raw, run_git};
+
+const DEFAULT_GRAPH_LIMIT: usize = 200;
+const MAX_GRAPH_LIMIT: usize = 2000;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitGraphQuery {
+    pub directory: Option<String>,
+    pub limit: Option<usize>,
+    /// Graph of a single ref; all branches, remotes and tags by default.
+    pub r#ref: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitGraphEdge {
+    pub parent: String,
+    /// Lane the line runs to; the parent is drawn there once it is listed.
+    pub lane: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitGraphCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub subject: String,
+    pub author_name: String,
+    pub author_date: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub refs: Vec<String>,
+    pub parents: Vec<String>,
+    pub lane: usize,
+    pub edges: Vec<GitGraphEdge>,
+    /// Lanes occupied at this row, counting lines entering and leaving it.
+    pub active_lanes: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitGraphResponse {
+    pub commits: Vec<GitGraphCommit>,
+    /// Widest row, for sizing the graph column.
+    pub lane_count: usize,
+    pub has_more: bool,
+}
+
+struct RawCommit {
+    hash: String,
+    short_hash: String,
+    parents: Vec<String>,
+    refs: Vec<String>,
+    subject: String,
+    author_name: String,
+    author_date: String,
+}
+
+fn parse_graph_records(out: &str) -> Vec<RawCommit> {
+    out.split('\x1e')
+        .map(|r| r.trim_matches(['\n', '\r']))
+        .filter(|r| !r.is_empty())
+        .filter_map(|record| {
+            let fields: Vec<&str> = record.split('\x1f').collect();
+            if fields.len() < 7 {
+                return None;
+            }
+            Some(RawCommit {
+                hash: fields[0].to_string(),
+                short_hash: fields[1].to_string(),
+                parents: fields[2].split_whitespace().map(str::to_string).collect(),
+                refs: fields[3]
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                subject: fields[4].to_string(),
+                author_name: fields[5].to_string(),
+                author_date: fields[6].to_string(),
+            })
+        })
+        .collect()
+}
+
+fn claim_lane(lanes: &mut Vec<Option<String>>, hash: &str) -> usize {
+    let lane = match lanes.iter().position(Option::is_none) {
+        Some(free) => free,
+        None => {
+            lanes.push(None);
+            lanes.len() - 1
+        }
+    };
+    lanes[lane] = Some(hash.to_string());
+    lane
+}
+
+/// Assign lanes in topological order: a commit takes the lane that was
+/// waiting for it (or a free one), its first parent inherits that lane and
+/// further parents reuse a lane already waiting for them or claim a new one.
+fn assign_lanes(raw: Vec<RawCommit>) -> (Vec<GitGraphCommit>, usize) {
+    let mut lanes: Vec<Option<String>> = Vec::new();
+    let mut lane_count = 0;
+    let mut commits = Vec::with_capacity(raw.len());
+
+    for commit in raw {
+        let lanes_in = lanes.len();
+        let waiting: Vec<usize> = lanes
+            .iter()
+            .enumerate()
+            .filter(|(_, h)| h.as_deref() == Some(commit.hash.as_str()))
+            .map(|(i, _)| i)
+            .collect();
+        let lane = match waiting.first() {
+            Some(&lane) => lane,
+            None => claim_lane(&mut lanes, &commit.hash),
+        };
+        // Branches merging into this commit end here.
+        for &other in waiting.iter().skip(1) {
+            lanes[other] = None;
+        }
+        lanes[lane] = None;
+
+        let mut edges = Vec::with_capacity(commit.parents.len());
+        for (i, parent) in commit.parents.iter().enumerate() {
+            let target = if i == 0 {
+                // Stay in lane even if another lane waits for the same
+                // parent; both converge when the parent is listed.
+                lanes[lane] = Some(parent.clone());
+                lane
+            } else {
+                match lanes.iter().position(|h| h.as_deref() == Some(parent)) {
+                    Some(target) => target,
+                    None => claim_lane(&mut lanes, parent),
+                }
+            };
+            edges.push(GitGraphEdge {
+                parent: parent.clone(),
+                lane: target,
+            });
+        }
+
+        while lanes.last().is_some_and(Option::is_none) {
+            lanes.pop();
+        }
+        let active_lanes = lanes_in.max(lanes.len()).max(lane + 1);
+        lane_count = lane_count.max(active_lanes);
+        commits.push(GitGraphCommit {
+            hash: commit.hash,
+            short_hash: commit.short_hash,
+            subject: commit.subject,
+            author_name: commit.author_name,
+            author_date: commit.author_date,
+            refs: commit.refs,
+            parents: commit.parents,
+            lane,
+            edges,
+            active_lanes,
+        });
+    }
+    (commits, lane_count)
+}
+
+async fn head_exists(dir: &Path) -> bool {
+    run_git(dir, &["rev-parse", "--verify", "--quiet", "HEAD"])
+        .await
+        .is_ok_and(|(code, _, _)| code == 0)
+}
+
+/// Commit graph with parent edges, ref decorations and lanes, newest first
+/// in topological order.
+pub async fn git_graph(Query(q): Query<GitGraphQuery>) -> Response {
+    let dir = match require_directory_raw(q.directory.as_deref()) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let limit = q
+        .limit
+        .unwrap_or(DEFAULT_GRAPH_LIMIT)
+        .clamp(1, MAX_GRAPH_LIMIT);
+    let ref_name = q.r#ref.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    if ref_name.is_some_and(|r| r.starts_with('-')) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid ref", "code": "invalid_ref"})),
+        )
+            .into_response();
+    }
+
+    let mut args: Vec<String> = vec![
+        "log".into(),
+        "--topo-order".into(),
+        "--date=iso-strict".into(),
+        "--decorate=full".into(),
+        "--pretty=format:%H%x1f%h%x1f%P%x1f%D%x1f%s%x1f%an%x1f%ad%x1e".into(),
+        format!("--max-count={}", limit + 1),
+    ];
+    match ref_name {
+        Some(r) => args.push(r.to_string()),
+        None => {
+            args.extend(["--branches", "--remotes", "--tags"].map(String::from));
+            // Unborn or detached HEAD: only list it when it resolves.
+            if head_exists(&dir).await {
+                args.push("HEAD".into());
+            }
+        }
+    }
+    args.push("--".into());
+
+    let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    let (code, out, err) =
+        run_git(&dir, &args_ref)
+            .await
+            .unwrap_or((1, "".to_string(), "".to_string()));
+    if let Some(resp) = map_git_failure(code, &out, &err) {
+        return resp;
+    }
+
+    let mut raw = parse_graph_records(&out);
+    let has_more = raw.len() > limit;
+    raw.truncate(limit);
+    let (commits, lane_count) = assign_lanes(raw);
+    Json(GitGraphResponse {
+        commits,
+        lane_count,
+        has_more,
+    })
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(hash: &str, parents: &[&str]) -> RawCommit {
+        RawCommit {
+            hash: hash.to_string(),
+            short_hash: hash.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            refs: Vec::new(),
+            subject: String::new(),
+            author_name: String::new(),
+            author_date: String::new(),
+        }
+    }
+
+    #[test]
+    fn merge_and_branch_get_separate_lanes_that_rejoin() {
+        // m merges feature (f) into main; both fork from base.
+        let (commits, lane_count) = assign_lanes(vec![
+            raw("m", &["c", "f"]),
+            raw("f", &["base"]),
+            raw("c", &["base"]),
+            raw("base", &[]),
+        ]);
+        let lanes: Vec<usize> = commits.iter().map(|c| c.lane).collect();
+        assert_eq!(lanes, vec![0, 1, 0, 0]);
+        assert_eq!(
+            commits[0].edges,
+            vec![
+                GitGraphEdge {
+                    parent: "c".into(),
+                    lane: 0
+                },
+                GitGraphEdge {
+                    parent: "f".into(),
+                    lane: 1
+                },
+            ]
+        );
+        // Both lines run down to base, which folds lane 1 back into lane 0.
+        assert_eq!(commits[1].edges[0].lane, 1);
+        assert_eq!(commits[2].edges[0].lane, 0);
+        assert_eq!(commits[3].active_lanes, 2);
+        assert_eq!(lane_count, 2);
+    }
+
+    #[test]
+    fn freed_lanes_are_reused_by_later_branches() {
+        // Two independent tips; once the first history ends, the second
+        // branch claims the freed lane instead of widening the graph.
+        let (commits, lane_count) = assign_lanes(vec![
+            raw("a2", &["a1"]),
+            raw("a1", &[]),
+            raw("b2", &["b1"]),
+            raw("b1", &[]),
+        ]);
+        let lanes: Vec<usize> = commits.iter().map(|c| c.lane).collect();
+        assert_eq!(lanes, vec![0, 0, 0, 0]);
+        assert_eq!(lane_count, 1);
+    }
+
+    #[test]
+    fn parses_decorations_and_parents() {
+        let out = "a1\x1fa\x1fb1 c1\x1fHEAD -> refs/heads/main, tag: refs/tags/v1\x1fsubj\x1fAda\x1f2024-01-01T00:00:00Z\x1e\n";
+        let parsed = parse_graph_records(out);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].parents, vec!["b1", "c1"]);
+        assert_eq!(
+            parsed[0].refs,
+            vec!["HEAD -> refs/heads/main", "tag: refs/tags/v1"]
+        );
+    }
+}
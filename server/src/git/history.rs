@@ -1,16 +1,20 @@
+use std::sync::Arc;
+
 use axum::{
     Json,
-    extract::Query,
+    extract::{Query, State},
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::git2_utils;
 
 use super::{
-    DirectoryQuery, MAX_BLOB_BYTES, abs_path, git2_open_error_response, is_safe_repo_rel_path,
-    lock_repo, map_git_failure, require_directory, run_git,
+    DirectoryQuery, MAX_BLOB_BYTES, SequencerOperation, abs_path, git2_open_error_response,
+    is_safe_repo_rel_path, lock_repo, map_git_failure, require_directory, resolve_git_identity,
+    run_git, run_git_env, sequencer_conflict_report, sequencer_conflict_response,
 };
 
 #[derive(Debug, Serialize)]
@@ -37,19 +41,123 @@ pub struct GitLogResponse {
     pub commits: Vec<GitLogCommit>,
     pub has_more: bool,
     pub next_offset: usize,
+    /// Pass back as `cursor` for the next page; pinned to the tip the first
+    /// page was read from, so new commits don't shift later pages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Commits matching the filters; omitted with `includeTotal=false` and
+    /// for `search`, which is matched in-process.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitLogQuery {
     pub directory: Option<String>,
     pub limit: Option<usize>,
     pub offset: Option<usize>,
+    pub cursor: Option<String>,
+    /// Pathspec; a directory matches everything below it.
     pub path: Option<String>,
     pub search: Option<String>,
     pub author: Option<String>,
+    #[serde(alias = "grep")]
     pub message: Option<String>,
+    /// Anything `git log --since` accepts, e.g. `2024-01-31` or `2 weeks ago`.
+    pub since: Option<String>,
+    pub until: Option<String>,
     pub r#ref: Option<String>,
     pub graph: Option<bool>,
+    #[serde(rename = "includeTotal")]
+    pub include_total: Option<bool>,
+}
+
+/// `<tip>:<offset>`: the commit the listing started from and how many
+/// commits were already returned.
+fn parse_log_cursor(raw: &str) -> Option<(String, usize)> {
+    let (tip, offset) = raw.trim().split_once(':')?;
+    let is_hash = matches!(tip.len(), 40 | 64) && tip.bytes().all(|b| b.is_ascii_hexdigit());
+    if !is_hash {
+        return None;
+    }
+    Some((tip.to_ascii_lowercase(), offset.parse().ok()?))
+}
+
+fn is_valid_date_filter(value: &str) -> bool {
+    value.len() <= 64
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | ':' | '+' | '.' | ','))
+}
+
+fn trimmed(value: Option<&str>) -> Option<&str> {
+    value.map(|s| s.trim()).filter(|s| !s.is_empty())
+}
+
+/// Filters git applies itself, shared by `log` and `rev-list --count`.
+fn log_filter_args(
+    author: Option<&str>,
+    message: Option<&str>,
+    since: Option<&str>,
+    until: Option<&str>,
+) -> Vec<String> {
+    let mut args = Vec::new();
+    if author.is_some() || message.is_some() {
+        args.push("--fixed-strings".into());
+        args.push("--regexp-ignore-case".into());
+    }
+    if let Some(a) = author {
+        args.push(format!("--author={}", a));
+    }
+    if let Some(m) = message {
+        args.push(format!("--grep={}", m));
+    }
+    if let Some(s) = since {
+        args.push(format!("--since={}", s));
+    }
+    if let Some(u) = until {
+        args.push(format!("--until={}", u));
+    }
+    args
+}
+
+async fn resolve_log_tip(dir: &std::path::Path, rev: &str) -> Option<String> {
+    let spec = format!("{}^{{commit}}", rev);
+    let (code, out, _) = run_git(dir, &["rev-parse", "--verify", "--quiet", &spec])
+        .await
+        .ok()?;
+    let hash = out.trim();
+    (code == 0 && !hash.is_empty()).then(|| hash.to_string())
+}
+
+async fn count_log_commits(
+    dir: &std::path::Path,
+    tip: &str,
+    filters: &[String],
+    path: Option<&str>,
+) -> Option<usize> {
+    let mut args: Vec<&str> = vec!["rev-list", "--count", tip];
+    args.extend(filters.iter().map(|s| s.as_str()));
+    if let Some(p) = path {
+        args.push("--");
+        args.push(p);
+    }
+    let (code, out, _) = run_git(dir, &args).await.ok()?;
+    if code != 0 {
+        return None;
+    }
+    out.trim().parse().ok()
+}
+
+fn git_log_failure(code: i32, out: &str, err: &str) -> Response {
+    if let Some(resp) = map_git_failure(code, out, err) {
+        return resp;
+    }
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(serde_json::json!({"error": err.trim(), "code": "git_log_failed"})),
+    )
+        .into_response()
 }
 
 fn parse_git_log_records(out: &str) -> Vec<GitLogCommit> {
@@ -126,36 +234,31 @@ pub async fn git_log(Query(q): Query<GitLogQuery>) -> Response {
     let dir = abs_path(dir_raw);
 
     let limit = q.limit.unwrap_or(50).clamp(1, 200);
-    let offset = q.offset.unwrap_or(0);
-
-    let path = q
-        .path
-        .as_deref()
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty());
-
-    let search = q
-        .search
-        .as_deref()
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_string());
-
-    let author = q
-        .author
-        .as_deref()
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty());
-    let message = q
-        .message
-        .as_deref()
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty());
-    let ref_name = q
-        .r#ref
-        .as_deref()
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty());
+    let cursor = match trimmed(q.cursor.as_deref()) {
+        Some(raw) => match parse_log_cursor(raw) {
+            Some(cursor) => Some(cursor),
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "Invalid cursor", "code": "invalid_cursor"})),
+                )
+                    .into_response();
+            }
+        },
+        None => None,
+    };
+    let offset = cursor
+        .as_ref()
+        .map(|(_, offset)| *offset)
+        .unwrap_or_else(|| q.offset.unwrap_or(0));
+
+    let path = trimmed(q.path.as_deref());
+    let search = trimmed(q.search.as_deref()).map(|s| s.to_string());
+    let author = trimmed(q.author.as_deref());
+    let message = trimmed(q.message.as_deref());
+    let since = trimmed(q.since.as_deref());
+    let until = trimmed(q.until.as_deref());
+    let ref_name = trimmed(q.r#ref.as_deref());
     let include_graph = q.graph.unwrap_or(false);
 
     if let Some(p) = path
@@ -167,6 +270,26 @@ pub async fn git_log(Query(q): Query<GitLogQuery>) -> Response {
         )
             .into_response();
     }
+    if [since, until]
+        .into_iter()
+        .flatten()
+        .any(|d| !is_valid_date_filter(d))
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid date filter", "code": "invalid_date"})),
+        )
+            .into_response();
+    }
+
+    // Pin the listing to a commit so later pages don't shift when the ref
+    // moves. Unborn branches have no tip; git log reports those itself.
+    let tip = match cursor {
+        Some((tip, _)) => Some(tip),
+        None => resolve_log_tip(&dir, ref_name.unwrap_or("HEAD")).await,
+    };
+    let rev = tip.as_deref().or(ref_name);
+    let filters = log_filter_args(author, message, since, until);
 
     let format = "%x1f%H%x1f%h%x1f%an%x1f%ae%x1f%ad%x1f%s%x1f%b%x1f%D%x1f%P%x1e";
     let mut base_args: Vec<String> = vec![
@@ -177,16 +300,22 @@ pub async fn git_log(Query(q): Query<GitLogQuery>) -> Response {
     if include_graph {
         base_args.push("--graph".into());
     }
-    if let Some(r) = ref_name {
+    base_args.extend(filters.iter().cloned());
+    if let Some(r) = rev {
         base_args.push(r.to_string());
     }
-    if let Some(p) = path {
-        base_args.push("--".into());
-        base_args.push(p.to_string());
-    }
+    // Options must precede `--`; everything after it is a pathspec.
+    let with_pathspec = |mut args: Vec<String>| {
+        if let Some(p) = path {
+            args.push("--".into());
+            args.push(p.to_string());
+        }
+        args
+    };
 
     let mut commits: Vec<GitLogCommit>;
     let has_more: bool;
+    let searching = search.is_some();
 
     if let Some(search_term) = search {
         let page_target = offset.saturating_add(limit).saturating_add(1);
@@ -198,6 +327,7 @@ pub async fn git_log(Query(q): Query<GitLogQuery>) -> Response {
             let mut args = base_args.clone();
             args.push(format!("--max-count={}", scan_batch_size));
             args.push(format!("--skip={}", scan_skip));
+            let args = with_pathspec(args);
 
             let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
             let (code, out, err) =
@@ -205,14 +335,7 @@ pub async fn git_log(Query(q): Query<GitLogQuery>) -> Response {
                     .await
                     .unwrap_or((1, "".to_string(), "".to_string()));
             if code != 0 {
-                if let Some(resp) = map_git_failure(code, &out, &err) {
-                    return resp;
-                }
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": err.trim(), "code": "git_log_failed"})),
-                )
-                    .into_response();
+                return git_log_failure(code, &out, &err);
             }
 
             let batch = parse_git_log_records(&out);
@@ -239,16 +362,7 @@ pub async fn git_log(Query(q): Query<GitLogQuery>) -> Response {
         let mut args = base_args;
         args.push(format!("--max-count={}", max_count));
         args.push(format!("--skip={}", offset));
-        if author.is_some() || message.is_some() {
-            args.push("--fixed-strings".into());
-            args.push("--regexp-ignore-case".into());
-        }
-        if let Some(a) = author {
-            args.push(format!("--author={}", a));
-        }
-        if let Some(m) = message {
-            args.push(format!("--grep={}", m));
-        }
+        let args = with_pathspec(args);
 
         let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
         let (code, out, err) =
@@ -256,14 +370,7 @@ pub async fn git_log(Query(q): Query<GitLogQuery>) -> Response {
                 .await
                 .unwrap_or((1, "".to_string(), "".to_string()));
         if code != 0 {
-            if let Some(resp) = map_git_failure(code, &out, &err) {
-                return resp;
-            }
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": err.trim(), "code": "git_log_failed"})),
-            )
-                .into_response();
+            return git_log_failure(code, &out, &err);
         }
 
         commits = parse_git_log_records(&out);
@@ -274,16 +381,28 @@ pub async fn git_log(Query(q): Query<GitLogQuery>) -> Response {
         commits.truncate(limit);
     }
     let returned_count = commits.len();
+    let next_offset = offset.saturating_add(returned_count);
+
+    let total = match tip.as_deref() {
+        Some(tip) if !searching && q.include_total.unwrap_or(true) => {
+            count_log_commits(&dir, tip, &filters, path).await
+        }
+        _ => None,
+    };
 
     Json(GitLogResponse {
         commits,
         has_more,
-        next_offset: offset.saturating_add(returned_count),
+        next_offset,
+        next_cursor: tip
+            .filter(|_| has_more)
+            .map(|tip| format!("{}:{}", tip, next_offset)),
+        total,
     })
     .into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitCommitDiffQuery {
     pub directory: Option<String>,
     pub commit: Option<String>,
@@ -369,7 +488,7 @@ pub struct GitCommitFilesResponse {
     pub has_next: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitCommitFilesQuery {
     pub directory: Option<String>,
@@ -662,7 +781,7 @@ pub async fn git_commit_files(Query(q): Query<GitCommitFilesQuery>) -> Response
     .into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitCommitFileDiffQuery {
     pub directory: Option<String>,
     pub commit: Option<String>,
@@ -757,7 +876,7 @@ pub struct GitCommitFileContentResponse {
     pub truncated: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitCommitFileContentQuery {
     pub directory: Option<String>,
     pub commit: Option<String>,
@@ -875,12 +994,48 @@ pub async fn git_commit_file_content(Query(q): Query<GitCommitFileContentQuery>)
     }
 }
 
-#[derive(Debug, Deserialize)]
+const MAX_COMMIT_ACTION_COMMITS: usize = 100;
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitCommitActionBody {
     pub commit: Option<String>,
+    /// Several commits, applied in the given order (alternative to `commit`).
+    #[serde(default)]
+    pub commits: Vec<String>,
+    #[serde(default, rename = "identityId")]
+    pub identity_id: Option<String>,
+}
+
+impl GitCommitActionBody {
+    fn requested_commits(&self) -> Result<Vec<String>, Box<Response>> {
+        let commits: Vec<String> = self
+            .commit
+            .iter()
+            .chain(self.commits.iter())
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(ToString::to_string)
+            .collect();
+        if commits.is_empty() {
+            return Err(Box::new((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "commit is required", "code": "missing_commit"})),
+            )
+                .into_response()));
+        }
+        if commits.len() > MAX_COMMIT_ACTION_COMMITS || commits.iter().any(|c| c.starts_with('-')) {
+            return Err(Box::new((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Invalid commit list", "code": "invalid_commit"})),
+            )
+                .into_response()));
+        }
+        Ok(commits)
+    }
 }
 
 pub async fn git_cherry_pick(
+    State(state): State<Arc<crate::AppState>>,
     Query(q): Query<DirectoryQuery>,
     Json(body): Json<GitCommitActionBody>,
 ) -> Response {
@@ -889,30 +1044,31 @@ pub async fn git_cherry_pick(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "cherry-pick").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
 
-    let Some(commit) = body
-        .commit
-        .as_deref()
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
-    else {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({"error": "commit is required", "code": "missing_commit"})),
-        )
-            .into_response();
+    let commits = match body.requested_commits() {
+        Ok(c) => c,
+        Err(resp) => return *resp,
     };
 
-    let (code, out, err) = run_git(&dir, &["cherry-pick", commit]).await.unwrap_or((
-        1,
-        "".to_string(),
-        "".to_string(),
-    ));
+    let identity = match resolve_git_identity(&state, &dir, body.identity_id.as_deref()).await {
+        Ok(identity) => identity,
+        Err(resp) => return *resp,
+    };
+    let identity_env = identity.as_ref().map(|i| i.committer_env());
+    let mut args = vec!["cherry-pick"];
+    args.extend(commits.iter().map(String::as_str));
+    let (code, out, err) = run_git_env(&dir, &args, identity_env.as_ref().map_or(&[], |env| env))
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
     if code != 0 {
+        let op = SequencerOperation::CherryPick;
+        if let Some(report) = sequencer_conflict_report(&dir, op).await {
+            return sequencer_conflict_response(op, report);
+        }
         if let Some(resp) = map_git_failure(code, &out, &err) {
             return resp;
         }
@@ -927,6 +1083,7 @@ pub async fn git_cherry_pick(
 }
 
 pub async fn git_revert_commit(
+    State(state): State<Arc<crate::AppState>>,
     Query(q): Query<DirectoryQuery>,
     Json(body): Json<GitCommitActionBody>,
 ) -> Response {
@@ -935,28 +1092,31 @@ pub async fn git_revert_commit(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "revert-commit").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
 
-    let Some(commit) = body
-        .commit
-        .as_deref()
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
-    else {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({"error": "commit is required", "code": "missing_commit"})),
-        )
-            .into_response();
+    let commits = match body.requested_commits() {
+        Ok(c) => c,
+        Err(resp) => return *resp,
     };
 
-    let (code, out, err) = run_git(&dir, &["revert", "--no-edit", commit])
+    let identity = match resolve_git_identity(&state, &dir, body.identity_id.as_deref()).await {
+        Ok(identity) => identity,
+        Err(resp) => return *resp,
+    };
+    let identity_env = identity.as_ref().map(|i| i.env());
+    let mut args = vec!["revert", "--no-edit"];
+    args.extend(commits.iter().map(String::as_str));
+    let (code, out, err) = run_git_env(&dir, &args, identity_env.as_ref().map_or(&[], |env| env))
         .await
         .unwrap_or((1, "".to_string(), "".to_string()));
     if code != 0 {
+        let op = SequencerOperation::Revert;
+        if let Some(report) = sequencer_conflict_report(&dir, op).await {
+            return sequencer_conflict_response(op, report);
+        }
         if let Some(resp) = map_git_failure(code, &out, &err) {
             return resp;
         }
@@ -0,0 +1,430 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{DirectoryQuery, abs_path, map_git_failure, require_directory, run_git};
+
+const PROFILES_KEY: &str = "gitIdentities";
+const REPOSITORY_MAP_KEY: &str = "gitRepositoryIdentities";
+const DEFAULT_KEY: &str = "defaultGitIdentityId";
+
+/// A named author/committer pair (e.g. work, personal, bot).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitIdentityProfile {
+    pub id: String,
+    pub label: String,
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GitIdentitySource {
+    Request,
+    Repository,
+    Default,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ResolvedGitIdentity {
+    pub profile: GitIdentityProfile,
+    pub source: GitIdentitySource,
+}
+
+impl ResolvedGitIdentity {
+    /// Environment overrides that attribute a commit to this profile without
+    /// touching any git config.
+    pub(crate) fn env(&self) -> [(&'static str, &str); 4] {
+        let name = self.profile.name.as_str();
+        let email = self.profile.email.as_str();
+        [
+            ("GIT_AUTHOR_NAME", name),
+            ("GIT_AUTHOR_EMAIL", email),
+            ("GIT_COMMITTER_NAME", name),
+            ("GIT_COMMITTER_EMAIL", email),
+        ]
+    }
+
+    /// Committer-only overrides, for operations that keep the original author
+    /// (cherry-pick).
+    pub(crate) fn committer_env(&self) -> [(&'static str, &str); 2] {
+        [
+            ("GIT_COMMITTER_NAME", self.profile.name.as_str()),
+            ("GIT_COMMITTER_EMAIL", self.profile.email.as_str()),
+        ]
+    }
+}
+
+fn trimmed_str<'a>(obj: &'a serde_json::Map<String, Value>, key: &str) -> &'a str {
+    obj.get(key).and_then(|v| v.as_str()).unwrap_or("").trim()
+}
+
+fn parse_identity_profiles(value: Option<&Value>) -> Vec<GitIdentityProfile> {
+    let Some(Value::Array(arr)) = value else {
+        return Vec::new();
+    };
+    let mut out: Vec<GitIdentityProfile> = Vec::new();
+    for entry in arr {
+        let Value::Object(obj) = entry else {
+            continue;
+        };
+        let id = trimmed_str(obj, "id");
+        let name = trimmed_str(obj, "name");
+        let email = trimmed_str(obj, "email");
+        if id.is_empty() || name.is_empty() || email.is_empty() {
+            continue;
+        }
+        if out.iter().any(|p| p.id == id) {
+            continue;
+        }
+        let label = match trimmed_str(obj, "label") {
+            "" => name,
+            label => label,
+        };
+        out.push(GitIdentityProfile {
+            id: id.to_string(),
+            label: label.to_string(),
+            name: name.to_string(),
+            email: email.to_string(),
+        });
+    }
+    out
+}
+
+fn repository_key(root: &Path) -> Option<String> {
+    crate::path_utils::normalize_directory_for_match(&root.to_string_lossy())
+}
+
+fn repository_identity_id(value: Option<&Value>, root: &Path) -> Option<String> {
+    let Some(Value::Object(map)) = value else {
+        return None;
+    };
+    let key = repository_key(root)?;
+    map.iter()
+        .find(|(path, _)| {
+            crate::path_utils::normalize_directory_for_match(path).as_deref() == Some(&key)
+        })
+        .and_then(|(_, id)| id.as_str())
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+}
+
+fn unknown_identity_response(id: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "error": format!("Unknown git identity '{id}'"),
+            "code": "git_identity_not_found",
+            "hint": "Add the identity under gitIdentities in settings or pick another one.",
+        })),
+    )
+        .into_response()
+}
+
+/// Pick the identity for `root`: the explicit request id wins, then the
+/// repository mapping, then the default. Repository and default ids that no
+/// longer name a profile are ignored so stale settings don't block commits.
+fn resolve_from_settings(
+    extra: &BTreeMap<String, Value>,
+    root: &Path,
+    requested: Option<&str>,
+) -> Result<Option<ResolvedGitIdentity>, Box<Response>> {
+    let profiles = parse_identity_profiles(extra.get(PROFILES_KEY));
+    let find = |id: &str| profiles.iter().find(|p| p.id == id).cloned();
+
+    if let Some(id) = requested.map(str::trim).filter(|id| !id.is_empty()) {
+        return match find(id) {
+            Some(profile) => Ok(Some(ResolvedGitIdentity {
+                profile,
+                source: GitIdentitySource::Request,
+            })),
+            None => Err(Box::new(unknown_identity_response(id))),
+        };
+    }
+
+    if let Some(profile) =
+        repository_identity_id(extra.get(REPOSITORY_MAP_KEY), root).and_then(|id| find(&id))
+    {
+        return Ok(Some(ResolvedGitIdentity {
+            profile,
+            source: GitIdentitySource::Repository,
+        }));
+    }
+
+    let default_id = extra
+        .get(DEFAULT_KEY)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .unwrap_or("");
+    Ok(find(default_id).map(|profile| ResolvedGitIdentity {
+        profile,
+        source: GitIdentitySource::Default,
+    }))
+}
+
+pub(super) async fn repository_root(dir: &Path) -> Result<PathBuf, Box<Response>> {
+    let (code, out, err) = run_git(dir, &["rev-parse", "--show-toplevel"])
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        if let Some(resp) = map_git_failure(code, &out, &err) {
+            return Err(Box::new(resp));
+        }
+        return Err(Box::new(
+            (
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({"error": err.trim(), "code": "not_git_repo"})),
+            )
+                .into_response(),
+        ));
+    }
+    Ok(abs_path(out.trim()))
+}
+
+/// Resolve the identity a commit in `dir` should be attributed to, if any
+/// profile applies. `None` leaves git's own config in charge.
+pub(crate) async fn resolve_git_identity(
+    state: &Arc<crate::AppState>,
+    dir: &Path,
+    requested: Option<&str>,
+) -> Result<Option<ResolvedGitIdentity>, Box<Response>> {
+    let root = repository_root(dir).await?;
+    let settings = state.settings.read().await;
+    resolve_from_settings(&settings.extra, &root, requested)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitIdentityResponse {
+    pub repository: String,
+    pub profiles: Vec<GitIdentityProfile>,
+    /// Profile id mapped to this repository, if any.
+    pub repository_identity_id: Option<String>,
+    /// Identity commits will use when no `identityId` is passed.
+    pub effective: Option<GitIdentityProfile>,
+    pub source: Option<GitIdentitySource>,
+}
+
+fn identity_response(extra: &BTreeMap<String, Value>, root: &Path) -> GitIdentityResponse {
+    let resolved = resolve_from_settings(extra, root, None).ok().flatten();
+    GitIdentityResponse {
+        repository: root.to_string_lossy().into_owned(),
+        profiles: parse_identity_profiles(extra.get(PROFILES_KEY)),
+        repository_identity_id: repository_identity_id(extra.get(REPOSITORY_MAP_KEY), root),
+        source: resolved.as_ref().map(|r| r.source),
+        effective: resolved.map(|r| r.profile),
+    }
+}
+
+pub async fn git_identity_get(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<DirectoryQuery>,
+) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let root = match repository_root(&dir).await {
+        Ok(root) => root,
+        Err(resp) => return *resp,
+    };
+    let settings = state.settings.read().await;
+    Json(identity_response(&settings.extra, &root)).into_response()
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitIdentitySetBody {
+    /// Profile id to pin to the repository; null or empty clears the mapping.
+    #[serde(default, rename = "identityId")]
+    pub identity_id: Option<String>,
+}
+
+pub async fn git_identity_set(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitIdentitySetBody>,
+) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let root = match repository_root(&dir).await {
+        Ok(root) => root,
+        Err(resp) => return *resp,
+    };
+    let Some(key) = repository_key(&root) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid path", "code": "invalid_path"})),
+        )
+            .into_response();
+    };
+    let identity_id = body
+        .identity_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty());
+
+    let mut guard = state.settings.write().await;
+    if let Some(id) = identity_id
+        && !parse_identity_profiles(guard.extra.get(PROFILES_KEY))
+            .iter()
+            .any(|p| p.id == id)
+    {
+        return unknown_identity_response(id);
+    }
+
+    let mut map = match guard.extra.get(REPOSITORY_MAP_KEY) {
+        Some(Value::Object(map)) => map.clone(),
+        _ => serde_json::Map::new(),
+    };
+    map.retain(|path, _| {
+        crate::path_utils::normalize_directory_for_match(path).as_deref() != Some(&key)
+    });
+    if let Some(id) = identity_id {
+        map.insert(key, Value::String(id.to_string()));
+    }
+    guard
+        .extra
+        .insert(REPOSITORY_MAP_KEY.to_string(), Value::Object(map));
+
+    guard.revision = guard.revision.saturating_add(1);
+    let next_settings = guard.clone();
+    drop(guard);
+    if let Err(err) =
+        crate::settings::persist_settings(state.studio_db.as_ref(), &next_settings).await
+    {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": err.to_string()})),
+        )
+            .into_response();
+    }
+    let value = serde_json::to_value(&next_settings).unwrap_or(serde_json::json!({}));
+    crate::settings_events::publish_settings_replace(crate::config::format_settings_response(
+        &value,
+    ))
+    .await;
+
+    Json(identity_response(&next_settings.extra, &root)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extra(value: Value) -> BTreeMap<String, Value> {
+        serde_json::from_value(value).expect("object")
+    }
+
+    fn settings_fixture() -> BTreeMap<String, Value> {
+        extra(serde_json::json!({
+            "gitIdentities": [
+                {"id": "work", "label": "Work", "name": "Ada Work", "email": "ada@corp.example"},
+                {"id": "bot", "name": "Studio Bot", "email": "bot@example.com"},
+                {"id": "broken", "name": "", "email": "x@example.com"},
+                {"id": "work", "name": "Duplicate", "email": "dup@example.com"},
+            ],
+            "gitRepositoryIdentities": {"/srv/repos/app/": "bot", "/srv/repos/stale": "gone"},
+            "defaultGitIdentityId": "work",
+        }))
+    }
+
+    #[test]
+    fn parse_identity_profiles_skips_incomplete_and_duplicate_entries() {
+        let extra = settings_fixture();
+        let profiles = parse_identity_profiles(extra.get(PROFILES_KEY));
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].label, "Work");
+        assert_eq!(profiles[0].email, "ada@corp.example");
+        assert_eq!(profiles[1].label, "Studio Bot");
+    }
+
+    #[test]
+    fn resolve_prefers_request_then_repository_then_default() {
+        let extra = settings_fixture();
+        let app = Path::new("/srv/repos/app");
+
+        let r = resolve_from_settings(&extra, app, Some("work"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (r.profile.id.as_str(), r.source),
+            ("work", GitIdentitySource::Request)
+        );
+
+        let r = resolve_from_settings(&extra, app, None).unwrap().unwrap();
+        assert_eq!(
+            (r.profile.id.as_str(), r.source),
+            ("bot", GitIdentitySource::Repository)
+        );
+
+        let r = resolve_from_settings(&extra, Path::new("/srv/repos/stale"), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (r.profile.id.as_str(), r.source),
+            ("work", GitIdentitySource::Default)
+        );
+
+        assert!(resolve_from_settings(&extra, app, Some("missing")).is_err());
+        assert!(
+            resolve_from_settings(&BTreeMap::new(), app, None)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn identity_env_attributes_commit_without_config() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let dir = tmp.path();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(args)
+                .current_dir(dir)
+                .output()
+                .expect("git")
+        };
+        git(&["init", "-q"]);
+        git(&["config", "commit.gpgsign", "false"]);
+
+        let resolved = ResolvedGitIdentity {
+            profile: GitIdentityProfile {
+                id: "bot".to_string(),
+                label: "Bot".to_string(),
+                name: "Studio Bot".to_string(),
+                email: "bot@example.com".to_string(),
+            },
+            source: GitIdentitySource::Request,
+        };
+        let (code, _, err) = super::super::run_git_env(
+            dir,
+            &["commit", "--allow-empty", "-m", "init"],
+            &resolved.env(),
+        )
+        .await
+        .expect("run git");
+        assert_eq!(code, 0, "{err}");
+
+        let out = git(&["log", "-1", "--format=%an <%ae>|%cn <%ce>"]);
+        assert_eq!(
+            String::from_utf8_lossy(&out.stdout).trim(),
+            "Studio Bot <bot@example.com>|Studio Bot <bot@example.com>"
+        );
+        let name = git(&["config", "--local", "user.name"]);
+        assert!(!name.status.success());
+    }
+}
@@ -4,11 +4,12 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use super::{DirectoryQuery, is_safe_repo_rel_path, lock_repo, require_directory};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitIgnoreBody {
     pub path: Option<String>,
 }
@@ -37,7 +38,7 @@ pub async fn git_ignore(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "ignore").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
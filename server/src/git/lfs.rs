@@ -4,9 +4,12 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use super::{DirectoryQuery, lock_repo, map_git_failure, require_directory, run_git};
+use super::{
+    DirectoryQuery, is_safe_repo_rel_path, lock_repo, map_git_failure, require_directory, run_git,
+};
 
 fn is_lfs_missing(out: &str, err: &str) -> bool {
     let combined = format!("{}\n{}", out, err).to_ascii_lowercase();
@@ -104,7 +107,7 @@ pub async fn git_lfs_status(Query(q): Query<DirectoryQuery>) -> Response {
     .into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitLfsInstallBody {
     pub force: Option<bool>,
 }
@@ -117,7 +120,7 @@ pub async fn git_lfs_install(
         Ok(d) => d,
         Err(resp) => return *resp,
     };
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "lfs-install").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -152,11 +155,23 @@ pub async fn git_lfs_install(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitLfsTrackBody {
     pub pattern: Option<String>,
 }
 
+/// `git lfs track` patterns go after `--`, but a leading `-` is still never a
+/// pattern anyone means.
+fn reject_option_like_pattern(pattern: &str) -> Option<Response> {
+    pattern.starts_with('-').then(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid pattern", "code": "invalid_pattern"})),
+        )
+            .into_response()
+    })
+}
+
 pub async fn git_lfs_track(
     Query(q): Query<DirectoryQuery>,
     Json(body): Json<GitLfsTrackBody>,
@@ -165,7 +180,7 @@ pub async fn git_lfs_track(
         Ok(d) => d,
         Err(resp) => return *resp,
     };
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "lfs-track").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -182,12 +197,13 @@ pub async fn git_lfs_track(
         )
             .into_response();
     };
+    if let Some(resp) = reject_option_like_pattern(pattern) {
+        return resp;
+    }
 
-    let (code, out, err) = run_git(&dir, &["lfs", "track", pattern]).await.unwrap_or((
-        1,
-        "".to_string(),
-        "".to_string(),
-    ));
+    let (code, out, err) = run_git(&dir, &["lfs", "track", "--", pattern])
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
     if code != 0 {
         if is_lfs_missing(&out, &err) {
             return (
@@ -292,7 +308,7 @@ pub async fn git_lfs_locks(Query(q): Query<DirectoryQuery>) -> Response {
     Json(GitLfsLocksResponse { locks }).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitLfsLockBody {
     pub path: Option<String>,
 }
@@ -305,7 +321,7 @@ pub async fn git_lfs_lock(
         Ok(d) => d,
         Err(resp) => return *resp,
     };
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "lfs-lock").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -348,7 +364,7 @@ pub async fn git_lfs_lock(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitLfsUnlockBody {
     pub path: Option<String>,
     pub force: Option<bool>,
@@ -362,7 +378,7 @@ pub async fn git_lfs_unlock(
         Ok(d) => d,
         Err(resp) => return *resp,
     };
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "lfs-unlock").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -410,3 +426,93 @@ pub async fn git_lfs_unlock(
 
     Json(serde_json::json!({"success": true})).into_response()
 }
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitLfsMigrateBody {
+    pub path: Option<String>,
+    /// Tracking pattern to add; defaults to the path itself.
+    pub pattern: Option<String>,
+}
+
+fn lfs_failure_response(code: i32, out: &str, err: &str, kind: &str) -> Response {
+    if is_lfs_missing(out, err) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "git-lfs not installed", "code": "lfs_missing"})),
+        )
+            .into_response();
+    }
+    if let Some(resp) = map_git_failure(code, out, err) {
+        return resp;
+    }
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(serde_json::json!({"error": err.trim(), "code": kind})),
+    )
+        .into_response()
+}
+
+/// Move a tracked path to LFS: track it, then re-stage it (and
+/// `.gitattributes`) so the next commit stores a pointer. History is left
+/// untouched.
+pub async fn git_lfs_migrate(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitLfsMigrateBody>,
+) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let _guard = match lock_repo(&dir, "lfs-migrate").await {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+
+    let Some(path) = body
+        .path
+        .as_deref()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "path is required", "code": "missing_path"})),
+        )
+            .into_response();
+    };
+    if !is_safe_repo_rel_path(path) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid path", "code": "invalid_path"})),
+        )
+            .into_response();
+    }
+    let pattern = body
+        .pattern
+        .as_deref()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .unwrap_or(path);
+    if let Some(resp) = reject_option_like_pattern(pattern) {
+        return resp;
+    }
+
+    let (code, out, err) = run_git(&dir, &["lfs", "track", "--", pattern])
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        return lfs_failure_response(code, &out, &err, "git_lfs_track_failed");
+    }
+
+    let (code, out, err) = run_git(
+        &dir,
+        &["add", "--renormalize", "--", path, ".gitattributes"],
+    )
+    .await
+    .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        return lfs_failure_response(code, &out, &err, "git_lfs_migrate_failed");
+    }
+
+    Json(serde_json::json!({"success": true, "path": path, "pattern": pattern})).into_response()
+}
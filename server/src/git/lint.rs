@@ -0,0 +1,646 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::process::Command;
+
+use super::identity::repository_root;
+use super::{DirectoryQuery, path_slash, require_directory, run_git, truncate_for_payload};
+
+const SETTINGS_KEY: &str = "gitLinters";
+const DEFAULT_TIMEOUT_SECS: u64 = 120;
+const MAX_STDERR_CHARS: usize = 2000;
+const MAX_DIAGNOSTICS: usize = 5000;
+
+/// Last report per repository root, so the diff view can show diagnostics
+/// without re-running linters on every render.
+static LAST_REPORTS: LazyLock<DashMap<String, GitLintReport>> = LazyLock::new(DashMap::new);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GitLintFormat {
+    /// `eslint -f json`
+    Eslint,
+    /// `cargo clippy --message-format=json`
+    Clippy,
+    /// `ruff check --output-format=json`
+    Ruff,
+}
+
+impl GitLintFormat {
+    pub(crate) fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "eslint" => Some(Self::Eslint),
+            "clippy" => Some(Self::Clippy),
+            "ruff" => Some(Self::Ruff),
+            _ => None,
+        }
+    }
+}
+
+/// A linter configured in settings (`gitLinters`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GitLinterConfig {
+    pub id: String,
+    pub label: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub format: GitLintFormat,
+    /// Lowercase extensions without the dot; empty matches every file.
+    pub extensions: Vec<String>,
+    /// Append the matching changed files to `args`. Off for tools that lint a
+    /// whole project (clippy); their output is filtered to changed files.
+    pub pass_files: bool,
+    pub timeout: Duration,
+}
+
+impl GitLinterConfig {
+    fn matches(&self, path: &str) -> bool {
+        if self.extensions.is_empty() {
+            return true;
+        }
+        let Some(ext) = Path::new(path).extension().and_then(|e| e.to_str()) else {
+            return false;
+        };
+        let ext = ext.to_ascii_lowercase();
+        self.extensions.contains(&ext)
+    }
+}
+
+pub(crate) fn parse_linter_configs(value: Option<&Value>) -> Vec<GitLinterConfig> {
+    let Some(Value::Array(arr)) = value else {
+        return Vec::new();
+    };
+    arr.iter()
+        .filter_map(|entry| {
+            let obj = entry.as_object()?;
+            let field = |key: &str| obj.get(key).and_then(|v| v.as_str()).unwrap_or("").trim();
+            let id = field("id");
+            let command = field("command");
+            if id.is_empty() || command.is_empty() {
+                return None;
+            }
+            let format = GitLintFormat::parse(field("format"))?;
+            let strings = |key: &str| -> Vec<String> {
+                obj.get(key)
+                    .and_then(|v| v.as_array())
+                    .map(|a| {
+                        a.iter()
+                            .filter_map(|v| v.as_str())
+                            .map(ToString::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            };
+            let label = Some(field("label"))
+                .filter(|l| !l.is_empty())
+                .unwrap_or(id)
+                .to_string();
+            let extensions = strings("extensions")
+                .iter()
+                .map(|e| e.trim().trim_start_matches('.').to_ascii_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect();
+            let pass_files = obj
+                .get("passFiles")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(format != GitLintFormat::Clippy);
+            let timeout = obj
+                .get("timeoutSeconds")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(DEFAULT_TIMEOUT_SECS);
+            Some(GitLinterConfig {
+                id: id.to_string(),
+                label,
+                command: command.to_string(),
+                args: strings("args"),
+                format,
+                extensions,
+                pass_files,
+                timeout: Duration::from_secs(timeout),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GitLintSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLintDiagnostic {
+    pub linter: String,
+    /// Repository-relative, slash-separated.
+    pub path: String,
+    pub line: u32,
+    pub column: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<u32>,
+    pub severity: GitLintSeverity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLinterRun {
+    pub id: String,
+    pub label: String,
+    pub format: GitLintFormat,
+    pub files: usize,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    pub diagnostics: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLintReport {
+    pub root: String,
+    pub ran_at: i64,
+    pub changed_files: Vec<String>,
+    pub linters: Vec<GitLinterRun>,
+    pub diagnostics: Vec<GitLintDiagnostic>,
+    pub truncated: bool,
+}
+
+fn u32_at(v: &Value, key: &str) -> Option<u32> {
+    v.get(key)
+        .and_then(|n| n.as_u64())
+        .and_then(|n| u32::try_from(n).ok())
+}
+
+fn str_at(v: &Value, key: &str) -> Option<String> {
+    v.get(key)
+        .and_then(|s| s.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(ToString::to_string)
+}
+
+/// Map a path reported by a linter (absolute or relative to `root`) to the
+/// repository-relative form used by git status.
+fn repo_relative(root: &Path, reported: &str) -> String {
+    let path = Path::new(reported);
+    let rel = if path.is_absolute() {
+        path.strip_prefix(root).unwrap_or(path)
+    } else {
+        path
+    };
+    path_slash(rel).trim_start_matches("./").to_string()
+}
+
+fn parse_eslint(linter: &str, root: &Path, stdout: &str) -> Vec<GitLintDiagnostic> {
+    let Ok(Value::Array(files)) = serde_json::from_str::<Value>(stdout.trim()) else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    for file in &files {
+        let Some(path) = file.get("filePath").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let path = repo_relative(root, path);
+        let Some(messages) = file.get("messages").and_then(|v| v.as_array()) else {
+            continue;
+        };
+        for msg in messages {
+            let severity = match msg.get("severity").and_then(|v| v.as_u64()) {
+                Some(2) => GitLintSeverity::Error,
+                Some(1) => GitLintSeverity::Warning,
+                _ => GitLintSeverity::Info,
+            };
+            out.push(GitLintDiagnostic {
+                linter: linter.to_string(),
+                path: path.clone(),
+                line: u32_at(msg, "line").unwrap_or(1),
+                column: u32_at(msg, "column").unwrap_or(1),
+                end_line: u32_at(msg, "endLine"),
+                end_column: u32_at(msg, "endColumn"),
+                severity,
+                code: str_at(msg, "ruleId"),
+                message: str_at(msg, "message").unwrap_or_default(),
+            });
+        }
+    }
+    out
+}
+
+fn parse_clippy(linter: &str, root: &Path, stdout: &str) -> Vec<GitLintDiagnostic> {
+    let mut out = Vec::new();
+    for line in stdout.lines() {
+        let Ok(record) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if record.get("reason").and_then(|v| v.as_str()) != Some("compiler-message") {
+            continue;
+        }
+        let Some(msg) = record.get("message") else {
+            continue;
+        };
+        let severity = match msg.get("level").and_then(|v| v.as_str()) {
+            Some("error") | Some("error: internal compiler error") => GitLintSeverity::Error,
+            Some("warning") => GitLintSeverity::Warning,
+            _ => continue,
+        };
+        let Some(span) = msg
+            .get("spans")
+            .and_then(|v| v.as_array())
+            .and_then(|spans| {
+                spans
+                    .iter()
+                    .find(|s| s.get("is_primary").and_then(|v| v.as_bool()) == Some(true))
+            })
+        else {
+            // Crate-level summaries ("N warnings emitted") have no span.
+            continue;
+        };
+        let Some(file) = span.get("file_name").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        out.push(GitLintDiagnostic {
+            linter: linter.to_string(),
+            path: repo_relative(root, file),
+            line: u32_at(span, "line_start").unwrap_or(1),
+            column: u32_at(span, "column_start").unwrap_or(1),
+            end_line: u32_at(span, "line_end"),
+            end_column: u32_at(span, "column_end"),
+            severity,
+            code: msg.get("code").and_then(|c| str_at(c, "code")),
+            message: str_at(msg, "message").unwrap_or_default(),
+        });
+    }
+    out
+}
+
+fn parse_ruff(linter: &str, root: &Path, stdout: &str) -> Vec<GitLintDiagnostic> {
+    let Ok(Value::Array(items)) = serde_json::from_str::<Value>(stdout.trim()) else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let file = item.get("filename").and_then(|v| v.as_str())?;
+            let start = item.get("location");
+            let end = item.get("end_location");
+            Some(GitLintDiagnostic {
+                linter: linter.to_string(),
+                path: repo_relative(root, file),
+                line: start.and_then(|l| u32_at(l, "row")).unwrap_or(1),
+                column: start.and_then(|l| u32_at(l, "column")).unwrap_or(1),
+                end_line: end.and_then(|l| u32_at(l, "row")),
+                end_column: end.and_then(|l| u32_at(l, "column")),
+                // Syntax errors have no rule code.
+                severity: if item.get("code").is_some_and(|c| !c.is_null()) {
+                    GitLintSeverity::Warning
+                } else {
+                    GitLintSeverity::Error
+                },
+                code: str_at(item, "code"),
+                message: str_at(item, "message").unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn parse_lint_output(
+    format: GitLintFormat,
+    linter: &str,
+    root: &Path,
+    stdout: &str,
+) -> Vec<GitLintDiagnostic> {
+    match format {
+        GitLintFormat::Eslint => parse_eslint(linter, root, stdout),
+        GitLintFormat::Clippy => parse_clippy(linter, root, stdout),
+        GitLintFormat::Ruff => parse_ruff(linter, root, stdout),
+    }
+}
+
+/// Parse `git status --porcelain=v1 -z` into paths that still exist in the
+/// worktree (deleted files have nothing to lint).
+fn parse_changed_paths(out: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut entries = out.split('\0');
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let (status, path) = entry.split_at(3);
+        let mut codes = status.chars();
+        let (x, y) = (codes.next().unwrap_or(' '), codes.next().unwrap_or(' '));
+        if matches!(x, 'R' | 'C') {
+            // The rename source follows as its own entry.
+            entries.next();
+        }
+        if x == 'D' || y == 'D' {
+            continue;
+        }
+        paths.push(path.to_string());
+    }
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+async fn changed_paths(root: &Path) -> Result<Vec<String>, String> {
+    let (code, out, err) = run_git(
+        root,
+        &["status", "--porcelain=v1", "-z", "--untracked-files=all"],
+    )
+    .await?;
+    if code != 0 {
+        return Err(err.trim().to_string());
+    }
+    Ok(parse_changed_paths(&out))
+}
+
+struct LinterOutput {
+    exit_code: Option<i32>,
+    timed_out: bool,
+    stdout: String,
+    stderr: String,
+}
+
+async fn run_linter(
+    root: &Path,
+    linter: &GitLinterConfig,
+    files: &[String],
+) -> Result<LinterOutput, String> {
+    let mut cmd = Command::new(&linter.command);
+    cmd.args(&linter.args)
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if linter.pass_files {
+        cmd.args(files);
+    }
+    let child = cmd
+        .spawn()
+        .map_err(|err| format!("failed to start '{}': {err}", linter.command))?;
+
+    match tokio::time::timeout(linter.timeout, child.wait_with_output()).await {
+        Ok(Ok(output)) => Ok(LinterOutput {
+            exit_code: output.status.code(),
+            timed_out: false,
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        }),
+        Ok(Err(err)) => Err(err.to_string()),
+        // Dropping the future kills the child (`kill_on_drop`).
+        Err(_) => Ok(LinterOutput {
+            exit_code: None,
+            timed_out: true,
+            stdout: String::new(),
+            stderr: String::new(),
+        }),
+    }
+}
+
+fn now_millis() -> i64 {
+    time::OffsetDateTime::now_utc().unix_timestamp_nanos() as i64 / 1_000_000
+}
+
+fn lint_error(status: StatusCode, code: &str, error: impl Into<String>) -> Response {
+    (
+        status,
+        Json(serde_json::json!({"error": error.into(), "code": code})),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLintBody {
+    /// Run only these linters; defaults to every configured linter.
+    pub linter_ids: Option<Vec<String>>,
+}
+
+/// Run configured linters over the files changed in the worktree and return
+/// unified diagnostics.
+pub async fn git_lint_run(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitLintBody>,
+) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let root = match repository_root(&dir).await {
+        Ok(root) => root,
+        Err(resp) => return *resp,
+    };
+
+    let mut linters = {
+        let guard = state.settings.read().await;
+        parse_linter_configs(guard.extra.get(SETTINGS_KEY))
+    };
+    if let Some(ids) = body.linter_ids.as_ref() {
+        if let Some(unknown) = ids.iter().find(|id| !linters.iter().any(|l| &l.id == *id)) {
+            return lint_error(
+                StatusCode::BAD_REQUEST,
+                "linter_not_found",
+                format!("Linter '{unknown}' is not configured"),
+            );
+        }
+        linters.retain(|l| ids.contains(&l.id));
+    }
+    if linters.is_empty() {
+        return lint_error(
+            StatusCode::BAD_REQUEST,
+            "no_linters_configured",
+            "No linters are configured (settings.gitLinters)",
+        );
+    }
+
+    let changed_files = match changed_paths(&root).await {
+        Ok(paths) => paths,
+        Err(err) => return lint_error(StatusCode::CONFLICT, "git_status_failed", err),
+    };
+    let changed: HashSet<&str> = changed_files.iter().map(String::as_str).collect();
+
+    let mut runs = Vec::new();
+    let mut diagnostics = Vec::new();
+    for linter in &linters {
+        let files: Vec<String> = changed_files
+            .iter()
+            .filter(|p| linter.matches(p))
+            .cloned()
+            .collect();
+        let mut run = GitLinterRun {
+            id: linter.id.clone(),
+            label: linter.label.clone(),
+            format: linter.format,
+            files: files.len(),
+            exit_code: None,
+            timed_out: false,
+            duration_ms: 0,
+            diagnostics: 0,
+            error: None,
+        };
+        if files.is_empty() {
+            runs.push(run);
+            continue;
+        }
+
+        let started = Instant::now();
+        let result = run_linter(&root, linter, &files).await;
+        run.duration_ms = started.elapsed().as_millis() as u64;
+        match result {
+            Ok(output) => {
+                run.exit_code = output.exit_code;
+                run.timed_out = output.timed_out;
+                let found: Vec<GitLintDiagnostic> =
+                    parse_lint_output(linter.format, &linter.id, &root, &output.stdout)
+                        .into_iter()
+                        .filter(|d| changed.contains(d.path.as_str()))
+                        .collect();
+                // A failing exit with nothing parsed means the tool itself broke
+                // (missing config, bad flags), not that it found problems.
+                if found.is_empty() && output.exit_code.is_some_and(|c| c != 0) {
+                    run.error = Some(truncate_for_payload(&output.stderr, MAX_STDERR_CHARS));
+                }
+                run.diagnostics = found.len();
+                diagnostics.extend(found);
+            }
+            Err(err) => run.error = Some(err),
+        }
+        runs.push(run);
+    }
+
+    diagnostics.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
+    let truncated = diagnostics.len() > MAX_DIAGNOSTICS;
+    diagnostics.truncate(MAX_DIAGNOSTICS);
+
+    let root_key = path_slash(&root);
+    let report = GitLintReport {
+        root: root_key.clone(),
+        ran_at: now_millis(),
+        changed_files,
+        linters: runs,
+        diagnostics,
+        truncated,
+    };
+    LAST_REPORTS.insert(root_key, report.clone());
+    Json(report).into_response()
+}
+
+/// The most recent lint report for the repository, or `null` if linters have
+/// not run since the server started.
+pub async fn git_lint_get(Query(q): Query<DirectoryQuery>) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let root: PathBuf = match repository_root(&dir).await {
+        Ok(root) => root,
+        Err(resp) => return *resp,
+    };
+    let report = LAST_REPORTS
+        .get(&path_slash(&root))
+        .map(|entry| entry.value().clone());
+    Json(report).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linter_configs_apply_defaults_and_skip_invalid_entries() {
+        let raw = serde_json::json!([
+            {"id": "eslint", "command": "npx", "args": ["eslint", "-f", "json"], "format": "eslint", "extensions": [".TS", "tsx"]},
+            {"id": "clippy", "command": "cargo", "args": ["clippy", "--message-format=json"], "format": "clippy"},
+            {"id": "broken", "command": "x", "format": "pylint"},
+            {"id": "", "command": "ruff", "format": "ruff"},
+        ]);
+        let configs = parse_linter_configs(Some(&raw));
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].extensions, vec!["ts", "tsx"]);
+        assert!(configs[0].pass_files);
+        assert!(configs[0].matches("web/src/App.TS"));
+        assert!(!configs[0].matches("README.md"));
+        assert!(!configs[1].pass_files);
+        assert_eq!(configs[1].label, "clippy");
+    }
+
+    #[test]
+    fn changed_paths_skip_deletions_and_rename_sources() {
+        let out = " M src/a.rs\0?? web/new.ts\0 D gone.rs\0R  src/b.rs\0src/old_b.rs\0";
+        assert_eq!(
+            parse_changed_paths(out),
+            vec!["src/a.rs", "src/b.rs", "web/new.ts"]
+        );
+    }
+
+    #[test]
+    fn parses_eslint_json() {
+        let root = Path::new("/repo");
+        let out = r#"[{"filePath":"/repo/web/a.ts","messages":[
+            {"ruleId":"no-unused-vars","severity":2,"message":"'x' is unused","line":3,"column":7,"endLine":3,"endColumn":8},
+            {"ruleId":null,"severity":1,"message":"Parsing hint","line":1,"column":1}
+        ]}]"#;
+        let diags = parse_lint_output(GitLintFormat::Eslint, "eslint", root, out);
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].path, "web/a.ts");
+        assert_eq!(diags[0].severity, GitLintSeverity::Error);
+        assert_eq!(diags[0].code.as_deref(), Some("no-unused-vars"));
+        assert_eq!(diags[1].severity, GitLintSeverity::Warning);
+        assert_eq!(diags[1].code, None);
+    }
+
+    #[test]
+    fn parses_clippy_json_lines() {
+        let root = Path::new("/repo");
+        let out = [
+            r#"{"reason":"compiler-artifact","target":{}}"#,
+            r#"{"reason":"compiler-message","message":{"message":"unused variable: `x`","code":{"code":"unused_variables"},"level":"warning","spans":[{"file_name":"src/main.rs","line_start":4,"line_end":4,"column_start":9,"column_end":10,"is_primary":true}]}}"#,
+            r#"{"reason":"compiler-message","message":{"message":"1 warning emitted","code":null,"level":"warning","spans":[]}}"#,
+        ]
+        .join("\n");
+        let diags = parse_lint_output(GitLintFormat::Clippy, "clippy", root, &out);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].path, "src/main.rs");
+        assert_eq!((diags[0].line, diags[0].column), (4, 9));
+        assert_eq!(diags[0].code.as_deref(), Some("unused_variables"));
+    }
+
+    #[test]
+    fn parses_ruff_json() {
+        let root = Path::new("/repo");
+        let out = r#"[
+            {"code":"F401","message":"`os` imported but unused","filename":"/repo/app/main.py","location":{"row":1,"column":8},"end_location":{"row":1,"column":10}},
+            {"code":null,"message":"SyntaxError: Expected an expression","filename":"/repo/app/bad.py","location":{"row":2,"column":1},"end_location":{"row":2,"column":2}}
+        ]"#;
+        let diags = parse_lint_output(GitLintFormat::Ruff, "ruff", root, out);
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].path, "app/main.py");
+        assert_eq!(diags[0].severity, GitLintSeverity::Warning);
+        assert_eq!(diags[1].severity, GitLintSeverity::Error);
+        assert_eq!(diags[1].code, None);
+    }
+}
@@ -0,0 +1,306 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use super::abs_path;
+
+const LOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_MAX_HOLD: Duration = Duration::from_secs(120);
+
+// VS Code queues git operations per repository. Do the same server-side so we don't
+// race on the index/worktree (and to reduce index.lock errors under rapid UI clicks).
+static REPO_LOCKS: OnceLock<DashMap<String, Arc<RepoLock>>> = OnceLock::new();
+static NEXT_HOLD_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone)]
+struct LockHolder {
+    id: u64,
+    operation: &'static str,
+    acquired: Instant,
+    acquired_at: u64,
+}
+
+#[derive(Default)]
+struct RepoLock {
+    mutex: Arc<Mutex<()>>,
+    holder: std::sync::Mutex<Option<LockHolder>>,
+    waiting: AtomicUsize,
+}
+
+impl RepoLock {
+    fn holder(&self) -> Option<LockHolder> {
+        self.holder.lock().ok().and_then(|h| h.clone())
+    }
+
+    fn clear_holder(&self, id: u64) {
+        if let Ok(mut holder) = self.holder.lock()
+            && holder.as_ref().is_some_and(|h| h.id == id)
+        {
+            *holder = None;
+        }
+    }
+}
+
+/// Held for the duration of a mutating git operation on one repository.
+pub(crate) struct RepoLockGuard {
+    _guard: tokio::sync::OwnedMutexGuard<()>,
+    lock: Arc<RepoLock>,
+    id: u64,
+}
+
+impl Drop for RepoLockGuard {
+    fn drop(&mut self) {
+        let held = self.lock.holder().filter(|h| h.id == self.id);
+        if let Some(holder) = held {
+            let elapsed = holder.acquired.elapsed();
+            if elapsed > max_hold() {
+                tracing::warn!(
+                    git_operation = holder.operation,
+                    held_ms = elapsed.as_millis() as u64,
+                    "repository lock released after exceeding max hold"
+                );
+            }
+        }
+        self.lock.clear_holder(self.id);
+    }
+}
+
+fn max_hold() -> Duration {
+    if let Ok(v) = std::env::var("OPENCODE_STUDIO_GIT_LOCK_MAX_HOLD_MS")
+        && let Ok(ms) = v.trim().parse::<u64>()
+        && ms > 0
+    {
+        return Duration::from_millis(ms);
+    }
+    DEFAULT_MAX_HOLD
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn repo_lock_key(dir: &Path) -> String {
+    dir.to_string_lossy().to_string()
+}
+
+fn locks() -> &'static DashMap<String, Arc<RepoLock>> {
+    REPO_LOCKS.get_or_init(DashMap::new)
+}
+
+/// Serialize mutating git operations per repository. `operation` names the
+/// holder in `/api/git/locks` and in max-hold warnings.
+pub(crate) async fn lock_repo(
+    dir: &Path,
+    operation: &'static str,
+) -> Result<RepoLockGuard, Response> {
+    let key = repo_lock_key(dir);
+    let lock = locks().entry(key.clone()).or_default().value().clone();
+
+    lock.waiting.fetch_add(1, Ordering::Relaxed);
+    let acquired = tokio::time::timeout(LOCK_WAIT_TIMEOUT, lock.mutex.clone().lock_owned()).await;
+    lock.waiting.fetch_sub(1, Ordering::Relaxed);
+
+    let Ok(guard) = acquired else {
+        let holder = lock.holder();
+        return Err((
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": "Repository is busy running another git operation",
+                "code": "git_busy",
+                "hint": "Wait for the current operation to finish, then retry.",
+                "holder": holder.map(|h| h.operation),
+            })),
+        )
+            .into_response());
+    };
+
+    let id = NEXT_HOLD_ID.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut holder) = lock.holder.lock() {
+        *holder = Some(LockHolder {
+            id,
+            operation,
+            acquired: Instant::now(),
+            acquired_at: now_millis(),
+        });
+    }
+    spawn_max_hold_watchdog(key, lock.clone(), id);
+
+    Ok(RepoLockGuard {
+        _guard: guard,
+        lock,
+        id,
+    })
+}
+
+/// Warn once while the operation is still running, so a stuck holder shows up
+/// in logs before it is released (or never is).
+fn spawn_max_hold_watchdog(directory: String, lock: Arc<RepoLock>, id: u64) {
+    let limit = max_hold();
+    tokio::spawn(async move {
+        tokio::time::sleep(limit).await;
+        if let Some(holder) = lock.holder().filter(|h| h.id == id) {
+            tracing::warn!(
+                directory = %directory,
+                git_operation = holder.operation,
+                held_ms = holder.acquired.elapsed().as_millis() as u64,
+                waiting = lock.waiting.load(Ordering::Relaxed),
+                "repository lock held longer than max hold"
+            );
+        }
+    });
+}
+
+/// Repositories with a git operation currently holding the per-repo lock.
+pub(crate) fn busy_repo_directories() -> Vec<String> {
+    let Some(locks) = REPO_LOCKS.get() else {
+        return Vec::new();
+    };
+    let mut out: Vec<String> = locks
+        .iter()
+        .filter(|entry| entry.value().holder().is_some())
+        .map(|entry| entry.key().clone())
+        .collect();
+    out.sort();
+    out
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRepoLockInfo {
+    pub directory: String,
+    pub operation: Option<String>,
+    pub acquired_at: Option<u64>,
+    pub held_ms: Option<u64>,
+    pub waiting: usize,
+    pub stale: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRepoLocksResponse {
+    pub max_hold_ms: u64,
+    pub locks: Vec<GitRepoLockInfo>,
+}
+
+fn lock_info(directory: &str, lock: &RepoLock, limit: Duration) -> GitRepoLockInfo {
+    let holder = lock.holder();
+    let held = holder.as_ref().map(|h| h.acquired.elapsed());
+    GitRepoLockInfo {
+        directory: directory.to_string(),
+        operation: holder.as_ref().map(|h| h.operation.to_string()),
+        acquired_at: holder.as_ref().map(|h| h.acquired_at),
+        held_ms: held.map(|d| d.as_millis() as u64),
+        waiting: lock.waiting.load(Ordering::Relaxed),
+        stale: held.is_some_and(|d| d > limit),
+    }
+}
+
+/// Locks that are held or have operations queued behind them.
+pub async fn git_locks_list() -> Json<GitRepoLocksResponse> {
+    let limit = max_hold();
+    let mut out: Vec<GitRepoLockInfo> = locks()
+        .iter()
+        .map(|entry| lock_info(entry.key(), entry.value(), limit))
+        .filter(|info| info.operation.is_some() || info.waiting > 0)
+        .collect();
+    out.sort_by(|a, b| a.directory.cmp(&b.directory));
+    Json(GitRepoLocksResponse {
+        max_hold_ms: limit.as_millis() as u64,
+        locks: out,
+    })
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitLockReleaseBody {
+    pub directory: String,
+}
+
+/// Detach a stuck holder: later operations get a fresh lock while the old
+/// holder (if it ever finishes) releases a lock nobody waits on. Operations
+/// already queued on the old lock still wait for it.
+pub(crate) async fn git_locks_release(Json(body): Json<GitLockReleaseBody>) -> Response {
+    let key = repo_lock_key(&abs_path(body.directory.trim()));
+    let Some((_, lock)) = locks().remove(&key) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": "No lock is held for this repository",
+                "code": "git_lock_not_found",
+            })),
+        )
+            .into_response();
+    };
+
+    let info = lock_info(&key, &lock, max_hold());
+    // The audit log records who did it; this keeps what was interrupted.
+    tracing::warn!(
+        directory = %key,
+        git_operation = info.operation.as_deref().unwrap_or(""),
+        held_ms = info.held_ms.unwrap_or(0),
+        waiting = info.waiting,
+        "repository lock force-released"
+    );
+    Json(info).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn lock_records_holder_until_dropped() {
+        let dir = Path::new("/tmp/opencode-studio-lock-test-holder");
+        let guard = lock_repo(dir, "commit").await.expect("lock");
+        let listed = git_locks_list().await.0;
+        let entry = listed
+            .locks
+            .iter()
+            .find(|l| l.directory == repo_lock_key(dir))
+            .expect("held lock listed");
+        assert_eq!(entry.operation.as_deref(), Some("commit"));
+        assert!(busy_repo_directories().contains(&repo_lock_key(dir)));
+
+        drop(guard);
+        assert!(!busy_repo_directories().contains(&repo_lock_key(dir)));
+    }
+
+    #[tokio::test]
+    async fn force_release_lets_new_operations_proceed() {
+        let dir = Path::new("/tmp/opencode-studio-lock-test-release");
+        let stuck = lock_repo(dir, "push").await.expect("lock");
+
+        let resp = git_locks_release(Json(GitLockReleaseBody {
+            directory: dir.to_string_lossy().to_string(),
+        }))
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let next = tokio::time::timeout(Duration::from_secs(1), lock_repo(dir, "fetch"))
+            .await
+            .expect("fresh lock is not blocked")
+            .expect("lock");
+        drop(stuck);
+        let listed = git_locks_list().await.0;
+        let entry = listed
+            .locks
+            .iter()
+            .find(|l| l.directory == repo_lock_key(dir))
+            .expect("new holder listed");
+        assert_eq!(entry.operation.as_deref(), Some("fetch"));
+        drop(next);
+    }
+}
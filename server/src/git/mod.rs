@@ -5,23 +5,38 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 mod auth;
+mod auto_fetch;
+mod bisect;
 mod blame;
+mod branch_compare;
 mod branches;
 mod commit;
+mod commit_message;
+mod conflicts;
 mod diff;
+mod dry_run;
 mod exec;
 mod gpg;
+mod graph;
 mod history;
+mod identity;
 mod ignore;
 mod lfs;
+mod lint;
+mod locks;
 mod ops;
 mod policy;
+mod rebase;
 mod remote;
 mod repos;
+mod sequencer;
+mod size_advisor;
 mod status;
+mod status_events;
 mod submodule;
 mod utils;
 mod worktrees;
@@ -31,7 +46,7 @@ mod regression_tests;
 
 pub(crate) const MAX_BLOB_BYTES: usize = 50 * 1024 * 1024;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct DirectoryQuery {
     pub directory: Option<String>,
 }
@@ -58,31 +73,57 @@ pub(crate) fn require_directory(q: &DirectoryQuery) -> Result<PathBuf, Box<Respo
 
 // Shared helpers/types re-exported for submodules.
 pub use auth::GitAuthInput;
-pub(crate) use auth::{TempGitAskpass, git_http_auth_env, normalize_http_auth};
+pub(crate) use auth::{TempGitAskpass, git_http_auth_env, http_auth_for_url, resolve_http_auth};
+pub(crate) use auto_fetch::spawn_auto_fetch_task;
+pub use bisect::*;
 pub use blame::*;
 
-pub(crate) use exec::{lock_repo, run_git, run_git_env, run_git_with_input};
+pub(crate) use dry_run::{
+    GitDryRunPreview, list_commits, list_uncommitted_tracked_paths, parse_clean_dry_run_output,
+    rev_parse_commit,
+};
+pub(crate) use exec::{
+    git_stdout, run_git, run_git_env, run_git_with_input, spawn_git_piped, spawn_git_piped_stdin,
+};
+pub(crate) use identity::resolve_git_identity;
+pub(crate) use locks::{busy_repo_directories, git_locks_release, lock_repo};
 pub(crate) use policy::{
     GitBranchProtectionPrompt, git_allow_force_push, git_allow_no_verify_commit,
     git_branch_protection_for_branch, git_enforce_branch_protection, git_strict_patch_validation,
 };
+pub(crate) use sequencer::{
+    SequencerOperation, sequencer_conflict_report, sequencer_conflict_response,
+};
+pub(crate) use status_events::announce_status_change;
 
 pub(crate) use utils::{
-    abs_path, git_config_get, git2_open_error_response, is_safe_repo_rel_path, map_git_failure,
-    path_slash, redact_git_output, rel_path_slash, truncate_for_payload,
+    abs_path, git_config_get, git_stdout_checked, git2_open_error_response, is_safe_repo_rel_path,
+    map_git_failure, path_slash, redact_git_output, rel_path_slash, sse_json, truncate_for_payload,
 };
 
 // Public HTTP handlers.
+pub use branch_compare::*;
 pub use branches::*;
 pub use commit::*;
+pub use commit_message::{GitCommitMessageBody, git_commit_message};
+pub use conflicts::*;
 pub use diff::*;
 pub use gpg::*;
+pub use graph::*;
 pub use history::*;
+pub use identity::{GitIdentitySetBody, git_identity_get, git_identity_set};
 pub use ignore::*;
 pub use lfs::*;
+pub use lint::{GitLintBody, git_lint_get, git_lint_run};
+pub use locks::{GitLockReleaseBody, GitRepoLocksResponse, git_locks_list};
 pub use ops::*;
+pub use rebase::{
+    GitRebaseInteractiveBody, GitRebasePlanQuery, git_rebase_interactive, git_rebase_plan,
+    git_rebase_status,
+};
 pub use remote::*;
 pub use repos::*;
+pub use size_advisor::*;
 pub use status::*;
 pub use submodule::*;
 pub use worktrees::*;
@@ -4,11 +4,12 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use super::super::{DirectoryQuery, lock_repo, map_git_failure, require_directory, run_git};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitAbortBody {
     // reserved for future
     pub _dummy: Option<bool>,
@@ -23,7 +24,7 @@ pub async fn git_merge_abort(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "merge-abort").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -53,7 +54,7 @@ pub async fn git_rebase_abort(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "rebase-abort").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -83,7 +84,7 @@ pub async fn git_cherry_pick_abort(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "cherry-pick-abort").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -114,7 +115,7 @@ pub async fn git_revert_abort(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "revert-abort").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -4,11 +4,15 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::Deserialize;
 
-use super::super::{DirectoryQuery, lock_repo, map_git_failure, require_directory, run_git};
+use super::super::{
+    DirectoryQuery, SequencerOperation, lock_repo, map_git_failure, require_directory, run_git,
+    sequencer_conflict_report, sequencer_conflict_response,
+};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitContinueBody {
     pub _dummy: Option<bool>,
 }
@@ -22,7 +26,7 @@ pub async fn git_rebase_continue(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "rebase-continue").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -53,7 +57,7 @@ pub async fn git_rebase_skip(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "rebase-skip").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -83,7 +87,7 @@ pub async fn git_cherry_pick_continue(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "cherry-pick-continue").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -91,6 +95,10 @@ pub async fn git_cherry_pick_continue(
         .await
         .unwrap_or((1, "".to_string(), "".to_string()));
     if code != 0 {
+        let op = SequencerOperation::CherryPick;
+        if let Some(report) = sequencer_conflict_report(&dir, op).await {
+            return sequencer_conflict_response(op, report);
+        }
         if let Some(resp) = map_git_failure(code, &out, &err) {
             return resp;
         }
@@ -112,7 +120,7 @@ pub async fn git_cherry_pick_skip(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "cherry-pick-skip").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -122,6 +130,10 @@ pub async fn git_cherry_pick_skip(
         "".to_string(),
     ));
     if code != 0 {
+        let op = SequencerOperation::CherryPick;
+        if let Some(report) = sequencer_conflict_report(&dir, op).await {
+            return sequencer_conflict_response(op, report);
+        }
         if let Some(resp) = map_git_failure(code, &out, &err) {
             return resp;
         }
@@ -143,7 +155,7 @@ pub async fn git_revert_continue(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "revert-continue").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -153,6 +165,10 @@ pub async fn git_revert_continue(
         "".to_string(),
     ));
     if code != 0 {
+        let op = SequencerOperation::Revert;
+        if let Some(report) = sequencer_conflict_report(&dir, op).await {
+            return sequencer_conflict_response(op, report);
+        }
         if let Some(resp) = map_git_failure(code, &out, &err) {
             return resp;
         }
@@ -174,7 +190,7 @@ pub async fn git_revert_skip(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "revert-skip").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -183,6 +199,10 @@ pub async fn git_revert_skip(
             .await
             .unwrap_or((1, "".to_string(), "".to_string()));
     if code != 0 {
+        let op = SequencerOperation::Revert;
+        if let Some(report) = sequencer_conflict_report(&dir, op).await {
+            return sequencer_conflict_response(op, report);
+        }
         if let Some(resp) = map_git_failure(code, &out, &err) {
             return resp;
         }
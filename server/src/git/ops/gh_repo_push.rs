@@ -8,6 +8,7 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tokio::process::Command;
 
@@ -16,7 +17,7 @@ use super::super::{DirectoryQuery, lock_repo, map_git_failure, require_directory
 
 const GH_TIMEOUT: Duration = Duration::from_secs(45);
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitCreateGithubRepoAndPushBody {
     pub name: Option<String>,
     pub remote: Option<String>,
@@ -145,7 +146,7 @@ pub async fn git_create_github_repo_and_push(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "create-github-repo-and-push").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
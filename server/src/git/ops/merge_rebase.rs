@@ -4,11 +4,17 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::Deserialize;
+use std::path::Path;
 
-use super::super::{DirectoryQuery, lock_repo, map_git_failure, require_directory, run_git};
+use super::super::remote::git_current_branch;
+use super::super::{
+    DirectoryQuery, GitDryRunPreview, list_commits, list_uncommitted_tracked_paths, lock_repo,
+    map_git_failure, require_directory, rev_parse_commit, run_git,
+};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitMergeBody {
     pub branch: Option<String>,
 }
@@ -22,7 +28,7 @@ pub async fn git_merge(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "merge").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -57,9 +63,11 @@ pub async fn git_merge(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitRebaseBody {
     pub branch: Option<String>,
+    #[serde(default, rename = "dryRun")]
+    pub dry_run: Option<bool>,
 }
 
 pub async fn git_rebase(
@@ -71,7 +79,7 @@ pub async fn git_rebase(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "rebase").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -89,6 +97,10 @@ pub async fn git_rebase(
             .into_response();
     };
 
+    if body.dry_run.unwrap_or(false) {
+        return git_rebase_dry_run(&dir, branch).await;
+    }
+
     let (code, out, err) =
         run_git(&dir, &["rebase", branch])
             .await
@@ -106,3 +118,45 @@ pub async fn git_rebase(
 
     Json(serde_json::json!({"success": true})).into_response()
 }
+
+async fn git_rebase_dry_run(dir: &Path, branch: &str) -> Response {
+    let Some(onto) = rev_parse_commit(dir, branch).await else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Unknown branch", "code": "invalid_branch"})),
+        )
+            .into_response();
+    };
+    let head = rev_parse_commit(dir, "HEAD").await;
+
+    let mut preview = GitDryRunPreview::new("rebase");
+    let (missing, _) = list_commits(dir, &onto, &["HEAD"]).await;
+    if missing.is_empty() {
+        // HEAD already contains the target; git reports "up to date".
+        return preview.into_response();
+    }
+
+    let (rewritten, truncated) = list_commits(dir, "HEAD", &[onto.as_str()]).await;
+    let head_ref = git_current_branch(dir)
+        .await
+        .map(|b| format!("refs/heads/{b}"))
+        .unwrap_or_else(|| "HEAD".to_string());
+    // With nothing to replay the branch simply fast-forwards onto the target.
+    let new_tip = if rewritten.is_empty() {
+        Some(onto)
+    } else {
+        None
+    };
+    preview.commits_rewritten = rewritten;
+    preview.truncated = truncated;
+    preview.move_ref(head_ref, head, new_tip);
+
+    let (dirty, _) = list_uncommitted_tracked_paths(dir).await;
+    if !dirty.is_empty() {
+        preview
+            .warnings
+            .push("Working tree has uncommitted changes; rebase will refuse to start.".to_string());
+    }
+
+    preview.into_response()
+}
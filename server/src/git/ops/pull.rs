@@ -4,14 +4,15 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::super::{
     DirectoryQuery, GitAuthInput, TempGitAskpass, git_http_auth_env, lock_repo, map_git_failure,
-    normalize_http_auth, require_directory, run_git_env,
+    require_directory, resolve_http_auth, run_git_env,
 };
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitPullBody {
     pub remote: Option<String>,
     pub branch: Option<String>,
@@ -84,7 +85,7 @@ pub async fn git_pull(Query(q): Query<DirectoryQuery>, Json(body): Json<GitPullB
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "pull").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -109,7 +110,7 @@ pub async fn git_pull(Query(q): Query<DirectoryQuery>, Json(body): Json<GitPullB
     let mut args: Vec<String> = Vec::new();
     let mut extra_env: Vec<(String, String)> = Vec::new();
     let mut _askpass: Option<TempGitAskpass> = None;
-    if let Some((u, p)) = body.auth.as_ref().and_then(normalize_http_auth) {
+    if let Some((u, p)) = resolve_http_auth(&dir, remote, body.auth.as_ref()).await {
         match git_http_auth_env(&u, &p).await {
             Ok((prefix, env, guard)) => {
                 args.extend(prefix);
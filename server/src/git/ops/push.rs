@@ -4,17 +4,20 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::sync::Arc;
 
-use super::super::remote::git_current_branch;
+use super::super::remote::{git_current_branch, git_upstream_ref};
 use super::super::{
-    DirectoryQuery, GitAuthInput, GitBranchProtectionPrompt, TempGitAskpass, git_allow_force_push,
-    git_branch_protection_for_branch, git_enforce_branch_protection, git_http_auth_env, lock_repo,
-    map_git_failure, normalize_http_auth, require_directory, run_git_env,
+    DirectoryQuery, GitAuthInput, GitBranchProtectionPrompt, GitDryRunPreview, TempGitAskpass,
+    git_allow_force_push, git_branch_protection_for_branch, git_enforce_branch_protection,
+    git_http_auth_env, list_commits, lock_repo, map_git_failure, require_directory,
+    resolve_http_auth, rev_parse_commit, run_git_env,
 };
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitPushBody {
     pub remote: Option<String>,
     pub branch: Option<String>,
@@ -29,6 +32,8 @@ pub struct GitPushBody {
     pub set_upstream: Option<bool>,
     #[serde(default)]
     pub auth: Option<GitAuthInput>,
+    #[serde(default, rename = "dryRun")]
+    pub dry_run: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
@@ -82,7 +87,7 @@ pub async fn git_push(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "push").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -160,7 +165,7 @@ pub async fn git_push(
     let mut auth_opts: Vec<String> = Vec::new();
     let mut extra_env: Vec<(String, String)> = Vec::new();
     let mut _askpass: Option<TempGitAskpass> = None;
-    if let Some((u, p)) = body.auth.as_ref().and_then(normalize_http_auth) {
+    if let Some((u, p)) = resolve_http_auth(&dir, remote, body.auth.as_ref()).await {
         match git_http_auth_env(&u, &p).await {
             Ok((prefix, env, guard)) => {
                 auth_opts = prefix;
@@ -240,6 +245,11 @@ pub async fn git_push(
         args.push(cur);
     }
 
+    if body.dry_run.unwrap_or(false) {
+        // Preview from local remote-tracking refs; never contacts the remote.
+        return git_push_dry_run(&dir, remote, rf.or(branch), &force).await;
+    }
+
     let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
     let env_ref: Vec<(&str, &str)> = extra_env
         .iter()
@@ -324,3 +334,90 @@ pub async fn git_push(
     })
     .into_response()
 }
+
+fn parse_remote_branch_from_refspec(spec: &str) -> Option<String> {
+    let spec = spec.trim().trim_start_matches('+');
+    let remote_side = match spec.split_once(':') {
+        Some((_, right)) => right.trim(),
+        None => return parse_local_branch_from_refspec(spec),
+    };
+    let name = remote_side
+        .strip_prefix("refs/heads/")
+        .unwrap_or(remote_side)
+        .trim();
+    if name.is_empty() || name.starts_with("refs/") || name.contains('*') {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+async fn git_push_dry_run(
+    dir: &Path,
+    remote: Option<&str>,
+    spec: Option<&str>,
+    force: &str,
+) -> Response {
+    let local_branch = match spec.and_then(parse_local_branch_from_refspec) {
+        Some(b) => Some(b),
+        None => git_current_branch(dir).await,
+    };
+    let Some(local_branch) = local_branch else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Cannot preview push from a detached HEAD",
+                "code": "git_detached_head",
+            })),
+        )
+            .into_response();
+    };
+    let local_ref = format!("refs/heads/{local_branch}");
+    let local_sha = rev_parse_commit(dir, &local_ref).await;
+
+    let upstream = if spec.is_none() {
+        git_upstream_ref(dir).await
+    } else {
+        None
+    };
+    let remote_ref = match (upstream, remote) {
+        (Some(up), None) => format!("refs/remotes/{up}"),
+        (_, remote) => {
+            let remote_branch = spec
+                .and_then(parse_remote_branch_from_refspec)
+                .unwrap_or_else(|| local_branch.clone());
+            format!(
+                "refs/remotes/{}/{remote_branch}",
+                remote.unwrap_or("origin")
+            )
+        }
+    };
+    let remote_sha = rev_parse_commit(dir, &remote_ref).await;
+
+    let mut preview = GitDryRunPreview::new("push");
+    preview.warnings.push(format!(
+        "Preview is based on {remote_ref} as of the last fetch."
+    ));
+
+    let mut rejected = false;
+    if remote_sha.is_some() {
+        let (dropped, truncated) = list_commits(dir, &remote_ref, &[local_ref.as_str()]).await;
+        if !dropped.is_empty() {
+            if force.is_empty() {
+                rejected = true;
+                preview.warnings.push(
+                    "Remote has commits missing locally; push would be rejected without force."
+                        .to_string(),
+                );
+            } else {
+                preview.commits_dropped = dropped;
+                preview.truncated = truncated;
+            }
+        }
+    }
+
+    if !rejected {
+        preview.move_ref(remote_ref, remote_sha, local_sha);
+    }
+
+    preview.into_response()
+}
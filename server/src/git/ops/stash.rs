@@ -4,6 +4,7 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::super::{
@@ -61,7 +62,7 @@ pub async fn git_stash_list(Query(q): Query<DirectoryQuery>) -> Response {
     Json(GitStashListResponse { stashes }).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitStashPushBody {
     pub message: Option<String>,
@@ -79,7 +80,7 @@ pub async fn git_stash_push(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "stash-push").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -133,7 +134,7 @@ pub async fn git_stash_push(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitStashShowQuery {
     pub directory: Option<String>,
     pub r#ref: Option<String>,
@@ -180,7 +181,7 @@ pub async fn git_stash_show(Query(q): Query<GitStashShowQuery>) -> Response {
     .into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitStashRefBody {
     pub r#ref: Option<String>,
@@ -217,7 +218,7 @@ pub async fn git_stash_apply(
             .into_response();
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "stash-apply").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -254,7 +255,7 @@ pub async fn git_stash_pop(
             .into_response();
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "stash-pop").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -291,7 +292,7 @@ pub async fn git_stash_drop(
             .into_response();
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "stash-drop").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -318,7 +319,7 @@ pub async fn git_stash_drop_all(Query(q): Query<DirectoryQuery>) -> Response {
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "stash-drop-all").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -360,7 +361,7 @@ pub async fn git_stash_drop_all(Query(q): Query<DirectoryQuery>) -> Response {
     Json(serde_json::json!({"success": true, "cleared": cleared})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitStashBranchBody {
     pub branch: Option<String>,
@@ -395,7 +396,7 @@ pub async fn git_stash_branch(
             .into_response();
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "stash-branch").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
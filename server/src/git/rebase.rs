@@ -0,0 +1,846 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use axum::{
+    Json,
+    extract::Query,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::dry_run::{GitDryRunCommit, parse_commit_records};
+use super::{
+    DirectoryQuery, lock_repo, map_git_failure, require_directory, require_directory_raw,
+    rev_parse_commit, run_git, run_git_env,
+};
+
+const PLAN_MAX_COMMITS: usize = 500;
+// Reword/squash messages and the generated todo live here until the rebase
+// finishes, since `exec` steps may run after a conflict stop + continue.
+const PLAN_DIR: &str = "opencode-studio-rebase";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GitRebaseAction {
+    Pick,
+    Reword,
+    Squash,
+    Fixup,
+    Drop,
+}
+
+impl GitRebaseAction {
+    fn folds_into_previous(self) -> bool {
+        matches!(self, Self::Squash | Self::Fixup)
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitRebasePlanQuery {
+    pub directory: Option<String>,
+    pub onto: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRebasePlanCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub subject: String,
+    pub author: String,
+    pub action: GitRebaseAction,
+    /// Commit a `fixup!`/`squash!` commit was matched to, if any.
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRebasePlanResponse {
+    pub onto: String,
+    pub head: String,
+    pub commits: Vec<GitRebasePlanCommit>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct GitRebaseStep {
+    pub hash: String,
+    pub action: GitRebaseAction,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitRebaseInteractiveBody {
+    pub onto: Option<String>,
+    /// HEAD the plan was built against; a moved HEAD rejects the plan.
+    pub head: Option<String>,
+    #[serde(default)]
+    pub steps: Vec<GitRebaseStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRebaseTodoItem {
+    pub action: String,
+    pub hash: Option<String>,
+    pub subject: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRebaseStatusResponse {
+    pub in_progress: bool,
+    pub interactive: bool,
+    pub head_name: Option<String>,
+    pub onto: Option<String>,
+    pub orig_head: Option<String>,
+    pub current_step: Option<usize>,
+    pub total_steps: Option<usize>,
+    pub stopped_at: Option<String>,
+    pub done: Vec<GitRebaseTodoItem>,
+    pub remaining: Vec<GitRebaseTodoItem>,
+    pub conflicted_files: Vec<String>,
+}
+
+fn error_response(status: StatusCode, code: &str, error: impl Into<String>) -> Response {
+    (
+        status,
+        Json(serde_json::json!({"error": error.into(), "code": code})),
+    )
+        .into_response()
+}
+
+async fn git_path(dir: &Path, name: &str) -> Option<PathBuf> {
+    let (code, out, _) = run_git(dir, &["rev-parse", "--git-path", name])
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    let raw = out.trim();
+    if code != 0 || raw.is_empty() {
+        return None;
+    }
+    let p = PathBuf::from(raw);
+    Some(if p.is_absolute() { p } else { dir.join(p) })
+}
+
+async fn read_trimmed(path: &Path) -> Option<String> {
+    let raw = tokio::fs::read_to_string(path).await.ok()?;
+    let v = raw.trim();
+    (!v.is_empty()).then(|| v.to_string())
+}
+
+/// Commits `git rebase -i <onto>` would replay, oldest first.
+async fn list_plan_commits(dir: &Path, onto: &str) -> Result<Vec<GitDryRunCommit>, Response> {
+    let max_count = format!("--max-count={}", PLAN_MAX_COMMITS + 1);
+    let range = format!("{onto}..HEAD");
+    let (code, out, err) = run_git(
+        dir,
+        &[
+            "log",
+            "--no-merges",
+            "--format=%H%x1f%h%x1f%s%x1f%an%x1e",
+            &max_count,
+            &range,
+            "--",
+        ],
+    )
+    .await
+    .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        if let Some(resp) = map_git_failure(code, &out, &err) {
+            return Err(resp);
+        }
+        return Err(error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "git_rebase_plan_failed",
+            err.trim(),
+        ));
+    }
+    let mut commits = parse_commit_records(&out);
+    if commits.len() > PLAN_MAX_COMMITS {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "rebase_plan_too_large",
+            format!("More than {PLAN_MAX_COMMITS} commits to rebase; pick a closer base"),
+        ));
+    }
+    commits.reverse();
+    Ok(commits)
+}
+
+fn autosquash_target(subject: &str) -> Option<(GitRebaseAction, &str)> {
+    let (action, mut rest) = if let Some(rest) = subject.strip_prefix("fixup! ") {
+        (GitRebaseAction::Fixup, rest)
+    } else if let Some(rest) = subject.strip_prefix("squash! ") {
+        (GitRebaseAction::Squash, rest)
+    } else {
+        return None;
+    };
+    // `fixup! fixup! x` targets `x`, like git's autosquash.
+    while let Some(next) = rest
+        .strip_prefix("fixup! ")
+        .or_else(|| rest.strip_prefix("squash! "))
+    {
+        rest = next;
+    }
+    let rest = rest.trim();
+    (!rest.is_empty()).then_some((action, rest))
+}
+
+/// Propose `pick` for every commit, moving `fixup!`/`squash!` commits after
+/// the commit they name (exact subject first, then subject or hash prefix).
+fn propose_plan(commits: Vec<GitDryRunCommit>) -> Vec<GitRebasePlanCommit> {
+    let mut out: Vec<GitRebasePlanCommit> = Vec::with_capacity(commits.len());
+    for c in commits {
+        let matched = autosquash_target(&c.subject).and_then(|(action, needle)| {
+            let candidates = || out.iter().filter(|p| p.target.is_none());
+            candidates()
+                .find(|p| p.subject == needle)
+                .or_else(|| candidates().find(|p| p.subject.starts_with(needle)))
+                .or_else(|| {
+                    (needle.len() >= 4 && needle.chars().all(|ch| ch.is_ascii_hexdigit()))
+                        .then(|| candidates().find(|p| p.hash.starts_with(needle)))
+                        .flatten()
+                })
+                .map(|p| (action, p.hash.clone()))
+        });
+        let entry = |action, target| GitRebasePlanCommit {
+            hash: c.hash.clone(),
+            short_hash: c.short_hash.clone(),
+            subject: c.subject.clone(),
+            author: c.author.clone(),
+            action,
+            target,
+        };
+        match matched {
+            Some((action, target)) => {
+                let mut at = out.iter().position(|p| p.hash == target).unwrap_or(0) + 1;
+                while out
+                    .get(at)
+                    .is_some_and(|p| p.target.as_deref() == Some(target.as_str()))
+                {
+                    at += 1;
+                }
+                out.insert(at, entry(action, Some(target)));
+            }
+            None => out.push(entry(GitRebaseAction::Pick, None)),
+        }
+    }
+    out
+}
+
+/// Check a submitted plan against the commits that would be replayed.
+/// Returns `(status, code, message)` on rejection.
+fn validate_steps(
+    planned: &[String],
+    steps: &[GitRebaseStep],
+) -> Result<(), (StatusCode, &'static str, String)> {
+    let invalid = |msg: String| (StatusCode::BAD_REQUEST, "invalid_rebase_plan", msg);
+    if steps.is_empty() {
+        return Err(invalid("steps are required".to_string()));
+    }
+
+    let mut seen: HashSet<&str> = HashSet::new();
+    for step in steps {
+        if !seen.insert(step.hash.as_str()) {
+            return Err(invalid(format!(
+                "Commit {} appears more than once",
+                step.hash
+            )));
+        }
+        let has_message = step
+            .message
+            .as_deref()
+            .is_some_and(|m| !m.trim().is_empty());
+        match step.action {
+            GitRebaseAction::Reword if !has_message => {
+                return Err(invalid(format!("reword of {} needs a message", step.hash)));
+            }
+            GitRebaseAction::Pick | GitRebaseAction::Fixup | GitRebaseAction::Drop
+                if step.message.is_some() =>
+            {
+                return Err(invalid(format!(
+                    "message is only allowed for reword or squash ({})",
+                    step.hash
+                )));
+            }
+            _ => {}
+        }
+    }
+
+    let planned_set: HashSet<&str> = planned.iter().map(String::as_str).collect();
+    if seen != planned_set {
+        return Err((
+            StatusCode::CONFLICT,
+            "rebase_plan_stale",
+            "Plan does not match the commits to rebase; reload the plan".to_string(),
+        ));
+    }
+
+    if let Some(first) = steps.iter().find(|s| s.action != GitRebaseAction::Drop)
+        && first.action.folds_into_previous()
+    {
+        return Err(invalid(
+            "The first kept commit cannot be squash or fixup".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Render the todo list. Rewording amends right after the pick; a squash
+/// message replaces the combined message once its group is complete.
+fn build_todo(steps: &[GitRebaseStep], plan_dir: &Path) -> (String, Vec<(PathBuf, String)>) {
+    let mut todo = String::new();
+    let mut messages: Vec<(PathBuf, String)> = Vec::new();
+    let mut pending: Option<PathBuf> = None;
+
+    let amend = |todo: &mut String, path: &Path| {
+        todo.push_str(&format!(
+            "exec git commit --amend --allow-empty -F {}\n",
+            sh_quote(&path.to_string_lossy())
+        ));
+    };
+
+    for (i, step) in steps.iter().enumerate() {
+        let message_path = || plan_dir.join(format!("{i}.msg"));
+        let message = step.message.as_deref().filter(|m| !m.trim().is_empty());
+        if matches!(step.action, GitRebaseAction::Pick | GitRebaseAction::Reword)
+            && let Some(path) = pending.take()
+        {
+            amend(&mut todo, &path);
+        }
+        match step.action {
+            GitRebaseAction::Pick => todo.push_str(&format!("pick {}\n", step.hash)),
+            GitRebaseAction::Drop => todo.push_str(&format!("drop {}\n", step.hash)),
+            GitRebaseAction::Fixup => todo.push_str(&format!("fixup {}\n", step.hash)),
+            GitRebaseAction::Reword => {
+                todo.push_str(&format!("pick {}\n", step.hash));
+                if let Some(msg) = message {
+                    let path = message_path();
+                    amend(&mut todo, &path);
+                    messages.push((path, msg.to_string()));
+                }
+            }
+            GitRebaseAction::Squash => {
+                todo.push_str(&format!("squash {}\n", step.hash));
+                if let Some(msg) = message {
+                    let path = message_path();
+                    messages.push((path.clone(), msg.to_string()));
+                    pending = Some(path);
+                }
+            }
+        }
+    }
+    if let Some(path) = pending {
+        amend(&mut todo, &path);
+    }
+    (todo, messages)
+}
+
+fn expand_todo_command(cmd: &str) -> &str {
+    match cmd {
+        "p" => "pick",
+        "r" => "reword",
+        "e" => "edit",
+        "s" => "squash",
+        "f" => "fixup",
+        "x" => "exec",
+        "b" => "break",
+        "d" => "drop",
+        "l" => "label",
+        "t" => "reset",
+        "m" => "merge",
+        "u" => "update-ref",
+        other => other,
+    }
+}
+
+pub(super) fn parse_todo(text: &str) -> Vec<GitRebaseTodoItem> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|line| {
+            let (cmd, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let action = expand_todo_command(cmd).to_string();
+            let rest = rest.trim();
+            if !matches!(
+                action.as_str(),
+                "pick" | "reword" | "edit" | "squash" | "fixup" | "drop" | "revert"
+            ) {
+                return GitRebaseTodoItem {
+                    action,
+                    hash: None,
+                    subject: rest.to_string(),
+                };
+            }
+            // `fixup -C <hash>` / `fixup -c <hash>` carry a flag before the hash.
+            let mut parts = rest.splitn(2, char::is_whitespace);
+            let mut hash = parts.next().unwrap_or("");
+            let mut subject = parts.next().unwrap_or("").trim();
+            if hash.starts_with('-') {
+                let mut parts = subject.splitn(2, char::is_whitespace);
+                hash = parts.next().unwrap_or("");
+                subject = parts.next().unwrap_or("").trim();
+            }
+            GitRebaseTodoItem {
+                action,
+                hash: (!hash.is_empty()).then(|| hash.to_string()),
+                subject: subject.trim_start_matches("# ").to_string(),
+            }
+        })
+        .collect()
+}
+
+async fn conflicted_files(dir: &Path) -> Vec<String> {
+    let (code, out, _) = run_git(dir, &["diff", "--name-only", "--diff-filter=U", "-z"])
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        return Vec::new();
+    }
+    let mut files: Vec<String> = out
+        .split('\0')
+        .filter(|p| !p.is_empty())
+        .map(ToString::to_string)
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+async fn read_rebase_status(dir: &Path) -> GitRebaseStatusResponse {
+    let mut status = GitRebaseStatusResponse::default();
+    let merge_dir = git_path(dir, "rebase-merge").await;
+    let apply_dir = git_path(dir, "rebase-apply").await;
+
+    if let Some(state) = merge_dir.filter(|p| p.is_dir()) {
+        status.in_progress = true;
+        status.interactive = state.join("interactive").exists();
+        status.current_step = read_trimmed(&state.join("msgnum"))
+            .await
+            .and_then(|v| v.parse().ok());
+        status.total_steps = read_trimmed(&state.join("end"))
+            .await
+            .and_then(|v| v.parse().ok());
+        status.stopped_at = read_trimmed(&state.join("stopped-sha")).await;
+        status.done = parse_todo(&read_trimmed(&state.join("done")).await.unwrap_or_default());
+        status.remaining = parse_todo(
+            &read_trimmed(&state.join("git-rebase-todo"))
+                .await
+                .unwrap_or_default(),
+        );
+        status.head_name = read_trimmed(&state.join("head-name")).await;
+        status.onto = read_trimmed(&state.join("onto")).await;
+        status.orig_head = read_trimmed(&state.join("orig-head")).await;
+    } else if let Some(state) = apply_dir.filter(|p| p.is_dir()) {
+        status.in_progress = true;
+        status.current_step = read_trimmed(&state.join("next"))
+            .await
+            .and_then(|v| v.parse().ok());
+        status.total_steps = read_trimmed(&state.join("last"))
+            .await
+            .and_then(|v| v.parse().ok());
+        status.head_name = read_trimmed(&state.join("head-name")).await;
+        status.onto = read_trimmed(&state.join("onto")).await;
+        status.orig_head = read_trimmed(&state.join("orig-head")).await;
+    }
+
+    if status.in_progress {
+        status.head_name = status.head_name.map(|h| {
+            h.strip_prefix("refs/heads/")
+                .map(str::to_string)
+                .unwrap_or(h)
+        });
+        status.conflicted_files = conflicted_files(dir).await;
+    }
+    status
+}
+
+async fn resolve_onto(dir: &Path, onto: Option<&str>) -> Result<String, Response> {
+    let Some(onto) = onto.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "missing_onto",
+            "onto is required",
+        ));
+    };
+    rev_parse_commit(dir, onto).await.ok_or_else(|| {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_onto",
+            format!("Unknown revision: {onto}"),
+        )
+    })
+}
+
+/// Propose an interactive rebase of `onto..HEAD`.
+pub async fn git_rebase_plan(Query(q): Query<GitRebasePlanQuery>) -> Response {
+    let dir = match require_directory_raw(q.directory.as_deref()) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let onto = match resolve_onto(&dir, q.onto.as_deref()).await {
+        Ok(o) => o,
+        Err(resp) => return resp,
+    };
+    let Some(head) = rev_parse_commit(&dir, "HEAD").await else {
+        return error_response(StatusCode::CONFLICT, "no_head", "Repository has no commits");
+    };
+    let commits = match list_plan_commits(&dir, &onto).await {
+        Ok(c) => c,
+        Err(resp) => return resp,
+    };
+    Json(GitRebasePlanResponse {
+        onto,
+        head,
+        commits: propose_plan(commits),
+    })
+    .into_response()
+}
+
+/// Run `git rebase -i` with the submitted plan as its todo list.
+pub async fn git_rebase_interactive(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitRebaseInteractiveBody>,
+) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+
+    let _guard = match lock_repo(&dir, "rebase-interactive").await {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+
+    if read_rebase_status(&dir).await.in_progress {
+        return error_response(
+            StatusCode::CONFLICT,
+            "rebase_in_progress",
+            "A rebase is already in progress; continue or abort it first",
+        );
+    }
+
+    let onto = match resolve_onto(&dir, body.onto.as_deref()).await {
+        Ok(o) => o,
+        Err(resp) => return resp,
+    };
+    let head = rev_parse_commit(&dir, "HEAD").await;
+    if let Some(expected) = body
+        .head
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        && head.as_deref() != Some(expected)
+    {
+        return error_response(
+            StatusCode::CONFLICT,
+            "rebase_plan_stale",
+            "HEAD moved since the plan was created; reload the plan",
+        );
+    }
+    let planned: Vec<String> = match list_plan_commits(&dir, &onto).await {
+        Ok(c) => c.into_iter().map(|c| c.hash).collect(),
+        Err(resp) => return resp,
+    };
+    let steps: Vec<GitRebaseStep> = body
+        .steps
+        .into_iter()
+        .map(|s| GitRebaseStep {
+            hash: s.hash.trim().to_ascii_lowercase(),
+            ..s
+        })
+        .collect();
+    if let Err((status, code, msg)) = validate_steps(&planned, &steps) {
+        return error_response(status, code, msg);
+    }
+
+    let Some(plan_dir) = git_path(&dir, PLAN_DIR).await else {
+        return error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "git_rebase_failed",
+            "Could not resolve the git directory",
+        );
+    };
+    let _ = tokio::fs::remove_dir_all(&plan_dir).await;
+    if let Err(err) = tokio::fs::create_dir_all(&plan_dir).await {
+        return error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "git_rebase_failed",
+            err.to_string(),
+        );
+    }
+    let (todo, messages) = build_todo(&steps, &plan_dir);
+    let todo_path = plan_dir.join("todo");
+    let mut writes = vec![(todo_path.clone(), todo)];
+    writes.extend(messages);
+    for (path, content) in writes {
+        if let Err(err) = tokio::fs::write(&path, content).await {
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "git_rebase_failed",
+                err.to_string(),
+            );
+        }
+    }
+
+    // git appends the todo path it wants edited: `cp <ours> <theirs>`.
+    let sequence_editor = format!("cp {}", sh_quote(&todo_path.to_string_lossy()));
+    let (code, out, err) = run_git_env(
+        &dir,
+        &["rebase", "-i", "--no-autosquash", &onto],
+        &[("GIT_SEQUENCE_EDITOR", sequence_editor.as_str())],
+    )
+    .await
+    .unwrap_or((1, "".to_string(), "".to_string()));
+
+    if code != 0 {
+        let status = read_rebase_status(&dir).await;
+        if status.in_progress {
+            return (
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({
+                    "error": "Rebase stopped before finishing",
+                    "code": "rebase_stopped",
+                    "hint": "Resolve conflicts, then continue or abort the rebase.",
+                    "status": status,
+                })),
+            )
+                .into_response();
+        }
+        let _ = tokio::fs::remove_dir_all(&plan_dir).await;
+        if let Some(resp) = map_git_failure(code, &out, &err) {
+            return resp;
+        }
+        return error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "git_rebase_failed",
+            err.trim(),
+        );
+    }
+
+    let _ = tokio::fs::remove_dir_all(&plan_dir).await;
+    let head = rev_parse_commit(&dir, "HEAD").await;
+    Json(serde_json::json!({"success": true, "head": head})).into_response()
+}
+
+/// Progress, remaining todo and conflicts of the rebase in progress, if any.
+pub async fn git_rebase_status(Query(q): Query<DirectoryQuery>) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    Json(read_rebase_status(&dir).await).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(hash: &str, subject: &str) -> GitDryRunCommit {
+        GitDryRunCommit {
+            hash: hash.to_string(),
+            short_hash: hash[..4].to_string(),
+            subject: subject.to_string(),
+            author: "a".to_string(),
+        }
+    }
+
+    fn step(hash: &str, action: GitRebaseAction, message: Option<&str>) -> GitRebaseStep {
+        GitRebaseStep {
+            hash: hash.to_string(),
+            action,
+            message: message.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn propose_plan_moves_autosquash_commits_after_target() {
+        let plan = propose_plan(vec![
+            commit("aaaa1", "Add parser"),
+            commit("bbbb2", "Add lexer"),
+            commit("cccc3", "fixup! Add parser"),
+            commit("dddd4", "squash! fixup! Add parser"),
+            commit("eeee5", "fixup! bbbb"),
+            commit("ffff6", "fixup! Missing target"),
+        ]);
+        let order: Vec<(&str, GitRebaseAction)> =
+            plan.iter().map(|c| (c.hash.as_str(), c.action)).collect();
+        assert_eq!(
+            order,
+            vec![
+                ("aaaa1", GitRebaseAction::Pick),
+                ("cccc3", GitRebaseAction::Fixup),
+                ("dddd4", GitRebaseAction::Squash),
+                ("bbbb2", GitRebaseAction::Pick),
+                ("eeee5", GitRebaseAction::Fixup),
+                ("ffff6", GitRebaseAction::Pick),
+            ]
+        );
+        assert_eq!(plan[1].target.as_deref(), Some("aaaa1"));
+    }
+
+    #[test]
+    fn validate_steps_rejects_malformed_and_stale_plans() {
+        let planned = vec!["a".to_string(), "b".to_string()];
+        let ok = [
+            step("b", GitRebaseAction::Pick, None),
+            step("a", GitRebaseAction::Squash, Some("combined")),
+        ];
+        assert!(validate_steps(&planned, &ok).is_ok());
+
+        let leading_fixup = [
+            step("a", GitRebaseAction::Drop, None),
+            step("b", GitRebaseAction::Fixup, None),
+        ];
+        assert_eq!(
+            validate_steps(&planned, &leading_fixup).unwrap_err().1,
+            "invalid_rebase_plan"
+        );
+        let reword_without_message = [
+            step("a", GitRebaseAction::Reword, Some(" ")),
+            step("b", GitRebaseAction::Pick, None),
+        ];
+        assert!(validate_steps(&planned, &reword_without_message).is_err());
+        let missing = [step("a", GitRebaseAction::Pick, None)];
+        assert_eq!(
+            validate_steps(&planned, &missing).unwrap_err().1,
+            "rebase_plan_stale"
+        );
+    }
+
+    #[test]
+    fn build_todo_amends_rewords_and_squash_groups() {
+        let (todo, messages) = build_todo(
+            &[
+                step("a", GitRebaseAction::Reword, Some("New A")),
+                step("b", GitRebaseAction::Squash, Some("A and B")),
+                step("c", GitRebaseAction::Fixup, None),
+                step("d", GitRebaseAction::Drop, None),
+                step("e", GitRebaseAction::Pick, None),
+            ],
+            Path::new("/repo/.git/plan"),
+        );
+        assert_eq!(
+            todo,
+            "pick a\n\
+             exec git commit --amend --allow-empty -F '/repo/.git/plan/0.msg'\n\
+             squash b\n\
+             fixup c\n\
+             drop d\n\
+             exec git commit --amend --allow-empty -F '/repo/.git/plan/1.msg'\n\
+             pick e\n"
+        );
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].1, "A and B");
+    }
+
+    #[test]
+    fn parse_todo_expands_abbreviations_and_flags() {
+        let items = parse_todo("# comment\np 1234 First\nfixup -C 5678 # Second\nx make test\n\n");
+        assert_eq!(
+            items,
+            vec![
+                GitRebaseTodoItem {
+                    action: "pick".into(),
+                    hash: Some("1234".into()),
+                    subject: "First".into(),
+                },
+                GitRebaseTodoItem {
+                    action: "fixup".into(),
+                    hash: Some("5678".into()),
+                    subject: "Second".into(),
+                },
+                GitRebaseTodoItem {
+                    action: "exec".into(),
+                    hash: None,
+                    subject: "make test".into(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn interactive_rebase_applies_submitted_plan() {
+        use std::process::Command;
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let repo = tmp.path();
+        let git = |args: &[&str]| {
+            let out = Command::new("git")
+                .args(args)
+                .current_dir(repo)
+                .output()
+                .expect("git");
+            assert!(out.status.success(), "git {args:?} failed");
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.name", "Fixture"]);
+        git(&["config", "user.email", "fixture@opencode-studio.local"]);
+        git(&["config", "commit.gpgsign", "false"]);
+        for (file, subject) in [
+            ("base.txt", "base"),
+            ("a.txt", "Add a"),
+            ("b.txt", "Add b"),
+            ("c.txt", "fixup! Add a"),
+        ] {
+            std::fs::write(repo.join(file), subject).expect("write");
+            git(&["add", file]);
+            git(&["commit", "-q", "-m", subject]);
+        }
+        let directory = Some(repo.to_string_lossy().to_string());
+
+        let resp = git_rebase_plan(Query(GitRebasePlanQuery {
+            directory: directory.clone(),
+            onto: Some("HEAD~3".to_string()),
+        }))
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        let plan: serde_json::Value = serde_json::from_slice(&bytes).expect("json");
+        let commits = plan["commits"].as_array().expect("commits");
+        let actions: Vec<&str> = commits
+            .iter()
+            .map(|c| c["action"].as_str().unwrap())
+            .collect();
+        assert_eq!(actions, vec!["pick", "fixup", "pick"]);
+
+        let mut steps: Vec<GitRebaseStep> = commits
+            .iter()
+            .map(|c| GitRebaseStep {
+                hash: c["hash"].as_str().unwrap().to_string(),
+                action: serde_json::from_value(c["action"].clone()).unwrap(),
+                message: None,
+            })
+            .collect();
+        steps[0].action = GitRebaseAction::Reword;
+        steps[0].message = Some("Add a and c".to_string());
+        steps[2].action = GitRebaseAction::Drop;
+
+        let resp = git_rebase_interactive(
+            Query(DirectoryQuery {
+                directory: directory.clone(),
+            }),
+            Json(GitRebaseInteractiveBody {
+                onto: plan["onto"].as_str().map(str::to_string),
+                head: plan["head"].as_str().map(str::to_string),
+                steps,
+            }),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(git(&["log", "--format=%s"]), "Add a and c\nbase");
+        assert!(repo.join("c.txt").exists());
+        assert!(!repo.join("b.txt").exists());
+
+        let status = read_rebase_status(repo).await;
+        assert!(!status.in_progress);
+    }
+}
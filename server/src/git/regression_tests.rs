@@ -8,12 +8,18 @@ use serde_json::Value;
 use tempfile::TempDir;
 
 use super::{
-    CheckoutBody, CreateBranchBody, DirectoryQuery, GitAbortBody, GitConflictResolveBody,
-    GitDiffQuery, GitFetchBody, GitFileDiffQuery, GitPullBody, GitRemoteBranchesQuery,
-    GitStatusQuery, GitTagCreateBody, GitTagDeleteBody, git_check, git_checkout, git_conflict_file,
-    git_conflict_resolve, git_conflicts_list, git_create_branch, git_diff, git_fetch, git_pull,
-    git_rebase_abort, git_remote_branches_list, git_stash_list, git_state, git_status,
-    git_tags_create, git_tags_delete,
+    CheckoutBody, CreateBranchBody, DeleteBranchBody, DirectoryQuery, GitAbortBody,
+    GitBisectMarkBody, GitBisectRunBody, GitBisectStartBody, GitBlameQuery, GitBranchCompareQuery,
+    GitCleanBody, GitConflictResolveBody, GitDiffQuery, GitFetchBody, GitFileDiffQuery,
+    GitGraphQuery, GitHunkLineSelection, GitHunksApplyBody, GitLogQuery, GitPullBody,
+    GitRemoteBranchesQuery, GitResetCommitBody, GitSignaturesBody, GitSizeAdvisorQuery,
+    GitStatusQuery, GitTagCreateBody, GitTagDeleteBody, git_bisect_bad, git_bisect_reset,
+    git_bisect_run, git_bisect_start, git_bisect_status, git_blame, git_branch_compare, git_check,
+    git_checkout, git_clean, git_conflict_file, git_conflict_resolve, git_conflicts_list,
+    git_create_branch, git_delete_branch, git_diff, git_fetch, git_graph, git_log, git_pull,
+    git_rebase_abort, git_remote_branches_list, git_reset_commit, git_signatures, git_size_advisor,
+    git_stage_hunks, git_stash_list, git_state, git_status, git_tags_create, git_tags_delete,
+    git_unstage_hunks,
 };
 
 fn run_git(cwd: &Path, args: &[&str]) -> Output {
@@ -591,3 +597,701 @@ async fn git_remote_fetch_pull_and_remote_branches_are_automated() {
         .expect("branches should be an array");
     assert!(branches.iter().any(|v| v.as_str() == Some("main")));
 }
+
+#[tokio::test]
+async fn git_dry_run_previews_do_not_modify_repository() {
+    let tmp = TempDir::new().expect("tempdir");
+    let repo = tmp.path().join("dry-run");
+    init_repo(&repo);
+    write_file(&repo.join("a.txt"), "one\n");
+    run_git_ok(&repo, &["add", "a.txt"]);
+    run_git_ok(&repo, &["commit", "-q", "-m", "first"]);
+    let first = run_git_ok(&repo, &["rev-parse", "HEAD"]).trim().to_string();
+    run_git_ok(&repo, &["checkout", "-q", "-b", "topic"]);
+    write_file(&repo.join("a.txt"), "two\n");
+    run_git_ok(&repo, &["commit", "-q", "-am", "second"]);
+    let second = run_git_ok(&repo, &["rev-parse", "HEAD"]).trim().to_string();
+    run_git_ok(&repo, &["checkout", "-q", "main"]);
+    write_file(&repo.join("a.txt"), "dirty\n");
+    write_file(&repo.join("scratch.txt"), "untracked\n");
+    let repo_s = repo.to_string_lossy().to_string();
+
+    let reset = expect_ok_json(
+        git_reset_commit(
+            Query(DirectoryQuery {
+                directory: Some(repo_s.clone()),
+            }),
+            Json(GitResetCommitBody {
+                commit: Some(second.clone()),
+                mode: Some("hard".to_string()),
+                dry_run: Some(true),
+            }),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(reset.get("dryRun").and_then(Value::as_bool), Some(true));
+    assert_eq!(reset["filesDiscarded"], serde_json::json!(["a.txt"]));
+    assert_eq!(reset["refsMoved"][0]["ref"], "refs/heads/main");
+    assert_eq!(reset["refsMoved"][0]["from"], first.as_str());
+    assert_eq!(reset["refsMoved"][0]["to"], second.as_str());
+
+    let clean = expect_ok_json(
+        git_clean(
+            Query(DirectoryQuery {
+                directory: Some(repo_s.clone()),
+            }),
+            Json(GitCleanBody {
+                scope: None,
+                paths: None,
+                dry_run: Some(true),
+            }),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(clean["filesRemoved"], serde_json::json!(["scratch.txt"]));
+
+    let delete = expect_ok_json(
+        git_delete_branch(
+            Query(DirectoryQuery {
+                directory: Some(repo_s),
+            }),
+            Json(DeleteBranchBody {
+                branch: Some("topic".to_string()),
+                force: None,
+                dry_run: Some(true),
+            }),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(delete["commitsDropped"][0]["hash"], second.as_str());
+    assert_eq!(delete["refsMoved"][0]["to"], Value::Null);
+    assert!(delete["warnings"].as_array().is_some_and(|w| !w.is_empty()));
+
+    assert!(repo.join("scratch.txt").exists());
+    assert_eq!(
+        fs::read_to_string(repo.join("a.txt")).expect("read a.txt"),
+        "dirty\n"
+    );
+    assert_eq!(run_git_ok(&repo, &["rev-parse", "HEAD"]).trim(), first);
+    run_git_ok(&repo, &["rev-parse", "--verify", "refs/heads/topic"]);
+}
+
+#[tokio::test]
+async fn git_size_advisor_reports_large_blobs_from_history_and_head() {
+    let tmp = TempDir::new().expect("tempdir");
+    let repo = tmp.path().join("size-advisor");
+    init_repo(&repo);
+    let big = "x".repeat(80 * 1024);
+    fs::create_dir_all(repo.join("assets")).expect("mkdir assets");
+    write_file(&repo.join("assets").join("big.bin"), &big);
+    write_file(&repo.join("old.log"), &big.replace('x', "y"));
+    write_file(&repo.join("small.txt"), "small\n");
+    run_git_ok(&repo, &["add", "."]);
+    run_git_ok(&repo, &["commit", "-q", "-m", "init"]);
+    run_git_ok(&repo, &["rm", "-q", "old.log"]);
+    run_git_ok(&repo, &["commit", "-q", "-m", "drop log"]);
+
+    let report = expect_ok_json(
+        git_size_advisor(Query(GitSizeAdvisorQuery {
+            directory: Some(repo.to_string_lossy().to_string()),
+            threshold_bytes: Some(64 * 1024),
+            limit: None,
+        }))
+        .await,
+    )
+    .await;
+
+    let history = report["historyBlobs"].as_array().expect("historyBlobs");
+    assert_eq!(history.len(), 2);
+    assert_eq!(report["trackedLargeFiles"][0]["path"], "assets/big.bin");
+    let suggestions = report["suggestions"].as_array().expect("suggestions");
+    assert!(suggestions.iter().any(|s| s["kind"] == "lfs-migrate"
+        && s["path"] == "assets/big.bin"
+        && s["pattern"] == "*.bin"));
+    assert!(
+        suggestions
+            .iter()
+            .any(|s| s["kind"] == "history-rewrite" && s["path"] == "old.log")
+    );
+}
+
+fn log_query(repo: &Path) -> GitLogQuery {
+    GitLogQuery {
+        directory: Some(repo.to_string_lossy().to_string()),
+        limit: None,
+        offset: None,
+        cursor: None,
+        path: None,
+        search: None,
+        author: None,
+        message: None,
+        since: None,
+        until: None,
+        r#ref: None,
+        graph: None,
+        include_total: None,
+    }
+}
+
+fn log_subjects(log: &Value) -> Vec<String> {
+    log["commits"]
+        .as_array()
+        .expect("commits")
+        .iter()
+        .map(|c| c["subject"].as_str().unwrap_or_default().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn git_log_filters_and_pages_with_a_stable_cursor() {
+    let tmp = TempDir::new().expect("tempdir");
+    let repo = tmp.path().join("history");
+    init_repo(&repo);
+    fs::create_dir_all(repo.join("docs")).expect("mkdir docs");
+    for (i, (author, file)) in [
+        ("Ada <ada@example.com>", "docs/a.md"),
+        ("Bob <bob@example.com>", "src.txt"),
+        ("Ada <ada@example.com>", "docs/b.md"),
+        ("Bob <bob@example.com>", "docs/c.md"),
+    ]
+    .iter()
+    .enumerate()
+    {
+        write_file(&repo.join(file), &format!("{i}\n"));
+        run_git_ok(&repo, &["add", "."]);
+        // `--since`/`--until` look at the committer date.
+        let out = Command::new("git")
+            .args(["commit", "-q", "--author", author, "-m"])
+            .arg(format!("change {i}"))
+            .env(
+                "GIT_COMMITTER_DATE",
+                format!("2024-01-0{}T12:00:00Z", i + 1),
+            )
+            .current_dir(&repo)
+            .output()
+            .expect("git commit");
+        assert!(out.status.success(), "commit {i} failed");
+    }
+
+    let log = expect_ok_json(
+        git_log(Query(GitLogQuery {
+            path: Some("docs".to_string()),
+            limit: Some(2),
+            ..log_query(&repo)
+        }))
+        .await,
+    )
+    .await;
+    assert_eq!(log_subjects(&log), vec!["change 3", "change 2"]);
+    assert_eq!(log["total"], 3);
+    let cursor = log["nextCursor"].as_str().expect("cursor").to_string();
+
+    // A new commit must not shift the next page.
+    write_file(&repo.join("docs").join("d.md"), "new\n");
+    run_git_ok(&repo, &["add", "."]);
+    run_git_ok(&repo, &["commit", "-q", "-m", "change 4"]);
+    let log = expect_ok_json(
+        git_log(Query(GitLogQuery {
+            path: Some("docs".to_string()),
+            limit: Some(2),
+            cursor: Some(cursor),
+            ..log_query(&repo)
+        }))
+        .await,
+    )
+    .await;
+    assert_eq!(log_subjects(&log), vec!["change 0"]);
+    assert_eq!(log["hasMore"], false);
+    assert!(log.get("nextCursor").is_none());
+
+    let log = expect_ok_json(
+        git_log(Query(GitLogQuery {
+            author: Some("ada".to_string()),
+            until: Some("2024-01-02".to_string()),
+            include_total: Some(false),
+            ..log_query(&repo)
+        }))
+        .await,
+    )
+    .await;
+    assert_eq!(log_subjects(&log), vec!["change 0"]);
+    assert!(log.get("total").is_none());
+
+    let (status, _) = response_json(
+        git_log(Query(GitLogQuery {
+            cursor: Some("not-a-cursor".to_string()),
+            ..log_query(&repo)
+        }))
+        .await,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn git_graph_assigns_lanes_to_merged_branches() {
+    let tmp = TempDir::new().expect("tempdir");
+    let repo = mk_conflict_repo(tmp.path());
+    run_git_ok(&repo, &["merge", "--abort"]);
+    run_git_ok(
+        &repo,
+        &["merge", "-q", "-s", "ours", "-m", "merge theirs", "theirs"],
+    );
+    run_git_ok(&repo, &["tag", "v1"]);
+
+    let graph = expect_ok_json(
+        git_graph(Query(GitGraphQuery {
+            directory: Some(repo.to_string_lossy().to_string()),
+            limit: None,
+            r#ref: None,
+        }))
+        .await,
+    )
+    .await;
+
+    let commits = graph["commits"].as_array().expect("commits");
+    let subjects: Vec<&str> = commits
+        .iter()
+        .map(|c| c["subject"].as_str().unwrap_or_default())
+        .collect();
+    assert_eq!(subjects.len(), 4);
+    assert_eq!(subjects[0], "merge theirs");
+    assert_eq!(subjects[3], "base");
+    assert_eq!(graph["laneCount"], 2);
+    assert_eq!(graph["hasMore"], false);
+
+    let merge = &commits[0];
+    assert_eq!(merge["lane"], 0);
+    assert_eq!(merge["parents"].as_array().map(Vec::len), Some(2));
+    assert_eq!(merge["edges"][1]["lane"], 1);
+    let refs = merge["refs"].as_array().expect("refs");
+    assert!(refs.iter().any(|r| r == "tag: refs/tags/v1"));
+    let theirs = commits
+        .iter()
+        .find(|c| c["subject"] == "theirs change")
+        .expect("theirs commit");
+    assert_eq!(theirs["lane"], 1);
+    assert_eq!(commits[3]["lane"], 0);
+
+    let graph = expect_ok_json(
+        git_graph(Query(GitGraphQuery {
+            directory: Some(repo.to_string_lossy().to_string()),
+            limit: Some(1),
+            r#ref: Some("theirs".to_string()),
+        }))
+        .await,
+    )
+    .await;
+    assert_eq!(graph["commits"][0]["subject"], "theirs change");
+    assert_eq!(graph["hasMore"], true);
+}
+
+fn blame_query(repo: &Path) -> GitBlameQuery {
+    GitBlameQuery {
+        directory: Some(repo.to_string_lossy().to_string()),
+        path: Some("a.txt".to_string()),
+        start: None,
+        end: None,
+        ignore_revs_file: None,
+    }
+}
+
+fn blame_summaries(blame: &Value) -> Vec<(i64, String)> {
+    blame["lines"]
+        .as_array()
+        .expect("lines")
+        .iter()
+        .map(|l| {
+            (
+                value_i64(l, "line"),
+                l["summary"].as_str().unwrap_or_default().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn git_blame_limits_lines_and_skips_ignored_revisions() {
+    let tmp = TempDir::new().expect("tempdir");
+    let repo = tmp.path().join("blame");
+    init_repo(&repo);
+    write_file(&repo.join("a.txt"), "one\ntwo\nthree\nfour\n");
+    run_git_ok(&repo, &["add", "a.txt"]);
+    run_git_ok(&repo, &["commit", "-q", "-m", "init"]);
+    write_file(&repo.join("a.txt"), "one\nTWO\nthree\nfour\n");
+    run_git_ok(&repo, &["commit", "-q", "-am", "reformat"]);
+    let reformat = run_git_ok(&repo, &["rev-parse", "HEAD"]);
+    write_file(&repo.join(".git-blame-ignore-revs"), &reformat);
+
+    let blame = expect_ok_json(
+        git_blame(Query(GitBlameQuery {
+            start: Some(2),
+            end: Some(3),
+            ..blame_query(&repo)
+        }))
+        .await,
+    )
+    .await;
+    assert_eq!(
+        blame_summaries(&blame),
+        vec![(2, "reformat".to_string()), (3, "init".to_string())]
+    );
+
+    let blame = expect_ok_json(
+        git_blame(Query(GitBlameQuery {
+            start: Some(2),
+            end: Some(2),
+            ignore_revs_file: Some(".git-blame-ignore-revs".to_string()),
+            ..blame_query(&repo)
+        }))
+        .await,
+    )
+    .await;
+    assert_eq!(blame_summaries(&blame), vec![(2, "init".to_string())]);
+
+    // A working-tree edit must not be served from the cached blame.
+    write_file(&repo.join("a.txt"), "one\ntwo\nthree\nfour\nfive\n");
+    let blame = expect_ok_json(
+        git_blame(Query(GitBlameQuery {
+            start: Some(5),
+            ..blame_query(&repo)
+        }))
+        .await,
+    )
+    .await;
+    assert_eq!(
+        blame["lines"][0]["hash"],
+        "0000000000000000000000000000000000000000"
+    );
+
+    for (start, end) in [(Some(3), Some(2)), (Some(40), None)] {
+        let (status, body) = response_json(
+            git_blame(Query(GitBlameQuery {
+                start,
+                end,
+                ..blame_query(&repo)
+            }))
+            .await,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_range");
+    }
+}
+
+fn hunk_body(path: &str) -> GitHunksApplyBody {
+    GitHunksApplyBody {
+        path: Some(path.to_string()),
+        hunks: None,
+        lines: None,
+        patch: None,
+        context_lines: Some(1),
+    }
+}
+
+#[tokio::test]
+async fn git_hunks_stage_and_unstage_selected_lines() {
+    let tmp = TempDir::new().expect("tempdir");
+    let repo = tmp.path().join("hunks");
+    init_repo(&repo);
+    let original: String = (1..=12).map(|i| format!("line{i}\n")).collect();
+    write_file(&repo.join("a.txt"), &original);
+    run_git_ok(&repo, &["add", "a.txt"]);
+    run_git_ok(&repo, &["commit", "-q", "-m", "init"]);
+    let edited = original
+        .replace("line2\n", "line2\nadded-a\nadded-b\n")
+        .replace("line10\n", "line10-changed\n");
+    write_file(&repo.join("a.txt"), &edited);
+    let dir = DirectoryQuery {
+        directory: Some(repo.to_string_lossy().to_string()),
+    };
+
+    // Stage the second hunk whole and only `added-b` from the first.
+    let breakdown = expect_ok_json(
+        git_stage_hunks(
+            Query(DirectoryQuery {
+                directory: dir.directory.clone(),
+            }),
+            Json(GitHunksApplyBody {
+                hunks: Some(vec![1]),
+                lines: Some(vec![GitHunkLineSelection {
+                    hunk: 0,
+                    lines: vec![2],
+                }]),
+                ..hunk_body("a.txt")
+            }),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(breakdown["staged"].as_array().map(Vec::len), Some(2));
+    assert_eq!(breakdown["unstaged"].as_array().map(Vec::len), Some(1));
+    let index = run_git_ok(&repo, &["show", ":a.txt"]);
+    assert_eq!(
+        index,
+        original
+            .replace("line2\n", "line2\nadded-b\n")
+            .replace("line10\n", "line10-changed\n")
+    );
+
+    // Unstage the line change again, leaving `added-b` staged.
+    let breakdown = expect_ok_json(
+        git_unstage_hunks(
+            Query(DirectoryQuery {
+                directory: dir.directory.clone(),
+            }),
+            Json(GitHunksApplyBody {
+                hunks: Some(vec![1]),
+                ..hunk_body("a.txt")
+            }),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(breakdown["staged"].as_array().map(Vec::len), Some(1));
+    let index = run_git_ok(&repo, &["show", ":a.txt"]);
+    assert_eq!(index, original.replace("line2\n", "line2\nadded-b\n"));
+    assert_eq!(fs::read_to_string(repo.join("a.txt")).unwrap(), edited);
+
+    let (status, body) = response_json(
+        git_stage_hunks(
+            Query(dir),
+            Json(GitHunksApplyBody {
+                hunks: Some(vec![7]),
+                ..hunk_body("a.txt")
+            }),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "stale_selection");
+}
+
+fn mk_bisect_repo(root: &Path) -> PathBuf {
+    let repo = root.join("bisect");
+    init_repo(&repo);
+    for i in 1..=8 {
+        let state = if i < 5 { "pass" } else { "fail" };
+        write_file(&repo.join("state.txt"), &format!("{state} {i}\n"));
+        run_git_ok(&repo, &["add", "state.txt"]);
+        run_git_ok(&repo, &["commit", "-q", "-m", &format!("step {i}")]);
+    }
+    repo
+}
+
+#[tokio::test]
+async fn git_bisect_run_finds_the_first_bad_commit() {
+    let tmp = TempDir::new().expect("tempdir");
+    let repo = mk_bisect_repo(tmp.path());
+    let dir = || DirectoryQuery {
+        directory: Some(repo.to_string_lossy().to_string()),
+    };
+    let first = run_git_ok(&repo, &["rev-list", "--max-parents=0", "HEAD"]);
+
+    let status = expect_ok_json(
+        git_bisect_start(
+            Query(dir()),
+            Json(GitBisectStartBody {
+                bad: Some("HEAD".to_string()),
+                good: vec![first.trim().to_string()],
+                paths: Vec::new(),
+            }),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(status["active"], true);
+    assert_eq!(status["remaining"], 7);
+    assert!(status["current"]["subject"].is_string());
+
+    // One manual step, then let the test command finish the hunt.
+    let status = expect_ok_json(
+        git_bisect_bad(
+            Query(dir()),
+            Json(GitBisectMarkBody {
+                rev: Some("main~1".to_string()),
+            }),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(status["active"], true);
+
+    let resp = git_bisect_run(
+        Query(dir()),
+        Json(GitBisectRunBody {
+            command: Some("grep".to_string()),
+            args: vec![
+                "-q".to_string(),
+                "pass".to_string(),
+                "state.txt".to_string(),
+            ],
+            timeout_seconds: Some(30),
+        }),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .expect("read sse body");
+    let body = String::from_utf8_lossy(&body);
+    let done: Value = body
+        .split("\n\n")
+        .find_map(|event| {
+            let data = event.strip_prefix("event: done\ndata: ")?;
+            serde_json::from_str(data).ok()
+        })
+        .expect("done event");
+    assert_eq!(done["error"], Value::Null);
+    assert_eq!(done["firstBad"]["subject"], "step 5");
+
+    let status = expect_ok_json(git_bisect_status(Query(dir())).await).await;
+    assert_eq!(status["firstBad"]["subject"], "step 5");
+
+    expect_ok_json(git_bisect_reset(Query(dir())).await).await;
+    let status = expect_ok_json(git_bisect_status(Query(dir())).await).await;
+    assert_eq!(status["active"], false);
+    assert_eq!(
+        run_git_ok(&repo, &["log", "-1", "--format=%s"]).trim(),
+        "step 8"
+    );
+}
+
+#[tokio::test]
+async fn git_branch_compare_reports_upstream_and_base_counts() {
+    let tmp = TempDir::new().expect("tempdir");
+    let repo = mk_remote_suite(tmp.path());
+    run_git_ok(&repo, &["fetch", "-q", "origin"]);
+    run_git_ok(&repo, &["checkout", "-q", "-b", "topic"]);
+    for step in ["one", "two"] {
+        write_file(&repo.join("topic.txt"), &format!("{step}\n"));
+        run_git_ok(&repo, &["add", "topic.txt"]);
+        run_git_ok(&repo, &["commit", "-q", "-m", step]);
+    }
+    run_git_ok(&repo, &["checkout", "-q", "main"]);
+    let query = |base: &str| GitBranchCompareQuery {
+        directory: Some(repo.to_string_lossy().to_string()),
+        base: Some(base.to_string()),
+    };
+
+    let compare = expect_ok_json(git_branch_compare(Query(query("origin/main"))).await).await;
+    assert_eq!(compare["base"], "origin/main");
+    let branch = |name: &str| {
+        compare["branches"]
+            .as_array()
+            .and_then(|b| b.iter().find(|b| b["name"] == name))
+            .cloned()
+            .unwrap_or_else(|| panic!("missing branch {name}"))
+    };
+    let main = branch("main");
+    assert_eq!(main["current"], true);
+    assert_eq!(main["upstream"], "origin/main");
+    assert_eq!(
+        (value_i64(&main, "ahead"), value_i64(&main, "behind")),
+        (1, 1)
+    );
+    assert_eq!(
+        (
+            value_i64(&main, "baseAhead"),
+            value_i64(&main, "baseBehind")
+        ),
+        (1, 1)
+    );
+    let topic = branch("topic");
+    assert_eq!(topic["subject"], "two");
+    assert!(topic.get("upstream").is_none());
+    assert!(topic.get("ahead").is_none());
+    assert_eq!(
+        (
+            value_i64(&topic, "baseAhead"),
+            value_i64(&topic, "baseBehind")
+        ),
+        (3, 1)
+    );
+
+    let (status, missing) =
+        response_json(git_branch_compare(Query(query("no-such-branch"))).await).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(missing["code"], "base_not_found");
+}
+
+#[tokio::test]
+async fn git_signatures_verify_ssh_signed_and_unsigned_commits() {
+    let tmp = TempDir::new().expect("tempdir");
+    let repo = tmp.path().join("signed");
+    init_repo(&repo);
+    let key = tmp.path().join("signing_key");
+    let keygen = Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-C", "fixture", "-f"])
+        .arg(&key)
+        .output();
+    if !keygen.is_ok_and(|out| out.status.success()) {
+        eprintln!("ssh-keygen unavailable; skipping signature verification test");
+        return;
+    }
+    let public_key = fs::read_to_string(key.with_extension("pub")).expect("read public key");
+    let allowed = tmp.path().join("allowed_signers");
+    write_file(
+        &allowed,
+        &format!("fixture@opencode-studio.local {}", public_key.trim()),
+    );
+    run_git_ok(&repo, &["config", "gpg.format", "ssh"]);
+    run_git_ok(
+        &repo,
+        &["config", "user.signingkey", &key.to_string_lossy()],
+    );
+    run_git_ok(
+        &repo,
+        &[
+            "config",
+            "gpg.ssh.allowedSignersFile",
+            &allowed.to_string_lossy(),
+        ],
+    );
+
+    write_file(&repo.join("a.txt"), "one\n");
+    run_git_ok(&repo, &["add", "a.txt"]);
+    run_git_ok(&repo, &["commit", "-q", "-m", "unsigned"]);
+    write_file(&repo.join("a.txt"), "two\n");
+    run_git_ok(&repo, &["add", "a.txt"]);
+    run_git_ok(&repo, &["commit", "-q", "-S", "-m", "signed"]);
+    let head = run_git_ok(&repo, &["rev-parse", "HEAD"]);
+
+    let dir = || DirectoryQuery {
+        directory: Some(repo.to_string_lossy().to_string()),
+    };
+    let sigs = expect_ok_json(
+        git_signatures(
+            Query(dir()),
+            Json(GitSignaturesBody {
+                commits: vec!["main~1".to_string(), head[..10].to_string()],
+            }),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(sigs["head"]["hash"], head.trim());
+    assert_eq!(sigs["head"]["status"], "good");
+    assert_eq!(sigs["head"]["verified"], true);
+    assert_eq!(sigs["head"]["signer"], "fixture@opencode-studio.local");
+    assert_eq!(sigs["commits"]["main~1"]["status"], "unsigned");
+    assert_eq!(sigs["commits"]["main~1"]["verified"], false);
+    assert_eq!(sigs["commits"][&head[..10]]["status"], "good");
+
+    let (status, body) = response_json(
+        git_signatures(
+            Query(dir()),
+            Json(GitSignaturesBody {
+                commits: vec!["no-such-commit".to_string()],
+            }),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "unknown_commit");
+}
@@ -9,6 +9,7 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tokio::process::Command;
 
@@ -135,7 +136,7 @@ pub async fn git_remote_info(Query(q): Query<DirectoryQuery>) -> Response {
     Json(GitRemoteInfoResponse { remotes }).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitRemoteAddBody {
     pub name: Option<String>,
     pub url: Option<String>,
@@ -150,7 +151,7 @@ pub async fn git_remote_add(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "remote-add").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -197,7 +198,7 @@ pub async fn git_remote_add(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitRemoteRenameBody {
     pub name: Option<String>,
@@ -213,7 +214,7 @@ pub async fn git_remote_rename(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "remote-rename").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -260,7 +261,7 @@ pub async fn git_remote_rename(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitRemoteSetUrlBody {
     pub name: Option<String>,
     pub url: Option<String>,
@@ -275,7 +276,7 @@ pub async fn git_remote_set_url(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "remote-set-url").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -322,7 +323,7 @@ pub async fn git_remote_set_url(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitRemoteRemoveBody {
     pub name: Option<String>,
 }
@@ -336,7 +337,7 @@ pub async fn git_remote_remove(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "remote-remove").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -673,7 +674,7 @@ pub async fn git_state(Query(q): Query<DirectoryQuery>) -> Response {
     .into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitRemoteBranchesQuery {
     pub directory: Option<String>,
     pub remote: Option<String>,
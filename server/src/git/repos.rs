@@ -1,21 +1,32 @@
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 use axum::{
     Json,
-    extract::Query,
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{
+        IntoResponse, Response,
+        sse::{KeepAlive, Sse},
+    },
 };
+use futures_util::StreamExt;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
 use super::{
-    DirectoryQuery, is_safe_repo_rel_path, map_git_failure, path_slash, rel_path_slash,
-    require_directory, require_directory_raw, run_git,
+    DirectoryQuery, GitAuthInput, TempGitAskpass, git_http_auth_env, http_auth_for_url,
+    is_safe_repo_rel_path, map_git_failure, path_slash, redact_git_output, rel_path_slash,
+    require_directory, require_directory_raw, run_git, spawn_git_piped, sse_json,
 };
 
-#[derive(Debug, Deserialize)]
+/// Non-progress stderr lines kept for the error message of a failed clone.
+const CLONE_STDERR_TAIL_LINES: usize = 40;
+
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitReposQuery {
     pub directory: Option<String>,
@@ -280,7 +291,7 @@ pub async fn git_safe_directory(Query(q): Query<DirectoryQuery>) -> Response {
     .into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitInitBody {
     pub path: Option<String>,
@@ -385,7 +396,7 @@ pub async fn git_init(Query(q): Query<DirectoryQuery>, Json(body): Json<GitInitB
     .into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitCloneBody {
     pub url: Option<String>,
@@ -394,6 +405,11 @@ pub struct GitCloneBody {
     pub recursive: Option<bool>,
     pub r#ref: Option<String>,
     pub depth: Option<u32>,
+    pub auth: Option<GitAuthInput>,
+    /// Register the clone as a project in settings (default true).
+    pub add_project: Option<bool>,
+    /// Run as a background job and return `202` with the job record.
+    pub background: Option<bool>,
 }
 
 fn infer_repo_dir(url: &str) -> Option<String> {
@@ -412,7 +428,166 @@ fn infer_repo_dir(url: &str) -> Option<String> {
     Some(name)
 }
 
+/// One line of `git clone --progress` stderr, e.g.
+/// `Receiving objects:  45% (450/1000), 1.20 MiB | 2.00 MiB/s`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CloneProgress {
+    phase: String,
+    percent: u32,
+    current: Option<u64>,
+    total: Option<u64>,
+}
+
+fn parse_clone_progress(line: &str) -> Option<CloneProgress> {
+    let line = line.trim();
+    let line = line.strip_prefix("remote:").map(str::trim).unwrap_or(line);
+    let (phase, rest) = line.split_once(':')?;
+    let (percent, rest) = rest.trim_start().split_once('%')?;
+    let percent = percent.trim().parse::<u32>().ok()?;
+    let counts = rest
+        .trim_start()
+        .strip_prefix('(')
+        .and_then(|r| r.split_once(')'))
+        .and_then(|(counts, _)| counts.split_once('/'));
+    Some(CloneProgress {
+        phase: phase.trim().to_string(),
+        percent: percent.min(100),
+        current: counts.and_then(|(c, _)| c.trim().parse().ok()),
+        total: counts.and_then(|(_, t)| t.trim().parse().ok()),
+    })
+}
+
+enum CloneEvent {
+    Progress(CloneProgress),
+    Message(String),
+    Finished { code: i32, stderr: String },
+}
+
+/// Run `git clone --progress`, reporting progress as it arrives and the
+/// exit code with the tail of stderr at the end.
+fn clone_events(
+    base: PathBuf,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    askpass: Option<TempGitAskpass>,
+) -> impl futures_util::Stream<Item = CloneEvent> {
+    async_stream::stream! {
+        let _askpass = askpass;
+        let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        let env_ref: Vec<(&str, &str)> =
+            env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        let mut child = match spawn_git_piped(&base, &args_ref, &env_ref) {
+            Ok(child) => child,
+            Err(err) => {
+                yield CloneEvent::Finished { code: 1, stderr: err };
+                return;
+            }
+        };
+        let mut tail: Vec<String> = Vec::new();
+        if let Some(mut stderr) = child.stderr.take() {
+            let mut last: Option<CloneProgress> = None;
+            let mut pending = String::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = match tokio::io::AsyncReadExt::read(&mut stderr, &mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                pending.push_str(&String::from_utf8_lossy(&buf[..n]));
+                // Progress redraws end in `\r`, everything else in `\n`.
+                while let Some(end) = pending.find(['\r', '\n']) {
+                    let line: String = pending.drain(..=end).collect();
+                    let line = line.trim_end_matches(['\r', '\n']);
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match parse_clone_progress(line) {
+                        Some(progress) => {
+                            let changed = last.as_ref().is_none_or(|prev| {
+                                prev.phase != progress.phase || prev.percent != progress.percent
+                            });
+                            if changed {
+                                last = Some(progress.clone());
+                                yield CloneEvent::Progress(progress);
+                            }
+                        }
+                        None => {
+                            tail.push(line.to_string());
+                            if tail.len() > CLONE_STDERR_TAIL_LINES {
+                                tail.remove(0);
+                            }
+                            yield CloneEvent::Message(line.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        let code = match child.wait().await {
+            Ok(status) => status.code().unwrap_or(1),
+            Err(err) => {
+                tail.push(err.to_string());
+                1
+            }
+        };
+        yield CloneEvent::Finished { code, stderr: tail.join("\n") };
+    }
+}
+
+/// Add `path` to the configured projects, or bump `lastOpenedAt` when it is
+/// already there. Returns the project id.
+async fn register_project(state: &crate::AppState, path: &Path) -> Result<String, String> {
+    let path = path_slash(path);
+    let key = crate::path_utils::normalize_directory_for_match(&path);
+    let now = time::OffsetDateTime::now_utc().unix_timestamp_nanos() as i64 / 1_000_000;
+
+    let mut guard = state.settings.write().await;
+    let existing = guard.projects.iter_mut().find(|p| {
+        key.is_some() && crate::path_utils::normalize_directory_for_match(&p.path) == key
+    });
+    let id = match existing {
+        Some(project) => {
+            project.last_opened_at = now;
+            project.id.clone()
+        }
+        None => {
+            let id = uuid::Uuid::new_v4().to_string();
+            guard.projects.push(crate::settings::Project {
+                id: id.clone(),
+                path,
+                added_at: now,
+                last_opened_at: now,
+            });
+            id
+        }
+    };
+    guard.revision = guard.revision.saturating_add(1);
+    let next_settings = guard.clone();
+    drop(guard);
+
+    crate::settings::persist_settings(state.studio_db.as_ref(), &next_settings).await?;
+    let value = serde_json::to_value(&next_settings).unwrap_or(serde_json::json!({}));
+    crate::settings_events::publish_settings_replace(crate::config::format_settings_response(
+        &value,
+    ))
+    .await;
+    Ok(id)
+}
+
+fn wants_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/event-stream"))
+}
+
+/// Clone `url` into `path` under the requested directory and register it as
+/// a project. With `Accept: text/event-stream` progress is streamed as
+/// `progress` / `message` events ending in `done` or `error`; with
+/// `background: true` the clone runs as a job instead.
 pub async fn git_clone(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
     Query(q): Query<DirectoryQuery>,
     Json(body): Json<GitCloneBody>,
 ) -> Response {
@@ -433,6 +608,13 @@ pub async fn git_clone(
         )
             .into_response();
     };
+    if url.starts_with('-') {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid url", "code": "invalid_url"})),
+        )
+            .into_response();
+    }
 
     let clone_ref = body
         .r#ref
@@ -525,7 +707,26 @@ pub async fn git_clone(
     }
 
     let target_str = target.to_string_lossy().to_string();
-    let mut args: Vec<String> = vec!["clone".to_string()];
+    let mut args: Vec<String> = Vec::new();
+    let mut env: Vec<(String, String)> = Vec::new();
+    let mut askpass: Option<TempGitAskpass> = None;
+    if let Some((u, p)) = http_auth_for_url(url, body.auth.as_ref()) {
+        match git_http_auth_env(&u, &p).await {
+            Ok((prefix, auth_env, guard)) => {
+                args.extend(prefix);
+                env.extend(auth_env);
+                askpass = Some(guard);
+            }
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": e, "code": "git_auth_setup_failed"})),
+                )
+                    .into_response();
+            }
+        }
+    }
+    args.extend(["clone".to_string(), "--progress".to_string()]);
     if body.recursive.unwrap_or(false) {
         args.push("--recursive".to_string());
     }
@@ -537,16 +738,99 @@ pub async fn git_clone(
         args.push("--depth".to_string());
         args.push(depth.to_string());
     }
+    args.push("--".to_string());
     args.push(url.to_string());
     args.push(target_str);
 
-    let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-    let (code, out, err) =
-        run_git(&base, &args_ref)
-            .await
-            .unwrap_or((1, "".to_string(), "".to_string()));
+    let add_project = body.add_project.unwrap_or(true);
+    let root = path_slash(&target);
+    let relative = rel_path_slash(&base, &target);
+    let events = clone_events(base, args, env, askpass);
+
+    if body.background.unwrap_or(false) {
+        let title = format!("Clone {}", redact_git_output(url));
+        let job = crate::jobs::submit("git.clone", title, move |ctx| async move {
+            let mut events = std::pin::pin!(events);
+            let (mut code, mut err) = (1, String::new());
+            while let Some(event) = events.next().await {
+                match event {
+                    CloneEvent::Progress(progress) => {
+                        ctx.progress(Some(progress.percent), progress.phase)
+                    }
+                    CloneEvent::Message(_) => {}
+                    CloneEvent::Finished { code: c, stderr } => (code, err) = (c, stderr),
+                }
+            }
+            if code != 0 {
+                return Err(err.trim().to_string());
+            }
+            let project_id = if add_project {
+                Some(register_project(&state, &target).await?)
+            } else {
+                None
+            };
+            Ok(serde_json::json!({
+                "success": true,
+                "root": root,
+                "relative": relative,
+                "projectId": project_id,
+            }))
+        });
+        return crate::jobs::accepted(job);
+    }
+
+    if wants_event_stream(&headers) {
+        let stream = async_stream::stream! {
+            let mut events = std::pin::pin!(events);
+            while let Some(event) = events.next().await {
+                match event {
+                    CloneEvent::Progress(progress) => {
+                        yield sse_json("progress", serde_json::to_value(progress).unwrap_or_default());
+                    }
+                    CloneEvent::Message(line) => {
+                        yield sse_json("message", serde_json::json!({"line": line}));
+                    }
+                    CloneEvent::Finished { code: 0, .. } => {
+                        let project = if add_project {
+                            Some(register_project(&state, &target).await)
+                        } else {
+                            None
+                        };
+                        yield sse_json("done", serde_json::json!({
+                            "success": true,
+                            "root": root,
+                            "relative": relative,
+                            "projectId": project.as_ref().and_then(|p| p.as_ref().ok()),
+                            "projectError": project.as_ref().and_then(|p| p.as_ref().err()),
+                        }));
+                    }
+                    CloneEvent::Finished { code, stderr } => {
+                        let error_code = super::utils::classify_git_failure(code, "", &stderr)
+                            .map(|c| c.code)
+                            .unwrap_or("git_clone_failed");
+                        yield sse_json("error", serde_json::json!({
+                            "error": stderr.trim(),
+                            "code": error_code,
+                        }));
+                    }
+                }
+            }
+        };
+        let keep = KeepAlive::new()
+            .interval(Duration::from_secs(15))
+            .text("ping");
+        return Sse::new(stream).keep_alive(keep).into_response();
+    }
+
+    let mut events = std::pin::pin!(events);
+    let (mut code, mut err) = (1, String::new());
+    while let Some(event) = events.next().await {
+        if let CloneEvent::Finished { code: c, stderr } = event {
+            (code, err) = (c, stderr);
+        }
+    }
     if code != 0 {
-        if let Some(resp) = map_git_failure(code, &out, &err) {
+        if let Some(resp) = map_git_failure(code, "", &err) {
             return resp;
         }
         return (
@@ -556,10 +840,124 @@ pub async fn git_clone(
             .into_response();
     }
 
+    let project_id = if add_project {
+        match register_project(&state, &target).await {
+            Ok(id) => Some(id),
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({
+                        "error": err,
+                        "code": "project_register_failed",
+                        "root": root,
+                    })),
+                )
+                    .into_response();
+            }
+        }
+    } else {
+        None
+    };
+
     Json(serde_json::json!({
         "success": true,
-        "root": path_slash(&target),
-        "relative": rel_path_slash(&base, &target),
+        "root": root,
+        "relative": relative,
+        "projectId": project_id,
     }))
     .into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_progress_lines_with_and_without_counts() {
+        assert_eq!(
+            parse_clone_progress("Receiving objects:  45% (450/1000), 1.20 MiB | 2.00 MiB/s"),
+            Some(CloneProgress {
+                phase: "Receiving objects".into(),
+                percent: 45,
+                current: Some(450),
+                total: Some(1000),
+            })
+        );
+        let remote = parse_clone_progress("remote: Counting objects: 100% (12/12), done.")
+            .expect("remote progress");
+        assert_eq!(remote.phase, "Counting objects");
+        assert_eq!(remote.total, Some(12));
+        assert_eq!(
+            parse_clone_progress("Cloning into 'repo'...").map(|p| p.phase),
+            None
+        );
+        assert_eq!(
+            parse_clone_progress("warning: remote HEAD refers to nonexistent ref"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn clone_events_finish_with_the_exit_code() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let src = tmp.path().join("src");
+        std::fs::create_dir_all(&src).expect("mkdir src");
+        for args in [
+            &["init", "-q"][..],
+            &[
+                "-c",
+                "user.name=T",
+                "-c",
+                "user.email=t@t",
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "init",
+            ],
+        ] {
+            let (code, _, err) = run_git(&src, args).await.expect("run git");
+            assert_eq!(code, 0, "{err}");
+        }
+
+        let clone_args = |target: &str| {
+            vec![
+                "clone".to_string(),
+                "--progress".to_string(),
+                "--".to_string(),
+                path_slash(&src),
+                target.to_string(),
+            ]
+        };
+        let finished = |events: Vec<CloneEvent>| {
+            events.into_iter().find_map(|e| match e {
+                CloneEvent::Finished { code, stderr } => Some((code, stderr)),
+                _ => None,
+            })
+        };
+
+        let events: Vec<CloneEvent> = clone_events(
+            tmp.path().to_path_buf(),
+            clone_args("dst"),
+            Vec::new(),
+            None,
+        )
+        .collect()
+        .await;
+        assert_eq!(finished(events).map(|(code, _)| code), Some(0));
+        assert!(tmp.path().join("dst/.git").is_dir());
+
+        // Cloning into a non-empty directory fails and reports git's error.
+        let events: Vec<CloneEvent> = clone_events(
+            tmp.path().to_path_buf(),
+            clone_args("dst"),
+            Vec::new(),
+            None,
+        )
+        .collect()
+        .await;
+        let (code, stderr) = finished(events).expect("finished event");
+        assert_ne!(code, 0);
+        assert!(stderr.contains("already exists"), "{stderr}");
+    }
+}
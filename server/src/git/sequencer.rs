@@ -0,0 +1,190 @@
+use std::path::Path;
+
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use super::rebase::{GitRebaseTodoItem, parse_todo};
+use super::{GitConflictReportFile, conflict_report_files, run_git};
+
+/// Multi-commit cherry-pick/revert state shared by `git cherry-pick` and
+/// `git revert` (`CHERRY_PICK_HEAD`/`REVERT_HEAD` plus `sequencer/todo`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SequencerOperation {
+    CherryPick,
+    Revert,
+}
+
+impl SequencerOperation {
+    fn name(self) -> &'static str {
+        match self {
+            Self::CherryPick => "cherry-pick",
+            Self::Revert => "revert",
+        }
+    }
+
+    fn head_file(self) -> &'static str {
+        match self {
+            Self::CherryPick => "CHERRY_PICK_HEAD",
+            Self::Revert => "REVERT_HEAD",
+        }
+    }
+
+    fn conflict_code(self) -> &'static str {
+        match self {
+            Self::CherryPick => "cherry_pick_conflict",
+            Self::Revert => "revert_conflict",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSequencerConflictReport {
+    pub operation: &'static str,
+    /// Commit the operation stopped on.
+    pub stopped_at: Option<String>,
+    /// Commits still queued after `stoppedAt`.
+    pub remaining: Vec<GitRebaseTodoItem>,
+    pub files: Vec<GitConflictReportFile>,
+    pub truncated: bool,
+}
+
+async fn read_git_file(dir: &Path, name: &str) -> Option<String> {
+    let (code, out, _) = run_git(dir, &["rev-parse", "--git-path", name])
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    let raw = out.trim();
+    if code != 0 || raw.is_empty() {
+        return None;
+    }
+    let path = Path::new(raw);
+    let full = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        dir.join(path)
+    };
+    tokio::fs::read_to_string(full).await.ok()
+}
+
+/// The stop state after a failed cherry-pick/revert, or `None` when git
+/// failed without leaving an operation in progress.
+pub(crate) async fn sequencer_conflict_report(
+    dir: &Path,
+    op: SequencerOperation,
+) -> Option<GitSequencerConflictReport> {
+    let stopped_at = read_git_file(dir, op.head_file())
+        .await
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    let (files, truncated) = conflict_report_files(dir).await;
+    if stopped_at.is_none() && files.is_empty() {
+        return None;
+    }
+
+    let todo = read_git_file(dir, "sequencer/todo")
+        .await
+        .unwrap_or_default();
+    let remaining = parse_todo(&todo)
+        .into_iter()
+        .filter(|item| {
+            let (Some(hash), Some(stopped)) = (item.hash.as_deref(), stopped_at.as_deref()) else {
+                return true;
+            };
+            !stopped.starts_with(hash)
+        })
+        .collect();
+
+    Some(GitSequencerConflictReport {
+        operation: op.name(),
+        stopped_at,
+        remaining,
+        files,
+        truncated,
+    })
+}
+
+pub(crate) fn sequencer_conflict_response(
+    op: SequencerOperation,
+    report: GitSequencerConflictReport,
+) -> Response {
+    (
+        StatusCode::CONFLICT,
+        Json(serde_json::json!({
+            "error": format!("{} stopped on conflicts", op.name()),
+            "code": op.conflict_code(),
+            "hint": format!(
+                "Resolve the conflicts, then continue, skip or abort the {}.",
+                op.name()
+            ),
+            "conflict": report,
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[tokio::test]
+    async fn conflict_report_lists_stop_point_queue_and_hunks() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let repo = tmp.path();
+        let git = |args: &[&str]| {
+            let out = Command::new("git")
+                .args(args)
+                .current_dir(repo)
+                .output()
+                .expect("git");
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.name", "Fixture"]);
+        git(&["config", "user.email", "fixture@opencode-studio.local"]);
+        git(&["config", "commit.gpgsign", "false"]);
+        std::fs::write(repo.join("f.txt"), "base\n").unwrap();
+        git(&["add", "f.txt"]);
+        git(&["commit", "-q", "-m", "base"]);
+        let main = git(&["rev-parse", "--abbrev-ref", "HEAD"]);
+
+        git(&["checkout", "-q", "-b", "topic"]);
+        std::fs::write(repo.join("f.txt"), "topic\n").unwrap();
+        git(&["commit", "-q", "-am", "topic edit"]);
+        let first = git(&["rev-parse", "HEAD"]);
+        std::fs::write(repo.join("g.txt"), "more\n").unwrap();
+        git(&["add", "g.txt"]);
+        git(&["commit", "-q", "-m", "add g"]);
+        let second = git(&["rev-parse", "HEAD"]);
+
+        git(&["checkout", "-q", &main]);
+        assert!(
+            sequencer_conflict_report(repo, SequencerOperation::CherryPick)
+                .await
+                .is_none()
+        );
+        std::fs::write(repo.join("f.txt"), "main\n").unwrap();
+        git(&["commit", "-q", "-am", "main edit"]);
+        git(&["cherry-pick", &first, &second]);
+
+        let report = sequencer_conflict_report(repo, SequencerOperation::CherryPick)
+            .await
+            .expect("stopped on conflict");
+        assert_eq!(report.stopped_at.as_deref(), Some(first.as_str()));
+        let remaining: Vec<&str> = report
+            .remaining
+            .iter()
+            .filter_map(|i| i.hash.as_deref())
+            .collect();
+        assert_eq!(remaining.len(), 1);
+        assert!(second.starts_with(remaining[0]));
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(report.files[0].path, "f.txt");
+        assert_eq!(report.files[0].blocks.len(), 1);
+        assert_eq!(report.files[0].blocks[0].theirs, "topic");
+    }
+}
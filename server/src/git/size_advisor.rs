@@ -0,0 +1,586 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use axum::{
+    Json,
+    extract::Query,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt as _, AsyncReadExt as _, BufReader};
+
+use super::{
+    git_stdout_checked, map_git_failure, require_directory_raw, run_git, spawn_git_piped,
+    spawn_git_piped_stdin,
+};
+
+const DEFAULT_THRESHOLD_BYTES: u64 = 5 * 1024 * 1024;
+const MIN_THRESHOLD_BYTES: u64 = 64 * 1024;
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+const MAX_DIRECTORIES: usize = 20;
+// Walking every reachable object of a large repository takes a while.
+const SCAN_TIMEOUT: Duration = Duration::from_secs(300);
+const BATCH_CHECK_FORMAT: &str =
+    "--batch-check=%(objectname) %(objecttype) %(objectsize) %(objectsize:disk) %(rest)";
+
+// Directories that are almost always build output or installed dependencies.
+const GENERATED_DIR_NAMES: &[&str] = &[
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    "out",
+    ".next",
+    ".nuxt",
+    ".venv",
+    "venv",
+    "__pycache__",
+    ".gradle",
+    "coverage",
+];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSizeAdvisorQuery {
+    pub directory: Option<String>,
+    pub threshold_bytes: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSizeHistoryBlob {
+    pub oid: String,
+    pub path: String,
+    pub size: u64,
+    pub disk_size: u64,
+    pub in_head: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSizeTrackedFile {
+    pub path: String,
+    pub size: u64,
+    pub lfs_pattern_match: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSizeDirectory {
+    pub path: String,
+    pub disk_size: u64,
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSizeSuggestion {
+    /// `lfs-migrate` (use `POST /git/lfs/migrate`), `gitignore` (use
+    /// `POST /git/ignore`) or `history-rewrite` (manual).
+    pub kind: &'static str,
+    pub path: String,
+    pub pattern: Option<String>,
+    pub bytes: u64,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSizeAdvisorResponse {
+    pub threshold_bytes: u64,
+    pub object_store_bytes: u64,
+    pub history_blobs: Vec<GitSizeHistoryBlob>,
+    pub tracked_large_files: Vec<GitSizeTrackedFile>,
+    pub directories: Vec<GitSizeDirectory>,
+    pub suggestions: Vec<GitSizeSuggestion>,
+    pub truncated: bool,
+}
+
+/// A blob from history: `(oid, path, size, disk_size)`.
+type Blob = (String, String, u64, u64);
+
+/// `(size, path, oid, disk_size)`, ordered so a min-heap pops the smallest
+/// blob first and, among equal sizes, the path sorting last.
+type HeapEntry = (u64, Reverse<String>, String, u64);
+
+/// Running totals over the blobs reachable from any ref, fed one
+/// `git rev-list --objects --all | git cat-file --batch-check` line at a time.
+/// Only the `limit` largest blobs at or above `threshold` are kept.
+struct BlobScan {
+    threshold: u64,
+    limit: usize,
+    largest: BinaryHeap<Reverse<HeapEntry>>,
+    dropped: bool,
+    by_dir: HashMap<String, u64>,
+    total_disk: u64,
+}
+
+impl BlobScan {
+    fn new(threshold: u64, limit: usize) -> Self {
+        Self {
+            threshold,
+            limit,
+            largest: BinaryHeap::new(),
+            dropped: false,
+            by_dir: HashMap::new(),
+            total_disk: 0,
+        }
+    }
+
+    /// `<oid> <type> <size> <disk_size> <path>`; non-blobs are skipped.
+    fn push_line(&mut self, line: &str) {
+        let mut it = line.splitn(5, ' ');
+        let (Some(oid), Some("blob"), Some(size), Some(disk)) =
+            (it.next(), it.next(), it.next(), it.next())
+        else {
+            return;
+        };
+        let (Ok(size), Ok(disk)) = (size.parse::<u64>(), disk.parse::<u64>()) else {
+            return;
+        };
+        let path = it.next().unwrap_or("");
+
+        self.total_disk += disk;
+        let dir = if path.is_empty() {
+            "."
+        } else {
+            top_level_dir(path)
+        };
+        match self.by_dir.get_mut(dir) {
+            Some(total) => *total += disk,
+            None => {
+                self.by_dir.insert(dir.to_string(), disk);
+            }
+        }
+
+        if size < self.threshold {
+            return;
+        }
+        self.largest.push(Reverse((
+            size,
+            Reverse(path.to_string()),
+            oid.to_string(),
+            disk,
+        )));
+        if self.largest.len() > self.limit {
+            self.largest.pop();
+            self.dropped = true;
+        }
+    }
+
+    /// The kept blobs, largest first, then by path.
+    fn take_largest(&mut self) -> Vec<Blob> {
+        std::mem::take(&mut self.largest)
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, Reverse(path), oid, disk))| (oid, path, size, disk))
+            .collect()
+    }
+}
+
+fn scan_failure(err: String) -> Response {
+    map_git_failure(1, "", &err)
+        .unwrap_or_else(|| axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Stream `git rev-list --objects --all` into `git cat-file --batch-check`
+/// so each reachable object is reported with its path, without holding
+/// either listing in memory.
+async fn scan_reachable_blobs(dir: &Path, scan: &mut BlobScan) -> Result<(), Response> {
+    let mut rev_list =
+        spawn_git_piped(dir, &["rev-list", "--objects", "--all"], &[]).map_err(scan_failure)?;
+    let objects: Stdio = rev_list
+        .stdout
+        .take()
+        .ok_or_else(|| scan_failure("git rev-list has no stdout".to_string()))?
+        .try_into()
+        .map_err(|err: std::io::Error| scan_failure(err.to_string()))?;
+    let mut cat_file = spawn_git_piped_stdin(dir, &["cat-file", BATCH_CHECK_FORMAT], &[], objects)
+        .map_err(scan_failure)?;
+
+    let stderr_of = |child: &mut tokio::process::Child| {
+        let stderr = child.stderr.take();
+        tokio::spawn(async move {
+            let mut text = String::new();
+            if let Some(mut stderr) = stderr {
+                let _ = stderr.read_to_string(&mut text).await;
+            }
+            text
+        })
+    };
+    let rev_list_stderr = stderr_of(&mut rev_list);
+    let cat_file_stderr = stderr_of(&mut cat_file);
+    let stdout = cat_file
+        .stdout
+        .take()
+        .ok_or_else(|| scan_failure("git cat-file has no stdout".to_string()))?;
+
+    let run = async {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            scan.push_line(&line);
+        }
+        let rev_list_status = rev_list.wait().await;
+        let cat_file_status = cat_file.wait().await;
+        (rev_list_status, cat_file_status)
+    };
+    let Ok((rev_list_status, cat_file_status)) = tokio::time::timeout(SCAN_TIMEOUT, run).await
+    else {
+        return Err(scan_failure("git object scan timed out".to_string()));
+    };
+
+    for (status, stderr) in [
+        (rev_list_status, rev_list_stderr),
+        (cat_file_status, cat_file_stderr),
+    ] {
+        let code = status.ok().and_then(|s| s.code()).unwrap_or(1);
+        let stderr = stderr.await.unwrap_or_default();
+        if let Some(resp) = map_git_failure(code, "", &stderr) {
+            return Err(resp);
+        }
+    }
+    Ok(())
+}
+
+/// `git ls-tree -r -l --full-tree HEAD`: `<mode> blob <oid> <size>\t<path>`.
+fn parse_ls_tree_long(out: &str) -> Vec<(String, String, u64)> {
+    out.lines()
+        .filter_map(|line| {
+            let (meta, path) = line.split_once('\t')?;
+            let mut it = meta.split_whitespace();
+            let _mode = it.next()?;
+            if it.next()? != "blob" {
+                return None;
+            }
+            let oid = it.next()?;
+            let size = it.next()?.trim().parse().ok()?;
+            Some((path.to_string(), oid.to_string(), size))
+        })
+        .collect()
+}
+
+/// `git count-objects -v` reports KiB for `size` (loose) and `size-pack`.
+fn parse_count_objects_bytes(out: &str) -> u64 {
+    out.lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            matches!(key.trim(), "size" | "size-pack")
+                .then(|| value.trim().parse::<u64>().ok())
+                .flatten()
+        })
+        .sum::<u64>()
+        * 1024
+}
+
+/// `git check-attr filter -- <paths>`: `<path>: filter: <value>`.
+fn parse_lfs_filtered_paths(out: &str) -> HashSet<String> {
+    out.lines()
+        .filter_map(|line| {
+            let (path, value) = line.rsplit_once(": filter: ")?;
+            (value.trim() == "lfs").then(|| path.to_string())
+        })
+        .collect()
+}
+
+fn top_level_dir(path: &str) -> &str {
+    path.split_once('/').map(|(dir, _)| dir).unwrap_or(".")
+}
+
+fn generated_dir_prefix(path: &str) -> Option<String> {
+    let components: Vec<&str> = path.split('/').collect();
+    // The last component is the file itself.
+    let dirs = &components[..components.len().saturating_sub(1)];
+    let idx = dirs.iter().position(|c| GENERATED_DIR_NAMES.contains(c))?;
+    Some(format!("{}/", dirs[..=idx].join("/")))
+}
+
+fn lfs_pattern_for(path: &str) -> Option<String> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let (stem, ext) = name.rsplit_once('.')?;
+    if stem.is_empty() || ext.is_empty() || ext.len() > 10 {
+        return None;
+    }
+    Some(format!("*.{}", ext.to_ascii_lowercase()))
+}
+
+struct AdvisorInput<'a> {
+    threshold: u64,
+    limit: usize,
+    scan: BlobScan,
+    head_files: &'a [(String, String, u64)],
+    lfs_paths: &'a HashSet<String>,
+}
+
+fn build_report(mut input: AdvisorInput<'_>, object_store_bytes: u64) -> GitSizeAdvisorResponse {
+    let head_oids: HashSet<&str> = input
+        .head_files
+        .iter()
+        .map(|(_, oid, _)| oid.as_str())
+        .collect();
+
+    let mut truncated = input.scan.dropped;
+    let history_blobs: Vec<GitSizeHistoryBlob> = input
+        .scan
+        .take_largest()
+        .into_iter()
+        .map(|(oid, path, size, disk)| GitSizeHistoryBlob {
+            in_head: head_oids.contains(oid.as_str()),
+            oid,
+            path,
+            size,
+            disk_size: disk,
+        })
+        .collect();
+
+    let mut tracked_large_files: Vec<GitSizeTrackedFile> = input
+        .head_files
+        .iter()
+        .filter(|(_, _, size)| *size >= input.threshold)
+        .map(|(path, _, size)| GitSizeTrackedFile {
+            path: path.clone(),
+            size: *size,
+            lfs_pattern_match: input.lfs_paths.contains(path),
+        })
+        .collect();
+    tracked_large_files.sort_by(|a, b| b.size.cmp(&a.size).then(a.path.cmp(&b.path)));
+    truncated |= tracked_large_files.len() > input.limit;
+    tracked_large_files.truncate(input.limit);
+
+    let total_disk = input.scan.total_disk;
+    let mut directories: Vec<GitSizeDirectory> = std::mem::take(&mut input.scan.by_dir)
+        .into_iter()
+        .map(|(path, disk_size)| GitSizeDirectory {
+            path,
+            disk_size,
+            share: if total_disk == 0 {
+                0.0
+            } else {
+                disk_size as f64 / total_disk as f64
+            },
+        })
+        .collect();
+    directories.sort_by(|a, b| b.disk_size.cmp(&a.disk_size).then(a.path.cmp(&b.path)));
+    directories.truncate(MAX_DIRECTORIES);
+
+    let mut suggestions = Vec::new();
+    let mut generated: HashMap<String, u64> = HashMap::new();
+    for (path, _, size) in input.head_files {
+        if let Some(prefix) = generated_dir_prefix(path) {
+            *generated.entry(prefix).or_default() += size;
+        }
+    }
+    let mut generated: Vec<(String, u64)> = generated.into_iter().collect();
+    generated.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    for (prefix, bytes) in generated {
+        suggestions.push(GitSizeSuggestion {
+            kind: "gitignore",
+            reason: format!("{prefix} looks like generated output but is tracked"),
+            path: prefix,
+            pattern: None,
+            bytes,
+        });
+    }
+
+    for file in &tracked_large_files {
+        if generated_dir_prefix(&file.path).is_some() {
+            continue;
+        }
+        let reason = if file.lfs_pattern_match {
+            "Matches an LFS pattern but is stored as a regular blob".to_string()
+        } else {
+            "Large file tracked directly in git".to_string()
+        };
+        suggestions.push(GitSizeSuggestion {
+            kind: "lfs-migrate",
+            path: file.path.clone(),
+            pattern: lfs_pattern_for(&file.path),
+            bytes: file.size,
+            reason,
+        });
+    }
+
+    for blob in history_blobs.iter().filter(|b| !b.in_head) {
+        suggestions.push(GitSizeSuggestion {
+            kind: "history-rewrite",
+            path: blob.path.clone(),
+            pattern: None,
+            bytes: blob.size,
+            reason: "Only in history; removing it needs a history rewrite (e.g. git filter-repo)"
+                .to_string(),
+        });
+    }
+
+    GitSizeAdvisorResponse {
+        threshold_bytes: input.threshold,
+        object_store_bytes,
+        history_blobs,
+        tracked_large_files,
+        directories,
+        suggestions,
+        truncated,
+    }
+}
+
+/// Report large blobs in history, large tracked files and the directories
+/// that dominate the object store, with suggested fixes.
+pub async fn git_size_advisor(Query(q): Query<GitSizeAdvisorQuery>) -> Response {
+    let dir = match require_directory_raw(q.directory.as_deref()) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let threshold = q
+        .threshold_bytes
+        .unwrap_or(DEFAULT_THRESHOLD_BYTES)
+        .max(MIN_THRESHOLD_BYTES);
+    let limit = q.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let count_objects = match git_stdout_checked(&dir, &["count-objects", "-v"]).await {
+        Ok(out) => out,
+        Err(resp) => return resp,
+    };
+    // Only blobs reachable from refs; unreachable objects go away on gc.
+    let mut scan = BlobScan::new(threshold, limit);
+    if let Err(resp) = scan_reachable_blobs(&dir, &mut scan).await {
+        return resp;
+    }
+    // An unborn HEAD just means nothing is tracked yet.
+    let head_files = run_git(&dir, &["ls-tree", "-r", "-l", "--full-tree", "HEAD"])
+        .await
+        .ok()
+        .filter(|(code, _, _)| *code == 0)
+        .map(|(_, out, _)| parse_ls_tree_long(&out))
+        .unwrap_or_default();
+
+    let large_paths: Vec<&str> = head_files
+        .iter()
+        .filter(|(_, _, size)| *size >= threshold)
+        .map(|(path, _, _)| path.as_str())
+        .take(MAX_LIMIT)
+        .collect();
+    let lfs_paths = if large_paths.is_empty() {
+        HashSet::new()
+    } else {
+        let mut args = vec!["check-attr", "filter", "--"];
+        args.extend(large_paths);
+        run_git(&dir, &args)
+            .await
+            .ok()
+            .filter(|(code, _, _)| *code == 0)
+            .map(|(_, out, _)| parse_lfs_filtered_paths(&out))
+            .unwrap_or_default()
+    };
+
+    Json(build_report(
+        AdvisorInput {
+            threshold,
+            limit,
+            scan,
+            head_files: &head_files,
+            lfs_paths: &lfs_paths,
+        },
+        parse_count_objects_bytes(&count_objects),
+    ))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_report_classifies_blobs_and_suggests_fixes() {
+        let mut scan = BlobScan::new(5_000_000, 10);
+        for line in [
+            "a1 blob 9000000 8000000 assets/video.mp4",
+            "b2 blob 7000000 1000000 old/dump.sql",
+            "c3 blob 6000000 1000000 node_modules/pkg/index.js",
+            "d4 blob 100 100 README.md",
+            "e5 tree 90 90 assets",
+            "f6 commit 200 150",
+        ] {
+            scan.push_line(line);
+        }
+        let head_files = vec![
+            ("assets/video.mp4".to_string(), "a1".to_string(), 9_000_000),
+            (
+                "node_modules/pkg/index.js".to_string(),
+                "c3".to_string(),
+                6_000_000,
+            ),
+            ("README.md".to_string(), "d4".to_string(), 100),
+        ];
+        let report = build_report(
+            AdvisorInput {
+                threshold: 5_000_000,
+                limit: 10,
+                scan,
+                head_files: &head_files,
+                lfs_paths: &HashSet::new(),
+            },
+            0,
+        );
+
+        assert_eq!(report.history_blobs.len(), 3);
+        assert!(!report.history_blobs[1].in_head);
+        assert_eq!(report.directories[0].path, "assets");
+        let kinds: Vec<(&str, &str)> = report
+            .suggestions
+            .iter()
+            .map(|s| (s.kind, s.path.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("gitignore", "node_modules/"),
+                ("lfs-migrate", "assets/video.mp4"),
+                ("history-rewrite", "old/dump.sql"),
+            ]
+        );
+        assert_eq!(report.suggestions[1].pattern.as_deref(), Some("*.mp4"));
+    }
+
+    #[test]
+    fn blob_scan_keeps_only_the_largest_blobs() {
+        let mut scan = BlobScan::new(10, 2);
+        for line in [
+            "a blob 50 5 big/a b.bin",
+            "b blob 5 5 small.txt",
+            "c blob 30 3 c.bin",
+            "d blob 40 4 d.bin",
+        ] {
+            scan.push_line(line);
+        }
+        let blobs = scan.take_largest();
+        let paths: Vec<&str> = blobs.iter().map(|(_, path, _, _)| path.as_str()).collect();
+        assert_eq!(paths, vec!["big/a b.bin", "d.bin"]);
+        assert!(scan.dropped);
+        assert_eq!(scan.total_disk, 17);
+        assert_eq!(scan.by_dir.get("big"), Some(&5));
+        assert_eq!(scan.by_dir.get("."), Some(&12));
+    }
+
+    #[test]
+    fn parsers_handle_git_output_shapes() {
+        assert_eq!(
+            parse_ls_tree_long("100644 blob abc     1234\tdir/a b.bin\n"),
+            vec![("dir/a b.bin".to_string(), "abc".to_string(), 1234)]
+        );
+        assert_eq!(
+            parse_count_objects_bytes("count: 1\nsize: 4\nin-pack: 9\nsize-pack: 6\n"),
+            10 * 1024
+        );
+        let lfs = parse_lfs_filtered_paths("a.psd: filter: lfs\nb.txt: filter: unspecified\n");
+        assert!(lfs.contains("a.psd") && !lfs.contains("b.txt"));
+        assert_eq!(
+            generated_dir_prefix("web/dist/app.js").as_deref(),
+            Some("web/dist/")
+        );
+        assert_eq!(generated_dir_prefix("src/build"), None);
+    }
+}
@@ -12,6 +12,7 @@ use axum::{
         sse::{Event, KeepAlive, Sse},
     },
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::git2_utils;
@@ -146,7 +147,7 @@ async fn select_base_ref_for_unpublished(dir: &Path) -> Option<String> {
     None
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitStatusQuery {
     pub directory: Option<String>,
     pub offset: Option<usize>,
@@ -456,7 +457,7 @@ pub async fn git_status(Query(q): Query<GitStatusQuery>) -> Response {
     .into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitWatchQuery {
     pub directory: Option<String>,
     #[serde(rename = "intervalMs")]
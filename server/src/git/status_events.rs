@@ -0,0 +1,185 @@
+use axum::{
+    body::Body,
+    extract::OriginalUri,
+    http::{Method, Request},
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+
+use super::{abs_path, run_git};
+
+const GIT_ROUTE_PREFIX: &str = "/api/git/";
+
+/// POST routes under `/api/git` that never touch the index, HEAD or refs.
+const READ_ONLY_ROUTES: &[&str] = &[
+    "commit-message",
+    "compare-any",
+    "lint",
+    "submodules/status",
+    "safe-directory",
+    "identity",
+    "commit-template",
+    "locks/release",
+];
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusSummary {
+    /// `None` on a detached HEAD.
+    branch: Option<String>,
+    ahead: u32,
+    behind: u32,
+    staged: u32,
+    unstaged: u32,
+    untracked: u32,
+    conflicted: u32,
+}
+
+/// Summarize `git status --porcelain=v2 --branch` output.
+fn parse_porcelain_v2(out: &str) -> StatusSummary {
+    let mut summary = StatusSummary::default();
+    for line in out.lines() {
+        if let Some(head) = line.strip_prefix("# branch.head ") {
+            if head != "(detached)" {
+                summary.branch = Some(head.to_string());
+            }
+        } else if let Some(ab) = line.strip_prefix("# branch.ab ") {
+            let mut fields = ab.split_whitespace();
+            summary.ahead = fields
+                .next()
+                .and_then(|v| v.trim_start_matches('+').parse().ok())
+                .unwrap_or(0);
+            summary.behind = fields
+                .next()
+                .and_then(|v| v.trim_start_matches('-').parse().ok())
+                .unwrap_or(0);
+        } else if line.starts_with("1 ") || line.starts_with("2 ") {
+            let mut xy = line[2..].chars();
+            if xy.next().is_some_and(|c| c != '.') {
+                summary.staged += 1;
+            }
+            if xy.next().is_some_and(|c| c != '.') {
+                summary.unstaged += 1;
+            }
+        } else if line.starts_with("u ") {
+            summary.conflicted += 1;
+        } else if line.starts_with("? ") {
+            summary.untracked += 1;
+        }
+    }
+    summary
+}
+
+/// Name of the git operation for a mutating request, e.g. `commit` or
+/// `stash/pop`; `None` for reads and read-only POSTs.
+fn mutating_operation<'a>(method: &Method, path: &'a str) -> Option<&'a str> {
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return None;
+    }
+    let op = path.strip_prefix(GIT_ROUTE_PREFIX)?.trim_end_matches('/');
+    if op.is_empty() || READ_ONLY_ROUTES.contains(&op) || op.starts_with("gpg/") {
+        return None;
+    }
+    Some(op)
+}
+
+async fn publish_status_changed(directory: String, operation: String) {
+    let dir = abs_path(&directory);
+    super::blame::invalidate_blame_cache(&dir);
+    let Ok((0, out, _)) = run_git(&dir, &["status", "--porcelain=v2", "--branch"]).await else {
+        return;
+    };
+    let mut properties = serde_json::to_value(parse_porcelain_v2(&out)).unwrap_or_default();
+    properties["directory"] = serde_json::Value::String(directory);
+    properties["operation"] = serde_json::Value::String(operation);
+    let payload = serde_json::json!({
+        "type": "git.status-changed",
+        "properties": properties,
+    });
+    crate::global_sse_hub::publish_downstream_json(&payload.to_string());
+}
+
+/// Announce `git.status-changed` after a mutating git request succeeds, so
+/// open views refresh without polling. The status is read after the
+/// response is sent.
+pub(crate) async fn announce_status_change(req: Request<Body>, next: Next) -> Response {
+    let path = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.path().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let Some(operation) = mutating_operation(req.method(), &path).map(str::to_string) else {
+        return next.run(req).await;
+    };
+    let directory = crate::audit::directory_for(&req);
+
+    let response = next.run(req).await;
+    if response.status().is_success()
+        && let Some(directory) = directory
+    {
+        tokio::spawn(publish_status_changed(directory, operation));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn porcelain_v2_is_summarized() {
+        let out = "# branch.oid 1234\n\
+# branch.head main\n\
+# branch.upstream origin/main\n\
+# branch.ab +2 -1\n\
+1 M. N... 100644 100644 100644 aaa bbb src/a.rs\n\
+1 .M N... 100644 100644 100644 aaa bbb src/b.rs\n\
+1 MM N... 100644 100644 100644 aaa bbb src/c.rs\n\
+2 R. N... 100644 100644 100644 aaa bbb R100 new.rs\told.rs\n\
+u UU N... 100644 100644 100644 100644 aaa bbb ccc conflict.rs\n\
+? notes.txt\n";
+        assert_eq!(
+            parse_porcelain_v2(out),
+            StatusSummary {
+                branch: Some("main".to_string()),
+                ahead: 2,
+                behind: 1,
+                staged: 3,
+                unstaged: 2,
+                untracked: 1,
+                conflicted: 1,
+            }
+        );
+        assert_eq!(
+            parse_porcelain_v2("# branch.head (detached)\n").branch,
+            None
+        );
+    }
+
+    #[test]
+    fn only_mutating_git_routes_announce() {
+        assert_eq!(
+            mutating_operation(&Method::POST, "/api/git/commit"),
+            Some("commit")
+        );
+        assert_eq!(
+            mutating_operation(&Method::POST, "/api/git/stash/pop"),
+            Some("stash/pop")
+        );
+        assert_eq!(
+            mutating_operation(&Method::DELETE, "/api/git/branches"),
+            Some("branches")
+        );
+        assert_eq!(mutating_operation(&Method::GET, "/api/git/status"), None);
+        assert_eq!(
+            mutating_operation(&Method::POST, "/api/git/commit-message"),
+            None
+        );
+        assert_eq!(
+            mutating_operation(&Method::POST, "/api/git/gpg/disable-signing"),
+            None
+        );
+        assert_eq!(mutating_operation(&Method::POST, "/api/session"), None);
+    }
+}
@@ -1,12 +1,25 @@
+use std::path::Path;
+use std::time::Duration;
+
 use axum::{
     Json,
     extract::Query,
     http::StatusCode,
-    response::{IntoResponse, Response},
+    response::{
+        IntoResponse, Response,
+        sse::{KeepAlive, Sse},
+    },
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use super::{DirectoryQuery, lock_repo, map_git_failure, require_directory, run_git, run_git_env};
+use super::{
+    DirectoryQuery, git_stdout, is_safe_repo_rel_path, lock_repo, map_git_failure,
+    require_directory, run_git, run_git_env, sse_json,
+};
+
+/// Nested submodules deeper than this are not inspected.
+const MAX_SUBMODULE_DEPTH: usize = 8;
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -89,7 +102,7 @@ pub async fn git_submodules(Query(q): Query<DirectoryQuery>) -> Response {
     Json(GitSubmoduleListResponse { submodules }).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitSubmoduleAddBody {
     pub url: Option<String>,
@@ -105,7 +118,7 @@ pub async fn git_submodule_add(
         Ok(d) => d,
         Err(resp) => return *resp,
     };
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "submodule-add").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -166,7 +179,7 @@ pub async fn git_submodule_add(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitSubmodulePathBody {
     pub path: Option<String>,
 }
@@ -179,7 +192,7 @@ pub async fn git_submodule_init(
         Ok(d) => d,
         Err(resp) => return *resp,
     };
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "submodule-init").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -214,7 +227,7 @@ pub async fn git_submodule_init(
     Json(serde_json::json!({"success": true})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitSubmoduleUpdateBody {
     pub path: Option<String>,
@@ -230,7 +243,7 @@ pub async fn git_submodule_update(
         Ok(d) => d,
         Err(resp) => return *resp,
     };
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "submodule-update").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -269,3 +282,348 @@ pub async fn git_submodule_update(
 
     Json(serde_json::json!({"success": true})).into_response()
 }
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSubmoduleStatus {
+    /// Relative to the top-level repository, slash-separated.
+    pub path: String,
+    /// Containing submodule, `None` for the top-level repository.
+    pub parent: Option<String>,
+    pub depth: usize,
+    /// Commit recorded in the parent's index.
+    pub recorded_sha: String,
+    /// Commit checked out in the submodule, `None` when not initialized.
+    pub head_sha: Option<String>,
+    pub initialized: bool,
+    /// The checkout differs from the recorded commit.
+    pub out_of_sync: bool,
+    pub detached: bool,
+    pub branch: Option<String>,
+    /// Tracked files have changes.
+    pub dirty: bool,
+    pub untracked: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GitSubmoduleStatusResponse {
+    pub submodules: Vec<GitSubmoduleStatus>,
+}
+
+/// `(recorded sha, path)` for every gitlink in `git ls-files -s` output.
+fn parse_gitlinks(stdout: &str) -> Vec<(String, String)> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let (meta, path) = line.split_once('\t')?;
+            let mut fields = meta.split_whitespace();
+            if fields.next()? != "160000" {
+                return None;
+            }
+            let sha = fields.next()?;
+            Some((sha.to_string(), path.to_string()))
+        })
+        .collect()
+}
+
+async fn inspect_submodule(
+    parent_dir: &Path,
+    parent: Option<&str>,
+    depth: usize,
+    recorded_sha: String,
+    rel_path: &str,
+) -> GitSubmoduleStatus {
+    let sub_dir = parent_dir.join(rel_path);
+    let path = match parent {
+        Some(parent) => format!("{parent}/{rel_path}"),
+        None => rel_path.to_string(),
+    };
+    let initialized = sub_dir.join(".git").exists();
+    let head_sha = if initialized {
+        git_stdout(&sub_dir, &["rev-parse", "HEAD"])
+            .await
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    } else {
+        None
+    };
+    let branch = if head_sha.is_some() {
+        git_stdout(&sub_dir, &["symbolic-ref", "-q", "--short", "HEAD"])
+            .await
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    } else {
+        None
+    };
+    let (dirty, untracked) = if head_sha.is_some() {
+        let porcelain = git_stdout(&sub_dir, &["status", "--porcelain"])
+            .await
+            .unwrap_or_default();
+        (
+            porcelain.lines().any(|l| !l.starts_with("??")),
+            porcelain.lines().any(|l| l.starts_with("??")),
+        )
+    } else {
+        (false, false)
+    };
+
+    GitSubmoduleStatus {
+        path,
+        parent: parent.map(str::to_string),
+        depth,
+        out_of_sync: head_sha.as_deref().is_some_and(|h| h != recorded_sha),
+        detached: head_sha.is_some() && branch.is_none(),
+        recorded_sha,
+        head_sha,
+        initialized,
+        branch,
+        dirty,
+        untracked,
+    }
+}
+
+async fn collect_submodule_status(root: &Path) -> Vec<GitSubmoduleStatus> {
+    let mut out = Vec::new();
+    // (directory, path prefix relative to root, depth)
+    let mut pending: Vec<(std::path::PathBuf, Option<String>, usize)> =
+        vec![(root.to_path_buf(), None, 0)];
+    while let Some((dir, prefix, depth)) = pending.pop() {
+        let Some(listing) = git_stdout(&dir, &["ls-files", "-s"]).await else {
+            continue;
+        };
+        for (sha, rel_path) in parse_gitlinks(&listing) {
+            let status = inspect_submodule(&dir, prefix.as_deref(), depth, sha, &rel_path).await;
+            if status.head_sha.is_some() && depth + 1 < MAX_SUBMODULE_DEPTH {
+                pending.push((dir.join(&rel_path), Some(status.path.clone()), depth + 1));
+            }
+            out.push(status);
+        }
+    }
+    out.sort_by(|a, b| a.path.cmp(&b.path));
+    out
+}
+
+/// Recursive submodule status: recorded vs checked-out commit, dirty and
+/// detached state for every submodule, nested ones included.
+pub async fn git_submodules_status(Query(q): Query<DirectoryQuery>) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+    let (code, out, err) = run_git(&dir, &["rev-parse", "--show-toplevel"])
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    if code != 0 {
+        if let Some(resp) = map_git_failure(code, &out, &err) {
+            return resp;
+        }
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({"error": err.trim(), "code": "not_git_repo"})),
+        )
+            .into_response();
+    }
+    let root = super::abs_path(out.trim());
+
+    let submodules = collect_submodule_status(&root).await;
+    Json(GitSubmoduleStatusResponse { submodules }).into_response()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GitSubmoduleStep {
+    Init,
+    Sync,
+    Update,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSubmoduleBatchBody {
+    /// Run in this order for each submodule; defaults to init, sync, update.
+    pub steps: Option<Vec<GitSubmoduleStep>>,
+    /// Defaults to every top-level submodule.
+    pub paths: Option<Vec<String>>,
+    /// `update --remote`: move to the tip of the tracked branch.
+    pub remote: Option<bool>,
+    pub recursive: Option<bool>,
+}
+
+fn step_args(step: GitSubmoduleStep, remote: bool, recursive: bool, path: &str) -> Vec<&str> {
+    let mut args = vec!["submodule"];
+    match step {
+        GitSubmoduleStep::Init => args.push("init"),
+        GitSubmoduleStep::Sync => {
+            args.push("sync");
+            if recursive {
+                args.push("--recursive");
+            }
+        }
+        GitSubmoduleStep::Update => {
+            args.push("update");
+            if remote {
+                args.push("--remote");
+            }
+            if recursive {
+                args.extend(["--init", "--recursive"]);
+            }
+        }
+    }
+    args.extend(["--", path]);
+    args
+}
+
+/// Run `init` / `sync` / `update` over submodules one at a time and stream
+/// per-submodule progress. The repository stays locked until the stream ends.
+pub async fn git_submodules_batch(
+    Query(q): Query<DirectoryQuery>,
+    Json(body): Json<GitSubmoduleBatchBody>,
+) -> Response {
+    let dir = match require_directory(&q) {
+        Ok(d) => d,
+        Err(resp) => return *resp,
+    };
+
+    let steps = body.steps.filter(|s| !s.is_empty()).unwrap_or_else(|| {
+        vec![
+            GitSubmoduleStep::Init,
+            GitSubmoduleStep::Sync,
+            GitSubmoduleStep::Update,
+        ]
+    });
+    let remote = body.remote.unwrap_or(false);
+    let recursive = body.recursive.unwrap_or(false);
+
+    let paths: Vec<String> = match body.paths {
+        Some(paths) => {
+            let paths: Vec<String> = paths
+                .iter()
+                .map(|p| p.trim().trim_end_matches('/').to_string())
+                .filter(|p| !p.is_empty())
+                .collect();
+            if let Some(bad) = paths.iter().find(|p| !is_safe_repo_rel_path(p)) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": format!("Invalid path: {bad}"), "code": "invalid_path"})),
+                )
+                    .into_response();
+            }
+            paths
+        }
+        None => {
+            let gitmodules = tokio::fs::read_to_string(dir.join(".gitmodules"))
+                .await
+                .unwrap_or_default();
+            let mut paths: Vec<String> = parse_gitmodules(&gitmodules)
+                .into_iter()
+                .map(|s| s.path)
+                .collect();
+            paths.sort();
+            paths
+        }
+    };
+    if paths.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "No submodules to update", "code": "no_submodules"})),
+        )
+            .into_response();
+    }
+
+    let guard = match lock_repo(&dir, "submodule-batch").await {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+
+    let stream = async_stream::stream! {
+        let _guard = guard;
+        yield sse_json("start", serde_json::json!({
+            "total": paths.len(),
+            "steps": steps,
+        }));
+
+        let mut ok = 0usize;
+        let mut failed = 0usize;
+        for (index, path) in paths.iter().enumerate() {
+            let mut error: Option<String> = None;
+            for step in &steps {
+                yield sse_json("progress", serde_json::json!({
+                    "index": index,
+                    "path": path,
+                    "step": step,
+                    "status": "running",
+                }));
+                let args = step_args(*step, remote, recursive, path);
+                let result = run_git_env(&dir, &args, &[]).await;
+                let step_error = match result {
+                    Ok((0, _, _)) => None,
+                    Ok((_, out, err)) => Some(
+                        Some(err.trim())
+                            .filter(|e| !e.is_empty())
+                            .unwrap_or(out.trim())
+                            .to_string(),
+                    ),
+                    Err(err) => Some(err),
+                };
+                yield sse_json("progress", serde_json::json!({
+                    "index": index,
+                    "path": path,
+                    "step": step,
+                    "status": if step_error.is_some() { "failed" } else { "ok" },
+                    "error": step_error,
+                }));
+                if step_error.is_some() {
+                    error = step_error;
+                    break;
+                }
+            }
+            if error.is_some() {
+                failed += 1;
+            } else {
+                ok += 1;
+            }
+        }
+
+        yield sse_json("done", serde_json::json!({ "ok": ok, "failed": failed }));
+    };
+
+    let keep = KeepAlive::new()
+        .interval(Duration::from_secs(15))
+        .text("ping");
+    Sse::new(stream).keep_alive(keep).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_gitlinks_and_step_args() {
+        let listing = "100644 1111111111111111111111111111111111111111 0\tREADME.md\n\
+160000 2222222222222222222222222222222222222222 0\tvendor/lib\n";
+        assert_eq!(
+            parse_gitlinks(listing),
+            vec![(
+                "2222222222222222222222222222222222222222".to_string(),
+                "vendor/lib".to_string()
+            )]
+        );
+
+        assert_eq!(
+            step_args(GitSubmoduleStep::Update, true, true, "vendor/lib"),
+            vec![
+                "submodule",
+                "update",
+                "--remote",
+                "--init",
+                "--recursive",
+                "--",
+                "vendor/lib"
+            ]
+        );
+        assert_eq!(
+            step_args(GitSubmoduleStep::Init, true, true, "a"),
+            vec!["submodule", "init", "--", "a"]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use std::convert::Infallible;
 use std::path::Component;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
@@ -5,7 +6,7 @@ use std::process::Stdio;
 use axum::{
     Json,
     http::StatusCode,
-    response::{IntoResponse, Response},
+    response::{IntoResponse, Response, sse::Event},
 };
 use tokio::process::Command;
 
@@ -651,6 +652,26 @@ pub(crate) async fn git_config_get(
     if v.is_empty() { None } else { Some(v) }
 }
 
+/// Stdout of a git command, or the mapped error response when it fails.
+pub(crate) async fn git_stdout_checked(
+    directory: &Path,
+    args: &[&str],
+) -> Result<String, Response> {
+    let (code, out, err) =
+        super::run_git(directory, args)
+            .await
+            .unwrap_or((1, "".to_string(), "".to_string()));
+    match map_git_failure(code, &out, &err) {
+        Some(resp) => Err(resp),
+        None => Ok(out),
+    }
+}
+
+/// Named SSE event carrying a JSON payload, for the streaming git routes.
+pub(crate) fn sse_json(event: &str, payload: serde_json::Value) -> Result<Event, Infallible> {
+    Ok(Event::default().event(event).data(payload.to_string()))
+}
+
 #[cfg(test)]
 mod path_tests {
     use super::*;
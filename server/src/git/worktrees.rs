@@ -7,6 +7,7 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::{
@@ -177,7 +178,7 @@ pub async fn git_worktrees(Query(q): Query<DirectoryQuery>) -> Response {
     Json(worktrees).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitWorktreeAddBody {
     pub path: Option<String>,
@@ -233,7 +234,7 @@ pub async fn git_worktree_add(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "worktree-add").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -304,7 +305,7 @@ pub async fn git_worktree_add(
     Json(serde_json::json!({"success": true, "path": target.to_string_lossy()})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GitWorktreeRemoveBody {
     pub path: Option<String>,
 }
@@ -318,7 +319,7 @@ pub async fn git_worktree_remove(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "worktree-remove").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -359,7 +360,7 @@ pub async fn git_worktree_prune(Query(q): Query<DirectoryQuery>) -> Response {
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "worktree-prune").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -382,7 +383,7 @@ pub async fn git_worktree_prune(Query(q): Query<DirectoryQuery>) -> Response {
     Json(serde_json::json!({"success": true, "output": out.trim()})).into_response()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GitWorktreeMigrateBody {
     pub source_path: Option<String>,
@@ -401,7 +402,7 @@ pub async fn git_worktree_migrate(
         Err(resp) => return *resp,
     };
 
-    let _guard = match lock_repo(&dir).await {
+    let _guard = match lock_repo(&dir, "worktree-migrate").await {
         Ok(g) => g,
         Err(resp) => return resp,
     };
@@ -1,5 +1,6 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::convert::Infallible;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, LazyLock, Mutex};
 use std::time::Duration;
@@ -11,7 +12,8 @@ use axum::{
 };
 use bytes::{BufMut as _, Bytes, BytesMut};
 use futures_util::StreamExt;
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast;
 
 use crate::ApiResult;
@@ -28,6 +30,11 @@ const DOWNSTREAM_WS_PING_INTERVAL: Duration = Duration::from_secs(25);
 const UPSTREAM_RETRY_BASE_DELAY: Duration = Duration::from_millis(900);
 const UPSTREAM_RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
 const UPSTREAM_SETTINGS_REFRESH: Duration = Duration::from_millis(900);
+const POOL_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+const REPLAY_SNAPSHOT_VERSION: u64 = 1;
+// Clients stop resuming long before this; older snapshots only waste replay budget.
+const REPLAY_SNAPSHOT_MAX_AGE_MS: i64 = 5 * 60 * 1000;
 
 #[derive(Clone, Debug)]
 struct HubFrame {
@@ -174,6 +181,72 @@ impl GlobalSseHub {
         let _ = self.tx.send(frame);
     }
 
+    /// Broadcast a restart notice and close downstream streams so a graceful
+    /// shutdown does not wait on long-lived connections.
+    fn publish_server_restarting_and_close(&self, retry_after: Duration) {
+        let payload = serde_json::json!({
+            "type": "opencode-studio:server-restarting",
+            "timestamp": time::OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000,
+            "properties": {
+                "retryAfterMs": retry_after.as_millis() as u64,
+            }
+        });
+        let encoded = serde_json::to_string(&payload).unwrap_or_else(|_| "{}".to_string());
+        self.publish_json_inner(&encoded, true, false);
+    }
+
+    fn snapshot(&self) -> ReplaySnapshot {
+        let frames = {
+            let buf = self.buffer.lock().unwrap();
+            buf.items
+                .iter()
+                .map(|frame| ReplaySnapshotFrame {
+                    seq: frame.seq,
+                    payload: frame.payload_json.to_string(),
+                })
+                .collect()
+        };
+        ReplaySnapshot {
+            version: REPLAY_SNAPSHOT_VERSION,
+            saved_at: now_millis(),
+            next_seq: self.next_seq.load(Ordering::SeqCst),
+            latest_unbuffered_seq: self.latest_unbuffered_seq(),
+            frames,
+        }
+    }
+
+    /// Seed an unused hub from a previous process so `Last-Event-ID` keeps
+    /// working across restarts. Returns the number of restored frames.
+    fn restore(&self, snapshot: ReplaySnapshot) -> usize {
+        if snapshot.version != REPLAY_SNAPSHOT_VERSION || self.latest_seq() != 0 {
+            return 0;
+        }
+
+        let mut buf = self.buffer.lock().unwrap();
+        let mut next_seq = snapshot.next_seq.max(1);
+        for frame in snapshot.frames {
+            if frame.seq == 0 || frame.payload.trim().is_empty() {
+                continue;
+            }
+            let restored = HubFrame {
+                seq: frame.seq,
+                bytes: sse_frame(frame.seq, &frame.payload),
+                payload_json: Arc::<str>::from(frame.payload.as_str()),
+                close_after: false,
+            };
+            let len = restored.storage_bytes();
+            if buf.bytes.saturating_add(len) > HUB_REPLAY_MAX_BYTES {
+                break;
+            }
+            next_seq = next_seq.max(frame.seq.saturating_add(1));
+            buf.bytes = buf.bytes.saturating_add(len);
+            buf.items.push_back(restored);
+        }
+        self.next_seq.store(next_seq, Ordering::SeqCst);
+        self.mark_unbuffered_seq(snapshot.latest_unbuffered_seq);
+        buf.items.len()
+    }
+
     fn replay_since_until(&self, last_seq: u64, max_seq_inclusive: u64) -> Vec<HubFrame> {
         let buf = self.buffer.lock().unwrap();
         buf.items
@@ -202,9 +275,50 @@ impl GlobalSseHub {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplaySnapshot {
+    version: u64,
+    saved_at: i64,
+    next_seq: u64,
+    latest_unbuffered_seq: u64,
+    frames: Vec<ReplaySnapshotFrame>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ReplaySnapshotFrame {
+    seq: u64,
+    payload: String,
+}
+
+fn now_millis() -> i64 {
+    (time::OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64
+}
+
 static GLOBAL_HUB: LazyLock<GlobalSseHub> = LazyLock::new(GlobalSseHub::new);
 
 // Internal publish helpers used by other server modules.
+//
+// Events the studio adds to the upstream OpenCode stream:
+// - `opencode-studio:upstream-disconnected`, `opencode-studio:server-restarting`,
+//   `opencode-studio:replay-gap`: hub/connection state (this module).
+// - `opencode-studio:session-activity`: derived session status (this module).
+// - `opencode-studio:opencode-restart`: an OpenCode instance moving through
+//   waiting/stopping/starting/ready/failed during a restart (opencode).
+// - `opencode-studio:fs-changed`: workspace file changes (fs, fs_watch).
+// - `opencode-studio:session-index-hot`: the startup session storage scan
+//   finished (opencode_session::warmup).
+// - `chat-sidebar.delta`: sidebar state patches (chat_sidebar).
+// - `sidebar.delta`: per-directory added/updated/removed sessions and
+//   counters, with a `since` cursor (sidebar_sync).
+// - `config.settings.replace`: settings updates (settings_events).
+// - `terminal-ui-state.patch`, `terminal-ui-state.snapshot` (terminal_ui_state).
+// - `git.remote-updated`: background fetch results (git::auto_fetch).
+// - `git.status-changed`: directory, operation, branch, ahead/behind and
+//   staged/unstaged/untracked/conflicted counts after a studio-initiated git
+//   operation succeeds (git::status_events).
+// - `attachment-cache.pressure`: the attachment cache is evicting heavily
+//   (attachment_cache).
 pub(crate) fn publish_downstream_json(payload_json: &str) {
     GLOBAL_HUB.publish_json(payload_json);
 }
@@ -213,6 +327,74 @@ pub(crate) fn downstream_client_count() -> usize {
     GLOBAL_HUB.downstream_client_count()
 }
 
+pub(crate) fn publish_server_restarting_and_close(retry_after: Duration) {
+    GLOBAL_HUB.publish_server_restarting_and_close(retry_after);
+}
+
+pub(crate) enum DownstreamRecv {
+    Json(Arc<str>),
+    /// The subscriber fell behind and frames were dropped.
+    Lagged,
+    Closed,
+}
+
+/// In-process view of the downstream stream for server-side SSE endpoints.
+pub(crate) struct DownstreamSubscriber {
+    rx: broadcast::Receiver<HubFrame>,
+}
+
+impl DownstreamSubscriber {
+    pub(crate) async fn recv(&mut self) -> DownstreamRecv {
+        match self.rx.recv().await {
+            Ok(frame) => DownstreamRecv::Json(frame.payload_json),
+            Err(broadcast::error::RecvError::Lagged(_)) => DownstreamRecv::Lagged,
+            Err(broadcast::error::RecvError::Closed) => DownstreamRecv::Closed,
+        }
+    }
+}
+
+pub(crate) fn subscribe_downstream() -> DownstreamSubscriber {
+    DownstreamSubscriber {
+        rx: GLOBAL_HUB.tx.subscribe(),
+    }
+}
+
+/// Write the replay buffer to `path` (via a temp file) for the next process.
+pub(crate) fn persist_replay_buffer(path: &Path) -> std::io::Result<()> {
+    let encoded = serde_json::to_vec(&GLOBAL_HUB.snapshot())?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, encoded)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Load (and consume) a replay snapshot written by a previous process.
+pub(crate) fn restore_replay_buffer(path: &Path) -> usize {
+    let Ok(raw) = std::fs::read(path) else {
+        return 0;
+    };
+    let _ = std::fs::remove_file(path);
+
+    let snapshot = match serde_json::from_slice::<ReplaySnapshot>(&raw) {
+        Ok(snapshot) => snapshot,
+        Err(err) => {
+            tracing::warn!(
+                target: "opencode_studio.global_sse_hub.downstream",
+                path = %path.display(),
+                error = %err,
+                "Ignoring unreadable SSE replay snapshot"
+            );
+            return 0;
+        }
+    };
+    if now_millis().saturating_sub(snapshot.saved_at) > REPLAY_SNAPSHOT_MAX_AGE_MS {
+        return 0;
+    }
+    GLOBAL_HUB.restore(snapshot)
+}
+
 #[cfg(test)]
 pub(crate) struct TestDownstreamSubscriber {
     rx: broadcast::Receiver<HubFrame>,
@@ -390,13 +572,13 @@ fn event_matches_directory_filter(payload_json: &str, directory_filter: Option<&
     event_dir.starts_with(&prefix)
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct GlobalEventSseQuery {
     pub directory: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct GlobalEventWsQuery {
     pub cursor: Option<u64>,
@@ -499,6 +681,8 @@ pub(crate) fn start_global_sse_hub_if_needed(state: Arc<crate::AppState>) {
         return;
     }
 
+    tokio::spawn(watch_opencode_pool(state.clone()));
+
     tokio::spawn(async move {
         let mut last_upstream_event_id: Option<String> = None;
         let mut attempt: u64 = 0;
@@ -616,17 +800,7 @@ pub(crate) fn start_global_sse_hub_if_needed(state: Arc<crate::AppState>) {
                         // SSE is UTF-8 text; normalize CRLF and accumulate bytes.
                         push_normalized_sse_chunk(&mut buffer, &chunk, &mut prev_cr);
 
-                        while scan_idx + 1 < buffer.len() {
-                            if buffer[scan_idx] != b'\n' || buffer[scan_idx + 1] != b'\n' {
-                                scan_idx += 1;
-                                continue;
-                            }
-
-                            let block = buffer.split_to(scan_idx).freeze();
-                            // drop delimiter
-                            let _ = buffer.split_to(2);
-                            scan_idx = 0;
-
+                        while let Some(block) = take_sse_block(&mut buffer, &mut scan_idx) {
                             let Ok(block_text) = std::str::from_utf8(&block) else {
                                 continue;
                             };
@@ -639,143 +813,11 @@ pub(crate) fn start_global_sse_hub_if_needed(state: Arc<crate::AppState>) {
                             if let Some(id) = upstream_id {
                                 last_upstream_event_id = Some(id);
                             }
-                            let Some(mut raw) = json.take() else {
+                            let Some(raw) = json.take() else {
                                 continue;
                             };
 
-                            if !crate::opencode_proxy::sanitize_sse_event_data(&mut raw, &filter, &detail) {
-                                continue;
-                            }
-                            let payload_json = match serde_json::to_string(&raw) {
-                                Ok(v) => v,
-                                Err(_) => continue,
-                            };
-                            GLOBAL_HUB.publish_json(&payload_json);
-
-                            let mut sidebar_needs_state_invalidate = false;
-
-                            if let Some(payload) = sse_event_payload(&raw)
-                                && let Some(event_type) = payload.get("type").and_then(|v| v.as_str())
-                            {
-                                let ty = event_type.trim().to_ascii_lowercase();
-                                let props = payload
-                                    .get("properties")
-                                    .and_then(|v| v.as_object());
-
-                                let read_session_id = |props: Option<&serde_json::Map<String, serde_json::Value>>| {
-                                    props
-                                        .and_then(|m| {
-                                            m.get("sessionID")
-                                                .or_else(|| m.get("sessionId"))
-                                                .or_else(|| m.get("session_id"))
-                                        })
-                                        .and_then(|v| v.as_str())
-                                        .map(|v| v.trim().to_string())
-                                        .filter(|v| !v.is_empty())
-                                };
-
-                                match ty.as_str() {
-                                    "session.created" | "session.updated" => {
-                                        if let Some(props) = props
-                                            && let Some(session) = props.get("session") {
-                                                state
-                                                    .directory_session_index
-                                                    .upsert_summary_from_value(session);
-                                                sidebar_needs_state_invalidate = true;
-                                            }
-                                    }
-                                    "session.deleted" => {
-                                        if let Some(sid) = read_session_id(props) {
-                                            state.directory_session_index.remove_summary(&sid);
-                                            sidebar_needs_state_invalidate = true;
-                                        }
-                                    }
-                                    "session.status" => {
-                                        if let Some(props) = props {
-                                            let sid = read_session_id(Some(props));
-                                            let status = props
-                                                .get("status")
-                                                .and_then(|v| v.get("type"))
-                                                .and_then(|v| v.as_str())
-                                                .unwrap_or("")
-                                                .trim();
-                                            if let Some(sid) = sid
-                                                && (status == "busy" || status == "retry" || status == "idle") {
-                                                    state
-                                                        .directory_session_index
-                                                        .upsert_runtime_status(&sid, status);
-                                                    sidebar_needs_state_invalidate = true;
-                                                }
-                                        }
-                                    }
-                                    "session.idle" => {
-                                        if let Some(sid) = read_session_id(props) {
-                                            state.directory_session_index.upsert_runtime_status(&sid, "idle");
-                                            state.directory_session_index.upsert_runtime_phase(&sid, "idle");
-                                            state.directory_session_index.upsert_runtime_attention(&sid, None);
-                                            sidebar_needs_state_invalidate = true;
-                                        }
-                                    }
-                                    "session.error" => {
-                                        if let Some(sid) = read_session_id(props) {
-                                            state.directory_session_index.upsert_runtime_status(&sid, "idle");
-                                            state.directory_session_index.upsert_runtime_phase(&sid, "idle");
-                                            state.directory_session_index.upsert_runtime_attention(&sid, None);
-                                            sidebar_needs_state_invalidate = true;
-                                        }
-                                    }
-                                    "permission.asked" => {
-                                        if let Some(sid) = read_session_id(props) {
-                                            state
-                                                .directory_session_index
-                                                .upsert_runtime_attention(&sid, Some("permission"));
-                                            sidebar_needs_state_invalidate = true;
-                                        }
-                                    }
-                                    "question.asked" => {
-                                        if let Some(sid) = read_session_id(props) {
-                                            state
-                                                .directory_session_index
-                                                .upsert_runtime_attention(&sid, Some("question"));
-                                            sidebar_needs_state_invalidate = true;
-                                        }
-                                    }
-                                    "permission.replied" | "question.replied" | "question.rejected" => {
-                                        if let Some(sid) = read_session_id(props) {
-                                            state.directory_session_index.upsert_runtime_attention(&sid, None);
-                                            sidebar_needs_state_invalidate = true;
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            }
-
-                            if let Some(payload) = sse_event_payload(&raw)
-                                && let Some((session_id, phase)) = crate::session_activity::derive_session_activity(payload)
-                            {
-                                state.session_activity.set_phase(&session_id, phase);
-                                state
-                                    .directory_session_index
-                                    .upsert_runtime_phase(&session_id, phase.as_str());
-                                sidebar_needs_state_invalidate = true;
-
-                                let injected = serde_json::json!({
-                                    "type": "opencode-studio:session-activity",
-                                    "properties": {
-                                        "sessionID": session_id,
-                                        "phase": phase.as_str(),
-                                    }
-                                });
-                                if let Ok(encoded) = serde_json::to_string(&injected) {
-                                    GLOBAL_HUB.publish_json(&encoded);
-                                }
-                            }
-
-                            if sidebar_needs_state_invalidate {
-                                let _ = crate::chat_sidebar::publish_chat_sidebar_delta_event(vec![
-                                    crate::chat_sidebar::ChatSidebarPatchOp::State,
-                                ]);
-                            }
+                            process_upstream_event(&state, raw, &filter, &detail);
                         }
                     }
                 }
@@ -790,6 +832,282 @@ pub(crate) fn start_global_sse_hub_if_needed(state: Arc<crate::AppState>) {
     });
 }
 
+/// Mirrors one upstream event to downstream clients and feeds it to the
+/// session index, activity tracking and the other observers.
+fn process_upstream_event(
+    state: &Arc<crate::AppState>,
+    mut raw: serde_json::Value,
+    filter: &crate::opencode_proxy::ActivityFilter,
+    detail: &crate::opencode_proxy::ActivityDetailPolicy,
+) {
+    if !crate::opencode_proxy::sanitize_sse_event_data(&mut raw, filter, detail) {
+        return;
+    }
+    let payload_json = match serde_json::to_string(&raw) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    GLOBAL_HUB.publish_json(&payload_json);
+
+    let mut sidebar_needs_state_invalidate = false;
+
+    if let Some(payload) = sse_event_payload(&raw)
+        && let Some(event_type) = payload.get("type").and_then(|v| v.as_str())
+    {
+        let ty = event_type.trim().to_ascii_lowercase();
+        let props = payload.get("properties").and_then(|v| v.as_object());
+
+        let read_session_id = |props: Option<&serde_json::Map<String, serde_json::Value>>| {
+            props
+                .and_then(|m| {
+                    m.get("sessionID")
+                        .or_else(|| m.get("sessionId"))
+                        .or_else(|| m.get("session_id"))
+                })
+                .and_then(|v| v.as_str())
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        match ty.as_str() {
+            "session.created" | "session.updated" => {
+                if let Some(props) = props
+                    && let Some(session) = props.get("session")
+                {
+                    state
+                        .directory_session_index
+                        .upsert_summary_from_value(session);
+                    sidebar_needs_state_invalidate = true;
+                }
+            }
+            "session.deleted" => {
+                if let Some(sid) = read_session_id(props) {
+                    state.directory_session_index.remove_summary(&sid);
+                    crate::session_tags::forget(state, std::slice::from_ref(&sid));
+                    sidebar_needs_state_invalidate = true;
+                }
+            }
+            "session.status" => {
+                if let Some(props) = props {
+                    let sid = read_session_id(Some(props));
+                    let status = props
+                        .get("status")
+                        .and_then(|v| v.get("type"))
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .trim();
+                    if let Some(sid) = sid
+                        && (status == "busy" || status == "retry" || status == "idle")
+                    {
+                        state
+                            .directory_session_index
+                            .upsert_runtime_status(&sid, status);
+                        sidebar_needs_state_invalidate = true;
+                    }
+                }
+            }
+            "session.idle" => {
+                if let Some(sid) = read_session_id(props) {
+                    state
+                        .directory_session_index
+                        .upsert_runtime_status(&sid, "idle");
+                    state
+                        .directory_session_index
+                        .upsert_runtime_phase(&sid, "idle");
+                    state
+                        .directory_session_index
+                        .upsert_runtime_attention(&sid, None);
+                    sidebar_needs_state_invalidate = true;
+                }
+            }
+            "session.error" => {
+                if let Some(sid) = read_session_id(props) {
+                    state
+                        .directory_session_index
+                        .upsert_runtime_status(&sid, "idle");
+                    state
+                        .directory_session_index
+                        .upsert_runtime_phase(&sid, "idle");
+                    state
+                        .directory_session_index
+                        .upsert_runtime_attention(&sid, None);
+                    sidebar_needs_state_invalidate = true;
+                }
+            }
+            "permission.asked" => {
+                if let Some(sid) = read_session_id(props) {
+                    state
+                        .directory_session_index
+                        .upsert_runtime_attention(&sid, Some("permission"));
+                    sidebar_needs_state_invalidate = true;
+                }
+            }
+            "question.asked" => {
+                if let Some(sid) = read_session_id(props) {
+                    state
+                        .directory_session_index
+                        .upsert_runtime_attention(&sid, Some("question"));
+                    sidebar_needs_state_invalidate = true;
+                }
+            }
+            "permission.replied" | "question.replied" | "question.rejected" => {
+                if let Some(sid) = read_session_id(props) {
+                    state
+                        .directory_session_index
+                        .upsert_runtime_attention(&sid, None);
+                    sidebar_needs_state_invalidate = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(payload) = sse_event_payload(&raw)
+        && let Some((session_id, phase)) = crate::session_activity::derive_session_activity(payload)
+    {
+        state.session_activity.set_phase(&session_id, phase);
+        state
+            .directory_session_index
+            .upsert_runtime_phase(&session_id, phase.as_str());
+        sidebar_needs_state_invalidate = true;
+
+        let injected = serde_json::json!({
+            "type": "opencode-studio:session-activity",
+            "properties": {
+                "sessionID": session_id,
+                "phase": phase.as_str(),
+            }
+        });
+        if let Ok(encoded) = serde_json::to_string(&injected) {
+            GLOBAL_HUB.publish_json(&encoded);
+        }
+    }
+
+    if let Some(payload) = sse_event_payload(&raw) {
+        crate::notifications::observe_event(state, payload);
+        crate::usage::observe_event(state, payload);
+        crate::session_diff_index::observe_event(state, payload);
+        crate::session_checkpoints::observe_event(state, payload);
+        crate::tool_output_retention::observe_event(state, payload);
+    }
+    crate::permission_grants::observe_event(state, &raw);
+    crate::plugin_runtime::observe_event(state, &raw);
+
+    if sidebar_needs_state_invalidate {
+        let _ = crate::chat_sidebar::publish_chat_sidebar_delta_event(vec![
+            crate::chat_sidebar::ChatSidebarPatchOp::State,
+        ]);
+    }
+}
+
+/// Splits the next complete `\n\n`-terminated block off `buffer`.
+fn take_sse_block(buffer: &mut BytesMut, scan_idx: &mut usize) -> Option<Bytes> {
+    while *scan_idx + 1 < buffer.len() {
+        if buffer[*scan_idx] != b'\n' || buffer[*scan_idx + 1] != b'\n' {
+            *scan_idx += 1;
+            continue;
+        }
+        let block = buffer.split_to(*scan_idx).freeze();
+        // drop delimiter
+        let _ = buffer.split_to(2);
+        *scan_idx = 0;
+        return Some(block);
+    }
+    None
+}
+
+/// Follows `/global/event` on every pooled OpenCode instance so sessions run
+/// by per-project processes reach the hub like the primary's.
+async fn watch_opencode_pool(state: Arc<crate::AppState>) {
+    let mut followed: HashMap<String, usize> = HashMap::new();
+    loop {
+        let instances = state.opencode.pool_instances();
+        followed.retain(|key, _| instances.iter().any(|(k, _)| k == key));
+        for (key, instance) in instances {
+            // A directory can be respawned under the same key; follow the new process.
+            let id = Arc::as_ptr(&instance) as usize;
+            if followed.get(&key) == Some(&id) {
+                continue;
+            }
+            followed.insert(key.clone(), id);
+            tokio::spawn(follow_pool_instance(state.clone(), key, instance));
+        }
+        tokio::time::sleep(POOL_WATCH_INTERVAL).await;
+    }
+}
+
+async fn follow_pool_instance(
+    state: Arc<crate::AppState>,
+    directory: String,
+    instance: Arc<crate::opencode::OpenCodeManager>,
+) {
+    let mut attempt: u64 = 0;
+    while !instance.is_retired() {
+        if instance.is_restarting().await {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            continue;
+        }
+        let Some(bridge) = instance.bridge().await else {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            continue;
+        };
+        let Ok(target) = bridge.build_url("/global/event", None) else {
+            return;
+        };
+        let resp = bridge
+            .sse_client
+            .get(target)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .header(reqwest::header::CACHE_CONTROL, "no-cache")
+            .send()
+            .await;
+        let resp = match resp {
+            Ok(resp) if resp.status().is_success() => resp,
+            _ => {
+                attempt = attempt.saturating_add(1);
+                tokio::time::sleep(backoff_delay(attempt)).await;
+                continue;
+            }
+        };
+        attempt = 0;
+
+        tracing::info!(
+            target: "opencode_studio.global_sse_hub.upstream",
+            directory = directory.as_str(),
+            "Connected to pooled OpenCode global SSE"
+        );
+
+        let (filter, detail) = read_activity_policy(&state).await;
+        let mut upstream = resp.bytes_stream();
+        let mut buffer = BytesMut::with_capacity(16 * 1024);
+        let mut scan_idx: usize = 0;
+        let mut prev_cr = false;
+        while let Some(Ok(chunk)) = upstream.next().await {
+            push_normalized_sse_chunk(&mut buffer, &chunk, &mut prev_cr);
+            while let Some(block) = take_sse_block(&mut buffer, &mut scan_idx) {
+                let Ok(block_text) = std::str::from_utf8(&block) else {
+                    continue;
+                };
+                if let (_, Some(raw)) = sse_data_json_from_block(block_text.trim()) {
+                    // Session activity keeps the instance from idling out;
+                    // server heartbeats do not.
+                    let server_event = sse_event_payload(&raw)
+                        .and_then(|payload| payload.get("type"))
+                        .and_then(|v| v.as_str())
+                        .is_some_and(|ty| ty.starts_with("server."));
+                    if !server_event {
+                        instance.touch();
+                    }
+                    process_upstream_event(&state, raw, &filter, &detail);
+                }
+            }
+        }
+
+        attempt = attempt.saturating_add(1);
+        tokio::time::sleep(backoff_delay(attempt)).await;
+    }
+}
+
 fn backoff_delay(attempt: u64) -> Duration {
     if attempt == 0 {
         return UPSTREAM_RETRY_BASE_DELAY;
@@ -1288,4 +1606,27 @@ mod tests {
             Some(seq_at_subscribe)
         );
     }
+
+    #[test]
+    fn replay_snapshot_restores_seq_continuity() {
+        let hub = GlobalSseHub::new();
+        hub.publish_json("{\"type\":\"event.a\"}");
+        hub.publish_json("{\"type\":\"event.b\"}");
+        hub.publish_server_restarting_and_close(Duration::from_secs(2));
+        let snapshot = hub.snapshot();
+        assert_eq!(snapshot.frames.len(), 2);
+
+        let restored = GlobalSseHub::new();
+        assert_eq!(restored.restore(snapshot), 2);
+        // The restart notice was live-only but still consumed seq 3.
+        assert_eq!(restored.latest_seq(), 3);
+        assert_eq!(restored.replay_gap_seq(3, restored.latest_seq()), None);
+        let replay = restored.replay_since_until(1, restored.latest_seq());
+        assert_eq!(replay.len(), 1);
+        assert_eq!(replay[0].seq, 2);
+
+        restored.publish_json("{\"type\":\"event.c\"}");
+        assert_eq!(restored.latest_seq(), 4);
+        assert_eq!(restored.restore(hub.snapshot()), 0);
+    }
 }
@@ -0,0 +1,230 @@
+use std::net::SocketAddr;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use axum::{
+    Json,
+    body::Body,
+    extract::Request,
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures_util::StreamExt;
+use serde::Serialize;
+use tokio::sync::watch;
+
+/// Hint for clients that hit the server while it drains; a replacement process
+/// is usually accepting connections by then.
+const DRAIN_RETRY_AFTER: Duration = Duration::from_secs(2);
+const LISTEN_BACKLOG: u32 = 1024;
+
+static DRAINING: AtomicBool = AtomicBool::new(false);
+static DRAIN_SIGNAL: LazyLock<watch::Sender<bool>> = LazyLock::new(|| watch::channel(false).0);
+
+#[derive(Debug, Serialize)]
+struct DrainingBody {
+    error: &'static str,
+    code: &'static str,
+    restarting: bool,
+}
+
+pub(crate) fn is_draining() -> bool {
+    DRAINING.load(Ordering::SeqCst)
+}
+
+/// Stop taking new work: reject new requests with 503 and close downstream
+/// event streams so in-flight requests can finish.
+pub(crate) fn begin_drain(reason: &str) {
+    if DRAINING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tracing::info!(reason, "Draining connections before shutdown");
+    crate::global_sse_hub::publish_server_restarting_and_close(DRAIN_RETRY_AFTER);
+    DRAIN_SIGNAL.send_replace(true);
+}
+
+/// Resolve on SIGINT/SIGTERM, after switching the server into drain mode.
+/// Also resolves when something inside the process calls [`begin_drain`].
+pub(crate) async fn wait_for_shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    let requested = async {
+        let mut rx = DRAIN_SIGNAL.subscribe();
+        let _ = rx.wait_for(|draining| *draining).await;
+    };
+
+    let reason = tokio::select! {
+        _ = ctrl_c => "SIGINT",
+        _ = terminate => "SIGTERM",
+        _ = requested => return,
+    };
+    begin_drain(reason);
+}
+
+/// Resolve `grace` after drain starts; bounds how long shutdown waits on
+/// requests that never finish (terminal streams, stuck upstream calls).
+pub(crate) async fn drain_deadline(grace: Duration) {
+    let mut rx = DRAIN_SIGNAL.subscribe();
+    let _ = rx.wait_for(|draining| *draining).await;
+    tokio::time::sleep(grace).await;
+}
+
+/// End an event-stream body once drain starts. Chunks already queued are
+/// flushed first so clients still see the restart notice.
+fn end_stream_on_drain(body: Body) -> Body {
+    let mut rx = DRAIN_SIGNAL.subscribe();
+    let mut inner = body.into_data_stream();
+    Body::from_stream(async_stream::stream! {
+        // The flag only ever flips to true, so any change means drain started.
+        if *rx.borrow_and_update() {
+            return;
+        }
+        loop {
+            tokio::select! {
+                biased;
+                chunk = inner.next() => match chunk {
+                    Some(chunk) => yield chunk,
+                    None => break,
+                },
+                _ = rx.changed() => break,
+            }
+        }
+    })
+}
+
+fn is_event_stream(resp: &Response) -> bool {
+    resp.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"))
+}
+
+pub(crate) async fn reject_while_draining(req: Request, next: Next) -> Response {
+    if !is_draining() {
+        let resp = next.run(req).await;
+        if !is_event_stream(&resp) {
+            return resp;
+        }
+        let (parts, body) = resp.into_parts();
+        return Response::from_parts(parts, end_stream_on_drain(body));
+    }
+
+    let mut resp = (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(DrainingBody {
+            error: "OpenCode Studio is restarting",
+            code: "server_restarting",
+            restarting: true,
+        }),
+    )
+        .into_response();
+    resp.headers_mut().insert(
+        header::RETRY_AFTER,
+        HeaderValue::from(DRAIN_RETRY_AFTER.as_secs()),
+    );
+    resp.headers_mut()
+        .insert(header::CONNECTION, HeaderValue::from_static("close"));
+    resp
+}
+
+/// Take over a listener passed via systemd-style socket activation
+/// (`LISTEN_PID`/`LISTEN_FDS`, first fd is 3).
+///
+/// The variables stay in the environment, which cannot be changed safely once
+/// the runtime's threads are up. Child processes (opencode, terminals) have
+/// their own pid, so `LISTEN_PID` already rules them out, and the fd is marked
+/// close-on-exec so they never inherit the socket.
+#[cfg(unix)]
+fn inherited_listener() -> std::io::Result<Option<std::net::TcpListener>> {
+    use std::os::fd::FromRawFd;
+
+    static TAKEN: AtomicBool = AtomicBool::new(false);
+
+    let pid_matches = std::env::var("LISTEN_PID")
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .is_some_and(|pid| pid == std::process::id());
+    let fds = std::env::var("LISTEN_FDS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .unwrap_or(0);
+    // fd 3 can only have one owner; a second bind gets a fresh socket.
+    if !pid_matches || fds == 0 || TAKEN.swap(true, Ordering::SeqCst) {
+        return Ok(None);
+    }
+
+    // SAFETY: the service manager hands us ownership of fd 3.
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(3) };
+    // SAFETY: fd 3 is open and owned by `listener`.
+    if unsafe { libc::fcntl(3, libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    listener.set_nonblocking(true)?;
+    Ok(Some(listener))
+}
+
+#[cfg(not(unix))]
+fn inherited_listener() -> std::io::Result<Option<std::net::TcpListener>> {
+    Ok(None)
+}
+
+/// Bind the HTTP listener. With `reuse_port`, a replacement process can bind
+/// the same address while this one drains (Linux/BSD `SO_REUSEPORT`).
+pub(crate) fn bind_listener(
+    addr: SocketAddr,
+    reuse_port: bool,
+) -> std::io::Result<tokio::net::TcpListener> {
+    if let Some(listener) = inherited_listener()? {
+        tracing::info!("Using listener inherited via socket activation");
+        return tokio::net::TcpListener::from_std(listener);
+    }
+
+    let socket = if addr.is_ipv4() {
+        tokio::net::TcpSocket::new_v4()?
+    } else {
+        tokio::net::TcpSocket::new_v6()?
+    };
+    #[cfg(unix)]
+    {
+        socket.set_reuseaddr(true)?;
+        if reuse_port {
+            socket.set_reuseport(true)?;
+        }
+    }
+    #[cfg(not(unix))]
+    if reuse_port {
+        tracing::warn!("--reuse-port is not supported on this platform; ignoring");
+    }
+    socket.bind(addr)?;
+    socket.listen(LISTEN_BACKLOG)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn bind_listener_with_reuse_port_allows_a_second_bind() {
+        let first = bind_listener("127.0.0.1:0".parse().unwrap(), true).expect("first bind");
+        let addr = first.local_addr().expect("local addr");
+        let second = bind_listener(addr, true).expect("second bind on same port");
+        assert_eq!(second.local_addr().unwrap().port(), addr.port());
+    }
+}
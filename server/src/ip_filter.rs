@@ -0,0 +1,226 @@
+//! Client address allow/deny lists, checked before authentication.
+//!
+//! Configured by the runtime config's `[ip_filter]` table, with one policy for
+//! the API (`/api`, `/auth`, `/health`) and one for the UI (everything else),
+//! picked by the path after `[[routes]]` rewrites so a rewrite cannot move an
+//! API request under the UI policy. A `deny` match always loses; a non-empty
+//! `allow` admits only matching addresses.
+//! Loopback clients skip both lists unless `localhost_bypass` is turned off,
+//! so a bad list cannot lock out the machine running the server.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use crate::client_addr::{Cidr, ClientAddr, any_contains};
+
+/// One `[ip_filter.api]` / `[ip_filter.ui]` table.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct IpPolicy {
+    pub allow: Vec<Cidr>,
+    pub deny: Vec<Cidr>,
+}
+
+impl IpPolicy {
+    fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    fn admits(&self, ip: IpAddr) -> bool {
+        !any_contains(&self.deny, ip) && (self.allow.is_empty() || any_contains(&self.allow, ip))
+    }
+}
+
+/// The runtime config's `[ip_filter]` table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct IpFilterConfig {
+    /// Always admit loopback clients (after trusted proxy resolution).
+    pub localhost_bypass: bool,
+    pub api: IpPolicy,
+    pub ui: IpPolicy,
+}
+
+impl Default for IpFilterConfig {
+    fn default() -> Self {
+        Self {
+            localhost_bypass: true,
+            api: IpPolicy::default(),
+            ui: IpPolicy::default(),
+        }
+    }
+}
+
+impl IpFilterConfig {
+    pub(crate) fn is_active(&self) -> bool {
+        !self.api.is_empty() || !self.ui.is_empty()
+    }
+
+    fn admits(&self, path: &str, ip: Option<IpAddr>) -> bool {
+        // No socket address means an in-process request, not a remote client.
+        let Some(ip) = ip else {
+            return true;
+        };
+        if self.localhost_bypass && ip.is_loopback() {
+            return true;
+        }
+        let policy = if crate::path_utils::is_api_auth_or_health_path(path) {
+            &self.api
+        } else {
+            &self.ui
+        };
+        policy.admits(ip)
+    }
+}
+
+/// Middleware state: the lists plus the route rules that may rewrite paths.
+pub(crate) struct IpFilter {
+    config: IpFilterConfig,
+    route_rules: Vec<crate::route_rules::RouteRule>,
+}
+
+impl IpFilter {
+    pub(crate) fn new(
+        config: IpFilterConfig,
+        route_rules: Vec<crate::route_rules::RouteRule>,
+    ) -> Self {
+        Self {
+            config,
+            route_rules,
+        }
+    }
+}
+
+pub(crate) async fn enforce_ip_filter(
+    State(filter): State<Arc<IpFilter>>,
+    client: ClientAddr,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = crate::route_rules::routed_path(&filter.route_rules, req.uri().path());
+    if filter.config.admits(&path, client.ip) {
+        return next.run(req).await;
+    }
+    tracing::debug!(
+        target: "opencode_studio.ip_filter",
+        ip = ?client.ip,
+        path = %path,
+        "Request rejected by IP filter"
+    );
+    if crate::path_utils::is_api_auth_or_health_path(&path) {
+        (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "Client address not allowed",
+                "code": "ip_forbidden",
+            })),
+        )
+            .into_response()
+    } else {
+        (StatusCode::FORBIDDEN, "Forbidden").into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(raw: &str) -> Option<IpAddr> {
+        Some(raw.parse().expect("ip"))
+    }
+
+    #[test]
+    fn policies_split_between_api_and_ui_with_localhost_bypass() {
+        let mut filter: IpFilterConfig = toml::from_str(
+            r#"
+            [api]
+            allow = ["10.0.0.0/8"]
+            deny = ["10.0.0.66"]
+
+            [ui]
+            deny = ["203.0.113.0/24"]
+            "#,
+        )
+        .expect("ip_filter toml");
+        assert!(filter.is_active());
+
+        assert!(filter.admits("/api/session", ip("10.1.2.3")));
+        assert!(!filter.admits("/api/session", ip("10.0.0.66")));
+        assert!(!filter.admits("/auth/session", ip("192.0.2.1")));
+        assert!(!filter.admits("/health", ip("192.0.2.1")));
+        assert!(filter.admits("/apidocs", ip("192.0.2.1")));
+        assert!(filter.admits("/", ip("192.0.2.1")));
+        assert!(!filter.admits("/assets/app.js", ip("203.0.113.7")));
+
+        assert!(filter.admits("/api/session", ip("127.0.0.1")));
+        assert!(filter.admits("/api/session", ip("::1")));
+        assert!(filter.admits("/api/session", None));
+        filter.localhost_bypass = false;
+        assert!(!filter.admits("/api/session", ip("127.0.0.1")));
+
+        assert!(toml::from_str::<IpFilterConfig>("[api]\nallow = [\"10.0.0.0/40\"]").is_err());
+        assert!(!IpFilterConfig::default().is_active());
+    }
+
+    #[tokio::test]
+    async fn rewritten_api_requests_are_judged_by_the_api_policy() {
+        use axum::{Router, middleware, routing::get};
+        use std::net::SocketAddr;
+
+        let rule: crate::route_rules::RouteRuleConfig =
+            toml::from_str("match = \"/legacy/api/*\"\nrewrite = \"/api/*\"").unwrap();
+        let rules = vec![crate::route_rules::RouteRule::compile(&rule, None).unwrap()];
+        let config: IpFilterConfig =
+            toml::from_str("localhost_bypass = false\n[api]\ndeny = [\"127.0.0.0/8\"]").unwrap();
+
+        // Same layering as the app: route rules wrap the router, the filter
+        // wraps both.
+        let inner = Router::new()
+            .route("/api/ping", get(|| async { "pong" }))
+            .fallback(|| async { "ui" });
+        let app = Router::new()
+            .fallback_service(inner)
+            .layer(middleware::from_fn_with_state(
+                Arc::new(rules.clone()),
+                crate::route_rules::apply_route_rules,
+            ))
+            .layer(middleware::from_fn_with_state(
+                Arc::new(IpFilter::new(config, rules)),
+                enforce_ip_filter,
+            ));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+        });
+
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let status = |path: &'static str| {
+            let client = client.clone();
+            async move {
+                client
+                    .get(format!("http://{addr}{path}"))
+                    .send()
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+        assert_eq!(status("/legacy/api/ping").await, StatusCode::FORBIDDEN);
+        assert_eq!(status("/api/ping").await, StatusCode::FORBIDDEN);
+        assert_eq!(status("/legacy/page").await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,374 @@
+//! Long-running server jobs.
+//!
+//! Work that would otherwise hold a request open (clones, exports, updates)
+//! can be submitted as a job instead: the caller gets a job id back at once
+//! and polls `GET /jobs/{id}` or subscribes to `GET /jobs/{id}/events` for
+//! progress. Canceling a job drops its future, so child processes spawned with
+//! `kill_on_drop` go with it.
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::{Arc, LazyLock, Mutex as StdMutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    body::{Body, Bytes},
+    extract::{Path as AxumPath, Query},
+    http::StatusCode,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{broadcast, watch};
+
+use crate::{ApiResult, AppError};
+
+/// Finished jobs kept for polling and downloads.
+const MAX_FINISHED_JOBS: usize = 100;
+const JOB_EVENTS_CAPACITY: usize = 64;
+
+static JOBS: LazyLock<DashMap<String, Arc<Job>>> = LazyLock::new(DashMap::new);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JobStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Canceled,
+}
+
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct JobProgress {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percent: Option<u32>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct JobInfo {
+    pub id: String,
+    /// What the job does, e.g. `git.clone` or `session.export`.
+    pub kind: &'static str,
+    pub title: String,
+    pub status: JobStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<JobProgress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The job produced a file, served by `GET /jobs/{id}/artifact`.
+    pub has_artifact: bool,
+    pub created_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+}
+
+/// A file a job produced, e.g. a rendered export.
+pub(crate) struct JobArtifact {
+    pub file_name: String,
+    pub content_type: &'static str,
+    pub body: Bytes,
+}
+
+struct Job {
+    info: StdMutex<JobInfo>,
+    artifact: StdMutex<Option<JobArtifact>>,
+    events: broadcast::Sender<JobInfo>,
+    cancel: watch::Sender<bool>,
+}
+
+impl Job {
+    fn info(&self) -> JobInfo {
+        self.info.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut JobInfo)) {
+        let mut info = self.info.lock().unwrap_or_else(|e| e.into_inner());
+        if info.status != JobStatus::Running {
+            return;
+        }
+        apply(&mut info);
+        let _ = self.events.send(info.clone());
+    }
+}
+
+/// Handed to a running job to report progress and attach its output.
+#[derive(Clone)]
+pub(crate) struct JobContext {
+    job: Arc<Job>,
+}
+
+impl JobContext {
+    pub(crate) fn progress(&self, percent: Option<u32>, message: impl Into<String>) {
+        let progress = JobProgress {
+            percent: percent.map(|p| p.min(100)),
+            message: message.into(),
+        };
+        self.job.update(|info| info.progress = Some(progress));
+    }
+
+    pub(crate) fn set_artifact(&self, artifact: JobArtifact) {
+        *self.job.artifact.lock().unwrap_or_else(|e| e.into_inner()) = Some(artifact);
+        self.job.update(|info| info.has_artifact = true);
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn prune_finished_jobs() {
+    let mut finished = JOBS
+        .iter()
+        .filter_map(|entry| {
+            let info = entry.value().info();
+            info.finished_at.map(|at| (at, info.id))
+        })
+        .collect::<Vec<_>>();
+    if finished.len() < MAX_FINISHED_JOBS {
+        return;
+    }
+    finished.sort();
+    let excess = finished.len() + 1 - MAX_FINISHED_JOBS;
+    for (_, id) in finished.into_iter().take(excess) {
+        JOBS.remove(&id);
+    }
+}
+
+/// Run `work` in the background and return its job record right away. The
+/// job's result is the `Ok` value; an `Err` marks it failed.
+pub(crate) fn submit<F, Fut>(kind: &'static str, title: impl Into<String>, work: F) -> JobInfo
+where
+    F: FnOnce(JobContext) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Value, String>> + Send + 'static,
+{
+    let (events, _) = broadcast::channel(JOB_EVENTS_CAPACITY);
+    let (cancel, mut canceled) = watch::channel(false);
+    let job = Arc::new(Job {
+        info: StdMutex::new(JobInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            title: title.into(),
+            status: JobStatus::Running,
+            progress: None,
+            result: None,
+            error: None,
+            has_artifact: false,
+            created_at: now_millis(),
+            finished_at: None,
+        }),
+        artifact: StdMutex::new(None),
+        events,
+        cancel,
+    });
+    let info = job.info();
+    prune_finished_jobs();
+    JOBS.insert(info.id.clone(), job.clone());
+
+    let ctx = JobContext { job: job.clone() };
+    tokio::spawn(async move {
+        let outcome = tokio::select! {
+            result = work(ctx) => Some(result),
+            _ = canceled.wait_for(|canceled| *canceled) => None,
+        };
+        job.update(|info| {
+            match outcome {
+                Some(Ok(result)) => {
+                    info.status = JobStatus::Succeeded;
+                    info.result = Some(result);
+                }
+                Some(Err(err)) => {
+                    info.status = JobStatus::Failed;
+                    info.error = Some(err);
+                }
+                None => info.status = JobStatus::Canceled,
+            }
+            info.finished_at = Some(now_millis());
+        });
+    });
+    info
+}
+
+/// The `202 Accepted` reply for a handler that handed its work to a job.
+pub(crate) fn accepted(info: JobInfo) -> Response {
+    (StatusCode::ACCEPTED, Json(info)).into_response()
+}
+
+fn find_job(job_id: &str) -> ApiResult<Arc<Job>> {
+    JOBS.get(job_id)
+        .map(|entry| entry.value().clone())
+        .ok_or_else(|| AppError::not_found(format!("Job not found: {job_id}")))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct JobsQuery {
+    pub kind: Option<String>,
+}
+
+/// Recent jobs, newest first.
+pub(crate) async fn jobs_get(Query(q): Query<JobsQuery>) -> Json<Vec<JobInfo>> {
+    let kind = q.kind.as_deref().map(str::trim).filter(|k| !k.is_empty());
+    let mut jobs = JOBS
+        .iter()
+        .map(|entry| entry.value().info())
+        .filter(|info| kind.is_none_or(|kind| info.kind == kind))
+        .collect::<Vec<_>>();
+    jobs.sort_by_key(|info| std::cmp::Reverse(info.created_at));
+    Json(jobs)
+}
+
+pub(crate) async fn job_get(AxumPath(job_id): AxumPath<String>) -> ApiResult<Json<JobInfo>> {
+    Ok(Json(find_job(&job_id)?.info()))
+}
+
+pub(crate) async fn job_cancel_post(
+    AxumPath(job_id): AxumPath<String>,
+) -> ApiResult<Json<JobInfo>> {
+    let job = find_job(&job_id)?;
+    let _ = job.cancel.send(true);
+    Ok(Json(job.info()))
+}
+
+pub(crate) async fn job_artifact_get(AxumPath(job_id): AxumPath<String>) -> ApiResult<Response> {
+    let job = find_job(&job_id)?;
+    let artifact = job.artifact.lock().unwrap_or_else(|e| e.into_inner());
+    let artifact = artifact
+        .as_ref()
+        .ok_or_else(|| AppError::not_found("Job has no artifact"))?;
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("cache-control", "no-store")
+        .header("content-type", artifact.content_type)
+        .header(
+            "content-disposition",
+            format!("attachment; filename=\"{}\"", artifact.file_name),
+        )
+        .body(Body::from(artifact.body.clone()))
+        .unwrap())
+}
+
+fn job_event(info: &JobInfo) -> Result<Event, Infallible> {
+    Ok(Event::default()
+        .event("job")
+        .data(serde_json::to_string(info).unwrap_or_else(|_| "{}".to_string())))
+}
+
+/// The job's current state, then one `job` event per change until it ends.
+pub(crate) async fn job_events_get(AxumPath(job_id): AxumPath<String>) -> ApiResult<Response> {
+    let job = find_job(&job_id)?;
+    let mut rx = job.events.subscribe();
+    let current = job.info();
+    let stream = async_stream::stream! {
+        let finished = current.status != JobStatus::Running;
+        yield job_event(&current);
+        if finished {
+            return;
+        }
+        loop {
+            match rx.recv().await {
+                Ok(info) => {
+                    let finished = info.status != JobStatus::Running;
+                    yield job_event(&info);
+                    if finished {
+                        break;
+                    }
+                }
+                // Every event is a full snapshot, so catching up is one read.
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    let info = job.info();
+                    let finished = info.status != JobStatus::Running;
+                    yield job_event(&info);
+                    if finished {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    };
+    Ok(Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn wait_finished(id: &str) -> JobInfo {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let info = find_job(id).expect("job").info();
+                if info.status != JobStatus::Running {
+                    return info;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("job finished in time")
+    }
+
+    #[tokio::test]
+    async fn jobs_report_progress_results_and_artifacts() {
+        let (go, wait) = tokio::sync::oneshot::channel::<()>();
+        let info = submit("test.ok", "ok", |ctx| async move {
+            ctx.progress(Some(150), "halfway");
+            let _ = wait.await;
+            ctx.set_artifact(JobArtifact {
+                file_name: "out.txt".to_string(),
+                content_type: "text/plain",
+                body: Bytes::from_static(b"hello"),
+            });
+            Ok(serde_json::json!({"done": true}))
+        });
+        assert_eq!(info.status, JobStatus::Running);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let running = find_job(&info.id).expect("job").info();
+        assert_eq!(running.progress.as_ref().and_then(|p| p.percent), Some(100));
+        let _ = go.send(());
+
+        let done = wait_finished(&info.id).await;
+        assert_eq!(done.status, JobStatus::Succeeded);
+        assert_eq!(done.result, Some(serde_json::json!({"done": true})));
+        assert!(done.has_artifact);
+        let resp = job_artifact_get(AxumPath(info.id)).await.expect("artifact");
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let failed = submit("test.fail", "fail", |_| async { Err("boom".to_string()) });
+        let failed = wait_finished(&failed.id).await;
+        assert_eq!(failed.status, JobStatus::Failed);
+        assert_eq!(failed.error.as_deref(), Some("boom"));
+    }
+
+    #[tokio::test]
+    async fn canceling_drops_the_job_future() {
+        let info = submit("test.cancel", "cancel", |_| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(Value::Null)
+        });
+        let _ = job_cancel_post(AxumPath(info.id.clone()))
+            .await
+            .expect("cancel");
+        let done = wait_finished(&info.id).await;
+        assert_eq!(done.status, JobStatus::Canceled);
+        // Late updates from a finished job are ignored.
+        let job = find_job(&info.id).expect("job");
+        JobContext { job }.progress(Some(10), "late");
+        assert!(find_job(&info.id).expect("job").info().progress.is_none());
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::Json;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::util::SubscriberInitExt as _;
+use tracing_subscriber::{EnvFilter, Registry, layer::SubscriberExt as _, reload};
+
+use crate::{ApiResult, AppError};
+
+const DEFAULT_DIRECTIVES: &str = "info,tower_http=info";
+const MAX_REVERT_MINUTES: u64 = 24 * 60;
+const MAX_DIRECTIVES_CHARS: usize = 2048;
+
+struct LogControl {
+    handle: reload::Handle<EnvFilter, Registry>,
+    /// Filter the process started with (`RUST_LOG` or the built-in default).
+    startup: String,
+    current: Mutex<ActiveFilter>,
+    /// Bumped on every change so a pending revert only fires for its own change.
+    generation: AtomicU64,
+}
+
+#[derive(Clone)]
+struct ActiveFilter {
+    directives: String,
+    revert_at: Option<u64>,
+}
+
+static LOG_CONTROL: OnceLock<LogControl> = OnceLock::new();
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Install the global subscriber with a reloadable `EnvFilter`.
+pub(crate) fn init_tracing() {
+    let startup = std::env::var(EnvFilter::DEFAULT_ENV)
+        .ok()
+        .filter(|raw| EnvFilter::try_new(raw).is_ok())
+        .unwrap_or_else(|| DEFAULT_DIRECTIVES.to_string());
+    let (filter, handle) = reload::Layer::new(EnvFilter::new(&startup));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .init();
+    let _ = LOG_CONTROL.set(LogControl {
+        handle,
+        current: Mutex::new(ActiveFilter {
+            directives: startup.clone(),
+            revert_at: None,
+        }),
+        startup,
+        generation: AtomicU64::new(0),
+    });
+}
+
+fn control() -> ApiResult<&'static LogControl> {
+    LOG_CONTROL
+        .get()
+        .ok_or_else(|| AppError::internal("Runtime log control is not initialized"))
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLevelResponse {
+    pub directives: String,
+    pub startup_directives: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_at: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLevelUpdateBody {
+    /// Full `EnvFilter` directives, replacing the current filter.
+    pub directives: Option<String>,
+    /// Per-target levels layered over `directives` (or the current filter),
+    /// e.g. `{"opencode_studio.attachment_cache": "debug"}`.
+    #[serde(default)]
+    pub targets: BTreeMap<String, String>,
+    /// Restore the startup filter after this many minutes.
+    pub revert_after_minutes: Option<u64>,
+}
+
+fn response(control: &LogControl) -> LogLevelResponse {
+    let active = control
+        .current
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    LogLevelResponse {
+        directives: active.directives,
+        startup_directives: control.startup.clone(),
+        revert_at: active.revert_at,
+    }
+}
+
+fn is_valid_target(target: &str) -> bool {
+    !target.is_empty()
+        && target
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | ':' | '.' | '-'))
+}
+
+/// Compose the new filter: `base` with each target's directive replaced.
+fn compose_directives(base: &str, targets: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut parts: Vec<String> = base
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .filter(|p| {
+            let target = p.split_once('=').map(|(t, _)| t.trim());
+            !target.is_some_and(|t| targets.contains_key(t))
+        })
+        .map(str::to_string)
+        .collect();
+    for (target, level) in targets {
+        let target = target.trim();
+        if !is_valid_target(target) {
+            return Err(format!("Invalid log target: {target}"));
+        }
+        let level = level.trim().to_ascii_lowercase();
+        if !matches!(
+            level.as_str(),
+            "off" | "error" | "warn" | "info" | "debug" | "trace"
+        ) {
+            return Err(format!("Invalid level for {target}: {level}"));
+        }
+        parts.push(format!("{target}={level}"));
+    }
+    let directives = parts.join(",");
+    if directives.is_empty() {
+        return Err("Log directives are required".to_string());
+    }
+    if directives.chars().count() > MAX_DIRECTIVES_CHARS {
+        return Err("Log directives are too long".to_string());
+    }
+    EnvFilter::try_new(&directives).map_err(|err| format!("Invalid log directives: {err}"))?;
+    Ok(directives)
+}
+
+fn apply(control: &LogControl, directives: String, revert_at: Option<u64>) -> ApiResult<u64> {
+    let filter = EnvFilter::try_new(&directives)
+        .map_err(|err| AppError::bad_request(format!("Invalid log directives: {err}")))?;
+    control
+        .handle
+        .reload(filter)
+        .map_err(|err| AppError::internal(err.to_string()))?;
+    let generation = control.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    *control.current.lock().unwrap_or_else(|e| e.into_inner()) = ActiveFilter {
+        directives,
+        revert_at,
+    };
+    Ok(generation)
+}
+
+pub(crate) async fn log_level_get() -> ApiResult<Json<LogLevelResponse>> {
+    Ok(Json(response(control()?)))
+}
+
+pub(crate) async fn log_level_put(
+    Json(body): Json<LogLevelUpdateBody>,
+) -> ApiResult<Json<LogLevelResponse>> {
+    let control = control()?;
+    let base = match body.directives.as_deref().map(str::trim) {
+        Some(raw) => raw.to_string(),
+        None => response(control).directives,
+    };
+    let directives = compose_directives(&base, &body.targets).map_err(AppError::bad_request)?;
+
+    let revert_after = match body.revert_after_minutes {
+        Some(0) | None => None,
+        Some(minutes) if minutes <= MAX_REVERT_MINUTES => Some(Duration::from_secs(minutes * 60)),
+        Some(_) => {
+            return Err(AppError::bad_request(format!(
+                "revertAfterMinutes must be at most {MAX_REVERT_MINUTES}"
+            )));
+        }
+    };
+    let revert_at = revert_after.map(|d| now_millis() + d.as_millis() as u64);
+    let generation = apply(control, directives.clone(), revert_at)?;
+    tracing::warn!(directives = %directives, revert_at = ?revert_at, "Log filter changed at runtime");
+
+    if let Some(delay) = revert_after {
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if control.generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            if apply(control, control.startup.clone(), None).is_ok() {
+                tracing::warn!(directives = %control.startup, "Log filter reverted to startup value");
+            }
+        });
+    }
+
+    Ok(Json(response(control)))
+}
+
+/// Restore the filter the process started with.
+pub(crate) async fn log_level_delete() -> ApiResult<Json<LogLevelResponse>> {
+    let control = control()?;
+    apply(control, control.startup.clone(), None)?;
+    Ok(Json(response(control)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compose_directives_replaces_targets_and_validates() {
+        let targets = BTreeMap::from([
+            (
+                "opencode_studio.attachment_cache".to_string(),
+                "DEBUG".to_string(),
+            ),
+            ("tower_http".to_string(), "warn".to_string()),
+        ]);
+        assert_eq!(
+            compose_directives("info,tower_http=info", &targets).as_deref(),
+            Ok("info,opencode_studio.attachment_cache=debug,tower_http=warn")
+        );
+        assert_eq!(
+            compose_directives("debug", &BTreeMap::new()).as_deref(),
+            Ok("debug")
+        );
+
+        let bad_level = BTreeMap::from([("x".to_string(), "loud".to_string())]);
+        assert!(compose_directives("info", &bad_level).is_err());
+        let bad_target = BTreeMap::from([("a b".to_string(), "info".to_string())]);
+        assert!(compose_directives("info", &bad_target).is_err());
+        assert!(compose_directives(" , ", &BTreeMap::new()).is_err());
+    }
+}
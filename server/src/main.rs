@@ -1,40 +1,93 @@
 use base64::Engine as _;
-use clap::{Parser, ValueEnum};
-use tracing::Level;
+use clap::{Parser, Subcommand, ValueEnum};
 
+mod api_tokens;
 mod app;
 mod attachment_cache;
+mod attachment_text;
+mod audit;
+mod budgets;
 mod chat_sidebar;
+mod chat_sidebar_devices;
+mod client_addr;
+mod compression;
 mod config;
 mod directory_session_index;
 mod directory_sessions;
+mod discovery;
 mod error;
+mod forge;
 mod fs;
+mod fs_preview;
+mod fs_trash;
+mod fs_tree;
+mod fs_usage;
 mod fs_watch;
 mod git;
 mod git2_utils;
 mod global_sse_hub;
+mod graceful_shutdown;
+mod ip_filter;
+mod jobs;
+mod log_level;
+mod markdown_render;
+mod memory_snippets;
+mod model_catalog;
+mod notifications;
+mod openapi;
 mod opencode;
 mod opencode_auth;
+mod opencode_capabilities;
 mod opencode_config;
 mod opencode_config_model;
 mod opencode_proxy;
 mod opencode_session;
 mod path_utils;
+mod permission_grants;
 mod persistence_paths;
+mod plugin_install;
 mod plugin_runtime;
+mod prompt_templates;
 mod providers;
+mod quick_captures;
+mod rate_limit;
+mod route_rules;
 mod runtime_config;
+mod secrets;
+mod self_update;
+mod service_install;
 mod session_activity;
+mod session_checkpoints;
+mod session_cleanup;
+mod session_diff_index;
+mod session_export;
+mod session_fork;
+mod session_import;
+mod session_index_export;
+mod session_part_apply;
+mod session_summary;
+mod session_tags;
 mod settings;
 mod settings_events;
+mod settings_migrations;
+mod sidebar_sync;
+mod slash_commands;
 mod studio_db;
+mod tasks;
 mod terminal;
+mod terminal_transfer;
 mod terminal_ui_state;
 #[cfg(test)]
 mod test_support;
+mod tls_roots;
+mod tls_server;
+mod tool_output_retention;
+mod tool_output_table;
 mod ui_auth;
+mod ui_static;
+mod ui_users;
 mod updates;
+mod usage;
 mod workspace_preview;
 mod workspace_preview_registry;
 mod workspace_preview_runtime;
@@ -55,6 +108,9 @@ pub(crate) struct Args {
     #[arg(long, env = "OPENCODE_STUDIO_CONFIG", value_name = "PATH")]
     pub(crate) config: Option<String>,
 
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+
     /// Bind address (e.g. 127.0.0.1 or 0.0.0.0)
     #[arg(long, env = "OPENCODE_STUDIO_HOST", default_value = "127.0.0.1")]
     pub(crate) host: String,
@@ -96,6 +152,51 @@ pub(crate) struct Args {
     )]
     pub(crate) opencode_log_level: Option<crate::opencode::OpenCodeLogLevel>,
 
+    /// Run up to this many extra `opencode serve` processes, one per project directory.
+    ///
+    /// Requests carrying a `directory` are routed to that project's instance; 0 disables the pool.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_OPENCODE_POOL_MAX",
+        default_value_t = 0,
+        value_name = "COUNT"
+    )]
+    pub(crate) opencode_pool_max: usize,
+
+    /// Stop a pooled OpenCode instance after this many seconds without
+    /// requests or session activity; 0 keeps instances until stopped.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_OPENCODE_POOL_IDLE_SECS",
+        default_value_t = crate::opencode::DEFAULT_POOL_IDLE_TIMEOUT.as_secs(),
+        value_name = "SECS"
+    )]
+    pub(crate) opencode_pool_idle_secs: u64,
+
+    /// How many OpenCode session files to read in parallel when scanning storage.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_SESSION_SCAN_CONCURRENCY",
+        value_name = "COUNT"
+    )]
+    pub(crate) session_scan_concurrency: Option<usize>,
+
+    /// How many session directory listings to keep cached.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_SESSION_DIR_CACHE_LIMIT",
+        value_name = "COUNT"
+    )]
+    pub(crate) session_dir_cache_limit: Option<usize>,
+
+    /// How many parsed session files to keep cached.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_SESSION_FILE_CACHE_LIMIT",
+        value_name = "COUNT"
+    )]
+    pub(crate) session_file_cache_limit: Option<usize>,
+
     /// Directory with built UI assets (Vite dist).
     ///
     /// When unset, OpenCode Studio runs API-only (no static UI).
@@ -138,6 +239,179 @@ pub(crate) struct Args {
         value_name = "MODE"
     )]
     pub(crate) ui_cookie_samesite: UiCookieSameSite,
+
+    /// Seconds to let in-flight requests finish after SIGTERM/SIGINT.
+    ///
+    /// New requests get 503 with Retry-After while draining.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_SHUTDOWN_GRACE_SECS",
+        default_value_t = 10,
+        value_name = "SECS"
+    )]
+    pub(crate) shutdown_grace_secs: u64,
+
+    /// Bind with SO_REUSEPORT so a replacement backend can start listening
+    /// before this one exits (Unix only).
+    #[arg(long, env = "OPENCODE_STUDIO_REUSE_PORT", default_value_t = false)]
+    pub(crate) reuse_port: bool,
+
+    /// Serve API responses uncompressed even when the client accepts gzip/brotli.
+    #[arg(long, env = "OPENCODE_STUDIO_NO_COMPRESSION", default_value_t = false)]
+    pub(crate) no_compression: bool,
+
+    /// Smallest response body, in bytes, worth compressing.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_COMPRESSION_MIN_BYTES",
+        default_value_t = crate::compression::DEFAULT_MIN_BYTES,
+        value_name = "BYTES"
+    )]
+    pub(crate) compression_min_bytes: u16,
+
+    /// Days to keep workspace files deleted or overwritten through the API in
+    /// the trash; 0 deletes them outright.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_TRASH_RETENTION_DAYS",
+        default_value_t = crate::fs_trash::DEFAULT_RETENTION_DAYS,
+        value_name = "DAYS"
+    )]
+    pub(crate) trash_retention_days: u32,
+
+    /// Reverse proxies whose X-Forwarded-For / X-Forwarded-Proto headers are
+    /// believed (addresses or CIDR ranges).
+    ///
+    /// Decides the client IP used for rate limiting, login lockouts and the
+    /// audit log, and whether session cookies are marked Secure. Use a
+    /// comma-separated list via env (OPENCODE_STUDIO_TRUSTED_PROXIES) or repeat
+    /// this flag.
+    #[arg(
+        long = "trusted-proxy",
+        env = "OPENCODE_STUDIO_TRUSTED_PROXIES",
+        value_delimiter = ',',
+        value_name = "CIDR",
+        default_values = ["127.0.0.0/8", "::1"]
+    )]
+    pub(crate) trusted_proxies: Vec<crate::client_addr::Cidr>,
+
+    /// Extra root CA certificates (PEM file or directory of .pem/.crt/.cer).
+    ///
+    /// Trusted in addition to the built-in roots by every outbound HTTP client
+    /// (OpenCode bridge, update checks, preview probes). Use a comma-separated
+    /// list via env (OPENCODE_STUDIO_CA_CERTS) or repeat this flag.
+    #[arg(
+        long = "ca-cert",
+        env = "OPENCODE_STUDIO_CA_CERTS",
+        value_delimiter = ',',
+        value_name = "PATH"
+    )]
+    pub(crate) ca_certs: Vec<String>,
+
+    /// PEM certificate chain for serving HTTPS directly.
+    ///
+    /// Requires --tls-key. Session cookies are then always marked Secure.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_TLS_CERT",
+        value_name = "PATH",
+        requires = "tls_key"
+    )]
+    pub(crate) tls_cert: Option<String>,
+
+    /// PEM private key matching --tls-cert.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_TLS_KEY",
+        value_name = "PATH",
+        requires = "tls_cert"
+    )]
+    pub(crate) tls_key: Option<String>,
+
+    /// Also listen for plain HTTP on this port and redirect it to HTTPS.
+    ///
+    /// Only used when TLS is enabled.
+    #[arg(long, env = "OPENCODE_STUDIO_TLS_REDIRECT_PORT", value_name = "PORT")]
+    pub(crate) tls_redirect_port: Option<u16>,
+
+    /// Advertise this server on the LAN via mDNS (`_opencode-studio._tcp`)
+    /// and collect other instances for /api/discovery/peers.
+    #[arg(long, env = "OPENCODE_STUDIO_DISCOVERY", default_value_t = false)]
+    pub(crate) discovery: bool,
+
+    /// Name shown to other devices when discovery is enabled.
+    ///
+    /// Defaults to "OpenCode Studio (<hostname>)".
+    #[arg(long, env = "OPENCODE_STUDIO_INSTANCE_NAME", value_name = "NAME")]
+    pub(crate) instance_name: Option<String>,
+
+    /// Allow admins to update this binary in place via /api/admin/self-update.
+    ///
+    /// Downloads must carry a minisign signature from the configured key.
+    #[arg(long, env = "OPENCODE_STUDIO_SELF_UPDATE", default_value_t = false)]
+    pub(crate) self_update: bool,
+
+    /// Download URL of the release archive to install; the signature is
+    /// fetched from `<url>.minisig`.
+    ///
+    /// Defaults to the matching asset of the latest GitHub release.
+    #[arg(long, env = "OPENCODE_STUDIO_SELF_UPDATE_URL", value_name = "URL")]
+    pub(crate) self_update_url: Option<String>,
+
+    /// Minisign public key (base64) trusted for self-updates, overriding the
+    /// key built into this binary.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_SELF_UPDATE_PUBLIC_KEY",
+        value_name = "KEY"
+    )]
+    pub(crate) self_update_public_key: Option<String>,
+
+    /// Hex-encoded 256-bit master key for the encrypted secrets store.
+    ///
+    /// The desktop app passes a key it keeps in the OS keychain.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_SECRETS_KEY",
+        value_name = "HEX",
+        hide_env_values = true
+    )]
+    pub(crate) secrets_key: Option<String>,
+
+    /// Derive the secrets master key from this passphrase instead of a key
+    /// file in the data directory. Ignored when --secrets-key is set.
+    #[arg(
+        long,
+        env = "OPENCODE_STUDIO_SECRETS_PASSPHRASE",
+        hide_env_values = true
+    )]
+    pub(crate) secrets_passphrase: Option<String>,
+
+    /// Redirect/rewrite/file rules from the runtime config's `[[routes]]`.
+    #[arg(skip)]
+    pub(crate) route_rules: Vec<crate::route_rules::RouteRule>,
+
+    /// Per-IP / per-token limits from the runtime config's `[rate_limit]`.
+    #[arg(skip)]
+    pub(crate) rate_limit: Option<crate::rate_limit::RateLimitConfig>,
+
+    /// Client address allow/deny lists from the runtime config's `[ip_filter]`.
+    #[arg(skip)]
+    pub(crate) ip_filter: Option<crate::ip_filter::IpFilterConfig>,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub(crate) enum Command {
+    /// Inspect the runtime configuration.
+    Config {
+        #[command(subcommand)]
+        action: runtime_config::ConfigAction,
+    },
+    /// Install, remove or inspect a background service running this binary.
+    Service {
+        #[command(subcommand)]
+        action: service_install::ServiceAction,
+    },
 }
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -157,14 +431,7 @@ pub(crate) fn issue_token() -> String {
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info,tower_http=info".into()),
-        )
-        .with_target(false)
-        .with_max_level(Level::INFO)
-        .init();
+    log_level::init_tracing();
 
     let args = match runtime_config::parse_args_with_runtime_config() {
         Ok(args) => args,
@@ -173,5 +440,26 @@ async fn main() {
             std::process::exit(2);
         }
     };
+    match &args.command {
+        Some(Command::Service { action }) => {
+            let config = runtime_config::resolved_config_path(&args);
+            if let Err(err) = service_install::run(action, config) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(Command::Config {
+            action: runtime_config::ConfigAction::Check,
+        }) => match runtime_config::check(args) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(2);
+            }
+        },
+        None => {}
+    }
     app::run(args).await;
 }
@@ -0,0 +1,202 @@
+use std::sync::LazyLock;
+
+use axum::Json;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd, html};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use syntect::highlighting::ThemeSet;
+use syntect::html::{ClassStyle, ClassedHTMLGenerator, css_for_theme_with_class_style};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+use crate::{ApiResult, AppError};
+
+const MAX_MARKDOWN_BYTES: usize = 2 * 1024 * 1024;
+/// Code blocks longer than this are emitted unhighlighted.
+const MAX_HIGHLIGHT_BYTES: usize = 256 * 1024;
+const HIGHLIGHT_CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+const HIGHLIGHT_THEME: &str = "InspiredGitHub";
+
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+static HIGHLIGHT_CSS: LazyLock<String> = LazyLock::new(|| {
+    let themes = ThemeSet::load_defaults();
+    themes
+        .themes
+        .get(HIGHLIGHT_THEME)
+        .and_then(|theme| css_for_theme_with_class_style(theme, HIGHLIGHT_CLASS_STYLE).ok())
+        .unwrap_or_default()
+});
+
+static SANITIZER: LazyLock<ammonia::Builder<'static>> = LazyLock::new(|| {
+    let mut builder = ammonia::Builder::default();
+    builder.add_generic_attributes(["class"]);
+    builder
+});
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn highlight_code(code: &str, lang: &str) -> String {
+    let syntax = (!lang.is_empty() && code.len() <= MAX_HIGHLIGHT_BYTES)
+        .then(|| SYNTAX_SET.find_syntax_by_token(lang))
+        .flatten();
+    let Some(syntax) = syntax else {
+        return escape_html(code);
+    };
+    let mut generator =
+        ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, HIGHLIGHT_CLASS_STYLE);
+    for line in LinesWithEndings::from(code) {
+        if generator
+            .parse_html_for_line_which_includes_newline(line)
+            .is_err()
+        {
+            return escape_html(code);
+        }
+    }
+    generator.finalize()
+}
+
+fn code_block_html(code: &str, lang: &str) -> String {
+    let lang_class = if lang.is_empty() {
+        String::new()
+    } else {
+        format!(" class=\"language-{}\"", escape_html(lang))
+    };
+    format!(
+        "<pre class=\"code\"><code{lang_class}>{}</code></pre>\n",
+        highlight_code(code, lang)
+    )
+}
+
+/// Render markdown to sanitized HTML. Fenced code is highlighted with
+/// `hl-`-prefixed classes (see [`highlight_css`]); `$…$` and `$$…$$` math is
+/// left as escaped TeX in `.math` elements for KaTeX to pick up client-side.
+pub(crate) fn render_markdown_html(src: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_MATH;
+
+    let mut events: Vec<Event> = Vec::new();
+    let mut code: Option<(String, String)> = None;
+    for event in Parser::new_ext(src, options) {
+        if let Some((lang, buf)) = code.as_mut() {
+            match event {
+                Event::Text(text) => buf.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => {
+                    let html = code_block_html(buf, lang);
+                    events.push(Event::Html(CowStr::from(html)));
+                    code = None;
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((lang, String::new()));
+            }
+            Event::InlineMath(tex) => events.push(Event::Html(CowStr::from(format!(
+                "<span class=\"math math-inline\">\\({}\\)</span>",
+                escape_html(&tex)
+            )))),
+            Event::DisplayMath(tex) => events.push(Event::Html(CowStr::from(format!(
+                "<span class=\"math math-display\">\\[{}\\]</span>",
+                escape_html(&tex)
+            )))),
+            other => events.push(other),
+        }
+    }
+
+    let mut raw = String::with_capacity(src.len() * 3 / 2);
+    html::push_html(&mut raw, events.into_iter());
+    SANITIZER.clean(&raw).to_string()
+}
+
+/// Stylesheet for the highlight classes emitted by [`render_markdown_html`].
+pub(crate) fn highlight_css() -> &'static str {
+    HIGHLIGHT_CSS.as_str()
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkdownRenderBody {
+    pub markdown: String,
+    #[serde(default)]
+    pub include_css: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkdownRenderResponse {
+    pub html: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub css: Option<String>,
+}
+
+pub(crate) async fn markdown_render_post(
+    Json(body): Json<MarkdownRenderBody>,
+) -> ApiResult<Json<MarkdownRenderResponse>> {
+    if body.markdown.len() > MAX_MARKDOWN_BYTES {
+        return Err(AppError::payload_too_large("markdown is too large"));
+    }
+    let html = tokio::task::spawn_blocking(move || render_markdown_html(&body.markdown))
+        .await
+        .map_err(|err| AppError::internal(err.to_string()))?;
+    Ok(Json(MarkdownRenderResponse {
+        html,
+        css: body.include_css.then(|| highlight_css().to_string()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_markdown_html_highlights_code_and_strips_scripts() {
+        let html = render_markdown_html(
+            "# Title\n\n<script>alert(1)</script>\n\n[x](javascript:alert(1))\n\n```rust\nfn main() {}\n```\n",
+        );
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("<code class=\"language-rust\">"));
+        assert!(html.contains("<span class=\"hl-"));
+        assert!(!highlight_css().is_empty());
+    }
+
+    #[test]
+    fn render_markdown_html_passes_math_through_escaped() {
+        let html = render_markdown_html("Euler: $e^{i\\pi} < 0$\n\n$$a_1 * b_2 * c$$\n");
+        assert!(html.contains("<span class=\"math math-inline\">\\(e^{i\\pi} &lt; 0\\)</span>"));
+        assert!(html.contains("<span class=\"math math-display\">\\[a_1 * b_2 * c\\]</span>"));
+    }
+
+    #[test]
+    fn render_markdown_html_escapes_unknown_language_code() {
+        let html = render_markdown_html("```nope\n<b>x</b>\n```\n");
+        assert!(html.contains("&lt;b&gt;x&lt;/b&gt;"));
+        assert!(!html.contains("<b>"));
+    }
+}
@@ -0,0 +1,461 @@
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    body::Body,
+    extract::{Path as AxumPath, Query, State},
+    http::StatusCode,
+    response::Response,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{ApiResult, AppError};
+
+const MAX_NAME_CHARS: usize = 120;
+const MAX_CONTENT_BYTES: usize = 16 * 1024;
+const MAX_TAGS: usize = 16;
+const MAX_TAG_CHARS: usize = 40;
+const MAX_SNIPPETS_PER_DIRECTORY: usize = 500;
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// A named, per-project note (decision, convention, command) that can be
+/// injected into prompts.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MemorySnippet {
+    pub id: String,
+    pub directory: String,
+    pub name: String,
+    pub content: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MemoryStore {
+    #[serde(default)]
+    snippets: Vec<MemorySnippet>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryListQuery {
+    pub directory: Option<String>,
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryExportQuery {
+    pub directory: Option<String>,
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryCreateBody {
+    pub directory: String,
+    pub name: String,
+    pub content: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryUpdateBody {
+    pub name: Option<String>,
+    pub content: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+fn normalize_directory(raw: &str) -> ApiResult<String> {
+    crate::path_utils::normalize_directory_for_match(raw)
+        .ok_or_else(|| AppError::bad_request("directory is required"))
+}
+
+fn sanitize_name(raw: &str) -> ApiResult<String> {
+    let name = raw.trim();
+    if name.is_empty() {
+        return Err(AppError::bad_request("name is required"));
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(AppError::bad_request("name is too long"));
+    }
+    Ok(name.to_string())
+}
+
+fn sanitize_content(raw: &str) -> ApiResult<String> {
+    let content = raw.trim();
+    if content.is_empty() {
+        return Err(AppError::bad_request("content is required"));
+    }
+    if content.len() > MAX_CONTENT_BYTES {
+        return Err(AppError::payload_too_large("content is too large"));
+    }
+    Ok(content.to_string())
+}
+
+fn sanitize_tags(raw: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in raw {
+        let tag = tag.trim().to_ascii_lowercase();
+        if tag.is_empty() || tag.chars().count() > MAX_TAG_CHARS || out.contains(&tag) {
+            continue;
+        }
+        out.push(tag);
+        if out.len() >= MAX_TAGS {
+            break;
+        }
+    }
+    out
+}
+
+async fn load_store(db: &crate::studio_db::StudioDb) -> ApiResult<MemoryStore> {
+    db.get_json::<MemoryStore>(crate::studio_db::KV_KEY_MEMORY_SNIPPETS)
+        .await
+        .map(Option::unwrap_or_default)
+        .map_err(AppError::internal)
+}
+
+async fn update_store<R>(
+    db: &crate::studio_db::StudioDb,
+    update: impl FnOnce(&mut MemoryStore) -> ApiResult<R>,
+) -> ApiResult<R> {
+    db.update_json(crate::studio_db::KV_KEY_MEMORY_SNIPPETS, update)
+        .await
+        .map_err(AppError::internal)?
+}
+
+fn snippets_for_directory<'a>(
+    store: &'a MemoryStore,
+    directory: Option<&'a str>,
+) -> impl Iterator<Item = &'a MemorySnippet> {
+    store
+        .snippets
+        .iter()
+        .filter(move |s| directory.is_none_or(|dir| s.directory == dir))
+}
+
+pub(crate) async fn memory_list(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<MemoryListQuery>,
+) -> ApiResult<Json<Vec<MemorySnippet>>> {
+    let directory = q
+        .directory
+        .as_deref()
+        .and_then(crate::path_utils::normalize_directory_for_match);
+    let tag = q
+        .tag
+        .as_deref()
+        .map(|t| t.trim().to_ascii_lowercase())
+        .filter(|t| !t.is_empty());
+
+    let store = load_store(state.studio_db.as_ref()).await?;
+    let mut out: Vec<MemorySnippet> = snippets_for_directory(&store, directory.as_deref())
+        .filter(|s| tag.as_ref().is_none_or(|t| s.tags.contains(t)))
+        .cloned()
+        .collect();
+    out.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+    Ok(Json(out))
+}
+
+pub(crate) async fn memory_get(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<MemorySnippet>> {
+    let store = load_store(state.studio_db.as_ref()).await?;
+    store
+        .snippets
+        .into_iter()
+        .find(|s| s.id == id)
+        .map(Json)
+        .ok_or_else(|| AppError::not_found("Memory snippet not found"))
+}
+
+pub(crate) async fn memory_create(
+    State(state): State<Arc<crate::AppState>>,
+    Json(body): Json<MemoryCreateBody>,
+) -> ApiResult<Json<MemorySnippet>> {
+    let directory = normalize_directory(&body.directory)?;
+    let name = sanitize_name(&body.name)?;
+    let content = sanitize_content(&body.content)?;
+    let tags = sanitize_tags(body.tags);
+
+    update_store(state.studio_db.as_ref(), |store| {
+        let existing = snippets_for_directory(store, Some(&directory)).count();
+        if existing >= MAX_SNIPPETS_PER_DIRECTORY {
+            return Err(AppError::bad_request(
+                "Too many memory snippets for this directory",
+            ));
+        }
+
+        let now = now_millis();
+        let snippet = MemorySnippet {
+            id: format!("mem_{}", uuid::Uuid::new_v4().simple()),
+            directory,
+            name,
+            content,
+            tags,
+            created_at: now,
+            updated_at: now,
+        };
+        store.snippets.push(snippet.clone());
+        Ok(Json(snippet))
+    })
+    .await
+}
+
+pub(crate) async fn memory_update(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(id): AxumPath<String>,
+    Json(body): Json<MemoryUpdateBody>,
+) -> ApiResult<Json<MemorySnippet>> {
+    let name = body.name.as_deref().map(sanitize_name).transpose()?;
+    let content = body.content.as_deref().map(sanitize_content).transpose()?;
+    let tags = body.tags.map(sanitize_tags);
+
+    update_store(state.studio_db.as_ref(), |store| {
+        let snippet = store
+            .snippets
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| AppError::not_found("Memory snippet not found"))?;
+        if let Some(name) = name {
+            snippet.name = name;
+        }
+        if let Some(content) = content {
+            snippet.content = content;
+        }
+        if let Some(tags) = tags {
+            snippet.tags = tags;
+        }
+        snippet.updated_at = now_millis();
+        Ok(Json(snippet.clone()))
+    })
+    .await
+}
+
+pub(crate) async fn memory_delete(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<Value>> {
+    update_store(state.studio_db.as_ref(), |store| {
+        let before = store.snippets.len();
+        store.snippets.retain(|s| s.id != id);
+        if store.snippets.len() == before {
+            return Err(AppError::not_found("Memory snippet not found"));
+        }
+        Ok(Json(json!({ "success": true })))
+    })
+    .await
+}
+
+fn render_markdown(snippets: &[MemorySnippet]) -> String {
+    let mut out = String::from("# Memory\n");
+    let mut current_dir: Option<&str> = None;
+    for snippet in snippets {
+        if current_dir != Some(snippet.directory.as_str()) {
+            out.push_str(&format!("\n## {}\n", snippet.directory));
+            current_dir = Some(snippet.directory.as_str());
+        }
+        out.push_str(&format!("\n### {}\n\n", snippet.name));
+        if !snippet.tags.is_empty() {
+            out.push_str(&format!("Tags: {}\n\n", snippet.tags.join(", ")));
+        }
+        out.push_str(&snippet.content);
+        out.push('\n');
+    }
+    out
+}
+
+pub(crate) async fn memory_export(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<MemoryExportQuery>,
+) -> ApiResult<Response> {
+    let directory = q
+        .directory
+        .as_deref()
+        .and_then(crate::path_utils::normalize_directory_for_match);
+    let store = load_store(state.studio_db.as_ref()).await?;
+    let mut snippets: Vec<MemorySnippet> = snippets_for_directory(&store, directory.as_deref())
+        .cloned()
+        .collect();
+    snippets.sort_by(|a, b| {
+        a.directory
+            .cmp(&b.directory)
+            .then(a.created_at.cmp(&b.created_at))
+    });
+
+    let (content_type, filename, body) = match q.format.as_deref().unwrap_or("json") {
+        "json" => (
+            "application/json",
+            "memory.json",
+            serde_json::to_string_pretty(&json!({ "snippets": snippets }))
+                .map_err(|err| AppError::internal(err.to_string()))?,
+        ),
+        "markdown" | "md" => (
+            "text/markdown; charset=utf-8",
+            "memory.md",
+            render_markdown(&snippets),
+        ),
+        _ => return Err(AppError::bad_request("format must be json or markdown")),
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("cache-control", "no-store")
+        .header("content-type", content_type)
+        .header(
+            "content-disposition",
+            format!("attachment; filename=\"{filename}\""),
+        )
+        .body(Body::from(body))
+        .unwrap())
+}
+
+/// Build the synthetic text part that carries the selected snippets into a
+/// prompt. Snippets are per project, so a directory is required and ids from
+/// other directories are ignored.
+pub(crate) async fn memory_prompt_part(
+    db: &crate::studio_db::StudioDb,
+    directory: Option<&str>,
+    ids: &[String],
+) -> ApiResult<Option<Value>> {
+    if ids.is_empty() {
+        return Ok(None);
+    }
+    let directory = directory
+        .and_then(crate::path_utils::normalize_directory_for_match)
+        .ok_or_else(|| AppError::bad_request("directory is required to attach memory snippets"))?;
+    let store = load_store(db).await?;
+    let selected: Vec<&MemorySnippet> = ids
+        .iter()
+        .filter_map(|id| snippets_for_directory(&store, Some(&directory)).find(|s| &s.id == id))
+        .collect();
+    Ok(render_prompt_text(&selected).map(|text| {
+        json!({
+            "type": "text",
+            "text": text,
+            "synthetic": true,
+        })
+    }))
+}
+
+fn render_prompt_text(snippets: &[&MemorySnippet]) -> Option<String> {
+    if snippets.is_empty() {
+        return None;
+    }
+    let mut text = String::from(
+        "<project-memory>\nNotes saved for this project; follow them unless told otherwise.\n",
+    );
+    for snippet in snippets {
+        text.push_str(&format!("\n## {}\n{}\n", snippet.name, snippet.content));
+    }
+    text.push_str("</project-memory>");
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(id: &str, directory: &str, name: &str) -> MemorySnippet {
+        MemorySnippet {
+            id: id.to_string(),
+            directory: directory.to_string(),
+            name: name.to_string(),
+            content: format!("{name} body"),
+            tags: Vec::new(),
+            created_at: 1,
+            updated_at: 1,
+        }
+    }
+
+    #[test]
+    fn sanitize_tags_dedupes_and_lowercases() {
+        let tags = sanitize_tags(vec![
+            " Build ".to_string(),
+            "build".to_string(),
+            "".to_string(),
+            "CI".to_string(),
+        ]);
+        assert_eq!(tags, vec!["build".to_string(), "ci".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn memory_prompt_part_only_includes_snippets_from_directory() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = crate::studio_db::StudioDb::open_at_path(tmp.path().join("studio.db"))
+            .await
+            .expect("open db");
+        update_store(&db, |store| {
+            store.snippets = vec![
+                snippet("mem_a", "/repo/a", "Use pnpm"),
+                snippet("mem_b", "/repo/b", "Use cargo nextest"),
+            ];
+            Ok(())
+        })
+        .await
+        .expect("persist");
+
+        let ids = vec!["mem_a".to_string(), "mem_b".to_string()];
+        let part = memory_prompt_part(&db, Some("/repo/a/"), &ids)
+            .await
+            .expect("part")
+            .expect("some");
+        let text = part["text"].as_str().unwrap();
+        assert_eq!(part["synthetic"], true);
+        assert!(text.contains("## Use pnpm\nUse pnpm body"));
+        assert!(!text.contains("nextest"));
+
+        let none = memory_prompt_part(&db, Some("/repo/c"), &ids)
+            .await
+            .expect("part");
+        assert!(none.is_none());
+
+        assert!(memory_prompt_part(&db, None, &ids).await.is_err());
+        assert!(memory_prompt_part(&db, None, &[]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn concurrent_updates_keep_every_snippet() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = Arc::new(
+            crate::studio_db::StudioDb::open_at_path(tmp.path().join("studio.db"))
+                .await
+                .expect("open db"),
+        );
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    update_store(&db, |store| {
+                        let id = format!("mem_{i}");
+                        store.snippets.push(snippet(&id, "/repo", &id));
+                        Ok(())
+                    })
+                    .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.expect("join").expect("update");
+        }
+        assert_eq!(load_store(&db).await.expect("load").snippets.len(), 8);
+    }
+}
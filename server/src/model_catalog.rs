@@ -0,0 +1,385 @@
+//! Cached provider/model catalog.
+//!
+//! `GET /config/providers` used to go straight to OpenCode on every call and
+//! failed whenever OpenCode (or the network behind it) was down. Responses
+//! are now kept per directory in memory and in the studio DB: fresh copies
+//! are served for `CATALOG_TTL_MS`, and when a fetch fails the last known
+//! copy is served instead, marked stale via `x-model-catalog`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::studio_db::StudioDb;
+
+const CATALOG_TTL_MS: u64 = 10 * 60 * 1000;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+const MAX_CATALOGS: usize = 64;
+
+pub(crate) const MODEL_CATALOG_HEADER: &str = "x-model-catalog";
+pub(crate) const MODEL_CATALOG_FETCHED_AT_HEADER: &str = "x-model-catalog-fetched-at";
+
+#[derive(Clone)]
+struct CatalogEntry {
+    payload: Bytes,
+    fetched_at: u64,
+    // Entries from an older epoch (or loaded from disk) are only used as a
+    // fallback.
+    epoch: u64,
+}
+
+impl CatalogEntry {
+    fn is_fresh(&self, now: u64) -> bool {
+        self.epoch == EPOCH.load(Ordering::SeqCst)
+            && now.saturating_sub(self.fetched_at) < CATALOG_TTL_MS
+    }
+}
+
+static CATALOGS: LazyLock<DashMap<String, CatalogEntry>> = LazyLock::new(DashMap::new);
+static EPOCH: AtomicU64 = AtomicU64::new(1);
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Force the next catalog read to go upstream, e.g. after provider config
+/// changed. Cached copies stay around as the offline fallback.
+pub(crate) fn invalidate() {
+    EPOCH.fetch_add(1, Ordering::SeqCst);
+}
+
+fn catalog_key(directory: Option<&str>) -> String {
+    directory
+        .map(|d| d.trim().trim_end_matches(['/', '\\']))
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn requested_directory(headers: &HeaderMap, query: Option<&str>) -> Option<String> {
+    query
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .or_else(|| {
+            headers
+                .get("x-opencode-directory")
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        })
+}
+
+async fn load_entry(db: &StudioDb, key: &str) -> Option<CatalogEntry> {
+    if let Some(entry) = CATALOGS.get(key) {
+        return Some(entry.clone());
+    }
+    let row = sqlx::query_as::<_, (String, i64)>(
+        "SELECT payload, fetched_at FROM model_catalogs WHERE directory = ?",
+    )
+    .bind(key)
+    .fetch_optional(db.pool())
+    .await
+    .ok()
+    .flatten()?;
+    let entry = CatalogEntry {
+        payload: Bytes::from(row.0),
+        fetched_at: row.1.max(0) as u64,
+        epoch: 0,
+    };
+    CATALOGS.insert(key.to_string(), entry.clone());
+    Some(entry)
+}
+
+async fn store_entry(db: &StudioDb, key: &str, payload: Bytes) -> CatalogEntry {
+    let entry = CatalogEntry {
+        payload,
+        fetched_at: now_millis(),
+        epoch: EPOCH.load(Ordering::SeqCst),
+    };
+    CATALOGS.insert(key.to_string(), entry.clone());
+    if CATALOGS.len() > MAX_CATALOGS {
+        let oldest = CATALOGS
+            .iter()
+            .min_by_key(|e| e.fetched_at)
+            .map(|e| e.key().clone());
+        if let Some(oldest) = oldest {
+            CATALOGS.remove(&oldest);
+        }
+    }
+
+    let text = String::from_utf8_lossy(&entry.payload).into_owned();
+    let result = sqlx::query(
+        "INSERT INTO model_catalogs (directory, payload, fetched_at) VALUES (?, ?, ?)\n         ON CONFLICT(directory) DO UPDATE SET\n           payload = excluded.payload,\n           fetched_at = excluded.fetched_at",
+    )
+    .bind(key)
+    .bind(text)
+    .bind(entry.fetched_at as i64)
+    .execute(db.pool())
+    .await;
+    let result = match result {
+        Ok(_) => sqlx::query(
+            "DELETE FROM model_catalogs WHERE directory NOT IN (\n               SELECT directory FROM model_catalogs ORDER BY fetched_at DESC LIMIT ?\n             )",
+        )
+        .bind(MAX_CATALOGS as i64)
+        .execute(db.pool())
+        .await
+        .map(|_| ()),
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        tracing::warn!(error = %err, "failed to persist model catalog");
+    }
+    entry
+}
+
+struct FetchFailure {
+    response: Response,
+    /// OpenCode or the network was unavailable, as opposed to OpenCode
+    /// rejecting the request; only then is a stale copy served.
+    unreachable: bool,
+}
+
+impl FetchFailure {
+    fn unreachable(response: Response) -> Self {
+        Self {
+            response,
+            unreachable: true,
+        }
+    }
+}
+
+async fn fetch_upstream(
+    state: &crate::AppState,
+    directory: Option<&str>,
+) -> Result<Bytes, FetchFailure> {
+    let upstream = state.opencode.for_directory(directory).await;
+    let oc = upstream.status().await;
+    if oc.restarting || !oc.ready {
+        return Err(FetchFailure::unreachable(
+            crate::opencode_proxy::open_code_not_ready(&oc),
+        ));
+    }
+    let Some(bridge) = upstream.bridge().await else {
+        return Err(FetchFailure::unreachable(
+            crate::opencode_proxy::open_code_unavailable(Some(&oc)),
+        ));
+    };
+    let mut url = format!("{}/config/providers", bridge.base_url.trim_end_matches('/'));
+    if let Some(directory) = directory {
+        url.push_str("?directory=");
+        url.push_str(&urlencoding::encode(directory));
+    }
+
+    let upstream_error = |status: StatusCode, message: String| {
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    };
+    let resp = bridge
+        .client
+        .get(&url)
+        .header("accept", "application/json")
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await
+        .map_err(|err| {
+            FetchFailure::unreachable(upstream_error(
+                StatusCode::BAD_GATEWAY,
+                format!("Failed to fetch model catalog: {err}"),
+            ))
+        })?;
+    let status = resp.status();
+    let body = resp.bytes().await.map_err(|err| {
+        FetchFailure::unreachable(upstream_error(
+            StatusCode::BAD_GATEWAY,
+            format!("Failed to read model catalog: {err}"),
+        ))
+    })?;
+    if !status.is_success() {
+        let status = StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        return Err(FetchFailure {
+            response: (
+                status,
+                [(axum::http::header::CONTENT_TYPE, "application/json")],
+                body,
+            )
+                .into_response(),
+            unreachable: status.is_server_error(),
+        });
+    }
+    if !serde_json::from_slice::<Value>(&body).is_ok_and(|v| v.is_object()) {
+        return Err(FetchFailure::unreachable(upstream_error(
+            StatusCode::BAD_GATEWAY,
+            "OpenCode returned an invalid model catalog".to_string(),
+        )));
+    }
+    Ok(body)
+}
+
+fn catalog_response(entry: &CatalogEntry, source: &'static str) -> Response {
+    (
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                "application/json".to_string(),
+            ),
+            (
+                axum::http::HeaderName::from_static(MODEL_CATALOG_HEADER),
+                source.to_string(),
+            ),
+            (
+                axum::http::HeaderName::from_static(MODEL_CATALOG_FETCHED_AT_HEADER),
+                entry.fetched_at.to_string(),
+            ),
+        ],
+        entry.payload.clone(),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct ModelCatalogQuery {
+    directory: Option<String>,
+}
+
+/// OpenCode's `/config/providers`, served from the catalog cache.
+/// `x-model-catalog` says whether the body is `fresh` from OpenCode,
+/// `cached`, or a `stale` fallback because OpenCode could not be reached.
+pub(crate) async fn config_providers_get(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Query(q): Query<ModelCatalogQuery>,
+) -> Response {
+    let directory = requested_directory(&headers, q.directory.as_deref());
+    let key = catalog_key(directory.as_deref());
+    let cached = load_entry(&state.studio_db, &key).await;
+    if let Some(entry) = &cached
+        && entry.is_fresh(now_millis())
+    {
+        return catalog_response(entry, "cached");
+    }
+
+    match fetch_upstream(&state, directory.as_deref()).await {
+        Ok(payload) => {
+            catalog_response(&store_entry(&state.studio_db, &key, payload).await, "fresh")
+        }
+        Err(failure) => match cached {
+            Some(entry) if failure.unreachable => {
+                tracing::debug!(
+                    directory = %key,
+                    fetched_at = entry.fetched_at,
+                    "serving stale model catalog"
+                );
+                catalog_response(&entry, "stale")
+            }
+            _ => failure.response,
+        },
+    }
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub(crate) struct ModelCatalogRefreshBody {
+    directory: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ModelCatalogRefreshResponse {
+    directory: Option<String>,
+    fetched_at: u64,
+    providers: usize,
+    models: usize,
+}
+
+fn count_models(payload: &Value) -> (usize, usize) {
+    let providers = payload
+        .get("providers")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let models = providers
+        .iter()
+        .filter_map(|p| p.get("models").and_then(Value::as_object))
+        .map(|m| m.len())
+        .sum();
+    (providers.len(), models)
+}
+
+/// Re-fetch the catalog from OpenCode now, bypassing the TTL.
+pub(crate) async fn model_catalog_refresh_post(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    body: Option<Json<ModelCatalogRefreshBody>>,
+) -> Response {
+    let body = body.map(|Json(body)| body).unwrap_or_default();
+    let directory = requested_directory(&headers, body.directory.as_deref());
+    let key = catalog_key(directory.as_deref());
+    let payload = match fetch_upstream(&state, directory.as_deref()).await {
+        Ok(payload) => payload,
+        Err(failure) => return failure.response,
+    };
+    let entry = store_entry(&state.studio_db, &key, payload).await;
+    let (providers, models) = serde_json::from_slice::<Value>(&entry.payload)
+        .map(|v| count_models(&v))
+        .unwrap_or_default();
+    Json(ModelCatalogRefreshResponse {
+        directory,
+        fetched_at: entry.fetched_at,
+        providers,
+        models,
+    })
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn stored_catalog_survives_restart_as_fallback_only() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = StudioDb::open_at_path(tmp.path().join("studio.db"))
+            .await
+            .expect("db");
+        let key = catalog_key(Some("/tmp/model-catalog-test/"));
+        assert_eq!(key, "/tmp/model-catalog-test");
+
+        let entry = store_entry(&db, &key, Bytes::from_static(b"{\"providers\":[]}")).await;
+        assert!(entry.is_fresh(entry.fetched_at));
+        assert!(!entry.is_fresh(entry.fetched_at + CATALOG_TTL_MS));
+
+        // A new process only has the DB copy.
+        CATALOGS.remove(&key);
+        let loaded = load_entry(&db, &key).await.expect("persisted");
+        assert_eq!(loaded.payload, entry.payload);
+        assert!(!loaded.is_fresh(loaded.fetched_at));
+    }
+
+    #[test]
+    fn count_models_sums_provider_model_maps() {
+        let payload = json!({
+            "providers": [
+                { "id": "openai", "models": { "gpt-5": {}, "gpt-5-mini": {} } },
+                { "id": "anthropic", "models": { "claude-sonnet-4-5": {} } },
+                { "id": "empty" },
+            ],
+            "default": { "openai": "gpt-5" },
+        });
+        assert_eq!(count_models(&payload), (3, 3));
+        assert_eq!(count_models(&json!({})), (0, 0));
+    }
+}
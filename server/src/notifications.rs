@@ -0,0 +1,759 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use dashmap::DashMap;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::session_activity::{SessionPhase, derive_session_activity};
+use crate::{ApiResult, AppError};
+
+const SETTINGS_KEY: &str = "notifications";
+const SMTP_PASSWORD_ENV: &str = "OPENCODE_STUDIO_SMTP_PASSWORD";
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const SMTP_TIMEOUT: Duration = Duration::from_secs(20);
+const DESKTOP_QUEUE_CAPACITY: usize = 100;
+/// `session.error` is followed by an idle status; don't report both.
+const IDLE_AFTER_ERROR_SUPPRESS: Duration = Duration::from_secs(10);
+const MAX_DETAIL_CHARS: usize = 280;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum NotificationEvent {
+    Idle,
+    Error,
+    Permission,
+    Question,
+}
+
+impl NotificationEvent {
+    pub(crate) const ALL: [NotificationEvent; 4] = [
+        NotificationEvent::Idle,
+        NotificationEvent::Error,
+        NotificationEvent::Permission,
+        NotificationEvent::Question,
+    ];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            NotificationEvent::Idle => "idle",
+            NotificationEvent::Error => "error",
+            NotificationEvent::Permission => "permission",
+            NotificationEvent::Question => "question",
+        }
+    }
+
+    fn headline(self) -> &'static str {
+        match self {
+            NotificationEvent::Idle => "Session finished",
+            NotificationEvent::Error => "Session failed",
+            NotificationEvent::Permission => "Permission requested",
+            NotificationEvent::Question => "Question waiting",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum NotificationSink {
+    Webhook,
+    Desktop,
+    Email,
+}
+
+impl NotificationSink {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "webhook" => Some(NotificationSink::Webhook),
+            "desktop" => Some(NotificationSink::Desktop),
+            "email" | "smtp" => Some(NotificationSink::Email),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SmtpSecurity {
+    StartTls,
+    Tls,
+    None,
+}
+
+#[derive(Debug, Clone)]
+struct SmtpSettings {
+    host: String,
+    port: Option<u16>,
+    security: SmtpSecurity,
+    username: Option<String>,
+    from: String,
+    to: Vec<String>,
+}
+
+/// Parsed `settings.notifications`. Events map to the sinks they fan out to;
+/// an event with no sinks is off.
+#[derive(Debug, Clone, Default)]
+struct NotificationSettings {
+    enabled: bool,
+    events: Vec<(NotificationEvent, Vec<NotificationSink>)>,
+    webhook_url: Option<String>,
+    smtp: Option<SmtpSettings>,
+}
+
+impl NotificationSettings {
+    fn sinks_for(&self, event: NotificationEvent) -> &[NotificationSink] {
+        if !self.enabled {
+            return &[];
+        }
+        self.events
+            .iter()
+            .find(|(e, _)| *e == event)
+            .map(|(_, sinks)| sinks.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+fn str_field<'a>(obj: &'a serde_json::Map<String, Value>, key: &str) -> Option<&'a str> {
+    obj.get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+fn parse_smtp(value: Option<&Value>) -> Option<SmtpSettings> {
+    let obj = value?.as_object()?;
+    let host = str_field(obj, "host")?.to_string();
+    let from = str_field(obj, "from")?.to_string();
+    let to: Vec<String> = obj
+        .get("to")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    if to.is_empty() {
+        return None;
+    }
+    let security = match str_field(obj, "security").unwrap_or("starttls") {
+        "tls" => SmtpSecurity::Tls,
+        "none" => SmtpSecurity::None,
+        _ => SmtpSecurity::StartTls,
+    };
+    Some(SmtpSettings {
+        host,
+        port: obj
+            .get("port")
+            .and_then(|v| v.as_u64())
+            .and_then(|p| u16::try_from(p).ok())
+            .filter(|p| *p != 0),
+        security,
+        username: str_field(obj, "username").map(str::to_string),
+        from,
+        to,
+    })
+}
+
+fn parse_notification_settings(value: Option<&Value>) -> NotificationSettings {
+    let Some(obj) = value.and_then(|v| v.as_object()) else {
+        return NotificationSettings::default();
+    };
+    let events_obj = obj.get("events").and_then(|v| v.as_object());
+    let events = NotificationEvent::ALL
+        .iter()
+        .map(|event| {
+            let mut sinks: Vec<NotificationSink> = Vec::new();
+            let raw = events_obj
+                .and_then(|m| m.get(event.as_str()))
+                .and_then(|v| v.as_array());
+            for sink in raw
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str())
+                .filter_map(NotificationSink::parse)
+            {
+                if !sinks.contains(&sink) {
+                    sinks.push(sink);
+                }
+            }
+            (*event, sinks)
+        })
+        .collect();
+    NotificationSettings {
+        enabled: obj
+            .get("enabled")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        events,
+        webhook_url: obj
+            .get("webhook")
+            .and_then(|v| v.as_object())
+            .and_then(|w| str_field(w, "url"))
+            .map(str::to_string),
+        smtp: parse_smtp(obj.get("smtp")),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Notification {
+    pub event: NotificationEvent,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    pub title: String,
+    pub body: String,
+    pub session_title: Option<String>,
+    pub directory: Option<String>,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DesktopNotification {
+    pub seq: u64,
+    #[serde(flatten)]
+    pub notification: Notification,
+}
+
+struct Notifier {
+    last_phase: DashMap<String, SessionPhase>,
+    last_error_at: DashMap<String, Instant>,
+    desktop_queue: Mutex<VecDeque<DesktopNotification>>,
+    next_desktop_seq: AtomicU64,
+}
+
+static NOTIFIER: LazyLock<Notifier> = LazyLock::new(|| Notifier {
+    last_phase: DashMap::new(),
+    last_error_at: DashMap::new(),
+    desktop_queue: Mutex::new(VecDeque::new()),
+    next_desktop_seq: AtomicU64::new(1),
+});
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(max) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+fn read_session_id(props: &serde_json::Map<String, Value>) -> Option<String> {
+    props
+        .get("sessionID")
+        .or_else(|| props.get("sessionId"))
+        .or_else(|| props.get("session_id"))
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn error_detail(props: &serde_json::Map<String, Value>) -> Option<String> {
+    let error = props.get("error")?;
+    let message = error
+        .pointer("/data/message")
+        .or_else(|| error.get("message"))
+        .and_then(|v| v.as_str())
+        .or_else(|| error.get("name").and_then(|v| v.as_str()))
+        .or_else(|| error.as_str())?;
+    Some(message.to_string())
+}
+
+fn is_abort_error(props: &serde_json::Map<String, Value>) -> bool {
+    props
+        .get("error")
+        .and_then(|e| e.get("name"))
+        .and_then(|v| v.as_str())
+        .is_some_and(|name| name == "MessageAbortedError")
+}
+
+fn permission_detail(props: &serde_json::Map<String, Value>) -> Option<String> {
+    let permission = str_field(props, "permission")?;
+    let patterns: Vec<&str> = props
+        .get("patterns")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    if patterns.is_empty() {
+        Some(permission.to_string())
+    } else {
+        Some(format!("{permission}: {}", patterns.join(", ")))
+    }
+}
+
+fn question_detail(props: &serde_json::Map<String, Value>) -> Option<String> {
+    props
+        .get("questions")
+        .and_then(|v| v.as_array())
+        .and_then(|arr| arr.first())
+        .and_then(|q| q.get("question").or_else(|| q.get("header")))
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+}
+
+/// Map an upstream event to a notification-worthy event. Idle is reported on
+/// the busy -> idle edge of the phase derived by `session_activity`, so
+/// repeated idle statuses for an already idle session stay quiet.
+fn derive_notification_event(
+    notifier: &Notifier,
+    payload: &Value,
+) -> Option<(String, NotificationEvent, Option<String>)> {
+    let obj = payload.as_object()?;
+    let ty = obj.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    let props = obj.get("properties").and_then(|v| v.as_object());
+
+    match ty {
+        "session.deleted" => {
+            let session_id = props.and_then(read_session_id)?;
+            notifier.last_phase.remove(&session_id);
+            notifier.last_error_at.remove(&session_id);
+            return None;
+        }
+        "session.error" => {
+            let props = props?;
+            let session_id = read_session_id(props)?;
+            notifier
+                .last_phase
+                .insert(session_id.clone(), SessionPhase::Idle);
+            if is_abort_error(props) {
+                return None;
+            }
+            notifier
+                .last_error_at
+                .insert(session_id.clone(), Instant::now());
+            return Some((session_id, NotificationEvent::Error, error_detail(props)));
+        }
+        "permission.asked" => {
+            let props = props?;
+            return Some((
+                read_session_id(props)?,
+                NotificationEvent::Permission,
+                permission_detail(props),
+            ));
+        }
+        "question.asked" => {
+            let props = props?;
+            return Some((
+                read_session_id(props)?,
+                NotificationEvent::Question,
+                question_detail(props),
+            ));
+        }
+        _ => {}
+    }
+
+    let (session_id, phase) = derive_session_activity(payload)?;
+    let previous = notifier.last_phase.insert(session_id.clone(), phase);
+    if phase != SessionPhase::Idle
+        || !matches!(
+            previous,
+            Some(SessionPhase::Busy) | Some(SessionPhase::Cooldown)
+        )
+    {
+        return None;
+    }
+    if let Some((_, at)) = notifier.last_error_at.remove(&session_id)
+        && at.elapsed() < IDLE_AFTER_ERROR_SUPPRESS
+    {
+        return None;
+    }
+    Some((session_id, NotificationEvent::Idle, None))
+}
+
+fn build_notification(
+    state: &crate::AppState,
+    session_id: String,
+    event: NotificationEvent,
+    detail: Option<String>,
+) -> Option<Notification> {
+    let index = &state.directory_session_index;
+    let summary = index.summary(&session_id);
+    // Subagent sessions finish and fail as part of their parent's run.
+    if matches!(event, NotificationEvent::Idle | NotificationEvent::Error)
+        && summary.as_ref().is_some_and(|s| s.parent_id.is_some())
+    {
+        return None;
+    }
+    let session_title = summary
+        .as_ref()
+        .map(|s| s.title.trim().to_string())
+        .filter(|t| !t.is_empty());
+    let directory = summary
+        .map(|s| s.directory_path)
+        .or_else(|| index.directory_for_session(&session_id));
+    let subject = session_title.as_deref().unwrap_or(session_id.as_str());
+    let body = match detail.map(|d| truncate_chars(&d, MAX_DETAIL_CHARS)) {
+        Some(detail) if !detail.is_empty() => format!("{subject}\n{detail}"),
+        _ => subject.to_string(),
+    };
+    Some(Notification {
+        event,
+        session_id,
+        title: event.headline().to_string(),
+        body,
+        session_title,
+        directory,
+        timestamp: now_millis(),
+    })
+}
+
+/// Feed one upstream event through the notification pipeline. Delivery runs
+/// in the background so the SSE loop never waits on a sink.
+pub(crate) fn observe_event(state: &Arc<crate::AppState>, payload: &Value) {
+    let Some((session_id, event, detail)) = derive_notification_event(&NOTIFIER, payload) else {
+        return;
+    };
+    let state = state.clone();
+    tokio::spawn(async move {
+        let settings = {
+            let guard = state.settings.read().await;
+            parse_notification_settings(guard.extra.get(SETTINGS_KEY))
+        };
+        let sinks = settings.sinks_for(event).to_vec();
+        if sinks.is_empty() {
+            return;
+        }
+        let Some(notification) = build_notification(&state, session_id, event, detail) else {
+            return;
+        };
+        for (sink, result) in deliver(&settings, &sinks, &notification).await {
+            if let Err(err) = result {
+                tracing::warn!(sink = ?sink, event = event.as_str(), error = %err, "Notification delivery failed");
+            }
+        }
+    });
+}
+
+async fn deliver(
+    settings: &NotificationSettings,
+    sinks: &[NotificationSink],
+    notification: &Notification,
+) -> Vec<(NotificationSink, Result<(), String>)> {
+    let mut out = Vec::with_capacity(sinks.len());
+    for sink in sinks {
+        let result = match sink {
+            NotificationSink::Desktop => {
+                push_desktop(notification.clone());
+                Ok(())
+            }
+            NotificationSink::Webhook => match settings.webhook_url.as_deref() {
+                Some(url) => send_webhook(url, notification).await,
+                None => Err("webhook url is not configured".to_string()),
+            },
+            NotificationSink::Email => match settings.smtp.as_ref() {
+                Some(smtp) => send_email(smtp, notification).await,
+                None => Err("smtp is not configured".to_string()),
+            },
+        };
+        out.push((*sink, result));
+    }
+    out
+}
+
+fn push_desktop(notification: Notification) {
+    let seq = NOTIFIER.next_desktop_seq.fetch_add(1, Ordering::SeqCst);
+    let mut queue = NOTIFIER
+        .desktop_queue
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    queue.push_back(DesktopNotification { seq, notification });
+    while queue.len() > DESKTOP_QUEUE_CAPACITY {
+        queue.pop_front();
+    }
+}
+
+async fn send_webhook(url: &str, notification: &Notification) -> Result<(), String> {
+    let mut payload = serde_json::to_value(notification).map_err(|e| e.to_string())?;
+    // Chat webhooks (Slack, Mattermost, ...) render `text` directly.
+    payload["text"] = Value::String(format!("{}: {}", notification.title, notification.body));
+    let client = crate::tls_roots::client_builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = client
+        .post(url)
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("webhook responded with {}", resp.status()));
+    }
+    Ok(())
+}
+
+async fn send_email(smtp: &SmtpSettings, notification: &Notification) -> Result<(), String> {
+    let from: Mailbox = smtp
+        .from
+        .parse()
+        .map_err(|e| format!("invalid from address: {e}"))?;
+    let mut builder = Message::builder().from(from).subject(format!(
+        "[OpenCode Studio] {}: {}",
+        notification.title,
+        notification
+            .session_title
+            .as_deref()
+            .unwrap_or(notification.session_id.as_str())
+    ));
+    for to in &smtp.to {
+        let mailbox: Mailbox = to
+            .parse()
+            .map_err(|e| format!("invalid recipient {to}: {e}"))?;
+        builder = builder.to(mailbox);
+    }
+    let mut body = notification.body.clone();
+    if let Some(dir) = notification.directory.as_deref() {
+        body.push_str(&format!("\n\nDirectory: {dir}"));
+    }
+    body.push_str(&format!("\nSession: {}", notification.session_id));
+    let message = builder.body(body).map_err(|e| e.to_string())?;
+
+    let mut transport = match smtp.security {
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host),
+        SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host),
+        SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+            &smtp.host,
+        )),
+    }
+    .map_err(|e| e.to_string())?
+    .timeout(Some(SMTP_TIMEOUT));
+    if let Some(port) = smtp.port {
+        transport = transport.port(port);
+    }
+    if let Some(username) = smtp.username.as_deref() {
+        let password = std::env::var(SMTP_PASSWORD_ENV).unwrap_or_default();
+        transport = transport.credentials(Credentials::new(username.to_string(), password));
+    }
+    transport
+        .build()
+        .send(message)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DesktopNotificationsQuery {
+    pub after: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DesktopNotificationsResponse {
+    pub latest_seq: u64,
+    pub notifications: Vec<DesktopNotification>,
+}
+
+/// Notifications queued for the desktop shell. Without `after` only the
+/// current sequence is returned, so a fresh poller starts from "now".
+pub(crate) async fn notifications_desktop_get(
+    Query(q): Query<DesktopNotificationsQuery>,
+) -> Json<DesktopNotificationsResponse> {
+    let latest_seq = NOTIFIER
+        .next_desktop_seq
+        .load(Ordering::SeqCst)
+        .saturating_sub(1);
+    let notifications = match q.after {
+        Some(after) => NOTIFIER
+            .desktop_queue
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|n| n.seq > after)
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    };
+    Json(DesktopNotificationsResponse {
+        latest_seq,
+        notifications,
+    })
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationTestBody {
+    /// Sinks to exercise; defaults to every configured sink.
+    pub sinks: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationTestResult {
+    pub sink: NotificationSink,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// Send a sample notification so sink settings can be checked from the UI.
+pub(crate) async fn notifications_test_post(
+    State(state): State<Arc<crate::AppState>>,
+    Json(body): Json<NotificationTestBody>,
+) -> ApiResult<Json<Vec<NotificationTestResult>>> {
+    let settings = {
+        let guard = state.settings.read().await;
+        parse_notification_settings(guard.extra.get(SETTINGS_KEY))
+    };
+    let sinks: Vec<NotificationSink> = match body.sinks {
+        Some(raw) => raw
+            .iter()
+            .map(|s| {
+                NotificationSink::parse(s)
+                    .ok_or_else(|| AppError::bad_request(format!("Unknown sink '{s}'")))
+            })
+            .collect::<Result<_, _>>()?,
+        None => {
+            let mut sinks = vec![NotificationSink::Desktop];
+            if settings.webhook_url.is_some() {
+                sinks.push(NotificationSink::Webhook);
+            }
+            if settings.smtp.is_some() {
+                sinks.push(NotificationSink::Email);
+            }
+            sinks
+        }
+    };
+
+    let notification = Notification {
+        event: NotificationEvent::Idle,
+        session_id: "test".to_string(),
+        title: "Test notification".to_string(),
+        body: "Notifications from OpenCode Studio are working.".to_string(),
+        session_title: None,
+        directory: None,
+        timestamp: now_millis(),
+    };
+    Ok(Json(
+        deliver(&settings, &sinks, &notification)
+            .await
+            .into_iter()
+            .map(|(sink, result)| NotificationTestResult {
+                sink,
+                ok: result.is_ok(),
+                error: result.err(),
+            })
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notifier() -> Notifier {
+        Notifier {
+            last_phase: DashMap::new(),
+            last_error_at: DashMap::new(),
+            desktop_queue: Mutex::new(VecDeque::new()),
+            next_desktop_seq: AtomicU64::new(1),
+        }
+    }
+
+    fn status(session_id: &str, ty: &str) -> Value {
+        serde_json::json!({
+            "type": "session.status",
+            "properties": {"sessionID": session_id, "status": {"type": ty}},
+        })
+    }
+
+    #[test]
+    fn idle_is_reported_once_on_busy_to_idle_edge() {
+        let n = notifier();
+        assert!(derive_notification_event(&n, &status("ses_1", "idle")).is_none());
+        assert!(derive_notification_event(&n, &status("ses_1", "busy")).is_none());
+        let (sid, event, _) = derive_notification_event(&n, &status("ses_1", "idle")).unwrap();
+        assert_eq!((sid.as_str(), event), ("ses_1", NotificationEvent::Idle));
+        assert!(derive_notification_event(&n, &status("ses_1", "idle")).is_none());
+    }
+
+    #[test]
+    fn error_suppresses_following_idle_and_aborts_are_ignored() {
+        let n = notifier();
+        derive_notification_event(&n, &status("ses_1", "busy"));
+        let error = serde_json::json!({
+            "type": "session.error",
+            "properties": {
+                "sessionID": "ses_1",
+                "error": {"name": "APIError", "data": {"message": "rate limited"}},
+            },
+        });
+        let (_, event, detail) = derive_notification_event(&n, &error).unwrap();
+        assert_eq!(event, NotificationEvent::Error);
+        assert_eq!(detail.as_deref(), Some("rate limited"));
+        derive_notification_event(&n, &status("ses_1", "busy"));
+        assert!(derive_notification_event(&n, &status("ses_1", "idle")).is_none());
+
+        let aborted = serde_json::json!({
+            "type": "session.error",
+            "properties": {"sessionID": "ses_2", "error": {"name": "MessageAbortedError"}},
+        });
+        assert!(derive_notification_event(&n, &aborted).is_none());
+    }
+
+    #[test]
+    fn permission_and_question_carry_details() {
+        let n = notifier();
+        let permission = serde_json::json!({
+            "type": "permission.asked",
+            "properties": {"id": "p1", "sessionID": "ses_1", "permission": "bash", "patterns": ["rm -rf build"]},
+        });
+        let (_, event, detail) = derive_notification_event(&n, &permission).unwrap();
+        assert_eq!(event, NotificationEvent::Permission);
+        assert_eq!(detail.as_deref(), Some("bash: rm -rf build"));
+
+        let question = serde_json::json!({
+            "type": "question.asked",
+            "properties": {"id": "q1", "sessionID": "ses_1", "questions": [{"question": "Which branch?"}]},
+        });
+        let (_, event, detail) = derive_notification_event(&n, &question).unwrap();
+        assert_eq!(event, NotificationEvent::Question);
+        assert_eq!(detail.as_deref(), Some("Which branch?"));
+    }
+
+    #[test]
+    fn settings_map_events_to_sinks_and_require_enabled() {
+        let value = serde_json::json!({
+            "enabled": true,
+            "events": {"idle": ["desktop", "webhook", "desktop", "pager"], "error": ["email"]},
+            "webhook": {"url": "https://hooks.example.com/x"},
+            "smtp": {"host": "smtp.example.com", "port": 2525, "from": "studio@example.com", "to": ["me@example.com"]},
+        });
+        let settings = parse_notification_settings(Some(&value));
+        assert_eq!(
+            settings.sinks_for(NotificationEvent::Idle),
+            &[NotificationSink::Desktop, NotificationSink::Webhook]
+        );
+        assert_eq!(
+            settings.sinks_for(NotificationEvent::Error),
+            &[NotificationSink::Email]
+        );
+        assert!(settings.sinks_for(NotificationEvent::Question).is_empty());
+        let smtp = settings.smtp.as_ref().expect("smtp");
+        assert_eq!(smtp.port, Some(2525));
+        assert_eq!(smtp.security, SmtpSecurity::StartTls);
+
+        let mut disabled = value.clone();
+        disabled["enabled"] = Value::Bool(false);
+        let settings = parse_notification_settings(Some(&disabled));
+        assert!(settings.sinks_for(NotificationEvent::Idle).is_empty());
+    }
+}
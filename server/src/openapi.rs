@@ -0,0 +1,1292 @@
+//! OpenAPI 3.1 document for the studio's own routes, served at
+//! `/api/openapi.json`. `/api/docs` redirects to the web UI's browser for it.
+//!
+//! Schemas are generated from the handler types with `schemars`. Operations
+//! whose handlers take or return free-form JSON are listed without one.
+//! Everything else under `/api` is proxied to OpenCode, which documents those
+//! routes itself at `/doc`.
+
+use std::sync::LazyLock;
+
+use axum::{Json, http::Method, response::Redirect};
+use schemars::{JsonSchema, Schema, SchemaGenerator, generate::SchemaSettings};
+use serde_json::{Map, Value, json};
+
+use crate::api_tokens::{ApiTokenCreateBody, ApiTokenCreated, ApiTokenView};
+use crate::app::{
+    DiagnosticsQuery, HealthResponse, OpenCodeInstanceQuery, OpenCodeInstancesResponse,
+};
+use crate::attachment_cache::{AttachmentCacheClearResponse, AttachmentCacheStats};
+use crate::audit::{AuditListQuery, AuditRecord};
+use crate::budgets::{BudgetAcknowledgeBody, BudgetStatusQuery, BudgetStatusResponse};
+use crate::chat_sidebar::{
+    ChatSidebarCommandsRequest, ChatSidebarFooterQuery, ChatSidebarSessionSearchQuery,
+    ChatSidebarStateQuery, DirectoriesQuery, RecentSessionsQuery, SessionSummariesByIdsQuery,
+};
+use crate::chat_sidebar_devices::{SidebarDevicesClearResponse, SidebarDevicesResponse};
+use crate::config::{OpencodeConfigPatchBody, OpencodeConfigQuery, SettingsMetaResponse};
+use crate::discovery::DiscoveryPeersResponse;
+use crate::error::ErrorBody;
+use crate::forge::{
+    ForgeInfoResponse, ForgePullCreateBody, ForgePullRequest, ForgeQuery, ForgeReviewComment,
+    ForgeTokenBody,
+};
+use crate::fs::{
+    ContentReplaceBody, ContentReplaceResponse, ContentSearchBody, ContentSearchResponse,
+    ContentSearchStreamQuery, DeleteBody, FsHomeResponse, FsPathQuery, ListQuery, ListResponse,
+    MkdirBody, ProjectDirQuery, ReadChunkQuery, ReadChunkResponse, ReadQuery, RenameBody,
+    SearchQuery, SearchResponse, SuccessPathResponse, UploadQuery, UploadResponse, WriteBody,
+    WriteResponse,
+};
+use crate::fs_preview::FsPreviewQuery;
+use crate::fs_trash::{TrashListResponse, TrashPurgeResponse, TrashRestoreBody};
+use crate::fs_tree::{FsTreeQuery, FsTreeResponse};
+use crate::fs_usage::{FsUsageQuery, FsUsageResponse};
+use crate::git::{
+    CheckoutBody, CreateBranchBody, DeleteBranchBody, DeleteRemoteBranchBody, DirectoryQuery,
+    GitAbortBody, GitApplyPatchBody, GitBisectMarkBody, GitBisectRunBody, GitBisectStartBody,
+    GitBlameQuery, GitBranchCompareQuery, GitBranchesQuery, GitCheckoutDetachedBody, GitCleanBody,
+    GitCloneBody, GitCommitActionBody, GitCommitBody, GitCommitDiffQuery,
+    GitCommitFileContentQuery, GitCommitFileDiffQuery, GitCommitFilesQuery, GitCommitMessageBody,
+    GitCompareAnyBody, GitCompareQuery, GitConflictResolveBody, GitConflictsResolveBody,
+    GitContinueBody, GitCreateBranchFromBody, GitCreateGithubRepoAndPushBody, GitDeleteBody,
+    GitDiffQuery, GitFetchBody, GitFileDiffQuery, GitGpgSetSigningKeyBody, GitGraphQuery,
+    GitHunksApplyBody, GitHunksQuery, GitIdentitySetBody, GitIgnoreBody, GitInitBody,
+    GitLfsInstallBody, GitLfsLockBody, GitLfsMigrateBody, GitLfsTrackBody, GitLfsUnlockBody,
+    GitLintBody, GitLockReleaseBody, GitLogQuery, GitMergeBody, GitPullBody, GitPushBody,
+    GitRebaseBody, GitRebaseInteractiveBody, GitRebasePlanQuery, GitRemoteAddBody,
+    GitRemoteBranchesQuery, GitRemoteRemoveBody, GitRemoteRenameBody, GitRemoteSetUrlBody,
+    GitRenameBody, GitRepoLocksResponse, GitReposQuery, GitResetCommitBody, GitRevertBody,
+    GitSignaturesBody, GitSizeAdvisorQuery, GitStageBody, GitStashBranchBody, GitStashPushBody,
+    GitStashRefBody, GitStashShowQuery, GitStatusQuery, GitSubmoduleAddBody, GitSubmoduleBatchBody,
+    GitSubmodulePathBody, GitSubmoduleUpdateBody, GitTagCreateBody, GitTagDeleteBody,
+    GitTagDeleteRemoteBody, GitUndoCommitBody, GitUnstageBody, GitWatchQuery, GitWorktreeAddBody,
+    GitWorktreeMigrateBody, GitWorktreeRemoveBody, RenameBranchBody,
+};
+use crate::global_sse_hub::{GlobalEventSseQuery, GlobalEventWsQuery};
+use crate::jobs::{JobInfo, JobsQuery};
+use crate::log_level::{LogLevelResponse, LogLevelUpdateBody};
+use crate::markdown_render::{MarkdownRenderBody, MarkdownRenderResponse};
+use crate::memory_snippets::{
+    MemoryCreateBody, MemoryExportQuery, MemoryListQuery, MemorySnippet, MemoryUpdateBody,
+};
+use crate::model_catalog::{
+    ModelCatalogQuery, ModelCatalogRefreshBody, ModelCatalogRefreshResponse,
+};
+use crate::notifications::{
+    DesktopNotificationsQuery, DesktopNotificationsResponse, NotificationTestBody,
+    NotificationTestResult,
+};
+use crate::opencode_capabilities::{CapabilitiesQuery, OpenCodeCapabilities};
+use crate::opencode_proxy::{AttentionListQuery, SessionLocateQuery};
+use crate::opencode_session::{SessionIndexStatus, SessionListQuery, SessionMessagesQuery};
+use crate::permission_grants::{PermissionGrantsQuery, PermissionGrantsResponse};
+use crate::plugin_install::{InstalledPlugin, PluginInstallBody, PluginUpdateBody};
+use crate::plugin_runtime::{
+    PluginActionRequest, PluginEventsQuery, PluginListResponse, PluginManifestResponse,
+};
+use crate::prompt_templates::{
+    PromptCreateBody, PromptListQuery, PromptRenderBody, PromptRenderResponse, PromptTemplate,
+    PromptUpdateBody,
+};
+use crate::providers::{
+    EnvCheckRequest, EnvCheckResponse, ProviderDirectoryQuery, ProviderOverridePingBody,
+    ProviderOverridePingResponse, ProviderSourceResponse, ProviderValidateBody,
+    ProviderValidateResponse,
+};
+use crate::quick_captures::{
+    QuickCapture, QuickCaptureConvertBody, QuickCaptureCreateBody, QuickCaptureUpdateBody,
+};
+use crate::secrets::{SecretBody, SecretView, SecretsListResponse};
+use crate::self_update::{SelfUpdateBody, SelfUpdateStatus};
+use crate::session_checkpoints::{
+    CheckpointDiffResponse, CheckpointRestoreBody, CheckpointRestoreResponse, SessionCheckpoint,
+};
+use crate::session_cleanup::{
+    SessionArchiveBody, SessionArchiveResponse, SessionCleanupBody, SessionCleanupResponse,
+};
+use crate::session_export::SessionExportQuery;
+use crate::session_fork::{SessionForkBody, SessionForkQuery, SessionForkResponse};
+use crate::session_import::{SessionImportQuery, SessionImportResponse};
+use crate::session_index_export::SessionIndexExportQuery;
+use crate::session_part_apply::{SessionPartApplyBody, SessionPartApplyQuery};
+use crate::session_summary::{SessionSummarizeBody, SessionSummarizeResponse, SessionSummary};
+use crate::session_tags::{SessionTagEntry, SessionTagsPatchBody, SessionTagsResponse};
+use crate::slash_commands::{SlashCommand, SlashCommandsQuery};
+use crate::tasks::{TaskRunBody, TaskRunDetail, TaskRunInfo, TasksQuery, TasksResponse};
+use crate::terminal::{
+    TerminalCreateBody, TerminalCreateResponse, TerminalInfoResponse, TerminalListQuery,
+    TerminalRenameBody, TerminalResizeBody, TerminalResizeResponse, TerminalSessionSummary,
+    TerminalStreamQuery, TerminalSuccessResponse,
+};
+use crate::terminal_transfer::{TerminalTransferQuery, TerminalUploadResponse};
+use crate::terminal_ui_state::{TerminalUiState, TerminalUiStateEventsQuery};
+use crate::tls_roots::{TlsProbeQuery, TlsProbeResponse};
+use crate::ui_auth::CreateSessionBody;
+use crate::ui_users::{UiUserCreateBody, UiUserUpdateBody, UiUsersResponse};
+use crate::updates::{UpdateCheckQuery, UpdateCheckResponse};
+use crate::usage::{UsageSummaryQuery, UsageSummaryResponse};
+use crate::workspace_preview::{
+    WorkspacePreviewProxyQuery, WorkspacePreviewQuery, WorkspacePreviewResponse,
+    WorkspacePreviewSessionCreateBody, WorkspacePreviewSessionDeleteResponse,
+    WorkspacePreviewSessionDiscoverBody, WorkspacePreviewSessionRenameBody,
+    WorkspacePreviewSessionUpdateBody,
+};
+use crate::workspace_preview_registry::{PreviewSessionRecord, PreviewSessionsResponse};
+
+type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+/// One method on one route. `path` uses the router's syntax, relative to
+/// `/api` unless the operation is mounted at the root.
+struct ApiOperation {
+    method: Method,
+    path: &'static str,
+    operation_id: &'static str,
+    root: bool,
+    query: Option<SchemaFn>,
+    body: Option<SchemaFn>,
+    response: Option<SchemaFn>,
+}
+
+impl ApiOperation {
+    fn new(method: Method, path: &'static str, operation_id: &'static str) -> Self {
+        Self {
+            method,
+            path,
+            operation_id,
+            root: false,
+            query: None,
+            body: None,
+            response: None,
+        }
+    }
+
+    fn get(path: &'static str, operation_id: &'static str) -> Self {
+        Self::new(Method::GET, path, operation_id)
+    }
+
+    fn post(path: &'static str, operation_id: &'static str) -> Self {
+        Self::new(Method::POST, path, operation_id)
+    }
+
+    fn put(path: &'static str, operation_id: &'static str) -> Self {
+        Self::new(Method::PUT, path, operation_id)
+    }
+
+    fn patch(path: &'static str, operation_id: &'static str) -> Self {
+        Self::new(Method::PATCH, path, operation_id)
+    }
+
+    fn delete(path: &'static str, operation_id: &'static str) -> Self {
+        Self::new(Method::DELETE, path, operation_id)
+    }
+
+    /// Mounted outside `/api`, so the UI auth layer does not apply.
+    fn root(mut self) -> Self {
+        self.root = true;
+        self
+    }
+
+    /// Query string extracted into `T`; each field becomes a parameter.
+    fn query<T: JsonSchema>(mut self) -> Self {
+        self.query = Some(T::json_schema);
+        self
+    }
+
+    fn body<T: JsonSchema>(mut self) -> Self {
+        self.body = Some(SchemaGenerator::subschema_for::<T>);
+        self
+    }
+
+    fn response<T: JsonSchema>(mut self) -> Self {
+        self.response = Some(SchemaGenerator::subschema_for::<T>);
+        self
+    }
+
+    fn openapi_path(&self) -> String {
+        // `{*rest}` wildcards become plain template parameters.
+        let path = self.path.replace("{*", "{");
+        if self.root {
+            path
+        } else {
+            format!("/api{path}")
+        }
+    }
+
+    fn render(&self, generator: &mut SchemaGenerator) -> Value {
+        let mut parameters: Vec<Value> = path_params(self.path)
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                })
+            })
+            .collect();
+        if let Some(query) = self.query {
+            parameters.extend(query_params(&query(generator)));
+        }
+
+        let tag = self
+            .path
+            .trim_start_matches('/')
+            .split('/')
+            .next()
+            .unwrap_or_default();
+        let ok = match self.response {
+            Some(response) => json!({
+                "description": "OK",
+                "content": { "application/json": { "schema": response(generator) } },
+            }),
+            None => json!({ "description": "OK" }),
+        };
+        let mut operation = json!({
+            "operationId": self.operation_id,
+            "tags": [tag],
+            "responses": {
+                "200": ok,
+                "default": { "$ref": "#/components/responses/Error" },
+            },
+        });
+        if !parameters.is_empty() {
+            operation["parameters"] = Value::Array(parameters);
+        }
+        if let Some(body) = self.body {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": { "application/json": { "schema": body(generator) } },
+            });
+        }
+        if self.root {
+            operation["security"] = json!([]);
+        }
+        operation
+    }
+}
+
+fn path_params(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter_map(|segment| {
+        segment
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .map(|s| s.trim_start_matches('*'))
+    })
+}
+
+fn query_params(schema: &Schema) -> Vec<Value> {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    properties
+        .iter()
+        .map(|(name, property)| {
+            let mut param = json!({
+                "name": name,
+                "in": "query",
+                "required": required.contains(&name.as_str()),
+                "schema": property,
+            });
+            if let Some(description) = property.get("description") {
+                param["description"] = description.clone();
+            }
+            param
+        })
+        .collect()
+}
+
+/// Every route the studio serves itself. The plugin route passthrough, the
+/// preview proxies and the OpenCode fallback accept any method and body, so
+/// they are left out.
+fn operations() -> Vec<ApiOperation> {
+    vec![
+        ApiOperation::get("/health", "health")
+            .root()
+            .response::<HealthResponse>(),
+        ApiOperation::get("/auth/session", "auth_session_status").root(),
+        ApiOperation::post("/auth/session", "auth_session_create")
+            .root()
+            .body::<CreateSessionBody>(),
+        ApiOperation::delete("/auth/session", "auth_session_delete").root(),
+        ApiOperation::get("/openapi.json", "openapi_json"),
+        ApiOperation::get("/docs", "openapi_docs"),
+        ApiOperation::get("/provider/{provider_id}/source", "provider_source_get")
+            .query::<ProviderDirectoryQuery>()
+            .response::<ProviderSourceResponse>(),
+        ApiOperation::post(
+            "/provider/{provider_id}/override/ping",
+            "provider_override_ping_post",
+        )
+        .body::<ProviderOverridePingBody>()
+        .response::<ProviderOverridePingResponse>(),
+        ApiOperation::post("/provider/{provider_id}/validate", "provider_validate_post")
+            .query::<ProviderDirectoryQuery>()
+            .body::<ProviderValidateBody>()
+            .response::<ProviderValidateResponse>(),
+        ApiOperation::post("/provider/models/refresh", "model_catalog_refresh_post")
+            .body::<ModelCatalogRefreshBody>()
+            .response::<ModelCatalogRefreshResponse>(),
+        ApiOperation::post("/provider/env/check", "env_check_post")
+            .body::<EnvCheckRequest>()
+            .response::<EnvCheckResponse>(),
+        ApiOperation::get("/discovery/peers", "discovery_peers")
+            .response::<DiscoveryPeersResponse>(),
+        ApiOperation::get("/config/settings", "config_settings_get"),
+        ApiOperation::put("/config/settings", "config_settings_put"),
+        ApiOperation::get("/config/settings/meta", "config_settings_meta_get")
+            .response::<SettingsMetaResponse>(),
+        ApiOperation::get("/config/settings/events", "config_settings_events"),
+        ApiOperation::get("/config/providers", "config_providers_get").query::<ModelCatalogQuery>(),
+        ApiOperation::get("/config/opencode", "config_opencode_get").query::<OpencodeConfigQuery>(),
+        ApiOperation::put("/config/opencode", "config_opencode_put").query::<OpencodeConfigQuery>(),
+        ApiOperation::patch("/config/opencode", "config_opencode_patch")
+            .query::<OpencodeConfigQuery>()
+            .body::<OpencodeConfigPatchBody>(),
+        ApiOperation::get("/plugins", "plugins_list_get").response::<PluginListResponse>(),
+        ApiOperation::get("/plugins/{plugin_id}/manifest", "plugin_manifest_get")
+            .response::<PluginManifestResponse>(),
+        ApiOperation::post("/plugins/{plugin_id}/action", "plugin_action_post")
+            .body::<PluginActionRequest>(),
+        ApiOperation::get("/plugins/{plugin_id}/events", "plugin_events_get")
+            .query::<PluginEventsQuery>(),
+        ApiOperation::get(
+            "/plugins/{plugin_id}/assets/{*asset_path}",
+            "plugin_asset_get",
+        ),
+        ApiOperation::post("/config/reload", "config_reload_post"),
+        ApiOperation::get("/admin/self-update", "self_update_get").response::<SelfUpdateStatus>(),
+        ApiOperation::post("/admin/self-update", "self_update_post").body::<SelfUpdateBody>(),
+        ApiOperation::get("/admin/diagnostics/tls", "tls_probe_get")
+            .query::<TlsProbeQuery>()
+            .response::<TlsProbeResponse>(),
+        ApiOperation::get("/admin/plugins", "admin_plugins_get"),
+        ApiOperation::get("/admin/plugins/installed", "installed_plugins_get")
+            .response::<Vec<InstalledPlugin>>(),
+        ApiOperation::post("/admin/plugins/install", "plugin_install_post")
+            .body::<PluginInstallBody>()
+            .response::<InstalledPlugin>(),
+        ApiOperation::delete(
+            "/admin/plugins/installed/{plugin_id}",
+            "plugin_uninstall_delete",
+        ),
+        ApiOperation::post(
+            "/admin/plugins/installed/{plugin_id}/update",
+            "plugin_update_post",
+        )
+        .body::<PluginUpdateBody>()
+        .response::<InstalledPlugin>(),
+        ApiOperation::get("/admin/log-level", "log_level_get").response::<LogLevelResponse>(),
+        ApiOperation::put("/admin/log-level", "log_level_put")
+            .body::<LogLevelUpdateBody>()
+            .response::<LogLevelResponse>(),
+        ApiOperation::delete("/admin/log-level", "log_level_delete").response::<LogLevelResponse>(),
+        ApiOperation::get("/ui/terminal/state", "terminal_ui_state_get"),
+        ApiOperation::put("/ui/terminal/state", "terminal_ui_state_put").body::<TerminalUiState>(),
+        ApiOperation::get("/ui/terminal/state/events", "terminal_ui_state_events")
+            .query::<TerminalUiStateEventsQuery>(),
+        ApiOperation::get("/memory", "memory_list")
+            .query::<MemoryListQuery>()
+            .response::<Vec<MemorySnippet>>(),
+        ApiOperation::post("/memory", "memory_create")
+            .body::<MemoryCreateBody>()
+            .response::<MemorySnippet>(),
+        ApiOperation::get("/memory/export", "memory_export").query::<MemoryExportQuery>(),
+        ApiOperation::get("/memory/{id}", "memory_get").response::<MemorySnippet>(),
+        ApiOperation::put("/memory/{id}", "memory_update")
+            .body::<MemoryUpdateBody>()
+            .response::<MemorySnippet>(),
+        ApiOperation::delete("/memory/{id}", "memory_delete"),
+        ApiOperation::get("/quick-captures", "quick_capture_list").response::<Vec<QuickCapture>>(),
+        ApiOperation::post("/quick-captures", "quick_capture_create")
+            .body::<QuickCaptureCreateBody>()
+            .response::<QuickCapture>(),
+        ApiOperation::put("/quick-captures/{id}", "quick_capture_update")
+            .body::<QuickCaptureUpdateBody>()
+            .response::<QuickCapture>(),
+        ApiOperation::delete("/quick-captures/{id}", "quick_capture_delete"),
+        ApiOperation::post("/quick-captures/{id}/convert", "quick_capture_convert")
+            .body::<QuickCaptureConvertBody>(),
+        ApiOperation::get("/prompts", "prompt_list")
+            .query::<PromptListQuery>()
+            .response::<Vec<PromptTemplate>>(),
+        ApiOperation::post("/prompts", "prompt_create")
+            .body::<PromptCreateBody>()
+            .response::<PromptTemplate>(),
+        ApiOperation::get("/prompts/{id}", "prompt_get").response::<PromptTemplate>(),
+        ApiOperation::put("/prompts/{id}", "prompt_update")
+            .body::<PromptUpdateBody>()
+            .response::<PromptTemplate>(),
+        ApiOperation::delete("/prompts/{id}", "prompt_delete"),
+        ApiOperation::post("/prompts/{id}/render", "prompt_render")
+            .body::<PromptRenderBody>()
+            .response::<PromptRenderResponse>(),
+        ApiOperation::get("/slash-commands", "slash_commands_list")
+            .query::<SlashCommandsQuery>()
+            .response::<Vec<SlashCommand>>(),
+        ApiOperation::get("/event", "proxy_opencode_sse_event"),
+        ApiOperation::get("/global/event", "global_event_sse").query::<GlobalEventSseQuery>(),
+        ApiOperation::get("/ws/events", "proxy_opencode_ws_events"),
+        ApiOperation::get("/global/ws", "global_event_ws").query::<GlobalEventWsQuery>(),
+        ApiOperation::get("/chat-sidebar/state", "chat_sidebar_state")
+            .query::<ChatSidebarStateQuery>(),
+        ApiOperation::post("/chat-sidebar/commands", "chat_sidebar_commands_post")
+            .body::<ChatSidebarCommandsRequest>(),
+        ApiOperation::get("/chat-sidebar/devices", "chat_sidebar_devices_list")
+            .response::<SidebarDevicesResponse>(),
+        ApiOperation::delete("/chat-sidebar/devices", "chat_sidebar_devices_clear")
+            .response::<SidebarDevicesClearResponse>(),
+        ApiOperation::delete(
+            "/chat-sidebar/devices/{device_id}",
+            "chat_sidebar_device_delete",
+        )
+        .response::<SidebarDevicesClearResponse>(),
+        ApiOperation::get("/chat-sidebar/search", "chat_sidebar_session_search")
+            .query::<ChatSidebarSessionSearchQuery>(),
+        ApiOperation::get("/chat-sidebar/footer", "chat_sidebar_footer_get")
+            .query::<ChatSidebarFooterQuery>(),
+        ApiOperation::get("/sessions/summaries", "sessions_summaries_get")
+            .query::<SessionSummariesByIdsQuery>(),
+        ApiOperation::get("/sessions/recent", "sessions_recent_get").query::<RecentSessionsQuery>(),
+        ApiOperation::get("/directories", "directories_get").query::<DirectoriesQuery>(),
+        ApiOperation::get(
+            "/directories/{directory_id}/sessions",
+            "directory_sessions_by_id_get",
+        ),
+        ApiOperation::get("/workspace/preview", "workspace_preview_get")
+            .query::<WorkspacePreviewQuery>()
+            .response::<WorkspacePreviewResponse>(),
+        ApiOperation::get("/workspace/preview-url", "workspace_preview_url_get")
+            .query::<WorkspacePreviewQuery>()
+            .response::<WorkspacePreviewResponse>(),
+        ApiOperation::get("/workspace/preview/proxy", "workspace_preview_proxy_get")
+            .query::<WorkspacePreviewProxyQuery>(),
+        ApiOperation::get(
+            "/workspace/preview/sessions",
+            "workspace_preview_sessions_get",
+        )
+        .response::<PreviewSessionsResponse>(),
+        ApiOperation::post(
+            "/workspace/preview/sessions",
+            "workspace_preview_sessions_post",
+        )
+        .body::<WorkspacePreviewSessionCreateBody>()
+        .response::<PreviewSessionRecord>(),
+        ApiOperation::get(
+            "/workspace/preview/sessions/{id}",
+            "workspace_preview_sessions_by_id_get",
+        )
+        .response::<PreviewSessionRecord>(),
+        ApiOperation::delete(
+            "/workspace/preview/sessions/{id}",
+            "workspace_preview_sessions_delete",
+        )
+        .response::<WorkspacePreviewSessionDeleteResponse>(),
+        ApiOperation::put(
+            "/workspace/preview/sessions/{id}",
+            "workspace_preview_sessions_put",
+        )
+        .body::<WorkspacePreviewSessionUpdateBody>()
+        .response::<PreviewSessionRecord>(),
+        ApiOperation::post(
+            "/workspace/preview/sessions/{id}/rename",
+            "workspace_preview_sessions_rename_post",
+        )
+        .body::<WorkspacePreviewSessionRenameBody>()
+        .response::<PreviewSessionRecord>(),
+        ApiOperation::post(
+            "/workspace/preview/sessions/{id}/start",
+            "workspace_preview_sessions_start_post",
+        )
+        .response::<PreviewSessionRecord>(),
+        ApiOperation::post(
+            "/workspace/preview/sessions/{id}/stop",
+            "workspace_preview_sessions_stop_post",
+        )
+        .response::<PreviewSessionRecord>(),
+        ApiOperation::post(
+            "/workspace/preview/sessions/discover",
+            "workspace_preview_sessions_discover_post",
+        )
+        .body::<WorkspacePreviewSessionDiscoverBody>()
+        .response::<PreviewSessionRecord>(),
+        ApiOperation::get("/session", "session_list").query::<SessionListQuery>(),
+        ApiOperation::post("/session", "session_post"),
+        ApiOperation::get("/session/status", "session_status_get").query::<AttentionListQuery>(),
+        ApiOperation::get("/session/tags", "session_tags_list").response::<SessionTagsResponse>(),
+        ApiOperation::post("/session/archive", "session_archive_post")
+            .body::<SessionArchiveBody>()
+            .response::<SessionArchiveResponse>(),
+        ApiOperation::post("/session/cleanup", "session_cleanup_post")
+            .body::<SessionCleanupBody>()
+            .response::<SessionCleanupResponse>(),
+        ApiOperation::post("/session/import", "session_import_post")
+            .query::<SessionImportQuery>()
+            .response::<SessionImportResponse>(),
+        ApiOperation::get("/session/{session_id}/export", "session_export_get")
+            .query::<SessionExportQuery>(),
+        ApiOperation::get("/session/{session_id}/tags", "session_tags_get")
+            .response::<SessionTagEntry>(),
+        ApiOperation::patch("/session/{session_id}/tags", "session_tags_patch")
+            .body::<SessionTagsPatchBody>()
+            .response::<SessionTagEntry>(),
+        ApiOperation::get(
+            "/session/{session_id}/checkpoints",
+            "session_checkpoints_list",
+        )
+        .response::<Vec<SessionCheckpoint>>(),
+        ApiOperation::get(
+            "/session/{session_id}/checkpoints/{message_id}/diff",
+            "session_checkpoint_diff",
+        )
+        .response::<CheckpointDiffResponse>(),
+        ApiOperation::post(
+            "/session/{session_id}/checkpoints/{message_id}/restore",
+            "session_checkpoint_restore",
+        )
+        .body::<CheckpointRestoreBody>()
+        .response::<CheckpointRestoreResponse>(),
+        ApiOperation::post("/session/{session_id}/fork", "session_fork_post")
+            .query::<SessionForkQuery>()
+            .body::<SessionForkBody>()
+            .response::<SessionForkResponse>(),
+        ApiOperation::get("/session/{session_id}/summary", "session_summary_get")
+            .response::<SessionSummary>(),
+        ApiOperation::post("/session/{session_id}/summary", "session_summary_post")
+            .body::<SessionSummarizeBody>()
+            .response::<SessionSummarizeResponse>(),
+        ApiOperation::get("/session/{session_id}/message", "session_message_get")
+            .query::<SessionMessagesQuery>(),
+        ApiOperation::post("/session/{session_id}/message", "session_message_post"),
+        ApiOperation::get(
+            "/session/{session_id}/message/{message_id}/part/{part_id}",
+            "session_message_part_get",
+        ),
+        ApiOperation::get(
+            "/session/{session_id}/message/{message_id}/part/{part_id}/detail",
+            "session_message_part_detail_get",
+        ),
+        ApiOperation::post(
+            "/session/{session_id}/message/{message_id}/part/{part_id}/apply",
+            "session_message_part_apply_post",
+        )
+        .query::<SessionPartApplyQuery>()
+        .body::<SessionPartApplyBody>(),
+        ApiOperation::get("/lsp", "lsp_list").query::<AttentionListQuery>(),
+        ApiOperation::get("/mcp", "mcp_status"),
+        ApiOperation::get("/permission", "permission_list").query::<AttentionListQuery>(),
+        ApiOperation::get("/permission/grants", "permission_grants_list")
+            .query::<PermissionGrantsQuery>()
+            .response::<PermissionGrantsResponse>(),
+        ApiOperation::delete("/permission/grants", "permission_grants_clear")
+            .query::<PermissionGrantsQuery>(),
+        ApiOperation::delete("/permission/grants/{id}", "permission_grant_delete"),
+        ApiOperation::get("/question", "question_list").query::<AttentionListQuery>(),
+        ApiOperation::get("/opencode/capabilities", "opencode_capabilities_get")
+            .query::<CapabilitiesQuery>()
+            .response::<OpenCodeCapabilities>(),
+        ApiOperation::get("/usage/summary", "usage_summary")
+            .query::<UsageSummaryQuery>()
+            .response::<UsageSummaryResponse>(),
+        ApiOperation::get("/usage/budgets", "budget_status_get")
+            .query::<BudgetStatusQuery>()
+            .response::<BudgetStatusResponse>(),
+        ApiOperation::post("/usage/budgets/acknowledge", "budget_acknowledge_post")
+            .body::<BudgetAcknowledgeBody>()
+            .response::<BudgetStatusResponse>(),
+        ApiOperation::get("/audit", "audit_list")
+            .query::<AuditListQuery>()
+            .response::<Vec<AuditRecord>>(),
+        ApiOperation::get("/session-activity", "session_activity"),
+        ApiOperation::get("/opencode-studio/busy", "opencode_studio_busy"),
+        ApiOperation::get(
+            "/opencode-studio/opencode-instances",
+            "opencode_instances_list",
+        )
+        .response::<OpenCodeInstancesResponse>(),
+        ApiOperation::delete(
+            "/opencode-studio/opencode-instances",
+            "opencode_instance_stop",
+        )
+        .query::<OpenCodeInstanceQuery>()
+        .response::<OpenCodeInstancesResponse>(),
+        ApiOperation::post(
+            "/opencode-studio/opencode-instances/restart",
+            "opencode_instance_restart",
+        )
+        .query::<OpenCodeInstanceQuery>()
+        .response::<OpenCodeInstancesResponse>(),
+        ApiOperation::get("/sessions/index/status", "session_index_status")
+            .response::<SessionIndexStatus>(),
+        ApiOperation::get("/opencode-studio/session-index", "session_index_export")
+            .query::<SessionIndexExportQuery>(),
+        ApiOperation::get("/opencode-studio/update-check", "update_check")
+            .query::<UpdateCheckQuery>()
+            .response::<UpdateCheckResponse>(),
+        ApiOperation::get(
+            "/opencode-studio/session-locate",
+            "opencode_studio_session_locate",
+        )
+        .query::<SessionLocateQuery>(),
+        ApiOperation::get(
+            "/opencode-studio/diagnostics",
+            "opencode_studio_diagnostics",
+        )
+        .query::<DiagnosticsQuery>(),
+        ApiOperation::post("/markdown/render", "markdown_render_post")
+            .body::<MarkdownRenderBody>()
+            .response::<MarkdownRenderResponse>(),
+        ApiOperation::get("/notifications/desktop", "notifications_desktop_get")
+            .query::<DesktopNotificationsQuery>()
+            .response::<DesktopNotificationsResponse>(),
+        ApiOperation::post("/notifications/test", "notifications_test_post")
+            .body::<NotificationTestBody>()
+            .response::<Vec<NotificationTestResult>>(),
+        ApiOperation::get("/auth/tokens", "api_tokens_list").response::<Vec<ApiTokenView>>(),
+        ApiOperation::post("/auth/tokens", "api_tokens_create")
+            .body::<ApiTokenCreateBody>()
+            .response::<ApiTokenCreated>(),
+        ApiOperation::delete("/auth/tokens/{id}", "api_tokens_delete"),
+        ApiOperation::get("/auth/users", "ui_users_list").response::<UiUsersResponse>(),
+        ApiOperation::post("/auth/users", "ui_users_create")
+            .body::<UiUserCreateBody>()
+            .response::<UiUsersResponse>(),
+        ApiOperation::put("/auth/users/{username}", "ui_users_update")
+            .body::<UiUserUpdateBody>()
+            .response::<UiUsersResponse>(),
+        ApiOperation::delete("/auth/users/{username}", "ui_users_delete")
+            .response::<UiUsersResponse>(),
+        ApiOperation::get("/attachments/cache/stats", "attachment_cache_stats")
+            .response::<AttachmentCacheStats>(),
+        ApiOperation::post("/attachments/cache/clear", "attachment_cache_clear")
+            .response::<AttachmentCacheClearResponse>(),
+        ApiOperation::get("/fs/home", "fs_home").response::<FsHomeResponse>(),
+        ApiOperation::post("/fs/mkdir", "fs_mkdir")
+            .query::<ProjectDirQuery>()
+            .body::<MkdirBody>()
+            .response::<SuccessPathResponse>(),
+        ApiOperation::get("/fs/read", "fs_read").query::<ReadQuery>(),
+        ApiOperation::get("/fs/read-chunk", "fs_read_chunk")
+            .query::<ReadChunkQuery>()
+            .response::<ReadChunkResponse>(),
+        ApiOperation::get("/fs/raw", "fs_raw").query::<ReadQuery>(),
+        ApiOperation::get("/fs/preview", "fs_preview").query::<FsPreviewQuery>(),
+        ApiOperation::get("/fs/download", "fs_download").query::<FsPathQuery>(),
+        ApiOperation::post("/fs/write", "fs_write")
+            .query::<ProjectDirQuery>()
+            .body::<WriteBody>()
+            .response::<WriteResponse>(),
+        ApiOperation::post("/fs/upload", "fs_upload")
+            .query::<UploadQuery>()
+            .response::<UploadResponse>(),
+        ApiOperation::post("/fs/delete", "fs_delete")
+            .query::<ProjectDirQuery>()
+            .body::<DeleteBody>()
+            .response::<SuccessPathResponse>(),
+        ApiOperation::post("/fs/rename", "fs_rename")
+            .query::<ProjectDirQuery>()
+            .body::<RenameBody>()
+            .response::<SuccessPathResponse>(),
+        ApiOperation::get("/fs/list", "fs_list")
+            .query::<ListQuery>()
+            .response::<ListResponse>(),
+        ApiOperation::get("/fs/tree", "fs_tree")
+            .query::<FsTreeQuery>()
+            .response::<FsTreeResponse>(),
+        ApiOperation::get("/fs/trash", "trash_list")
+            .query::<ProjectDirQuery>()
+            .response::<TrashListResponse>(),
+        ApiOperation::delete("/fs/trash", "trash_purge_delete")
+            .query::<ProjectDirQuery>()
+            .response::<TrashPurgeResponse>(),
+        ApiOperation::delete("/fs/trash/{id}", "trash_entry_delete")
+            .query::<ProjectDirQuery>()
+            .response::<TrashPurgeResponse>(),
+        ApiOperation::post("/fs/trash/{id}/restore", "trash_restore_post")
+            .query::<ProjectDirQuery>()
+            .body::<TrashRestoreBody>()
+            .response::<SuccessPathResponse>(),
+        ApiOperation::get("/fs/usage", "fs_usage")
+            .query::<FsUsageQuery>()
+            .response::<FsUsageResponse>(),
+        ApiOperation::get("/fs/watch", "fs_watch_sse").query::<ProjectDirQuery>(),
+        ApiOperation::get("/fs/search", "fs_search")
+            .query::<SearchQuery>()
+            .response::<SearchResponse>(),
+        ApiOperation::post("/fs/search-content", "fs_content_search")
+            .query::<ProjectDirQuery>()
+            .body::<ContentSearchBody>()
+            .response::<ContentSearchResponse>(),
+        ApiOperation::get("/fs/search-content/stream", "fs_content_search_stream")
+            .query::<ContentSearchStreamQuery>(),
+        ApiOperation::post("/fs/replace-content", "fs_content_replace")
+            .query::<ProjectDirQuery>()
+            .body::<ContentReplaceBody>()
+            .response::<ContentReplaceResponse>(),
+        ApiOperation::get("/tool-output/{archive_id}", "tool_output_archive_get"),
+        ApiOperation::get("/jobs", "jobs_get")
+            .query::<JobsQuery>()
+            .response::<Vec<JobInfo>>(),
+        ApiOperation::get("/jobs/{job_id}", "job_get").response::<JobInfo>(),
+        ApiOperation::get("/jobs/{job_id}/events", "job_events_get"),
+        ApiOperation::get("/jobs/{job_id}/artifact", "job_artifact_get"),
+        ApiOperation::post("/jobs/{job_id}/cancel", "job_cancel_post").response::<JobInfo>(),
+        ApiOperation::get("/tasks", "tasks_get")
+            .query::<TasksQuery>()
+            .response::<TasksResponse>(),
+        ApiOperation::post("/tasks/run", "tasks_run_post")
+            .body::<TaskRunBody>()
+            .response::<TaskRunInfo>(),
+        ApiOperation::get("/tasks/runs", "task_runs_get")
+            .query::<TasksQuery>()
+            .response::<Vec<TaskRunInfo>>(),
+        ApiOperation::get("/tasks/runs/{run_id}", "task_run_get").response::<TaskRunDetail>(),
+        ApiOperation::get("/tasks/runs/{run_id}/events", "task_run_events_get"),
+        ApiOperation::post("/tasks/runs/{run_id}/cancel", "task_run_cancel_post")
+            .response::<TaskRunInfo>(),
+        ApiOperation::post("/terminal/create", "terminal_create")
+            .body::<TerminalCreateBody>()
+            .response::<TerminalCreateResponse>(),
+        ApiOperation::get("/terminal/sessions", "terminal_list")
+            .query::<TerminalListQuery>()
+            .response::<Vec<TerminalSessionSummary>>(),
+        ApiOperation::post("/terminal/{session_id}/rename", "terminal_rename")
+            .body::<TerminalRenameBody>()
+            .response::<TerminalSuccessResponse>(),
+        ApiOperation::get("/terminal/{session_id}/stream", "terminal_stream")
+            .query::<TerminalStreamQuery>(),
+        ApiOperation::post("/terminal/{session_id}/input", "terminal_input")
+            .response::<TerminalSuccessResponse>(),
+        ApiOperation::post("/terminal/{session_id}/resize", "terminal_resize")
+            .body::<TerminalResizeBody>()
+            .response::<TerminalResizeResponse>(),
+        ApiOperation::get("/terminal/{session_id}", "terminal_get")
+            .response::<TerminalInfoResponse>(),
+        ApiOperation::delete("/terminal/{session_id}", "terminal_delete")
+            .response::<TerminalSuccessResponse>(),
+        ApiOperation::post("/terminal/{session_id}/start", "terminal_start")
+            .response::<TerminalInfoResponse>(),
+        ApiOperation::post("/terminal/{session_id}/stop", "terminal_stop")
+            .response::<TerminalSuccessResponse>(),
+        ApiOperation::post("/terminal/{session_id}/restart", "terminal_restart")
+            .body::<TerminalCreateBody>()
+            .response::<TerminalCreateResponse>(),
+        ApiOperation::post("/terminal/{session_id}/upload", "terminal_upload")
+            .query::<TerminalTransferQuery>()
+            .response::<TerminalUploadResponse>(),
+        ApiOperation::get("/terminal/{session_id}/download", "terminal_download")
+            .query::<TerminalTransferQuery>(),
+        ApiOperation::get("/secrets", "secrets_list").response::<SecretsListResponse>(),
+        ApiOperation::post("/secrets", "secrets_create")
+            .body::<SecretBody>()
+            .response::<SecretView>(),
+        ApiOperation::put("/secrets/{id}", "secrets_update")
+            .body::<SecretBody>()
+            .response::<SecretView>(),
+        ApiOperation::delete("/secrets/{id}", "secrets_delete"),
+        ApiOperation::get("/forge", "forge_info")
+            .query::<ForgeQuery>()
+            .response::<ForgeInfoResponse>(),
+        ApiOperation::put("/forge/token", "forge_token_put")
+            .query::<ForgeQuery>()
+            .body::<ForgeTokenBody>(),
+        ApiOperation::delete("/forge/token", "forge_token_delete").query::<ForgeQuery>(),
+        ApiOperation::get("/forge/pulls", "forge_pulls_list")
+            .query::<ForgeQuery>()
+            .response::<Vec<ForgePullRequest>>(),
+        ApiOperation::post("/forge/pulls", "forge_pulls_create")
+            .query::<ForgeQuery>()
+            .body::<ForgePullCreateBody>()
+            .response::<ForgePullRequest>(),
+        ApiOperation::get("/forge/pulls/{number}/comments", "forge_pull_comments")
+            .query::<ForgeQuery>()
+            .response::<Vec<ForgeReviewComment>>(),
+        ApiOperation::get("/git/check", "git_check").query::<DirectoryQuery>(),
+        ApiOperation::get("/git/repos", "git_repos").query::<GitReposQuery>(),
+        ApiOperation::post("/git/safe-directory", "git_safe_directory").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/init", "git_init")
+            .query::<DirectoryQuery>()
+            .body::<GitInitBody>(),
+        ApiOperation::post("/git/clone", "git_clone")
+            .query::<DirectoryQuery>()
+            .body::<GitCloneBody>(),
+        ApiOperation::post(
+            "/git/gpg/enable-preset-passphrase",
+            "git_gpg_enable_preset_passphrase",
+        )
+        .query::<DirectoryQuery>(),
+        ApiOperation::post("/git/gpg/disable-signing", "git_gpg_disable_signing")
+            .query::<DirectoryQuery>(),
+        ApiOperation::post("/git/gpg/set-signing-key", "git_gpg_set_signing_key")
+            .query::<DirectoryQuery>()
+            .body::<GitGpgSetSigningKeyBody>(),
+        ApiOperation::post("/git/signatures", "git_signatures")
+            .query::<DirectoryQuery>()
+            .body::<GitSignaturesBody>(),
+        ApiOperation::get("/git/remote-info", "git_remote_info").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/remotes", "git_remote_add")
+            .query::<DirectoryQuery>()
+            .body::<GitRemoteAddBody>(),
+        ApiOperation::put("/git/remotes", "git_remote_rename")
+            .query::<DirectoryQuery>()
+            .body::<GitRemoteRenameBody>(),
+        ApiOperation::delete("/git/remotes", "git_remote_remove")
+            .query::<DirectoryQuery>()
+            .body::<GitRemoteRemoveBody>(),
+        ApiOperation::post("/git/remotes/set-url", "git_remote_set_url")
+            .query::<DirectoryQuery>()
+            .body::<GitRemoteSetUrlBody>(),
+        ApiOperation::get("/git/signing-info", "git_signing_info").query::<DirectoryQuery>(),
+        ApiOperation::get("/git/state", "git_state").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/merge/abort", "git_merge_abort")
+            .query::<DirectoryQuery>()
+            .body::<GitAbortBody>(),
+        ApiOperation::post("/git/rebase/abort", "git_rebase_abort")
+            .query::<DirectoryQuery>()
+            .body::<GitAbortBody>(),
+        ApiOperation::get("/git/stash", "git_stash_list").query::<DirectoryQuery>(),
+        ApiOperation::get("/git/stash/show", "git_stash_show").query::<GitStashShowQuery>(),
+        ApiOperation::post("/git/stash/push", "git_stash_push")
+            .query::<DirectoryQuery>()
+            .body::<GitStashPushBody>(),
+        ApiOperation::post("/git/stash/apply", "git_stash_apply")
+            .query::<DirectoryQuery>()
+            .body::<GitStashRefBody>(),
+        ApiOperation::post("/git/stash/pop", "git_stash_pop")
+            .query::<DirectoryQuery>()
+            .body::<GitStashRefBody>(),
+        ApiOperation::post("/git/stash/drop", "git_stash_drop")
+            .query::<DirectoryQuery>()
+            .body::<GitStashRefBody>(),
+        ApiOperation::post("/git/stash/drop-all", "git_stash_drop_all").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/stash/branch", "git_stash_branch")
+            .query::<DirectoryQuery>()
+            .body::<GitStashBranchBody>(),
+        ApiOperation::post("/git/rebase/continue", "git_rebase_continue")
+            .query::<DirectoryQuery>()
+            .body::<GitContinueBody>(),
+        ApiOperation::post("/git/rebase/skip", "git_rebase_skip")
+            .query::<DirectoryQuery>()
+            .body::<GitContinueBody>(),
+        ApiOperation::post("/git/cherry-pick/abort", "git_cherry_pick_abort")
+            .query::<DirectoryQuery>()
+            .body::<GitAbortBody>(),
+        ApiOperation::post("/git/cherry-pick/continue", "git_cherry_pick_continue")
+            .query::<DirectoryQuery>()
+            .body::<GitContinueBody>(),
+        ApiOperation::post("/git/cherry-pick/skip", "git_cherry_pick_skip")
+            .query::<DirectoryQuery>()
+            .body::<GitContinueBody>(),
+        ApiOperation::post("/git/cherry-pick", "git_cherry_pick")
+            .query::<DirectoryQuery>()
+            .body::<GitCommitActionBody>(),
+        ApiOperation::post("/git/revert/abort", "git_revert_abort")
+            .query::<DirectoryQuery>()
+            .body::<GitAbortBody>(),
+        ApiOperation::post("/git/revert/continue", "git_revert_continue")
+            .query::<DirectoryQuery>()
+            .body::<GitContinueBody>(),
+        ApiOperation::post("/git/revert/skip", "git_revert_skip")
+            .query::<DirectoryQuery>()
+            .body::<GitContinueBody>(),
+        ApiOperation::post("/git/revert-commit", "git_revert_commit")
+            .query::<DirectoryQuery>()
+            .body::<GitCommitActionBody>(),
+        ApiOperation::post("/git/merge", "git_merge")
+            .query::<DirectoryQuery>()
+            .body::<GitMergeBody>(),
+        ApiOperation::post("/git/rebase", "git_rebase")
+            .query::<DirectoryQuery>()
+            .body::<GitRebaseBody>(),
+        ApiOperation::post("/git/rebase/interactive", "git_rebase_interactive")
+            .query::<DirectoryQuery>()
+            .body::<GitRebaseInteractiveBody>(),
+        ApiOperation::get("/git/rebase/plan", "git_rebase_plan").query::<GitRebasePlanQuery>(),
+        ApiOperation::get("/git/rebase/status", "git_rebase_status").query::<DirectoryQuery>(),
+        ApiOperation::get("/git/remote-branches", "git_remote_branches_list")
+            .query::<GitRemoteBranchesQuery>(),
+        ApiOperation::get("/git/status", "git_status").query::<GitStatusQuery>(),
+        ApiOperation::get("/git/watch", "git_watch").query::<GitWatchQuery>(),
+        ApiOperation::get("/git/diff", "git_diff").query::<GitDiffQuery>(),
+        ApiOperation::get("/git/file-diff", "git_file_diff").query::<GitFileDiffQuery>(),
+        ApiOperation::get("/git/compare", "git_compare").query::<GitCompareQuery>(),
+        ApiOperation::post("/git/compare-any", "git_compare_any")
+            .query::<DirectoryQuery>()
+            .body::<GitCompareAnyBody>(),
+        ApiOperation::post("/git/patch", "git_apply_patch")
+            .query::<DirectoryQuery>()
+            .body::<GitApplyPatchBody>(),
+        ApiOperation::get("/git/hunks", "git_hunks").query::<GitHunksQuery>(),
+        ApiOperation::post("/git/hunks/stage", "git_stage_hunks")
+            .query::<DirectoryQuery>()
+            .body::<GitHunksApplyBody>(),
+        ApiOperation::post("/git/hunks/unstage", "git_unstage_hunks")
+            .query::<DirectoryQuery>()
+            .body::<GitHunksApplyBody>(),
+        ApiOperation::get("/git/lfs", "git_lfs_status").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/lfs/install", "git_lfs_install")
+            .query::<DirectoryQuery>()
+            .body::<GitLfsInstallBody>(),
+        ApiOperation::post("/git/lfs/track", "git_lfs_track")
+            .query::<DirectoryQuery>()
+            .body::<GitLfsTrackBody>(),
+        ApiOperation::get("/git/lfs/locks", "git_lfs_locks").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/lfs/lock", "git_lfs_lock")
+            .query::<DirectoryQuery>()
+            .body::<GitLfsLockBody>(),
+        ApiOperation::post("/git/lfs/unlock", "git_lfs_unlock")
+            .query::<DirectoryQuery>()
+            .body::<GitLfsUnlockBody>(),
+        ApiOperation::post("/git/lfs/migrate", "git_lfs_migrate")
+            .query::<DirectoryQuery>()
+            .body::<GitLfsMigrateBody>(),
+        ApiOperation::get("/git/size-advisor", "git_size_advisor").query::<GitSizeAdvisorQuery>(),
+        ApiOperation::get("/git/submodules", "git_submodules").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/submodules/add", "git_submodule_add")
+            .query::<DirectoryQuery>()
+            .body::<GitSubmoduleAddBody>(),
+        ApiOperation::post("/git/submodules/init", "git_submodule_init")
+            .query::<DirectoryQuery>()
+            .body::<GitSubmodulePathBody>(),
+        ApiOperation::post("/git/submodules/update", "git_submodule_update")
+            .query::<DirectoryQuery>()
+            .body::<GitSubmoduleUpdateBody>(),
+        ApiOperation::get("/git/submodules/status", "git_submodules_status")
+            .query::<DirectoryQuery>(),
+        ApiOperation::post("/git/submodules/batch", "git_submodules_batch")
+            .query::<DirectoryQuery>()
+            .body::<GitSubmoduleBatchBody>(),
+        ApiOperation::get("/git/log", "git_log").query::<GitLogQuery>(),
+        ApiOperation::get("/git/graph", "git_graph").query::<GitGraphQuery>(),
+        ApiOperation::get("/git/commit-diff", "git_commit_diff").query::<GitCommitDiffQuery>(),
+        ApiOperation::get("/git/commit-files", "git_commit_files").query::<GitCommitFilesQuery>(),
+        ApiOperation::get("/git/commit-file-diff", "git_commit_file_diff")
+            .query::<GitCommitFileDiffQuery>(),
+        ApiOperation::get("/git/commit-file-content", "git_commit_file_content")
+            .query::<GitCommitFileContentQuery>(),
+        ApiOperation::get("/git/blame", "git_blame").query::<GitBlameQuery>(),
+        ApiOperation::get("/git/bisect", "git_bisect_status").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/bisect/start", "git_bisect_start")
+            .query::<DirectoryQuery>()
+            .body::<GitBisectStartBody>(),
+        ApiOperation::post("/git/bisect/good", "git_bisect_good")
+            .query::<DirectoryQuery>()
+            .body::<GitBisectMarkBody>(),
+        ApiOperation::post("/git/bisect/bad", "git_bisect_bad")
+            .query::<DirectoryQuery>()
+            .body::<GitBisectMarkBody>(),
+        ApiOperation::post("/git/bisect/skip", "git_bisect_skip")
+            .query::<DirectoryQuery>()
+            .body::<GitBisectMarkBody>(),
+        ApiOperation::post("/git/bisect/reset", "git_bisect_reset").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/bisect/run", "git_bisect_run")
+            .query::<DirectoryQuery>()
+            .body::<GitBisectRunBody>(),
+        ApiOperation::post("/git/stage", "git_stage")
+            .query::<DirectoryQuery>()
+            .body::<GitStageBody>(),
+        ApiOperation::post("/git/clean", "git_clean")
+            .query::<DirectoryQuery>()
+            .body::<GitCleanBody>(),
+        ApiOperation::post("/git/ignore", "git_ignore")
+            .query::<DirectoryQuery>()
+            .body::<GitIgnoreBody>(),
+        ApiOperation::post("/git/rename", "git_rename")
+            .query::<DirectoryQuery>()
+            .body::<GitRenameBody>(),
+        ApiOperation::post("/git/delete", "git_delete")
+            .query::<DirectoryQuery>()
+            .body::<GitDeleteBody>(),
+        ApiOperation::post("/git/unstage", "git_unstage")
+            .query::<DirectoryQuery>()
+            .body::<GitUnstageBody>(),
+        ApiOperation::post("/git/revert", "git_revert")
+            .query::<DirectoryQuery>()
+            .body::<GitRevertBody>(),
+        ApiOperation::post("/git/pull", "git_pull")
+            .query::<DirectoryQuery>()
+            .body::<GitPullBody>(),
+        ApiOperation::post("/git/push", "git_push")
+            .query::<DirectoryQuery>()
+            .body::<GitPushBody>(),
+        ApiOperation::post(
+            "/git/create-github-repo-and-push",
+            "git_create_github_repo_and_push",
+        )
+        .query::<DirectoryQuery>()
+        .body::<GitCreateGithubRepoAndPushBody>(),
+        ApiOperation::post("/git/fetch", "git_fetch")
+            .query::<DirectoryQuery>()
+            .body::<GitFetchBody>(),
+        ApiOperation::post("/git/commit", "git_commit")
+            .query::<DirectoryQuery>()
+            .body::<GitCommitBody>(),
+        ApiOperation::post("/git/commit-message", "git_commit_message")
+            .query::<DirectoryQuery>()
+            .body::<GitCommitMessageBody>(),
+        ApiOperation::get("/git/locks", "git_locks_list").response::<GitRepoLocksResponse>(),
+        ApiOperation::post("/git/locks/release", "git_locks_release").body::<GitLockReleaseBody>(),
+        ApiOperation::get("/git/lint", "git_lint_get").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/lint", "git_lint_run")
+            .query::<DirectoryQuery>()
+            .body::<GitLintBody>(),
+        ApiOperation::get("/git/identity", "git_identity_get").query::<DirectoryQuery>(),
+        ApiOperation::put("/git/identity", "git_identity_set")
+            .query::<DirectoryQuery>()
+            .body::<GitIdentitySetBody>(),
+        ApiOperation::post("/git/undo-commit", "git_undo_commit")
+            .query::<DirectoryQuery>()
+            .body::<GitUndoCommitBody>(),
+        ApiOperation::post("/git/reset", "git_reset_commit")
+            .query::<DirectoryQuery>()
+            .body::<GitResetCommitBody>(),
+        ApiOperation::get("/git/commit-template", "git_commit_template").query::<DirectoryQuery>(),
+        ApiOperation::get("/git/conflicts", "git_conflicts_list").query::<DirectoryQuery>(),
+        ApiOperation::get("/git/conflicts/file", "git_conflict_file").query::<GitFileDiffQuery>(),
+        ApiOperation::get("/git/conflicts/details", "git_conflicts_details")
+            .query::<DirectoryQuery>(),
+        ApiOperation::post(
+            "/git/conflicts/resolve-batch",
+            "git_conflicts_resolve_batch",
+        )
+        .query::<DirectoryQuery>()
+        .body::<GitConflictsResolveBody>(),
+        ApiOperation::post("/git/conflicts/resolve", "git_conflict_resolve")
+            .query::<DirectoryQuery>()
+            .body::<GitConflictResolveBody>(),
+        ApiOperation::get("/git/branches", "git_branches").query::<GitBranchesQuery>(),
+        ApiOperation::post("/git/branches", "git_create_branch")
+            .query::<DirectoryQuery>()
+            .body::<CreateBranchBody>(),
+        ApiOperation::delete("/git/branches", "git_delete_branch")
+            .query::<DirectoryQuery>()
+            .body::<DeleteBranchBody>(),
+        ApiOperation::get("/git/branches/compare", "git_branch_compare")
+            .query::<GitBranchCompareQuery>(),
+        ApiOperation::post("/git/branches/rename", "git_rename_branch")
+            .query::<DirectoryQuery>()
+            .body::<RenameBranchBody>(),
+        ApiOperation::post("/git/branches/delete-remote", "git_delete_remote_branch")
+            .query::<DirectoryQuery>()
+            .body::<DeleteRemoteBranchBody>(),
+        ApiOperation::get("/git/tags", "git_tags_list").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/tags", "git_tags_create")
+            .query::<DirectoryQuery>()
+            .body::<GitTagCreateBody>(),
+        ApiOperation::delete("/git/tags", "git_tags_delete")
+            .query::<DirectoryQuery>()
+            .body::<GitTagDeleteBody>(),
+        ApiOperation::post("/git/tags/delete-remote", "git_tags_delete_remote")
+            .query::<DirectoryQuery>()
+            .body::<GitTagDeleteRemoteBody>(),
+        ApiOperation::post("/git/checkout", "git_checkout")
+            .query::<DirectoryQuery>()
+            .body::<CheckoutBody>(),
+        ApiOperation::post("/git/checkout-detached", "git_checkout_detached")
+            .query::<DirectoryQuery>()
+            .body::<GitCheckoutDetachedBody>(),
+        ApiOperation::post("/git/branches/create-from", "git_create_branch_from")
+            .query::<DirectoryQuery>()
+            .body::<GitCreateBranchFromBody>(),
+        ApiOperation::get("/git/worktrees", "git_worktrees").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/worktrees", "git_worktree_add")
+            .query::<DirectoryQuery>()
+            .body::<GitWorktreeAddBody>(),
+        ApiOperation::delete("/git/worktrees", "git_worktree_remove")
+            .query::<DirectoryQuery>()
+            .body::<GitWorktreeRemoveBody>(),
+        ApiOperation::post("/git/worktrees/prune", "git_worktree_prune").query::<DirectoryQuery>(),
+        ApiOperation::post("/git/worktrees/migrate", "git_worktree_migrate")
+            .query::<DirectoryQuery>()
+            .body::<GitWorktreeMigrateBody>(),
+    ]
+}
+
+fn build_document() -> Value {
+    let mut generator = SchemaSettings::draft2020_12()
+        .with(|settings| {
+            settings.definitions_path = "/components/schemas".into();
+            settings.meta_schema = None;
+        })
+        .into_generator();
+
+    let mut paths = Map::new();
+    for operation in operations() {
+        let rendered = operation.render(&mut generator);
+        let item = paths
+            .entry(operation.openapi_path())
+            .or_insert_with(|| json!({}));
+        item[operation.method.as_str().to_ascii_lowercase()] = rendered;
+    }
+    let error = generator.subschema_for::<ErrorBody>();
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "OpenCode Studio",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Routes served by OpenCode Studio itself. Any other path under `/api` is proxied to the OpenCode server; see its `/doc` for those.",
+        },
+        "security": [{ "session": [] }, { "apiToken": [] }],
+        "paths": paths,
+        "components": {
+            "schemas": generator.take_definitions(true),
+            "responses": {
+                "Error": {
+                    "description": "Error",
+                    "content": { "application/json": { "schema": error } },
+                },
+            },
+            "securitySchemes": {
+                "session": { "type": "apiKey", "in": "cookie", "name": "oc_ui_session" },
+                "apiToken": { "type": "http", "scheme": "bearer" },
+            },
+        },
+    })
+}
+
+static DOCUMENT: LazyLock<Value> = LazyLock::new(build_document);
+
+pub(crate) async fn openapi_json() -> Json<Value> {
+    Json(DOCUMENT.clone())
+}
+
+/// Relative, so it still lands on the web UI when the studio is served under
+/// a path prefix.
+pub(crate) async fn openapi_docs() -> Redirect {
+    Redirect::temporary("../api-docs")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `path` with each parameter replaced by a placeholder segment.
+    fn concrete_path(path: &str) -> String {
+        path.split('/')
+            .map(|segment| match segment.strip_prefix('{') {
+                Some(param) if param.starts_with('*') => "a/b",
+                Some(_) => "x",
+                None => segment,
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Probes the real `/api` router with `TRACE`, which no studio route
+    /// accepts: the `Allow` header of the 405 must list exactly the documented
+    /// methods. Routes that answer anything else take every method (`any`) and
+    /// are left out of the document.
+    #[tokio::test]
+    async fn documents_every_api_route() {
+        use std::collections::BTreeSet;
+
+        let routes = crate::app::api_routes();
+        let ops = operations();
+        let documented = |path: &str| -> BTreeSet<String> {
+            ops.iter()
+                .filter(|op| !op.root && op.path == path)
+                .map(|op| op.method.to_string())
+                .collect()
+        };
+        for op in ops.iter().filter(|op| !op.root) {
+            assert!(
+                routes.paths.contains(&op.path),
+                "{} /api{} is documented but not routed",
+                op.method,
+                op.path
+            );
+        }
+
+        let db_dir = tempfile::tempdir().expect("tempdir");
+        let state = crate::test_support::app_state(db_dir.path(), Default::default()).await;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let router = routes.router.with_state(state);
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .expect("client");
+        let mut checked = 0;
+        for path in BTreeSet::from_iter(routes.paths.iter().copied()) {
+            let response = client
+                .request(
+                    Method::TRACE,
+                    format!("http://{addr}{}", concrete_path(path)),
+                )
+                .send()
+                .await
+                .expect("probe");
+            if response.status() != axum::http::StatusCode::METHOD_NOT_ALLOWED {
+                assert!(
+                    documented(path).is_empty(),
+                    "/api{path} takes any method but is documented"
+                );
+                continue;
+            }
+            let allowed: BTreeSet<String> = response
+                .headers()
+                .get(axum::http::header::ALLOW)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|method| !method.is_empty() && *method != "HEAD")
+                .map(str::to_string)
+                .collect();
+            assert_eq!(allowed, documented(path), "methods of /api{path}");
+            checked += 1;
+        }
+        assert!(checked > 200, "checked {checked} routes");
+    }
+
+    #[test]
+    fn document_resolves_schema_refs() {
+        let doc = build_document();
+        let text = doc.to_string();
+        let schemas = doc["components"]["schemas"].as_object().expect("schemas");
+        for name in [
+            "MemorySnippet",
+            "JobInfo",
+            "TerminalCreateBody",
+            "GitCommitBody",
+            "TaskRunInfo",
+            "ErrorBody",
+        ] {
+            assert!(schemas.contains_key(name), "missing schema {name}");
+        }
+        for reference in text.split("\"#/components/schemas/").skip(1) {
+            let name = reference.split('"').next().unwrap_or_default();
+            assert!(schemas.contains_key(name), "dangling ref {name}");
+        }
+
+        let list = &doc["paths"]["/api/memory"]["get"];
+        assert_eq!(list["operationId"], "memory_list");
+        assert!(
+            list["parameters"]
+                .as_array()
+                .expect("params")
+                .iter()
+                .any(|p| p["in"] == "query")
+        );
+        let commit = &doc["paths"]["/api/git/commit"]["post"];
+        assert_eq!(commit["parameters"][0]["name"], "directory");
+        assert_eq!(
+            commit["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/GitCommitBody"
+        );
+        assert_eq!(
+            doc["paths"]["/api/terminal/{session_id}"]["get"]["parameters"][0]["name"],
+            "session_id"
+        );
+        assert_eq!(doc["paths"]["/health"]["get"]["security"], json!([]));
+    }
+}
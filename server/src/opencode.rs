@@ -1,18 +1,22 @@
 use std::collections::VecDeque;
 use std::net::TcpListener;
+use std::path::PathBuf;
 use std::process::{ExitStatus, Stdio};
 use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use clap::ValueEnum;
 use dashmap::DashMap;
 use reqwest::StatusCode as ReqStatus;
+use schemars::JsonSchema;
 use serde::Serialize;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio::process::{Child, Command};
 use tokio::sync::{Mutex, RwLock};
 
+use crate::opencode_capabilities::OpenCodeCapabilities;
 use crate::ui_auth;
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -56,6 +60,10 @@ fn forward_logs_enabled() -> bool {
 
 const OPENCODE_STARTUP_STDERR_MAX_LINES: usize = 64;
 const OPENCODE_STARTUP_STDERR_MAX_CHARS: usize = 2000;
+const OPENCODE_READY_TIMEOUT: Duration = Duration::from_secs(20);
+/// How long a pooled instance may go unused before it is stopped.
+pub const DEFAULT_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+const POOL_REAP_INTERVAL: Duration = Duration::from_secs(60);
 
 fn normalize_directory_for_upstream_query(value: &str) -> String {
     crate::path_utils::normalize_directory_for_match(value).unwrap_or_else(|| {
@@ -103,9 +111,39 @@ pub struct OpenCodeBridge {
     pub base_url: String,
     pub client: reqwest::Client,
     pub sse_client: reqwest::Client,
+    // Filled in by the capability probe once the upstream is ready.
+    pub capabilities: Arc<std::sync::RwLock<Option<OpenCodeCapabilities>>>,
 }
 
 impl OpenCodeBridge {
+    pub fn capabilities(&self) -> Option<OpenCodeCapabilities> {
+        self.capabilities
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn set_capabilities(&self, capabilities: OpenCodeCapabilities) {
+        *self.capabilities.write().unwrap_or_else(|e| e.into_inner()) = Some(capabilities);
+    }
+
+    /// Record that the upstream answered 404 for `/prompt_async`.
+    pub fn disable_prompt_async(&self) {
+        if let Some(capabilities) = self
+            .capabilities
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            capabilities.prompt_async = false;
+        }
+    }
+
+    /// Unprobed upstreams are assumed to be modern.
+    pub fn supports_prompt_async(&self) -> bool {
+        self.capabilities().is_none_or(|c| c.prompt_async)
+    }
+
     pub fn build_url(
         &self,
         path: &str,
@@ -134,7 +172,20 @@ pub struct OpenCodeStatus {
     pub last_error_info: Option<OpenCodeErrorInfo>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenCodeInstanceStatus {
+    /// `None` for the primary instance, which serves every directory that has
+    /// no pooled instance of its own.
+    pub directory: Option<String>,
+    pub port: Option<u16>,
+    pub ready: bool,
+    pub restarting: bool,
+    pub last_error: Option<String>,
+    pub last_used_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct OpenCodeErrorInfo {
     pub code: String,
@@ -238,6 +289,8 @@ pub struct OpenCodeManager {
     // Optional back-reference so OpenCode plugins can call back into Studio.
     studio_base_url: Option<String>,
     ui_auth: ui_auth::UiAuth,
+    // Inline config (provider overrides) handed to the managed process.
+    config_content: RwLock<Option<String>>,
 
     // When we start OpenCode ourselves, we keep using the same port.
     managed_port: RwLock<Option<u16>>,
@@ -251,6 +304,17 @@ pub struct OpenCodeManager {
 
     // Small in-memory cache of the bridge instance by port.
     bridge_cache: DashMap<u16, OpenCodeBridge>,
+
+    // Project directory the managed process runs in (pooled instances only).
+    working_directory: Option<PathBuf>,
+    // Per-project instances keyed by normalized directory; only the primary
+    // manager populates this, and only while `pool_max` > 0.
+    pool: DashMap<String, Arc<OpenCodeManager>>,
+    pool_max: AtomicUsize,
+    pool_idle_timeout_ms: AtomicU64,
+    pool_spawn_lock: Mutex<()>,
+    last_used_at: AtomicU64,
+    retired: AtomicBool,
 }
 
 impl OpenCodeManager {
@@ -269,6 +333,7 @@ impl OpenCodeManager {
             configured_log_level,
             studio_base_url,
             ui_auth,
+            config_content: RwLock::new(None),
             managed_port: RwLock::new(None),
             child: Mutex::new(None),
             restarting: RwLock::new(false),
@@ -277,6 +342,259 @@ impl OpenCodeManager {
             last_error_info: RwLock::new(None),
             startup_stderr: RwLock::new(VecDeque::new()),
             bridge_cache: DashMap::new(),
+            working_directory: None,
+            pool: DashMap::new(),
+            pool_max: AtomicUsize::new(0),
+            pool_idle_timeout_ms: AtomicU64::new(DEFAULT_POOL_IDLE_TIMEOUT.as_millis() as u64),
+            pool_spawn_lock: Mutex::new(()),
+            last_used_at: AtomicU64::new(0),
+            retired: AtomicBool::new(false),
+        }
+    }
+
+    /// Run up to `max` extra OpenCode processes, one per project directory.
+    /// Zero keeps every request on the primary instance.
+    pub fn set_pool_max(&self, max: usize) {
+        self.pool_max.store(max, Ordering::Relaxed);
+    }
+
+    pub fn pool_max(&self) -> usize {
+        self.pool_max.load(Ordering::Relaxed)
+    }
+
+    /// Stop pooled instances unused for `timeout`; zero keeps them until
+    /// stopped explicitly.
+    pub fn set_pool_idle_timeout(&self, timeout: Duration) {
+        self.pool_idle_timeout_ms
+            .store(timeout.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn pool_enabled(&self) -> bool {
+        self.pool_max() > 0 && !self.skip_start && self.configured_port.is_none()
+    }
+
+    /// Set once a pooled instance has been stopped and dropped from the pool.
+    pub fn is_retired(&self) -> bool {
+        self.retired.load(Ordering::Relaxed)
+    }
+
+    pub fn pool_instances(&self) -> Vec<(String, Arc<OpenCodeManager>)> {
+        let mut out = self
+            .pool
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect::<Vec<_>>();
+        out.sort_by(|a, b| a.0.cmp(&b.0));
+        out
+    }
+
+    /// Mark the instance as in use; requests and session events both count.
+    pub fn touch(&self) {
+        self.last_used_at.store(now_millis(), Ordering::Relaxed);
+    }
+
+    /// Stop pooled instances that have been idle longer than the idle timeout
+    /// and return their directories.
+    pub async fn evict_idle_pool_instances(&self) -> Vec<String> {
+        self.evict_idle_pool_instances_at(now_millis()).await
+    }
+
+    async fn evict_idle_pool_instances_at(&self, now: u64) -> Vec<String> {
+        let timeout = self.pool_idle_timeout_ms.load(Ordering::Relaxed);
+        if timeout == 0 {
+            return Vec::new();
+        }
+        let is_idle = |instance: &OpenCodeManager| {
+            now.saturating_sub(instance.last_used_at.load(Ordering::Relaxed)) >= timeout
+        };
+        let candidates = self
+            .pool
+            .iter()
+            .filter(|entry| is_idle(entry.value()))
+            .map(|entry| entry.key().clone())
+            .collect::<Vec<_>>();
+        let mut evicted = Vec::new();
+        for key in candidates {
+            // Checked again on removal so an instance touched meanwhile stays.
+            let Some((_, instance)) = self.pool.remove_if(&key, |_, instance| is_idle(instance))
+            else {
+                continue;
+            };
+            tracing::info!("Stopping idle pooled OpenCode instance for {}", key);
+            instance.retire().await;
+            evicted.push(key);
+        }
+        evicted
+    }
+
+    /// Periodically stop idle pooled instances for as long as `self` lives.
+    pub fn spawn_pool_reaper(self: &Arc<Self>) {
+        let manager = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(POOL_REAP_INTERVAL);
+            loop {
+                tick.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                manager.evict_idle_pool_instances().await;
+            }
+        });
+    }
+
+    async fn new_pool_instance(&self, directory: PathBuf) -> Self {
+        let mut instance = Self::new(
+            self.hostname.clone(),
+            None,
+            false,
+            self.configured_log_level,
+            self.studio_base_url.clone(),
+            self.ui_auth.clone(),
+        );
+        instance.working_directory = Some(directory);
+        *instance.config_content.get_mut() = self.config_content.read().await.clone();
+        instance
+    }
+
+    /// The instance that should serve requests for `directory`.
+    ///
+    /// Falls back to the primary instance when pooling is off, no directory
+    /// is given, the pool is full of recently used instances, or a pooled
+    /// instance fails to start.
+    pub async fn for_directory(self: &Arc<Self>, directory: Option<&str>) -> Arc<OpenCodeManager> {
+        if !self.pool_enabled() {
+            return self.clone();
+        }
+        let Some(directory) = directory.map(str::trim).filter(|d| !d.is_empty()) else {
+            return self.clone();
+        };
+        let Some(key) = crate::path_utils::normalize_directory_for_match(directory) else {
+            return self.clone();
+        };
+
+        if let Some(instance) = self.live_pool_instance(&key).await {
+            return instance;
+        }
+
+        let _spawn = self.pool_spawn_lock.lock().await;
+        if let Some(instance) = self.live_pool_instance(&key).await {
+            return instance;
+        }
+        if self.pool.len() >= self.pool_max() {
+            self.evict_idle_pool_instances().await;
+        }
+        if self.pool.len() >= self.pool_max() {
+            return self.clone();
+        }
+        let path = PathBuf::from(crate::path_utils::normalize_directory_path(directory));
+        if !path.is_dir() {
+            return self.clone();
+        }
+
+        let instance = Arc::new(self.new_pool_instance(path).await);
+        tracing::info!("Starting pooled OpenCode instance for {}", key);
+        let started = async {
+            instance.start_managed().await?;
+            instance.wait_for_ready(OPENCODE_READY_TIMEOUT).await
+        }
+        .await;
+        if let Err(err) = started {
+            tracing::warn!(
+                "Pooled OpenCode instance for {} failed to start: {}",
+                key,
+                err
+            );
+            instance.retire().await;
+            return self.clone();
+        }
+        instance.touch();
+        self.pool.insert(key, instance.clone());
+        instance
+    }
+
+    async fn live_pool_instance(&self, key: &str) -> Option<Arc<OpenCodeManager>> {
+        let instance = self.pool.get(key).map(|entry| entry.value().clone())?;
+        if instance
+            .check_managed_process_before_ready()
+            .await
+            .is_some()
+        {
+            tracing::warn!("Pooled OpenCode instance for {} exited; respawning", key);
+            self.pool.remove(key);
+            instance.retire().await;
+            return None;
+        }
+        instance.touch();
+        Some(instance)
+    }
+
+    /// Stop a pooled instance and drop it from the pool.
+    pub async fn stop_pool_instance(&self, directory: &str) -> bool {
+        let Some(key) = crate::path_utils::normalize_directory_for_match(directory) else {
+            return false;
+        };
+        let Some((_, instance)) = self.pool.remove(&key) else {
+            return false;
+        };
+        instance.retire().await;
+        true
+    }
+
+    /// The instance already serving `directory`, or the primary one. Never
+    /// starts or touches an instance, so background work does not keep idle
+    /// instances alive.
+    pub fn running_for_directory(
+        self: &Arc<Self>,
+        directory: Option<&str>,
+    ) -> Arc<OpenCodeManager> {
+        directory
+            .and_then(|directory| self.pool_instance(directory))
+            .filter(|instance| !instance.is_retired())
+            .unwrap_or_else(|| self.clone())
+    }
+
+    /// The primary instance followed by every pooled instance.
+    pub fn running_instances(self: &Arc<Self>) -> Vec<Arc<OpenCodeManager>> {
+        let mut out = vec![self.clone()];
+        out.extend(
+            self.pool_instances()
+                .into_iter()
+                .map(|(_, instance)| instance),
+        );
+        out
+    }
+
+    /// Looks up a pooled instance without starting one.
+    pub fn pool_instance(&self, directory: &str) -> Option<Arc<OpenCodeManager>> {
+        let key = crate::path_utils::normalize_directory_for_match(directory)?;
+        self.pool.get(&key).map(|entry| entry.value().clone())
+    }
+
+    async fn retire(&self) {
+        self.retired.store(true, Ordering::Relaxed);
+        *self.ready.write().await = false;
+        self.stop_managed().await;
+    }
+
+    /// The primary instance followed by every pooled instance.
+    pub async fn instances(&self) -> Vec<OpenCodeInstanceStatus> {
+        let mut out = vec![self.instance_status(None).await];
+        for (key, instance) in self.pool_instances() {
+            out.push(instance.instance_status(Some(key)).await);
+        }
+        out
+    }
+
+    async fn instance_status(&self, directory: Option<String>) -> OpenCodeInstanceStatus {
+        let status = self.status().await;
+        let last_used_at = self.last_used_at.load(Ordering::Relaxed);
+        OpenCodeInstanceStatus {
+            directory,
+            port: status.port,
+            ready: status.ready,
+            restarting: status.restarting,
+            last_error: status.last_error,
+            last_used_at: (last_used_at > 0).then_some(last_used_at),
         }
     }
 
@@ -298,6 +616,14 @@ impl OpenCodeManager {
         *self.managed_port.read().await
     }
 
+    /// Takes effect the next time the managed process (re)starts.
+    pub async fn set_config_content(&self, content: Option<String>) {
+        for (_, instance) in self.pool_instances() {
+            *instance.config_content.write().await = content.clone();
+        }
+        *self.config_content.write().await = content;
+    }
+
     pub async fn is_restarting(&self) -> bool {
         *self.restarting.read().await
     }
@@ -310,15 +636,16 @@ impl OpenCodeManager {
         let base_url = format_http_base_url(&self.hostname, port);
         let bridge = OpenCodeBridge {
             base_url,
-            client: reqwest::Client::builder()
+            client: crate::tls_roots::client_builder()
                 .timeout(Duration::from_secs(120))
                 .build()
                 .ok()?,
-            sse_client: reqwest::Client::builder()
+            sse_client: crate::tls_roots::client_builder()
                 // reqwest requires a concrete timeout; use a long one for SSE.
                 .timeout(Duration::from_secs(24 * 60 * 60))
                 .build()
                 .ok()?,
+            capabilities: Default::default(),
         };
         self.bridge_cache.insert(port, bridge.clone());
         Some(bridge)
@@ -338,7 +665,23 @@ impl OpenCodeManager {
         self.start_managed().await
     }
 
+    /// Restarts this instance and, for the primary, every pooled instance.
     pub async fn restart(self: &Arc<Self>, reason: &str) -> Result<(), String> {
+        let result = self.restart_instance(reason).await;
+        for (key, instance) in self.pool_instances() {
+            if let Err(err) = instance.restart_instance(reason).await {
+                tracing::warn!(
+                    "Failed to restart pooled OpenCode instance for {}: {}",
+                    key,
+                    err
+                );
+            }
+        }
+        result
+    }
+
+    /// Restarts only this instance.
+    pub async fn restart_instance(self: &Arc<Self>, reason: &str) -> Result<(), String> {
         if self.skip_start {
             return Ok(());
         }
@@ -356,25 +699,64 @@ impl OpenCodeManager {
         let result = async {
             if self.configured_port.is_some() {
                 // External server: can't restart, just re-check readiness.
+                self.publish_restart_phase("waiting", reason, None);
                 self.clear_last_error().await;
-                self.wait_for_ready(Duration::from_secs(20)).await?;
+                self.wait_for_ready(OPENCODE_READY_TIMEOUT).await?;
                 return Ok(());
             }
 
             tracing::info!("Restarting OpenCode ({})", reason);
+            self.publish_restart_phase("stopping", reason, None);
             self.stop_managed().await;
             // Small delay to allow port release.
             tokio::time::sleep(Duration::from_millis(250)).await;
+            self.publish_restart_phase("starting", reason, None);
             self.start_managed().await?;
-            self.wait_for_ready(Duration::from_secs(20)).await?;
+            self.wait_for_ready(OPENCODE_READY_TIMEOUT).await?;
             Ok::<(), String>(())
         }
         .await;
 
         *self.restarting.write().await = false;
+        match &result {
+            Ok(()) => self.publish_restart_phase("ready", reason, None),
+            Err(err) => self.publish_restart_phase("failed", reason, Some(err)),
+        }
         result
     }
 
+    /// Broadcast a restart transition on the global event stream so clients
+    /// can tell "stopping" from "starting" and know when requests resume.
+    fn publish_restart_phase(&self, phase: &str, reason: &str, error: Option<&str>) {
+        let directory = self
+            .working_directory
+            .as_deref()
+            .map(|dir| dir.to_string_lossy().into_owned());
+        let payload = serde_json::json!({
+            "type": "opencode-studio:opencode-restart",
+            "properties": {
+                "phase": phase,
+                "reason": reason,
+                "directory": directory,
+                "error": error,
+            }
+        });
+        crate::global_sse_hub::publish_downstream_json(&payload.to_string());
+    }
+
+    /// Stop the managed OpenCode process before this server replaces itself.
+    pub async fn shutdown(&self) {
+        if self.skip_start || self.configured_port.is_some() {
+            return;
+        }
+        for (key, _) in self.pool_instances() {
+            if let Some((_, instance)) = self.pool.remove(&key) {
+                instance.retire().await;
+            }
+        }
+        self.stop_managed().await;
+    }
+
     pub async fn ensure_ready(&self, timeout: Duration) -> Result<(), String> {
         self.wait_for_ready(timeout).await
     }
@@ -434,6 +816,9 @@ impl OpenCodeManager {
             .arg("--log-level")
             .arg(log_level)
             .stdin(Stdio::null());
+        if let Some(dir) = self.working_directory.as_deref() {
+            cmd.current_dir(dir);
+        }
 
         if let Some(base_url) = self.studio_base_url.as_deref() {
             cmd.env("OPENCODE_STUDIO_BASE_URL", base_url);
@@ -441,6 +826,9 @@ impl OpenCodeManager {
         if let Some(token) = ui_auth::issue_internal_token(&self.ui_auth) {
             cmd.env("OPENCODE_STUDIO_UI_AUTH_TOKEN", token);
         }
+        if let Some(content) = self.config_content.read().await.as_deref() {
+            cmd.env(crate::opencode_config::OPENCODE_CONFIG_CONTENT_ENV, content);
+        }
 
         if cfg!(windows) {
             apply_windows_home_env_defaults(&mut cmd);
@@ -519,7 +907,7 @@ impl OpenCodeManager {
             timeout.as_secs()
         );
 
-        let client = reqwest::Client::builder()
+        let client = crate::tls_roots::client_builder()
             .timeout(Duration::from_secs(4))
             .connect_timeout(Duration::from_secs(2))
             .build()
@@ -581,6 +969,10 @@ impl OpenCodeManager {
             if ok {
                 *self.ready.write().await = true;
                 self.clear_last_error().await;
+                // The binary may have changed across a restart; re-probe.
+                if let Some(bridge) = self.bridge().await {
+                    crate::opencode_capabilities::probe(&bridge).await;
+                }
                 return Ok(());
             }
 
@@ -684,6 +1076,13 @@ impl OpenCodeManager {
     }
 }
 
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 fn pick_free_port() -> Option<u16> {
     let listener = TcpListener::bind("127.0.0.1:0").ok()?;
     let port = listener.local_addr().ok()?.port();
@@ -1003,6 +1402,90 @@ mod tests {
     use crate::test_support::ENV_LOCK;
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+    fn test_manager(configured_port: Option<u16>) -> Arc<OpenCodeManager> {
+        Arc::new(OpenCodeManager::new(
+            "127.0.0.1".to_string(),
+            configured_port,
+            false,
+            None,
+            None,
+            crate::ui_auth::UiAuth::Disabled,
+        ))
+    }
+
+    #[tokio::test]
+    async fn for_directory_uses_primary_when_pool_cannot_serve() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let directory = dir.path().to_string_lossy().into_owned();
+
+        // Pool disabled.
+        let manager = test_manager(None);
+        let picked = manager.for_directory(Some(&directory)).await;
+        assert!(Arc::ptr_eq(&picked, &manager));
+
+        // Pool enabled but no directory to route by.
+        manager.set_pool_max(2);
+        let picked = manager.for_directory(None).await;
+        assert!(Arc::ptr_eq(&picked, &manager));
+        let picked = manager.for_directory(Some("  ")).await;
+        assert!(Arc::ptr_eq(&picked, &manager));
+
+        // External upstreams are never pooled.
+        let external = test_manager(Some(1));
+        external.set_pool_max(2);
+        let picked = external.for_directory(Some(&directory)).await;
+        assert!(Arc::ptr_eq(&picked, &external));
+        assert!(external.pool_instances().is_empty());
+    }
+
+    #[tokio::test]
+    async fn idle_pool_instances_are_evicted() {
+        let dirs = [
+            tempfile::tempdir().expect("tempdir"),
+            tempfile::tempdir().expect("tempdir"),
+        ];
+        let manager = test_manager(None);
+        manager.set_pool_max(2);
+        let now = now_millis();
+        let mut instances = Vec::new();
+        for (dir, last_used) in dirs.iter().zip([now - 20 * 60 * 1000, now - 60 * 1000]) {
+            let instance = Arc::new(manager.new_pool_instance(dir.path().to_path_buf()).await);
+            instance.last_used_at.store(last_used, Ordering::Relaxed);
+            let key =
+                crate::path_utils::normalize_directory_for_match(&dir.path().to_string_lossy())
+                    .unwrap();
+            manager.pool.insert(key.clone(), instance.clone());
+            instances.push((key, instance));
+        }
+
+        let evicted = manager.evict_idle_pool_instances_at(now).await;
+        assert_eq!(evicted, vec![instances[0].0.clone()]);
+        assert!(instances[0].1.is_retired());
+        assert!(!instances[1].1.is_retired());
+        assert_eq!(manager.pool_instances().len(), 1);
+
+        // Disabled timeout keeps everything.
+        manager.set_pool_idle_timeout(Duration::ZERO);
+        assert!(
+            manager
+                .evict_idle_pool_instances_at(now + 60 * 60 * 1000)
+                .await
+                .is_empty()
+        );
+        assert_eq!(manager.pool_instances().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn instances_lists_primary_without_directory() {
+        let manager = test_manager(Some(4096));
+        let instances = manager.instances().await;
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].directory, None);
+        assert_eq!(instances[0].port, Some(4096));
+        assert_eq!(instances[0].last_used_at, None);
+        assert!(!manager.stop_pool_instance("/tmp/nowhere").await);
+    }
+
     #[test]
     fn parse_forward_logs_value_accepts_common_truthy_values() {
         for v in ["1", "true", "TRUE", "yes", "on", " On "] {
@@ -1028,6 +1511,7 @@ mod tests {
             base_url: "http://127.0.0.1:4096".to_string(),
             client: reqwest::Client::new(),
             sse_client: reqwest::Client::new(),
+            capabilities: Default::default(),
         };
         let uri: axum::http::Uri =
             "/session/status?directory=C%3A%5CUsers%5CAlice%5CRepo%5C&sessionId=ses_1"
@@ -1058,6 +1542,7 @@ mod tests {
             base_url: "http://127.0.0.1:4096".to_string(),
             client: reqwest::Client::new(),
             sse_client: reqwest::Client::new(),
+            capabilities: Default::default(),
         };
         let uri: axum::http::Uri = "/session/status?sessionId=ses_1&local=true"
             .parse()
@@ -0,0 +1,195 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::opencode::OpenCodeBridge;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the upstream OpenCode server supports.
+///
+/// Flags come from the upstream's OpenAPI document when it publishes one;
+/// otherwise every flag is assumed on and `detected` is false.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenCodeCapabilities {
+    pub version: Option<String>,
+    pub detected: bool,
+    pub prompt_async: bool,
+    pub session_status: bool,
+    pub permission_list: bool,
+    pub question_list: bool,
+    pub lsp: bool,
+    pub mcp: bool,
+    pub probed_at: u64,
+}
+
+impl OpenCodeCapabilities {
+    fn assumed(version: Option<String>) -> Self {
+        Self {
+            version,
+            detected: false,
+            prompt_async: true,
+            session_status: true,
+            permission_list: true,
+            question_list: true,
+            lsp: true,
+            mcp: true,
+            probed_at: now_millis(),
+        }
+    }
+
+    fn from_openapi_paths<'a>(
+        version: Option<String>,
+        paths: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        let paths: Vec<String> = paths.into_iter().map(normalize_openapi_path).collect();
+        let has = |path: &str| paths.iter().any(|p| p == path);
+        Self {
+            version,
+            detected: true,
+            prompt_async: has("/session/{}/prompt_async"),
+            session_status: has("/session/status"),
+            permission_list: has("/permission"),
+            question_list: has("/question"),
+            lsp: has("/lsp"),
+            mcp: has("/mcp"),
+            probed_at: now_millis(),
+        }
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// `/session/{sessionID}/message` -> `/session/{}/message`, so detection does
+/// not depend on how the upstream names its path parameters.
+fn normalize_openapi_path(path: &str) -> String {
+    path.trim_end_matches('/')
+        .split('/')
+        .map(|segment| {
+            if segment.starts_with('{') && segment.ends_with('}') {
+                "{}"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+async fn get_json(bridge: &OpenCodeBridge, path: &str) -> Option<serde_json::Value> {
+    let url = bridge.build_url(path, None).ok()?;
+    let resp = bridge
+        .client
+        .get(url)
+        .header("accept", "application/json")
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    resp.json().await.ok()
+}
+
+/// Probe the upstream's version and routes, and cache the result on `bridge`.
+pub(crate) async fn probe(bridge: &OpenCodeBridge) -> OpenCodeCapabilities {
+    let version = get_json(bridge, "/global/health").await.and_then(|health| {
+        health
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    });
+    let doc = get_json(bridge, "/doc").await;
+    let capabilities = match doc
+        .as_ref()
+        .and_then(|doc| doc.get("paths"))
+        .and_then(|paths| paths.as_object())
+    {
+        Some(paths) => {
+            OpenCodeCapabilities::from_openapi_paths(version, paths.keys().map(String::as_str))
+        }
+        None => OpenCodeCapabilities::assumed(version),
+    };
+    tracing::info!(
+        version = capabilities.version.as_deref().unwrap_or("unknown"),
+        detected = capabilities.detected,
+        prompt_async = capabilities.prompt_async,
+        "Probed OpenCode capabilities"
+    );
+    bridge.set_capabilities(capabilities.clone());
+    capabilities
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct CapabilitiesQuery {
+    directory: Option<String>,
+}
+
+pub(crate) async fn opencode_capabilities_get(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<CapabilitiesQuery>,
+) -> Response {
+    let upstream = state.opencode.for_directory(q.directory.as_deref()).await;
+    let oc = upstream.status().await;
+    if oc.restarting || !oc.ready {
+        return crate::opencode_proxy::open_code_not_ready(&oc);
+    }
+    let Some(bridge) = upstream.bridge().await else {
+        return crate::opencode_proxy::open_code_unavailable(Some(&oc));
+    };
+    let capabilities = match bridge.capabilities() {
+        Some(capabilities) => capabilities,
+        None => probe(&bridge).await,
+    };
+    Json(capabilities).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_capabilities_from_openapi_paths() {
+        let caps = OpenCodeCapabilities::from_openapi_paths(
+            Some("1.2.3".to_string()),
+            [
+                "/session/{sessionID}/message",
+                "/session/{id}/prompt_async",
+                "/session/status",
+                "/permission/",
+                "/mcp",
+            ],
+        );
+        assert!(caps.detected);
+        assert!(caps.prompt_async);
+        assert!(caps.session_status);
+        assert!(caps.permission_list);
+        assert!(caps.mcp);
+        assert!(!caps.question_list);
+        assert!(!caps.lsp);
+
+        let old = OpenCodeCapabilities::from_openapi_paths(None, ["/session/{id}/message"]);
+        assert!(!old.prompt_async);
+    }
+
+    #[test]
+    fn assumes_everything_without_openapi_document() {
+        let caps = OpenCodeCapabilities::assumed(None);
+        assert!(!caps.detected);
+        assert!(caps.prompt_async && caps.lsp && caps.question_list);
+    }
+}
@@ -1,3 +1,5 @@
+mod provider_overrides;
 mod store;
 
+pub use provider_overrides::*;
 pub use store::*;
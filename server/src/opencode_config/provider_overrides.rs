@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Studio settings key holding `providerId -> ProviderOverride`.
+pub const PROVIDER_OVERRIDES_KEY: &str = "providerOverrides";
+
+/// Env var OpenCode reads inline config from; merged over its file layers.
+pub const OPENCODE_CONFIG_CONTENT_ENV: &str = "OPENCODE_CONFIG_CONTENT";
+
+/// Header values are shown as this prefix plus the last few characters.
+const MASK_PREFIX: &str = "••••";
+const MASK_VISIBLE_CHARS: usize = 4;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+impl ProviderOverride {
+    pub fn is_empty(&self) -> bool {
+        self.base_url.is_none() && self.headers.is_empty() && self.timeout_ms.is_none()
+    }
+
+    /// `provider.<id>.options` fragment for the OpenCode config.
+    fn options(&self) -> Value {
+        let mut options = serde_json::Map::new();
+        if let Some(url) = &self.base_url {
+            options.insert("baseURL".to_string(), Value::String(url.clone()));
+        }
+        if !self.headers.is_empty() {
+            options.insert(
+                "headers".to_string(),
+                serde_json::to_value(&self.headers).unwrap_or(Value::Null),
+            );
+        }
+        if let Some(ms) = self.timeout_ms {
+            options.insert("timeout".to_string(), Value::Number(ms.into()));
+        }
+        Value::Object(options)
+    }
+}
+
+pub fn provider_overrides(
+    settings: &crate::settings::Settings,
+) -> BTreeMap<String, ProviderOverride> {
+    settings
+        .extra
+        .get(PROVIDER_OVERRIDES_KEY)
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+pub fn mask_header_value(value: &str) -> String {
+    let count = value.chars().count();
+    if count <= MASK_VISIBLE_CHARS * 2 {
+        return MASK_PREFIX.to_string();
+    }
+    let tail: String = value.chars().skip(count - MASK_VISIBLE_CHARS).collect();
+    format!("{MASK_PREFIX}{tail}")
+}
+
+fn headers_mut(entry: &mut Value) -> Option<&mut serde_json::Map<String, Value>> {
+    entry.get_mut("headers").and_then(|h| h.as_object_mut())
+}
+
+/// Replace header values with their masked form for display.
+pub fn mask_provider_overrides(value: &mut Value) {
+    let Some(map) = value.as_object_mut() else {
+        return;
+    };
+    for entry in map.values_mut() {
+        if let Some(headers) = headers_mut(entry) {
+            for v in headers.values_mut() {
+                if let Value::String(s) = v {
+                    *s = mask_header_value(s);
+                }
+            }
+        }
+    }
+}
+
+/// Masked header values sent back unchanged keep the stored secret; a masked
+/// value that no longer matches anything stored is dropped.
+pub fn restore_masked_headers(incoming: &mut Value, current: Option<&Value>) {
+    let Some(map) = incoming.as_object_mut() else {
+        return;
+    };
+    for (provider_id, entry) in map.iter_mut() {
+        let stored = current
+            .and_then(|c| c.get(provider_id))
+            .and_then(|e| e.get("headers"))
+            .and_then(|h| h.as_object());
+        let Some(headers) = headers_mut(entry) else {
+            continue;
+        };
+        headers.retain(|name, v| {
+            let Some(s) = v.as_str() else {
+                return true;
+            };
+            if !s.starts_with(MASK_PREFIX) {
+                return true;
+            }
+            match stored.and_then(|h| h.get(name)).and_then(|v| v.as_str()) {
+                Some(raw) if mask_header_value(raw) == s => {
+                    *v = Value::String(raw.to_string());
+                    true
+                }
+                _ => false,
+            }
+        });
+    }
+}
+
+fn deep_merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (k, v) in overlay {
+                deep_merge(base.entry(k.clone()).or_insert(Value::Null), v);
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// Merge overrides and stored provider API keys into `base` (an existing
+/// `OPENCODE_CONFIG_CONTENT`, if any). Returns `None` when there is nothing
+/// to inject.
+fn merge_config_content(
+    base: Option<&str>,
+    overrides: &BTreeMap<String, ProviderOverride>,
+    api_keys: &BTreeMap<String, String>,
+) -> Option<String> {
+    let mut providers: serde_json::Map<String, Value> = overrides
+        .iter()
+        .filter(|(_, o)| !o.is_empty())
+        .map(|(id, o)| (id.clone(), serde_json::json!({ "options": o.options() })))
+        .collect();
+    for (id, key) in api_keys {
+        let entry = providers
+            .entry(id.clone())
+            .or_insert_with(|| serde_json::json!({ "options": {} }));
+        entry["options"]["apiKey"] = Value::String(key.clone());
+    }
+    if providers.is_empty() {
+        return None;
+    }
+    let mut config = base
+        .map(str::trim)
+        .filter(|raw| !raw.is_empty())
+        .and_then(|raw| json5::from_str::<Value>(raw).ok())
+        .filter(Value::is_object)
+        .unwrap_or_else(|| Value::Object(serde_json::Map::new()));
+    deep_merge(
+        &mut config,
+        &serde_json::json!({ "provider": Value::Object(providers) }),
+    );
+    serde_json::to_string(&config).ok()
+}
+
+/// `OPENCODE_CONFIG_CONTENT` for the managed `opencode serve`, layering the
+/// overrides and API keys from the secrets store over whatever the
+/// environment already provides.
+pub fn managed_config_content(settings: &crate::settings::Settings) -> Option<String> {
+    let inherited = std::env::var(OPENCODE_CONFIG_CONTENT_ENV).ok();
+    merge_config_content(
+        inherited.as_deref(),
+        &provider_overrides(settings),
+        &crate::secrets::provider_api_keys(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masked_headers_round_trip_to_stored_values() {
+        let stored = serde_json::json!({
+            "openai": {"headers": {"X-Api-Key": "sk-secret-1234", "X-Org": "org"}}
+        });
+        let mut shown = stored.clone();
+        mask_provider_overrides(&mut shown);
+        assert_eq!(shown["openai"]["headers"]["X-Api-Key"], "••••1234");
+        assert_eq!(shown["openai"]["headers"]["X-Org"], "••••");
+
+        shown["openai"]["headers"]["X-New"] = Value::String("plain".into());
+        shown["openai"]["headers"]["X-Stale"] = Value::String("••••9999".into());
+        restore_masked_headers(&mut shown, Some(&stored));
+        assert_eq!(
+            shown["openai"]["headers"],
+            serde_json::json!({"X-Api-Key": "sk-secret-1234", "X-Org": "org", "X-New": "plain"})
+        );
+    }
+
+    #[test]
+    fn merge_config_content_layers_over_inherited_config() {
+        let overrides = BTreeMap::from([
+            (
+                "anthropic".to_string(),
+                ProviderOverride {
+                    base_url: Some("https://eu.example.com/v1".into()),
+                    headers: BTreeMap::from([("X-Region".into(), "eu".into())]),
+                    timeout_ms: Some(60_000),
+                },
+            ),
+            ("openai".to_string(), ProviderOverride::default()),
+        ]);
+        let merged = merge_config_content(
+            Some(r#"{ provider: { anthropic: { options: { apiKey: "k" } } }, model: "x" }"#),
+            &overrides,
+            &BTreeMap::new(),
+        )
+        .expect("content");
+        let value: Value = serde_json::from_str(&merged).unwrap();
+        assert_eq!(value["model"], "x");
+        let options = &value["provider"]["anthropic"]["options"];
+        assert_eq!(options["apiKey"], "k");
+        assert_eq!(options["baseURL"], "https://eu.example.com/v1");
+        assert_eq!(options["headers"]["X-Region"], "eu");
+        assert_eq!(options["timeout"], 60_000);
+        assert!(value["provider"].get("openai").is_none());
+
+        assert_eq!(
+            merge_config_content(None, &BTreeMap::new(), &BTreeMap::new()),
+            None,
+            "no overrides leaves the env untouched"
+        );
+    }
+
+    #[test]
+    fn stored_api_keys_are_injected_as_provider_options() {
+        let overrides = BTreeMap::from([(
+            "openai".to_string(),
+            ProviderOverride {
+                timeout_ms: Some(5_000),
+                ..ProviderOverride::default()
+            },
+        )]);
+        let keys = BTreeMap::from([
+            ("openai".to_string(), "sk-openai".to_string()),
+            ("groq".to_string(), "gsk-groq".to_string()),
+        ]);
+        let merged = merge_config_content(
+            Some(r#"{ provider: { openai: { options: { apiKey: "env" } } } }"#),
+            &overrides,
+            &keys,
+        )
+        .expect("content");
+        let value: Value = serde_json::from_str(&merged).unwrap();
+        assert_eq!(
+            value["provider"]["openai"]["options"]["apiKey"],
+            "sk-openai"
+        );
+        assert_eq!(value["provider"]["openai"]["options"]["timeout"], 5_000);
+        assert_eq!(value["provider"]["groq"]["options"]["apiKey"], "gsk-groq");
+    }
+}
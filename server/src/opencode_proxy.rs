@@ -4,13 +4,17 @@ use std::time::Duration;
 
 use axum::{
     Json,
-    extract::{Path as AxumPath, Query, State},
+    extract::{
+        Path as AxumPath, Query, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
     http::{HeaderMap, Method, StatusCode, Uri},
     response::{IntoResponse, Response},
 };
 use base64::Engine as _;
 use bytes::{BufMut as _, Bytes, BytesMut};
 use futures_util::StreamExt;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::config::{
@@ -20,6 +24,11 @@ use crate::config::{
 use crate::{ApiResult, AppError};
 
 const OPENCODE_STUDIO_SSE_HEARTBEAT: Duration = Duration::from_secs(15);
+// Idempotent reads that arrive while OpenCode restarts wait this long for it
+// to come back before failing with 503.
+const RESTART_DRAIN_WAIT: Duration = Duration::from_secs(15);
+const RESTART_DRAIN_POLL: Duration = Duration::from_millis(200);
+const MAX_PROXY_BODY_BYTES: usize = 50 * 1024 * 1024;
 
 static KNOWN_TOOL_ACTIVITY_FILTER_IDS: LazyLock<HashSet<String>> =
     LazyLock::new(|| default_chat_activity_tool_filters().into_iter().collect());
@@ -188,7 +197,7 @@ struct OpenCodeUnavailableBody {
     opencode_error: Option<crate::opencode::OpenCodeErrorInfo>,
 }
 
-fn open_code_unavailable(oc: Option<&crate::opencode::OpenCodeStatus>) -> Response {
+pub(crate) fn open_code_unavailable(oc: Option<&crate::opencode::OpenCodeStatus>) -> Response {
     let info = oc.and_then(|status| status.last_error_info.clone());
     let body = OpenCodeUnavailableBody {
         error: info
@@ -218,14 +227,58 @@ fn open_code_restarting(oc: &crate::opencode::OpenCodeStatus) -> Response {
     (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response()
 }
 
-fn open_code_not_ready(oc: &crate::opencode::OpenCodeStatus) -> Response {
+pub(crate) fn open_code_not_ready(oc: &crate::opencode::OpenCodeStatus) -> Response {
     if oc.restarting {
         return open_code_restarting(oc);
     }
     open_code_unavailable(Some(oc))
 }
 
-#[derive(Debug, Deserialize)]
+fn is_idempotent_read(method: &Method) -> bool {
+    *method == Method::GET || *method == Method::HEAD
+}
+
+/// A request body's chunks as they arrive, failing once more than `limit`
+/// bytes have been read.
+fn limited_body_stream(
+    body: axum::body::Body,
+    limit: usize,
+) -> impl futures_util::Stream<Item = Result<Bytes, axum::Error>> + Send + 'static {
+    let mut seen = 0usize;
+    body.into_data_stream().map(move |chunk| {
+        let chunk = chunk?;
+        seen += chunk.len();
+        if seen > limit {
+            return Err(axum::Error::new("Request body too large"));
+        }
+        Ok(chunk)
+    })
+}
+
+fn declared_content_length(headers: &HeaderMap) -> Option<usize> {
+    headers
+        .get(axum::http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Hold a read while `upstream` is mid-restart; returns its status once the
+/// restart settles or `wait` runs out.
+async fn status_after_restart(
+    upstream: &crate::opencode::OpenCodeManager,
+    wait: Duration,
+) -> crate::opencode::OpenCodeStatus {
+    let deadline = tokio::time::Instant::now() + wait;
+    loop {
+        let oc = upstream.status().await;
+        if !oc.restarting || tokio::time::Instant::now() >= deadline {
+            return oc;
+        }
+        tokio::time::sleep(RESTART_DRAIN_POLL).await;
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct AttentionListQuery {
     pub session_id: Option<String>,
@@ -281,16 +334,16 @@ fn extract_session_id_from_diff_path(path: &str) -> Option<String> {
     Some(session_id.to_string())
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct SessionDiffItem {
-    file: String,
-    before: String,
-    after: String,
+pub(crate) struct SessionDiffItem {
+    pub(crate) file: String,
+    pub(crate) before: String,
+    pub(crate) after: String,
     additions: usize,
     deletions: usize,
-    #[serde(skip_serializing_if = "String::is_empty")]
-    diff: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub(crate) diff: String,
 }
 
 fn normalize_diff_path(raw_path: &str, directory: Option<&str>) -> Option<String> {
@@ -315,17 +368,7 @@ fn normalize_diff_path(raw_path: &str, directory: Option<&str>) -> Option<String
     }
 
     if let Some(dir) = directory {
-        let dir = dir
-            .trim()
-            .replace('\\', "/")
-            .trim_end_matches('/')
-            .to_string();
-        if !dir.is_empty() {
-            let prefix = format!("{dir}/");
-            if out.starts_with(&prefix) {
-                out = out[prefix.len()..].to_string();
-            }
-        }
+        out = strip_diff_directory_prefix(&out, dir).to_string();
     }
 
     let trimmed = out.trim();
@@ -336,6 +379,18 @@ fn normalize_diff_path(raw_path: &str, directory: Option<&str>) -> Option<String
     }
 }
 
+fn strip_diff_directory_prefix<'a>(path: &'a str, directory: &str) -> &'a str {
+    let dir = directory
+        .trim()
+        .replace('\\', "/")
+        .trim_end_matches('/')
+        .to_string();
+    if dir.is_empty() {
+        return path;
+    }
+    path.strip_prefix(&format!("{dir}/")).unwrap_or(path)
+}
+
 fn first_trimmed(
     map: &serde_json::Map<String, serde_json::Value>,
     keys: &[&str],
@@ -659,7 +714,7 @@ fn append_session_diff_from_metadata_value(
     }
 }
 
-fn session_diff_items_from_part(
+pub(crate) fn session_diff_items_from_part(
     part_map: &serde_json::Map<String, serde_json::Value>,
     directory: Option<&str>,
 ) -> Vec<SessionDiffItem> {
@@ -680,34 +735,6 @@ fn session_diff_items_from_part(
     by_file.into_values().collect()
 }
 
-fn build_session_diff_from_messages(
-    messages: &[serde_json::Value],
-    directory: Option<&str>,
-) -> Vec<SessionDiffItem> {
-    let mut by_file = BTreeMap::<String, SessionDiffItem>::new();
-
-    for message in messages {
-        let Some(parts) = message
-            .as_object()
-            .and_then(|obj| obj.get("parts"))
-            .and_then(|v| v.as_array())
-        else {
-            continue;
-        };
-
-        for part in parts {
-            let Some(part_map) = part.as_object() else {
-                continue;
-            };
-            for item in session_diff_items_from_part(part_map, directory) {
-                by_file.insert(item.file.clone(), item);
-            }
-        }
-    }
-
-    by_file.into_values().collect()
-}
-
 fn looks_like_diff_item_map(map: &serde_json::Map<String, serde_json::Value>) -> bool {
     first_trimmed(
         map,
@@ -774,7 +801,70 @@ fn read_session_diff_items(value: &serde_json::Value, depth: usize) -> Vec<serde
     Vec::new()
 }
 
-async fn session_diff_get_authoritative(uri: Uri, path: &str) -> ApiResult<Response> {
+/// Orders a part's diff items within the session: later parts win per file.
+pub(crate) fn session_diff_source_key(message_id: &str, part_id: &str) -> String {
+    format!("{message_id}/{part_id}")
+}
+
+/// Every part's diff items, tagged with their source key and with file paths
+/// left as OpenCode reported them.
+pub(crate) fn session_diff_rows_from_messages(
+    messages: &[serde_json::Value],
+) -> Vec<(String, SessionDiffItem)> {
+    let mut rows = Vec::new();
+    for (message_index, message) in messages.iter().enumerate() {
+        let Some(message_map) = message.as_object() else {
+            continue;
+        };
+        let Some(parts) = message_map.get("parts").and_then(|v| v.as_array()) else {
+            continue;
+        };
+        let message_id = message_map
+            .get("info")
+            .and_then(|v| v.get("id"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{message_index:08}"));
+
+        for (part_index, part) in parts.iter().enumerate() {
+            let Some(part_map) = part.as_object() else {
+                continue;
+            };
+            let part_id = part_map
+                .get("id")
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{part_index:08}"));
+            let source = session_diff_source_key(&message_id, &part_id);
+            for item in session_diff_items_from_part(part_map, None) {
+                rows.push((source.clone(), item));
+            }
+        }
+    }
+    rows
+}
+
+/// Collapses aggregate rows to one item per file, relative to `directory`.
+pub(crate) fn merge_session_diff_rows(
+    mut rows: Vec<(String, SessionDiffItem)>,
+    directory: Option<&str>,
+) -> Vec<SessionDiffItem> {
+    rows.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut by_file = BTreeMap::<String, SessionDiffItem>::new();
+    for (_, mut item) in rows {
+        if let Some(directory) = directory {
+            item.file = strip_diff_directory_prefix(&item.file, directory).to_string();
+        }
+        by_file.insert(item.file.clone(), item);
+    }
+    by_file.into_values().collect()
+}
+
+async fn session_diff_get_authoritative(
+    state: &crate::AppState,
+    uri: Uri,
+    path: &str,
+) -> ApiResult<Response> {
     let Some(session_id) = extract_session_id_from_diff_path(path) else {
         return Ok((
             StatusCode::BAD_REQUEST,
@@ -789,9 +879,18 @@ async fn session_diff_get_authoritative(uri: Uri, path: &str) -> ApiResult<Respo
         .unwrap_or(100);
 
     let directory = directory_from_uri_query(&uri);
-    let local_messages =
-        crate::opencode_session::load_session_messages_unfiltered(&session_id).await;
-    let items = build_session_diff_from_messages(&local_messages, directory.as_deref());
+    let rows = match crate::session_diff_index::load(state, &session_id).await {
+        Some(rows) => rows,
+        None => {
+            let generation = crate::session_diff_index::generation(&session_id);
+            let local_messages =
+                crate::opencode_session::load_session_messages_unfiltered(&session_id).await;
+            let rows = session_diff_rows_from_messages(&local_messages);
+            crate::session_diff_index::store_rebuilt(state, &session_id, generation, rows.clone());
+            rows
+        }
+    };
+    let items = merge_session_diff_rows(rows, directory.as_deref());
 
     let total = items.len();
     let start = offset.min(total);
@@ -906,23 +1005,35 @@ fn infer_attachment_filename(
         })
 }
 
-async fn proxy_opencode_sse_event_inner(
-    state: Arc<crate::AppState>,
-    headers: HeaderMap,
-    uri: Uri,
+/// Upstream OpenCode event stream plus the sanitization policy captured at
+/// connect time. Shared by the SSE and WebSocket transports.
+struct OpenCodeEventStream {
+    resp: reqwest::Response,
+    filter: ActivityFilter,
+    detail: ActivityDetailPolicy,
+}
+
+async fn open_opencode_event_stream(
+    state: &crate::AppState,
+    last_event_id: Option<&str>,
+    uri: &Uri,
     path: &str,
-) -> ApiResult<Response> {
-    let oc = state.opencode.status().await;
+) -> Result<OpenCodeEventStream, Response> {
+    let upstream = state
+        .opencode
+        .for_directory(directory_from_uri_query(uri).as_deref())
+        .await;
+    let oc = status_after_restart(&upstream, RESTART_DRAIN_WAIT).await;
     if oc.restarting || !oc.ready {
-        return Ok(open_code_not_ready(&oc));
+        return Err(open_code_not_ready(&oc));
     }
-    let Some(bridge) = state.opencode.bridge().await else {
-        return Ok(open_code_unavailable(Some(&oc)));
+    let Some(bridge) = upstream.bridge().await else {
+        return Err(open_code_unavailable(Some(&oc)));
     };
 
-    let target = match bridge.build_url(path, Some(&uri)) {
+    let target = match bridge.build_url(path, Some(uri)) {
         Ok(url) => url,
-        Err(_) => return Ok(open_code_unavailable(Some(&oc))),
+        Err(_) => return Err(open_code_unavailable(Some(&oc))),
     };
 
     let mut req = reqwest::Request::new(reqwest::Method::GET, target.parse().expect("valid url"));
@@ -935,27 +1046,27 @@ async fn proxy_opencode_sse_event_inner(
         req_headers.insert(reqwest::header::CACHE_CONTROL, "no-cache".parse().unwrap());
         req_headers.insert(reqwest::header::CONNECTION, "keep-alive".parse().unwrap());
 
-        if let Some(last_id) = headers.get("Last-Event-ID").and_then(|v| v.to_str().ok())
+        if let Some(last_id) = last_event_id
             && !last_id.is_empty()
+            && let Ok(value) = last_id.parse()
         {
             req_headers.insert(
                 reqwest::header::HeaderName::from_static("last-event-id"),
-                last_id.parse().unwrap(),
+                value,
             );
         }
     }
 
-    let resp = bridge
-        .sse_client
-        .execute(req)
-        .await
-        .map_err(|_| AppError::bad_gateway("Failed to connect to OpenCode event stream"))?;
+    let resp = bridge.sse_client.execute(req).await.map_err(|_| {
+        AppError::bad_gateway("Failed to connect to OpenCode event stream").into_response()
+    })?;
 
     if !resp.status().is_success() {
         return Err(AppError::bad_gateway(format!(
             "OpenCode event stream unavailable ({})",
             resp.status().as_u16()
-        )));
+        ))
+        .into_response());
     }
 
     let (filter, detail) = {
@@ -966,7 +1077,31 @@ async fn proxy_opencode_sse_event_inner(
         )
     };
 
-    let stream = sse_passthrough_with_heartbeat_and_activity(state.clone(), resp, filter, detail);
+    Ok(OpenCodeEventStream {
+        resp,
+        filter,
+        detail,
+    })
+}
+
+async fn proxy_opencode_sse_event_inner(
+    state: Arc<crate::AppState>,
+    headers: HeaderMap,
+    uri: Uri,
+    path: &str,
+) -> ApiResult<Response> {
+    let last_event_id = headers.get("Last-Event-ID").and_then(|v| v.to_str().ok());
+    let upstream = match open_opencode_event_stream(&state, last_event_id, &uri, path).await {
+        Ok(upstream) => upstream,
+        Err(resp) => return Ok(resp),
+    };
+
+    let stream = sse_passthrough_with_heartbeat_and_activity(
+        state.clone(),
+        upstream.resp,
+        upstream.filter,
+        upstream.detail,
+    );
 
     let mut out = Response::new(axum::body::Body::from_stream(stream));
     *out.status_mut() = StatusCode::OK;
@@ -995,6 +1130,102 @@ pub(crate) async fn proxy_opencode_sse_event(
     proxy_opencode_sse_event_inner(state, headers, uri, "/event").await
 }
 
+/// Re-encode one SSE block from the passthrough stream as a WebSocket text
+/// frame: `{"id": <last event id>?, "payload": <data>}`. Comment-only blocks
+/// are dropped; non-JSON data is sent as a string.
+fn sse_block_to_ws_text(block: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(block);
+    let mut id: Option<&str> = None;
+    let mut data = Vec::<&str>::new();
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("data:") {
+            data.push(rest.strip_prefix(' ').unwrap_or(rest));
+        } else if let Some(rest) = line.strip_prefix("id:") {
+            id = Some(rest.trim());
+        }
+    }
+    if data.is_empty() {
+        return None;
+    }
+
+    let data = data.join("\n");
+    let payload =
+        serde_json::from_str::<serde_json::Value>(&data).unwrap_or(serde_json::Value::String(data));
+    let mut envelope = serde_json::Map::new();
+    if let Some(id) = id.filter(|v| !v.is_empty()) {
+        envelope.insert("id".to_string(), serde_json::Value::String(id.to_string()));
+    }
+    envelope.insert("payload".to_string(), payload);
+    serde_json::to_string(&envelope).ok()
+}
+
+/// WebSocket transport for `/event`, for clients behind proxies that buffer
+/// SSE. Carries the same sanitized stream, including studio heartbeat and
+/// session-activity events.
+pub(crate) async fn proxy_opencode_ws_events(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    uri: Uri,
+    ws: WebSocketUpgrade,
+) -> ApiResult<Response> {
+    let last_event_id = query_param(&uri, "lastEventId").or_else(|| {
+        headers
+            .get("Last-Event-ID")
+            .and_then(|v| v.to_str().ok())
+            .map(ToString::to_string)
+    });
+    // Connect upstream before upgrading so failures surface as HTTP errors.
+    let upstream =
+        match open_opencode_event_stream(&state, last_event_id.as_deref(), &uri, "/event").await {
+            Ok(upstream) => upstream,
+            Err(resp) => return Ok(resp),
+        };
+
+    let stream = sse_passthrough_with_heartbeat_and_activity(
+        state.clone(),
+        upstream.resp,
+        upstream.filter,
+        upstream.detail,
+    );
+    Ok(ws
+        .on_upgrade(move |socket| run_opencode_event_ws_client(socket, stream))
+        .into_response())
+}
+
+async fn run_opencode_event_ws_client<S>(mut socket: WebSocket, stream: S)
+where
+    S: futures_util::Stream<Item = Result<Bytes, std::convert::Infallible>>,
+{
+    let mut stream = std::pin::pin!(stream);
+    loop {
+        tokio::select! {
+            item = stream.next() => {
+                let Some(Ok(block)) = item else {
+                    let _ = socket.send(Message::Close(None)).await;
+                    break;
+                };
+                let Some(text) = sse_block_to_ws_text(&block) else {
+                    continue;
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.next() => {
+                match incoming {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(Message::Ping(payload))) => {
+                        if socket.send(Message::Pong(payload)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+}
+
 fn sse_passthrough_with_heartbeat_and_activity(
     state: Arc<crate::AppState>,
     resp: reqwest::Response,
@@ -1160,23 +1391,29 @@ pub(crate) async fn proxy_opencode_rest_inner(
         && extract_session_id_from_diff_path(normalized_path).is_some()
         && !query_has_message_id(&uri);
     if is_session_diff_get {
-        return session_diff_get_authoritative(uri, normalized_path).await;
+        return session_diff_get_authoritative(&state, uri, normalized_path).await;
     }
 
-    let oc = state.opencode.status().await;
+    let query_directory = directory_from_uri_query(&uri);
+
+    let upstream = state
+        .opencode
+        .for_directory(query_directory.as_deref())
+        .await;
+    let mut oc = upstream.status().await;
+    if oc.restarting && is_idempotent_read(&method) {
+        oc = status_after_restart(&upstream, RESTART_DRAIN_WAIT).await;
+    }
     if oc.restarting || !oc.ready {
         return Ok(open_code_not_ready(&oc));
     }
-    let Some(bridge) = state.opencode.bridge().await else {
+    let Some(bridge) = upstream.bridge().await else {
         return Ok(open_code_unavailable(Some(&oc)));
     };
 
-    let query_directory = directory_from_uri_query(&uri);
-
-    let body = match axum::body::to_bytes(body, 50 * 1024 * 1024).await {
-        Ok(body) => body,
-        Err(_) => return Err(AppError::payload_too_large("Request body too large")),
-    };
+    if declared_content_length(&headers).is_some_and(|len| len > MAX_PROXY_BODY_BYTES) {
+        return Err(AppError::payload_too_large("Request body too large"));
+    }
 
     let upstream_path = format!("/{}", path);
     let target = match bridge.build_url(&upstream_path, Some(&uri)) {
@@ -1194,7 +1431,36 @@ pub(crate) async fn proxy_opencode_rest_inner(
         // OpenCode-compatible data: URLs before forwarding.
         let directory = query_directory.clone();
 
+        let body = match axum::body::to_bytes(body, MAX_PROXY_BODY_BYTES).await {
+            Ok(body) => body,
+            Err(_) => return Err(AppError::payload_too_large("Request body too large")),
+        };
         let body = if let Ok(mut json) = serde_json::from_slice::<serde_json::Value>(&body) {
+            crate::slash_commands::expand_prompt(&state, directory.as_deref(), &mut json).await?;
+
+            // Studio-only: `memoryIds` selects saved memory snippets to prepend.
+            let memory_ids: Vec<String> = json
+                .as_object_mut()
+                .and_then(|obj| obj.remove("memoryIds"))
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default();
+            // Without `?directory=`, fall back to the session's known project.
+            let memory_directory = directory.clone().or_else(|| {
+                path.split('/')
+                    .nth(1)
+                    .and_then(|sid| state.directory_session_index.directory_for_session(sid))
+            });
+            if let Some(memory_part) = crate::memory_snippets::memory_prompt_part(
+                state.studio_db.as_ref(),
+                memory_directory.as_deref(),
+                &memory_ids,
+            )
+            .await?
+                && let Some(parts) = json.get_mut("parts").and_then(|v| v.as_array_mut())
+            {
+                parts.insert(0, memory_part);
+            }
+
             if let Some(parts) = json.get_mut("parts").and_then(|v| v.as_array_mut()) {
                 // Optional: validate directory when provided, so serverPath can't escape the project.
                 let base_dir = if let Some(dir) = directory.as_deref() {
@@ -1203,7 +1469,12 @@ pub(crate) async fn proxy_opencode_rest_inner(
                     None
                 };
 
-                for part in parts.iter_mut() {
+                let text_max_chars =
+                    crate::attachment_text::max_chars_from_settings(&*state.settings.read().await);
+                // Extracted document text, inserted right after its file part.
+                let mut extracted_text: Vec<(usize, serde_json::Value)> = Vec::new();
+
+                for (index, part) in parts.iter_mut().enumerate() {
                     let Some(obj) = part.as_object_mut() else {
                         continue;
                     };
@@ -1213,11 +1484,22 @@ pub(crate) async fn proxy_opencode_rest_inner(
                     }
 
                     // Already a fully-formed attachment.
-                    if obj
+                    if let Some(url) = obj
                         .get("url")
                         .and_then(|v| v.as_str())
-                        .is_some_and(|u| !u.trim().is_empty())
+                        .filter(|u| !u.trim().is_empty())
                     {
+                        if let Some(max_chars) = text_max_chars
+                            && let Some(text_part) = crate::attachment_text::text_part_for_data_url(
+                                url,
+                                obj.get("mime").and_then(|v| v.as_str()),
+                                obj.get("filename").and_then(|v| v.as_str()),
+                                max_chars,
+                            )
+                            .await
+                        {
+                            extracted_text.push((index, text_part));
+                        }
                         obj.remove("serverPath");
                         continue;
                     }
@@ -1296,6 +1578,18 @@ pub(crate) async fn proxy_opencode_rest_inner(
                         }
                     };
 
+                    if let Some(max_chars) = text_max_chars
+                        && let Some(text_part) = crate::attachment_text::text_part_for_file(
+                            &abs,
+                            &mime,
+                            filename.as_deref(),
+                            max_chars,
+                        )
+                        .await
+                    {
+                        extracted_text.push((index, text_part));
+                    }
+
                     obj.insert("mime".to_string(), serde_json::Value::String(mime));
                     obj.insert("url".to_string(), serde_json::Value::String(url));
                     if let Some(name) = filename {
@@ -1303,6 +1597,10 @@ pub(crate) async fn proxy_opencode_rest_inner(
                     }
                     obj.remove("serverPath");
                 }
+
+                for (index, text_part) in extracted_text.into_iter().rev() {
+                    parts.insert(index + 1, text_part);
+                }
             }
 
             serde_json::to_vec(&json)
@@ -1317,67 +1615,88 @@ pub(crate) async fn proxy_opencode_rest_inner(
         let target_url = target.clone();
         let body_bytes = body.clone();
 
-        // Prefer OpenCode's native async route to avoid holding a long-running HTTP
-        // connection open for the entire generation (SSE drives the UI).
-        //
-        // We intentionally do not fall back to /message: this repo targets a modern
-        // OpenCode upstream that supports /prompt_async.
-        let async_target_url = match rewrite_opencode_prompt_async_url(&target_url) {
-            Some(url) => url,
-            None => {
-                return Err(AppError::bad_gateway(
-                    "OpenCode async prompt endpoint unavailable (expected /prompt_async)",
-                ));
-            }
-        };
+        let build_request = |url: &str| -> Option<reqwest::Request> {
+            let mut req = reqwest::Request::new(reqwest::Method::POST, url.parse().ok()?);
 
-        let mut req = reqwest::Request::new(
-            reqwest::Method::POST,
-            match async_target_url.parse() {
-                Ok(url) => url,
-                Err(_) => return Ok(open_code_unavailable(Some(&oc))),
-            },
-        );
-
-        // Copy request headers (minus hop-by-hop headers).
-        {
-            let req_headers = req.headers_mut();
-            for (k, v) in headers_in.iter() {
-                let name = k.as_str().to_ascii_lowercase();
-                if name == "host" || name == "connection" || name == "content-length" {
-                    continue;
+            // Copy request headers (minus hop-by-hop headers).
+            {
+                let req_headers = req.headers_mut();
+                for (k, v) in headers_in.iter() {
+                    let name = k.as_str().to_ascii_lowercase();
+                    if name == "host" || name == "connection" || name == "content-length" {
+                        continue;
+                    }
+                    if let Ok(header_name) =
+                        reqwest::header::HeaderName::from_bytes(k.as_str().as_bytes())
+                        && let Ok(header_value) =
+                            reqwest::header::HeaderValue::from_bytes(v.as_bytes())
+                    {
+                        req_headers.insert(header_name, header_value);
+                    }
                 }
-                if let Ok(header_name) =
-                    reqwest::header::HeaderName::from_bytes(k.as_str().as_bytes())
-                    && let Ok(header_value) = reqwest::header::HeaderValue::from_bytes(v.as_bytes())
+
+                if let Some(directory) = directory.as_deref()
+                    && !req_headers.contains_key("x-opencode-directory")
+                    && let Ok(value) = reqwest::header::HeaderValue::from_str(directory)
                 {
-                    req_headers.insert(header_name, header_value);
+                    req_headers.insert(
+                        reqwest::header::HeaderName::from_static("x-opencode-directory"),
+                        value,
+                    );
                 }
             }
+            *req.body_mut() = Some(reqwest::Body::from(body_bytes.clone()));
+            Some(req)
+        };
 
-            if let Some(directory) = directory.as_deref()
-                && !req_headers.contains_key("x-opencode-directory")
-                && let Ok(value) = reqwest::header::HeaderValue::from_str(directory)
-            {
-                req_headers.insert(
-                    reqwest::header::HeaderName::from_static("x-opencode-directory"),
-                    value,
-                );
+        // Prefer OpenCode's native async route to avoid holding a long-running HTTP
+        // connection open for the entire generation (SSE drives the UI).
+        let async_target_url = if bridge.supports_prompt_async() {
+            rewrite_opencode_prompt_async_url(&target_url)
+        } else {
+            None
+        };
+
+        let mut queued = false;
+        if let Some(async_target_url) = async_target_url {
+            let Some(req) = build_request(&async_target_url) else {
+                return Ok(open_code_unavailable(Some(&oc)));
+            };
+            let resp = bridge
+                .client
+                .execute(req)
+                .await
+                .map_err(|_| AppError::bad_gateway("OpenCode request failed"))?;
+
+            if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                // Older upstream without /prompt_async; remember and fall back.
+                bridge.disable_prompt_async();
+            } else if !resp.status().is_success() {
+                return Err(AppError::bad_gateway(format!(
+                    "OpenCode async prompt failed ({})",
+                    resp.status().as_u16()
+                )));
+            } else {
+                queued = true;
             }
         }
-        *req.body_mut() = Some(reqwest::Body::from(body_bytes));
-
-        let resp = bridge
-            .client
-            .execute(req)
-            .await
-            .map_err(|_| AppError::bad_gateway("OpenCode request failed"))?;
-
-        if !resp.status().is_success() {
-            return Err(AppError::bad_gateway(format!(
-                "OpenCode async prompt failed ({})",
-                resp.status().as_u16()
-            )));
+
+        if !queued {
+            // Older upstreams only have the blocking /message route; run it detached so
+            // the client still gets an immediate 202 and SSE drives the UI.
+            let Some(req) = build_request(&target_url) else {
+                return Ok(open_code_unavailable(Some(&oc)));
+            };
+            tokio::spawn(async move {
+                match bridge.sse_client.execute(req).await {
+                    Ok(resp) if !resp.status().is_success() => tracing::warn!(
+                        status = resp.status().as_u16(),
+                        "OpenCode prompt via /message failed"
+                    ),
+                    Ok(_) => {}
+                    Err(err) => tracing::warn!(error = %err, "OpenCode prompt via /message failed"),
+                }
+            });
         }
 
         let mut out = Json(serde_json::json!({ "queued": true })).into_response();
@@ -1398,7 +1717,10 @@ pub(crate) async fn proxy_opencode_rest_inner(
         let req_headers = req.headers_mut();
         for (k, v) in headers.iter() {
             let name = k.as_str().to_ascii_lowercase();
-            if name == "host" || name == "connection" || name == "content-length" {
+            if matches!(
+                name.as_str(),
+                "host" | "connection" | "content-length" | "transfer-encoding"
+            ) {
                 continue;
             }
             if let Ok(header_name) = reqwest::header::HeaderName::from_bytes(k.as_str().as_bytes())
@@ -1418,13 +1740,42 @@ pub(crate) async fn proxy_opencode_rest_inner(
             );
         }
     }
-    *req.body_mut() = Some(reqwest::Body::from(body));
-
-    let resp = bridge
-        .client
-        .execute(req)
-        .await
-        .map_err(|_| AppError::bad_gateway("OpenCode request failed"))?;
+    // A read that loses its connection because OpenCode went down mid-flight
+    // gets one retry after the restart settles, so its (normally empty) body is
+    // buffered; everything else streams straight through.
+    let retry = if is_idempotent_read(&method) {
+        let body = match axum::body::to_bytes(body, MAX_PROXY_BODY_BYTES).await {
+            Ok(body) => body,
+            Err(_) => return Err(AppError::payload_too_large("Request body too large")),
+        };
+        *req.body_mut() = Some(reqwest::Body::from(body));
+        req.try_clone()
+    } else {
+        *req.body_mut() = Some(reqwest::Body::wrap_stream(limited_body_stream(
+            body,
+            MAX_PROXY_BODY_BYTES,
+        )));
+        None
+    };
+    let resp = match bridge.client.execute(req).await {
+        Ok(resp) => resp,
+        Err(_) => {
+            let Some(retry) = retry else {
+                return Err(AppError::bad_gateway("OpenCode request failed"));
+            };
+            // Give the manager a moment to flag the restart that dropped us.
+            tokio::time::sleep(RESTART_DRAIN_POLL).await;
+            let oc = status_after_restart(&upstream, RESTART_DRAIN_WAIT).await;
+            if oc.restarting || !oc.ready {
+                return Ok(open_code_not_ready(&oc));
+            }
+            bridge
+                .client
+                .execute(retry)
+                .await
+                .map_err(|_| AppError::bad_gateway("OpenCode request failed"))?
+        }
+    };
 
     let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
 
@@ -1435,6 +1786,7 @@ pub(crate) async fn proxy_opencode_rest_inner(
         // Keep sidebar aggregates consistent even when upstream session.deleted SSE
         // is delayed or dropped.
         state.directory_session_index.remove_summary(&session_id);
+        crate::session_tags::forget(&state, std::slice::from_ref(&session_id));
     }
 
     let mut builder = axum::http::Response::builder().status(status);
@@ -1452,14 +1804,18 @@ pub(crate) async fn proxy_opencode_rest_inner(
         }
     }
 
+    // Only chat session payloads are rewritten; every other response streams.
+    if !(status.is_success() && should_sanitize_chat_session_response(&path)) {
+        return Ok(builder
+            .body(axum::body::Body::from_stream(resp.bytes_stream()))
+            .unwrap_or_else(|_| StatusCode::BAD_GATEWAY.into_response()));
+    }
+
     match resp.bytes().await {
         Ok(bytes) => {
             let mut body_bytes = bytes.to_vec();
 
-            if status.is_success()
-                && should_sanitize_chat_session_response(&path)
-                && let Ok(mut payload) = serde_json::from_slice::<serde_json::Value>(&body_bytes)
-            {
+            if let Ok(mut payload) = serde_json::from_slice::<serde_json::Value>(&body_bytes) {
                 sanitize_chat_session_response_payload(&mut payload);
                 if let Ok(encoded) = serde_json::to_vec(&payload) {
                     body_bytes = encoded;
@@ -1511,6 +1867,11 @@ pub(crate) struct ActivityDetailPolicy {
     pub(crate) enabled: bool,
     pub(crate) expanded: HashSet<String>,
     pub(crate) expanded_tools: HashSet<String>,
+    /// Row limit for structured table previews of CSV/JSON tool output; `None`
+    /// when `chatToolOutputTablePreview` is off.
+    pub(crate) table_preview_rows: Option<usize>,
+    /// Output size caps; `None` when `chatToolOutputRetention` is off.
+    pub(crate) tool_output_retention: Option<crate::tool_output_retention::ToolOutputRetention>,
 }
 
 const DEFAULT_ACTIVITY_EXPAND_KEYS: [&str; 9] = [
@@ -1579,7 +1940,43 @@ pub(crate) fn activity_detail_policy_from_settings(
         enabled,
         expanded,
         expanded_tools,
+        table_preview_rows: tool_output_table_rows_from_settings(settings),
+        tool_output_retention: crate::tool_output_retention::ToolOutputRetention::from_settings(
+            settings,
+        ),
+    }
+}
+
+/// Studio-side enrichment and limits for tool output. Runs before collapsed
+/// pruning drops `state.output`; tables are detected on the full text.
+fn apply_tool_output_policies(part: &mut serde_json::Value, detail: &ActivityDetailPolicy) {
+    if let Some(rows) = detail.table_preview_rows {
+        crate::tool_output_table::attach_tool_output_table(part, rows);
+    }
+    if let Some(retention) = detail.tool_output_retention.as_ref() {
+        retention.apply(part);
+    }
+}
+
+pub(crate) fn tool_output_table_rows_from_settings(
+    settings: &crate::settings::Settings,
+) -> Option<usize> {
+    let enabled = settings
+        .extra
+        .get("chatToolOutputTablePreview")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if !enabled {
+        return None;
     }
+    let rows = settings
+        .extra
+        .get("chatToolOutputTablePreviewRows")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as usize)
+        .filter(|v| *v > 0)
+        .unwrap_or(crate::tool_output_table::DEFAULT_TABLE_PREVIEW_ROWS);
+    Some(rows)
 }
 
 pub(crate) fn activity_filter_from_settings(
@@ -2815,9 +3212,11 @@ fn prune_part_for_sse(
     message_id: &str,
     part_id: Option<&str>,
     include_detail: bool,
+    detail: &ActivityDetailPolicy,
 ) {
     if is_tool_part(part_type) {
         prune_tool_metadata(part);
+        apply_tool_output_policies(part, detail);
     } else {
         prune_part_metadata(part, part_type);
         if part_type == "patch"
@@ -3107,6 +3506,7 @@ fn sanitize_message_part_event_properties(
         &message_id,
         part_id.as_deref(),
         include_detail,
+        detail,
     );
 
     props.clear();
@@ -3988,6 +4388,7 @@ pub(crate) fn filter_message_payload(
 
             if is_tool_part(&part_type) {
                 prune_tool_metadata(part);
+                apply_tool_output_policies(part, detail);
                 if !include_detail {
                     if let Some(obj) = part.as_object_mut() {
                         let tool_id = obj
@@ -4139,7 +4540,11 @@ pub(crate) async fn session_status_get(
         return Ok(out);
     }
 
-    let oc = state.opencode.status().await;
+    let upstream = state
+        .opencode
+        .for_directory(query_directory.as_deref())
+        .await;
+    let oc = upstream.status().await;
     if oc.restarting || !oc.ready {
         let mut payload = local_status_snapshot(
             state.as_ref(),
@@ -4161,7 +4566,7 @@ pub(crate) async fn session_status_get(
         );
         return Ok(out);
     }
-    let Some(bridge) = state.opencode.bridge().await else {
+    let Some(bridge) = upstream.bridge().await else {
         return Ok(open_code_unavailable(Some(&oc)));
     };
 
@@ -4369,11 +4774,15 @@ pub(crate) async fn lsp_list(
     uri: Uri,
     Query(q): Query<AttentionListQuery>,
 ) -> ApiResult<Response> {
-    let oc = state.opencode.status().await;
+    let upstream = state
+        .opencode
+        .for_directory(directory_from_uri_query(&uri).as_deref())
+        .await;
+    let oc = status_after_restart(&upstream, RESTART_DRAIN_WAIT).await;
     if oc.restarting || !oc.ready {
         return Ok(open_code_not_ready(&oc));
     }
-    let Some(bridge) = state.opencode.bridge().await else {
+    let Some(bridge) = upstream.bridge().await else {
         return Ok(open_code_unavailable(Some(&oc)));
     };
 
@@ -4429,11 +4838,15 @@ pub(crate) async fn mcp_status(
     State(state): State<Arc<crate::AppState>>,
     uri: Uri,
 ) -> ApiResult<Response> {
-    let oc = state.opencode.status().await;
+    let upstream = state
+        .opencode
+        .for_directory(directory_from_uri_query(&uri).as_deref())
+        .await;
+    let oc = status_after_restart(&upstream, RESTART_DRAIN_WAIT).await;
     if oc.restarting || !oc.ready {
         return Ok(open_code_not_ready(&oc));
     }
-    let Some(bridge) = state.opencode.bridge().await else {
+    let Some(bridge) = upstream.bridge().await else {
         return Ok(open_code_unavailable(Some(&oc)));
     };
 
@@ -4477,11 +4890,15 @@ pub(crate) async fn permission_list(
     uri: Uri,
     Query(q): Query<AttentionListQuery>,
 ) -> ApiResult<Response> {
-    let oc = state.opencode.status().await;
+    let upstream = state
+        .opencode
+        .for_directory(directory_from_uri_query(&uri).as_deref())
+        .await;
+    let oc = status_after_restart(&upstream, RESTART_DRAIN_WAIT).await;
     if oc.restarting || !oc.ready {
         return Ok(open_code_not_ready(&oc));
     }
-    let Some(bridge) = state.opencode.bridge().await else {
+    let Some(bridge) = upstream.bridge().await else {
         return Ok(open_code_unavailable(Some(&oc)));
     };
 
@@ -4541,11 +4958,15 @@ pub(crate) async fn question_list(
     uri: Uri,
     Query(q): Query<AttentionListQuery>,
 ) -> ApiResult<Response> {
-    let oc = state.opencode.status().await;
+    let upstream = state
+        .opencode
+        .for_directory(directory_from_uri_query(&uri).as_deref())
+        .await;
+    let oc = status_after_restart(&upstream, RESTART_DRAIN_WAIT).await;
     if oc.restarting || !oc.ready {
         return Ok(open_code_not_ready(&oc));
     }
-    let Some(bridge) = state.opencode.bridge().await else {
+    let Some(bridge) = upstream.bridge().await else {
         return Ok(open_code_unavailable(Some(&oc)));
     };
 
@@ -4600,7 +5021,7 @@ pub(crate) async fn question_list(
     Ok(out)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct SessionLocateQuery {
     session_id: String,
@@ -4806,6 +5227,23 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn sse_block_to_ws_text_wraps_payload_with_event_id() {
+        let text =
+            sse_block_to_ws_text(b"id: 42\ndata: {\"type\":\"session.idle\"}\n\n").expect("frame");
+        let frame: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(
+            frame,
+            json!({"id": "42", "payload": {"type": "session.idle"}})
+        );
+
+        let text = sse_block_to_ws_text(b"data: line1\ndata: line2\n\n").expect("frame");
+        let frame: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(frame, json!({"payload": "line1\nline2"}));
+
+        assert!(sse_block_to_ws_text(b": keepalive\n\n").is_none());
+    }
+
     #[test]
     fn directory_matches_project_root_is_windows_case_insensitive() {
         let directory =
@@ -4883,6 +5321,40 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn limited_body_stream_fails_past_limit() {
+        let within: Vec<_> = limited_body_stream(axum::body::Body::from(vec![0u8; 8]), 8)
+            .collect()
+            .await;
+        assert!(within.iter().all(|chunk| chunk.is_ok()));
+
+        let over: Vec<_> = limited_body_stream(axum::body::Body::from(vec![0u8; 16]), 8)
+            .collect()
+            .await;
+        assert!(over.last().is_some_and(|chunk| chunk.is_err()));
+    }
+
+    #[tokio::test]
+    async fn status_after_restart_returns_at_once_when_not_restarting() {
+        let upstream = crate::opencode::OpenCodeManager::new(
+            "127.0.0.1".to_string(),
+            Some(1),
+            true,
+            None,
+            None,
+            crate::ui_auth::UiAuth::Disabled,
+        );
+        let oc = tokio::time::timeout(
+            Duration::from_secs(1),
+            status_after_restart(&upstream, Duration::from_secs(60)),
+        )
+        .await
+        .expect("no wait outside a restart");
+        assert!(!oc.restarting);
+        assert!(is_idempotent_read(&Method::GET));
+        assert!(!is_idempotent_read(&Method::POST));
+    }
+
     #[test]
     fn directory_from_uri_query_parses_directory_value() {
         let uri: Uri = "/api/session?directory=%2Ftmp%2Fproj&x=1"
@@ -4935,6 +5407,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
         filter_message_payload(&mut payload, &filter, &detail);
 
@@ -5120,6 +5594,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
         filter_message_payload(&mut payload, &filter, &detail);
 
@@ -5275,6 +5751,62 @@ mod tests {
             .collect::<HashSet<String>>();
 
         assert_eq!(detail.expanded_tools, default_tool_filters);
+        assert_eq!(detail.table_preview_rows, None);
+    }
+
+    #[test]
+    fn filter_message_payload_attaches_table_preview_to_collapsed_tool_output() {
+        let mut payload = json!([
+            {
+                "info": {"id": "msg_table", "role": "assistant"},
+                "parts": [
+                    {
+                        "id": "p_bash",
+                        "type": "tool",
+                        "tool": "bash",
+                        "state": {
+                            "status": "completed",
+                            "input": {"command": "cat data.csv"},
+                            "output": "id,name\n1,alpha\n2,beta\n3,gamma\n"
+                        }
+                    }
+                ]
+            }
+        ]);
+
+        let filter = ActivityFilter {
+            allowed: ["tool"].into_iter().map(String::from).collect(),
+            tool_allowed: HashSet::new(),
+            tool_explicit: false,
+            show_reasoning: false,
+            show_justification: false,
+        };
+        let mut settings = crate::settings::Settings::default();
+        settings
+            .extra
+            .insert("chatToolOutputTablePreview".to_string(), json!(true));
+        settings
+            .extra
+            .insert("chatToolOutputTablePreviewRows".to_string(), json!(2));
+        settings.extra.insert(
+            "chatActivityDefaultExpandedToolFilters".to_string(),
+            json!([]),
+        );
+        let detail = activity_detail_policy_from_settings(&settings);
+        assert_eq!(detail.table_preview_rows, Some(2));
+
+        filter_message_payload(&mut payload, &filter, &detail);
+
+        let part = &payload[0]["parts"][0];
+        assert_eq!(part["ocLazy"], json!(true));
+        assert!(part["state"].get("output").is_none());
+        assert_eq!(part["table"]["columns"], json!(["id", "name"]));
+        assert_eq!(
+            part["table"]["rows"],
+            json!([["1", "alpha"], ["2", "beta"]])
+        );
+        assert_eq!(part["table"]["totalRows"], json!(3));
+        assert_eq!(part["table"]["truncated"], json!(true));
     }
 
     #[test]
@@ -5319,6 +5851,8 @@ mod tests {
             enabled: true,
             expanded: HashSet::new(),
             expanded_tools: ["read"].into_iter().map(String::from).collect(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         filter_message_payload(&mut payload, &filter, &detail);
@@ -5401,6 +5935,8 @@ mod tests {
             enabled: true,
             expanded: HashSet::new(),
             expanded_tools: ["unknown"].into_iter().map(String::from).collect(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         filter_message_payload(&mut payload, &filter, &detail);
@@ -5474,6 +6010,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         filter_message_payload(&mut payload, &filter, &detail);
@@ -5528,6 +6066,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         assert!(sanitize_sse_event_data(&mut event, &filter, &detail));
@@ -5578,6 +6118,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         assert!(sanitize_sse_event_data(&mut event, &filter, &detail));
@@ -5659,6 +6201,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         assert!(sanitize_sse_event_data(&mut event, &filter, &detail));
@@ -5708,6 +6252,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         assert!(!sanitize_sse_event_data(&mut event, &filter, &detail));
@@ -5745,6 +6291,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         assert!(sanitize_sse_event_data(&mut event, &filter, &detail));
@@ -5794,6 +6342,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         assert!(sanitize_sse_event_data(&mut event, &filter, &detail));
@@ -5854,6 +6404,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         assert!(sanitize_sse_event_data(&mut event, &filter, &detail));
@@ -5931,6 +6483,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         filter_message_payload(&mut payload, &filter, &detail);
@@ -6185,7 +6739,7 @@ mod tests {
     }
 
     #[test]
-    fn build_session_diff_from_messages_aggregates_full_history_for_pagination() {
+    fn session_diff_rows_aggregate_full_history_for_pagination() {
         let messages = json!([
             {
                 "info": {"id": "msg_old", "sessionID": "ses_1"},
@@ -6225,8 +6779,10 @@ mod tests {
             }
         ]);
 
-        let computed =
-            build_session_diff_from_messages(messages.as_array().expect("messages"), Some("/repo"));
+        let computed = merge_session_diff_rows(
+            session_diff_rows_from_messages(messages.as_array().expect("messages")),
+            Some("/repo"),
+        );
 
         let files = computed.iter().map(|v| v.file.as_str()).collect::<Vec<_>>();
         assert_eq!(files, vec!["src/new.ts", "src/old.ts"]);
@@ -6240,7 +6796,7 @@ mod tests {
     }
 
     #[test]
-    fn build_session_diff_from_messages_reads_result_metadata_and_result_diff_keys() {
+    fn session_diff_rows_read_result_metadata_and_result_diff_keys() {
         let messages = json!([
             {
                 "info": {"id": "msg_result_meta", "sessionID": "ses_1"},
@@ -6282,8 +6838,10 @@ mod tests {
             }
         ]);
 
-        let computed =
-            build_session_diff_from_messages(messages.as_array().expect("messages"), Some("/repo"));
+        let computed = merge_session_diff_rows(
+            session_diff_rows_from_messages(messages.as_array().expect("messages")),
+            Some("/repo"),
+        );
         let files = computed.iter().map(|v| v.file.as_str()).collect::<Vec<_>>();
 
         assert_eq!(
@@ -6322,6 +6880,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         assert!(!sanitize_sse_event_data(&mut event, &filter, &detail));
@@ -6351,6 +6911,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         assert!(sanitize_sse_event_data(&mut event, &filter, &detail));
@@ -6459,6 +7021,8 @@ mod tests {
             enabled: false,
             expanded: HashSet::new(),
             expanded_tools: HashSet::new(),
+            table_preview_rows: None,
+            tool_output_retention: None,
         };
 
         filter_message_payload(&mut payload, &filter, &detail);
@@ -1,5 +1,6 @@
 use std::collections::{HashSet, VecDeque};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, LazyLock, Mutex};
 use std::time::SystemTime;
 
@@ -12,6 +13,7 @@ use axum::{
 };
 use dashmap::DashMap;
 use futures_util::stream::{self as futures_stream, StreamExt as _};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use tokio::fs;
@@ -20,6 +22,7 @@ use tokio::process::Command;
 mod consistency;
 mod fallback;
 mod sqlite_dao;
+mod warmup;
 
 use consistency::{DEFAULT_DEGRADED_RETRY_AFTER_MS, ResponseConsistency};
 use fallback::{ReadJsonError, ReadJsonOutcome, mark_consistency_read_error, read_json_value};
@@ -28,6 +31,7 @@ use sqlite_dao::{
     load_session_records_by_ids_from_sqlite, load_session_records_by_parent_ids_from_sqlite,
     load_session_records_from_sqlite,
 };
+pub(crate) use warmup::{SessionIndexStatus, session_index_status, spawn_session_index_warmup};
 
 #[derive(Clone)]
 struct SessionRecord {
@@ -38,7 +42,7 @@ struct SessionRecord {
 }
 
 use crate::ApiResult;
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, JsonSchema)]
 pub(crate) struct SessionListQuery {
     pub directory: Option<String>,
     pub scope: Option<String>,
@@ -54,9 +58,15 @@ pub(crate) struct SessionListQuery {
     pub ids: Option<String>,
     #[serde(rename = "focusSessionId")]
     pub focus_session_id: Option<String>,
+    /// Comma-separated tags; sessions must carry all of them.
+    pub tag: Option<String>,
+    pub favorite: Option<String>,
+    /// Archived sessions are hidden unless `archived=true` (only archived)
+    /// or `archived=all`.
+    pub archived: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, JsonSchema)]
 pub(crate) struct SessionMessagesQuery {
     pub offset: Option<String>,
     pub limit: Option<String>,
@@ -98,9 +108,35 @@ struct SessionMessageListResponse {
     consistency: Option<ResponseConsistency>,
 }
 
-const DIR_CACHE_LIMIT: usize = 128;
-const FILE_CACHE_LIMIT: usize = 512;
-const SESSION_SCAN_CONCURRENCY: usize = 12;
+const DEFAULT_DIR_CACHE_LIMIT: usize = 128;
+const DEFAULT_FILE_CACHE_LIMIT: usize = 512;
+const DEFAULT_SESSION_SCAN_CONCURRENCY: usize = 12;
+
+// Overridable from the CLI / runtime config; see `configure_storage_scan`.
+static DIR_CACHE_LIMIT: AtomicUsize = AtomicUsize::new(DEFAULT_DIR_CACHE_LIMIT);
+static FILE_CACHE_LIMIT: AtomicUsize = AtomicUsize::new(DEFAULT_FILE_CACHE_LIMIT);
+static SESSION_SCAN_CONCURRENCY: AtomicUsize = AtomicUsize::new(DEFAULT_SESSION_SCAN_CONCURRENCY);
+
+/// Apply storage scan knobs; `None` or zero keeps the default.
+pub(crate) fn configure_storage_scan(
+    scan_concurrency: Option<usize>,
+    dir_cache_limit: Option<usize>,
+    file_cache_limit: Option<usize>,
+) {
+    for (knob, value) in [
+        (&SESSION_SCAN_CONCURRENCY, scan_concurrency),
+        (&DIR_CACHE_LIMIT, dir_cache_limit),
+        (&FILE_CACHE_LIMIT, file_cache_limit),
+    ] {
+        if let Some(value) = value.filter(|v| *v > 0) {
+            knob.store(value, Ordering::Relaxed);
+        }
+    }
+}
+
+fn session_scan_concurrency() -> usize {
+    SESSION_SCAN_CONCURRENCY.load(Ordering::Relaxed)
+}
 
 #[derive(Debug, Clone)]
 struct DirectoryCacheEntry {
@@ -156,7 +192,7 @@ impl OpenCodeStorageCache {
             order.remove(pos);
         }
         order.push_back(key.clone());
-        if order.len() > DIR_CACHE_LIMIT
+        if order.len() > DIR_CACHE_LIMIT.load(Ordering::Relaxed)
             && let Some(evicted) = order.pop_front()
         {
             self.dir_cache.remove(&evicted);
@@ -169,7 +205,7 @@ impl OpenCodeStorageCache {
             order.remove(pos);
         }
         order.push_back(key.clone());
-        if order.len() > FILE_CACHE_LIMIT
+        if order.len() > FILE_CACHE_LIMIT.load(Ordering::Relaxed)
             && let Some(evicted) = order.pop_front()
         {
             self.file_cache.remove(&evicted);
@@ -360,7 +396,7 @@ async fn git_root_commit(dir: &Path) -> Result<Option<String>, std::io::Error> {
     Ok(roots.into_iter().next())
 }
 
-async fn project_id_for_directory(directory: &str) -> String {
+pub(crate) async fn project_id_for_directory(directory: &str) -> String {
     let git_dir = match find_git_dir(Path::new(directory)).await {
         Some(dir) => dir,
         None => return "global".to_string(),
@@ -769,6 +805,16 @@ pub async fn session_list(
         Err(resp) => return Ok(*resp),
     };
     let term = query.search.map(|t| t.to_lowercase());
+    // Sessions requested by id are returned even when archived.
+    let archived = match query.archived.as_deref() {
+        None if !ids_filter.is_empty() => crate::session_tags::ArchivedFilter::Include,
+        raw => crate::session_tags::ArchivedFilter::parse(raw),
+    };
+    let tag_filter = crate::session_tags::SessionTagFilter::parse(
+        query.tag.as_deref(),
+        parse_boolish(query.favorite),
+        archived,
+    );
 
     let directory = resolve_directory(query_directory.as_deref(), &headers);
     let project_id = project_id_for_directory(&directory).await;
@@ -1087,7 +1133,7 @@ pub async fn session_list(
                         )
                     }
                 }))
-                .buffer_unordered(SESSION_SCAN_CONCURRENCY)
+                .buffer_unordered(session_scan_concurrency())
                 .collect::<Vec<_>>()
                 .await;
 
@@ -1124,6 +1170,8 @@ pub async fn session_list(
         }
     }
 
+    tag_filter.retain(&mut records, |record| record.id.as_str());
+
     if roots || include_children || focus_session_id.is_some() {
         backfill_missing_parent_records(&state, &mut records).await;
     }
@@ -1604,26 +1652,9 @@ pub async fn session_message_part_get(
         ));
     }
 
-    let sqlite = load_session_message_part_from_sqlite(sid, mid, pid).await;
-    let (info, part) = if let Some((info, part)) = sqlite {
-        (info, part)
-    } else {
-        let message_path = crate::persistence_paths::opencode_messages_dir()
-            .join(sid)
-            .join(format!("{mid}.json"));
-        let (info, _) = match read_json_value(&message_path).await {
-            Ok(value) => value,
-            Err(err) => return Ok(read_json_error_response(&message_path, err)),
-        };
-
-        let part_path = crate::persistence_paths::opencode_message_parts_dir()
-            .join(mid)
-            .join(format!("{pid}.json"));
-        let (part, _) = match read_json_value(&part_path).await {
-            Ok(value) => value,
-            Err(err) => return Ok(read_json_error_response(&part_path, err)),
-        };
-        (info, part)
+    let (info, part) = match load_session_message_part_local(sid, mid, pid).await {
+        Ok(value) => value,
+        Err(resp) => return Ok(resp),
     };
 
     let mut payload = json!([
@@ -1639,6 +1670,10 @@ pub async fn session_message_part_get(
         enabled: false,
         expanded: std::collections::HashSet::new(),
         expanded_tools: std::collections::HashSet::new(),
+        table_preview_rows: crate::opencode_proxy::tool_output_table_rows_from_settings(&settings),
+        tool_output_retention: crate::tool_output_retention::ToolOutputRetention::from_settings(
+            &settings,
+        ),
     };
     crate::opencode_proxy::filter_message_payload(&mut payload, &filter, &detail);
 
@@ -1660,22 +1695,133 @@ pub async fn session_message_part_get(
         }
     };
 
-    if let Some(obj) = part.as_object_mut() {
-        obj.insert("sessionId".to_string(), Value::String(sid.to_string()));
-        obj.insert("sessionID".to_string(), Value::String(sid.to_string()));
-        obj.insert("messageId".to_string(), Value::String(mid.to_string()));
-        obj.insert("messageID".to_string(), Value::String(mid.to_string()));
-        obj.insert("partId".to_string(), Value::String(pid.to_string()));
-        obj.insert("partID".to_string(), Value::String(pid.to_string()));
-        if obj
-            .get("id")
-            .and_then(|v| v.as_str())
-            .is_none_or(|v| v.trim().is_empty())
-        {
-            obj.insert("id".to_string(), Value::String(pid.to_string()));
+    stamp_part_ids(&mut part, sid, mid, pid);
+    Ok(Json(part).into_response())
+}
+
+/// Read one message info + part from sqlite, falling back to the legacy JSON
+/// storage layout.
+pub(crate) async fn load_session_message_part_local(
+    sid: &str,
+    mid: &str,
+    pid: &str,
+) -> Result<(Value, Value), Response> {
+    if let Some(found) = load_session_message_part_from_sqlite(sid, mid, pid).await {
+        return Ok(found);
+    }
+
+    let message_path = crate::persistence_paths::opencode_messages_dir()
+        .join(sid)
+        .join(format!("{mid}.json"));
+    let (info, _) = read_json_value(&message_path)
+        .await
+        .map_err(|err| read_json_error_response(&message_path, err))?;
+
+    let part_path = crate::persistence_paths::opencode_message_parts_dir()
+        .join(mid)
+        .join(format!("{pid}.json"));
+    let (part, _) = read_json_value(&part_path)
+        .await
+        .map_err(|err| read_json_error_response(&part_path, err))?;
+    Ok((info, part))
+}
+
+fn stamp_part_ids(part: &mut Value, sid: &str, mid: &str, pid: &str) {
+    let Some(obj) = part.as_object_mut() else {
+        return;
+    };
+    obj.insert("sessionId".to_string(), Value::String(sid.to_string()));
+    obj.insert("sessionID".to_string(), Value::String(sid.to_string()));
+    obj.insert("messageId".to_string(), Value::String(mid.to_string()));
+    obj.insert("messageID".to_string(), Value::String(mid.to_string()));
+    obj.insert("partId".to_string(), Value::String(pid.to_string()));
+    obj.insert("partID".to_string(), Value::String(pid.to_string()));
+    if obj
+        .get("id")
+        .and_then(|v| v.as_str())
+        .is_none_or(|v| v.trim().is_empty())
+    {
+        obj.insert("id".to_string(), Value::String(pid.to_string()));
+    }
+}
+
+fn find_part_in_message(message: &Value, pid: &str) -> Option<Value> {
+    message
+        .get("parts")
+        .and_then(|v| v.as_array())?
+        .iter()
+        .find(|part| part.get("id").and_then(|v| v.as_str()) == Some(pid))
+        .cloned()
+}
+
+/// Ask OpenCode for the message when the part is not in local storage yet
+/// (e.g. still streaming and not flushed).
+async fn fetch_session_message_part_upstream(
+    state: &crate::AppState,
+    sid: &str,
+    mid: &str,
+    pid: &str,
+) -> Option<Value> {
+    let directory = state
+        .directory_session_index
+        .summary(sid)
+        .map(|summary| summary.directory_path);
+    let upstream = state.opencode.running_for_directory(directory.as_deref());
+    let oc = upstream.status().await;
+    if oc.restarting || !oc.ready {
+        return None;
+    }
+    let bridge = upstream.bridge().await?;
+    let path = format!(
+        "/session/{}/message/{}",
+        urlencoding::encode(sid),
+        urlencoding::encode(mid)
+    );
+    let target = bridge.build_url(&path, None).ok()?;
+    let resp = bridge.client.get(target).send().await.ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    let message = resp.json::<Value>().await.ok()?;
+    find_part_in_message(&message, pid)
+}
+
+/// Full detail for a part the list endpoints marked `ocLazy`. Unlike
+/// `session_message_part_get`, no activity pruning is applied; only the tool
+/// output retention cap (whose archive holds the rest).
+pub async fn session_message_part_detail_get(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath((session_id, message_id, part_id)): AxumPath<(String, String, String)>,
+) -> ApiResult<Response> {
+    let sid = session_id.trim();
+    let mid = message_id.trim();
+    let pid = part_id.trim();
+    if sid.is_empty() || mid.is_empty() || pid.is_empty() {
+        return Ok(json_response(
+            StatusCode::BAD_REQUEST,
+            json!({"error": "session_id, message_id, and part_id are required"}),
+        ));
+    }
+
+    let mut part = match load_session_message_part_local(sid, mid, pid).await {
+        Ok((_, part)) => part,
+        Err(local_err) => {
+            match fetch_session_message_part_upstream(state.as_ref(), sid, mid, pid).await {
+                Some(part) => part,
+                None => return Ok(local_err),
+            }
         }
+    };
+
+    let retention = {
+        let settings = state.settings.read().await;
+        crate::tool_output_retention::ToolOutputRetention::from_settings(&settings)
+    };
+    if let Some(retention) = retention {
+        retention.apply(&mut part);
     }
 
+    stamp_part_ids(&mut part, sid, mid, pid);
     Ok(Json(part).into_response())
 }
 
@@ -1742,6 +1888,32 @@ mod tests {
         assert!(!parse_boolish(None));
     }
 
+    #[test]
+    fn configure_storage_scan_keeps_defaults_for_unset_and_zero_knobs() {
+        let _env_lock = ENV_LOCK.lock().unwrap();
+        let limits = || {
+            (
+                session_scan_concurrency(),
+                DIR_CACHE_LIMIT.load(Ordering::Relaxed),
+                FILE_CACHE_LIMIT.load(Ordering::Relaxed),
+            )
+        };
+
+        configure_storage_scan(Some(3), Some(0), None);
+        assert_eq!(
+            limits(),
+            (3, DEFAULT_DIR_CACHE_LIMIT, DEFAULT_FILE_CACHE_LIMIT)
+        );
+        configure_storage_scan(Some(0), Some(256), Some(1024));
+        assert_eq!(limits(), (3, 256, 1024));
+
+        configure_storage_scan(
+            Some(DEFAULT_SESSION_SCAN_CONCURRENCY),
+            Some(DEFAULT_DIR_CACHE_LIMIT),
+            Some(DEFAULT_FILE_CACHE_LIMIT),
+        );
+    }
+
     #[test]
     fn normalize_dir_for_compare_handles_windows_drive_case_and_encoded_input() {
         let plain = normalize_dir_for_compare("C:\\Users\\Alice\\Repo\\").expect("plain");
@@ -2223,6 +2395,9 @@ mod tests {
                 include_children: None,
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -2264,6 +2439,9 @@ mod tests {
                 include_children: None,
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -2305,6 +2483,9 @@ mod tests {
                 include_children: None,
                 ids: Some("ses_c,ses_b,ses_a".to_string()),
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -2330,6 +2511,91 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn session_list_filters_by_tag_and_favorite() {
+        let _env_lock = ENV_LOCK.lock().unwrap();
+        STORAGE_CACHE.clear();
+
+        let tmp = unique_tmp_dir("session-list-tags");
+        tokio::fs::create_dir_all(&tmp).await.unwrap();
+        let _home = EnvVarGuard::set("HOME", tmp.to_string_lossy().to_string());
+        let proj = tmp.join("proj");
+        tokio::fs::create_dir_all(&proj).await.unwrap();
+        let session_dir = tmp
+            .join(".local")
+            .join("share")
+            .join("opencode")
+            .join("storage")
+            .join("sessions")
+            .join("global");
+        for (id, updated) in [
+            ("ses_tag_a", 100.0),
+            ("ses_tag_b", 200.0),
+            ("ses_tag_c", 300.0),
+        ] {
+            write_json(
+                &session_dir.join(format!("{id}.json")),
+                &serde_json::json!({
+                    "id": id,
+                    "directory": proj.to_string_lossy(),
+                    "title": id,
+                    "time": {"updated": updated}
+                }),
+            )
+            .await;
+        }
+
+        let state = dummy_state().await;
+        for (id, add, favorite) in [
+            ("ses_tag_a", vec!["bug", "api"], None),
+            ("ses_tag_b", vec!["bug"], Some(true)),
+        ] {
+            let entry = crate::session_tags::session_tags_patch(
+                State(state.clone()),
+                axum::extract::Path(id.to_string()),
+                axum::Json(crate::session_tags::SessionTagsPatchBody {
+                    add: add.into_iter().map(str::to_string).collect(),
+                    remove: Vec::new(),
+                    favorite,
+                }),
+            )
+            .await
+            .unwrap();
+            assert_eq!(entry.favorite, favorite.unwrap_or(false));
+        }
+
+        let list = |tag: Option<&str>, favorite: Option<&str>| {
+            let state = state.clone();
+            let query = SessionListQuery {
+                directory: Some(proj.to_string_lossy().to_string()),
+                tag: tag.map(str::to_string),
+                favorite: favorite.map(str::to_string),
+                ..SessionListQuery::default()
+            };
+            async move {
+                let resp = session_list(State(state), HeaderMap::new(), Query(query))
+                    .await
+                    .unwrap();
+                let body = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+                let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                json.as_array()
+                    .expect("sessions array")
+                    .iter()
+                    .filter_map(|s| s.get("id").and_then(|v| v.as_str()).map(str::to_string))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(list(None, None).await.len(), 3);
+        assert_eq!(
+            list(Some("bug"), None).await,
+            vec!["ses_tag_b", "ses_tag_a"]
+        );
+        assert_eq!(list(Some("BUG,api"), None).await, vec!["ses_tag_a"]);
+        assert_eq!(list(None, Some("true")).await, vec!["ses_tag_b"]);
+        assert!(list(Some("api"), Some("1")).await.is_empty());
+    }
+
     #[tokio::test]
     async fn session_list_directory_scope_is_strict_and_isolates_global_bucket() {
         let _env_lock = ENV_LOCK.lock().unwrap();
@@ -2404,6 +2670,9 @@ mod tests {
                 include_children: None,
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -2440,6 +2709,9 @@ mod tests {
                 include_children: None,
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -2473,6 +2745,9 @@ mod tests {
                 include_children: None,
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -2537,6 +2812,9 @@ mod tests {
                 include_children: None,
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -2615,6 +2893,9 @@ mod tests {
                 include_children: None,
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -2682,6 +2963,9 @@ mod tests {
                 include_children: None,
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -2763,6 +3047,9 @@ mod tests {
                 include_children: Some("true".to_string()),
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -2850,6 +3137,9 @@ mod tests {
                 include_children: Some("true".to_string()),
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -2924,6 +3214,9 @@ mod tests {
                 include_children: Some("true".to_string()),
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -3016,6 +3309,9 @@ mod tests {
                 include_children: Some("true".to_string()),
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -3106,6 +3402,9 @@ mod tests {
                 include_children: Some("true".to_string()),
                 ids: None,
                 focus_session_id: Some("child_leaf".to_string()),
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -3220,6 +3519,9 @@ mod tests {
                 include_children: Some("true".to_string()),
                 ids: None,
                 focus_session_id: Some("parent_root".to_string()),
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -3302,6 +3604,9 @@ mod tests {
                 include_children: None,
                 ids: None,
                 focus_session_id: None,
+                tag: None,
+                favorite: None,
+                archived: None,
             }),
         )
         .await
@@ -3374,6 +3679,9 @@ mod tests {
             include_children: Some("true".to_string()),
             ids: None,
             focus_session_id: None,
+            tag: None,
+            favorite: None,
+            archived: None,
         };
 
         let state = dummy_state().await;
@@ -3963,4 +4271,60 @@ mod tests {
             Some(DEFAULT_DEGRADED_RETRY_AFTER_MS as u64)
         );
     }
+
+    #[tokio::test]
+    async fn session_message_part_detail_get_returns_unpruned_tool_part() {
+        let _env_lock = ENV_LOCK.lock().unwrap();
+        STORAGE_CACHE.clear();
+
+        let tmp = unique_tmp_dir("session-message-part-detail");
+        tokio::fs::create_dir_all(&tmp).await.unwrap();
+        let _home = EnvVarGuard::set("HOME", tmp.to_string_lossy().to_string());
+
+        let storage = tmp
+            .join(".local")
+            .join("share")
+            .join("opencode")
+            .join("storage");
+        write_json(
+            &storage.join("messages").join("ses_3").join("msg_1.json"),
+            &serde_json::json!({"id": "msg_1", "role": "assistant", "time": {"created": 3.0}}),
+        )
+        .await;
+        write_json(
+            &storage
+                .join("message-parts")
+                .join("msg_1")
+                .join("part_1.json"),
+            &serde_json::json!({
+                "id": "part_1",
+                "type": "tool",
+                "tool": "bash",
+                "callID": "call_1",
+                "metadata": {"raw": true},
+                "state": {"status": "completed", "output": "ok", "metadata": {"exit": 0}}
+            }),
+        )
+        .await;
+
+        let response = session_message_part_detail_get(
+            State(dummy_state().await),
+            AxumPath((
+                "ses_3".to_string(),
+                "msg_1".to_string(),
+                "part_1".to_string(),
+            )),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), 1024 * 1024).await.unwrap();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["callID"], "call_1");
+        assert_eq!(payload["metadata"]["raw"], true);
+        assert_eq!(payload["state"]["metadata"]["exit"], 0);
+        assert_eq!(payload["messageId"], "msg_1");
+        assert!(payload.get("ocLazy").is_none());
+    }
 }
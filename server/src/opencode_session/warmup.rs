@@ -0,0 +1,287 @@
+//! Startup scan of OpenCode's session storage, so the first session-list
+//! loads hit a warm file cache and a populated directory index.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use axum::Json;
+use futures_util::stream::{self as futures_stream, StreamExt as _};
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::fs;
+
+use super::{dedupe_session_dirs, list_json_ids_cached, read_json_value, session_scan_concurrency};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum WarmupPhase {
+    #[default]
+    Idle,
+    Scanning,
+    Hot,
+}
+
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SessionIndexStatus {
+    phase: WarmupPhase,
+    projects_total: usize,
+    projects_scanned: usize,
+    /// Grows as each project directory is listed.
+    sessions_total: usize,
+    sessions_scanned: usize,
+    read_errors: usize,
+    concurrency: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    started_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finished_at: Option<u64>,
+}
+
+static STATUS: LazyLock<Mutex<SessionIndexStatus>> =
+    LazyLock::new(|| Mutex::new(SessionIndexStatus::default()));
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn update(f: impl FnOnce(&mut SessionIndexStatus)) {
+    f(&mut STATUS.lock().unwrap_or_else(|e| e.into_inner()));
+}
+
+fn snapshot() -> SessionIndexStatus {
+    STATUS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+async fn project_session_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for root in crate::persistence_paths::opencode_sessions_dir_candidates() {
+        let Ok(mut entries) = fs::read_dir(&root).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if entry.file_type().await.is_ok_and(|t| t.is_dir()) {
+                dirs.push(entry.path());
+            }
+        }
+    }
+    dirs.sort();
+    dedupe_session_dirs(&mut dirs);
+    dirs
+}
+
+/// Start the warm-up scan once per process.
+pub(crate) fn spawn_session_index_warmup(state: Arc<crate::AppState>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tokio::spawn(async move {
+        warm(&state).await;
+    });
+}
+
+async fn warm(state: &crate::AppState) {
+    let started = Instant::now();
+    let concurrency = session_scan_concurrency();
+    update(|status| {
+        status.phase = WarmupPhase::Scanning;
+        status.concurrency = concurrency;
+        status.started_at = Some(now_millis());
+    });
+
+    let dirs = project_session_dirs().await;
+    update(|status| status.projects_total = dirs.len());
+
+    for dir in dirs {
+        let ids = list_json_ids_cached(&dir).await;
+        update(|status| status.sessions_total += ids.len());
+
+        futures_stream::iter(ids.into_iter().map(|session_id| {
+            let path = dir.join(format!("{session_id}.json"));
+            async move { read_json_value(&path).await }
+        }))
+        .buffer_unordered(concurrency)
+        .for_each(|result| {
+            let ok = match result {
+                Ok((mut session, _)) => {
+                    if crate::opencode_proxy::prune_session_summary_value(&mut session) {
+                        state
+                            .directory_session_index
+                            .upsert_summary_from_value(&session);
+                    }
+                    true
+                }
+                Err(_) => false,
+            };
+            update(|status| {
+                status.sessions_scanned += 1;
+                if !ok {
+                    status.read_errors += 1;
+                }
+            });
+            async {}
+        })
+        .await;
+
+        update(|status| status.projects_scanned += 1);
+    }
+
+    update(|status| {
+        status.phase = WarmupPhase::Hot;
+        status.finished_at = Some(now_millis());
+    });
+    let status = snapshot();
+    let duration_ms = started.elapsed().as_millis() as u64;
+    tracing::info!(
+        sessions = status.sessions_scanned,
+        read_errors = status.read_errors,
+        duration_ms,
+        "Session index warm-up finished"
+    );
+    let payload = serde_json::json!({
+        "type": "opencode-studio:session-index-hot",
+        "properties": {
+            "sessions": status.sessions_scanned,
+            "readErrors": status.read_errors,
+            "durationMs": duration_ms,
+        }
+    });
+    crate::global_sse_hub::publish_downstream_json(&payload.to_string());
+}
+
+/// Progress of the startup storage scan.
+pub(crate) async fn session_index_status() -> Json<SessionIndexStatus> {
+    Json(snapshot())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::await_holding_lock)]
+
+    use super::*;
+    use crate::test_support::ENV_LOCK;
+    use std::time::Duration;
+
+    struct EnvVarGuard {
+        key: &'static str,
+        prev: Option<String>,
+    }
+
+    impl EnvVarGuard {
+        fn set(key: &'static str, value: String) -> Self {
+            let prev = std::env::var(key).ok();
+            unsafe {
+                std::env::set_var(key, value);
+            }
+            Self { key, prev }
+        }
+    }
+
+    impl Drop for EnvVarGuard {
+        fn drop(&mut self) {
+            unsafe {
+                match self.prev.as_deref() {
+                    Some(v) => std::env::set_var(self.key, v),
+                    None => std::env::remove_var(self.key),
+                }
+            }
+        }
+    }
+
+    fn unique_tmp_dir(label: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "opencode-studio-{label}-{}-{}",
+            std::process::id(),
+            now_millis()
+        ))
+    }
+
+    fn reset_status() {
+        update(|status| *status = SessionIndexStatus::default());
+    }
+
+    async fn wait_for_hot() -> SessionIndexStatus {
+        for _ in 0..200 {
+            let status = snapshot();
+            if status.phase == WarmupPhase::Hot {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("warm-up never finished: {:?}", snapshot());
+    }
+
+    #[tokio::test]
+    async fn warm_indexes_readable_sessions_and_counts_read_errors() {
+        let _env_lock = ENV_LOCK.lock().unwrap();
+        crate::opencode_session::STORAGE_CACHE.clear();
+        let tmp = unique_tmp_dir("warmup-scan");
+        let _home = EnvVarGuard::set("HOME", tmp.to_string_lossy().to_string());
+
+        let sessions = crate::persistence_paths::opencode_sessions_dir_candidates()[0].clone();
+        let write = |project: &str, name: &str, body: String| {
+            let dir = sessions.join(project);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(name), body).unwrap();
+        };
+        write(
+            "proj_a",
+            "ses_a.json",
+            serde_json::json!({ "id": "ses_a", "title": "A", "directory": "/work/a" }).to_string(),
+        );
+        write("proj_a", "ses_broken.json", "not json".to_string());
+        // Readable but not a session object: scanned, not indexed, not an error.
+        write("proj_b", "ses_b.json", "[1, 2]".to_string());
+
+        let state = crate::test_support::app_state(&tmp, Default::default()).await;
+        reset_status();
+        warm(&state).await;
+
+        let status = snapshot();
+        assert_eq!(status.phase, WarmupPhase::Hot);
+        assert_eq!((status.projects_total, status.projects_scanned), (2, 2));
+        assert_eq!((status.sessions_total, status.sessions_scanned), (3, 3));
+        assert_eq!(status.read_errors, 1);
+        assert_eq!(status.concurrency, session_scan_concurrency());
+        assert!(status.started_at.is_some() && status.finished_at.is_some());
+
+        let index = &state.directory_session_index;
+        assert_eq!(
+            index.directory_for_session("ses_a").as_deref(),
+            Some("/work/a")
+        );
+        assert!(index.summary("ses_b").is_none());
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[tokio::test]
+    async fn warmup_is_spawned_once_per_process() {
+        let _env_lock = ENV_LOCK.lock().unwrap();
+        let tmp = unique_tmp_dir("warmup-once");
+        std::fs::create_dir_all(&tmp).unwrap();
+        let _home = EnvVarGuard::set("HOME", tmp.to_string_lossy().to_string());
+        let state = crate::test_support::app_state(&tmp, Default::default()).await;
+
+        STARTED.store(false, Ordering::SeqCst);
+        reset_status();
+        spawn_session_index_warmup(state.clone());
+        let status = wait_for_hot().await;
+        assert_eq!(status.projects_total, 0);
+        assert!(STARTED.load(Ordering::SeqCst));
+
+        // A second call is a no-op.
+        reset_status();
+        spawn_session_index_warmup(state);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(snapshot().phase, WarmupPhase::Idle);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
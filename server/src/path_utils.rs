@@ -163,11 +163,36 @@ pub(crate) fn normalize_directory_path(input: &str) -> String {
     trimmed.to_string()
 }
 
+/// Whether the URL `path` is `prefix` or lies below it: `/api` matches
+/// `/api/x` but not `/apix`.
+pub(crate) fn url_path_within(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// `/api`, `/auth` and `/health`: everything the server answers itself
+/// rather than the web UI.
+pub(crate) fn is_api_auth_or_health_path(path: &str) -> bool {
+    ["/api", "/auth", "/health"]
+        .iter()
+        .any(|prefix| url_path_within(path, prefix))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::test_support::ENV_LOCK;
 
+    #[test]
+    fn url_path_within_matches_whole_segments() {
+        assert!(url_path_within("/api", "/api"));
+        assert!(url_path_within("/api/fs/read", "/api"));
+        assert!(!url_path_within("/apix", "/api"));
+        assert!(is_api_auth_or_health_path("/health"));
+        assert!(is_api_auth_or_health_path("/auth/session"));
+        assert!(!is_api_auth_or_health_path("/settings"));
+    }
+
     struct EnvVarGuard {
         key: String,
         old: Option<String>,
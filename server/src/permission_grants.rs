@@ -0,0 +1,381 @@
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, Query, State},
+};
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{ApiResult, AppError};
+
+/// Unanswered `permission.asked` events kept to pair with their reply.
+const MAX_PENDING_ASKS: usize = 1000;
+const PENDING_ASK_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+const MAX_GRANTS: usize = 2000;
+
+static PENDING: LazyLock<PendingAsks> = LazyLock::new(PendingAsks::default);
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// An "always allow" reply the agent received, as seen on the event stream.
+///
+/// OpenCode keeps these approvals in memory for the lifetime of its
+/// instance, so revoking here drops the record and takes effect on the
+/// agent once OpenCode is reloaded.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PermissionGrant {
+    pub id: String,
+    pub directory: String,
+    pub permission: String,
+    /// Patterns approved for future requests (the ask's `always` list).
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub granted_at: u64,
+    /// How many "always" replies matched this grant.
+    #[serde(default)]
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GrantStore {
+    #[serde(default)]
+    grants: Vec<PermissionGrant>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NewGrant {
+    directory: String,
+    permission: String,
+    patterns: Vec<String>,
+    session_id: String,
+}
+
+struct PendingAsk {
+    directory: Option<String>,
+    session_id: String,
+    permission: String,
+    patterns: Vec<String>,
+    asked_at: Instant,
+}
+
+#[derive(Default)]
+struct PendingAsks {
+    asks: DashMap<String, PendingAsk>,
+}
+
+fn read_str(props: &serde_json::Map<String, Value>, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|k| props.get(*k).and_then(|v| v.as_str()))
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn read_patterns(props: &serde_json::Map<String, Value>) -> Vec<String> {
+    // Newer asks list what "always" approves; older ones only have `pattern`.
+    let list = props
+        .get("always")
+        .or_else(|| props.get("patterns"))
+        .or_else(|| props.get("pattern"));
+    let mut out: Vec<String> = match list {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect(),
+        Some(Value::String(s)) if !s.trim().is_empty() => vec![s.trim().to_string()],
+        _ => Vec::new(),
+    };
+    out.sort();
+    out.dedup();
+    out
+}
+
+impl PendingAsks {
+    /// Track asks and return the grant when an ask is answered "always".
+    fn observe(
+        &self,
+        raw: &Value,
+        directory_for_session: impl Fn(&str) -> Option<String>,
+    ) -> Option<NewGrant> {
+        let payload = raw.get("payload").filter(|p| p.get("type").is_some());
+        let payload = payload.unwrap_or(raw);
+        let ty = payload.get("type").and_then(|v| v.as_str())?;
+        let props = payload.get("properties").and_then(|v| v.as_object())?;
+        let directory = raw
+            .get("directory")
+            .and_then(|v| v.as_str())
+            .and_then(crate::path_utils::normalize_directory_for_match);
+
+        match ty {
+            "permission.asked" | "permission.updated" => {
+                let id = read_str(props, &["id"])?;
+                let session_id = read_str(props, &["sessionID", "sessionId", "session_id"])?;
+                let permission = read_str(props, &["permission", "type"])?;
+                if self.asks.len() >= MAX_PENDING_ASKS {
+                    self.asks
+                        .retain(|_, ask| ask.asked_at.elapsed() < PENDING_ASK_TTL);
+                    if self.asks.len() >= MAX_PENDING_ASKS {
+                        return None;
+                    }
+                }
+                self.asks.insert(
+                    id,
+                    PendingAsk {
+                        directory,
+                        session_id,
+                        permission,
+                        patterns: read_patterns(props),
+                        asked_at: Instant::now(),
+                    },
+                );
+                None
+            }
+            "permission.replied" => {
+                let id = read_str(props, &["requestID", "permissionID", "id"])?;
+                let (_, ask) = self.asks.remove(&id)?;
+                let reply = read_str(props, &["reply", "response"])?;
+                if reply != "always" {
+                    return None;
+                }
+                let directory = ask
+                    .directory
+                    .or(directory)
+                    .or_else(|| directory_for_session(&ask.session_id))?;
+                Some(NewGrant {
+                    directory,
+                    permission: ask.permission,
+                    patterns: ask.patterns,
+                    session_id: ask.session_id,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Fold a new grant into the store; repeats of the same rule bump its count.
+fn merge_grant(store: &mut GrantStore, grant: NewGrant, now: u64) {
+    if let Some(existing) = store.grants.iter_mut().find(|g| {
+        g.directory == grant.directory
+            && g.permission == grant.permission
+            && g.patterns == grant.patterns
+    }) {
+        existing.granted_at = now;
+        existing.session_id = Some(grant.session_id);
+        existing.count = existing.count.saturating_add(1);
+        return;
+    }
+    store.grants.push(PermissionGrant {
+        id: format!("grant_{}", uuid::Uuid::new_v4().simple()),
+        directory: grant.directory,
+        permission: grant.permission,
+        patterns: grant.patterns,
+        session_id: Some(grant.session_id),
+        granted_at: now,
+        count: 1,
+    });
+    if store.grants.len() > MAX_GRANTS {
+        store
+            .grants
+            .sort_by_key(|g| std::cmp::Reverse(g.granted_at));
+        store.grants.truncate(MAX_GRANTS);
+    }
+}
+
+async fn load_store(db: &crate::studio_db::StudioDb) -> ApiResult<GrantStore> {
+    db.get_json::<GrantStore>(crate::studio_db::KV_KEY_PERMISSION_GRANTS)
+        .await
+        .map(Option::unwrap_or_default)
+        .map_err(AppError::internal)
+}
+
+async fn update_store<R>(
+    db: &crate::studio_db::StudioDb,
+    update: impl FnOnce(&mut GrantStore) -> ApiResult<R>,
+) -> ApiResult<R> {
+    db.update_json(crate::studio_db::KV_KEY_PERMISSION_GRANTS, update)
+        .await
+        .map_err(AppError::internal)?
+}
+
+/// Feed one raw upstream event (global wrapper included) through the grant
+/// tracker. Persisting runs in the background so the SSE loop never waits.
+pub(crate) fn observe_event(state: &Arc<crate::AppState>, raw: &Value) {
+    let index = &state.directory_session_index;
+    let Some(grant) = PENDING.observe(raw, |sid| index.directory_for_session(sid)) else {
+        return;
+    };
+    let state = state.clone();
+    tokio::spawn(async move {
+        let result = update_store(state.studio_db.as_ref(), |store| {
+            merge_grant(store, grant, now_millis());
+            Ok(())
+        })
+        .await;
+        if let Err(err) = result {
+            tracing::warn!(error = ?err, "Failed to record permission grant");
+        }
+    });
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PermissionGrantsQuery {
+    pub directory: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionGrantProject {
+    pub directory: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionGrantsResponse {
+    pub grants: Vec<PermissionGrant>,
+    /// Per-project totals across all recorded grants.
+    pub projects: Vec<PermissionGrantProject>,
+}
+
+fn revoked_response(removed: usize) -> Json<Value> {
+    Json(json!({
+        "removed": removed,
+        "reloadRequired": removed > 0,
+        "hint": "OpenCode keeps approvals until it restarts; reload it to apply revocations.",
+    }))
+}
+
+pub(crate) async fn permission_grants_list(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<PermissionGrantsQuery>,
+) -> ApiResult<Json<PermissionGrantsResponse>> {
+    let directory = q
+        .directory
+        .as_deref()
+        .and_then(crate::path_utils::normalize_directory_for_match);
+    let store = load_store(state.studio_db.as_ref()).await?;
+
+    let mut projects: Vec<PermissionGrantProject> = Vec::new();
+    for grant in &store.grants {
+        match projects.iter_mut().find(|p| p.directory == grant.directory) {
+            Some(project) => project.count += 1,
+            None => projects.push(PermissionGrantProject {
+                directory: grant.directory.clone(),
+                count: 1,
+            }),
+        }
+    }
+    projects.sort_by(|a, b| a.directory.cmp(&b.directory));
+
+    let mut grants: Vec<PermissionGrant> = store
+        .grants
+        .into_iter()
+        .filter(|g| directory.as_ref().is_none_or(|dir| &g.directory == dir))
+        .collect();
+    grants.sort_by_key(|g| std::cmp::Reverse(g.granted_at));
+    Ok(Json(PermissionGrantsResponse { grants, projects }))
+}
+
+pub(crate) async fn permission_grant_delete(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<Value>> {
+    update_store(state.studio_db.as_ref(), |store| {
+        let before = store.grants.len();
+        store.grants.retain(|g| g.id != id);
+        if store.grants.len() == before {
+            return Err(AppError::not_found("Permission grant not found"));
+        }
+        Ok(revoked_response(1))
+    })
+    .await
+}
+
+/// Bulk-clear every grant recorded for one project.
+pub(crate) async fn permission_grants_clear(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<PermissionGrantsQuery>,
+) -> ApiResult<Json<Value>> {
+    let directory = q
+        .directory
+        .as_deref()
+        .and_then(crate::path_utils::normalize_directory_for_match)
+        .ok_or_else(|| AppError::bad_request("directory is required"))?;
+
+    update_store(state.studio_db.as_ref(), |store| {
+        let before = store.grants.len();
+        store.grants.retain(|g| g.directory != directory);
+        Ok(revoked_response(before - store.grants.len()))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn always_replies_become_deduplicated_grants() {
+        let pending = PendingAsks::default();
+        let lookup = |_: &str| Some("/work/fallback".to_string());
+        let ask = |id: &str| {
+            json!({
+                "directory": "/work/app",
+                "payload": {
+                    "type": "permission.asked",
+                    "properties": {
+                        "id": id,
+                        "sessionID": "ses_1",
+                        "permission": "bash",
+                        "patterns": ["git status"],
+                        "always": ["git *", "git *"],
+                    }
+                }
+            })
+        };
+        let reply = |id: &str, reply: &str| {
+            json!({
+                "type": "permission.replied",
+                "properties": {"sessionID": "ses_1", "requestID": id, "reply": reply}
+            })
+        };
+
+        assert_eq!(pending.observe(&ask("per_1"), lookup), None);
+        assert_eq!(pending.observe(&reply("per_1", "once"), lookup), None);
+        assert_eq!(
+            pending.observe(&reply("per_1", "always"), lookup),
+            None,
+            "a request is only paired once"
+        );
+
+        let mut store = GrantStore::default();
+        for (id, at) in [("per_2", 10), ("per_3", 20)] {
+            pending.observe(&ask(id), lookup);
+            let grant = pending
+                .observe(&reply(id, "always"), lookup)
+                .expect("grant");
+            assert_eq!(grant.directory, "/work/app");
+            assert_eq!(grant.patterns, vec!["git *".to_string()]);
+            merge_grant(&mut store, grant, at);
+        }
+        assert_eq!(store.grants.len(), 1);
+        assert_eq!(store.grants[0].count, 2);
+        assert_eq!(store.grants[0].granted_at, 20);
+    }
+}
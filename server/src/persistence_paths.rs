@@ -10,6 +10,13 @@ pub(crate) const TERMINAL_UI_STATE_FILE: &str = "terminal-ui-state.json";
 pub(crate) const LEGACY_TERMINAL_UI_STATE_FILE: &str = "terminal.state.json";
 pub(crate) const TERMINAL_SESSION_REGISTRY_FILE: &str = "session-registry.json";
 pub(crate) const LEGACY_TERMINAL_SESSION_REGISTRY_FILE: &str = "sessions.json";
+pub(crate) const SSE_REPLAY_SNAPSHOT_FILE: &str = "sse-replay-snapshot.json";
+pub(crate) const TOOL_OUTPUT_ARCHIVE_DIR: &str = "tool-output-archive";
+pub(crate) const THUMBNAIL_CACHE_DIR: &str = "thumbnail-cache";
+pub(crate) const TRASH_DIR: &str = "trash";
+pub(crate) const INSTALLED_PLUGINS_DIR: &str = "plugins";
+pub(crate) const AUDIT_LOG_FILE: &str = "audit-log.jsonl";
+pub(crate) const SECRETS_KEY_FILE: &str = "secrets.key";
 
 // OpenCode Studio state is stored in a single SQLite database.
 pub(crate) const STUDIO_DB_FILE: &str = "opencode-studio.db";
@@ -189,6 +196,90 @@ pub(crate) fn terminal_session_registry_path() -> PathBuf {
     select_existing_path(terminal_session_registry_path_candidates())
 }
 
+pub(crate) fn sse_replay_snapshot_path_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::<PathBuf>::new();
+    for root in studio_data_dir_candidates() {
+        candidates.push(root.join("runtime").join(SSE_REPLAY_SNAPSHOT_FILE));
+    }
+    dedupe_paths(candidates)
+}
+
+pub(crate) fn sse_replay_snapshot_path() -> PathBuf {
+    select_existing_path(sse_replay_snapshot_path_candidates())
+}
+
+pub(crate) fn tool_output_archive_dir_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::<PathBuf>::new();
+    for root in studio_data_dir_candidates() {
+        candidates.push(root.join(TOOL_OUTPUT_ARCHIVE_DIR));
+    }
+    dedupe_paths(candidates)
+}
+
+pub(crate) fn tool_output_archive_dir() -> PathBuf {
+    select_existing_path(tool_output_archive_dir_candidates())
+}
+
+pub(crate) fn thumbnail_cache_dir_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::<PathBuf>::new();
+    for root in studio_data_dir_candidates() {
+        candidates.push(root.join(THUMBNAIL_CACHE_DIR));
+    }
+    dedupe_paths(candidates)
+}
+
+pub(crate) fn thumbnail_cache_dir() -> PathBuf {
+    select_existing_path(thumbnail_cache_dir_candidates())
+}
+
+pub(crate) fn trash_dir_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::<PathBuf>::new();
+    for root in studio_data_dir_candidates() {
+        candidates.push(root.join(TRASH_DIR));
+    }
+    dedupe_paths(candidates)
+}
+
+pub(crate) fn trash_dir() -> PathBuf {
+    select_existing_path(trash_dir_candidates())
+}
+
+pub(crate) fn installed_plugins_dir_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::<PathBuf>::new();
+    for root in studio_data_dir_candidates() {
+        candidates.push(root.join(INSTALLED_PLUGINS_DIR));
+    }
+    dedupe_paths(candidates)
+}
+
+pub(crate) fn installed_plugins_dir() -> PathBuf {
+    select_existing_path(installed_plugins_dir_candidates())
+}
+
+pub(crate) fn audit_log_path_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::<PathBuf>::new();
+    for root in studio_data_dir_candidates() {
+        candidates.push(root.join(AUDIT_LOG_FILE));
+    }
+    dedupe_paths(candidates)
+}
+
+pub(crate) fn audit_log_path() -> PathBuf {
+    select_existing_path(audit_log_path_candidates())
+}
+
+pub(crate) fn secrets_key_path_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::<PathBuf>::new();
+    for root in studio_data_dir_candidates() {
+        candidates.push(root.join(SECRETS_KEY_FILE));
+    }
+    dedupe_paths(candidates)
+}
+
+pub(crate) fn secrets_key_path() -> PathBuf {
+    select_existing_path(secrets_key_path_candidates())
+}
+
 pub(crate) fn opencode_data_dir_candidates() -> Vec<PathBuf> {
     vec![crate::path_utils::opencode_data_dir()]
 }
@@ -0,0 +1,771 @@
+//! Studio-managed plugin installs.
+//!
+//! Plugins installed through the API live in their own directory under the
+//! studio data dir (`plugins/<id>`), next to a `.studio-install.json` record
+//! of where they came from. The runtime loads them alongside the plugins
+//! listed in the OpenCode config, so managing them needs no filesystem access.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, State},
+    http::Extensions,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::plugin_runtime::{
+    PluginStatus, parse_manifest_value, read_package_json, resolve_manifest_path,
+    sanitize_plugin_id,
+};
+use crate::{ApiResult, AppError};
+
+const INSTALL_RECORD_FILE: &str = ".studio-install.json";
+const STAGING_PREFIX: &str = ".staging-";
+const MAX_TARBALL_BYTES: usize = 64 * 1024 * 1024;
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Installs, updates and removals touch the same directory; run one at a time.
+static INSTALL_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PluginSourceKind {
+    Git,
+    Tarball,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PluginInstallRecord {
+    kind: PluginSourceKind,
+    source: String,
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    reference: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+    installed_at: u64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PluginInstallBody {
+    /// Git URL, or a `.tgz`/`.tar.gz` URL or server path.
+    source: String,
+    /// Inferred from `source` when omitted.
+    kind: Option<PluginSourceKind>,
+    /// Branch or tag to clone (git only).
+    #[serde(rename = "ref")]
+    reference: Option<String>,
+    /// Replace an installed plugin with the same id instead of failing.
+    #[serde(default)]
+    replace: bool,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PluginUpdateBody {
+    /// Switch to another branch or tag (git only).
+    #[serde(rename = "ref")]
+    reference: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InstalledPlugin {
+    id: String,
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    display_name: Option<String>,
+    permissions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    install: Option<PluginInstallRecord>,
+    /// Runtime load status once the runtime has picked the plugin up.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<PluginStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct VerifiedManifest {
+    id: String,
+    version: String,
+    display_name: Option<String>,
+    permissions: Vec<String>,
+}
+
+/// Path specs for every installed plugin, for the runtime to load.
+pub(crate) fn installed_plugin_specs() -> Vec<String> {
+    installed_plugin_dirs(&crate::persistence_paths::installed_plugins_dir())
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect()
+}
+
+fn installed_plugin_dirs(plugins_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(plugins_dir) else {
+        return Vec::new();
+    };
+    let mut dirs = entries
+        .filter_map(Result::ok)
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+    dirs.sort();
+    dirs
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn infer_source_kind(source: &str) -> PluginSourceKind {
+    let path = source.split(['?', '#']).next().unwrap_or(source);
+    let lower = path.to_ascii_lowercase();
+    if lower.ends_with(".tgz") || lower.ends_with(".tar.gz") || lower.ends_with(".tar") {
+        PluginSourceKind::Tarball
+    } else {
+        PluginSourceKind::Git
+    }
+}
+
+fn looks_like_version(raw: &str) -> bool {
+    raw.starts_with(|c: char| c.is_ascii_digit())
+        && raw
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+}
+
+/// Check what an install needs from a manifest: an id (or package name), a
+/// version, and `permissions` as a list of names.
+fn verify_manifest_value(
+    manifest: &Value,
+    package_json: Option<&Value>,
+) -> Result<VerifiedManifest, String> {
+    let raw_id = manifest
+        .get("id")
+        .and_then(Value::as_str)
+        .or_else(|| package_json?.get("name")?.as_str())
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .ok_or("manifest has no id (and package.json has no name)")?;
+    let id = sanitize_plugin_id(raw_id);
+    if id.starts_with('.') {
+        return Err(format!("invalid plugin id: {raw_id}"));
+    }
+
+    let version = manifest
+        .get("version")
+        .or_else(|| package_json?.get("version"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|version| !version.is_empty())
+        .ok_or("manifest has no version")?;
+    if !looks_like_version(version) {
+        return Err(format!("invalid plugin version: {version}"));
+    }
+
+    let permissions = match manifest.get("permissions") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(ToString::to_string)
+                    .ok_or("permissions must be non-empty strings")
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(_) => return Err("permissions must be an array of strings".to_string()),
+    };
+
+    Ok(VerifiedManifest {
+        id,
+        version: version.to_string(),
+        display_name: manifest
+            .get("displayName")
+            .and_then(Value::as_str)
+            .map(ToString::to_string),
+        permissions,
+    })
+}
+
+fn verify_plugin_dir(root: &Path) -> Result<VerifiedManifest, String> {
+    let package_json = read_package_json(root);
+    let manifest_path = resolve_manifest_path(root, package_json.as_ref())
+        .filter(|path| path.is_file())
+        .ok_or("studio manifest not found")?;
+    let raw = std::fs::read_to_string(&manifest_path)
+        .map_err(|err| format!("failed to read studio manifest: {err}"))?;
+    let manifest = parse_manifest_value(&raw)?;
+    verify_manifest_value(&manifest, package_json.as_ref())
+}
+
+fn read_install_record(dir: &Path) -> Option<PluginInstallRecord> {
+    let raw = std::fs::read_to_string(dir.join(INSTALL_RECORD_FILE)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn installed_plugin(dir: &Path) -> InstalledPlugin {
+    let id = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let install = read_install_record(dir);
+    let path = dir.to_string_lossy().into_owned();
+    match verify_plugin_dir(dir) {
+        Ok(manifest) => InstalledPlugin {
+            id,
+            path,
+            version: Some(manifest.version),
+            display_name: manifest.display_name,
+            permissions: manifest.permissions,
+            install,
+            status: None,
+            error: None,
+        },
+        Err(err) => InstalledPlugin {
+            id,
+            path,
+            version: None,
+            display_name: None,
+            permissions: Vec::new(),
+            install,
+            status: None,
+            error: Some(err),
+        },
+    }
+}
+
+/// With npm-style tarballs everything sits under one top-level directory.
+fn unwrap_single_dir(root: &Path) -> PathBuf {
+    if resolve_manifest_path(root, read_package_json(root).as_ref()).is_some() {
+        return root.to_path_buf();
+    }
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return root.to_path_buf();
+    };
+    let entries = entries.filter_map(Result::ok).collect::<Vec<_>>();
+    match entries.as_slice() {
+        [only] if only.path().is_dir() => only.path(),
+        _ => root.to_path_buf(),
+    }
+}
+
+fn unpack_tarball(data: &[u8], dest: &Path) -> Result<(), String> {
+    let reader: Box<dyn Read + '_> = if data.starts_with(&[0x1f, 0x8b]) {
+        Box::new(flate2::read::GzDecoder::new(data))
+    } else {
+        Box::new(data)
+    };
+    tar::Archive::new(reader)
+        .unpack(dest)
+        .map_err(|err| format!("unpack tarball: {err}"))
+}
+
+async fn download_tarball(url: &str) -> ApiResult<Vec<u8>> {
+    let client = crate::tls_roots::client_builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()
+        .map_err(|err| AppError::internal(format!("build http client: {err}")))?;
+    let mut resp = client
+        .get(url)
+        .header(
+            reqwest::header::USER_AGENT,
+            "opencode-studio-plugin-install",
+        )
+        .send()
+        .await
+        .map_err(|err| AppError::bad_gateway(format!("download {url}: {err}")))?;
+    if !resp.status().is_success() {
+        return Err(AppError::bad_gateway(format!(
+            "download {url} failed ({})",
+            resp.status()
+        )));
+    }
+    let mut out = Vec::new();
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|err| AppError::bad_gateway(format!("download {url}: {err}")))?
+    {
+        if out.len() + chunk.len() > MAX_TARBALL_BYTES {
+            return Err(AppError::payload_too_large(format!(
+                "download {url} exceeds {MAX_TARBALL_BYTES} bytes"
+            )));
+        }
+        out.extend_from_slice(&chunk);
+    }
+    Ok(out)
+}
+
+/// Fetch `record.source` into `staging` and return the commit for git sources.
+async fn fetch_source(
+    record: &PluginInstallRecord,
+    plugins_dir: &Path,
+    staging: &Path,
+) -> ApiResult<Option<String>> {
+    match record.kind {
+        PluginSourceKind::Git => {
+            let target = staging.join("src");
+            let target = target.to_string_lossy();
+            let mut args = vec!["clone", "--depth", "1"];
+            if let Some(reference) = record.reference.as_deref() {
+                args.extend(["--branch", reference]);
+            }
+            args.extend(["--", record.source.as_str(), target.as_ref()]);
+            let (code, _out, err) = crate::git::run_git(plugins_dir, &args)
+                .await
+                .map_err(AppError::internal)?;
+            if code != 0 {
+                return Err(AppError::bad_gateway(format!(
+                    "git clone failed: {}",
+                    crate::git::redact_git_output(err.trim())
+                )));
+            }
+            let (code, out, _err) =
+                crate::git::run_git(&staging.join("src"), &["rev-parse", "HEAD"])
+                    .await
+                    .map_err(AppError::internal)?;
+            Ok((code == 0).then(|| out.trim().to_string()))
+        }
+        PluginSourceKind::Tarball => {
+            let source = record.source.as_str();
+            let data = if source.starts_with("http://") || source.starts_with("https://") {
+                download_tarball(source).await?
+            } else {
+                let path = source.strip_prefix("file://").unwrap_or(source);
+                tokio::fs::read(path)
+                    .await
+                    .map_err(|err| AppError::bad_request(format!("read {path}: {err}")))?
+            };
+            let dest = staging.join("src");
+            tokio::task::spawn_blocking(move || unpack_tarball(&data, &dest))
+                .await
+                .map_err(|err| AppError::internal(err.to_string()))?
+                .map_err(AppError::bad_request)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Swap `source` in as `target`, keeping the old directory until the new one
+/// is in place.
+fn replace_dir(source: &Path, target: &Path, plugins_dir: &Path) -> Result<(), String> {
+    if !target.exists() {
+        return std::fs::rename(source, target).map_err(|err| format!("install plugin: {err}"));
+    }
+    let old = plugins_dir.join(format!(".removing-{}", uuid::Uuid::new_v4()));
+    std::fs::rename(target, &old).map_err(|err| format!("replace plugin: {err}"))?;
+    if let Err(err) = std::fs::rename(source, target) {
+        let _ = std::fs::rename(&old, target);
+        return Err(format!("install plugin: {err}"));
+    }
+    let _ = std::fs::remove_dir_all(&old);
+    Ok(())
+}
+
+/// Fetch, verify and place a plugin. `expected_id` pins the id on updates.
+async fn install_into(
+    plugins_dir: &Path,
+    mut record: PluginInstallRecord,
+    replace: bool,
+    expected_id: Option<&str>,
+) -> ApiResult<InstalledPlugin> {
+    let source = record.source.trim();
+    if source.is_empty() {
+        return Err(AppError::bad_request("source is required"));
+    }
+    if source.starts_with('-')
+        || record
+            .reference
+            .as_deref()
+            .is_some_and(|r| r.starts_with('-'))
+    {
+        return Err(AppError::bad_request("Invalid plugin source"));
+    }
+    record.source = source.to_string();
+
+    tokio::fs::create_dir_all(plugins_dir)
+        .await
+        .map_err(|err| AppError::internal(format!("create plugin directory: {err}")))?;
+    let staging = tempfile::Builder::new()
+        .prefix(STAGING_PREFIX)
+        .tempdir_in(plugins_dir)
+        .map_err(|err| AppError::internal(format!("create staging directory: {err}")))?;
+
+    record.commit = fetch_source(&record, plugins_dir, staging.path()).await?;
+    let root = unwrap_single_dir(&staging.path().join("src"));
+    let manifest = verify_plugin_dir(&root)
+        .map_err(|err| AppError::bad_request(format!("Invalid plugin: {err}")))?;
+    if let Some(expected) = expected_id
+        && manifest.id != expected
+    {
+        return Err(AppError::bad_request(format!(
+            "Updated plugin id {} does not match {expected}",
+            manifest.id
+        )));
+    }
+
+    let target = plugins_dir.join(&manifest.id);
+    if target.exists() && !replace {
+        return Err(AppError::conflict(format!(
+            "Plugin {} is already installed",
+            manifest.id
+        )));
+    }
+    record.installed_at = now_millis();
+    let raw =
+        serde_json::to_vec_pretty(&record).map_err(|err| AppError::internal(err.to_string()))?;
+    std::fs::write(root.join(INSTALL_RECORD_FILE), raw)
+        .map_err(|err| AppError::internal(format!("write install record: {err}")))?;
+    replace_dir(&root, &target, plugins_dir).map_err(AppError::internal)?;
+
+    Ok(InstalledPlugin {
+        id: manifest.id,
+        path: target.to_string_lossy().into_owned(),
+        version: Some(manifest.version),
+        display_name: manifest.display_name,
+        permissions: manifest.permissions,
+        install: Some(record),
+        status: None,
+        error: None,
+    })
+}
+
+fn installed_dir(plugins_dir: &Path, plugin_id: &str) -> ApiResult<PathBuf> {
+    let dir = plugins_dir.join(plugin_id);
+    if plugin_id.starts_with('.') || sanitize_plugin_id(plugin_id) != plugin_id || !dir.is_dir() {
+        return Err(AppError::not_found(format!(
+            "Plugin {plugin_id} is not installed"
+        )));
+    }
+    Ok(dir)
+}
+
+/// Reload the runtime and fill in how it loaded the plugin.
+async fn reload_runtime(state: &crate::AppState, plugin: &mut InstalledPlugin) {
+    if let Err(err) = state
+        .plugin_runtime
+        .refresh_from_opencode_config_layers(None)
+        .await
+    {
+        tracing::warn!("failed to reload plugin runtime after install: {err}");
+    }
+    if let Some((_, status, error)) = state
+        .plugin_runtime
+        .status_for_root(Path::new(&plugin.path))
+        .await
+    {
+        plugin.status = Some(status);
+        plugin.error = plugin.error.take().or(error);
+    }
+}
+
+pub(crate) async fn installed_plugins_get(
+    State(state): State<Arc<crate::AppState>>,
+    extensions: Extensions,
+) -> ApiResult<Json<Vec<InstalledPlugin>>> {
+    crate::ui_auth::require_admin(&state.ui_auth, &extensions)?;
+    let plugins_dir = crate::persistence_paths::installed_plugins_dir();
+    let mut out = Vec::new();
+    for dir in installed_plugin_dirs(&plugins_dir) {
+        let mut plugin = installed_plugin(&dir);
+        if let Some((_, status, error)) = state.plugin_runtime.status_for_root(&dir).await {
+            plugin.status = Some(status);
+            plugin.error = plugin.error.or(error);
+        }
+        out.push(plugin);
+    }
+    Ok(Json(out))
+}
+
+/// Install a plugin from a git URL or tarball and load it.
+pub(crate) async fn plugin_install_post(
+    State(state): State<Arc<crate::AppState>>,
+    extensions: Extensions,
+    Json(body): Json<PluginInstallBody>,
+) -> ApiResult<Json<InstalledPlugin>> {
+    crate::ui_auth::require_admin(&state.ui_auth, &extensions)?;
+    let record = PluginInstallRecord {
+        kind: body.kind.unwrap_or_else(|| infer_source_kind(&body.source)),
+        source: body.source,
+        reference: body.reference.filter(|r| !r.trim().is_empty()),
+        commit: None,
+        installed_at: 0,
+    };
+    let plugins_dir = crate::persistence_paths::installed_plugins_dir();
+    let mut plugin = {
+        let _lock = INSTALL_LOCK.lock().await;
+        install_into(&plugins_dir, record, body.replace, None).await?
+    };
+    reload_runtime(&state, &mut plugin).await;
+    Ok(Json(plugin))
+}
+
+/// Re-fetch an installed plugin from the source it was installed from.
+pub(crate) async fn plugin_update_post(
+    State(state): State<Arc<crate::AppState>>,
+    extensions: Extensions,
+    AxumPath(plugin_id): AxumPath<String>,
+    body: Option<Json<PluginUpdateBody>>,
+) -> ApiResult<Json<InstalledPlugin>> {
+    crate::ui_auth::require_admin(&state.ui_auth, &extensions)?;
+    let body = body.map(|Json(b)| b).unwrap_or_default();
+    let plugins_dir = crate::persistence_paths::installed_plugins_dir();
+    let mut plugin = {
+        let _lock = INSTALL_LOCK.lock().await;
+        let dir = installed_dir(&plugins_dir, &plugin_id)?;
+        let mut record = read_install_record(&dir).ok_or_else(|| {
+            AppError::bad_request(format!("Plugin {plugin_id} has no install record"))
+        })?;
+        if let Some(reference) = body.reference.filter(|r| !r.trim().is_empty()) {
+            record.reference = Some(reference);
+        }
+        install_into(&plugins_dir, record, true, Some(&plugin_id)).await?
+    };
+    reload_runtime(&state, &mut plugin).await;
+    Ok(Json(plugin))
+}
+
+pub(crate) async fn plugin_uninstall_delete(
+    State(state): State<Arc<crate::AppState>>,
+    extensions: Extensions,
+    AxumPath(plugin_id): AxumPath<String>,
+) -> ApiResult<Json<Value>> {
+    crate::ui_auth::require_admin(&state.ui_auth, &extensions)?;
+    let plugins_dir = crate::persistence_paths::installed_plugins_dir();
+    {
+        let _lock = INSTALL_LOCK.lock().await;
+        let dir = installed_dir(&plugins_dir, &plugin_id)?;
+        tokio::fs::remove_dir_all(&dir)
+            .await
+            .map_err(|err| AppError::internal(format!("remove plugin: {err}")))?;
+    }
+    if let Err(err) = state
+        .plugin_runtime
+        .refresh_from_opencode_config_layers(None)
+        .await
+    {
+        tracing::warn!("failed to reload plugin runtime after uninstall: {err}");
+    }
+    Ok(Json(serde_json::json!({"removed": true, "id": plugin_id})))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_AUTHOR_NAME", "Test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "Test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .status()
+            .expect("git");
+        assert!(status.success(), "git {args:?}");
+    }
+
+    fn record(kind: PluginSourceKind, source: &Path) -> PluginInstallRecord {
+        PluginInstallRecord {
+            kind,
+            source: source.to_string_lossy().into_owned(),
+            reference: None,
+            commit: None,
+            installed_at: 0,
+        }
+    }
+
+    #[test]
+    fn verifies_manifest_fields() {
+        let ok = verify_manifest_value(
+            &json!({"id": "demo", "version": "1.2.0", "permissions": ["fs.read"]}),
+            None,
+        )
+        .expect("valid");
+        assert_eq!(ok.id, "demo");
+        assert_eq!(ok.permissions, vec!["fs.read"]);
+
+        let from_package = verify_manifest_value(
+            &json!({}),
+            Some(&json!({"name": "@acme/tool", "version": "0.1.0"})),
+        )
+        .expect("package fallback");
+        assert_eq!(from_package.id, "acme-tool");
+        assert_eq!(from_package.version, "0.1.0");
+
+        assert!(verify_manifest_value(&json!({"version": "1.0.0"}), None).is_err());
+        assert!(verify_manifest_value(&json!({"id": "demo"}), None).is_err());
+        assert!(verify_manifest_value(&json!({"id": "demo", "version": "latest"}), None).is_err());
+        assert!(verify_manifest_value(&json!({"id": "..", "version": "1.0.0"}), None).is_err());
+        assert!(
+            verify_manifest_value(
+                &json!({"id": "demo", "version": "1.0.0", "permissions": "fs.read"}),
+                None
+            )
+            .is_err()
+        );
+        assert!(
+            verify_manifest_value(
+                &json!({"id": "demo", "version": "1.0.0", "permissions": [1]}),
+                None
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn infers_source_kind_from_extension() {
+        assert_eq!(
+            infer_source_kind("https://example.com/p-1.0.0.tgz?sig=1"),
+            PluginSourceKind::Tarball
+        );
+        assert_eq!(
+            infer_source_kind("https://github.com/acme/plugin.git"),
+            PluginSourceKind::Git
+        );
+    }
+
+    #[tokio::test]
+    async fn installs_updates_and_lists_git_plugin() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let repo = tmp.path().join("repo");
+        std::fs::create_dir_all(&repo).expect("repo dir");
+        git(&repo, &["init", "-q"]);
+        std::fs::write(
+            repo.join("studio.manifest.json"),
+            r#"{"id": "demo", "version": "1.0.0", "permissions": ["net"]}"#,
+        )
+        .expect("manifest");
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-qm", "init"]);
+
+        let plugins_dir = tmp.path().join("plugins");
+        let installed = install_into(
+            &plugins_dir,
+            record(PluginSourceKind::Git, &repo),
+            false,
+            None,
+        )
+        .await
+        .expect("install");
+        assert_eq!(installed.id, "demo");
+        assert_eq!(installed.version.as_deref(), Some("1.0.0"));
+        assert!(
+            installed
+                .install
+                .as_ref()
+                .and_then(|r| r.commit.as_ref())
+                .is_some()
+        );
+
+        let again = install_into(
+            &plugins_dir,
+            record(PluginSourceKind::Git, &repo),
+            false,
+            None,
+        )
+        .await;
+        assert!(matches!(again, Err(AppError::Conflict { .. })));
+
+        std::fs::write(
+            repo.join("studio.manifest.json"),
+            r#"{"id": "demo", "version": "1.1.0"}"#,
+        )
+        .expect("manifest");
+        git(&repo, &["commit", "-qam", "bump"]);
+        let dir = installed_dir(&plugins_dir, "demo").expect("installed");
+        let stored = read_install_record(&dir).expect("record");
+        let updated = install_into(&plugins_dir, stored, true, Some("demo"))
+            .await
+            .expect("update");
+        assert_eq!(updated.version.as_deref(), Some("1.1.0"));
+
+        let dirs = installed_plugin_dirs(&plugins_dir);
+        assert_eq!(dirs, vec![plugins_dir.join("demo")]);
+        let listed = installed_plugin(&dirs[0]);
+        assert_eq!(listed.version.as_deref(), Some("1.1.0"));
+        assert_eq!(listed.install.map(|r| r.kind), Some(PluginSourceKind::Git));
+    }
+
+    #[tokio::test]
+    async fn installs_npm_style_tarball_and_rejects_bad_manifest() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let tarball = tmp.path().join("plugin.tgz");
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            std::fs::File::create(&tarball).expect("tarball"),
+            flate2::Compression::fast(),
+        ));
+        let files = [
+            (
+                "package/package.json",
+                r#"{"name": "tar-demo", "version": "2.0.0"}"#,
+            ),
+            (
+                "package/studio.manifest.json",
+                r#"{"displayName": "Tar Demo"}"#,
+            ),
+        ];
+        for (path, body) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(body.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, body.as_bytes())
+                .expect("append");
+        }
+        builder
+            .into_inner()
+            .expect("finish")
+            .finish()
+            .expect("gzip");
+
+        let plugins_dir = tmp.path().join("plugins");
+        let installed = install_into(
+            &plugins_dir,
+            record(PluginSourceKind::Tarball, &tarball),
+            false,
+            None,
+        )
+        .await
+        .expect("install");
+        assert_eq!(installed.id, "tar-demo");
+        assert_eq!(installed.display_name.as_deref(), Some("Tar Demo"));
+        assert!(plugins_dir.join("tar-demo/studio.manifest.json").is_file());
+
+        let empty = tmp.path().join("empty.tar");
+        tar::Builder::new(std::fs::File::create(&empty).expect("tar"))
+            .finish()
+            .expect("finish");
+        let bad = install_into(
+            &plugins_dir,
+            record(PluginSourceKind::Tarball, &empty),
+            false,
+            None,
+        )
+        .await;
+        assert!(matches!(bad, Err(AppError::BadRequest { .. })));
+        // Failed installs leave no staging directories behind.
+        assert_eq!(installed_plugin_dirs(&plugins_dir).len(), 1);
+        assert_eq!(std::fs::read_dir(&plugins_dir).expect("dir").count(), 1);
+    }
+}
@@ -1,31 +1,35 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::convert::Infallible;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex as StdMutex};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use async_stream::stream;
 use axum::{
     Json,
+    body::Bytes,
     extract::{Path as AxumPath, Query, State},
     http::{
-        StatusCode,
-        header::{CONTENT_TYPE, HeaderValue},
+        HeaderMap, Method, StatusCode, Uri,
+        header::{CONTENT_TYPE, HeaderName, HeaderValue},
     },
     response::{
         IntoResponse, Response,
         sse::{Event, KeepAlive, Sse},
     },
 };
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_STANDARD};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, mpsc};
 
 use crate::{ApiResult, AppError};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum PluginStatus {
     Ready,
@@ -59,9 +63,10 @@ struct PluginRegistrySnapshot {
 #[derive(Debug, Default)]
 pub(crate) struct PluginRuntime {
     inner: RwLock<PluginRegistrySnapshot>,
+    hooks: StdMutex<HashMap<String, HookDispatcher>>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct PluginListItem {
     pub id: String,
@@ -80,9 +85,10 @@ pub(crate) struct PluginListItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
     pub has_manifest: bool,
+    pub enabled: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct PluginListResponse {
     pub updated_at: u64,
@@ -90,7 +96,7 @@ pub(crate) struct PluginListResponse {
     pub plugins: Vec<PluginListItem>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct PluginManifestResponse {
     pub id: String,
@@ -100,7 +106,7 @@ pub(crate) struct PluginManifestResponse {
     pub manifest: Value,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct PluginActionRequest {
     pub action: String,
@@ -110,7 +116,7 @@ pub(crate) struct PluginActionRequest {
     pub context: Value,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct PluginEventsQuery {
     pub cursor: Option<String>,
@@ -157,6 +163,13 @@ const DEFAULT_BRIDGE_TIMEOUT_MS: u64 = 12_000;
 const MAX_BRIDGE_TIMEOUT_MS: u64 = 120_000;
 const BRIDGE_OUTPUT_SNIPPET_MAX_CHARS: usize = 2000;
 
+/// Settings key listing plugin ids whose actions, events and routes are off.
+const DISABLED_PLUGINS_KEY: &str = "disabledPlugins";
+/// Settings key listing `<pluginId>:<permission>` grants for plugin routes.
+const PLUGIN_PERMISSION_GRANTS_KEY: &str = "pluginPermissionGrants";
+/// Request header approving a route permission: `once` or `always`.
+const PLUGIN_PERMISSION_HEADER: &str = "x-opencode-plugin-permission";
+
 impl PluginRuntime {
     pub(crate) fn new() -> Self {
         Self::default()
@@ -171,7 +184,8 @@ impl PluginRuntime {
             .read_config_layers(working_directory)
             .map_err(|err| err.to_string())?;
 
-        let specs = merge_plugin_specs(user.plugin, project.plugin, custom.plugin);
+        let mut specs = merge_plugin_specs(user.plugin, project.plugin, custom.plugin);
+        specs.extend(crate::plugin_install::installed_plugin_specs());
         self.refresh_from_specs(specs).await;
         Ok(())
     }
@@ -185,6 +199,11 @@ impl PluginRuntime {
             by_id.insert(plugin.id.clone(), idx);
         }
 
+        // Replacing the dispatchers closes the old queues; their workers
+        // finish what is already queued.
+        let hooks = build_hook_dispatchers(&discovered);
+        *self.hooks.lock().unwrap_or_else(|e| e.into_inner()) = hooks;
+
         let mut guard = self.inner.write().await;
         guard.updated_at = now_millis();
         guard.source_specs = normalized_specs;
@@ -192,6 +211,81 @@ impl PluginRuntime {
         guard.by_id = by_id;
     }
 
+    fn has_hook_subscribers(&self) -> bool {
+        !self
+            .hooks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_empty()
+    }
+
+    fn dispatch_hook(&self, name: &str, event: Value, disabled: &[String]) {
+        let hooks = self.hooks.lock().unwrap_or_else(|e| e.into_inner());
+        for (plugin_id, dispatcher) in hooks.iter() {
+            if disabled.contains(plugin_id) || !dispatcher.events.iter().any(|e| e == name) {
+                continue;
+            }
+            if dispatcher.sender.try_send(event.clone()).is_err() {
+                dispatcher.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    async fn admin_items(&self, disabled: &[String]) -> Vec<PluginAdminItem> {
+        let plugins = self.inner.read().await.plugins.clone();
+        let hooks = self.hooks.lock().unwrap_or_else(|e| e.into_inner());
+        plugins
+            .into_iter()
+            .map(|plugin| {
+                let hooks = match hooks.get(&plugin.id) {
+                    Some(dispatcher) => Some(PluginHookStatus {
+                        events: dispatcher.events.clone(),
+                        queued: HOOK_QUEUE_CAPACITY - dispatcher.sender.capacity(),
+                        delivered: dispatcher.stats.delivered.load(Ordering::Relaxed),
+                        failed: dispatcher.stats.failed.load(Ordering::Relaxed),
+                        dropped: dispatcher.stats.dropped.load(Ordering::Relaxed),
+                        last_error: dispatcher
+                            .stats
+                            .last_error
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .clone(),
+                    }),
+                    None => {
+                        let events = plugin_hook_subscriptions(&plugin);
+                        (!events.is_empty()).then(|| PluginHookStatus {
+                            events,
+                            queued: 0,
+                            delivered: 0,
+                            failed: 0,
+                            dropped: 0,
+                            last_error: Some("Plugin bridge is unavailable".to_string()),
+                        })
+                    }
+                };
+                PluginAdminItem {
+                    enabled: !disabled.contains(&plugin.id),
+                    routes: plugin_routes(&plugin)
+                        .into_iter()
+                        .map(|route| PluginRouteSummary {
+                            method: route.method,
+                            path: route.path,
+                            permission: route.permission,
+                        })
+                        .collect(),
+                    hooks,
+                    id: plugin.id,
+                    spec: plugin.spec,
+                    status: plugin.status,
+                    display_name: plugin.display_name,
+                    version: plugin.version,
+                    capabilities: plugin.capabilities,
+                    error: plugin.error,
+                }
+            })
+            .collect()
+    }
+
     pub(crate) async fn list_response(&self) -> PluginListResponse {
         let guard = self.inner.read().await;
         PluginListResponse {
@@ -217,6 +311,7 @@ impl PluginRuntime {
                     capabilities: plugin.capabilities.clone(),
                     error: plugin.error.clone(),
                     has_manifest: plugin.manifest.is_some(),
+                    enabled: true,
                 })
                 .collect(),
         }
@@ -249,6 +344,26 @@ impl PluginRuntime {
         })
     }
 
+    /// Load status of the plugin rooted at `root`, if the runtime knows it.
+    pub(crate) async fn status_for_root(
+        &self,
+        root: &Path,
+    ) -> Option<(String, PluginStatus, Option<String>)> {
+        let root = canonicalize_fallback(root.to_path_buf());
+        let guard = self.inner.read().await;
+        guard
+            .plugins
+            .iter()
+            .find(|plugin| plugin.root_path.as_deref() == Some(root.as_path()))
+            .map(|plugin| {
+                (
+                    plugin.id.clone(),
+                    plugin.status.clone(),
+                    plugin.error.clone(),
+                )
+            })
+    }
+
     async fn registered_plugin(&self, plugin_id: &str) -> Option<RegisteredPlugin> {
         let guard = self.inner.read().await;
         let idx = guard.by_id.get(plugin_id)?;
@@ -259,7 +374,15 @@ impl PluginRuntime {
 pub(crate) async fn plugins_list_get(
     State(state): State<Arc<crate::AppState>>,
 ) -> ApiResult<Json<PluginListResponse>> {
-    Ok(Json(state.plugin_runtime.list_response().await))
+    let mut response = state.plugin_runtime.list_response().await;
+    let disabled = {
+        let settings = state.settings.read().await;
+        settings_string_list(settings.extra.get(DISABLED_PLUGINS_KEY))
+    };
+    for plugin in &mut response.plugins {
+        plugin.enabled = !disabled.contains(&plugin.id);
+    }
+    Ok(Json(response))
 }
 
 pub(crate) async fn plugin_manifest_get(
@@ -312,6 +435,9 @@ pub(crate) async fn plugin_action_post(
             None,
         ));
     };
+    if !plugin_enabled(&state, &plugin.id).await {
+        return action_failure_response(plugin_disabled_failure(&plugin.id));
+    }
 
     let bridge = match resolve_bridge_invocation(&plugin) {
         Ok(bridge) => bridge,
@@ -431,6 +557,10 @@ pub(crate) async fn plugin_events_get(
         ));
     };
 
+    if !plugin_enabled(&state, &plugin.id).await {
+        return action_failure_response(plugin_disabled_failure(&plugin.id));
+    }
+
     if !plugin_supports_events(&plugin) {
         return action_failure_response(action_failure(
             StatusCode::NOT_FOUND,
@@ -529,6 +659,726 @@ pub(crate) async fn plugin_events_get(
         .into_response()
 }
 
+pub(crate) async fn plugin_route_handler(
+    AxumPath((plugin_id, route_path)): AxumPath<(String, String)>,
+    State(state): State<Arc<crate::AppState>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let plugin_id = plugin_id.trim();
+    if plugin_id.is_empty() {
+        return action_failure_response(action_failure(
+            StatusCode::BAD_REQUEST,
+            "invalid_plugin_id",
+            "Plugin id is required",
+            None,
+        ));
+    }
+
+    let Some(plugin) = state.plugin_runtime.registered_plugin(plugin_id).await else {
+        return action_failure_response(action_failure(
+            StatusCode::NOT_FOUND,
+            "plugin_not_found",
+            format!("Plugin '{plugin_id}' is not registered"),
+            None,
+        ));
+    };
+    if !plugin_enabled(&state, &plugin.id).await {
+        return action_failure_response(plugin_disabled_failure(&plugin.id));
+    }
+
+    let routes = plugin_routes(&plugin);
+    if routes.is_empty() {
+        return action_failure_response(action_failure(
+            StatusCode::NOT_FOUND,
+            "plugin_routes_unsupported",
+            format!("Plugin '{}' does not declare HTTP routes", plugin.id),
+            None,
+        ));
+    }
+
+    let path = format!("/{}", route_path.trim_start_matches('/'));
+    let (route, params) = match match_plugin_route(&routes, method.as_str(), &path) {
+        Ok(matched) => matched,
+        Err(status) => {
+            let (code, message) = if status == StatusCode::METHOD_NOT_ALLOWED {
+                (
+                    "plugin_route_method_not_allowed",
+                    format!("Plugin route {path} does not accept {method}"),
+                )
+            } else {
+                (
+                    "plugin_route_not_found",
+                    format!("Plugin '{}' has no route for {path}", plugin.id),
+                )
+            };
+            return action_failure_response(action_failure(status, code, message, None));
+        }
+    };
+
+    if let Some(permission) = route.permission.as_deref()
+        && let Err(err) =
+            check_route_permission(&state, &plugin.id, route, permission, &headers).await
+    {
+        return action_failure_response(err);
+    }
+
+    let bridge = match resolve_bridge_invocation(&plugin) {
+        Ok(bridge) => bridge,
+        Err(err) => return action_failure_response(err),
+    };
+
+    let request_body = match route_request_body(&headers, &body) {
+        Ok(body) => body,
+        Err(err) => return action_failure_response(err),
+    };
+    let query_string = uri.query().unwrap_or("");
+    let query: serde_json::Map<String, Value> =
+        serde_urlencoded::from_str::<Vec<(String, String)>>(query_string)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(k, v)| (k, Value::String(v)))
+            .collect();
+
+    let bridge_payload = json!({
+        "action": "http.request",
+        "payload": {
+            "method": method.as_str(),
+            "path": path,
+            "route": route.path,
+            "params": params,
+            "query": query,
+            "queryString": query_string,
+            "headers": forwarded_route_headers(&headers),
+            "body": request_body.0,
+            "bodyEncoding": request_body.1,
+        },
+        "context": {
+            "transport": "http",
+        },
+        "plugin": {
+            "id": plugin.id,
+            "spec": plugin.spec,
+            "rootPath": plugin.root_path.as_ref().map(|p| p.to_string_lossy().into_owned()),
+            "manifestPath": plugin.manifest_path.as_ref().map(|p| p.to_string_lossy().into_owned()),
+        }
+    });
+
+    match invoke_bridge_action(&bridge, &bridge_payload).await {
+        Ok(output) => route_response(output).unwrap_or_else(action_failure_response),
+        Err(err) => action_failure_response(err),
+    }
+}
+
+/// An HTTP route a plugin declares in its manifest `routes`, either as
+/// `"GET /items/:id"` or `{ "method", "path", "permission", "description" }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PluginRoute {
+    /// Uppercase method; `None` accepts any.
+    method: Option<String>,
+    path: String,
+    permission: Option<String>,
+    description: Option<String>,
+}
+
+fn plugin_routes(plugin: &RegisteredPlugin) -> Vec<PluginRoute> {
+    let Some(Value::Array(entries)) = plugin
+        .manifest
+        .as_ref()
+        .and_then(|manifest| manifest.get("routes"))
+    else {
+        return Vec::new();
+    };
+
+    let normalize_method = |raw: &str| {
+        let raw = raw.trim().to_ascii_uppercase();
+        (!raw.is_empty() && raw != "*" && raw != "ANY").then_some(raw)
+    };
+    let normalize_path = |raw: &str| format!("/{}", raw.trim().trim_matches('/'));
+    let non_empty = |value: Option<&Value>| {
+        value
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(ToString::to_string)
+    };
+
+    entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Value::String(raw) => {
+                let (method, path) = raw.trim().split_once(char::is_whitespace)?;
+                Some(PluginRoute {
+                    method: normalize_method(method),
+                    path: normalize_path(path),
+                    permission: None,
+                    description: None,
+                })
+            }
+            Value::Object(obj) => Some(PluginRoute {
+                method: obj
+                    .get("method")
+                    .and_then(Value::as_str)
+                    .and_then(normalize_method),
+                path: normalize_path(obj.get("path")?.as_str()?),
+                permission: non_empty(obj.get("permission")),
+                description: non_empty(obj.get("description")),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Match `path` against a route pattern: `:name` captures one segment and a
+/// trailing `*name` captures the rest.
+fn match_route_path(pattern: &str, path: &str) -> Option<serde_json::Map<String, Value>> {
+    let mut params = serde_json::Map::new();
+    let mut pattern_segments = pattern.split('/').filter(|s| !s.is_empty()).peekable();
+    let path_segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let mut idx = 0;
+
+    while let Some(segment) = pattern_segments.next() {
+        if let Some(name) = segment.strip_prefix('*') {
+            if pattern_segments.peek().is_some() {
+                return None;
+            }
+            if !name.is_empty() {
+                params.insert(
+                    name.to_string(),
+                    Value::String(path_segments[idx.min(path_segments.len())..].join("/")),
+                );
+            }
+            return Some(params);
+        }
+        let actual = path_segments.get(idx)?;
+        if let Some(name) = segment.strip_prefix(':') {
+            params.insert(name.to_string(), Value::String(actual.to_string()));
+        } else if segment != *actual {
+            return None;
+        }
+        idx += 1;
+    }
+
+    (idx == path_segments.len()).then_some(params)
+}
+
+fn match_plugin_route<'a>(
+    routes: &'a [PluginRoute],
+    method: &str,
+    path: &str,
+) -> Result<(&'a PluginRoute, serde_json::Map<String, Value>), StatusCode> {
+    let mut path_matched = false;
+    for route in routes {
+        let Some(params) = match_route_path(&route.path, path) else {
+            continue;
+        };
+        path_matched = true;
+        if route.method.as_deref().is_none_or(|m| m == method) {
+            return Ok((route, params));
+        }
+    }
+    Err(if path_matched {
+        StatusCode::METHOD_NOT_ALLOWED
+    } else {
+        StatusCode::NOT_FOUND
+    })
+}
+
+fn settings_string_list(value: Option<&Value>) -> Vec<String> {
+    let Some(Value::Array(items)) = value else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+async fn plugin_enabled(state: &crate::AppState, plugin_id: &str) -> bool {
+    let settings = state.settings.read().await;
+    !settings_string_list(settings.extra.get(DISABLED_PLUGINS_KEY))
+        .iter()
+        .any(|id| id == plugin_id)
+}
+
+fn plugin_disabled_failure(plugin_id: &str) -> PluginActionFailure {
+    action_failure(
+        StatusCode::FORBIDDEN,
+        "plugin_disabled",
+        format!("Plugin '{plugin_id}' is disabled in settings"),
+        None,
+    )
+}
+
+fn permission_grant_key(plugin_id: &str, permission: &str) -> String {
+    format!("{plugin_id}:{permission}")
+}
+
+/// Routes declaring a `permission` need it granted, either stored in
+/// settings or approved for this request through the permission header
+/// (`once`, or `always` to store it).
+async fn check_route_permission(
+    state: &crate::AppState,
+    plugin_id: &str,
+    route: &PluginRoute,
+    permission: &str,
+    headers: &HeaderMap,
+) -> Result<(), PluginActionFailure> {
+    let key = permission_grant_key(plugin_id, permission);
+    {
+        let settings = state.settings.read().await;
+        if settings_string_list(settings.extra.get(PLUGIN_PERMISSION_GRANTS_KEY)).contains(&key) {
+            return Ok(());
+        }
+    }
+
+    let approval = headers
+        .get(PLUGIN_PERMISSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_ascii_lowercase());
+    match approval.as_deref() {
+        Some("once") => Ok(()),
+        Some("always") => persist_permission_grant(state, key).await.map_err(|err| {
+            action_failure(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "plugin_permission_persist_failed",
+                format!("Failed to store plugin permission: {err}"),
+                None,
+            )
+        }),
+        _ => Err(action_failure(
+            StatusCode::FORBIDDEN,
+            "plugin_permission_required",
+            format!("Plugin '{plugin_id}' needs permission '{permission}'"),
+            Some(json!({
+                "plugin": plugin_id,
+                "permission": permission,
+                "description": route.description,
+                "route": {
+                    "method": route.method,
+                    "path": route.path,
+                },
+                "grantHeader": PLUGIN_PERMISSION_HEADER,
+            })),
+        )),
+    }
+}
+
+async fn persist_permission_grant(state: &crate::AppState, key: String) -> Result<(), String> {
+    let mut guard = state.settings.write().await;
+    let mut grants = settings_string_list(guard.extra.get(PLUGIN_PERMISSION_GRANTS_KEY));
+    if grants.contains(&key) {
+        return Ok(());
+    }
+    grants.push(key);
+    guard.extra.insert(
+        PLUGIN_PERMISSION_GRANTS_KEY.to_string(),
+        Value::Array(grants.into_iter().map(Value::String).collect()),
+    );
+    guard.revision = guard.revision.saturating_add(1);
+    let next_settings = guard.clone();
+    drop(guard);
+
+    crate::settings::persist_settings(state.studio_db.as_ref(), &next_settings).await?;
+    let value = serde_json::to_value(&next_settings).unwrap_or(json!({}));
+    crate::settings_events::publish_settings_replace(crate::config::format_settings_response(
+        &value,
+    ))
+    .await;
+    Ok(())
+}
+
+/// Request headers passed to the bridge; credentials stay with Studio.
+fn forwarded_route_headers(headers: &HeaderMap) -> serde_json::Map<String, Value> {
+    headers
+        .iter()
+        .filter(|(name, _)| {
+            let name = name.as_str();
+            !matches!(name, "authorization" | "cookie" | "proxy-authorization")
+                && !name.starts_with("x-opencode-")
+        })
+        .filter_map(|(name, value)| {
+            Some((
+                name.as_str().to_string(),
+                Value::String(value.to_str().ok()?.to_string()),
+            ))
+        })
+        .collect()
+}
+
+/// Request body for the bridge: parsed JSON, text, or base64 for binary
+/// content, with the encoding used.
+fn route_request_body(
+    headers: &HeaderMap,
+    body: &Bytes,
+) -> Result<(Value, &'static str), PluginActionFailure> {
+    if body.is_empty() {
+        return Ok((Value::Null, "none"));
+    }
+    let is_json = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("json"));
+    if is_json {
+        return serde_json::from_slice::<Value>(body)
+            .map(|value| (value, "json"))
+            .map_err(|err| {
+                action_failure(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_json_body",
+                    format!("Request body is not valid JSON: {err}"),
+                    None,
+                )
+            });
+    }
+    Ok(match std::str::from_utf8(body) {
+        Ok(text) => (Value::String(text.to_string()), "text"),
+        Err(_) => (Value::String(BASE64_STANDARD.encode(body)), "base64"),
+    })
+}
+
+/// Turn the bridge result into the HTTP response. Results with `status`,
+/// `headers` or `body` describe the response; anything else is returned as
+/// JSON.
+fn route_response(value: Value) -> Result<Response, PluginActionFailure> {
+    let Some(obj) = value.as_object().filter(|obj| {
+        obj.contains_key("status") || obj.contains_key("headers") || obj.contains_key("body")
+    }) else {
+        return Ok((StatusCode::OK, Json(value)).into_response());
+    };
+
+    let invalid = |message: String| {
+        action_failure(
+            StatusCode::BAD_GATEWAY,
+            "bridge_invalid_response",
+            message,
+            None,
+        )
+    };
+
+    let status = match obj.get("status") {
+        None | Some(Value::Null) => StatusCode::OK,
+        Some(raw) => raw
+            .as_u64()
+            .and_then(|code| u16::try_from(code).ok())
+            .and_then(|code| StatusCode::from_u16(code).ok())
+            .ok_or_else(|| invalid(format!("Invalid response status: {raw}")))?,
+    };
+
+    let base64_body = obj.get("bodyEncoding").and_then(Value::as_str) == Some("base64");
+    let (bytes, default_type) = match obj.get("body") {
+        None | Some(Value::Null) => (Vec::new(), None),
+        Some(Value::String(text)) if base64_body => (
+            BASE64_STANDARD
+                .decode(text)
+                .map_err(|err| invalid(format!("Invalid base64 response body: {err}")))?,
+            Some("application/octet-stream"),
+        ),
+        Some(Value::String(text)) => (text.clone().into_bytes(), Some("text/plain; charset=utf-8")),
+        Some(other) => (
+            serde_json::to_vec(other).unwrap_or_default(),
+            Some("application/json"),
+        ),
+    };
+
+    let mut response = Response::new(axum::body::Body::from(bytes));
+    *response.status_mut() = status;
+    if let Some(Value::Object(headers)) = obj.get("headers") {
+        for (name, value) in headers {
+            let Some(value) = value.as_str() else {
+                continue;
+            };
+            let Ok(name) = HeaderName::from_bytes(name.trim().as_bytes()) else {
+                continue;
+            };
+            // Plugins must not set Studio cookies or break framing.
+            if matches!(
+                name.as_str(),
+                "set-cookie" | "content-length" | "transfer-encoding" | "connection"
+            ) {
+                continue;
+            }
+            if let Ok(value) = HeaderValue::from_str(value) {
+                response.headers_mut().insert(name, value);
+            }
+        }
+    }
+    if let Some(content_type) = default_type
+        && !response.headers().contains_key(CONTENT_TYPE)
+    {
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    }
+    Ok(response)
+}
+
+/// Session events plugins can subscribe to through the manifest `hooks` list.
+const HOOK_EVENTS: [&str; 3] = ["message.completed", "session.idle", "permission.asked"];
+/// Events buffered per plugin while its bridge is busy; newer events are
+/// dropped (and counted) once the queue is full.
+const HOOK_QUEUE_CAPACITY: usize = 64;
+/// Completed assistant messages remembered to report each only once.
+const HOOK_COMPLETED_MESSAGES_MAX: usize = 512;
+
+static HOOK_COMPLETED_MESSAGES: LazyLock<StdMutex<VecDeque<String>>> =
+    LazyLock::new(|| StdMutex::new(VecDeque::new()));
+
+#[derive(Debug, Default)]
+struct HookStats {
+    delivered: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+    last_error: StdMutex<Option<String>>,
+}
+
+#[derive(Debug)]
+struct HookDispatcher {
+    events: Vec<String>,
+    sender: mpsc::Sender<Value>,
+    stats: Arc<HookStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PluginHookStatus {
+    pub events: Vec<String>,
+    pub queued: usize,
+    pub delivered: u64,
+    pub failed: u64,
+    pub dropped: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PluginRouteSummary {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permission: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PluginAdminItem {
+    pub id: String,
+    pub spec: String,
+    pub status: PluginStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub enabled: bool,
+    pub capabilities: Vec<String>,
+    pub routes: Vec<PluginRouteSummary>,
+    /// Declared hook subscriptions; absent when the plugin declares none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<PluginHookStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn plugin_hook_subscriptions(plugin: &RegisteredPlugin) -> Vec<String> {
+    let Some(Value::Array(entries)) = plugin
+        .manifest
+        .as_ref()
+        .and_then(|manifest| manifest.get("hooks"))
+    else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    for name in entries.iter().filter_map(Value::as_str).map(str::trim) {
+        if HOOK_EVENTS.contains(&name) && !out.iter().any(|e| e == name) {
+            out.push(name.to_string());
+        }
+    }
+    out
+}
+
+/// Bridge worker delivering one plugin's hook events in order.
+fn spawn_hook_worker(
+    bridge: BridgeInvocation,
+    plugin_context: Value,
+    stats: Arc<HookStats>,
+) -> mpsc::Sender<Value> {
+    let (sender, mut receiver) = mpsc::channel::<Value>(HOOK_QUEUE_CAPACITY);
+    tokio::spawn(async move {
+        while let Some(event) = receiver.recv().await {
+            let payload = json!({
+                "action": "hooks.event",
+                "payload": event,
+                "context": {
+                    "transport": "hook",
+                },
+                "plugin": plugin_context,
+            });
+            match invoke_bridge_action(&bridge, &payload).await {
+                Ok(_) => {
+                    stats.delivered.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(err) => {
+                    stats.failed.fetch_add(1, Ordering::Relaxed);
+                    tracing::debug!(
+                        target: "opencode_studio.plugin_runtime",
+                        code = err.code,
+                        error = %err.message,
+                        "plugin hook delivery failed"
+                    );
+                    *stats.last_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(err.message);
+                }
+            }
+        }
+    });
+    sender
+}
+
+fn build_hook_dispatchers(plugins: &[RegisteredPlugin]) -> HashMap<String, HookDispatcher> {
+    let mut out = HashMap::new();
+    for plugin in plugins {
+        let events = plugin_hook_subscriptions(plugin);
+        if events.is_empty() {
+            continue;
+        }
+        let Ok(bridge) = resolve_bridge_invocation(plugin) else {
+            continue;
+        };
+        let stats = Arc::new(HookStats::default());
+        let plugin_context = json!({
+            "id": plugin.id,
+            "spec": plugin.spec,
+            "rootPath": plugin.root_path.as_ref().map(|p| p.to_string_lossy().into_owned()),
+            "manifestPath": plugin.manifest_path.as_ref().map(|p| p.to_string_lossy().into_owned()),
+        });
+        let sender = spawn_hook_worker(bridge, plugin_context, stats.clone());
+        out.insert(
+            plugin.id.clone(),
+            HookDispatcher {
+                events,
+                sender,
+                stats,
+            },
+        );
+    }
+    out
+}
+
+fn hook_event_payload(raw: &Value) -> Option<&Value> {
+    if raw.get("type").and_then(Value::as_str).is_some() {
+        return Some(raw);
+    }
+    raw.get("payload")
+        .filter(|payload| payload.get("type").and_then(Value::as_str).is_some())
+}
+
+/// Map an already sanitized upstream event to a hook event, if it is one.
+fn derive_hook_event(raw: &Value) -> Option<(&'static str, Value)> {
+    let payload = hook_event_payload(raw)?;
+    let ty = payload.get("type").and_then(Value::as_str)?.trim();
+    let props = payload.get("properties").and_then(Value::as_object)?;
+
+    let (name, session_id, properties) = match ty {
+        "message.updated" => {
+            let info = props.get("info")?;
+            let completed = info
+                .get("time")
+                .and_then(|t| t.get("completed"))
+                .is_some_and(Value::is_number);
+            if info.get("role").and_then(Value::as_str) != Some("assistant") || !completed {
+                return None;
+            }
+            (
+                "message.completed",
+                info.get("sessionID").or_else(|| props.get("sessionID")),
+                info.clone(),
+            )
+        }
+        "session.idle" => (
+            "session.idle",
+            props.get("sessionID"),
+            Value::Object(props.clone()),
+        ),
+        "permission.asked" => (
+            "permission.asked",
+            props.get("sessionID"),
+            Value::Object(props.clone()),
+        ),
+        _ => return None,
+    };
+
+    Some((
+        name,
+        json!({
+            "event": name,
+            "directory": raw.get("directory").and_then(Value::as_str),
+            "sessionID": session_id.and_then(Value::as_str),
+            "properties": properties,
+            "timestamp": now_millis(),
+        }),
+    ))
+}
+
+/// Whether this completed message was already reported.
+fn hook_message_seen(message_id: &str) -> bool {
+    let mut seen = HOOK_COMPLETED_MESSAGES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if seen.iter().any(|id| id == message_id) {
+        return true;
+    }
+    seen.push_back(message_id.to_string());
+    while seen.len() > HOOK_COMPLETED_MESSAGES_MAX {
+        seen.pop_front();
+    }
+    false
+}
+
+/// Feed one sanitized upstream event to subscribed plugins. Delivery is
+/// queued per plugin so the SSE loop never waits on a bridge.
+pub(crate) fn observe_event(state: &Arc<crate::AppState>, raw: &Value) {
+    if !state.plugin_runtime.has_hook_subscribers() {
+        return;
+    }
+    let Some((name, event)) = derive_hook_event(raw) else {
+        return;
+    };
+    if name == "message.completed"
+        && let Some(message_id) = event["properties"].get("id").and_then(Value::as_str)
+        && hook_message_seen(message_id)
+    {
+        return;
+    }
+    let state = state.clone();
+    tokio::spawn(async move {
+        let disabled = {
+            let settings = state.settings.read().await;
+            settings_string_list(settings.extra.get(DISABLED_PLUGINS_KEY))
+        };
+        state.plugin_runtime.dispatch_hook(name, event, &disabled);
+    });
+}
+
+pub(crate) async fn admin_plugins_get(
+    State(state): State<Arc<crate::AppState>>,
+) -> ApiResult<Json<Value>> {
+    let disabled = {
+        let settings = state.settings.read().await;
+        settings_string_list(settings.extra.get(DISABLED_PLUGINS_KEY))
+    };
+    let plugins = state.plugin_runtime.admin_items(&disabled).await;
+    Ok(Json(json!({
+        "hookEvents": HOOK_EVENTS,
+        "plugins": plugins,
+    })))
+}
+
 fn plugin_supports_events(plugin: &RegisteredPlugin) -> bool {
     if plugin
         .capabilities
@@ -1635,7 +2485,7 @@ fn discover_plugin(spec: &str) -> RegisteredPlugin {
     }
 }
 
-fn parse_manifest_value(raw: &str) -> Result<Value, String> {
+pub(crate) fn parse_manifest_value(raw: &str) -> Result<Value, String> {
     match serde_json::from_str::<Value>(raw) {
         Ok(value) => Ok(value),
         Err(json_err) => match json5::from_str::<Value>(raw) {
@@ -1854,13 +2704,13 @@ fn find_package_root(path: &Path) -> Option<PathBuf> {
     }
 }
 
-fn read_package_json(root: &Path) -> Option<Value> {
+pub(crate) fn read_package_json(root: &Path) -> Option<Value> {
     let path = root.join("package.json");
     let raw = std::fs::read_to_string(path).ok()?;
     serde_json::from_str::<Value>(&raw).ok()
 }
 
-fn resolve_manifest_path(root: &Path, package_json: Option<&Value>) -> Option<PathBuf> {
+pub(crate) fn resolve_manifest_path(root: &Path, package_json: Option<&Value>) -> Option<PathBuf> {
     if let Some(path) = package_json
         .and_then(|pkg| pkg.get("opencodeStudio"))
         .and_then(|meta| meta.get("manifest"))
@@ -1932,7 +2782,7 @@ fn derive_plugin_id_from_spec(spec: &str) -> String {
     sanitize_plugin_id(&raw)
 }
 
-fn sanitize_plugin_id(raw: &str) -> String {
+pub(crate) fn sanitize_plugin_id(raw: &str) -> String {
     let mut out = String::with_capacity(raw.len());
     for ch in raw.chars() {
         if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || ch == '.' {
@@ -1957,11 +2807,15 @@ fn canonicalize_fallback(path: PathBuf) -> PathBuf {
 #[cfg(test)]
 mod tests {
     use super::{
-        PluginStatus, RegisteredPlugin, bridge_program_not_found_hint, discover_plugins,
-        extract_cursor_from_poll_result, extract_events_from_poll_result, normalize_specs,
-        resolve_bridge_invocation, resolve_bridge_program, resolve_bridge_program_override,
-        resolve_manifest_path, sanitize_plugin_id,
+        HookDispatcher, HookStats, PluginRuntime, PluginStatus, RegisteredPlugin,
+        bridge_program_not_found_hint, derive_hook_event, discover_plugins,
+        extract_cursor_from_poll_result, extract_events_from_poll_result, match_plugin_route,
+        normalize_specs, plugin_hook_subscriptions, plugin_routes, resolve_bridge_invocation,
+        resolve_bridge_program, resolve_bridge_program_override, resolve_manifest_path,
+        route_request_body, route_response, sanitize_plugin_id,
     };
+    use axum::body::{Bytes, to_bytes};
+    use axum::http::{HeaderMap, StatusCode, header::CONTENT_TYPE};
     use serde_json::{Value, json};
     use std::path::PathBuf;
     use std::time::Duration;
@@ -2471,4 +3325,177 @@ mod tests {
         assert_eq!(events[0].1.as_deref(), Some("cursor-1"));
         assert_eq!(events[0].2, json!({ "activePlan": { "id": 1 } }));
     }
+
+    #[test]
+    fn plugin_routes_parse_strings_and_objects() {
+        let plugin = plugin_with_manifest(
+            PathBuf::from("/tmp/plugin"),
+            json!({
+                "routes": [
+                    "get items/:id",
+                    {"method": "POST", "path": "/deploy/", "permission": "deploy", "description": "Ship it"},
+                    {"path": "/files/*rest"},
+                    {"method": "GET"},
+                    42,
+                ]
+            }),
+        );
+        let routes = plugin_routes(&plugin);
+        assert_eq!(routes.len(), 3);
+        assert_eq!(routes[0].method.as_deref(), Some("GET"));
+        assert_eq!(routes[0].path, "/items/:id");
+        assert_eq!(routes[1].path, "/deploy");
+        assert_eq!(routes[1].permission.as_deref(), Some("deploy"));
+        assert_eq!(routes[2].method, None);
+    }
+
+    #[test]
+    fn match_plugin_route_captures_params_and_reports_method_mismatch() {
+        let plugin = plugin_with_manifest(
+            PathBuf::from("/tmp/plugin"),
+            json!({"routes": ["GET /items/:id", "* /files/*rest", "POST /items"]}),
+        );
+        let routes = plugin_routes(&plugin);
+
+        let (route, params) = match_plugin_route(&routes, "GET", "/items/42").expect("match");
+        assert_eq!(route.path, "/items/:id");
+        assert_eq!(params.get("id"), Some(&json!("42")));
+
+        let (_, params) = match_plugin_route(&routes, "PUT", "/files/a/b.txt").expect("match");
+        assert_eq!(params.get("rest"), Some(&json!("a/b.txt")));
+
+        assert_eq!(
+            match_plugin_route(&routes, "DELETE", "/items/42").err(),
+            Some(StatusCode::METHOD_NOT_ALLOWED)
+        );
+        assert_eq!(
+            match_plugin_route(&routes, "GET", "/items/42/extra").err(),
+            Some(StatusCode::NOT_FOUND)
+        );
+    }
+
+    #[test]
+    fn route_request_body_decodes_json_text_and_binary() {
+        let mut json_headers = HeaderMap::new();
+        json_headers.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        let (body, encoding) =
+            route_request_body(&json_headers, &Bytes::from_static(b"{\"a\":1}")).expect("json");
+        assert_eq!((body, encoding), (json!({"a": 1}), "json"));
+        assert!(route_request_body(&json_headers, &Bytes::from_static(b"{")).is_err());
+
+        let plain = HeaderMap::new();
+        let (body, encoding) =
+            route_request_body(&plain, &Bytes::from_static(b"hello")).expect("text");
+        assert_eq!((body, encoding), (json!("hello"), "text"));
+        let (body, encoding) =
+            route_request_body(&plain, &Bytes::from_static(&[0xff, 0x00])).expect("binary");
+        assert_eq!((body, encoding), (json!("/wA="), "base64"));
+    }
+
+    #[tokio::test]
+    async fn route_response_maps_status_headers_and_body() {
+        let response = route_response(json!({
+            "status": 201,
+            "headers": {"x-plugin": "yes", "set-cookie": "nope=1"},
+            "body": "created",
+        }))
+        .expect("response");
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()["x-plugin"], "yes");
+        assert!(response.headers().get("set-cookie").is_none());
+        assert_eq!(
+            response.headers()[CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"created");
+
+        let response = route_response(json!({"items": [1, 2]})).expect("plain json");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], br#"{"items":[1,2]}"#);
+
+        assert!(route_response(json!({"status": 1000})).is_err());
+    }
+
+    #[test]
+    fn plugin_hook_subscriptions_keep_known_events_only() {
+        let plugin = plugin_with_manifest(
+            PathBuf::from("/tmp/plugin"),
+            json!({"hooks": ["session.idle", "message.part.updated", "session.idle", " permission.asked "]}),
+        );
+        assert_eq!(
+            plugin_hook_subscriptions(&plugin),
+            vec!["session.idle".to_string(), "permission.asked".to_string()]
+        );
+    }
+
+    #[test]
+    fn derive_hook_event_reports_completed_assistant_messages_idle_and_permissions() {
+        let completed = json!({
+            "directory": "/work/app",
+            "payload": {
+                "type": "message.updated",
+                "properties": {
+                    "sessionID": "s_1",
+                    "info": {"id": "m_1", "sessionID": "s_1", "role": "assistant", "time": {"created": 1, "completed": 2}},
+                },
+            },
+        });
+        let (name, event) = derive_hook_event(&completed).expect("completed message");
+        assert_eq!(name, "message.completed");
+        assert_eq!(event["directory"], "/work/app");
+        assert_eq!(event["sessionID"], "s_1");
+        assert_eq!(event["properties"]["id"], "m_1");
+
+        let streaming = json!({
+            "type": "message.updated",
+            "properties": {"info": {"id": "m_2", "role": "assistant", "time": {"created": 1}}},
+        });
+        assert!(derive_hook_event(&streaming).is_none());
+        let user = json!({
+            "type": "message.updated",
+            "properties": {"info": {"id": "m_3", "role": "user", "time": {"created": 1, "completed": 1}}},
+        });
+        assert!(derive_hook_event(&user).is_none());
+
+        let idle = json!({"type": "session.idle", "properties": {"sessionID": "s_1"}});
+        assert_eq!(
+            derive_hook_event(&idle).map(|(n, _)| n),
+            Some("session.idle")
+        );
+        let asked = json!({
+            "type": "permission.asked",
+            "properties": {"id": "p_1", "sessionID": "s_1", "permission": "bash"},
+        });
+        let (name, event) = derive_hook_event(&asked).expect("permission");
+        assert_eq!(name, "permission.asked");
+        assert_eq!(event["properties"]["permission"], "bash");
+        assert!(derive_hook_event(&json!({"type": "session.status", "properties": {}})).is_none());
+    }
+
+    #[tokio::test]
+    async fn dispatch_hook_drops_when_queue_is_full_and_skips_disabled_plugins() {
+        let runtime = PluginRuntime::new();
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+        let stats = std::sync::Arc::new(HookStats::default());
+        runtime.hooks.lock().unwrap().insert(
+            "p".to_string(),
+            HookDispatcher {
+                events: vec!["session.idle".to_string()],
+                sender,
+                stats: stats.clone(),
+            },
+        );
+        assert!(runtime.has_hook_subscribers());
+
+        runtime.dispatch_hook("permission.asked", json!({"n": 0}), &[]);
+        runtime.dispatch_hook("session.idle", json!({"n": 1}), &[]);
+        runtime.dispatch_hook("session.idle", json!({"n": 2}), &[]);
+        runtime.dispatch_hook("session.idle", json!({"n": 3}), &["p".to_string()]);
+
+        assert_eq!(receiver.recv().await, Some(json!({"n": 1})));
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(stats.dropped.load(std::sync::atomic::Ordering::Relaxed), 1);
+    }
 }
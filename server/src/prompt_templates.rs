@@ -0,0 +1,514 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{ApiResult, AppError};
+
+const MAX_NAME_CHARS: usize = 120;
+const MAX_BODY_BYTES: usize = 32 * 1024;
+const MAX_TAGS: usize = 16;
+const MAX_TAG_CHARS: usize = 40;
+const MAX_TEMPLATES: usize = 500;
+const MAX_VARIABLE_BYTES: usize = 64 * 1024;
+const STAGED_DIFF_MAX_BYTES: usize = 48 * 1024;
+
+/// Variables filled in by the server; a request value of the same name wins.
+const BUILTIN_VARIABLES: &[&str] = &["directory", "branch", "stagedFiles", "stagedDiff", "date"];
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// A reusable prompt whose body may reference `{{variables}}`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PromptTemplate {
+    pub id: String,
+    pub name: String,
+    pub body: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Variables referenced by `body`, in order of first use.
+    #[serde(default)]
+    pub variables: Vec<String>,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptStore {
+    #[serde(default)]
+    templates: Vec<PromptTemplate>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptListQuery {
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptCreateBody {
+    pub name: String,
+    pub body: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptUpdateBody {
+    pub name: Option<String>,
+    pub body: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptRenderBody {
+    /// Workspace for the built-in variables and the prompt.
+    pub directory: Option<String>,
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    /// Post the rendered text to this session; otherwise only render it.
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptRenderResponse {
+    pub text: String,
+    /// Built-in variables that were filled in by the server.
+    pub builtins: Vec<String>,
+    #[serde(rename = "sessionID")]
+    pub session_id: Option<String>,
+    pub sent: bool,
+}
+
+fn sanitize_name(raw: &str) -> ApiResult<String> {
+    let name = raw.trim();
+    if name.is_empty() {
+        return Err(AppError::bad_request("name is required"));
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(AppError::bad_request("name is too long"));
+    }
+    Ok(name.to_string())
+}
+
+fn sanitize_body(raw: &str) -> ApiResult<String> {
+    let body = raw.trim();
+    if body.is_empty() {
+        return Err(AppError::bad_request("body is required"));
+    }
+    if body.len() > MAX_BODY_BYTES {
+        return Err(AppError::payload_too_large("body is too large"));
+    }
+    Ok(body.to_string())
+}
+
+fn sanitize_tags(raw: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in raw {
+        let tag = tag.trim().to_ascii_lowercase();
+        if tag.is_empty() || tag.chars().count() > MAX_TAG_CHARS || out.contains(&tag) {
+            continue;
+        }
+        out.push(tag);
+        if out.len() >= MAX_TAGS {
+            break;
+        }
+    }
+    out
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Split `body` into literal text and `{{ name }}` references. Braces that do
+/// not enclose a valid name are kept as text.
+fn segments(body: &str) -> Vec<(bool, &str)> {
+    let mut out = Vec::new();
+    let mut rest = body;
+    while let Some(open) = rest.find("{{") {
+        let after = &rest[open + 2..];
+        let Some(close) = after.find("}}") else {
+            break;
+        };
+        let name = after[..close].trim();
+        if is_variable_name(name) {
+            out.push((false, &rest[..open]));
+            out.push((true, name));
+        } else {
+            out.push((false, &rest[..open + 2 + close + 2]));
+        }
+        rest = &after[close + 2..];
+    }
+    out.push((false, rest));
+    out
+}
+
+fn template_variables(body: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for (is_var, name) in segments(body) {
+        if is_var && !out.iter().any(|v| v == name) {
+            out.push(name.to_string());
+        }
+    }
+    out
+}
+
+/// Substitute every reference in `body`; `Err` lists the names without a value.
+pub(crate) fn render_body(
+    body: &str,
+    values: &BTreeMap<String, String>,
+) -> Result<String, Vec<String>> {
+    let mut out = String::with_capacity(body.len());
+    let mut missing = BTreeSet::new();
+    for (is_var, text) in segments(body) {
+        if !is_var {
+            out.push_str(text);
+        } else if let Some(value) = values.get(text) {
+            out.push_str(value);
+        } else {
+            missing.insert(text.to_string());
+        }
+    }
+    if missing.is_empty() {
+        Ok(out)
+    } else {
+        Err(missing.into_iter().collect())
+    }
+}
+
+async fn builtin_value(name: &str, directory: Option<&Path>) -> Option<String> {
+    if name == "date" {
+        return Some(crate::usage::day_for_millis(now_millis() as i64));
+    }
+    let dir = directory?;
+    match name {
+        "directory" => Some(dir.to_string_lossy().into_owned()),
+        "branch" => {
+            let (code, out, _) = crate::git::run_git(dir, &["rev-parse", "--abbrev-ref", "HEAD"])
+                .await
+                .ok()?;
+            (code == 0).then(|| out.trim().to_string())
+        }
+        "stagedFiles" | "stagedDiff" => {
+            let changes = crate::git::staged_changes(dir, STAGED_DIFF_MAX_BYTES)
+                .await
+                .ok()?;
+            if name == "stagedFiles" {
+                return Some(changes.files.join("\n"));
+            }
+            let mut diff = changes.patch.trim_end().to_string();
+            if changes.truncated {
+                diff.push_str("\n...(diff truncated)");
+            }
+            Some(diff)
+        }
+        _ => None,
+    }
+}
+
+/// Add values for the built-ins `body` references and the caller left unset;
+/// returns the names that were filled in.
+pub(crate) async fn fill_builtins(
+    body: &str,
+    values: &mut BTreeMap<String, String>,
+    directory: Option<&str>,
+) -> Vec<String> {
+    let mut filled = Vec::new();
+    for name in template_variables(body) {
+        if values.contains_key(&name) || !BUILTIN_VARIABLES.contains(&name.as_str()) {
+            continue;
+        }
+        if let Some(value) = builtin_value(&name, directory.map(Path::new)).await {
+            values.insert(name.clone(), value);
+            filled.push(name);
+        }
+    }
+    filled
+}
+
+async fn load_store(db: &crate::studio_db::StudioDb) -> ApiResult<PromptStore> {
+    db.get_json::<PromptStore>(crate::studio_db::KV_KEY_PROMPT_TEMPLATES)
+        .await
+        .map(Option::unwrap_or_default)
+        .map_err(AppError::internal)
+}
+
+async fn update_store<R>(
+    db: &crate::studio_db::StudioDb,
+    update: impl FnOnce(&mut PromptStore) -> ApiResult<R>,
+) -> ApiResult<R> {
+    db.update_json(crate::studio_db::KV_KEY_PROMPT_TEMPLATES, update)
+        .await
+        .map_err(AppError::internal)?
+}
+
+pub(crate) async fn prompt_list(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<PromptListQuery>,
+) -> ApiResult<Json<Vec<PromptTemplate>>> {
+    let tag = q
+        .tag
+        .as_deref()
+        .map(|t| t.trim().to_ascii_lowercase())
+        .filter(|t| !t.is_empty());
+    let store = load_store(state.studio_db.as_ref()).await?;
+    let mut out: Vec<PromptTemplate> = store
+        .templates
+        .into_iter()
+        .filter(|t| tag.as_ref().is_none_or(|tag| t.tags.contains(tag)))
+        .collect();
+    out.sort_by_key(|t| t.name.to_lowercase());
+    Ok(Json(out))
+}
+
+pub(crate) async fn prompt_get(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<PromptTemplate>> {
+    let store = load_store(state.studio_db.as_ref()).await?;
+    store
+        .templates
+        .into_iter()
+        .find(|t| t.id == id)
+        .map(Json)
+        .ok_or_else(|| AppError::not_found("Prompt template not found"))
+}
+
+pub(crate) async fn prompt_create(
+    State(state): State<Arc<crate::AppState>>,
+    Json(body): Json<PromptCreateBody>,
+) -> ApiResult<Json<PromptTemplate>> {
+    let name = sanitize_name(&body.name)?;
+    let text = sanitize_body(&body.body)?;
+    let tags = sanitize_tags(body.tags);
+
+    update_store(state.studio_db.as_ref(), |store| {
+        if store.templates.len() >= MAX_TEMPLATES {
+            return Err(AppError::bad_request("Too many prompt templates"));
+        }
+        if store.templates.iter().any(|t| t.name == name) {
+            return Err(AppError::conflict(
+                "A prompt template with this name already exists",
+            ));
+        }
+
+        let now = now_millis();
+        let template = PromptTemplate {
+            id: format!("prm_{}", uuid::Uuid::new_v4().simple()),
+            name,
+            variables: template_variables(&text),
+            body: text,
+            tags,
+            created_at: now,
+            updated_at: now,
+        };
+        store.templates.push(template.clone());
+        Ok(Json(template))
+    })
+    .await
+}
+
+pub(crate) async fn prompt_update(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(id): AxumPath<String>,
+    Json(body): Json<PromptUpdateBody>,
+) -> ApiResult<Json<PromptTemplate>> {
+    let name = body.name.as_deref().map(sanitize_name).transpose()?;
+    let text = body.body.as_deref().map(sanitize_body).transpose()?;
+    let tags = body.tags.map(sanitize_tags);
+
+    update_store(state.studio_db.as_ref(), |store| {
+        if let Some(name) = name.as_ref()
+            && store
+                .templates
+                .iter()
+                .any(|t| &t.name == name && t.id != id)
+        {
+            return Err(AppError::conflict(
+                "A prompt template with this name already exists",
+            ));
+        }
+        let template = store
+            .templates
+            .iter_mut()
+            .find(|t| t.id == id)
+            .ok_or_else(|| AppError::not_found("Prompt template not found"))?;
+        if let Some(name) = name {
+            template.name = name;
+        }
+        if let Some(text) = text {
+            template.variables = template_variables(&text);
+            template.body = text;
+        }
+        if let Some(tags) = tags {
+            template.tags = tags;
+        }
+        template.updated_at = now_millis();
+        Ok(Json(template.clone()))
+    })
+    .await
+}
+
+pub(crate) async fn prompt_delete(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<Value>> {
+    update_store(state.studio_db.as_ref(), |store| {
+        let before = store.templates.len();
+        store.templates.retain(|t| t.id != id);
+        if store.templates.len() == before {
+            return Err(AppError::not_found("Prompt template not found"));
+        }
+        Ok(Json(json!({ "success": true })))
+    })
+    .await
+}
+
+/// Fill in a template's variables and, with `sessionId`, send the result as
+/// the session's next prompt. Built-ins are only computed when referenced.
+pub(crate) async fn prompt_render(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(id): AxumPath<String>,
+    body: Option<Json<PromptRenderBody>>,
+) -> ApiResult<Response> {
+    let body = body.map(|Json(b)| b).unwrap_or_default();
+    if body.variables.values().map(String::len).sum::<usize>() > MAX_VARIABLE_BYTES {
+        return Err(AppError::payload_too_large("variables are too large"));
+    }
+    let template = load_store(state.studio_db.as_ref())
+        .await?
+        .templates
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| AppError::not_found("Prompt template not found"))?;
+    let directory = body
+        .directory
+        .as_deref()
+        .and_then(crate::path_utils::normalize_directory_for_match);
+
+    let mut values = body.variables;
+    let builtins = fill_builtins(&template.body, &mut values, directory.as_deref()).await;
+    let text = match render_body(&template.body, &values) {
+        Ok(text) => text,
+        Err(missing) => {
+            return Ok((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!("Missing values for: {}", missing.join(", ")),
+                    "code": "missing_variables",
+                    "missing": missing,
+                })),
+            )
+                .into_response());
+        }
+    };
+
+    let session_id = body
+        .session_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string);
+    if let Some(sid) = session_id.as_deref() {
+        // The session's own project decides the instance, not the directory
+        // used for the built-ins.
+        let session_directory = state
+            .directory_session_index
+            .summary(sid)
+            .map(|summary| summary.directory_path)
+            .or(directory);
+        let upstream = state
+            .opencode
+            .for_directory(session_directory.as_deref())
+            .await;
+        let Some(bridge) = upstream.bridge().await else {
+            return Err(AppError::bad_gateway("OpenCode is not running"));
+        };
+        let prompt = json!({ "parts": [{ "type": "text", "text": text }] });
+        crate::quick_captures::send_prompt(&bridge, sid, session_directory.as_deref(), &prompt)
+            .await?;
+    }
+
+    Ok(Json(PromptRenderResponse {
+        sent: session_id.is_some(),
+        text,
+        builtins,
+        session_id,
+    })
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variables_are_listed_once_in_order_of_use() {
+        let body = "Review {{ file }} on {{branch}}; {{file}} again. Keep {{not valid}} and {{";
+        assert_eq!(template_variables(body), vec!["file", "branch"]);
+    }
+
+    #[test]
+    fn render_substitutes_values_and_reports_missing_names() {
+        let body = "Fix {{issue}} in {{directory}} ({{ issue }}).";
+        let mut values = BTreeMap::new();
+        values.insert("issue".to_string(), "#12".to_string());
+        assert_eq!(
+            render_body(body, &values),
+            Err(vec!["directory".to_string()])
+        );
+
+        values.insert("directory".to_string(), "/repo".to_string());
+        assert_eq!(
+            render_body(body, &values).unwrap(),
+            "Fix #12 in /repo (#12)."
+        );
+        assert_eq!(
+            render_body("literal {{a b}}", &BTreeMap::new()).unwrap(),
+            "literal {{a b}}"
+        );
+    }
+
+    #[tokio::test]
+    async fn builtin_values_need_a_directory_except_date() {
+        assert!(builtin_value("branch", None).await.is_none());
+        assert_eq!(
+            builtin_value("date", None).await.map(|d| d.len()),
+            Some("2024-05-01".len())
+        );
+        let tmp = tempfile::tempdir().expect("tempdir");
+        assert_eq!(
+            builtin_value("directory", Some(tmp.path()))
+                .await
+                .as_deref(),
+            tmp.path().to_str()
+        );
+    }
+}
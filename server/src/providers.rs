@@ -1,21 +1,28 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use axum::{
     Json,
     extract::{Path as AxumPath, Query, State},
     http::HeaderMap,
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::opencode_config::{PROVIDER_OVERRIDES_KEY, ProviderOverride};
 use crate::{ApiResult, AppError, fs, opencode_auth, opencode_config};
 
-#[derive(Debug, Deserialize)]
+const PING_DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+const PING_MAX_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct EnvCheckRequest {
     pub vars: Vec<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct EnvCheckResponse {
     pub present: Vec<String>,
@@ -64,23 +71,17 @@ pub async fn env_check_post(
     Ok(Json(EnvCheckResponse { present, missing }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ProviderDirectoryQuery {
     pub directory: Option<String>,
 }
 
-pub async fn provider_source_get(
-    State(_state): State<Arc<crate::AppState>>,
-    headers: HeaderMap,
-    Query(q): Query<ProviderDirectoryQuery>,
-    AxumPath(provider_id): AxumPath<String>,
-) -> ApiResult<Json<ProviderSourceResponse>> {
-    let provider_id = provider_id.trim().to_string();
-    if provider_id.is_empty() {
-        return Err(AppError::bad_request("Provider ID is required"));
-    }
-
-    // Directory is optional. If a directory header/query is provided and invalid, return 400.
+/// Directory is optional. If a directory header/query is provided and
+/// invalid, return 400.
+async fn requested_directory(
+    headers: &HeaderMap,
+    q: &ProviderDirectoryQuery,
+) -> ApiResult<Option<String>> {
     let requested = headers
         .get("x-opencode-directory")
         .and_then(|v| v.to_str().ok())
@@ -93,12 +94,27 @@ pub async fn provider_source_get(
                 .filter(|v| !v.is_empty())
         });
 
-    let working_directory: Option<String> = if let Some(req) = requested {
-        let abs = fs::validate_directory(&req).await?;
-        Some(abs.to_string_lossy().into_owned())
-    } else {
-        None
-    };
+    match requested {
+        Some(req) => {
+            let abs = fs::validate_directory(&req).await?;
+            Ok(Some(abs.to_string_lossy().into_owned()))
+        }
+        None => Ok(None),
+    }
+}
+
+pub async fn provider_source_get(
+    State(_state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Query(q): Query<ProviderDirectoryQuery>,
+    AxumPath(provider_id): AxumPath<String>,
+) -> ApiResult<Json<ProviderSourceResponse>> {
+    let provider_id = provider_id.trim().to_string();
+    if provider_id.is_empty() {
+        return Err(AppError::bad_request("Provider ID is required"));
+    }
+
+    let working_directory = requested_directory(&headers, &q).await?;
 
     let working_path = working_directory.as_deref().map(Path::new);
     let sources = match opencode_config::get_provider_sources(&provider_id, working_path) {
@@ -109,7 +125,8 @@ pub async fn provider_source_get(
     let auth_exists = opencode_auth::get_provider_auth(&provider_id)
         .ok()
         .flatten()
-        .is_some();
+        .is_some()
+        || crate::secrets::has_provider_api_key(&provider_id);
 
     // Ensure response shape stays stable for the UI.
     let mut out_sources = sources;
@@ -131,9 +148,513 @@ pub async fn provider_source_get(
     }))
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProviderSourceResponse {
     pub provider_id: String,
     pub sources: serde_json::Value,
 }
+
+/// Unsaved values to try; unset fields fall back to the stored override.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderOverridePingBody {
+    pub base_url: Option<String>,
+    pub headers: Option<BTreeMap<String, String>>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderOverridePingResponse {
+    pub provider_id: String,
+    pub url: String,
+    pub ok: bool,
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    /// `certificate`, `connect`, `timeout` or `request`.
+    pub error_kind: Option<&'static str>,
+    pub error: Option<String>,
+}
+
+fn request_headers(headers: &BTreeMap<String, String>) -> ApiResult<reqwest::header::HeaderMap> {
+    let mut header_map = reqwest::header::HeaderMap::new();
+    for (name, value) in headers {
+        let name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| AppError::bad_request(format!("Invalid header name: {name}")))?;
+        let value = reqwest::header::HeaderValue::from_str(value)
+            .map_err(|_| AppError::bad_request(format!("Invalid value for header {name}")))?;
+        header_map.insert(name, value);
+    }
+    Ok(header_map)
+}
+
+/// Check that a provider override's base URL answers with its headers by
+/// requesting `<baseUrl>/models` (the OpenAI-compatible model list).
+pub async fn provider_override_ping_post(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(provider_id): AxumPath<String>,
+    Json(body): Json<ProviderOverridePingBody>,
+) -> ApiResult<Json<ProviderOverridePingResponse>> {
+    let provider_id = provider_id.trim().to_string();
+    if provider_id.is_empty() {
+        return Err(AppError::bad_request("Provider ID is required"));
+    }
+
+    let (stored, stored_raw) = {
+        let settings = state.settings.read().await;
+        let stored = opencode_config::provider_overrides(&settings)
+            .remove(&provider_id)
+            .unwrap_or_default();
+        let raw = settings.extra.get(PROVIDER_OVERRIDES_KEY).cloned();
+        (stored, raw)
+    };
+    let headers = match body.headers {
+        Some(headers) => {
+            // Masked values from the settings view stand for the stored secret.
+            let mut incoming = serde_json::json!({ provider_id.as_str(): { "headers": headers } });
+            opencode_config::restore_masked_headers(&mut incoming, stored_raw.as_ref());
+            serde_json::from_value(incoming[provider_id.as_str()]["headers"].take())
+                .unwrap_or_default()
+        }
+        None => stored.headers,
+    };
+    let target = ProviderOverride {
+        base_url: body.base_url.or(stored.base_url),
+        headers,
+        timeout_ms: body.timeout_ms.or(stored.timeout_ms),
+    };
+
+    let base_url = target
+        .base_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| AppError::bad_request("baseUrl is required to ping a provider"))?;
+    let parsed = url::Url::parse(base_url).map_err(|_| AppError::bad_request("Invalid baseUrl"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(AppError::bad_request("baseUrl must use http or https"));
+    }
+    let url = format!("{}/models", base_url.trim_end_matches('/'));
+
+    let header_map = request_headers(&target.headers)?;
+    let timeout = target
+        .timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(PING_DEFAULT_TIMEOUT)
+        .min(PING_MAX_TIMEOUT);
+
+    let client = crate::tls_roots::client_builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|err| AppError::internal(err.to_string()))?;
+    let started = Instant::now();
+    let result = client.get(&url).headers(header_map).send().await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    Ok(Json(match result {
+        Ok(resp) => ProviderOverridePingResponse {
+            provider_id,
+            url,
+            ok: resp.status().is_success(),
+            status: Some(resp.status().as_u16()),
+            latency_ms,
+            error_kind: None,
+            error: None,
+        },
+        Err(err) => {
+            let text = crate::tls_roots::error_chain_text(&err);
+            ProviderOverridePingResponse {
+                provider_id,
+                url,
+                ok: false,
+                status: None,
+                latency_ms,
+                error_kind: Some(crate::tls_roots::classify_probe_error(&err, &text)),
+                error: Some(text),
+            }
+        }
+    }))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyScheme {
+    Bearer,
+    /// Anthropic: `x-api-key` plus an API version header.
+    Anthropic,
+    /// Google AI Studio: `x-goog-api-key`.
+    Google,
+}
+
+struct KnownProvider {
+    id: &'static str,
+    base_url: &'static str,
+    scheme: KeyScheme,
+    /// Cheapest request that still requires a valid key.
+    probe_path: &'static str,
+    env: &'static [&'static str],
+}
+
+const KNOWN_PROVIDERS: &[KnownProvider] = &[
+    KnownProvider {
+        id: "openai",
+        base_url: "https://api.openai.com/v1",
+        scheme: KeyScheme::Bearer,
+        probe_path: "/models",
+        env: &["OPENAI_API_KEY"],
+    },
+    KnownProvider {
+        id: "anthropic",
+        base_url: "https://api.anthropic.com/v1",
+        scheme: KeyScheme::Anthropic,
+        probe_path: "/models",
+        env: &["ANTHROPIC_API_KEY"],
+    },
+    KnownProvider {
+        id: "google",
+        base_url: "https://generativelanguage.googleapis.com/v1beta",
+        scheme: KeyScheme::Google,
+        probe_path: "/models",
+        env: &["GOOGLE_GENERATIVE_AI_API_KEY", "GEMINI_API_KEY"],
+    },
+    KnownProvider {
+        // The OpenRouter model list is public; `/key` is not.
+        id: "openrouter",
+        base_url: "https://openrouter.ai/api/v1",
+        scheme: KeyScheme::Bearer,
+        probe_path: "/key",
+        env: &["OPENROUTER_API_KEY"],
+    },
+    KnownProvider {
+        id: "groq",
+        base_url: "https://api.groq.com/openai/v1",
+        scheme: KeyScheme::Bearer,
+        probe_path: "/models",
+        env: &["GROQ_API_KEY"],
+    },
+    KnownProvider {
+        id: "deepseek",
+        base_url: "https://api.deepseek.com",
+        scheme: KeyScheme::Bearer,
+        probe_path: "/models",
+        env: &["DEEPSEEK_API_KEY"],
+    },
+    KnownProvider {
+        id: "mistral",
+        base_url: "https://api.mistral.ai/v1",
+        scheme: KeyScheme::Bearer,
+        probe_path: "/models",
+        env: &["MISTRAL_API_KEY"],
+    },
+    KnownProvider {
+        id: "xai",
+        base_url: "https://api.x.ai/v1",
+        scheme: KeyScheme::Bearer,
+        probe_path: "/models",
+        env: &["XAI_API_KEY"],
+    },
+];
+
+/// Unsaved values to try; unset fields fall back to what OpenCode would use.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderValidateBody {
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderValidateResponse {
+    pub provider_id: String,
+    pub url: Option<String>,
+    pub ok: bool,
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    /// Where the key came from: `request`, `studio`, `auth`, `config` or `env`.
+    pub credential_source: Option<&'static str>,
+    /// `missing_credentials`, `unsupported_auth`, `missing_base_url`,
+    /// `unauthorized`, `forbidden`, `rate_limited`, `not_found`, `server`,
+    /// `http`, or a transport failure (`certificate`, `connect`, `timeout`,
+    /// `request`).
+    pub error_kind: Option<&'static str>,
+    pub error: Option<String>,
+}
+
+impl ProviderValidateResponse {
+    fn failed(provider_id: String, kind: &'static str, error: impl Into<String>) -> Self {
+        Self {
+            provider_id,
+            url: None,
+            ok: false,
+            status: None,
+            latency_ms: 0,
+            credential_source: None,
+            error_kind: Some(kind),
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// Resolve `{env:VAR}` references the way OpenCode does; other values are
+/// used as-is. `{file:...}` references are not followed.
+fn resolve_config_value(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    if let Some(name) = raw.strip_prefix("{env:").and_then(|v| v.strip_suffix('}')) {
+        return std::env::var(name.trim())
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+    }
+    if raw.is_empty() || raw.starts_with("{file:") {
+        return None;
+    }
+    Some(raw.to_string())
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn classify_status(status: reqwest::StatusCode) -> Option<&'static str> {
+    match status.as_u16() {
+        200..=299 => None,
+        401 => Some("unauthorized"),
+        403 => Some("forbidden"),
+        404 => Some("not_found"),
+        429 => Some("rate_limited"),
+        500..=599 => Some("server"),
+        _ => Some("http"),
+    }
+}
+
+/// Verify a provider key by making the smallest authenticated request the
+/// provider offers (usually the model list), straight from the server.
+pub async fn provider_validate_post(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Query(q): Query<ProviderDirectoryQuery>,
+    AxumPath(provider_id): AxumPath<String>,
+    body: Option<Json<ProviderValidateBody>>,
+) -> ApiResult<Json<ProviderValidateResponse>> {
+    let provider_id = provider_id.trim().to_string();
+    if provider_id.is_empty() {
+        return Err(AppError::bad_request("Provider ID is required"));
+    }
+    let body = body.map(|Json(body)| body).unwrap_or_default();
+    let working_directory = requested_directory(&headers, &q).await?;
+    let known = KNOWN_PROVIDERS.iter().find(|p| p.id == provider_id);
+
+    // Later layers win, as in OpenCode: custom > project > user.
+    let (user, project, custom, _) = opencode_config::OpenCodeConfigStore::from_env()
+        .read_config_layers(working_directory.as_deref().map(Path::new))
+        .map_err(|err| AppError::internal(err.to_string()))?;
+    let layers: Vec<_> = [&custom, &project, &user]
+        .into_iter()
+        .filter_map(|cfg| cfg.provider.get(&provider_id))
+        .collect();
+    let options = || layers.iter().filter_map(|p| p.options.as_ref());
+    let config_key = options().find_map(|o| o.api_key.as_deref().and_then(resolve_config_value));
+    let config_base_url = options().find_map(|o| o.base_url.clone());
+    let env_names: Vec<String> = layers
+        .iter()
+        .find_map(|p| p.env.clone())
+        .unwrap_or_else(|| {
+            known
+                .map(|k| k.env.iter().map(|s| s.to_string()).collect())
+                .unwrap_or_default()
+        });
+
+    let stored_override = {
+        let settings = state.settings.read().await;
+        opencode_config::provider_overrides(&settings)
+            .remove(&provider_id)
+            .unwrap_or_default()
+    };
+
+    let auth = opencode_auth::get_provider_auth(&provider_id)
+        .ok()
+        .flatten();
+    let auth_type = auth
+        .as_ref()
+        .and_then(|v| v.get("type"))
+        .and_then(|v| v.as_str());
+    let auth_key = auth
+        .as_ref()
+        .filter(|_| auth_type == Some("api"))
+        .and_then(|v| v.get("key"))
+        .and_then(|v| v.as_str())
+        .map(str::to_string);
+
+    let credential = [
+        ("request", non_empty(body.api_key)),
+        (
+            "studio",
+            crate::secrets::provider_api_keys().remove(&provider_id),
+        ),
+        ("auth", non_empty(auth_key)),
+        ("config", config_key),
+        (
+            "env",
+            env_names
+                .iter()
+                .find_map(|name| non_empty(std::env::var(name).ok())),
+        ),
+    ]
+    .into_iter()
+    .find_map(|(source, key)| key.map(|key| (source, key)));
+
+    let Some((credential_source, api_key)) = credential else {
+        let (kind, error) = if auth_type.is_some_and(|t| t != "api") {
+            (
+                "unsupported_auth",
+                "This provider signs in with OAuth; start a session to verify it",
+            )
+        } else {
+            ("missing_credentials", "No API key found for this provider")
+        };
+        return Ok(Json(ProviderValidateResponse::failed(
+            provider_id,
+            kind,
+            error,
+        )));
+    };
+
+    let base_url = non_empty(body.base_url)
+        .or(non_empty(stored_override.base_url))
+        .or(non_empty(config_base_url))
+        .or_else(|| known.map(|k| k.base_url.to_string()));
+    let Some(base_url) = base_url else {
+        let mut out = ProviderValidateResponse::failed(
+            provider_id,
+            "missing_base_url",
+            "Set a baseURL for this provider to validate it",
+        );
+        out.credential_source = Some(credential_source);
+        return Ok(Json(out));
+    };
+    let parsed =
+        url::Url::parse(&base_url).map_err(|_| AppError::bad_request("Invalid baseUrl"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(AppError::bad_request("baseUrl must use http or https"));
+    }
+    let url = format!(
+        "{}{}",
+        base_url.trim_end_matches('/'),
+        known.map_or("/models", |k| k.probe_path)
+    );
+
+    let mut header_map = request_headers(&stored_override.headers)?;
+    let scheme = known.map_or(KeyScheme::Bearer, |k| k.scheme);
+    let auth_header = match scheme {
+        KeyScheme::Bearer => ("authorization", format!("Bearer {api_key}")),
+        KeyScheme::Anthropic => {
+            header_map.insert(
+                "anthropic-version",
+                reqwest::header::HeaderValue::from_static("2023-06-01"),
+            );
+            ("x-api-key", api_key)
+        }
+        KeyScheme::Google => ("x-goog-api-key", api_key),
+    };
+    let mut value = reqwest::header::HeaderValue::from_str(&auth_header.1)
+        .map_err(|_| AppError::bad_request("API key contains invalid characters"))?;
+    value.set_sensitive(true);
+    header_map.insert(auth_header.0, value);
+
+    let timeout = body
+        .timeout_ms
+        .or(stored_override.timeout_ms)
+        .map(Duration::from_millis)
+        .unwrap_or(PING_DEFAULT_TIMEOUT)
+        .min(PING_MAX_TIMEOUT);
+    let client = crate::tls_roots::client_builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|err| AppError::internal(err.to_string()))?;
+    let started = Instant::now();
+    let result = client.get(&url).headers(header_map).send().await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let mut out = ProviderValidateResponse {
+        provider_id,
+        url: Some(url),
+        ok: false,
+        status: None,
+        latency_ms,
+        credential_source: Some(credential_source),
+        error_kind: None,
+        error: None,
+    };
+    match result {
+        Ok(resp) => {
+            let status = resp.status();
+            out.status = Some(status.as_u16());
+            out.error_kind = classify_status(status);
+            out.ok = out.error_kind.is_none();
+            if !out.ok {
+                let text = resp.text().await.unwrap_or_default();
+                let text = text.trim();
+                out.error = Some(if text.is_empty() {
+                    status.to_string()
+                } else {
+                    text.chars().take(500).collect()
+                });
+            }
+        }
+        Err(err) => {
+            let text = crate::tls_roots::error_chain_text(&err);
+            out.error_kind = Some(crate::tls_roots::classify_probe_error(&err, &text));
+            out.error = Some(text);
+        }
+    }
+    Ok(Json(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_config_value_follows_env_references() {
+        let _env_lock = crate::test_support::ENV_LOCK.lock().unwrap();
+        unsafe {
+            std::env::set_var("OPENCODE_STUDIO_TEST_PROVIDER_KEY", "sk-test");
+        }
+        assert_eq!(
+            resolve_config_value("{env:OPENCODE_STUDIO_TEST_PROVIDER_KEY}").as_deref(),
+            Some("sk-test")
+        );
+        assert_eq!(
+            resolve_config_value("{env:OPENCODE_STUDIO_TEST_MISSING}"),
+            None
+        );
+        assert_eq!(resolve_config_value("{file:~/.key}"), None);
+        assert_eq!(
+            resolve_config_value(" sk-inline ").as_deref(),
+            Some("sk-inline")
+        );
+        unsafe {
+            std::env::remove_var("OPENCODE_STUDIO_TEST_PROVIDER_KEY");
+        }
+    }
+
+    #[test]
+    fn classify_status_separates_auth_from_transient_failures() {
+        use reqwest::StatusCode;
+        assert_eq!(classify_status(StatusCode::OK), None);
+        assert_eq!(
+            classify_status(StatusCode::UNAUTHORIZED),
+            Some("unauthorized")
+        );
+        assert_eq!(classify_status(StatusCode::FORBIDDEN), Some("forbidden"));
+        assert_eq!(
+            classify_status(StatusCode::TOO_MANY_REQUESTS),
+            Some("rate_limited")
+        );
+        assert_eq!(classify_status(StatusCode::BAD_GATEWAY), Some("server"));
+        assert_eq!(classify_status(StatusCode::BAD_REQUEST), Some("http"));
+    }
+}
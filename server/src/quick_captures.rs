@@ -0,0 +1,359 @@
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{ApiResult, AppError};
+
+const MAX_TEXT_BYTES: usize = 16 * 1024;
+const MAX_TITLE_CHARS: usize = 120;
+const MAX_CAPTURES: usize = 200;
+const MAX_SOURCE_CHARS: usize = 32;
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// A prompt or note captured while no session was open (e.g. from the
+/// desktop hotkey), waiting to be sent to a session.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QuickCapture {
+    pub id: String,
+    pub text: String,
+    #[serde(default)]
+    pub directory: Option<String>,
+    /// Where the capture came from, e.g. `hotkey` or `web`.
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CaptureStore {
+    #[serde(default)]
+    captures: Vec<QuickCapture>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickCaptureCreateBody {
+    pub text: String,
+    pub directory: Option<String>,
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickCaptureUpdateBody {
+    pub text: Option<String>,
+    /// `null` clears the directory; omitting the field keeps it.
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub directory: Option<Option<String>>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickCaptureConvertBody {
+    /// Append to this session; otherwise a new session is created.
+    pub session_id: Option<String>,
+    pub directory: Option<String>,
+    pub title: Option<String>,
+}
+
+fn deserialize_present<'de, D>(deserializer: D) -> Result<Option<Option<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer).map(Some)
+}
+
+fn sanitize_text(raw: &str) -> ApiResult<String> {
+    let text = raw.trim();
+    if text.is_empty() {
+        return Err(AppError::bad_request("text is required"));
+    }
+    if text.len() > MAX_TEXT_BYTES {
+        return Err(AppError::payload_too_large("text is too large"));
+    }
+    Ok(text.to_string())
+}
+
+fn sanitize_directory(raw: Option<&str>) -> Option<String> {
+    raw.and_then(crate::path_utils::normalize_directory_for_match)
+}
+
+fn sanitize_source(raw: Option<&str>) -> Option<String> {
+    raw.map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.chars().take(MAX_SOURCE_CHARS).collect())
+}
+
+/// Session title derived from the first line of the capture.
+fn default_title(text: &str) -> String {
+    let first = text.lines().next().unwrap_or_default().trim();
+    let mut title: String = first.chars().take(MAX_TITLE_CHARS).collect();
+    if first.chars().count() > MAX_TITLE_CHARS {
+        title.push('…');
+    }
+    title
+}
+
+async fn load_store(db: &crate::studio_db::StudioDb) -> ApiResult<CaptureStore> {
+    db.get_json::<CaptureStore>(crate::studio_db::KV_KEY_QUICK_CAPTURES)
+        .await
+        .map(Option::unwrap_or_default)
+        .map_err(AppError::internal)
+}
+
+async fn update_store<R>(
+    db: &crate::studio_db::StudioDb,
+    update: impl FnOnce(&mut CaptureStore) -> ApiResult<R>,
+) -> ApiResult<R> {
+    db.update_json(crate::studio_db::KV_KEY_QUICK_CAPTURES, update)
+        .await
+        .map_err(AppError::internal)?
+}
+
+pub(crate) async fn quick_capture_list(
+    State(state): State<Arc<crate::AppState>>,
+) -> ApiResult<Json<Vec<QuickCapture>>> {
+    let store = load_store(state.studio_db.as_ref()).await?;
+    Ok(Json(store.captures))
+}
+
+pub(crate) async fn quick_capture_create(
+    State(state): State<Arc<crate::AppState>>,
+    Json(body): Json<QuickCaptureCreateBody>,
+) -> ApiResult<Json<QuickCapture>> {
+    let text = sanitize_text(&body.text)?;
+
+    update_store(state.studio_db.as_ref(), |store| {
+        if store.captures.len() >= MAX_CAPTURES {
+            return Err(AppError::bad_request(
+                "Quick capture queue is full; convert or discard some captures first",
+            ));
+        }
+
+        let now = now_millis();
+        let capture = QuickCapture {
+            id: format!("cap_{}", uuid::Uuid::new_v4().simple()),
+            text,
+            directory: sanitize_directory(body.directory.as_deref()),
+            source: sanitize_source(body.source.as_deref()),
+            created_at: now,
+            updated_at: now,
+        };
+        store.captures.push(capture.clone());
+        Ok(Json(capture))
+    })
+    .await
+}
+
+pub(crate) async fn quick_capture_update(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(id): AxumPath<String>,
+    Json(body): Json<QuickCaptureUpdateBody>,
+) -> ApiResult<Json<QuickCapture>> {
+    let text = body.text.as_deref().map(sanitize_text).transpose()?;
+
+    update_store(state.studio_db.as_ref(), |store| {
+        let capture = store
+            .captures
+            .iter_mut()
+            .find(|c| c.id == id)
+            .ok_or_else(|| AppError::not_found("Quick capture not found"))?;
+        if let Some(text) = text {
+            capture.text = text;
+        }
+        if let Some(directory) = body.directory {
+            capture.directory = sanitize_directory(directory.as_deref());
+        }
+        capture.updated_at = now_millis();
+        Ok(Json(capture.clone()))
+    })
+    .await
+}
+
+pub(crate) async fn quick_capture_delete(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<Value>> {
+    update_store(state.studio_db.as_ref(), |store| {
+        let before = store.captures.len();
+        store.captures.retain(|c| c.id != id);
+        if store.captures.len() == before {
+            return Err(AppError::not_found("Quick capture not found"));
+        }
+        Ok(Json(json!({ "success": true })))
+    })
+    .await
+}
+
+fn with_directory(url: String, directory: Option<&str>) -> String {
+    match directory {
+        Some(dir) => format!("{url}?directory={}", urlencoding::encode(dir)),
+        None => url,
+    }
+}
+
+async fn upstream_json(request: reqwest::RequestBuilder, what: &str) -> ApiResult<Option<Value>> {
+    let resp = request
+        .send()
+        .await
+        .map_err(|_| AppError::bad_gateway(format!("OpenCode is unreachable ({what})")))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let detail = resp.text().await.unwrap_or_default();
+        return Err(AppError::bad_gateway(format!(
+            "OpenCode rejected {what} ({status}): {}",
+            detail.trim()
+        )));
+    }
+    let bytes = resp
+        .bytes()
+        .await
+        .map_err(|_| AppError::bad_gateway("Failed to read OpenCode response"))?;
+    Ok(serde_json::from_slice(&bytes).ok())
+}
+
+/// Queue `prompt` on a session without waiting for the reply.
+pub(crate) async fn send_prompt(
+    bridge: &crate::opencode::OpenCodeBridge,
+    session_id: &str,
+    directory: Option<&str>,
+    prompt: &Value,
+) -> ApiResult<()> {
+    let base = bridge.base_url.trim_end_matches('/');
+    if bridge.supports_prompt_async() {
+        let url = with_directory(
+            format!(
+                "{base}/session/{}/prompt_async",
+                urlencoding::encode(session_id)
+            ),
+            directory,
+        );
+        upstream_json(bridge.client.post(url).json(prompt), "prompt").await?;
+    } else {
+        // Older upstream: /message blocks until the reply finishes, so send it detached.
+        let url = with_directory(
+            format!("{base}/session/{}/message", urlencoding::encode(session_id)),
+            directory,
+        );
+        let request = bridge.sse_client.post(url).json(prompt);
+        tokio::spawn(async move {
+            if let Err(err) = request.send().await {
+                tracing::warn!(error = %err, "OpenCode prompt via /message failed");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Send a capture as the next prompt of an existing session, or of a new
+/// session created for it. The capture leaves the queue once OpenCode
+/// accepts the prompt.
+pub(crate) async fn quick_capture_convert(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(id): AxumPath<String>,
+    body: Option<Json<QuickCaptureConvertBody>>,
+) -> ApiResult<Json<Value>> {
+    let body = body.map(|Json(b)| b).unwrap_or_default();
+    let capture = load_store(state.studio_db.as_ref())
+        .await?
+        .captures
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| AppError::not_found("Quick capture not found"))?;
+    let directory = sanitize_directory(body.directory.as_deref()).or(capture.directory.clone());
+
+    let upstream = state.opencode.for_directory(directory.as_deref()).await;
+    let Some(bridge) = upstream.bridge().await else {
+        return Err(AppError::bad_gateway("OpenCode is not running"));
+    };
+    let base = bridge.base_url.trim_end_matches('/').to_string();
+
+    let existing = body
+        .session_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string);
+    let created = existing.is_none();
+    let session_id = match existing {
+        Some(sid) => sid,
+        None => {
+            let title = body
+                .title
+                .as_deref()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(|t| t.chars().take(MAX_TITLE_CHARS).collect())
+                .unwrap_or_else(|| default_title(&capture.text));
+            let url = with_directory(format!("{base}/session"), directory.as_deref());
+            let session = upstream_json(
+                bridge.client.post(url).json(&json!({ "title": title })),
+                "session create",
+            )
+            .await?;
+            session
+                .as_ref()
+                .and_then(|s| s.get("id"))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .ok_or_else(|| AppError::bad_gateway("OpenCode returned no session id"))?
+        }
+    };
+
+    let prompt = json!({
+        "parts": [{ "type": "text", "text": capture.text }],
+    });
+    send_prompt(&bridge, &session_id, directory.as_deref(), &prompt).await?;
+
+    update_store(state.studio_db.as_ref(), |store| {
+        store.captures.retain(|c| c.id != id);
+        Ok(())
+    })
+    .await?;
+
+    Ok(Json(json!({
+        "sessionId": session_id,
+        "created": created,
+        "directory": directory,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_body_distinguishes_cleared_and_missing_directory() {
+        let cleared: QuickCaptureUpdateBody =
+            serde_json::from_value(json!({ "directory": null })).unwrap();
+        assert_eq!(cleared.directory, Some(None));
+        let kept: QuickCaptureUpdateBody = serde_json::from_value(json!({ "text": "x" })).unwrap();
+        assert_eq!(kept.directory, None);
+
+        let long = "a".repeat(MAX_TITLE_CHARS + 5);
+        assert_eq!(default_title("fix the build\nmore detail"), "fix the build");
+        assert_eq!(
+            default_title(&long).chars().count(),
+            MAX_TITLE_CHARS + 1,
+            "long first lines are cut with an ellipsis"
+        );
+    }
+}
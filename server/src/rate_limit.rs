@@ -0,0 +1,333 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::Body,
+    extract::{OriginalUri, Request, State},
+    http::{HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use serde::Deserialize;
+
+/// Limits are counted per minute.
+const WINDOW: Duration = Duration::from_secs(60);
+/// Buckets idle this long are full again and can be forgotten.
+const IDLE_BUCKET_TTL: Duration = Duration::from_secs(10 * 60);
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Requests per minute for one route family. `0` disables that limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct FamilyLimitConfig {
+    pub per_ip: u32,
+    pub per_token: u32,
+}
+
+impl FamilyLimitConfig {
+    const fn new(per_ip: u32, per_token: u32) -> Self {
+        Self { per_ip, per_token }
+    }
+}
+
+impl Default for FamilyLimitConfig {
+    fn default() -> Self {
+        Self::new(60, 300)
+    }
+}
+
+/// The runtime config's `[rate_limit]` table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct RateLimitConfig {
+    pub enabled: bool,
+    /// Deprecated and ignored: anonymous clients are keyed by the address
+    /// resolved through `--trusted-proxy`, which only believes forwarded
+    /// headers from known proxies.
+    pub trust_proxy_headers: bool,
+    /// Mutating git operations (`POST`/`PUT`/`DELETE /api/git/*`).
+    pub git: FamilyLimitConfig,
+    /// Session prompts (`POST /api/session/{id}/message` and `prompt_async`).
+    pub session_message: FamilyLimitConfig,
+    /// File name and content search (`/api/fs/search*`).
+    pub fs_search: FamilyLimitConfig,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            trust_proxy_headers: false,
+            git: FamilyLimitConfig::new(60, 300),
+            session_message: FamilyLimitConfig::new(30, 120),
+            fs_search: FamilyLimitConfig::new(60, 300),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum RouteFamily {
+    Git,
+    SessionMessage,
+    FsSearch,
+}
+
+impl RouteFamily {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Git => "git",
+            Self::SessionMessage => "session_message",
+            Self::FsSearch => "fs_search",
+        }
+    }
+}
+
+/// Rate-limited family of a request. `path` is the full request path
+/// including `/api`.
+fn classify(method: &Method, path: &str) -> Option<RouteFamily> {
+    if path.starts_with("/api/git/") {
+        return (method != Method::GET && method != Method::HEAD).then_some(RouteFamily::Git);
+    }
+    if path == "/api/fs/search" || path.starts_with("/api/fs/search-content") {
+        return Some(RouteFamily::FsSearch);
+    }
+    if method == Method::POST
+        && let Some(rest) = path.strip_prefix("/api/session/")
+        && let Some((id, action)) = rest.split_once('/')
+        && !id.is_empty()
+        && matches!(action, "message" | "prompt_async")
+    {
+        return Some(RouteFamily::SessionMessage);
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ClientKey {
+    Token(String),
+    Ip(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets keyed by route family and client. Each bucket holds up to
+/// one minute's allowance and refills continuously.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: DashMap<(RouteFamily, ClientKey), Bucket>,
+}
+
+impl RateLimiter {
+    /// Limiter for the runtime config's `[rate_limit]` table; without one,
+    /// nothing is limited.
+    pub(crate) fn from_config(config: Option<RateLimitConfig>) -> Self {
+        Self::new(config.unwrap_or(RateLimitConfig {
+            enabled: false,
+            ..RateLimitConfig::default()
+        }))
+    }
+
+    fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: DashMap::new(),
+        }
+    }
+
+    fn limit_for(&self, family: RouteFamily, client: &ClientKey) -> u32 {
+        let limits = match family {
+            RouteFamily::Git => self.config.git,
+            RouteFamily::SessionMessage => self.config.session_message,
+            RouteFamily::FsSearch => self.config.fs_search,
+        };
+        match client {
+            ClientKey::Token(_) => limits.per_token,
+            ClientKey::Ip(_) => limits.per_ip,
+        }
+    }
+
+    /// Take one request from the client's bucket; `Err` carries how long
+    /// until the next request would be allowed.
+    fn check(&self, family: RouteFamily, client: ClientKey, now: Instant) -> Result<(), Duration> {
+        let limit = self.limit_for(family, &client);
+        if limit == 0 {
+            return Ok(());
+        }
+        if self.buckets.len() > PRUNE_THRESHOLD {
+            self.buckets.retain(|_, bucket| {
+                now.saturating_duration_since(bucket.updated) < IDLE_BUCKET_TTL
+            });
+        }
+
+        let capacity = f64::from(limit);
+        let per_sec = capacity / WINDOW.as_secs_f64();
+        let mut bucket = self.buckets.entry((family, client)).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+        }
+    }
+}
+
+fn client_key(req: &Request<Body>) -> ClientKey {
+    if let Some(token) = req
+        .extensions()
+        .get::<crate::api_tokens::ApiTokenPrincipal>()
+    {
+        return ClientKey::Token(token.id.clone());
+    }
+    let ip = crate::client_addr::ClientAddr::from_request(req.extensions(), req.headers())
+        .ip
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    ClientKey::Ip(ip)
+}
+
+fn too_many_requests(family: RouteFamily, retry_after: Duration) -> Response {
+    let secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        axum::Json(serde_json::json!({
+            "error": "Too many requests; try again later",
+            "code": "rate_limited",
+            "family": family.as_str(),
+            "retryAfterSecs": secs,
+        })),
+    )
+        .into_response();
+    if let Ok(value) = HeaderValue::from_str(&secs.to_string()) {
+        response.headers_mut().insert(header::RETRY_AFTER, value);
+    }
+    response
+}
+
+/// Reject requests to expensive route families once the caller's per-token
+/// (API token) or per-IP allowance is used up. Runs inside the auth layer so
+/// the token principal is known.
+pub(crate) async fn enforce_rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let path = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.path().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let family = classify(req.method(), &path).filter(|_| limiter.config.enabled);
+    let Some(family) = family else {
+        return next.run(req).await;
+    };
+
+    let client = client_key(&req);
+    if let Err(retry_after) = limiter.check(family, client.clone(), Instant::now()) {
+        tracing::debug!(
+            target: "opencode_studio.rate_limit",
+            family = family.as_str(),
+            client = ?client,
+            "request rate limited"
+        );
+        return too_many_requests(family, retry_after);
+    }
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_expensive_route_families() {
+        assert_eq!(
+            classify(&Method::POST, "/api/git/push"),
+            Some(RouteFamily::Git)
+        );
+        assert_eq!(classify(&Method::GET, "/api/git/status"), None);
+        assert_eq!(
+            classify(&Method::POST, "/api/session/ses_1/message"),
+            Some(RouteFamily::SessionMessage)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/session/ses_1/prompt_async"),
+            Some(RouteFamily::SessionMessage)
+        );
+        assert_eq!(classify(&Method::GET, "/api/session/ses_1/message"), None);
+        assert_eq!(
+            classify(&Method::GET, "/api/fs/search-content/stream"),
+            Some(RouteFamily::FsSearch)
+        );
+        assert_eq!(classify(&Method::GET, "/api/fs/read"), None);
+    }
+
+    #[test]
+    fn forwarded_headers_only_key_clients_behind_trusted_proxies() {
+        let request = |peer: &str| {
+            let mut req = Request::new(Body::empty());
+            req.headers_mut()
+                .insert("x-forwarded-for", HeaderValue::from_static("203.0.113.5"));
+            req.extensions_mut().insert(axum::extract::ConnectInfo(
+                peer.parse::<std::net::SocketAddr>().unwrap(),
+            ));
+            req
+        };
+        assert_eq!(
+            client_key(&request("198.51.100.7:4000")),
+            ClientKey::Ip("198.51.100.7".to_string())
+        );
+        assert_eq!(
+            client_key(&request("127.0.0.1:4000")),
+            ClientKey::Ip("203.0.113.5".to_string())
+        );
+    }
+
+    #[test]
+    fn buckets_refill_and_report_retry_after() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            git: FamilyLimitConfig::new(2, 0),
+            ..RateLimitConfig::default()
+        });
+        let ip = || ClientKey::Ip("10.0.0.1".to_string());
+        let start = Instant::now();
+
+        assert!(limiter.check(RouteFamily::Git, ip(), start).is_ok());
+        assert!(limiter.check(RouteFamily::Git, ip(), start).is_ok());
+        let retry = limiter
+            .check(RouteFamily::Git, ip(), start)
+            .expect_err("third request in the window is limited");
+        assert!((29..=30).contains(&retry.as_secs()));
+
+        // Other clients and unlimited token keys are unaffected.
+        assert!(
+            limiter
+                .check(RouteFamily::Git, ClientKey::Ip("10.0.0.2".into()), start)
+                .is_ok()
+        );
+        for _ in 0..10 {
+            assert!(
+                limiter
+                    .check(RouteFamily::Git, ClientKey::Token("tok".into()), start)
+                    .is_ok()
+            );
+        }
+
+        assert!(
+            limiter
+                .check(RouteFamily::Git, ip(), start + Duration::from_secs(30))
+                .is_ok()
+        );
+    }
+}
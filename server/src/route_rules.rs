@@ -0,0 +1,340 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderValue, Method, StatusCode, Uri, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+/// One `[[routes]]` entry from the runtime config.
+///
+/// `match` is an exact path, or a prefix ending in `*`; a `*` at the end of
+/// `redirect`/`rewrite` is replaced by the matched remainder.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct RouteRuleConfig {
+    #[serde(rename = "match")]
+    pub path: String,
+    pub redirect: Option<String>,
+    pub rewrite: Option<String>,
+    pub file: Option<String>,
+    /// Redirect status (301, 302, 303, 307 or 308). Defaults to 302.
+    pub status: Option<u16>,
+    /// Content type for `file`; guessed from the extension when unset.
+    pub content_type: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RouteAction {
+    Redirect { to: String, status: StatusCode },
+    Rewrite { to: String },
+    File { path: PathBuf, content_type: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RouteRule {
+    pattern: String,
+    prefix: bool,
+    action: RouteAction,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RouteOutcome<'a> {
+    Redirect {
+        location: String,
+        status: StatusCode,
+    },
+    Rewrite {
+        path_and_query: String,
+    },
+    File {
+        path: &'a Path,
+        content_type: &'a str,
+    },
+}
+
+fn guess_content_type(path: &Path) -> String {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "" | "txt" => "text/plain; charset=utf-8",
+        "json" => "application/json",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "xml" => "application/xml",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        _ => crate::fs::mime_for_ext(path),
+    }
+    .to_string()
+}
+
+fn validate_target(rule: &str, key: &str, target: &str) -> Result<(), String> {
+    if !target.starts_with('/') && !target.starts_with("http://") && !target.starts_with("https://")
+    {
+        return Err(format!(
+            "routes[{rule}].{key} must be an absolute path or http(s) URL: {target}"
+        ));
+    }
+    if target.trim_end_matches('*').contains('*') {
+        return Err(format!(
+            "routes[{rule}].{key} may only use '*' at the end: {target}"
+        ));
+    }
+    Ok(())
+}
+
+impl RouteRule {
+    /// Validate a config entry. Relative `file` paths resolve against
+    /// `base_dir` (the runtime config's directory).
+    pub(crate) fn compile(cfg: &RouteRuleConfig, base_dir: Option<&Path>) -> Result<Self, String> {
+        let raw = cfg.path.trim();
+        if !raw.starts_with('/') {
+            return Err(format!("routes.match must start with '/': {raw:?}"));
+        }
+        let (pattern, prefix) = match raw.strip_suffix('*') {
+            Some(p) => (p, true),
+            None => (raw, false),
+        };
+        if pattern.contains('*') {
+            return Err(format!("routes.match may only use '*' at the end: {raw}"));
+        }
+
+        let targets = [
+            cfg.redirect.is_some(),
+            cfg.rewrite.is_some(),
+            cfg.file.is_some(),
+        ];
+        if targets.iter().filter(|t| **t).count() != 1 {
+            return Err(format!(
+                "routes[{raw}] needs exactly one of redirect, rewrite or file"
+            ));
+        }
+
+        let action = if let Some(to) = cfg.redirect.as_deref().map(str::trim) {
+            validate_target(raw, "redirect", to)?;
+            let status = match cfg.status.unwrap_or(302) {
+                code @ (301 | 302 | 303 | 307 | 308) => {
+                    StatusCode::from_u16(code).expect("valid redirect status")
+                }
+                other => {
+                    return Err(format!(
+                        "routes[{raw}].status must be a redirect status, got {other}"
+                    ));
+                }
+            };
+            RouteAction::Redirect {
+                to: to.to_string(),
+                status,
+            }
+        } else if let Some(to) = cfg.rewrite.as_deref().map(str::trim) {
+            if !to.starts_with('/') {
+                return Err(format!("routes[{raw}].rewrite must start with '/': {to}"));
+            }
+            validate_target(raw, "rewrite", to)?;
+            RouteAction::Rewrite { to: to.to_string() }
+        } else {
+            if prefix {
+                return Err(format!("routes[{raw}] cannot serve a file for a prefix"));
+            }
+            let file = PathBuf::from(cfg.file.as_deref().unwrap_or("").trim());
+            let path = match base_dir {
+                Some(dir) if file.is_relative() => dir.join(file),
+                _ => file,
+            };
+            let content_type = cfg
+                .content_type
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(ToString::to_string)
+                .unwrap_or_else(|| guess_content_type(&path));
+            if HeaderValue::from_str(&content_type).is_err() {
+                return Err(format!("routes[{raw}].content_type is invalid"));
+            }
+            RouteAction::File { path, content_type }
+        };
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            prefix,
+            action,
+        })
+    }
+
+    fn capture<'p>(&self, path: &'p str) -> Option<&'p str> {
+        if self.prefix {
+            path.strip_prefix(self.pattern.as_str())
+        } else {
+            (path == self.pattern).then_some("")
+        }
+    }
+}
+
+fn expand(target: &str, rest: &str) -> String {
+    match target.strip_suffix('*') {
+        Some(base) => format!("{base}{rest}"),
+        None => target.to_string(),
+    }
+}
+
+fn with_query(target: String, query: Option<&str>) -> String {
+    match query {
+        Some(q) if !q.is_empty() && !target.contains('?') => format!("{target}?{q}"),
+        _ => target,
+    }
+}
+
+/// First matching rule wins; rewritten paths are not matched again.
+fn resolve<'a>(
+    rules: &'a [RouteRule],
+    path: &str,
+    query: Option<&str>,
+) -> Option<RouteOutcome<'a>> {
+    rules.iter().find_map(|rule| {
+        let rest = rule.capture(path)?;
+        Some(match &rule.action {
+            RouteAction::Redirect { to, status } => RouteOutcome::Redirect {
+                location: with_query(expand(to, rest), query),
+                status: *status,
+            },
+            RouteAction::Rewrite { to } => RouteOutcome::Rewrite {
+                path_and_query: with_query(expand(to, rest), query),
+            },
+            RouteAction::File { path, content_type } => RouteOutcome::File { path, content_type },
+        })
+    })
+}
+
+/// The path the app router will see for `path`: the rewrite target when a
+/// rewrite rule matches, otherwise `path` itself.
+pub(crate) fn routed_path(rules: &[RouteRule], path: &str) -> String {
+    match resolve(rules, path, None) {
+        Some(RouteOutcome::Rewrite { path_and_query }) => path_and_query
+            .split_once('?')
+            .map_or(path_and_query.as_str(), |(path, _)| path)
+            .to_string(),
+        _ => path.to_string(),
+    }
+}
+
+async fn serve_file(method: &Method, path: &Path, content_type: &str) -> Response {
+    if method != Method::GET && method != Method::HEAD {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+    let bytes = match tokio::fs::read(path).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            tracing::warn!(path = %path.display(), error = %err, "Route rule file unavailable");
+            return StatusCode::NOT_FOUND.into_response();
+        }
+    };
+    let len = bytes.len();
+    let body = if method == Method::HEAD {
+        Body::empty()
+    } else {
+        Body::from(bytes)
+    };
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_LENGTH, len)
+        .body(body)
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Applies runtime-config route rules before the app router sees the request.
+pub(crate) async fn apply_route_rules(
+    State(rules): State<Arc<Vec<RouteRule>>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let outcome = resolve(&rules, req.uri().path(), req.uri().query());
+    match outcome {
+        None => next.run(req).await,
+        Some(RouteOutcome::Redirect { location, status }) => {
+            match HeaderValue::from_str(&location) {
+                Ok(value) => (status, [(header::LOCATION, value)]).into_response(),
+                Err(_) => StatusCode::BAD_REQUEST.into_response(),
+            }
+        }
+        Some(RouteOutcome::Rewrite { path_and_query }) => {
+            let Ok(uri) = path_and_query.parse::<Uri>() else {
+                return StatusCode::BAD_REQUEST.into_response();
+            };
+            *req.uri_mut() = uri;
+            next.run(req).await
+        }
+        Some(RouteOutcome::File { path, content_type }) => {
+            serve_file(req.method(), path, content_type).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(toml_src: &str) -> Result<RouteRule, String> {
+        let cfg: RouteRuleConfig = toml::from_str(toml_src).expect("rule toml");
+        RouteRule::compile(&cfg, Some(Path::new("/etc/studio")))
+    }
+
+    #[test]
+    fn compile_rejects_ambiguous_or_malformed_rules() {
+        assert!(rule(r#"match = "ui""#).is_err());
+        assert!(rule("match = \"/\"\nredirect = \"/ui\"\nrewrite = \"/x\"").is_err());
+        assert!(rule("match = \"/a/*/b\"\nrewrite = \"/b\"").is_err());
+        assert!(rule("match = \"/\"\nredirect = \"/ui\"\nstatus = 200").is_err());
+        assert!(rule("match = \"/docs/*\"\nfile = \"docs.txt\"").is_err());
+    }
+
+    #[test]
+    fn resolves_redirects_rewrites_and_files_in_order() {
+        let rules = vec![
+            rule("match = \"/\"\nredirect = \"/ui\"\nstatus = 308").unwrap(),
+            rule("match = \"/legacy/api/*\"\nrewrite = \"/api/*\"").unwrap(),
+            rule("match = \"/.well-known/security.txt\"\nfile = \"security.txt\"").unwrap(),
+            rule("match = \"/legacy/*\"\nredirect = \"https://example.com/*\"").unwrap(),
+        ];
+
+        assert_eq!(
+            resolve(&rules, "/", Some("a=1")),
+            Some(RouteOutcome::Redirect {
+                location: "/ui?a=1".to_string(),
+                status: StatusCode::PERMANENT_REDIRECT,
+            })
+        );
+        assert_eq!(
+            resolve(&rules, "/legacy/api/git/status", Some("directory=/x")),
+            Some(RouteOutcome::Rewrite {
+                path_and_query: "/api/git/status?directory=/x".to_string(),
+            })
+        );
+        assert_eq!(
+            resolve(&rules, "/.well-known/security.txt", None),
+            Some(RouteOutcome::File {
+                path: Path::new("/etc/studio/security.txt"),
+                content_type: "text/plain; charset=utf-8",
+            })
+        );
+        assert_eq!(
+            resolve(&rules, "/legacy/page", None),
+            Some(RouteOutcome::Redirect {
+                location: "https://example.com/page".to_string(),
+                status: StatusCode::FOUND,
+            })
+        );
+        assert_eq!(resolve(&rules, "/ui", None), None);
+        assert_eq!(
+            routed_path(&rules, "/legacy/api/git/status"),
+            "/api/git/status"
+        );
+        assert_eq!(routed_path(&rules, "/legacy/page"), "/legacy/page");
+    }
+}
@@ -1,6 +1,6 @@
 use std::path::{Path, PathBuf};
 
-use clap::{CommandFactory, FromArgMatches, parser::ValueSource};
+use clap::{CommandFactory, FromArgMatches, Subcommand, parser::ValueSource};
 use serde::Deserialize;
 
 const DEFAULT_RUNTIME_CONFIG_FILE: &str = "opencode-studio.toml";
@@ -9,6 +9,9 @@ const DEFAULT_RUNTIME_CONFIG_FILE: &str = "opencode-studio.toml";
 #[serde(default)]
 struct RuntimeConfig {
     backend: BackendRuntimeConfig,
+    routes: Vec<crate::route_rules::RouteRuleConfig>,
+    rate_limit: Option<crate::rate_limit::RateLimitConfig>,
+    ip_filter: Option<crate::ip_filter::IpFilterConfig>,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -21,10 +24,30 @@ struct BackendRuntimeConfig {
     opencode_host: Option<String>,
     skip_opencode_start: Option<bool>,
     opencode_log_level: Option<String>,
+    opencode_pool_max: Option<usize>,
+    opencode_pool_idle_secs: Option<u64>,
+    session_scan_concurrency: Option<usize>,
+    session_dir_cache_limit: Option<usize>,
+    session_file_cache_limit: Option<usize>,
     ui_dir: Option<String>,
     cors_origins: Option<Vec<String>>,
     cors_allow_all: Option<bool>,
     ui_cookie_samesite: Option<String>,
+    shutdown_grace_secs: Option<u64>,
+    reuse_port: Option<bool>,
+    compression: Option<bool>,
+    compression_min_bytes: Option<u16>,
+    trash_retention_days: Option<u32>,
+    ca_certs: Option<Vec<String>>,
+    trusted_proxies: Option<Vec<String>>,
+    tls_cert: Option<String>,
+    tls_key: Option<String>,
+    tls_redirect_port: Option<u16>,
+    discovery: Option<bool>,
+    instance_name: Option<String>,
+    self_update: Option<bool>,
+    self_update_url: Option<String>,
+    self_update_public_key: Option<String>,
 }
 
 pub(crate) fn parse_args_with_runtime_config() -> Result<crate::Args, String> {
@@ -54,6 +77,14 @@ pub(crate) fn parse_args_with_runtime_config() -> Result<crate::Args, String> {
 
     let runtime_config = read_runtime_config(&config_path)?;
     apply_runtime_overrides(&mut args, &matches, &runtime_config)?;
+    args.route_rules = runtime_config
+        .routes
+        .iter()
+        .map(|rule| crate::route_rules::RouteRule::compile(rule, config_path.parent()))
+        .collect::<Result<_, _>>()
+        .map_err(|err| format!("invalid runtime config {}: {err}", config_path.display()))?;
+    args.rate_limit = runtime_config.rate_limit;
+    args.ip_filter = runtime_config.ip_filter;
 
     Ok(args)
 }
@@ -75,6 +106,186 @@ fn default_runtime_config_path() -> Option<PathBuf> {
     Some(dir.join(DEFAULT_RUNTIME_CONFIG_FILE))
 }
 
+/// The runtime config file `args` was loaded from, if any.
+pub(crate) fn resolved_config_path(args: &crate::Args) -> Option<PathBuf> {
+    args.config
+        .as_deref()
+        .map(PathBuf::from)
+        .or_else(default_runtime_config_path)
+        .filter(|path| path.exists())
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum ConfigAction {
+    /// Validate the runtime config and print the effective settings.
+    Check,
+}
+
+/// Field names a `#[derive(Deserialize)]` struct accepts.
+fn struct_fields<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    use serde::de::{Error as _, Visitor, value::Error};
+
+    struct Probe<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> serde::Deserializer<'de> for Probe<'_> {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+            Err(Error::custom("field probe"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Error> {
+            *self.0 = fields;
+            Err(Error::custom("field probe"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(Probe(&mut fields));
+    fields
+}
+
+/// Keys in `raw` that the runtime config does not read. `[[routes]]`,
+/// `[rate_limit]` and `[ip_filter]` reject unknown keys while parsing.
+fn unknown_keys(raw: &toml::Table) -> Vec<String> {
+    let top = struct_fields::<RuntimeConfig>();
+    let backend = struct_fields::<BackendRuntimeConfig>();
+    let mut unknown = Vec::new();
+    for (key, value) in raw {
+        if !top.contains(&key.as_str()) {
+            unknown.push(key.clone());
+        } else if key == "backend"
+            && let Some(table) = value.as_table()
+        {
+            unknown.extend(
+                table
+                    .keys()
+                    .filter(|k| !backend.contains(&k.as_str()))
+                    .map(|k| format!("backend.{k}")),
+            );
+        }
+    }
+    unknown
+}
+
+/// Settings that parse but do not combine into what was likely intended.
+fn conflicts(args: &crate::Args) -> Vec<String> {
+    let mut out = Vec::new();
+    let tls = args.tls_cert.is_some() && args.tls_key.is_some();
+    if args.skip_opencode_start && args.opencode_port.is_none() {
+        out.push(
+            "skip_opencode_start is set without opencode_port; no OpenCode will be available"
+                .to_string(),
+        );
+    }
+    if args.cors_allow_all && !args.cors_origin.is_empty() {
+        out.push("cors_allow_all makes cors_origins redundant".to_string());
+    }
+    if args.tls_redirect_port.is_some() && !tls {
+        out.push("tls_redirect_port is ignored without tls_cert/tls_key".to_string());
+    }
+    if !args.self_update
+        && (args.self_update_url.is_some() || args.self_update_public_key.is_some())
+    {
+        out.push(
+            "self_update_url/self_update_public_key are ignored unless self_update is enabled"
+                .to_string(),
+        );
+    }
+    if args.secrets_key.is_some() && args.secrets_passphrase.is_some() {
+        out.push("secrets_passphrase is ignored when secrets_key is set".to_string());
+    }
+    if args
+        .ip_filter
+        .as_ref()
+        .is_some_and(|filter| filter.is_active() && filter.localhost_bypass)
+        && args
+            .host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+    {
+        out.push(
+            "ip_filter has no effect on a loopback host while localhost_bypass is on".to_string(),
+        );
+    }
+    if args
+        .rate_limit
+        .as_ref()
+        .is_some_and(|cfg| cfg.trust_proxy_headers)
+    {
+        out.push(
+            "rate_limit.trust_proxy_headers is ignored; list your proxies in trusted_proxies"
+                .to_string(),
+        );
+    }
+    if matches!(args.ui_cookie_samesite, crate::UiCookieSameSite::None) && !tls {
+        out.push(
+            "ui_cookie_samesite = none needs HTTPS (direct TLS or a proxy setting X-Forwarded-Proto)"
+                .to_string(),
+        );
+    }
+    out
+}
+
+/// `opencode-studio config check`: print problems and the effective settings.
+/// Returns whether no problems were found.
+pub(crate) fn check(mut args: crate::Args) -> Result<bool, String> {
+    let path = resolved_config_path(&args);
+    let mut problems = Vec::new();
+    match &path {
+        Some(path) => {
+            println!("Runtime config: {}", path.display());
+            let raw = std::fs::read_to_string(path).map_err(|err| {
+                format!("failed to read runtime config {}: {err}", path.display())
+            })?;
+            let table = toml::from_str::<toml::Table>(&raw).map_err(|err| {
+                format!("failed to parse runtime config {}: {err}", path.display())
+            })?;
+            problems.extend(
+                unknown_keys(&table)
+                    .into_iter()
+                    .map(|key| format!("unknown key: {key}")),
+            );
+        }
+        None => println!("Runtime config: none (flags, env and defaults only)"),
+    }
+    problems.extend(conflicts(&args));
+
+    if problems.is_empty() {
+        println!("No problems found.");
+    } else {
+        println!("Problems:");
+        for problem in &problems {
+            println!("  - {problem}");
+        }
+    }
+
+    let redacted = Some("<redacted>".to_string());
+    for secret in [
+        &mut args.ui_password,
+        &mut args.secrets_key,
+        &mut args.secrets_passphrase,
+    ] {
+        if secret.is_some() {
+            *secret = redacted.clone();
+        }
+    }
+    args.command = None;
+    println!("\nEffective configuration:\n{args:#?}");
+    Ok(problems.is_empty())
+}
+
 fn allow_file_override(matches: &clap::ArgMatches, id: &str) -> bool {
     matches
         .value_source(id)
@@ -128,6 +339,36 @@ fn apply_runtime_overrides(
         };
     }
 
+    if allow_file_override(matches, "opencode_pool_max")
+        && let Some(max) = cfg.backend.opencode_pool_max
+    {
+        args.opencode_pool_max = max;
+    }
+
+    if allow_file_override(matches, "opencode_pool_idle_secs")
+        && let Some(secs) = cfg.backend.opencode_pool_idle_secs
+    {
+        args.opencode_pool_idle_secs = secs;
+    }
+
+    if allow_file_override(matches, "session_scan_concurrency")
+        && let Some(value) = cfg.backend.session_scan_concurrency
+    {
+        args.session_scan_concurrency = Some(value);
+    }
+
+    if allow_file_override(matches, "session_dir_cache_limit")
+        && let Some(value) = cfg.backend.session_dir_cache_limit
+    {
+        args.session_dir_cache_limit = Some(value);
+    }
+
+    if allow_file_override(matches, "session_file_cache_limit")
+        && let Some(value) = cfg.backend.session_file_cache_limit
+    {
+        args.session_file_cache_limit = Some(value);
+    }
+
     if allow_file_override(matches, "ui_dir") {
         args.ui_dir = cfg
             .backend
@@ -157,6 +398,114 @@ fn apply_runtime_overrides(
         };
     }
 
+    if allow_file_override(matches, "shutdown_grace_secs")
+        && let Some(secs) = cfg.backend.shutdown_grace_secs
+    {
+        args.shutdown_grace_secs = secs;
+    }
+
+    if allow_file_override(matches, "reuse_port")
+        && let Some(reuse_port) = cfg.backend.reuse_port
+    {
+        args.reuse_port = reuse_port;
+    }
+
+    if allow_file_override(matches, "no_compression")
+        && let Some(compression) = cfg.backend.compression
+    {
+        args.no_compression = !compression;
+    }
+
+    if allow_file_override(matches, "compression_min_bytes")
+        && let Some(bytes) = cfg.backend.compression_min_bytes
+    {
+        args.compression_min_bytes = bytes;
+    }
+
+    if allow_file_override(matches, "trash_retention_days")
+        && let Some(days) = cfg.backend.trash_retention_days
+    {
+        args.trash_retention_days = days;
+    }
+
+    if allow_file_override(matches, "ca_certs")
+        && let Some(paths) = cfg.backend.ca_certs.clone()
+    {
+        args.ca_certs = paths;
+    }
+
+    if allow_file_override(matches, "trusted_proxies")
+        && let Some(entries) = cfg.backend.trusted_proxies.as_ref()
+    {
+        args.trusted_proxies = entries
+            .iter()
+            .map(|entry| {
+                entry
+                    .parse()
+                    .map_err(|err| format!("backend.trusted_proxies: {err}"))
+            })
+            .collect::<Result<_, _>>()?;
+    }
+
+    if allow_file_override(matches, "tls_cert") && allow_file_override(matches, "tls_key") {
+        let non_empty = |v: &Option<String>| {
+            v.as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(ToOwned::to_owned)
+        };
+        match (
+            non_empty(&cfg.backend.tls_cert),
+            non_empty(&cfg.backend.tls_key),
+        ) {
+            (Some(cert), Some(key)) => {
+                args.tls_cert = Some(cert);
+                args.tls_key = Some(key);
+            }
+            (None, None) => {}
+            _ => return Err("backend.tls_cert and backend.tls_key must be set together".into()),
+        }
+    }
+
+    if allow_file_override(matches, "tls_redirect_port")
+        && let Some(port) = cfg.backend.tls_redirect_port
+    {
+        args.tls_redirect_port = Some(port);
+    }
+
+    if allow_file_override(matches, "discovery")
+        && let Some(discovery) = cfg.backend.discovery
+    {
+        args.discovery = discovery;
+    }
+
+    if allow_file_override(matches, "instance_name")
+        && let Some(name) = cfg.backend.instance_name.as_deref().map(str::trim)
+        && !name.is_empty()
+    {
+        args.instance_name = Some(name.to_string());
+    }
+
+    if allow_file_override(matches, "self_update")
+        && let Some(enabled) = cfg.backend.self_update
+    {
+        args.self_update = enabled;
+    }
+
+    if allow_file_override(matches, "self_update_url")
+        && let Some(url) = cfg.backend.self_update_url.as_deref().map(str::trim)
+        && !url.is_empty()
+    {
+        args.self_update_url = Some(url.to_string());
+    }
+
+    if allow_file_override(matches, "self_update_public_key")
+        && let Some(key) = cfg.backend.self_update_public_key.as_deref().map(str::trim)
+        && !key.is_empty()
+    {
+        args.self_update_public_key = Some(key.to_string());
+    }
+
     Ok(())
 }
 
@@ -191,3 +540,78 @@ fn parse_ui_cookie_samesite(value: &str) -> Result<crate::UiCookieSameSite, Stri
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser as _;
+
+    #[test]
+    fn reports_unknown_top_level_and_backend_keys() {
+        let raw: toml::Table = toml::from_str(
+            r#"
+            [backend]
+            host = "127.0.0.1"
+            backend_log_level = "INFO"
+
+            [extra]
+            x = 1
+
+            [[routes]]
+            match = "/"
+            redirect = "/ui"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(unknown_keys(&raw), ["backend.backend_log_level", "extra"]);
+    }
+
+    #[test]
+    fn flags_options_that_do_not_combine() {
+        let args = crate::Args::try_parse_from([
+            "opencode-studio",
+            "--skip-opencode-start",
+            "--tls-redirect-port",
+            "8080",
+        ])
+        .unwrap();
+        let found = conflicts(&args);
+        assert!(found.iter().any(|c| c.starts_with("skip_opencode_start")));
+        assert!(found.iter().any(|c| c.starts_with("tls_redirect_port")));
+
+        let mut args = crate::Args::try_parse_from(["opencode-studio"]).unwrap();
+        args.ip_filter = Some(toml::from_str("[api]\nallow = [\"10.0.0.0/8\"]").unwrap());
+        assert!(conflicts(&args).iter().any(|c| c.starts_with("ip_filter")));
+        args.host = "0.0.0.0".to_string();
+        assert!(!conflicts(&args).iter().any(|c| c.starts_with("ip_filter")));
+    }
+
+    #[test]
+    fn storage_scan_knobs_come_from_the_file_unless_set_on_the_command_line() {
+        let cfg: RuntimeConfig = toml::from_str(
+            r#"
+            [backend]
+            session_scan_concurrency = 4
+            session_dir_cache_limit = 64
+            session_file_cache_limit = 256
+            "#,
+        )
+        .unwrap();
+        let matches = crate::Args::command()
+            .try_get_matches_from(["opencode-studio", "--session-scan-concurrency", "2"])
+            .unwrap();
+        let mut args = crate::Args::from_arg_matches(&matches).unwrap();
+        apply_runtime_overrides(&mut args, &matches, &cfg).unwrap();
+        assert_eq!(args.session_scan_concurrency, Some(2));
+        assert_eq!(args.session_dir_cache_limit, Some(64));
+        assert_eq!(args.session_file_cache_limit, Some(256));
+
+        assert!(
+            toml::from_str::<RuntimeConfig>(
+                "[backend]
+session_scan_concurrency = -1"
+            )
+            .is_err()
+        );
+    }
+}
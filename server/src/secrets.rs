@@ -0,0 +1,673 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use argon2::Argon2;
+use aws_lc_rs::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use axum::{
+    Json,
+    extract::{Path as AxumPath, State},
+    http::{Extensions, StatusCode},
+};
+use base64::Engine as _;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{ApiResult, AppError};
+
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const CIPHERTEXT_PREFIX: &str = "v1:";
+/// Sealed with the master key on first start so a wrong key is detected
+/// before anything is decrypted with it.
+const KEY_CHECK_AAD: &[u8] = b"secrets.keyCheck";
+const KEY_CHECK_PLAINTEXT: &str = "opencode-studio secrets";
+const MAX_SECRETS: usize = 200;
+const MAX_NAME_CHARS: usize = 120;
+const MAX_VALUE_CHARS: usize = 8 * 1024;
+
+/// Where the master key came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum KeySource {
+    /// `--secrets-key`; the desktop app passes a key kept in the OS keychain.
+    Keychain,
+    /// Derived from `--secrets-passphrase` with Argon2id.
+    Passphrase,
+    /// Random key in the data directory, used when neither is configured.
+    KeyFile,
+}
+
+struct MasterKey {
+    source: KeySource,
+    key: Option<[u8; KEY_LEN]>,
+    /// Why `key` is unusable, e.g. the passphrase changed.
+    locked: Option<String>,
+}
+
+static MASTER: OnceLock<MasterKey> = OnceLock::new();
+static RECORDS: RwLock<Vec<SecretRecord>> = RwLock::new(Vec::new());
+static STORE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum SecretKind {
+    /// HTTPS git credentials, matched to remotes by host.
+    GitHttp,
+    /// API key handed to the managed OpenCode process for `providerId`.
+    ProviderApiKey,
+    Generic,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SecretRecord {
+    id: String,
+    name: String,
+    kind: SecretKind,
+    #[serde(default)]
+    host: Option<String>,
+    #[serde(default)]
+    provider_id: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    /// Sealed value; the record id is bound in as associated data.
+    ciphertext: String,
+    created_at: u64,
+    updated_at: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SecretStore {
+    #[serde(default)]
+    secrets: Vec<SecretRecord>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SecretsMeta {
+    /// Argon2 salt for passphrase-derived keys (base64).
+    #[serde(default)]
+    salt: Option<String>,
+    #[serde(default)]
+    key_check: Option<String>,
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn parse_hex_key(raw: &str) -> Result<[u8; KEY_LEN], String> {
+    let raw = raw.trim();
+    if raw.len() != KEY_LEN * 2 || !raw.is_ascii() {
+        return Err(format!(
+            "secrets key must be {} hex characters",
+            KEY_LEN * 2
+        ));
+    }
+    let mut key = [0u8; KEY_LEN];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&raw[i * 2..i * 2 + 2], 16)
+            .map_err(|_| "secrets key is not valid hex".to_string())?;
+    }
+    Ok(key)
+}
+
+fn derive_passphrase_key(passphrase: &str, salt: &[u8]) -> Result<[u8; KEY_LEN], String> {
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| format!("derive secrets key: {err}"))?;
+    Ok(key)
+}
+
+fn load_or_create_key_file(path: &Path) -> Result<[u8; KEY_LEN], String> {
+    let read = |path: &Path| -> Result<[u8; KEY_LEN], String> {
+        std::fs::read(path)
+            .map_err(|e| e.to_string())?
+            .try_into()
+            .map_err(|_| format!("{} is not a {KEY_LEN}-byte key", path.display()))
+    };
+    if path.exists() {
+        return read(path);
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut key = [0u8; KEY_LEN];
+    getrandom::fill(&mut key).map_err(|e| e.to_string())?;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    match options.open(path) {
+        Ok(mut file) => {
+            use std::io::Write;
+            file.write_all(&key).map_err(|e| e.to_string())?;
+            Ok(key)
+        }
+        // Lost a race with another writer; use its key.
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => read(path),
+        Err(err) => Err(format!("create {}: {err}", path.display())),
+    }
+}
+
+pub(crate) fn seal_with(
+    key: &[u8; KEY_LEN],
+    aad: &[u8],
+    plaintext: &str,
+) -> Result<String, String> {
+    let key = LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).map_err(|e| e.to_string())?);
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::fill(&mut nonce).map_err(|e| e.to_string())?;
+    let mut data = plaintext.as_bytes().to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(aad),
+        &mut data,
+    )
+    .map_err(|_| "encryption failed".to_string())?;
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&data);
+    Ok(format!(
+        "{CIPHERTEXT_PREFIX}{}",
+        base64::engine::general_purpose::STANDARD.encode(out)
+    ))
+}
+
+pub(crate) fn open_with(key: &[u8; KEY_LEN], aad: &[u8], sealed: &str) -> Result<String, String> {
+    let raw = sealed
+        .strip_prefix(CIPHERTEXT_PREFIX)
+        .ok_or_else(|| "unknown ciphertext encoding".to_string())?;
+    let mut data = base64::engine::general_purpose::STANDARD
+        .decode(raw)
+        .map_err(|_| "ciphertext is not base64".to_string())?;
+    if data.len() < NONCE_LEN {
+        return Err("ciphertext is truncated".to_string());
+    }
+    let mut ciphertext = data.split_off(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(&data).map_err(|e| e.to_string())?;
+    let key = LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).map_err(|e| e.to_string())?);
+    let plain = key
+        .open_in_place(nonce, Aad::from(aad), &mut ciphertext)
+        .map_err(|_| "decryption failed".to_string())?;
+    String::from_utf8(plain.to_vec()).map_err(|_| "plaintext is not valid UTF-8".to_string())
+}
+
+fn master_key() -> Result<&'static [u8; KEY_LEN], String> {
+    let master = MASTER
+        .get()
+        .ok_or_else(|| "secrets store is not initialized".to_string())?;
+    if let Some(reason) = &master.locked {
+        return Err(format!("secrets store is locked: {reason}"));
+    }
+    master
+        .key
+        .as_ref()
+        .ok_or_else(|| "secrets store has no key".to_string())
+}
+
+/// Encrypt `plaintext` with the master key. `aad` ties the ciphertext to its
+/// owner so it cannot be swapped onto another record.
+pub(crate) fn seal(aad: &[u8], plaintext: &str) -> Result<String, String> {
+    seal_with(master_key()?, aad, plaintext)
+}
+
+pub(crate) fn open(aad: &[u8], sealed: &str) -> Result<String, String> {
+    open_with(master_key()?, aad, sealed)
+}
+
+/// Resolve the master key, check it against the stored key check, and load
+/// the secret records. Called once at startup.
+pub(crate) async fn init(
+    db: &crate::studio_db::StudioDb,
+    key_hex: Option<&str>,
+    passphrase: Option<&str>,
+) {
+    let mut meta = match db
+        .get_json::<SecretsMeta>(crate::studio_db::KV_KEY_SECRETS_META)
+        .await
+    {
+        Ok(meta) => meta.unwrap_or_default(),
+        Err(err) => {
+            tracing::warn!(error = %err, "Failed to load secrets metadata");
+            SecretsMeta::default()
+        }
+    };
+    let key_hex = key_hex.map(str::trim).filter(|v| !v.is_empty());
+    let passphrase = passphrase.filter(|v| !v.is_empty());
+
+    let (source, key) = if let Some(raw) = key_hex {
+        (KeySource::Keychain, parse_hex_key(raw))
+    } else if let Some(passphrase) = passphrase {
+        let salt = match meta
+            .salt
+            .as_deref()
+            .and_then(|s| base64::engine::general_purpose::STANDARD.decode(s).ok())
+        {
+            Some(salt) => salt,
+            None => {
+                let mut salt = vec![0u8; SALT_LEN];
+                let _ = getrandom::fill(&mut salt);
+                meta.salt = Some(base64::engine::general_purpose::STANDARD.encode(&salt));
+                salt
+            }
+        };
+        (
+            KeySource::Passphrase,
+            derive_passphrase_key(passphrase, &salt),
+        )
+    } else {
+        let path = crate::persistence_paths::secrets_key_path();
+        (KeySource::KeyFile, load_or_create_key_file(&path))
+    };
+
+    let master = match key {
+        Err(err) => MasterKey {
+            source,
+            key: None,
+            locked: Some(err),
+        },
+        Ok(key) => match meta.key_check.as_deref() {
+            Some(check) if open_with(&key, KEY_CHECK_AAD, check).is_err() => MasterKey {
+                source,
+                key: Some(key),
+                locked: Some(
+                    "stored secrets were encrypted with a different key or passphrase".to_string(),
+                ),
+            },
+            Some(_) => MasterKey {
+                source,
+                key: Some(key),
+                locked: None,
+            },
+            None => {
+                meta.key_check = seal_with(&key, KEY_CHECK_AAD, KEY_CHECK_PLAINTEXT).ok();
+                if let Err(err) = db
+                    .set_json(crate::studio_db::KV_KEY_SECRETS_META, &meta)
+                    .await
+                {
+                    tracing::warn!(error = %err, "Failed to persist secrets metadata");
+                }
+                MasterKey {
+                    source,
+                    key: Some(key),
+                    locked: None,
+                }
+            }
+        },
+    };
+    if let Some(reason) = &master.locked {
+        tracing::warn!(source = ?master.source, reason = %reason, "Secrets store is locked");
+    }
+    let _ = MASTER.set(master);
+
+    let records = match db
+        .get_json::<SecretStore>(crate::studio_db::KV_KEY_SECRETS)
+        .await
+    {
+        Ok(store) => store.unwrap_or_default().secrets,
+        Err(err) => {
+            tracing::warn!(error = %err, "Failed to load secrets");
+            Vec::new()
+        }
+    };
+    *RECORDS.write().unwrap_or_else(|e| e.into_inner()) = records;
+}
+
+fn snapshot() -> Vec<SecretRecord> {
+    RECORDS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn reveal(record: &SecretRecord) -> Option<String> {
+    match open(record.id.as_bytes(), &record.ciphertext) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            tracing::warn!(id = %record.id, error = %err, "Failed to decrypt secret");
+            None
+        }
+    }
+}
+
+/// Stored HTTPS git credentials for `host`, as `(username, password)`.
+pub(crate) fn git_http_credentials(host: &str) -> Option<(String, String)> {
+    let host = host.trim();
+    snapshot()
+        .iter()
+        .filter(|r| r.kind == SecretKind::GitHttp)
+        .find(|r| {
+            r.host
+                .as_deref()
+                .is_some_and(|h| h.eq_ignore_ascii_case(host))
+        })
+        .and_then(|r| {
+            let password = reveal(r)?;
+            // Token-only hosts accept any username.
+            let username = r
+                .username
+                .clone()
+                .filter(|u| !u.trim().is_empty())
+                .unwrap_or_else(|| "x-access-token".to_string());
+            Some((username, password))
+        })
+}
+
+/// Provider API keys to inject into the managed OpenCode process.
+pub(crate) fn provider_api_keys() -> BTreeMap<String, String> {
+    snapshot()
+        .iter()
+        .filter(|r| r.kind == SecretKind::ProviderApiKey)
+        .filter_map(|r| Some((r.provider_id.clone()?, reveal(r)?)))
+        .collect()
+}
+
+pub(crate) fn has_provider_api_key(provider_id: &str) -> bool {
+    snapshot().iter().any(|r| {
+        r.kind == SecretKind::ProviderApiKey && r.provider_id.as_deref() == Some(provider_id)
+    })
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SecretView {
+    id: String,
+    name: String,
+    kind: SecretKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provider_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    created_at: u64,
+    updated_at: u64,
+}
+
+impl From<&SecretRecord> for SecretView {
+    fn from(r: &SecretRecord) -> Self {
+        Self {
+            id: r.id.clone(),
+            name: r.name.clone(),
+            kind: r.kind,
+            host: r.host.clone(),
+            provider_id: r.provider_id.clone(),
+            username: r.username.clone(),
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SecretsListResponse {
+    key_source: Option<KeySource>,
+    locked: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    locked_reason: Option<String>,
+    secrets: Vec<SecretView>,
+}
+
+/// Stored secrets without their values.
+pub(crate) async fn secrets_list(
+    State(state): State<Arc<crate::AppState>>,
+    extensions: Extensions,
+) -> ApiResult<Json<SecretsListResponse>> {
+    crate::ui_auth::require_admin(&state.ui_auth, &extensions)?;
+    let master = MASTER.get();
+    Ok(Json(SecretsListResponse {
+        key_source: master.map(|m| m.source),
+        locked: master.is_none_or(|m| m.locked.is_some()),
+        locked_reason: master.and_then(|m| m.locked.clone()),
+        secrets: snapshot().iter().map(SecretView::from).collect(),
+    }))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SecretBody {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub kind: Option<SecretKind>,
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub provider_id: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Write-only; never returned.
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+fn trimmed(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Apply `body` to `record`, checking the fields its kind needs.
+fn apply_body(record: &mut SecretRecord, body: SecretBody) -> ApiResult<Option<String>> {
+    if let Some(name) = body.name {
+        record.name = name.trim().to_string();
+    }
+    if let Some(kind) = body.kind {
+        record.kind = kind;
+    }
+    if body.host.is_some() {
+        record.host = trimmed(body.host).map(|h| h.to_ascii_lowercase());
+    }
+    if body.provider_id.is_some() {
+        record.provider_id = trimmed(body.provider_id);
+    }
+    if body.username.is_some() {
+        record.username = trimmed(body.username);
+    }
+
+    if record.name.is_empty() {
+        return Err(AppError::bad_request("name is required"));
+    }
+    if record.name.chars().count() > MAX_NAME_CHARS {
+        return Err(AppError::bad_request("name is too long"));
+    }
+    match record.kind {
+        SecretKind::GitHttp if record.host.is_none() => {
+            return Err(AppError::bad_request(
+                "host is required for gitHttp secrets",
+            ));
+        }
+        SecretKind::ProviderApiKey if record.provider_id.is_none() => {
+            return Err(AppError::bad_request(
+                "providerId is required for providerApiKey secrets",
+            ));
+        }
+        _ => {}
+    }
+
+    let value = body.value.filter(|v| !v.is_empty());
+    if value
+        .as_ref()
+        .is_some_and(|v| v.chars().count() > MAX_VALUE_CHARS)
+    {
+        return Err(AppError::bad_request("value is too long"));
+    }
+    Ok(value)
+}
+
+async fn persist(state: &crate::AppState, secrets: Vec<SecretRecord>) -> ApiResult<()> {
+    state
+        .studio_db
+        .set_json(
+            crate::studio_db::KV_KEY_SECRETS,
+            &SecretStore {
+                secrets: secrets.clone(),
+            },
+        )
+        .await
+        .map_err(AppError::internal)?;
+    *RECORDS.write().unwrap_or_else(|e| e.into_inner()) = secrets;
+
+    // Provider keys reach OpenCode through its inline config on next start.
+    let settings = state.settings.read().await.clone();
+    state
+        .opencode
+        .set_config_content(crate::opencode_config::managed_config_content(&settings))
+        .await;
+    Ok(())
+}
+
+pub(crate) async fn secrets_create(
+    State(state): State<Arc<crate::AppState>>,
+    extensions: Extensions,
+    Json(body): Json<SecretBody>,
+) -> ApiResult<(StatusCode, Json<SecretView>)> {
+    crate::ui_auth::require_admin(&state.ui_auth, &extensions)?;
+    let now = now_millis();
+    let mut record = SecretRecord {
+        id: format!("sec_{}", uuid::Uuid::new_v4().simple()),
+        name: String::new(),
+        kind: body.kind.unwrap_or(SecretKind::Generic),
+        host: None,
+        provider_id: None,
+        username: None,
+        ciphertext: String::new(),
+        created_at: now,
+        updated_at: now,
+    };
+    let value =
+        apply_body(&mut record, body)?.ok_or_else(|| AppError::bad_request("value is required"))?;
+    record.ciphertext = seal(record.id.as_bytes(), &value).map_err(AppError::conflict)?;
+
+    let _guard = STORE_LOCK.lock().await;
+    let mut secrets = snapshot();
+    if secrets.len() >= MAX_SECRETS {
+        return Err(AppError::conflict("Too many secrets; delete unused ones"));
+    }
+    let view = SecretView::from(&record);
+    secrets.push(record);
+    persist(&state, secrets).await?;
+    tracing::info!(id = %view.id, kind = ?view.kind, "Secret stored");
+    Ok((StatusCode::CREATED, Json(view)))
+}
+
+pub(crate) async fn secrets_update(
+    State(state): State<Arc<crate::AppState>>,
+    extensions: Extensions,
+    AxumPath(id): AxumPath<String>,
+    Json(body): Json<SecretBody>,
+) -> ApiResult<Json<SecretView>> {
+    crate::ui_auth::require_admin(&state.ui_auth, &extensions)?;
+    let _guard = STORE_LOCK.lock().await;
+    let mut secrets = snapshot();
+    let record = secrets
+        .iter_mut()
+        .find(|r| r.id == id)
+        .ok_or_else(|| AppError::not_found(format!("Secret '{id}' not found")))?;
+    if let Some(value) = apply_body(record, body)? {
+        record.ciphertext = seal(record.id.as_bytes(), &value).map_err(AppError::conflict)?;
+    }
+    record.updated_at = now_millis();
+    let view = SecretView::from(&*record);
+    persist(&state, secrets).await?;
+    tracing::info!(id = %view.id, "Secret updated");
+    Ok(Json(view))
+}
+
+pub(crate) async fn secrets_delete(
+    State(state): State<Arc<crate::AppState>>,
+    extensions: Extensions,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<StatusCode> {
+    crate::ui_auth::require_admin(&state.ui_auth, &extensions)?;
+    let _guard = STORE_LOCK.lock().await;
+    let mut secrets = snapshot();
+    let before = secrets.len();
+    secrets.retain(|r| r.id != id);
+    if secrets.len() == before {
+        return Err(AppError::not_found(format!("Secret '{id}' not found")));
+    }
+    persist(&state, secrets).await?;
+    tracing::info!(id = %id, "Secret deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_values_round_trip_and_are_bound_to_aad() {
+        let key = [7u8; KEY_LEN];
+        let sealed = seal_with(&key, b"sec_1", "ghp_secret").unwrap();
+        assert!(sealed.starts_with(CIPHERTEXT_PREFIX));
+        assert!(!sealed.contains("ghp_secret"));
+        assert_eq!(open_with(&key, b"sec_1", &sealed).unwrap(), "ghp_secret");
+        assert!(open_with(&key, b"sec_2", &sealed).is_err());
+        assert!(open_with(&[8u8; KEY_LEN], b"sec_1", &sealed).is_err());
+    }
+
+    #[test]
+    fn key_sources_produce_stable_keys() {
+        let hex = "00ff".repeat(16);
+        let key = parse_hex_key(&hex).unwrap();
+        assert_eq!(key[0], 0x00);
+        assert_eq!(key[1], 0xff);
+        assert!(parse_hex_key("abc").is_err());
+        assert!(parse_hex_key(&"zz".repeat(32)).is_err());
+
+        let salt = [1u8; SALT_LEN];
+        let a = derive_passphrase_key("correct horse", &salt).unwrap();
+        assert_eq!(a, derive_passphrase_key("correct horse", &salt).unwrap());
+        assert_ne!(a, derive_passphrase_key("battery staple", &salt).unwrap());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir
+            .path()
+            .join("nested")
+            .join(crate::persistence_paths::SECRETS_KEY_FILE);
+        let first = load_or_create_key_file(&path).unwrap();
+        assert_eq!(load_or_create_key_file(&path).unwrap(), first);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn secret_bodies_require_kind_specific_fields() {
+        let mut record = SecretRecord {
+            id: "sec_1".into(),
+            name: String::new(),
+            kind: SecretKind::GitHttp,
+            host: None,
+            provider_id: None,
+            username: None,
+            ciphertext: String::new(),
+            created_at: 0,
+            updated_at: 0,
+        };
+        let body = |host: Option<&str>| SecretBody {
+            name: Some(" GitHub ".into()),
+            kind: None,
+            host: host.map(str::to_string),
+            provider_id: None,
+            username: None,
+            value: Some("token".into()),
+        };
+        assert!(apply_body(&mut record, body(None)).is_err());
+        let value = apply_body(&mut record, body(Some(" GitHub.com "))).unwrap();
+        assert_eq!(value.as_deref(), Some("token"));
+        assert_eq!(record.name, "GitHub");
+        assert_eq!(record.host.as_deref(), Some("github.com"));
+    }
+}
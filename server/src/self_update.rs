@@ -0,0 +1,468 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use axum::{
+    Json,
+    extract::State,
+    http::Extensions,
+    response::{IntoResponse, Response},
+};
+use minisign_verify::{PublicKey, Signature};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{ApiResult, AppError};
+
+/// Key release archives are signed with, set at build time. Deployments can
+/// trust a different key with `--self-update-public-key`.
+const BUILTIN_PUBLIC_KEY: Option<&str> = option_env!("OPENCODE_STUDIO_UPDATE_PUBLIC_KEY");
+const MAX_DOWNLOAD_BYTES: usize = 256 * 1024 * 1024;
+const MAX_SIGNATURE_BYTES: usize = 4 * 1024;
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Lets the response reach the client before the server starts draining.
+const RESTART_DELAY: Duration = Duration::from_millis(500);
+
+#[cfg(windows)]
+const BINARY_NAME: &str = "opencode-studio.exe";
+#[cfg(not(windows))]
+const BINARY_NAME: &str = "opencode-studio";
+
+#[derive(Debug)]
+struct SelfUpdateConfig {
+    enabled: bool,
+    url: Option<String>,
+    public_key: Option<String>,
+}
+
+static CONFIG: OnceLock<SelfUpdateConfig> = OnceLock::new();
+static IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+/// Installed binary to exec once the server has drained.
+static RESTART_WITH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+pub(crate) fn configure(args: &crate::Args) {
+    let _ = CONFIG.set(SelfUpdateConfig {
+        enabled: args.self_update,
+        url: args.self_update_url.clone(),
+        public_key: args
+            .self_update_public_key
+            .clone()
+            .or_else(|| BUILTIN_PUBLIC_KEY.map(str::to_string))
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty()),
+    });
+}
+
+fn config() -> Option<&'static SelfUpdateConfig> {
+    CONFIG.get()
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SelfUpdateStatus {
+    enabled: bool,
+    current_version: &'static str,
+    public_key_configured: bool,
+    /// Fixed download URL; otherwise the latest GitHub release is used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    in_progress: bool,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SelfUpdateBody {
+    /// Reinstall even when the latest release is not newer.
+    #[serde(default)]
+    force: bool,
+    /// Run as a background job and return `202` with the job record.
+    #[serde(default)]
+    background: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SelfUpdateResponse {
+    updated: bool,
+    restarting: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    asset: Option<String>,
+}
+
+pub(crate) async fn self_update_get(
+    State(state): State<Arc<crate::AppState>>,
+    extensions: Extensions,
+) -> ApiResult<Json<SelfUpdateStatus>> {
+    crate::ui_auth::require_admin(&state.ui_auth, &extensions)?;
+    let config = config();
+    Ok(Json(SelfUpdateStatus {
+        enabled: config.is_some_and(|c| c.enabled),
+        current_version: env!("CARGO_PKG_VERSION"),
+        public_key_configured: config.is_some_and(|c| c.public_key.is_some()),
+        url: config.and_then(|c| c.url.clone()),
+        in_progress: IN_PROGRESS.load(Ordering::SeqCst),
+    }))
+}
+
+/// Download, verify and install a new server binary, then restart into it.
+pub(crate) async fn self_update_post(
+    State(state): State<Arc<crate::AppState>>,
+    extensions: Extensions,
+    body: Option<Json<SelfUpdateBody>>,
+) -> ApiResult<Response> {
+    crate::ui_auth::require_admin(&state.ui_auth, &extensions)?;
+    let config = config()
+        .filter(|c| c.enabled)
+        .ok_or_else(|| AppError::forbidden("Self-update is disabled (see --self-update)"))?;
+    let public_key = config.public_key.as_deref().ok_or_else(|| {
+        AppError::forbidden("Self-update needs a trusted public key (--self-update-public-key)")
+    })?;
+    let public_key = parse_public_key(public_key).map_err(AppError::internal)?;
+    let body = body.map(|Json(b)| b).unwrap_or_default();
+
+    if IN_PROGRESS.swap(true, Ordering::SeqCst) {
+        return Err(AppError::conflict("A self-update is already running"));
+    }
+    let mut guard = InProgressGuard { restarting: false };
+    if body.background {
+        let job = crate::jobs::submit("self_update", "Self-update", move |ctx| async move {
+            let result = run_update(config, &public_key, body.force, Some(&ctx)).await;
+            guard.restarting(result.as_ref().is_ok_and(|r| r.restarting));
+            result
+                .map(|r| serde_json::to_value(r).unwrap_or_default())
+                .map_err(|err| err.to_string())
+        });
+        return Ok(crate::jobs::accepted(job));
+    }
+    let result = run_update(config, &public_key, body.force, None).await;
+    guard.restarting(result.as_ref().is_ok_and(|r| r.restarting));
+    result.map(|r| Json(r).into_response())
+}
+
+/// Clears `IN_PROGRESS` unless the update is restarting the server, also
+/// when a background update is canceled.
+struct InProgressGuard {
+    restarting: bool,
+}
+
+impl InProgressGuard {
+    fn restarting(&mut self, restarting: bool) {
+        self.restarting = restarting;
+    }
+}
+
+impl Drop for InProgressGuard {
+    fn drop(&mut self) {
+        if !self.restarting {
+            IN_PROGRESS.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+async fn run_update(
+    config: &SelfUpdateConfig,
+    public_key: &PublicKey,
+    force: bool,
+    job: Option<&crate::jobs::JobContext>,
+) -> ApiResult<SelfUpdateResponse> {
+    let report = |message: &str| {
+        if let Some(job) = job {
+            job.progress(None, message);
+        }
+    };
+    report("Checking for updates");
+    let (url, version, asset) = match config.url.as_deref() {
+        Some(url) => (url.to_string(), None, None),
+        None => {
+            let latest = crate::updates::latest_service_asset()
+                .await
+                .map_err(AppError::bad_gateway)?;
+            if !latest.newer && !force {
+                return Ok(SelfUpdateResponse {
+                    updated: false,
+                    restarting: false,
+                    version: Some(latest.version),
+                    asset: Some(latest.name),
+                });
+            }
+            (latest.url, Some(latest.version), Some(latest.name))
+        }
+    };
+
+    let client = crate::tls_roots::client_builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()
+        .map_err(|err| AppError::internal(format!("build http client: {err}")))?;
+    report("Downloading");
+    let archive = download(&client, &url, MAX_DOWNLOAD_BYTES).await?;
+    let signature = download(&client, &format!("{url}.minisig"), MAX_SIGNATURE_BYTES).await?;
+    report("Verifying");
+    verify(public_key, &archive, &signature).map_err(AppError::bad_request)?;
+
+    let file_name = asset.as_deref().unwrap_or_else(|| url_file_name(&url));
+    let binary = extract_binary(file_name, archive).map_err(AppError::bad_request)?;
+    let exe = std::env::current_exe()
+        .and_then(|p| p.canonicalize())
+        .map_err(|err| AppError::internal(format!("locate current executable: {err}")))?;
+    report("Installing");
+    tokio::task::spawn_blocking({
+        let exe = exe.clone();
+        move || install_binary(&exe, &binary)
+    })
+    .await
+    .map_err(|err| AppError::internal(err.to_string()))?
+    .map_err(AppError::internal)?;
+
+    tracing::warn!(
+        url = %url,
+        version = version.as_deref().unwrap_or("unknown"),
+        "Installed self-update; restarting"
+    );
+    *RESTART_WITH.lock().unwrap_or_else(|e| e.into_inner()) = Some(exe);
+    tokio::spawn(async {
+        tokio::time::sleep(RESTART_DELAY).await;
+        crate::graceful_shutdown::begin_drain("self-update");
+    });
+
+    Ok(SelfUpdateResponse {
+        updated: true,
+        restarting: true,
+        version,
+        asset,
+    })
+}
+
+async fn download(client: &reqwest::Client, url: &str, max: usize) -> ApiResult<Vec<u8>> {
+    let mut resp = client
+        .get(url)
+        .header(reqwest::header::USER_AGENT, "opencode-studio-self-update")
+        .send()
+        .await
+        .map_err(|err| AppError::bad_gateway(format!("download {url}: {err}")))?;
+    if !resp.status().is_success() {
+        return Err(AppError::bad_gateway(format!(
+            "download {url} failed ({})",
+            resp.status()
+        )));
+    }
+    let mut out = Vec::new();
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|err| AppError::bad_gateway(format!("download {url}: {err}")))?
+    {
+        if out.len() + chunk.len() > max {
+            return Err(AppError::payload_too_large(format!(
+                "download {url} exceeds {max} bytes"
+            )));
+        }
+        out.extend_from_slice(&chunk);
+    }
+    Ok(out)
+}
+
+fn url_file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Accept the bare base64 key or the full `minisign.pub` file.
+fn parse_public_key(raw: &str) -> Result<PublicKey, String> {
+    let raw = raw.trim();
+    let parsed = if raw.contains('\n') {
+        PublicKey::decode(raw)
+    } else {
+        PublicKey::from_base64(raw)
+    };
+    parsed.map_err(|err| format!("invalid self-update public key: {err}"))
+}
+
+fn verify(public_key: &PublicKey, data: &[u8], signature: &[u8]) -> Result<(), String> {
+    let signature = std::str::from_utf8(signature)
+        .map_err(|_| "signature is not valid UTF-8".to_string())
+        .and_then(|txt| {
+            Signature::decode(txt).map_err(|err| format!("invalid signature: {err}"))
+        })?;
+    public_key
+        .verify(data, &signature, false)
+        .map_err(|err| format!("signature verification failed: {err}"))
+}
+
+/// Pull the server binary out of a `.tar.gz`/`.zip` release archive; any
+/// other download is taken to be the binary itself.
+fn extract_binary(file_name: &str, data: Vec<u8>) -> Result<Vec<u8>, String> {
+    let lower = file_name.to_ascii_lowercase();
+    if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(data.as_slice()));
+        let entries = archive
+            .entries()
+            .map_err(|err| format!("read archive: {err}"))?;
+        for entry in entries {
+            let mut entry = entry.map_err(|err| format!("read archive: {err}"))?;
+            let is_binary = entry.header().entry_type().is_file()
+                && entry
+                    .path()
+                    .ok()
+                    .and_then(|p| p.file_name().map(|n| n == BINARY_NAME))
+                    .unwrap_or(false);
+            if is_binary {
+                let mut out = Vec::new();
+                entry
+                    .read_to_end(&mut out)
+                    .map_err(|err| format!("read archive: {err}"))?;
+                return Ok(out);
+            }
+        }
+        return Err(format!("{BINARY_NAME} not found in {file_name}"));
+    }
+    if lower.ends_with(".zip") {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data))
+            .map_err(|err| format!("read archive: {err}"))?;
+        for index in 0..archive.len() {
+            let mut entry = archive
+                .by_index(index)
+                .map_err(|err| format!("read archive: {err}"))?;
+            let is_binary = entry.is_file()
+                && entry
+                    .enclosed_name()
+                    .and_then(|p| p.file_name().map(|n| n == BINARY_NAME))
+                    .unwrap_or(false);
+            if is_binary {
+                let mut out = Vec::new();
+                entry
+                    .read_to_end(&mut out)
+                    .map_err(|err| format!("read archive: {err}"))?;
+                return Ok(out);
+            }
+        }
+        return Err(format!("{BINARY_NAME} not found in {file_name}"));
+    }
+    Ok(data)
+}
+
+/// Replace `exe` with `binary`. The new file is written next to it first so
+/// the swap is a rename on the same filesystem.
+fn install_binary(exe: &Path, binary: &[u8]) -> Result<(), String> {
+    let dir = exe
+        .parent()
+        .ok_or_else(|| format!("no parent directory for {}", exe.display()))?;
+    let staged = dir.join(format!(".{BINARY_NAME}.update-{}", std::process::id()));
+    std::fs::write(&staged, binary).map_err(|err| format!("write {}: {err}", staged.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(exe)
+            .map(|m| m.permissions().mode())
+            .unwrap_or(0o755);
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(mode | 0o111))
+            .map_err(|err| format!("chmod {}: {err}", staged.display()))?;
+    }
+
+    // A running executable cannot be overwritten on Windows, but it can be
+    // renamed out of the way.
+    #[cfg(windows)]
+    {
+        let old = exe.with_extension("exe.old");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old).map_err(|err| format!("move old binary aside: {err}"))?;
+    }
+
+    std::fs::rename(&staged, exe).map_err(|err| {
+        let _ = std::fs::remove_file(&staged);
+        format!("replace {}: {err}", exe.display())
+    })
+}
+
+pub(crate) fn restart_pending() -> bool {
+    RESTART_WITH
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_some()
+}
+
+/// Start the installed binary in place of this process, once the server has
+/// drained after a self-update. Does nothing when no update is pending.
+pub(crate) fn exec_restart() {
+    let Some(exe) = RESTART_WITH
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+    else {
+        return;
+    };
+    let args: Vec<_> = std::env::args_os().skip(1).collect();
+    tracing::info!(exe = %exe.display(), "Restarting into updated binary");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let err = std::process::Command::new(&exe).args(&args).exec();
+        tracing::error!(error = %err, "exec after self-update failed");
+        std::process::exit(1);
+    }
+
+    #[cfg(not(unix))]
+    {
+        match std::process::Command::new(&exe).args(&args).spawn() {
+            Ok(_) => std::process::exit(0),
+            Err(err) => {
+                tracing::error!(error = %err, "restart after self-update failed");
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vector from the minisign-verify crate.
+    const PUBLIC_KEY: &str = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==";
+
+    #[test]
+    fn verify_accepts_signed_data_only() {
+        let key = parse_public_key(PUBLIC_KEY).expect("public key");
+        assert!(verify(&key, b"test", SIGNATURE.as_bytes()).is_ok());
+        assert!(verify(&key, b"tampered", SIGNATURE.as_bytes()).is_err());
+        assert!(verify(&key, b"test", b"not a signature").is_err());
+    }
+
+    #[test]
+    fn extract_binary_from_tar_gz() {
+        let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::fast(),
+        ));
+        for (path, body) in [
+            ("release/README.md", &b"docs"[..]),
+            (&format!("release/{BINARY_NAME}")[..], &b"new binary"[..]),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(body.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            tar.append_data(&mut header, path, body).unwrap();
+        }
+        let archive = tar.into_inner().unwrap().finish().unwrap();
+
+        assert_eq!(
+            extract_binary("opencode-studio-x86_64-unknown-linux-musl.tar.gz", archive).unwrap(),
+            b"new binary"
+        );
+        assert_eq!(
+            extract_binary("opencode-studio", b"raw".to_vec()).unwrap(),
+            b"raw"
+        );
+        assert_eq!(url_file_name("https://h/x/a.tar.gz?sig=1"), "a.tar.gz");
+    }
+}
@@ -0,0 +1,401 @@
+//! `opencode-studio service install|uninstall|status`: register the current
+//! binary and runtime config as a background service (systemd on Linux,
+//! launchd on macOS, NSSM-wrapped Windows service), mirroring what
+//! `scripts/install-service.*` set up.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::{Subcommand, ValueEnum};
+
+const SYSTEMD_UNIT: &str = "opencode-studio.service";
+const LAUNCHD_LABEL: &str = "cn.cxits.opencode-studio";
+const WINDOWS_SERVICE: &str = "OpenCodeStudio";
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum ServiceAction {
+    /// Write the service definition and start it.
+    Install {
+        #[arg(long, value_enum, default_value = "user")]
+        mode: ServiceMode,
+    },
+    /// Stop the service and remove its definition.
+    Uninstall {
+        #[arg(long, value_enum, default_value = "user")]
+        mode: ServiceMode,
+    },
+    /// Show whether the service is installed and running.
+    Status {
+        #[arg(long, value_enum, default_value = "user")]
+        mode: ServiceMode,
+    },
+}
+
+/// systemd scope; launchd always installs a user agent and Windows a system service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab_case")]
+pub(crate) enum ServiceMode {
+    User,
+    System,
+}
+
+/// What the service runs.
+#[derive(Debug, Clone)]
+struct ServiceSpec {
+    binary: PathBuf,
+    config: Option<PathBuf>,
+    home: String,
+    path_env: String,
+}
+
+impl ServiceSpec {
+    fn current(config: Option<PathBuf>) -> Result<Self, String> {
+        let binary = std::env::current_exe()
+            .and_then(|exe| exe.canonicalize())
+            .map_err(|err| format!("failed to resolve current binary: {err}"))?;
+        let config = config.map(|path| std::path::absolute(&path).unwrap_or(path));
+        Ok(Self {
+            binary,
+            config,
+            home: std::env::var("HOME")
+                .or_else(|_| std::env::var("USERPROFILE"))
+                .unwrap_or_default(),
+            path_env: service_path_env(),
+        })
+    }
+
+    fn program_args(&self) -> Vec<String> {
+        let mut args = vec![self.binary.to_string_lossy().into_owned()];
+        if let Some(config) = &self.config {
+            args.push("--config".to_string());
+            args.push(config.to_string_lossy().into_owned());
+        }
+        args
+    }
+}
+
+/// Services start with a minimal PATH, so carry over the directory holding
+/// `opencode` plus the usual install locations.
+fn service_path_env() -> String {
+    let current = std::env::var_os("PATH").unwrap_or_default();
+    let mut entries: Vec<PathBuf> = Vec::new();
+    if let Some(dir) = std::env::split_paths(&current)
+        .find(|dir| dir.join("opencode").is_file() || dir.join("opencode.exe").is_file())
+    {
+        entries.push(dir);
+    }
+    if !cfg!(windows) {
+        if let Ok(home) = std::env::var("HOME") {
+            entries.push(Path::new(&home).join(".bun/bin"));
+            entries.push(Path::new(&home).join(".local/bin"));
+        }
+        for dir in [
+            "/opt/homebrew/bin",
+            "/usr/local/bin",
+            "/usr/bin",
+            "/bin",
+            "/usr/sbin",
+            "/sbin",
+        ] {
+            entries.push(PathBuf::from(dir));
+        }
+    }
+    entries.extend(std::env::split_paths(&current));
+
+    let mut seen = std::collections::HashSet::new();
+    entries.retain(|dir| !dir.as_os_str().is_empty() && seen.insert(dir.clone()));
+    std::env::join_paths(entries)
+        .map(|joined| joined.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| current.to_string_lossy().into_owned())
+}
+
+fn systemd_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn render_systemd_unit(spec: &ServiceSpec, mode: ServiceMode, user: Option<&str>) -> String {
+    let exec = spec
+        .program_args()
+        .iter()
+        .map(|arg| systemd_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut unit = String::from(
+        "[Unit]\nDescription=OpenCode Studio server\nAfter=network.target\n\n[Service]\nType=simple\n",
+    );
+    if mode == ServiceMode::System
+        && let Some(user) = user
+    {
+        unit.push_str(&format!("User={user}\n"));
+    }
+    unit.push_str(&format!(
+        "Environment={}\nEnvironment={}\nExecStart={exec}\nRestart=on-failure\nRestartSec=2\n\n[Install]\nWantedBy={}\n",
+        systemd_quote(&format!("PATH={}", spec.path_env)),
+        systemd_quote(&format!("HOME={}", spec.home)),
+        match mode {
+            ServiceMode::User => "default.target",
+            ServiceMode::System => "multi-user.target",
+        }
+    ));
+    unit
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn render_launchd_plist(spec: &ServiceSpec) -> String {
+    let args = spec
+        .program_args()
+        .iter()
+        .map(|arg| format!("    <string>{}</string>\n", xml_escape(arg)))
+        .collect::<String>();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>
+  <string>{LAUNCHD_LABEL}</string>
+  <key>ProgramArguments</key>
+  <array>
+{args}  </array>
+  <key>EnvironmentVariables</key>
+  <dict>
+    <key>PATH</key>
+    <string>{path}</string>
+    <key>HOME</key>
+    <string>{home}</string>
+  </dict>
+  <key>RunAtLoad</key>
+  <true/>
+  <key>KeepAlive</key>
+  <true/>
+</dict>
+</plist>
+"#,
+        path = xml_escape(&spec.path_env),
+        home = xml_escape(&spec.home),
+    )
+}
+
+fn run_command(program: &str, args: &[&str]) -> Result<(), String> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|err| format!("failed to run {program}: {err}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{program} {} failed ({status})", args.join(" ")))
+    }
+}
+
+fn systemd_unit_path(mode: ServiceMode, home: &str) -> PathBuf {
+    match mode {
+        ServiceMode::User => Path::new(home)
+            .join(".config/systemd/user")
+            .join(SYSTEMD_UNIT),
+        ServiceMode::System => Path::new("/etc/systemd/system").join(SYSTEMD_UNIT),
+    }
+}
+
+fn systemctl(mode: ServiceMode, args: &[&str]) -> Result<(), String> {
+    let mut full = Vec::with_capacity(args.len() + 1);
+    if mode == ServiceMode::User {
+        full.push("--user");
+    }
+    full.extend_from_slice(args);
+    run_command("systemctl", &full)
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|err| format!("failed to create {}: {err}", parent.display()))?;
+    }
+    std::fs::write(path, contents).map_err(|err| {
+        let hint = if err.kind() == std::io::ErrorKind::PermissionDenied {
+            " (re-run with sudo for --mode system)"
+        } else {
+            ""
+        };
+        format!("failed to write {}: {err}{hint}", path.display())
+    })
+}
+
+fn launchd_plist_path(home: &str) -> PathBuf {
+    Path::new(home)
+        .join("Library/LaunchAgents")
+        .join(format!("{LAUNCHD_LABEL}.plist"))
+}
+
+fn launchd_target() -> Result<String, String> {
+    let output = Command::new("id")
+        .arg("-u")
+        .output()
+        .map_err(|err| format!("failed to run id: {err}"))?;
+    let uid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(format!("gui/{uid}"))
+}
+
+/// NSSM wraps the server as a Windows service; look next to the binary
+/// (where install-service.ps1 puts it) before PATH.
+fn find_nssm(binary: &Path) -> Option<PathBuf> {
+    let install_dir = binary.parent()?.parent()?;
+    let bundled = install_dir.join("tools").join("nssm.exe");
+    if bundled.is_file() {
+        return Some(bundled);
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join("nssm.exe"))
+        .find(|candidate| candidate.is_file())
+}
+
+fn install(spec: &ServiceSpec, mode: ServiceMode) -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        let plist = launchd_plist_path(&spec.home);
+        write_file(&plist, &render_launchd_plist(spec))?;
+        let domain = launchd_target()?;
+        let plist_arg = plist.to_string_lossy();
+        // Replace a previously loaded agent; failure just means none was loaded.
+        let _ = run_command("launchctl", &["bootout", &domain, &plist_arg]);
+        run_command("launchctl", &["bootstrap", &domain, &plist_arg])?;
+        run_command(
+            "launchctl",
+            &["kickstart", "-k", &format!("{domain}/{LAUNCHD_LABEL}")],
+        )?;
+        println!("Installed launchd agent: {}", plist.display());
+    } else if cfg!(windows) {
+        let nssm = find_nssm(&spec.binary).ok_or_else(|| {
+            "nssm.exe not found; install NSSM or use scripts/install-service.ps1".to_string()
+        })?;
+        let nssm = nssm.to_string_lossy().into_owned();
+        let _ = run_command("sc.exe", &["stop", WINDOWS_SERVICE]);
+        let _ = run_command("sc.exe", &["delete", WINDOWS_SERVICE]);
+        let mut args = vec!["install".to_string(), WINDOWS_SERVICE.to_string()];
+        args.extend(spec.program_args());
+        run_command(&nssm, &args.iter().map(String::as_str).collect::<Vec<_>>())?;
+        run_command(
+            &nssm,
+            &["set", WINDOWS_SERVICE, "Start", "SERVICE_AUTO_START"],
+        )?;
+        run_command("sc.exe", &["start", WINDOWS_SERVICE])?;
+        println!("Installed Windows service: {WINDOWS_SERVICE}");
+    } else {
+        let path = systemd_unit_path(mode, &spec.home);
+        let user = std::env::var("SUDO_USER")
+            .or_else(|_| std::env::var("USER"))
+            .ok();
+        write_file(&path, &render_systemd_unit(spec, mode, user.as_deref()))?;
+        systemctl(mode, &["daemon-reload"])?;
+        systemctl(mode, &["enable", "--now", SYSTEMD_UNIT])?;
+        println!("Installed systemd unit: {}", path.display());
+    }
+    match &spec.config {
+        Some(config) => println!("Runtime config: {}", config.display()),
+        None => println!("No runtime config found; the service runs with defaults."),
+    }
+    Ok(())
+}
+
+fn uninstall(spec: &ServiceSpec, mode: ServiceMode) -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        let plist = launchd_plist_path(&spec.home);
+        let domain = launchd_target()?;
+        let _ = run_command("launchctl", &["bootout", &domain, &plist.to_string_lossy()]);
+        remove_if_exists(&plist)?;
+    } else if cfg!(windows) {
+        let _ = run_command("sc.exe", &["stop", WINDOWS_SERVICE]);
+        run_command("sc.exe", &["delete", WINDOWS_SERVICE])?;
+    } else {
+        let path = systemd_unit_path(mode, &spec.home);
+        let _ = systemctl(mode, &["disable", "--now", SYSTEMD_UNIT]);
+        remove_if_exists(&path)?;
+        systemctl(mode, &["daemon-reload"])?;
+    }
+    println!("Service removed.");
+    Ok(())
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(format!("failed to remove {}: {err}", path.display())),
+    }
+}
+
+fn status(spec: &ServiceSpec, mode: ServiceMode) -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        let plist = launchd_plist_path(&spec.home);
+        println!("Plist: {} (exists: {})", plist.display(), plist.is_file());
+        let domain = launchd_target()?;
+        run_command(
+            "launchctl",
+            &["print", &format!("{domain}/{LAUNCHD_LABEL}")],
+        )
+    } else if cfg!(windows) {
+        run_command("sc.exe", &["query", WINDOWS_SERVICE])
+    } else {
+        let path = systemd_unit_path(mode, &spec.home);
+        println!("Unit: {} (exists: {})", path.display(), path.is_file());
+        systemctl(mode, &["status", "--no-pager", SYSTEMD_UNIT])
+    }
+}
+
+/// Run a `service` subcommand; `config` is the runtime config the service should load.
+pub(crate) fn run(action: &ServiceAction, config: Option<PathBuf>) -> Result<(), String> {
+    let spec = ServiceSpec::current(config)?;
+    match *action {
+        ServiceAction::Install { mode } => install(&spec, mode),
+        ServiceAction::Uninstall { mode } => uninstall(&spec, mode),
+        ServiceAction::Status { mode } => status(&spec, mode),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> ServiceSpec {
+        ServiceSpec {
+            binary: PathBuf::from("/opt/studio/bin/opencode-studio"),
+            config: Some(PathBuf::from("/opt/studio/opencode-studio.toml")),
+            home: "/home/dev".to_string(),
+            path_env: "/home/dev/.bun/bin:/usr/bin".to_string(),
+        }
+    }
+
+    #[test]
+    fn systemd_unit_points_at_binary_and_config() {
+        let unit = render_systemd_unit(&spec(), ServiceMode::System, Some("dev"));
+        assert!(unit.contains(
+            "ExecStart=\"/opt/studio/bin/opencode-studio\" \"--config\" \"/opt/studio/opencode-studio.toml\"\n"
+        ));
+        assert!(unit.contains("User=dev\n"));
+        assert!(unit.contains("Environment=\"PATH=/home/dev/.bun/bin:/usr/bin\"\n"));
+        assert!(unit.contains("WantedBy=multi-user.target\n"));
+
+        let user_unit = render_systemd_unit(&spec(), ServiceMode::User, Some("dev"));
+        assert!(!user_unit.contains("User="));
+        assert!(user_unit.contains("WantedBy=default.target\n"));
+    }
+
+    #[test]
+    fn launchd_plist_escapes_values() {
+        let mut spec = spec();
+        spec.home = "/Users/a&b".to_string();
+        spec.config = None;
+        let plist = render_launchd_plist(&spec);
+        assert!(plist.contains("<string>/Users/a&amp;b</string>"));
+        assert!(plist.contains("<string>/opt/studio/bin/opencode-studio</string>\n  </array>"));
+        assert!(!plist.contains("--config"));
+    }
+}
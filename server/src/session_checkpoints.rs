@@ -0,0 +1,754 @@
+//! Workspace checkpoints taken as each assistant message starts, so the edits
+//! an agent run made can be reviewed against, or rolled back to, the state
+//! the workspace was in before it.
+//!
+//! A checkpoint is a commit of the whole working tree (tracked and untracked
+//! files, minus ignored ones) built through a private index under the git
+//! dir, so the user's index, HEAD and stash are never touched. The commit is
+//! pinned by `refs/opencode-studio/checkpoints/<messageID>` and recorded in
+//! the studio db. Workspaces outside a git repository are not checkpointed.
+//! Capture is best-effort: it runs alongside the message, so a tool that
+//! starts writing immediately may land partly in the snapshot.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, State},
+    response::{IntoResponse, Response},
+};
+use dashmap::DashSet;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row as _;
+
+use crate::git::{is_safe_repo_rel_path, lock_repo, run_git, run_git_env};
+use crate::studio_db::StudioDb;
+use crate::{ApiResult, AppError};
+
+const REF_PREFIX: &str = "refs/opencode-studio/checkpoints/";
+const INDEX_FILE: &str = "opencode-studio-checkpoint.index";
+/// Older checkpoints for a workspace are dropped, refs included.
+const MAX_CHECKPOINTS_PER_DIRECTORY: i64 = 200;
+const MAX_PATCH_BYTES: usize = 1024 * 1024;
+const SEEN_MESSAGES_CAP: usize = 4096;
+
+// Message ids already handed to a capture, so the stream of `message.updated`
+// events for one message triggers a single snapshot.
+static SEEN_MESSAGES: LazyLock<DashSet<String>> = LazyLock::new(DashSet::new);
+
+// Captures share one private index per repository; running them one at a
+// time keeps `git add` from tripping over the index lock.
+static CAPTURE_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCheckpoint {
+    pub message_id: String,
+    pub session_id: String,
+    pub directory: String,
+    pub commit: String,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct CheckpointFileChange {
+    /// Relative to the checkpoint's directory, slash-separated.
+    pub path: String,
+    /// `A` (created since the checkpoint), `M`, `D` (deleted since) or `T`.
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointDiffResponse {
+    pub checkpoint: SessionCheckpoint,
+    pub files: Vec<CheckpointFileChange>,
+    /// Unified diff from the checkpoint to the current working tree.
+    pub patch: String,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointRestoreBody {
+    /// Restore only these paths (as listed by the diff); all changes when
+    /// omitted.
+    #[serde(default)]
+    pub paths: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointRestoreResponse {
+    /// Files written back to their checkpoint contents.
+    pub restored: Vec<String>,
+    /// Files created after the checkpoint and removed (moved to the trash).
+    pub removed: Vec<String>,
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+async fn git_ok(dir: &Path, args: &[&str], env: &[(&str, &str)]) -> Result<String, String> {
+    let (code, out, err) = run_git_env(dir, args, env).await?;
+    if code != 0 {
+        return Err(format!("git {} failed: {}", args[0], err.trim()));
+    }
+    Ok(out.trim().to_string())
+}
+
+/// Tree id of the working tree under `dir` as it is right now, staged through
+/// the private checkpoint index.
+async fn snapshot_tree(dir: &Path) -> Result<String, String> {
+    let git_dir = git_ok(dir, &["rev-parse", "--absolute-git-dir"], &[]).await?;
+    let index = Path::new(&git_dir).join(INDEX_FILE);
+    let index = index.to_string_lossy();
+    let env = [("GIT_INDEX_FILE", index.as_ref())];
+    let _guard = CAPTURE_LOCK.lock().await;
+    git_ok(dir, &["add", "-A", "--", "."], &env).await?;
+    git_ok(dir, &["write-tree"], &env).await
+}
+
+/// Commit the working tree under `dir` and pin it for `message_id`.
+async fn capture(dir: &Path, message_id: &str) -> Result<String, String> {
+    let tree = snapshot_tree(dir).await?;
+    let message = format!("opencode-studio checkpoint {message_id}");
+    let identity = [
+        ("GIT_AUTHOR_NAME", "OpenCode Studio"),
+        ("GIT_AUTHOR_EMAIL", "opencode-studio@localhost"),
+        ("GIT_COMMITTER_NAME", "OpenCode Studio"),
+        ("GIT_COMMITTER_EMAIL", "opencode-studio@localhost"),
+    ];
+    let commit = git_ok(
+        dir,
+        &["commit-tree", "--no-gpg-sign", &tree, "-m", &message],
+        &identity,
+    )
+    .await?;
+    let reference = format!("{REF_PREFIX}{message_id}");
+    git_ok(dir, &["update-ref", &reference, &commit], &[]).await?;
+    Ok(commit)
+}
+
+fn parse_name_status(out: &str) -> Vec<CheckpointFileChange> {
+    let mut fields = out.split('\0').filter(|s| !s.is_empty());
+    let mut changes = Vec::new();
+    while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+        changes.push(CheckpointFileChange {
+            path: path.to_string(),
+            status: status.chars().next().unwrap_or('M').to_string(),
+        });
+    }
+    changes
+}
+
+/// Files under `dir` that differ between `commit` and `tree`.
+async fn changed_files(
+    dir: &Path,
+    commit: &str,
+    tree: &str,
+) -> Result<Vec<CheckpointFileChange>, String> {
+    let out = git_ok(
+        dir,
+        &[
+            "diff",
+            "--no-renames",
+            "--no-ext-diff",
+            "--relative",
+            "--name-status",
+            "-z",
+            commit,
+            tree,
+            "--",
+            ".",
+        ],
+        &[],
+    )
+    .await?;
+    Ok(parse_name_status(&out))
+}
+
+async fn diff_patch(dir: &Path, commit: &str, tree: &str) -> Result<(String, bool), String> {
+    let mut patch = git_ok(
+        dir,
+        &[
+            "diff",
+            "--no-renames",
+            "--no-ext-diff",
+            "--no-color",
+            "--relative",
+            commit,
+            tree,
+            "--",
+            ".",
+        ],
+        &[],
+    )
+    .await?;
+    if patch.len() <= MAX_PATCH_BYTES {
+        return Ok((patch, false));
+    }
+    let mut end = MAX_PATCH_BYTES;
+    while !patch.is_char_boundary(end) {
+        end -= 1;
+    }
+    patch.truncate(end);
+    Ok((patch, true))
+}
+
+enum RestoreAction {
+    Remove,
+    Write { bytes: Vec<u8>, mode: i32 },
+}
+
+/// Checkpoint contents for each change; files the checkpoint lacks are
+/// marked for removal.
+fn load_restore_actions(
+    dir: &Path,
+    prefix: &str,
+    commit: &str,
+    changes: &[CheckpointFileChange],
+) -> Result<Vec<(String, RestoreAction)>, String> {
+    let repo = crate::git2_utils::open_repo_discover(dir).map_err(|err| err.message())?;
+    let oid = git2::Oid::from_str(commit).map_err(|err| err.to_string())?;
+    let tree = repo
+        .find_commit(oid)
+        .and_then(|commit| commit.tree())
+        .map_err(|err| err.to_string())?;
+    let mut actions = Vec::with_capacity(changes.len());
+    for change in changes {
+        if change.status == "A" {
+            actions.push((change.path.clone(), RestoreAction::Remove));
+            continue;
+        }
+        let entry = tree
+            .get_path(Path::new(&format!("{prefix}{}", change.path)))
+            .map_err(|err| format!("{}: {err}", change.path))?;
+        let blob = repo
+            .find_blob(entry.id())
+            .map_err(|err| format!("{}: {err}", change.path))?;
+        actions.push((
+            change.path.clone(),
+            RestoreAction::Write {
+                bytes: blob.content().to_vec(),
+                mode: entry.filemode(),
+            },
+        ));
+    }
+    Ok(actions)
+}
+
+async fn write_restored(target: &Path, bytes: &[u8], mode: i32) -> std::io::Result<()> {
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    #[cfg(unix)]
+    if mode == 0o120000 {
+        use std::os::unix::ffi::OsStrExt;
+        let link = PathBuf::from(std::ffi::OsStr::from_bytes(bytes));
+        if tokio::fs::symlink_metadata(target).await.is_ok() {
+            tokio::fs::remove_file(target).await?;
+        }
+        return tokio::fs::symlink(link, target).await;
+    }
+    if tokio::fs::symlink_metadata(target)
+        .await
+        .is_ok_and(|meta| meta.file_type().is_symlink())
+    {
+        tokio::fs::remove_file(target).await?;
+    }
+    tokio::fs::write(target, bytes).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let perms = if mode == 0o100755 { 0o755 } else { 0o644 };
+        tokio::fs::set_permissions(target, std::fs::Permissions::from_mode(perms)).await?;
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    Ok(())
+}
+
+/// Drop directories left empty by removals, stopping at `root`.
+async fn remove_empty_parents(root: &Path, path: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if current == root || !current.starts_with(root) {
+            break;
+        }
+        if tokio::fs::remove_dir(current).await.is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+/// Put `changes` back to their state in `commit`. With `trash` set, files
+/// about to be overwritten or removed go to the workspace trash first.
+async fn restore_files(
+    dir: &Path,
+    commit: &str,
+    changes: Vec<CheckpointFileChange>,
+    trash: bool,
+) -> Result<CheckpointRestoreResponse, String> {
+    let (code, prefix, _) = run_git(dir, &["rev-parse", "--show-prefix"]).await?;
+    let prefix = if code == 0 {
+        prefix.trim().to_string()
+    } else {
+        String::new()
+    };
+    let actions = {
+        let dir = dir.to_path_buf();
+        let commit = commit.to_string();
+        tokio::task::spawn_blocking(move || load_restore_actions(&dir, &prefix, &commit, &changes))
+            .await
+            .map_err(|err| err.to_string())??
+    };
+
+    let mut response = CheckpointRestoreResponse::default();
+    // Removals first, so a file that replaced a directory (or the reverse)
+    // is out of the way before its old contents are written back.
+    let (removals, writes): (Vec<_>, Vec<_>) = actions
+        .into_iter()
+        .partition(|(_, action)| matches!(action, RestoreAction::Remove));
+    for (path, action) in removals.into_iter().chain(writes) {
+        let target = dir.join(&path);
+        match action {
+            RestoreAction::Remove => {
+                let moved = trash && crate::fs_trash::trash_before_delete(dir, &target).await;
+                if !moved {
+                    match tokio::fs::remove_file(&target).await {
+                        Ok(()) => {}
+                        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                        Err(err) => return Err(format!("{path}: {err}")),
+                    }
+                }
+                remove_empty_parents(dir, &target).await;
+                response.removed.push(path);
+            }
+            RestoreAction::Write { bytes, mode } => {
+                if trash {
+                    crate::fs_trash::trash_before_overwrite(dir, &target).await;
+                }
+                write_restored(&target, &bytes, mode)
+                    .await
+                    .map_err(|err| format!("{path}: {err}"))?;
+                response.restored.push(path);
+            }
+        }
+    }
+    Ok(response)
+}
+
+fn checkpoint_from_row(row: &sqlx::sqlite::SqliteRow) -> Option<SessionCheckpoint> {
+    Some(SessionCheckpoint {
+        message_id: row.try_get("message_id").ok()?,
+        session_id: row.try_get("session_id").ok()?,
+        directory: row.try_get("directory").ok()?,
+        commit: row.try_get("commit_id").ok()?,
+        created_at: row.try_get("created_at").ok()?,
+    })
+}
+
+async fn exists(db: &StudioDb, message_id: &str) -> bool {
+    sqlx::query("SELECT 1 FROM session_checkpoints WHERE message_id = ?")
+        .bind(message_id)
+        .fetch_optional(db.pool())
+        .await
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+async fn insert(db: &StudioDb, checkpoint: &SessionCheckpoint) -> Result<(), String> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO session_checkpoints (message_id, session_id, directory, commit_id, created_at)\n         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&checkpoint.message_id)
+    .bind(&checkpoint.session_id)
+    .bind(&checkpoint.directory)
+    .bind(&checkpoint.commit)
+    .bind(checkpoint.created_at)
+    .execute(db.pool())
+    .await
+    .map(|_| ())
+    .map_err(|err| err.to_string())
+}
+
+/// Delete the given checkpoints' rows and refs.
+async fn forget(db: &StudioDb, rows: Vec<(String, String)>) {
+    for (message_id, directory) in rows {
+        let reference = format!("{REF_PREFIX}{message_id}");
+        let _ = run_git(Path::new(&directory), &["update-ref", "-d", &reference]).await;
+        let _ = sqlx::query("DELETE FROM session_checkpoints WHERE message_id = ?")
+            .bind(&message_id)
+            .execute(db.pool())
+            .await;
+    }
+}
+
+async fn prune(db: &StudioDb, directory: &str) {
+    let rows = sqlx::query(
+        "SELECT message_id, directory FROM session_checkpoints WHERE directory = ?\n         ORDER BY created_at DESC LIMIT -1 OFFSET ?",
+    )
+    .bind(directory)
+    .bind(MAX_CHECKPOINTS_PER_DIRECTORY)
+    .fetch_all(db.pool())
+    .await
+    .unwrap_or_default();
+    forget(db, message_dirs(rows)).await;
+}
+
+fn message_dirs(rows: Vec<sqlx::sqlite::SqliteRow>) -> Vec<(String, String)> {
+    rows.into_iter()
+        .filter_map(|row| {
+            Some((
+                row.try_get("message_id").ok()?,
+                row.try_get("directory").ok()?,
+            ))
+        })
+        .collect()
+}
+
+async fn capture_for_message(
+    db: Arc<StudioDb>,
+    session_id: String,
+    message_id: String,
+    dir: String,
+) {
+    if exists(&db, &message_id).await {
+        return;
+    }
+    let path = Path::new(&dir);
+    match run_git(path, &["rev-parse", "--is-inside-work-tree"]).await {
+        Ok((0, out, _)) if out.trim() == "true" => {}
+        _ => return,
+    }
+    match capture(path, &message_id).await {
+        Ok(commit) => {
+            let checkpoint = SessionCheckpoint {
+                message_id,
+                session_id,
+                directory: dir.clone(),
+                commit,
+                created_at: now_millis(),
+            };
+            if let Err(err) = insert(&db, &checkpoint).await {
+                tracing::warn!(error = %err, "Failed to record workspace checkpoint");
+                return;
+            }
+            prune(&db, &dir).await;
+        }
+        Err(err) => {
+            tracing::debug!(directory = %dir, error = %err, "Workspace checkpoint skipped");
+        }
+    }
+}
+
+fn read_str<'a>(value: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .find_map(|key| value.get(*key).and_then(|v| v.as_str()))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// `(sessionID, messageID, cwd)` for an assistant message that has not
+/// completed yet.
+fn started_assistant_message(props: &Value) -> Option<(&str, &str, Option<&str>)> {
+    let info = props.get("info")?;
+    if read_str(info, &["role"])? != "assistant" {
+        return None;
+    }
+    if info
+        .get("time")
+        .and_then(|time| time.get("completed"))
+        .is_some_and(|completed| !completed.is_null())
+    {
+        return None;
+    }
+    let session_id = read_str(info, &["sessionID", "sessionId"])?;
+    let message_id = read_str(info, &["id"])?;
+    let cwd = info.get("path").and_then(|path| read_str(path, &["cwd"]));
+    Some((session_id, message_id, cwd))
+}
+
+/// Snapshot the workspace when an assistant message starts, and drop a
+/// session's checkpoints when the session is deleted.
+pub(crate) fn observe_event(state: &Arc<crate::AppState>, payload: &Value) {
+    let Some(event_type) = payload.get("type").and_then(|v| v.as_str()) else {
+        return;
+    };
+    let Some(props) = payload.get("properties") else {
+        return;
+    };
+    match event_type {
+        "message.updated" => {
+            let Some((session_id, message_id, cwd)) = started_assistant_message(props) else {
+                return;
+            };
+            if SEEN_MESSAGES.contains(message_id) {
+                return;
+            }
+            let Some(dir) = state
+                .directory_session_index
+                .directory_for_session(session_id)
+                .or_else(|| cwd.map(str::to_string))
+            else {
+                return;
+            };
+            if SEEN_MESSAGES.len() >= SEEN_MESSAGES_CAP {
+                SEEN_MESSAGES.clear();
+            }
+            SEEN_MESSAGES.insert(message_id.to_string());
+            tokio::spawn(capture_for_message(
+                state.studio_db.clone(),
+                session_id.to_string(),
+                message_id.to_string(),
+                dir,
+            ));
+        }
+        "session.deleted" => {
+            let session_id = read_str(props, &["sessionID", "sessionId"])
+                .or_else(|| props.get("info").and_then(|info| read_str(info, &["id"])));
+            if let Some(session_id) = session_id {
+                let db = state.studio_db.clone();
+                let session_id = session_id.to_string();
+                tokio::spawn(async move {
+                    let rows = sqlx::query(
+                        "SELECT message_id, directory FROM session_checkpoints WHERE session_id = ?",
+                    )
+                    .bind(&session_id)
+                    .fetch_all(db.pool())
+                    .await
+                    .unwrap_or_default();
+                    forget(&db, message_dirs(rows)).await;
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+async fn load_checkpoint(
+    db: &StudioDb,
+    session_id: &str,
+    message_id: &str,
+) -> ApiResult<SessionCheckpoint> {
+    let row = sqlx::query(
+        "SELECT message_id, session_id, directory, commit_id, created_at FROM session_checkpoints\n         WHERE session_id = ? AND message_id = ?",
+    )
+    .bind(session_id.trim())
+    .bind(message_id.trim())
+    .fetch_optional(db.pool())
+    .await
+    .map_err(|err| AppError::internal(err.to_string()))?;
+    row.as_ref()
+        .and_then(checkpoint_from_row)
+        .ok_or_else(|| AppError::not_found("Checkpoint not found"))
+}
+
+/// Checkpoints recorded for a session, oldest first.
+pub(crate) async fn session_checkpoints_list(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(session_id): AxumPath<String>,
+) -> ApiResult<Json<Vec<SessionCheckpoint>>> {
+    let rows = sqlx::query(
+        "SELECT message_id, session_id, directory, commit_id, created_at FROM session_checkpoints\n         WHERE session_id = ? ORDER BY created_at ASC",
+    )
+    .bind(session_id.trim())
+    .fetch_all(state.studio_db.pool())
+    .await
+    .map_err(|err| AppError::internal(err.to_string()))?;
+    Ok(Json(rows.iter().filter_map(checkpoint_from_row).collect()))
+}
+
+/// What changed in the workspace since the checkpoint for `message_id`.
+pub(crate) async fn session_checkpoint_diff(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath((session_id, message_id)): AxumPath<(String, String)>,
+) -> ApiResult<Json<CheckpointDiffResponse>> {
+    let checkpoint = load_checkpoint(&state.studio_db, &session_id, &message_id).await?;
+    let dir = PathBuf::from(&checkpoint.directory);
+    let tree = snapshot_tree(&dir).await.map_err(AppError::internal)?;
+    let files = changed_files(&dir, &checkpoint.commit, &tree)
+        .await
+        .map_err(AppError::internal)?;
+    let (patch, truncated) = diff_patch(&dir, &checkpoint.commit, &tree)
+        .await
+        .map_err(AppError::internal)?;
+    Ok(Json(CheckpointDiffResponse {
+        checkpoint,
+        files,
+        patch,
+        truncated,
+    }))
+}
+
+/// Roll the workspace back to the checkpoint for `message_id`. Files the
+/// restore overwrites or removes are kept in the workspace trash.
+pub(crate) async fn session_checkpoint_restore(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath((session_id, message_id)): AxumPath<(String, String)>,
+    body: Option<Json<CheckpointRestoreBody>>,
+) -> ApiResult<Response> {
+    let body = body.map(|Json(body)| body).unwrap_or_default();
+    let checkpoint = load_checkpoint(&state.studio_db, &session_id, &message_id).await?;
+    let dir = PathBuf::from(&checkpoint.directory);
+    let _guard = match lock_repo(&dir, "restore-checkpoint").await {
+        Ok(guard) => guard,
+        Err(resp) => return Ok(resp),
+    };
+
+    let tree = snapshot_tree(&dir).await.map_err(AppError::internal)?;
+    let mut changes = changed_files(&dir, &checkpoint.commit, &tree)
+        .await
+        .map_err(AppError::internal)?;
+    if let Some(paths) = body.paths.as_ref() {
+        changes.retain(|change| paths.iter().any(|p| p.trim() == change.path));
+    }
+    if changes
+        .iter()
+        .any(|change| !is_safe_repo_rel_path(&change.path))
+    {
+        return Err(AppError::bad_request("Checkpoint contains an unsafe path"));
+    }
+
+    let response = restore_files(&dir, &checkpoint.commit, changes, true)
+        .await
+        .map_err(|err| AppError::internal(format!("Failed to restore checkpoint: {err}")))?;
+    let touched: Vec<PathBuf> = response
+        .restored
+        .iter()
+        .chain(&response.removed)
+        .map(|path| dir.join(path))
+        .collect();
+    if !touched.is_empty() {
+        crate::fs::publish_fs_changed_event(&dir, "checkpoint-restore", touched, None, None);
+    }
+    Ok(Json(response).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .status()
+            .expect("run git");
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn detects_started_assistant_messages() {
+        let started = json!({
+            "info": {
+                "id": "msg_1",
+                "sessionID": "ses_1",
+                "role": "assistant",
+                "time": { "created": 1 },
+                "path": { "cwd": "/repo" }
+            }
+        });
+        assert_eq!(
+            started_assistant_message(&started),
+            Some(("ses_1", "msg_1", Some("/repo")))
+        );
+
+        let completed = json!({
+            "info": { "id": "msg_1", "sessionID": "ses_1", "role": "assistant", "time": { "created": 1, "completed": 2 } }
+        });
+        assert_eq!(started_assistant_message(&completed), None);
+
+        let user = json!({ "info": { "id": "msg_0", "sessionID": "ses_1", "role": "user" } });
+        assert_eq!(started_assistant_message(&user), None);
+    }
+
+    #[tokio::test]
+    async fn restores_workspace_to_checkpoint() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        git(root, &["init", "-q"]);
+        std::fs::create_dir_all(root.join("app/src")).unwrap();
+        std::fs::write(root.join("app/src/lib.rs"), "before\n").unwrap();
+        std::fs::write(root.join("app/notes.txt"), "keep me\n").unwrap();
+        std::fs::write(root.join("outside.txt"), "outside\n").unwrap();
+        git(root, &["add", "app/src/lib.rs"]);
+        git(root, &["commit", "-q", "-m", "init"]);
+        let head_before = std::fs::read_to_string(root.join(".git/HEAD")).unwrap();
+
+        let app = root.join("app");
+        let commit = capture(&app, "msg_1").await.expect("capture");
+
+        std::fs::write(app.join("src/lib.rs"), "after\n").unwrap();
+        std::fs::remove_file(app.join("notes.txt")).unwrap();
+        std::fs::create_dir_all(app.join("gen")).unwrap();
+        std::fs::write(app.join("gen/new.rs"), "new\n").unwrap();
+        std::fs::write(root.join("outside.txt"), "changed\n").unwrap();
+
+        let tree = snapshot_tree(&app).await.expect("tree");
+        let mut files = changed_files(&app, &commit, &tree).await.expect("diff");
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        let summary: Vec<(&str, &str)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("gen/new.rs", "A"), ("notes.txt", "D"), ("src/lib.rs", "M")]
+        );
+        let (patch, truncated) = diff_patch(&app, &commit, &tree).await.expect("patch");
+        assert!(!truncated);
+        assert!(patch.contains("+after"));
+
+        let restored = restore_files(&app, &commit, files, false)
+            .await
+            .expect("restore");
+        assert_eq!(restored.removed, vec!["gen/new.rs"]);
+        assert_eq!(
+            std::fs::read_to_string(app.join("src/lib.rs")).unwrap(),
+            "before\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(app.join("notes.txt")).unwrap(),
+            "keep me\n"
+        );
+        assert!(!app.join("gen").exists());
+        assert_eq!(
+            std::fs::read_to_string(root.join("outside.txt")).unwrap(),
+            "changed\n"
+        );
+
+        // The user's index and HEAD are untouched.
+        assert_eq!(
+            std::fs::read_to_string(root.join(".git/HEAD")).unwrap(),
+            head_before
+        );
+        let status = std::process::Command::new("git")
+            .args(["diff", "--cached", "--name-only"])
+            .current_dir(root)
+            .output()
+            .unwrap();
+        assert!(status.stdout.is_empty());
+        assert!(
+            root.join(".git/refs/opencode-studio/checkpoints/msg_1")
+                .exists()
+        );
+    }
+}
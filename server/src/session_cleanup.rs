@@ -0,0 +1,382 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{Json, extract::State};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::directory_session_index::SessionSummaryRecord;
+use crate::{ApiResult, AppError};
+
+const DAY_MILLIS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+const MAX_ARCHIVE_IDS: usize = 1000;
+/// Upper bound on sessions removed by one cleanup request.
+const MAX_CLEANUP_SESSIONS: usize = 500;
+
+fn now_millis() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as f64)
+        .unwrap_or(0.0)
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionArchiveBody {
+    pub ids: Vec<String>,
+    /// `false` unarchives.
+    #[serde(default = "default_true")]
+    pub archived: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionArchiveResponse {
+    pub archived: bool,
+    /// Sessions whose state changed.
+    pub changed: Vec<String>,
+}
+
+/// Archive (or unarchive) sessions. Archived sessions keep their storage
+/// and are hidden from default session lists and the recent list.
+pub async fn session_archive_post(
+    State(state): State<Arc<crate::AppState>>,
+    Json(body): Json<SessionArchiveBody>,
+) -> ApiResult<Json<SessionArchiveResponse>> {
+    let mut ids: Vec<String> = Vec::new();
+    for id in body
+        .ids
+        .iter()
+        .map(|id| id.trim())
+        .filter(|id| !id.is_empty())
+    {
+        if !ids.iter().any(|seen| seen == id) {
+            ids.push(id.to_string());
+        }
+    }
+    if ids.is_empty() {
+        return Err(AppError::bad_request("ids must list at least one session"));
+    }
+    if ids.len() > MAX_ARCHIVE_IDS {
+        return Err(AppError::bad_request(format!(
+            "At most {MAX_ARCHIVE_IDS} sessions per request"
+        )));
+    }
+    let changed = crate::session_tags::set_archived(&state.studio_db, &ids, body.archived).await?;
+    Ok(Json(SessionArchiveResponse {
+        archived: body.archived,
+        changed,
+    }))
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCleanupBody {
+    /// Only sessions not updated for this many days.
+    #[serde(default)]
+    pub older_than_days: Option<f64>,
+    /// Only sessions in this directory.
+    #[serde(default)]
+    pub directory: Option<String>,
+    /// Only archived sessions.
+    #[serde(default)]
+    pub archived_only: bool,
+    /// Favorites are kept unless this is set.
+    #[serde(default)]
+    pub include_favorites: bool,
+    /// Report what would be removed without deleting anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupSession {
+    pub id: String,
+    pub title: String,
+    pub directory: String,
+    pub updated_at: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupSkipped {
+    pub id: String,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupFailed {
+    pub id: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCleanupResponse {
+    pub dry_run: bool,
+    /// Sessions matching the criteria (deleted unless `dryRun`).
+    pub sessions: Vec<CleanupSession>,
+    pub skipped: Vec<CleanupSkipped>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<CleanupFailed>,
+    /// More sessions matched than one request removes.
+    pub truncated: bool,
+}
+
+struct CleanupCriteria {
+    cutoff: Option<f64>,
+    directory: Option<String>,
+    archived_only: bool,
+    include_favorites: bool,
+}
+
+impl CleanupCriteria {
+    fn from_body(body: &SessionCleanupBody, now: f64) -> ApiResult<Self> {
+        let cutoff = match body.older_than_days {
+            Some(days) if !days.is_finite() || days < 0.0 => {
+                return Err(AppError::bad_request("olderThanDays must be >= 0"));
+            }
+            Some(days) => Some(now - days * DAY_MILLIS),
+            None => None,
+        };
+        let directory = match body.directory.as_deref().map(str::trim) {
+            Some(dir) if !dir.is_empty() => Some(
+                crate::path_utils::normalize_directory_for_match(dir)
+                    .ok_or_else(|| AppError::bad_request("Invalid directory"))?,
+            ),
+            _ => None,
+        };
+        // Never wipe everything by accident.
+        if cutoff.is_none() && directory.is_none() && !body.archived_only {
+            return Err(AppError::bad_request(
+                "Set olderThanDays, directory or archivedOnly",
+            ));
+        }
+        Ok(Self {
+            cutoff,
+            directory,
+            archived_only: body.archived_only,
+            include_favorites: body.include_favorites,
+        })
+    }
+
+    fn matches(&self, summary: &SessionSummaryRecord) -> bool {
+        if self
+            .cutoff
+            .is_some_and(|cutoff| summary.updated_at >= cutoff)
+        {
+            return false;
+        }
+        if let Some(dir) = &self.directory {
+            let in_dir = crate::path_utils::normalize_directory_for_match(&summary.directory_path)
+                .is_some_and(|key| key == *dir);
+            if !in_dir {
+                return false;
+            }
+        }
+        !self.archived_only || crate::session_tags::is_archived(&summary.session_id)
+    }
+}
+
+/// Split matching sessions into removable ones and ones kept for a reason.
+fn plan_cleanup(
+    criteria: &CleanupCriteria,
+    summaries: Vec<SessionSummaryRecord>,
+    busy: &HashSet<String>,
+) -> (Vec<CleanupSession>, Vec<CleanupSkipped>) {
+    let mut sessions = Vec::new();
+    let mut skipped = Vec::new();
+    for summary in summaries {
+        if !criteria.matches(&summary) {
+            continue;
+        }
+        let id = summary.session_id;
+        if busy.contains(&id) {
+            skipped.push(CleanupSkipped { id, reason: "busy" });
+            continue;
+        }
+        if !criteria.include_favorites
+            && crate::session_tags::entry(&id).is_some_and(|e| e.favorite)
+        {
+            skipped.push(CleanupSkipped {
+                id,
+                reason: "favorite",
+            });
+            continue;
+        }
+        sessions.push(CleanupSession {
+            id,
+            title: summary.title,
+            directory: summary.directory_path,
+            updated_at: summary.updated_at,
+            parent_id: summary.parent_id,
+        });
+    }
+    // Oldest first, so a truncated run removes the stalest sessions.
+    sessions.sort_by(|a, b| {
+        a.updated_at
+            .partial_cmp(&b.updated_at)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    (sessions, skipped)
+}
+
+async fn delete_upstream(
+    bridge: &crate::opencode::OpenCodeBridge,
+    session: &CleanupSession,
+) -> Result<(), String> {
+    let url = format!(
+        "{}/session/{}?directory={}",
+        bridge.base_url.trim_end_matches('/'),
+        urlencoding::encode(&session.id),
+        urlencoding::encode(&session.directory)
+    );
+    let resp = bridge
+        .client
+        .delete(url)
+        .send()
+        .await
+        .map_err(|_| "OpenCode is unreachable".to_string())?;
+    let status = resp.status();
+    // Already gone, e.g. removed together with its parent.
+    if status.is_success() || status == reqwest::StatusCode::NOT_FOUND {
+        return Ok(());
+    }
+    let detail = resp.text().await.unwrap_or_default();
+    Err(format!(
+        "OpenCode rejected delete ({status}): {}",
+        detail.trim()
+    ))
+}
+
+/// Bulk-delete sessions older than N days, in a directory, or archived.
+/// With `dryRun` only the plan is returned. Deleted sessions are dropped
+/// from the session index right away so sidebar aggregates stay in step.
+pub async fn session_cleanup_post(
+    State(state): State<Arc<crate::AppState>>,
+    Json(body): Json<SessionCleanupBody>,
+) -> ApiResult<Json<SessionCleanupResponse>> {
+    let criteria = CleanupCriteria::from_body(&body, now_millis())?;
+    let index = &state.directory_session_index;
+    let busy: HashSet<String> = index.busy_session_ids().into_iter().collect();
+    let (mut sessions, skipped) = plan_cleanup(&criteria, index.summaries_snapshot(), &busy);
+    let truncated = sessions.len() > MAX_CLEANUP_SESSIONS;
+    sessions.truncate(MAX_CLEANUP_SESSIONS);
+
+    if body.dry_run || sessions.is_empty() {
+        return Ok(Json(SessionCleanupResponse {
+            dry_run: body.dry_run,
+            sessions,
+            skipped,
+            failed: Vec::new(),
+            truncated,
+        }));
+    }
+
+    if state.opencode.bridge().await.is_none() {
+        return Err(AppError::bad_gateway("OpenCode is not running"));
+    }
+    let mut deleted = Vec::new();
+    let mut removed_ids = Vec::new();
+    let mut failed = Vec::new();
+    for session in sessions {
+        // Deleting should not start pooled instances for every directory.
+        let upstream = state
+            .opencode
+            .running_for_directory(Some(&session.directory));
+        let result = match upstream.bridge().await {
+            Some(bridge) => delete_upstream(&bridge, &session).await,
+            None => Err("OpenCode is not running".to_string()),
+        };
+        match result {
+            Ok(()) => {
+                index.remove_summary(&session.id);
+                removed_ids.push(session.id.clone());
+                deleted.push(session);
+            }
+            Err(error) => failed.push(CleanupFailed {
+                id: session.id,
+                error,
+            }),
+        }
+    }
+    crate::session_tags::forget(&state, &removed_ids);
+    tracing::info!(
+        deleted = deleted.len(),
+        failed = failed.len(),
+        "Session cleanup finished"
+    );
+
+    Ok(Json(SessionCleanupResponse {
+        dry_run: false,
+        sessions: deleted,
+        skipped,
+        failed,
+        truncated,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(id: &str, dir: &str, updated_at: f64) -> SessionSummaryRecord {
+        SessionSummaryRecord {
+            session_id: id.to_string(),
+            directory_path: dir.to_string(),
+            parent_id: None,
+            title: id.to_string(),
+            updated_at,
+            raw: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn cleanup_requires_a_criterion() {
+        let body = SessionCleanupBody {
+            dry_run: true,
+            ..SessionCleanupBody::default()
+        };
+        assert!(CleanupCriteria::from_body(&body, 0.0).is_err());
+        let body = SessionCleanupBody {
+            older_than_days: Some(-1.0),
+            ..SessionCleanupBody::default()
+        };
+        assert!(CleanupCriteria::from_body(&body, 0.0).is_err());
+    }
+
+    #[test]
+    fn cleanup_plan_filters_by_age_and_directory_and_skips_busy() {
+        let now = 100.0 * DAY_MILLIS;
+        let body = SessionCleanupBody {
+            older_than_days: Some(30.0),
+            directory: Some("/work/a/".to_string()),
+            ..SessionCleanupBody::default()
+        };
+        let criteria = CleanupCriteria::from_body(&body, now).unwrap();
+        let summaries = vec![
+            summary("ses_cleanup_recent", "/work/a", now - DAY_MILLIS),
+            summary("ses_cleanup_old_b", "/work/a", now - 40.0 * DAY_MILLIS),
+            summary("ses_cleanup_old_a", "/work/a", now - 60.0 * DAY_MILLIS),
+            summary("ses_cleanup_other", "/work/b", now - 60.0 * DAY_MILLIS),
+            summary("ses_cleanup_busy", "/work/a", now - 60.0 * DAY_MILLIS),
+        ];
+        let busy = HashSet::from(["ses_cleanup_busy".to_string()]);
+        let (sessions, skipped) = plan_cleanup(&criteria, summaries, &busy);
+        let ids: Vec<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["ses_cleanup_old_a", "ses_cleanup_old_b"]);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].id, "ses_cleanup_busy");
+        assert_eq!(skipped[0].reason, "busy");
+    }
+}
@@ -0,0 +1,325 @@
+//! Per-session diff aggregate kept in the studio db, so `GET /session/{id}/diff`
+//! reads one row per file instead of re-parsing the whole message history.
+//!
+//! Each row holds a file's latest diff item and the `messageID/partID` that
+//! produced it; a later part wins. Parts streaming through the global event
+//! hub upsert rows as they arrive. Removing a message or part drops the
+//! session's aggregate, and the next diff request rebuilds it from history.
+
+use std::sync::{Arc, LazyLock, OnceLock};
+
+use dashmap::DashMap;
+use serde_json::Value;
+use sqlx::Row as _;
+use tokio::sync::mpsc;
+
+use crate::opencode_proxy::SessionDiffItem;
+use crate::studio_db::StudioDb;
+
+type DiffRow = (String, SessionDiffItem);
+
+#[derive(Debug)]
+enum DiffWrite {
+    Upsert {
+        session_id: String,
+        rows: Vec<DiffRow>,
+    },
+    /// Rows rebuilt from history; dropped if the session was invalidated
+    /// after the history snapshot was taken.
+    Rebuilt {
+        session_id: String,
+        generation: u64,
+        rows: Vec<DiffRow>,
+    },
+    Invalidate {
+        session_id: String,
+    },
+}
+
+// Bumped on every invalidation so a rebuild racing a removal is discarded.
+static GENERATIONS: LazyLock<DashMap<String, u64>> = LazyLock::new(DashMap::new);
+
+// Writes go through one queue so updates to the same part land in event order.
+static WRITER: OnceLock<mpsc::UnboundedSender<DiffWrite>> = OnceLock::new();
+
+pub(crate) fn generation(session_id: &str) -> u64 {
+    GENERATIONS.get(session_id).map(|g| *g).unwrap_or(0)
+}
+
+fn enqueue(state: &crate::AppState, write: DiffWrite) {
+    let tx = WRITER.get_or_init(|| {
+        let (tx, mut rx) = mpsc::unbounded_channel::<DiffWrite>();
+        let db = state.studio_db.clone();
+        tokio::spawn(async move {
+            while let Some(write) = rx.recv().await {
+                if let Err(err) = apply(&db, write).await {
+                    tracing::warn!(error = %err, "Failed to update session diff aggregate");
+                }
+            }
+        });
+        tx
+    });
+    let _ = tx.send(write);
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+async fn upsert_rows(
+    tx: &mut sqlx::SqliteConnection,
+    session_id: &str,
+    rows: &[DiffRow],
+) -> Result<(), String> {
+    let now = now_millis();
+    for (source, item) in rows {
+        let item_json = serde_json::to_string(item).map_err(|err| err.to_string())?;
+        sqlx::query(
+            "INSERT INTO session_diff_files (session_id, file, source, item_json, updated_at)\n             VALUES (?, ?, ?, ?, ?)\n             ON CONFLICT(session_id, file) DO UPDATE SET\n               source = excluded.source,\n               item_json = excluded.item_json,\n               updated_at = excluded.updated_at\n             WHERE excluded.source >= session_diff_files.source",
+        )
+        .bind(session_id)
+        .bind(&item.file)
+        .bind(source)
+        .bind(item_json)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+async fn apply(db: &StudioDb, write: DiffWrite) -> Result<(), String> {
+    let mut tx = db.pool().begin().await.map_err(|err| err.to_string())?;
+    match write {
+        DiffWrite::Upsert { session_id, rows } => {
+            upsert_rows(&mut tx, &session_id, &rows).await?;
+        }
+        DiffWrite::Rebuilt {
+            session_id,
+            generation: snapshot,
+            rows,
+        } => {
+            if generation(&session_id) != snapshot {
+                return Ok(());
+            }
+            upsert_rows(&mut tx, &session_id, &rows).await?;
+            sqlx::query(
+                "INSERT OR REPLACE INTO session_diff_sessions (session_id, built_at) VALUES (?, ?)",
+            )
+            .bind(&session_id)
+            .bind(now_millis())
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| err.to_string())?;
+        }
+        DiffWrite::Invalidate { session_id } => {
+            for sql in [
+                "DELETE FROM session_diff_files WHERE session_id = ?",
+                "DELETE FROM session_diff_sessions WHERE session_id = ?",
+            ] {
+                sqlx::query(sql)
+                    .bind(&session_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|err| err.to_string())?;
+            }
+        }
+    }
+    tx.commit().await.map_err(|err| err.to_string())
+}
+
+/// The session's aggregate rows, or `None` until it has been built from history.
+pub(crate) async fn load(state: &crate::AppState, session_id: &str) -> Option<Vec<DiffRow>> {
+    load_from(&state.studio_db, session_id).await
+}
+
+async fn load_from(db: &StudioDb, session_id: &str) -> Option<Vec<DiffRow>> {
+    sqlx::query("SELECT 1 FROM session_diff_sessions WHERE session_id = ?")
+        .bind(session_id)
+        .fetch_optional(db.pool())
+        .await
+        .ok()??;
+
+    let rows = sqlx::query("SELECT source, item_json FROM session_diff_files WHERE session_id = ?")
+        .bind(session_id)
+        .fetch_all(db.pool())
+        .await
+        .ok()?;
+    Some(
+        rows.into_iter()
+            .filter_map(|row| {
+                let source: String = row.try_get("source").ok()?;
+                let item_json: String = row.try_get("item_json").ok()?;
+                let item = serde_json::from_str(&item_json).ok()?;
+                Some((source, item))
+            })
+            .collect(),
+    )
+}
+
+/// Record an aggregate rebuilt from a history snapshot taken at `generation`.
+pub(crate) fn store_rebuilt(
+    state: &crate::AppState,
+    session_id: &str,
+    generation: u64,
+    rows: Vec<DiffRow>,
+) {
+    enqueue(
+        state,
+        DiffWrite::Rebuilt {
+            session_id: session_id.to_string(),
+            generation,
+            rows,
+        },
+    );
+}
+
+fn invalidate(state: &crate::AppState, session_id: &str) {
+    *GENERATIONS.entry(session_id.to_string()).or_insert(0) += 1;
+    enqueue(
+        state,
+        DiffWrite::Invalidate {
+            session_id: session_id.to_string(),
+        },
+    );
+}
+
+fn read_str<'a>(value: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .find_map(|key| value.get(*key).and_then(|v| v.as_str()))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// The session and aggregate rows carried by a `message.part.*` event.
+fn rows_from_part_event(props: &Value) -> Option<(String, Vec<DiffRow>)> {
+    let part = props.get("part")?;
+    let session_id = read_str(props, &["sessionID", "sessionId"])
+        .or_else(|| read_str(part, &["sessionID", "sessionId"]))?;
+    let message_id = read_str(props, &["messageID", "messageId"])
+        .or_else(|| read_str(part, &["messageID", "messageId"]))?;
+    let part_id = read_str(part, &["id"]).or_else(|| read_str(props, &["partID", "partId"]))?;
+    let source = crate::opencode_proxy::session_diff_source_key(message_id, part_id);
+    let rows = crate::opencode_proxy::session_diff_items_from_part(part.as_object()?, None)
+        .into_iter()
+        .map(|item| (source.clone(), item))
+        .collect::<Vec<_>>();
+    Some((session_id.to_string(), rows))
+}
+
+pub(crate) fn observe_event(state: &Arc<crate::AppState>, payload: &Value) {
+    let Some(event_type) = payload.get("type").and_then(|v| v.as_str()) else {
+        return;
+    };
+    let Some(props) = payload.get("properties") else {
+        return;
+    };
+    match event_type {
+        "message.part.updated" | "message.part.created" => {
+            if let Some((session_id, rows)) = rows_from_part_event(props)
+                && !rows.is_empty()
+            {
+                enqueue(state, DiffWrite::Upsert { session_id, rows });
+            }
+        }
+        "message.removed" | "message.part.removed" => {
+            if let Some(session_id) = read_str(props, &["sessionID", "sessionId"]) {
+                invalidate(state, session_id);
+            }
+        }
+        "session.deleted" => {
+            let session_id = read_str(props, &["sessionID", "sessionId"])
+                .or_else(|| props.get("info").and_then(|info| read_str(info, &["id"])));
+            if let Some(session_id) = session_id {
+                invalidate(state, session_id);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn part_event(message_id: &str, part_id: &str, file: &str, after: &str) -> Value {
+        json!({
+            "sessionID": "ses_1",
+            "messageID": message_id,
+            "part": {
+                "id": part_id,
+                "type": "tool",
+                "state": {
+                    "metadata": {
+                        "files": [{"path": file, "before": "", "after": after}]
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn rows_from_part_event_tags_items_with_source() {
+        let (session_id, rows) =
+            rows_from_part_event(&part_event("msg_1", "prt_1", "src/a.ts", "x")).expect("rows");
+        assert_eq!(session_id, "ses_1");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].0, "msg_1/prt_1");
+        assert_eq!(rows[0].1.file, "src/a.ts");
+    }
+
+    #[tokio::test]
+    async fn aggregate_keeps_latest_part_per_file_and_drops_on_invalidate() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let db = StudioDb::open_at_path(dir.path().join("studio.db"))
+            .await
+            .expect("db");
+
+        let (_, newer) =
+            rows_from_part_event(&part_event("msg_2", "prt_1", "src/a.ts", "new")).expect("rows");
+        let (_, older) =
+            rows_from_part_event(&part_event("msg_1", "prt_1", "src/a.ts", "old")).expect("rows");
+
+        // Streamed before any request: stored but not yet served.
+        apply(
+            &db,
+            DiffWrite::Upsert {
+                session_id: "ses_1".to_string(),
+                rows: newer,
+            },
+        )
+        .await
+        .expect("upsert");
+        assert!(load_from(&db, "ses_1").await.is_none());
+
+        // A rebuild from history must not clobber the newer streamed part.
+        apply(
+            &db,
+            DiffWrite::Rebuilt {
+                session_id: "ses_1".to_string(),
+                generation: generation("ses_1"),
+                rows: older,
+            },
+        )
+        .await
+        .expect("rebuild");
+        let rows = load_from(&db, "ses_1").await.expect("built");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].1.after, "new");
+
+        apply(
+            &db,
+            DiffWrite::Invalidate {
+                session_id: "ses_1".to_string(),
+            },
+        )
+        .await
+        .expect("invalidate");
+        assert!(load_from(&db, "ses_1").await.is_none());
+    }
+}
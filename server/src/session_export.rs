@@ -0,0 +1,605 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path as AxumPath, Query, State},
+    http::StatusCode,
+    response::Response,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::markdown_render::{escape_html, highlight_css, render_markdown_html};
+use crate::{ApiResult, AppError};
+
+/// Bumped when the JSON bundle layout changes; the importer checks it.
+pub(crate) const SESSION_EXPORT_VERSION: u64 = 1;
+pub(crate) const SESSION_EXPORT_KIND: &str = "opencode-studio.session-export";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SessionExportQuery {
+    pub format: Option<String>,
+    /// Render as a background job; the file is served as the job artifact.
+    pub background: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Markdown,
+    Json,
+    Html,
+}
+
+impl ExportFormat {
+    fn parse(raw: Option<&str>) -> Option<Self> {
+        match raw.map(str::trim).unwrap_or("markdown") {
+            "" | "markdown" | "md" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
+            "html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Json => "application/json",
+            Self::Html => "text/html; charset=utf-8",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Json => "json",
+            Self::Html => "html",
+        }
+    }
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+fn format_timestamp_ms(value: Option<&Value>) -> Option<String> {
+    let ms = value?.as_f64()?;
+    if !ms.is_finite() || ms <= 0.0 {
+        return None;
+    }
+    let nanos = (ms as i128).checked_mul(1_000_000)?;
+    time::OffsetDateTime::from_unix_timestamp_nanos(nanos)
+        .ok()?
+        .format(&time::format_description::well_known::Rfc3339)
+        .ok()
+}
+
+fn session_title(info: &Value, session_id: &str) -> String {
+    str_field(info, "title")
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("Session {session_id}"))
+}
+
+fn export_filename(session_id: &str, format: ExportFormat) -> String {
+    let safe: String = session_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect();
+    let safe = if safe.is_empty() { "session" } else { &safe };
+    format!("{safe}.{}", format.extension())
+}
+
+/// Session info from the index, falling back to OpenCode for sessions the
+/// index has not seen. Without a directory to route by, every running
+/// instance is asked in turn.
+pub(crate) async fn load_session_info(state: &crate::AppState, session_id: &str) -> Option<Value> {
+    if let Some(summary) = state.directory_session_index.summary(session_id) {
+        return Some(summary.raw);
+    }
+    let path = format!("/session/{}", urlencoding::encode(session_id));
+    for upstream in state.opencode.running_instances() {
+        let oc = upstream.status().await;
+        if oc.restarting || !oc.ready {
+            continue;
+        }
+        let Some(bridge) = upstream.bridge().await else {
+            continue;
+        };
+        let Ok(target) = bridge.build_url(&path, None) else {
+            continue;
+        };
+        let Ok(resp) = bridge.client.get(target).send().await else {
+            continue;
+        };
+        if resp.status().is_success()
+            && let Ok(info) = resp.json::<Value>().await
+        {
+            return Some(info);
+        }
+    }
+    None
+}
+
+// ---- Markdown ----
+
+/// A backtick fence longer than any run inside `text`.
+fn code_fence(text: &str) -> String {
+    let mut longest = 0usize;
+    let mut run = 0usize;
+    for c in text.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn push_code_block(out: &mut String, lang: &str, text: &str) {
+    let fence = code_fence(text);
+    out.push_str(&format!("{fence}{lang}\n{}", text.trim_end_matches('\n')));
+    out.push_str(&format!("\n{fence}\n\n"));
+}
+
+fn tool_input_text(state: &Value) -> Option<String> {
+    let input = state.get("input")?;
+    if input.as_object().is_some_and(|o| o.is_empty()) {
+        return None;
+    }
+    serde_json::to_string_pretty(input).ok()
+}
+
+fn tool_diff_text(state: &Value) -> Option<&str> {
+    state
+        .get("metadata")
+        .and_then(|m| m.get("diff"))
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.trim().is_empty())
+}
+
+fn render_tool_part_markdown(out: &mut String, part: &Value) {
+    let tool = str_field(part, "tool").unwrap_or("tool");
+    let empty = Value::Null;
+    let state = part.get("state").unwrap_or(&empty);
+    let status = str_field(state, "status").unwrap_or("unknown");
+
+    out.push_str(&format!("#### Tool: `{tool}` ({status})"));
+    if let Some(title) = str_field(state, "title") {
+        out.push_str(&format!(" — {title}"));
+    }
+    out.push_str("\n\n");
+    let time = state.get("time").unwrap_or(&empty);
+    if let Some(start) = format_timestamp_ms(time.get("start")) {
+        match format_timestamp_ms(time.get("end")) {
+            Some(end) => out.push_str(&format!("_{start} → {end}_\n\n")),
+            None => out.push_str(&format!("_{start}_\n\n")),
+        }
+    }
+    if let Some(input) = tool_input_text(state) {
+        out.push_str("Input:\n\n");
+        push_code_block(out, "json", &input);
+    }
+    if let Some(diff) = tool_diff_text(state) {
+        out.push_str("Diff:\n\n");
+        push_code_block(out, "diff", diff);
+    }
+    if let Some(output) = state.get("output").and_then(|v| v.as_str())
+        && !output.trim().is_empty()
+    {
+        out.push_str("Output:\n\n");
+        push_code_block(out, "", output);
+    }
+    if let Some(error) = str_field(state, "error") {
+        out.push_str("Error:\n\n");
+        push_code_block(out, "", error);
+    }
+}
+
+fn render_part_markdown(out: &mut String, part: &Value) {
+    match str_field(part, "type").unwrap_or("") {
+        "text" => {
+            if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
+                out.push_str(text.trim_end());
+                out.push_str("\n\n");
+            }
+        }
+        "reasoning" => {
+            if let Some(text) = str_field(part, "text") {
+                out.push_str("> **Thinking**\n>\n");
+                for line in text.lines() {
+                    out.push_str(&format!("> {line}\n"));
+                }
+                out.push('\n');
+            }
+        }
+        "tool" => render_tool_part_markdown(out, part),
+        "file" => {
+            let name = str_field(part, "filename")
+                .or_else(|| str_field(part, "url"))
+                .unwrap_or("file");
+            let mime = str_field(part, "mime").unwrap_or("unknown");
+            out.push_str(&format!("_Attachment: {name} ({mime})_\n\n"));
+        }
+        "patch" => {
+            let files: Vec<&str> = part
+                .get("files")
+                .and_then(|v| v.as_array())
+                .map(|list| list.iter().filter_map(|f| f.as_str()).collect())
+                .unwrap_or_default();
+            if !files.is_empty() {
+                out.push_str("_Files changed:_\n\n");
+                for file in files {
+                    out.push_str(&format!("- `{file}`\n"));
+                }
+                out.push('\n');
+            }
+        }
+        _ => {}
+    }
+}
+
+fn message_heading(info: &Value) -> String {
+    let role = match str_field(info, "role").unwrap_or("message") {
+        "user" => "User".to_string(),
+        "assistant" => "Assistant".to_string(),
+        other => other.to_string(),
+    };
+    let model = str_field(info, "modelID").map(|m| format!(" · {m}"));
+    let time = info
+        .get("time")
+        .and_then(|t| format_timestamp_ms(t.get("created")))
+        .map(|t| format!(" · {t}"));
+    format!(
+        "{role}{}{}",
+        model.unwrap_or_default(),
+        time.unwrap_or_default()
+    )
+}
+
+fn render_markdown(session_id: &str, info: &Value, messages: &[Value]) -> String {
+    let mut out = format!("# {}\n\n", session_title(info, session_id));
+    out.push_str(&format!("- Session: `{session_id}`\n"));
+    if let Some(dir) = str_field(info, "directory") {
+        out.push_str(&format!("- Directory: `{dir}`\n"));
+    }
+    let time = info.get("time");
+    if let Some(created) = time.and_then(|t| format_timestamp_ms(t.get("created"))) {
+        out.push_str(&format!("- Created: {created}\n"));
+    }
+    if let Some(updated) = time.and_then(|t| format_timestamp_ms(t.get("updated"))) {
+        out.push_str(&format!("- Updated: {updated}\n"));
+    }
+    out.push('\n');
+
+    for message in messages {
+        let empty = Value::Null;
+        let info = message.get("info").unwrap_or(&empty);
+        out.push_str(&format!("---\n\n### {}\n\n", message_heading(info)));
+        if let Some(error) = info
+            .get("error")
+            .and_then(|e| e.get("data"))
+            .and_then(|d| str_field(d, "message"))
+        {
+            out.push_str(&format!("> **Error:** {error}\n\n"));
+        }
+        for part in message
+            .get("parts")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+        {
+            render_part_markdown(&mut out, part);
+        }
+    }
+    out
+}
+
+// ---- HTML ----
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:56rem;margin:2rem auto;padding:0 1rem;line-height:1.5}\
+.message{border-top:1px solid #ddd;padding:1rem 0}\
+.message h3{font-size:1rem;margin:0 0 .5rem}\
+.reasoning{color:#555;border-left:3px solid #ccc;padding-left:.75rem}\
+pre{background:#f6f8fa;padding:.75rem;overflow-x:auto;white-space:pre-wrap}\
+details{margin:.5rem 0}\
+.meta{color:#666;font-size:.875rem}";
+
+fn push_pre(out: &mut String, class: &str, text: &str) {
+    out.push_str(&format!(
+        "<pre class=\"{class}\">{}</pre>\n",
+        escape_html(text.trim_end_matches('\n'))
+    ));
+}
+
+fn render_part_html(out: &mut String, part: &Value) {
+    match str_field(part, "type").unwrap_or("") {
+        "text" => {
+            if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
+                out.push_str(&format!(
+                    "<div class=\"text\">{}</div>\n",
+                    render_markdown_html(text)
+                ));
+            }
+        }
+        "reasoning" => {
+            if let Some(text) = str_field(part, "text") {
+                out.push_str(&format!(
+                    "<div class=\"reasoning\">{}</div>\n",
+                    render_markdown_html(text)
+                ));
+            }
+        }
+        "tool" => {
+            let tool = str_field(part, "tool").unwrap_or("tool");
+            let empty = Value::Null;
+            let state = part.get("state").unwrap_or(&empty);
+            let status = str_field(state, "status").unwrap_or("unknown");
+            out.push_str(&format!(
+                "<details class=\"tool\"><summary>Tool: <code>{}</code> ({})",
+                escape_html(tool),
+                escape_html(status)
+            ));
+            if let Some(title) = str_field(state, "title") {
+                out.push_str(&format!(" — {}", escape_html(title)));
+            }
+            out.push_str("</summary>\n");
+            if let Some(input) = tool_input_text(state) {
+                push_pre(out, "input", &input);
+            }
+            if let Some(diff) = tool_diff_text(state) {
+                push_pre(out, "diff", diff);
+            }
+            if let Some(output) = state.get("output").and_then(|v| v.as_str())
+                && !output.trim().is_empty()
+            {
+                push_pre(out, "output", output);
+            }
+            if let Some(error) = str_field(state, "error") {
+                push_pre(out, "error", error);
+            }
+            out.push_str("</details>\n");
+        }
+        "file" => {
+            let name = str_field(part, "filename")
+                .or_else(|| str_field(part, "url"))
+                .unwrap_or("file");
+            out.push_str(&format!(
+                "<p class=\"meta\">Attachment: {}</p>\n",
+                escape_html(name)
+            ));
+        }
+        "patch" => {
+            let files: Vec<&str> = part
+                .get("files")
+                .and_then(|v| v.as_array())
+                .map(|list| list.iter().filter_map(|f| f.as_str()).collect())
+                .unwrap_or_default();
+            if !files.is_empty() {
+                out.push_str("<ul class=\"meta\">");
+                for file in files {
+                    out.push_str(&format!("<li><code>{}</code></li>", escape_html(file)));
+                }
+                out.push_str("</ul>\n");
+            }
+        }
+        _ => {}
+    }
+}
+
+fn render_html(session_id: &str, info: &Value, messages: &[Value]) -> String {
+    let title = escape_html(&session_title(info, session_id));
+    let mut out = format!(
+        "<!doctype html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title><style>{HTML_STYLE}{}</style></head><body>\n<h1>{title}</h1>\n",
+        highlight_css()
+    );
+    out.push_str(&format!(
+        "<p class=\"meta\">Session <code>{}</code>",
+        escape_html(session_id)
+    ));
+    if let Some(dir) = str_field(info, "directory") {
+        out.push_str(&format!(" · <code>{}</code>", escape_html(dir)));
+    }
+    out.push_str("</p>\n");
+
+    for message in messages {
+        let empty = Value::Null;
+        let info = message.get("info").unwrap_or(&empty);
+        let role = str_field(info, "role").unwrap_or("message");
+        out.push_str(&format!(
+            "<section class=\"message {}\"><h3>{}</h3>\n",
+            escape_html(role),
+            escape_html(&message_heading(info))
+        ));
+        for part in message
+            .get("parts")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+        {
+            render_part_html(&mut out, part);
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body></html>\n");
+    out
+}
+
+// ---- JSON ----
+
+fn render_json(session_id: &str, info: &Value, messages: Vec<Value>) -> ApiResult<String> {
+    let exported_at = time::OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap_or_default();
+    serde_json::to_string_pretty(&json!({
+        "kind": SESSION_EXPORT_KIND,
+        "version": SESSION_EXPORT_VERSION,
+        "exportedAt": exported_at,
+        "sessionID": session_id,
+        "session": info,
+        "messages": messages,
+    }))
+    .map_err(|err| AppError::internal(err.to_string()))
+}
+
+async fn render_export(
+    state: &crate::AppState,
+    sid: &str,
+    format: ExportFormat,
+) -> ApiResult<String> {
+    let messages = crate::opencode_session::load_session_messages_unfiltered(sid).await;
+    let info = load_session_info(state, sid).await;
+    if messages.is_empty() && info.is_none() {
+        return Err(AppError::not_found("Session not found"));
+    }
+    let info = info.unwrap_or_else(|| json!({ "id": sid }));
+
+    // The JSON bundle is the `POST /session/import` format, so it keeps the
+    // stored records verbatim; the readable formats follow the chat filter.
+    let body = if format == ExportFormat::Json {
+        render_json(sid, &info, messages)?
+    } else {
+        let mut payload = Value::Array(messages);
+        {
+            let settings = state.settings.read().await;
+            let filter = crate::opencode_proxy::activity_filter_from_settings(&settings);
+            let detail = crate::opencode_proxy::ActivityDetailPolicy {
+                enabled: false,
+                expanded: Default::default(),
+                expanded_tools: Default::default(),
+                table_preview_rows: None,
+                tool_output_retention: None,
+            };
+            crate::opencode_proxy::filter_message_payload(&mut payload, &filter, &detail);
+        }
+        let messages = match payload {
+            Value::Array(list) => list,
+            _ => Vec::new(),
+        };
+        match format {
+            ExportFormat::Html => render_html(sid, &info, &messages),
+            _ => render_markdown(sid, &info, &messages),
+        }
+    };
+    Ok(body)
+}
+
+/// Download a session as Markdown, JSON or HTML. Markdown and HTML go through
+/// the same activity filter as the chat view, with every detail expanded.
+/// With `background=true` the export is rendered by a job instead.
+pub(crate) async fn session_export_get(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(session_id): AxumPath<String>,
+    Query(q): Query<SessionExportQuery>,
+) -> ApiResult<Response> {
+    let sid = session_id.trim().to_string();
+    if sid.is_empty() {
+        return Err(AppError::bad_request("session id is required"));
+    }
+    let format = ExportFormat::parse(q.format.as_deref())
+        .ok_or_else(|| AppError::bad_request("format must be markdown, json or html"))?;
+
+    if q.background.unwrap_or(false) {
+        let title = format!("Export session {sid}");
+        let job = crate::jobs::submit("session.export", title, move |ctx| async move {
+            ctx.progress(None, "Rendering");
+            let body = render_export(state.as_ref(), &sid, format)
+                .await
+                .map_err(|err| err.to_string())?;
+            let bytes = body.len();
+            ctx.set_artifact(crate::jobs::JobArtifact {
+                file_name: export_filename(&sid, format),
+                content_type: format.content_type(),
+                body: body.into(),
+            });
+            Ok(json!({ "sessionID": sid, "bytes": bytes }))
+        });
+        return Ok(crate::jobs::accepted(job));
+    }
+
+    let body = render_export(state.as_ref(), &sid, format).await?;
+    let sid = sid.as_str();
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("cache-control", "no-store")
+        .header("content-type", format.content_type())
+        .header(
+            "content-disposition",
+            format!("attachment; filename=\"{}\"", export_filename(sid, format)),
+        )
+        .body(Body::from(body))
+        .unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_messages() -> Vec<Value> {
+        vec![
+            json!({
+                "info": {"id": "msg_1", "role": "user", "time": {"created": 1_700_000_000_000i64}},
+                "parts": [{"id": "p1", "type": "text", "text": "Fix the `<bug>`"}]
+            }),
+            json!({
+                "info": {"id": "msg_2", "role": "assistant", "modelID": "m1"},
+                "parts": [
+                    {"id": "p2", "type": "text", "text": "Done:\n```rust\nfn a() {}\n```"},
+                    {
+                        "id": "p3",
+                        "type": "tool",
+                        "tool": "apply_patch",
+                        "state": {
+                            "status": "completed",
+                            "input": {"path": "src/a.rs"},
+                            "metadata": {"diff": "-old\n+new\n"},
+                            "output": "ok",
+                            "time": {"start": 1_700_000_001_000i64, "end": 1_700_000_002_000i64}
+                        }
+                    }
+                ]
+            }),
+        ]
+    }
+
+    #[test]
+    fn render_markdown_includes_tool_calls_diffs_and_timestamps() {
+        let info = json!({"id": "ses_1", "title": "Bug hunt", "directory": "/repo"});
+        let md = render_markdown("ses_1", &info, &sample_messages());
+
+        assert!(md.starts_with("# Bug hunt\n"));
+        assert!(md.contains("- Directory: `/repo`"));
+        assert!(md.contains("### User · 2023-11-14T22:13:20Z"));
+        assert!(md.contains("### Assistant · m1"));
+        assert!(md.contains("#### Tool: `apply_patch` (completed)"));
+        assert!(md.contains("_2023-11-14T22:13:21Z → 2023-11-14T22:13:22Z_"));
+        assert!(md.contains("```diff\n-old\n+new\n```"));
+        // Code fences inside text stay intact.
+        assert!(md.contains("```rust\nfn a() {}\n```"));
+    }
+
+    #[test]
+    fn render_html_escapes_content() {
+        let info = json!({"id": "ses_1"});
+        let html = render_html("ses_1", &info, &sample_messages());
+
+        assert!(html.contains("<title>Session ses_1</title>"));
+        assert!(html.contains("Fix the <code>&lt;bug&gt;</code>"));
+        assert!(html.contains("<code class=\"language-rust\">"));
+        assert!(html.contains("<pre class=\"diff\">-old\n+new</pre>"));
+        assert!(!html.contains("<bug>"));
+    }
+
+    #[test]
+    fn export_format_and_filename() {
+        assert_eq!(ExportFormat::parse(None), Some(ExportFormat::Markdown));
+        assert_eq!(ExportFormat::parse(Some("html")), Some(ExportFormat::Html));
+        assert_eq!(ExportFormat::parse(Some("pdf")), None);
+        assert_eq!(export_filename("ses/../1", ExportFormat::Json), "ses1.json");
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, Query, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::session_export::{SESSION_EXPORT_KIND, SESSION_EXPORT_VERSION};
+use crate::{ApiResult, AppError};
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionForkQuery {
+    /// Last message to keep (inclusive); omitted forks the whole session.
+    pub at_message_id: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionForkBody {
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionForkResponse {
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "forkedFromID")]
+    pub forked_from_id: String,
+    pub title: String,
+    pub messages: usize,
+    pub parts: usize,
+    /// `sqlite` or `json`.
+    pub storage: &'static str,
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// An id in OpenCode's format: prefix, 12 hex digits of `millis * 0x1000 +
+/// counter` (bit-inverted for descending ids), then random characters.
+/// Messages and parts are ordered by id, so forked records must sort in the
+/// same order as the originals.
+fn opencode_id(prefix: &str, millis: i64, counter: u64, descending: bool) -> String {
+    let mut value = (millis.max(0) as u64)
+        .wrapping_mul(0x1000)
+        .wrapping_add(counter);
+    if descending {
+        value = !value;
+    }
+    let random = uuid::Uuid::new_v4().simple().to_string();
+    format!(
+        "{prefix}_{:012x}{}",
+        value & 0xffff_ffff_ffff,
+        &random[..14]
+    )
+}
+
+fn message_created(entry: &Value) -> f64 {
+    entry
+        .pointer("/info/time/created")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(0.0)
+}
+
+fn message_id(entry: &Value) -> &str {
+    entry
+        .pointer("/info/id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+}
+
+/// Build an import bundle holding a copy of `messages` up to and including
+/// `at_message_id`, with fresh session, message and part ids.
+fn fork_bundle(
+    info: &Value,
+    mut messages: Vec<Value>,
+    at_message_id: Option<&str>,
+    title: &str,
+    now: i64,
+) -> ApiResult<(String, Value, usize, usize)> {
+    messages.sort_by(|a, b| {
+        message_created(a)
+            .partial_cmp(&message_created(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| message_id(a).cmp(message_id(b)))
+    });
+    if let Some(at) = at_message_id {
+        let Some(pos) = messages.iter().position(|m| message_id(m) == at) else {
+            return Err(AppError::not_found("Message not found in session"));
+        };
+        messages.truncate(pos + 1);
+    }
+
+    let session_id = opencode_id("ses", now, 0, true);
+    let mut counter = 0u64;
+    let mut next_id = |prefix: &str| {
+        counter += 1;
+        opencode_id(prefix, now, counter, false)
+    };
+
+    let id_map: HashMap<String, String> = messages
+        .iter()
+        .map(|m| (message_id(m).to_string(), next_id("msg")))
+        .collect();
+
+    let mut part_count = 0;
+    let mut forked_messages = Vec::with_capacity(messages.len());
+    for entry in messages {
+        let Value::Object(mut entry) = entry else {
+            continue;
+        };
+        let Some(Value::Object(mut msg)) = entry.remove("info") else {
+            continue;
+        };
+        let old_id = msg
+            .get("id")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let Some(new_id) = id_map.get(&old_id) else {
+            continue;
+        };
+        msg.insert("id".to_string(), json!(new_id));
+        // Assistant replies point at the user message they answer.
+        if let Some(parent) = msg.get("parentID").and_then(|v| v.as_str())
+            && let Some(mapped) = id_map.get(parent)
+        {
+            msg.insert("parentID".to_string(), json!(mapped));
+        }
+
+        let parts: Vec<Value> = match entry.remove("parts") {
+            Some(Value::Array(list)) => list
+                .into_iter()
+                .filter_map(|part| match part {
+                    Value::Object(mut part) => {
+                        part.insert("id".to_string(), json!(next_id("prt")));
+                        Some(Value::Object(part))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        part_count += parts.len();
+        forked_messages.push(json!({ "info": msg, "parts": parts }));
+    }
+
+    let mut session = info.as_object().cloned().unwrap_or_default();
+    session.insert("id".to_string(), json!(session_id));
+    session.insert("title".to_string(), json!(title));
+    // Revert markers and share links belong to the original conversation.
+    session.remove("revert");
+    session.remove("share");
+    session.remove("slug");
+    session.insert(
+        "time".to_string(),
+        json!({ "created": now, "updated": now }),
+    );
+
+    let message_count = forked_messages.len();
+    let bundle = json!({
+        "kind": SESSION_EXPORT_KIND,
+        "version": SESSION_EXPORT_VERSION,
+        "session": session,
+        "messages": forked_messages,
+    });
+    Ok((session_id, bundle, message_count, part_count))
+}
+
+fn fork_title(info: &Value, session_id: &str) -> String {
+    let base = info
+        .get("title")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .unwrap_or(session_id);
+    format!("{base} (fork)")
+}
+
+/// Copy a session, optionally cut after one message, into a new root
+/// session so an alternate continuation can be explored.
+pub(crate) async fn session_fork_post(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(session_id): AxumPath<String>,
+    Query(q): Query<SessionForkQuery>,
+    body: Option<Json<SessionForkBody>>,
+) -> ApiResult<Json<SessionForkResponse>> {
+    let sid = session_id.trim();
+    if sid.is_empty() {
+        return Err(AppError::bad_request("session id is required"));
+    }
+    let body = body.map(|Json(b)| b).unwrap_or_default();
+
+    let messages = crate::opencode_session::load_session_messages_unfiltered(sid).await;
+    let Some(info) = crate::session_export::load_session_info(state.as_ref(), sid).await else {
+        return Err(AppError::not_found("Session not found"));
+    };
+    let title = body
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| fork_title(&info, sid));
+    let at = q
+        .at_message_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|m| !m.is_empty());
+
+    let (new_id, bundle, messages, parts) = fork_bundle(&info, messages, at, &title, now_millis())?;
+    let plan = crate::session_import::plan_import(bundle, None)?;
+    let storage = crate::session_import::store_plan(state.as_ref(), &plan).await?;
+
+    Ok(Json(SessionForkResponse {
+        session_id: new_id,
+        forked_from_id: sid.to_string(),
+        title,
+        messages,
+        parts,
+        storage,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fork_bundle_truncates_and_remaps_ids_in_order() {
+        let info = json!({ "id": "ses_old", "title": "Original", "directory": "/repo", "revert": {"messageID": "msg_b"} });
+        let messages = vec![
+            json!({
+                "info": {"id": "msg_b", "role": "assistant", "parentID": "msg_a", "time": {"created": 2}},
+                "parts": [{"id": "prt_b", "type": "text", "text": "answer"}]
+            }),
+            json!({
+                "info": {"id": "msg_a", "role": "user", "time": {"created": 1}},
+                "parts": [{"id": "prt_a", "type": "text", "text": "question"}]
+            }),
+            json!({
+                "info": {"id": "msg_c", "role": "user", "time": {"created": 3}},
+                "parts": []
+            }),
+        ];
+
+        let (sid, bundle, count, parts) =
+            fork_bundle(&info, messages, Some("msg_b"), "Copy", 1_700_000_000_000).unwrap();
+        assert_eq!((count, parts), (2, 2));
+        assert!(sid.starts_with("ses_"));
+        assert_eq!(bundle["session"]["title"], json!("Copy"));
+        assert!(bundle["session"].get("revert").is_none());
+
+        let user = &bundle["messages"][0]["info"];
+        let assistant = &bundle["messages"][1]["info"];
+        assert_ne!(user["id"], json!("msg_a"));
+        assert_eq!(assistant["parentID"], user["id"]);
+        assert!(user["id"].as_str() < assistant["id"].as_str());
+
+        let plan = crate::session_import::plan_import(bundle, None);
+        assert!(plan.is_ok());
+
+        let missing = fork_bundle(&info, Vec::new(), Some("msg_x"), "Copy", 1);
+        assert!(missing.is_err());
+    }
+}
@@ -0,0 +1,498 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Query, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{Connection, SqliteConnection};
+
+use crate::session_export::{SESSION_EXPORT_KIND, SESSION_EXPORT_VERSION};
+use crate::{ApiResult, AppError};
+
+pub(crate) const MAX_IMPORT_BYTES: usize = 256 * 1024 * 1024;
+const SQLITE_BUSY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Keys the export/list endpoints stamp onto records; storage keeps ids in
+/// their own columns or file names.
+const MESSAGE_TRANSPORT_KEYS: &[&str] = &["id", "sessionID", "sessionId"];
+const PART_TRANSPORT_KEYS: &[&str] = &[
+    "id",
+    "sessionID",
+    "sessionId",
+    "messageID",
+    "messageId",
+    "partID",
+    "partId",
+];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SessionImportQuery {
+    /// Re-home the session, e.g. when the project lives at a different path
+    /// on this machine.
+    pub directory: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionImportResponse {
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    pub directory: String,
+    pub messages: usize,
+    pub parts: usize,
+    /// `sqlite` or `json`.
+    pub storage: &'static str,
+}
+
+struct ImportMessage {
+    id: String,
+    created: i64,
+    updated: i64,
+    info: Map<String, Value>,
+    parts: Vec<(String, Map<String, Value>)>,
+}
+
+pub(crate) struct ImportPlan {
+    session_id: String,
+    directory: String,
+    session: Map<String, Value>,
+    messages: Vec<ImportMessage>,
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn required_id(value: &Map<String, Value>, what: &str) -> ApiResult<String> {
+    let id = value
+        .get("id")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .unwrap_or("");
+    // Ids become file names in JSON storage.
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(AppError::bad_request(format!("{what} has an invalid id")));
+    }
+    Ok(id.to_string())
+}
+
+fn time_field(value: &Map<String, Value>, key: &str) -> Option<i64> {
+    value
+        .get("time")
+        .and_then(|t| t.get(key))
+        .and_then(|v| v.as_f64())
+        .filter(|v| v.is_finite() && *v > 0.0)
+        .map(|v| v as i64)
+}
+
+fn strip_keys(mut value: Map<String, Value>, keys: &[&str]) -> Map<String, Value> {
+    for key in keys {
+        value.remove(*key);
+    }
+    value
+}
+
+/// Validate the bundle and normalize it into storage records.
+pub(crate) fn plan_import(
+    bundle: Value,
+    directory_override: Option<&str>,
+) -> ApiResult<ImportPlan> {
+    let Value::Object(mut bundle) = bundle else {
+        return Err(AppError::bad_request("bundle must be a JSON object"));
+    };
+    if bundle.get("kind").and_then(|v| v.as_str()) != Some(SESSION_EXPORT_KIND) {
+        return Err(AppError::bad_request(
+            "not an OpenCode Studio session export",
+        ));
+    }
+    let version = bundle.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+    if version == 0 || version > SESSION_EXPORT_VERSION {
+        return Err(AppError::bad_request(format!(
+            "unsupported export version {version}"
+        )));
+    }
+
+    let Some(Value::Object(mut session)) = bundle.remove("session") else {
+        return Err(AppError::bad_request("bundle is missing session"));
+    };
+    let session_id = required_id(&session, "session")?;
+    let directory = directory_override
+        .map(crate::path_utils::normalize_directory_path)
+        .or_else(|| {
+            session
+                .get("directory")
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        })
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty())
+        .ok_or_else(|| AppError::bad_request("directory is required"))?;
+
+    let now = now_millis();
+    let session_created = time_field(&session, "created").unwrap_or(now);
+    let session_updated = time_field(&session, "updated").unwrap_or(session_created);
+    session.insert("directory".to_string(), Value::String(directory.clone()));
+    // The parent may not exist here; imported sessions become roots.
+    session.remove("parentID");
+    session.remove("share");
+    session.insert(
+        "time".to_string(),
+        serde_json::json!({ "created": session_created, "updated": session_updated }),
+    );
+
+    let raw_messages = match bundle.remove("messages") {
+        Some(Value::Array(list)) => list,
+        _ => return Err(AppError::bad_request("bundle is missing messages")),
+    };
+    let mut messages = Vec::with_capacity(raw_messages.len());
+    for entry in raw_messages {
+        let Value::Object(mut entry) = entry else {
+            return Err(AppError::bad_request("message entry must be an object"));
+        };
+        let Some(Value::Object(info)) = entry.remove("info") else {
+            return Err(AppError::bad_request("message entry is missing info"));
+        };
+        let id = required_id(&info, "message")?;
+        let created = time_field(&info, "created").unwrap_or(session_created);
+        let updated = time_field(&info, "completed").unwrap_or(created);
+
+        let mut parts = Vec::new();
+        if let Some(Value::Array(list)) = entry.remove("parts") {
+            for part in list {
+                let Value::Object(part) = part else {
+                    return Err(AppError::bad_request("part must be an object"));
+                };
+                let part_id = required_id(&part, "part")?;
+                parts.push((part_id, strip_keys(part, PART_TRANSPORT_KEYS)));
+            }
+        }
+
+        messages.push(ImportMessage {
+            id,
+            created,
+            updated,
+            info: strip_keys(info, MESSAGE_TRANSPORT_KEYS),
+            parts,
+        });
+    }
+
+    Ok(ImportPlan {
+        session_id,
+        directory,
+        session,
+        messages,
+    })
+}
+
+fn slug_for(session: &Map<String, Value>, session_id: &str) -> String {
+    session
+        .get("slug")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| session_id.to_string())
+}
+
+async fn import_into_sqlite(db_path: &Path, plan: &ImportPlan, project_id: &str) -> ApiResult<()> {
+    let options = SqliteConnectOptions::new()
+        .filename(db_path)
+        .create_if_missing(false)
+        .busy_timeout(SQLITE_BUSY_TIMEOUT);
+    let mut conn = SqliteConnection::connect_with(&options)
+        .await
+        .map_err(|err| AppError::internal(format!("open OpenCode database: {err}")))?;
+    let mut tx = conn
+        .begin()
+        .await
+        .map_err(|err| AppError::internal(err.to_string()))?;
+
+    let exists = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM session WHERE id = ?")
+        .bind(&plan.session_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|err| AppError::internal(err.to_string()))?;
+    if exists > 0 {
+        return Err(AppError::conflict("Session already exists"));
+    }
+
+    let title = plan
+        .session
+        .get("title")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let version = plan
+        .session
+        .get("version")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let created = time_field(&plan.session, "created").unwrap_or_else(now_millis);
+    let updated = time_field(&plan.session, "updated").unwrap_or(created);
+    sqlx::query(
+        "INSERT INTO session (id, project_id, parent_id, slug, directory, title, version, time_created, time_updated)
+         VALUES (?, ?, NULL, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&plan.session_id)
+    .bind(project_id)
+    .bind(slug_for(&plan.session, &plan.session_id))
+    .bind(&plan.directory)
+    .bind(title)
+    .bind(version)
+    .bind(created)
+    .bind(updated)
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| AppError::internal(format!("insert session: {err}")))?;
+
+    for message in &plan.messages {
+        sqlx::query(
+            "INSERT INTO message (id, session_id, time_created, time_updated, data) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&message.id)
+        .bind(&plan.session_id)
+        .bind(message.created)
+        .bind(message.updated)
+        .bind(Value::Object(message.info.clone()).to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| AppError::internal(format!("insert message {}: {err}", message.id)))?;
+
+        for (part_id, part) in &message.parts {
+            sqlx::query(
+                "INSERT INTO part (id, message_id, session_id, time_created, time_updated, data) VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(part_id)
+            .bind(&message.id)
+            .bind(&plan.session_id)
+            .bind(message.created)
+            .bind(message.updated)
+            .bind(Value::Object(part.clone()).to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| AppError::internal(format!("insert part {part_id}: {err}")))?;
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|err| AppError::internal(err.to_string()))
+}
+
+async fn write_json_file(path: &Path, value: &Value) -> ApiResult<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|err| AppError::internal(err.to_string()))?;
+    }
+    let body =
+        serde_json::to_vec_pretty(value).map_err(|err| AppError::internal(err.to_string()))?;
+    tokio::fs::write(path, body)
+        .await
+        .map_err(|err| AppError::internal(format!("write {}: {err}", path.display())))
+}
+
+async fn import_into_json_storage(plan: &ImportPlan, project_id: &str) -> ApiResult<()> {
+    let session_path = crate::persistence_paths::opencode_sessions_dir()
+        .join(project_id)
+        .join(format!("{}.json", plan.session_id));
+    if tokio::fs::metadata(&session_path).await.is_ok() {
+        return Err(AppError::conflict("Session already exists"));
+    }
+
+    let messages_dir = crate::persistence_paths::opencode_messages_dir().join(&plan.session_id);
+    let parts_root = crate::persistence_paths::opencode_message_parts_dir();
+    for message in &plan.messages {
+        let mut info = message.info.clone();
+        info.insert("id".to_string(), Value::String(message.id.clone()));
+        info.insert(
+            "sessionID".to_string(),
+            Value::String(plan.session_id.clone()),
+        );
+        write_json_file(
+            &messages_dir.join(format!("{}.json", message.id)),
+            &Value::Object(info),
+        )
+        .await?;
+
+        for (part_id, part) in &message.parts {
+            let mut part = part.clone();
+            part.insert("id".to_string(), Value::String(part_id.clone()));
+            part.insert(
+                "sessionID".to_string(),
+                Value::String(plan.session_id.clone()),
+            );
+            part.insert("messageID".to_string(), Value::String(message.id.clone()));
+            write_json_file(
+                &parts_root.join(&message.id).join(format!("{part_id}.json")),
+                &Value::Object(part),
+            )
+            .await?;
+        }
+    }
+
+    // Session record last so a partial import never shows up in listings.
+    let mut session = plan.session.clone();
+    session.insert("id".to_string(), Value::String(plan.session_id.clone()));
+    session.insert(
+        "projectID".to_string(),
+        Value::String(project_id.to_string()),
+    );
+    write_json_file(&session_path, &Value::Object(session)).await
+}
+
+/// Recreate a session from a `GET /session/{id}/export?format=json` bundle.
+/// Ids are kept, so importing a session that already exists is refused.
+pub(crate) async fn session_import_post(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<SessionImportQuery>,
+    body: Bytes,
+) -> ApiResult<Json<SessionImportResponse>> {
+    let bundle: Value = serde_json::from_slice(&body)
+        .map_err(|err| AppError::bad_request(format!("invalid JSON: {err}")))?;
+    let directory_override = q
+        .directory
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty());
+    let plan = plan_import(bundle, directory_override)?;
+    let storage = store_plan(state.as_ref(), &plan).await?;
+
+    Ok(Json(SessionImportResponse {
+        parts: plan.messages.iter().map(|m| m.parts.len()).sum(),
+        messages: plan.messages.len(),
+        session_id: plan.session_id,
+        directory: plan.directory,
+        storage,
+    }))
+}
+
+/// Write the planned records to whichever storage OpenCode uses and index
+/// the new session. Returns `sqlite` or `json`.
+pub(crate) async fn store_plan(
+    state: &crate::AppState,
+    plan: &ImportPlan,
+) -> ApiResult<&'static str> {
+    let project_id = crate::opencode_session::project_id_for_directory(&plan.directory).await;
+
+    let db_path = crate::persistence_paths::opencode_db_path();
+    let storage = if tokio::fs::metadata(&db_path).await.is_ok() {
+        import_into_sqlite(&db_path, plan, &project_id).await?;
+        "sqlite"
+    } else {
+        import_into_json_storage(plan, &project_id).await?;
+        "json"
+    };
+
+    let mut summary = plan.session.clone();
+    summary.insert("id".to_string(), Value::String(plan.session_id.clone()));
+    state
+        .directory_session_index
+        .upsert_summary_from_value(&Value::Object(summary));
+    Ok(storage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn bundle() -> Value {
+        json!({
+            "kind": SESSION_EXPORT_KIND,
+            "version": SESSION_EXPORT_VERSION,
+            "session": {
+                "id": "ses_1",
+                "title": "Imported",
+                "directory": "/old/repo",
+                "parentID": "ses_parent",
+                "time": {"created": 10, "updated": 20}
+            },
+            "messages": [
+                {
+                    "info": {"id": "msg_1", "sessionID": "ses_1", "role": "user", "time": {"created": 11}},
+                    "parts": [
+                        {"id": "prt_1", "sessionID": "ses_1", "messageID": "msg_1", "partId": "prt_1", "type": "text", "text": "hi"}
+                    ]
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn plan_import_rehomes_and_strips_transport_ids() {
+        let plan = plan_import(bundle(), Some("/new/repo")).expect("plan");
+        assert_eq!(plan.session_id, "ses_1");
+        assert_eq!(plan.directory, "/new/repo");
+        assert!(plan.session.get("parentID").is_none());
+        assert_eq!(plan.messages.len(), 1);
+        let message = &plan.messages[0];
+        assert_eq!(message.created, 11);
+        assert!(message.info.get("sessionID").is_none());
+        let (part_id, part) = &message.parts[0];
+        assert_eq!(part_id, "prt_1");
+        assert_eq!(part.get("text"), Some(&json!("hi")));
+        assert!(part.get("messageID").is_none() && part.get("partId").is_none());
+    }
+
+    #[test]
+    fn plan_import_rejects_foreign_or_unsafe_bundles() {
+        let mut foreign = bundle();
+        foreign["kind"] = json!("something-else");
+        assert!(plan_import(foreign, None).is_err());
+
+        let mut newer = bundle();
+        newer["version"] = json!(SESSION_EXPORT_VERSION + 1);
+        assert!(plan_import(newer, None).is_err());
+
+        let mut traversal = bundle();
+        traversal["messages"][0]["info"]["id"] = json!("../../etc");
+        assert!(plan_import(traversal, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn import_into_sqlite_writes_rows_and_refuses_duplicates() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let db_path = dir.path().join("opencode.sqlite");
+        let options = SqliteConnectOptions::new()
+            .filename(&db_path)
+            .create_if_missing(true);
+        let mut conn = SqliteConnection::connect_with(&options).await.unwrap();
+        for ddl in [
+            "CREATE TABLE session (id TEXT PRIMARY KEY, project_id TEXT NOT NULL, parent_id TEXT, slug TEXT NOT NULL, directory TEXT NOT NULL, title TEXT NOT NULL, version TEXT NOT NULL, time_created INTEGER NOT NULL, time_updated INTEGER NOT NULL)",
+            "CREATE TABLE message (id TEXT PRIMARY KEY, session_id TEXT NOT NULL, time_created INTEGER NOT NULL, time_updated INTEGER NOT NULL, data TEXT NOT NULL)",
+            "CREATE TABLE part (id TEXT PRIMARY KEY, message_id TEXT NOT NULL, session_id TEXT NOT NULL, time_created INTEGER NOT NULL, time_updated INTEGER NOT NULL, data TEXT NOT NULL)",
+        ] {
+            sqlx::query(ddl).execute(&mut conn).await.unwrap();
+        }
+
+        let plan = plan_import(bundle(), None).expect("plan");
+        import_into_sqlite(&db_path, &plan, "global")
+            .await
+            .expect("import");
+
+        let data: String = sqlx::query_scalar("SELECT data FROM part WHERE id = 'prt_1'")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        let part: Value = serde_json::from_str(&data).unwrap();
+        assert_eq!(part["text"], json!("hi"));
+        assert!(part.get("id").is_none());
+
+        let err = import_into_sqlite(&db_path, &plan, "global")
+            .await
+            .expect_err("duplicate");
+        assert!(matches!(err, AppError::Conflict { .. }));
+    }
+}
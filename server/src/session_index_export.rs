@@ -0,0 +1,322 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    body::Body,
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use base64::Engine as _;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::directory_session_index::{
+    DirectorySessionIndexManager, RemoteTrackingRecord, RuntimeDisplayState,
+};
+use crate::{ApiResult, AppError};
+
+/// Bumped whenever a field is removed or changes meaning; new fields may be
+/// added without a bump.
+const EXPORT_SCHEMA_VERSION: u32 = 1;
+const DEFAULT_PAGE_LIMIT: usize = 500;
+const MAX_PAGE_LIMIT: usize = 5000;
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionIndexExportQuery {
+    /// `json` (default) or `ndjson`.
+    pub format: Option<String>,
+    pub directory: Option<String>,
+    /// Opaque `nextCursor` from the previous page.
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExportedSession {
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    pub directory: String,
+    pub directory_id: Option<String>,
+    #[serde(rename = "parentID")]
+    pub parent_id: Option<String>,
+    pub title: String,
+    pub updated_at: f64,
+    pub display_state: RuntimeDisplayState,
+    pub status_type: String,
+    pub phase: String,
+    pub attention: Option<String>,
+    pub runtime_updated_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExportedDirectory {
+    pub directory: String,
+    pub directory_id: Option<String>,
+    pub session_count: usize,
+    pub busy_count: usize,
+    pub attention_count: usize,
+    /// Latest of session `updatedAt` and runtime transitions, in ms.
+    pub last_activity_at: f64,
+    /// Upstream ahead/behind from the last auto-fetch, if any.
+    pub remote: Option<RemoteTrackingRecord>,
+}
+
+fn collect_sessions(
+    index: &DirectorySessionIndexManager,
+    directory: Option<&str>,
+) -> Vec<ExportedSession> {
+    let mut out: Vec<ExportedSession> = index
+        .summaries_snapshot()
+        .into_iter()
+        .filter(|summary| {
+            directory.is_none_or(|dir| {
+                crate::path_utils::normalize_directory_for_match(&summary.directory_path)
+                    .is_some_and(|key| key == dir)
+            })
+        })
+        .map(|summary| {
+            let runtime = index.runtime(&summary.session_id);
+            ExportedSession {
+                directory_id: index.directory_id_for_path(&summary.directory_path),
+                display_state: runtime
+                    .as_ref()
+                    .map(|r| r.display_state)
+                    .unwrap_or(RuntimeDisplayState::Idle),
+                status_type: runtime
+                    .as_ref()
+                    .map(|r| r.status_type.clone())
+                    .unwrap_or_else(|| "idle".to_string()),
+                phase: runtime
+                    .as_ref()
+                    .map(|r| r.phase.clone())
+                    .unwrap_or_else(|| "idle".to_string()),
+                attention: runtime.as_ref().and_then(|r| r.attention.clone()),
+                runtime_updated_at: runtime.map(|r| r.updated_at),
+                session_id: summary.session_id,
+                directory: summary.directory_path,
+                parent_id: summary.parent_id,
+                title: summary.title,
+                updated_at: summary.updated_at,
+            }
+        })
+        .collect();
+    out.sort_by(|a, b| {
+        a.directory
+            .cmp(&b.directory)
+            .then_with(|| a.session_id.cmp(&b.session_id))
+    });
+    out
+}
+
+/// Per-directory rollup over every exported session (not just one page).
+fn summarize_directories(sessions: &[ExportedSession]) -> Vec<ExportedDirectory> {
+    let mut by_directory: BTreeMap<&str, ExportedDirectory> = BTreeMap::new();
+    for session in sessions {
+        let entry = by_directory
+            .entry(session.directory.as_str())
+            .or_insert_with(|| ExportedDirectory {
+                directory: session.directory.clone(),
+                directory_id: session.directory_id.clone(),
+                session_count: 0,
+                busy_count: 0,
+                attention_count: 0,
+                last_activity_at: 0.0,
+                remote: None,
+            });
+        entry.session_count += 1;
+        if matches!(
+            session.display_state,
+            RuntimeDisplayState::Running | RuntimeDisplayState::Retrying
+        ) {
+            entry.busy_count += 1;
+        }
+        if session.attention.is_some() {
+            entry.attention_count += 1;
+        }
+        let runtime_at = session.runtime_updated_at.unwrap_or(0) as f64;
+        entry.last_activity_at = entry
+            .last_activity_at
+            .max(session.updated_at)
+            .max(runtime_at);
+    }
+    by_directory.into_values().collect()
+}
+
+fn encode_cursor(session: &ExportedSession) -> String {
+    let raw = format!("{}\n{}", session.directory, session.session_id);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw)
+}
+
+fn decode_cursor(cursor: &str) -> Option<(String, String)> {
+    let raw = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(cursor.trim())
+        .ok()?;
+    let raw = String::from_utf8(raw).ok()?;
+    let (directory, session_id) = raw.split_once('\n')?;
+    Some((directory.to_string(), session_id.to_string()))
+}
+
+/// Slice one page after `cursor`; returns the page and the cursor for the next.
+fn paginate(
+    sessions: &[ExportedSession],
+    cursor: Option<&(String, String)>,
+    limit: usize,
+) -> (Vec<ExportedSession>, Option<String>) {
+    let start = match cursor {
+        Some((directory, session_id)) => sessions.partition_point(|s| {
+            (s.directory.as_str(), s.session_id.as_str()) <= (directory, session_id)
+        }),
+        None => 0,
+    };
+    let page: Vec<ExportedSession> = sessions.iter().skip(start).take(limit).cloned().collect();
+    let next = if start + page.len() < sessions.len() {
+        page.last().map(encode_cursor)
+    } else {
+        None
+    };
+    (page, next)
+}
+
+/// Stable export of Studio's per-directory session view for external
+/// dashboards. `json` returns one document; `ndjson` streams a `header`
+/// line, `directory` and `session` lines, then an `end` line carrying
+/// `nextCursor`.
+pub(crate) async fn session_index_export(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<SessionIndexExportQuery>,
+) -> ApiResult<Response> {
+    let ndjson = match q.format.as_deref().unwrap_or("json") {
+        "json" => false,
+        "ndjson" => true,
+        _ => return Err(AppError::bad_request("format must be json or ndjson")),
+    };
+    let directory = match q.directory.as_deref().map(str::trim) {
+        Some(raw) if !raw.is_empty() => Some(
+            crate::path_utils::normalize_directory_for_match(raw)
+                .ok_or_else(|| AppError::bad_request("Invalid directory"))?,
+        ),
+        _ => None,
+    };
+    let cursor = match q.cursor.as_deref().map(str::trim) {
+        Some(raw) if !raw.is_empty() => {
+            Some(decode_cursor(raw).ok_or_else(|| AppError::bad_request("Invalid cursor"))?)
+        }
+        _ => None,
+    };
+    let limit = q
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .clamp(1, MAX_PAGE_LIMIT);
+
+    crate::app::reconcile_runtime_status_from_opencode(&state).await;
+
+    let sessions = collect_sessions(&state.directory_session_index, directory.as_deref());
+    let mut directories = summarize_directories(&sessions);
+    for dir in &mut directories {
+        dir.remote = state
+            .directory_session_index
+            .remote_tracking(&dir.directory);
+    }
+    let total = sessions.len();
+    let (page, next_cursor) = paginate(&sessions, cursor.as_ref(), limit);
+    let generated_at = now_millis();
+
+    if !ndjson {
+        return Ok(Json(serde_json::json!({
+            "version": EXPORT_SCHEMA_VERSION,
+            "generatedAt": generated_at,
+            "totalSessions": total,
+            "directories": directories,
+            "sessions": page,
+            "nextCursor": next_cursor,
+        }))
+        .into_response());
+    }
+
+    let stream = async_stream::stream! {
+        let header = serde_json::json!({
+            "type": "header",
+            "version": EXPORT_SCHEMA_VERSION,
+            "generatedAt": generated_at,
+            "totalSessions": total,
+        });
+        yield Ok::<String, std::convert::Infallible>(format!("{header}\n"));
+        // Rollups are only sent with the first page.
+        if cursor.is_none() {
+            for dir in directories {
+                let mut line = serde_json::to_value(&dir).unwrap_or_default();
+                line["type"] = "directory".into();
+                yield Ok(format!("{line}\n"));
+            }
+        }
+        for session in page {
+            let mut line = serde_json::to_value(&session).unwrap_or_default();
+            line["type"] = "session".into();
+            yield Ok(format!("{line}\n"));
+        }
+        let end = serde_json::json!({ "type": "end", "nextCursor": next_cursor });
+        yield Ok(format!("{end}\n"));
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("cache-control", "no-store")
+        .header("content-type", "application/x-ndjson")
+        .body(Body::from_stream(stream))
+        .unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_pages_through_sessions_with_cursor_and_rolls_up_directories() {
+        let index = DirectorySessionIndexManager::new();
+        for (id, dir, updated) in [
+            ("ses_b", "/repo/one", 20.0),
+            ("ses_a", "/repo/one", 10.0),
+            ("ses_c", "/repo/two", 5.0),
+        ] {
+            index.upsert_summary_from_value(&serde_json::json!({
+                "id": id,
+                "directory": dir,
+                "title": id,
+                "time": { "updated": updated },
+            }));
+        }
+        index.upsert_runtime_status("ses_b", "busy");
+
+        let sessions = collect_sessions(&index, None);
+        let ids: Vec<&str> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, vec!["ses_a", "ses_b", "ses_c"]);
+
+        let dirs = summarize_directories(&sessions);
+        assert_eq!(dirs.len(), 2);
+        assert_eq!(dirs[0].session_count, 2);
+        assert_eq!(dirs[0].busy_count, 1);
+
+        let (first, next) = paginate(&sessions, None, 2);
+        assert_eq!(first.len(), 2);
+        let cursor = decode_cursor(&next.expect("more pages")).unwrap();
+        let (second, next) = paginate(&sessions, Some(&cursor), 2);
+        assert_eq!(second[0].session_id, "ses_c");
+        assert!(next.is_none());
+
+        let only_two = collect_sessions(&index, Some("/repo/two"));
+        assert_eq!(only_two.len(), 1);
+    }
+}
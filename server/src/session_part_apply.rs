@@ -0,0 +1,398 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::git::{is_safe_repo_rel_path, lock_repo, run_git, run_git_with_input};
+use crate::opencode_proxy::{SessionDiffItem, session_diff_items_from_part};
+use crate::{ApiResult, AppError};
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SessionPartApplyQuery {
+    pub directory: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPartApplyBody {
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Limit the apply to these files (paths as reported for the part).
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PartApplyStatus {
+    /// The file still matches the part's "before"; the change applies as-is.
+    Clean,
+    /// The file changed since, but a 3-way merge is conflict-free.
+    Merged,
+    AlreadyApplied,
+    Conflict,
+    /// Nothing usable to apply (no before/after or diff, or unsafe path).
+    Unsupported,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartApplyFile {
+    pub path: String,
+    pub status: PartApplyStatus,
+    /// Conflicting hunks from the 3-way merge, when known.
+    pub conflicts: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartApplyResponse {
+    pub dry_run: bool,
+    pub applied: bool,
+    pub files: Vec<PartApplyFile>,
+}
+
+/// What to do with one file once every file has been checked.
+enum PlannedWrite {
+    Content(String),
+    Delete,
+    Patch(String),
+    Nothing,
+}
+
+fn file_result(path: &str, status: PartApplyStatus) -> PartApplyFile {
+    PartApplyFile {
+        path: path.to_string(),
+        status,
+        conflicts: 0,
+        detail: None,
+    }
+}
+
+/// 3-way merge `current` with the part's `before -> after` change via
+/// `git merge-file`. Returns the merged text and the conflict count.
+async fn merge_three_way(
+    current: &str,
+    before: &str,
+    after: &str,
+) -> Result<(String, usize), String> {
+    let tmp = tempfile::TempDir::new().map_err(|e| e.to_string())?;
+    let paths = ["current", "before", "after"].map(|n| tmp.path().join(n));
+    for (path, content) in paths.iter().zip([current, before, after]) {
+        tokio::fs::write(path, content)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let [cur, base, other] = paths.map(|p| p.to_string_lossy().to_string());
+    let (code, out, err) = run_git(
+        tmp.path(),
+        &[
+            "merge-file",
+            "-p",
+            "-L",
+            "current",
+            "-L",
+            "before",
+            "-L",
+            "suggested",
+            &cur,
+            &base,
+            &other,
+        ],
+    )
+    .await?;
+    // Exit code is the number of conflicts; negative values are errors.
+    if !(0..=127).contains(&code) {
+        return Err(err.trim().to_string());
+    }
+    Ok((out, code as usize))
+}
+
+/// Rewrite a per-file diff so `git apply` resolves it relative to the repo
+/// root (`prefix` is the directory's path inside the repo, if any).
+fn rebase_patch_paths(diff: &str, file: &str, prefix: &str) -> Option<String> {
+    let hunks_at = diff
+        .find("\n@@")
+        .map(|i| i + 1)
+        .or_else(|| diff.starts_with("@@").then_some(0))?;
+    let target = format!("{prefix}{file}");
+    let mut patch = format!("--- a/{target}\n+++ b/{target}\n");
+    patch.push_str(&diff[hunks_at..]);
+    if !patch.ends_with('\n') {
+        patch.push('\n');
+    }
+    Some(patch)
+}
+
+async fn repo_prefix(dir: &Path) -> String {
+    let (code, out, _) = run_git(dir, &["rev-parse", "--show-prefix"])
+        .await
+        .unwrap_or((1, "".to_string(), "".to_string()));
+    if code == 0 {
+        out.trim().to_string()
+    } else {
+        String::new()
+    }
+}
+
+async fn git_apply_check(dir: &Path, patch: &str, reverse: bool) -> Result<(), String> {
+    let mut args = vec!["apply", "--check", "--recount", "--whitespace=nowarn"];
+    if reverse {
+        args.push("--reverse");
+    }
+    let (code, _, err) = run_git_with_input(dir, &args, patch).await?;
+    if code == 0 {
+        Ok(())
+    } else {
+        Err(err.trim().to_string())
+    }
+}
+
+async fn plan_file(
+    dir: &Path,
+    prefix: &str,
+    item: &SessionDiffItem,
+) -> (PartApplyFile, PlannedWrite) {
+    let path = item.file.as_str();
+    if !is_safe_repo_rel_path(path) {
+        let mut result = file_result(path, PartApplyStatus::Unsupported);
+        result.detail = Some("Path is outside the directory".to_string());
+        return (result, PlannedWrite::Nothing);
+    }
+    let full = dir.join(path);
+    let current = tokio::fs::read_to_string(&full).await.ok();
+
+    if !item.before.is_empty() || !item.after.is_empty() {
+        let deletes = item.after.is_empty();
+        let current_text = current.as_deref().unwrap_or("");
+        if (deletes && current.is_none()) || (!deletes && current_text == item.after) {
+            return (
+                file_result(path, PartApplyStatus::AlreadyApplied),
+                PlannedWrite::Nothing,
+            );
+        }
+        if current_text == item.before {
+            let write = if deletes {
+                PlannedWrite::Delete
+            } else {
+                PlannedWrite::Content(item.after.clone())
+            };
+            return (file_result(path, PartApplyStatus::Clean), write);
+        }
+        if deletes {
+            let mut result = file_result(path, PartApplyStatus::Conflict);
+            result.detail = Some("File changed since the suggested deletion".to_string());
+            return (result, PlannedWrite::Nothing);
+        }
+        return match merge_three_way(current_text, &item.before, &item.after).await {
+            Ok((merged, 0)) => (
+                file_result(path, PartApplyStatus::Merged),
+                PlannedWrite::Content(merged),
+            ),
+            Ok((_, conflicts)) => {
+                let mut result = file_result(path, PartApplyStatus::Conflict);
+                result.conflicts = conflicts;
+                (result, PlannedWrite::Nothing)
+            }
+            Err(err) => {
+                let mut result = file_result(path, PartApplyStatus::Unsupported);
+                result.detail = Some(err);
+                (result, PlannedWrite::Nothing)
+            }
+        };
+    }
+
+    let Some(patch) = rebase_patch_paths(&item.diff, path, prefix) else {
+        return (
+            file_result(path, PartApplyStatus::Unsupported),
+            PlannedWrite::Nothing,
+        );
+    };
+    match git_apply_check(dir, &patch, false).await {
+        Ok(()) => (
+            file_result(path, PartApplyStatus::Clean),
+            PlannedWrite::Patch(patch),
+        ),
+        Err(err) => {
+            if git_apply_check(dir, &patch, true).await.is_ok() {
+                return (
+                    file_result(path, PartApplyStatus::AlreadyApplied),
+                    PlannedWrite::Nothing,
+                );
+            }
+            let mut result = file_result(path, PartApplyStatus::Conflict);
+            result.detail = Some(err);
+            (result, PlannedWrite::Nothing)
+        }
+    }
+}
+
+async fn write_planned(dir: &Path, path: &str, write: PlannedWrite) -> Result<(), String> {
+    let full: PathBuf = dir.join(path);
+    match write {
+        PlannedWrite::Nothing => Ok(()),
+        PlannedWrite::Delete => tokio::fs::remove_file(&full)
+            .await
+            .map_err(|e| e.to_string()),
+        PlannedWrite::Content(content) => {
+            if let Some(parent) = full.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            tokio::fs::write(&full, content)
+                .await
+                .map_err(|e| e.to_string())
+        }
+        PlannedWrite::Patch(patch) => {
+            let (code, _, err) =
+                run_git_with_input(dir, &["apply", "--recount", "--whitespace=nowarn"], &patch)
+                    .await?;
+            if code == 0 {
+                Ok(())
+            } else {
+                Err(err.trim().to_string())
+            }
+        }
+    }
+}
+
+/// Apply the diff carried by one chat part (patch or edit tool) to the
+/// working tree. All files are checked first; nothing is written if any
+/// file conflicts or when `dryRun` is set.
+pub async fn session_message_part_apply_post(
+    State(_state): State<Arc<crate::AppState>>,
+    AxumPath((session_id, message_id, part_id)): AxumPath<(String, String, String)>,
+    Query(q): Query<SessionPartApplyQuery>,
+    Json(body): Json<SessionPartApplyBody>,
+) -> ApiResult<Response> {
+    let (sid, mid, pid) = (session_id.trim(), message_id.trim(), part_id.trim());
+    if sid.is_empty() || mid.is_empty() || pid.is_empty() {
+        return Err(AppError::bad_request(
+            "session_id, message_id, and part_id are required",
+        ));
+    }
+    let requested = q
+        .directory
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| AppError::bad_request("directory parameter is required"))?;
+    let dir = crate::fs::validate_directory(requested).await?;
+
+    let (_, part) =
+        match crate::opencode_session::load_session_message_part_local(sid, mid, pid).await {
+            Ok(found) => found,
+            Err(resp) => return Ok(resp),
+        };
+    let Some(part_map) = part.as_object() else {
+        return Err(AppError::not_found("Part not available"));
+    };
+    let mut items = session_diff_items_from_part(part_map, Some(&dir.to_string_lossy()));
+    if !body.files.is_empty() {
+        items.retain(|item| body.files.iter().any(|f| f.trim() == item.file));
+    }
+    if items.is_empty() {
+        return Err(AppError::bad_request("Part has no applicable diff"));
+    }
+
+    let _guard = match lock_repo(&dir, "apply-session-part").await {
+        Ok(g) => g,
+        Err(resp) => return Ok(resp),
+    };
+
+    let prefix = repo_prefix(&dir).await;
+    let mut files = Vec::with_capacity(items.len());
+    let mut writes = Vec::with_capacity(items.len());
+    for item in &items {
+        let (result, write) = plan_file(&dir, &prefix, item).await;
+        writes.push((item.file.clone(), write));
+        files.push(result);
+    }
+
+    let conflicted = files.iter().any(|f| f.status == PartApplyStatus::Conflict);
+    if body.dry_run || conflicted {
+        let status = if conflicted && !body.dry_run {
+            StatusCode::CONFLICT
+        } else {
+            StatusCode::OK
+        };
+        let response = PartApplyResponse {
+            dry_run: body.dry_run,
+            applied: false,
+            files,
+        };
+        return Ok((status, Json(response)).into_response());
+    }
+
+    for (path, write) in writes {
+        if let Err(err) = write_planned(&dir, &path, write).await {
+            return Err(AppError::internal(format!("Failed to apply {path}: {err}")));
+        }
+    }
+
+    Ok(Json(PartApplyResponse {
+        dry_run: false,
+        applied: true,
+        files,
+    })
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebase_patch_paths_drops_foreign_headers() {
+        let diff = "Index: /repo/src/a.txt\n===\n--- /repo/src/a.txt\n+++ /repo/src/a.txt\n@@ -1 +1 @@\n-old\n+new";
+        assert_eq!(
+            rebase_patch_paths(diff, "a.txt", "src/").as_deref(),
+            Some("--- a/src/a.txt\n+++ b/src/a.txt\n@@ -1 +1 @@\n-old\n+new\n")
+        );
+        assert!(rebase_patch_paths("no hunks", "a.txt", "").is_none());
+    }
+
+    #[tokio::test]
+    async fn plan_file_classifies_clean_merged_applied_and_conflicting() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let dir = tmp.path();
+        let item = |file: &str, before: &str, after: &str| {
+            let mut item = SessionDiffItem::default();
+            item.file = file.to_string();
+            item.before = before.to_string();
+            item.after = after.to_string();
+            item
+        };
+        std::fs::write(dir.join("clean.txt"), "a\nb\nc\n").unwrap();
+        std::fs::write(dir.join("merged.txt"), "a\nb\nc\nlocal\n").unwrap();
+        std::fs::write(dir.join("done.txt"), "a\nB\nc\n").unwrap();
+        std::fs::write(dir.join("conflict.txt"), "a\nX\nc\n").unwrap();
+
+        let mut statuses = Vec::new();
+        for file in ["clean.txt", "merged.txt", "done.txt", "conflict.txt"] {
+            let (result, _) = plan_file(dir, "", &item(file, "a\nb\nc\n", "a\nB\nc\n")).await;
+            statuses.push(result.status);
+        }
+        let (unsafe_path, _) = plan_file(dir, "", &item("../x", "", "y")).await;
+        assert_eq!(
+            statuses,
+            vec![
+                PartApplyStatus::Clean,
+                PartApplyStatus::Merged,
+                PartApplyStatus::AlreadyApplied,
+                PartApplyStatus::Conflict,
+            ]
+        );
+        assert_eq!(unsafe_path.status, PartApplyStatus::Unsupported);
+    }
+}
@@ -0,0 +1,549 @@
+//! Conversation summaries for long sessions.
+//!
+//! The transcript (text and tool call titles only) is summarized by the
+//! connected model in a throwaway OpenCode session, like commit message
+//! generation. Summaries are kept in `session_summaries`; incremental runs
+//! feed the previous summary plus the messages after it instead of the whole
+//! conversation.
+
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::studio_db::StudioDb;
+use crate::{ApiResult, AppError};
+
+const MAX_PART_CHARS: usize = 4000;
+const MAX_TRANSCRIPT_CHARS: usize = 120_000;
+
+const PROMPT: &str = "Summarize the conversation below between a user and a coding assistant.
+Cover the goal, decisions made, files and components touched, and anything left open.
+Use short Markdown bullet points and reply with the summary only.";
+
+const INCREMENTAL_PROMPT: &str = "Update the summary of a conversation between a user and a coding assistant.
+The current summary is given first, followed by the messages exchanged since it was written.
+Merge the new information in, covering the goal, decisions made, files and components touched, and anything left open.
+Use short Markdown bullet points and reply with the full updated summary only.";
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SessionSummary {
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    pub summary: String,
+    /// Newest message covered by the summary.
+    #[serde(rename = "lastMessageID")]
+    pub last_message_id: String,
+    /// Messages covered so far, across incremental runs.
+    pub message_count: i64,
+    pub model: Option<String>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SessionSummarizeBody {
+    /// `provider/model`; OpenCode's default model when omitted.
+    pub model: Option<String>,
+    /// Only summarize messages after the stored summary (default `true`).
+    pub incremental: Option<bool>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SessionSummarizeResponse {
+    #[serde(flatten)]
+    pub summary: SessionSummary,
+    /// Whether the previous summary was extended rather than rebuilt.
+    pub incremental: bool,
+    /// Messages sent to the model in this run; 0 when nothing was new.
+    pub summarized_messages: usize,
+    /// The oldest messages did not fit in the prompt and were left out.
+    pub truncated: bool,
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn summary_error(status: StatusCode, code: &str, error: impl Into<String>) -> Response {
+    (status, Json(json!({"error": error.into(), "code": code}))).into_response()
+}
+
+async fn load_summary(db: &StudioDb, session_id: &str) -> Option<SessionSummary> {
+    let row = sqlx::query_as::<_, (String, String, i64, Option<String>, i64)>(
+        "SELECT summary, last_message_id, message_count, model, updated_at FROM session_summaries WHERE session_id = ?",
+    )
+    .bind(session_id)
+    .fetch_optional(db.pool())
+    .await
+    .ok()??;
+    Some(SessionSummary {
+        session_id: session_id.to_string(),
+        summary: row.0,
+        last_message_id: row.1,
+        message_count: row.2,
+        model: row.3,
+        updated_at: row.4,
+    })
+}
+
+async fn store_summary(db: &StudioDb, summary: &SessionSummary) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO session_summaries (session_id, summary, last_message_id, message_count, model, updated_at) VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT(session_id) DO UPDATE SET summary = excluded.summary, last_message_id = excluded.last_message_id, message_count = excluded.message_count, model = excluded.model, updated_at = excluded.updated_at",
+    )
+    .bind(&summary.session_id)
+    .bind(&summary.summary)
+    .bind(&summary.last_message_id)
+    .bind(summary.message_count)
+    .bind(&summary.model)
+    .bind(summary.updated_at)
+    .execute(db.pool())
+    .await
+    .map(|_| ())
+    .map_err(|err| err.to_string())
+}
+
+fn message_id(entry: &Value) -> &str {
+    entry
+        .pointer("/info/id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+}
+
+fn sort_messages(messages: &mut [Value]) {
+    let created = |entry: &Value| {
+        entry
+            .pointer("/info/time/created")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0)
+    };
+    messages.sort_by(|a, b| {
+        created(a)
+            .partial_cmp(&created(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| message_id(a).cmp(message_id(b)))
+    });
+}
+
+fn clip(text: &str, max: usize) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(max) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+/// One message as transcript text: prose and tool call titles, without
+/// reasoning, tool output or parts OpenCode marks synthetic or ignored.
+fn render_message(entry: &Value) -> Option<String> {
+    let role = match entry.pointer("/info/role").and_then(|v| v.as_str())? {
+        "user" => "User",
+        "assistant" => "Assistant",
+        _ => return None,
+    };
+    let mut lines = Vec::new();
+    for part in entry
+        .get("parts")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let flag = |key: &str| part.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+        if flag("synthetic") || flag("ignored") {
+            continue;
+        }
+        match part.get("type").and_then(|v| v.as_str()).unwrap_or("") {
+            "text" => {
+                if let Some(text) = part.get("text").and_then(|v| v.as_str())
+                    && !text.trim().is_empty()
+                {
+                    lines.push(clip(text, MAX_PART_CHARS));
+                }
+            }
+            "tool" => {
+                let tool = part.get("tool").and_then(|v| v.as_str()).unwrap_or("tool");
+                match part.pointer("/state/title").and_then(|v| v.as_str()) {
+                    Some(title) if !title.trim().is_empty() => {
+                        lines.push(format!("[{tool}: {}]", title.trim()))
+                    }
+                    _ => lines.push(format!("[{tool}]")),
+                }
+            }
+            "file" => {
+                let name = part
+                    .get("filename")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("file");
+                lines.push(format!("[attachment: {name}]"));
+            }
+            _ => {}
+        }
+    }
+    (!lines.is_empty()).then(|| format!("{role}:\n{}", lines.join("\n")))
+}
+
+/// Transcript of `messages`, dropping the oldest ones when it would exceed
+/// `max_chars`. Returns the text, the messages rendered and whether any were
+/// dropped.
+fn build_transcript(messages: &[Value], max_chars: usize) -> (String, usize, bool) {
+    let rendered: Vec<String> = messages.iter().filter_map(render_message).collect();
+    let mut kept = Vec::new();
+    let mut total = 0;
+    for block in rendered.iter().rev() {
+        let len = block.chars().count() + 2;
+        if total + len > max_chars && !kept.is_empty() {
+            break;
+        }
+        total += len;
+        kept.push(block.as_str());
+    }
+    let truncated = kept.len() < rendered.len();
+    kept.reverse();
+    (kept.join("\n\n"), kept.len(), truncated)
+}
+
+fn render_prompt(previous: Option<&str>, transcript: &str, truncated: bool) -> String {
+    let omitted = if truncated {
+        "(earlier messages omitted)\n\n"
+    } else {
+        ""
+    };
+    match previous {
+        Some(previous) => format!(
+            "{INCREMENTAL_PROMPT}\n\nCurrent summary:\n{previous}\n\nNew messages:\n{omitted}{transcript}"
+        ),
+        None => format!("{PROMPT}\n\nConversation:\n{omitted}{transcript}"),
+    }
+}
+
+fn model_payload(model: &str) -> Option<Value> {
+    let (provider, model) = model.split_once('/')?;
+    let (provider, model) = (provider.trim(), model.trim());
+    if provider.is_empty() || model.is_empty() {
+        return None;
+    }
+    Some(json!({ "providerID": provider, "modelID": model }))
+}
+
+/// Run `prompt` in a throwaway OpenCode session and return the reply text.
+async fn run_prompt(
+    state: &crate::AppState,
+    directory: Option<&str>,
+    prompt: String,
+    model: Option<Value>,
+) -> Result<String, Response> {
+    let upstream = state.opencode.for_directory(directory).await;
+    let Some(bridge) = upstream.bridge().await else {
+        return Err(summary_error(
+            StatusCode::BAD_GATEWAY,
+            "opencode_unavailable",
+            "OpenCode is not running",
+        ));
+    };
+    let base = bridge.base_url.trim_end_matches('/').to_string();
+    let query = directory
+        .map(|dir| format!("?directory={}", urlencoding::encode(dir)))
+        .unwrap_or_default();
+
+    let session_id = match bridge
+        .client
+        .post(format!("{base}/session{query}"))
+        .json(&json!({ "title": "Session summary" }))
+        .send()
+        .await
+    {
+        Ok(resp) if resp.status().is_success() => resp
+            .json::<Value>()
+            .await
+            .ok()
+            .and_then(|v| v.get("id").and_then(|id| id.as_str()).map(str::to_string)),
+        _ => None,
+    };
+    let Some(session_id) = session_id else {
+        return Err(summary_error(
+            StatusCode::BAD_GATEWAY,
+            "opencode_session_failed",
+            "Failed to create an OpenCode session",
+        ));
+    };
+    let session_url = format!("{base}/session/{}", urlencoding::encode(&session_id));
+
+    let mut payload = json!({ "parts": [{ "type": "text", "text": prompt }] });
+    if let Some(model) = model {
+        payload["model"] = model;
+    }
+    let reply = bridge
+        .client
+        .post(format!("{session_url}/message{query}"))
+        .json(&payload)
+        .send()
+        .await;
+
+    // The session only exists to run this prompt.
+    let _ = bridge
+        .client
+        .delete(format!("{session_url}{query}"))
+        .send()
+        .await;
+
+    let reply = match reply {
+        Ok(resp) if resp.status().is_success() => resp.json::<Value>().await.ok(),
+        Ok(resp) => {
+            let status = resp.status();
+            let detail = resp.text().await.unwrap_or_default();
+            return Err(summary_error(
+                StatusCode::BAD_GATEWAY,
+                "opencode_prompt_failed",
+                format!("OpenCode rejected the prompt ({status}): {}", detail.trim()),
+            ));
+        }
+        Err(_) => {
+            return Err(summary_error(
+                StatusCode::BAD_GATEWAY,
+                "opencode_unavailable",
+                "OpenCode did not answer the prompt",
+            ));
+        }
+    };
+    let text = reply
+        .as_ref()
+        .and_then(|v| v.get("parts"))
+        .and_then(|v| v.as_array())
+        .map(|parts| {
+            parts
+                .iter()
+                .filter(|p| p.get("type").and_then(|t| t.as_str()) == Some("text"))
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default();
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(summary_error(
+            StatusCode::BAD_GATEWAY,
+            "empty_reply",
+            "The model returned no summary",
+        ));
+    }
+    Ok(text.to_string())
+}
+
+fn session_id_param(raw: &str) -> ApiResult<&str> {
+    let sid = raw.trim();
+    if sid.is_empty() {
+        return Err(AppError::bad_request("session id is required"));
+    }
+    Ok(sid)
+}
+
+/// The stored summary of a session.
+pub(crate) async fn session_summary_get(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(session_id): AxumPath<String>,
+) -> ApiResult<Json<SessionSummary>> {
+    let sid = session_id_param(&session_id)?;
+    load_summary(&state.studio_db, sid)
+        .await
+        .map(Json)
+        .ok_or_else(|| AppError::not_found("Session has no summary"))
+}
+
+/// Summarize a session's conversation and store the result. With a stored
+/// summary, only the messages after it are sent unless `incremental` is off.
+pub(crate) async fn session_summary_post(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(session_id): AxumPath<String>,
+    body: Option<Json<SessionSummarizeBody>>,
+) -> ApiResult<Response> {
+    let sid = session_id_param(&session_id)?;
+    let body = body.map(|Json(b)| b).unwrap_or_default();
+    let model = body
+        .model
+        .as_deref()
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .map(str::to_string);
+    let model_value = match model.as_deref() {
+        Some(m) => match model_payload(m) {
+            Some(v) => Some(v),
+            None => {
+                return Ok(summary_error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_model",
+                    "model must be in provider/model form",
+                ));
+            }
+        },
+        None => None,
+    };
+
+    let mut messages = crate::opencode_session::load_session_messages_unfiltered(sid).await;
+    if messages.is_empty() {
+        return Err(AppError::not_found("Session has no messages"));
+    }
+    sort_messages(&mut messages);
+    let info = crate::session_export::load_session_info(state.as_ref(), sid).await;
+    let directory = info
+        .as_ref()
+        .and_then(|i| i.get("directory"))
+        .and_then(|v| v.as_str())
+        .map(str::to_string);
+
+    let previous = if body.incremental.unwrap_or(true) {
+        load_summary(&state.studio_db, sid).await
+    } else {
+        None
+    };
+    // A summary whose last message was reverted away cannot be extended.
+    let start = previous.as_ref().and_then(|p| {
+        messages
+            .iter()
+            .position(|m| message_id(m) == p.last_message_id)
+            .map(|pos| pos + 1)
+    });
+    let previous = previous.filter(|_| start.is_some());
+    let pending = &messages[start.unwrap_or(0)..];
+
+    if let Some(previous) = previous.as_ref()
+        && pending.is_empty()
+    {
+        return Ok(Json(SessionSummarizeResponse {
+            summary: previous.clone(),
+            incremental: true,
+            summarized_messages: 0,
+            truncated: false,
+        })
+        .into_response());
+    }
+
+    let (transcript, rendered, truncated) = build_transcript(pending, MAX_TRANSCRIPT_CHARS);
+    let last_message_id = pending
+        .last()
+        .map(|m| message_id(m).to_string())
+        .unwrap_or_default();
+    let covered = previous.as_ref().map_or(0, |p| p.message_count) + pending.len() as i64;
+    if rendered == 0 {
+        return Err(AppError::bad_request("Session has no text to summarize"));
+    }
+
+    let prompt = render_prompt(
+        previous.as_ref().map(|p| p.summary.as_str()),
+        &transcript,
+        truncated,
+    );
+    let text = match run_prompt(&state, directory.as_deref(), prompt, model_value).await {
+        Ok(text) => text,
+        Err(resp) => return Ok(resp),
+    };
+
+    let summary = SessionSummary {
+        session_id: sid.to_string(),
+        summary: text,
+        last_message_id,
+        message_count: covered,
+        model,
+        updated_at: now_millis(),
+    };
+    store_summary(&state.studio_db, &summary)
+        .await
+        .map_err(AppError::internal)?;
+    Ok(Json(SessionSummarizeResponse {
+        summary,
+        incremental: previous.is_some(),
+        summarized_messages: rendered,
+        truncated,
+    })
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, role: &str, created: i64, parts: Value) -> Value {
+        json!({ "info": { "id": id, "role": role, "time": { "created": created } }, "parts": parts })
+    }
+
+    #[test]
+    fn transcript_keeps_prose_and_tool_titles_only() {
+        let messages = vec![
+            message(
+                "msg_1",
+                "user",
+                1,
+                json!([
+                    { "type": "text", "text": "Fix the login bug" },
+                    { "type": "text", "text": "<file contents>", "synthetic": true },
+                ]),
+            ),
+            message(
+                "msg_2",
+                "assistant",
+                2,
+                json!([
+                    { "type": "reasoning", "text": "thinking" },
+                    { "type": "tool", "tool": "edit", "state": { "title": "src/login.rs", "output": "ok" } },
+                    { "type": "text", "text": "Done." },
+                ]),
+            ),
+        ];
+        let (text, rendered, truncated) = build_transcript(&messages, MAX_TRANSCRIPT_CHARS);
+        assert_eq!(
+            text,
+            "User:\nFix the login bug\n\nAssistant:\n[edit: src/login.rs]\nDone."
+        );
+        assert_eq!(rendered, 2);
+        assert!(!truncated);
+
+        let (text, rendered, truncated) = build_transcript(&messages, 10);
+        assert!(text.starts_with("Assistant:"));
+        assert_eq!(rendered, 1);
+        assert!(truncated);
+
+        let prompt = render_prompt(Some("- old"), &text, truncated);
+        assert!(prompt.starts_with(INCREMENTAL_PROMPT));
+        assert!(
+            prompt.contains("Current summary:\n- old\n\nNew messages:\n(earlier messages omitted)")
+        );
+    }
+
+    #[tokio::test]
+    async fn summaries_round_trip_through_the_db() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let db = StudioDb::open_at_path(tmp.path().join("studio.db"))
+            .await
+            .unwrap();
+        assert!(load_summary(&db, "ses_a").await.is_none());
+        let mut summary = SessionSummary {
+            session_id: "ses_a".into(),
+            summary: "- first".into(),
+            last_message_id: "msg_2".into(),
+            message_count: 2,
+            model: None,
+            updated_at: 1,
+        };
+        store_summary(&db, &summary).await.unwrap();
+        summary.summary = "- second".into();
+        summary.message_count = 5;
+        summary.model = Some("a/b".into());
+        store_summary(&db, &summary).await.unwrap();
+        let loaded = load_summary(&db, "ses_a").await.unwrap();
+        assert_eq!(loaded.summary, "- second");
+        assert_eq!(loaded.message_count, 5);
+        assert_eq!(loaded.model.as_deref(), Some("a/b"));
+    }
+}
@@ -0,0 +1,432 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{ApiResult, AppError};
+
+const MAX_TAGS_PER_SESSION: usize = 16;
+const MAX_TAG_CHARS: usize = 40;
+const MAX_TAGGED_SESSIONS: usize = 20_000;
+
+/// In-memory copy of the persisted tags, consulted by every session list.
+static SESSION_TAGS: LazyLock<RwLock<HashMap<String, SessionTagEntry>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SessionTagEntry {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub updated_at: u64,
+    /// Archived sessions keep their storage but are left out of default
+    /// session lists.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<u64>,
+}
+
+impl SessionTagEntry {
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && !self.favorite && self.archived_at.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SessionTagStore {
+    #[serde(default)]
+    sessions: HashMap<String, SessionTagEntry>,
+}
+
+fn normalize_tag(raw: &str) -> Option<String> {
+    let tag = raw.trim().to_lowercase();
+    if tag.is_empty() || tag.chars().count() > MAX_TAG_CHARS {
+        return None;
+    }
+    Some(tag)
+}
+
+/// Load persisted tags into memory; called once at startup.
+pub(crate) async fn load(db: &crate::studio_db::StudioDb) {
+    let sessions = match db
+        .get_json::<SessionTagStore>(crate::studio_db::KV_KEY_SESSION_TAGS)
+        .await
+    {
+        Ok(store) => store.unwrap_or_default().sessions,
+        Err(err) => {
+            tracing::warn!(error = %err, "Failed to load session tags");
+            HashMap::new()
+        }
+    };
+    if let Ok(mut guard) = SESSION_TAGS.write() {
+        *guard = sessions;
+    }
+}
+
+fn snapshot() -> HashMap<String, SessionTagEntry> {
+    SESSION_TAGS
+        .read()
+        .map(|guard| guard.clone())
+        .unwrap_or_default()
+}
+
+async fn update_sessions<R>(
+    db: &crate::studio_db::StudioDb,
+    update: impl FnOnce(&mut HashMap<String, SessionTagEntry>) -> ApiResult<R>,
+) -> ApiResult<R> {
+    db.update_json(
+        crate::studio_db::KV_KEY_SESSION_TAGS,
+        |store: &mut SessionTagStore| {
+            let result = update(&mut store.sessions)?;
+            // Still under the update lock, so the cache sees updates in order.
+            if let Ok(mut guard) = SESSION_TAGS.write() {
+                *guard = store.sessions.clone();
+            }
+            Ok(result)
+        },
+    )
+    .await
+    .map_err(AppError::internal)?
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum ArchivedFilter {
+    #[default]
+    Exclude,
+    Include,
+    Only,
+}
+
+impl ArchivedFilter {
+    /// `archived=true|only` lists archived sessions, `archived=all` lists
+    /// both; anything else hides them.
+    pub(crate) fn parse(raw: Option<&str>) -> Self {
+        let Some(raw) = raw else {
+            return Self::Exclude;
+        };
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "" | "1" | "true" | "yes" | "on" | "only" => Self::Only,
+            "all" | "include" => Self::Include,
+            _ => Self::Exclude,
+        }
+    }
+}
+
+/// `tag`/`favorite`/`archived` filter for the session list.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SessionTagFilter {
+    /// Every listed tag must be present.
+    pub tags: Vec<String>,
+    pub favorite_only: bool,
+    pub archived: ArchivedFilter,
+}
+
+impl SessionTagFilter {
+    /// `tag` is a comma-separated list.
+    pub(crate) fn parse(tag: Option<&str>, favorite_only: bool, archived: ArchivedFilter) -> Self {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in tag.unwrap_or("").split(',').filter_map(normalize_tag) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        Self {
+            tags,
+            favorite_only,
+            archived,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.favorite_only || !self.tags.is_empty() || self.archived != ArchivedFilter::Include
+    }
+
+    fn matches(&self, entry: Option<&SessionTagEntry>) -> bool {
+        let entry = entry.cloned().unwrap_or_default();
+        let archived_ok = match self.archived {
+            ArchivedFilter::Exclude => entry.archived_at.is_none(),
+            ArchivedFilter::Include => true,
+            ArchivedFilter::Only => entry.archived_at.is_some(),
+        };
+        archived_ok
+            && (!self.favorite_only || entry.favorite)
+            && self.tags.iter().all(|tag| entry.tags.contains(tag))
+    }
+
+    /// Keep the items whose session id passes the filter.
+    pub(crate) fn retain<T>(&self, items: &mut Vec<T>, id: impl Fn(&T) -> &str) {
+        if !self.is_active() {
+            return;
+        }
+        let Ok(guard) = SESSION_TAGS.read() else {
+            return;
+        };
+        items.retain(|item| self.matches(guard.get(id(item))));
+    }
+}
+
+pub(crate) fn entry(session_id: &str) -> Option<SessionTagEntry> {
+    SESSION_TAGS
+        .read()
+        .ok()
+        .and_then(|guard| guard.get(session_id).cloned())
+}
+
+pub(crate) fn is_archived(session_id: &str) -> bool {
+    entry(session_id).is_some_and(|e| e.archived_at.is_some())
+}
+
+/// Archive or unarchive sessions; returns the ids whose state changed.
+pub(crate) async fn set_archived(
+    db: &crate::studio_db::StudioDb,
+    session_ids: &[String],
+    archived: bool,
+) -> ApiResult<Vec<String>> {
+    update_sessions(db, |sessions| {
+        let now = now_millis();
+        let mut changed = Vec::new();
+        for session_id in session_ids {
+            let mut entry = sessions.get(session_id).cloned().unwrap_or_default();
+            if entry.archived_at.is_some() == archived {
+                continue;
+            }
+            entry.archived_at = archived.then_some(now);
+            entry.updated_at = now;
+            if entry.is_empty() {
+                sessions.remove(session_id);
+            } else {
+                sessions.insert(session_id.clone(), entry);
+            }
+            changed.push(session_id.clone());
+        }
+        if sessions.len() > MAX_TAGGED_SESSIONS {
+            return Err(AppError::conflict("Too many tagged sessions"));
+        }
+        Ok(changed)
+    })
+    .await
+}
+
+/// Drop the tags of deleted sessions.
+pub(crate) fn forget(state: &Arc<crate::AppState>, session_ids: &[String]) {
+    let tagged: Vec<String> = match SESSION_TAGS.read() {
+        Ok(guard) => session_ids
+            .iter()
+            .filter(|id| guard.contains_key(id.as_str()))
+            .cloned()
+            .collect(),
+        Err(_) => return,
+    };
+    if tagged.is_empty() {
+        return;
+    }
+    let state = state.clone();
+    tokio::spawn(async move {
+        let result = update_sessions(&state.studio_db, |sessions| {
+            sessions.retain(|id, _| !tagged.contains(id));
+            Ok(())
+        })
+        .await;
+        if let Err(err) = result {
+            tracing::warn!(sessions = tagged.len(), error = ?err, "Failed to drop session tags");
+        }
+    });
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTagsResponse {
+    /// Every tag in use, most used first.
+    pub tags: Vec<SessionTagCount>,
+    pub favorites: usize,
+    pub sessions: BTreeMap<String, SessionTagEntry>,
+}
+
+fn tag_counts(sessions: &HashMap<String, SessionTagEntry>) -> Vec<SessionTagCount> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for entry in sessions.values() {
+        for tag in &entry.tags {
+            *counts.entry(tag.as_str()).or_default() += 1;
+        }
+    }
+    let mut out: Vec<SessionTagCount> = counts
+        .into_iter()
+        .map(|(tag, count)| SessionTagCount {
+            tag: tag.to_string(),
+            count,
+        })
+        .collect();
+    out.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    out
+}
+
+/// Tags and favorites of every session.
+pub async fn session_tags_list() -> Json<SessionTagsResponse> {
+    let sessions = snapshot();
+    Json(SessionTagsResponse {
+        tags: tag_counts(&sessions),
+        favorites: sessions.values().filter(|e| e.favorite).count(),
+        sessions: sessions.into_iter().collect(),
+    })
+}
+
+pub async fn session_tags_get(AxumPath(session_id): AxumPath<String>) -> Json<SessionTagEntry> {
+    Json(snapshot().remove(&session_id).unwrap_or_default())
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTagsPatchBody {
+    #[serde(default)]
+    pub add: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+    #[serde(default)]
+    pub favorite: Option<bool>,
+}
+
+fn apply_patch(entry: &mut SessionTagEntry, body: SessionTagsPatchBody) -> ApiResult<()> {
+    let remove: Vec<String> = body
+        .remove
+        .iter()
+        .filter_map(|t| normalize_tag(t))
+        .collect();
+    entry.tags.retain(|tag| !remove.contains(tag));
+    for raw in &body.add {
+        let tag = normalize_tag(raw).ok_or_else(|| {
+            AppError::bad_request(format!(
+                "Tags must be 1-{MAX_TAG_CHARS} characters: '{}'",
+                raw.trim()
+            ))
+        })?;
+        if !entry.tags.contains(&tag) {
+            entry.tags.push(tag);
+        }
+    }
+    if entry.tags.len() > MAX_TAGS_PER_SESSION {
+        return Err(AppError::bad_request(format!(
+            "A session can have at most {MAX_TAGS_PER_SESSION} tags"
+        )));
+    }
+    if let Some(favorite) = body.favorite {
+        entry.favorite = favorite;
+    }
+    Ok(())
+}
+
+/// Add/remove tags and set or clear the favorite flag.
+pub async fn session_tags_patch(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(session_id): AxumPath<String>,
+    Json(body): Json<SessionTagsPatchBody>,
+) -> ApiResult<Json<SessionTagEntry>> {
+    let session_id = session_id.trim().to_string();
+    if session_id.is_empty() {
+        return Err(AppError::bad_request("Session id is required"));
+    }
+
+    update_sessions(&state.studio_db, |sessions| {
+        let mut entry = sessions.get(&session_id).cloned().unwrap_or_default();
+        apply_patch(&mut entry, body)?;
+        entry.updated_at = now_millis();
+
+        if entry.is_empty() {
+            sessions.remove(&session_id);
+        } else {
+            if !sessions.contains_key(&session_id) && sessions.len() >= MAX_TAGGED_SESSIONS {
+                return Err(AppError::conflict("Too many tagged sessions"));
+            }
+            sessions.insert(session_id, entry.clone());
+        }
+        Ok(Json(entry))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patches_normalize_and_dedupe_tags() {
+        let mut entry = SessionTagEntry {
+            tags: vec!["wip".to_string(), "bug".to_string()],
+            ..SessionTagEntry::default()
+        };
+        apply_patch(
+            &mut entry,
+            SessionTagsPatchBody {
+                add: vec![" Refactor ".to_string(), "BUG".to_string()],
+                remove: vec!["WIP".to_string()],
+                favorite: Some(true),
+            },
+        )
+        .unwrap();
+        assert_eq!(entry.tags, vec!["bug".to_string(), "refactor".to_string()]);
+        assert!(entry.favorite);
+
+        let err = apply_patch(
+            &mut entry,
+            SessionTagsPatchBody {
+                add: vec!["  ".to_string()],
+                ..SessionTagsPatchBody::default()
+            },
+        );
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn filters_require_every_tag_and_favorite() {
+        let entry = SessionTagEntry {
+            tags: vec!["bug".to_string(), "api".to_string()],
+            ..SessionTagEntry::default()
+        };
+        let parse = |tag, favorite| SessionTagFilter::parse(tag, favorite, ArchivedFilter::Exclude);
+        let filter = parse(Some("Bug, api,bug"), false);
+        assert_eq!(filter.tags, vec!["bug".to_string(), "api".to_string()]);
+        assert!(filter.matches(Some(&entry)));
+        assert!(!filter.matches(None));
+        assert!(!parse(Some("bug,ui"), false).matches(Some(&entry)));
+        assert!(!parse(None, true).matches(Some(&entry)));
+        assert!(parse(Some(" , "), false).matches(None));
+    }
+
+    #[test]
+    fn archived_sessions_are_hidden_unless_requested() {
+        let archived = SessionTagEntry {
+            archived_at: Some(1),
+            ..SessionTagEntry::default()
+        };
+        assert!(!archived.is_empty());
+        let filter = |raw| SessionTagFilter::parse(None, false, ArchivedFilter::parse(raw));
+        assert!(!filter(None).matches(Some(&archived)));
+        assert!(filter(None).matches(None));
+        assert!(filter(Some("true")).matches(Some(&archived)));
+        assert!(!filter(Some("only")).matches(None));
+        assert!(filter(Some("all")).matches(Some(&archived)));
+        assert!(filter(Some("all")).matches(None));
+        assert_eq!(ArchivedFilter::parse(Some("no")), ArchivedFilter::Exclude);
+    }
+}
@@ -3,13 +3,22 @@ use std::path::{Path, PathBuf};
 use std::time::UNIX_EPOCH;
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::BTreeMap;
 
-use crate::studio_db;
+use crate::{settings_migrations, studio_db};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Settings {
+    /// Layout version, see `settings_migrations`. Absent before versioning.
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+
+    /// Bumped on every write; clients send it back to detect concurrent edits.
+    #[serde(default)]
+    pub revision: u64,
+
     #[serde(default)]
     pub projects: Vec<Project>,
 
@@ -25,6 +34,10 @@ pub struct Settings {
     pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+fn legacy_schema_version() -> u32 {
+    settings_migrations::LEGACY_SETTINGS_SCHEMA_VERSION
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Project {
@@ -36,13 +49,20 @@ pub struct Project {
     pub last_opened_at: i64,
 }
 
-async fn read_settings_file(path: &Path) -> Option<Settings> {
+/// Raw settings JSON, if it has the shape of a settings object. Migrations
+/// run on the raw value, before it is typed.
+fn settings_shaped(value: Value) -> Option<Value> {
+    serde_json::from_value::<Settings>(value.clone()).ok()?;
+    Some(value)
+}
+
+async fn read_settings_file(path: &Path) -> Option<Value> {
     let raw = tokio::fs::read_to_string(path).await.ok()?;
     let trimmed = raw.trim();
     if trimmed.is_empty() {
         return None;
     }
-    serde_json::from_str::<Settings>(trimmed).ok()
+    settings_shaped(serde_json::from_str::<Value>(trimmed).ok()?)
 }
 
 async fn file_mtime_ms(path: &Path) -> u64 {
@@ -79,8 +99,8 @@ fn settings_disk_candidates() -> Vec<PathBuf> {
     deduped
 }
 
-async fn read_best_settings_from_disk() -> Option<Settings> {
-    let mut best: Option<(u64, Settings)> = None;
+async fn read_best_settings_from_disk() -> Option<Value> {
+    let mut best: Option<(u64, Value)> = None;
     for path in settings_disk_candidates() {
         let Some(settings) = read_settings_file(&path).await else {
             continue;
@@ -95,18 +115,58 @@ async fn read_best_settings_from_disk() -> Option<Settings> {
     best.map(|(_, settings)| settings)
 }
 
+/// Load settings from the studio database (or a legacy settings file on
+/// first run), migrate them to the current schema and persist the result
+/// when anything changed.
 pub async fn init_settings(db: &studio_db::StudioDb) -> Settings {
-    if let Ok(Some(settings)) = db.get_json::<Settings>(studio_db::KV_KEY_SETTINGS).await {
-        return settings;
-    }
+    let stored = match db.get_value(studio_db::KV_KEY_SETTINGS).await {
+        Ok(Some(value)) => settings_shaped(value).map(|value| (value, true)),
+        _ => None,
+    };
+    let stored = match stored {
+        Some(stored) => Some(stored),
+        None => read_best_settings_from_disk()
+            .await
+            .map(|value| (value, false)),
+    };
 
-    if let Some(settings) = read_best_settings_from_disk().await {
-        let _ = db.set_json(studio_db::KV_KEY_SETTINGS, &settings).await;
+    let Some((mut value, from_db)) = stored else {
+        let settings = Settings {
+            schema_version: settings_migrations::SETTINGS_SCHEMA_VERSION,
+            ..Settings::default()
+        };
+        let _ = persist_settings(db, &settings).await;
+        settings_migrations::record_load(settings_migrations::SettingsLoadReport {
+            loaded_version: settings.schema_version,
+            ..Default::default()
+        });
         return settings;
-    }
+    };
 
-    let settings = Settings::default();
-    let _ = db.set_json(studio_db::KV_KEY_SETTINGS, &settings).await;
+    let report = settings_migrations::migrate(&mut value);
+    if report.newer_than_supported {
+        tracing::warn!(
+            version = report.loaded_version,
+            supported = settings_migrations::SETTINGS_SCHEMA_VERSION,
+            "Settings were saved by a newer version; loading without migrating"
+        );
+    }
+    for applied in &report.applied {
+        tracing::info!(
+            from = applied.from,
+            to = applied.to,
+            migration = applied.name,
+            "Migrated settings"
+        );
+    }
+    let settings = serde_json::from_value::<Settings>(value.clone()).unwrap_or_default();
+    // A single upsert, so a crash leaves either the old or the migrated value.
+    if (!from_db || !report.applied.is_empty())
+        && let Err(err) = db.set_value(studio_db::KV_KEY_SETTINGS, &value).await
+    {
+        tracing::warn!(error = %err, "Failed to persist migrated settings");
+    }
+    settings_migrations::record_load(report);
     settings
 }
 
@@ -9,7 +9,7 @@ use axum::{
     http::HeaderMap,
     response::{IntoResponse, Response, sse::Event},
 };
-use serde_json::{Value, json};
+use serde_json::{Map, Value, json};
 use tokio::sync::{Mutex as AsyncMutex, broadcast};
 
 fn now_millis() -> u64 {
@@ -137,6 +137,34 @@ impl SettingsEventHub {
 
 static SETTINGS_EVENT_HUB: LazyLock<SettingsEventHub> = LazyLock::new(SettingsEventHub::new);
 
+pub(crate) const SETTINGS_REVISION_HEADER: &str = "x-settings-revision";
+
+fn revision_of(settings: &Value) -> u64 {
+    settings
+        .get("revision")
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+}
+
+/// Top-level keys whose values differ between two formatted settings
+/// objects, and the keys only `before` has. The revision itself is left out.
+fn diff_settings(before: &Value, after: &Value) -> (Map<String, Value>, Vec<String>) {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+    let changed = after
+        .iter()
+        .filter(|(key, value)| key.as_str() != "revision" && before.get(*key) != Some(*value))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let removed = before
+        .keys()
+        .filter(|key| !after.contains_key(*key))
+        .cloned()
+        .collect();
+    (changed, removed)
+}
+
 fn parse_last_event_id(headers: &HeaderMap) -> Option<u64> {
     let header_value = headers
         .get("last-event-id")
@@ -156,14 +184,47 @@ pub(crate) async fn publish_settings_replace(settings: Value) {
         *guard = Some(settings.clone());
     }
 
+    publish_sequenced(
+        "config.settings.replace",
+        json!({
+            "revision": revision_of(&settings),
+            "settings": settings,
+        }),
+    );
+}
+
+/// Publish only the top-level keys that changed between two formatted
+/// settings objects. Nothing is sent when they are equal. A client holding
+/// a revision other than `baseRevision` missed an update and should refetch.
+pub(crate) async fn publish_settings_patch(before: &Value, after: Value) {
+    let (changed, removed) = diff_settings(before, &after);
+    let (revision, base_revision) = (revision_of(&after), revision_of(before));
+    {
+        let mut guard = SETTINGS_EVENT_HUB.cached_settings.lock().await;
+        *guard = Some(after);
+    }
+    if changed.is_empty() && removed.is_empty() {
+        return;
+    }
+
+    publish_sequenced(
+        "settings.patch",
+        json!({
+            "revision": revision,
+            "baseRevision": base_revision,
+            "changed": changed,
+            "removed": removed,
+        }),
+    );
+}
+
+fn publish_sequenced(event_type: &str, properties: Value) {
     let seq = SETTINGS_EVENT_HUB.next_seq.fetch_add(1, Ordering::SeqCst);
     let payload = serde_json::to_string(&json!({
-        "type": "config.settings.replace",
+        "type": event_type,
         "seq": seq,
         "ts": now_millis(),
-        "properties": {
-            "settings": settings,
-        }
+        "properties": properties,
     }))
     .unwrap_or_else(|_| "{}".to_string());
 
@@ -217,7 +278,10 @@ pub(crate) async fn config_settings_events(
             "type": "config.settings.replace",
             "seq": forced_seq,
             "ts": now_millis(),
-            "properties": { "settings": settings }
+            "properties": {
+                "revision": revision_of(&settings),
+                "settings": settings,
+            }
         }))
         .unwrap_or_else(|_| "{}".to_string());
         forced_snapshot_event = Some(SequencedSettingsEvent {
@@ -378,6 +442,21 @@ mod tests {
         assert_eq!(replay[1].seq, 3);
     }
 
+    #[test]
+    fn diff_settings_reports_changed_and_removed_keys() {
+        let before = json!({ "revision": 1, "theme": "dark", "projects": [1], "legacy": true });
+        let after = json!({ "revision": 2, "theme": "light", "projects": [1], "fontSize": 14 });
+        let (changed, removed) = diff_settings(&before, &after);
+        assert_eq!(
+            Value::Object(changed),
+            json!({ "theme": "light", "fontSize": 14 })
+        );
+        assert_eq!(removed, vec!["legacy".to_string()]);
+
+        let (changed, removed) = diff_settings(&after, &after);
+        assert!(changed.is_empty() && removed.is_empty());
+    }
+
     #[tokio::test]
     async fn broadcast_receiver_can_lag_and_return_lagged_error() {
         let (tx, mut rx) = broadcast::channel::<u64>(1);
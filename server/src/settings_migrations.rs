@@ -0,0 +1,177 @@
+use std::sync::Mutex;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Schema version written by this build. Bump it together with a new entry
+/// in [`MIGRATIONS`].
+pub(crate) const SETTINGS_SCHEMA_VERSION: u32 = 2;
+/// Settings persisted before versioning have no `schemaVersion` field.
+pub(crate) const LEGACY_SETTINGS_SCHEMA_VERSION: u32 = 1;
+
+struct SettingsMigration {
+    /// Version the migration upgrades from; it produces `from + 1`.
+    from: u32,
+    name: &'static str,
+    apply: fn(&mut Map<String, Value>),
+}
+
+/// Ordered v1 -> v2 -> ... pipeline. Migrations work on the raw JSON so they
+/// can rename or reshape keys that the typed `Settings` would otherwise
+/// carry along in `extra`.
+const MIGRATIONS: &[SettingsMigration] = &[SettingsMigration {
+    from: 1,
+    name: "projects-from-directories-alias",
+    apply: migrate_directories_alias,
+}];
+
+/// `directories` used to be accepted as an alias for `projects` and could
+/// end up persisted next to it. Keep `projects` as the only stored key.
+fn migrate_directories_alias(obj: &mut Map<String, Value>) {
+    let Some(directories) = obj.remove("directories") else {
+        return;
+    };
+    let has_projects = obj
+        .get("projects")
+        .and_then(Value::as_array)
+        .is_some_and(|projects| !projects.is_empty());
+    if !has_projects && directories.is_array() {
+        obj.insert("projects".to_string(), directories);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AppliedMigration {
+    pub from: u32,
+    pub to: u32,
+    pub name: &'static str,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SettingsLoadReport {
+    /// Version found in storage, before migrating.
+    pub loaded_version: u32,
+    pub applied: Vec<AppliedMigration>,
+    /// Set when the stored settings were written by a newer build; they are
+    /// left untouched.
+    pub newer_than_supported: bool,
+}
+
+static LAST_LOAD: Mutex<Option<SettingsLoadReport>> = Mutex::new(None);
+
+pub(crate) fn stored_version(value: &Value) -> u32 {
+    value
+        .get("schemaVersion")
+        .and_then(Value::as_u64)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(LEGACY_SETTINGS_SCHEMA_VERSION)
+}
+
+/// Bring `value` up to [`SETTINGS_SCHEMA_VERSION`] in place.
+pub(crate) fn migrate(value: &mut Value) -> SettingsLoadReport {
+    let loaded_version = stored_version(value);
+    let mut report = SettingsLoadReport {
+        loaded_version,
+        ..SettingsLoadReport::default()
+    };
+    if loaded_version > SETTINGS_SCHEMA_VERSION {
+        report.newer_than_supported = true;
+        return report;
+    }
+    let Value::Object(obj) = value else {
+        return report;
+    };
+
+    let mut version = loaded_version;
+    for migration in MIGRATIONS {
+        if migration.from != version {
+            continue;
+        }
+        (migration.apply)(obj);
+        version = migration.from + 1;
+        report.applied.push(AppliedMigration {
+            from: migration.from,
+            to: version,
+            name: migration.name,
+        });
+    }
+    obj.insert("schemaVersion".to_string(), Value::from(version));
+    report
+}
+
+pub(crate) fn record_load(report: SettingsLoadReport) {
+    *LAST_LOAD.lock().unwrap_or_else(|e| e.into_inner()) = Some(report);
+}
+
+pub(crate) fn last_load() -> SettingsLoadReport {
+    LAST_LOAD
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipeline_is_contiguous_and_reaches_current_version() {
+        let mut expected = LEGACY_SETTINGS_SCHEMA_VERSION;
+        for migration in MIGRATIONS {
+            assert_eq!(migration.from, expected, "{}", migration.name);
+            expected += 1;
+        }
+        assert_eq!(expected, SETTINGS_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn legacy_settings_are_migrated_once() {
+        let mut value = serde_json::json!({
+            "directories": [{"id": "a", "path": "/work/a"}],
+            "showChatTimestamps": false,
+        });
+        let report = migrate(&mut value);
+        assert_eq!(report.loaded_version, 1);
+        assert_eq!(
+            report.applied,
+            vec![AppliedMigration {
+                from: 1,
+                to: 2,
+                name: "projects-from-directories-alias",
+            }]
+        );
+        assert_eq!(value["schemaVersion"], 2);
+        assert_eq!(value["projects"][0]["path"], "/work/a");
+        assert!(value.get("directories").is_none());
+        assert_eq!(value["showChatTimestamps"], false);
+
+        let again = migrate(&mut value);
+        assert_eq!(again.loaded_version, 2);
+        assert!(again.applied.is_empty());
+    }
+
+    #[test]
+    fn directories_alias_never_overrides_projects() {
+        let mut value = serde_json::json!({
+            "projects": [{"id": "p", "path": "/work/p"}],
+            "directories": [{"id": "d", "path": "/work/d"}],
+        });
+        migrate(&mut value);
+        assert_eq!(value["projects"][0]["id"], "p");
+        assert!(value.get("directories").is_none());
+    }
+
+    #[test]
+    fn newer_settings_are_left_alone() {
+        let mut value = serde_json::json!({"schemaVersion": 99, "directories": []});
+        let report = migrate(&mut value);
+        assert!(report.newer_than_supported);
+        assert!(report.applied.is_empty());
+        assert_eq!(value["schemaVersion"], 99);
+        assert!(value.get("directories").is_some());
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::directory_session_index::{DirectorySessionIndexManager, RuntimeDisplayState};
+
+const SIDEBAR_SYNC_TICK: Duration = Duration::from_millis(750);
+/// Deltas kept for `since` resyncs; older cursors get a full list.
+const SIDEBAR_DELTA_LOG_LIMIT: usize = 256;
+
+/// Distinguishes cursors of this process from ones handed out before a
+/// restart, when sequence numbers start over.
+static SIDEBAR_SYNC_EPOCH: LazyLock<String> = LazyLock::new(|| {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0);
+    format!("{millis:x}")
+});
+static SIDEBAR_SYNC: LazyLock<Mutex<SidebarSyncState>> =
+    LazyLock::new(|| Mutex::new(SidebarSyncState::default()));
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SidebarSessionEntry {
+    pub id: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    pub updated_at: f64,
+    pub state: RuntimeDisplayState,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SidebarCounters {
+    pub sessions: usize,
+    pub roots: usize,
+    pub running: usize,
+    pub blocked: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct DirectorySnapshot {
+    sessions: HashMap<String, SidebarSessionEntry>,
+    counters: SidebarCounters,
+}
+
+impl DirectorySnapshot {
+    fn insert(&mut self, entry: SidebarSessionEntry) {
+        self.counters.sessions += 1;
+        if entry.parent_id.is_none() {
+            self.counters.roots += 1;
+        }
+        match entry.state {
+            RuntimeDisplayState::Running
+            | RuntimeDisplayState::Retrying
+            | RuntimeDisplayState::CoolingDown => self.counters.running += 1,
+            RuntimeDisplayState::NeedsPermission | RuntimeDisplayState::NeedsReply => {
+                self.counters.blocked += 1
+            }
+            RuntimeDisplayState::Idle => {}
+        }
+        self.sessions.insert(entry.id.clone(), entry);
+    }
+}
+
+/// Changes to one directory's sidebar; the payload of `sidebar.delta`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DirectorySidebarDelta {
+    pub directory_id: String,
+    pub added: Vec<SidebarSessionEntry>,
+    pub updated: Vec<SidebarSessionEntry>,
+    pub removed: Vec<String>,
+    pub counters: SidebarCounters,
+}
+
+struct DeltaRecord {
+    seq: u64,
+    directories: Vec<DirectorySidebarDelta>,
+}
+
+#[derive(Default)]
+struct SidebarSyncState {
+    initialized: bool,
+    seq: u64,
+    /// Highest sequence number no longer in `log`.
+    floor: u64,
+    directories: BTreeMap<String, DirectorySnapshot>,
+    log: VecDeque<DeltaRecord>,
+}
+
+fn cursor_for(seq: u64) -> String {
+    format!("{}:{seq}", SIDEBAR_SYNC_EPOCH.as_str())
+}
+
+fn parse_cursor(raw: &str) -> Option<u64> {
+    let (epoch, seq) = raw.trim().split_once(':')?;
+    if epoch != SIDEBAR_SYNC_EPOCH.as_str() {
+        return None;
+    }
+    seq.parse().ok()
+}
+
+/// Cursor for the current sidebar state. Taken before a list is read, so
+/// changes racing the read are replayed rather than lost.
+pub(crate) fn current_cursor() -> String {
+    let seq = SIDEBAR_SYNC.lock().map(|s| s.seq).unwrap_or(0);
+    cursor_for(seq)
+}
+
+fn build_snapshot(index: &DirectorySessionIndexManager) -> BTreeMap<String, DirectorySnapshot> {
+    let mut out: BTreeMap<String, DirectorySnapshot> = BTreeMap::new();
+    index.for_each_summary(|summary| {
+        if crate::session_tags::is_archived(&summary.session_id) {
+            return;
+        }
+        let Some(directory_id) = index.directory_id_for_path(&summary.directory_path) else {
+            return;
+        };
+        let state = index
+            .runtime(&summary.session_id)
+            .map(|runtime| runtime.display_state)
+            .unwrap_or(RuntimeDisplayState::Idle);
+        out.entry(directory_id)
+            .or_default()
+            .insert(SidebarSessionEntry {
+                id: summary.session_id.clone(),
+                title: summary.title.clone(),
+                parent_id: summary.parent_id.clone(),
+                updated_at: summary.updated_at,
+                state,
+            });
+    });
+    out
+}
+
+fn diff_directory(
+    directory_id: &str,
+    prev: Option<&DirectorySnapshot>,
+    next: Option<&DirectorySnapshot>,
+) -> Option<DirectorySidebarDelta> {
+    let empty = DirectorySnapshot::default();
+    let prev = prev.unwrap_or(&empty);
+    let next = next.unwrap_or(&empty);
+    if prev == next {
+        return None;
+    }
+    let mut delta = DirectorySidebarDelta {
+        directory_id: directory_id.to_string(),
+        counters: next.counters,
+        ..DirectorySidebarDelta::default()
+    };
+    for (id, entry) in &next.sessions {
+        match prev.sessions.get(id) {
+            None => delta.added.push(entry.clone()),
+            Some(old) if old != entry => delta.updated.push(entry.clone()),
+            Some(_) => {}
+        }
+    }
+    delta.removed = prev
+        .sessions
+        .keys()
+        .filter(|id| !next.sessions.contains_key(*id))
+        .cloned()
+        .collect();
+    delta.added.sort_by(|a, b| a.id.cmp(&b.id));
+    delta.updated.sort_by(|a, b| a.id.cmp(&b.id));
+    delta.removed.sort();
+    Some(delta)
+}
+
+fn diff_snapshots(
+    prev: &BTreeMap<String, DirectorySnapshot>,
+    next: &BTreeMap<String, DirectorySnapshot>,
+) -> Vec<DirectorySidebarDelta> {
+    let ids: BTreeSet<&String> = prev.keys().chain(next.keys()).collect();
+    ids.into_iter()
+        .filter_map(|id| diff_directory(id, prev.get(id), next.get(id)))
+        .collect()
+}
+
+impl SidebarSyncState {
+    /// Swap in a new snapshot; returns the sequence number and changes when
+    /// anything differs.
+    fn apply(
+        &mut self,
+        next: BTreeMap<String, DirectorySnapshot>,
+    ) -> Option<(u64, Vec<DirectorySidebarDelta>)> {
+        if !self.initialized {
+            self.initialized = true;
+            self.directories = next;
+            return None;
+        }
+        let directories = diff_snapshots(&self.directories, &next);
+        self.directories = next;
+        if directories.is_empty() {
+            return None;
+        }
+        self.seq += 1;
+        self.log.push_back(DeltaRecord {
+            seq: self.seq,
+            directories: directories.clone(),
+        });
+        while self.log.len() > SIDEBAR_DELTA_LOG_LIMIT {
+            if let Some(evicted) = self.log.pop_front() {
+                self.floor = evicted.seq;
+            }
+        }
+        Some((self.seq, directories))
+    }
+
+    /// Net changes to `directory_id` after `since`, or `None` when the log no
+    /// longer reaches back that far.
+    fn changes_since(&self, directory_id: &str, since: u64) -> Option<DirectorySidebarDelta> {
+        if since < self.floor || since > self.seq {
+            return None;
+        }
+        let current = self.directories.get(directory_id);
+        // First op seen per session decides added vs updated.
+        let mut first_added: BTreeMap<&str, bool> = BTreeMap::new();
+        for record in self.log.iter().filter(|r| r.seq > since) {
+            for delta in record
+                .directories
+                .iter()
+                .filter(|d| d.directory_id == directory_id)
+            {
+                for entry in &delta.added {
+                    first_added.entry(entry.id.as_str()).or_insert(true);
+                }
+                for entry in &delta.updated {
+                    first_added.entry(entry.id.as_str()).or_insert(false);
+                }
+                for id in &delta.removed {
+                    first_added.entry(id.as_str()).or_insert(false);
+                }
+            }
+        }
+        let mut out = DirectorySidebarDelta {
+            directory_id: directory_id.to_string(),
+            counters: current.map(|d| d.counters).unwrap_or_default(),
+            ..DirectorySidebarDelta::default()
+        };
+        for (id, added) in first_added {
+            match current.and_then(|d| d.sessions.get(id)) {
+                Some(entry) if added => out.added.push(entry.clone()),
+                Some(entry) => out.updated.push(entry.clone()),
+                // Added and removed again within the window: nothing to send.
+                None if added => {}
+                None => out.removed.push(id.to_string()),
+            }
+        }
+        Some(out)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct SidebarSyncQuery {
+    /// Cursor from an earlier list response or `sidebar.delta` event.
+    pub since: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SidebarSyncResponse {
+    pub cursor: String,
+    #[serde(flatten)]
+    pub delta: DirectorySidebarDelta,
+}
+
+/// Changes to a directory since `since`, or `None` when the client must
+/// reload the full list (unknown, expired or pre-restart cursor).
+pub(crate) fn sync_since(directory_id: &str, since: &str) -> Option<SidebarSyncResponse> {
+    let since = parse_cursor(since)?;
+    let state = SIDEBAR_SYNC.lock().ok()?;
+    let delta = state.changes_since(directory_id, since)?;
+    Some(SidebarSyncResponse {
+        cursor: cursor_for(state.seq),
+        delta,
+    })
+}
+
+fn publish_delta(seq: u64, directories: &[DirectorySidebarDelta]) {
+    if crate::global_sse_hub::downstream_client_count() == 0 {
+        return;
+    }
+    let payload = serde_json::json!({
+        "type": "sidebar.delta",
+        "properties": {
+            "seq": seq,
+            "cursor": cursor_for(seq),
+            "directories": directories,
+        }
+    });
+    crate::global_sse_hub::publish_downstream_json(&payload.to_string());
+}
+
+/// Diff per-directory sidebar snapshots off the session index and publish
+/// the changes as `sidebar.delta` events.
+pub(crate) fn spawn_sidebar_sync_task(state: Arc<crate::AppState>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(SIDEBAR_SYNC_TICK).await;
+            let next = build_snapshot(&state.directory_session_index);
+            let applied = match SIDEBAR_SYNC.lock() {
+                Ok(mut sync) => sync.apply(next),
+                Err(_) => None,
+            };
+            if let Some((seq, directories)) = applied {
+                publish_delta(seq, &directories);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, updated_at: f64, state: RuntimeDisplayState) -> SidebarSessionEntry {
+        SidebarSessionEntry {
+            id: id.to_string(),
+            title: id.to_string(),
+            parent_id: None,
+            updated_at,
+            state,
+        }
+    }
+
+    fn snapshot(entries: Vec<SidebarSessionEntry>) -> BTreeMap<String, DirectorySnapshot> {
+        let mut dir = DirectorySnapshot::default();
+        for e in entries {
+            dir.insert(e);
+        }
+        BTreeMap::from([("d1".to_string(), dir)])
+    }
+
+    #[test]
+    fn deltas_report_added_updated_removed_and_counters() {
+        let mut sync = SidebarSyncState::default();
+        assert!(
+            sync.apply(snapshot(vec![
+                entry("a", 1.0, RuntimeDisplayState::Idle),
+                entry("b", 1.0, RuntimeDisplayState::Idle),
+            ]))
+            .is_none(),
+            "first snapshot is the baseline"
+        );
+
+        let (seq, dirs) = sync
+            .apply(snapshot(vec![
+                entry("a", 2.0, RuntimeDisplayState::Running),
+                entry("c", 1.0, RuntimeDisplayState::NeedsReply),
+            ]))
+            .unwrap();
+        assert_eq!(seq, 1);
+        let delta = &dirs[0];
+        assert_eq!(delta.added[0].id, "c");
+        assert_eq!(delta.updated[0].id, "a");
+        assert_eq!(delta.removed, vec!["b".to_string()]);
+        assert_eq!(
+            delta.counters,
+            SidebarCounters {
+                sessions: 2,
+                roots: 2,
+                running: 1,
+                blocked: 1,
+            }
+        );
+        assert!(
+            sync.apply(snapshot(vec![
+                entry("a", 2.0, RuntimeDisplayState::Running),
+                entry("c", 1.0, RuntimeDisplayState::NeedsReply),
+            ]))
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn changes_since_folds_the_log_and_expires_old_cursors() {
+        let mut sync = SidebarSyncState::default();
+        sync.apply(snapshot(vec![entry("a", 1.0, RuntimeDisplayState::Idle)]));
+        sync.apply(snapshot(vec![
+            entry("a", 1.0, RuntimeDisplayState::Idle),
+            entry("b", 1.0, RuntimeDisplayState::Idle),
+            entry("tmp", 1.0, RuntimeDisplayState::Idle),
+        ]));
+        sync.apply(snapshot(vec![
+            entry("a", 3.0, RuntimeDisplayState::Idle),
+            entry("b", 2.0, RuntimeDisplayState::Idle),
+        ]));
+
+        let delta = sync.changes_since("d1", 0).unwrap();
+        let ids =
+            |list: &[SidebarSessionEntry]| list.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&delta.added), vec!["b"]);
+        assert_eq!(ids(&delta.updated), vec!["a"]);
+        assert!(delta.removed.is_empty(), "tmp came and went");
+        assert_eq!(delta.added[0].updated_at, 2.0);
+
+        let delta = sync.changes_since("d1", 1).unwrap();
+        assert_eq!(delta.removed, vec!["tmp".to_string()]);
+        assert!(sync.changes_since("d1", 2).unwrap().updated.is_empty());
+        assert!(sync.changes_since("d1", 5).is_none());
+
+        sync.floor = 1;
+        assert!(sync.changes_since("d1", 0).is_none());
+        assert!(parse_cursor("0:1").is_none());
+        assert_eq!(parse_cursor(&cursor_for(7)), Some(7));
+    }
+}
@@ -0,0 +1,237 @@
+//! Slash commands defined in `settings.slashCommands` and expanded by the
+//! prompt proxy, so every client gets the same behaviour.
+//!
+//! A prompt whose first text part starts with `/name` is replaced by the
+//! command's template (`{{args}}` is the rest of the line, `{{1}}`, `{{2}}`…
+//! its words, plus the prompt template built-ins) and the command's files are
+//! attached. Commands with a `directory` only apply inside that project and
+//! shadow global commands of the same name.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{ApiResult, AppError};
+
+const SETTINGS_KEY: &str = "slashCommands";
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SlashCommand {
+    /// Without the leading `/`.
+    pub name: String,
+    pub description: Option<String>,
+    pub template: String,
+    /// Attached on every use; relative paths resolve against the project.
+    pub files: Vec<String>,
+    /// Project the command is limited to; `None` for global commands.
+    pub directory: Option<String>,
+}
+
+fn parse_commands(value: Option<&Value>) -> Vec<SlashCommand> {
+    let Some(Value::Array(entries)) = value else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let obj = entry.as_object()?;
+            let text = |key: &str| {
+                obj.get(key)
+                    .and_then(Value::as_str)
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+            };
+            Some(SlashCommand {
+                name: text("name")?.to_string(),
+                description: text("description").map(str::to_string),
+                template: text("template")?.to_string(),
+                files: obj
+                    .get("files")
+                    .and_then(Value::as_array)
+                    .map(|files| {
+                        files
+                            .iter()
+                            .filter_map(Value::as_str)
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                directory: text("directory")
+                    .and_then(crate::path_utils::normalize_directory_for_match),
+            })
+        })
+        .collect()
+}
+
+fn in_project(project: &str, directory: &str) -> bool {
+    directory
+        .strip_prefix(project)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Commands usable in `directory`, sorted by name; project commands win over
+/// global ones with the same name.
+fn commands_for_directory(all: Vec<SlashCommand>, directory: Option<&str>) -> Vec<SlashCommand> {
+    let directory = directory.and_then(crate::path_utils::normalize_directory_for_match);
+    let mut out: BTreeMap<String, SlashCommand> = BTreeMap::new();
+    for command in all {
+        match command.directory.as_deref() {
+            None => {
+                out.entry(command.name.clone()).or_insert(command);
+            }
+            Some(project) => {
+                if directory
+                    .as_deref()
+                    .is_some_and(|dir| in_project(project, dir))
+                {
+                    out.insert(command.name.clone(), command);
+                }
+            }
+        }
+    }
+    out.into_values().collect()
+}
+
+async fn load_commands(state: &crate::AppState, directory: Option<&str>) -> Vec<SlashCommand> {
+    let all = {
+        let guard = state.settings.read().await;
+        parse_commands(guard.extra.get(SETTINGS_KEY))
+    };
+    commands_for_directory(all, directory)
+}
+
+/// `/name rest of line` -> (`name`, `rest of line`).
+fn split_invocation(text: &str) -> Option<(&str, &str)> {
+    let rest = text.trim_start().strip_prefix('/')?;
+    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let name = &rest[..end];
+    (!name.is_empty()).then(|| (name, rest[end..].trim()))
+}
+
+fn argument_values(args: &str) -> BTreeMap<String, String> {
+    let mut values = BTreeMap::new();
+    values.insert("args".to_string(), args.to_string());
+    for (index, word) in args.split_whitespace().enumerate() {
+        values.insert((index + 1).to_string(), word.to_string());
+    }
+    values
+}
+
+/// Expand a registered slash command at the start of `prompt` in place.
+/// Returns the command name when one was expanded; unknown commands are left
+/// for OpenCode and the client.
+pub(crate) async fn expand_prompt(
+    state: &crate::AppState,
+    directory: Option<&str>,
+    prompt: &mut Value,
+) -> ApiResult<Option<String>> {
+    let Some(parts) = prompt.get_mut("parts").and_then(Value::as_array_mut) else {
+        return Ok(None);
+    };
+    let Some(index) = parts.iter().position(|part| {
+        part.get("type").and_then(Value::as_str) == Some("text")
+            && part.get("synthetic").and_then(Value::as_bool) != Some(true)
+    }) else {
+        return Ok(None);
+    };
+    let text = parts[index]
+        .get("text")
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    let Some((name, args)) = split_invocation(text) else {
+        return Ok(None);
+    };
+    let commands = load_commands(state, directory).await;
+    let Some(command) = commands.into_iter().find(|c| c.name == name) else {
+        return Ok(None);
+    };
+    if !command.files.is_empty() && directory.is_none() {
+        return Err(AppError::bad_request(format!(
+            "/{} attaches files and needs a project directory",
+            command.name
+        )));
+    }
+
+    let mut values = argument_values(args);
+    crate::prompt_templates::fill_builtins(&command.template, &mut values, directory).await;
+    let expanded =
+        crate::prompt_templates::render_body(&command.template, &values).map_err(|missing| {
+            AppError::bad_request(format!(
+                "/{} is missing values for: {}",
+                command.name,
+                missing.join(", ")
+            ))
+        })?;
+
+    parts[index]["text"] = Value::String(expanded);
+    // The proxy turns `serverPath` file parts into attachments.
+    for (offset, file) in command.files.iter().enumerate() {
+        parts.insert(
+            index + 1 + offset,
+            json!({ "type": "file", "serverPath": file }),
+        );
+    }
+    Ok(Some(command.name))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SlashCommandsQuery {
+    directory: Option<String>,
+}
+
+/// Slash commands available in a project, for client autocomplete.
+pub(crate) async fn slash_commands_list(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<SlashCommandsQuery>,
+) -> ApiResult<Json<Vec<SlashCommand>>> {
+    Ok(Json(load_commands(&state, q.directory.as_deref()).await))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_commands_shadow_global_ones_inside_the_project() {
+        let all = parse_commands(Some(&json!([
+            { "name": "test", "template": "Run the tests" },
+            { "name": "test", "template": "Run cargo test", "directory": "/repo/api/" },
+            { "name": "review", "template": "Review {{stagedDiff}}", "files": ["CONTRIBUTING.md"] },
+            { "name": "broken" },
+        ])));
+        assert_eq!(all.len(), 3);
+
+        let inside = commands_for_directory(all.clone(), Some("/repo/api/src"));
+        let names: Vec<_> = inside.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["review", "test"]);
+        assert_eq!(inside[1].template, "Run cargo test");
+
+        let outside = commands_for_directory(all, Some("/repo/apiary"));
+        assert_eq!(outside[1].template, "Run the tests");
+    }
+
+    #[test]
+    fn invocations_split_into_name_and_arguments() {
+        assert_eq!(
+            split_invocation("  /test unit  fast "),
+            Some(("test", "unit  fast"))
+        );
+        assert_eq!(split_invocation("/review"), Some(("review", "")));
+        assert_eq!(split_invocation("/ nothing"), None);
+        assert_eq!(split_invocation("no command"), None);
+
+        let values = argument_values("unit  fast");
+        assert_eq!(values["args"], "unit  fast");
+        assert_eq!(values["1"], "unit");
+        assert_eq!(values["2"], "fast");
+    }
+}
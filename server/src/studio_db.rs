@@ -1,10 +1,14 @@
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
+use dashmap::DashMap;
+
 use serde::{Serialize, de::DeserializeOwned};
 use serde_json::Value;
 use sqlx::SqlitePool;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use tokio::sync::Mutex as AsyncMutex;
 
 const DB_BUSY_TIMEOUT_MS: u64 = 15000;
 const DB_POOL_MAX_CONNECTIONS: u32 = 8;
@@ -13,9 +17,21 @@ const DB_POOL_IDLE_TIMEOUT_SECS: u64 = 120;
 
 pub(crate) const KV_KEY_SETTINGS: &str = "settings";
 pub(crate) const KV_KEY_CHAT_SIDEBAR_PREFERENCES: &str = "ui.chatSidebar.preferences";
+pub(crate) const KV_KEY_CHAT_SIDEBAR_DEVICE_PREFERENCES: &str = "ui.chatSidebar.devicePreferences";
 pub(crate) const KV_KEY_TERMINAL_UI_STATE: &str = "ui.terminal.state";
 pub(crate) const KV_KEY_TERMINAL_SESSION_REGISTRY: &str = "terminal.sessionRegistry";
 pub(crate) const KV_KEY_WORKSPACE_PREVIEW_STUDIO_STATE: &str = "workspacePreview.state.studio";
+pub(crate) const KV_KEY_MEMORY_SNIPPETS: &str = "memory.snippets";
+pub(crate) const KV_KEY_UI_USERS: &str = "ui.users";
+pub(crate) const KV_KEY_API_TOKENS: &str = "auth.apiTokens";
+pub(crate) const KV_KEY_PERMISSION_GRANTS: &str = "permission.grants";
+pub(crate) const KV_KEY_QUICK_CAPTURES: &str = "quickCaptures.queue";
+pub(crate) const KV_KEY_PROMPT_TEMPLATES: &str = "prompts.templates";
+pub(crate) const KV_KEY_FORGE_TOKENS: &str = "forge.tokens";
+pub(crate) const KV_KEY_SESSION_TAGS: &str = "session.tags";
+pub(crate) const KV_KEY_BUDGET_ACKNOWLEDGEMENTS: &str = "budgets.acknowledged";
+pub(crate) const KV_KEY_SECRETS: &str = "secrets.store";
+pub(crate) const KV_KEY_SECRETS_META: &str = "secrets.meta";
 
 pub(crate) const STUDIO_DB_SCHEMA_VERSION: i64 = 1;
 
@@ -23,6 +39,8 @@ pub(crate) const STUDIO_DB_SCHEMA_VERSION: i64 = 1;
 pub(crate) struct StudioDb {
     path: PathBuf,
     pool: SqlitePool,
+    /// One lock per key, held across the read-modify-write in `update_json`.
+    update_locks: Arc<DashMap<String, Arc<AsyncMutex<()>>>>,
 }
 
 impl StudioDb {
@@ -58,7 +76,11 @@ impl StudioDb {
 
         initialize_schema(&pool).await?;
 
-        Ok(Self { path, pool })
+        Ok(Self {
+            path,
+            pool,
+            update_locks: Arc::default(),
+        })
     }
 
     pub(crate) fn path(&self) -> &Path {
@@ -120,6 +142,32 @@ impl StudioDb {
         let json = serde_json::to_value(value).map_err(|err| err.to_string())?;
         self.set_value(key, &json).await
     }
+
+    /// Load the document at `key` (default when missing), apply `update`, and
+    /// store it again if `update` returned `Ok`. Updates to the same key are
+    /// serialized so concurrent read-modify-writes cannot drop each other's
+    /// changes. The outer error is a storage failure.
+    pub(crate) async fn update_json<T, R, E>(
+        &self,
+        key: &str,
+        update: impl FnOnce(&mut T) -> Result<R, E>,
+    ) -> Result<Result<R, E>, String>
+    where
+        T: Serialize + DeserializeOwned + Default,
+    {
+        let lock = self
+            .update_locks
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+        let _guard = lock.lock().await;
+        let mut doc = self.get_json::<T>(key).await?.unwrap_or_default();
+        let result = update(&mut doc);
+        if result.is_ok() {
+            self.set_json(key, &doc).await?;
+        }
+        Ok(result)
+    }
 }
 
 fn sqlite_sidecar_path(db_path: &Path, suffix: &str) -> PathBuf {
@@ -307,6 +355,65 @@ async fn initialize_schema(pool: &SqlitePool) -> Result<(), String> {
     .await
     .map_err(|err| err.to_string())?;
 
+    // Per-message token/cost usage (see `usage.rs`).
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS usage_messages (\n           message_id TEXT PRIMARY KEY,\n           session_id TEXT NOT NULL,\n           directory TEXT NOT NULL,\n           provider_id TEXT NOT NULL,\n           model_id TEXT NOT NULL,\n           day TEXT NOT NULL,\n           created_at INTEGER NOT NULL,\n           input_tokens INTEGER NOT NULL DEFAULT 0,\n           output_tokens INTEGER NOT NULL DEFAULT 0,\n           reasoning_tokens INTEGER NOT NULL DEFAULT 0,\n           cache_read_tokens INTEGER NOT NULL DEFAULT 0,\n           cache_write_tokens INTEGER NOT NULL DEFAULT 0,\n           cost REAL NOT NULL DEFAULT 0\n         )",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| err.to_string())?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_usage_messages_day ON usage_messages(day)")
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    // Per-session diff aggregate (see `session_diff_index.rs`).
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS session_diff_files (\n           session_id TEXT NOT NULL,\n           file TEXT NOT NULL,\n           source TEXT NOT NULL,\n           item_json TEXT NOT NULL,\n           updated_at INTEGER NOT NULL,\n           PRIMARY KEY (session_id, file)\n         )",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| err.to_string())?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS session_diff_sessions (\n           session_id TEXT PRIMARY KEY,\n           built_at INTEGER NOT NULL\n         )",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| err.to_string())?;
+
+    // Workspace checkpoints per assistant message (see `session_checkpoints.rs`).
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS session_checkpoints (\n           message_id TEXT PRIMARY KEY,\n           session_id TEXT NOT NULL,\n           directory TEXT NOT NULL,\n           commit_id TEXT NOT NULL,\n           created_at INTEGER NOT NULL\n         )",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| err.to_string())?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_session_checkpoints_session ON session_checkpoints(session_id, created_at)",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| err.to_string())?;
+
+    // Last known provider/model catalog per directory (see `model_catalog.rs`).
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS model_catalogs (\n           directory TEXT PRIMARY KEY,\n           payload TEXT NOT NULL,\n           fetched_at INTEGER NOT NULL\n         )",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| err.to_string())?;
+
+    // Stored conversation summaries (see `session_summary.rs`).
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS session_summaries (\n           session_id TEXT PRIMARY KEY,\n           summary TEXT NOT NULL,\n           last_message_id TEXT NOT NULL,\n           message_count INTEGER NOT NULL,\n           model TEXT,\n           updated_at INTEGER NOT NULL\n         )",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| err.to_string())?;
+
     tx.commit().await.map_err(|err| err.to_string())?;
     Ok(())
 }
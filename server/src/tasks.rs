@@ -0,0 +1,823 @@
+//! Workspace task runner.
+//!
+//! Detects runnable tasks in a project (package.json scripts, Makefile and
+//! justfile targets, cargo commands) and runs them as managed child
+//! processes whose output is buffered and streamed. This is separate from the
+//! interactive terminal: runs have no PTY, no input, and a tracked exit status.
+
+use std::collections::{HashSet, VecDeque};
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, LazyLock, Mutex as StdMutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Json,
+    extract::{Path as AxumPath, Query, State},
+    http::HeaderMap,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+use tokio::sync::{broadcast, oneshot};
+
+use crate::fs::{resolve_project_directory, to_api_path};
+use crate::{ApiResult, AppError};
+
+/// Output kept per run for late subscribers; older chunks are dropped.
+const MAX_RUN_OUTPUT_BYTES: usize = 1024 * 1024;
+/// Finished runs kept for inspection.
+const MAX_FINISHED_RUNS: usize = 50;
+const RUN_EVENTS_CAPACITY: usize = 256;
+const CANCEL_GRACE: Duration = Duration::from_millis(1500);
+
+static RUNS: LazyLock<DashMap<String, Arc<TaskRun>>> = LazyLock::new(DashMap::new);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TaskSource {
+    Npm,
+    Make,
+    Just,
+    Cargo,
+}
+
+impl TaskSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Npm => "npm",
+            Self::Make => "make",
+            Self::Just => "just",
+            Self::Cargo => "cargo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WorkspaceTask {
+    /// `<source>:<name>`, e.g. `npm:build` or `make:test`.
+    pub id: String,
+    pub source: TaskSource,
+    pub name: String,
+    /// Program and arguments the task runs.
+    pub command: Vec<String>,
+    /// Script body for package.json scripts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct TasksQuery {
+    pub directory: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TasksResponse {
+    pub directory: String,
+    pub tasks: Vec<WorkspaceTask>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TaskRunBody {
+    pub directory: Option<String>,
+    /// A task id from `GET /tasks`.
+    pub task: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TaskRunStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Canceled,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TaskRunInfo {
+    pub id: String,
+    pub task_id: String,
+    pub directory: String,
+    pub command: Vec<String>,
+    pub status: TaskRunStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub started_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TaskOutputChunk {
+    pub stream: &'static str,
+    pub data: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TaskRunDetail {
+    #[serde(flatten)]
+    pub info: TaskRunInfo,
+    pub output: Vec<TaskOutputChunk>,
+    /// Early output was dropped to stay under the buffer limit.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone)]
+enum TaskRunEvent {
+    Output(TaskOutputChunk),
+    Exit(TaskRunInfo),
+}
+
+struct TaskRunState {
+    info: TaskRunInfo,
+    output: VecDeque<TaskOutputChunk>,
+    output_bytes: usize,
+    truncated: bool,
+}
+
+struct TaskRun {
+    state: StdMutex<TaskRunState>,
+    events: broadcast::Sender<TaskRunEvent>,
+    cancel: StdMutex<Option<oneshot::Sender<()>>>,
+}
+
+impl TaskRun {
+    fn info(&self) -> TaskRunInfo {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .info
+            .clone()
+    }
+
+    fn detail(&self) -> TaskRunDetail {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        TaskRunDetail {
+            info: state.info.clone(),
+            output: state.output.iter().cloned().collect(),
+            truncated: state.truncated,
+        }
+    }
+
+    fn push_output(&self, stream: &'static str, data: String) {
+        let chunk = TaskOutputChunk { stream, data };
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.output_bytes += chunk.data.len();
+        state.output.push_back(chunk.clone());
+        while state.output_bytes > MAX_RUN_OUTPUT_BYTES {
+            let Some(dropped) = state.output.pop_front() else {
+                break;
+            };
+            state.output_bytes -= dropped.data.len();
+            state.truncated = true;
+        }
+        // Sent under the lock so subscribers never miss or repeat a chunk.
+        let _ = self.events.send(TaskRunEvent::Output(chunk));
+    }
+
+    fn finish(&self, status: TaskRunStatus, exit_code: Option<i32>, error: Option<String>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.info.status = status;
+        state.info.exit_code = exit_code;
+        state.info.error = error;
+        state.info.finished_at = Some(now_millis());
+        let _ = self.events.send(TaskRunEvent::Exit(state.info.clone()));
+    }
+
+    /// Buffered output plus a receiver for everything after it.
+    fn subscribe(
+        &self,
+    ) -> (
+        Vec<TaskOutputChunk>,
+        Option<TaskRunInfo>,
+        broadcast::Receiver<TaskRunEvent>,
+    ) {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let finished = (state.info.status != TaskRunStatus::Running).then(|| state.info.clone());
+        (
+            state.output.iter().cloned().collect(),
+            finished,
+            self.events.subscribe(),
+        )
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn task(
+    source: TaskSource,
+    name: &str,
+    command: Vec<String>,
+    detail: Option<String>,
+) -> WorkspaceTask {
+    WorkspaceTask {
+        id: format!("{}:{name}", source.as_str()),
+        source,
+        name: name.to_string(),
+        command,
+        detail,
+    }
+}
+
+/// The package manager a project uses, from `packageManager` or its lockfile.
+fn detect_package_manager(dir: &Path, package_json: &Value) -> &'static str {
+    if let Some(declared) = package_json.get("packageManager").and_then(Value::as_str) {
+        for pm in ["pnpm", "yarn", "bun", "npm"] {
+            if declared.starts_with(pm) {
+                return pm;
+            }
+        }
+    }
+    if dir.join("bun.lockb").is_file() || dir.join("bun.lock").is_file() {
+        "bun"
+    } else if dir.join("pnpm-lock.yaml").is_file() {
+        "pnpm"
+    } else if dir.join("yarn.lock").is_file() {
+        "yarn"
+    } else {
+        "npm"
+    }
+}
+
+fn detect_npm_tasks(dir: &Path) -> Vec<WorkspaceTask> {
+    let Some(package_json) = std::fs::read_to_string(dir.join("package.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+    else {
+        return Vec::new();
+    };
+    let Some(scripts) = package_json.get("scripts").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    let pm = detect_package_manager(dir, &package_json);
+    scripts
+        .iter()
+        .filter_map(|(name, body)| {
+            let body = body.as_str()?;
+            Some(task(
+                TaskSource::Npm,
+                name,
+                vec![pm.to_string(), "run".to_string(), name.clone()],
+                Some(body.to_string()),
+            ))
+        })
+        .collect()
+}
+
+fn is_make_target_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+}
+
+/// Explicit targets declared at the start of a line. Pattern rules,
+/// variables and special targets like `.PHONY` are skipped.
+fn parse_make_targets(raw: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut seen = HashSet::new();
+    for line in raw.lines() {
+        if line.starts_with(['\t', ' ', '#']) {
+            continue;
+        }
+        let Some((head, rest)) = line.split_once(':') else {
+            continue;
+        };
+        // `VAR := value` and `VAR ::= value`.
+        if rest.starts_with('=') || rest.starts_with(":=") || head.contains('=') {
+            continue;
+        }
+        for name in head.split_whitespace() {
+            if is_make_target_name(name) && seen.insert(name.to_string()) {
+                out.push(name.to_string());
+            }
+        }
+    }
+    out
+}
+
+fn detect_make_tasks(dir: &Path) -> Vec<WorkspaceTask> {
+    let Some(raw) = ["GNUmakefile", "makefile", "Makefile"]
+        .iter()
+        .find_map(|name| std::fs::read_to_string(dir.join(name)).ok())
+    else {
+        return Vec::new();
+    };
+    parse_make_targets(&raw)
+        .into_iter()
+        .map(|name| {
+            let command = vec!["make".to_string(), name.clone()];
+            task(TaskSource::Make, &name, command, None)
+        })
+        .collect()
+}
+
+/// Public recipes of a justfile. Settings, aliases, imports and `_private`
+/// recipes are skipped.
+fn parse_just_recipes(raw: &str) -> Vec<String> {
+    const KEYWORDS: [&str; 5] = ["set", "alias", "export", "import", "mod"];
+    let mut out = Vec::new();
+    let mut seen = HashSet::new();
+    for line in raw.lines() {
+        if line.starts_with([' ', '\t', '#', '[']) {
+            continue;
+        }
+        let line = line.strip_prefix('@').unwrap_or(line);
+        let end = line
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(line.len());
+        let (name, rest) = line.split_at(end);
+        if name.is_empty() || name.starts_with('_') || KEYWORDS.contains(&name) {
+            continue;
+        }
+        let rest = rest.trim_start();
+        if rest.starts_with(":=") || !rest.contains(':') {
+            continue;
+        }
+        if seen.insert(name.to_string()) {
+            out.push(name.to_string());
+        }
+    }
+    out
+}
+
+fn detect_just_tasks(dir: &Path) -> Vec<WorkspaceTask> {
+    let Some(raw) = ["justfile", "Justfile", ".justfile"]
+        .iter()
+        .find_map(|name| std::fs::read_to_string(dir.join(name)).ok())
+    else {
+        return Vec::new();
+    };
+    parse_just_recipes(&raw)
+        .into_iter()
+        .map(|name| {
+            let command = vec!["just".to_string(), name.clone()];
+            task(TaskSource::Just, &name, command, None)
+        })
+        .collect()
+}
+
+fn detect_cargo_tasks(dir: &Path) -> Vec<WorkspaceTask> {
+    if !dir.join("Cargo.toml").is_file() {
+        return Vec::new();
+    }
+    let mut names = vec!["build", "check", "test", "clippy"];
+    if dir.join("src").join("main.rs").is_file() || dir.join("src").join("bin").is_dir() {
+        names.push("run");
+    }
+    names
+        .into_iter()
+        .map(|name| {
+            let command = vec!["cargo".to_string(), name.to_string()];
+            task(TaskSource::Cargo, name, command, None)
+        })
+        .collect()
+}
+
+pub(crate) fn detect_tasks(dir: &Path) -> Vec<WorkspaceTask> {
+    let mut tasks = detect_npm_tasks(dir);
+    tasks.extend(detect_make_tasks(dir));
+    tasks.extend(detect_just_tasks(dir));
+    tasks.extend(detect_cargo_tasks(dir));
+    tasks
+}
+
+fn resolve_task_program(program: &str) -> String {
+    // npm and friends are `.cmd` shims on Windows.
+    if cfg!(windows) && matches!(program, "npm" | "pnpm" | "yarn") {
+        return format!("{program}.cmd");
+    }
+    program.to_string()
+}
+
+async fn pump_output<R: AsyncRead + Unpin>(run: Arc<TaskRun>, stream: &'static str, mut reader: R) {
+    let mut buf = [0u8; 8192];
+    let mut pending = Vec::<u8>::new();
+    loop {
+        let n = match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        pending.extend_from_slice(&buf[..n]);
+        // Hold back an incomplete UTF-8 sequence at the end of the read.
+        let valid = match std::str::from_utf8(&pending) {
+            Ok(_) => pending.len(),
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => pending.len(),
+        };
+        if valid == 0 {
+            continue;
+        }
+        let data = String::from_utf8_lossy(&pending[..valid]).into_owned();
+        pending.drain(..valid);
+        run.push_output(stream, data);
+    }
+    if !pending.is_empty() {
+        run.push_output(stream, String::from_utf8_lossy(&pending).into_owned());
+    }
+}
+
+#[cfg(unix)]
+fn signal_process_group(pid: u32, signal: i32) {
+    // Tasks run in their own process group so the whole tree is stopped,
+    // not just the package manager wrapper.
+    unsafe {
+        let _ = libc::kill(-(pid as i32), signal);
+    }
+}
+
+async fn start_run(dir: PathBuf, task: WorkspaceTask) -> ApiResult<Arc<TaskRun>> {
+    let (program, args) = task
+        .command
+        .split_first()
+        .ok_or_else(|| AppError::internal("task has no command"))?;
+    let mut cmd = Command::new(resolve_task_program(program));
+    cmd.args(args)
+        .current_dir(&dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    let mut child = cmd.spawn().map_err(|err| {
+        AppError::bad_gateway(format!(
+            "failed to start task '{}' (cwd={}): {err}",
+            task.command.join(" "),
+            dir.to_string_lossy()
+        ))
+    })?;
+
+    let (events, _) = broadcast::channel(RUN_EVENTS_CAPACITY);
+    let (cancel_tx, cancel_rx) = oneshot::channel();
+    let run = Arc::new(TaskRun {
+        state: StdMutex::new(TaskRunState {
+            info: TaskRunInfo {
+                id: uuid::Uuid::new_v4().to_string(),
+                task_id: task.id,
+                directory: to_api_path(&dir),
+                command: task.command,
+                status: TaskRunStatus::Running,
+                exit_code: None,
+                error: None,
+                started_at: now_millis(),
+                finished_at: None,
+            },
+            output: VecDeque::new(),
+            output_bytes: 0,
+            truncated: false,
+        }),
+        events,
+        cancel: StdMutex::new(Some(cancel_tx)),
+    });
+
+    let stdout = child
+        .stdout
+        .take()
+        .map(|out| tokio::spawn(pump_output(run.clone(), "stdout", out)));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|err| tokio::spawn(pump_output(run.clone(), "stderr", err)));
+
+    let watched = run.clone();
+    tokio::spawn(async move {
+        #[cfg(unix)]
+        let pid = child.id();
+        let mut canceled = false;
+        let status = tokio::select! {
+            status = child.wait() => status,
+            _ = cancel_rx => {
+                canceled = true;
+                #[cfg(unix)]
+                if let Some(pid) = pid {
+                    signal_process_group(pid, libc::SIGTERM);
+                }
+                match tokio::time::timeout(CANCEL_GRACE, child.wait()).await {
+                    Ok(status) => status,
+                    Err(_) => {
+                        #[cfg(unix)]
+                        if let Some(pid) = pid {
+                            signal_process_group(pid, libc::SIGKILL);
+                        }
+                        let _ = child.start_kill();
+                        child.wait().await
+                    }
+                }
+            }
+        };
+        for pump in [stdout, stderr].into_iter().flatten() {
+            let _ = pump.await;
+        }
+        match status {
+            Ok(_) if canceled => watched.finish(TaskRunStatus::Canceled, None, None),
+            Ok(status) if status.success() => {
+                watched.finish(TaskRunStatus::Succeeded, status.code(), None)
+            }
+            Ok(status) => watched.finish(TaskRunStatus::Failed, status.code(), None),
+            Err(err) => watched.finish(
+                TaskRunStatus::Failed,
+                None,
+                Some(format!("task wait failed: {err}")),
+            ),
+        }
+    });
+
+    prune_finished_runs();
+    RUNS.insert(run.info().id, run.clone());
+    Ok(run)
+}
+
+fn prune_finished_runs() {
+    let mut finished = RUNS
+        .iter()
+        .filter_map(|entry| {
+            let info = entry.value().info();
+            info.finished_at.map(|at| (at, info.id))
+        })
+        .collect::<Vec<_>>();
+    if finished.len() < MAX_FINISHED_RUNS {
+        return;
+    }
+    finished.sort();
+    let excess = finished.len() + 1 - MAX_FINISHED_RUNS;
+    for (_, id) in finished.into_iter().take(excess) {
+        RUNS.remove(&id);
+    }
+}
+
+fn find_run(run_id: &str) -> ApiResult<Arc<TaskRun>> {
+    RUNS.get(run_id)
+        .map(|entry| entry.value().clone())
+        .ok_or_else(|| AppError::not_found(format!("Task run not found: {run_id}")))
+}
+
+fn run_event(name: &str, payload: &impl Serialize) -> Result<Event, Infallible> {
+    Ok(Event::default()
+        .event(name)
+        .data(serde_json::to_string(payload).unwrap_or_else(|_| "{}".to_string())))
+}
+
+pub(crate) async fn tasks_get(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Query(q): Query<TasksQuery>,
+) -> ApiResult<Json<TasksResponse>> {
+    let dir = resolve_project_directory(state.as_ref(), &headers, q.directory.as_deref()).await?;
+    let tasks = tokio::task::spawn_blocking({
+        let dir = dir.clone();
+        move || detect_tasks(&dir)
+    })
+    .await
+    .map_err(|err| AppError::internal(err.to_string()))?;
+    Ok(Json(TasksResponse {
+        directory: to_api_path(&dir),
+        tasks,
+    }))
+}
+
+/// Start a detected task. Only tasks found in the directory can be run.
+pub(crate) async fn tasks_run_post(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<TaskRunBody>,
+) -> ApiResult<Json<TaskRunInfo>> {
+    let dir =
+        resolve_project_directory(state.as_ref(), &headers, body.directory.as_deref()).await?;
+    let task_id = body.task.trim().to_string();
+    let task = tokio::task::spawn_blocking({
+        let dir = dir.clone();
+        move || {
+            detect_tasks(&dir)
+                .into_iter()
+                .find(|task| task.id == task_id)
+        }
+    })
+    .await
+    .map_err(|err| AppError::internal(err.to_string()))?
+    .ok_or_else(|| AppError::not_found(format!("Task not found: {}", body.task.trim())))?;
+    let run = start_run(dir, task).await?;
+    Ok(Json(run.info()))
+}
+
+pub(crate) async fn task_runs_get(Query(q): Query<TasksQuery>) -> Json<Vec<TaskRunInfo>> {
+    let directory = q
+        .directory
+        .as_deref()
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| to_api_path(Path::new(dir)));
+    let mut runs = RUNS
+        .iter()
+        .map(|entry| entry.value().info())
+        .filter(|info| directory.as_ref().is_none_or(|dir| &info.directory == dir))
+        .collect::<Vec<_>>();
+    runs.sort_by_key(|info| std::cmp::Reverse(info.started_at));
+    Json(runs)
+}
+
+pub(crate) async fn task_run_get(
+    AxumPath(run_id): AxumPath<String>,
+) -> ApiResult<Json<TaskRunDetail>> {
+    Ok(Json(find_run(&run_id)?.detail()))
+}
+
+/// Stop a running task: SIGTERM to its process group, then SIGKILL.
+pub(crate) async fn task_run_cancel_post(
+    AxumPath(run_id): AxumPath<String>,
+) -> ApiResult<Json<TaskRunInfo>> {
+    let run = find_run(&run_id)?;
+    let cancel = run.cancel.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(cancel) = cancel {
+        let _ = cancel.send(());
+    }
+    Ok(Json(run.info()))
+}
+
+/// Buffered output then live `output` events, ending with one `exit` event.
+pub(crate) async fn task_run_events_get(AxumPath(run_id): AxumPath<String>) -> ApiResult<Response> {
+    let run = find_run(&run_id)?;
+    let (buffered, finished, mut rx) = run.subscribe();
+    let stream = async_stream::stream! {
+        for chunk in buffered {
+            yield run_event("output", &chunk);
+        }
+        if let Some(info) = finished {
+            yield run_event("exit", &info);
+            return;
+        }
+        loop {
+            match rx.recv().await {
+                Ok(TaskRunEvent::Output(chunk)) => yield run_event("output", &chunk),
+                Ok(TaskRunEvent::Exit(info)) => {
+                    yield run_event("exit", &info);
+                    break;
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    yield run_event("lagged", &serde_json::json!({ "skipped": skipped }));
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    yield run_event("exit", &run.info());
+                    break;
+                }
+            }
+        }
+    };
+    Ok(Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_make_targets() {
+        let raw = "\
+CC := gcc
+VERSION = 1
+.PHONY: build test
+build: deps
+\t$(CC) main.c
+test lint:
+%.o: %.c
+# comment: here
+install ::= nope
+";
+        assert_eq!(parse_make_targets(raw), vec!["build", "test", "lint"]);
+    }
+
+    #[test]
+    fn parses_just_recipes() {
+        let raw = "\
+set shell := [\"bash\", \"-c\"]
+alias b := build
+version := \"1\"
+
+# Build it
+build target='debug': fmt
+    cargo build
+@fmt:
+    cargo fmt
+_private:
+    echo hi
+[group('ci')]
+ci: build
+";
+        assert_eq!(parse_just_recipes(raw), vec!["build", "fmt", "ci"]);
+    }
+
+    #[test]
+    fn detects_tasks_across_sources() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let dir = tmp.path();
+        std::fs::write(
+            dir.join("package.json"),
+            r#"{"scripts": {"dev": "vite", "build": "vite build"}}"#,
+        )
+        .expect("package.json");
+        std::fs::write(dir.join("pnpm-lock.yaml"), "").expect("lockfile");
+        std::fs::write(dir.join("Makefile"), "all:\n\techo all\n").expect("Makefile");
+        std::fs::write(dir.join("Cargo.toml"), "[package]\nname = \"x\"\n").expect("Cargo.toml");
+
+        let tasks = detect_tasks(dir);
+        let ids = tasks.iter().map(|t| t.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![
+                "npm:build",
+                "npm:dev",
+                "make:all",
+                "cargo:build",
+                "cargo:check",
+                "cargo:test",
+                "cargo:clippy"
+            ]
+        );
+        assert_eq!(tasks[0].command, vec!["pnpm", "run", "build"]);
+        assert_eq!(tasks[0].detail.as_deref(), Some("vite build"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn runs_streams_and_cancels_tasks() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let dir = tmp.path().to_path_buf();
+        let ok = task(
+            TaskSource::Make,
+            "hello",
+            vec![
+                "sh".into(),
+                "-c".into(),
+                "echo out; echo err >&2; exit 3".into(),
+            ],
+            None,
+        );
+        let run = start_run(dir.clone(), ok).await.expect("start");
+        let (_, _, mut rx) = run.subscribe();
+        let info = loop {
+            if let TaskRunEvent::Exit(info) = rx.recv().await.expect("event") {
+                break info;
+            }
+        };
+        assert_eq!(info.status, TaskRunStatus::Failed);
+        assert_eq!(info.exit_code, Some(3));
+        let detail = run.detail();
+        let text = |stream| {
+            detail
+                .output
+                .iter()
+                .filter(|c| c.stream == stream)
+                .map(|c| c.data.as_str())
+                .collect::<String>()
+        };
+        assert_eq!(text("stdout"), "out\n");
+        assert_eq!(text("stderr"), "err\n");
+
+        let slow = task(
+            TaskSource::Make,
+            "slow",
+            vec!["sh".into(), "-c".into(), "sleep 30".into()],
+            None,
+        );
+        let run = start_run(dir, slow).await.expect("start");
+        let (_, _, mut rx) = run.subscribe();
+        let _ = task_run_cancel_post(AxumPath(run.info().id))
+            .await
+            .expect("cancel");
+        let info = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Ok(TaskRunEvent::Exit(info)) = rx.recv().await {
+                    break info;
+                }
+            }
+        })
+        .await
+        .expect("canceled in time");
+        assert_eq!(info.status, TaskRunStatus::Canceled);
+    }
+}
@@ -6,7 +6,7 @@ use std::{
     time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
 use axum::{
     Json,
@@ -19,6 +19,7 @@ use bytes::Bytes;
 use dashmap::DashMap;
 use portable_pty::ChildKiller;
 use portable_pty::{CommandBuilder, PtySize, native_pty_system};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::sync::{broadcast, watch};
@@ -29,13 +30,20 @@ use crate::studio_db;
 
 const MAX_TERMINAL_SESSIONS: usize = 20;
 const TERMINAL_IDLE_TIMEOUT_ENV: &str = "OPENCODE_STUDIO_TERMINAL_IDLE_TIMEOUT_SECS";
+const TERMINAL_DETACH_GRACE_ENV: &str = "OPENCODE_STUDIO_TERMINAL_DETACH_GRACE_SECS";
+const TERMINAL_SCROLLBACK_BYTES_ENV: &str = "OPENCODE_STUDIO_TERMINAL_SCROLLBACK_BYTES";
 const TERMINAL_CLEANUP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const TERMINAL_DETACH_SWEEP_MIN_INTERVAL: Duration = Duration::from_secs(5);
 const TERMINAL_HEARTBEAT: Duration = Duration::from_secs(15);
 const TERMINAL_SESSION_FILE_VERSION: u64 = 1;
 const TMUX_SESSION_PREFIX: &str = "opencode-studio-";
+const TERMINAL_TITLE_MAX_CHARS: usize = 80;
 
-// Keep a bounded recent scrollback for resumable streams.
+// Keep a bounded recent scrollback for resumable streams; the env override
+// is clamped to the min/max below.
 const TERMINAL_HISTORY_MAX_BYTES: usize = 512 * 1024;
+const TERMINAL_HISTORY_MIN_BYTES: usize = 64 * 1024;
+const TERMINAL_HISTORY_LIMIT_BYTES: usize = 16 * 1024 * 1024;
 
 // For a fresh stream connection (no `since` cursor), only paint a compact
 // tail snapshot to avoid replaying long historical output that visibly races
@@ -56,7 +64,7 @@ pub enum TerminalError {
     Kill(#[source] anyhow::Error),
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 #[derive(Default)]
 enum PersistedTerminalBackend {
@@ -73,6 +81,11 @@ struct PersistedTerminalSession {
     rows: u16,
     backend: PersistedTerminalBackend,
     updated_at: u64,
+    /// User-visible tab title; `None` falls back to the cwd in the UI.
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    created_at: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -132,8 +145,8 @@ fn now_millis() -> u64 {
         .unwrap_or(0)
 }
 
-fn terminal_idle_timeout() -> Option<Duration> {
-    let Ok(raw) = std::env::var(TERMINAL_IDLE_TIMEOUT_ENV) else {
+fn env_duration_secs(name: &str) -> Option<Duration> {
+    let Ok(raw) = std::env::var(name) else {
         return None;
     };
 
@@ -144,8 +157,9 @@ fn terminal_idle_timeout() -> Option<Duration> {
 
     let Ok(secs) = trimmed.parse::<u64>() else {
         tracing::warn!(
-            terminal_idle_timeout_secs = trimmed,
-            "invalid terminal idle timeout value; disabling timeout"
+            env = name,
+            value = trimmed,
+            "invalid terminal timeout value; disabling it"
         );
         return None;
     };
@@ -157,6 +171,32 @@ fn terminal_idle_timeout() -> Option<Duration> {
     Some(Duration::from_secs(secs))
 }
 
+fn terminal_idle_timeout() -> Option<Duration> {
+    env_duration_secs(TERMINAL_IDLE_TIMEOUT_ENV)
+}
+
+/// How long a PTY with no attached stream is kept for a reattach.
+fn terminal_detach_grace() -> Option<Duration> {
+    env_duration_secs(TERMINAL_DETACH_GRACE_ENV)
+}
+
+fn terminal_history_max_bytes() -> usize {
+    std::env::var(TERMINAL_SCROLLBACK_BYTES_ENV)
+        .ok()
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .map(|bytes| bytes.clamp(TERMINAL_HISTORY_MIN_BYTES, TERMINAL_HISTORY_LIMIT_BYTES))
+        .unwrap_or(TERMINAL_HISTORY_MAX_BYTES)
+}
+
+fn sanitize_terminal_title(raw: Option<&str>) -> Option<String> {
+    raw.map(str::trim).filter(|t| !t.is_empty()).map(|t| {
+        t.chars()
+            .filter(|c| !c.is_control())
+            .take(TERMINAL_TITLE_MAX_CHARS)
+            .collect()
+    })
+}
+
 fn tmux_session_name(session_id: &str) -> String {
     format!("{TMUX_SESSION_PREFIX}{session_id}")
 }
@@ -329,6 +369,8 @@ pub struct TerminalManager {
     registry_flush_queue: Arc<Mutex<TerminalRegistryFlushQueue>>,
     restore_lock: Arc<Mutex<()>>,
     idle_timeout: Option<Duration>,
+    detach_grace: Option<Duration>,
+    history_max_bytes: usize,
     prefer_tmux: bool,
 }
 
@@ -337,6 +379,8 @@ impl TerminalManager {
         let session_registry = load_session_registry_from_store(db.as_ref()).await;
         let prefer_tmux = *TMUX_AVAILABLE;
         let idle_timeout = terminal_idle_timeout();
+        let detach_grace = terminal_detach_grace();
+        let history_max_bytes = terminal_history_max_bytes();
 
         if prefer_tmux {
             tracing::info!("terminal persistence backend: tmux");
@@ -353,6 +397,8 @@ impl TerminalManager {
             registry_flush_queue: Arc::new(Mutex::new(TerminalRegistryFlushQueue::default())),
             restore_lock: Arc::new(Mutex::new(())),
             idle_timeout,
+            detach_grace,
+            history_max_bytes,
             prefer_tmux,
         }
     }
@@ -462,15 +508,19 @@ impl TerminalManager {
         let sid = sid.to_string();
         let cwd = cwd.to_string();
         self.persist_registry_with(move |registry| {
+            let now = now_millis();
+            let current = registry.sessions.get(&sid);
             let next = PersistedTerminalSession {
                 cwd,
                 cols,
                 rows,
                 backend,
-                updated_at: now_millis(),
+                updated_at: now,
+                title: current.and_then(|c| c.title.clone()),
+                created_at: current.map_or(now, |c| c.created_at),
             };
 
-            match registry.sessions.get(&sid) {
+            match current {
                 Some(current)
                     if current.cwd == next.cwd
                         && current.cols == next.cols
@@ -557,6 +607,7 @@ impl TerminalManager {
             persisted.cols,
             persisted.rows,
             self.prefer_tmux,
+            self.history_max_bytes,
         ) {
             Ok(session) => session,
             Err(error) => {
@@ -578,30 +629,46 @@ impl TerminalManager {
     }
 
     pub fn spawn_cleanup_task(self: Arc<Self>) {
-        let Some(idle_timeout) = self.idle_timeout else {
-            tracing::info!("terminal idle timeout disabled");
+        if self.idle_timeout.is_none() && self.detach_grace.is_none() {
+            tracing::info!("terminal idle timeout and detach grace disabled");
             return;
-        };
+        }
+
+        // Sweep often enough that a detached PTY outlives its grace period
+        // by at most about half of it.
+        let interval = self
+            .detach_grace
+            .map(|grace| (grace / 2).max(TERMINAL_DETACH_SWEEP_MIN_INTERVAL))
+            .map_or(TERMINAL_CLEANUP_INTERVAL, |sweep| {
+                sweep.min(TERMINAL_CLEANUP_INTERVAL)
+            });
 
         tokio::spawn(async move {
-            let mut ticker = tokio::time::interval(TERMINAL_CLEANUP_INTERVAL);
+            let mut ticker = tokio::time::interval(interval);
             loop {
                 ticker.tick().await;
                 let now = Instant::now();
                 let mut to_remove = Vec::new();
                 for entry in self.sessions.iter() {
+                    let session = entry.value();
                     let idle = {
-                        let last = entry.value().last_activity.lock().unwrap();
+                        let last = session.last_activity.lock().unwrap();
                         now.duration_since(*last)
                     };
-                    if idle > idle_timeout {
-                        to_remove.push(entry.key().clone());
+                    if self.idle_timeout.is_some_and(|limit| idle > limit) {
+                        to_remove.push((entry.key().clone(), "idle"));
+                    } else if self
+                        .detach_grace
+                        .zip(session.detached_for(now))
+                        .is_some_and(|(grace, detached)| detached > grace)
+                    {
+                        to_remove.push((entry.key().clone(), "detached"));
                     }
                 }
 
-                for id in to_remove {
+                for (id, reason) in to_remove {
                     if let Some((_, session)) = self.sessions.remove(&id) {
-                        tracing::info!("Cleaning up idle terminal session: {}", id);
+                        tracing::info!("Cleaning up {} terminal session: {}", reason, id);
                         let _ = session.kill();
                         self.remove_persisted_session(&id);
                     }
@@ -652,6 +719,7 @@ impl TerminalManager {
         cwd: String,
         cols: u16,
         rows: u16,
+        title: Option<String>,
     ) -> Result<TerminalCreateResponse, TerminalError> {
         if self.sessions.len() >= MAX_TERMINAL_SESSIONS {
             return Err(TerminalError::LimitReached);
@@ -672,6 +740,7 @@ impl TerminalManager {
             cols,
             rows,
             self.prefer_tmux,
+            self.history_max_bytes,
         )
         .map_err(TerminalError::Spawn)?;
 
@@ -680,14 +749,83 @@ impl TerminalManager {
         self.sessions.insert(session_id.clone(), session.clone());
         self.track_session_lifecycle(session_id.clone(), session);
         self.upsert_persisted_session(&session_id, &cwd, cols, rows, backend);
+        if title.is_some() {
+            let _ = self.rename_session(&session_id, title.clone());
+        }
 
         Ok(TerminalCreateResponse {
             session_id,
             cols,
             rows,
+            title,
         })
     }
 
+    /// Set or clear (`None`) the tab title of a live or persisted session.
+    pub fn rename_session(
+        &self,
+        session_id: &str,
+        title: Option<String>,
+    ) -> Result<(), TerminalError> {
+        let sid = session_id.trim();
+        if sid.is_empty() || self.persisted_session(sid).is_none() {
+            return Err(TerminalError::NotFound);
+        }
+
+        let sid = sid.to_string();
+        self.persist_registry_with(move |registry| {
+            let Some(entry) = registry.sessions.get_mut(&sid) else {
+                return false;
+            };
+            if entry.title == title {
+                return false;
+            }
+            entry.title = title;
+            entry.updated_at = now_millis();
+            true
+        });
+        Ok(())
+    }
+
+    /// Every known session (running or restorable), optionally limited to one
+    /// working directory, in creation order.
+    pub fn list_sessions(&self, directory: Option<&str>) -> Vec<TerminalSessionSummary> {
+        let registry = self.session_registry.lock().unwrap().clone();
+        let mut out: Vec<TerminalSessionSummary> = registry
+            .sessions
+            .into_iter()
+            .filter(|(_, entry)| {
+                directory.is_none_or(|dir| {
+                    crate::path_utils::normalize_directory_for_match(&entry.cwd)
+                        .is_some_and(|cwd| cwd == dir)
+                })
+            })
+            .map(|(session_id, entry)| {
+                let live = self.sessions.get(&session_id).map(|s| s.value().clone());
+                TerminalSessionSummary {
+                    running: live.is_some(),
+                    attached: live
+                        .as_ref()
+                        .map_or(0, |s| s.attached.load(Ordering::SeqCst)),
+                    session_id,
+                    title: entry.title,
+                    cwd: entry.cwd,
+                    cols: entry.cols,
+                    rows: entry.rows,
+                    backend: entry.backend,
+                    created_at: entry.created_at,
+                    updated_at: entry.updated_at,
+                }
+            })
+            .collect();
+        out.sort_by(|a, b| {
+            a.created_at
+                .cmp(&b.created_at)
+                .then_with(|| a.session_id.cmp(&b.session_id))
+        });
+        out
+    }
+
     pub fn remember_dimensions(&self, session_id: &str, cols: u16, rows: u16) {
         let sid = session_id.trim();
         if sid.is_empty() {
@@ -769,6 +907,22 @@ pub struct TerminalSession {
     // Keep a bounded history of recent output for new subscribers.
     seq: AtomicU64,
     history: Mutex<TerminalHistory>,
+    history_max_bytes: usize,
+
+    // Open output streams; when it drops to zero the detach clock starts.
+    attached: AtomicUsize,
+    detached_since: Mutex<Option<Instant>>,
+}
+
+/// Held by an output stream for as long as the client is connected.
+struct TerminalAttachGuard(Arc<TerminalSession>);
+
+impl Drop for TerminalAttachGuard {
+    fn drop(&mut self) {
+        if self.0.attached.fetch_sub(1, Ordering::SeqCst) == 1 {
+            *self.0.detached_since.lock().unwrap() = Some(Instant::now());
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -789,6 +943,20 @@ struct TerminalHistory {
     bytes: usize,
 }
 
+impl TerminalHistory {
+    fn push(&mut self, seq: u64, chunk: String, max_bytes: usize) {
+        self.bytes += chunk.len();
+        self.chunks.push_back((seq, chunk));
+        while self.bytes > max_bytes {
+            if let Some((_s, old)) = self.chunks.pop_front() {
+                self.bytes = self.bytes.saturating_sub(old.len());
+            } else {
+                break;
+            }
+        }
+    }
+}
+
 impl TerminalSession {
     fn spawn(
         session_id: String,
@@ -796,6 +964,7 @@ impl TerminalSession {
         cols: u16,
         rows: u16,
         prefer_tmux: bool,
+        history_max_bytes: usize,
     ) -> Result<Arc<Self>, anyhow::Error> {
         let pty_system = native_pty_system();
         let pair = pty_system.openpty(PtySize {
@@ -876,6 +1045,10 @@ impl TerminalSession {
 
             seq: AtomicU64::new(0),
             history: Mutex::new(TerminalHistory::default()),
+            history_max_bytes,
+
+            attached: AtomicUsize::new(0),
+            detached_since: Mutex::new(Some(Instant::now())),
         });
 
         Self::spawn_reader_task(session.clone(), reader);
@@ -914,18 +1087,11 @@ impl TerminalSession {
                         *session.last_activity.lock().unwrap() = Instant::now();
 
                         let seq = session.seq.fetch_add(1, Ordering::Relaxed) + 1;
-                        {
-                            let mut hist = session.history.lock().unwrap();
-                            hist.bytes += chunk.len();
-                            hist.chunks.push_back((seq, chunk.clone()));
-                            while hist.bytes > TERMINAL_HISTORY_MAX_BYTES {
-                                if let Some((_s, old)) = hist.chunks.pop_front() {
-                                    hist.bytes = hist.bytes.saturating_sub(old.len());
-                                } else {
-                                    break;
-                                }
-                            }
-                        }
+                        session.history.lock().unwrap().push(
+                            seq,
+                            chunk.clone(),
+                            session.history_max_bytes,
+                        );
 
                         let _ = session.tx.send(TerminalEvent::Data { seq, data: chunk });
                     }
@@ -1001,9 +1167,21 @@ impl TerminalSession {
     fn subscribe_exit(&self) -> watch::Receiver<bool> {
         self.exit_state.subscribe()
     }
+
+    fn attach(self: &Arc<Self>) -> TerminalAttachGuard {
+        self.attached.fetch_add(1, Ordering::SeqCst);
+        *self.detached_since.lock().unwrap() = None;
+        TerminalAttachGuard(self.clone())
+    }
+
+    /// How long the session has had no output stream attached.
+    fn detached_for(&self, now: Instant) -> Option<Duration> {
+        let since = *self.detached_since.lock().unwrap();
+        since.map(|since| now.duration_since(since))
+    }
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, JsonSchema)]
 pub struct TerminalStreamQuery {
     pub since: Option<String>,
 }
@@ -1108,33 +1286,64 @@ fn default_shell() -> String {
     "/bin/sh".to_string()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct TerminalCreateBody {
     pub cwd: Option<String>,
     pub cols: Option<u16>,
     pub rows: Option<u16>,
+    pub title: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TerminalCreateResponse {
     pub session_id: String,
     pub cols: u16,
     pub rows: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalSessionSummary {
+    pub session_id: String,
+    pub title: Option<String>,
+    pub cwd: String,
+    pub cols: u16,
+    pub rows: u16,
+    backend: PersistedTerminalBackend,
+    /// A PTY is live; otherwise it is restored on the next stream or start.
+    pub running: bool,
+    /// Number of output streams currently connected.
+    pub attached: usize,
+    pub created_at: u64,
+    pub updated_at: u64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TerminalListQuery {
+    pub directory: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TerminalRenameBody {
+    /// `null` or blank clears the title.
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct TerminalResizeBody {
     pub cols: Option<u16>,
     pub rows: Option<u16>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub(crate) struct TerminalSuccessResponse {
     success: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct TerminalResizeResponse {
     success: bool,
@@ -1156,8 +1365,9 @@ pub async fn terminal_create(
 
     let cols = body.cols.unwrap_or(80);
     let rows = body.rows.unwrap_or(24);
+    let title = sanitize_terminal_title(body.title.as_deref());
 
-    match state.terminal.create(cwd, cols, rows).await {
+    match state.terminal.create(cwd, cols, rows, title).await {
         Ok(resp) => Ok(Json(resp)),
         Err(TerminalError::LimitReached) => Err(AppError::too_many_requests(
             TerminalError::LimitReached.to_string(),
@@ -1181,6 +1391,8 @@ pub async fn terminal_stream(
         .ok_or_else(|| AppError::not_found("Terminal session not found"))?;
 
     *session.last_activity.lock().unwrap() = Instant::now();
+    let was_detached_for = session.detached_for(Instant::now());
+    let attach = session.attach();
     let mut rx = session.subscribe();
     let snapshot_chunks = session.snapshot_history_chunks();
     let snapshot_last_seq = snapshot_chunks.last().map(|(seq, _)| *seq).unwrap_or(0);
@@ -1222,10 +1434,18 @@ pub async fn terminal_stream(
     };
 
     let connected = {
+        // Reattach handshake: clients resume with `since=<lastSeq>` (or the
+        // SSE Last-Event-ID) to replay only the output they missed.
         let payload = serde_json::json!({
             "type": "connected",
             "runtime": "rust",
             "ptyBackend": "portable-pty",
+            "sessionId": session_id,
+            "reattached": resume_since.is_some(),
+            "lastSeq": snapshot_last_seq,
+            "firstAvailableSeq": snapshot_first_seq,
+            "detachedForMs": was_detached_for.map(|d| d.as_millis() as u64),
+            "detachGraceSecs": state.terminal.detach_grace.map(|d| d.as_secs()),
         });
         sse_json(payload, None)
     };
@@ -1234,6 +1454,8 @@ pub async fn terminal_stream(
     let mut ticker = tokio::time::interval_at(start, TERMINAL_HEARTBEAT);
 
     let stream = async_stream::stream! {
+        // Dropped with the stream when the client goes away.
+        let _attach = attach;
         yield Ok::<Bytes, std::convert::Infallible>(connected);
 
         if needs_resync_notice {
@@ -1376,7 +1598,7 @@ pub async fn terminal_delete(
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TerminalInfoResponse {
     pub session_id: String,
@@ -1399,6 +1621,30 @@ pub async fn terminal_get(
     }))
 }
 
+pub async fn terminal_list(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<TerminalListQuery>,
+) -> ApiResult<Json<Vec<TerminalSessionSummary>>> {
+    let directory = q
+        .directory
+        .as_deref()
+        .and_then(crate::path_utils::normalize_directory_for_match);
+    Ok(Json(state.terminal.list_sessions(directory.as_deref())))
+}
+
+pub async fn terminal_rename(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(session_id): AxumPath<String>,
+    Json(body): Json<TerminalRenameBody>,
+) -> ApiResult<Json<TerminalSuccessResponse>> {
+    let title = sanitize_terminal_title(body.title.as_deref());
+    match state.terminal.rename_session(&session_id, title) {
+        Ok(()) => Ok(Json(TerminalSuccessResponse { success: true })),
+        Err(TerminalError::NotFound) => Err(AppError::not_found("Terminal session not found")),
+        Err(err) => Err(AppError::internal(err.to_string())),
+    }
+}
+
 pub async fn terminal_start(
     State(state): State<Arc<crate::AppState>>,
     AxumPath(session_id): AxumPath<String>,
@@ -1439,11 +1685,18 @@ pub async fn terminal_restart(
         .to_string();
     let cols = body.cols.unwrap_or(80);
     let rows = body.rows.unwrap_or(24);
+    // The replacement keeps the old tab title unless a new one is given.
+    let title = sanitize_terminal_title(body.title.as_deref()).or_else(|| {
+        state
+            .terminal
+            .persisted_session(&old_session_id)
+            .and_then(|s| s.title)
+    });
 
     // Kill old session if it exists.
     let _ = state.terminal.kill_session(&old_session_id);
 
-    match state.terminal.create(cwd, cols, rows).await {
+    match state.terminal.create(cwd, cols, rows, title).await {
         Ok(resp) => Ok(Json(resp)),
         Err(TerminalError::LimitReached) => Err(AppError::too_many_requests(
             TerminalError::LimitReached.to_string(),
@@ -1454,3 +1707,39 @@ pub async fn terminal_restart(
         Err(err) => Err(AppError::internal(err.to_string())),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_push_evicts_oldest_chunks_over_limit() {
+        let mut hist = TerminalHistory::default();
+        hist.push(1, "aaaa".to_string(), 8);
+        hist.push(2, "bbbb".to_string(), 8);
+        hist.push(3, "cc".to_string(), 8);
+        let seqs: Vec<u64> = hist.chunks.iter().map(|(seq, _)| *seq).collect();
+        assert_eq!(seqs, vec![2, 3]);
+        assert_eq!(hist.bytes, 6);
+    }
+
+    #[test]
+    fn legacy_registry_entries_load_without_title() {
+        let registry: PersistedTerminalRegistry = serde_json::from_value(serde_json::json!({
+            "version": 1,
+            "sessions": {
+                "t1": { "cwd": "/tmp", "cols": 80, "rows": 24, "backend": "shell", "updatedAt": 5 }
+            }
+        }))
+        .unwrap();
+        let entry = &registry.sessions["t1"];
+        assert_eq!(entry.title, None);
+        assert_eq!(entry.created_at, 0);
+
+        assert_eq!(
+            sanitize_terminal_title(Some("  build\u{7}  ")).as_deref(),
+            Some("build")
+        );
+        assert_eq!(sanitize_terminal_title(Some("   ")), None);
+    }
+}
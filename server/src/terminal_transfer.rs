@@ -0,0 +1,270 @@
+use std::{
+    error::Error as _,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use axum::{
+    Json,
+    body::Body,
+    extract::{Path as AxumPath, Query, State},
+    http::StatusCode,
+    response::Response,
+};
+use bytes::Bytes;
+use futures_util::StreamExt;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::{ApiResult, AppError};
+
+/// Transfers stream to/from disk, so the cap can sit well above the buffered
+/// `/fs/upload` limit.
+pub(crate) const MAX_TERMINAL_TRANSFER_BYTES: usize = 1024 * 1024 * 1024;
+const DOWNLOAD_CHUNK_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TerminalTransferQuery {
+    pub path: Option<String>,
+    pub overwrite: Option<bool>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TerminalUploadResponse {
+    pub success: bool,
+    pub path: String,
+    pub bytes: u64,
+}
+
+fn map_io_error(err: std::io::Error) -> AppError {
+    match err.kind() {
+        std::io::ErrorKind::NotFound => AppError::not_found("File not found"),
+        std::io::ErrorKind::PermissionDenied => AppError::forbidden("Access denied"),
+        std::io::ErrorKind::IsADirectory => AppError::bad_request("Target path is a directory"),
+        _ => AppError::internal(err.to_string()),
+    }
+}
+
+/// Resolve `path` against the terminal session's cwd. Absolute paths are
+/// accepted as long as they stay inside that directory.
+async fn resolve_terminal_path(
+    state: &crate::AppState,
+    session_id: &str,
+    path: Option<&str>,
+) -> ApiResult<(PathBuf, PathBuf)> {
+    let (cwd, _) = state
+        .terminal
+        .peek_info(session_id)
+        .ok_or_else(|| AppError::not_found("Terminal session not found"))?;
+    let target = path
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| AppError::bad_request("Path is required"))?;
+
+    let base = crate::fs::validate_directory(&cwd).await?;
+    let resolved = crate::fs::resolve_path_within_workspace(&base, target)?;
+    Ok((base, resolved))
+}
+
+/// The route's body limit shows up as a stream error; any other stream
+/// error means the client went away or sent a broken body.
+fn upload_stream_error(err: axum::Error) -> AppError {
+    let mut source = err.source();
+    let mut over_limit = false;
+    while let Some(cause) = source {
+        over_limit |= cause.is::<http_body_util::LengthLimitError>();
+        source = cause.source();
+    }
+    if over_limit {
+        AppError::payload_too_large("File too large")
+    } else {
+        AppError::bad_request(format!("Upload interrupted: {err}"))
+    }
+}
+
+fn upload_temp_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "upload".to_string());
+    target.with_file_name(format!(".{name}.{}.upload", uuid::Uuid::new_v4().simple()))
+}
+
+/// Stream `body` into `target` via a sibling temp file so an interrupted upload
+/// never leaves a truncated file behind.
+async fn write_upload_stream(target: &Path, body: Body, overwrite: bool) -> ApiResult<u64> {
+    match tokio::fs::symlink_metadata(target).await {
+        Ok(meta) if meta.is_dir() => {
+            return Err(AppError::bad_request("Target path is a directory"));
+        }
+        Ok(_) if !overwrite => return Err(AppError::bad_request("File already exists")),
+        Ok(_) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(map_io_error(err)),
+    }
+
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(map_io_error)?;
+    }
+
+    let temp_path = upload_temp_path(target);
+    let result = async {
+        let mut file = tokio::fs::File::create(&temp_path)
+            .await
+            .map_err(map_io_error)?;
+        let mut written: u64 = 0;
+        let mut stream = body.into_data_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(upload_stream_error)?;
+            written += chunk.len() as u64;
+            if written > MAX_TERMINAL_TRANSFER_BYTES as u64 {
+                return Err(AppError::payload_too_large("File too large"));
+            }
+            file.write_all(&chunk).await.map_err(map_io_error)?;
+        }
+        file.flush().await.map_err(map_io_error)?;
+        drop(file);
+        tokio::fs::rename(&temp_path, target)
+            .await
+            .map_err(map_io_error)?;
+        Ok(written)
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+    result
+}
+
+pub async fn terminal_upload(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(session_id): AxumPath<String>,
+    Query(q): Query<TerminalTransferQuery>,
+    body: Body,
+) -> ApiResult<Json<TerminalUploadResponse>> {
+    let (base, resolved) =
+        resolve_terminal_path(state.as_ref(), &session_id, q.path.as_deref()).await?;
+
+    let bytes = write_upload_stream(&resolved, body, q.overwrite.unwrap_or(false)).await?;
+    crate::fs::publish_fs_changed_event(&base, "upload", [resolved.as_path()], None, None);
+
+    Ok(Json(TerminalUploadResponse {
+        success: true,
+        path: crate::fs::to_api_path(&resolved),
+        bytes,
+    }))
+}
+
+/// Stream a file from the session cwd. `content-length` is always set so
+/// clients can report download progress.
+pub async fn terminal_download(
+    State(state): State<Arc<crate::AppState>>,
+    AxumPath(session_id): AxumPath<String>,
+    Query(q): Query<TerminalTransferQuery>,
+) -> ApiResult<Response> {
+    let (_base, resolved) =
+        resolve_terminal_path(state.as_ref(), &session_id, q.path.as_deref()).await?;
+
+    let meta = tokio::fs::metadata(&resolved).await.map_err(map_io_error)?;
+    if !meta.is_file() {
+        return Err(AppError::bad_request("Specified path is not a file"));
+    }
+    if meta.len() > MAX_TERMINAL_TRANSFER_BYTES as u64 {
+        return Err(AppError::payload_too_large("File too large"));
+    }
+
+    let mut file = tokio::fs::File::open(&resolved)
+        .await
+        .map_err(map_io_error)?;
+    let stream = async_stream::stream! {
+        let mut buf = vec![0u8; DOWNLOAD_CHUNK_BYTES];
+        loop {
+            match file.read(&mut buf).await {
+                Ok(0) => break,
+                Ok(n) => yield Ok::<Bytes, std::io::Error>(Bytes::copy_from_slice(&buf[..n])),
+                Err(err) => {
+                    yield Err(err);
+                    break;
+                }
+            }
+        }
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("cache-control", "no-store")
+        .header("content-type", crate::fs::mime_for_ext(&resolved))
+        .header("content-length", meta.len())
+        .header(
+            "content-disposition",
+            crate::fs::content_disposition_attachment(&resolved),
+        )
+        .body(Body::from_stream(stream))
+        .unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn write_upload_stream_respects_overwrite_and_cleans_temp_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let target = dir.path().join("nested").join("config.toml");
+
+        let bytes = write_upload_stream(&target, Body::from("a = 1\n"), false)
+            .await
+            .expect("first upload");
+        assert_eq!(bytes, 6);
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "a = 1\n");
+
+        let err = write_upload_stream(&target, Body::from("a = 2\n"), false).await;
+        assert!(err.is_err());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "a = 1\n");
+
+        write_upload_stream(&target, Body::from("a = 2\n"), true)
+            .await
+            .expect("overwrite upload");
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "a = 2\n");
+
+        let leftovers = std::fs::read_dir(target.parent().unwrap())
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".upload"))
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[tokio::test]
+    async fn write_upload_stream_tells_limit_from_broken_body() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let target = dir.path().join("upload.bin");
+
+        let limited = Body::new(http_body_util::Limited::new(Body::from("0123456789"), 4));
+        let err = write_upload_stream(&target, limited, true)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::PayloadTooLarge { .. }));
+
+        let broken = Body::from_stream(futures_util::stream::iter([
+            Ok(Bytes::from_static(b"abc")),
+            Err(std::io::Error::other("reset")),
+        ]));
+        let err = write_upload_stream(&target, broken, true)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::BadRequest { .. }));
+        assert!(!target.exists());
+    }
+
+    #[tokio::test]
+    async fn write_upload_stream_rejects_directory_target() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let err = write_upload_stream(dir.path(), Body::from("x"), true).await;
+        assert!(err.is_err());
+    }
+}
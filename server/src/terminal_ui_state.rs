@@ -13,6 +13,7 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response, sse::Event},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tokio::sync::{Mutex as AsyncMutex, RwLock, broadcast};
@@ -29,14 +30,14 @@ fn now_millis() -> u64 {
         .unwrap_or(0)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct TerminalUiFolder {
     pub id: String,
     pub name: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct TerminalUiSessionMeta {
     #[serde(default)]
@@ -49,7 +50,7 @@ pub(crate) struct TerminalUiSessionMeta {
     pub last_used_at: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct TerminalUiState {
     #[serde(default)]
@@ -418,7 +419,7 @@ fn snapshot_payload(state: &TerminalUiState) -> String {
     .unwrap_or_else(|_| "{}".to_string())
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, JsonSchema)]
 pub(crate) struct TerminalUiStateEventsQuery {
     pub since: Option<String>,
 }
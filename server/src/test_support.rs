@@ -1,3 +1,55 @@
-use std::sync::{LazyLock, Mutex};
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex};
 
 pub(crate) static ENV_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+/// App state backed by a studio db in `db_dir`, with UI auth off and an
+/// OpenCode manager that never starts a process.
+pub(crate) async fn app_state(
+    db_dir: &Path,
+    settings: crate::settings::Settings,
+) -> Arc<crate::AppState> {
+    let studio_db = Arc::new(
+        crate::studio_db::StudioDb::open_at_path(db_dir.join("opencode.db"))
+            .await
+            .expect("open studio db"),
+    );
+
+    let workspace_preview_registry = Arc::new(
+        crate::workspace_preview_registry::WorkspacePreviewRegistry::new(studio_db.clone()),
+    );
+    let workspace_preview_runtime = Arc::new(
+        crate::workspace_preview_runtime::WorkspacePreviewRuntime::new(
+            workspace_preview_registry.clone(),
+        ),
+    );
+
+    let terminal = Arc::new(crate::terminal::TerminalManager::new(studio_db.clone()).await);
+
+    Arc::new(crate::AppState {
+        ui_auth: crate::ui_auth::UiAuth::Disabled,
+        ui_cookie_same_site: axum_extra::extract::cookie::SameSite::Strict,
+        cors_allowed_origins: Vec::new(),
+        cors_allow_all: false,
+        opencode: Arc::new(crate::opencode::OpenCodeManager::new(
+            "127.0.0.1".to_string(),
+            Some(1),
+            true,
+            None,
+            None,
+            crate::ui_auth::UiAuth::Disabled,
+        )),
+        plugin_runtime: Arc::new(crate::plugin_runtime::PluginRuntime::new()),
+        terminal,
+        attachment_cache: Arc::new(crate::attachment_cache::AttachmentCacheManager::new(
+            studio_db.clone(),
+        )),
+        session_activity: crate::session_activity::SessionActivityManager::new(),
+        directory_session_index: crate::directory_session_index::DirectorySessionIndexManager::new(
+        ),
+        workspace_preview_registry,
+        workspace_preview_runtime,
+        studio_db,
+        settings: Arc::new(tokio::sync::RwLock::new(settings)),
+    })
+}
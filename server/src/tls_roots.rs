@@ -0,0 +1,290 @@
+use std::error::Error as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::Extensions,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{ApiResult, AppError};
+
+const TLS_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+const CERT_FILE_EXTENSIONS: &[&str] = &["pem", "crt", "cer"];
+
+/// One configured `--ca-cert` entry and what was loaded from it.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CaSourceReport {
+    pub path: String,
+    pub certificates: usize,
+    pub errors: Vec<String>,
+}
+
+struct ExtraRoots {
+    certs: Vec<reqwest::Certificate>,
+    sources: Vec<CaSourceReport>,
+}
+
+static EXTRA_ROOTS: OnceLock<ExtraRoots> = OnceLock::new();
+
+fn cert_files_in(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| {
+                        CERT_FILE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+                    })
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn load_source(raw: &str) -> (Vec<reqwest::Certificate>, CaSourceReport) {
+    let path = PathBuf::from(crate::path_utils::normalize_directory_path(raw));
+    let mut report = CaSourceReport {
+        path: path.to_string_lossy().into_owned(),
+        certificates: 0,
+        errors: Vec::new(),
+    };
+
+    let files = if path.is_dir() {
+        match cert_files_in(&path) {
+            Ok(files) => files,
+            Err(err) => {
+                report.errors.push(err.to_string());
+                return (Vec::new(), report);
+            }
+        }
+    } else {
+        vec![path]
+    };
+
+    let mut certs = Vec::new();
+    for file in files {
+        let loaded = std::fs::read(&file)
+            .map_err(|err| err.to_string())
+            .and_then(|pem| {
+                reqwest::Certificate::from_pem_bundle(&pem).map_err(|err| err.to_string())
+            });
+        match loaded {
+            Ok(found) if found.is_empty() => report
+                .errors
+                .push(format!("{}: no PEM certificates found", file.display())),
+            Ok(found) => {
+                report.certificates += found.len();
+                certs.extend(found);
+            }
+            Err(err) => report.errors.push(format!("{}: {err}", file.display())),
+        }
+    }
+    (certs, report)
+}
+
+/// Load extra root CAs (PEM files or directories of them). Call once at
+/// startup, before any HTTP client is built; unreadable entries are logged
+/// and skipped.
+pub(crate) fn init(paths: &[String]) {
+    let mut certs = Vec::new();
+    let mut sources = Vec::new();
+    for raw in paths.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let (loaded, report) = load_source(raw);
+        for error in &report.errors {
+            tracing::warn!(source = %report.path, error = %error, "Failed to load CA certificate");
+        }
+        certs.extend(loaded);
+        sources.push(report);
+    }
+    if !certs.is_empty() {
+        tracing::info!(
+            certificates = certs.len(),
+            "Trusting extra root CA certificates"
+        );
+    }
+    let _ = EXTRA_ROOTS.set(ExtraRoots { certs, sources });
+}
+
+fn extra_root_certs() -> &'static [reqwest::Certificate] {
+    EXTRA_ROOTS
+        .get()
+        .map(|roots| roots.certs.as_slice())
+        .unwrap_or_default()
+}
+
+/// `reqwest::Client::builder()` with the configured extra roots merged into
+/// the built-in trust store. Use this for every outbound client.
+pub(crate) fn client_builder() -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder();
+    let certs = extra_root_certs();
+    if certs.is_empty() {
+        return builder;
+    }
+    builder.tls_certs_merge(certs.iter().cloned())
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TlsProbeQuery {
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsProbeResult {
+    pub ok: bool,
+    pub status: Option<u16>,
+    /// `certificate`, `connect`, `timeout` or `request`.
+    pub error_kind: Option<&'static str>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsProbeResponse {
+    pub url: String,
+    pub extra_roots: Vec<CaSourceReport>,
+    pub with_extra_roots: TlsProbeResult,
+    /// Only probed when extra roots are configured, to show whether they are
+    /// what makes verification pass.
+    pub without_extra_roots: Option<TlsProbeResult>,
+}
+
+pub(crate) fn error_chain_text(err: &reqwest::Error) -> String {
+    let mut text = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        text.push_str(": ");
+        text.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    text
+}
+
+pub(crate) fn classify_probe_error(err: &reqwest::Error, text: &str) -> &'static str {
+    let lower = text.to_ascii_lowercase();
+    if lower.contains("certificate") || lower.contains("unknownissuer") || lower.contains("tls") {
+        "certificate"
+    } else if err.is_timeout() {
+        "timeout"
+    } else if err.is_connect() {
+        "connect"
+    } else {
+        "request"
+    }
+}
+
+async fn probe(builder: reqwest::ClientBuilder, url: &str) -> TlsProbeResult {
+    let client = match builder.timeout(TLS_PROBE_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(err) => {
+            return TlsProbeResult {
+                ok: false,
+                status: None,
+                error_kind: Some("request"),
+                error: Some(error_chain_text(&err)),
+            };
+        }
+    };
+    match client.head(url).send().await {
+        Ok(resp) => TlsProbeResult {
+            ok: true,
+            status: Some(resp.status().as_u16()),
+            error_kind: None,
+            error: None,
+        },
+        Err(err) => {
+            let text = error_chain_text(&err);
+            TlsProbeResult {
+                ok: false,
+                status: None,
+                error_kind: Some(classify_probe_error(&err, &text)),
+                error: Some(text),
+            }
+        }
+    }
+}
+
+/// Report whether TLS verification succeeds for `url` with the configured
+/// trust store. Admin-only: it makes the server connect to any https host.
+pub(crate) async fn tls_probe_get(
+    State(state): State<Arc<crate::AppState>>,
+    extensions: Extensions,
+    Query(q): Query<TlsProbeQuery>,
+) -> ApiResult<Json<TlsProbeResponse>> {
+    crate::ui_auth::require_admin(&state.ui_auth, &extensions)?;
+    let raw = q
+        .url
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| AppError::bad_request("url is required"))?;
+    let url = url::Url::parse(raw).map_err(|_| AppError::bad_request("Invalid url"))?;
+    if url.scheme() != "https" {
+        return Err(AppError::bad_request("url must use https"));
+    }
+    let url = url.to_string();
+
+    let with_extra_roots = probe(client_builder(), &url).await;
+    let without_extra_roots = if extra_root_certs().is_empty() {
+        None
+    } else {
+        Some(probe(reqwest::Client::builder(), &url).await)
+    };
+
+    Ok(Json(TlsProbeResponse {
+        url,
+        extra_roots: EXTRA_ROOTS
+            .get()
+            .map(|roots| roots.sources.clone())
+            .unwrap_or_default(),
+        with_extra_roots,
+        without_extra_roots,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Self-signed test CA (CN=opencode-studio-test-ca).
+    const TEST_CA_PEM: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIBmzCCAUGgAwIBAgIUWnQ0eBEqgarSqFnpKB4AvOrNd7swCgYIKoZIzj0EAwIw
+IjEgMB4GA1UEAwwXb3BlbmNvZGUtc3R1ZGlvLXRlc3QtY2EwIBcNMjYxMDE2MDgy
+NjAzWhgPMjEyNjA5MjIwODI2MDNaMCIxIDAeBgNVBAMMF29wZW5jb2RlLXN0dWRp
+by10ZXN0LWNhMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEJdA7lq6kQ5IL5u6B
+/toEiCKVS7QmCNEHgmKDZvmdokz1vMM0C2dRDe3Ji1XBoXI60s/bKIGy3sDwQICc
+qvP796NTMFEwHQYDVR0OBBYEFI/lrDwC2AHoLG1HkmQktg9woulHMB8GA1UdIwQY
+MBaAFI/lrDwC2AHoLG1HkmQktg9woulHMA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZI
+zj0EAwIDSAAwRQIhAINmZHZXyn25zLA7YH0+fvKpNsAlswiZWH4W0NeNrkZ2AiAp
+jwTI/c5o6fZvqQfpqN08NprtIWdQwEMO509x/ytQ4A==
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn load_source_reads_pem_files_from_directory() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("corp.crt"), TEST_CA_PEM).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+        std::fs::write(dir.path().join("empty.pem"), "").unwrap();
+
+        let (certs, report) = load_source(&dir.path().to_string_lossy());
+        assert_eq!(certs.len(), 1);
+        assert_eq!(report.certificates, 1);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("empty.pem"));
+
+        let (certs, report) = load_source(&dir.path().join("missing.pem").to_string_lossy());
+        assert!(certs.is_empty());
+        assert_eq!(report.errors.len(), 1);
+    }
+}
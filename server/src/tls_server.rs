@@ -0,0 +1,216 @@
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use axum::{
+    Router,
+    extract::{ConnectInfo, Request, connect_info::Connected},
+    http::{HeaderMap, StatusCode, Uri, header},
+    response::IntoResponse,
+    serve::{IncomingStream, Listener},
+};
+use rustls::ServerConfig;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::{TlsAcceptor, server::TlsStream};
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const PENDING_CONNECTIONS: usize = 128;
+
+static SERVING_HTTPS: AtomicBool = AtomicBool::new(false);
+
+/// Whether this process terminates TLS itself, so every request arrived over
+/// HTTPS and session cookies can be marked Secure.
+pub(crate) fn serving_https() -> bool {
+    SERVING_HTTPS.load(Ordering::Relaxed)
+}
+
+/// Build the rustls server config from a PEM certificate chain and private key.
+pub(crate) fn load_server_config(cert: &Path, key: &Path) -> Result<Arc<ServerConfig>, String> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| format!("failed to read TLS certificate {}: {err}", cert.display()))?;
+    if certs.is_empty() {
+        return Err(format!("no certificates found in {}", cert.display()));
+    }
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|err| format!("failed to read TLS key {}: {err}", key.display()))?;
+
+    let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+    let mut config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|err| format!("failed to configure TLS: {err}"))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|err| format!("invalid TLS certificate/key pair: {err}"))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// TCP listener that completes TLS handshakes off the accept loop, so a slow
+/// or stalled client cannot hold up other connections.
+pub(crate) struct TlsListener {
+    local_addr: io::Result<SocketAddr>,
+    ready: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+}
+
+impl TlsListener {
+    pub(crate) fn new(listener: TcpListener, config: Arc<ServerConfig>) -> Self {
+        SERVING_HTTPS.store(true, Ordering::Relaxed);
+        let local_addr = listener.local_addr();
+        let acceptor = TlsAcceptor::from(config);
+        let (tx, ready) = mpsc::channel(PENDING_CONNECTIONS);
+        tokio::spawn(async move {
+            loop {
+                let (stream, addr) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        tracing::debug!(target: "opencode_studio.tls", error = %err, "accept failed");
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        continue;
+                    }
+                };
+                if tx.is_closed() {
+                    break;
+                }
+                let acceptor = acceptor.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(tls)) => {
+                            let _ = tx.send((tls, addr)).await;
+                        }
+                        Ok(Err(err)) => {
+                            tracing::debug!(target: "opencode_studio.tls", peer = %addr, error = %err, "TLS handshake failed");
+                        }
+                        Err(_) => {
+                            tracing::debug!(target: "opencode_studio.tls", peer = %addr, "TLS handshake timed out");
+                        }
+                    }
+                });
+            }
+        });
+        Self { local_addr, ready }
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.ready.recv().await {
+            Some(accepted) => accepted,
+            // The accept task only stops once this listener is gone.
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        match &self.local_addr {
+            Ok(addr) => Ok(*addr),
+            Err(err) => Err(io::Error::new(err.kind(), err.to_string())),
+        }
+    }
+}
+
+/// Peer address of a TLS connection; see [`expose_peer_addr`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TlsPeer(SocketAddr);
+
+impl Connected<IncomingStream<'_, TlsListener>> for TlsPeer {
+    fn connect_info(stream: IncomingStream<'_, TlsListener>) -> Self {
+        Self(*stream.remote_addr())
+    }
+}
+
+/// Re-publish the TLS peer as `ConnectInfo<SocketAddr>`, which is what the
+/// rest of the app reads for plain HTTP connections.
+pub(crate) async fn expose_peer_addr(
+    ConnectInfo(peer): ConnectInfo<TlsPeer>,
+    mut req: Request,
+) -> Request {
+    req.extensions_mut().insert(ConnectInfo(peer.0));
+    req
+}
+
+fn https_location(headers: &HeaderMap, uri: &Uri, https_port: u16) -> Option<String> {
+    let host = headers.get(header::HOST)?.to_str().ok()?.trim();
+    let authority: Uri = format!("http://{host}").parse().ok()?;
+    let hostname = authority.host()?;
+    let path = uri.path_and_query().map_or("/", |pq| pq.as_str());
+    Some(if https_port == 443 {
+        format!("https://{hostname}{path}")
+    } else {
+        format!("https://{hostname}:{https_port}{path}")
+    })
+}
+
+/// Plain-HTTP app that sends every request to the same host over HTTPS.
+pub(crate) fn redirect_to_https_router(https_port: u16) -> Router {
+    Router::new().fallback(move |headers: HeaderMap, uri: Uri| async move {
+        match https_location(&headers, &uri, https_port) {
+            Some(location) => (
+                StatusCode::PERMANENT_REDIRECT,
+                [(header::LOCATION, location)],
+            )
+                .into_response(),
+            None => (StatusCode::BAD_REQUEST, "Missing Host header").into_response(),
+        }
+    })
+}
+
+/// Serve [`redirect_to_https_router`] on `addr` in the background.
+pub(crate) async fn spawn_https_redirect(addr: SocketAddr, https_port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!("Redirecting http://{} to HTTPS", addr);
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, redirect_to_https_router(https_port))
+            .with_graceful_shutdown(crate::graceful_shutdown::wait_for_shutdown_signal())
+            .await
+        {
+            tracing::warn!(error = %err, "HTTPS redirect listener stopped");
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn https_location_keeps_host_and_path() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, "studio.lan:8080".parse().unwrap());
+        let uri: Uri = "/api/health?x=1".parse().unwrap();
+        assert_eq!(
+            https_location(&headers, &uri, 8443).as_deref(),
+            Some("https://studio.lan:8443/api/health?x=1")
+        );
+        assert_eq!(
+            https_location(&headers, &uri, 443).as_deref(),
+            Some("https://studio.lan/api/health?x=1")
+        );
+
+        headers.insert(header::HOST, "[::1]:80".parse().unwrap());
+        assert_eq!(
+            https_location(&headers, &"/".parse().unwrap(), 8443).as_deref(),
+            Some("https://[::1]:8443/")
+        );
+        assert_eq!(https_location(&HeaderMap::new(), &uri, 443), None);
+    }
+
+    #[test]
+    fn load_server_config_reports_missing_files() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let cert = tmp.path().join("cert.pem");
+        std::fs::write(&cert, "not a certificate").unwrap();
+        let err = load_server_config(&cert, &tmp.path().join("key.pem")).unwrap_err();
+        assert!(err.contains("no certificates found"), "{err}");
+    }
+}
@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{body::Body, extract::Path as AxumPath, http::StatusCode, response::Response};
+use serde_json::{Value, json};
+use sha2::{Digest as _, Sha256};
+
+use dashmap::DashMap;
+
+use crate::{ApiResult, AppError};
+
+pub(crate) const DEFAULT_TOOL_OUTPUT_MAX_BYTES: usize = 64 * 1024;
+/// Upper bound accepted from settings (per tool and default).
+pub(crate) const MAX_TOOL_OUTPUT_LIMIT_BYTES: i64 = 64 * 1024 * 1024;
+// Head+tail needs room for the omission marker to be useful.
+const MIN_TOOL_OUTPUT_MAX_BYTES: usize = 1024;
+/// Archives are pruned oldest first once they add up to more than this.
+const ARCHIVE_MAX_BYTES: u64 = 512 * 1024 * 1024;
+/// Archives older than this are pruned regardless of total size.
+const ARCHIVE_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Archive ids this process has written or is writing, so each completed
+/// part is archived once rather than on every serve.
+static ARCHIVED: LazyLock<DashMap<String, ()>> = LazyLock::new(DashMap::new);
+
+/// Per-tool caps on `state.output` for served tool parts. Oversized output
+/// keeps its head and tail; once the part has completed, the full text is
+/// archived and can be fetched via `/api/tool-output/{archiveId}`.
+#[derive(Debug, Clone)]
+pub(crate) struct ToolOutputRetention {
+    /// `None` means unlimited.
+    default_max_bytes: Option<usize>,
+    per_tool: HashMap<String, Option<usize>>,
+    archive_dir: Option<PathBuf>,
+}
+
+fn limit_from_value(value: &Value) -> Option<Option<usize>> {
+    let n = value.as_u64()?;
+    if n == 0 {
+        return Some(None);
+    }
+    Some(Some((n as usize).max(MIN_TOOL_OUTPUT_MAX_BYTES)))
+}
+
+impl ToolOutputRetention {
+    pub(crate) fn from_settings(settings: &crate::settings::Settings) -> Option<Self> {
+        let enabled = settings
+            .extra
+            .get("chatToolOutputRetention")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if !enabled {
+            return None;
+        }
+
+        let default_max_bytes = settings
+            .extra
+            .get("chatToolOutputRetentionMaxBytes")
+            .and_then(limit_from_value)
+            .unwrap_or(Some(DEFAULT_TOOL_OUTPUT_MAX_BYTES));
+
+        let mut per_tool = HashMap::new();
+        if let Some(map) = settings
+            .extra
+            .get("chatToolOutputRetentionToolLimits")
+            .and_then(|v| v.as_object())
+        {
+            for (tool, limit) in map {
+                let tool = tool.trim().to_ascii_lowercase();
+                if tool.is_empty() {
+                    continue;
+                }
+                if let Some(limit) = limit_from_value(limit) {
+                    per_tool.insert(tool, limit);
+                }
+            }
+        }
+
+        Some(Self {
+            default_max_bytes,
+            per_tool,
+            archive_dir: Some(crate::persistence_paths::tool_output_archive_dir()),
+        })
+    }
+
+    fn max_bytes_for(&self, tool_id: &str) -> Option<usize> {
+        self.per_tool
+            .get(tool_id)
+            .copied()
+            .unwrap_or(self.default_max_bytes)
+    }
+
+    /// Enforce the limit on a tool part in place, recording what was dropped
+    /// under `ocOutputRetention`. Output of a running part is still growing,
+    /// so only completed parts get an `archiveId`.
+    pub(crate) fn apply(&self, part: &mut Value) {
+        let Some(obj) = part.as_object_mut() else {
+            return;
+        };
+        let tool_id = obj
+            .get("tool")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        let Some(max_bytes) = self.max_bytes_for(&tool_id) else {
+            return;
+        };
+        let Some(state) = obj.get_mut("state").and_then(|v| v.as_object_mut()) else {
+            return;
+        };
+        let Some(output) = state.get("output").and_then(|v| v.as_str()) else {
+            return;
+        };
+        let Some((kept, omitted)) = truncate_head_tail(output, max_bytes) else {
+            return;
+        };
+
+        let original_bytes = output.len();
+        let completed = state.get("status").and_then(|v| v.as_str()) == Some("completed");
+        let archive_id = match self.archive_dir.as_deref() {
+            Some(dir) if completed => Some(schedule_archive(dir, output)),
+            _ => None,
+        };
+        state.insert("output".to_string(), Value::String(kept));
+        obj.insert(
+            "ocOutputRetention".to_string(),
+            json!({
+                "originalBytes": original_bytes,
+                "omittedBytes": omitted,
+                "archiveId": archive_id,
+            }),
+        );
+    }
+}
+
+fn floor_char_boundary(text: &str, mut idx: usize) -> usize {
+    while idx > 0 && !text.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    idx
+}
+
+fn ceil_char_boundary(text: &str, mut idx: usize) -> usize {
+    while idx < text.len() && !text.is_char_boundary(idx) {
+        idx += 1;
+    }
+    idx
+}
+
+/// Keep the first and last `max_bytes / 2` bytes of `text` (on char
+/// boundaries). Returns `None` when `text` already fits.
+pub(crate) fn truncate_head_tail(text: &str, max_bytes: usize) -> Option<(String, usize)> {
+    if text.len() <= max_bytes {
+        return None;
+    }
+
+    let head_end = floor_char_boundary(text, max_bytes / 2);
+    let tail_start = ceil_char_boundary(text, text.len() - (max_bytes - max_bytes / 2));
+    let omitted = tail_start - head_end;
+    let mut out = String::with_capacity(max_bytes + 64);
+    out.push_str(&text[..head_end]);
+    out.push_str(&format!("\n\n… {omitted} bytes omitted …\n\n"));
+    out.push_str(&text[tail_start..]);
+    Some((out, omitted))
+}
+
+fn archive_path(dir: &Path, archive_id: &str) -> PathBuf {
+    dir.join(format!("{archive_id}.txt"))
+}
+
+/// Content-addressed id of `output`; writes the archive in the background
+/// unless this process already has. The file may briefly lag the response.
+fn schedule_archive(dir: &Path, output: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(output.as_bytes());
+    let archive_id = format!("{:x}", hasher.finalize());
+    if ARCHIVED.insert(archive_id.clone(), ()).is_some() {
+        return archive_id;
+    }
+
+    let dir = dir.to_path_buf();
+    let output = output.to_string();
+    let id = archive_id.clone();
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn_blocking(move || write_archive(&dir, &id, &output));
+        }
+        Err(_) => write_archive(&dir, &id, &output),
+    }
+    archive_id
+}
+
+fn write_archive(dir: &Path, archive_id: &str, output: &str) {
+    let path = archive_path(dir, archive_id);
+    if path.is_file() {
+        return;
+    }
+
+    let write = || -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let tmp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4().simple()));
+        std::fs::write(&tmp, output.as_bytes())?;
+        std::fs::rename(&tmp, &path)
+    };
+    match write() {
+        Ok(()) => prune_archives(dir, ARCHIVE_MAX_BYTES, ARCHIVE_MAX_AGE),
+        Err(err) => {
+            ARCHIVED.remove(archive_id);
+            tracing::warn!(
+                path = %path.display(),
+                error = %err,
+                "failed to archive tool output"
+            );
+        }
+    }
+}
+
+/// Drop archives older than `max_age`, then the oldest ones until the rest
+/// fit in 90% of `max_bytes`.
+fn prune_archives(dir: &Path, max_bytes: u64, max_age: Duration) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            meta.is_file().then(|| {
+                (
+                    meta.modified().unwrap_or(UNIX_EPOCH),
+                    meta.len(),
+                    entry.path(),
+                )
+            })
+        })
+        .collect();
+    files.sort_by_key(|(modified, _, _)| *modified);
+
+    let cutoff = SystemTime::now().checked_sub(max_age).unwrap_or(UNIX_EPOCH);
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    let target = if total > max_bytes {
+        max_bytes.saturating_mul(90) / 100
+    } else {
+        max_bytes
+    };
+    for (modified, len, path) in files {
+        if modified >= cutoff && total <= target {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total = total.saturating_sub(len);
+            if let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) {
+                ARCHIVED.remove(id);
+            }
+        }
+    }
+}
+
+/// Archive a tool part's output when its completion comes through the
+/// upstream event stream, whether or not any client is watching.
+pub(crate) fn observe_event(state: &Arc<crate::AppState>, payload: &Value) {
+    if payload.get("type").and_then(|v| v.as_str()) != Some("message.part.updated") {
+        return;
+    }
+    let Some(part) = payload
+        .get("properties")
+        .and_then(|props| props.get("part"))
+    else {
+        return;
+    };
+    let completed = part
+        .get("state")
+        .and_then(|state| state.get("status"))
+        .and_then(|v| v.as_str())
+        == Some("completed");
+    if part.get("type").and_then(|v| v.as_str()) != Some("tool") || !completed {
+        return;
+    }
+
+    let state = state.clone();
+    let mut part = part.clone();
+    tokio::spawn(async move {
+        let retention = {
+            let settings = state.settings.read().await;
+            ToolOutputRetention::from_settings(&settings)
+        };
+        if let Some(retention) = retention {
+            let _ = tokio::task::spawn_blocking(move || retention.apply(&mut part)).await;
+        }
+    });
+}
+
+fn is_archive_id(raw: &str) -> bool {
+    raw.len() == 64 && raw.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+pub(crate) async fn tool_output_archive_get(
+    AxumPath(archive_id): AxumPath<String>,
+) -> ApiResult<Response> {
+    let archive_id = archive_id.trim().to_ascii_lowercase();
+    if !is_archive_id(&archive_id) {
+        return Err(AppError::bad_request("Invalid archive id"));
+    }
+
+    let path = archive_path(
+        &crate::persistence_paths::tool_output_archive_dir(),
+        &archive_id,
+    );
+    let content = tokio::fs::read(&path)
+        .await
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => AppError::not_found("Archived output not found"),
+            _ => AppError::internal(err.to_string()),
+        })?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("cache-control", "private, max-age=31536000, immutable")
+        .header("content-type", "text/plain; charset=utf-8")
+        .body(Body::from(content))
+        .unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_head_tail_keeps_both_ends_on_char_boundaries() {
+        assert!(truncate_head_tail("short", 10).is_none());
+
+        let text = format!("{}{}", "é".repeat(600), "z".repeat(600));
+        let (kept, omitted) = truncate_head_tail(&text, 1000).expect("truncated");
+        assert!(kept.starts_with("éé"));
+        assert!(kept.ends_with(&"z".repeat(500)));
+        assert!(kept.contains(&format!("{omitted} bytes omitted")));
+        assert_eq!(omitted, text.len() - 500 - 500);
+    }
+
+    #[test]
+    fn apply_archives_full_output_and_honors_per_tool_limits() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let retention = ToolOutputRetention {
+            default_max_bytes: Some(1024),
+            per_tool: HashMap::from([("read".to_string(), None)]),
+            archive_dir: Some(dir.path().to_path_buf()),
+        };
+        let output = "line\n".repeat(1000);
+
+        let mut bash = json!({
+            "type": "tool",
+            "tool": "bash",
+            "state": {"status": "completed", "output": output}
+        });
+        retention.apply(&mut bash);
+        let kept = bash["state"]["output"].as_str().unwrap();
+        assert!(kept.len() < 1200);
+        assert_eq!(bash["ocOutputRetention"]["originalBytes"], 5000);
+        let archive_id = bash["ocOutputRetention"]["archiveId"].as_str().unwrap();
+        assert!(is_archive_id(archive_id));
+        assert_eq!(
+            std::fs::read_to_string(archive_path(dir.path(), archive_id)).unwrap(),
+            output
+        );
+
+        let mut read = json!({
+            "type": "tool",
+            "tool": "read",
+            "state": {"status": "completed", "output": output}
+        });
+        retention.apply(&mut read);
+        assert_eq!(read["state"]["output"].as_str().unwrap().len(), 5000);
+        assert!(read.get("ocOutputRetention").is_none());
+    }
+
+    #[test]
+    fn apply_truncates_running_parts_without_archiving() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let retention = ToolOutputRetention {
+            default_max_bytes: Some(1024),
+            per_tool: HashMap::new(),
+            archive_dir: Some(dir.path().to_path_buf()),
+        };
+        let mut part = json!({
+            "type": "tool",
+            "tool": "bash",
+            "state": {"status": "running", "output": "partial\n".repeat(1000)}
+        });
+        retention.apply(&mut part);
+
+        assert!(part["state"]["output"].as_str().unwrap().len() < 1200);
+        assert!(part["ocOutputRetention"]["archiveId"].is_null());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn prune_archives_drops_expired_then_oldest_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let now = SystemTime::now();
+        let write = |name: &str, len: usize, age_secs: u64| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, vec![b'x'; len]).unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(now - Duration::from_secs(age_secs))
+                .unwrap();
+        };
+        write("expired.txt", 10, 10_000);
+        write("oldest.txt", 400, 300);
+        write("older.txt", 400, 200);
+        write("newest.txt", 400, 100);
+
+        prune_archives(dir.path(), 1000, Duration::from_secs(1000));
+
+        let mut left: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["newest.txt", "older.txt"]);
+    }
+}
@@ -0,0 +1,289 @@
+use serde_json::{Map, Value, json};
+
+/// Skip detection for very large outputs; parsing them would cost more than the
+/// collapsed row saves.
+const MAX_TABLE_SOURCE_BYTES: usize = 2 * 1024 * 1024;
+const MAX_TABLE_COLUMNS: usize = 64;
+const MAX_TABLE_CELL_CHARS: usize = 512;
+
+pub(crate) const DEFAULT_TABLE_PREVIEW_ROWS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TableFormat {
+    Csv,
+    Tsv,
+    Json,
+}
+
+impl TableFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
+            Self::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DetectedTable {
+    format: TableFormat,
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+    total_rows: usize,
+}
+
+impl DetectedTable {
+    fn into_value(self, max_rows: usize) -> Value {
+        let truncated = self.total_rows > max_rows;
+        let rows = self
+            .rows
+            .into_iter()
+            .take(max_rows)
+            .map(Value::Array)
+            .collect::<Vec<_>>();
+        json!({
+            "format": self.format.as_str(),
+            "columns": self.columns,
+            "rows": rows,
+            "totalRows": self.total_rows,
+            "truncated": truncated,
+        })
+    }
+}
+
+/// Detect a CSV/TSV/JSON table in tool output text and return the structured
+/// preview (`format`, `columns`, up to `max_rows` rows, `totalRows`).
+pub(crate) fn detect_table(text: &str, max_rows: usize) -> Option<Value> {
+    let trimmed = text.trim();
+    if trimmed.is_empty() || trimmed.len() > MAX_TABLE_SOURCE_BYTES || max_rows == 0 {
+        return None;
+    }
+
+    let detected = if trimmed.starts_with('[') {
+        detect_json_table(trimmed)
+    } else {
+        detect_delimited_table(trimmed)
+    }?;
+    Some(detected.into_value(max_rows))
+}
+
+/// Attach `table` to a tool part when its output looks tabular.
+///
+/// Must run before collapsed-summary pruning drops `state.output`.
+pub(crate) fn attach_tool_output_table(part: &mut Value, max_rows: usize) {
+    let Some(obj) = part.as_object_mut() else {
+        return;
+    };
+    let table = obj
+        .get("state")
+        .and_then(|v| v.get("output"))
+        .and_then(|v| v.as_str())
+        .and_then(|output| detect_table(output, max_rows));
+    if let Some(table) = table {
+        obj.insert("table".to_string(), table);
+    }
+}
+
+fn truncate_cell(input: &str) -> String {
+    match input.char_indices().nth(MAX_TABLE_CELL_CHARS) {
+        Some((idx, _)) => input[..idx].to_string(),
+        None => input.to_string(),
+    }
+}
+
+fn json_cell(value: Option<&Value>) -> Value {
+    match value {
+        None | Some(Value::Null) => Value::Null,
+        Some(Value::String(s)) => Value::String(truncate_cell(s)),
+        Some(v @ (Value::Number(_) | Value::Bool(_))) => v.clone(),
+        Some(other) => Value::String(truncate_cell(&other.to_string())),
+    }
+}
+
+fn detect_json_table(text: &str) -> Option<DetectedTable> {
+    let value = serde_json::from_str::<Value>(text).ok()?;
+    let items = value.as_array()?;
+    if items.is_empty() {
+        return None;
+    }
+
+    let mut objects = Vec::<&Map<String, Value>>::with_capacity(items.len());
+    for item in items {
+        objects.push(item.as_object()?);
+    }
+
+    let mut columns = Vec::<String>::new();
+    for obj in objects.iter() {
+        for key in obj.keys() {
+            if columns.len() >= MAX_TABLE_COLUMNS {
+                break;
+            }
+            if !columns.iter().any(|c| c == key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    if columns.is_empty() {
+        return None;
+    }
+
+    let rows = objects
+        .iter()
+        .map(|obj| columns.iter().map(|c| json_cell(obj.get(c))).collect())
+        .collect::<Vec<_>>();
+
+    Some(DetectedTable {
+        format: TableFormat::Json,
+        columns,
+        total_rows: rows.len(),
+        rows,
+    })
+}
+
+/// RFC 4180-style record splitting: quoted fields may contain delimiters,
+/// doubled quotes, and newlines.
+fn parse_delimited_records(text: &str, delimiter: char) -> Option<Vec<Vec<String>>> {
+    let mut records = Vec::<Vec<String>>::new();
+    let mut record = Vec::<String>::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            if ch == '"' {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(ch);
+            }
+            continue;
+        }
+
+        match ch {
+            '"' if field.is_empty() => in_quotes = true,
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if !(record.len() == 1 && record[0].trim().is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            c if c == delimiter => record.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return None;
+    }
+    record.push(field);
+    if !(record.len() == 1 && record[0].trim().is_empty()) {
+        records.push(record);
+    }
+    Some(records)
+}
+
+fn detect_delimited_table(text: &str) -> Option<DetectedTable> {
+    let header_line = text.lines().next()?;
+    let (format, delimiter) = if header_line.contains('\t') {
+        (TableFormat::Tsv, '\t')
+    } else if header_line.contains(',') {
+        (TableFormat::Csv, ',')
+    } else {
+        return None;
+    };
+
+    let mut records = parse_delimited_records(text, delimiter)?.into_iter();
+    let header = records.next()?;
+    let width = header.len();
+    if !(2..=MAX_TABLE_COLUMNS).contains(&width) {
+        return None;
+    }
+    let columns = header
+        .into_iter()
+        .map(|c| truncate_cell(c.trim()))
+        .collect::<Vec<_>>();
+    if columns.iter().any(|c| c.is_empty()) {
+        return None;
+    }
+
+    let mut rows = Vec::<Vec<Value>>::new();
+    for record in records {
+        // Prose with stray commas rarely keeps a consistent column count.
+        if record.len() != width {
+            return None;
+        }
+        rows.push(
+            record
+                .into_iter()
+                .map(|cell| Value::String(truncate_cell(cell.trim())))
+                .collect(),
+        );
+    }
+    if rows.is_empty() {
+        return None;
+    }
+
+    Some(DetectedTable {
+        format,
+        columns,
+        total_rows: rows.len(),
+        rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_table_parses_csv_with_quoted_fields() {
+        let table = detect_table("name,note\nalice,\"a, b\"\nbob,\"say \"\"hi\"\"\"\n", 10)
+            .expect("csv table");
+        assert_eq!(table["format"], "csv");
+        assert_eq!(table["columns"], json!(["name", "note"]));
+        assert_eq!(table["rows"][0], json!(["alice", "a, b"]));
+        assert_eq!(table["rows"][1], json!(["bob", "say \"hi\""]));
+        assert_eq!(table["totalRows"], 2);
+        assert_eq!(table["truncated"], false);
+    }
+
+    #[test]
+    fn detect_table_limits_rows_and_reports_total() {
+        let mut text = "a\tb\n".to_string();
+        for i in 0..5 {
+            text.push_str(&format!("{i}\tx\n"));
+        }
+        let table = detect_table(&text, 2).expect("tsv table");
+        assert_eq!(table["format"], "tsv");
+        assert_eq!(table["rows"].as_array().map(|r| r.len()), Some(2));
+        assert_eq!(table["totalRows"], 5);
+        assert_eq!(table["truncated"], true);
+    }
+
+    #[test]
+    fn detect_table_parses_json_array_of_objects() {
+        let table =
+            detect_table(r#"[{"id":1,"tags":["x"]},{"id":2,"name":"b"}]"#, 10).expect("json table");
+        assert_eq!(table["format"], "json");
+        assert_eq!(table["columns"], json!(["id", "tags", "name"]));
+        assert_eq!(table["rows"][0], json!([1, "[\"x\"]", null]));
+        assert_eq!(table["rows"][1], json!([2, null, "b"]));
+    }
+
+    #[test]
+    fn detect_table_rejects_prose_and_ragged_rows() {
+        assert!(detect_table("Hello, world.\nThis is fine, really, ok.", 10).is_none());
+        assert!(detect_table("just some text", 10).is_none());
+        assert!(detect_table("[1, 2, 3]", 10).is_none());
+        assert!(detect_table("a,b\n", 10).is_none());
+    }
+}
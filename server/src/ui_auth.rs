@@ -1,10 +1,6 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use argon2::{
-    Argon2,
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-};
 use axum::{
     Json,
     extract::State,
@@ -14,9 +10,13 @@ use axum::{
 };
 use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
 use dashmap::DashMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
+use crate::client_addr::ClientAddr;
+use crate::ui_users::{UiRole, UiUser, hash_password, verify_password};
+
 const UI_COOKIE_NAME: &str = "oc_ui_session";
 const UI_SESSION_TTL: Duration = Duration::from_secs(12 * 60 * 60);
 const UI_SESSION_CLEANUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
@@ -31,7 +31,9 @@ pub(crate) enum UiAuth {
 }
 
 pub(crate) struct UiAuthInner {
-    password_phc: String,
+    /// Shared `--ui-password`; signing in with it grants the admin role.
+    password_phc: Option<String>,
+    users: std::sync::RwLock<Vec<UiUser>>,
     sessions: DashMap<String, SessionRecord>,
     login_attempts: DashMap<String, LoginAttemptRecord>,
 }
@@ -39,6 +41,49 @@ pub(crate) struct UiAuthInner {
 #[derive(Clone, Debug)]
 struct SessionRecord {
     last_seen: OffsetDateTime,
+    principal: UiPrincipal,
+}
+
+/// Who a UI session belongs to. `username` is `None` for sessions created
+/// with the shared password and for internal tokens.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct UiPrincipal {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    pub role: UiRole,
+}
+
+impl UiPrincipal {
+    fn shared_admin() -> Self {
+        Self {
+            username: None,
+            role: UiRole::Admin,
+        }
+    }
+}
+
+impl UiAuth {
+    pub(crate) fn has_legacy_password(&self) -> bool {
+        matches!(self, UiAuth::Enabled(inner) if inner.password_phc.is_some())
+    }
+
+    /// Swap in an updated user list. No-op while auth is disabled; the list
+    /// is loaded on the next start instead.
+    pub(crate) fn set_users(&self, users: Vec<UiUser>) {
+        if let UiAuth::Enabled(inner) = self
+            && let Ok(mut guard) = inner.users.write()
+        {
+            *guard = users;
+        }
+    }
+
+    pub(crate) fn revoke_user_sessions(&self, username: &str) {
+        if let UiAuth::Enabled(inner) = self {
+            inner
+                .sessions
+                .retain(|_, record| record.principal.username.as_deref() != Some(username));
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -55,6 +100,8 @@ struct AuthStatusOk {
     disabled: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<UiPrincipal>,
 }
 
 #[derive(Debug, Serialize)]
@@ -63,8 +110,9 @@ struct AuthStatusLocked {
     locked: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub(crate) struct CreateSessionBody {
+    username: Option<String>,
     password: Option<String>,
 }
 
@@ -84,16 +132,6 @@ fn normalize_password(candidate: Option<&str>) -> String {
     candidate.unwrap_or("").trim().to_string()
 }
 
-fn is_secure_request(headers: &HeaderMap) -> bool {
-    // Treat requests as secure behind reverse proxies when X-Forwarded-Proto includes "https".
-    headers
-        .get("x-forwarded-proto")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|v| v.split(',').next())
-        .map(|v| v.trim().eq_ignore_ascii_case("https"))
-        .unwrap_or(false)
-}
-
 fn normalize_client_key_value(raw: &str) -> Option<String> {
     let mut v = raw.trim().trim_matches('"').trim().to_string();
     if v.starts_with('[') && v.ends_with(']') && v.len() > 2 {
@@ -129,7 +167,12 @@ fn parse_forwarded_for(raw: &str) -> Option<String> {
     None
 }
 
-fn login_attempt_key(headers: &HeaderMap) -> String {
+fn login_attempt_key(client: &ClientAddr, headers: &HeaderMap) -> String {
+    // The socket peer, or the forwarded address when it is a trusted proxy.
+    if let Some(ip) = client.ip {
+        return format!("ip:{ip}");
+    }
+
     if let Some(v) = headers
         .get("x-forwarded-for")
         .and_then(|v| v.to_str().ok())
@@ -283,15 +326,6 @@ fn is_allowed_origin(headers: &HeaderMap, allowed: &[String], allow_all: bool) -
     false
 }
 
-fn verify_password(phc: &str, candidate: &str) -> bool {
-    let Ok(hash) = PasswordHash::new(phc) else {
-        return false;
-    };
-    Argon2::default()
-        .verify_password(candidate.as_bytes(), &hash)
-        .is_ok()
-}
-
 fn get_token_from_jar(jar: &CookieJar) -> Option<String> {
     jar.get(UI_COOKIE_NAME).map(|c| c.value().to_string())
 }
@@ -332,20 +366,40 @@ fn is_global_ws_path(method: &Method, path: &str) -> bool {
     method == Method::GET && path == "/api/global/ws"
 }
 
-fn is_session_valid(inner: &UiAuthInner, token: &str) -> bool {
+fn session_principal(inner: &UiAuthInner, token: &str) -> Option<UiPrincipal> {
     let now = OffsetDateTime::now_utc();
-    let Some(mut entry) = inner.sessions.get_mut(token) else {
-        return false;
-    };
+    let mut entry = inner.sessions.get_mut(token)?;
 
     if now - entry.last_seen > time::Duration::seconds(UI_SESSION_TTL.as_secs() as i64) {
         drop(entry);
         inner.sessions.remove(token);
-        return false;
+        return None;
     }
 
     entry.last_seen = now;
-    true
+    Some(entry.principal.clone())
+}
+
+fn authenticate(
+    inner: &UiAuthInner,
+    username: Option<&str>,
+    candidate: &str,
+) -> Option<UiPrincipal> {
+    match username {
+        Some(username) => {
+            let users = inner.users.read().ok()?;
+            let user = users.iter().find(|u| u.username == username)?;
+            verify_password(&user.password_phc, candidate).then(|| UiPrincipal {
+                username: Some(user.username.clone()),
+                role: user.role,
+            })
+        }
+        None => inner
+            .password_phc
+            .as_deref()
+            .is_some_and(|phc| verify_password(phc, candidate))
+            .then(UiPrincipal::shared_admin),
+    }
 }
 
 fn login_failure_window_duration() -> time::Duration {
@@ -458,6 +512,7 @@ pub(crate) fn issue_internal_token(ui_auth: &UiAuth) -> Option<String> {
                 token.clone(),
                 SessionRecord {
                     last_seen: OffsetDateTime::now_utc(),
+                    principal: UiPrincipal::shared_admin(),
                 },
             );
             Some(token)
@@ -465,22 +520,18 @@ pub(crate) fn issue_internal_token(ui_auth: &UiAuth) -> Option<String> {
     }
 }
 
-pub(crate) fn init_ui_auth(ui_password: Option<String>) -> UiAuth {
+/// Auth is enabled when a shared password is set or any UI user exists.
+pub(crate) fn init_ui_auth(ui_password: Option<String>, users: Vec<UiUser>) -> UiAuth {
     let password = normalize_password(ui_password.as_deref());
-    if password.is_empty() {
+    if password.is_empty() && users.is_empty() {
         return UiAuth::Disabled;
     }
 
-    let mut salt_bytes = [0u8; 16];
-    getrandom::fill(&mut salt_bytes).expect("init_ui_auth: getrandom failed");
-    let salt = SaltString::encode_b64(&salt_bytes).expect("init_ui_auth: encode salt");
-    let password_phc = Argon2::default()
-        .hash_password(password.as_bytes(), &salt)
-        .expect("hash password")
-        .to_string();
+    let password_phc = (!password.is_empty()).then(|| hash_password(&password));
 
     UiAuth::Enabled(Arc::new(UiAuthInner {
         password_phc,
+        users: std::sync::RwLock::new(users),
         sessions: DashMap::new(),
         login_attempts: DashMap::new(),
     }))
@@ -489,6 +540,7 @@ pub(crate) fn init_ui_auth(ui_password: Option<String>) -> UiAuth {
 pub(crate) async fn auth_session_status(
     State(state): State<Arc<crate::AppState>>,
     headers: HeaderMap,
+    client: ClientAddr,
     jar: CookieJar,
 ) -> impl IntoResponse {
     match &state.ui_auth {
@@ -496,29 +548,23 @@ pub(crate) async fn auth_session_status(
             authenticated: true,
             disabled: Some(true),
             token: None,
+            user: None,
         })
         .into_response(),
         UiAuth::Enabled(inner) => {
-            let secure = is_secure_request(&headers);
-
-            if let Some(token) = get_token_from_authorization(&headers)
-                && is_session_valid(inner, &token)
-            {
-                return Json(AuthStatusOk {
-                    authenticated: true,
-                    disabled: None,
-                    token: None,
-                })
-                .into_response();
-            }
-
-            if let Some(token) = get_token_from_jar(&jar)
-                && is_session_valid(inner, &token)
-            {
+            let secure = client.secure;
+
+            let principal = get_token_from_authorization(&headers)
+                .and_then(|token| session_principal(inner, &token))
+                .or_else(|| {
+                    get_token_from_jar(&jar).and_then(|token| session_principal(inner, &token))
+                });
+            if let Some(principal) = principal {
                 return Json(AuthStatusOk {
                     authenticated: true,
                     disabled: None,
                     token: None,
+                    user: Some(principal),
                 })
                 .into_response();
             }
@@ -540,16 +586,22 @@ pub(crate) async fn auth_session_status(
 pub(crate) async fn auth_session_create(
     State(state): State<Arc<crate::AppState>>,
     headers: HeaderMap,
+    client: ClientAddr,
     jar: CookieJar,
     Json(body): Json<CreateSessionBody>,
 ) -> impl IntoResponse {
     let candidate = normalize_password(body.password.as_deref());
+    let username = body
+        .username
+        .as_deref()
+        .map(|u| u.trim().to_ascii_lowercase())
+        .filter(|u| !u.is_empty());
 
     match &state.ui_auth {
         UiAuth::Disabled => (
             StatusCode::BAD_REQUEST,
             Json(AuthErrorBody {
-                error: "UI password not configured".to_string(),
+                error: "UI authentication not configured".to_string(),
                 locked: None,
                 code: Some("auth_disabled".to_string()),
                 retry_after_seconds: None,
@@ -557,8 +609,8 @@ pub(crate) async fn auth_session_create(
         )
             .into_response(),
         UiAuth::Enabled(inner) => {
-            let secure = is_secure_request(&headers);
-            let attempt_key = login_attempt_key(&headers);
+            let secure = client.secure;
+            let attempt_key = login_attempt_key(&client, &headers);
             let now = OffsetDateTime::now_utc();
 
             if let Some(retry_after_seconds) =
@@ -581,7 +633,7 @@ pub(crate) async fn auth_session_create(
                     .into_response();
             }
 
-            if !verify_password(&inner.password_phc, &candidate) {
+            let Some(principal) = authenticate(inner, username.as_deref(), &candidate) else {
                 let jar = jar.add(build_expired_cookie(secure, state.ui_cookie_same_site));
                 if let Some(retry_after_seconds) =
                     record_failed_login_attempt(inner, &attempt_key, now)
@@ -602,18 +654,23 @@ pub(crate) async fn auth_session_create(
                         .into_response();
                 }
 
+                let error = if username.is_some() {
+                    "Invalid username or password"
+                } else {
+                    "Invalid password"
+                };
                 return (
                     StatusCode::UNAUTHORIZED,
                     jar,
                     Json(AuthErrorBody {
-                        error: "Invalid password".to_string(),
+                        error: error.to_string(),
                         locked: Some(true),
                         code: Some("auth_invalid_password".to_string()),
                         retry_after_seconds: None,
                     }),
                 )
                     .into_response();
-            }
+            };
 
             clear_failed_login_attempts(inner, &attempt_key);
 
@@ -622,9 +679,13 @@ pub(crate) async fn auth_session_create(
             }
 
             let token = crate::issue_token();
-            inner
-                .sessions
-                .insert(token.clone(), SessionRecord { last_seen: now });
+            inner.sessions.insert(
+                token.clone(),
+                SessionRecord {
+                    last_seen: now,
+                    principal: principal.clone(),
+                },
+            );
 
             let jar = jar.add(build_session_cookie(
                 &token,
@@ -638,6 +699,7 @@ pub(crate) async fn auth_session_create(
                     authenticated: true,
                     disabled: None,
                     token: Some(token),
+                    user: Some(principal),
                 }),
             )
                 .into_response()
@@ -645,9 +707,86 @@ pub(crate) async fn auth_session_create(
     }
 }
 
+pub(crate) async fn auth_session_delete(
+    State(state): State<Arc<crate::AppState>>,
+    headers: HeaderMap,
+    client: ClientAddr,
+    jar: CookieJar,
+) -> impl IntoResponse {
+    if let UiAuth::Enabled(inner) = &state.ui_auth {
+        for token in [
+            get_token_from_authorization(&headers),
+            get_token_from_jar(&jar),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            inner.sessions.remove(&token);
+        }
+    }
+
+    let secure = client.secure;
+    let jar = jar.add(build_expired_cookie(secure, state.ui_cookie_same_site));
+    (
+        StatusCode::OK,
+        jar,
+        Json(AuthStatusOk {
+            authenticated: false,
+            disabled: matches!(state.ui_auth, UiAuth::Disabled).then_some(true),
+            token: None,
+            user: None,
+        }),
+    )
+}
+
+/// Gate for server-wide admin actions (plugins, secrets, self-update). API
+/// tokens never qualify, and a request without a principal passes only when
+/// UI auth is off.
+pub(crate) fn require_admin(
+    ui_auth: &UiAuth,
+    extensions: &axum::http::Extensions,
+) -> crate::ApiResult<()> {
+    let forbidden = || crate::AppError::forbidden("This action requires the admin role");
+    if extensions
+        .get::<crate::api_tokens::ApiTokenPrincipal>()
+        .is_some()
+    {
+        return Err(forbidden());
+    }
+    match extensions.get::<UiPrincipal>() {
+        Some(principal) if principal.role == UiRole::Admin => Ok(()),
+        Some(_) => Err(forbidden()),
+        None if matches!(ui_auth, UiAuth::Disabled) => Ok(()),
+        None => Err(forbidden()),
+    }
+}
+
+/// Run the request as `principal`, rejecting it when the role does not allow it.
+async fn run_as(
+    principal: UiPrincipal,
+    mut req: axum::http::Request<axum::body::Body>,
+    next: middleware::Next,
+) -> axum::response::Response {
+    if !principal.role.allows(req.method(), req.uri().path()) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(AuthErrorBody {
+                error: "This action requires the admin role".to_string(),
+                locked: None,
+                code: Some("auth_role_forbidden".to_string()),
+                retry_after_seconds: None,
+            }),
+        )
+            .into_response();
+    }
+    req.extensions_mut().insert(principal);
+    next.run(req).await
+}
+
 pub(crate) async fn require_ui_auth(
     State(state): State<Arc<crate::AppState>>,
     headers: HeaderMap,
+    client: ClientAddr,
     jar: CookieJar,
     req: axum::http::Request<axum::body::Body>,
     next: middleware::Next,
@@ -664,27 +803,68 @@ pub(crate) async fn require_ui_auth(
                 return next.run(req).await;
             }
 
+            // Scoped API tokens for headless clients; never fall back to the
+            // cookie when one is presented.
+            if let Some(token) = get_token_from_authorization(&headers)
+                && crate::api_tokens::is_api_token(&token)
+            {
+                return match crate::api_tokens::authorize(&state, &token, &req_method, &req_path) {
+                    Ok(principal) => {
+                        tracing::debug!(
+                            token_id = %principal.id,
+                            token_name = %principal.name,
+                            method = %req_method,
+                            path = %req_path,
+                            "API token request"
+                        );
+                        let mut req = req;
+                        req.extensions_mut().insert(principal);
+                        next.run(req).await
+                    }
+                    Err(crate::api_tokens::ApiTokenDenied::Invalid) => (
+                        StatusCode::UNAUTHORIZED,
+                        Json(AuthErrorBody {
+                            error: "Invalid or expired API token".to_string(),
+                            locked: Some(true),
+                            code: Some("api_token_invalid".to_string()),
+                            retry_after_seconds: None,
+                        }),
+                    )
+                        .into_response(),
+                    Err(crate::api_tokens::ApiTokenDenied::MissingScope) => (
+                        StatusCode::FORBIDDEN,
+                        Json(AuthErrorBody {
+                            error: "API token lacks the scope for this request".to_string(),
+                            locked: None,
+                            code: Some("api_token_scope_forbidden".to_string()),
+                            retry_after_seconds: None,
+                        }),
+                    )
+                        .into_response(),
+                };
+            }
+
             // Header token (preferred): avoids third-party cookie issues and doesn't
             // require CSRF origin enforcement because the token isn't sent automatically.
-            if let Some(token) = get_token_from_authorization(&headers)
-                && is_session_valid(inner, &token)
+            if let Some(principal) =
+                get_token_from_authorization(&headers).and_then(|t| session_principal(inner, &t))
             {
-                return next.run(req).await;
+                return run_as(principal, req, next).await;
             }
 
             // WebSocket API in browsers cannot set custom Authorization headers.
             // Allow a query-token fallback only for the global WS endpoint.
             if is_global_ws_path(&req_method, &req_path)
-                && let Some(token) = get_token_from_query(&req)
-                && is_session_valid(inner, &token)
+                && let Some(principal) =
+                    get_token_from_query(&req).and_then(|t| session_principal(inner, &t))
             {
-                return next.run(req).await;
+                return run_as(principal, req, next).await;
             }
 
             // Cookie token fallback (legacy / same-origin): enforce Origin allowlist for
             // unsafe methods when cookies may be sent cross-site.
-            if let Some(token) = get_token_from_jar(&jar)
-                && is_session_valid(inner, &token)
+            if let Some(principal) =
+                get_token_from_jar(&jar).and_then(|t| session_principal(inner, &t))
             {
                 if !is_safe_method(req.method())
                     && (matches!(state.ui_cookie_same_site, SameSite::None)
@@ -707,10 +887,10 @@ pub(crate) async fn require_ui_auth(
                     )
                         .into_response();
                 }
-                return next.run(req).await;
+                return run_as(principal, req, next).await;
             }
 
-            let secure = is_secure_request(&headers);
+            let secure = client.secure;
             let jar = jar.add(build_expired_cookie(secure, state.ui_cookie_same_site));
             (
                 StatusCode::UNAUTHORIZED,
@@ -726,3 +906,41 @@ pub(crate) async fn require_ui_auth(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn require_admin_rejects_tokens_and_anonymous_requests_under_auth() {
+        let enabled = UiAuth::Enabled(Arc::new(UiAuthInner {
+            password_phc: None,
+            users: std::sync::RwLock::new(Vec::new()),
+            sessions: DashMap::new(),
+            login_attempts: DashMap::new(),
+        }));
+        let with = |value: Option<UiPrincipal>| {
+            let mut extensions = axum::http::Extensions::new();
+            if let Some(value) = value {
+                extensions.insert(value);
+            }
+            extensions
+        };
+        let read_only = UiPrincipal {
+            username: Some("dev".to_string()),
+            role: UiRole::ReadOnly,
+        };
+
+        assert!(require_admin(&enabled, &with(Some(UiPrincipal::shared_admin()))).is_ok());
+        assert!(require_admin(&enabled, &with(Some(read_only))).is_err());
+        assert!(require_admin(&enabled, &with(None)).is_err());
+        assert!(require_admin(&UiAuth::Disabled, &with(None)).is_ok());
+
+        let mut token = with(None);
+        token.insert(crate::api_tokens::ApiTokenPrincipal {
+            id: "t".to_string(),
+            name: "ci".to_string(),
+        });
+        assert!(require_admin(&UiAuth::Disabled, &token).is_err());
+    }
+}
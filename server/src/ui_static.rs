@@ -0,0 +1,187 @@
+//! The built web UI served from `--ui-dir`.
+//!
+//! Files are served with `.br`/`.gz` siblings when the build produced them.
+//! Vite's hashed `assets/` are cached forever; everything else, `index.html`
+//! included, must be revalidated so a new build is picked up on reload.
+//! Client routes fall back to `index.html`; `/api`, `assets/` and paths that
+//! look like files keep their 404.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderValue, Method, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use tower_http::services::{ServeDir, ServeFile};
+
+use crate::path_utils::url_path_within;
+
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+const REVALIDATE: &str = "no-cache";
+
+#[derive(Clone)]
+struct UiFiles {
+    files: ServeDir,
+    index: ServeFile,
+}
+
+/// Fallback router serving the UI in `dir`.
+pub(crate) fn router(dir: &Path) -> Router {
+    let files = UiFiles {
+        files: ServeDir::new(dir).precompressed_br().precompressed_gzip(),
+        index: ServeFile::new(dir.join("index.html"))
+            .precompressed_br()
+            .precompressed_gzip(),
+    };
+    Router::new().fallback(serve).with_state(Arc::new(files))
+}
+
+fn is_hashed_asset(path: &str) -> bool {
+    path.starts_with("/assets/")
+}
+
+/// Whether a missing `path` is a client-side route that should get
+/// `index.html` rather than a 404.
+fn is_spa_route(method: &Method, path: &str, accepts_html: bool) -> bool {
+    if !matches!(*method, Method::GET | Method::HEAD)
+        || url_path_within(path, "/api")
+        || is_hashed_asset(path)
+    {
+        return false;
+    }
+    let last = path.rsplit('/').next().unwrap_or("");
+    accepts_html || !last.contains('.')
+}
+
+fn cache_control(path: &str, status: StatusCode) -> &'static str {
+    if is_hashed_asset(path) && (status.is_success() || status == StatusCode::NOT_MODIFIED) {
+        IMMUTABLE
+    } else {
+        REVALIDATE
+    }
+}
+
+fn bare_request(method: &Method, req: &Request) -> Request {
+    let mut out = Request::new(Body::empty());
+    *out.method_mut() = method.clone();
+    *out.uri_mut() = req.uri().clone();
+    *out.headers_mut() = req.headers().clone();
+    out
+}
+
+async fn serve(State(ui): State<Arc<UiFiles>>, req: Request) -> Response {
+    let path = req.uri().path().to_string();
+    if url_path_within(&path, "/api") {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Not found" })),
+        )
+            .into_response();
+    }
+    let method = req.method().clone();
+    let accepts_html = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/html"));
+    let index_request = bare_request(&method, &req);
+
+    let mut resp = match ui.files.clone().try_call(req).await {
+        Ok(resp) => resp.map(Body::new),
+        Err(err) => {
+            tracing::warn!(path = %path, error = %err, "Failed to serve UI file");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if resp.status() == StatusCode::NOT_FOUND && is_spa_route(&method, &path, accepts_html) {
+        resp = match ui.index.clone().try_call(index_request).await {
+            Ok(resp) => resp.map(Body::new),
+            Err(err) => {
+                tracing::warn!(error = %err, "Failed to serve index.html");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+    }
+    let cache = cache_control(&path, resp.status());
+    resp.headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static(cache));
+    resp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(path: &str, accept_encoding: Option<&str>) -> Request {
+        let mut req = Request::new(Body::empty());
+        *req.uri_mut() = path.parse().unwrap();
+        if let Some(encoding) = accept_encoding {
+            req.headers_mut().insert(
+                header::ACCEPT_ENCODING,
+                HeaderValue::from_str(encoding).unwrap(),
+            );
+        }
+        req
+    }
+
+    fn header_str(resp: &Response, name: header::HeaderName) -> Option<&str> {
+        resp.headers().get(name).and_then(|v| v.to_str().ok())
+    }
+
+    #[test]
+    fn spa_fallback_skips_api_assets_and_files() {
+        assert!(is_spa_route(&Method::GET, "/chat/ses_1", false));
+        assert!(is_spa_route(&Method::HEAD, "/", false));
+        assert!(!is_spa_route(&Method::POST, "/chat", true));
+        assert!(!is_spa_route(&Method::GET, "/api", true));
+        assert!(!is_spa_route(&Method::GET, "/api/unknown", true));
+        assert!(is_spa_route(&Method::GET, "/apidocs", false));
+        assert!(!is_spa_route(&Method::GET, "/assets/missing-abc.js", true));
+        assert!(!is_spa_route(&Method::GET, "/favicon.png", false));
+        assert!(is_spa_route(&Method::GET, "/notes/v1.2", true));
+    }
+
+    #[tokio::test]
+    async fn serves_precompressed_files_with_cache_headers() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let dir = tmp.path();
+        std::fs::create_dir(dir.join("assets")).unwrap();
+        std::fs::write(dir.join("index.html"), "<html></html>").unwrap();
+        std::fs::write(dir.join("assets/app-abc.js"), "console.log(1)").unwrap();
+        std::fs::write(dir.join("assets/app-abc.js.br"), "brotli").unwrap();
+        let ui = Arc::new(UiFiles {
+            files: ServeDir::new(dir).precompressed_br().precompressed_gzip(),
+            index: ServeFile::new(dir.join("index.html")),
+        });
+
+        let resp = serve(
+            State(ui.clone()),
+            get("/assets/app-abc.js", Some("br, gzip")),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(header_str(&resp, header::CONTENT_ENCODING), Some("br"));
+        assert_eq!(header_str(&resp, header::CACHE_CONTROL), Some(IMMUTABLE));
+
+        let resp = serve(State(ui.clone()), get("/assets/app-abc.js", None)).await;
+        assert_eq!(header_str(&resp, header::CONTENT_ENCODING), None);
+
+        let resp = serve(State(ui.clone()), get("/sessions/ses_1", None)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(header_str(&resp, header::CACHE_CONTROL), Some(REVALIDATE));
+        assert!(
+            header_str(&resp, header::CONTENT_TYPE).is_some_and(|ct| ct.starts_with("text/html"))
+        );
+
+        let resp = serve(State(ui.clone()), get("/assets/gone-123.js", None)).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(header_str(&resp, header::CACHE_CONTROL), Some(REVALIDATE));
+
+        let resp = serve(State(ui), get("/api", None)).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,361 @@
+use std::sync::{Arc, LazyLock};
+
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::{Method, StatusCode},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::ui_auth::UiAuth;
+use crate::{ApiResult, AppError};
+
+const MAX_USERNAME_CHARS: usize = 64;
+const MIN_PASSWORD_CHARS: usize = 8;
+
+/// Non-GET routes a read-only user may still call because they do not change
+/// any state.
+const READ_ONLY_ALLOWED_MUTATIONS: &[&str] = &["/api/markdown/render"];
+/// Routes that are admin-only for every method.
+const ADMIN_ONLY_PREFIXES: &[&str] = &[
+    "/api/admin",
+    "/api/audit",
+    "/api/auth",
+    "/api/secrets",
+    "/api/terminal",
+];
+
+/// Serializes read-modify-write cycles on the persisted user list.
+static USERS_WRITE_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum UiRole {
+    Admin,
+    ReadOnly,
+}
+
+impl UiRole {
+    /// Whether this role may make the request at all.
+    pub(crate) fn allows(self, method: &Method, path: &str) -> bool {
+        match self {
+            UiRole::Admin => true,
+            UiRole::ReadOnly => !requires_admin(method, path),
+        }
+    }
+}
+
+fn path_has_prefix(path: &str, prefix: &str) -> bool {
+    path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn requires_admin(method: &Method, path: &str) -> bool {
+    if ADMIN_ONLY_PREFIXES
+        .iter()
+        .any(|prefix| path_has_prefix(path, prefix))
+    {
+        return true;
+    }
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return false;
+    }
+    !READ_ONLY_ALLOWED_MUTATIONS.contains(&path)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UiUser {
+    pub username: String,
+    pub role: UiRole,
+    pub password_phc: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct UiUserStore {
+    #[serde(default)]
+    users: Vec<UiUser>,
+}
+
+pub(crate) fn hash_password(password: &str) -> String {
+    let mut salt_bytes = [0u8; 16];
+    getrandom::fill(&mut salt_bytes).expect("hash_password: getrandom failed");
+    let salt = SaltString::encode_b64(&salt_bytes).expect("hash_password: encode salt");
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("hash password")
+        .to_string()
+}
+
+pub(crate) fn verify_password(phc: &str, candidate: &str) -> bool {
+    let Ok(hash) = PasswordHash::new(phc) else {
+        return false;
+    };
+    Argon2::default()
+        .verify_password(candidate.as_bytes(), &hash)
+        .is_ok()
+}
+
+/// Users persisted in the studio database. A store that fails to load is
+/// treated as empty so a corrupt entry cannot lock the server.
+pub(crate) async fn load_users(db: &crate::studio_db::StudioDb) -> Vec<UiUser> {
+    match db
+        .get_json::<UiUserStore>(crate::studio_db::KV_KEY_UI_USERS)
+        .await
+    {
+        Ok(store) => store.unwrap_or_default().users,
+        Err(err) => {
+            tracing::warn!(error = %err, "Failed to load UI users");
+            Vec::new()
+        }
+    }
+}
+
+async fn persist_users(db: &crate::studio_db::StudioDb, users: &[UiUser]) -> ApiResult<()> {
+    let store = UiUserStore {
+        users: users.to_vec(),
+    };
+    db.set_json(crate::studio_db::KV_KEY_UI_USERS, &store)
+        .await
+        .map_err(AppError::internal)
+}
+
+fn normalize_username(raw: &str) -> ApiResult<String> {
+    let name = raw.trim().to_ascii_lowercase();
+    if name.is_empty() {
+        return Err(AppError::bad_request("username is required"));
+    }
+    if name.chars().count() > MAX_USERNAME_CHARS {
+        return Err(AppError::bad_request("username is too long"));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '@'))
+    {
+        return Err(AppError::bad_request(
+            "username may only contain letters, digits, '.', '_', '-' and '@'",
+        ));
+    }
+    Ok(name)
+}
+
+fn validate_password(raw: &str) -> ApiResult<&str> {
+    let password = raw.trim();
+    if password.chars().count() < MIN_PASSWORD_CHARS {
+        return Err(AppError::bad_request(format!(
+            "password must be at least {MIN_PASSWORD_CHARS} characters"
+        )));
+    }
+    Ok(password)
+}
+
+/// Without `--ui-password` the user list is the only way in, so it must keep
+/// an admin.
+fn ensure_admin_remains(users: &[UiUser], has_legacy_password: bool) -> ApiResult<()> {
+    if users.is_empty() || has_legacy_password || users.iter().any(|u| u.role == UiRole::Admin) {
+        return Ok(());
+    }
+    Err(AppError::conflict("At least one admin user is required"))
+}
+
+fn now_millis() -> i64 {
+    time::OffsetDateTime::now_utc().unix_timestamp_nanos() as i64 / 1_000_000
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UiUserView {
+    pub username: String,
+    pub role: UiRole,
+    pub created_at: i64,
+}
+
+impl From<&UiUser> for UiUserView {
+    fn from(user: &UiUser) -> Self {
+        Self {
+            username: user.username.clone(),
+            role: user.role,
+            created_at: user.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UiUsersResponse {
+    pub users: Vec<UiUserView>,
+    /// Users were saved while UI auth is off; they apply after a restart.
+    pub restart_required: bool,
+}
+
+async fn mutate_users(
+    state: &crate::AppState,
+    apply: impl FnOnce(&mut Vec<UiUser>) -> ApiResult<()>,
+) -> ApiResult<UiUsersResponse> {
+    let _guard = USERS_WRITE_LOCK.lock().await;
+    let mut users = load_users(&state.studio_db).await;
+    apply(&mut users)?;
+    ensure_admin_remains(&users, state.ui_auth.has_legacy_password())?;
+    persist_users(&state.studio_db, &users).await?;
+    state.ui_auth.set_users(users.clone());
+    Ok(UiUsersResponse {
+        users: users.iter().map(UiUserView::from).collect(),
+        restart_required: matches!(state.ui_auth, UiAuth::Disabled) && !users.is_empty(),
+    })
+}
+
+pub(crate) async fn ui_users_list(
+    State(state): State<Arc<crate::AppState>>,
+) -> ApiResult<Json<UiUsersResponse>> {
+    let users = load_users(&state.studio_db).await;
+    Ok(Json(UiUsersResponse {
+        restart_required: matches!(state.ui_auth, UiAuth::Disabled) && !users.is_empty(),
+        users: users.iter().map(UiUserView::from).collect(),
+    }))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UiUserCreateBody {
+    pub username: String,
+    pub password: String,
+    pub role: UiRole,
+}
+
+pub(crate) async fn ui_users_create(
+    State(state): State<Arc<crate::AppState>>,
+    Json(body): Json<UiUserCreateBody>,
+) -> ApiResult<(StatusCode, Json<UiUsersResponse>)> {
+    let username = normalize_username(&body.username)?;
+    let password_phc = hash_password(validate_password(&body.password)?);
+    let out = mutate_users(&state, |users| {
+        if users.iter().any(|u| u.username == username) {
+            return Err(AppError::conflict(format!(
+                "User '{username}' already exists"
+            )));
+        }
+        users.push(UiUser {
+            username: username.clone(),
+            role: body.role,
+            password_phc,
+            created_at: now_millis(),
+        });
+        Ok(())
+    })
+    .await?;
+    Ok((StatusCode::CREATED, Json(out)))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UiUserUpdateBody {
+    pub password: Option<String>,
+    pub role: Option<UiRole>,
+}
+
+pub(crate) async fn ui_users_update(
+    State(state): State<Arc<crate::AppState>>,
+    Path(username): Path<String>,
+    Json(body): Json<UiUserUpdateBody>,
+) -> ApiResult<Json<UiUsersResponse>> {
+    let username = normalize_username(&username)?;
+    let password_phc = match body.password.as_deref() {
+        Some(raw) => Some(hash_password(validate_password(raw)?)),
+        None => None,
+    };
+    let out = mutate_users(&state, |users| {
+        let user = users
+            .iter_mut()
+            .find(|u| u.username == username)
+            .ok_or_else(|| AppError::not_found(format!("User '{username}' not found")))?;
+        if let Some(role) = body.role {
+            user.role = role;
+        }
+        if let Some(phc) = password_phc {
+            user.password_phc = phc;
+        }
+        Ok(())
+    })
+    .await?;
+    // Existing sessions carry the old role; make the user sign in again.
+    state.ui_auth.revoke_user_sessions(&username);
+    Ok(Json(out))
+}
+
+pub(crate) async fn ui_users_delete(
+    State(state): State<Arc<crate::AppState>>,
+    Path(username): Path<String>,
+) -> ApiResult<Json<UiUsersResponse>> {
+    let username = normalize_username(&username)?;
+    let out = mutate_users(&state, |users| {
+        let before = users.len();
+        users.retain(|u| u.username != username);
+        if users.len() == before {
+            return Err(AppError::not_found(format!("User '{username}' not found")));
+        }
+        Ok(())
+    })
+    .await?;
+    state.ui_auth.revoke_user_sessions(&username);
+    Ok(Json(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(name: &str, role: UiRole) -> UiUser {
+        UiUser {
+            username: name.to_string(),
+            role,
+            password_phc: String::new(),
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn read_only_role_is_limited_to_safe_requests() {
+        let ro = UiRole::ReadOnly;
+        assert!(ro.allows(&Method::GET, "/api/git/status"));
+        assert!(ro.allows(&Method::POST, "/api/markdown/render"));
+        assert!(!ro.allows(&Method::POST, "/api/git/commit"));
+        assert!(!ro.allows(&Method::PUT, "/api/config/settings"));
+        assert!(!ro.allows(&Method::GET, "/api/terminal/abc/stream"));
+        assert!(!ro.allows(&Method::GET, "/api/auth/users"));
+        assert!(ro.allows(&Method::GET, "/api/terminals-overview"));
+        assert!(UiRole::Admin.allows(&Method::POST, "/api/terminal/create"));
+    }
+
+    #[test]
+    fn usernames_are_normalized_and_validated() {
+        assert_eq!(normalize_username("  Ada.Dev ").unwrap(), "ada.dev");
+        assert!(normalize_username("").is_err());
+        assert!(normalize_username("ada lovelace").is_err());
+        assert!(validate_password("short").is_err());
+        assert!(validate_password("long enough").is_ok());
+    }
+
+    #[test]
+    fn last_admin_is_kept_unless_legacy_password_exists() {
+        let users = vec![user("viewer", UiRole::ReadOnly)];
+        assert!(ensure_admin_remains(&users, false).is_err());
+        assert!(ensure_admin_remains(&users, true).is_ok());
+        assert!(ensure_admin_remains(&[], false).is_ok());
+        assert!(ensure_admin_remains(&[user("root", UiRole::Admin)], false).is_ok());
+    }
+
+    #[test]
+    fn password_hash_round_trips() {
+        let phc = hash_password("correct horse");
+        assert!(verify_password(&phc, "correct horse"));
+        assert!(!verify_password(&phc, "wrong horse"));
+        assert!(!verify_password("not-a-phc", "correct horse"));
+    }
+}
@@ -2,6 +2,7 @@ use std::cmp::Ordering;
 use std::time::Duration;
 
 use axum::{Json, extract::Query};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 const DEFAULT_RELEASE_REPO: &str = "canxin121/opencode-studio";
@@ -9,7 +10,7 @@ const GITHUB_API_BASE: &str = "https://api.github.com";
 const GITHUB_WEB_BASE: &str = "https://github.com";
 const SOURCE_KIND: &str = "githubRelease";
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct UpdateCheckQuery {
     installer_version: Option<String>,
@@ -19,7 +20,7 @@ pub(crate) struct UpdateCheckQuery {
     installer_manager: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct UpdateCheckResponse {
     source: &'static str,
@@ -34,7 +35,7 @@ pub(crate) struct UpdateCheckResponse {
     error: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct ReleaseSummary {
     tag: String,
@@ -46,7 +47,7 @@ struct ReleaseSummary {
     published_at: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct ServiceUpdateStatus {
     current_version: String,
@@ -63,7 +64,7 @@ struct ServiceUpdateStatus {
     update_command: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct InstallerUpdateStatus {
     current_version: String,
@@ -83,7 +84,7 @@ struct InstallerUpdateStatus {
     selection_error: Option<InstallerSelectionError>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct ReleaseAssetLink {
     name: String,
@@ -92,7 +93,7 @@ struct ReleaseAssetLink {
     manager: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct InstallerSelectionError {
     code: String,
@@ -287,6 +288,39 @@ pub async fn update_check(Query(query): Query<UpdateCheckQuery>) -> Json<UpdateC
     Json(response)
 }
 
+/// This platform's service archive in the latest release.
+#[derive(Debug)]
+pub(crate) struct ServiceReleaseAsset {
+    pub version: String,
+    pub name: String,
+    pub url: String,
+    /// Whether `version` is newer than the running service.
+    pub newer: bool,
+}
+
+pub(crate) async fn latest_service_asset() -> Result<ServiceReleaseAsset, String> {
+    let repo = release_repo();
+    let release = fetch_latest_release(&repo).await?;
+    let target = preferred_service_target_triple()
+        .ok_or_else(|| "no service build is published for this platform".to_string())?;
+    let tag = release.tag_name.trim();
+    let version = release_version(tag).ok_or_else(|| format!("unrecognized release tag: {tag}"))?;
+    let (Some(name), Some(url)) = resolve_release_asset_url(
+        &release.assets,
+        &repo,
+        tag,
+        service_asset_candidates(&target, tag).as_slice(),
+    ) else {
+        return Err(format!("release {tag} has no service build for {target}"));
+    };
+    Ok(ServiceReleaseAsset {
+        newer: is_newer_version(&current_service_version(), &version),
+        version,
+        name,
+        url,
+    })
+}
+
 #[derive(Debug)]
 struct InstallerRuntime {
     current_version: String,
@@ -311,7 +345,7 @@ impl InstallerRuntime {
     }
 }
 
-#[derive(Debug, Serialize, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Serialize, Clone, Eq, PartialEq, Ord, PartialOrd, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct AssetIdentity {
     installer_type: String,
@@ -956,7 +990,7 @@ fn collect_available_identities(assets: &[ReleaseAssetLink]) -> Vec<AssetIdentit
 }
 
 async fn fetch_latest_release(repo: &str) -> Result<GithubRelease, String> {
-    let client = reqwest::Client::builder()
+    let client = crate::tls_roots::client_builder()
         .timeout(Duration::from_secs(10))
         .build()
         .map_err(|err| format!("build http client: {err}"))?;
@@ -999,7 +1033,7 @@ async fn fetch_latest_release_via_api(
 }
 
 async fn fetch_latest_release_via_web(repo: &str) -> Result<GithubRelease, String> {
-    let client = reqwest::Client::builder()
+    let client = crate::tls_roots::client_builder()
         .timeout(Duration::from_secs(10))
         .build()
         .map_err(|err| format!("build fallback client: {err}"))?;
@@ -0,0 +1,457 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row as _;
+
+use crate::{ApiResult, AppError};
+
+const MAX_SESSION_ROWS: i64 = 100;
+
+/// Token and cost figures of one assistant message, as reported by OpenCode.
+#[derive(Debug, Clone, PartialEq)]
+struct MessageUsage {
+    message_id: String,
+    session_id: String,
+    provider_id: String,
+    model_id: String,
+    /// UTC `YYYY-MM-DD` of the message's creation.
+    day: String,
+    created_at: i64,
+    input: i64,
+    output: i64,
+    reasoning: i64,
+    cache_read: i64,
+    cache_write: i64,
+    cost: f64,
+}
+
+pub(crate) fn day_for_millis(ms: i64) -> String {
+    time::OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000)
+        .map(|dt| dt.date().to_string())
+        .unwrap_or_else(|_| "1970-01-01".to_string())
+}
+
+fn count(value: Option<&Value>) -> i64 {
+    value
+        .and_then(|v| v.as_f64())
+        .filter(|n| n.is_finite() && *n > 0.0)
+        .map_or(0, |n| n as i64)
+}
+
+/// Usage from a `message.updated` event, once the assistant message has
+/// finished (or failed); streaming updates before that are skipped.
+fn usage_from_event(payload: &Value) -> Option<MessageUsage> {
+    if payload.get("type").and_then(|v| v.as_str()) != Some("message.updated") {
+        return None;
+    }
+    let info = payload.pointer("/properties/info")?;
+    if info.get("role").and_then(|v| v.as_str()) != Some("assistant") {
+        return None;
+    }
+    let completed = info
+        .pointer("/time/completed")
+        .is_some_and(|v| !v.is_null());
+    if !completed && info.get("error").is_none() {
+        return None;
+    }
+    let str_field = |key: &str| {
+        info.get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let created_at = info
+        .pointer("/time/created")
+        .and_then(|v| v.as_f64())
+        .filter(|n| n.is_finite())
+        .map_or(0, |n| n as i64);
+    let tokens = info.get("tokens");
+    Some(MessageUsage {
+        message_id: str_field("id")?,
+        session_id: str_field("sessionID")?,
+        provider_id: str_field("providerID").unwrap_or_else(|| "unknown".to_string()),
+        model_id: str_field("modelID").unwrap_or_else(|| "unknown".to_string()),
+        day: day_for_millis(created_at),
+        created_at,
+        input: count(tokens.and_then(|t| t.get("input"))),
+        output: count(tokens.and_then(|t| t.get("output"))),
+        reasoning: count(tokens.and_then(|t| t.get("reasoning"))),
+        cache_read: count(tokens.and_then(|t| t.pointer("/cache/read"))),
+        cache_write: count(tokens.and_then(|t| t.pointer("/cache/write"))),
+        cost: info
+            .get("cost")
+            .and_then(|v| v.as_f64())
+            .filter(|n| n.is_finite() && *n >= 0.0)
+            .unwrap_or(0.0),
+    })
+}
+
+/// Record usage carried by an upstream event. Rows are keyed by message id,
+/// so repeated updates of the same message overwrite rather than add up.
+pub(crate) fn observe_event(state: &Arc<crate::AppState>, payload: &Value) {
+    let Some(usage) = usage_from_event(payload) else {
+        return;
+    };
+    let directory = state
+        .directory_session_index
+        .directory_for_session(&usage.session_id)
+        .and_then(|dir| crate::path_utils::normalize_directory_for_match(&dir))
+        .unwrap_or_default();
+    let state = state.clone();
+    tokio::spawn(async move {
+        let result = sqlx::query(
+            "INSERT OR REPLACE INTO usage_messages\n               (message_id, session_id, directory, provider_id, model_id, day, created_at,\n                input_tokens, output_tokens, reasoning_tokens, cache_read_tokens, cache_write_tokens, cost)\n             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&usage.message_id)
+        .bind(&usage.session_id)
+        .bind(&directory)
+        .bind(&usage.provider_id)
+        .bind(&usage.model_id)
+        .bind(&usage.day)
+        .bind(usage.created_at)
+        .bind(usage.input)
+        .bind(usage.output)
+        .bind(usage.reasoning)
+        .bind(usage.cache_read)
+        .bind(usage.cache_write)
+        .bind(usage.cost)
+        .execute(state.studio_db.pool())
+        .await;
+        match result {
+            Ok(_) => crate::budgets::check_after_usage(&state, &usage.session_id, &usage.day).await,
+            Err(err) => tracing::warn!(error = %err, "Failed to record usage"),
+        }
+    });
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSummaryQuery {
+    /// Inclusive UTC day, `YYYY-MM-DD`.
+    pub from: Option<String>,
+    /// Inclusive UTC day, `YYYY-MM-DD`.
+    pub to: Option<String>,
+    pub directory: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+    pub messages: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub reasoning_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cache_write_tokens: i64,
+    pub cost: f64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageByDay {
+    pub day: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageByModel {
+    #[serde(rename = "providerID")]
+    pub provider_id: String,
+    #[serde(rename = "modelID")]
+    pub model_id: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageBySession {
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    pub directory: String,
+    pub title: Option<String>,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSummaryResponse {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub directory: Option<String>,
+    pub totals: UsageTotals,
+    pub by_day: Vec<UsageByDay>,
+    pub by_model: Vec<UsageByModel>,
+    /// Most expensive sessions first, at most 100.
+    pub by_session: Vec<UsageBySession>,
+}
+
+fn parse_day(raw: Option<&str>, name: &str) -> ApiResult<Option<String>> {
+    let Some(raw) = raw.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    let invalid = || AppError::bad_request(format!("{name} must be YYYY-MM-DD"));
+    let mut parts = raw.splitn(3, '-');
+    let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    let year = year.parse::<i32>().map_err(|_| invalid())?;
+    let month = month
+        .parse::<u8>()
+        .ok()
+        .and_then(|m| time::Month::try_from(m).ok())
+        .ok_or_else(invalid)?;
+    let day = day.parse::<u8>().map_err(|_| invalid())?;
+    time::Date::from_calendar_date(year, month, day)
+        .map(|d| Some(d.to_string()))
+        .map_err(|_| invalid())
+}
+
+const TOTAL_COLUMNS: &str = "COUNT(*) AS messages, SUM(input_tokens) AS input_tokens, SUM(output_tokens) AS output_tokens, SUM(reasoning_tokens) AS reasoning_tokens, SUM(cache_read_tokens) AS cache_read_tokens, SUM(cache_write_tokens) AS cache_write_tokens, SUM(cost) AS cost";
+
+fn totals_from_row(row: &sqlx::sqlite::SqliteRow) -> UsageTotals {
+    let int = |name: &str| {
+        row.try_get::<Option<i64>, _>(name)
+            .ok()
+            .flatten()
+            .unwrap_or(0)
+    };
+    UsageTotals {
+        messages: int("messages"),
+        input_tokens: int("input_tokens"),
+        output_tokens: int("output_tokens"),
+        reasoning_tokens: int("reasoning_tokens"),
+        cache_read_tokens: int("cache_read_tokens"),
+        cache_write_tokens: int("cache_write_tokens"),
+        cost: row
+            .try_get::<Option<f64>, _>("cost")
+            .ok()
+            .flatten()
+            .unwrap_or(0.0),
+    }
+}
+
+async fn grouped_rows(
+    pool: &sqlx::SqlitePool,
+    group_columns: &str,
+    filter: &UsageFilter,
+    order: &str,
+    limit: Option<i64>,
+) -> ApiResult<Vec<sqlx::sqlite::SqliteRow>> {
+    let mut sql = format!(
+        "SELECT {group_columns}{comma}{TOTAL_COLUMNS} FROM usage_messages WHERE (?1 IS NULL OR day >= ?1) AND (?2 IS NULL OR day <= ?2) AND (?3 IS NULL OR directory = ?3)",
+        comma = if group_columns.is_empty() { "" } else { ", " },
+    );
+    if !group_columns.is_empty() {
+        sql.push_str(&format!(" GROUP BY {group_columns}"));
+    }
+    if !order.is_empty() {
+        sql.push_str(&format!(" ORDER BY {order}"));
+    }
+    if let Some(limit) = limit {
+        sql.push_str(&format!(" LIMIT {limit}"));
+    }
+    sqlx::query(&sql)
+        .bind(filter.from.as_deref())
+        .bind(filter.to.as_deref())
+        .bind(filter.directory.as_deref())
+        .fetch_all(pool)
+        .await
+        .map_err(|err| AppError::internal(err.to_string()))
+}
+
+struct UsageFilter {
+    from: Option<String>,
+    to: Option<String>,
+    directory: Option<String>,
+}
+
+async fn summarize(
+    pool: &sqlx::SqlitePool,
+    filter: &UsageFilter,
+) -> ApiResult<UsageSummaryResponse> {
+    let text = |row: &sqlx::sqlite::SqliteRow, name: &str| {
+        row.try_get::<String, _>(name).unwrap_or_default()
+    };
+    let totals = grouped_rows(pool, "", filter, "", None)
+        .await?
+        .first()
+        .map(totals_from_row)
+        .unwrap_or_default();
+    let by_day = grouped_rows(pool, "day", filter, "day", None)
+        .await?
+        .iter()
+        .map(|row| UsageByDay {
+            day: text(row, "day"),
+            totals: totals_from_row(row),
+        })
+        .collect();
+    let by_model = grouped_rows(
+        pool,
+        "provider_id, model_id",
+        filter,
+        "cost DESC, provider_id, model_id",
+        None,
+    )
+    .await?
+    .iter()
+    .map(|row| UsageByModel {
+        provider_id: text(row, "provider_id"),
+        model_id: text(row, "model_id"),
+        totals: totals_from_row(row),
+    })
+    .collect();
+    let by_session = grouped_rows(
+        pool,
+        "session_id, directory",
+        filter,
+        "cost DESC, session_id",
+        Some(MAX_SESSION_ROWS),
+    )
+    .await?
+    .iter()
+    .map(|row| UsageBySession {
+        session_id: text(row, "session_id"),
+        directory: text(row, "directory"),
+        title: None,
+        totals: totals_from_row(row),
+    })
+    .collect();
+
+    Ok(UsageSummaryResponse {
+        from: filter.from.clone(),
+        to: filter.to.clone(),
+        directory: filter.directory.clone(),
+        totals,
+        by_day,
+        by_model,
+        by_session,
+    })
+}
+
+/// Token and cost totals over a day range, broken down by day, by
+/// provider/model, and by session.
+pub(crate) async fn usage_summary(
+    State(state): State<Arc<crate::AppState>>,
+    Query(q): Query<UsageSummaryQuery>,
+) -> ApiResult<Json<UsageSummaryResponse>> {
+    let from = parse_day(q.from.as_deref(), "from")?;
+    let to = parse_day(q.to.as_deref(), "to")?;
+    if let (Some(from), Some(to)) = (&from, &to)
+        && from > to
+    {
+        return Err(AppError::bad_request("from must not be after to"));
+    }
+    let directory = match q.directory.as_deref().map(str::trim) {
+        Some(raw) if !raw.is_empty() => Some(
+            crate::path_utils::normalize_directory_for_match(raw)
+                .ok_or_else(|| AppError::bad_request("Invalid directory"))?,
+        ),
+        _ => None,
+    };
+
+    let filter = UsageFilter {
+        from,
+        to,
+        directory,
+    };
+    let mut summary = summarize(state.studio_db.pool(), &filter).await?;
+    for session in &mut summary.by_session {
+        session.title = state
+            .directory_session_index
+            .summary(&session.session_id)
+            .map(|s| s.title)
+            .filter(|t| !t.is_empty());
+    }
+    Ok(Json(summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: &str, completed: bool, cost: f64) -> Value {
+        serde_json::json!({
+            "type": "message.updated",
+            "properties": {
+                "sessionID": "ses_1",
+                "info": {
+                    "id": id,
+                    "sessionID": "ses_1",
+                    "role": "assistant",
+                    "providerID": "anthropic",
+                    "modelID": "model-a",
+                    "cost": cost,
+                    "tokens": {"input": 100, "output": 20, "reasoning": 0, "cache": {"read": 50, "write": 5}},
+                    "time": if completed {
+                        serde_json::json!({"created": 1_700_000_000_000i64, "completed": 1_700_000_001_000i64})
+                    } else {
+                        serde_json::json!({"created": 1_700_000_000_000i64})
+                    },
+                }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn usage_events_are_upserted_and_summarized() {
+        assert!(usage_from_event(&event("msg_1", false, 0.1)).is_none());
+        let usage = usage_from_event(&event("msg_1", true, 0.25)).unwrap();
+        assert_eq!(usage.day, "2023-11-14");
+        assert_eq!((usage.input, usage.cache_read), (100, 50));
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let db = crate::studio_db::StudioDb::open_at_path(tmp.path().join("studio.db"))
+            .await
+            .unwrap();
+        let pool = db.pool();
+        for (id, dir, cost) in [
+            ("msg_1", "/a", 0.25),
+            ("msg_1", "/a", 0.5),
+            ("msg_2", "/b", 1.0),
+        ] {
+            sqlx::query("INSERT OR REPLACE INTO usage_messages (message_id, session_id, directory, provider_id, model_id, day, created_at, input_tokens, output_tokens, reasoning_tokens, cache_read_tokens, cache_write_tokens, cost) VALUES (?, 'ses_1', ?, 'anthropic', 'model-a', '2023-11-14', 0, 10, 1, 0, 0, 0, ?)")
+                .bind(id)
+                .bind(dir)
+                .bind(cost)
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+
+        let all = UsageFilter {
+            from: Some("2023-11-01".into()),
+            to: None,
+            directory: None,
+        };
+        let summary = summarize(pool, &all).await.unwrap();
+        assert_eq!(summary.totals.messages, 2);
+        assert_eq!(summary.totals.input_tokens, 20);
+        assert!((summary.totals.cost - 1.5).abs() < 1e-9);
+        assert_eq!(summary.by_day.len(), 1);
+        assert_eq!(summary.by_model[0].model_id, "model-a");
+
+        let only_a = UsageFilter {
+            from: None,
+            to: Some("2023-11-14".into()),
+            directory: Some("/a".into()),
+        };
+        assert_eq!(summarize(pool, &only_a).await.unwrap().totals.messages, 1);
+
+        assert!(parse_day(Some("2023-13-01"), "from").is_err());
+        assert_eq!(
+            parse_day(Some(" 2023-01-05 "), "from").unwrap().as_deref(),
+            Some("2023-01-05")
+        );
+    }
+}
@@ -9,6 +9,7 @@ use axum::http::{HeaderMap, HeaderName, HeaderValue, Request, StatusCode, Uri, h
 use axum::response::Response;
 use futures_util::{SinkExt, StreamExt};
 use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
@@ -28,24 +29,24 @@ const PREVIEW_DISCOVERY_HOSTS: [&str; 2] = ["127.0.0.1", "localhost"];
 const PREVIEW_DISCOVERY_PORTS: [u16; 8] = [5173, 3000, 4173, 8080, 8000, 4200, 4321, 5174];
 const PREVIEW_PROXY_BASE_PREFIX: &str = "/api/workspace/preview/s/";
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub(crate) struct WorkspacePreviewQuery {
     pub(crate) directory: Option<String>,
     pub(crate) target: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub(crate) struct WorkspacePreviewProxyQuery {
     pub(crate) target: String,
     pub(crate) refresh: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub(crate) struct WorkspacePreviewResponse {
     pub(crate) url: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct WorkspacePreviewSessionCreateBody {
     pub(crate) id: String,
@@ -58,7 +59,7 @@ pub(crate) struct WorkspacePreviewSessionCreateBody {
     pub(crate) target_url: String,
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct WorkspacePreviewSessionDiscoverBody {
     pub(crate) id: String,
@@ -70,7 +71,7 @@ pub(crate) struct WorkspacePreviewSessionDiscoverBody {
     pub(crate) logs_path: String,
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct WorkspacePreviewSessionUpdateBody {
     pub(crate) directory: Option<String>,
@@ -82,13 +83,13 @@ pub(crate) struct WorkspacePreviewSessionUpdateBody {
     pub(crate) target_url: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct WorkspacePreviewSessionDeleteResponse {
     pub(crate) ok: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct WorkspacePreviewSessionRenameBody {
     pub(crate) id: String,
@@ -817,7 +818,7 @@ fn preview_probe_candidates() -> Vec<Url> {
 }
 
 fn preview_probe_client() -> ApiResult<reqwest::Client> {
-    reqwest::Client::builder()
+    crate::tls_roots::client_builder()
         .redirect(reqwest::redirect::Policy::none())
         .connect_timeout(Duration::from_millis(350))
         .timeout(Duration::from_millis(700))
@@ -838,7 +839,7 @@ async fn response_from_upstream(upstream: reqwest::Response) -> ApiResult<Respon
 }
 
 fn preview_proxy_client() -> ApiResult<reqwest::Client> {
-    reqwest::Client::builder()
+    crate::tls_roots::client_builder()
         .redirect(reqwest::redirect::Policy::none())
         .build()
         .map_err(|err| AppError::internal(format!("failed to build preview proxy client: {err}")))
@@ -4,6 +4,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tokio::sync::{Mutex, RwLock};
 use url::{Host, Url};
@@ -18,7 +19,7 @@ const PREVIEW_STATE_ENV: &str = "OPENCODE_WEB_PREVIEW_STATE_PATH";
 const STUDIO_PREVIEW_STATE_ENV: &str = "OPENCODE_STUDIO_PREVIEW_STATE_PATH";
 const XDG_DATA_HOME_ENV: &str = "XDG_DATA_HOME";
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct PreviewSessionRecord {
     pub(crate) id: String,
@@ -68,7 +69,7 @@ pub(crate) struct PreviewSessionUpdatePatch {
     pub(crate) target_url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct PreviewSessionsResponse {
     pub(crate) version: u32,
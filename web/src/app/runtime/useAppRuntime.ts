@@ -241,6 +241,7 @@ export function useAppRuntime() {
     'chat-sidebar.state',
     'opencode-studio:replay-gap',
     'config.settings.replace',
+    'settings.patch',
   ])
 
   function shouldBypassDirectoryFilter(evt: { type?: unknown }): boolean {
@@ -340,7 +341,7 @@ export function useAppRuntime() {
 
           if (!isSelectedSessionEvent && !shouldBypassDirectoryFilter(evt) && !eventMatchesCurrentDirectory(evt)) return
 
-          if (evt.type === 'config.settings.replace') {
+          if (evt.type === 'config.settings.replace' || evt.type === 'settings.patch') {
             void settings.refresh().catch(() => {})
             return
           }
@@ -36,8 +36,13 @@ export type FsReadChunkResponse = {
   totalBytes: number
   hasMore: boolean
   nextOffset?: number
+  mtime?: number
+  /** Present only when the chunk covers the whole file. */
+  hash?: string
 }
 
+export type FsFileVersion = { mtime?: number; hash?: string }
+
 export type FsContentSearchMatch = {
   line: number
   startColumn: number
@@ -183,16 +188,27 @@ export async function readFileChunk(input: {
   return apiJson<FsReadChunkResponse>(`/api/fs/read-chunk?${params.join('&')}`)
 }
 
+// Pass the version the content was loaded at; the server answers 409 when the file changed since.
 export async function writeFile(input: {
   directory: string
   path: string
   content: string
-}): Promise<{ success: boolean }> {
-  return apiJson<{ success: boolean }>(`/api/fs/write?directory=${encodeURIComponent(input.directory)}`, {
-    method: 'POST',
-    headers: { 'content-type': 'application/json' },
-    body: JSON.stringify({ path: input.path, content: input.content }),
-  })
+  expected?: FsFileVersion | null
+}): Promise<{ success: boolean } & FsFileVersion> {
+  const expected = input.expected
+  return apiJson<{ success: boolean } & FsFileVersion>(
+    `/api/fs/write?directory=${encodeURIComponent(input.directory)}`,
+    {
+      method: 'POST',
+      headers: { 'content-type': 'application/json' },
+      body: JSON.stringify({
+        path: input.path,
+        content: input.content,
+        expectedHash: expected?.hash,
+        expectedMtime: expected?.hash ? undefined : expected?.mtime,
+      }),
+    },
+  )
 }
 
 export async function uploadFile(input: { directory: string; path: string; file: Blob }): Promise<FsUploadResponse> {
@@ -519,6 +519,23 @@ export default {
     openRuntimeConfig: 'Open runtime config',
     removeBackendTitle: 'Remove backend?',
   },
+  apiDocs: {
+    title: 'API reference',
+    subtitle: 'Studio server routes, version {version}. Raw document:',
+    filter: 'Filter by method, path or summary',
+    loading: 'Loading API document...',
+    loadFailed: 'Failed to load the API document',
+    empty: 'No matching routes',
+    parameters: 'Parameters',
+    requestBody: 'Request body',
+    response: 'Response {status}',
+  },
+  quickPrompt: {
+    placeholder: 'Send to the most recent session...',
+    hint: 'Enter to send, Esc to dismiss',
+    sending: 'Sending...',
+    failed: 'Failed to send prompt',
+  },
   settings: {
     title: 'Settings',
     refresh: 'Refresh',
@@ -3752,6 +3769,7 @@ export default {
       movedPartial: 'Moved {success} items, failed {failed}',
       moveFailedCount: 'Failed to move {count} items',
       savedFile: 'Saved file',
+      saveConflict: 'File changed on disk since it was opened. Refresh it before saving.',
       fileCreated: 'File created',
       folderCreated: 'Folder created',
       renamed: 'Renamed',
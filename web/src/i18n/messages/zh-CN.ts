@@ -518,6 +518,23 @@ export default {
     openRuntimeConfig: '打开运行配置',
     removeBackendTitle: '移除后端？',
   },
+  apiDocs: {
+    title: 'API 参考',
+    subtitle: 'Studio 服务端路由，版本 {version}。原始文档：',
+    filter: '按方法、路径或摘要筛选',
+    loading: '正在加载 API 文档...',
+    loadFailed: '加载 API 文档失败',
+    empty: '没有匹配的路由',
+    parameters: '参数',
+    requestBody: '请求体',
+    response: '响应 {status}',
+  },
+  quickPrompt: {
+    placeholder: '发送到最近的会话...',
+    hint: 'Enter 发送，Esc 关闭',
+    sending: '发送中...',
+    failed: '发送失败',
+  },
   settings: {
     title: '设置',
     refresh: '刷新',
@@ -3746,6 +3763,7 @@ export default {
       movedPartial: '已移动 {success} 项，失败 {failed} 项',
       moveFailedCount: '移动失败，共 {count} 项',
       savedFile: '文件已保存',
+      saveConflict: '文件在打开后已在磁盘上被修改，请先刷新再保存。',
       fileCreated: '文件已创建',
       folderCreated: '文件夹已创建',
       renamed: '已重命名',
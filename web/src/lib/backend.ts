@@ -297,6 +297,8 @@ function normalizeDesktopStatus(raw: unknown): DesktopBackendStatus | null {
     url: typeof raw.url === 'string' ? raw.url : null,
     last_error: typeof raw.last_error === 'string' ? raw.last_error : null,
     last_error_info: normalizeDesktopErrorInfo(raw.last_error_info ?? raw.lastErrorInfo),
+    crash_count: typeof raw.crash_count === 'number' ? Math.max(0, Math.floor(raw.crash_count)) : 0,
+    degraded: raw.degraded === true,
   }
 }
 
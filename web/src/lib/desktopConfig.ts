@@ -14,6 +14,32 @@ export type DesktopConfig = {
     skip_opencode_start: boolean
     opencode_log_level?: string | null
   }
+  // Passed through untouched so saving here keeps the profile setup.
+  active_profile?: string | null
+  profiles?: Record<string, unknown>
+  shortcuts?: DesktopShortcuts
+}
+
+export type DesktopShortcuts = {
+  toggle_window: string
+  quick_prompt: string
+}
+
+export type DesktopQuickPromptSent = {
+  sessionId: string
+  directory: string
+}
+
+export type DesktopProfileSummary = {
+  name: string
+  host: string
+  port: number
+  dataDir: string | null
+}
+
+export type DesktopProfileList = {
+  active: string
+  profiles: DesktopProfileSummary[]
 }
 
 export type DesktopRuntimeInfo = {
@@ -48,6 +74,42 @@ export type DesktopBackendStatus = {
   url?: string | null
   last_error?: string | null
   last_error_info?: DesktopBackendErrorInfo | null
+  crash_count: number
+  degraded: boolean
+}
+
+export type DesktopLogLevel = 'trace' | 'debug' | 'info' | 'warn' | 'error'
+
+export type DesktopLogLine = {
+  level: DesktopLogLevel
+  text: string
+}
+
+export type DesktopLogFile = {
+  name: string
+  size: number
+  modifiedMs: number | null
+  current: boolean
+}
+
+export type DesktopLogTail = {
+  file: string
+  lines: DesktopLogLine[]
+  followId: number | null
+}
+
+export type DesktopLogFollowEvent = { kind: 'lines'; lines: DesktopLogLine[] } | { kind: 'rotated' }
+
+type TauriTransformCallback = (callback: (payload: unknown) => void, once?: boolean) => number
+
+function readTauriTransformCallback(): TauriTransformCallback | null {
+  try {
+    const candidate = (window as unknown as { __TAURI_INTERNALS__?: { transformCallback?: unknown } })
+      .__TAURI_INTERNALS__?.transformCallback
+    return typeof candidate === 'function' ? (candidate as TauriTransformCallback) : null
+  } catch {
+    return null
+  }
 }
 
 function readTauriInvoke(): TauriInvoke | null {
@@ -85,6 +147,45 @@ function asDesktopConfig(value: unknown): DesktopConfig | null {
       skip_opencode_start: backend.skip_opencode_start === true,
       opencode_log_level: typeof backend.opencode_log_level === 'string' ? backend.opencode_log_level : null,
     },
+    active_profile: typeof root.active_profile === 'string' ? root.active_profile : null,
+    profiles:
+      root.profiles && typeof root.profiles === 'object' && !Array.isArray(root.profiles)
+        ? (root.profiles as Record<string, unknown>)
+        : undefined,
+    shortcuts: asDesktopShortcuts(root.shortcuts),
+  }
+}
+
+function asDesktopShortcuts(value: unknown): DesktopShortcuts | undefined {
+  if (!value || typeof value !== 'object' || Array.isArray(value)) return undefined
+  const root = value as Record<string, unknown>
+  return {
+    toggle_window: typeof root.toggle_window === 'string' ? root.toggle_window.trim() : '',
+    quick_prompt: typeof root.quick_prompt === 'string' ? root.quick_prompt.trim() : '',
+  }
+}
+
+function asDesktopProfileList(value: unknown): DesktopProfileList | null {
+  if (!value || typeof value !== 'object' || Array.isArray(value)) return null
+  const root = value as Record<string, unknown>
+  const rawProfiles = Array.isArray(root.profiles) ? root.profiles : []
+  const profiles: DesktopProfileSummary[] = []
+  for (const item of rawProfiles) {
+    if (!item || typeof item !== 'object' || Array.isArray(item)) continue
+    const entry = item as Record<string, unknown>
+    const name = typeof entry.name === 'string' ? entry.name.trim() : ''
+    if (!name) continue
+    const portRaw = Number(entry.port)
+    profiles.push({
+      name,
+      host: typeof entry.host === 'string' ? entry.host : '127.0.0.1',
+      port: Number.isFinite(portRaw) ? Math.max(0, Math.floor(portRaw)) : 0,
+      dataDir: typeof entry.dataDir === 'string' && entry.dataDir.trim() ? entry.dataDir : null,
+    })
+  }
+  return {
+    active: typeof root.active === 'string' && root.active.trim() ? root.active.trim() : 'default',
+    profiles,
   }
 }
 
@@ -157,9 +258,56 @@ function asDesktopBackendStatus(value: unknown): DesktopBackendStatus | null {
     url: typeof root.url === 'string' ? root.url : null,
     last_error: typeof root.last_error === 'string' ? root.last_error : null,
     last_error_info: asDesktopBackendErrorInfo(root.last_error_info ?? root.lastErrorInfo),
+    crash_count: typeof root.crash_count === 'number' ? Math.max(0, Math.floor(root.crash_count)) : 0,
+    degraded: root.degraded === true,
   }
 }
 
+const DESKTOP_LOG_LEVELS: DesktopLogLevel[] = ['trace', 'debug', 'info', 'warn', 'error']
+
+function asDesktopLogLines(value: unknown): DesktopLogLine[] {
+  if (!Array.isArray(value)) return []
+  const lines: DesktopLogLine[] = []
+  for (const item of value) {
+    if (!item || typeof item !== 'object' || Array.isArray(item)) continue
+    const entry = item as Record<string, unknown>
+    const level = DESKTOP_LOG_LEVELS.find((candidate) => candidate === entry.level) || 'info'
+    lines.push({ level, text: typeof entry.text === 'string' ? entry.text : '' })
+  }
+  return lines
+}
+
+function asDesktopLogFiles(value: unknown): DesktopLogFile[] {
+  if (!Array.isArray(value)) return []
+  const files: DesktopLogFile[] = []
+  for (const item of value) {
+    if (!item || typeof item !== 'object' || Array.isArray(item)) continue
+    const entry = item as Record<string, unknown>
+    const name = typeof entry.name === 'string' ? entry.name.trim() : ''
+    if (!name) continue
+    const sizeRaw = Number(entry.size)
+    const modifiedRaw = Number(entry.modifiedMs)
+    files.push({
+      name,
+      size: Number.isFinite(sizeRaw) && sizeRaw > 0 ? Math.floor(sizeRaw) : 0,
+      modifiedMs: entry.modifiedMs != null && Number.isFinite(modifiedRaw) ? Math.floor(modifiedRaw) : null,
+      current: entry.current === true,
+    })
+  }
+  return files
+}
+
+function asDesktopLogFollowEvent(value: unknown): DesktopLogFollowEvent | null {
+  if (!value || typeof value !== 'object' || Array.isArray(value)) return null
+  const root = value as Record<string, unknown>
+  // Tauri channels wrap each payload as `{ index, message }`.
+  const event = 'message' in root ? asDesktopLogFollowEvent(root.message) : null
+  if (event) return event
+  if (root.kind === 'rotated') return { kind: 'rotated' }
+  if (root.kind === 'lines') return { kind: 'lines', lines: asDesktopLogLines(root.lines) }
+  return null
+}
+
 export function isDesktopRuntime(): boolean {
   return !!readTauriInvoke()
 }
@@ -178,6 +326,38 @@ export async function desktopConfigSave(config: DesktopConfig): Promise<DesktopC
   return asDesktopConfig(raw)
 }
 
+export async function desktopProfileList(): Promise<DesktopProfileList | null> {
+  const invoke = readTauriInvoke()
+  if (!invoke) return null
+  const raw = await invoke('desktop_profile_list')
+  return asDesktopProfileList(raw)
+}
+
+export async function desktopProfileSwitch(name: string): Promise<DesktopProfileList | null> {
+  const invoke = readTauriInvoke()
+  if (!invoke) return null
+  const raw = await invoke('desktop_profile_switch', { name })
+  return asDesktopProfileList(raw)
+}
+
+export async function desktopQuickPromptSend(text: string): Promise<DesktopQuickPromptSent | null> {
+  const invoke = readTauriInvoke()
+  if (!invoke) return null
+  const raw = await invoke('desktop_quick_prompt_send', { text })
+  if (!raw || typeof raw !== 'object' || Array.isArray(raw)) return null
+  const root = raw as Record<string, unknown>
+  return {
+    sessionId: typeof root.sessionId === 'string' ? root.sessionId : '',
+    directory: typeof root.directory === 'string' ? root.directory : '',
+  }
+}
+
+export async function desktopQuickPromptClose(): Promise<void> {
+  const invoke = readTauriInvoke()
+  if (!invoke) return
+  await invoke('desktop_quick_prompt_close')
+}
+
 export async function desktopBackendRestart(): Promise<void> {
   const invoke = readTauriInvoke()
   if (!invoke) return
@@ -191,6 +371,52 @@ export async function desktopBackendStatus(): Promise<DesktopBackendStatus | nul
   return asDesktopBackendStatus(raw)
 }
 
+export async function desktopLogsList(): Promise<DesktopLogFile[]> {
+  const invoke = readTauriInvoke()
+  if (!invoke) return []
+  const raw = await invoke('desktop_logs_list')
+  return asDesktopLogFiles(raw)
+}
+
+export async function desktopLogsTail(opts?: {
+  file?: string
+  lines?: number
+  level?: DesktopLogLevel
+  // New lines are pushed here until `desktopLogsUnfollow(followId)`.
+  onEvent?: (event: DesktopLogFollowEvent) => void
+}): Promise<DesktopLogTail | null> {
+  const invoke = readTauriInvoke()
+  if (!invoke) return null
+  const args: Record<string, unknown> = {
+    file: opts?.file ?? null,
+    lines: opts?.lines ?? null,
+    level: opts?.level ?? null,
+  }
+  const onEvent = opts?.onEvent
+  const transformCallback = readTauriTransformCallback()
+  if (onEvent && transformCallback) {
+    const id = transformCallback((payload) => {
+      const event = asDesktopLogFollowEvent(payload)
+      if (event) onEvent(event)
+    })
+    args.onLine = `__CHANNEL__:${id}`
+  }
+  const raw = await invoke('desktop_logs_tail', args)
+  if (!raw || typeof raw !== 'object' || Array.isArray(raw)) return null
+  const root = raw as Record<string, unknown>
+  return {
+    file: typeof root.file === 'string' ? root.file : '',
+    lines: asDesktopLogLines(root.lines),
+    followId: typeof root.followId === 'number' ? root.followId : null,
+  }
+}
+
+export async function desktopLogsUnfollow(followId: number): Promise<void> {
+  const invoke = readTauriInvoke()
+  if (!invoke) return
+  await invoke('desktop_logs_unfollow', { followId })
+}
+
 export async function desktopOpenConfig(): Promise<void> {
   const invoke = readTauriInvoke()
   if (!invoke) return
@@ -55,6 +55,11 @@ const routes: RouteRecordRaw[] = [
     component: () => import('./pages/GitPage.vue'),
     meta: { shellSidebar: 'none', mobilePanel: 'git' },
   },
+  {
+    path: '/api-docs',
+    component: () => import('./pages/ApiDocsPage.vue'),
+    meta: { shellSidebar: 'none' },
+  },
   { path: '/settings', redirect: '/settings/opencode/general' },
   {
     path: '/settings/plan/:section?',
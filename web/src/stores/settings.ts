@@ -19,6 +19,8 @@ export type Project = {
 }
 
 export type Settings = {
+  // Bumped by the server on every write; sent back as If-Match.
+  revision?: number
   projects: Project[]
   // API compatibility alias; projects remains the persisted schema key.
   directories?: Project[]
@@ -146,7 +148,7 @@ export const useSettingsStore = defineStore('settings', () => {
     try {
       const updated = await apiJson<Settings>('/api/config/settings', {
         method: 'PUT',
-        headers: { 'content-type': 'application/json' },
+        headers: { 'content-type': 'application/json', 'if-match': String(data.value?.revision ?? 0) },
         body: JSON.stringify(partial),
       })
       data.value = updated
@@ -154,6 +156,9 @@ export const useSettingsStore = defineStore('settings', () => {
       postAppBroadcast('settings.updated', { updatedAt: Date.now() })
     } catch (err) {
       if (err instanceof ApiError) {
+        // Another client saved first: adopt its copy so the next edit starts from it.
+        const latest = err.status === 409 ? (err.bodyJson as { settings?: Settings } | undefined)?.settings : undefined
+        if (latest) data.value = latest
         error.value = err.message || err.bodyText || null
       } else {
         error.value = err instanceof Error ? err.message : String(err)